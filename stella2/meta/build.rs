@@ -1,5 +1,6 @@
 fn main() {
     tcw3_designer::BuildScriptConfig::new()
         .link("tcw3", tcw3_meta::DESIGNER_METADATA.into())
+        .hot_reload(std::env::var_os("CARGO_FEATURE_HOT_RELOAD").is_some())
         .run_and_exit_on_error();
 }
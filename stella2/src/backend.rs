@@ -0,0 +1,96 @@
+//! Chat service backends
+//!
+//! A backend connects to a chat service (e.g., an IRC network) and provides
+//! access to its channels and messages. The operations return futures, which
+//! are polled on the main thread by `Wm::spawn_local`. Backends are expected
+//! to perform blocking I/O on their own threads and wake up the futures when
+//! the results are ready.
+//!
+//! Events originating from the service (e.g., a message posted by another
+//! user) are delivered through a main-thread channel created by
+//! `Wm::channel`. `AppView` converts them into `AppAction`s.
+use futures::{future::BoxFuture, FutureExt};
+use tcw3::pal::channel::Sender;
+
+use crate::model;
+
+pub mod irc;
+pub mod matrix;
+pub mod mock;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The smallest message ID backends should assign to the messages of the
+/// current session. The lower IDs are left for the messages restored from
+/// the local message store, which are placed before the oldest message the
+/// backend can provide.
+pub const MESSAGE_ID_ORIGIN: u64 = 1 << 62;
+
+#[derive(Debug, Clone, PartialEq, displaydoc::Display)]
+pub enum Error {
+    /// The backend is not connected.
+    NotConnected,
+    /// The channel {0} does not exist.
+    NoSuchChannel(String),
+    /// Connection error: {0}
+    Connection(String),
+}
+
+impl std::error::Error for Error {}
+
+/// An event reported by a backend.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A message was posted to a channel, possibly by the user.
+    MessageReceived {
+        channel: String,
+        message: model::Message,
+    },
+    /// The user joined or left a channel.
+    ChannelsChanged(Vec<model::ChannelGroup>),
+    /// The connection was lost. The backend keeps trying to reconnect.
+    Disconnected(Error),
+    /// The connection was re-established after `Disconnected` or a failed
+    /// `connect`.
+    Reconnected,
+}
+
+pub type EventSender = Sender<Event>;
+
+/// A connection to a chat service.
+///
+/// The methods are called on the main thread. Channels are identified by the
+/// IDs in `model::Channel::id`.
+pub trait Backend: Send + Sync {
+    /// Connect to the service. After the returned future completes
+    /// successfully, events are sent to `events` until the backend is dropped
+    /// or the connection is lost.
+    fn connect(&self, events: EventSender) -> BoxFuture<'static, Result<()>>;
+
+    /// Get the channels the user has joined.
+    fn list_channels(&self) -> BoxFuture<'static, Result<Vec<model::ChannelGroup>>>;
+
+    /// Get up to `limit` most recent messages of a channel, sorted by time.
+    /// If `before` is specified, only the messages older than the one with
+    /// the given ID are returned.
+    ///
+    /// Fewer than `limit` messages are returned only if there are no more
+    /// older messages.
+    fn fetch_history(
+        &self,
+        channel: &str,
+        before: Option<u64>,
+        limit: usize,
+    ) -> BoxFuture<'static, Result<Vec<model::Message>>>;
+
+    /// Post a message to a channel. The posted message is reported back as
+    /// `Event::MessageReceived`.
+    fn send_message(&self, channel: &str, body: &str) -> BoxFuture<'static, Result<()>>;
+
+    /// Notify the service that the user has read the messages in a channel
+    /// (e.g., by sending a read receipt). The default implementation does
+    /// nothing.
+    fn mark_read(&self, _channel: &str) -> BoxFuture<'static, Result<()>> {
+        async { Ok(()) }.boxed()
+    }
+}
@@ -0,0 +1,955 @@
+//! The IRC backend
+//!
+//! The connection is driven by a dedicated worker thread using blocking I/O.
+//! The worker thread registers the connection, negotiates the IRCv3
+//! capabilities listed in `CAPS`, joins the configured channels, and
+//! reconnects with an exponential backoff when the connection is lost.
+//! Outgoing messages are written by a separate writer thread so that the main
+//! thread never blocks on the socket. The writer thread throttles messages so
+//! that pasting a long text doesn't get us disconnected for flooding.
+//!
+//! Connections use TLS unless `IrcSettings::tls` is disabled (see `tls`). The
+//! connection password is never sent over an unencrypted connection.
+//!
+//! IRC has no standard way to retrieve past messages, so `fetch_history` only
+//! returns the messages received during the current session.
+//!
+//! TODO: Support private messages
+use chrono::{DateTime, Local};
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{Shutdown, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Backend, Error, Event, EventSender, Result, MESSAGE_ID_ORIGIN};
+use crate::{config::settings::IrcSettings, model};
+
+mod proto;
+mod tls;
+
+/// The IRCv3 capabilities requested if supported by the server.
+const CAPS: &[&str] = &[
+    "echo-message",
+    "message-tags",
+    "multi-prefix",
+    "server-time",
+];
+
+/// The delay before the first reconnection attempt. Doubled on every failed
+/// attempt.
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(300);
+
+/// A connection that lasted this long resets the reconnection delay.
+const STABLE_CONNECTION_DURATION: Duration = Duration::from_secs(60);
+
+/// If nothing is received for this duration, `PING` is sent to check if the
+/// connection is still alive. If nothing is received for another period, the
+/// connection is considered dead.
+const PING_INTERVAL: Duration = Duration::from_secs(120);
+
+/// The maximum number of messages retained for each channel.
+const HISTORY_LEN_MAX: usize = 1000;
+
+/// The number of bytes reserved for the prefix the server prepends when
+/// relaying our messages (`:nick!user@host `).
+const PREFIX_LEN_RESERVED: usize = 100;
+
+/// Each outgoing message adds this to the flood control timer
+/// (RFC 1459 section 8.10).
+const FLOOD_PENALTY: Duration = Duration::from_secs(2);
+
+/// The writer thread holds back messages while the flood control timer is
+/// ahead of the current time by more than this.
+const FLOOD_BURST: Duration = Duration::from_secs(10);
+
+pub struct IrcBackend {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    settings: IrcSettings,
+    /// Identifies the channel group.
+    group_id: String,
+    shutdown: AtomicBool,
+    /// Wakes up the worker thread waiting for reconnection when the backend
+    /// is dropped.
+    wake: Mutex<mpsc::Sender<()>>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    events: Option<EventSender>,
+    /// Completes the future returned by `connect`.
+    connect_result: Option<oneshot::Sender<Result<()>>>,
+    /// Taken by the worker thread when it starts.
+    wake_recv: Option<mpsc::Receiver<()>>,
+
+    /// The current connection. Used to terminate the connection on drop.
+    stream: Option<TcpStream>,
+    /// Sends messages to the writer thread of the current connection.
+    /// `None` if the connection is not registered yet.
+    outgoing: Option<mpsc::Sender<proto::Message>>,
+    /// The nickname we were given.
+    nick: String,
+    /// The enabled capabilities.
+    caps: HashSet<String>,
+    /// The joined channels in the order they were joined.
+    channels: Vec<JoinedChannel>,
+
+    /// Keyed by case-folded channel names.
+    histories: HashMap<String, VecDeque<model::Message>>,
+    next_message_id: u64,
+}
+
+struct JoinedChannel {
+    /// The channel name as the server spelled it in our `JOIN`.
+    name: String,
+    /// The number of messages from others since the last `mark_read`.
+    unread_count: u32,
+    /// `true` if any of the unread messages mention our nickname.
+    mentioned: bool,
+}
+
+impl IrcBackend {
+    pub fn new(settings: IrcSettings) -> Self {
+        let group_id = format!("irc:{}", settings.server);
+        let (wake, wake_recv) = mpsc::channel();
+
+        Self {
+            shared: Arc::new(Shared {
+                settings,
+                group_id,
+                shutdown: AtomicBool::new(false),
+                wake: Mutex::new(wake),
+                state: Mutex::new(State {
+                    wake_recv: Some(wake_recv),
+                    next_message_id: MESSAGE_ID_ORIGIN,
+                    ..State::default()
+                }),
+            }),
+        }
+    }
+}
+
+impl Drop for IrcBackend {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+
+        // Wake up the worker thread if it's waiting for reconnection
+        let _ = self.shared.wake.lock().unwrap().send(());
+
+        let state = self.shared.state.lock().unwrap();
+        if let Some(outgoing) = &state.outgoing {
+            let _ = outgoing.send(proto::Message::new("QUIT", &[]));
+        }
+        if let Some(stream) = &state.stream {
+            // Wake up the worker thread
+            let _ = stream.shutdown(Shutdown::Read);
+        }
+    }
+}
+
+impl Backend for IrcBackend {
+    fn connect(&self, events: EventSender) -> BoxFuture<'static, Result<()>> {
+        let (send, recv) = oneshot::channel();
+
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.events = Some(events);
+            state.connect_result = Some(send);
+
+            if let Some(wake_recv) = state.wake_recv.take() {
+                let shared = Arc::clone(&self.shared);
+                thread::Builder::new()
+                    .name("IRC worker".to_owned())
+                    .spawn(move || shared.run(wake_recv))
+                    .unwrap();
+            }
+        }
+
+        recv.map(|result| result.unwrap_or(Err(Error::NotConnected)))
+            .boxed()
+    }
+
+    fn list_channels(&self) -> BoxFuture<'static, Result<Vec<model::ChannelGroup>>> {
+        let result = self
+            .shared
+            .channel_groups(&self.shared.state.lock().unwrap());
+        async { Ok(result) }.boxed()
+    }
+
+    fn fetch_history(
+        &self,
+        channel: &str,
+        before: Option<u64>,
+        limit: usize,
+    ) -> BoxFuture<'static, Result<Vec<model::Message>>> {
+        let result = (|| -> Result<Vec<model::Message>> {
+            let name = self.shared.channel_name(channel)?;
+            let state = self.shared.state.lock().unwrap();
+
+            let history = state
+                .histories
+                .get(&proto::fold_case(name))
+                .ok_or_else(|| Error::NoSuchChannel(channel.to_owned()))?;
+
+            let end = before.map_or(history.len(), |before| {
+                history.iter().take_while(|m| m.id < before).count()
+            });
+            let start = end.saturating_sub(limit);
+            Ok(history.range(start..end).cloned().collect())
+        })();
+
+        async { result }.boxed()
+    }
+
+    fn send_message(&self, channel: &str, body: &str) -> BoxFuture<'static, Result<()>> {
+        let result = (|| -> Result<()> {
+            let name = self.shared.channel_name(channel)?;
+            let mut state = self.shared.state.lock().unwrap();
+
+            let outgoing = state.outgoing.clone().ok_or(Error::NotConnected)?;
+            let echoed = state.caps.contains("echo-message");
+
+            let overhead = PREFIX_LEN_RESERVED + "PRIVMSG  :\r\n".len() + name.len();
+            let len_max = proto::LINE_LEN_MAX.saturating_sub(overhead);
+
+            for line in body.lines().filter(|line| !line.is_empty()) {
+                for chunk in proto::split_text(line, len_max) {
+                    outgoing
+                        .send(proto::Message::new("PRIVMSG", &[name, chunk]))
+                        .map_err(|_| Error::NotConnected)?;
+
+                    if !echoed {
+                        // The server doesn't echo our messages back, so
+                        // report them by ourselves
+                        let nick = state.nick.clone();
+                        let message = state.push_message(name, nick, Local::now(), chunk);
+                        if let Some(events) = &state.events {
+                            // We are on the main thread, so don't block
+                            let _ = events.try_send(Event::MessageReceived {
+                                channel: channel.to_owned(),
+                                message,
+                            });
+                        }
+                    }
+                }
+            }
+
+            Ok(())
+        })();
+
+        async { result }.boxed()
+    }
+
+    fn mark_read(&self, channel: &str) -> BoxFuture<'static, Result<()>> {
+        let result = (|| -> Result<()> {
+            let name = self.shared.channel_name(channel)?;
+            let mut state = self.shared.state.lock().unwrap();
+
+            let joined = state
+                .channel_mut(name)
+                .ok_or_else(|| Error::NoSuchChannel(channel.to_owned()))?;
+            joined.unread_count = 0;
+            joined.mentioned = false;
+
+            Ok(())
+        })();
+
+        async { result }.boxed()
+    }
+}
+
+impl Shared {
+    /// The main function of the worker thread.
+    fn run(self: Arc<Self>, wake_recv: mpsc::Receiver<()>) {
+        let mut delay = RECONNECT_DELAY_MIN;
+
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let start = Instant::now();
+            let result = self.run_connection();
+
+            {
+                let mut state = self.state.lock().unwrap();
+                state.stream = None;
+                state.outgoing = None;
+                state.caps.clear();
+            }
+
+            if self.shutdown.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let e = match result {
+                Ok(()) => Error::Connection("The server closed the connection".to_owned()),
+                Err(e) => e,
+            };
+            log::warn!("IRC connection to {:?} failed: {}", self.settings.server, e);
+
+            // Fail the pending call to `connect` or report the disconnection
+            let connect_result = self.state.lock().unwrap().connect_result.take();
+            if let Some(connect_result) = connect_result {
+                let _ = connect_result.send(Err(e));
+            } else {
+                self.send_events(vec![Event::Disconnected(e)]);
+            }
+
+            if start.elapsed() >= STABLE_CONNECTION_DURATION {
+                delay = RECONNECT_DELAY_MIN;
+            }
+
+            log::info!("Reconnecting in {:?}", delay);
+            match wake_recv.recv_timeout(delay) {
+                Ok(()) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+        }
+
+        log::debug!("The IRC worker thread is exiting");
+    }
+
+    /// Connect to the server and process messages until the connection is
+    /// closed.
+    fn run_connection(&self) -> Result<()> {
+        let settings = &self.settings;
+        if settings.password.is_some() && !settings.tls {
+            return Err(Error::Connection(
+                "Refusing to send the password over an unencrypted connection".to_owned(),
+            ));
+        }
+
+        log::info!(
+            "Connecting to {}:{} ({})",
+            settings.server,
+            settings.port(),
+            if settings.tls { "TLS" } else { "unencrypted" }
+        );
+
+        let stream =
+            TcpStream::connect((settings.server.as_str(), settings.port())).map_err(io_error)?;
+        stream
+            .set_read_timeout(Some(PING_INTERVAL))
+            .map_err(io_error)?;
+
+        let (reader, mut writer): (Box<dyn Read>, Box<dyn Write + Send>) = if settings.tls {
+            let (reader, writer) = tls::connect(&stream, &settings.server).map_err(io_error)?;
+            (Box::new(reader), Box::new(writer))
+        } else {
+            let reader = stream.try_clone().map_err(io_error)?;
+            let writer = stream.try_clone().map_err(io_error)?;
+            (Box::new(reader), Box::new(writer))
+        };
+
+        // Spawn the writer thread. It exits when `outgoing` is dropped.
+        let (outgoing, outgoing_recv) = mpsc::channel::<proto::Message>();
+        thread::Builder::new()
+            .name("IRC writer".to_owned())
+            .spawn(move || {
+                let mut flood_control = FloodControl::new(Instant::now());
+                for msg in outgoing_recv {
+                    thread::sleep(flood_control.delay(Instant::now()));
+                    log::trace!("IRC send: {}", msg);
+                    // Write the whole line at once, which is sent in a single
+                    // TLS record
+                    let line = format!("{}\r\n", msg);
+                    if let Err(e) = writer.write_all(line.as_bytes()) {
+                        log::warn!("Could not send an IRC message: {}", e);
+                        break;
+                    }
+                }
+            })
+            .unwrap();
+
+        {
+            let mut state = self.state.lock().unwrap();
+            state.stream = Some(stream);
+            state.nick = settings.nick.clone();
+        }
+
+        // Register the connection
+        let send = |msg: proto::Message| outgoing.send(msg).map_err(|_| Error::NotConnected);
+        send(proto::Message::new("CAP", &["LS", "302"]))?;
+        if let Some(password) = &settings.password {
+            send(proto::Message::new("PASS", &[password.as_str()]))?;
+        }
+        send(proto::Message::new("NICK", &[settings.nick.as_str()]))?;
+        send(proto::Message::new(
+            "USER",
+            &[
+                settings.username.as_deref().unwrap_or(&settings.nick),
+                "0",
+                "*",
+                settings.realname.as_deref().unwrap_or(&settings.nick),
+            ],
+        ))?;
+
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        let mut available_caps = Vec::new();
+        let mut ping_sent = false;
+
+        loop {
+            line.clear();
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) => return Ok(()),
+                Ok(_) => {}
+                Err(e)
+                    if e.kind() == io::ErrorKind::WouldBlock
+                        || e.kind() == io::ErrorKind::TimedOut =>
+                {
+                    if ping_sent {
+                        return Err(Error::Connection("Ping timeout".to_owned()));
+                    }
+                    send(proto::Message::new("PING", &[settings.server.as_str()]))?;
+                    ping_sent = true;
+                    continue;
+                }
+                Err(e) => return Err(io_error(e)),
+            }
+            ping_sent = false;
+
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(|c| c == '\r' || c == '\n');
+            log::trace!("IRC recv: {}", line);
+
+            let msg = if let Some(msg) = proto::Message::parse(line) {
+                msg
+            } else {
+                log::warn!("Ignoring a malformed IRC message: {:?}", line);
+                continue;
+            };
+
+            let events = self.handle_message(&msg, &outgoing, &mut available_caps)?;
+            self.send_events(events);
+        }
+    }
+
+    /// Process a message from the server. Returns the events to be sent to
+    /// the main thread.
+    fn handle_message(
+        &self,
+        msg: &proto::Message,
+        outgoing: &mpsc::Sender<proto::Message>,
+        available_caps: &mut Vec<String>,
+    ) -> Result<Vec<Event>> {
+        let send = |msg: proto::Message| outgoing.send(msg).map_err(|_| Error::NotConnected);
+        let mut events = Vec::new();
+
+        let mut state = self.state.lock().unwrap();
+        let from_me =
+            msg.source_nick().map(proto::fold_case) == Some(proto::fold_case(&state.nick));
+
+        match msg.command.as_str() {
+            "PING" => {
+                let mut pong = msg.clone();
+                pong.tags.clear();
+                pong.prefix = None;
+                pong.command = "PONG".to_owned();
+                send(pong)?;
+            }
+            "CAP" => match msg.param(1) {
+                "LS" => {
+                    // `CAP * LS * :caps` indicates more lines follow
+                    let (is_last, caps) = if msg.param(2) == "*" {
+                        (false, msg.param(3))
+                    } else {
+                        (true, msg.param(2))
+                    };
+
+                    // Capabilities may have values, e.g., `sasl=PLAIN`
+                    available_caps.extend(
+                        caps.split(' ')
+                            .map(|cap| cap.split('=').next().unwrap().to_owned()),
+                    );
+
+                    if is_last {
+                        let wanted: Vec<&str> = CAPS
+                            .iter()
+                            .cloned()
+                            .filter(|cap| available_caps.iter().any(|c| c == cap))
+                            .collect();
+
+                        if wanted.is_empty() {
+                            send(proto::Message::new("CAP", &["END"]))?;
+                        } else {
+                            send(proto::Message::new(
+                                "CAP",
+                                &["REQ", wanted.join(" ").as_str()],
+                            ))?;
+                        }
+                    }
+                }
+                "ACK" => {
+                    state
+                        .caps
+                        .extend(msg.param(2).split(' ').map(str::to_owned));
+                    send(proto::Message::new("CAP", &["END"]))?;
+                }
+                "NAK" => {
+                    send(proto::Message::new("CAP", &["END"]))?;
+                }
+                _ => {}
+            },
+            // RPL_WELCOME
+            "001" => {
+                state.nick = msg.param(0).to_owned();
+                state.outgoing = Some(outgoing.clone());
+
+                // The server will send `JOIN`s back to us
+                state.channels.clear();
+                events.push(Event::ChannelsChanged(self.channel_groups(&state)));
+
+                if !self.settings.channels.is_empty() {
+                    send(proto::Message::new(
+                        "JOIN",
+                        &[self.settings.channels.join(",").as_str()],
+                    ))?;
+                }
+
+                if let Some(connect_result) = state.connect_result.take() {
+                    let _ = connect_result.send(Ok(()));
+                } else {
+                    events.push(Event::Reconnected);
+                }
+            }
+            // ERR_NICKNAMEINUSE
+            "433" if state.outgoing.is_none() => {
+                // Try another nickname during registration
+                state.nick.push('_');
+                send(proto::Message::new("NICK", &[state.nick.as_str()]))?;
+            }
+            "NICK" if from_me => {
+                state.nick = msg.param(0).to_owned();
+            }
+            "JOIN" if from_me => {
+                let name = msg.param(0);
+                if state.channel_mut(name).is_none() {
+                    state.channels.push(JoinedChannel {
+                        name: name.to_owned(),
+                        unread_count: 0,
+                        mentioned: false,
+                    });
+                    state.histories.entry(proto::fold_case(name)).or_default();
+                    events.push(Event::ChannelsChanged(self.channel_groups(&state)));
+                }
+            }
+            "PART" if from_me => {
+                state.remove_channel(msg.param(0));
+                events.push(Event::ChannelsChanged(self.channel_groups(&state)));
+            }
+            "KICK" if proto::fold_case(msg.param(1)) == proto::fold_case(&state.nick) => {
+                state.remove_channel(msg.param(0));
+                events.push(Event::ChannelsChanged(self.channel_groups(&state)));
+            }
+            "PRIVMSG" | "NOTICE" if proto::is_channel_name(msg.param(0)) => {
+                let body = msg.param(1);
+                let nick = state.nick.clone();
+
+                // The server may spell the channel name differently from
+                // our `JOIN`. Use the latter, which the channel ID is
+                // derived from.
+                let joined = if let Some(joined) = state.channel_mut(msg.param(0)) {
+                    joined
+                } else {
+                    log::debug!(
+                        "Ignoring a message to {:?}, which we haven't joined",
+                        msg.param(0)
+                    );
+                    return Ok(events);
+                };
+                if !from_me {
+                    joined.unread_count += 1;
+                    joined.mentioned |= proto::mentions(body, &nick);
+                }
+                let name = joined.name.clone();
+
+                // Use the time when the server received the message if
+                // available (`server-time`)
+                let timestamp = msg
+                    .tag("time")
+                    .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                    .map_or_else(Local::now, |t| t.with_timezone(&Local));
+
+                let author = msg.source_nick().unwrap_or("").to_owned();
+                let message = state.push_message(&name, author, timestamp, body);
+
+                events.push(Event::MessageReceived {
+                    channel: format!("{}/{}", self.group_id, name),
+                    message,
+                });
+            }
+            "ERROR" => {
+                return Err(Error::Connection(msg.param(0).to_owned()));
+            }
+            _ => {}
+        }
+
+        Ok(events)
+    }
+
+    /// Send events to the main thread. This must not be called while `state`
+    /// is locked because it may block until the main thread processes the
+    /// pending events, and the main thread may be waiting for the lock.
+    fn send_events(&self, events: Vec<Event>) {
+        if events.is_empty() {
+            return;
+        }
+
+        let sender = self.state.lock().unwrap().events.clone();
+        if let Some(sender) = sender {
+            for event in events {
+                let _ = sender.send(event);
+            }
+        }
+    }
+
+    fn channel_groups(&self, state: &State) -> Vec<model::ChannelGroup> {
+        vec![model::ChannelGroup {
+            id: self.group_id.clone(),
+            name: self.settings.server.clone(),
+            channels: state
+                .channels
+                .iter()
+                .map(|joined| model::Channel {
+                    id: format!("{}/{}", self.group_id, joined.name),
+                    name: joined.name.clone(),
+                    unread_count: joined.unread_count,
+                    mentioned: joined.mentioned,
+                })
+                .collect(),
+        }]
+    }
+
+    /// Get the IRC channel name from a channel ID.
+    fn channel_name<'a>(&self, channel: &'a str) -> Result<&'a str> {
+        let prefix_len = self.group_id.len() + 1;
+
+        if channel.len() > prefix_len
+            && channel.starts_with(&self.group_id)
+            && channel[self.group_id.len()..].starts_with('/')
+        {
+            Ok(&channel[prefix_len..])
+        } else {
+            Err(Error::NoSuchChannel(channel.to_owned()))
+        }
+    }
+}
+
+impl State {
+    /// Append a message to a channel's history.
+    fn push_message(
+        &mut self,
+        channel_name: &str,
+        author: String,
+        timestamp: DateTime<Local>,
+        body: &str,
+    ) -> model::Message {
+        let message = model::Message {
+            id: self.next_message_id,
+            author,
+            timestamp: timestamp.naive_local(),
+            body: body.to_owned(),
+        };
+        self.next_message_id += 1;
+
+        let history = self
+            .histories
+            .entry(proto::fold_case(channel_name))
+            .or_default();
+        if history.len() >= HISTORY_LEN_MAX {
+            history.pop_front();
+        }
+        history.push_back(message.clone());
+
+        message
+    }
+
+    /// Find a joined channel by a case-insensitive name.
+    fn channel_mut(&mut self, name: &str) -> Option<&mut JoinedChannel> {
+        let key = proto::fold_case(name);
+        self.channels
+            .iter_mut()
+            .find(|c| proto::fold_case(&c.name) == key)
+    }
+
+    fn remove_channel(&mut self, name: &str) {
+        let key = proto::fold_case(name);
+        self.channels.retain(|c| proto::fold_case(&c.name) != key);
+    }
+}
+
+/// Limits the rate of outgoing messages using the algorithm described in
+/// RFC 1459 section 8.10.
+struct FloodControl {
+    /// Advanced by `FLOOD_PENALTY` for every message. Never behind the
+    /// current time.
+    timer: Instant,
+}
+
+impl FloodControl {
+    fn new(now: Instant) -> Self {
+        Self { timer: now }
+    }
+
+    /// Account for a message to be sent and get how long to wait before
+    /// sending it.
+    fn delay(&mut self, now: Instant) -> Duration {
+        if self.timer < now {
+            self.timer = now;
+        }
+        let ahead = self.timer - now;
+        self.timer += FLOOD_PENALTY;
+        ahead.checked_sub(FLOOD_BURST).unwrap_or_default()
+    }
+}
+
+fn io_error(e: io::Error) -> Error {
+    Error::Connection(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    const GROUP_ID: &str = "irc:irc.example.com";
+
+    /// Feeds messages to `Shared::handle_message` and collects the responses.
+    struct Harness {
+        backend: IrcBackend,
+        outgoing: mpsc::Sender<proto::Message>,
+        outgoing_recv: mpsc::Receiver<proto::Message>,
+        available_caps: Vec<String>,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let (outgoing, outgoing_recv) = mpsc::channel();
+            let backend = IrcBackend::new(IrcSettings {
+                server: "irc.example.com".to_owned(),
+                tls: true,
+                port: None,
+                nick: "alice".to_owned(),
+                username: None,
+                realname: None,
+                password: None,
+                channels: vec!["#rust".to_owned(), "#tcw3".to_owned()],
+            });
+            backend.shared.state.lock().unwrap().nick = "alice".to_owned();
+
+            Self {
+                backend,
+                outgoing,
+                outgoing_recv,
+                available_caps: Vec::new(),
+            }
+        }
+
+        /// Process a line received from the server and return the events
+        /// to be sent to the main thread.
+        fn recv(&mut self, line: &str) -> Vec<Event> {
+            let msg = proto::Message::parse(line).unwrap();
+            (self.backend.shared)
+                .handle_message(&msg, &self.outgoing, &mut self.available_caps)
+                .unwrap()
+        }
+
+        /// Get the messages sent to the server so far.
+        fn sent(&self) -> Vec<String> {
+            self.outgoing_recv
+                .try_iter()
+                .map(|m| m.to_string())
+                .collect()
+        }
+
+        /// Complete the registration and join `#Rust`.
+        fn register(&mut self) {
+            self.recv(":irc.example.com 001 alice :Welcome");
+            self.recv(":alice!a@example.com JOIN #Rust");
+            self.sent();
+        }
+
+        fn channels(&self) -> Vec<model::Channel> {
+            let shared = &self.backend.shared;
+            let groups = shared.channel_groups(&shared.state.lock().unwrap());
+            groups.into_iter().next().unwrap().channels
+        }
+    }
+
+    #[test]
+    fn negotiate_caps() {
+        let mut h = Harness::new();
+
+        // Capabilities are requested after the last line of `CAP LS`
+        h.recv(":irc.example.com CAP * LS * :multi-prefix sasl=PLAIN");
+        assert_eq!(h.sent(), Vec::<String>::new());
+        h.recv(":irc.example.com CAP * LS :server-time away-notify");
+        assert_eq!(h.sent(), ["CAP REQ :multi-prefix server-time"]);
+
+        h.recv(":irc.example.com CAP * ACK :multi-prefix server-time");
+        assert_eq!(h.sent(), ["CAP END"]);
+        let caps = &h.backend.shared.state.lock().unwrap().caps;
+        assert!(caps.contains("server-time"));
+        assert!(!caps.contains("echo-message"));
+    }
+
+    #[test]
+    fn negotiate_no_caps() {
+        let mut h = Harness::new();
+        h.recv(":irc.example.com CAP * LS :away-notify");
+        assert_eq!(h.sent(), ["CAP END"]);
+    }
+
+    #[test]
+    fn register() {
+        let mut h = Harness::new();
+        let (send, mut connect_result) = oneshot::channel();
+        h.backend.shared.state.lock().unwrap().connect_result = Some(send);
+
+        // Another nickname is tried during registration
+        h.recv(":irc.example.com 433 * alice :Nickname is already in use");
+        assert_eq!(h.sent(), ["NICK alice_"]);
+
+        let events = h.recv(":irc.example.com 001 alice_ :Welcome");
+        assert_eq!(h.sent(), ["JOIN #rust,#tcw3"]);
+        assert!(matches!(&events[..], [Event::ChannelsChanged(_)]));
+        assert!(matches!(connect_result.try_recv(), Ok(Some(Ok(())))));
+
+        let state = h.backend.shared.state.lock().unwrap();
+        assert_eq!(state.nick, "alice_");
+        assert!(state.outgoing.is_some());
+    }
+
+    #[test]
+    fn reconnect() {
+        let mut h = Harness::new();
+
+        // There's no pending `connect`, so this is a reconnection
+        let events = h.recv(":irc.example.com 001 alice :Welcome");
+        assert!(matches!(
+            &events[..],
+            [Event::ChannelsChanged(_), Event::Reconnected]
+        ));
+    }
+
+    #[test]
+    fn ping() {
+        let mut h = Harness::new();
+        h.recv("PING :irc.example.com");
+        assert_eq!(h.sent(), ["PONG irc.example.com"]);
+    }
+
+    #[test]
+    fn join_and_part() {
+        let mut h = Harness::new();
+        h.recv(":irc.example.com 001 alice :Welcome");
+
+        let events = h.recv(":alice!a@example.com JOIN #Rust");
+        match &events[..] {
+            [Event::ChannelsChanged(groups)] => {
+                assert_eq!(groups[0].channels[0].id, format!("{}/#Rust", GROUP_ID));
+            }
+            _ => panic!("{:?}", events),
+        }
+
+        // Others joining doesn't change our channel list
+        assert!(h.recv(":bob!b@example.com JOIN #tcw3").is_empty());
+        // A duplicate `JOIN` is ignored
+        assert!(h.recv(":alice!a@example.com JOIN #rust").is_empty());
+        assert_eq!(h.channels().len(), 1);
+
+        h.recv(":alice!a@example.com PART #RUST :bye");
+        assert!(h.channels().is_empty());
+    }
+
+    #[test]
+    fn kick() {
+        let mut h = Harness::new();
+        h.register();
+
+        h.recv(":bob!b@example.com KICK #rust carol :spam");
+        assert_eq!(h.channels().len(), 1);
+        h.recv(":bob!b@example.com KICK #rust ALICE :spam");
+        assert!(h.channels().is_empty());
+    }
+
+    #[test]
+    fn receive_message() {
+        let mut h = Harness::new();
+        h.register();
+
+        // The channel name is spelled differently from our `JOIN`
+        let events =
+            h.recv("@time=2020-05-01T12:00:00.000Z :bob!b@example.com PRIVMSG #rust :hi alice");
+        match &events[..] {
+            [Event::MessageReceived { channel, message }] => {
+                assert_eq!(*channel, format!("{}/#Rust", GROUP_ID));
+                assert_eq!(message.author, "bob");
+                assert_eq!(message.body, "hi alice");
+            }
+            _ => panic!("{:?}", events),
+        }
+
+        let history = block_on(
+            h.backend
+                .fetch_history(&format!("{}/#Rust", GROUP_ID), None, 10),
+        );
+        assert_eq!(history.unwrap().len(), 1);
+
+        // A message to a channel we haven't joined is ignored
+        assert!(h.recv(":bob!b@example.com PRIVMSG #tcw3 :hi").is_empty());
+        // So is a private message
+        assert!(h.recv(":bob!b@example.com PRIVMSG alice :hi").is_empty());
+    }
+
+    #[test]
+    fn unread_count() {
+        let mut h = Harness::new();
+        h.register();
+        let channel_id = format!("{}/#Rust", GROUP_ID);
+
+        h.recv(":bob!b@example.com PRIVMSG #rust :hello");
+        assert_eq!(h.channels()[0].unread_count, 1);
+        assert!(!h.channels()[0].mentioned);
+
+        h.recv(":bob!b@example.com NOTICE #rust :alice: ping");
+        assert_eq!(h.channels()[0].unread_count, 2);
+        assert!(h.channels()[0].mentioned);
+
+        // Our own messages echoed back don't count
+        h.recv(":alice!a@example.com PRIVMSG #rust :pong");
+        assert_eq!(h.channels()[0].unread_count, 2);
+
+        block_on(h.backend.mark_read(&channel_id)).unwrap();
+        assert_eq!(h.channels()[0].unread_count, 0);
+        assert!(!h.channels()[0].mentioned);
+    }
+
+    #[test]
+    fn flood_control() {
+        let start = Instant::now();
+        let mut fc = FloodControl::new(start);
+
+        // A burst of messages is sent immediately
+        for _ in 0..6 {
+            assert_eq!(fc.delay(start), Duration::from_secs(0));
+        }
+        assert_eq!(fc.delay(start), Duration::from_secs(2));
+        assert_eq!(fc.delay(start), Duration::from_secs(4));
+
+        // The timer catches up over time
+        let later = start + Duration::from_secs(60);
+        assert_eq!(fc.delay(later), Duration::from_secs(0));
+    }
+}
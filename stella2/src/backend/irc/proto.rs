@@ -0,0 +1,277 @@
+//! IRC message syntax (RFC 2812 section 2.3.1) with IRCv3 message tags
+use std::fmt;
+
+/// The maximum length of a message excluding tags, including the trailing
+/// CR-LF.
+pub const LINE_LEN_MAX: usize = 512;
+
+/// An IRC message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// IRCv3 message tags. Values are unescaped.
+    pub tags: Vec<(String, Option<String>)>,
+    pub prefix: Option<String>,
+    pub command: String,
+    pub params: Vec<String>,
+}
+
+impl Message {
+    pub fn new(command: &str, params: &[&str]) -> Self {
+        Self {
+            tags: Vec::new(),
+            prefix: None,
+            command: command.to_owned(),
+            params: params.iter().map(|&p| p.to_owned()).collect(),
+        }
+    }
+
+    /// Parse a line without the trailing CR-LF. Returns `None` if the line is
+    /// malformed.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut rest = line;
+
+        let mut tags = Vec::new();
+        if rest.starts_with('@') {
+            let (tags_str, r) = split_word(&rest[1..]);
+            rest = r;
+
+            for tag in tags_str.split(';').filter(|t| !t.is_empty()) {
+                let mut kv = tag.splitn(2, '=');
+                let key = kv.next().unwrap().to_owned();
+                let value = kv.next().map(unescape_tag_value);
+                tags.push((key, value));
+            }
+        }
+
+        let mut prefix = None;
+        if rest.starts_with(':') {
+            let (prefix_str, r) = split_word(&rest[1..]);
+            prefix = Some(prefix_str.to_owned());
+            rest = r;
+        }
+
+        let (command, mut rest) = split_word(rest);
+        if command.is_empty() {
+            return None;
+        }
+
+        let mut params = Vec::new();
+        while !rest.is_empty() {
+            if rest.starts_with(':') {
+                params.push(rest[1..].to_owned());
+                break;
+            }
+            let (param, r) = split_word(rest);
+            params.push(param.to_owned());
+            rest = r;
+        }
+
+        Some(Self {
+            tags,
+            prefix,
+            command: command.to_ascii_uppercase(),
+            params,
+        })
+    }
+
+    /// Get the value of the specified tag.
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(k, _)| k == key)
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    /// Get the nickname part of the prefix, e.g., `nick` in
+    /// `nick!user@host`.
+    pub fn source_nick(&self) -> Option<&str> {
+        let prefix = self.prefix.as_ref()?;
+        Some(prefix.split(|c| c == '!' || c == '@').next().unwrap())
+    }
+
+    /// Get the specified parameter or an empty string if it doesn't exist.
+    pub fn param(&self, i: usize) -> &str {
+        self.params.get(i).map_or("", String::as_str)
+    }
+}
+
+/// Serializes a message without the trailing CR-LF. Tags are not included
+/// because we don't send client tags.
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(f, ":{} ", prefix)?;
+        }
+        f.write_str(&self.command)?;
+
+        for (i, param) in self.params.iter().enumerate() {
+            let is_last = i == self.params.len() - 1;
+            if is_last && (param.is_empty() || param.starts_with(':') || param.contains(' ')) {
+                write!(f, " :{}", param)?;
+            } else {
+                write!(f, " {}", param)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Split `s` at the first space, skipping the following spaces.
+fn split_word(s: &str) -> (&str, &str) {
+    match s.find(' ') {
+        Some(i) => (&s[..i], s[i..].trim_start_matches(' ')),
+        None => (s, ""),
+    }
+}
+
+/// Unescape a tag value as specified by IRCv3 Message Tags.
+fn unescape_tag_value(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some(':') => out.push(';'),
+            Some('s') => out.push(' '),
+            Some('r') => out.push('\r'),
+            Some('n') => out.push('\n'),
+            Some(c) => out.push(c),
+            // A trailing backslash is dropped
+            None => {}
+        }
+    }
+
+    out
+}
+
+/// Check if `name` is a channel name (as opposed to a nickname).
+pub fn is_channel_name(name: &str) -> bool {
+    name.starts_with(|c| c == '#' || c == '&' || c == '+' || c == '!')
+}
+
+/// Convert a nickname or channel name to the canonical form for
+/// case-insensitive comparison, using the `rfc1459` case mapping.
+pub fn fold_case(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            'A'..='Z' => c.to_ascii_lowercase(),
+            '[' => '{',
+            ']' => '}',
+            '\\' => '|',
+            '~' => '^',
+            _ => c,
+        })
+        .collect()
+}
+
+/// Check if `text` mentions `nick` as a whole word, ignoring case.
+pub fn mentions(text: &str, nick: &str) -> bool {
+    // The characters allowed in nicknames (RFC 2812 section 2.3.1)
+    let is_nick_char = |c: char| c.is_ascii_alphanumeric() || "-[]\\`^_{|}".contains(c);
+
+    let text = fold_case(text);
+    let nick = fold_case(nick);
+    if nick.is_empty() {
+        return false;
+    }
+
+    text.match_indices(nick.as_str()).any(|(i, _)| {
+        let before = text[..i].chars().next_back();
+        let after = text[i + nick.len()..].chars().next();
+        !before.map_or(false, is_nick_char) && !after.map_or(false, is_nick_char)
+    })
+}
+
+/// Split `text` into chunks of at most `len_max` bytes, at character
+/// boundaries.
+pub fn split_text(text: &str, len_max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+
+    while rest.len() > len_max {
+        let mut i = len_max;
+        while !rest.is_char_boundary(i) {
+            i -= 1;
+        }
+        if i == 0 {
+            // `len_max` is smaller than the character
+            i = rest.chars().next().unwrap().len_utf8();
+        }
+        chunks.push(&rest[..i]);
+        rest = &rest[i..];
+    }
+    chunks.push(rest);
+
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let msg = Message::parse(
+            "@time=2020-05-01T12:00:00.000Z;msgid=a\\sb :nick!user@host \
+             PRIVMSG #rust :hello world",
+        )
+        .unwrap();
+        assert_eq!(msg.tag("time"), Some("2020-05-01T12:00:00.000Z"));
+        assert_eq!(msg.tag("msgid"), Some("a b"));
+        assert_eq!(msg.source_nick(), Some("nick"));
+        assert_eq!(msg.command, "PRIVMSG");
+        assert_eq!(msg.params, ["#rust", "hello world"]);
+
+        let msg = Message::parse("ping  irc.example.com").unwrap();
+        assert_eq!(msg.prefix, None);
+        assert_eq!(msg.command, "PING");
+        assert_eq!(msg.params, ["irc.example.com"]);
+
+        let msg = Message::parse(":irc.example.com CAP * LS :").unwrap();
+        assert_eq!(msg.params, ["*", "LS", ""]);
+
+        assert_eq!(Message::parse(""), None);
+        assert_eq!(Message::parse(":prefix-only"), None);
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            Message::new("PRIVMSG", &["#rust", "hello world"]).to_string(),
+            "PRIVMSG #rust :hello world"
+        );
+        assert_eq!(Message::new("NICK", &["alice"]).to_string(), "NICK alice");
+        assert_eq!(
+            Message::new("PRIVMSG", &["#rust", ":)"]).to_string(),
+            "PRIVMSG #rust ::)"
+        );
+    }
+
+    #[test]
+    fn case_mapping() {
+        assert_eq!(fold_case("#Rust[Lang]"), fold_case("#rust{lang}"));
+        assert_ne!(fold_case("#rust"), fold_case("#rusty"));
+    }
+
+    #[test]
+    fn mention() {
+        assert!(mentions("Alice: hi", "alice"));
+        assert!(mentions("thanks, alice!", "alice"));
+        assert!(mentions("hi alice", "Alice"));
+        assert!(!mentions("hi alicex", "alice"));
+        assert!(!mentions("malice", "alice"));
+        assert!(!mentions("hi", ""));
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(split_text("abcdef", 4), ["abcd", "ef"]);
+        assert_eq!(split_text("abc", 4), ["abc"]);
+        assert_eq!(split_text("あいう", 4), ["あ", "い", "う"]);
+    }
+}
@@ -0,0 +1,120 @@
+//! TLS connections
+//!
+//! The worker thread reads from the connection while the writer thread
+//! writes to it. Both of them share a `rustls::ClientSession`, which is
+//! locked only while processing data, never while waiting for incoming data.
+//! Server certificates are verified against the roots provided by
+//! `webpki-roots`.
+use rustls::Session;
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex},
+};
+
+/// The reading half of a TLS connection.
+pub struct Reader {
+    tcp: TcpStream,
+    session: Arc<Mutex<rustls::ClientSession>>,
+    buf: Box<[u8]>,
+}
+
+/// The writing half of a TLS connection.
+pub struct Writer {
+    tcp: TcpStream,
+    session: Arc<Mutex<rustls::ClientSession>>,
+}
+
+/// Start a TLS handshake with `server_name` over `tcp`. The handshake
+/// proceeds as `Reader` receives data. Data written to `Writer` before the
+/// handshake completes is sent after that.
+pub fn connect(tcp: &TcpStream, server_name: &str) -> io::Result<(Reader, Writer)> {
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str(server_name).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} is not a valid DNS name", server_name),
+        )
+    })?;
+
+    let mut config = rustls::ClientConfig::new();
+    config
+        .root_store
+        .add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
+
+    let mut session = rustls::ClientSession::new(&Arc::new(config), dns_name);
+    let mut writer_tcp = tcp.try_clone()?;
+
+    // Send `ClientHello`
+    write_pending(&mut session, &mut writer_tcp)?;
+
+    let session = Arc::new(Mutex::new(session));
+
+    Ok((
+        Reader {
+            tcp: tcp.try_clone()?,
+            session: Arc::clone(&session),
+            buf: vec![0; 16384].into_boxed_slice(),
+        },
+        Writer {
+            tcp: writer_tcp,
+            session,
+        },
+    ))
+}
+
+/// Send the TLS records produced by `session`.
+fn write_pending(session: &mut rustls::ClientSession, tcp: &mut TcpStream) -> io::Result<()> {
+    while session.wants_write() {
+        session.write_tls(tcp)?;
+    }
+    Ok(())
+}
+
+impl Read for Reader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match self.session.lock().unwrap().read(out) {
+                Ok(0) => {}
+                // `close_notify` was received
+                Err(ref e) if e.kind() == io::ErrorKind::ConnectionAborted => return Ok(0),
+                result => return result,
+            }
+
+            // Errors including timeouts are reported to the caller, who may
+            // call `read` again
+            let len = self.tcp.read(&mut self.buf)?;
+            if len == 0 {
+                return Ok(0);
+            }
+
+            let mut session = self.session.lock().unwrap();
+            let mut data = &self.buf[..len];
+            while !data.is_empty() {
+                session.read_tls(&mut data)?;
+                session
+                    .process_new_packets()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+
+            // Send handshake messages, and data written before the handshake
+            // was completed
+            write_pending(&mut session, &mut self.tcp)?;
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut session = self.session.lock().unwrap();
+        let len = session.write(buf)?;
+        write_pending(&mut session, &mut self.tcp)?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut session = self.session.lock().unwrap();
+        session.flush()?;
+        write_pending(&mut session, &mut self.tcp)?;
+        self.tcp.flush()
+    }
+}
@@ -0,0 +1,1088 @@
+//! The Matrix backend
+//!
+//! The worker thread logs in to the homeserver and runs the `/sync` loop,
+//! which long-polls the server for new events. Requests made on behalf of the
+//! main thread (sending messages, fetching history, and sending read
+//! receipts) are performed on short-lived threads so that they don't have to
+//! wait for the long poll to finish. So is the long poll itself, so that the
+//! worker thread can exit as soon as the backend is dropped.
+//!
+//! The access token is stored in the platform's credential store via
+//! `pal::secrets`. So is the password, which is only needed for the first
+//! login and whenever the stored token is revoked. A password found in the
+//! settings file is moved to the credential store on the next connection
+//! attempt (`SettingsStore` has already removed it from the file by then).
+//!
+//! Matrix identifies events by strings, whereas `model::Message::id` is an
+//! integer that increases with time. Each room assigns increasing IDs to the
+//! events received by `/sync` starting from `ID_ORIGIN`, and decreasing IDs to
+//! the events fetched by `/messages`. `ID_ORIGIN` is chosen so that the latter
+//! stay above `MESSAGE_ID_ORIGIN` in practice.
+//!
+//! TODO: Fill the gap left by a limited (truncated) timeline
+//! TODO: Support end-to-end encrypted rooms
+use chrono::{Local, TimeZone};
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tcw3::pal;
+
+use super::{Backend, Error, Event, EventSender, Result, MESSAGE_ID_ORIGIN};
+use crate::{config::settings::MatrixSettings, model};
+
+/// The service name of the access tokens in the credential store.
+const SECRET_SERVICE: &str = "Stella2 Matrix access token";
+
+/// The service name of the passwords in the credential store.
+const PASSWORD_SECRET_SERVICE: &str = "Stella2 Matrix password";
+
+const API_PREFIX: &str = "/_matrix/client/r0";
+
+/// How long the server may hold a `/sync` request when there are no new
+/// events.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The additional time allowed for a response to arrive.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The number of timeline events requested per room by the initial sync.
+const INITIAL_TIMELINE_LEN: usize = 20;
+
+/// The number of events requested per `/messages` call.
+const BACKFILL_LEN: usize = 50;
+
+/// The delay before the first reconnection attempt. Doubled on every failed
+/// attempt.
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(2);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(300);
+
+/// A session that lasted this long resets the reconnection delay.
+const STABLE_CONNECTION_DURATION: Duration = Duration::from_secs(60);
+
+/// The maximum number of messages retained for each room.
+const HISTORY_LEN_MAX: usize = 1000;
+
+/// The message ID assigned to the first event received by `/sync`.
+const ID_ORIGIN: u64 = MESSAGE_ID_ORIGIN + (1 << 61);
+
+pub struct MatrixBackend {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    settings: MatrixSettings,
+    /// `settings.homeserver` without a trailing slash.
+    base_url: String,
+    /// Identifies the channel group.
+    group_id: String,
+    /// Identifies the access token and the password in the credential store.
+    secret_account: String,
+    shutdown: AtomicBool,
+    /// Wakes up the worker thread.
+    wake: Mutex<mpsc::Sender<Wake>>,
+    state: Mutex<State>,
+}
+
+/// Messages to the worker thread.
+enum Wake {
+    /// The backend was dropped.
+    Shutdown,
+    /// A `/sync` request completed.
+    Sync(std::result::Result<SyncResponse, ApiError>),
+}
+
+#[derive(Default)]
+struct State {
+    events: Option<EventSender>,
+    /// Completes the future returned by `connect`.
+    connect_result: Option<oneshot::Sender<Result<()>>>,
+    /// Taken by the worker thread when it starts.
+    wake_recv: Option<mpsc::Receiver<Wake>>,
+    /// `true` if the last session failed. Reset by the next successful
+    /// `/sync`, which reports `Event::Reconnected`.
+    disconnected: bool,
+
+    access_token: Option<String>,
+    /// The fully-qualified user ID, e.g., `@alice:example.com`.
+    user_id: String,
+    /// The `next_batch` token of the last `/sync`. It's kept across sessions
+    /// so that the events that arrived while disconnected are reported as
+    /// new messages.
+    since: Option<String>,
+    /// The joined rooms, keyed by room IDs.
+    rooms: HashMap<String, Room>,
+    /// The channel list most recently reported by `Event::ChannelsChanged`.
+    reported_groups: Vec<model::ChannelGroup>,
+    next_txn_id: u64,
+}
+
+struct Room {
+    /// The value of `m.room.name`.
+    name: Option<String>,
+    /// The value of `m.room.canonical_alias`.
+    canonical_alias: Option<String>,
+    /// The display names of the joined members.
+    members: HashMap<String, String>,
+    /// The messages and the IDs of the events they originate from.
+    history: VecDeque<(String, model::Message)>,
+    /// The pagination token for fetching older events. `None` if there are
+    /// no more events.
+    prev_batch: Option<String>,
+    /// The ID assigned to the next event received by `/sync`.
+    next_id: u64,
+    /// The ID assigned to the next event fetched by `/messages`.
+    prev_id: u64,
+    unread_count: u32,
+    mentioned: bool,
+}
+
+impl MatrixBackend {
+    pub fn new(settings: MatrixSettings) -> Self {
+        let base_url = settings.homeserver.trim_end_matches('/').to_owned();
+        let group_id = format!("matrix:{}", host_name(&base_url));
+        let secret_account = format!("{}@{}", settings.user, base_url);
+        let (wake, wake_recv) = mpsc::channel();
+
+        Self {
+            shared: Arc::new(Shared {
+                settings,
+                base_url,
+                group_id,
+                secret_account,
+                shutdown: AtomicBool::new(false),
+                wake: Mutex::new(wake),
+                state: Mutex::new(State {
+                    wake_recv: Some(wake_recv),
+                    ..State::default()
+                }),
+            }),
+        }
+    }
+
+    /// Call `f` on a new thread and return a future that completes with its
+    /// result.
+    fn spawn_request<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Shared) -> Result<T> + Send + 'static,
+    ) -> BoxFuture<'static, Result<T>> {
+        let (send, recv) = oneshot::channel();
+        let shared = Arc::clone(&self.shared);
+
+        thread::Builder::new()
+            .name("Matrix request".to_owned())
+            .spawn(move || {
+                let _ = send.send(f(&shared));
+            })
+            .unwrap();
+
+        recv.map(|result| result.unwrap_or(Err(Error::NotConnected)))
+            .boxed()
+    }
+}
+
+impl Drop for MatrixBackend {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+
+        // Wake up the worker thread if it's waiting for `/sync` or a
+        // reconnection attempt
+        let _ = self.shared.wake.lock().unwrap().send(Wake::Shutdown);
+    }
+}
+
+impl Backend for MatrixBackend {
+    fn connect(&self, events: EventSender) -> BoxFuture<'static, Result<()>> {
+        let (send, recv) = oneshot::channel();
+
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            state.events = Some(events);
+            state.connect_result = Some(send);
+
+            if let Some(wake_recv) = state.wake_recv.take() {
+                let shared = Arc::clone(&self.shared);
+                thread::Builder::new()
+                    .name("Matrix worker".to_owned())
+                    .spawn(move || shared.run(wake_recv))
+                    .unwrap();
+            }
+        }
+
+        recv.map(|result| result.unwrap_or(Err(Error::NotConnected)))
+            .boxed()
+    }
+
+    fn list_channels(&self) -> BoxFuture<'static, Result<Vec<model::ChannelGroup>>> {
+        let result = self
+            .shared
+            .channel_groups(&self.shared.state.lock().unwrap());
+        async { Ok(result) }.boxed()
+    }
+
+    fn fetch_history(
+        &self,
+        channel: &str,
+        before: Option<u64>,
+        limit: usize,
+    ) -> BoxFuture<'static, Result<Vec<model::Message>>> {
+        let room_id = match self.shared.room_id(channel) {
+            Ok(x) => x.to_owned(),
+            Err(e) => return async { Err(e) }.boxed(),
+        };
+
+        self.spawn_request(move |shared| shared.fetch_history(&room_id, before, limit))
+    }
+
+    fn send_message(&self, channel: &str, body: &str) -> BoxFuture<'static, Result<()>> {
+        let room_id = match self.shared.room_id(channel) {
+            Ok(x) => x.to_owned(),
+            Err(e) => return async { Err(e) }.boxed(),
+        };
+        let body = body.to_owned();
+
+        // The sent message is reported back by `/sync`
+        self.spawn_request(move |shared| shared.send_message(&room_id, &body))
+    }
+
+    fn mark_read(&self, channel: &str) -> BoxFuture<'static, Result<()>> {
+        let room_id = match self.shared.room_id(channel) {
+            Ok(x) => x.to_owned(),
+            Err(e) => return async { Err(e) }.boxed(),
+        };
+
+        self.spawn_request(move |shared| shared.mark_read(&room_id))
+    }
+}
+
+impl Shared {
+    /// The main function of the worker thread.
+    fn run(self: Arc<Self>, wake_recv: mpsc::Receiver<Wake>) {
+        let mut delay = RECONNECT_DELAY_MIN;
+
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let start = Instant::now();
+            let e = match self.run_session(&wake_recv) {
+                Ok(()) => break,
+                Err(e) => e,
+            };
+
+            log::warn!("Matrix session on {:?} failed: {}", self.base_url, e);
+
+            // Fail the pending call to `connect` or report the disconnection
+            let connect_result = {
+                let mut state = self.state.lock().unwrap();
+                state.disconnected = true;
+                state.connect_result.take()
+            };
+            if let Some(connect_result) = connect_result {
+                let _ = connect_result.send(Err(e));
+            } else {
+                self.send_events(vec![Event::Disconnected(e)]);
+            }
+
+            if start.elapsed() >= STABLE_CONNECTION_DURATION {
+                delay = RECONNECT_DELAY_MIN;
+            }
+
+            log::info!("Reconnecting in {:?}", delay);
+            match wake_recv.recv_timeout(delay) {
+                Ok(Wake::Shutdown) | Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                Ok(Wake::Sync(_)) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            }
+            delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+        }
+
+        log::debug!("The Matrix worker thread is exiting");
+    }
+
+    /// Log in and run the sync loop until an error occurs or the backend is
+    /// dropped.
+    fn run_session(self: &Arc<Self>, wake_recv: &mpsc::Receiver<Wake>) -> Result<()> {
+        self.log_in()?;
+
+        // TODO: Persist the sync token so that the initial sync can be
+        //       skipped on the next launch
+        let filter = json!({
+            "room": { "timeline": { "limit": INITIAL_TIMELINE_LEN } },
+        })
+        .to_string();
+        let timeout_ms = (SYNC_TIMEOUT.as_millis() as u64).to_string();
+
+        while !self.shutdown.load(Ordering::Relaxed) {
+            let since = self.state.lock().unwrap().since.clone();
+            let mut query = vec![("timeout", timeout_ms.clone())];
+            if let Some(since) = &since {
+                query.push(("since", since.clone()));
+            } else {
+                query.push(("filter", filter.clone()));
+            }
+
+            let shared = Arc::clone(self);
+            thread::Builder::new()
+                .name("Matrix sync".to_owned())
+                .spawn(move || {
+                    let query: Vec<_> = query.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                    let timeout = SYNC_TIMEOUT + REQUEST_TIMEOUT;
+                    let result = shared.request("GET", "/sync", &query, None, timeout);
+                    let _ = shared.wake.lock().unwrap().send(Wake::Sync(result));
+                })
+                .unwrap();
+
+            let result = match wake_recv.recv() {
+                Ok(Wake::Sync(result)) => result,
+                Ok(Wake::Shutdown) | Err(_) => break,
+            };
+
+            let response: SyncResponse = match result {
+                Ok(x) => x,
+                Err(ApiError::Server { ref errcode, .. }) if errcode == "M_UNKNOWN_TOKEN" => {
+                    // The token was revoked, e.g., by logging out from
+                    // another client. Log in again on the next attempt.
+                    self.state.lock().unwrap().access_token = None;
+                    if let Err(e) = pal::secrets::delete(SECRET_SERVICE, &self.secret_account) {
+                        log::warn!("Could not delete the access token: {}", e);
+                    }
+                    return Err(Error::Connection("The access token was revoked".to_owned()));
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            self.handle_sync(&response, since.is_none());
+            self.state.lock().unwrap().since = Some(response.next_batch);
+        }
+
+        Ok(())
+    }
+
+    /// Load the access token from the credential store, or log in with the
+    /// password if there is no stored token.
+    fn log_in(&self) -> Result<()> {
+        if self.state.lock().unwrap().access_token.is_some() {
+            return Ok(());
+        }
+
+        if let Some(password) = &self.settings.password {
+            // The password came from the settings file, which doesn't have it
+            // anymore. Keep it for the next time the token is revoked.
+            if let Err(e) =
+                pal::secrets::store(PASSWORD_SECRET_SERVICE, &self.secret_account, password)
+            {
+                log::warn!("Could not store the password: {}", e);
+            }
+        }
+
+        let stored_token =
+            pal::secrets::load(SECRET_SERVICE, &self.secret_account).unwrap_or_else(|e| {
+                log::warn!("Could not load the access token: {}", e);
+                None
+            });
+
+        if let Some(token) = stored_token {
+            self.state.lock().unwrap().access_token = Some(token);
+
+            match self.request::<WhoamiResponse>(
+                "GET",
+                "/account/whoami",
+                &[],
+                None,
+                REQUEST_TIMEOUT,
+            ) {
+                Ok(response) => {
+                    self.state.lock().unwrap().user_id = response.user_id;
+                    return Ok(());
+                }
+                Err(ApiError::Server { ref errcode, .. }) if errcode == "M_UNKNOWN_TOKEN" => {
+                    log::info!("The stored access token is invalid. Logging in again");
+                    self.state.lock().unwrap().access_token = None;
+                }
+                Err(e) => {
+                    self.state.lock().unwrap().access_token = None;
+                    return Err(e.into());
+                }
+            }
+        }
+
+        let password = match &self.settings.password {
+            Some(x) => Some(x.clone()),
+            None => pal::secrets::load(PASSWORD_SECRET_SERVICE, &self.secret_account)
+                .unwrap_or_else(|e| {
+                    log::warn!("Could not load the password: {}", e);
+                    None
+                }),
+        };
+        let password = password.ok_or_else(|| {
+            Error::Connection("Not logged in, and no password is specified".to_owned())
+        })?;
+
+        log::info!(
+            "Logging in to {:?} as {:?}",
+            self.base_url,
+            self.settings.user
+        );
+
+        let response: LoginResponse = self.request(
+            "POST",
+            "/login",
+            &[],
+            Some(json!({
+                "type": "m.login.password",
+                "identifier": { "type": "m.id.user", "user": self.settings.user },
+                "password": password,
+                "initial_device_display_name": "Stella2",
+            })),
+            REQUEST_TIMEOUT,
+        )?;
+
+        if let Err(e) =
+            pal::secrets::store(SECRET_SERVICE, &self.secret_account, &response.access_token)
+        {
+            log::warn!("Could not store the access token: {}", e);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.access_token = Some(response.access_token);
+        state.user_id = response.user_id;
+
+        Ok(())
+    }
+
+    /// Apply a `/sync` response to the local state and report the changes to
+    /// the main thread.
+    fn handle_sync(&self, response: &SyncResponse, initial: bool) {
+        let mut events = Vec::new();
+        let mut state_guard = self.state.lock().unwrap();
+        let state = &mut *state_guard;
+
+        for (room_id, joined) in response.rooms.join.iter() {
+            let room = state.rooms.entry(room_id.clone()).or_insert_with(|| Room {
+                name: None,
+                canonical_alias: None,
+                members: HashMap::new(),
+                history: VecDeque::new(),
+                prev_batch: joined.timeline.prev_batch.clone(),
+                next_id: ID_ORIGIN,
+                prev_id: ID_ORIGIN,
+                unread_count: 0,
+                mentioned: false,
+            });
+
+            for event in joined.state.events.iter() {
+                room.apply_state_event(event);
+            }
+
+            for event in joined.timeline.events.iter() {
+                room.apply_state_event(event);
+
+                let event_id = if let Some(x) = &event.event_id {
+                    x
+                } else {
+                    continue;
+                };
+
+                // An event may be delivered again after re-logging in
+                if room.history.iter().any(|(id, _)| id == event_id) {
+                    continue;
+                }
+
+                let id = room.next_id;
+                if let Some(message) = room.message_from_event(event, id) {
+                    room.next_id += 1;
+
+                    if room.history.len() >= HISTORY_LEN_MAX {
+                        // Backfilling would leave a gap where the dropped
+                        // message was, so stop it altogether
+                        room.history.pop_front();
+                        room.prev_batch = None;
+                    }
+                    room.history.push_back((event_id.clone(), message.clone()));
+
+                    // The initial sync only contains past messages, which are
+                    // loaded through `fetch_history` instead
+                    if !initial {
+                        events.push(Event::MessageReceived {
+                            channel: format!("{}/{}", self.group_id, room_id),
+                            message,
+                        });
+                    }
+                }
+            }
+
+            let unread = &joined.unread_notifications;
+            room.unread_count = unread.notification_count;
+            room.mentioned = unread.highlight_count > 0;
+        }
+
+        for room_id in response.rooms.leave.keys() {
+            state.rooms.remove(room_id);
+        }
+
+        let groups = self.channel_groups(state);
+        if groups != state.reported_groups {
+            state.reported_groups = groups.clone();
+            events.insert(0, Event::ChannelsChanged(groups));
+        }
+
+        if let Some(connect_result) = state.connect_result.take() {
+            let _ = connect_result.send(Ok(()));
+        } else if state.disconnected {
+            events.push(Event::Reconnected);
+        }
+        state.disconnected = false;
+
+        drop(state_guard);
+        self.send_events(events);
+    }
+
+    fn fetch_history(
+        &self,
+        room_id: &str,
+        before: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<model::Message>> {
+        loop {
+            let token = {
+                let state = self.state.lock().unwrap();
+                let room = state
+                    .rooms
+                    .get(room_id)
+                    .ok_or_else(|| Error::NoSuchChannel(room_id.to_owned()))?;
+
+                let end = before.map_or(room.history.len(), |before| {
+                    room.history
+                        .iter()
+                        .take_while(|(_, m)| m.id < before)
+                        .count()
+                });
+
+                match &room.prev_batch {
+                    Some(token) if end < limit => token.clone(),
+                    _ => {
+                        let start = end.saturating_sub(limit);
+                        return Ok(room
+                            .history
+                            .range(start..end)
+                            .map(|(_, m)| m.clone())
+                            .collect());
+                    }
+                }
+            };
+
+            // Fetch older events
+            let limit_str = BACKFILL_LEN.to_string();
+            let response: MessagesResponse = self.request(
+                "GET",
+                &format!("/rooms/{}/messages", encode_component(room_id)),
+                &[
+                    ("from", token.as_str()),
+                    ("dir", "b"),
+                    ("limit", limit_str.as_str()),
+                ],
+                None,
+                REQUEST_TIMEOUT,
+            )?;
+
+            let mut state = self.state.lock().unwrap();
+            let room = state
+                .rooms
+                .get_mut(room_id)
+                .ok_or_else(|| Error::NoSuchChannel(room_id.to_owned()))?;
+
+            if room.prev_batch.as_ref() != Some(&token) {
+                // Another request has already inserted these events
+                continue;
+            }
+
+            // `chunk` is in reverse chronological order
+            for event in response.chunk.iter() {
+                let event_id = if let Some(x) = &event.event_id {
+                    x
+                } else {
+                    continue;
+                };
+
+                let id = room.prev_id - 1;
+                if let Some(message) = room.message_from_event(event, id) {
+                    room.prev_id = id;
+                    room.history.push_front((event_id.clone(), message));
+                }
+            }
+
+            room.prev_batch = if response.chunk.is_empty() {
+                None
+            } else {
+                response.end
+            };
+        }
+    }
+
+    fn send_message(&self, room_id: &str, body: &str) -> Result<()> {
+        let txn_id = {
+            let mut state = self.state.lock().unwrap();
+            state.next_txn_id += 1;
+            format!(
+                "stella2.{}.{}",
+                Local::now().timestamp_millis(),
+                state.next_txn_id
+            )
+        };
+
+        let _: serde_json::Value = self.request(
+            "PUT",
+            &format!(
+                "/rooms/{}/send/m.room.message/{}",
+                encode_component(room_id),
+                encode_component(&txn_id)
+            ),
+            &[],
+            Some(json!({ "msgtype": "m.text", "body": body })),
+            REQUEST_TIMEOUT,
+        )?;
+
+        Ok(())
+    }
+
+    /// Send a read receipt for the latest event of a room.
+    fn mark_read(&self, room_id: &str) -> Result<()> {
+        let event_id = {
+            let mut state = self.state.lock().unwrap();
+            let room = state
+                .rooms
+                .get_mut(room_id)
+                .ok_or_else(|| Error::NoSuchChannel(room_id.to_owned()))?;
+
+            match room.history.back() {
+                Some((event_id, _)) if room.unread_count > 0 || room.mentioned => {
+                    room.unread_count = 0;
+                    room.mentioned = false;
+                    event_id.clone()
+                }
+                _ => return Ok(()),
+            }
+        };
+
+        let _: serde_json::Value = self.request(
+            "POST",
+            &format!(
+                "/rooms/{}/receipt/m.read/{}",
+                encode_component(room_id),
+                encode_component(&event_id)
+            ),
+            &[],
+            Some(json!({})),
+            REQUEST_TIMEOUT,
+        )?;
+
+        Ok(())
+    }
+
+    /// Call a client-server API endpoint and decode the JSON response.
+    fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<serde_json::Value>,
+        timeout: Duration,
+    ) -> std::result::Result<T, ApiError> {
+        let mut url = format!("{}{}{}", self.base_url, API_PREFIX, path);
+        for (i, (key, value)) in query.iter().enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url.push_str(key);
+            url.push('=');
+            url.push_str(&encode_component(value));
+        }
+
+        let mut request = ureq::request(method, &url);
+        request.timeout(timeout);
+        if let Some(token) = &self.state.lock().unwrap().access_token {
+            request.set("Authorization", &format!("Bearer {}", token));
+        }
+
+        log::trace!("Matrix request: {} {}", method, path);
+
+        let response = if let Some(body) = body {
+            request
+                .set("Content-Type", "application/json")
+                .send_string(&body.to_string())
+        } else {
+            request.call()
+        };
+
+        if let Some(e) = response.synthetic_error() {
+            return Err(ApiError::Transport(e.to_string()));
+        }
+
+        let status = response.status();
+        let text = response
+            .into_string()
+            .map_err(|e| ApiError::Transport(e.to_string()))?;
+
+        if !(200..300).contains(&status) {
+            let e: ErrorResponse = serde_json::from_str(&text).unwrap_or_default();
+            return Err(ApiError::Server {
+                status,
+                errcode: e.errcode,
+                error: e.error,
+            });
+        }
+
+        serde_json::from_str(&text).map_err(|e| ApiError::Transport(e.to_string()))
+    }
+
+    /// Send events to the main thread. This must not be called while `state`
+    /// is locked because it may block until the main thread processes the
+    /// pending events, and the main thread may be waiting for the lock.
+    fn send_events(&self, events: Vec<Event>) {
+        if events.is_empty() {
+            return;
+        }
+
+        let sender = self.state.lock().unwrap().events.clone();
+        if let Some(sender) = sender {
+            for event in events {
+                let _ = sender.send(event);
+            }
+        }
+    }
+
+    fn channel_groups(&self, state: &State) -> Vec<model::ChannelGroup> {
+        let mut channels: Vec<model::Channel> = state
+            .rooms
+            .iter()
+            .map(|(room_id, room)| model::Channel {
+                id: format!("{}/{}", self.group_id, room_id),
+                name: room.display_name(room_id, &state.user_id),
+                unread_count: room.unread_count,
+                mentioned: room.mentioned,
+            })
+            .collect();
+
+        channels.sort_by_cached_key(|ch| ch.name.to_lowercase());
+
+        vec![model::ChannelGroup {
+            id: self.group_id.clone(),
+            name: host_name(&self.base_url).to_owned(),
+            channels,
+        }]
+    }
+
+    /// Get the room ID from a channel ID.
+    fn room_id<'a>(&self, channel: &'a str) -> Result<&'a str> {
+        let prefix_len = self.group_id.len() + 1;
+
+        if channel.len() > prefix_len
+            && channel.starts_with(&self.group_id)
+            && channel[self.group_id.len()..].starts_with('/')
+        {
+            Ok(&channel[prefix_len..])
+        } else {
+            Err(Error::NoSuchChannel(channel.to_owned()))
+        }
+    }
+}
+
+impl Room {
+    /// Update the room's name and members.
+    fn apply_state_event(&mut self, event: &RoomEvent) {
+        let content = &event.content;
+
+        match (event.ty.as_str(), &event.state_key) {
+            ("m.room.name", _) => {
+                self.name = content["name"].as_str().map(str::to_owned);
+            }
+            ("m.room.canonical_alias", _) => {
+                self.canonical_alias = content["alias"].as_str().map(str::to_owned);
+            }
+            ("m.room.member", Some(user_id)) => match content["membership"].as_str() {
+                Some("join") => {
+                    let name = content["displayname"]
+                        .as_str()
+                        .map_or_else(|| localpart(user_id), str::to_owned);
+                    self.members.insert(user_id.clone(), name);
+                }
+                _ => {
+                    self.members.remove(user_id);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Convert an `m.room.message` event to `model::Message`.
+    fn message_from_event(&self, event: &RoomEvent, id: u64) -> Option<model::Message> {
+        if event.ty != "m.room.message" {
+            return None;
+        }
+
+        // Redacted events don't have `body`
+        let body = event.content["body"].as_str()?;
+
+        let author = self
+            .members
+            .get(&event.sender)
+            .cloned()
+            .unwrap_or_else(|| localpart(&event.sender));
+
+        let body = if event.content["msgtype"] == "m.emote" {
+            format!("* {} {}", author, body)
+        } else {
+            body.to_owned()
+        };
+
+        Some(model::Message {
+            id,
+            author,
+            timestamp: Local.timestamp_millis(event.origin_server_ts).naive_local(),
+            body,
+        })
+    }
+
+    /// Compute the name displayed in the channel list, roughly following
+    /// the algorithm in the client-server API specification.
+    fn display_name(&self, room_id: &str, user_id: &str) -> String {
+        if let Some(name) = self.name.as_ref().filter(|x| !x.is_empty()) {
+            return name.clone();
+        }
+        if let Some(alias) = &self.canonical_alias {
+            return alias.clone();
+        }
+
+        let mut others: Vec<&str> = self
+            .members
+            .iter()
+            .filter(|(id, _)| id.as_str() != user_id)
+            .map(|(_, name)| name.as_str())
+            .collect();
+        others.sort_unstable();
+
+        match others.len() {
+            0 => room_id.to_owned(),
+            1 | 2 => others.join(" and "),
+            n => format!("{} and {} others", others[0], n - 1),
+        }
+    }
+}
+
+/// Get the localpart of a user ID, e.g., `alice` in `@alice:example.com`.
+fn localpart(user_id: &str) -> String {
+    let s = user_id.trim_start_matches('@');
+    s.split(':').next().unwrap_or(s).to_owned()
+}
+
+/// Get the host name of a URL, e.g., `example.com` in
+/// `https://example.com:8448`.
+fn host_name(url: &str) -> &str {
+    let s = url.find("://").map_or(url, |i| &url[i + 3..]);
+    s.split(|c| c == '/' || c == ':').next().unwrap_or(s)
+}
+
+/// Percent-encode a path segment or a query parameter value.
+fn encode_component(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+#[derive(Debug)]
+enum ApiError {
+    /// The request didn't reach the server, or the response was malformed.
+    Transport(String),
+    /// The server returned an error response.
+    Server {
+        status: u16,
+        errcode: String,
+        error: String,
+    },
+}
+
+impl From<ApiError> for Error {
+    fn from(x: ApiError) -> Self {
+        Error::Connection(match x {
+            ApiError::Transport(e) => e,
+            ApiError::Server {
+                status,
+                errcode,
+                error,
+            } => format!("{} {}: {}", status, errcode, error),
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ErrorResponse {
+    errcode: String,
+    error: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoginResponse {
+    access_token: String,
+    user_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoamiResponse {
+    user_id: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SyncResponse {
+    next_batch: String,
+    rooms: SyncRooms,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SyncRooms {
+    join: HashMap<String, JoinedRoom>,
+    leave: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct JoinedRoom {
+    state: EventList,
+    timeline: Timeline,
+    unread_notifications: UnreadNotifications,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct EventList {
+    events: Vec<RoomEvent>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Timeline {
+    events: Vec<RoomEvent>,
+    prev_batch: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct UnreadNotifications {
+    highlight_count: u32,
+    notification_count: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RoomEvent {
+    #[serde(rename = "type")]
+    ty: String,
+    event_id: Option<String>,
+    sender: String,
+    origin_server_ts: i64,
+    state_key: Option<String>,
+    content: serde_json::Value,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct MessagesResponse {
+    chunk: Vec<RoomEvent>,
+    end: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sync_response() {
+        let response: SyncResponse = serde_json::from_str(
+            r#"{
+                "next_batch": "s72595_4483_1934",
+                "rooms": {
+                    "join": {
+                        "!abc:example.com": {
+                            "state": { "events": [
+                                {
+                                    "type": "m.room.member",
+                                    "state_key": "@bob:example.com",
+                                    "sender": "@bob:example.com",
+                                    "content": { "membership": "join", "displayname": "Bob" }
+                                }
+                            ] },
+                            "timeline": {
+                                "events": [
+                                    {
+                                        "type": "m.room.message",
+                                        "event_id": "$1",
+                                        "sender": "@bob:example.com",
+                                        "origin_server_ts": 1432735824653,
+                                        "content": { "msgtype": "m.text", "body": "hello" }
+                                    }
+                                ],
+                                "limited": true,
+                                "prev_batch": "t34-23535_0_0"
+                            },
+                            "unread_notifications": { "highlight_count": 1, "notification_count": 2 }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(response.next_batch, "s72595_4483_1934");
+
+        let joined = &response.rooms.join["!abc:example.com"];
+        assert_eq!(joined.timeline.prev_batch.as_deref(), Some("t34-23535_0_0"));
+        assert_eq!(joined.unread_notifications.notification_count, 2);
+
+        let mut room = Room {
+            name: None,
+            canonical_alias: None,
+            members: HashMap::new(),
+            history: VecDeque::new(),
+            prev_batch: None,
+            next_id: ID_ORIGIN,
+            prev_id: ID_ORIGIN,
+            unread_count: 0,
+            mentioned: false,
+        };
+        room.apply_state_event(&joined.state.events[0]);
+
+        let message = room
+            .message_from_event(&joined.timeline.events[0], 42)
+            .unwrap();
+        assert_eq!(message.id, 42);
+        assert_eq!(message.author, "Bob");
+        assert_eq!(message.body, "hello");
+
+        assert_eq!(
+            room.display_name("!abc:example.com", "@alice:example.com"),
+            "Bob"
+        );
+    }
+
+    #[test]
+    fn helpers() {
+        assert_eq!(localpart("@alice:example.com"), "alice");
+        assert_eq!(
+            host_name("https://matrix.example.com:8448/"),
+            "matrix.example.com"
+        );
+        assert_eq!(encode_component("!abc:example.com"), "%21abc%3Aexample.com");
+    }
+}
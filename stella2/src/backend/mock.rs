@@ -0,0 +1,243 @@
+//! An in-memory backend for development and testing
+use chrono::{Local, NaiveDate};
+use futures::{future::BoxFuture, FutureExt};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use super::{Backend, Error, EventSender, Result};
+use crate::model;
+
+/// The name of the user posting messages by `send_message`.
+const USER_NAME: &str = "me";
+
+/// A backend serving a fixed set of channels and messages. Messages sent by
+/// `send_message` are stored in memory and echoed back.
+#[derive(Debug)]
+pub struct MockBackend {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug)]
+struct State {
+    channel_groups: Vec<model::ChannelGroup>,
+    histories: HashMap<String, Vec<model::Message>>,
+    next_message_id: u64,
+    events: Option<EventSender>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        let channel_groups = channel_groups();
+
+        let mut next_message_id = 0;
+        let histories = channel_groups
+            .iter()
+            .flat_map(|g| &g.channels)
+            .map(|ch| {
+                let messages = messages(next_message_id);
+                next_message_id += messages.len() as u64;
+                (ch.id.clone(), messages)
+            })
+            .collect();
+
+        Self {
+            state: Arc::new(Mutex::new(State {
+                channel_groups,
+                histories,
+                next_message_id,
+                events: None,
+            })),
+        }
+    }
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for MockBackend {
+    fn connect(&self, events: EventSender) -> BoxFuture<'static, Result<()>> {
+        self.state.lock().unwrap().events = Some(events);
+        async { Ok(()) }.boxed()
+    }
+
+    fn list_channels(&self) -> BoxFuture<'static, Result<Vec<model::ChannelGroup>>> {
+        let result = self.state.lock().unwrap().channel_groups.clone();
+        async { Ok(result) }.boxed()
+    }
+
+    fn fetch_history(
+        &self,
+        channel: &str,
+        before: Option<u64>,
+        limit: usize,
+    ) -> BoxFuture<'static, Result<Vec<model::Message>>> {
+        let state = self.state.lock().unwrap();
+
+        let result = if let Some(messages) = state.histories.get(channel) {
+            let end = before.map_or(messages.len(), |before| {
+                messages.iter().take_while(|m| m.id < before).count()
+            });
+            let start = end.saturating_sub(limit);
+            Ok(messages[start..end].to_vec())
+        } else {
+            Err(Error::NoSuchChannel(channel.to_owned()))
+        };
+
+        async { result }.boxed()
+    }
+
+    fn send_message(&self, channel: &str, body: &str) -> BoxFuture<'static, Result<()>> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+
+        let result = if let Some(messages) = state.histories.get_mut(channel) {
+            let message = model::Message {
+                id: state.next_message_id,
+                author: USER_NAME.to_owned(),
+                timestamp: Local::now().naive_local(),
+                body: body.to_owned(),
+            };
+            state.next_message_id += 1;
+            messages.push(message.clone());
+
+            if let Some(events) = &state.events {
+                // We are on the main thread, so don't block
+                let _ = events.try_send(super::Event::MessageReceived {
+                    channel: channel.to_owned(),
+                    message,
+                });
+            }
+
+            Ok(())
+        } else {
+            Err(Error::NoSuchChannel(channel.to_owned()))
+        };
+
+        async { result }.boxed()
+    }
+}
+
+/// The channels served by `MockBackend`.
+pub fn channel_groups() -> Vec<model::ChannelGroup> {
+    let groups = [
+        ("randomserver", "randomserver — Slack"),
+        ("workplace", "workplace — Slack"),
+        ("thawedpeach", "thawedpeach — GNU Social"),
+        ("freenode", "FreeNode"),
+    ];
+
+    // (name, unread_count, mentioned)
+    let channels = [
+        ("#general", 3, false),
+        ("#prolang", 0, false),
+        ("#random", 12, true),
+    ];
+
+    groups
+        .iter()
+        .map(|&(group_id, group_name)| model::ChannelGroup {
+            id: group_id.to_owned(),
+            name: group_name.to_owned(),
+            channels: channels
+                .iter()
+                .map(|&(name, unread_count, mentioned)| model::Channel {
+                    id: format!("{}/{}", group_id, name),
+                    name: name.to_owned(),
+                    unread_count,
+                    mentioned,
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The messages in each channel served by `MockBackend`.
+fn messages(first_id: u64) -> Vec<model::Message> {
+    let lipsum = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. \
+                  Pellentesque ultricies diam sit amet ante auctor, et \
+                  pretium orci molestie. Aenean facilisis justo ac tincidunt \
+                  lobortis. Nulla molestie sem vel vehicula scelerisque. \
+                  Quisque in viverra lacus, a suscipit lectus. Integer \
+                  dignissim lacus neque, a condimentum tellus tempus ac. \
+                  Praesent interdum, velit id mattis fringilla, tortor velit \
+                  bibendum lorem, eget blandit augue nibh vel nunc. Duis ex \
+                  ligula, porttitor ultricies velit vel, porta lacinia lectus. \
+                  In pharetra auctor lorem, a efficitur tellus. Maecenas \
+                  feugiat dapibus dolor quis dignissim. Quisque sed tortor \
+                  sagittis, pretium mauris sit amet, ullamcorper turpis. \
+                  Suspendisse potenti."
+        .split_whitespace();
+    let mk_lipsum = |num_words| lipsum.clone().take(num_words).collect::<Vec<_>>().join(" ");
+
+    let items = [
+        ((2018, 3, 1), (13, 0), "bob", 20),
+        ((2018, 3, 1), (13, 32), "alice", 25),
+        ((2018, 3, 1), (13, 33), "alice", 8),
+        ((2018, 3, 1), (14, 4), "bob", 35),
+        ((2018, 3, 1), (14, 36), "alice", 5),
+        ((2018, 3, 1), (15, 8), "bob", 12),
+        ((2018, 3, 2), (10, 40), "bob", 12),
+        ((2018, 3, 2), (10, 42), "bob", 6),
+        ((2018, 3, 2), (11, 12), "alice", 15),
+        ((2018, 3, 2), (11, 44), "bob", 17),
+        ((2018, 3, 2), (14, 16), "alice", 40),
+        ((2018, 3, 2), (14, 48), "bob", 20),
+    ];
+
+    items
+        .iter()
+        .enumerate()
+        .map(
+            |(i, &((y, m, d), (h, min), author, num_words))| model::Message {
+                id: first_id + i as u64,
+                author: author.to_owned(),
+                timestamp: NaiveDate::from_ymd(y, m, d).and_hms(h, min, 0),
+                body: mk_lipsum(num_words),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    #[test]
+    fn fetch_history_in_pages() {
+        let backend = MockBackend::new();
+        let channel = "freenode/#general";
+
+        let page1 = block_on(backend.fetch_history(channel, None, 5)).unwrap();
+        assert_eq!(page1.len(), 5);
+
+        let page2 = block_on(backend.fetch_history(channel, Some(page1[0].id), 5)).unwrap();
+        assert_eq!(page2.len(), 5);
+        assert!(page2.last().unwrap().id < page1[0].id);
+
+        let page3 = block_on(backend.fetch_history(channel, Some(page2[0].id), 5)).unwrap();
+        assert_eq!(page3.len(), 2);
+
+        assert_eq!(
+            block_on(backend.fetch_history("nonexistent", None, 5)),
+            Err(Error::NoSuchChannel("nonexistent".to_owned()))
+        );
+    }
+
+    #[test]
+    fn send_message() {
+        let backend = MockBackend::new();
+        let channel = "freenode/#prolang";
+
+        block_on(backend.send_message(channel, "hello")).unwrap();
+
+        let history = block_on(backend.fetch_history(channel, None, 1)).unwrap();
+        assert_eq!(history[0].author, USER_NAME);
+        assert_eq!(history[0].body, "hello");
+    }
+}
@@ -2,4 +2,5 @@
 pub mod cmdline;
 pub mod lock;
 pub mod profile;
+pub mod settings;
 pub mod viewpersistence;
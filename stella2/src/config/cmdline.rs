@@ -10,6 +10,12 @@ use std::{
 pub struct Args {
     /// the path to a custom profile directory
     pub profile: Option<PathBuf>,
+    /// the path to a text stylesheet to apply on top of the built-in one
+    pub stylesheet: Option<PathBuf>,
+    /// the path to an action recording to replay
+    pub replay: Option<PathBuf>,
+    /// the number of recorded actions to replay
+    pub replay_count: Option<usize>,
 }
 
 impl Args {
@@ -47,6 +53,15 @@ static HANDLER_TABLE: &[(&str, &(dyn ArgHandler<Args> + Send + Sync))] = &[
     ("-h", &(handle_help as fn(&mut Args))),
     ("--help", &(handle_help as fn(&mut Args))),
     ("--profile", &(handle_profile as fn(&mut Args, OsString))),
+    (
+        "--stylesheet",
+        &(handle_stylesheet as fn(&mut Args, OsString)),
+    ),
+    ("--replay", &(handle_replay as fn(&mut Args, OsString))),
+    (
+        "--replay-count",
+        &(handle_replay_count as fn(&mut Args, OsString)),
+    ),
 ];
 
 fn display_help_and_exit() -> ! {
@@ -62,7 +77,13 @@ FLAGS:
     -h, --help       display help information
 
 OPTIONS:
-    --profile <PROFILE>    the path to a custom profile directory"
+    --profile <PROFILE>    the path to a custom profile directory
+    --stylesheet <FILE>    the path to a text stylesheet to apply on top of
+                           the built-in one
+    --replay <FILE>        the path to an action recording to replay. Debug
+                           builds record actions to `actions.json` in the
+                           profile directory
+    --replay-count <N>     replay only the first N actions of the recording"
     );
     std::process::exit(0);
 }
@@ -95,3 +116,20 @@ fn handle_help<T>(_: &mut T) {
 fn handle_profile(args: &mut Args, value: OsString) {
     args.profile = Some(value.into());
 }
+
+fn handle_stylesheet(args: &mut Args, value: OsString) {
+    args.stylesheet = Some(value.into());
+}
+
+fn handle_replay(args: &mut Args, value: OsString) {
+    args.replay = Some(value.into());
+}
+
+fn handle_replay_count(args: &mut Args, value: OsString) {
+    if let Some(count) = value.to_str().and_then(|s| s.parse().ok()) {
+        args.replay_count = Some(count);
+    } else {
+        eprintln!("error: The argument '--replay-count' requires a non-negative integer");
+        std::process::exit(1);
+    }
+}
@@ -8,6 +8,7 @@ use std::{
 #[derive(Debug)]
 pub struct Profile {
     data_dir: PathBuf,
+    config_dir: PathBuf,
 }
 
 /// The directory name used for application-specific directories.
@@ -72,12 +73,14 @@ impl Profile {
     fn from_sys_dirs() -> Option<Self> {
         Some(Self {
             data_dir: dirs::data_dir()?.join(APP_DIR_NAME),
+            config_dir: dirs::config_dir()?.join(APP_DIR_NAME),
         })
     }
 
     fn from_portable_dir(d: &Path) -> Self {
         Self {
             data_dir: d.join("Data"),
+            config_dir: d.join("Config"),
         }
     }
 
@@ -91,10 +94,19 @@ impl Profile {
         log::debug!("Creating the directory {:?}", self.data_dir);
         std::fs::create_dir_all(&self.data_dir)?;
 
+        log::debug!("Creating the directory {:?}", self.config_dir);
+        std::fs::create_dir_all(&self.config_dir)?;
+
         Ok(())
     }
 
     pub fn data_dir(&self) -> &Path {
         &self.data_dir
     }
+
+    /// Get the directory where user-editable configuration files (e.g.,
+    /// `settings.toml`) are stored.
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
 }
@@ -0,0 +1,424 @@
+//! User-editable application settings
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::BTreeMap, path::PathBuf, rc::Rc};
+use tcw3::{
+    pal::{prelude::*, Wm},
+    ui::{EventSource, Sub},
+};
+
+use super::{profile::Profile, viewpersistence::write_atomically};
+
+/// The application settings, stored in `settings.toml` in the profile's
+/// configuration directory.
+///
+/// Missing fields are filled with default values, so that the settings file
+/// can be edited by hand and remains loadable after a new field is added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub check_updates: bool,
+    pub time_format: TimeFormat,
+    pub theme: Theme,
+    /// The font size of messages, measured in points.
+    pub font_size: u32,
+    pub notifications: NotificationSettings,
+    /// Overrides the key bindings of application actions. Maps action names
+    /// to key combinations. See `view::keymap` for the format.
+    pub keybindings: BTreeMap<String, String>,
+    /// The IRC network to connect to.
+    pub irc: Option<IrcSettings>,
+    /// The Matrix account to use. Takes precedence over `irc`. If neither is
+    /// specified, the application displays mock-up data instead.
+    pub matrix: Option<MatrixSettings>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[serde(rename = "12h")]
+    H12,
+    #[serde(rename = "24h")]
+    H24,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Follows the appearance of the operating system.
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Show desktop notifications for new messages.
+    pub enabled: bool,
+    /// Play a sound along with notifications.
+    pub sound: bool,
+    /// Include message contents in notifications.
+    pub show_preview: bool,
+}
+
+/// The account settings of an IRC network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IrcSettings {
+    /// The host name of the server.
+    pub server: String,
+    /// Connect using TLS. Defaults to `true`.
+    #[serde(default = "IrcSettings::default_tls")]
+    pub tls: bool,
+    /// The port number. Defaults to 6697 if `tls` is enabled or 6667
+    /// otherwise.
+    #[serde(default)]
+    pub port: Option<u16>,
+    pub nick: String,
+    /// The user name sent during registration. Defaults to `nick`.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// The real name sent during registration. Defaults to `nick`.
+    #[serde(default)]
+    pub realname: Option<String>,
+    /// The connection password (`PASS`). It's never sent over an
+    /// unencrypted connection, so this requires `tls`.
+    #[serde(default)]
+    pub password: Option<String>,
+    /// The channels to join after connecting.
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl IrcSettings {
+    fn default_tls() -> bool {
+        true
+    }
+
+    /// Get the port number to connect to.
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or(if self.tls { 6697 } else { 6667 })
+    }
+}
+
+/// The account settings of a Matrix homeserver.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatrixSettings {
+    /// The base URL of the homeserver, e.g., `https://matrix.org`.
+    pub homeserver: String,
+    /// The user ID (`@alice:matrix.org`) or its localpart (`alice`).
+    pub user: String,
+    /// The password used to log in. It's read from the settings file but
+    /// never written back: `SettingsStore::load` rewrites the file without
+    /// it, and the Matrix backend moves it to the system's credential store
+    /// along with the access token.
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            check_updates: false,
+            time_format: TimeFormat::H12,
+            theme: Theme::System,
+            font_size: 12,
+            notifications: NotificationSettings::default(),
+            keybindings: BTreeMap::new(),
+            irc: None,
+            matrix: None,
+        }
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            sound: true,
+            show_preview: true,
+        }
+    }
+}
+
+impl Settings {
+    /// The range of `font_size`.
+    pub const FONT_SIZE_MIN: u32 = 10;
+    pub const FONT_SIZE_MAX: u32 = 17;
+
+    /// Clamp out-of-range values, which might have been introduced by
+    /// editing the settings file by hand.
+    fn sanitize(&mut self) {
+        self.font_size = self
+            .font_size
+            .max(Self::FONT_SIZE_MIN)
+            .min(Self::FONT_SIZE_MAX);
+    }
+}
+
+impl TimeFormat {
+    pub const ALL: &'static [Self] = &[TimeFormat::H12, TimeFormat::H24];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            TimeFormat::H12 => "12 Hour",
+            TimeFormat::H24 => "24 Hour",
+        }
+    }
+}
+
+impl Theme {
+    pub const ALL: &'static [Self] = &[Theme::System, Theme::Light, Theme::Dark];
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            Theme::System => "System",
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+        }
+    }
+}
+
+/// The file path to store the settings.
+fn settings_path(profile: &Profile) -> PathBuf {
+    profile.config_dir().join("settings.toml")
+}
+
+/// The temporary file path used during saving the settings.
+fn settings_tmp_path(profile: &Profile) -> PathBuf {
+    profile.config_dir().join(".settings.toml.tmp")
+}
+
+/// Owns the current `Settings` and notifies subscribers of changes.
+///
+/// This type is bound to the main thread. Change notifications are delivered
+/// asynchronously (via `Wm::invoke`) on the main thread, so handlers are
+/// allowed to call `SettingsStore::set`.
+pub struct SettingsStore {
+    wm: Wm,
+    profile: &'static Profile,
+    settings: RefCell<Settings>,
+    changed_handlers: EventSource<dyn Fn(Wm, &Settings)>,
+}
+
+impl SettingsStore {
+    /// Load the settings from a given profile. Falls back to the default
+    /// settings if the settings file does not exist or is malformed.
+    pub fn load(wm: Wm, profile: &'static Profile) -> Rc<Self> {
+        let settings = load_settings(profile).unwrap_or_default();
+        let has_password = settings
+            .matrix
+            .as_ref()
+            .map_or(false, |matrix| matrix.password.is_some());
+
+        let this = Rc::new(Self {
+            wm,
+            profile,
+            settings: RefCell::new(settings),
+            changed_handlers: EventSource::new(),
+        });
+
+        if has_password {
+            // Remove the password from the file as soon as possible. It's
+            // kept in memory until the backend stores it in the credential
+            // store.
+            log::info!("Removing the Matrix password from the settings file");
+            this.save();
+        }
+
+        this
+    }
+
+    /// Get the current settings.
+    pub fn get(&self) -> Settings {
+        self.settings.borrow().clone()
+    }
+
+    /// Replace the current settings. The new settings are written to disk
+    /// immediately, and the change handlers are called later.
+    pub fn set(self: &Rc<Self>, mut new_settings: Settings) {
+        new_settings.sanitize();
+
+        if *self.settings.borrow() == new_settings {
+            return;
+        }
+
+        self.settings.replace(new_settings);
+        self.save();
+
+        let this = Rc::clone(self);
+        self.wm.invoke(move |wm| {
+            let settings = this.get();
+            this.changed_handlers
+                .raise_with(|handler| handler(wm, &settings));
+        });
+    }
+
+    /// Add a function called when the settings are changed by `set`.
+    pub fn subscribe_changed(&self, cb: Box<dyn Fn(Wm, &Settings)>) -> Sub {
+        self.changed_handlers.subscribe(cb)
+    }
+
+    fn save(&self) {
+        let path = settings_path(self.profile);
+        let tmp_path = settings_tmp_path(self.profile);
+
+        let toml = match toml::to_string_pretty(&*self.settings.borrow()) {
+            Ok(x) => x,
+            Err(e) => {
+                log::error!("Could not serialize the settings: {}", e);
+                return;
+            }
+        };
+
+        log::info!("Writing the settings to {:?}", path);
+
+        if let Err(e) = write_atomically(&path, &tmp_path, &toml) {
+            // TODO: Report the error to the user
+            log::error!(
+                "Could not write the settings to {:?} using a temporary file at {:?}: {}",
+                path,
+                tmp_path,
+                e
+            );
+        }
+    }
+}
+
+/// Load `Settings` from a given profile.
+fn load_settings(profile: &Profile) -> Option<Settings> {
+    let path = settings_path(profile);
+
+    if !path.is_file() {
+        log::info!("The settings file was not found at {:?}.", path);
+        return None;
+    }
+
+    log::info!("Loading the settings from {:?}.", path);
+
+    let toml = match std::fs::read_to_string(&path) {
+        Ok(x) => x,
+        Err(e) => {
+            // TODO: Report the error to the user
+            log::error!("Could not read the settings: {}", e);
+            return None;
+        }
+    };
+
+    match toml::from_str::<Settings>(&toml) {
+        Ok(mut settings) => {
+            settings.sanitize();
+            Some(settings)
+        }
+        Err(e) => {
+            // TODO: Report the error to the user
+            log::error!("Could not parse the settings: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let settings = Settings {
+            check_updates: true,
+            time_format: TimeFormat::H24,
+            theme: Theme::Dark,
+            font_size: 15,
+            notifications: NotificationSettings {
+                enabled: false,
+                sound: true,
+                show_preview: false,
+            },
+            keybindings: vec![("next_channel".to_owned(), "Ctrl+Tab".to_owned())]
+                .into_iter()
+                .collect(),
+            irc: Some(IrcSettings {
+                server: "irc.example.com".to_owned(),
+                tls: false,
+                port: Some(6697),
+                nick: "alice".to_owned(),
+                username: None,
+                realname: Some("Alice".to_owned()),
+                password: None,
+                channels: vec!["#rust".to_owned()],
+            }),
+            matrix: Some(MatrixSettings {
+                homeserver: "https://matrix.example.com".to_owned(),
+                user: "@alice:example.com".to_owned(),
+                password: None,
+            }),
+        };
+
+        let toml = toml::to_string_pretty(&settings).unwrap();
+        assert_eq!(toml::from_str::<Settings>(&toml).unwrap(), settings);
+    }
+
+    #[test]
+    fn matrix_password_not_serialized() {
+        let settings: Settings = toml::from_str(
+            r#"
+                [matrix]
+                homeserver = "https://matrix.example.com"
+                user = "alice"
+                password = "hunter2"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            settings.matrix.as_ref().unwrap().password.as_deref(),
+            Some("hunter2")
+        );
+
+        let toml = toml::to_string_pretty(&settings).unwrap();
+        assert!(!toml.contains("hunter2"), "{}", toml);
+        assert!(!toml.contains("password"), "{}", toml);
+    }
+
+    #[test]
+    fn missing_fields() {
+        let settings: Settings = toml::from_str(
+            r#"
+                time_format = "24h"
+
+                [notifications]
+                sound = false
+
+                [keybindings]
+                quick_switcher = "Ctrl+P"
+
+                [irc]
+                server = "irc.example.com"
+                nick = "alice"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                time_format: TimeFormat::H24,
+                notifications: NotificationSettings {
+                    sound: false,
+                    ..Default::default()
+                },
+                keybindings: vec![("quick_switcher".to_owned(), "Ctrl+P".to_owned())]
+                    .into_iter()
+                    .collect(),
+                irc: Some(IrcSettings {
+                    server: "irc.example.com".to_owned(),
+                    tls: true,
+                    port: None,
+                    nick: "alice".to_owned(),
+                    username: None,
+                    realname: None,
+                    password: None,
+                    channels: Vec::new(),
+                }),
+                ..Default::default()
+            }
+        );
+    }
+}
@@ -6,7 +6,10 @@ use std::{
     sync::{Arc, Condvar, Mutex},
     time::Duration,
 };
-use tcw3::pal::{prelude::*, HInvoke, MtLock, Wm};
+use tcw3::{
+    pal::{prelude::*, HInvoke, MtLock, Wm},
+    ui::session::Session,
+};
 
 use super::profile::Profile;
 use crate::model;
@@ -55,6 +58,60 @@ fn state_tmp_path(profile: &Profile) -> PathBuf {
     profile.data_dir().join(".view.json.tmp")
 }
 
+/// The file path to store the window session (e.g., window sizes).
+fn session_path(profile: &Profile) -> PathBuf {
+    profile.data_dir().join("session.json")
+}
+
+/// The temporary file path used during saving the window session.
+fn session_tmp_path(profile: &Profile) -> PathBuf {
+    profile.data_dir().join(".session.json.tmp")
+}
+
+/// Restore the window session from a given profile. Returns an empty
+/// `Session` if there's none or it could not be loaded.
+pub fn restore_session(profile: &Profile) -> Session {
+    let path = session_path(profile);
+
+    if !path.is_file() {
+        log::info!("The session file was not found at {:?}.", path);
+        return Session::new();
+    }
+
+    log::info!("Loading the session from {:?}.", path);
+
+    let result = std::fs::read_to_string(&path).and_then(|json| {
+        Session::from_json(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    });
+
+    match result {
+        Ok(session) => session,
+        Err(e) => {
+            log::error!("Could not read the session: {}", e);
+            Session::new()
+        }
+    }
+}
+
+/// Persist the window session to a given profile. Blocks the current thread
+/// until the operation is complete.
+pub fn save_session(profile: &Profile, session: &Session) {
+    let path = session_path(profile);
+    let tmp_path = session_tmp_path(profile);
+
+    log::info!("Writing the session to {:?}", path);
+
+    if let Err(e) = write_atomically(&path, &tmp_path, &session.to_json()) {
+        log::error!(
+            "Could not write the session to {:?} using a temporary file at {:?}: {}",
+            path,
+            tmp_path,
+            e
+        );
+    }
+}
+
 /// Restore the application state from a given profile. `state` will be
 /// updated with the restored state.
 pub fn restore_state(profile: &Profile, app_state: Elem<model::AppState>) -> Elem<model::AppState> {
@@ -104,7 +161,11 @@ fn load_persisted_state(path: &Path) -> Result<PersistedState, std::io::Error> {
 }
 
 /// Write a file atomically.
-fn write_atomically(path: &Path, tmp_path: &Path, contents: &str) -> Result<(), std::io::Error> {
+pub(super) fn write_atomically(
+    path: &Path,
+    tmp_path: &Path,
+    contents: &str,
+) -> Result<(), std::io::Error> {
     // Use a temporary file `tmp_path` to atomically update `patH`.
     std::fs::write(tmp_path, contents)?;
 
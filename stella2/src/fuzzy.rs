@@ -0,0 +1,143 @@
+//! Fuzzy string matching
+//!
+//! A pattern matches a candidate string if all characters of the pattern
+//! appear in the candidate in the same order, ignoring case. For example,
+//! `"rgn"` matches `"#general randomserver"`. Matches are ranked by `score`,
+//! which favors consecutive runs of characters and characters at the start of
+//! words.
+
+/// The score of each matched character.
+const SCORE_MATCH: i32 = 16;
+/// The bonus for a matched character immediately following the previous one.
+const BONUS_CONSECUTIVE: i32 = 16;
+/// The bonus for a matched character at the start of a word.
+const BONUS_WORD_START: i32 = 12;
+/// The penalty for each unmatched character between matched ones.
+const PENALTY_GAP: i32 = 1;
+/// The penalty for each unmatched character before the first matched one.
+const PENALTY_LEADING: i32 = 2;
+/// The upper bound of the total penalty for leading unmatched characters.
+const PENALTY_LEADING_MAX: i32 = 8;
+
+/// Compute how well `pattern` matches `candidate`. Returns `None` if it doesn't
+/// match at all. A greater score indicates a better match.
+///
+/// The empty pattern matches everything with a score of zero.
+pub fn score(pattern: &str, candidate: &str) -> Option<i32> {
+    let pattern: Vec<char> = pattern.chars().map(fold_case).collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    let (n, m) = (pattern.len(), candidate.len());
+
+    if n == 0 {
+        return Some(0);
+    }
+    if n > m {
+        return None;
+    }
+
+    // The score of each candidate character when it's matched
+    let char_scores: Vec<i32> = (0..m)
+        .map(|j| {
+            if is_word_start(&candidate, j) {
+                SCORE_MATCH + BONUS_WORD_START
+            } else {
+                SCORE_MATCH
+            }
+        })
+        .collect();
+    let folded: Vec<char> = candidate.iter().cloned().map(fold_case).collect();
+
+    // `best[j]` = the best score of matching `pattern[..=i]` where `pattern[i]`
+    // is matched with `candidate[j]`, for the current `i`
+    let mut best: Vec<Option<i32>> = (0..m)
+        .map(|j| {
+            if folded[j] == pattern[0] {
+                let leading = (PENALTY_LEADING * j as i32).min(PENALTY_LEADING_MAX);
+                Some(char_scores[j] - leading)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for &p in pattern[1..].iter() {
+        let mut new_best = vec![None; m];
+
+        // The best score of the previous matches that leave a gap before
+        // `candidate[j]`, including the gap penalty
+        let mut gapped: Option<i32> = None;
+
+        for j in 1..m {
+            if j >= 2 {
+                gapped = max_opt(gapped, best[j - 2]).map(|x| x - PENALTY_GAP);
+            }
+
+            if folded[j] != p {
+                continue;
+            }
+
+            let consecutive = best[j - 1].map(|x| x + BONUS_CONSECUTIVE);
+            new_best[j] = max_opt(consecutive, gapped).map(|x| x + char_scores[j]);
+        }
+
+        best = new_best;
+    }
+
+    best.into_iter().flatten().max()
+}
+
+fn max_opt(x: Option<i32>, y: Option<i32>) -> Option<i32> {
+    match (x, y) {
+        (Some(x), Some(y)) => Some(x.max(y)),
+        (x, None) => x,
+        (None, y) => y,
+    }
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Return `true` if `s[i]` starts a word, e.g., `g` in `#general` and `S` in
+/// `randomServer`.
+fn is_word_start(s: &[char], i: usize) -> bool {
+    if i == 0 {
+        return true;
+    }
+    let (prev, cur) = (s[i - 1], s[i]);
+    (!prev.is_alphanumeric() && cur.is_alphanumeric())
+        || (prev.is_lowercase() && cur.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsequence() {
+        assert!(score("gnrl", "#general").is_some());
+        assert!(score("GEN", "#general").is_some());
+        assert!(score("lareneg", "#general").is_none());
+        assert!(score("generals", "#general").is_none());
+        assert_eq!(score("", "#general"), Some(0));
+    }
+
+    #[test]
+    fn ranking() {
+        // Consecutive characters are preferred
+        assert!(score("gen", "#general") > score("gen", "#gardening"));
+        // Word starts are preferred
+        assert!(score("rs", "#random server") > score("rs", "#rust"));
+        // Leading unmatched characters are penalized
+        assert!(score("ran", "#random") > score("ran", "#prolang random"));
+    }
+
+    #[test]
+    fn optimal_alignment() {
+        // Greedily matching `g` with the first occurrence would miss the
+        // consecutive run in `#general`. The best alignment scores
+        // (28 - 6) + (16 + 16) + (16 + 16) = 86, whereas the greedy one scores
+        // 28 + (16 - 3) + (16 + 16) = 73.
+        assert_eq!(score("gen", "g #general"), Some(86));
+    }
+}
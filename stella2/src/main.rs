@@ -11,9 +11,12 @@
 use log::debug;
 use tcw3::pal::{self, prelude::*};
 
+mod backend;
 mod config;
 mod crashhandler;
+mod fuzzy;
 mod model;
+mod store;
 mod stylesheet;
 mod view;
 
@@ -94,8 +97,26 @@ fn main() {
     // Register the application's custom stylesheet
     let style_manager = tcw3::ui::theming::Manager::global(wm);
     stylesheet::register_stylesheet(style_manager);
+    if let Some(stylesheet_path) = &args.stylesheet {
+        stylesheet::load_stylesheet_file(style_manager, stylesheet_path);
+    }
+
+    // Load the action recording to replay
+    let replay = args.replay.as_ref().map(|path| {
+        let mut recording = self::view::Recording::load(path).unwrap_or_else(|e| {
+            eprintln!("error: Could not load the recording from {:?}: {}", path, e);
+            std::process::exit(1);
+        });
+        if let Some(count) = args.replay_count {
+            recording.actions.truncate(count);
+        }
+        recording
+    });
+
+    let _view = self::view::AppView::new(wm, profile, replay);
 
-    let _view = self::view::AppView::new(wm, profile);
+    #[cfg(feature = "hot_reload")]
+    tcw3::designer_runtime::hot_reload::start(wm);
 
     debug!("Entering the main loop");
     wm.enter_main_loop();
@@ -1,35 +1,175 @@
+use chrono::NaiveDateTime;
 use harmony::{set_field, Elem};
 use miniserde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+mod serialize;
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub main_wnd: Elem<WndState>,
+    /// The groups of the channels the user has joined.
+    pub channel_groups: Elem<Vec<ChannelGroup>>,
+    /// The loaded portions of the channels' message histories, keyed by
+    /// channel IDs.
+    pub histories: Elem<BTreeMap<String, Elem<ChannelHistory>>>,
     /// Indicates whether the Preferences window is visible.
     pub pref_visible: bool,
+    /// The message search in the sidebar.
+    pub search: Elem<SearchState>,
+    /// The state of the connection to the backend.
+    pub connection: ConnectionState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WndState {
     // UI state - It could be a local state of widget controllers, but we store
     // it here instead so that it can be intercepted by a persistence middleware
-    pub sidebar_width: f32,
-    pub editor_height: f32,
     pub sidebar_visible: bool,
+
+    /// The ID of the channel displayed in the window.
+    pub current_channel: String,
+    /// Unsent messages, keyed by channel IDs.
+    pub drafts: BTreeMap<String, String>,
+}
+
+/// A group of channels, e.g., a chat server or a workspace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelGroup {
+    /// Uniquely identifies the group.
+    pub id: String,
+    /// The display name of the group.
+    pub name: String,
+    pub channels: Vec<Channel>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Channel {
+    /// Uniquely identifies the channel across all groups.
+    pub id: String,
+    /// The display name of the channel.
+    pub name: String,
+    /// The number of unread messages.
+    pub unread_count: u32,
+    /// `true` if any of the unread messages mention the user.
+    pub mentioned: bool,
+}
+
+/// A message posted to a channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    /// Uniquely identifies the message. Newer messages have greater IDs.
+    pub id: u64,
+    pub author: String,
+    pub timestamp: NaiveDateTime,
+    pub body: String,
+}
+
+/// The state of the message search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    /// The search string entered by the user. The search results are
+    /// displayed in place of the channel list if this is not empty.
+    pub query: String,
+    /// The messages matching `query`, newest first. This may be outdated
+    /// while a new search is in progress.
+    pub hits: Vec<SearchHit>,
+}
+
+/// A message found by the message search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchHit {
+    /// The ID of the channel the message was posted to.
+    pub channel: String,
+    /// The message. `id` is only meaningful to the message store.
+    pub message: Message,
+}
+
+/// The state of the connection to the backend.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionState {
+    /// The first connection attempt is in progress.
+    Connecting,
+    Connected,
+    /// The connection was lost or could not be established. The backend keeps
+    /// trying to reconnect. Contains the description of the error.
+    Disconnected(String),
+}
+
+impl ConnectionState {
+    /// Get the text displayed in the connection status bar. Returns `None` if
+    /// the status bar should be hidden.
+    pub fn status_text(&self) -> Option<String> {
+        match self {
+            ConnectionState::Connecting => Some("Connecting…".to_owned()),
+            ConnectionState::Connected => None,
+            ConnectionState::Disconnected(e) => {
+                Some(format!("Disconnected ({}). Reconnecting…", e))
+            }
+        }
+    }
+}
+
+/// The loaded portion of a channel's message history.
+#[derive(Debug, Clone, Default)]
+pub struct ChannelHistory {
+    /// The most recent messages, sorted by time.
+    pub messages: Vec<Message>,
+    /// `true` if `messages` includes the first message of the channel, i.e.,
+    /// there are no older messages to load.
+    pub complete: bool,
+    /// The ID of the first message the user hasn't read yet.
+    pub first_unread: Option<u64>,
+    /// The number of unread messages older than `messages`. `first_unread` is
+    /// moved to the oldest of them when they are loaded.
+    pub unread_older: usize,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
             main_wnd: Elem::new(WndState {
-                sidebar_width: 200.0,
-                editor_height: 50.0,
                 sidebar_visible: true,
+                current_channel: "randomserver/#random".to_owned(),
+                drafts: BTreeMap::new(),
             }),
+            // Populated by the backend
+            channel_groups: Elem::new(Vec::new()),
+            histories: Elem::new(BTreeMap::new()),
             pref_visible: false,
+            search: Elem::new(SearchState::default()),
+            connection: ConnectionState::Connecting,
         }
     }
 }
 
+/// Find the channel next to the one with ID `current` in the order they are
+/// displayed in the channel list, wrapping around at the ends.
+///
+/// Returns the first (or last if `!forward`) channel if `current` is not
+/// found, or `None` if there are no channels.
+pub fn adjacent_channel<'a>(
+    groups: &'a [ChannelGroup],
+    current: &str,
+    forward: bool,
+) -> Option<&'a Channel> {
+    let channels: Vec<&Channel> = groups.iter().flat_map(|g| &g.channels).collect();
+    let len = channels.len();
+
+    if len == 0 {
+        return None;
+    }
+
+    let i = match channels.iter().position(|c| c.id == current) {
+        Some(i) if forward => (i + 1) % len,
+        Some(i) => (i + len - 1) % len,
+        None if forward => 0,
+        None => len - 1,
+    };
+
+    Some(channels[i])
+}
+
 #[derive(Debug, Clone)]
 pub enum AppAction {
     Wnd(WndAction),
@@ -37,18 +177,77 @@ pub enum AppAction {
     HidePref,
     /// Toggles the visibility of the Preferences window.
     TogglePref,
+    /// Replaces the channel list with the one provided by the backend.
+    SetChannelGroups(Vec<ChannelGroup>),
+    /// Requests older messages of the specified channel from the backend.
+    /// This doesn't change the state by itself. `PrependHistory` is
+    /// dispatched when the messages arrive.
+    RequestHistory(String),
+    /// Inserts older messages fetched from the backend.
+    PrependHistory {
+        channel: String,
+        messages: Vec<Message>,
+        /// `true` if there are no more messages to load.
+        complete: bool,
+    },
+    /// Appends a message that was posted to a channel.
+    ReceiveMessage {
+        channel: String,
+        message: Message,
+    },
+    /// Replaces the search string of the message search. `AppView` performs
+    /// the search and dispatches `SetSearchResults`.
+    SetSearchQuery(String),
+    /// Supplies the search results for `query`. Ignored if `query` is not the
+    /// current search string anymore.
+    SetSearchResults {
+        query: String,
+        hits: Vec<SearchHit>,
+    },
+    /// Updates the connection status reported by the backend.
+    SetConnectionState(ConnectionState),
 }
 
 #[derive(Debug, Clone)]
 pub enum WndAction {
-    SetSidebarWidth(f32),
-    SetEditorHeight(f32),
     ToggleSidebar,
+    /// Replaces the draft message of the current channel.
+    SetDraft(String),
+    /// Sends a message to the current channel.
+    SendMessage(String),
+    /// Switches to the channel with the specified ID.
+    SetCurrentChannel(String),
 }
 
 impl AppState {
     pub fn reduce(this: Elem<Self>, action: &AppAction) -> Elem<Self> {
         match action {
+            AppAction::Wnd(WndAction::SetCurrentChannel(id)) => {
+                let old_id = this.main_wnd.current_channel.clone();
+                if old_id == *id {
+                    return this;
+                }
+
+                let this = Self::mark_channel_read(this, id);
+
+                // The messages in the channel we are leaving have been read
+                let this = if this.histories.contains_key(&old_id) {
+                    Self::update_history(this, &old_id, |history| {
+                        history.first_unread = None;
+                        history.unread_older = 0;
+                    })
+                } else {
+                    this
+                };
+
+                set_field! {
+                    main_wnd: WndState::reduce(
+                        Elem::clone(&this.main_wnd),
+                        &WndAction::SetCurrentChannel(id.clone()),
+                    ),
+                    ..this
+                }
+            }
             AppAction::Wnd(wnd_action) => set_field! {
                 main_wnd: WndState::reduce(Elem::clone(&this.main_wnd), wnd_action),
                 ..this
@@ -61,6 +260,188 @@ impl AppState {
                 pref_visible: !this.pref_visible,
                 ..this
             },
+            AppAction::SetChannelGroups(groups) => {
+                // Carry over the unread state of the known channels. It's
+                // maintained by `ReceiveMessage` and `SetCurrentChannel`,
+                // which backends don't necessarily keep track of.
+                let old_channels: HashMap<&str, &Channel> = (this.channel_groups.iter())
+                    .flat_map(|g| &g.channels)
+                    .map(|c| (c.id.as_str(), c))
+                    .collect();
+
+                let mut groups = groups.clone();
+                for channel in groups.iter_mut().flat_map(|g| &mut g.channels) {
+                    if let Some(old) = old_channels.get(channel.id.as_str()) {
+                        channel.unread_count = old.unread_count;
+                        channel.mentioned = old.mentioned;
+                    }
+                }
+
+                set_field! {
+                    channel_groups: Elem::new(groups),
+                    ..this
+                }
+            }
+            AppAction::RequestHistory(_) => this,
+            AppAction::PrependHistory {
+                channel,
+                messages,
+                complete,
+            } => Self::update_history(this, channel, |history| {
+                // Discard the messages we already have in case the request
+                // raced with `ReceiveMessage`
+                let oldest = history.messages.first().map(|m| m.id);
+                let messages: Vec<Message> = messages
+                    .iter()
+                    .filter(|m| oldest.map_or(true, |oldest| m.id < oldest))
+                    .cloned()
+                    .collect();
+
+                // Move the unread marker to the unread messages that have just
+                // been loaded
+                let num_unread = history.unread_older.min(messages.len());
+                if num_unread > 0 {
+                    history.first_unread = Some(messages[messages.len() - num_unread].id);
+                    history.unread_older -= num_unread;
+                }
+                if *complete {
+                    history.unread_older = 0;
+                }
+
+                history.messages.splice(0..0, messages);
+                history.complete = *complete;
+            }),
+            AppAction::ReceiveMessage { channel, message } => {
+                let is_current = this.main_wnd.current_channel == *channel;
+
+                let this = Self::update_history(this, channel, |history| {
+                    if !is_current && history.first_unread.is_none() {
+                        history.first_unread = Some(message.id);
+                    }
+                    history.messages.push(message.clone());
+                });
+
+                if is_current {
+                    this
+                } else {
+                    Self::update_channel(this, channel, |ch| ch.unread_count += 1)
+                }
+            }
+            AppAction::SetSearchQuery(query) => {
+                if *query == this.search.query {
+                    return this;
+                }
+
+                // Keep the old results until the new ones arrive so that
+                // the result list doesn't flicker while typing
+                let hits = if query.trim().is_empty() {
+                    Vec::new()
+                } else {
+                    this.search.hits.clone()
+                };
+
+                set_field! {
+                    search: Elem::new(SearchState {
+                        query: query.clone(),
+                        hits,
+                    }),
+                    ..this
+                }
+            }
+            AppAction::SetSearchResults { query, hits } => {
+                if *query != this.search.query {
+                    return this;
+                }
+
+                set_field! {
+                    search: Elem::new(SearchState {
+                        query: query.clone(),
+                        hits: hits.clone(),
+                    }),
+                    ..this
+                }
+            }
+            AppAction::SetConnectionState(connection) => set_field! {
+                connection: connection.clone(),
+                ..this
+            },
+        }
+    }
+
+    /// Clear the unread message count of the specified channel. Also, place
+    /// the unread marker before the first unread message, or remember where
+    /// to place it if the message hasn't been loaded yet.
+    fn mark_channel_read(this: Elem<Self>, id: &str) -> Elem<Self> {
+        let unread_count = this
+            .channel_groups
+            .iter()
+            .flat_map(|g| &g.channels)
+            .find(|c| c.id == id)
+            .map_or(0, |c| c.unread_count as usize);
+
+        if unread_count == 0 {
+            return this;
+        }
+
+        // The unread messages that haven't been loaded yet are counted in
+        // `unread_older` so that `PrependHistory` can place the marker
+        let this = Self::update_history(this, id, |history| {
+            let len = history.messages.len();
+            if let Some(message) = history.messages.get(len.saturating_sub(unread_count)) {
+                let first_unread = history
+                    .first_unread
+                    .map_or(message.id, |x| x.min(message.id));
+                history.first_unread = Some(first_unread);
+            }
+            history.unread_older = unread_count.saturating_sub(len);
+        });
+
+        Self::update_channel(this, id, |ch| {
+            ch.unread_count = 0;
+            ch.mentioned = false;
+        })
+    }
+
+    /// Modify the channel with the specified ID using `f`. Does nothing if
+    /// there is no such channel.
+    fn update_channel(this: Elem<Self>, id: &str, f: impl FnOnce(&mut Channel)) -> Elem<Self> {
+        let mut groups = Vec::clone(&this.channel_groups);
+
+        if let Some(ch) = groups
+            .iter_mut()
+            .flat_map(|g| &mut g.channels)
+            .find(|c| c.id == id)
+        {
+            f(ch);
+        } else {
+            return this;
+        }
+
+        set_field! {
+            channel_groups: Elem::new(groups),
+            ..this
+        }
+    }
+
+    /// Modify the message history of the specified channel using `f`.
+    /// Creates an empty history if there isn't one.
+    fn update_history(
+        this: Elem<Self>,
+        id: &str,
+        f: impl FnOnce(&mut ChannelHistory),
+    ) -> Elem<Self> {
+        let mut histories = BTreeMap::clone(&this.histories);
+
+        let mut history = histories
+            .get(id)
+            .map(|history| ChannelHistory::clone(history))
+            .unwrap_or_default();
+        f(&mut history);
+        histories.insert(id.to_owned(), Elem::new(history));
+
+        set_field! {
+            histories: Elem::new(histories),
+            ..this
         }
     }
 }
@@ -68,18 +449,280 @@ impl AppState {
 impl WndState {
     fn reduce(this: Elem<Self>, action: &WndAction) -> Elem<Self> {
         match action {
-            WndAction::SetSidebarWidth(x) => set_field! {
-                sidebar_width: *x,
+            WndAction::ToggleSidebar => set_field! {
+                sidebar_visible: !this.sidebar_visible,
                 ..this
             },
-            WndAction::SetEditorHeight(x) => set_field! {
-                editor_height: *x,
+            WndAction::SetDraft(text) => {
+                let mut drafts = this.drafts.clone();
+                if text.is_empty() {
+                    drafts.remove(&this.current_channel);
+                } else {
+                    drafts.insert(this.current_channel.clone(), text.clone());
+                }
+
+                set_field! {
+                    drafts: drafts,
+                    ..this
+                }
+            }
+            WndAction::SendMessage(_) => {
+                // The message is delivered to the backend by `AppView`
+                let mut drafts = this.drafts.clone();
+                drafts.remove(&this.current_channel);
+
+                set_field! {
+                    drafts: drafts,
+                    ..this
+                }
+            }
+            WndAction::SetCurrentChannel(id) => set_field! {
+                current_channel: id.clone(),
                 ..this
             },
-            WndAction::ToggleSidebar => set_field! {
-                sidebar_visible: !this.sidebar_visible,
-                ..this
+        }
+    }
+
+    /// Get the draft message of the current channel.
+    pub fn current_draft(&self) -> &str {
+        self.drafts
+            .get(&self.current_channel)
+            .map(String::as_str)
+            .unwrap_or("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock;
+
+    #[test]
+    fn adjacent_channel_wraps_around() {
+        let groups = mock::channel_groups();
+        let next = |id: &str| adjacent_channel(&groups, id, true).map(|c| c.id.as_str());
+        let prev = |id: &str| adjacent_channel(&groups, id, false).map(|c| c.id.as_str());
+
+        assert_eq!(next("randomserver/#random"), Some("workplace/#general"));
+        assert_eq!(prev("workplace/#general"), Some("randomserver/#random"));
+        assert_eq!(next("freenode/#random"), Some("randomserver/#general"));
+        assert_eq!(prev("randomserver/#general"), Some("freenode/#random"));
+        assert_eq!(next("nonexistent"), Some("randomserver/#general"));
+        assert_eq!(adjacent_channel(&[], "nonexistent", true), None);
+    }
+
+    fn message(id: u64) -> Message {
+        Message {
+            id,
+            author: "alice".to_owned(),
+            timestamp: NaiveDateTime::from_timestamp(1_500_000_000 + id as i64, 0),
+            body: "hello".to_owned(),
+        }
+    }
+
+    fn find_channel<'a>(state: &'a AppState, id: &str) -> &'a Channel {
+        state
+            .channel_groups
+            .iter()
+            .flat_map(|g| &g.channels)
+            .find(|c| c.id == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn receive_message() {
+        let mut state = Elem::new(AppState::new());
+        state = AppState::reduce(state, &AppAction::SetChannelGroups(mock::channel_groups()));
+        state = AppState::reduce(
+            state,
+            &AppAction::Wnd(WndAction::SetCurrentChannel("freenode/#prolang".to_owned())),
+        );
+
+        // A message to the current channel is read immediately
+        state = AppState::reduce(
+            state,
+            &AppAction::ReceiveMessage {
+                channel: "freenode/#prolang".to_owned(),
+                message: message(1),
+            },
+        );
+        assert_eq!(find_channel(&state, "freenode/#prolang").unread_count, 0);
+
+        // A message to another channel is not
+        let unread_count = find_channel(&state, "freenode/#general").unread_count;
+        state = AppState::reduce(
+            state,
+            &AppAction::ReceiveMessage {
+                channel: "freenode/#general".to_owned(),
+                message: message(2),
+            },
+        );
+        assert_eq!(
+            find_channel(&state, "freenode/#general").unread_count,
+            unread_count + 1
+        );
+        assert_eq!(state.histories["freenode/#general"].first_unread, Some(2));
+
+        // Older messages are inserted before the received one
+        state = AppState::reduce(
+            state,
+            &AppAction::PrependHistory {
+                channel: "freenode/#prolang".to_owned(),
+                messages: vec![message(0), message(1)],
+                complete: true,
+            },
+        );
+        let ids: Vec<u64> = state.histories["freenode/#prolang"]
+            .messages
+            .iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, [0, 1]);
+
+        // Switching to the channel clears the unread count
+        state = AppState::reduce(
+            state,
+            &AppAction::Wnd(WndAction::SetCurrentChannel("freenode/#general".to_owned())),
+        );
+        assert_eq!(find_channel(&state, "freenode/#general").unread_count, 0);
+        assert_eq!(state.histories["freenode/#general"].first_unread, Some(2));
+    }
+
+    #[test]
+    fn unread_marker_is_placed_after_history_loads() {
+        let mut state = Elem::new(AppState::new());
+        state = AppState::reduce(state, &AppAction::SetChannelGroups(mock::channel_groups()));
+
+        // `freenode/#general` has three unread messages, none of which have
+        // been loaded yet
+        assert_eq!(find_channel(&state, "freenode/#general").unread_count, 3);
+        state = AppState::reduce(
+            state,
+            &AppAction::Wnd(WndAction::SetCurrentChannel("freenode/#general".to_owned())),
+        );
+        assert_eq!(find_channel(&state, "freenode/#general").unread_count, 0);
+        assert_eq!(state.histories["freenode/#general"].first_unread, None);
+
+        // Two of them are loaded
+        state = AppState::reduce(
+            state,
+            &AppAction::PrependHistory {
+                channel: "freenode/#general".to_owned(),
+                messages: vec![message(10), message(11)],
+                complete: false,
             },
+        );
+        assert_eq!(state.histories["freenode/#general"].first_unread, Some(10));
+
+        // The rest is loaded
+        state = AppState::reduce(
+            state,
+            &AppAction::PrependHistory {
+                channel: "freenode/#general".to_owned(),
+                messages: vec![message(7), message(8), message(9)],
+                complete: true,
+            },
+        );
+        assert_eq!(state.histories["freenode/#general"].first_unread, Some(9));
+
+        // Older messages are not unread
+        assert_eq!(state.histories["freenode/#general"].unread_older, 0);
+    }
+
+    #[test]
+    fn set_channel_groups_keeps_unread_state() {
+        let mut state = Elem::new(AppState::new());
+        state = AppState::reduce(state, &AppAction::SetChannelGroups(mock::channel_groups()));
+        state = AppState::reduce(
+            state,
+            &AppAction::ReceiveMessage {
+                channel: "freenode/#prolang".to_owned(),
+                message: message(1),
+            },
+        );
+
+        // The backend reports the channels again with a new one and without
+        // the unread state
+        let mut groups = mock::channel_groups();
+        for channel in groups.iter_mut().flat_map(|g| &mut g.channels) {
+            channel.unread_count = 0;
+            channel.mentioned = false;
         }
+        groups[0].channels.push(Channel {
+            id: "randomserver/#new".to_owned(),
+            name: "#new".to_owned(),
+            unread_count: 5,
+            mentioned: false,
+        });
+        state = AppState::reduce(state, &AppAction::SetChannelGroups(groups));
+
+        assert_eq!(find_channel(&state, "freenode/#prolang").unread_count, 1);
+        assert_eq!(find_channel(&state, "freenode/#random").unread_count, 12);
+        assert!(find_channel(&state, "freenode/#random").mentioned);
+
+        // The new channel's unread state comes from the backend
+        assert_eq!(find_channel(&state, "randomserver/#new").unread_count, 5);
+    }
+
+    #[test]
+    fn stale_search_results_are_ignored() {
+        let hit = |id| SearchHit {
+            channel: "freenode/#prolang".to_owned(),
+            message: message(id),
+        };
+
+        let mut state = Elem::new(AppState::new());
+        state = AppState::reduce(state, &AppAction::SetSearchQuery("fo".to_owned()));
+        state = AppState::reduce(state, &AppAction::SetSearchQuery("foo".to_owned()));
+
+        state = AppState::reduce(
+            state,
+            &AppAction::SetSearchResults {
+                query: "foo".to_owned(),
+                hits: vec![hit(1)],
+            },
+        );
+        // The results of the previous query arrived late
+        state = AppState::reduce(
+            state,
+            &AppAction::SetSearchResults {
+                query: "fo".to_owned(),
+                hits: vec![hit(1), hit(2)],
+            },
+        );
+        assert_eq!(state.search.hits, [hit(1)]);
+
+        // Clearing the search string clears the results
+        state = AppState::reduce(state, &AppAction::SetSearchQuery(String::new()));
+        assert!(state.search.hits.is_empty());
+    }
+
+    #[test]
+    fn connection_state() {
+        let mut state = Elem::new(AppState::new());
+        assert_eq!(state.connection, ConnectionState::Connecting);
+        assert!(state.connection.status_text().is_some());
+
+        state = AppState::reduce(
+            state,
+            &AppAction::SetConnectionState(ConnectionState::Connected),
+        );
+        assert_eq!(state.connection.status_text(), None);
+
+        let disconnected = ConnectionState::Disconnected("Ping timeout".to_owned());
+        state = AppState::reduce(state, &AppAction::SetConnectionState(disconnected));
+        assert!(state
+            .connection
+            .status_text()
+            .unwrap()
+            .contains("Ping timeout"));
+
+        // Setting the same state doesn't create a new `AppState`
+        let old_state = Elem::clone(&state);
+        state = AppState::reduce(
+            state,
+            &AppAction::SetConnectionState(old_state.connection.clone()),
+        );
+        assert!(Elem::ptr_eq(&state, &old_state));
     }
 }
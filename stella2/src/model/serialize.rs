@@ -0,0 +1,321 @@
+//! `miniserde` implementations for the model types the derive macros don't
+//! support
+//!
+//! Enums are represented in the same way as `serde`'s default (externally
+//! tagged) representation: unit variants as strings, and other variants as
+//! maps with a single entry from the variant name to the contents.
+use chrono::NaiveDateTime;
+use miniserde::{de, make_place, ser, Deserialize, Error, Result, Serialize};
+use std::borrow::Cow;
+
+use super::{AppAction, ChannelGroup, ConnectionState, Message, SearchHit, WndAction};
+
+make_place!(Place);
+
+/// The format of `Message::timestamp`. This is the format accepted by
+/// `NaiveDateTime`'s `FromStr` implementation.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// Serializes a variant with contents as `{name: value}`.
+struct Variant<T> {
+    name: &'static str,
+    value: T,
+    done: bool,
+}
+
+fn variant<'a, T: Serialize + 'a>(name: &'static str, value: T) -> ser::Fragment<'a> {
+    ser::Fragment::Map(Box::new(Variant {
+        name,
+        value,
+        done: false,
+    }))
+}
+
+impl<T: Serialize> ser::Map for Variant<T> {
+    fn next(&mut self) -> Option<(Cow<str>, &dyn Serialize)> {
+        if self.done {
+            return None;
+        }
+        self.done = true;
+        Some((Cow::Borrowed(self.name), &self.value))
+    }
+}
+
+/// Forwards to the referenced value.
+struct Ref<'a>(&'a dyn Serialize);
+
+impl Serialize for Ref<'_> {
+    fn begin(&self) -> ser::Fragment {
+        self.0.begin()
+    }
+}
+
+/// Serializes as a map with the given entries.
+struct Fields<'a>(Vec<(&'static str, &'a dyn Serialize)>);
+
+impl Serialize for Fields<'_> {
+    fn begin(&self) -> ser::Fragment {
+        ser::Fragment::Map(Box::new(FieldsIter(self.0.iter())))
+    }
+}
+
+struct FieldsIter<'a, 'b>(std::slice::Iter<'a, (&'static str, &'b dyn Serialize)>);
+
+impl ser::Map for FieldsIter<'_, '_> {
+    fn next(&mut self) -> Option<(Cow<str>, &dyn Serialize)> {
+        self.0
+            .next()
+            .map(|&(name, value)| (Cow::Borrowed(name), value))
+    }
+}
+
+/// Implement `Serialize` and `Deserialize` for an enum. All variants must be
+/// listed in the corresponding section.
+macro_rules! impl_enum {
+    (
+        $ty:ident {
+            unit { $( $unit:ident ),* $(,)? }
+            newtype { $( $newtype:ident($newtype_ty:ty) ),* $(,)? }
+            struct {
+                $( $strukt:ident { $( $field:ident: $field_ty:ty ),* $(,)? } ),* $(,)?
+            }
+        }
+    ) => {
+        impl Serialize for $ty {
+            fn begin(&self) -> ser::Fragment {
+                match self {
+                    $( $ty::$unit => ser::Fragment::Str(Cow::Borrowed(stringify!($unit))), )*
+                    $( $ty::$newtype(x) => variant(stringify!($newtype), Ref(x)), )*
+                    $(
+                        $ty::$strukt { $( $field ),* } => variant(
+                            stringify!($strukt),
+                            Fields(vec![ $( (stringify!($field), $field as &dyn Serialize) ),* ]),
+                        ),
+                    )*
+                }
+            }
+        }
+
+        impl Deserialize for $ty {
+            fn begin(out: &mut Option<Self>) -> &mut dyn de::Visitor {
+                Place::new(out)
+            }
+        }
+
+        impl de::Visitor for Place<$ty> {
+            fn string(&mut self, s: &str) -> Result<()> {
+                self.out = Some(match s {
+                    $( stringify!($unit) => $ty::$unit, )*
+                    _ => return Err(Error),
+                });
+                Ok(())
+            }
+
+            fn map(&mut self) -> Result<Box<dyn de::Map + '_>> {
+                // The contents of the struct-like variants
+                $(
+                    #[derive(Deserialize)]
+                    struct $strukt {
+                        $( $field: $field_ty ),*
+                    }
+                )*
+
+                #[allow(non_snake_case)]
+                struct Builder<'a> {
+                    out: &'a mut Option<$ty>,
+                    $( $newtype: Option<$newtype_ty>, )*
+                    $( $strukt: Option<$strukt>, )*
+                }
+
+                impl de::Map for Builder<'_> {
+                    fn key(&mut self, k: &str) -> Result<&mut dyn de::Visitor> {
+                        match k {
+                            $( stringify!($newtype) => Ok(Deserialize::begin(&mut self.$newtype)), )*
+                            $( stringify!($strukt) => Ok(Deserialize::begin(&mut self.$strukt)), )*
+                            _ => Err(Error),
+                        }
+                    }
+
+                    fn finish(&mut self) -> Result<()> {
+                        $(
+                            if let Some(x) = self.$newtype.take() {
+                                *self.out = Some($ty::$newtype(x));
+                                return Ok(());
+                            }
+                        )*
+                        $(
+                            if let Some(x) = self.$strukt.take() {
+                                *self.out = Some($ty::$strukt { $( $field: x.$field ),* });
+                                return Ok(());
+                            }
+                        )*
+                        Err(Error)
+                    }
+                }
+
+                Ok(Box::new(Builder {
+                    out: &mut self.out,
+                    $( $newtype: None, )*
+                    $( $strukt: None, )*
+                }))
+            }
+        }
+    };
+}
+
+impl_enum! {
+    AppAction {
+        unit { HidePref, TogglePref }
+        newtype {
+            Wnd(WndAction),
+            SetChannelGroups(Vec<ChannelGroup>),
+            RequestHistory(String),
+            SetSearchQuery(String),
+            SetConnectionState(ConnectionState),
+        }
+        struct {
+            PrependHistory { channel: String, messages: Vec<Message>, complete: bool },
+            ReceiveMessage { channel: String, message: Message },
+            SetSearchResults { query: String, hits: Vec<SearchHit> },
+        }
+    }
+}
+
+impl_enum! {
+    WndAction {
+        unit { ToggleSidebar }
+        newtype {
+            SetDraft(String),
+            SendMessage(String),
+            SetCurrentChannel(String),
+        }
+        struct {}
+    }
+}
+
+impl_enum! {
+    ConnectionState {
+        unit { Connecting, Connected }
+        newtype { Disconnected(String) }
+        struct {}
+    }
+}
+
+// `NaiveDateTime` doesn't implement `miniserde`'s traits, so `Message` is
+// implemented by hand. `timestamp` is represented as a string.
+impl Serialize for Message {
+    fn begin(&self) -> ser::Fragment {
+        struct MessageFields<'a> {
+            message: &'a Message,
+            timestamp: String,
+            state: usize,
+        }
+
+        impl ser::Map for MessageFields<'_> {
+            fn next(&mut self) -> Option<(Cow<str>, &dyn Serialize)> {
+                let state = self.state;
+                self.state += 1;
+                let (name, value): (_, &dyn Serialize) = match state {
+                    0 => ("id", &self.message.id),
+                    1 => ("author", &self.message.author),
+                    2 => ("timestamp", &self.timestamp),
+                    3 => ("body", &self.message.body),
+                    _ => return None,
+                };
+                Some((Cow::Borrowed(name), value))
+            }
+        }
+
+        ser::Fragment::Map(Box::new(MessageFields {
+            message: self,
+            timestamp: self.timestamp.format(TIMESTAMP_FORMAT).to_string(),
+            state: 0,
+        }))
+    }
+}
+
+impl Deserialize for Message {
+    fn begin(out: &mut Option<Self>) -> &mut dyn de::Visitor {
+        Place::new(out)
+    }
+}
+
+impl de::Visitor for Place<Message> {
+    fn map(&mut self) -> Result<Box<dyn de::Map + '_>> {
+        struct Builder<'a> {
+            out: &'a mut Option<Message>,
+            id: Option<u64>,
+            author: Option<String>,
+            timestamp: Option<String>,
+            body: Option<String>,
+        }
+
+        impl de::Map for Builder<'_> {
+            fn key(&mut self, k: &str) -> Result<&mut dyn de::Visitor> {
+                Ok(match k {
+                    "id" => Deserialize::begin(&mut self.id),
+                    "author" => Deserialize::begin(&mut self.author),
+                    "timestamp" => Deserialize::begin(&mut self.timestamp),
+                    "body" => Deserialize::begin(&mut self.body),
+                    _ => <dyn de::Visitor>::ignore(),
+                })
+            }
+
+            fn finish(&mut self) -> Result<()> {
+                let timestamp = self.timestamp.take().ok_or(Error)?;
+                *self.out = Some(Message {
+                    id: self.id.take().ok_or(Error)?,
+                    author: self.author.take().ok_or(Error)?,
+                    timestamp: NaiveDateTime::parse_from_str(&timestamp, TIMESTAMP_FORMAT)
+                        .map_err(|_| Error)?,
+                    body: self.body.take().ok_or(Error)?,
+                });
+                Ok(())
+            }
+        }
+
+        Ok(Box::new(Builder {
+            out: &mut self.out,
+            id: None,
+            author: None,
+            timestamp: None,
+            body: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use miniserde::json;
+
+    #[test]
+    fn enum_round_trip() {
+        let actions = vec![
+            AppAction::TogglePref,
+            AppAction::Wnd(WndAction::SetDraft("hello".to_owned())),
+            AppAction::ReceiveMessage {
+                channel: "#random".to_owned(),
+                message: Message {
+                    id: 42,
+                    author: "kawase".to_owned(),
+                    timestamp: NaiveDateTime::from_timestamp(1_590_000_000, 250_000_000),
+                    body: "hi".to_owned(),
+                },
+            },
+            AppAction::SetConnectionState(ConnectionState::Disconnected("timeout".to_owned())),
+        ];
+
+        let json = json::to_string(&actions);
+        assert!(json.starts_with(r#"["TogglePref",{"Wnd":{"SetDraft":"hello"}},"#));
+
+        let actions2: Vec<AppAction> = json::from_str(&json).unwrap();
+        assert_eq!(format!("{:?}", actions2), format!("{:?}", actions));
+    }
+
+    #[test]
+    fn unknown_variant() {
+        assert!(json::from_str::<WndAction>(r#""Frobnicate""#).is_err());
+        assert!(json::from_str::<WndAction>(r#"{"Frobnicate":1}"#).is_err());
+    }
+}
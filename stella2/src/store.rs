@@ -0,0 +1,449 @@
+//! The local message store
+//!
+//! Messages are persisted in an SQLite database in the profile's data
+//! directory so that the scrollback survives restarts and can be searched.
+//! The full-text index is maintained by SQLite's FTS5 extension.
+//!
+//! The database is accessed by a worker thread. Insertions are queued without
+//! blocking the caller and committed in batches (write-behind), and queries
+//! return futures, which are polled on the main thread by `Wm::spawn_local`.
+//!
+//! Backends don't provide message IDs that persist across sessions, so the
+//! store identifies messages by their contents. The `id`s of the messages
+//! returned by the store are meaningless to the rest of the application.
+//! The same message may legitimately be posted more than once, so a message
+//! is considered to be already stored only as many times as it appears in the
+//! database (see `MessageStore::merge`).
+use chrono::NaiveDateTime;
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use rusqlite::{params, Connection};
+use std::{collections::HashMap, path::Path, sync::mpsc, thread};
+
+use crate::model;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS messages (
+        id INTEGER PRIMARY KEY,
+        channel TEXT NOT NULL,
+        author TEXT NOT NULL,
+        -- Milliseconds since 1970-01-01 00:00:00 in the local time
+        timestamp INTEGER NOT NULL,
+        body TEXT NOT NULL
+    );
+
+    CREATE INDEX IF NOT EXISTS messages_channel_timestamp
+        ON messages (channel, timestamp);
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5 (
+        author, body, content = 'messages', content_rowid = 'id'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS messages_insert AFTER INSERT ON messages BEGIN
+        INSERT INTO messages_fts (rowid, author, body)
+            VALUES (new.id, new.author, new.body);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_delete AFTER DELETE ON messages BEGIN
+        INSERT INTO messages_fts (messages_fts, rowid, author, body)
+            VALUES ('delete', old.id, old.author, old.body);
+    END;
+";
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, displaydoc::Display)]
+pub enum Error {
+    /// Database error: {0}
+    Database(rusqlite::Error),
+    /// The store is closed.
+    Closed,
+}
+
+impl std::error::Error for Error {}
+
+impl From<rusqlite::Error> for Error {
+    fn from(x: rusqlite::Error) -> Self {
+        Error::Database(x)
+    }
+}
+
+/// A handle to the message database. The worker thread exits when
+/// `MessageStore` is dropped, after committing the queued insertions.
+pub struct MessageStore {
+    commands: mpsc::Sender<Command>,
+}
+
+enum Command {
+    Insert(PendingInsert),
+    Query(Box<dyn FnOnce(&Connection) + Send>),
+}
+
+struct PendingInsert {
+    channel: String,
+    messages: Vec<model::Message>,
+    /// Skip the messages already in the database.
+    merge: bool,
+}
+
+impl MessageStore {
+    /// Open the database at `path`, creating it if it doesn't exist.
+    pub fn open(path: &Path) -> Result<Self> {
+        log::debug!("Opening the message store at {:?}", path);
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+
+        let (commands, commands_recv) = mpsc::channel();
+        thread::Builder::new()
+            .name("message store".to_owned())
+            .spawn(move || run(conn, commands_recv))
+            .unwrap();
+
+        Ok(Self { commands })
+    }
+
+    /// Queue new messages (e.g., the ones received just now) to be written to
+    /// the database.
+    pub fn insert(&self, channel: &str, messages: &[model::Message]) {
+        self.queue_insert(channel, messages, false);
+    }
+
+    /// Queue messages that may already be in the database (e.g., the ones
+    /// fetched from a backend) to be written to the database. Each message is
+    /// written only if the database has fewer copies of it (the messages with
+    /// the same author, timestamp, and body) than `messages`.
+    pub fn merge(&self, channel: &str, messages: &[model::Message]) {
+        self.queue_insert(channel, messages, true);
+    }
+
+    fn queue_insert(&self, channel: &str, messages: &[model::Message], merge: bool) {
+        if messages.is_empty() {
+            return;
+        }
+
+        let _ = self.commands.send(Command::Insert(PendingInsert {
+            channel: channel.to_owned(),
+            messages: messages.to_vec(),
+            merge,
+        }));
+    }
+
+    /// Get up to `limit` most recent messages of a channel posted before
+    /// `before`, sorted by time.
+    pub fn load_before(
+        &self,
+        channel: &str,
+        before: NaiveDateTime,
+        limit: usize,
+    ) -> BoxFuture<'static, Result<Vec<model::Message>>> {
+        let channel = channel.to_owned();
+
+        self.query(move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, author, timestamp, body FROM messages
+                    WHERE channel = ?1 AND timestamp < ?2
+                    ORDER BY timestamp DESC, id DESC LIMIT ?3",
+            )?;
+
+            let mut messages = stmt
+                .query_map(
+                    params![channel, to_millis(before), limit as i64],
+                    message_from_row,
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            messages.reverse();
+            Ok(messages)
+        })
+    }
+
+    /// Find up to `limit` messages containing all words in `text`, newest
+    /// first. A word matches any word it's a prefix of.
+    pub fn search(
+        &self,
+        text: &str,
+        limit: usize,
+    ) -> BoxFuture<'static, Result<Vec<model::SearchHit>>> {
+        let fts_query = if let Some(x) = fts_query(text) {
+            x
+        } else {
+            return async { Ok(Vec::new()) }.boxed();
+        };
+
+        self.query(move |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT m.id, m.author, m.timestamp, m.body, m.channel
+                    FROM messages_fts JOIN messages m ON m.id = messages_fts.rowid
+                    WHERE messages_fts MATCH ?1
+                    ORDER BY m.timestamp DESC LIMIT ?2",
+            )?;
+
+            let hits = stmt
+                .query_map(params![fts_query, limit as i64], |row| {
+                    Ok(model::SearchHit {
+                        message: message_from_row(row)?,
+                        channel: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            Ok(hits)
+        })
+    }
+
+    /// Run `f` on the worker thread after the queued insertions are committed.
+    fn query<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Connection) -> Result<T> + Send + 'static,
+    ) -> BoxFuture<'static, Result<T>> {
+        let (send, recv) = oneshot::channel();
+
+        let _ = self.commands.send(Command::Query(Box::new(move |conn| {
+            let _ = send.send(f(conn));
+        })));
+
+        recv.map(|result| result.unwrap_or(Err(Error::Closed)))
+            .boxed()
+    }
+}
+
+/// The main function of the worker thread.
+fn run(mut conn: Connection, commands: mpsc::Receiver<Command>) {
+    let mut pending = Vec::new();
+
+    while let Ok(command) = commands.recv() {
+        // Process all queued commands, gathering insertions so that they can
+        // be committed in a single transaction
+        let mut next = Some(command);
+        while let Some(command) = next {
+            match command {
+                Command::Insert(insert) => pending.push(insert),
+                Command::Query(f) => {
+                    flush(&mut conn, &mut pending);
+                    f(&conn);
+                }
+            }
+            next = commands.try_recv().ok();
+        }
+
+        flush(&mut conn, &mut pending);
+    }
+
+    log::debug!("The message store worker thread is exiting");
+}
+
+/// Commit the pending insertions.
+fn flush(conn: &mut Connection, pending: &mut Vec<PendingInsert>) {
+    if pending.is_empty() {
+        return;
+    }
+
+    let result = (|| -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        {
+            let mut insert_stmt = tx.prepare_cached(
+                "INSERT INTO messages (channel, author, timestamp, body)
+                    VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let mut count_stmt = tx.prepare_cached(
+                "SELECT COUNT(*) FROM messages
+                    WHERE channel = ?1 AND timestamp = ?2 AND author = ?3 AND body = ?4",
+            )?;
+
+            for insert in pending.iter() {
+                let channel = &insert.channel;
+
+                // The number of copies of each message seen so far in
+                // `insert.messages`
+                let mut num_seen = HashMap::new();
+
+                for m in insert.messages.iter() {
+                    let timestamp = to_millis(m.timestamp);
+
+                    if insert.merge {
+                        let num_seen = num_seen.entry((timestamp, &m.author, &m.body)).or_insert(0);
+                        *num_seen += 1;
+
+                        let num_stored: i64 = count_stmt
+                            .query_row(params![channel, timestamp, m.author, m.body], |row| {
+                                row.get(0)
+                            })?;
+                        if num_stored >= *num_seen {
+                            continue;
+                        }
+                    }
+
+                    insert_stmt.execute(params![channel, m.author, timestamp, m.body])?;
+                }
+            }
+        }
+        tx.commit()
+    })();
+
+    if let Err(e) = result {
+        log::warn!("Could not write messages to the message store: {}", e);
+    }
+
+    pending.clear();
+}
+
+fn message_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<model::Message> {
+    Ok(model::Message {
+        id: row.get::<_, i64>(0)? as u64,
+        author: row.get(1)?,
+        timestamp: from_millis(row.get(2)?),
+        body: row.get(3)?,
+    })
+}
+
+fn to_millis(t: NaiveDateTime) -> i64 {
+    t.timestamp_millis()
+}
+
+fn from_millis(ms: i64) -> NaiveDateTime {
+    NaiveDateTime::from_timestamp(
+        ms.div_euclid(1000),
+        (ms.rem_euclid(1000) * 1_000_000) as u32,
+    )
+}
+
+/// Convert a search string entered by the user to an FTS5 query. Each word is
+/// quoted so that the characters having special meanings in the FTS5 query
+/// syntax are matched literally. Returns `None` if there are no words.
+fn fts_query(text: &str) -> Option<String> {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use futures::executor::block_on;
+
+    fn message(hour: u32, author: &str, body: &str) -> model::Message {
+        model::Message {
+            id: 0,
+            author: author.to_owned(),
+            timestamp: NaiveDate::from_ymd(2020, 6, 1).and_hms(hour, 0, 0),
+            body: body.to_owned(),
+        }
+    }
+
+    fn open() -> MessageStore {
+        MessageStore::with_connection(Connection::open_in_memory().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn load_before() {
+        let store = open();
+        store.insert(
+            "freenode/#rust",
+            &[
+                message(1, "alice", "one"),
+                message(2, "bob", "two"),
+                message(3, "alice", "three"),
+            ],
+        );
+        // Merging ignores the messages already stored
+        store.merge("freenode/#rust", &[message(2, "bob", "two")]);
+        store.insert("freenode/#go", &[message(1, "carol", "other")]);
+
+        let before = NaiveDate::from_ymd(2020, 6, 1).and_hms(3, 0, 0);
+        let messages = block_on(store.load_before("freenode/#rust", before, 10)).unwrap();
+        let bodies: Vec<&str> = messages.iter().map(|m| m.body.as_str()).collect();
+        assert_eq!(bodies, ["one", "two"]);
+        assert_eq!(messages[0].timestamp, message(1, "", "").timestamp);
+
+        let messages = block_on(store.load_before("freenode/#rust", before, 1)).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].body, "two");
+    }
+
+    #[test]
+    fn merge() {
+        let store = open();
+        let channel = "freenode/#rust";
+        let bodies = || {
+            let before = NaiveDate::from_ymd(2020, 6, 2).and_hms(0, 0, 0);
+            let messages = block_on(store.load_before(channel, before, 10)).unwrap();
+            messages.into_iter().map(|m| m.body).collect::<Vec<_>>()
+        };
+
+        // A message posted twice is stored twice
+        store.insert(channel, &[message(1, "alice", "+1")]);
+        store.insert(channel, &[message(1, "alice", "+1")]);
+        assert_eq!(bodies(), ["+1", "+1"]);
+
+        // The fetched history overlaps with the stored messages
+        store.merge(
+            channel,
+            &[
+                message(1, "alice", "+1"),
+                message(1, "alice", "+1"),
+                message(2, "bob", "hi"),
+            ],
+        );
+        assert_eq!(bodies(), ["+1", "+1", "hi"]);
+
+        // The fetched history has more copies than stored
+        store.merge(
+            channel,
+            &[
+                message(2, "bob", "hi"),
+                message(2, "bob", "hi"),
+                message(2, "bob", "hi"),
+            ],
+        );
+        assert_eq!(bodies(), ["+1", "+1", "hi", "hi", "hi"]);
+    }
+
+    #[test]
+    fn search() {
+        let store = open();
+        store.insert(
+            "freenode/#rust",
+            &[
+                message(1, "alice", "the borrow checker is happy"),
+                message(2, "bob", "borrowing \"quotes\" AND operators"),
+            ],
+        );
+        store.insert("freenode/#go", &[message(3, "carol", "no borrow checker")]);
+
+        let channels = |text: &str| -> Vec<String> {
+            block_on(store.search(text, 10))
+                .unwrap()
+                .into_iter()
+                .map(|hit| hit.channel)
+                .collect()
+        };
+
+        // Newest first
+        assert_eq!(
+            channels("borrow checker"),
+            ["freenode/#go", "freenode/#rust"]
+        );
+        // Prefix match
+        assert_eq!(channels("borrowi"), ["freenode/#rust"]);
+        // Special characters are matched literally
+        assert_eq!(channels("\"quotes\" AND"), ["freenode/#rust"]);
+        assert!(channels("   ").is_empty());
+        assert!(channels("nonexistent").is_empty());
+    }
+
+    #[test]
+    fn millis_roundtrip() {
+        let t = NaiveDate::from_ymd(1969, 12, 31).and_hms_milli(23, 59, 59, 250);
+        assert_eq!(from_millis(to_millis(t)), t);
+    }
+}
@@ -1,71 +1,110 @@
 use cggeom::box2;
 use cgmath::{Rad, Vector2};
-use std::f32::{consts::PI, NAN};
+use std::{
+    cell::Cell,
+    f32::{consts::PI, NAN},
+    path::Path,
+};
 use stella2_assets as assets;
 #[allow(unused_imports)]
 use tcw3::{
     images::{himg_figures, HImg},
-    pal::{LayerFlags, SysFontType, RGBAF32},
+    pal::{prelude::*, LayerFlags, MtLock, SysFontType, Wm, RGBAF32},
     stvg::StvgImg,
     stylesheet,
     ui::{
-        theming::{roles, LayerXform, Layouter, Manager, Metrics, Stylesheet},
+        theming::{
+            roles, LayerXform, Layouter, Manager, Metrics, Stylesheet, StylesheetSet,
+            TextStylesheet,
+        },
         AlignFlags,
     },
 };
 
+use crate::config::settings::Theme;
+
 /// Define styling ID values.
 pub mod elem_id {
     use tcw3::ui::theming::ClassSet;
 
-    iota::iota! {
-        pub const SHOW_MENU: ClassSet = ClassSet::id(iota);
-                , SIDEBAR_SHOW
-                , SIDEBAR_HIDE
-
-                , SEARCH_FIELD_WRAP
-                , SEARCH_FIELD
-
-                , TOOLBAR_SEPARATOR
-                , MEMBER_COUNT_ICON
-
-                , TOOLBAR
-                , SIDEBAR
-                , CENTRAL
-                , LOG_VIEW
-                , EDITOR
-                , EDITOR_SPLIT
-                , EDITOR_FIELD
-
-                , SIDEBAR_GROUP_HEADER
-                , SIDEBAR_GROUP_BULLET
-                , SIDEBAR_ITEM
-
-                , TABBAR
-                , TABBAR_TAB
-                , TABBAR_TAB_CLOSE
-                , TABBAR_CLOSE
-
-                , PREF
-                , PREF_HEADER
-                , PREF_TITLE_WRAP
-                , PREF_MAIN
-                , PREF_TAB_BAR
-                , PREF_TAB_GENERAL
-                , PREF_TAB_ACCOUNTS
-                , PREF_TAB_CONNECTION
-                , PREF_TAB_ADVANCED
-                , PREF_TAB_ABOUT
-                , PREF_GROUP_CAPTION
-                , PREF_GROUP
-
-                , PREF_CONTENT_GENERAL
-                , PREF_GENERAL_FONT_SIZE
-
-                , WND
-
-                , STACK_HORZ_LEFT_TOP
-                , STACK_HORZ_LEFT_VCENTER
+    macro_rules! define_elem_ids {
+        ($($name:ident),* $(,)*) => {
+            #[allow(non_camel_case_types)]
+            enum Id { $($name),* }
+
+            $( pub const $name: ClassSet = ClassSet::id(Id::$name as u16); )*
+
+            /// The names of all styling ID values defined in this module. Used
+            /// to resolve `#NAME` in text stylesheets.
+            pub static NAMES: &[(&str, ClassSet)] = &[
+                $( (stringify!($name), $name) ),*
+            ];
+        };
+    }
+
+    define_elem_ids! {
+        SHOW_MENU,
+        SIDEBAR_SHOW,
+        SIDEBAR_HIDE,
+
+        SEARCH_FIELD_WRAP,
+        SEARCH_FIELD,
+
+        TOOLBAR_SEPARATOR,
+        MEMBER_COUNT_ICON,
+
+        TOOLBAR,
+        CONNECTION_BAR,
+        SIDEBAR,
+        CENTRAL,
+        LOG_VIEW,
+        EDITOR,
+        EDITOR_SPLIT,
+        EDITOR_FIELD,
+        EDITOR_SIDE,
+        EDITOR_CHAR_COUNTER,
+
+        SIDEBAR_GROUP_HEADER,
+        SIDEBAR_GROUP_BULLET,
+        SIDEBAR_ITEM,
+        SIDEBAR_BADGE,
+
+        QUICK_SWITCHER,
+        QUICK_SWITCHER_ITEM,
+        QUICK_SWITCHER_ITEM_DETAIL,
+        QUICK_SWITCHER_PLACEHOLDER,
+
+        SEARCH_PANE,
+        SEARCH_PANE_ITEM,
+        SEARCH_PANE_ITEM_DETAIL,
+        SEARCH_PANE_PLACEHOLDER,
+
+        TABBAR,
+        TABBAR_TAB,
+        TABBAR_TAB_CLOSE,
+        TABBAR_CLOSE,
+
+        PREF,
+        PREF_HEADER,
+        PREF_TITLE_WRAP,
+        PREF_MAIN,
+        PREF_TAB_BAR,
+        PREF_TAB_GENERAL,
+        PREF_TAB_ACCOUNTS,
+        PREF_TAB_CONNECTION,
+        PREF_TAB_ADVANCED,
+        PREF_TAB_ABOUT,
+        PREF_GROUP_CAPTION,
+        PREF_GROUP,
+
+        PREF_CONTENT_GENERAL,
+        PREF_GENERAL_FONT_SIZE,
+
+        WND,
+
+        STACK_HORZ_LEFT_TOP,
+        STACK_HORZ_LEFT_VCENTER,
+        STACK_VERT_LEFT_TOP,
     }
 }
 
@@ -92,6 +131,8 @@ pub mod my_roles {
     iota::iota! {
         pub const BULLET: Role = iota + 1;
                 , CLOSE
+                , BADGE
+                , DETAIL
     }
 }
 
@@ -237,6 +278,23 @@ fn new_custom_stylesheet() -> impl Stylesheet {
 
             subview_table_cell[2]: [0, 2],
             subview_table_align[2]: AlignFlags::JUSTIFY,
+
+            subview_table_cell[3]: [0, 3],
+            subview_table_align[3]: AlignFlags::JUSTIFY,
+        },
+
+        ([#CONNECTION_BAR]) (priority = 10000) {
+            num_layers: 1,
+            layer_bg_color[0]: RGBAF32::new(1.0, 0.93, 0.7, 1.0),
+
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [4.0, 9.0, 4.0, 9.0],
+                ..Metrics::default()
+            },
+        },
+        ([.LABEL] .. [#CONNECTION_BAR]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.3, 0.25, 0.1, 1.0),
+            font: SysFontType::Small,
         },
 
         ([#LOG_VIEW]) (priority = 10000) {
@@ -255,10 +313,27 @@ fn new_custom_stylesheet() -> impl Stylesheet {
             subview_table_align[0]: AlignFlags::JUSTIFY,
             subview_table_col_spacing[0]: 5.0,
 
-            // "Send button"
+            // "Send" button and the character counter
             subview_table_cell[1]: [1, 0],
-            subview_table_align[1]: AlignFlags::from_bits_truncate(
+            subview_table_align[1]: AlignFlags::JUSTIFY,
+        },
+        ([#EDITOR_SIDE]) (priority = 10000) {
+            subview_layouter: Layouter::Table,
+
+            // "Send" button
+            subview_table_cell[0]: [0, 0],
+            subview_table_align[0]: AlignFlags::from_bits_truncate(
                 AlignFlags::HORZ_JUSTIFY.bits() | AlignFlags::TOP.bits()),
+            subview_table_row_spacing[0]: 5.0,
+
+            // Character counter
+            subview_table_cell[1]: [0, 1],
+            subview_table_align[1]: AlignFlags::from_bits_truncate(
+                AlignFlags::RIGHT.bits() | AlignFlags::BOTTOM.bits()),
+        },
+        ([#EDITOR_CHAR_COUNTER]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 0.5),
+            font: SysFontType::Small,
         },
 
         // Toolbar buttons
@@ -482,15 +557,14 @@ fn new_custom_stylesheet() -> impl Stylesheet {
             subview_layouter: Layouter::Table,
             subview_padding: [3.0; 4],
             subview_table_cell[0]: [0, 0],
-            subview_table_align[0]: AlignFlags::from_bits_truncate(
-                AlignFlags::TOP.bits() | AlignFlags::LEFT.bits()),
+            subview_table_align[0]: AlignFlags::JUSTIFY,
         },
         ([#EDITOR_FIELD.FOCUS]) (priority = 10500) {
             // Focus ring
             layer_opacity[0]: 0.5,
         },
-        ([.LABEL] < [#EDITOR_FIELD]) (priority = 10000) {
-            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 0.4),
+        ([] < [#EDITOR_FIELD]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 1.0),
         },
 
         // Sidebar
@@ -533,12 +607,21 @@ fn new_custom_stylesheet() -> impl Stylesheet {
             },
         },
 
+        // `.USER1` indicates the channel has unread mentions
         ([#SIDEBAR_ITEM]) (priority = 10000) {
             subview_metrics[roles::GENERIC]: Metrics {
-                margin: [NAN, NAN, NAN, 25.0],
+                margin: [NAN, 40.0, NAN, 25.0],
+                ..Metrics::default()
+            },
+            // unread badge
+            subview_metrics[my_roles::BADGE]: Metrics {
+                margin: [NAN, 8.0, NAN, NAN],
                 ..Metrics::default()
             },
         },
+        ([.LABEL] < [#SIDEBAR_ITEM.USER1]) (priority = 10000) {
+            font: SysFontType::Emph,
+        },
         ([#SIDEBAR_ITEM.ACTIVE]) (priority = 10000) {
             num_layers: 1,
             layer_bg_color[0]: RGBAF32::new(0.3, 0.3, 0.3, 0.25),
@@ -553,6 +636,124 @@ fn new_custom_stylesheet() -> impl Stylesheet {
             fg_color: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
         },
 
+        // `.USER1` indicates the unread messages include mentions
+        ([#SIDEBAR_BADGE]) (priority = 10000) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_figures![rect([0.0, 0.0, 0.0, 0.3]).radius(7.0)]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            min_size: Vector2::new(14.0, 14.0),
+            allow_grow: [false, false],
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [0.0, 4.0, 0.0, 4.0],
+                ..Metrics::default()
+            },
+        },
+        ([#SIDEBAR_BADGE.USER1]) (priority = 10500) {
+            #[dyn] layer_img[0]: Some(himg_figures![rect([0.85, 0.2, 0.2, 1.0]).radius(7.0)]),
+        },
+        ([#SIDEBAR_BADGE] < [#SIDEBAR_ITEM.ACTIVE]) (priority = 10000) {
+            #[dyn] layer_img[0]: Some(himg_figures![rect([1.0, 1.0, 1.0, 0.3]).radius(7.0)]),
+        },
+        ([.LABEL] < [#SIDEBAR_BADGE]) (priority = 10000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
+            font: SysFontType::SmallEmph,
+        },
+
+        // Quick switcher
+        ([#QUICK_SWITCHER]) (priority = 10000) {
+            num_layers: 2,
+
+            // Shadow
+            #[dyn] layer_img[0]: Some(himg_figures![rect([0.0, 0.0, 0.0, 0.15]).radius(8.0)]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            layer_metrics[0]: Metrics {
+                margin: [-1.0, -1.0, -3.0, -1.0],
+                ..Metrics::default()
+            },
+
+            // Background
+            #[dyn] layer_img[1]: Some(himg_figures![rect([0.97, 0.97, 0.97, 1.0]).radius(6.0)]),
+            layer_center[1]: box2! { point: [0.5, 0.5] },
+
+            subview_layouter: Layouter::Table,
+            subview_padding: [8.0; 4],
+
+            // Search field
+            subview_table_cell[0]: [0, 0],
+            subview_table_align[0]: AlignFlags::HORZ_JUSTIFY,
+            subview_table_row_spacing[0]: 6.0,
+
+            // Result list
+            subview_table_cell[1]: [0, 1],
+            subview_table_align[1]: AlignFlags::JUSTIFY,
+        },
+        ([#QUICK_SWITCHER_ITEM]) (priority = 10000) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_figures![rect([0.1, 0.3, 0.6, 0.9]).radius(3.0)]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            layer_opacity[0]: 0.0,
+
+            // channel name
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [3.0, NAN, 3.0, 8.0],
+                ..Metrics::default()
+            },
+            // group name
+            subview_metrics[my_roles::DETAIL]: Metrics {
+                margin: [NAN, 8.0, NAN, NAN],
+                ..Metrics::default()
+            },
+        },
+        ([#QUICK_SWITCHER_ITEM.ACTIVE]) (priority = 10000) {
+            layer_opacity[0]: 1.0,
+        },
+        ([.LABEL] < [#QUICK_SWITCHER_ITEM.ACTIVE]) (priority = 10000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
+        },
+        ([#QUICK_SWITCHER_ITEM_DETAIL]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 0.5),
+            font: SysFontType::Small,
+        },
+        ([#QUICK_SWITCHER_ITEM_DETAIL] < [#QUICK_SWITCHER_ITEM.ACTIVE]) (priority = 10100) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.7),
+        },
+        ([#QUICK_SWITCHER_PLACEHOLDER]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 0.5),
+        },
+
+        // Message search results (displayed in place of the channel list)
+        ([#SEARCH_PANE]) (priority = 10000) {
+            subview_layouter: Layouter::Table,
+
+            // Result list
+            subview_table_cell[0]: [0, 0],
+            subview_table_align[0]: AlignFlags::JUSTIFY,
+
+            // Placeholder, overlapping the result list
+            subview_table_cell[1]: [0, 0],
+            subview_table_align[1]: AlignFlags::CENTER,
+        },
+        ([#SEARCH_PANE_ITEM]) (priority = 10000) {
+            subview_layouter: Layouter::Table,
+            subview_padding: [4.0, 10.0, 4.0, 25.0],
+
+            // channel name, author, and time
+            subview_table_cell[my_roles::DETAIL]: [0, 0],
+            subview_table_align[my_roles::DETAIL]: AlignFlags::HORZ_JUSTIFY,
+            subview_table_row_spacing[0]: 1.0,
+
+            // message body
+            subview_table_cell[roles::GENERIC]: [0, 1],
+            subview_table_align[roles::GENERIC]: AlignFlags::HORZ_JUSTIFY,
+        },
+        ([#SEARCH_PANE_ITEM_DETAIL]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 0.5),
+            font: SysFontType::Small,
+        },
+        ([#SEARCH_PANE_PLACEHOLDER]) (priority = 10000) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 0.5),
+        },
+
         // -------------------------------------------------------------------
         // "Preferences" window
         ([#PREF]) (priority = 10000) {
@@ -784,6 +985,20 @@ fn new_custom_stylesheet() -> impl Stylesheet {
             subview_table_cell[1]: [1, 0],
             subview_table_align[1]: AlignFlags::LEFT,
         },
+        ([#STACK_VERT_LEFT_TOP]) (priority = 10000) {
+            subview_layouter: Layouter::Table,
+            subview_table_cell[0]: [0, 0],
+            subview_table_align[0]: AlignFlags::from_bits_truncate(
+                AlignFlags::LEFT.bits() | AlignFlags::TOP.bits()),
+            subview_table_cell[1]: [0, 1],
+            subview_table_align[1]: AlignFlags::from_bits_truncate(
+                AlignFlags::LEFT.bits() | AlignFlags::TOP.bits()),
+            subview_table_cell[2]: [0, 2],
+            subview_table_align[2]: AlignFlags::from_bits_truncate(
+                AlignFlags::LEFT.bits() | AlignFlags::TOP.bits()),
+            subview_table_row_spacing[0]: 5.0,
+            subview_table_row_spacing[1]: 5.0,
+        },
     }
 }
 
@@ -852,12 +1067,154 @@ fn new_custom_platform_stylesheet() -> impl Stylesheet {
     stylesheet! {}
 }
 
+/// Overrides the colors of the main window's surfaces. Used when the dark
+/// theme is selected or the operating system is in the dark mode.
+fn new_dark_stylesheet() -> impl Stylesheet {
+    const TEXT_COLOR: RGBAF32 = RGBAF32::new(1.0, 1.0, 1.0, 0.9);
+    const PANE_COLOR: RGBAF32 = RGBAF32::new(0.16, 0.16, 0.16, 1.0);
+
+    stylesheet! {
+        ([#SPLITTER]) (priority = 30000) {
+            layer_bg_color[0]: RGBAF32::new(0.05, 0.05, 0.05, 1.0),
+        },
+        ([#TOOLBAR]) (priority = 30000) {
+            layer_bg_color[0]: RGBAF32::new(0.2, 0.2, 0.2, 1.0),
+            layer_bg_color[1]: RGBAF32::new(0.0, 0.0, 0.0, 0.6),
+        },
+        ([#TOOLBAR] .. [#WND.ACTIVE]) (priority = 30500) {
+            layer_bg_color[0]: RGBAF32::new(0.24, 0.24, 0.24, 1.0),
+        },
+        ([#SIDEBAR]) (priority = 30000) {
+            layer_bg_color[0]: PANE_COLOR,
+        },
+        ([#TABBAR]) (priority = 30000) {
+            layer_bg_color[0]: PANE_COLOR,
+            layer_bg_color[1]: RGBAF32::new(1.0, 1.0, 1.0, 0.1),
+        },
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        ([#SIDEBAR] .. [#WND.ACTIVE]) (priority = 30500) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 0.8),
+        },
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        ([#TABBAR] .. [#WND.ACTIVE]) (priority = 30500) {
+            layer_bg_color[0]: RGBAF32::new(0.16, 0.16, 0.16, 0.8),
+        },
+        ([#LOG_VIEW]) (priority = 30000) {
+            layer_bg_color[0]: RGBAF32::new(0.11, 0.11, 0.11, 1.0),
+        },
+        ([#EDITOR]) (priority = 30000) {
+            layer_bg_color[0]: PANE_COLOR,
+        },
+        ([#CONNECTION_BAR]) (priority = 30000) {
+            layer_bg_color[0]: RGBAF32::new(0.35, 0.3, 0.15, 1.0),
+        },
+
+        // Text
+        ([.LABEL] .. [#SIDEBAR]) (priority = 30000) {
+            fg_color: TEXT_COLOR,
+        },
+        ([.LABEL] .. [#TOOLBAR]) (priority = 30000) {
+            fg_color: TEXT_COLOR,
+        },
+        ([.LABEL] .. [#LOG_VIEW]) (priority = 30000) {
+            fg_color: TEXT_COLOR,
+        },
+        ([.LABEL] .. [#CONNECTION_BAR]) (priority = 30000) {
+            fg_color: TEXT_COLOR,
+        },
+        ([.LABEL] < [#SIDEBAR_GROUP_HEADER]) (priority = 30100) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.4),
+        },
+        ([#EDITOR_CHAR_COUNTER]) (priority = 30000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.5),
+        },
+
+        ([#QUICK_SWITCHER]) (priority = 30000) {
+            #[dyn] layer_img[1]: Some(himg_figures![rect([0.2, 0.2, 0.2, 1.0]).radius(6.0)]),
+        },
+        ([.LABEL] < [#QUICK_SWITCHER_ITEM]) (priority = 30000) {
+            fg_color: TEXT_COLOR,
+        },
+        ([#QUICK_SWITCHER_ITEM_DETAIL]) (priority = 30000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.5),
+        },
+        ([#QUICK_SWITCHER_PLACEHOLDER]) (priority = 30000) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.5),
+        },
+
+        // These are inside `#SIDEBAR`, so they must take precedence over
+        // `[.LABEL] .. [#SIDEBAR]`
+        ([#SEARCH_PANE_ITEM_DETAIL]) (priority = 30100) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.5),
+        },
+        ([#SEARCH_PANE_PLACEHOLDER]) (priority = 30100) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 0.5),
+        },
+    }
+}
+
 pub const ENABLE_BACKDROP_BLUR: bool = cfg!(any(target_os = "windows", target_os = "macos"));
 
+/// The color theme selected by `set_theme`.
+static THEME: MtLock<Cell<Theme>> = MtLock::new(Cell::new(Theme::System));
+
 pub fn register_stylesheet(manager: &'static Manager) {
-    manager.subscribe_new_sheet_set(Box::new(move |_, _, ctx| {
+    manager.subscribe_new_sheet_set(Box::new(move |wm, _, ctx| {
         ctx.insert_stylesheet(new_custom_stylesheet());
         ctx.insert_stylesheet(new_custom_platform_stylesheet());
+
+        // `Manager` calls this again when the system appearance changes
+        let dark = match THEME.get_with_wm(wm).get() {
+            Theme::System => wm.system_appearance().dark_mode,
+            Theme::Light => false,
+            Theme::Dark => true,
+        };
+        if dark {
+            ctx.insert_stylesheet(new_dark_stylesheet());
+        }
     }));
     manager.update_sheet_set();
 }
+
+/// Switch the color theme. The stylesheets registered by
+/// `register_stylesheet` are rebuilt if the theme has changed.
+pub fn set_theme(wm: Wm, theme: Theme) {
+    if THEME.get_with_wm(wm).replace(theme) != theme {
+        Manager::global(wm).update_sheet_set();
+    }
+}
+
+/// Load a text stylesheet from the specified file and install it as the
+/// application-level stylesheet set of `manager`.
+///
+/// This is intended for iterating on themes without recompiling the
+/// application. Errors are logged and otherwise ignored.
+pub fn load_stylesheet_file(manager: &'static Manager, path: &Path) {
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            log::warn!("Could not read the stylesheet {:?}: {}", path, e);
+            return;
+        }
+    };
+
+    let parser = elem_id::NAMES
+        .iter()
+        .fold(TextStylesheet::parser(), |parser, &(name, id)| {
+            parser.with_id(name, id)
+        });
+
+    match parser.parse(&source) {
+        Ok(sheet) => {
+            log::info!(
+                "Loaded {} rule(s) from the stylesheet {:?}",
+                sheet.num_rules(),
+                path
+            );
+            manager.set_stylesheet_set(StylesheetSet::new().with(sheet));
+        }
+        Err(e) => {
+            log::warn!("Could not parse the stylesheet {:?}: {}", path, e);
+        }
+    }
+}
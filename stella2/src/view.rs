@@ -1,79 +1,224 @@
+use futures::FutureExt;
 use harmony::Elem;
-use log::trace;
 use std::{
     cell::{Cell, RefCell},
+    collections::{BTreeMap, HashSet},
     rc::{Rc, Weak},
+    sync::Arc,
 };
 use tcw3::{
     pal,
     pal::prelude::*,
-    ui::layouts::FillLayout,
+    ui::layouts::{FillLayout, TableLayout},
+    ui::session::{Persist, Session},
     ui::theming,
-    uicore::{ActionId, ActionStatus, HWnd, HWndRef, WndListener, WndStyleFlags},
+    ui::AlignFlags,
+    uicore::{ActionId, ActionStatus, HView, HWnd, HWndRef, WndListener, WndStyleFlags},
 };
 
 use crate::{
-    config::{profile::Profile, viewpersistence},
-    model, stylesheet,
+    backend,
+    config::{profile::Profile, settings::SettingsStore, viewpersistence},
+    model, store, stylesheet,
 };
 
+mod badge;
 mod channellist;
+mod composer;
 mod dpiscalewatcher;
 mod global;
-mod logview;
+mod keymap;
+mod middleware;
 mod prefwnd;
+mod quickswitcher;
 mod radiolist;
-mod splitutils;
+mod recorder;
+mod searchpane;
 mod tabbar;
+mod timeline;
 mod toolbar;
 
+pub use self::recorder::Recording;
+
+/// The `Session` key for the main window.
+const SESSION_MAIN_WND: &str = "main_wnd";
+/// The `Session` key for the split between the sidebar and the main area.
+const SESSION_SPLIT_SIDE: &str = "main_wnd.split_side";
+/// The `Session` key for the split between the chat log and the composer.
+const SESSION_SPLIT_EDITOR: &str = "main_wnd.split_editor";
+
+/// The number of messages fetched from the backend at once.
+const HISTORY_PAGE_SIZE: usize = 50;
+
+/// The maximum number of messages displayed by the message search.
+const SEARCH_RESULT_COUNT_MAX: usize = 100;
+
 pub struct AppView {
     wm: pal::Wm,
     profile: &'static Profile,
     state: RefCell<Elem<model::AppState>>,
     pending_actions: RefCell<Vec<model::AppAction>>,
-    persist_sched: viewpersistence::PersistenceScheduler,
+    /// Dispatched actions pass through these before they are applied to
+    /// `state`.
+    middlewares: Vec<Box<dyn middleware::Middleware>>,
+    settings: Rc<SettingsStore>,
+    keymap: Rc<RefCell<keymap::Keymap>>,
     main_wnd: Rc<WndView>,
+    /// The window session restored from `profile`. It's updated and written
+    /// back when the application quits.
+    session: RefCell<Session>,
     pref_wnd: Cell<Option<Rc<prefwnd::PrefWndView>>>,
+    backend: Arc<dyn backend::Backend>,
+    /// Receives events from `backend`.
+    backend_events: pal::channel::Receiver<backend::Event>,
+    /// The IDs of the channels whose messages are being fetched.
+    history_requests: RefCell<HashSet<String>>,
+    /// Persists messages. `None` if the database couldn't be opened.
+    store: Option<store::MessageStore>,
 }
 
 impl AppView {
-    pub fn new(wm: pal::Wm, profile: &'static Profile) -> Rc<Self> {
-        let mut state = Elem::new(model::AppState::new());
+    /// Construct an `AppView`. If `replay` is given, the actions in it are
+    /// applied to its initial state instead of the one restored from
+    /// `profile`, and the backend, the message store, and the persistence
+    /// are disabled.
+    pub fn new(wm: pal::Wm, profile: &'static Profile, replay: Option<Recording>) -> Rc<Self> {
+        let state = if let Some(recording) = &replay {
+            recording.initial_state()
+        } else {
+            // Restore the app state from the user profile
+            let state = Elem::new(model::AppState::new());
+            viewpersistence::restore_state(profile, state)
+        };
 
-        // Restore the app state from the user profile
-        state = viewpersistence::restore_state(profile, state);
+        let mut middlewares: Vec<Box<dyn middleware::Middleware>> =
+            vec![Box::new(middleware::LogActions)];
+        if replay.is_none() {
+            middlewares.push(Box::new(middleware::PerformSideEffects));
+            middlewares.push(Box::new(middleware::PersistState::new(&state)));
 
-        let persist_sched = viewpersistence::PersistenceScheduler::new(&state);
+            // Record the actions for debugging. This must come last so that
+            // the recorded actions are exactly the ones applied to the state.
+            if cfg!(debug_assertions) {
+                middlewares.push(Box::new(recorder::ActionRecorder::new(&state)));
+            }
+        }
+
+        let settings = SettingsStore::load(wm, profile);
+        stylesheet::set_theme(wm, settings.get().theme);
+
+        let keymap = Rc::new(RefCell::new(keymap::Keymap::new(
+            &settings.get().keybindings,
+        )));
 
-        global::set_main_menu(wm);
+        global::set_main_menu(wm, &keymap.borrow());
 
-        let main_wnd = WndView::new(wm, Elem::clone(&state.main_wnd));
+        let main_wnd = WndView::new(
+            wm,
+            Elem::clone(&state.main_wnd),
+            Elem::clone(&state.channel_groups),
+            Elem::clone(&state.histories),
+            Rc::clone(&keymap),
+        );
+
+        // Changes to the account settings take effect on restart. The backend
+        // is never connected while replaying a recording.
+        let account_settings = settings.get();
+        let backend: Arc<dyn backend::Backend> = if replay.is_some() {
+            Arc::new(backend::mock::MockBackend::new())
+        } else if let Some(matrix) = account_settings.matrix {
+            Arc::new(backend::matrix::MatrixBackend::new(matrix))
+        } else if let Some(irc) = account_settings.irc {
+            Arc::new(backend::irc::IrcBackend::new(irc))
+        } else {
+            Arc::new(backend::mock::MockBackend::new())
+        };
+        let (event_sender, backend_events) = pal::Wm::channel();
+
+        let store = if replay.is_some() {
+            None
+        } else {
+            match store::MessageStore::open(&profile.data_dir().join("messages.db")) {
+                Ok(x) => Some(x),
+                Err(e) => {
+                    log::error!("Could not open the message store: {}", e);
+                    None
+                }
+            }
+        };
+
+        // Restore the window geometry from the last session
+        let session = viewpersistence::restore_session(profile);
+        main_wnd.restore_session(&session);
 
         let this = Rc::new(Self {
             wm,
             profile,
             main_wnd,
+            session: RefCell::new(session),
             state: RefCell::new(state),
             pending_actions: RefCell::new(Vec::new()),
-            persist_sched,
+            middlewares,
+            settings,
+            keymap,
             pref_wnd: Cell::new(None),
+            backend,
+            backend_events,
+            history_requests: RefCell::new(HashSet::new()),
+            store,
         });
 
+        // Apply theme and key binding changes. `AppView` lives as long as the
+        // application does, so the subscription is never removed.
+        let this_weak = Rc::downgrade(&this);
+        let _ = this
+            .settings
+            .subscribe_changed(Box::new(move |wm, new_settings| {
+                stylesheet::set_theme(wm, new_settings.theme);
+
+                if let Some(this) = this_weak.upgrade() {
+                    let new_keymap = keymap::Keymap::new(&new_settings.keybindings);
+                    if *this.keymap.borrow() != new_keymap {
+                        global::set_main_menu(wm, &new_keymap);
+                        this.keymap.replace(new_keymap);
+                    }
+                }
+            }));
+
         let this_weak = Rc::downgrade(&this);
         this.main_wnd
             .set_dispatch(move |app_action| Self::dispatch_weak(&this_weak, app_action));
 
         let this_weak = Rc::downgrade(&this);
         this.main_wnd.set_quit(move || {
-            // Persist the state to disk before quitting
+            // Persist the state to disk before quitting. This is done by
+            // `PersistState`, which is absent while replaying a recording.
             if let Some(this) = this_weak.upgrade() {
-                this.persist_sched.flush(wm, &this.state.borrow(), profile);
+                let state = this.state.borrow();
+                for middleware in this.middlewares.iter() {
+                    middleware.flush(&this, &state);
+                }
             }
 
             wm.terminate();
         });
 
+        let this_weak = Rc::downgrade(&this);
+        this.backend_events.set_handler(wm, move |_, events| {
+            for event in events {
+                Self::handle_backend_event(&this_weak, event);
+            }
+        });
+
+        if let Some(recording) = replay {
+            log::info!("Replaying {} actions", recording.actions.len());
+            for action in recording.actions {
+                Self::dispatch(&this, action);
+            }
+        } else {
+            Self::connect_backend(&this, event_sender);
+        }
+
         this
     }
 
@@ -84,16 +229,292 @@ impl AppView {
     }
 
     fn dispatch(this: &Rc<Self>, action: model::AppAction) {
-        trace!("Dispatching the action: {:?}", action);
+        middleware::Next::new(this, &this.middlewares).dispatch(action);
+    }
 
-        let mut pending_actions = this.pending_actions.borrow_mut();
+    /// Perform the backend operations requested by `action`.
+    fn perform_side_effects(this: &Rc<Self>, action: &model::AppAction) {
+        match action {
+            model::AppAction::Wnd(model::WndAction::SendMessage(text)) => {
+                let channel = this.state.borrow().main_wnd.current_channel.clone();
+                let future = this.backend.send_message(&channel, text);
+
+                this.wm.spawn_local(async move {
+                    if let Err(e) = future.await {
+                        log::warn!("Could not send a message to {:?}: {}", channel, e);
+                    }
+                });
+            }
+            model::AppAction::Wnd(model::WndAction::SetCurrentChannel(channel)) => {
+                // The history might exist without any messages, e.g., if
+                // the reducer only recorded the unread state in it
+                let loaded = (this.state.borrow().histories.get(channel))
+                    .map_or(false, |history| !history.messages.is_empty());
+                if !loaded {
+                    Self::fetch_history(this, channel);
+                }
+                Self::mark_read(this, channel);
+            }
+            model::AppAction::RequestHistory(channel) => {
+                Self::fetch_history(this, channel);
+            }
+            model::AppAction::PrependHistory {
+                channel, messages, ..
+            } => {
+                if let Some(store) = &this.store {
+                    // This includes the messages restored from the store and
+                    // the ones stored by the previous sessions
+                    store.merge(channel, messages);
+                }
+            }
+            model::AppAction::ReceiveMessage { channel, message } => {
+                if let Some(store) = &this.store {
+                    store.insert(channel, std::slice::from_ref(message));
+                }
+                if this.state.borrow().main_wnd.current_channel == *channel {
+                    Self::mark_read(this, channel);
+                }
+            }
+            model::AppAction::SetSearchQuery(query) => {
+                Self::search(this, query);
+            }
+            _ => {}
+        }
+    }
 
-        pending_actions.push(action);
+    /// Tell the backend that the user has read a channel.
+    fn mark_read(this: &Rc<Self>, channel: &str) {
+        let future = this.backend.mark_read(channel);
+        let channel = channel.to_owned();
 
-        if pending_actions.len() == 1 {
-            // Schedule polling
-            let this = Rc::clone(this);
-            this.wm.invoke(move |_| this.poll());
+        this.wm.spawn_local(async move {
+            if let Err(e) = future.await {
+                log::warn!("Could not mark {:?} as read: {}", channel, e);
+            }
+        });
+    }
+
+    /// Connect to the backend and load the channel list.
+    fn connect_backend(this: &Rc<Self>, events: backend::EventSender) {
+        let backend = Arc::clone(&this.backend);
+        let this_weak = Rc::downgrade(this);
+
+        this.wm.spawn_local(async move {
+            if let Err(e) = backend.connect(events).await {
+                log::error!("Could not connect to the backend: {}", e);
+                // The backend keeps trying and reports `Reconnected` when it
+                // succeeds
+                Self::dispatch_weak(
+                    &this_weak,
+                    model::AppAction::SetConnectionState(model::ConnectionState::Disconnected(
+                        e.to_string(),
+                    )),
+                );
+                return;
+            }
+
+            Self::dispatch_weak(
+                &this_weak,
+                model::AppAction::SetConnectionState(model::ConnectionState::Connected),
+            );
+
+            let channel_groups = match backend.list_channels().await {
+                Ok(x) => x,
+                Err(e) => {
+                    log::error!("Could not get the channel list: {}", e);
+                    return;
+                }
+            };
+
+            if let Some(this) = this_weak.upgrade() {
+                Self::dispatch(&this, model::AppAction::SetChannelGroups(channel_groups));
+
+                // Load the messages of the current channel
+                let channel = this.state.borrow().main_wnd.current_channel.clone();
+                Self::dispatch(&this, model::AppAction::RequestHistory(channel));
+            }
+        });
+    }
+
+    /// Fetch the messages older than the loaded ones from the backend. When
+    /// the backend runs out of messages, continue with the ones saved in the
+    /// message store by the previous sessions. The saved messages are also
+    /// shown if the backend is unavailable and nothing is loaded yet. Does
+    /// nothing if there are no more messages or they are already being
+    /// fetched.
+    fn fetch_history(this: &Rc<Self>, channel: &str) {
+        let oldest = match this.state.borrow().histories.get(channel) {
+            Some(history) if history.complete => return,
+            Some(history) => history.messages.first().map(|m| (m.id, m.timestamp)),
+            None => None,
+        };
+
+        let mut history_requests = this.history_requests.borrow_mut();
+        if !history_requests.insert(channel.to_owned()) {
+            return;
+        }
+        drop(history_requests);
+
+        // The restored messages are older than any message the backend can
+        // provide
+        let restored_reached = oldest.map_or(false, |(id, _)| id < backend::MESSAGE_ID_ORIGIN);
+
+        let future = if restored_reached {
+            async { Ok(Vec::new()) }.boxed()
+        } else {
+            let before = oldest.map(|(id, _)| id);
+            this.backend
+                .fetch_history(channel, before, HISTORY_PAGE_SIZE)
+        };
+        let channel = channel.to_owned();
+        let this_weak = Rc::downgrade(this);
+
+        this.wm.spawn_local(async move {
+            let result = future.await;
+
+            let (mut messages, backend_failed) = match result {
+                Ok(x) => (x, false),
+                Err(e) => {
+                    log::warn!("Could not fetch the messages of {:?}: {}", channel, e);
+
+                    // Restoring messages in the middle of the backend's
+                    // history would leave a gap. Try again later.
+                    if oldest.is_some() {
+                        Self::with_weak(&this_weak, |this| {
+                            this.history_requests.borrow_mut().remove(&channel);
+                        });
+                        return;
+                    }
+
+                    // Show the saved scrollback while the backend is
+                    // unavailable
+                    (Vec::new(), true)
+                }
+            };
+
+            let mut complete = messages.len() < HISTORY_PAGE_SIZE;
+
+            // Restore the messages from the store
+            let restore = if complete {
+                let oldest = messages
+                    .first()
+                    .map(|m| (m.id, m.timestamp))
+                    .or(oldest)
+                    .unwrap_or_else(|| {
+                        let now = chrono::Local::now().naive_local();
+                        (backend::MESSAGE_ID_ORIGIN, now)
+                    });
+                let limit = HISTORY_PAGE_SIZE - messages.len();
+
+                Self::with_weak(&this_weak, |this| {
+                    let store = this.store.as_ref()?;
+                    let future = store.load_before(&channel, oldest.1, limit);
+                    Some((oldest.0, future, limit))
+                })
+                .flatten()
+            } else {
+                None
+            };
+
+            if let Some((next_id, future, limit)) = restore {
+                match future.await {
+                    Ok(restored) => {
+                        // The restored messages must have IDs less than
+                        // `next_id`, but IDs can't go below zero
+                        let num_restored = restored.len();
+                        let restored = assign_restored_ids(restored, next_id);
+                        complete = num_restored < limit || restored.len() < num_restored;
+
+                        messages.splice(0..0, restored);
+                    }
+                    Err(e) => {
+                        log::warn!("Could not load the messages of {:?}: {}", channel, e);
+                    }
+                }
+            }
+
+            if backend_failed && messages.is_empty() {
+                // Let the next request try the backend again
+                complete = false;
+            }
+
+            Self::with_weak(&this_weak, |this| {
+                this.history_requests.borrow_mut().remove(&channel);
+                Self::dispatch(
+                    this,
+                    model::AppAction::PrependHistory {
+                        channel,
+                        messages,
+                        complete,
+                    },
+                );
+            });
+        });
+    }
+
+    /// Call `f` with `this_weak` if it's still alive.
+    fn with_weak<R>(this_weak: &Weak<Self>, f: impl FnOnce(&Rc<Self>) -> R) -> Option<R> {
+        this_weak.upgrade().map(|this| f(&this))
+    }
+
+    /// Search the message store and dispatch `SetSearchResults`.
+    fn search(this: &Rc<Self>, query: &str) {
+        let store = if let Some(x) = &this.store {
+            x
+        } else {
+            return;
+        };
+
+        if query.trim().is_empty() {
+            return;
+        }
+
+        let future = store.search(query, SEARCH_RESULT_COUNT_MAX);
+        let query = query.to_owned();
+        let this_weak = Rc::downgrade(this);
+
+        this.wm.spawn_local(async move {
+            match future.await {
+                Ok(hits) => Self::dispatch_weak(
+                    &this_weak,
+                    model::AppAction::SetSearchResults { query, hits },
+                ),
+                Err(e) => {
+                    log::warn!("Could not search the messages for {:?}: {}", query, e);
+                }
+            }
+        });
+    }
+
+    fn handle_backend_event(this_weak: &Weak<Self>, event: backend::Event) {
+        match event {
+            backend::Event::MessageReceived { channel, message } => {
+                Self::dispatch_weak(
+                    this_weak,
+                    model::AppAction::ReceiveMessage { channel, message },
+                );
+            }
+            backend::Event::ChannelsChanged(channel_groups) => {
+                Self::dispatch_weak(
+                    this_weak,
+                    model::AppAction::SetChannelGroups(channel_groups),
+                );
+            }
+            backend::Event::Disconnected(e) => {
+                log::error!("Disconnected from the backend: {}", e);
+                Self::dispatch_weak(
+                    this_weak,
+                    model::AppAction::SetConnectionState(model::ConnectionState::Disconnected(
+                        e.to_string(),
+                    )),
+                );
+            }
+            backend::Event::Reconnected => {
+                Self::dispatch_weak(
+                    this_weak,
+                    model::AppAction::SetConnectionState(model::ConnectionState::Connected),
+                );
+            }
         }
     }
 
@@ -108,19 +529,19 @@ impl AppView {
                 new_state = model::AppState::reduce(new_state, &action);
             }
             *state = new_state;
-
-            // Persist the app state
-            self.persist_sched
-                .handle_update(self.wm, &state, self.profile);
         }
 
         let state = self.state.borrow();
 
-        self.main_wnd.poll(&state.main_wnd);
+        for middleware in self.middlewares.iter() {
+            middleware.state_updated(&self, &state);
+        }
+
+        self.main_wnd.poll(&state);
 
         match (cell_is_some(&self.pref_wnd), state.pref_visible) {
             (false, true) => {
-                let pref_wnd = prefwnd::PrefWndView::new(self.wm);
+                let pref_wnd = prefwnd::PrefWndView::new(self.wm, Rc::clone(&self.settings));
 
                 let this_weak = Rc::downgrade(&self);
                 pref_wnd
@@ -136,22 +557,56 @@ impl AppView {
     }
 }
 
+impl middleware::ChainEnd for AppView {
+    fn enqueue(this: &Rc<Self>, action: model::AppAction) {
+        let mut pending_actions = this.pending_actions.borrow_mut();
+
+        pending_actions.push(action);
+
+        if pending_actions.len() == 1 {
+            // Schedule polling
+            let this = Rc::clone(this);
+            this.wm.invoke(move |_| this.poll());
+        }
+    }
+}
+
 struct WndView {
     hwnd: HWnd,
     dispatch: RefCell<Box<dyn Fn(model::AppAction)>>,
     quit: RefCell<Box<dyn Fn()>>,
     wnd_state: RefCell<Elem<model::WndState>>,
+    channel_groups: RefCell<Elem<Vec<model::ChannelGroup>>>,
+    histories: RefCell<Elem<BTreeMap<String, Elem<model::ChannelHistory>>>>,
+    keymap: Rc<RefCell<keymap::Keymap>>,
     main_view: MainView,
+    /// Displayed if the current channel's messages haven't been loaded yet.
+    empty_history: Elem<model::ChannelHistory>,
+    /// The quick switcher and the view that had the keyboard focus before it
+    /// was opened.
+    quick_switcher: RefCell<Option<(quickswitcher::QuickSwitcherView, Option<HView>)>>,
 }
 
 impl WndView {
-    pub fn new(wm: pal::Wm, wnd_state: Elem<model::WndState>) -> Rc<Self> {
+    pub fn new(
+        wm: pal::Wm,
+        wnd_state: Elem<model::WndState>,
+        channel_groups: Elem<Vec<model::ChannelGroup>>,
+        histories: Elem<BTreeMap<String, Elem<model::ChannelHistory>>>,
+        keymap: Rc<RefCell<keymap::Keymap>>,
+    ) -> Rc<Self> {
         let hwnd = HWnd::new(wm);
         let style_manager = theming::Manager::global(wm);
+        let empty_history = Elem::new(model::ChannelHistory::default());
 
         let main_view = MainViewBuilder::new()
             .with_wm(wm)
             .with_wnd_state(Elem::clone(&wnd_state))
+            .with_channel_groups(Elem::clone(&channel_groups))
+            .with_history(Elem::clone(&empty_history))
+            // Replaced by `poll`
+            .with_search(Elem::new(model::SearchState::default()))
+            .with_connection(model::ConnectionState::Connecting)
             .with_style_manager(style_manager)
             .build();
 
@@ -167,7 +622,12 @@ impl WndView {
             dispatch: RefCell::new(Box::new(|_| {})),
             quit: RefCell::new(Box::new(|| {})),
             wnd_state: RefCell::new(wnd_state),
+            channel_groups: RefCell::new(channel_groups),
+            histories: RefCell::new(histories),
+            keymap,
             main_view,
+            empty_history,
+            quick_switcher: RefCell::new(None),
         });
 
         // Event handlers
@@ -209,6 +669,28 @@ impl WndView {
         *self.quit.borrow_mut() = Box::new(cb);
     }
 
+    /// Restore the window geometry and the split positions from `session`.
+    fn restore_session(&self, session: &Session) {
+        self.hwnd.restore_session(session, SESSION_MAIN_WND);
+        self.main_view
+            .split_side()
+            .restore_session(session, SESSION_SPLIT_SIDE);
+        self.main_view
+            .split_editor()
+            .restore_session(session, SESSION_SPLIT_EDITOR);
+    }
+
+    /// Store the window geometry and the split positions in `session`.
+    fn save_session(&self, session: &mut Session) {
+        self.hwnd.save_session(session, SESSION_MAIN_WND);
+        self.main_view
+            .split_side()
+            .save_session(session, SESSION_SPLIT_SIDE);
+        self.main_view
+            .split_editor()
+            .save_session(session, SESSION_SPLIT_EDITOR);
+    }
+
     fn update_wnd_style_flags(hwnd: HWndRef, is_focused: bool) {
         hwnd.set_style_flags(
             if stylesheet::ENABLE_BACKDROP_BLUR && is_focused {
@@ -219,6 +701,87 @@ impl WndView {
         );
     }
 
+    /// Get the ID of the channel to switch to by `NEXT_CHANNEL` or
+    /// `PREV_CHANNEL`.
+    fn adjacent_channel_id(&self, forward: bool) -> Option<String> {
+        let channel_groups = self.channel_groups.borrow();
+        let wnd_state = self.wnd_state.borrow();
+        let channel =
+            model::adjacent_channel(&channel_groups, &wnd_state.current_channel, forward)?;
+        Some(channel.id.clone())
+    }
+
+    fn toggle_quick_switcher(self: &Rc<Self>, wm: pal::Wm) {
+        if self.quick_switcher.borrow().is_some() {
+            self.hide_quick_switcher();
+        } else {
+            self.show_quick_switcher(wm);
+        }
+    }
+
+    fn show_quick_switcher(self: &Rc<Self>, wm: pal::Wm) {
+        let quick_switcher = quickswitcher::QuickSwitcherViewBuilder::new()
+            .with_wm(wm)
+            .with_style_manager(theming::Manager::global(wm))
+            .with_channel_groups(Elem::clone(&self.channel_groups.borrow()))
+            .with_histories(Elem::clone(&self.histories.borrow()))
+            .build();
+
+        let this_weak = Rc::downgrade(self);
+        quick_switcher.subscribe_select_channel(Box::new(move |channel| {
+            if let Some(this) = this_weak.upgrade() {
+                this.dispatch.borrow()(model::AppAction::Wnd(model::WndAction::SetCurrentChannel(
+                    channel,
+                )));
+                this.hide_quick_switcher();
+            }
+        }));
+
+        let this_weak = Rc::downgrade(self);
+        quick_switcher.subscribe_dismiss(Box::new(move || {
+            if let Some(this) = this_weak.upgrade() {
+                this.hide_quick_switcher();
+            }
+        }));
+
+        // Overlay the quick switcher on the main view
+        self.hwnd.content_view().set_layout(TableLayout::new(vec![
+            (self.main_view.view().clone(), [0, 0], AlignFlags::JUSTIFY),
+            (quick_switcher.view(), [0, 0], AlignFlags::JUSTIFY),
+        ]));
+
+        // The text field can't be focused until it's added to the window
+        let focused_view = self.hwnd.focused_view();
+        let weak_quick_switcher = quick_switcher.downgrade();
+        self.hwnd.invoke_after_update(move |_| {
+            if let Some(quick_switcher) = weak_quick_switcher.upgrade() {
+                quick_switcher.focus();
+            }
+        });
+
+        *self.quick_switcher.borrow_mut() = Some((quick_switcher, focused_view));
+    }
+
+    fn hide_quick_switcher(self: &Rc<Self>) {
+        // This method is called by the quick switcher's event handlers, so
+        // defer the removal of the quick switcher's view
+        let this = Rc::clone(self);
+        self.hwnd.invoke_after_update(move |_| {
+            let (_, focused_view) = if let Some(x) = this.quick_switcher.borrow_mut().take() {
+                x
+            } else {
+                return;
+            };
+
+            this.hwnd
+                .content_view()
+                .set_layout(FillLayout::new(this.main_view.view().clone()));
+
+            // Restore the keyboard focus
+            this.hwnd.set_focused_view(focused_view);
+        });
+    }
+
     fn update_focus(&self) {
         let is_focused = self.hwnd.is_focused();
         if stylesheet::ENABLE_BACKDROP_BLUR {
@@ -227,10 +790,30 @@ impl WndView {
         self.main_view.set_wnd_focused(is_focused);
     }
 
-    fn poll(&self, new_wnd_state: &Elem<model::WndState>) {
-        *self.wnd_state.borrow_mut() = new_wnd_state.clone();
+    fn poll(&self, state: &model::AppState) {
+        let new_wnd_state = &state.main_wnd;
+        let new_channel_groups = &state.channel_groups;
 
-        self.main_view.set_wnd_state(new_wnd_state.clone());
+        *self.wnd_state.borrow_mut() = new_wnd_state.clone();
+        *self.channel_groups.borrow_mut() = new_channel_groups.clone();
+        *self.histories.borrow_mut() = state.histories.clone();
+
+        let history = state
+            .histories
+            .get(&new_wnd_state.current_channel)
+            .unwrap_or(&self.empty_history);
+
+        let main_view = &self.main_view;
+        main_view.set_wnd_state(new_wnd_state.clone());
+        main_view.set_channel_groups(new_channel_groups.clone());
+        main_view.set_history(history.clone());
+        main_view.set_search(state.search.clone());
+        main_view.set_connection(state.connection.clone());
+
+        if let Some((quick_switcher, _)) = &*self.quick_switcher.borrow() {
+            quick_switcher.set_channel_groups(new_channel_groups.clone());
+            quick_switcher.set_histories(state.histories.clone());
+        }
     }
 }
 
@@ -251,6 +834,24 @@ impl WndListener for WndViewWndListener {
         _: HWndRef<'_>,
         ctx: &mut tcw3::uicore::InterpretEventCtx<'_>,
     ) {
+        let owner = if let Some(owner) = self.owner.upgrade() {
+            owner
+        } else {
+            return;
+        };
+
+        if owner.quick_switcher.borrow().is_some() {
+            // Take precedence over the text field's bindings for the
+            // arrow keys
+            quickswitcher::interpret_event(ctx);
+        } else if owner.main_view.composer_is_focused() {
+            composer::interpret_event(ctx);
+        }
+
+        // Consulted before the text input context's bindings, so the
+        // user-defined key bindings work while a text field has focus
+        ctx.use_accel(owner.keymap.borrow().accel_table());
+
         global::interpret_event(ctx);
     }
 
@@ -269,12 +870,32 @@ impl WndListener for WndViewWndListener {
                     );
                 }
             }
+            global::NEXT_CHANNEL | global::PREV_CHANNEL => {
+                status = ActionStatus::VALID;
+                if let Some(owner) = self.owner.upgrade() {
+                    status.set(
+                        ActionStatus::ENABLED,
+                        owner
+                            .adjacent_channel_id(action == global::NEXT_CHANNEL)
+                            .is_some(),
+                    );
+                }
+            }
+            global::QUICK_SWITCHER => {
+                status = ActionStatus::VALID | ActionStatus::ENABLED;
+                if let Some(owner) = self.owner.upgrade() {
+                    status.set(
+                        ActionStatus::CHECKED,
+                        owner.quick_switcher.borrow().is_some(),
+                    );
+                }
+            }
             _ => {}
         }
         status
     }
 
-    fn perform_action(&self, _: pal::Wm, _: HWndRef<'_>, action: ActionId) {
+    fn perform_action(&self, wm: pal::Wm, _: HWndRef<'_>, action: ActionId) {
         let owner = if let Some(owner) = self.owner.upgrade() {
             owner
         } else {
@@ -285,6 +906,16 @@ impl WndListener for WndViewWndListener {
             global::TOGGLE_SIDEBAR => {
                 owner.dispatch.borrow()(model::AppAction::Wnd(model::WndAction::ToggleSidebar));
             }
+            global::NEXT_CHANNEL | global::PREV_CHANNEL => {
+                if let Some(id) = owner.adjacent_channel_id(action == global::NEXT_CHANNEL) {
+                    owner.dispatch.borrow()(model::AppAction::Wnd(
+                        model::WndAction::SetCurrentChannel(id),
+                    ));
+                }
+            }
+            global::QUICK_SWITCHER => {
+                owner.toggle_quick_switcher(wm);
+            }
             global::SHOW_PREF => {
                 owner.dispatch.borrow()(model::AppAction::TogglePref);
             }
@@ -300,9 +931,65 @@ stella2_meta::designer_impl! {
     crate::view::MainView
 }
 
+impl MainView {
+    /// Get a flag indicating whether the composer has the keyboard focus.
+    fn composer_is_focused(&self) -> bool {
+        self.composer().view().improper_subview_is_focused()
+    }
+}
+
 fn cell_is_some<T>(cell: &Cell<Option<T>>) -> bool {
     let inner = cell.take();
     let x = inner.is_some();
     cell.set(inner);
     x
 }
+
+/// Assign IDs less than `next_id` to the messages restored from the message
+/// store, which are sorted by time. The oldest messages are discarded if
+/// there aren't enough IDs.
+fn assign_restored_ids(mut messages: Vec<model::Message>, next_id: u64) -> Vec<model::Message> {
+    let len = (messages.len() as u64).min(next_id) as usize;
+    messages.drain(..messages.len() - len);
+
+    let first_id = next_id - len as u64;
+    for (i, message) in messages.iter_mut().enumerate() {
+        message.id = first_id + i as u64;
+    }
+
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restored_ids() {
+        let messages: Vec<model::Message> = (0..3)
+            .map(|i| model::Message {
+                id: 0,
+                author: "alice".to_owned(),
+                timestamp: chrono::NaiveDateTime::from_timestamp(i, 0),
+                body: i.to_string(),
+            })
+            .collect();
+
+        let ids = |messages: Vec<model::Message>| -> Vec<(u64, String)> {
+            messages.into_iter().map(|m| (m.id, m.body)).collect()
+        };
+
+        assert_eq!(
+            ids(assign_restored_ids(messages.clone(), 10)),
+            [
+                (7, "0".to_owned()),
+                (8, "1".to_owned()),
+                (9, "2".to_owned())
+            ]
+        );
+        assert_eq!(
+            ids(assign_restored_ids(messages, 2)),
+            [(0, "1".to_owned()), (1, "2".to_owned())]
+        );
+    }
+}
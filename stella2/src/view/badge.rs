@@ -0,0 +1,27 @@
+use tcw3::{ui::theming, uicore::HViewRef};
+
+stella2_meta::designer_impl! {
+    crate::view::badge::BadgeView
+}
+
+/// The largest count displayed as is by `BadgeView`.
+const COUNT_MAX: u32 = 99;
+
+/// Get the text displayed by `BadgeView`.
+fn count_text(count: u32) -> String {
+    if count > COUNT_MAX {
+        format!("{}+", COUNT_MAX)
+    } else {
+        count.to_string()
+    }
+}
+
+impl theming::Widget for BadgeView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view().as_ref()
+    }
+
+    fn style_elem(&self) -> Option<theming::HElem> {
+        Some(self.style_elem())
+    }
+}
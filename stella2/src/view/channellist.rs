@@ -1,14 +1,20 @@
+use cgmath::Point2;
 use std::{ops::Range, rc::Rc};
 use tcw3::{
+    pal,
     ui::{
         prelude::*,
         theming,
-        views::{table, table::LineTy, Button, Label},
+        views::{table, table::LineTy, Button, Label, LineBreakMode},
     },
-    uicore::{HView, HViewRef},
+    uicore::{HView, HViewRef, MouseDragListener, ViewListener},
 };
 
-use crate::stylesheet::{elem_id, my_roles};
+use crate::{
+    model,
+    stylesheet::{elem_id, my_roles},
+    view::badge::BadgeViewBuilder,
+};
 
 stella2_meta::designer_impl! {
     crate::view::channellist::ChannelListView
@@ -22,11 +28,93 @@ impl ChannelListView {
             edit.set_model(TableModelQuery {
                 style_manager: self.style_manager(),
                 elem: Rc::clone(self.elem()),
+                rows: Vec::new(),
+                current_channel: String::new(),
             });
-            edit.insert(LineTy::Row, 0..29);
             edit.insert(LineTy::Col, 0..1);
             edit.set_scroll_pos([0.0, 0.0]);
         }
+
+        self.view().set_listener(ChannelListViewListener {
+            owner: self.downgrade(),
+        });
+
+        self.update_rows();
+    }
+
+    /// Update the table to reflect `channel_groups` and `current_channel`.
+    fn update_rows(&self) {
+        let new_rows = rows_from_groups(&self.channel_groups());
+        let current_channel = self.current_channel();
+
+        let mut edit = self.table().table().edit().unwrap();
+
+        let (ops, old_current_channel) = {
+            let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+            let ops = diff(&model.rows, &new_rows);
+            model.rows = new_rows;
+
+            let old_current_channel =
+                std::mem::replace(&mut model.current_channel, current_channel.clone());
+
+            (ops, old_current_channel)
+        };
+
+        // Apply the changes incrementally so that the unchanged rows keep
+        // their views
+        for op in ops {
+            match op {
+                DiffOp::Remove(range) => {
+                    edit.remove(LineTy::Row, range.start as u64..range.end as u64)
+                }
+                DiffOp::Insert(range) => {
+                    edit.insert(LineTy::Row, range.start as u64..range.end as u64)
+                }
+            }
+        }
+
+        // Update the highlight
+        if old_current_channel != current_channel {
+            let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+            let affected_rows: Vec<usize> = model
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| {
+                    matches!(row, Row::Channel { id, .. }
+                        if *id == old_current_channel || *id == current_channel)
+                })
+                .map(|(i, _)| i)
+                .collect();
+
+            for i in affected_rows {
+                edit.renew_subviews(LineTy::Row, i as u64..i as u64 + 1);
+            }
+        }
+    }
+
+    /// Get the ID of the channel displayed at the specified vertical position
+    /// (relative to `view`).
+    fn channel_at(&self, y: f32) -> Option<String> {
+        let mut edit = self.table().table().edit().ok()?;
+        let y = y as f64 + edit.scroll_pos()[1] + edit.display_offset()[1];
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
+        let mut row_y = 0.0;
+        let channel = model
+            .rows
+            .iter()
+            .find(|row| {
+                row_y += row.height();
+                row_y > y
+            })
+            .and_then(|row| match row {
+                Row::Channel { id, .. } => Some(id.clone()),
+                Row::Group { .. } => None,
+            });
+
+        channel
     }
 }
 
@@ -40,42 +128,220 @@ impl theming::Widget for ChannelListView {
     }
 }
 
+struct ChannelListViewListener {
+    owner: WeakChannelListView,
+}
+
+impl ViewListener for ChannelListViewListener {
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        hview: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        // Select a channel by the primary mouse button
+        if button != 0 {
+            return Box::new(());
+        }
+
+        if let Some(owner) = self.owner.upgrade() {
+            let y = loc.y - hview.global_frame().min.y;
+
+            if let Some(channel) = owner.channel_at(y) {
+                owner.raise_select_channel(channel);
+            }
+        }
+
+        Box::new(())
+    }
+}
+
+/// A row of the channel list.
+#[derive(Debug, Clone, PartialEq)]
+enum Row {
+    /// The header of a channel group.
+    Group { id: String, name: String },
+    Channel {
+        id: String,
+        name: String,
+        unread_count: u32,
+        mentioned: bool,
+    },
+}
+
+impl Row {
+    fn height(&self) -> f64 {
+        match self {
+            Row::Group { .. } => 25.0,
+            Row::Channel { .. } => 20.0,
+        }
+    }
+}
+
+/// Flatten channel groups into rows.
+fn rows_from_groups(groups: &[model::ChannelGroup]) -> Vec<Row> {
+    let mut rows = Vec::new();
+
+    for group in groups.iter() {
+        rows.push(Row::Group {
+            id: group.id.clone(),
+            name: group.name.clone(),
+        });
+        rows.extend(group.channels.iter().map(|channel| Row::Channel {
+            id: channel.id.clone(),
+            name: channel.name.clone(),
+            unread_count: channel.unread_count,
+            mentioned: channel.mentioned,
+        }));
+    }
+
+    rows
+}
+
+/// An operation generated by `diff`. The operations are supposed to be
+/// applied in order. The indices are based on the sequence being edited
+/// (i.e., reflect the preceding operations).
+#[derive(Debug, Clone, PartialEq)]
+enum DiffOp {
+    /// Remove the elements in the specified range.
+    Remove(Range<usize>),
+    /// Insert the elements `new[range]` at `range.start`.
+    Insert(Range<usize>),
+}
+
+/// The maximum product of the lengths of the changed portions `diff` compares
+/// element by element. Beyond this limit, `diff` just replaces the entire
+/// changed portion to bound the memory usage.
+const DIFF_COST_MAX: usize = 1 << 20;
+
+/// Compute a minimal sequence of removals and insertions that transforms
+/// `old` into `new`.
+fn diff<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffOp> {
+    // Skip the common prefix and suffix
+    let prefix_len = old
+        .iter()
+        .zip(new.iter())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let suffix_len = old[prefix_len..]
+        .iter()
+        .rev()
+        .zip(new[prefix_len..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let old = &old[prefix_len..old.len() - suffix_len];
+    let new = &new[prefix_len..new.len() - suffix_len];
+    let (n, m) = (old.len(), new.len());
+
+    let mut ops = Vec::new();
+
+    if n * m > DIFF_COST_MAX {
+        push_diff_op(&mut ops, DiffOp::Remove(prefix_len..prefix_len + n));
+        push_diff_op(&mut ops, DiffOp::Insert(prefix_len..prefix_len + m));
+        return ops;
+    }
+
+    // `lcs[at(i, j)]` = the length of the longest common subsequence of
+    // `old[i..]` and `new[j..]`
+    let at = |i: usize, j: usize| i * (m + 1) + j;
+    let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[at(i, j)] = if old[i] == new[j] {
+                lcs[at(i + 1, j + 1)] + 1
+            } else {
+                lcs[at(i + 1, j)].max(lcs[at(i, j + 1)])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let pos = prefix_len + j;
+        if i < n && j < m && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j < m && (i == n || lcs[at(i, j + 1)] >= lcs[at(i + 1, j)]) {
+            push_diff_op(&mut ops, DiffOp::Insert(pos..pos + 1));
+            j += 1;
+        } else {
+            push_diff_op(&mut ops, DiffOp::Remove(pos..pos + 1));
+            i += 1;
+        }
+    }
+
+    ops
+}
+
+/// Push `op` to `ops`, merging it with the last element if possible.
+fn push_diff_op(ops: &mut Vec<DiffOp>, op: DiffOp) {
+    if op_is_empty(&op) {
+        return;
+    }
+
+    if let Some(last) = ops.last_mut() {
+        match (last, &op) {
+            (DiffOp::Remove(last), DiffOp::Remove(range)) if last.start == range.start => {
+                last.end += range.end - range.start;
+                return;
+            }
+            (DiffOp::Insert(last), DiffOp::Insert(range)) if last.end == range.start => {
+                last.end = range.end;
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    ops.push(op);
+}
+
+fn op_is_empty(op: &DiffOp) -> bool {
+    match op {
+        DiffOp::Remove(range) | DiffOp::Insert(range) => range.start == range.end,
+    }
+}
+
 struct TableModelQuery {
     style_manager: &'static theming::Manager,
     elem: Rc<theming::Elem>,
+    rows: Vec<Row>,
+    current_channel: String,
 }
 
 impl table::TableModelQuery for TableModelQuery {
     fn new_view(&mut self, cell: table::CellIdx) -> (HView, Box<dyn table::CellCtrler>) {
+        let row = &self.rows[cell[1] as usize];
+
         let label = Label::new(self.style_manager);
-        label.set_text(match (cell[1] % 4, (cell[1] / 4) % 4) {
-            (0, 0) => "randomserver — Slack",
-            (0, 1) => "workplace — Slack",
-            (0, 2) => "thawedpeach — GNU Social",
-            (0, 3) => "FreeNode",
-            (1, _) => "#general",
-            (2, _) => "#prolang",
-            (3, _) => "#random",
-            _ => unreachable!(),
+        label.set_line_break_mode(LineBreakMode::TruncateTail);
+        label.set_text(match row {
+            Row::Group { name, .. } | Row::Channel { name, .. } => name.as_str(),
         });
 
         let wrap = theming::StyledBox::new(self.style_manager, Default::default());
         wrap.set_child(theming::roles::GENERIC, Some(&label));
-        wrap.set_class_set(
-            if cell[1] % 4 == 0 {
-                elem_id::SIDEBAR_GROUP_HEADER
-            } else {
-                elem_id::SIDEBAR_ITEM
-            } | if cell[1] == 1 || (cell[1] % 4 == 0 && cell[1] < 28) {
-                theming::ClassSet::ACTIVE
-            } else {
-                theming::ClassSet::empty()
-            },
-        );
+        wrap.set_class_set(match row {
+            // `ACTIVE` indicates the group is expanded
+            Row::Group { .. } => elem_id::SIDEBAR_GROUP_HEADER | theming::ClassSet::ACTIVE,
+            Row::Channel { id, mentioned, .. } => {
+                let mut class_set = elem_id::SIDEBAR_ITEM;
+                if *id == self.current_channel {
+                    class_set |= theming::ClassSet::ACTIVE;
+                }
+                if *mentioned {
+                    // Highlight the channel
+                    class_set |= theming::ClassSet::USER1;
+                }
+                class_set
+            }
+        });
 
         self.elem.insert_child(wrap.style_elem());
 
-        let button = if cell[1] % 4 == 0 {
+        let button = if let Row::Group { .. } = row {
             let button = Button::new(self.style_manager);
             // Clear `.BUTTON` and replace with `#SIDEBAR_GROUP_BULLET`
             button.set_class_set(elem_id::SIDEBAR_GROUP_BULLET);
@@ -87,13 +353,33 @@ impl table::TableModelQuery for TableModelQuery {
             None
         };
 
-        (wrap.view(), Box::new(((wrap, button),)))
+        let badge = match *row {
+            Row::Channel {
+                unread_count,
+                mentioned,
+                ..
+            } if unread_count > 0 => {
+                let badge = BadgeViewBuilder::new()
+                    .with_style_manager(self.style_manager)
+                    .with_count(unread_count)
+                    .with_mentioned(mentioned)
+                    .build();
+
+                wrap.set_child(my_roles::BADGE, Some(&badge));
+
+                Some(badge)
+            }
+            _ => None,
+        };
+
+        (wrap.view(), Box::new(((wrap, button, badge),)))
     }
 
     fn range_size(&mut self, line_ty: LineTy, range: Range<u64>, _approx: bool) -> f64 {
         match line_ty {
-            LineTy::Row => (range.start..range.end)
-                .map(|i| if i % 4 == 0 { 25.0 } else { 20.0 })
+            LineTy::Row => self.rows[range.start as usize..range.end as usize]
+                .iter()
+                .map(Row::height)
                 .sum(),
 
             // `TableFlags::GROW_LAST_COL` expands the column to cover the region.
@@ -102,3 +388,66 @@ impl table::TableModelQuery for TableModelQuery {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Apply `ops` to `old` and return the result.
+    fn apply(old: &[char], new: &[char], ops: &[DiffOp]) -> Vec<char> {
+        let mut seq = old.to_vec();
+        for op in ops {
+            match op {
+                DiffOp::Remove(range) => {
+                    seq.drain(range.clone());
+                }
+                DiffOp::Insert(range) => {
+                    seq.splice(range.start..range.start, new[range.clone()].iter().cloned());
+                }
+            }
+        }
+        seq
+    }
+
+    fn check(old: &str, new: &str, num_changed: usize) {
+        let old: Vec<char> = old.chars().collect();
+        let new: Vec<char> = new.chars().collect();
+        let ops = diff(&old, &new);
+
+        assert_eq!(apply(&old, &new, &ops), new);
+
+        let actual_num_changed: usize = ops
+            .iter()
+            .map(|op| match op {
+                DiffOp::Remove(range) | DiffOp::Insert(range) => range.end - range.start,
+            })
+            .sum();
+        assert_eq!(actual_num_changed, num_changed);
+    }
+
+    #[test]
+    fn diff_identical() {
+        check("", "", 0);
+        check("abc", "abc", 0);
+    }
+
+    #[test]
+    fn diff_insert() {
+        check("", "abc", 3);
+        check("ac", "abc", 1);
+        check("abc", "xabcy", 2);
+    }
+
+    #[test]
+    fn diff_remove() {
+        check("abc", "", 3);
+        check("abc", "ac", 1);
+        check("xabcy", "abc", 2);
+    }
+
+    #[test]
+    fn diff_mixed() {
+        check("abcdef", "axcdyf", 4);
+        check("abcabba", "cbabac", 5);
+    }
+}
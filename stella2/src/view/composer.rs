@@ -0,0 +1,115 @@
+use tcw3::{
+    pal,
+    ui::theming,
+    uicore::{ActionId, ActionStatus, HViewRef, InterpretEventCtx, ViewListener},
+};
+
+use crate::view::global;
+
+/// The maximum length of a message, measured in characters.
+pub const MESSAGE_LEN_MAX: usize = 4000;
+
+stella2_meta::designer_impl! {
+    crate::view::composer::ComposerView
+}
+
+/// Bind the Return key to `global::SEND_MESSAGE`. This should be called only
+/// while the composer has the keyboard focus because the binding shadows
+/// `INSERT_PARAGRAPH_BREAK`. `Shift+Return` still inserts a line break.
+pub fn interpret_event(ctx: &mut InterpretEventCtx<'_>) {
+    ctx.use_accel(&pal::accel_table![(
+        global::SEND_MESSAGE,
+        windows("Return"),
+        gtk("Return"),
+        macos("Return")
+    )]);
+}
+
+impl ComposerView {
+    fn init(&self) {
+        self.view().set_listener(ComposerViewListener {
+            owner: self.downgrade(),
+        });
+
+        self.load_draft();
+    }
+
+    /// Load the draft of the current channel to the text field if it hasn't
+    /// been loaded yet.
+    ///
+    /// `draft` is not reflected to the text field on every update because it
+    /// is updated asynchronously and may lag behind the text field.
+    fn load_draft(&self) {
+        let channel = self.channel();
+
+        {
+            let mut loaded_channel = self.loaded_channel().borrow_mut();
+            if loaded_channel.as_ref() == Some(&channel) {
+                return;
+            }
+            *loaded_channel = Some(channel);
+        }
+
+        self.text_area().set_text(self.draft());
+    }
+
+    /// Handle `text_area.changed` event.
+    fn handle_text_change(&self) {
+        self.raise_change_draft(self.text_area().text());
+    }
+
+    /// Send the message in the text field (if it's sendable).
+    fn send(&self) {
+        let text = self.text_area().text();
+        if !is_sendable(&text) {
+            return;
+        }
+
+        self.text_area().set_text(String::new());
+        self.raise_send(text);
+    }
+}
+
+impl theming::Widget for ComposerView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view().as_ref()
+    }
+
+    fn style_elem(&self) -> Option<theming::HElem> {
+        Some(self.style_elem())
+    }
+}
+
+fn is_sendable(text: &str) -> bool {
+    !text.trim().is_empty() && text.chars().count() <= MESSAGE_LEN_MAX
+}
+
+struct ComposerViewListener {
+    owner: WeakComposerView,
+}
+
+impl ViewListener for ComposerViewListener {
+    fn validate_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
+        let mut status = ActionStatus::empty();
+        if action == global::SEND_MESSAGE {
+            // Disable the action if there's nothing to send (or the
+            // message is too long)
+            status = ActionStatus::VALID;
+            if let Some(owner) = self.owner.upgrade() {
+                status.set(
+                    ActionStatus::ENABLED,
+                    is_sendable(&owner.text_area().text()),
+                );
+            }
+        }
+        status
+    }
+
+    fn perform_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) {
+        if action == global::SEND_MESSAGE {
+            if let Some(owner) = self.owner.upgrade() {
+                owner.send();
+            }
+        }
+    }
+}
@@ -4,6 +4,8 @@ use tcw3::{
     uicore::{ActionId, InterpretEventCtx},
 };
 
+use super::keymap::Keymap;
+
 /// Re-export system actions
 pub use tcw3::uicore::actions as sys;
 
@@ -11,6 +13,14 @@ iota::iota! {
     pub const QUIT: ActionId = iota + 1;
             , TOGGLE_SIDEBAR
             , SHOW_PREF
+            , SEND_MESSAGE
+            , NEXT_CHANNEL
+            , PREV_CHANNEL
+            , QUICK_SWITCHER
+            , QUICK_SWITCHER_PREV
+            , QUICK_SWITCHER_NEXT
+            , QUICK_SWITCHER_ACCEPT
+            , QUICK_SWITCHER_DISMISS
 }
 
 pub fn interpret_event(ctx: &mut InterpretEventCtx<'_>) {
@@ -58,14 +68,22 @@ pub fn interpret_event(ctx: &mut InterpretEventCtx<'_>) {
             gtk("Ctrl+Q"),
             macos_sel("terminate:")
         ),
+        // The key bindings of these actions are provided by `Keymap`
         (TOGGLE_SIDEBAR, macos_sel("toggleSidebar:")),
+        (NEXT_CHANNEL, macos_sel("selectNextChannel:")),
+        (PREV_CHANNEL, macos_sel("selectPreviousChannel:")),
+        (QUICK_SWITCHER, macos_sel("showQuickSwitcher:")),
         (SHOW_PREF, macos_sel("orderFrontPreferencesPanel:")),
     ]);
 }
 
 /// Create a main menu on macOS.
+///
+/// The key equivalents of the items bound to actions in `BINDABLE_ACTIONS`
+/// are taken from `keymap`. This function should be called again when
+/// `keymap` changes.
 #[cfg(target_os = "macos")]
-pub fn set_main_menu(_: pal::Wm) {
+pub fn set_main_menu(_: pal::Wm, keymap: &Keymap) {
     // Most of these are predefined by the Interface Builder template that
     // comes with Xcode.
     static MENU: &[Item] = &[
@@ -118,7 +136,12 @@ pub fn set_main_menu(_: pal::Wm) {
         Item::Submenu(
             "View",
             &[
-                Item::leaf("Show Sidebar", "toggleSidebar:").with_cmd_ctrl("s"),
+                Item::leaf("Show Sidebar", "toggleSidebar:"),
+                Item::Sep,
+                Item::leaf("Next Channel", "selectNextChannel:"),
+                Item::leaf("Previous Channel", "selectPreviousChannel:"),
+                Item::leaf("Quick Switcher…", "showQuickSwitcher:"),
+                Item::Sep,
                 Item::leaf("Enter Full Screen", "toggleFullScreen:").with_cmd_ctrl("f"),
             ],
         ),
@@ -146,6 +169,10 @@ pub fn set_main_menu(_: pal::Wm) {
         foundation::{NSAutoreleasePool, NSString},
     };
     use objc::{msg_send, runtime::Sel, sel, sel_impl};
+    use std::borrow::Cow;
+    use tcw3::pal::{Key, ModifierState};
+
+    use super::keymap::{KeyCombo, BINDABLE_ACTIONS};
 
     enum Item {
         Leaf(Leaf),
@@ -233,7 +260,46 @@ pub fn set_main_menu(_: pal::Wm) {
 
     let _arp = AutoreleasePool::new();
 
-    unsafe fn new_menu(title: &str, items: &[Item], app: id) -> id {
+    /// Convert `KeyCombo` to a key equivalent and a modifier mask.
+    fn combo_to_key_eq(combo: KeyCombo) -> Option<(Cow<'static, str>, NSEventModifierFlags)> {
+        let key_eq: Cow<'static, str> = match combo.key {
+            Key::Char(c) => c.to_string().into(),
+            Key::Backspace => "\u{8}".into(),
+            Key::Tab => "\t".into(),
+            Key::Return => "\r".into(),
+            Key::Escape => "\u{1b}".into(),
+            // `NS*FunctionKey`
+            Key::Up => "\u{f700}".into(),
+            Key::Down => "\u{f701}".into(),
+            Key::Left => "\u{f702}".into(),
+            Key::Right => "\u{f703}".into(),
+            Key::Delete => "\u{f728}".into(),
+            Key::Home => "\u{f729}".into(),
+            Key::End => "\u{f72b}".into(),
+            Key::PageUp => "\u{f72c}".into(),
+            Key::PageDown => "\u{f72d}".into(),
+            _ => return None,
+        };
+
+        let mut mod_flags = NSEventModifierFlags::empty();
+        for &(modifier, flag) in &[
+            (ModifierState::SHIFT, NSEventModifierFlags::NSShiftKeyMask),
+            (
+                ModifierState::CONTROL,
+                NSEventModifierFlags::NSControlKeyMask,
+            ),
+            (ModifierState::ALT, NSEventModifierFlags::NSAlternateKeyMask),
+            (ModifierState::SUPER, NSEventModifierFlags::NSCommandKeyMask),
+        ] {
+            if combo.modifiers.contains(modifier) {
+                mod_flags |= flag;
+            }
+        }
+
+        Some((key_eq, mod_flags))
+    }
+
+    unsafe fn new_menu(title: &str, items: &[Item], app: id, keymap: &Keymap) -> id {
         let menu = NSMenu::alloc(nil)
             .autorelease()
             .initWithTitle_(NSString::alloc(nil).autorelease().init_str(title));
@@ -253,18 +319,29 @@ pub fn set_main_menu(_: pal::Wm) {
                     key_eq,
                     mod_flags,
                 }) => {
+                    let (key_eq, mod_flags) = if let Some(bindable) =
+                        BINDABLE_ACTIONS.iter().find(|a| a.macos_sel == *action)
+                    {
+                        keymap
+                            .combo_for_action(bindable.action)
+                            .and_then(combo_to_key_eq)
+                            .unwrap_or(("".into(), NSEventModifierFlags::empty()))
+                    } else {
+                        ((*key_eq).into(), *mod_flags)
+                    };
+
                     let cocoa_item = menu.addItemWithTitle_action_keyEquivalent(
                         NSString::alloc(nil).autorelease().init_str(title),
                         Sel::register(action),
-                        NSString::alloc(nil).autorelease().init_str(key_eq),
+                        NSString::alloc(nil).autorelease().init_str(&key_eq),
                     );
-                    cocoa_item.setKeyEquivalentModifierMask_(*mod_flags);
+                    cocoa_item.setKeyEquivalentModifierMask_(mod_flags);
                 }
                 Item::Submenu(title, children) => {
                     let submenu = NSMenuItem::alloc(nil).autorelease();
                     let () = msg_send![submenu, setTitle:
                         NSString::alloc(nil).autorelease().init_str(title)];
-                    submenu.setSubmenu_(new_menu(title, children, app));
+                    submenu.setSubmenu_(new_menu(title, children, app, keymap));
                     menu.addItem_(submenu);
                 }
                 Item::Sep => {
@@ -277,9 +354,9 @@ pub fn set_main_menu(_: pal::Wm) {
 
     unsafe {
         let app = cocoa::appkit::NSApp();
-        app.setMainMenu_(new_menu("", MENU, app));
+        app.setMainMenu_(new_menu("", MENU, app, keymap));
     }
 }
 
 #[cfg(not(target_os = "macos"))]
-pub fn set_main_menu(_: pal::Wm) {}
+pub fn set_main_menu(_: pal::Wm, _: &Keymap) {}
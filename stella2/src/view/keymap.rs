@@ -0,0 +1,333 @@
+//! User-remappable keyboard shortcuts
+//!
+//! The system and edit actions (e.g., `sys::COPY`) are bound by the static
+//! accelerator table in `global::interpret_event`. The application actions
+//! listed in `BINDABLE_ACTIONS` are bound by the accelerator table of `Keymap`
+//! instead, so that the user can override their key bindings through the
+//! `keybindings` table of the settings file:
+//!
+//! ```toml
+//! [keybindings]
+//! next_channel = "Ctrl+Tab"
+//! # An empty string removes the binding
+//! quick_switcher = ""
+//! ```
+//!
+//! Key combinations use the same notation as `accel_table!`, e.g.,
+//! `"Ctrl+Shift+K"`. The recognized modifiers are `Shift`, `Ctrl`, `Alt`, and
+//! `Super` (the Command key on macOS).
+use std::{collections::BTreeMap, fmt, str::FromStr};
+use tcw3::{
+    pal::{prelude::*, AccelTable, Key, KeyBinding, ModifierState},
+    uicore::ActionId,
+};
+
+use super::global;
+
+/// An application action that can be bound to a key combination.
+pub struct BindableAction {
+    /// The name used in the settings file.
+    pub name: &'static str,
+    /// The human-readable name displayed in the Preferences window.
+    pub title: &'static str,
+    pub action: ActionId,
+    /// The selector of the main menu item on macOS.
+    pub macos_sel: &'static str,
+    /// The default key combination.
+    pub default: &'static str,
+}
+
+/// Prepend the platform's primary modifier key to a key combination.
+#[cfg(target_os = "macos")]
+macro_rules! primary {
+    ($s:literal) => {
+        concat!("Super+", $s)
+    };
+}
+
+#[cfg(not(target_os = "macos"))]
+macro_rules! primary {
+    ($s:literal) => {
+        concat!("Ctrl+", $s)
+    };
+}
+
+/// The default binding of `toggle_sidebar`. On macOS, this follows the
+/// convention of the system applications.
+#[cfg(target_os = "macos")]
+const TOGGLE_SIDEBAR_DEFAULT: &str = "Ctrl+Super+s";
+#[cfg(not(target_os = "macos"))]
+const TOGGLE_SIDEBAR_DEFAULT: &str = "Ctrl+Shift+s";
+
+pub static BINDABLE_ACTIONS: &[BindableAction] = &[
+    BindableAction {
+        name: "next_channel",
+        title: "Next Channel",
+        action: global::NEXT_CHANNEL,
+        macos_sel: "selectNextChannel:",
+        default: "Alt+Down",
+    },
+    BindableAction {
+        name: "prev_channel",
+        title: "Previous Channel",
+        action: global::PREV_CHANNEL,
+        macos_sel: "selectPreviousChannel:",
+        default: "Alt+Up",
+    },
+    BindableAction {
+        name: "quick_switcher",
+        title: "Quick Switcher",
+        action: global::QUICK_SWITCHER,
+        macos_sel: "showQuickSwitcher:",
+        default: primary!("k"),
+    },
+    BindableAction {
+        name: "toggle_sidebar",
+        title: "Toggle Sidebar",
+        action: global::TOGGLE_SIDEBAR,
+        macos_sel: "toggleSidebar:",
+        default: TOGGLE_SIDEBAR_DEFAULT,
+    },
+];
+
+/// A key combination, e.g., <kbd>Ctrl</kbd>+<kbd>K</kbd>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyCombo {
+    pub modifiers: ModifierState,
+    pub key: Key,
+}
+
+impl fmt::Display for KeyCombo {
+    /// Format the key combination using the notation accepted by `FromStr`,
+    /// e.g., `"Ctrl+Shift+K"`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(modifier, name) in &[
+            (ModifierState::CONTROL, "Ctrl"),
+            (ModifierState::ALT, "Alt"),
+            (ModifierState::SHIFT, "Shift"),
+            (ModifierState::SUPER, "Super"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.key {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            // The variant names are what `Key::from_str` accepts
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// The error type returned by `KeyCombo`'s implementation of `FromStr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseKeyComboError;
+
+impl fmt::Display for ParseKeyComboError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("malformed key combination")
+    }
+}
+
+impl std::error::Error for ParseKeyComboError {}
+
+impl FromStr for KeyCombo {
+    type Err = ParseKeyComboError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Split at the last `+` so that `"Ctrl++"` is accepted
+        let (modifier_names, key_name) = match s.rfind('+') {
+            Some(0) | None => ("", s),
+            Some(i) if i + 1 == s.len() => (&s[..i - 1], "+"),
+            Some(i) => (&s[..i], &s[i + 1..]),
+        };
+
+        let mut modifiers = ModifierState::empty();
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name {
+                "Shift" => ModifierState::SHIFT,
+                "Ctrl" => ModifierState::CONTROL,
+                "Alt" => ModifierState::ALT,
+                "Super" => ModifierState::SUPER,
+                _ => return Err(ParseKeyComboError),
+            };
+        }
+
+        let key = key_name.parse().map_err(|_| ParseKeyComboError)?;
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+/// Maps key combinations to the actions in `BINDABLE_ACTIONS`.
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: Vec<(KeyCombo, ActionId)>,
+    accel_table: AccelTable,
+}
+
+impl PartialEq for Keymap {
+    fn eq(&self, other: &Self) -> bool {
+        self.bindings == other.bindings
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new())
+    }
+}
+
+impl Keymap {
+    /// Construct a `Keymap` from the default bindings and `overrides`, which
+    /// maps action names to key combinations.
+    ///
+    /// Invalid entries are reported via `log` and ignored.
+    pub fn new(overrides: &BTreeMap<String, String>) -> Self {
+        for name in overrides.keys() {
+            if !BINDABLE_ACTIONS.iter().any(|a| a.name == name.as_str()) {
+                log::warn!("Unknown action name in the key bindings: {:?}", name);
+            }
+        }
+
+        let bindings: Vec<(KeyCombo, ActionId)> = BINDABLE_ACTIONS
+            .iter()
+            .filter_map(|a| {
+                let combo = overrides.get(a.name).map_or(a.default, String::as_str);
+
+                if combo.is_empty() {
+                    return None;
+                }
+
+                match combo.parse() {
+                    Ok(combo) => Some((combo, a.action)),
+                    Err(e) => {
+                        log::warn!("Invalid key binding for {:?}: {:?}: {}", a.name, combo, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        let accel_table = AccelTable::new_with_key_bindings(
+            bindings
+                .iter()
+                .map(|&(combo, action)| KeyBinding {
+                    action,
+                    modifiers: combo.modifiers,
+                    key: combo.key,
+                })
+                .collect(),
+        );
+
+        Self {
+            bindings,
+            accel_table,
+        }
+    }
+
+    /// Get the accelerator table to be used by
+    /// `WndListener::interpret_event`. Unlike `WndListener::key_down`, the
+    /// table takes precedence over the focused view's key handling, so the
+    /// bindings work while a text field has focus.
+    pub fn accel_table(&self) -> &AccelTable {
+        &self.accel_table
+    }
+
+    /// Get the key combination bound to the specified action.
+    pub fn combo_for_action(&self, action: ActionId) -> Option<KeyCombo> {
+        self.bindings
+            .iter()
+            .find(|&&(_, a)| a == action)
+            .map(|&(combo, _)| combo)
+    }
+
+    /// Describe the bindings of all actions in `BINDABLE_ACTIONS`, one action
+    /// per line. Only macOS displays them in the main menu, so this is shown
+    /// in the Preferences window.
+    pub fn describe(&self) -> String {
+        BINDABLE_ACTIONS
+            .iter()
+            .map(|a| match self.combo_for_action(a.action) {
+                Some(combo) => format!("{}: {}", a.title, combo),
+                None => format!("{}: (none)", a.title),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_combo() {
+        assert_eq!(
+            "Ctrl+Shift+K".parse(),
+            Ok(KeyCombo {
+                modifiers: ModifierState::CONTROL | ModifierState::SHIFT,
+                key: Key::Char('k'),
+            })
+        );
+        assert_eq!(
+            "PageUp".parse(),
+            Ok(KeyCombo {
+                modifiers: ModifierState::empty(),
+                key: Key::PageUp,
+            })
+        );
+        assert_eq!(
+            "Alt++".parse(),
+            Ok(KeyCombo {
+                modifiers: ModifierState::ALT,
+                key: Key::Char('+'),
+            })
+        );
+        assert_eq!("Hyper+K".parse::<KeyCombo>(), Err(ParseKeyComboError));
+        assert_eq!("Ctrl+Foo".parse::<KeyCombo>(), Err(ParseKeyComboError));
+    }
+
+    #[test]
+    fn format_combo() {
+        for &s in &["Ctrl+Shift+K", "PageUp", "Alt++", "Ctrl+Super+S"] {
+            assert_eq!(s.parse::<KeyCombo>().unwrap().to_string(), s);
+        }
+    }
+
+    #[test]
+    fn overrides() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("next_channel".to_owned(), "Ctrl+Tab".to_owned());
+        overrides.insert("prev_channel".to_owned(), "".to_owned());
+
+        let keymap = Keymap::new(&overrides);
+
+        let ctrl_tab = KeyCombo {
+            modifiers: ModifierState::CONTROL,
+            key: Key::Tab,
+        };
+        let alt_down = KeyCombo {
+            modifiers: ModifierState::ALT,
+            key: Key::Down,
+        };
+        let alt_up = KeyCombo {
+            modifiers: ModifierState::ALT,
+            key: Key::Up,
+        };
+
+        assert_eq!(
+            keymap.combo_for_action(global::NEXT_CHANNEL),
+            Some(ctrl_tab)
+        );
+        assert!(!keymap.bindings.iter().any(|&(c, _)| c == alt_down));
+        assert!(!keymap.bindings.iter().any(|&(c, _)| c == alt_up));
+        assert_eq!(keymap.combo_for_action(global::PREV_CHANNEL), None);
+        assert!(keymap.combo_for_action(global::QUICK_SWITCHER).is_some());
+
+        let description = keymap.describe();
+        assert!(description.contains("Next Channel: Ctrl+Tab"));
+        assert!(description.contains("Previous Channel: (none)"));
+    }
+}
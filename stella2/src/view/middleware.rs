@@ -0,0 +1,190 @@
+//! The middleware chain of `AppView::dispatch`
+//!
+//! Every dispatched action passes through the middlewares in order before it
+//! reaches the reducer (`model::AppState::reduce`). A middleware can observe
+//! the action, perform side effects, replace it, or swallow it by not passing
+//! it on.
+use log::trace;
+use std::rc::Rc;
+
+use super::AppView;
+use crate::{config::viewpersistence, model};
+
+/// The owner of a middleware chain, which receives the actions that made it
+/// through the chain.
+pub(super) trait ChainEnd {
+    /// Queue `action` to be applied to the state.
+    fn enqueue(this: &Rc<Self>, action: model::AppAction);
+}
+
+pub(super) trait Middleware<A: ChainEnd = AppView> {
+    /// Handle `action`. Call `next.dispatch` to pass `action` (or another
+    /// action) to the rest of the chain. The action is discarded if
+    /// `next.dispatch` is not called.
+    fn dispatch(&self, app: &Rc<A>, action: model::AppAction, next: Next<'_, A>);
+
+    /// Called after the pending actions are applied to the state.
+    fn state_updated(&self, _app: &Rc<A>, _state: &model::AppState) {}
+
+    /// Called before the application quits. Blocks the current thread until
+    /// any outstanding work is complete.
+    fn flush(&self, _app: &Rc<A>, _state: &model::AppState) {}
+}
+
+/// The rest of the middleware chain.
+pub(super) struct Next<'a, A: ChainEnd = AppView> {
+    app: &'a Rc<A>,
+    chain: &'a [Box<dyn Middleware<A>>],
+}
+
+impl<'a, A: ChainEnd> Next<'a, A> {
+    pub(super) fn new(app: &'a Rc<A>, chain: &'a [Box<dyn Middleware<A>>]) -> Self {
+        Self { app, chain }
+    }
+
+    /// Pass `action` to the next middleware. At the end of the chain, the
+    /// action is queued to be applied to the state.
+    pub(super) fn dispatch(self, action: model::AppAction) {
+        if let Some((first, rest)) = self.chain.split_first() {
+            first.dispatch(self.app, action, Next::new(self.app, rest));
+        } else {
+            A::enqueue(self.app, action);
+        }
+    }
+}
+
+/// Logs every action.
+pub(super) struct LogActions;
+
+impl Middleware for LogActions {
+    fn dispatch(&self, _: &Rc<AppView>, action: model::AppAction, next: Next<'_>) {
+        trace!("Dispatching the action: {:?}", action);
+        next.dispatch(action);
+    }
+}
+
+/// Performs the backend operations requested by actions.
+pub(super) struct PerformSideEffects;
+
+impl Middleware for PerformSideEffects {
+    fn dispatch(&self, app: &Rc<AppView>, action: model::AppAction, next: Next<'_>) {
+        AppView::perform_side_effects(app, &action);
+        next.dispatch(action);
+    }
+}
+
+/// Persists the app state and the window geometry to the user profile.
+pub(super) struct PersistState {
+    sched: viewpersistence::PersistenceScheduler,
+}
+
+impl PersistState {
+    /// Construct a `PersistState` with an initial app state, which it will
+    /// *not* persist to disk.
+    pub(super) fn new(state: &model::AppState) -> Self {
+        Self {
+            sched: viewpersistence::PersistenceScheduler::new(state),
+        }
+    }
+}
+
+impl Middleware for PersistState {
+    fn dispatch(&self, _: &Rc<AppView>, action: model::AppAction, next: Next<'_>) {
+        next.dispatch(action);
+    }
+
+    fn state_updated(&self, app: &Rc<AppView>, state: &model::AppState) {
+        self.sched.handle_update(app.wm, state, app.profile);
+    }
+
+    fn flush(&self, app: &Rc<AppView>, state: &model::AppState) {
+        self.sched.flush(app.wm, state, app.profile);
+
+        let mut session = app.session.borrow_mut();
+        app.main_wnd.save_session(&mut session);
+        viewpersistence::save_session(app.profile, &session);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Records the order in which the middlewares and the end of the chain
+    /// receive actions.
+    #[derive(Default)]
+    struct TestApp {
+        log: RefCell<Vec<String>>,
+    }
+
+    impl ChainEnd for TestApp {
+        fn enqueue(this: &Rc<Self>, action: model::AppAction) {
+            this.log.borrow_mut().push(format!("enqueue {:?}", action));
+        }
+    }
+
+    struct Tag(&'static str);
+
+    impl Middleware<TestApp> for Tag {
+        fn dispatch(&self, app: &Rc<TestApp>, action: model::AppAction, next: Next<'_, TestApp>) {
+            app.log.borrow_mut().push(self.0.to_owned());
+            next.dispatch(action);
+        }
+    }
+
+    /// Replaces `TogglePref` with `ToggleSidebar` and swallows `ToggleSidebar`.
+    struct Filter;
+
+    impl Middleware<TestApp> for Filter {
+        fn dispatch(&self, _: &Rc<TestApp>, action: model::AppAction, next: Next<'_, TestApp>) {
+            match action {
+                model::AppAction::TogglePref => {
+                    next.dispatch(model::AppAction::Wnd(model::WndAction::ToggleSidebar))
+                }
+                model::AppAction::Wnd(model::WndAction::ToggleSidebar) => {}
+                action => next.dispatch(action),
+            }
+        }
+    }
+
+    fn dispatch(chain: &[Box<dyn Middleware<TestApp>>], action: model::AppAction) -> Vec<String> {
+        let app = Rc::new(TestApp::default());
+        Next::new(&app, chain).dispatch(action);
+        app.log.replace(Vec::new())
+    }
+
+    #[test]
+    fn chain_order() {
+        let chain: Vec<Box<dyn Middleware<TestApp>>> = vec![
+            Box::new(Tag("first")),
+            Box::new(Filter),
+            Box::new(Tag("last")),
+        ];
+
+        // Every middleware sees the action in order
+        let action = model::AppAction::Wnd(model::WndAction::SetDraft("hi".to_owned()));
+        assert_eq!(
+            dispatch(&chain, action),
+            ["first", "last", "enqueue Wnd(SetDraft(\"hi\"))"]
+        );
+
+        // The rest of the chain receives the replaced action
+        assert_eq!(
+            dispatch(&chain, model::AppAction::TogglePref),
+            ["first", "last", "enqueue Wnd(ToggleSidebar)"]
+        );
+
+        // The rest of the chain doesn't see a swallowed action
+        let action = model::AppAction::Wnd(model::WndAction::ToggleSidebar);
+        assert_eq!(dispatch(&chain, action), ["first"]);
+    }
+
+    #[test]
+    fn empty_chain() {
+        assert_eq!(
+            dispatch(&[], model::AppAction::TogglePref),
+            ["enqueue TogglePref"]
+        );
+    }
+}
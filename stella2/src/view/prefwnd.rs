@@ -5,11 +5,11 @@ use std::{
 
 use tcw3::{
     pal,
-    ui::{layouts::FillLayout, theming},
+    ui::{layouts::FillLayout, theming, Sub},
     uicore::{ActionId, HWnd, HWndRef, WndListener, WndStyleFlags},
 };
 
-use crate::{model, stylesheet, view::global};
+use crate::{config::settings::SettingsStore, model, stylesheet, view::global};
 
 // TODO: Most of these are copypasta of `WndView`, which hopefully we should
 //       refactor.
@@ -17,16 +17,18 @@ pub(super) struct PrefWndView {
     hwnd: HWnd,
     dispatch: RefCell<Box<dyn Fn(model::AppAction)>>,
     pref_view: PrefView,
+    settings_sub: Sub,
 }
 
 impl PrefWndView {
-    pub(super) fn new(wm: pal::Wm) -> Rc<Self> {
+    pub(super) fn new(wm: pal::Wm, settings: Rc<SettingsStore>) -> Rc<Self> {
         let hwnd = HWnd::new(wm);
         let style_manager = theming::Manager::global(wm);
 
         let pref_view = PrefViewBuilder::new()
             .with_wm(wm)
             .with_style_manager(style_manager)
+            .with_settings(settings.get())
             .build();
 
         hwnd.content_view()
@@ -36,10 +38,20 @@ impl PrefWndView {
         Self::update_wnd_style_flags(hwnd.as_ref(), false);
         hwnd.set_visibility(true);
 
+        // Reflect changes made elsewhere (including the ones made by this
+        // window) to the view
+        let pref_view_weak = pref_view.downgrade();
+        let settings_sub = settings.subscribe_changed(Box::new(move |_, new_settings| {
+            if let Some(pref_view) = pref_view_weak.upgrade() {
+                pref_view.set_settings(new_settings.clone());
+            }
+        }));
+
         let this = Rc::new(Self {
             hwnd,
             dispatch: RefCell::new(Box::new(|_| {})),
             pref_view,
+            settings_sub,
         });
 
         // Event handlers
@@ -54,6 +66,9 @@ impl PrefWndView {
             }
         }));
 
+        this.pref_view
+            .subscribe_change_settings(Box::new(move |new_settings| settings.set(new_settings)));
+
         let this_weak = Rc::downgrade(&this);
         this.pref_view.subscribe_close(Box::new(move || {
             if let Some(this) = this_weak.upgrade() {
@@ -96,6 +111,14 @@ impl PrefWndView {
     }
 }
 
+impl Drop for PrefWndView {
+    fn drop(&mut self) {
+        std::mem::take(&mut self.settings_sub)
+            .unsubscribe()
+            .unwrap();
+    }
+}
+
 struct PrefWndViewWndListener {
     owner: Weak<PrefWndView>,
 }
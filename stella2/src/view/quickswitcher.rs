@@ -0,0 +1,475 @@
+use cggeom::{box2, prelude::*};
+use cgmath::{Point2, Vector2};
+use chrono::NaiveDateTime;
+use harmony::Elem;
+use std::collections::BTreeMap;
+use tcw3::{
+    pal,
+    ui::{
+        layouts::TableLayout,
+        theming,
+        views::{Label, LineBreakMode},
+        AlignFlags,
+    },
+    uicore::{
+        ActionId, ActionStatus, HView, HViewRef, InterpretEventCtx, Layout, LayoutCtx,
+        MouseDragListener, SizeTraits, ViewListener,
+    },
+};
+
+use crate::{
+    fuzzy, model,
+    stylesheet::{elem_id, my_roles},
+    view::global,
+};
+
+stella2_meta::designer_impl! {
+    crate::view::quickswitcher::QuickSwitcherView
+}
+
+/// The maximum number of search results displayed at once.
+const RESULT_COUNT_MAX: usize = 8;
+
+/// The width of the panel.
+const PANEL_WIDTH: f32 = 400.0;
+
+/// The distance between the panel and the top edge of the window. It's
+/// chosen so that the panel doesn't cover the window's title bar.
+const PANEL_TOP_MARGIN: f32 = 60.0;
+
+/// The minimum distance between the panel and the left and right edges of the
+/// window.
+const PANEL_SIDE_MARGIN: f32 = 20.0;
+
+/// A channel or a user found by the quick switcher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// The channel to switch to when this result is chosen.
+    pub channel_id: String,
+    /// The channel name or the user's nickname.
+    pub title: String,
+    /// The name of the channel group, or for a user, the channel where they
+    /// were last seen.
+    pub detail: String,
+}
+
+/// Bind the navigation keys to the quick switcher's actions. This should be
+/// called while the quick switcher is open, before the other accelerator
+/// tables so that the bindings take precedence over the text input actions.
+pub fn interpret_event(ctx: &mut InterpretEventCtx<'_>) {
+    ctx.use_accel(&tcw3::pal::accel_table![
+        (
+            global::QUICK_SWITCHER_PREV,
+            windows("Up"),
+            gtk("Up"),
+            macos("Up")
+        ),
+        (
+            global::QUICK_SWITCHER_NEXT,
+            windows("Down"),
+            gtk("Down"),
+            macos("Down")
+        ),
+        (
+            global::QUICK_SWITCHER_ACCEPT,
+            windows("Return"),
+            gtk("Return"),
+            macos("Return")
+        ),
+        (
+            global::QUICK_SWITCHER_DISMISS,
+            windows("Escape"),
+            gtk("Escape"),
+            macos("Escape")
+        ),
+    ]);
+}
+
+impl QuickSwitcherView {
+    fn init(&self) {
+        self.view().set_listener(QuickSwitcherViewListener {
+            owner: self.downgrade(),
+        });
+
+        self.update_results();
+    }
+
+    /// Move the keyboard focus to the text field.
+    pub fn focus(&self) {
+        self.entry().view().focus();
+    }
+
+    /// Search the channels and users for the text in the text field and
+    /// update the result list.
+    fn update_results(&self) {
+        let new_results = search(
+            &self.channel_groups(),
+            &self.histories(),
+            &self.entry().text(),
+        );
+
+        let rows: Vec<theming::StyledBox> = new_results
+            .iter()
+            .map(|result| self.new_row(result))
+            .collect();
+
+        let placeholder = if new_results.is_empty() {
+            Some(self.placeholder().view())
+        } else {
+            None
+        };
+
+        self.result_list().set_layout(TableLayout::stack_vert(
+            rows.iter()
+                .map(|row| row.view())
+                .chain(placeholder)
+                .map(|view| (view, AlignFlags::JUSTIFY)),
+        ));
+
+        // Detach the old rows from the styling element tree
+        for row in self.rows().replace(rows) {
+            self.elem().remove_child(row.style_elem());
+        }
+
+        *self.results().borrow_mut() = new_results;
+
+        self.set_selected_index(0);
+    }
+
+    fn new_row(&self, result: &SearchResult) -> theming::StyledBox {
+        let style_manager = self.style_manager();
+
+        let name_label = Label::new(style_manager);
+        name_label.set_line_break_mode(LineBreakMode::TruncateTail);
+        name_label.set_text(result.title.as_str());
+
+        let detail_label = Label::new(style_manager);
+        detail_label.set_class_set(elem_id::QUICK_SWITCHER_ITEM_DETAIL);
+        detail_label.set_line_break_mode(LineBreakMode::TruncateTail);
+        detail_label.set_text(result.detail.as_str());
+
+        let row = theming::StyledBox::new(style_manager, Default::default());
+        row.set_class_set(elem_id::QUICK_SWITCHER_ITEM);
+        row.set_child(theming::roles::GENERIC, Some(&name_label));
+        row.set_child(my_roles::DETAIL, Some(&detail_label));
+
+        self.elem().insert_child(row.style_elem());
+
+        row
+    }
+
+    /// Highlight the specified search result.
+    fn set_selected_index(&self, index: usize) {
+        self.selected_index().set(index);
+
+        for (i, row) in self.rows().borrow().iter().enumerate() {
+            row.set_class_set(if i == index {
+                elem_id::QUICK_SWITCHER_ITEM | theming::ClassSet::ACTIVE
+            } else {
+                elem_id::QUICK_SWITCHER_ITEM
+            });
+        }
+    }
+
+    /// Move the highlight by one row, wrapping around at the ends.
+    fn move_selection(&self, forward: bool) {
+        let len = self.results().borrow().len();
+        if len == 0 {
+            return;
+        }
+
+        let i = self.selected_index().get();
+        self.set_selected_index(if forward {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        });
+    }
+
+    /// Choose the highlighted search result.
+    fn accept(&self) {
+        let channel = self
+            .results()
+            .borrow()
+            .get(self.selected_index().get())
+            .map(|result| result.channel_id.clone());
+
+        if let Some(channel) = channel {
+            self.raise_select_channel(channel);
+        }
+    }
+
+    /// Handle a mouse click at `loc` (in window coordinates).
+    fn handle_click(&self, loc: Point2<f32>) {
+        if !self.root().view_ref().global_frame().contains_point(&loc) {
+            self.raise_dismiss();
+            return;
+        }
+
+        let index = self
+            .rows()
+            .borrow()
+            .iter()
+            .position(|row| row.view_ref().global_frame().contains_point(&loc));
+
+        if let Some(index) = index {
+            self.set_selected_index(index);
+            self.accept();
+        }
+    }
+}
+
+impl theming::Widget for QuickSwitcherView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view().as_ref()
+    }
+
+    fn style_elem(&self) -> Option<theming::HElem> {
+        Some(self.style_elem())
+    }
+}
+
+/// Find the channels and users matching `query` and return them in the order
+/// of relevance.
+fn search(
+    groups: &[model::ChannelGroup],
+    histories: &BTreeMap<String, Elem<model::ChannelHistory>>,
+    query: &str,
+) -> Vec<SearchResult> {
+    let channels = groups.iter().flat_map(|group| {
+        group.channels.iter().filter_map(move |channel| {
+            // Include the group name so that the user can narrow down
+            // the results by the server name, e.g., "gen free"
+            let candidate = format!("{} {}", channel.name, group.name);
+            let score = fuzzy::score(query, &candidate)?;
+
+            Some((
+                score,
+                SearchResult {
+                    channel_id: channel.id.clone(),
+                    title: channel.name.clone(),
+                    detail: group.name.clone(),
+                },
+            ))
+        })
+    });
+
+    let users = known_users(groups, histories)
+        .into_iter()
+        .filter_map(|(author, (_, channel))| {
+            let score = fuzzy::score(query, author)?;
+
+            Some((
+                score,
+                SearchResult {
+                    channel_id: channel.id.clone(),
+                    title: author.to_owned(),
+                    detail: format!("in {}", channel.name),
+                },
+            ))
+        });
+
+    let mut scored: Vec<(i32, SearchResult)> = channels.chain(users).collect();
+
+    // `sort_by_key` is stable, so the channels with the same score stay in the
+    // order they appear in the channel list, followed by the users
+    scored.sort_by_key(|&(score, _)| std::cmp::Reverse(score));
+
+    scored
+        .into_iter()
+        .take(RESULT_COUNT_MAX)
+        .map(|(_, result)| result)
+        .collect()
+}
+
+/// Find the authors of the loaded messages. Each of them is associated with
+/// the channel where they posted most recently, which is where the quick
+/// switcher takes the user to.
+fn known_users<'a>(
+    groups: &'a [model::ChannelGroup],
+    histories: &'a BTreeMap<String, Elem<model::ChannelHistory>>,
+) -> BTreeMap<&'a str, (NaiveDateTime, &'a model::Channel)> {
+    let mut users = BTreeMap::new();
+
+    let channels = groups.iter().flat_map(|group| group.channels.iter());
+    for channel in channels {
+        let messages = histories
+            .get(&channel.id)
+            .map_or(&[][..], |history| &history.messages[..]);
+
+        for message in messages {
+            let entry = users
+                .entry(message.author.as_str())
+                .or_insert((message.timestamp, channel));
+            if message.timestamp > entry.0 {
+                *entry = (message.timestamp, channel);
+            }
+        }
+    }
+
+    users
+}
+
+struct QuickSwitcherViewListener {
+    owner: WeakQuickSwitcherView,
+}
+
+impl ViewListener for QuickSwitcherViewListener {
+    fn validate_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
+        match action {
+            global::QUICK_SWITCHER_PREV
+            | global::QUICK_SWITCHER_NEXT
+            | global::QUICK_SWITCHER_ACCEPT
+            | global::QUICK_SWITCHER_DISMISS => ActionStatus::VALID | ActionStatus::ENABLED,
+            _ => ActionStatus::empty(),
+        }
+    }
+
+    fn perform_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) {
+        let owner = if let Some(owner) = self.owner.upgrade() {
+            owner
+        } else {
+            return;
+        };
+
+        match action {
+            global::QUICK_SWITCHER_PREV => owner.move_selection(false),
+            global::QUICK_SWITCHER_NEXT => owner.move_selection(true),
+            global::QUICK_SWITCHER_ACCEPT => owner.accept(),
+            global::QUICK_SWITCHER_DISMISS => owner.raise_dismiss(),
+            _ => {}
+        }
+    }
+
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        if button == 0 {
+            if let Some(owner) = self.owner.upgrade() {
+                owner.handle_click(loc);
+            }
+        }
+
+        Box::new(())
+    }
+}
+
+/// Places the panel near the top edge of the view.
+#[derive(Debug)]
+pub struct PanelLayout {
+    subviews: [HView; 1],
+}
+
+impl PanelLayout {
+    pub fn new(panel: HView) -> Self {
+        Self { subviews: [panel] }
+    }
+}
+
+impl Layout for PanelLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, _: &LayoutCtx<'_>) -> SizeTraits {
+        // Don't constrain the window size
+        SizeTraits::default()
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let panel = self.subviews[0].as_ref();
+        let st = ctx.subview_size_traits(panel);
+
+        let width = PANEL_WIDTH
+            .min(size.x - PANEL_SIDE_MARGIN * 2.0)
+            .min(st.max.x)
+            .max(st.min.x);
+        let height = st.preferred.y.min(st.max.y).max(st.min.y);
+
+        let x = ((size.x - width) * 0.5).round();
+
+        ctx.set_subview_frame(
+            panel,
+            box2! { top_left: [x, PANEL_TOP_MARGIN], size: [width, height] },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    fn channel_ids(results: &[SearchResult]) -> Vec<&str> {
+        results.iter().map(|r| r.channel_id.as_str()).collect()
+    }
+
+    fn message(id: u64, author: &str, (h, m): (u32, u32)) -> model::Message {
+        model::Message {
+            id,
+            author: author.to_owned(),
+            timestamp: NaiveDate::from_ymd(2020, 6, 1).and_hms(h, m, 0),
+            body: String::new(),
+        }
+    }
+
+    fn history(messages: Vec<model::Message>) -> Elem<model::ChannelHistory> {
+        Elem::new(model::ChannelHistory {
+            messages,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn search_channels() {
+        let groups = crate::backend::mock::channel_groups();
+        let histories = BTreeMap::new();
+
+        let results = search(&groups, &histories, "");
+        assert_eq!(results.len(), RESULT_COUNT_MAX);
+        assert_eq!(results[0].channel_id, "randomserver/#general");
+
+        let results = search(&groups, &histories, "prolang free");
+        assert_eq!(channel_ids(&results), ["freenode/#prolang"]);
+
+        // Matches the other channels too, but `#random` is the closest match
+        let results = search(&groups, &histories, "rnd");
+        assert!(results[..4].iter().all(|r| r.title == "#random"));
+
+        assert!(search(&groups, &histories, "nonexistent").is_empty());
+    }
+
+    #[test]
+    fn search_users() {
+        let groups = crate::backend::mock::channel_groups();
+        let mut histories = BTreeMap::new();
+        histories.insert(
+            "freenode/#prolang".to_owned(),
+            history(vec![message(1, "alice", (9, 0)), message(2, "bob", (9, 5))]),
+        );
+        histories.insert(
+            "freenode/#random".to_owned(),
+            history(vec![message(3, "alice", (10, 0))]),
+        );
+
+        // Users are associated with the channel where they posted most recently
+        let results = search(&groups, &histories, "alice");
+        assert_eq!(
+            results,
+            [SearchResult {
+                channel_id: "freenode/#random".to_owned(),
+                title: "alice".to_owned(),
+                detail: "in #random".to_owned(),
+            }]
+        );
+
+        let results = search(&groups, &histories, "bob");
+        assert_eq!(channel_ids(&results), ["freenode/#prolang"]);
+        assert_eq!(results[0].title, "bob");
+    }
+}
@@ -0,0 +1,143 @@
+//! Action recording and replay for debugging
+//!
+//! The reducer is a pure function, so the state at any point of a session can
+//! be reconstructed from the initial state and the sequence of actions that
+//! reached the reducer. In debug builds, `ActionRecorder` records the actions
+//! and writes them to the profile directory on exit. The recording can be
+//! replayed by `--replay`, which starts the application with the recorded
+//! state and no side effects.
+use harmony::{set_field, Elem};
+use miniserde::{json, Deserialize, Serialize};
+use std::{cell::RefCell, io, path::Path, rc::Rc};
+
+use super::{
+    middleware::{Middleware, Next},
+    AppView,
+};
+use crate::{config::profile::Profile, model};
+
+/// A sequence of actions and the state they were applied to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recording {
+    /// The window state restored from the profile when the recording started.
+    /// The rest of the initial state is `AppState::new()`.
+    pub main_wnd: model::WndState,
+    /// The actions in the order they were applied.
+    pub actions: Vec<model::AppAction>,
+}
+
+impl Recording {
+    /// Load a recording from a file.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        json::from_str(&json)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the recording is malformed"))
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, json::to_string(self))
+    }
+
+    pub(super) fn initial_state(&self) -> Elem<model::AppState> {
+        set_field! {
+            main_wnd: Elem::new(self.main_wnd.clone()),
+            ..Elem::new(model::AppState::new())
+        }
+    }
+
+    /// Reconstruct the state after applying the first `count` actions.
+    #[cfg(test)]
+    fn state_at(&self, count: usize) -> Elem<model::AppState> {
+        self.actions[..count]
+            .iter()
+            .fold(self.initial_state(), model::AppState::reduce)
+    }
+}
+
+/// The file path to store the recording of the current session.
+fn recording_path(profile: &Profile) -> std::path::PathBuf {
+    profile.data_dir().join("actions.json")
+}
+
+/// Records every action that reaches the reducer. This should be placed at
+/// the end of the chain.
+///
+/// The recording is kept in memory for the entire session, so this is only
+/// used in debug builds.
+pub(super) struct ActionRecorder {
+    recording: RefCell<Recording>,
+}
+
+impl ActionRecorder {
+    pub(super) fn new(state: &model::AppState) -> Self {
+        Self {
+            recording: RefCell::new(Recording {
+                main_wnd: (*state.main_wnd).clone(),
+                actions: Vec::new(),
+            }),
+        }
+    }
+}
+
+impl Middleware for ActionRecorder {
+    fn dispatch(&self, _: &Rc<AppView>, action: model::AppAction, next: Next<'_>) {
+        self.recording.borrow_mut().actions.push(action.clone());
+        next.dispatch(action);
+    }
+
+    fn flush(&self, app: &Rc<AppView>, _: &model::AppState) {
+        let recording = self.recording.borrow();
+        let path = recording_path(app.profile);
+
+        log::info!(
+            "Writing the recording of {} actions to {:?}",
+            recording.actions.len(),
+            path
+        );
+
+        if let Err(e) = recording.save(&path) {
+            log::error!("Could not write the recording to {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay() {
+        let state = Elem::new(model::AppState::new());
+        let recording = Recording {
+            main_wnd: (*state.main_wnd).clone(),
+            actions: vec![
+                model::AppAction::SetChannelGroups(crate::backend::mock::channel_groups()),
+                model::AppAction::Wnd(model::WndAction::ToggleSidebar),
+                model::AppAction::Wnd(model::WndAction::SetDraft("hello".to_owned())),
+                model::AppAction::TogglePref,
+            ],
+        };
+
+        // Survives a round trip through JSON
+        let json = json::to_string(&recording);
+        let recording: Recording = json::from_str(&json).unwrap();
+
+        let replayed = recording.state_at(recording.actions.len());
+        assert_eq!(
+            *replayed.channel_groups,
+            crate::backend::mock::channel_groups()
+        );
+        let main_wnd = &replayed.main_wnd;
+        assert!(!main_wnd.sidebar_visible);
+        assert_eq!(
+            main_wnd.drafts.get(&main_wnd.current_channel),
+            Some(&"hello".to_owned())
+        );
+        assert!(replayed.pref_visible);
+
+        // Travel back in time
+        let replayed = recording.state_at(1);
+        assert!(replayed.main_wnd.sidebar_visible);
+        assert!(!replayed.pref_visible);
+    }
+}
@@ -0,0 +1,229 @@
+use cgmath::Point2;
+use std::{ops::Range, rc::Rc};
+use tcw3::{
+    pal,
+    ui::{
+        theming,
+        views::{table, table::LineTy, Label, LineBreakMode},
+    },
+    uicore::{HView, HViewRef, MouseDragListener, ViewListener},
+};
+
+use crate::{
+    model,
+    stylesheet::{elem_id, my_roles},
+};
+
+stella2_meta::designer_impl! {
+    crate::view::searchpane::SearchPaneView
+}
+
+/// The height of each row. A row consists of two lines: the channel name,
+/// author, and time, followed by the message body.
+const ROW_HEIGHT: f64 = 40.0;
+
+impl SearchPaneView {
+    fn init(&self) {
+        // Set up the table model
+        {
+            let mut edit = self.table().table().edit().unwrap();
+            edit.set_model(TableModelQuery {
+                style_manager: self.style_manager(),
+                elem: Rc::clone(self.elem()),
+                rows: Vec::new(),
+            });
+            edit.insert(LineTy::Col, 0..1);
+            edit.set_scroll_pos([0.0, 0.0]);
+        }
+
+        self.view().set_listener(SearchPaneViewListener {
+            owner: self.downgrade(),
+        });
+
+        self.update_rows();
+    }
+
+    /// Update the table to reflect `search` and `channel_groups`.
+    fn update_rows(&self) {
+        let new_rows = rows_from_hits(&self.channel_groups(), &self.search().hits);
+
+        let mut edit = self.table().table().edit().unwrap();
+
+        let (old_len, new_len) = {
+            let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+            if model.rows == new_rows {
+                return;
+            }
+            let new_len = new_rows.len();
+            let old_rows = std::mem::replace(&mut model.rows, new_rows);
+            (old_rows.len(), new_len)
+        };
+
+        // The results of a new query are mostly unrelated to the previous
+        // ones, so just replace all rows
+        edit.remove(LineTy::Row, 0..old_len as u64);
+        edit.insert(LineTy::Row, 0..new_len as u64);
+        edit.set_scroll_pos([0.0, 0.0]);
+    }
+
+    /// Get the channel of the search result displayed at the specified
+    /// vertical position (relative to `view`).
+    fn channel_at(&self, y: f32) -> Option<String> {
+        let mut edit = self.table().table().edit().ok()?;
+        let y = y as f64 + edit.scroll_pos()[1] + edit.display_offset()[1];
+        if y < 0.0 {
+            return None;
+        }
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+        let row = model.rows.get((y / ROW_HEIGHT) as usize)?;
+
+        Some(row.channel.clone())
+    }
+}
+
+impl theming::Widget for SearchPaneView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view().as_ref()
+    }
+
+    fn style_elem(&self) -> Option<theming::HElem> {
+        Some(self.style_elem())
+    }
+}
+
+struct SearchPaneViewListener {
+    owner: WeakSearchPaneView,
+}
+
+impl ViewListener for SearchPaneViewListener {
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        hview: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        // Jump to the channel by the primary mouse button
+        if button != 0 {
+            return Box::new(());
+        }
+
+        if let Some(owner) = self.owner.upgrade() {
+            let y = loc.y - hview.global_frame().min.y;
+
+            if let Some(channel) = owner.channel_at(y) {
+                owner.raise_select_channel(channel);
+            }
+        }
+
+        Box::new(())
+    }
+}
+
+/// A row of the search result list.
+#[derive(Debug, Clone, PartialEq)]
+struct Row {
+    channel: String,
+    detail: String,
+    body: String,
+}
+
+fn rows_from_hits(groups: &[model::ChannelGroup], hits: &[model::SearchHit]) -> Vec<Row> {
+    hits.iter()
+        .map(|hit| {
+            let channel_name = groups
+                .iter()
+                .flat_map(|group| group.channels.iter())
+                .find(|channel| channel.id == hit.channel)
+                .map(|channel| channel.name.as_str())
+                .unwrap_or(hit.channel.as_str());
+
+            Row {
+                channel: hit.channel.clone(),
+                detail: format!(
+                    "{} — {}, {}",
+                    channel_name,
+                    hit.message.author,
+                    hit.message.timestamp.format("%Y-%m-%d %H:%M"),
+                ),
+                // Only the first line fits in the row
+                body: hit.message.body.lines().next().unwrap_or("").to_owned(),
+            }
+        })
+        .collect()
+}
+
+struct TableModelQuery {
+    style_manager: &'static theming::Manager,
+    elem: Rc<theming::Elem>,
+    rows: Vec<Row>,
+}
+
+impl table::TableModelQuery for TableModelQuery {
+    fn new_view(&mut self, cell: table::CellIdx) -> (HView, Box<dyn table::CellCtrler>) {
+        let row = &self.rows[cell[1] as usize];
+
+        let body_label = Label::new(self.style_manager);
+        body_label.set_line_break_mode(LineBreakMode::TruncateTail);
+        body_label.set_text(row.body.as_str());
+
+        let detail_label = Label::new(self.style_manager);
+        detail_label.set_class_set(elem_id::SEARCH_PANE_ITEM_DETAIL);
+        detail_label.set_line_break_mode(LineBreakMode::TruncateTail);
+        detail_label.set_text(row.detail.as_str());
+
+        let wrap = theming::StyledBox::new(self.style_manager, Default::default());
+        wrap.set_class_set(elem_id::SEARCH_PANE_ITEM);
+        wrap.set_child(theming::roles::GENERIC, Some(&body_label));
+        wrap.set_child(my_roles::DETAIL, Some(&detail_label));
+
+        self.elem.insert_child(wrap.style_elem());
+
+        (wrap.view(), Box::new((wrap,)))
+    }
+
+    fn range_size(&mut self, line_ty: LineTy, range: Range<u64>, _approx: bool) -> f64 {
+        match line_ty {
+            LineTy::Row => (range.end - range.start) as f64 * ROW_HEIGHT,
+
+            // `TableFlags::GROW_LAST_COL` expands the column to cover the region.
+            // The column needs some width for this flag to work.
+            LineTy::Col => (range.end - range.start) as f64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn rows() {
+        let groups = crate::backend::mock::channel_groups();
+        let channel = &groups[0].channels[0];
+
+        let hit = |channel: &str| model::SearchHit {
+            channel: channel.to_owned(),
+            message: model::Message {
+                id: 0,
+                author: "alice".to_owned(),
+                timestamp: NaiveDate::from_ymd(2020, 6, 1).and_hms(12, 34, 0),
+                body: "first line\nsecond line".to_owned(),
+            },
+        };
+
+        let rows = rows_from_hits(&groups, &[hit(&channel.id), hit("unknown/#left")]);
+
+        assert_eq!(rows[0].channel, channel.id);
+        assert_eq!(
+            rows[0].detail,
+            format!("{} — alice, 2020-06-01 12:34", channel.name)
+        );
+        assert_eq!(rows[0].body, "first line");
+
+        // Falls back to the ID if the channel isn't in the channel list anymore
+        assert!(rows[1].detail.starts_with("unknown/#left — "));
+    }
+}
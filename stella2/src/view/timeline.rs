@@ -0,0 +1,905 @@
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{Matrix3, Vector2};
+use chrono::NaiveDate;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ops::Range,
+    rc::Rc,
+};
+use tcw3::{
+    pal,
+    prelude::*,
+    ui::{
+        prelude::*,
+        views::{table, table::LineTy},
+    },
+    uicore::{HView, HViewRef, HWndRef, Layout, LayoutCtx, SizeTraits, UpdateCtx, ViewListener},
+};
+
+use crate::model::Message;
+
+stella2_meta::designer_impl! {
+    crate::view::timeline::MessageTimelineView
+}
+
+const GUTTER_WIDTH: f32 = 100.0;
+
+/// Consecutive messages from the same author are grouped together if they
+/// were posted within this number of seconds.
+const GROUPING_INTERVAL_SECS: i64 = 5 * 60;
+
+/// `history_requested` is raised when the viewport comes within this distance
+/// from the top of the timeline.
+const HISTORY_REQUEST_MARGIN: f64 = 50.0;
+
+/// The horizontal margin of a row's contents.
+const ROW_H_MARGIN: f32 = 10.0;
+
+/// The space reserved for the vertical scrollbar on the right side of a row.
+const ROW_SCROLLBAR_MARGIN: f32 = 12.0;
+
+impl MessageTimelineView {
+    fn init(&self) {
+        {
+            let mut edit = self.table().table().edit().unwrap();
+            edit.set_model(TableModelQuery {
+                width: 100.0,
+                dpi_scale: 1.0,
+                messages: Vec::new(),
+                first_unread: None,
+                rows: Vec::new(),
+                row_heights: Vec::new(),
+            });
+            edit.insert(LineTy::Col, 0..1);
+        }
+
+        self.update_history();
+    }
+
+    /// Update the displayed messages from `history`.
+    ///
+    /// The displayed portion of the timeline does not move unless the
+    /// timeline was scrolled to the bottom, in which case the timeline is
+    /// scrolled to display new messages. If the messages are replaced
+    /// entirely (e.g., when switching channels), the timeline is scrolled to
+    /// the latest message.
+    fn update_history(&self) {
+        let history = self.history();
+
+        let at_bottom = {
+            let edit = self.table().table().edit().unwrap();
+            edit.scroll_pos()[1] >= edit.scroll_limit()[1] - 1.0
+        };
+        let mut replaced = true;
+
+        self.update_model(|model| {
+            // Reuse the existing `Rc`s so that the rows of the unchanged
+            // messages are preserved
+            let old_messages: HashMap<u64, Rc<Message>> = model
+                .messages
+                .drain(..)
+                .map(|message| (message.id, message))
+                .collect();
+
+            model.messages = history
+                .messages
+                .iter()
+                .map(|message| match old_messages.get(&message.id) {
+                    Some(old) if **old == *message => {
+                        replaced = false;
+                        Rc::clone(old)
+                    }
+                    _ => Rc::new(message.clone()),
+                })
+                .collect();
+
+            model.first_unread = history.first_unread;
+        });
+
+        if replaced || at_bottom {
+            // Scroll to the latest message
+            let mut edit = self.table().table().edit().unwrap();
+            let scroll_limit = edit.scroll_limit();
+            edit.set_scroll_pos([0.0, scroll_limit[1]]);
+        }
+    }
+
+    /// Update the model using `f` and update the rows accordingly.
+    ///
+    /// Only the changed rows are removed and inserted, so that the table
+    /// can keep the viewport anchored to the existing rows.
+    fn update_model(&self, f: impl FnOnce(&mut TableModelQuery)) {
+        let mut edit = self.table().table().edit().unwrap();
+
+        let (old_range, new_range, new_rows, new_heights) = {
+            let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+            f(model);
+
+            let new_rows = build_rows(&model.messages, model.first_unread);
+            let old_rows = &model.rows;
+
+            let prefix_len = old_rows
+                .iter()
+                .zip(new_rows.iter())
+                .take_while(|(old, new)| old == new)
+                .count();
+            let suffix_len = old_rows[prefix_len..]
+                .iter()
+                .rev()
+                .zip(new_rows[prefix_len..].iter().rev())
+                .take_while(|(old, new)| old == new)
+                .count();
+
+            let old_range = prefix_len..old_rows.len() - suffix_len;
+            let new_range = prefix_len..new_rows.len() - suffix_len;
+
+            // The rows are rendered when they are materialized by `new_view`
+            let new_heights: Vec<_> = new_rows[new_range.clone()]
+                .iter()
+                .map(|row| RowLayout::new(row, model.width).height)
+                .collect();
+
+            (old_range, new_range, new_rows, new_heights)
+        };
+
+        if old_range.start < old_range.end {
+            edit.remove(LineTy::Row, old_range.start as u64..old_range.end as u64);
+        }
+
+        {
+            let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+            model.row_heights.splice(old_range, new_heights);
+            model.rows = new_rows;
+        }
+
+        if new_range.start < new_range.end {
+            edit.insert(LineTy::Row, new_range.start as u64..new_range.end as u64);
+        }
+    }
+
+    /// Update the row heights and re-render the materialized rows after the
+    /// width or the DPI scale changes.
+    fn update_row_heights(&self) {
+        let dpi_scale = self.table().view().containing_wnd().unwrap().dpi_scale();
+
+        let mut edit = self.table().table().edit().unwrap();
+        let width = self.table().view().frame().size().x;
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
+        let width_changed = (width - model.width).abs() >= 0.1;
+        if !width_changed && dpi_scale == model.dpi_scale {
+            return;
+        }
+
+        model.width = width;
+        model.dpi_scale = dpi_scale;
+
+        let num_rows = model.rows.len() as u64;
+
+        // The row heights don't depend on the DPI scale
+        if width_changed {
+            model.row_heights = model
+                .rows
+                .iter()
+                .map(|row| RowLayout::new(row, width).height)
+                .collect();
+
+            edit.resize(LineTy::Row, 0..num_rows);
+        }
+
+        edit.renew_subviews(LineTy::Row, 0..num_rows);
+    }
+
+    fn handle_model_update(&self) {
+        let mut edit = self.table().table().edit().unwrap();
+        let scroll_y = edit.scroll_pos()[1] + edit.display_offset()[1];
+
+        let model: &mut TableModelQuery = edit.model_downcast_mut().unwrap();
+
+        // Find the row at the top of the viewport
+        let mut y = 0.0;
+        let top_row_i = model.row_heights.iter().position(|&height| {
+            y += height as f64;
+            y > scroll_y
+        });
+
+        // Display the day separator of the topmost row unless the separator
+        // itself is visible
+        let sticky_date = top_row_i.and_then(|i| match model.rows[i] {
+            Row::Date(_) => None,
+            _ => model.rows[..i].iter().rev().find_map(|row| match row {
+                Row::Date(date) => Some(*date),
+                _ => None,
+            }),
+        });
+
+        let near_top = scroll_y < HISTORY_REQUEST_MARGIN && !model.rows.is_empty();
+        let (width, dpi_scale) = (model.width, model.dpi_scale);
+
+        drop(edit);
+
+        self.sticky_date().set_date(sticky_date, width, dpi_scale);
+
+        if near_top != self.near_top().get() {
+            self.near_top().set(near_top);
+
+            if near_top {
+                // We might be inside a layout pass. Raise the event later so
+                // that the handler can update `history`.
+                let this = self.downgrade();
+                self.wm().invoke(move |_| {
+                    if let Some(this) = this.upgrade() {
+                        this.raise_history_requested();
+                    }
+                });
+            }
+        }
+    }
+}
+
+struct TableModelQuery {
+    /// The heights of `rows`. The rows are rendered only when they are
+    /// materialized.
+    row_heights: Vec<f32>,
+    width: f32,
+    dpi_scale: f32,
+    messages: Vec<Rc<Message>>,
+    first_unread: Option<u64>,
+    rows: Vec<Row>,
+}
+
+impl table::TableModelQuery for TableModelQuery {
+    fn new_view(&mut self, cell: table::CellIdx) -> (HView, Box<dyn table::CellCtrler>) {
+        let row = &self.rows[cell[1] as usize];
+        let visual = RowVisual::from_row(row, self.width, self.dpi_scale, false);
+
+        let hview = HView::new(Default::default());
+        hview.set_listener(RowViewListener::new(visual));
+        (hview, Box::new(()))
+    }
+
+    fn range_size(&mut self, line_ty: LineTy, range: Range<u64>, _approx: bool) -> f64 {
+        match line_ty {
+            LineTy::Row => self.row_heights[range.start as usize..range.end as usize]
+                .iter()
+                .map(|&height| height as f64)
+                .sum(),
+
+            // `TableFlags::GROW_LAST_COL` expands the column to cover the region.
+            // The column needs some width for this flag to work.
+            LineTy::Col => (range.end - range.start) as f64,
+        }
+    }
+}
+
+struct RowViewListener {
+    layer: Cell<Option<pal::HLayer>>,
+    row_visual: RowVisual,
+}
+
+impl RowViewListener {
+    fn new(row_visual: RowVisual) -> Self {
+        Self {
+            layer: Cell::new(None),
+            row_visual,
+        }
+    }
+}
+
+impl ViewListener for RowViewListener {
+    fn mount(&self, wm: pal::Wm, hview: HViewRef<'_>, _: HWndRef<'_>) {
+        self.layer.set(Some(wm.new_layer(pal::LayerAttrs {
+            contents: Some(Some(self.row_visual.bmp.clone().into())),
+            ..Default::default()
+        })));
+
+        hview.pend_update();
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if let Some(hlayer) = self.layer.take() {
+            wm.remove_layer(&hlayer);
+        }
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let layer = self.layer.take().unwrap();
+
+        let view_frame = view.global_frame();
+
+        wm.set_layer_attr(
+            &layer,
+            pal::LayerAttrs {
+                bounds: Some(
+                    self.row_visual
+                        .bmp_bounds
+                        .translate(view_frame.min - cgmath::Point2::new(0.0, 0.0)),
+                ),
+                ..Default::default()
+            },
+        );
+
+        if ctx.layers().len() != 1 {
+            ctx.set_layers(vec![layer.clone()]);
+        }
+
+        self.layer.set(Some(layer));
+    }
+}
+
+/// The day separator displayed at the top of `MessageTimelineView`.
+///
+/// It shows the date of the topmost visible message so that the date is
+/// always visible even if the actual day separator is scrolled out.
+struct StickyDate {
+    view: HView,
+    shared: Rc<StickyDateShared>,
+}
+
+#[derive(Default)]
+struct StickyDateShared {
+    /// The parameters used to create `visual`.
+    key: Cell<Option<(NaiveDate, f32, f32)>>,
+    visual: RefCell<Option<RowVisual>>,
+}
+
+impl StickyDate {
+    fn new() -> Self {
+        let shared = Rc::new(StickyDateShared::default());
+
+        let view = HView::new(Default::default());
+        view.set_listener(StickyDateListener {
+            layer: Cell::new(None),
+            shared: Rc::clone(&shared),
+        });
+
+        Self { view, shared }
+    }
+
+    fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    /// Set the displayed date. `None` hides the separator.
+    fn set_date(&self, date: Option<NaiveDate>, row_width: f32, dpi_scale: f32) {
+        let key = date.map(|date| (date, row_width, dpi_scale));
+        if self.shared.key.get() == key {
+            return;
+        }
+
+        self.shared.key.set(key);
+        self.shared.visual.replace(
+            date.map(|date| RowVisual::from_row(&Row::Date(date), row_width, dpi_scale, true)),
+        );
+        self.view.pend_update();
+    }
+}
+
+struct StickyDateListener {
+    layer: Cell<Option<pal::HLayer>>,
+    shared: Rc<StickyDateShared>,
+}
+
+impl ViewListener for StickyDateListener {
+    fn mount(&self, wm: pal::Wm, hview: HViewRef<'_>, _: HWndRef<'_>) {
+        self.layer
+            .set(Some(wm.new_layer(pal::LayerAttrs::default())));
+
+        hview.pend_update();
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if let Some(hlayer) = self.layer.take() {
+            wm.remove_layer(&hlayer);
+        }
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let layer = self.layer.take().unwrap();
+
+        if let Some(visual) = &*self.shared.visual.borrow() {
+            let view_frame = view.global_frame();
+
+            wm.set_layer_attr(
+                &layer,
+                pal::LayerAttrs {
+                    contents: Some(Some(visual.bmp.clone().into())),
+                    bounds: Some(
+                        visual
+                            .bmp_bounds
+                            .translate(view_frame.min - cgmath::Point2::new(0.0, 0.0)),
+                    ),
+                    ..Default::default()
+                },
+            );
+
+            if ctx.layers().len() != 1 {
+                ctx.set_layers(vec![layer.clone()]);
+            }
+        } else if !ctx.layers().is_empty() {
+            ctx.set_layers(Vec::new());
+        }
+
+        self.layer.set(Some(layer));
+    }
+}
+
+/// A `Layout` that places the second subview over the first one. Both subviews
+/// occupy the whole frame, and the size traits are derived from the first one.
+struct OverlayLayout {
+    subviews: [HView; 2],
+}
+
+impl OverlayLayout {
+    fn new(base: HView, overlay: HView) -> Self {
+        Self {
+            subviews: [base, overlay],
+        }
+    }
+}
+
+impl Layout for OverlayLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        ctx.subview_size_traits(self.subviews[0].as_ref())
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let frame = box2! { min: [0.0, 0.0], max: [size.x, size.y] };
+        for subview in self.subviews.iter() {
+            ctx.set_subview_frame(subview.as_ref(), frame);
+        }
+    }
+}
+
+/// A row of the timeline.
+#[derive(Clone)]
+enum Row {
+    /// A day separator.
+    Date(NaiveDate),
+    /// Indicates the following messages are unread.
+    UnreadMarker,
+    /// A message. `continued` indicates the message is grouped with the
+    /// previous one, in which case the author is not displayed.
+    Message {
+        message: Rc<Message>,
+        continued: bool,
+    },
+}
+
+impl PartialEq for Row {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Row::Date(x), Row::Date(y)) => x == y,
+            (Row::UnreadMarker, Row::UnreadMarker) => true,
+            (
+                Row::Message {
+                    message: m1,
+                    continued: c1,
+                },
+                Row::Message {
+                    message: m2,
+                    continued: c2,
+                },
+            ) => Rc::ptr_eq(m1, m2) && c1 == c2,
+            _ => false,
+        }
+    }
+}
+
+/// Convert a list of messages (sorted by time) into rows.
+fn build_rows(messages: &[Rc<Message>], first_unread: Option<u64>) -> Vec<Row> {
+    let mut rows = Vec::with_capacity(messages.len() + 1);
+    let mut last: Option<&Message> = None;
+
+    for message in messages.iter() {
+        let date = message.timestamp.date();
+        let new_day = last.map_or(true, |last| last.timestamp.date() != date);
+        let unread = first_unread == Some(message.id);
+
+        if new_day {
+            rows.push(Row::Date(date));
+        }
+        if unread {
+            rows.push(Row::UnreadMarker);
+        }
+
+        let continued = !new_day
+            && !unread
+            && last.map_or(false, |last| {
+                let interval = (message.timestamp - last.timestamp).num_seconds();
+                last.author == message.author && (0..GROUPING_INTERVAL_SECS).contains(&interval)
+            });
+
+        rows.push(Row::Message {
+            message: Rc::clone(message),
+            continued,
+        });
+
+        last = Some(message);
+    }
+
+    rows
+}
+
+/// The text layouts and the metrics of a row. This is cheaper to compute than
+/// `RowVisual`, so it's used to measure the rows that aren't materialized.
+struct RowLayout<'a> {
+    row: &'a Row,
+    row_width: f32,
+    v_margin: f32,
+    author_layout: Option<pal::TextLayout>,
+    text_layout: pal::TextLayout,
+    author_height: f32,
+    height: f32,
+}
+
+impl<'a> RowLayout<'a> {
+    fn new(row: &'a Row, row_width: f32) -> Self {
+        let v_margin = match row {
+            Row::Message {
+                continued: true, ..
+            } => 1.0,
+            _ => 3.0,
+        };
+        let text_width =
+            (row_width - ROW_H_MARGIN * 2.0 - GUTTER_WIDTH - ROW_SCROLLBAR_MARGIN).max(1.0);
+
+        let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+            ..Default::default()
+        });
+        let author_char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+            sys: Some(pal::SysFontType::Emph),
+            ..Default::default()
+        });
+        let small_char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+            sys: Some(pal::SysFontType::Small),
+            ..Default::default()
+        });
+
+        // The text layouts making up the row
+        let (author_layout, text_layout) = match row {
+            Row::Date(d) => (
+                None,
+                pal::TextLayout::from_text(&d.format("%B %-d, %Y").to_string(), &char_style, None),
+            ),
+            Row::UnreadMarker => (
+                None,
+                pal::TextLayout::from_text("New messages", &small_char_style, None),
+            ),
+            Row::Message { message, continued } => (
+                if *continued {
+                    None
+                } else {
+                    Some(pal::TextLayout::from_text(
+                        &message.author,
+                        &author_char_style,
+                        Some(text_width),
+                    ))
+                },
+                pal::TextLayout::from_text(&message.body, &char_style, Some(text_width)),
+            ),
+        };
+        let author_height = author_layout
+            .as_ref()
+            .map_or(0.0, |layout| layout.layout_bounds().size().y.ceil() + 2.0);
+
+        let height = author_height + text_layout.layout_bounds().size().y.ceil() + v_margin * 2.0;
+
+        Self {
+            row,
+            row_width,
+            v_margin,
+            author_layout,
+            text_layout,
+            author_height,
+            height,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RowVisual {
+    bmp: pal::Bitmap,
+    bmp_bounds: Box2<f32>,
+}
+
+impl RowVisual {
+    /// Render a row. `opaque` fills the background, which is used when the row
+    /// is displayed over other rows.
+    fn from_row(row: &Row, row_width: f32, dpi_scale: f32, opaque: bool) -> Self {
+        Self::from_layout(&RowLayout::new(row, row_width), dpi_scale, opaque)
+    }
+
+    #[allow(clippy::possible_missing_comma)]
+    fn from_layout(layout: &RowLayout<'_>, dpi_scale: f32, opaque: bool) -> Self {
+        let RowLayout {
+            row,
+            row_width,
+            v_margin,
+            ref author_layout,
+            ref text_layout,
+            author_height,
+            height: row_height,
+        } = *layout;
+        let h_margin = ROW_H_MARGIN;
+        let scrollbar_margin = ROW_SCROLLBAR_MARGIN;
+
+        let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+            ..Default::default()
+        });
+        let layout_bounds = text_layout.layout_bounds();
+
+        let bmp_size = [
+            (row_width * dpi_scale).ceil() as u32,
+            (row_height * dpi_scale).ceil() as u32,
+        ];
+        let bmp_bounds = box2! {
+            min: [0.0, 0.0],
+            max: [bmp_size[0] as f32 / dpi_scale, bmp_size[1] as f32 / dpi_scale],
+        };
+
+        let bmp = {
+            let mut builder = pal::BitmapBuilder::new(bmp_size);
+
+            // Apply DPI scaling
+            builder.mult_transform(Matrix3::from_scale_2d(dpi_scale));
+
+            if opaque {
+                builder.set_fill_rgb([1.0, 1.0, 1.0, 1.0].into());
+                builder.fill_rect(box2! {
+                    min: [0.0, 0.0],
+                    max: [row_width, row_height],
+                });
+            }
+
+            builder.set_fill_rgb([0.5, 0.5, 0.5, 0.05].into());
+            builder.fill_rect(box2! {
+                min: [0.0, 0.0],
+                max: [GUTTER_WIDTH, row_height],
+            });
+
+            match row {
+                Row::Date(_) => {
+                    let y = row_height / 2.0;
+                    let text_x_min =
+                        (row_width - GUTTER_WIDTH - layout_bounds.size().x) / 2.0 + GUTTER_WIDTH;
+                    let text_x_max = text_x_min + layout_bounds.size().x;
+
+                    builder.begin_path();
+                    builder.move_to([GUTTER_WIDTH, y].into());
+                    builder.line_to([text_x_min - 8.0, y].into());
+                    builder.move_to([row_width, y].into());
+                    builder.line_to([text_x_max + 8.0, y].into());
+                    builder.set_stroke_rgb([0.0, 0.0, 0.0, 0.2].into());
+                    builder.stroke();
+
+                    builder.draw_text(
+                        text_layout,
+                        [text_x_min, v_margin - layout_bounds.min.y].into(),
+                        pal::RGBAF32::new(0.0, 0.0, 0.0, 1.0),
+                    );
+                }
+                Row::UnreadMarker => {
+                    let y = row_height / 2.0;
+                    let text_x_max = row_width - scrollbar_margin - h_margin;
+                    let text_x_min = text_x_max - layout_bounds.size().x;
+                    let color = pal::RGBAF32::new(0.9, 0.3, 0.2, 1.0);
+
+                    builder.begin_path();
+                    builder.move_to([GUTTER_WIDTH, y].into());
+                    builder.line_to([text_x_min - 8.0, y].into());
+                    builder.set_stroke_rgb(color);
+                    builder.stroke();
+
+                    builder.draw_text(
+                        text_layout,
+                        [text_x_min, v_margin - layout_bounds.min.y].into(),
+                        color,
+                    );
+                }
+                Row::Message { message, .. } => {
+                    let text_x = h_margin + GUTTER_WIDTH;
+                    let y = v_margin;
+
+                    if let Some(author_layout) = author_layout {
+                        builder.draw_text(
+                            author_layout,
+                            [text_x, y - author_layout.layout_bounds().min.y].into(),
+                            pal::RGBAF32::new(0.0, 0.0, 0.0, 1.0),
+                        );
+                    }
+
+                    builder.draw_text(
+                        text_layout,
+                        [text_x, y + author_height - layout_bounds.min.y].into(),
+                        pal::RGBAF32::new(0.0, 0.0, 0.0, 1.0),
+                    );
+
+                    // Only the first message of a group has an avatar
+                    let avatar_size = 16.0;
+                    if author_layout.is_some() {
+                        builder.set_fill_rgb(avatar_color(&message.author));
+                        builder.begin_path();
+                        builder.rounded_rect(
+                            box2! {
+                                top_right: [GUTTER_WIDTH - 6.0, y],
+                                size: [avatar_size, avatar_size],
+                            },
+                            [[2.0; 2]; 4],
+                        );
+                        builder.fill();
+                    }
+
+                    // Time
+                    let time = message.timestamp.format("%H:%M").to_string();
+                    let time_text_layout = pal::TextLayout::from_text(&time, &char_style, None);
+                    builder.draw_text(
+                        &time_text_layout,
+                        [
+                            GUTTER_WIDTH
+                                - time_text_layout.layout_bounds().max.x
+                                - 12.0
+                                - avatar_size,
+                            y - time_text_layout.layout_bounds().min.y,
+                        ]
+                        .into(),
+                        pal::RGBAF32::new(0.0, 0.0, 0.0, 0.6),
+                    );
+                }
+            }
+
+            builder.into_bitmap()
+        };
+
+        Self { bmp, bmp_bounds }
+    }
+}
+
+/// Choose an avatar placeholder color based on a user name.
+fn avatar_color(name: &str) -> pal::RGBAF32 {
+    const COLORS: &[[f32; 4]] = &[
+        [0.8, 0.4, 0.3, 1.0],
+        [0.1, 0.6, 0.6, 1.0],
+        [0.5, 0.4, 0.8, 1.0],
+        [0.4, 0.6, 0.2, 1.0],
+    ];
+
+    let hash = name.bytes().fold(0usize, |acc, b| {
+        acc.wrapping_mul(31).wrapping_add(b as usize)
+    });
+
+    COLORS[hash % COLORS.len()].into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn message(id: u64, author: &str, timestamp: &str) -> Rc<Message> {
+        Rc::new(Message {
+            id,
+            author: author.to_owned(),
+            timestamp: NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M").unwrap(),
+            body: String::new(),
+        })
+    }
+
+    /// Describe `rows` in a way that can be compared by `assert_eq!`.
+    fn describe(rows: &[Row]) -> Vec<String> {
+        rows.iter()
+            .map(|row| match row {
+                Row::Date(date) => format!("date {}", date),
+                Row::UnreadMarker => "unread".to_owned(),
+                Row::Message {
+                    message,
+                    continued: false,
+                } => format!("{}", message.id),
+                Row::Message {
+                    message,
+                    continued: true,
+                } => format!("{} (continued)", message.id),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn build_rows_grouping() {
+        let messages = [
+            message(1, "alice", "2020-06-01 10:00"),
+            // Same author, within `GROUPING_INTERVAL_SECS`
+            message(2, "alice", "2020-06-01 10:04"),
+            // Different author
+            message(3, "bob", "2020-06-01 10:05"),
+            // Same author, but too long after the previous one
+            message(4, "bob", "2020-06-01 10:15"),
+            message(5, "bob", "2020-06-01 10:16"),
+        ];
+
+        assert_eq!(
+            describe(&build_rows(&messages, None)),
+            [
+                "date 2020-06-01",
+                "1",
+                "2 (continued)",
+                "3",
+                "4",
+                "5 (continued)",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_rows_day_separators() {
+        let messages = [
+            message(1, "alice", "2020-06-01 23:58"),
+            // Not grouped across a day boundary
+            message(2, "alice", "2020-06-02 00:01"),
+            message(3, "alice", "2020-06-04 09:00"),
+        ];
+
+        assert_eq!(
+            describe(&build_rows(&messages, None)),
+            [
+                "date 2020-06-01",
+                "1",
+                "date 2020-06-02",
+                "2",
+                "date 2020-06-04",
+                "3",
+            ]
+        );
+    }
+
+    #[test]
+    fn build_rows_unread_marker() {
+        let messages = [
+            message(1, "alice", "2020-06-01 10:00"),
+            // Not grouped across the unread marker
+            message(2, "alice", "2020-06-01 10:01"),
+            message(3, "alice", "2020-06-01 10:02"),
+            message(4, "bob", "2020-06-02 10:00"),
+        ];
+
+        assert_eq!(
+            describe(&build_rows(&messages, Some(2))),
+            [
+                "date 2020-06-01",
+                "1",
+                "unread",
+                "2",
+                "3 (continued)",
+                "date 2020-06-02",
+                "4",
+            ]
+        );
+
+        // The marker follows the day separator
+        assert_eq!(
+            describe(&build_rows(&messages, Some(4)))[4..],
+            ["date 2020-06-02", "unread", "4"]
+        );
+
+        // No marker for a message that isn't loaded
+        assert!(!describe(&build_rows(&messages, Some(42))).contains(&"unread".to_owned()));
+    }
+
+    #[test]
+    fn build_rows_empty() {
+        assert!(build_rows(&[], None).is_empty());
+    }
+}
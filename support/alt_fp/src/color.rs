@@ -0,0 +1,66 @@
+//! Packed color operations.
+use packed_simd::{f32x4, shuffle};
+
+/// Convert sRGB-encoded values to linear values.
+///
+/// All lanes are converted. The inputs are expected to be in range `[0, 1]`.
+///
+/// # Examples
+///
+///     # use packed_simd::f32x4;
+///     use alt_fp::srgb_to_linear;
+///
+///     let x = srgb_to_linear(f32x4::new(0.0, 0.02, 0.5, 1.0));
+///     assert_eq!(x.extract(0), 0.0);
+///     assert!((x.extract(1) - 0.02 / 12.92).abs() < 1.0e-6);
+///     assert!((x.extract(2) - 0.21404).abs() < 1.0e-4);
+///     assert!((x.extract(3) - 1.0).abs() < 1.0e-6);
+///
+#[inline]
+pub fn srgb_to_linear(x: f32x4) -> f32x4 {
+    let lo = x * (1.0 / 12.92);
+    let hi = ((x + 0.055) * (1.0 / 1.055)).powf(f32x4::splat(2.4));
+    x.le(f32x4::splat(0.04045)).select(lo, hi)
+}
+
+/// Convert linear values to sRGB-encoded values.
+///
+/// All lanes are converted. The inputs are expected to be in range `[0, 1]`.
+///
+/// # Examples
+///
+///     # use packed_simd::f32x4;
+///     use alt_fp::{linear_to_srgb, srgb_to_linear};
+///
+///     let x = f32x4::new(0.0, 0.002, 0.3, 1.0);
+///     let y = srgb_to_linear(linear_to_srgb(x));
+///     assert!((x - y).abs().max_element() < 1.0e-5);
+///
+#[inline]
+pub fn linear_to_srgb(x: f32x4) -> f32x4 {
+    let lo = x * 12.92;
+    let hi = x.powf(f32x4::splat(1.0 / 2.4)) * 1.055 - 0.055;
+    x.le(f32x4::splat(0.0031308)).select(lo, hi)
+}
+
+/// Composite a premultiplied color `src` over another premultiplied color
+/// `dst`. The alpha value is stored in the last lane.
+///
+/// # Examples
+///
+///     # use packed_simd::f32x4;
+///     use alt_fp::blend_premul_over;
+///
+///     assert_eq!(
+///         blend_premul_over(
+///             f32x4::new(0.25, 0.0, 0.0, 0.5),
+///             f32x4::new(0.0, 1.0, 0.5, 1.0),
+///         ),
+///         f32x4::new(0.25, 0.5, 0.25, 1.0),
+///     );
+///
+#[inline]
+pub fn blend_premul_over(src: f32x4, dst: f32x4) -> f32x4 {
+    let src_alpha: f32x4 = shuffle!(src, [3, 3, 3, 3]);
+    dst.mul_add(f32x4::splat(1.0) - src_alpha, src)
+}
@@ -2,6 +2,8 @@
 //! floating-point operations.
 pub mod cast;
 pub mod cmp;
+#[cfg(feature = "packed_simd")]
+pub mod color;
 pub mod fma;
 #[cfg(feature = "packed_simd")]
 pub mod simd;
@@ -11,4 +13,4 @@ pub use self::{cast::*, cmp::*, fma::*};
 
 #[cfg(feature = "packed_simd")]
 #[doc(no_inline)]
-pub use self::simd::*;
+pub use self::{color::*, simd::*};
@@ -1,4 +1,4 @@
-use packed_simd::f32x4;
+use packed_simd::{f32x4, shuffle};
 #[allow(unused_imports)]
 use std::{
     mem::transmute,
@@ -79,3 +79,27 @@ impl SimdExt for f32x4 {
         unsafe { transmute(_mm_dp_ps(transmute(self), transmute(other), 0b0011_1111)) }
     }
 }
+
+/// Calculate the intersection of two axis-aligned boxes, each represented as
+/// `[min.x, min.y, max.x, max.y]`.
+///
+/// The result is not normalized, i.e., it represents an empty box if
+/// `min.x > max.x` or `min.y > max.y`.
+///
+/// # Examples
+///
+///     # use packed_simd::f32x4;
+///     use alt_fp::aabb_intersection;
+///
+///     assert_eq!(
+///         aabb_intersection(
+///             f32x4::new(0.0, 0.0, 10.0, 10.0),
+///             f32x4::new(5.0, -5.0, 20.0, 8.0),
+///         ),
+///         f32x4::new(5.0, 0.0, 10.0, 8.0),
+///     );
+///
+#[inline]
+pub fn aabb_intersection(a: f32x4, b: f32x4) -> f32x4 {
+    shuffle!(a.max(b), a.min(b), [0, 1, 6, 7])
+}
@@ -1,4 +1,4 @@
-use codemap_diagnostic::{ColorConfig, Diagnostic, Emitter, Level};
+use codemap_diagnostic::{Diagnostic, Level};
 use log::info;
 use std::{
     borrow::Cow,
@@ -11,6 +11,8 @@ use std::{
 
 use crate::metadata::Repo;
 
+pub use self::diag::DiagFormat;
+
 mod diag;
 mod implgen;
 mod metagen;
@@ -23,10 +25,14 @@ pub struct BuildScriptConfig<'a, 'b, 'c> {
     in_root_source_file: Option<PathBuf>,
     out_source_file: OutputFile<'b>,
     crate_name: Option<String>,
-    linked_crates: Vec<(String, Cow<'a, [u8]>)>,
+    linked_crates: Vec<(String, LinkedMetadata<'a>)>,
+    out_metadata_file: Option<PathBuf>,
     tcw3_path: String,
     designer_runtime_path: String,
+    test_harness: bool,
+    hot_reload: bool,
     out_diag: Option<&'c mut (dyn std::io::Write + Send)>,
+    diag_format: DiagFormat,
 }
 
 enum OutputFile<'a> {
@@ -36,6 +42,13 @@ enum OutputFile<'a> {
     Custom(&'a mut dyn Write),
 }
 
+enum LinkedMetadata<'a> {
+    Bytes(Cow<'a, [u8]>),
+    File(PathBuf),
+    /// Read from the file specified by `DEP_{links}_DESIGNER_METADATA`.
+    Dep(String),
+}
+
 impl Default for BuildScriptConfig<'_, '_, '_> {
     fn default() -> Self {
         Self {
@@ -43,9 +56,13 @@ impl Default for BuildScriptConfig<'_, '_, '_> {
             out_source_file: OutputFile::FromEnv,
             crate_name: None,
             linked_crates: Vec::new(),
+            out_metadata_file: None,
             tcw3_path: "::tcw3".to_string(),
             designer_runtime_path: "::tcw3::designer_runtime".to_string(),
+            test_harness: false,
+            hot_reload: false,
             out_diag: None,
+            diag_format: DiagFormat::default(),
         }
     }
 }
@@ -84,10 +101,44 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
     }
 
     pub fn link(mut self, name: impl Into<String>, metadata: Cow<'a, [u8]>) -> Self {
-        self.linked_crates.push((name.into(), metadata));
+        self.linked_crates
+            .push((name.into(), LinkedMetadata::Bytes(metadata)));
+        self
+    }
+
+    /// Import the metadata of the crate `name` from a file written by
+    /// [`BuildScriptConfig::out_metadata_file`].
+    pub fn link_file(mut self, name: impl Into<String>, path: impl AsRef<Path>) -> Self {
+        self.linked_crates.push((
+            name.into(),
+            LinkedMetadata::File(path.as_ref().to_path_buf()),
+        ));
+        self
+    }
+
+    /// Import the metadata of the crate `name` from the build artifacts of
+    /// its meta crate, which must be a direct dependency of the current crate
+    /// and have the `links` key set to `links`.
+    ///
+    /// The metadata file is located using the environment variable
+    /// `DEP_{LINKS}_DESIGNER_METADATA`, which Cargo sets based on the output
+    /// of the meta crate's build script.
+    pub fn link_dep(mut self, name: impl Into<String>, links: impl Into<String>) -> Self {
+        self.linked_crates
+            .push((name.into(), LinkedMetadata::Dep(links.into())));
         self
     }
 
+    /// Write the encoded metadata to the specified file. By default, it's
+    /// written to `$OUT_DIR/designer.tcwdm` only if the output source file
+    /// is not specified.
+    pub fn out_metadata_file(self, path: impl AsRef<Path>) -> Self {
+        Self {
+            out_metadata_file: Some(path.as_ref().to_path_buf()),
+            ..self
+        }
+    }
+
     /// Set the path of `tcw3` used by the generated implementation code.
     /// Defaults to `::tcw3`.
     pub fn tcw3_path(self, path: impl Into<String>) -> Self {
@@ -106,6 +157,28 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
         }
     }
 
+    /// Generate `#[cfg(test)]` helpers for each component. Defaults to
+    /// `false`.
+    ///
+    /// The generated code uses `tcw3::pal::testing`. See the crate
+    /// documentation for the list of generated methods.
+    pub fn test_harness(self, test_harness: bool) -> Self {
+        Self {
+            test_harness,
+            ..self
+        }
+    }
+
+    /// Generate code for hot-reloading literal values in object
+    /// initialization literals. Defaults to `false`.
+    ///
+    /// The generated code uses `tcw3::designer_runtime::hot_reload`, which
+    /// requires `tcw3_designer_runtime`'s `hot_reload` feature. See the crate
+    /// documentation for details.
+    pub fn hot_reload(self, hot_reload: bool) -> Self {
+        Self { hot_reload, ..self }
+    }
+
     /// Set the destination of the diagnostic output.
     pub fn out_diag_stream(self, out_diag: &'c mut (dyn std::io::Write + Send)) -> Self {
         Self {
@@ -114,6 +187,15 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
         }
     }
 
+    /// Set the format of the diagnostic output. Defaults to
+    /// [`DiagFormat::Human`].
+    pub fn diag_format(self, diag_format: DiagFormat) -> Self {
+        Self {
+            diag_format,
+            ..self
+        }
+    }
+
     /// Run the code generator. Terminate the current process on failure.
     ///
     /// This method automatically sets up a logger using `env_logger`.
@@ -127,23 +209,24 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
 
     pub fn run(mut self) -> Result<(), EmittedError> {
         let mut out_diag = self.out_diag.take();
+        let diag_format = self.diag_format;
         let result = self.run_inner(out_diag.as_mut().map(|x| x as _));
         if let Err(e) = result {
-            let mut emitter = if let Some(out_diag) = out_diag {
-                Emitter::new(Box::new(&mut *out_diag), None)
-            } else {
-                Emitter::stderr(ColorConfig::Auto, None)
-            };
-            emitter.emit(&[Diagnostic {
-                level: Level::Error,
-                message: if let BuildError::Emitted = e {
-                    "Aborting due to previous error(s)".to_string()
-                } else {
-                    format!("{}", e)
-                },
-                code: None,
-                spans: vec![],
-            }]);
+            diag::emit_to(
+                out_diag.as_mut().map(|x| &mut **x as _),
+                None,
+                diag_format,
+                &[Diagnostic {
+                    level: Level::Error,
+                    message: if let BuildError::Emitted = e {
+                        "Aborting due to previous error(s)".to_string()
+                    } else {
+                        format!("{}", e)
+                    },
+                    code: None,
+                    spans: vec![],
+                }],
+            );
 
             Err(EmittedError)
         } else {
@@ -177,15 +260,22 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
             Path::new(&dir).join("lib.tcwdl")
         };
 
+        let is_build_script = matches!(self.out_source_file, OutputFile::FromEnv);
+
+        let mut out_metadata_file = self.out_metadata_file;
+
         let out_source_file = if let OutputFile::FromEnv = self.out_source_file {
             let out_dir = env::var_os("OUT_DIR").ok_or(BuildError::OutDirMissing)?;
             info!("OUT_DIR = {:?}", out_dir);
+            if out_metadata_file.is_none() {
+                out_metadata_file = Some(Path::new(&out_dir).join("designer.tcwdm"));
+            }
             OutputFile::File(Path::new(&out_dir).join("designer.rs").into())
         } else {
             self.out_source_file
         };
 
-        let mut diag = diag::Diag::new(out_diag);
+        let mut diag = diag::Diag::new(out_diag, self.diag_format);
 
         // Parse the input source files
         let mut files = Vec::new();
@@ -236,27 +326,43 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
             }
         }
 
-        // Load prelude
-        let prelude = resolve::Prelude::new(&mut diag);
-
-        // Resolve paths, meaning they are all expanded to absolute paths
-        // as specified by `use` items.
-        for (parsed_file, diag_file) in files.iter_mut() {
-            resolve::resolve_paths(parsed_file, diag_file, &mut diag, &prelude);
-        }
-
-        if diag.has_error() {
-            return Err(BuildError::Emitted);
-        }
-
         // Import metadata of dependencies
         let mut deps: Vec<(&str, Repo)> = self
             .linked_crates
             .iter()
             .map(|(name, metadata)| {
+                let path = match metadata {
+                    LinkedMetadata::Bytes(_) => None,
+                    LinkedMetadata::File(path) => Some(path.clone()),
+                    LinkedMetadata::Dep(links) => {
+                        let var_name = format!(
+                            "DEP_{}_DESIGNER_METADATA",
+                            links.to_uppercase().replace('-', "_")
+                        );
+                        let path = env::var_os(&var_name).ok_or_else(|| {
+                            BuildError::DepMetadataMissing(name.clone(), var_name.clone())
+                        })?;
+                        info!("{} = {:?}", var_name, path);
+                        Some(PathBuf::from(path))
+                    }
+                };
+
+                let bytes: Cow<'_, [u8]> = if let Some(path) = path {
+                    if is_build_script {
+                        println!("cargo:rerun-if-changed={}", path.display());
+                    }
+                    std::fs::read(&path)
+                        .map_err(|e| BuildError::MetadataReadError(name.clone(), path, e))?
+                        .into()
+                } else if let LinkedMetadata::Bytes(bytes) = metadata {
+                    Cow::Borrowed(bytes)
+                } else {
+                    unreachable!()
+                };
+
                 Ok((
                     name.as_str(),
-                    bincode::deserialize(metadata)
+                    Repo::decode(&bytes)
                         .map_err(|e| BuildError::MetadataDeserializationFailure(name.clone(), e))?,
                 ))
             })
@@ -335,6 +441,29 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
             })
             .collect();
 
+        // Load prelude
+        let prelude = resolve::Prelude::new(&mut diag);
+
+        // Resolve paths, meaning they are all expanded to absolute paths
+        // as specified by `use` items. Glob imports are resolved using
+        // the metadata of dependencies.
+        for (parsed_file, diag_file) in files.iter_mut() {
+            resolve::resolve_paths(
+                parsed_file,
+                diag_file,
+                &mut diag,
+                &prelude,
+                &resolve::GlobScope {
+                    repo: &repo,
+                    imports_crate_i: &imports_crate_i,
+                },
+            );
+        }
+
+        if diag.has_error() {
+            return Err(BuildError::Emitted);
+        }
+
         // Start analysis of this crate
         // -------------------------------------------------------------------
         let mut comps = Vec::new();
@@ -360,6 +489,8 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
         // Generate implementation code
         let tcw3_path = self.tcw3_path.as_str();
         let designer_runtime_path = self.designer_runtime_path.as_str();
+        let test_harness = self.test_harness;
+        let hot_reload = self.hot_reload;
         let comp_code_chunks: Vec<_> = comps
             .iter()
             .enumerate()
@@ -372,6 +503,8 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
                     cur_meta_comp_i: comp_i,
                     tcw3_path,
                     designer_runtime_path,
+                    test_harness,
+                    hot_reload,
                 };
                 (
                     comp,
@@ -389,7 +522,18 @@ impl<'a, 'b, 'c> BuildScriptConfig<'a, 'b, 'c> {
             &mut repo,
         );
 
-        let meta_bin = bincode::serialize(&repo).unwrap();
+        let meta_bin = repo.encode();
+
+        if let Some(path) = &out_metadata_file {
+            std::fs::write(path, &meta_bin)
+                .map_err(|e| BuildError::OutputFileError(path.clone(), e))?;
+
+            if is_build_script {
+                // Let the dependents' build scripts find the metadata via
+                // `DEP_{links}_DESIGNER_METADATA`
+                println!("cargo:designer_metadata={}", path.display());
+            }
+        }
 
         // Move out `PathBuf` from `out_source_file`, leaving `Cow::Borrowed`
         let out_source_file_path_storage;
@@ -498,7 +642,12 @@ enum BuildError {
     /// really in a build script?
     OutDirMissing,
     /// Failed to import the metadata of `{0}`: {1}
-    MetadataDeserializationFailure(String, bincode::Error),
+    MetadataDeserializationFailure(String, crate::metadata::DecodeError),
+    /// Could not read the metadata of `{0}` from `{1}`: {2}
+    MetadataReadError(String, PathBuf, std::io::Error),
+    /// Could not locate the metadata of `{0}` because `{1}` is not set; does
+    /// the meta crate have the `links` key and is it a direct dependency?
+    DepMetadataMissing(String, String),
     /// Could not write the output file `{0}`: {1}
     OutputFileError(PathBuf, std::io::Error),
     /// Could not write the output stream: {0}
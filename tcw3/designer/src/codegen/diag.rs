@@ -1,23 +1,48 @@
-use codemap::Span;
+use codemap::{CodeMap, Span};
 use codemap_diagnostic::{ColorConfig, Diagnostic, Emitter, Level, SpanLabel, SpanStyle};
+use serde::Serialize;
 use std::{fs::File, io::prelude::*, path::Path, sync::Arc};
 
 use super::EmittedError;
 
 pub type FileRef = Arc<codemap::File>;
 
+/// Specifies the format of diagnostic messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagFormat {
+    /// Human-readable messages with source code excerpts.
+    Human,
+    /// One JSON object per message, each on its own line. This is intended
+    /// to be consumed by tools such as editors.
+    ///
+    /// Each object has the following fields: `level` (`"error"`,
+    /// `"warning"`, etc.), `message`, `code` (may be `null`), and `spans`,
+    /// an array of objects each having the following fields: `file_name`,
+    /// `line_start`, `column_start`, `line_end`, `column_end` (all 1-based),
+    /// `is_primary`, and `label` (may be `null`).
+    Json,
+}
+
+impl Default for DiagFormat {
+    fn default() -> Self {
+        DiagFormat::Human
+    }
+}
+
 pub struct Diag<'a> {
-    codemap: codemap::CodeMap,
-    has_error: bool,
+    codemap: CodeMap,
+    num_errors: usize,
     out_diag: Option<&'a mut (dyn std::io::Write + Send)>,
+    format: DiagFormat,
 }
 
 impl<'a> Diag<'a> {
-    pub fn new(out_diag: Option<&'a mut (dyn std::io::Write + Send)>) -> Self {
+    pub fn new(out_diag: Option<&'a mut (dyn std::io::Write + Send)>, format: DiagFormat) -> Self {
         Self {
-            codemap: codemap::CodeMap::new(),
-            has_error: false,
+            codemap: CodeMap::new(),
+            num_errors: 0,
             out_diag,
+            format,
         }
     }
 
@@ -55,7 +80,13 @@ impl<'a> Diag<'a> {
     }
 
     pub fn has_error(&self) -> bool {
-        self.has_error
+        self.num_errors > 0
+    }
+
+    /// Get the number of errors reported so far. This can be used to check if
+    /// an error was reported during a particular step.
+    pub fn num_errors(&self) -> usize {
+        self.num_errors
     }
 
     pub fn look_up_pos(&self, pos: codemap::Pos) -> Loc {
@@ -68,16 +99,106 @@ impl<'a> Diag<'a> {
     }
 
     pub fn emit(&mut self, msgs: &[Diagnostic]) {
-        self.has_error |= msgs
+        self.num_errors += msgs
             .iter()
-            .any(|m| m.level == Level::Error || m.level == Level::Bug);
-
-        let mut emitter = if let Some(out_diag) = &mut self.out_diag {
-            Emitter::new(Box::new(&mut *out_diag), Some(&self.codemap))
-        } else {
-            Emitter::stderr(ColorConfig::Auto, Some(&self.codemap))
-        };
-        emitter.emit(msgs);
+            .filter(|m| m.level == Level::Error || m.level == Level::Bug)
+            .count();
+
+        emit_to(
+            self.out_diag.as_mut().map(|x| &mut **x as _),
+            Some(&self.codemap),
+            self.format,
+            msgs,
+        );
+    }
+}
+
+/// Write diagnostic messages to `out_diag` (or the standard error if `None`)
+/// in the specified format.
+pub fn emit_to(
+    out_diag: Option<&mut (dyn std::io::Write + Send)>,
+    codemap: Option<&CodeMap>,
+    format: DiagFormat,
+    msgs: &[Diagnostic],
+) {
+    match format {
+        DiagFormat::Human => {
+            let mut emitter = if let Some(out_diag) = out_diag {
+                Emitter::new(Box::new(out_diag), codemap)
+            } else {
+                Emitter::stderr(ColorConfig::Auto, codemap)
+            };
+            emitter.emit(msgs);
+        }
+        DiagFormat::Json => {
+            let mut stderr;
+            let out_diag: &mut dyn std::io::Write = if let Some(out_diag) = out_diag {
+                out_diag
+            } else {
+                stderr = std::io::stderr();
+                &mut stderr
+            };
+
+            for msg in msgs.iter() {
+                let json = serde_json::to_string(&JsonDiagnostic::new(msg, codemap)).unwrap();
+                // Ignore I/O errors like `Emitter` does
+                let _ = writeln!(out_diag, "{}", json);
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    level: &'static str,
+    message: &'a str,
+    code: Option<&'a str>,
+    spans: Vec<JsonSpan<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan<'a> {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    line_end: usize,
+    column_end: usize,
+    is_primary: bool,
+    label: Option<&'a str>,
+}
+
+impl<'a> JsonDiagnostic<'a> {
+    fn new(msg: &'a Diagnostic, codemap: Option<&CodeMap>) -> Self {
+        Self {
+            level: match msg.level {
+                Level::Bug => "bug",
+                Level::Error => "error",
+                Level::Warning => "warning",
+                Level::Note => "note",
+                Level::Help => "help",
+            },
+            message: &msg.message,
+            code: msg.code.as_ref().map(String::as_str),
+            spans: codemap
+                .map(|codemap| {
+                    msg.spans
+                        .iter()
+                        .map(|label| {
+                            let loc = codemap.look_up_span(label.span);
+                            JsonSpan {
+                                file_name: loc.file.name().to_owned(),
+                                line_start: loc.begin.line + 1,
+                                column_start: loc.begin.column + 1,
+                                line_end: loc.end.line + 1,
+                                column_end: loc.end.column + 1,
+                                is_primary: label.style == SpanStyle::Primary,
+                                label: label.label.as_ref().map(String::as_str),
+                            }
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
     }
 }
 
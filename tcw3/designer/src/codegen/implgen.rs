@@ -13,10 +13,13 @@ mod accessorgen;
 mod analysis;
 mod bitsetgen;
 mod buildergen;
+mod debuggen;
 mod dropgen;
 mod evalgen;
+mod hotreloadgen;
 mod initgen;
 pub mod iterutils;
+mod testgen;
 mod weakrefgen;
 
 /// Paths to standard library items.
@@ -25,6 +28,7 @@ mod paths {
     pub const CLONE: &str = "::std::clone::Clone";
     pub const OPTION: &str = "::std::option::Option";
     pub const SOME: &str = "::std::option::Option::Some";
+    pub const NONE: &str = "::std::option::Option::None";
     pub const RC: &str = "::std::rc::Rc";
     pub const WEAK: &str = "::std::rc::Weak";
     pub const CELL: &str = "::std::cell::Cell";
@@ -37,6 +41,11 @@ mod paths {
     pub const FORGET: &str = "::std::mem::forget";
     pub const DEBUG_ASSERT: &str = "::std::debug_assert";
     pub const MAYBE_UNINIT: &str = "::std::mem::MaybeUninit";
+    pub const DURATION: &str = "::std::time::Duration";
+    pub const DEBUG: &str = "::std::fmt::Debug";
+    pub const FMT: &str = "::std::fmt";
+    pub const VEC: &str = "::std::vec";
+    pub const FORMAT: &str = "::std::format";
 }
 
 /// The fields of generated types.
@@ -54,6 +63,7 @@ mod methods {
     pub const DOWNGRADE: &str = "downgrade";
     pub const UPGRADE: &str = "upgrade";
     pub const COMMIT: &str = "__commit";
+    pub const SET_PROPS: &str = "set_props";
 }
 
 /// Recognized field (not Rust field, but our field) names.
@@ -75,6 +85,12 @@ pub struct Ctx<'a> {
 
     pub tcw3_path: &'a str,
     pub designer_runtime_path: &'a str,
+
+    /// Generate `#[cfg(test)]` helpers.
+    pub test_harness: bool,
+
+    /// Generate code for `designer_runtime::hot_reload`.
+    pub hot_reload: bool,
 }
 
 impl<'a> Ctx<'a> {
@@ -89,6 +105,37 @@ impl<'a> Ctx<'a> {
         self.repo.comp_by_ref(&self.cur_meta_comp_ref())
     }
 
+    /// Find the component referred to by `path`, which must be already rooted
+    /// by `super::resolve`.
+    fn find_comp_by_path(&self, path: &syn::Path) -> Option<metadata::CompRef> {
+        // Paths with arguments never refer to a component
+        if path.segments.iter().any(|s| !s.arguments.is_empty()) {
+            return None;
+        }
+
+        let crate_name = path.segments[0].ident.to_string();
+        let crate_i = if path.leading_colon.is_none() {
+            if crate_name != "crate" {
+                // `path` refers to Rust's built-in type
+                return None;
+            }
+            self.repo.main_crate_i
+        } else if crate_name == self.repo.crates[self.repo.main_crate_i].name {
+            self.repo.main_crate_i
+        } else {
+            *self.imports_crate_i.get(&*crate_name)?
+        };
+
+        let comp_i = self.repo.crates[crate_i].comps.iter().position(|comp| {
+            let segs1 = path.segments.iter().skip(1).map(|s| &s.ident);
+            let segs2 = comp.paths.iter().map(|p| p.idents.iter());
+
+            { segs2 }.any(|p| segs1.clone().eq(p.clone()))
+        })?;
+
+        Some(metadata::CompRef { crate_i, comp_i })
+    }
+
     // `::tcw3::designer_runtime::SubscriberList`
     fn path_sub_list(&self) -> impl std::fmt::Display + Clone + '_ {
         DisplayFn(move |f| write!(f, "{}::SubscriberList", self.designer_runtime_path))
@@ -114,6 +161,11 @@ impl<'a> Ctx<'a> {
         DisplayFn(move |f| write!(f, "{}::uicore::WmExt::invoke_on_update", self.tcw3_path))
     }
 
+    // `::tcw3::pal::prelude::WmTrait::spawn_local`
+    fn path_spawn_local(&self) -> impl std::fmt::Display + Clone + '_ {
+        DisplayFn(move |f| write!(f, "{}::pal::prelude::WmTrait::spawn_local", self.tcw3_path))
+    }
+
     // `::tcw3::designer_runtime::unwrap_unchecked`
     fn path_unwrap_unchecked(&self) -> impl std::fmt::Display + Clone + '_ {
         DisplayFn(move |f| write!(f, "{}::unwrap_unchecked", self.designer_runtime_path))
@@ -124,6 +176,16 @@ impl<'a> Ctx<'a> {
         DisplayFn(move |f| write!(f, "{}::ShallowEq", self.designer_runtime_path))
     }
 
+    // `::tcw3::designer_runtime::mount_if`
+    fn path_mount_if(&self) -> impl std::fmt::Display + Clone + '_ {
+        DisplayFn(move |f| write!(f, "{}::mount_if", self.designer_runtime_path))
+    }
+
+    // `::tcw3::designer_runtime::mount_for`
+    fn path_mount_for(&self) -> impl std::fmt::Display + Clone + '_ {
+        DisplayFn(move |f| write!(f, "{}::mount_for", self.designer_runtime_path))
+    }
+
     // `::tcw3::designer_runtime::unsubscribe_subs_unchecked`
     fn path_unsubscribe_subs_unchecked(&self) -> impl std::fmt::Display + Clone + '_ {
         DisplayFn(move |f| {
@@ -143,6 +205,12 @@ pub fn gen_comp(
 ) -> Result<String, EmittedError> {
     let comp = ctx.cur_comp;
 
+    // `diag` is shared by all components. Only count errors reported for this
+    // component so that errors in other components do not stop the analysis
+    // of this component.
+    let num_errors = diag.num_errors();
+    let has_new_error = |diag: &Diag<'_>| diag.num_errors() > num_errors;
+
     if comp.flags.contains(sem::CompFlags::PROTOTYPE_ONLY) {
         return Ok(r#"compile_error!(
             "`designer_impl!` can't generate code because the component is defined with #[prototype_only]"
@@ -181,7 +249,7 @@ pub fn gen_comp(
         diag,
     );
 
-    if diag.has_error() {
+    if has_new_error(diag) {
         // Duplicate item names cause may false errors down below, so
         // return early.
         return Err(EmittedError);
@@ -244,6 +312,8 @@ pub fn gen_comp(
         &item_meta2sem_map,
         &mut scoped_out,
     );
+    // `ComponentType::set_props`
+    initgen::gen_set_props(ctx, &mut scoped_out);
     writeln!(scoped_out, "}}").unwrap();
 
     // `struct WeakComponentType`
@@ -296,7 +366,7 @@ pub fn gen_comp(
         .unwrap();
     }
 
-    for item in comp.items.iter() {
+    for (item_i, item) in comp.items.iter().enumerate() {
         match item {
             sem::CompItemDef::Field(item) => match item.field_ty {
                 sem::FieldType::Const => {
@@ -333,7 +403,21 @@ pub fn gen_comp(
                 )
                 .unwrap();
             }
-            sem::CompItemDef::On(_) => {}
+            sem::CompItemDef::On(item) => {
+                if let Some(rate_limit) = &item.rate_limit {
+                    writeln!(
+                        out,
+                        "    {ident}: {rt}::{ty},",
+                        ident = RateLimiterField(item_i),
+                        rt = ctx.designer_runtime_path,
+                        ty = match rate_limit.kind {
+                            sem::RateLimitKind::Debounce => "Debounce",
+                            sem::RateLimitKind::Throttle => "Throttle",
+                        },
+                    )
+                    .unwrap();
+                }
+            }
         }
     }
 
@@ -398,8 +482,33 @@ pub fn gen_comp(
     // -------------------------------------------------------------------
     accessorgen::gen_accessors(&dep_analysis, ctx, &mut out);
 
+    // `Debug` and `introspect`
+    // -------------------------------------------------------------------
+    debuggen::gen_debug_impls(ctx, &mut scoped_out);
+    if comp.flags.contains(sem::CompFlags::INTROSPECT) {
+        debuggen::gen_introspect(ctx, &mut scoped_out);
+    }
+
+    // `#[cfg(test)]` helpers
+    // -------------------------------------------------------------------
+    if ctx.test_harness {
+        testgen::gen_test_harness(ctx, &mut scoped_out);
+    }
+
+    // Hot-reloadable literals
+    // -------------------------------------------------------------------
+    if ctx.hot_reload {
+        hotreloadgen::gen_sites(ctx, diag, &mut scoped_out);
+    }
+
     writeln!(out, "mod {} {{\n{}}}", scoping_mod_name, scoped_out).unwrap();
 
+    if has_new_error(diag) {
+        // Code generation continues after an error to report as many errors
+        // as possible, but the generated code is broken
+        return Err(EmittedError);
+    }
+
     Ok(out)
 }
 
@@ -466,6 +575,13 @@ impl<T: fmt::Display> fmt::Display for EventInnerSubList<T> {
     fn_fmt_write! { |this| ("subscriptions_{}", this.0) }
 }
 
+/// The rate limiter of an `on` item, indexed by an index into
+/// `sem::CompDef::items`.
+struct RateLimiterField<T>(T);
+impl<T: fmt::Display> fmt::Display for RateLimiterField<T> {
+    fn_fmt_write! { |this| ("rate_limiter_{}", this.0) }
+}
+
 struct GetterMethod<T>(T);
 impl<T: fmt::Display> fmt::Display for GetterMethod<T> {
     fn_fmt_write! { |this| ("{}", this.0) }
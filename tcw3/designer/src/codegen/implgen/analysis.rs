@@ -1,6 +1,5 @@
 //! Resolves what `Input` points to and provides the analysis result.
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
-use try_match::try_match;
 
 use super::super::{diag::Diag, sem};
 use super::Ctx;
@@ -19,8 +18,8 @@ pub struct Analysis {
     //              event.event1_foo as param, // used only with `event1`
     //              event.event2_bar as param, // used only with `event2`
     //          | { body }
-    /// Analysis for `ObjInit` of each field in `sem::CompDef::items`. `None`
-    /// for fields that do not have `ObjInit`.
+    /// Analysis for `ObjInit` of each field in `sem::CompDef::items`, including
+    /// the one in `Mount`. `None` for fields that do not have `ObjInit`.
     pub obj_inits: Vec<Option<ObjInitInfo>>,
 }
 
@@ -175,6 +174,18 @@ impl Analysis {
                     Some(sem::DynExpr::ObjInit(init)) => {
                         analyze_inputs_obj_init(&mut actx, init);
                     }
+                    Some(sem::DynExpr::Mount(mount)) => {
+                        let head = match &mount.kind {
+                            sem::MountKind::If { cond } => cond,
+                            sem::MountKind::For { iter, .. } => iter,
+                        };
+                        analyze_inputs(
+                            &mut actx,
+                            head.inputs.iter().map(|func_input| &func_input.input),
+                            Err(EventTriggerUnavailableReason::NotEventHandler),
+                        );
+                        analyze_inputs_obj_init(&mut actx, &mount.obj_init);
+                    }
                 },
                 sem::CompItemDef::On(item) => {
                     analyze_inputs_on(&mut actx, item);
@@ -187,9 +198,9 @@ impl Analysis {
     }
 }
 
-/// Analyze an `ObjInit` in the current component and store the result in
-/// `Analysis::obj_inits`. Do nothing if `cur_comp.items[item_i]` does
-/// not contain an `ObjInit`.
+/// Analyze an `ObjInit` (which may be a part of `Mount`) in the current
+/// component and store the result in `Analysis::obj_inits`. Do nothing if
+/// `cur_comp.items[item_i]` does not contain an `ObjInit`.
 ///
 /// The caller should ignore the return value. It's only used for early return
 /// by the `?` operator.
@@ -202,15 +213,27 @@ fn analyze_obj_init(
     let diag = &mut *actx.diag;
 
     let field = comp.items[item_i].field()?;
-    let init = try_match!(Some(sem::DynExpr::ObjInit(init)) = &field.value).ok()?;
 
-    // Find the component we are constructing. The field's type is guaranteed to
-    // match the component's type because we do not allow explicitly specifying
-    // the type when `ObjInit` is in use.
-    let meta_item_i = item_meta2sem_map.iter().position(|&i| i == item_i).unwrap();
-    let meta_field = actx.ctx.cur_meta_comp().items[meta_item_i].field().unwrap();
+    // Find the component we are constructing
+    let (init, target_comp_ref) = match &field.value {
+        Some(sem::DynExpr::ObjInit(init)) => {
+            // The field's type is guaranteed to match the component's type
+            // because we do not allow explicitly specifying the type when
+            // `ObjInit` is in use.
+            let meta_item_i = item_meta2sem_map.iter().position(|&i| i == item_i).unwrap();
+            let meta_field = actx.ctx.cur_meta_comp().items[meta_item_i].field().unwrap();
+            (init, meta_field.ty)
+        }
+        Some(sem::DynExpr::Mount(mount)) => {
+            // The field's type wraps the component's type, so look up the
+            // component by the path instead
+            let init = &mount.obj_init;
+            (init, actx.ctx.find_comp_by_path(&init.path.syn_path))
+        }
+        _ => return None,
+    };
 
-    let target_comp_ref = if let Some(target_comp_ref) = meta_field.ty {
+    let target_comp_ref = if let Some(target_comp_ref) = target_comp_ref {
         target_comp_ref
     } else {
         diag.emit(&[Diagnostic {
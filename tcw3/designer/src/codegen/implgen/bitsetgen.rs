@@ -1,20 +1,26 @@
 //! Code generation for bit set types
 use super::DisplayFn;
 
-#[derive(Debug, Clone, Copy)]
-pub struct TooLargeError;
-
 #[allow(dead_code)] // Work-around rust-lang/rust#64362
 #[derive(Debug, Clone, Copy)]
 pub enum BitsetTy {
     Empty,
-    Ux { ty: &'static str },
+    Ux {
+        ty: &'static str,
+    },
+    /// `[u32; len]`
+    Array {
+        len: usize,
+    },
 }
 
+/// The number of bits in each element of `BitsetTy::Array`.
+const WORD_BITS: usize = 32;
+
 impl BitsetTy {
     /// Construct a code generator for a bitset type capable of storing `size`
     /// elements.
-    pub fn new(size: usize) -> Result<Self, TooLargeError> {
+    pub fn new(size: usize) -> Self {
         // A few points regarding this branch:
         //  - `u8` should be more than sufficient for most use cases.
         //  - `u16` and `u64` often need a size prefix, so it's beneficial in terms
@@ -24,28 +30,34 @@ impl BitsetTy {
         //    application)
         //  - `u128` is not natively supported by x86, so it generates code that is
         //    larger and slower (by like a few nanoseconds).
+        //  - Beyond 128 elements, an array of `u32` is used. Operations on
+        //    it are expanded to per-element operations, which are slower
+        //    but such components are rare (e.g., auto-generated settings
+        //    panes).
         if size == 0 {
-            Ok(Self::Empty)
+            Self::Empty
         } else if size <= 8 {
-            Ok(Self::Ux { ty: "u8" })
+            Self::Ux { ty: "u8" }
         } else if size <= 32 {
-            Ok(Self::Ux { ty: "u32" })
+            Self::Ux { ty: "u32" }
         } else if size <= 64 {
-            Ok(Self::Ux { ty: "u64" })
+            Self::Ux { ty: "u64" }
         } else if size <= 128 {
-            Ok(Self::Ux { ty: "u128" })
+            Self::Ux { ty: "u128" }
         } else {
-            // Maybe we support a larger bitset in the future
-            Err(TooLargeError)
+            Self::Array {
+                len: (size + WORD_BITS - 1) / WORD_BITS,
+            }
         }
     }
 
     /// Get a `Display`-able type name.
     pub fn gen_ty(&self) -> impl std::fmt::Display + '_ {
-        match self {
-            Self::Empty => "()",
-            Self::Ux { ty } => *ty,
-        }
+        DisplayFn(move |f| match self {
+            Self::Empty => write!(f, "()"),
+            Self::Ux { ty } => write!(f, "{}", ty),
+            Self::Array { len } => write!(f, "[u32; {}]", len),
+        })
     }
 
     /// Generate an expression representing an empty set.
@@ -53,6 +65,7 @@ impl BitsetTy {
         DisplayFn(move |f| match self {
             Self::Empty => write!(f, "()"),
             Self::Ux { ty } => write!(f, "0{}", ty),
+            Self::Array { len } => write!(f, "[0u32; {}]", len),
         })
     }
 
@@ -65,6 +78,7 @@ impl BitsetTy {
         DisplayFn(move |f| match self {
             Self::Empty => write!(f, "true"),
             Self::Ux { .. } => write!(f, "{} == 0", expr),
+            Self::Array { .. } => write!(f, "({}).iter().all(|&x| x == 0)", expr),
         })
     }
 
@@ -79,7 +93,8 @@ impl BitsetTy {
     }
 
     /// Generate an expression that evaluates to a `bool` value indicating
-    /// whether `expr` includes any of `elements` as its element.
+    /// whether `expr` includes any of `elements` as its element. `expr` may be
+    /// evaluated more than once.
     pub fn gen_intersects<'a>(
         &'a self,
         expr: impl std::fmt::Display + 'a,
@@ -88,11 +103,24 @@ impl BitsetTy {
         DisplayFn(move |f| match self {
             Self::Empty => write!(f, "false"),
             Self::Ux { .. } => write!(f, "({} & {}) != 0", expr, self.gen_multi(elements.clone())),
+            Self::Array { len } => {
+                let words = array_words(*len, elements.clone());
+                let mut nonzero_words = words.iter().enumerate().filter(|&(_, &w)| w != 0);
+                if let Some((i, w)) = nonzero_words.next() {
+                    write!(f, "(({})[{}] & {:#x}) != 0", expr, i, w)?;
+                    for (i, w) in nonzero_words {
+                        write!(f, " || (({})[{}] & {:#x}) != 0", expr, i, w)?;
+                    }
+                    Ok(())
+                } else {
+                    write!(f, "false")
+                }
+            }
         })
     }
 
     /// Generate an expression that inserts specified elements to `expr`, and
-    /// evaluates to `()`.
+    /// evaluates to `()`. `expr` may be evaluated more than once.
     pub fn gen_insert<'a>(
         &'a self,
         expr: impl std::fmt::Display + 'a,
@@ -101,6 +129,14 @@ impl BitsetTy {
         DisplayFn(move |f| match self {
             Self::Empty => panic!("vector size is 0, can't insert any elements"),
             Self::Ux { .. } => write!(f, "{} |= {}", expr, self.gen_multi(elements.clone())),
+            Self::Array { len } => {
+                let words = array_words(*len, elements.clone());
+                write!(f, "{{ ")?;
+                for (i, w) in words.iter().enumerate().filter(|&(_, &w)| w != 0) {
+                    write!(f, "({})[{}] |= {:#x}; ", expr, i, w)?;
+                }
+                write!(f, "}}")
+            }
         })
     }
 
@@ -114,6 +150,20 @@ impl BitsetTy {
         DisplayFn(move |f| match self {
             Self::Empty => write!(f, "()"),
             Self::Ux { .. } => write!(f, "{} | {}", expr1, expr2),
+            Self::Array { len } => {
+                write!(
+                    f,
+                    "{{ let __bitset1: [u32; {len}] = {e1}; \
+                     let __bitset2: [u32; {len}] = {e2}; [",
+                    len = len,
+                    e1 = expr1,
+                    e2 = expr2
+                )?;
+                for i in 0..*len {
+                    write!(f, "__bitset1[{i}] | __bitset2[{i}], ", i = i)?;
+                }
+                write!(f, "] }}")
+            }
         })
     }
 
@@ -122,6 +172,17 @@ impl BitsetTy {
         &'a self,
         elements: impl IntoIterator<Item = usize> + Clone + 'a,
     ) -> impl std::fmt::Display + 'a {
+        if let Self::Array { len } = *self {
+            let words = array_words(len, elements);
+            return either::Left(DisplayFn(move |f| {
+                write!(f, "[")?;
+                for w in words.iter() {
+                    write!(f, "{:#x}u32, ", w)?;
+                }
+                write!(f, "]")
+            }));
+        }
+
         let single = {
             let mut elements = elements.clone().into_iter().fuse();
             let x = elements.next();
@@ -132,7 +193,7 @@ impl BitsetTy {
             }
         };
 
-        DisplayFn(move |f| {
+        either::Right(DisplayFn(move |f| {
             if let Some(x) = single {
                 if let Some(x) = x {
                     write!(f, "{}", self.gen_one(x))
@@ -148,13 +209,14 @@ impl BitsetTy {
                 }
                 Ok(())
             }
-        })
+        }))
     }
 
     /// Generate an expression representing the set `{i}`.
     fn gen_one(&self, i: usize) -> impl std::fmt::Display + '_ {
         DisplayFn(move |f| match self {
             Self::Empty => panic!("vector size is 0, can't have any elements"),
+            Self::Array { .. } => unreachable!(),
             Self::Ux { ty } => {
                 if i == 0 {
                     write!(f, "1{}", ty)
@@ -165,3 +227,12 @@ impl BitsetTy {
         })
     }
 }
+
+/// Convert a set of elements to the words of `BitsetTy::Array { len }`.
+fn array_words(len: usize, elements: impl IntoIterator<Item = usize>) -> Vec<u32> {
+    let mut words = vec![0u32; len];
+    for i in elements {
+        words[i / WORD_BITS] |= 1 << (i % WORD_BITS);
+    }
+    words
+}
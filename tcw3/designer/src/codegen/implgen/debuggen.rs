@@ -0,0 +1,294 @@
+//! Generates `Debug` implementations and `introspect`.
+use either::{Left, Right};
+use std::fmt::Write;
+
+use super::{
+    fields, paths, sem, Angle, CommaSeparated, CompBuilderTy, CompStateTy, CompTy, Ctx,
+    FactoryGenParamNameForField, InnerValueField,
+};
+
+/// Generate `<ComponentType as Debug>::fmt`, `<ComponentTypeState as
+/// Debug>::fmt`, and `<ComponentTypeBuilder as Debug>::fmt`.
+pub fn gen_debug_impls(ctx: &Ctx<'_>, out: &mut String) {
+    let comp = ctx.cur_comp;
+    let comp_ident = &comp.ident.sym;
+    let comp_path = &comp.path;
+
+    let comp_fields = comp.items.iter().filter_map(|item| item.field());
+    let const_fields = comp_fields
+        .clone()
+        .filter(|field| field.field_ty == sem::FieldType::Const);
+    let state_fields = comp_fields
+        .clone()
+        .filter(|field| field.field_ty != sem::FieldType::Const);
+
+    // `<ComponentType as Debug>::fmt`
+    // -------------------------------------------------------------------
+    gen_debug_impl_header(ctx, "", CompTy(comp_path), out);
+    writeln!(
+        out,
+        "        let mut s = f.debug_struct({:?});",
+        comp_ident.to_string()
+    )
+    .unwrap();
+    for field in const_fields.clone() {
+        writeln!(
+            out,
+            "        s.field({name:?}, {value});",
+            name = field.ident.sym.to_string(),
+            value = DebugValue(
+                ctx,
+                format!(
+                    "self.{shared}.{field}",
+                    shared = fields::SHARED,
+                    field = InnerValueField(&field.ident.sym)
+                )
+            ),
+        )
+        .unwrap();
+    }
+    if state_fields.clone().next().is_some() {
+        // Do not panic if the state is being mutated (e.g., when the
+        // component is formatted by a handler called during a commit)
+        writeln!(
+            out,
+            "        match self.{shared}.{state}.try_borrow() {{",
+            shared = fields::SHARED,
+            state = fields::STATE,
+        )
+        .unwrap();
+        writeln!(out, "            Ok(state) => {{").unwrap();
+        for field in state_fields.clone() {
+            writeln!(
+                out,
+                "                s.field({name:?}, {value});",
+                name = field.ident.sym.to_string(),
+                value = DebugValue(
+                    ctx,
+                    format!("state.{field}", field = InnerValueField(&field.ident.sym))
+                ),
+            )
+            .unwrap();
+        }
+        writeln!(out, "            }}").unwrap();
+        writeln!(out, "            Err(_) => {{").unwrap();
+        writeln!(out, "                s.field(\"state\", &\"<borrowed>\");").unwrap();
+        writeln!(out, "            }}").unwrap();
+        writeln!(out, "        }}").unwrap();
+    }
+    writeln!(out, "        s.finish()").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    // `<ComponentTypeState as Debug>::fmt`
+    // -------------------------------------------------------------------
+    gen_debug_impl_header(ctx, "", CompStateTy(comp_path), out);
+    writeln!(
+        out,
+        "        f.debug_struct({:?})",
+        CompStateTy(comp_ident).to_string()
+    )
+    .unwrap();
+    for field in state_fields.clone() {
+        writeln!(
+            out,
+            "            .field({name:?}, {value})",
+            name = field.ident.sym.to_string(),
+            value = DebugValue(
+                ctx,
+                format!("self.{field}", field = InnerValueField(&field.ident.sym))
+            ),
+        )
+        .unwrap();
+    }
+    writeln!(out, "            .finish()").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+
+    // `<ComponentTypeBuilder as Debug>::fmt`
+    // -------------------------------------------------------------------
+    let settable_fields = comp_fields
+        .clone()
+        .filter(|field| field.accessors.set.is_some());
+
+    // `T_field1`, `T_field2`, ...
+    let builder_ty_params = settable_fields
+        .clone()
+        .filter(|field| field.value.is_none())
+        .map(|field| FactoryGenParamNameForField(&field.ident.sym));
+
+    // Mandatory parameters are generic. Since their types are unknown here,
+    // they are displayed through `ViaOpaque`, which is still useful to tell
+    // whether they are set or not (`Unset`).
+    let gen = if builder_ty_params.clone().next().is_some() {
+        Left(Angle(CommaSeparated(builder_ty_params)))
+    } else {
+        Right("")
+    };
+
+    writeln!(out, "#[allow(non_camel_case_types)]").unwrap();
+    gen_debug_impl_header(
+        ctx,
+        &gen.to_string(),
+        format!("{}{}", CompBuilderTy(comp_path), gen),
+        out,
+    );
+    writeln!(
+        out,
+        "        f.debug_struct({:?})",
+        CompBuilderTy(comp_ident).to_string()
+    )
+    .unwrap();
+    for field in settable_fields {
+        writeln!(
+            out,
+            "            .field({name:?}, {value})",
+            name = field.ident.sym.to_string(),
+            value = DebugValue(
+                ctx,
+                format!("self.{field}", field = InnerValueField(&field.ident.sym))
+            ),
+        )
+        .unwrap();
+    }
+    writeln!(out, "            .finish()").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+/// Generate `ComponentType::introspect`.
+pub fn gen_introspect(ctx: &Ctx<'_>, out: &mut String) {
+    let comp = ctx.cur_comp;
+    let comp_ident = &comp.ident.sym;
+    let comp_path = &comp.path;
+
+    let comp_fields = comp.items.iter().filter_map(|item| item.field());
+
+    writeln!(out, "impl {} {{", CompTy(comp_path)).unwrap();
+    writeln!(
+        out,
+        "    {}",
+        doc_attr!("Get a snapshot of the component's fields for debugging.")
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    {vis} fn introspect(&self) -> {rt}::CompInfo {{",
+        vis = comp.vis,
+        rt = ctx.designer_runtime_path,
+    )
+    .unwrap();
+    gen_use_debug_traits(ctx, out);
+    if comp_fields
+        .clone()
+        .any(|f| f.field_ty != sem::FieldType::Const)
+    {
+        writeln!(
+            out,
+            "        let state = self.{shared}.{state}.borrow();",
+            shared = fields::SHARED,
+            state = fields::STATE,
+        )
+        .unwrap();
+    }
+    writeln!(out, "        {}::CompInfo {{", ctx.designer_runtime_path).unwrap();
+    writeln!(out, "            name: {:?},", comp_ident.to_string()).unwrap();
+    writeln!(out, "            fields: {}![", paths::VEC).unwrap();
+    for field in comp_fields {
+        let (field_ty, value) = match field.field_ty {
+            sem::FieldType::Const => (
+                "Const",
+                format!(
+                    "self.{shared}.{field}",
+                    shared = fields::SHARED,
+                    field = InnerValueField(&field.ident.sym)
+                ),
+            ),
+            sem::FieldType::Prop => (
+                "Prop",
+                format!("state.{field}", field = InnerValueField(&field.ident.sym)),
+            ),
+            sem::FieldType::Wire => (
+                "Wire",
+                format!("state.{field}", field = InnerValueField(&field.ident.sym)),
+            ),
+        };
+        writeln!(
+            out,
+            "                {}::FieldInfo {{",
+            ctx.designer_runtime_path
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "                    name: {:?},",
+            field.ident.sym.to_string()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "                    field_ty: {}::FieldType::{},",
+            ctx.designer_runtime_path, field_ty
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "                    value: {}!(\"{{:?}}\", {}),",
+            paths::FORMAT,
+            DebugValue(ctx, value)
+        )
+        .unwrap();
+        writeln!(out, "                }},").unwrap();
+    }
+    writeln!(out, "            ],").unwrap();
+    writeln!(out, "        }}").unwrap();
+    writeln!(out, "    }}").unwrap();
+    writeln!(out, "}}").unwrap();
+}
+
+/// Generate `impl Debug for ty { fn fmt(...) {` and bring the traits used by
+/// `DebugValue` into scope.
+fn gen_debug_impl_header(ctx: &Ctx<'_>, gen: &str, ty: impl std::fmt::Display, out: &mut String) {
+    writeln!(
+        out,
+        "impl{gen} {debug} for {ty} {{",
+        gen = gen,
+        debug = paths::DEBUG,
+        ty = ty
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    fn fmt(&self, f: &mut {fmt}::Formatter<'_>) -> {fmt}::Result {{",
+        fmt = paths::FMT,
+    )
+    .unwrap();
+    gen_use_debug_traits(ctx, out);
+}
+
+/// Bring the traits used by `DebugValue` into scope.
+fn gen_use_debug_traits(ctx: &Ctx<'_>, out: &mut String) {
+    // They are unused if there are no fields
+    writeln!(out, "        #[allow(unused_imports)]").unwrap();
+    writeln!(
+        out,
+        "        use {rt}::{{ViaDebug as _, ViaOpaque as _}};",
+        rt = ctx.designer_runtime_path
+    )
+    .unwrap();
+}
+
+/// Evaluates to `&dyn Debug` for the place expression `.1`. Requires
+/// `ViaDebug` and `ViaOpaque` to be in scope.
+struct DebugValue<'a>(&'a Ctx<'a>, String);
+
+impl std::fmt::Display for DebugValue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "(&{rt}::DebugIfAvailable(&{place})).as_debug()",
+            rt = self.0.designer_runtime_path,
+            place = self.1
+        )
+    }
+}
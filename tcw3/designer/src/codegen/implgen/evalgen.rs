@@ -2,7 +2,7 @@
 use quote::ToTokens;
 use std::fmt::Write;
 
-use super::{analysis, paths, sem, CommaSeparatedWithTrailingComma, Ctx};
+use super::{analysis, methods, paths, sem, CommaSeparatedWithTrailingComma, Ctx, TempVar};
 use crate::metadata;
 
 /// Provides a set of methods to generate an expression appropriate in the
@@ -59,69 +59,15 @@ pub fn gen_func_eval(
             continue;
         }
 
-        match analysis.get_input(&func_input.input) {
-            analysis::InputInfo::EventParam(param_input) => {
-                let trigger_i = input_gen.trigger_i();
-                let param_i = param_input.param_i[trigger_i];
-                input_gen.gen_event_param(param_i, func_input.by_ref, out);
-            }
-            analysis::InputInfo::Item(item_input) => {
-                let ind0 = item_input.indirections.first().unwrap();
-                if item_input.indirections.len() == 1 {
-                    // Dereference the current component's field, and that's it
-                    input_gen.gen_field_ref(item_meta2sem_map[ind0.item_i], func_input.by_ref, out);
-                } else {
-                    let ind_last = item_input.indirections.last().unwrap();
-                    // | getter mode | by_ref | output               |
-                    // | ----------- | ------ | -------------------- |
-                    // | borrow      | false  | Clone::clone(&*expr) |
-                    // | borrow      | true   | &*expr               |
-                    // | clone       | false  | expr                 |
-                    // | clone       | true   | &expr                |
-                    let needs_closing_parenthesis = {
-                        let field = ind_last.item(ctx.repo).field().unwrap();
-                        let getter = field.accessors.get.as_ref().unwrap();
-                        match (getter.mode, func_input.by_ref) {
-                            (metadata::FieldGetMode::Borrow, false) => {
-                                write!(out, "{}::clone(&*", paths::CLONE).unwrap();
-                                true
-                            }
-                            (metadata::FieldGetMode::Borrow, true) => {
-                                write!(out, "&*").unwrap();
-                                false
-                            }
-                            (metadata::FieldGetMode::Clone, false) => false,
-                            (metadata::FieldGetMode::Clone, true) => {
-                                write!(out, "&").unwrap();
-                                false
-                            }
-                        }
-                    };
-
-                    // Get the current component's field by reference
-                    input_gen.gen_field_ref(item_meta2sem_map[ind0.item_i], true, out);
-
-                    for ind in item_input.indirections[1..].iter() {
-                        let item = ind.item(ctx.repo).field().unwrap();
-                        write!(out, ".{}()", item.ident).unwrap();
-                    }
-
-                    if needs_closing_parenthesis {
-                        write!(out, ")").unwrap();
-                    }
-                }
-            }
-            analysis::InputInfo::This => {
-                if func_input.by_ref {
-                    input_gen.gen_this(out);
-                } else {
-                    write!(out, "{}::clone(", paths::CLONE).unwrap();
-                    input_gen.gen_this(out);
-                    write!(out, ")").unwrap();
-                }
-            }
-            analysis::InputInfo::Invalid => {}
-        }
+        gen_input_value(
+            func_input,
+            func_input.by_ref,
+            analysis,
+            ctx,
+            item_meta2sem_map,
+            input_gen,
+            out,
+        );
         write!(out, ", ").unwrap();
     }
     write!(out, ")").unwrap();
@@ -141,3 +87,207 @@ pub fn gen_func_eval(
     )
     .unwrap();
 }
+
+/// The form of an expression generated by `gen_detached_func_eval`.
+pub enum DetachedFunc<'a> {
+    /// `async move { ... }`. The body of the `Func` must be an `async` block.
+    Future,
+    /// `move |_: Wm| { ... }`
+    Closure,
+    /// `move |wm: Wm| spawn_local(wm, async move { ... })`. The body of the
+    /// `Func` must be an `async` block.
+    ClosureSpawningFuture {
+        /// The path to `Wm::spawn_local`.
+        spawn_local: &'a dyn std::fmt::Display,
+    },
+}
+
+/// Generates an expression that evaluates to a `'static` future or closure
+/// that evaluates the given `Func` later.
+///
+/// All inputs are evaluated by value when the expression is evaluated, and
+/// the future or closure takes the ownership of them. By-ref inputs refer to
+/// the owned values. The component (`get!(self)` or `get!(&self)`) is
+/// captured as a weak reference, and the body is not run if the component
+/// is gone by the time the body is about to be run.
+pub fn gen_detached_func_eval(
+    func: &sem::Func,
+    form: DetachedFunc<'_>,
+    analysis: &analysis::Analysis,
+    ctx: &Ctx,
+    item_meta2sem_map: &[usize],
+    input_gen: &mut dyn FuncInputGen,
+    out: &mut String,
+) {
+    let input_var = |i: usize| TempVar(format!("input_{}", i));
+
+    let body = match (&form, &func.body) {
+        (DetachedFunc::Closure, body) => format!("{{ {} }}", body.to_token_stream()),
+        (_, syn::Expr::Async(syn::ExprAsync { block, .. })) => block.to_token_stream().to_string(),
+        (_, _) => unreachable!(),
+    };
+
+    let func_inputs = func
+        .inputs
+        .iter()
+        .enumerate()
+        .filter(|(_, func_input)| analysis.get_input(&func_input.input).has_value(ctx.repo));
+    let is_this = |func_input: &sem::FuncInput| match analysis.get_input(&func_input.input) {
+        analysis::InputInfo::This => true,
+        _ => false,
+    };
+
+    // `match` input
+    write!(out, "match (").unwrap();
+    for (_, func_input) in func_inputs.clone() {
+        if is_this(func_input) {
+            input_gen.gen_this(out);
+            write!(out, ".{}()", methods::DOWNGRADE).unwrap();
+        } else {
+            gen_input_value(
+                func_input,
+                false,
+                analysis,
+                ctx,
+                item_meta2sem_map,
+                input_gen,
+                out,
+            );
+        }
+        write!(out, ", ").unwrap();
+    }
+    write!(out, ")").unwrap();
+
+    // `match` pattern
+    write!(
+        out,
+        " {{ ({args}) => ",
+        args = CommaSeparatedWithTrailingComma(func_inputs.clone().map(|(i, _)| input_var(i))),
+    )
+    .unwrap();
+    match form {
+        DetachedFunc::Future => write!(out, "async move {{ "),
+        DetachedFunc::Closure => write!(out, "move |_| {{ "),
+        DetachedFunc::ClosureSpawningFuture { spawn_local } => write!(
+            out,
+            "move |{wm}| {spawn_local}({wm}, async move {{ ",
+            wm = TempVar("wm"),
+            spawn_local = spawn_local
+        ),
+    }
+    .unwrap();
+
+    // Upgrade the weak reference(s) to the component
+    for (i, _) in func_inputs
+        .clone()
+        .filter(|(_, func_input)| is_this(func_input))
+    {
+        write!(
+            out,
+            "let {var} = match {var}.{upgrade}() {{ {some}(x) => x, {none} => return }}; ",
+            var = input_var(i),
+            upgrade = methods::UPGRADE,
+            some = paths::SOME,
+            none = paths::NONE,
+        )
+        .unwrap();
+    }
+
+    // Bind the inputs to the names used in the body
+    write!(out, "match (").unwrap();
+    for (i, func_input) in func_inputs.clone() {
+        if func_input.by_ref {
+            write!(out, "&").unwrap();
+        }
+        write!(out, "{}, ", input_var(i)).unwrap();
+    }
+    write!(
+        out,
+        ") {{ ({args}) => {body} }} }}",
+        args = CommaSeparatedWithTrailingComma(
+            func_inputs.map(|(_, func_input)| &func_input.ident.sym)
+        ),
+        body = body,
+    )
+    .unwrap();
+    if let DetachedFunc::ClosureSpawningFuture { .. } = form {
+        write!(out, ")").unwrap();
+    }
+    write!(out, " }}").unwrap();
+}
+
+/// Generates an expression that evaluates to the value or reference of the
+/// given input.
+fn gen_input_value(
+    func_input: &sem::FuncInput,
+    by_ref: bool,
+    analysis: &analysis::Analysis,
+    ctx: &Ctx,
+    item_meta2sem_map: &[usize],
+    input_gen: &mut dyn FuncInputGen,
+    out: &mut String,
+) {
+    match analysis.get_input(&func_input.input) {
+        analysis::InputInfo::EventParam(param_input) => {
+            let trigger_i = input_gen.trigger_i();
+            let param_i = param_input.param_i[trigger_i];
+            input_gen.gen_event_param(param_i, by_ref, out);
+        }
+        analysis::InputInfo::Item(item_input) => {
+            let ind0 = item_input.indirections.first().unwrap();
+            if item_input.indirections.len() == 1 {
+                // Dereference the current component's field, and that's it
+                input_gen.gen_field_ref(item_meta2sem_map[ind0.item_i], by_ref, out);
+            } else {
+                let ind_last = item_input.indirections.last().unwrap();
+                // | getter mode | by_ref | output               |
+                // | ----------- | ------ | -------------------- |
+                // | borrow      | false  | Clone::clone(&*expr) |
+                // | borrow      | true   | &*expr               |
+                // | clone       | false  | expr                 |
+                // | clone       | true   | &expr                |
+                let needs_closing_parenthesis = {
+                    let field = ind_last.item(ctx.repo).field().unwrap();
+                    let getter = field.accessors.get.as_ref().unwrap();
+                    match (getter.mode, by_ref) {
+                        (metadata::FieldGetMode::Borrow, false) => {
+                            write!(out, "{}::clone(&*", paths::CLONE).unwrap();
+                            true
+                        }
+                        (metadata::FieldGetMode::Borrow, true) => {
+                            write!(out, "&*").unwrap();
+                            false
+                        }
+                        (metadata::FieldGetMode::Clone, false) => false,
+                        (metadata::FieldGetMode::Clone, true) => {
+                            write!(out, "&").unwrap();
+                            false
+                        }
+                    }
+                };
+
+                // Get the current component's field by reference
+                input_gen.gen_field_ref(item_meta2sem_map[ind0.item_i], true, out);
+
+                for ind in item_input.indirections[1..].iter() {
+                    let item = ind.item(ctx.repo).field().unwrap();
+                    write!(out, ".{}()", item.ident).unwrap();
+                }
+
+                if needs_closing_parenthesis {
+                    write!(out, ")").unwrap();
+                }
+            }
+        }
+        analysis::InputInfo::This => {
+            if by_ref {
+                input_gen.gen_this(out);
+            } else {
+                write!(out, "{}::clone(", paths::CLONE).unwrap();
+                input_gen.gen_this(out);
+                write!(out, ")").unwrap();
+            }
+        }
+        analysis::InputInfo::Invalid => {}
+    }
+}
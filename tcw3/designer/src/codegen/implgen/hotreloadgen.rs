@@ -0,0 +1,210 @@
+//! Generates code for `designer_runtime::hot_reload`.
+use quote::ToTokens;
+use std::fmt::Write;
+
+use super::{
+    super::diag::Diag, fields, methods, paths, sem, CompTy, Ctx, InnerValueField, SetterMethod,
+};
+use crate::metadata;
+
+const SITES: &str = "HOT_RELOAD_SITES";
+
+/// A literal value of a `prop` in an object initialization literal, which can
+/// be updated by `hot_reload`.
+struct Site<'a> {
+    /// The `const` field having the object initialization literal.
+    field: &'a sem::FieldDef<'a>,
+    /// The `prop` of the subcomponent.
+    subfield: &'a sem::Ident,
+    lit: &'a syn::Expr,
+    /// The method of `hot_reload::Literal` converting the literal to the
+    /// value passed to the setter.
+    conv: String,
+}
+
+fn find_sites<'a>(ctx: &Ctx<'a>) -> Vec<Site<'a>> {
+    let comp = ctx.cur_comp;
+    let mut sites = Vec::new();
+
+    for field in comp.items.iter().filter_map(|item| item.field()) {
+        let obj_init = match (field.field_ty, &field.value) {
+            (sem::FieldType::Const, Some(sem::DynExpr::ObjInit(x))) => x,
+            _ => continue,
+        };
+
+        let target_comp = match ctx.find_comp_by_path(&obj_init.path.syn_path) {
+            Some(x) => ctx.repo.comp_by_ref(&x),
+            None => continue,
+        };
+
+        for obj_field in obj_init.fields.iter() {
+            if !obj_field.value.inputs.is_empty() {
+                continue;
+            }
+
+            // The subcomponent's field must be a settable `prop`
+            let is_settable_prop = target_comp
+                .find_item_by_ident(&obj_field.ident.sym)
+                .and_then(|(_, item)| item.field())
+                .map_or(false, |f| {
+                    f.field_ty == metadata::FieldType::Prop && f.accessors.set.is_some()
+                });
+            if !is_settable_prop {
+                continue;
+            }
+
+            if let Some(conv) = literal_conversion(&obj_field.value.body) {
+                sites.push(Site {
+                    field,
+                    subfield: &obj_field.ident,
+                    lit: &obj_field.value.body,
+                    conv,
+                });
+            }
+        }
+    }
+
+    sites
+}
+
+/// Get the method of `hot_reload::Literal` that produces a value of the same
+/// type as `expr`. Returns `None` if `expr` is not a supported literal
+/// expression.
+fn literal_conversion(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(lit) => match &lit.lit {
+            syn::Lit::Str(_) => Some("to_str()".to_owned()),
+            syn::Lit::Bool(_) => Some("to_bool()".to_owned()),
+            syn::Lit::Char(_) => Some("to_char()".to_owned()),
+            // An unsuffixed literal gets its type from the setter
+            syn::Lit::Int(x) if x.suffix().is_empty() => Some("to_num()".to_owned()),
+            syn::Lit::Int(x) => Some(format!("to_num::<{}>()", x.suffix())),
+            syn::Lit::Float(x) if x.suffix().is_empty() => Some("to_num()".to_owned()),
+            syn::Lit::Float(x) => Some(format!("to_num::<{}>()", x.suffix())),
+            _ => None,
+        },
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => match &**expr {
+            syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(_),
+                ..
+            })
+            | syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Float(_),
+                ..
+            }) => literal_conversion(expr),
+            _ => None,
+        },
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => literal_conversion(expr),
+        _ => None,
+    }
+}
+
+/// Generate `static HOT_RELOAD_SITES: hot_reload::CompSites<_>` if the
+/// component has any literals to hot-reload.
+pub fn gen_sites(ctx: &Ctx<'_>, diag: &Diag<'_>, out: &mut String) {
+    let comp = ctx.cur_comp;
+    let sites = find_sites(ctx);
+    if sites.is_empty() {
+        return;
+    }
+
+    let file = if let Some(span) = comp.path.span {
+        diag.look_up_pos(span.low()).name
+    } else {
+        return;
+    };
+
+    let comp_path: String = (comp.path.syn_path.to_token_stream().to_string())
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    writeln!(
+        out,
+        "static {sites}: {rt}::hot_reload::CompSites<{ty}> = {rt}::hot_reload::CompSites {{",
+        sites = SITES,
+        rt = ctx.designer_runtime_path,
+        ty = CompTy(&comp.path),
+    )
+    .unwrap();
+    writeln!(out, "    file: {:?},", file).unwrap();
+    writeln!(out, "    comp: {:?},", comp_path).unwrap();
+    writeln!(out, "    sites: &[").unwrap();
+    for site in sites.iter() {
+        writeln!(
+            out,
+            "        {}::hot_reload::Site {{",
+            ctx.designer_runtime_path
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "            field: {:?},",
+            site.field.ident.sym.to_string()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "            subfield: {:?},",
+            site.subfield.sym.to_string()
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "            compiled: {:?},",
+            site.lit.to_token_stream().to_string()
+        )
+        .unwrap();
+        writeln!(out, "            apply: |this, lit| {{").unwrap();
+        writeln!(
+            out,
+            "                if let {some}(value) = lit.{conv} {{",
+            some = paths::SOME,
+            conv = site.conv,
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "                    this.{shared}.{field}.{setter}(value);",
+            shared = fields::SHARED,
+            field = InnerValueField(&site.field.ident.sym),
+            setter = SetterMethod(&site.subfield.sym),
+        )
+        .unwrap();
+        writeln!(out, "                }}").unwrap();
+        writeln!(out, "            }},").unwrap();
+        writeln!(out, "        }},").unwrap();
+    }
+    writeln!(out, "    ],").unwrap();
+    writeln!(out, "}};").unwrap();
+}
+
+/// Generate a statement registering the newly constructed component `this` to
+/// `hot_reload`. Must be consistent with `gen_sites`.
+pub fn gen_register(ctx: &Ctx<'_>, var_this: &impl std::fmt::Display, out: &mut String) {
+    if find_sites(ctx).is_empty() || ctx.cur_comp.path.span.is_none() {
+        return;
+    }
+
+    writeln!(out, "{{").unwrap();
+    writeln!(
+        out,
+        "    let weak = {this}.{downgrade}();",
+        this = var_this,
+        downgrade = methods::DOWNGRADE,
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    {rt}::hot_reload::register(&{sites}, move || weak.{upgrade}());",
+        rt = ctx.designer_runtime_path,
+        sites = SITES,
+        upgrade = methods::UPGRADE,
+    )
+    .unwrap();
+    writeln!(out, "}}").unwrap();
+}
@@ -11,11 +11,10 @@ use try_match::try_match;
 
 use super::super::{diag::Diag, sem, EmittedError};
 use super::{
-    analysis,
-    bitsetgen::{self, BitsetTy},
-    evalgen, fields, known_fields, methods, paths, CommaSeparated, CompBuilderTy, CompSharedTy,
-    CompStateTy, CompTy, Ctx, EventInnerSubList, FactorySetterForField, InnerValueField,
-    RaiseMethod, SetterMethod, SubscribeMethod, TempVar,
+    analysis, bitsetgen::BitsetTy, evalgen, fields, hotreloadgen, known_fields, methods, paths,
+    CommaSeparated, CompBuilderTy, CompSharedTy, CompStateTy, CompTy, Ctx, EventInnerSubList,
+    FactorySetterForField, InnerValueField, RaiseMethod, RateLimiterField, SetterMethod,
+    SubscribeMethod, TempVar,
 };
 use crate::metadata;
 
@@ -175,6 +174,11 @@ fn analyze_dep(
                         Some(sem::DynExpr::Func(func)) => {
                             push_func_deps(&mut deps, func);
                         }
+                        Some(sem::DynExpr::Mount(mount)) => {
+                            for func in mount.funcs() {
+                                push_func_deps(&mut deps, func);
+                            }
+                        }
                         Some(sem::DynExpr::ObjInit(_)) => {
                             // In `nodes`, this node is followed by zero or more
                             // `DepNode::ObjInitField` nodes
@@ -500,12 +504,23 @@ fn analyze_dep(
                 }
                 sem::FieldType::Wire => {
                     let node_i = commit_nodes.len();
-                    // `wire` must have a value. `DynExpr::ObjInit` is only allowed
-                    // for `FieldType::Const`, so it must be `DynExpr::Func`.
-                    let func = item.value.as_ref().unwrap().func().unwrap();
 
                     trigger_emitted.set(false);
-                    define_func_trigger(&mut trigger_info, diag, func, node_i);
+
+                    // `wire` must have a value. `DynExpr::ObjInit` is only allowed
+                    // for `FieldType::Const`, so it must be `DynExpr::Func` or
+                    // `DynExpr::Mount`.
+                    match item.value.as_ref().unwrap() {
+                        sem::DynExpr::Func(func) => {
+                            define_func_trigger(&mut trigger_info, diag, func, node_i);
+                        }
+                        sem::DynExpr::Mount(mount) => {
+                            for func in mount.funcs() {
+                                define_func_trigger(&mut trigger_info, diag, func, node_i);
+                            }
+                        }
+                        sem::DynExpr::ObjInit(_) => unreachable!(),
+                    }
 
                     // Emit a node only if it has a trigger
                     if trigger_emitted.get() {
@@ -680,28 +695,7 @@ fn analyze_dep(
 
     // Construct a `BitsetTy` that represents a run-time type large enough to
     // store all the CDFs.
-    let cdf_ty = match BitsetTy::new(cdf2triggerset.len()) {
-        Ok(x) => x,
-        Err(bitsetgen::TooLargeError) => {
-            diag.emit(&[Diagnostic {
-                level: Level::Error,
-                message: "The component requires more dirty flags than \
-                          currently supported by the code generator"
-                    .to_string(),
-                code: None,
-                spans: (comp.path.span)
-                    .map(|span| SpanLabel {
-                        span,
-                        label: None,
-                        style: SpanStyle::Primary,
-                    })
-                    .into_iter()
-                    .collect(),
-            }]);
-
-            BitsetTy::new(0).unwrap()
-        }
-    };
+    let cdf_ty = BitsetTy::new(cdf2triggerset.len());
 
     // Compile the list of events to subscribe
     // ----------------------------------------------------------------------
@@ -768,7 +762,12 @@ fn analyze_dep(
     let needs_wm = trigger_info.triggers.iter().any(|tr| match tr {
         CommitTrigger::Event { .. } | CommitTrigger::SetItem { .. } => true,
         CommitTrigger::WatchField { .. } => false,
-    }) || !input2handlers.is_empty();
+    }) || !input2handlers.is_empty()
+        || comp
+            .items
+            .iter()
+            .filter_map(|item| item.on())
+            .any(|on| on.is_async || on.rate_limit.is_some());
 
     if needs_wm {
         let item_i = item_name_map.get(known_fields::WM);
@@ -993,7 +992,17 @@ pub fn gen_construct(
                             )
                             .unwrap();
                         }
-                        _ => {}
+                        sem::CompItemDef::On(item) => {
+                            if item.rate_limit.is_some() {
+                                writeln!(
+                                    out,
+                                    "    {ident}: {def}::default(),",
+                                    ident = RateLimiterField(i),
+                                    def = paths::DEFAULT,
+                                )
+                                .unwrap();
+                            }
+                        }
                     }
                 }
                 writeln!(
@@ -1099,7 +1108,34 @@ pub fn gen_construct(
                                 out,
                             );
                         } else {
-                            write!(out, "panic!(\"codegen failed\")").unwrap();
+                            // The analysis has already reported an error, and
+                            // `gen_comp` will discard the generated code
+                        }
+                    }
+                    sem::DynExpr::Mount(mount) => {
+                        let obj_init_info = analysis.obj_inits[*item_i].as_ref().unwrap();
+
+                        if obj_init_info.comp_ref.is_some() {
+                            // There are no existing instances to reuse
+                            let old = match &mount.kind {
+                                sem::MountKind::If { .. } => paths::NONE,
+                                sem::MountKind::For { .. } => "[]",
+                            };
+                            write!(out, "(").unwrap();
+                            gen_mount_eval(
+                                mount,
+                                obj_init_info,
+                                &format_args!("&{}", old),
+                                analysis,
+                                ctx,
+                                item_meta2sem_map,
+                                &mut func_input_gen,
+                                out,
+                            );
+                            write!(out, ").0").unwrap();
+                        } else {
+                            // The analysis has already reported an error, and
+                            // `gen_comp` will discard the generated code
                         }
                     }
                 }
@@ -1337,16 +1373,17 @@ pub fn gen_construct(
                             let on = comp.items[*item_i].on().unwrap();
                             func_input_gen2.on_trigger_i = *on_trigger_i;
 
-                            write!(code_frag, "    (").unwrap();
-                            evalgen::gen_func_eval(
-                                &on.func,
+                            write!(code_frag, "    ").unwrap();
+                            gen_on_func_eval(
+                                on,
+                                *item_i,
                                 analysis,
                                 ctx,
                                 item_meta2sem_map,
                                 &mut func_input_gen2,
+                                &var_shared,
                                 &mut code_frag,
                             );
-                            writeln!(code_frag, ");").unwrap();
                         }
                     }
                 }
@@ -1401,7 +1438,12 @@ pub fn gen_construct(
     // Activate `init` trigger
     // ----------------------------------------------------------------------
 
-    for item in comp.items.iter().filter_map(|item| item.on()) {
+    for (item_i, item) in comp
+        .items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| Some((i, item.on()?)))
+    {
         let has_init_trigger = item
             .triggers
             .iter()
@@ -1410,16 +1452,16 @@ pub fn gen_construct(
             continue;
         }
 
-        write!(postinit_code, "(").unwrap();
-        evalgen::gen_func_eval(
-            &item.func,
+        gen_on_func_eval(
+            item,
+            item_i,
             analysis,
             ctx,
             item_meta2sem_map,
             &mut func_input_gen,
+            &var_shared,
             &mut postinit_code,
         );
-        writeln!(postinit_code, ");").unwrap();
     }
 
     if func_input_gen.needs_state {
@@ -1443,9 +1485,81 @@ pub fn gen_construct(
         write!(out, "{}", postinit_code).unwrap();
     }
 
+    if ctx.hot_reload {
+        hotreloadgen::gen_register(ctx, &var_this, out);
+    }
+
     writeln!(out, "{}", var_this).unwrap();
 }
 
+/// Generate a statement that evaluates the handler function of the `on` item.
+/// If the handler is `async`, the statement spawns the returned future. If
+/// the handler has a rate limit, the statement passes a closure to the rate
+/// limiter.
+#[allow(clippy::too_many_arguments)]
+fn gen_on_func_eval(
+    on: &sem::OnDef<'_>,
+    item_i: usize,
+    analysis: &analysis::Analysis,
+    ctx: &Ctx,
+    item_meta2sem_map: &[usize],
+    input_gen: &mut dyn evalgen::FuncInputGen,
+    var_shared: &dyn std::fmt::Display,
+    out: &mut String,
+) {
+    let path_spawn_local = ctx.path_spawn_local();
+
+    if let Some(rate_limit) = &on.rate_limit {
+        write!(
+            out,
+            "{shared}.{limiter}.call({shared}.{wm}, {duration}::from_millis({ms}), ",
+            shared = var_shared,
+            limiter = RateLimiterField(item_i),
+            wm = InnerValueField(known_fields::WM),
+            duration = paths::DURATION,
+            ms = rate_limit.duration.as_millis(),
+        )
+        .unwrap();
+        evalgen::gen_detached_func_eval(
+            &on.func,
+            if on.is_async {
+                evalgen::DetachedFunc::ClosureSpawningFuture {
+                    spawn_local: &path_spawn_local,
+                }
+            } else {
+                evalgen::DetachedFunc::Closure
+            },
+            analysis,
+            ctx,
+            item_meta2sem_map,
+            input_gen,
+            out,
+        );
+    } else if on.is_async {
+        write!(
+            out,
+            "{spawn_local}({shared}.{wm}, ",
+            spawn_local = path_spawn_local,
+            shared = var_shared,
+            wm = InnerValueField(known_fields::WM),
+        )
+        .unwrap();
+        evalgen::gen_detached_func_eval(
+            &on.func,
+            evalgen::DetachedFunc::Future,
+            analysis,
+            ctx,
+            item_meta2sem_map,
+            input_gen,
+            out,
+        );
+    } else {
+        write!(out, "(").unwrap();
+        evalgen::gen_func_eval(&on.func, analysis, ctx, item_meta2sem_map, input_gen, out);
+    }
+    writeln!(out, ");").unwrap();
+}
+
 /// Generate code to subscribe to the event specified by `item_input` by
 /// registering `expr` as the event handler.
 fn gen_subscribe_event(
@@ -1581,6 +1695,97 @@ fn gen_obj_init(
     }
 }
 
+/// Generate an expression that instantiates, reuses, or drops components as
+/// specified by `mount` and evaluates to a tuple `(T, bool)`. The first element
+/// is the new value of the field. The second element indicates whether any
+/// instances were created, dropped, or reordered.
+///
+/// `expr_old` is an expression that evaluates to a reference to the old value
+/// of the field. The instances in the old value are reused and have their
+/// `prop`s updated. Their `const`s are evaluated only when created.
+#[allow(clippy::too_many_arguments)]
+fn gen_mount_eval(
+    mount: &sem::Mount,
+    obj_init_info: &analysis::ObjInitInfo,
+    expr_old: &dyn std::fmt::Display,
+    analysis: &analysis::Analysis,
+    ctx: &Ctx,
+    item_meta2sem_map: &[usize],
+    input_gen: &mut impl evalgen::FuncInputGen,
+    out: &mut String,
+) {
+    let comp = ctx
+        .repo
+        .comp_by_ref(obj_init_info.comp_ref.as_ref().unwrap());
+    let var_instance = TempVar("instance");
+
+    let key_param = match &mount.kind {
+        sem::MountKind::If { cond } => {
+            write!(out, "{}({}, ", ctx.path_mount_if(), expr_old).unwrap();
+            evalgen::gen_func_eval(cond, analysis, ctx, item_meta2sem_map, input_gen, out);
+            String::new()
+        }
+        sem::MountKind::For { ident, iter } => {
+            write!(out, "{}({}, ", ctx.path_mount_for(), expr_old).unwrap();
+            evalgen::gen_func_eval(iter, analysis, ctx, item_meta2sem_map, input_gen, out);
+            // The loop variable might be unused by the fields
+            format!("#[allow(unused_variables)] {}", ident.sym)
+        }
+    };
+
+    // Update the `prop`s of an existing instance. The closure is checked
+    // before `create`, so the instance type must be specified here.
+    writeln!(
+        out,
+        ", |{key}{comma}{inst}: &{ty}| {{",
+        key = key_param,
+        comma = if key_param.is_empty() { "" } else { ", " },
+        inst = var_instance,
+        ty = CompTy(&mount.obj_init.path),
+    )
+    .unwrap();
+    for (obj_field, item_i) in mount.obj_init.fields.iter().zip(&obj_init_info.item_i_list) {
+        let field = if let Some(x) = item_i.and_then(|i| comp.items[i].field()) {
+            x
+        } else {
+            continue;
+        };
+        if field.field_ty != metadata::FieldType::Prop {
+            continue;
+        }
+
+        write!(
+            out,
+            "    {inst}.{setter}(",
+            inst = var_instance,
+            setter = SetterMethod(&obj_field.ident.sym),
+        )
+        .unwrap();
+        evalgen::gen_func_eval(
+            &obj_field.value,
+            analysis,
+            ctx,
+            item_meta2sem_map,
+            input_gen,
+            out,
+        );
+        writeln!(out, ");").unwrap();
+    }
+
+    // Create a new instance
+    write!(out, "}}, |{}| ", key_param).unwrap();
+    gen_obj_init(
+        &mount.obj_init,
+        obj_init_info,
+        analysis,
+        ctx,
+        item_meta2sem_map,
+        input_gen,
+        out,
+    );
+    write!(out, ")").unwrap();
+}
+
 /// Generate `xxxShared::set_dirty_flags` (`methods::SET_DIRTY_FLAGS`).
 pub fn gen_set_dirty_flags(dep_analysis: &DepAnalysis, ctx: &Ctx<'_>, out: &mut String) {
     let comp_path = &ctx.cur_comp.path;
@@ -1676,6 +1881,50 @@ pub fn gen_set_dirty_flags(dep_analysis: &DepAnalysis, ctx: &Ctx<'_>, out: &mut
     writeln!(out, "    }}",).unwrap();
 }
 
+/// Generate `xxx::set_props` (`methods::SET_PROPS`).
+pub fn gen_set_props(ctx: &Ctx<'_>, out: &mut String) {
+    let comp = ctx.cur_comp;
+
+    let has_settable_props = comp.items.iter().any(|item| match item.field() {
+        Some(field) => field.field_ty == sem::FieldType::Prop && field.accessors.set.is_some(),
+        None => false,
+    });
+
+    if !has_settable_props {
+        return;
+    }
+
+    writeln!(
+        out,
+        "    {}",
+        doc_attr!(
+            "Call the given function (which usually calls setters) and then \
+             commit the changes immediately."
+        )
+    )
+    .unwrap();
+    writeln!(out, "    {}", doc_attr!("")).unwrap();
+    writeln!(
+        out,
+        "    {}",
+        doc_attr!(
+            "The changes are committed at once, so dependent fields are \
+             recalculated and `on` handlers are called only once."
+        )
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    {vis} fn {meth}(&self, f: impl FnOnce(&Self)) {{",
+        vis = comp.vis,
+        meth = methods::SET_PROPS,
+    )
+    .unwrap();
+    writeln!(out, "        f(self);").unwrap();
+    writeln!(out, "        self.{}();", methods::COMMIT).unwrap();
+    writeln!(out, "    }}").unwrap();
+}
+
 /// Generate `xxx::__commit` (`methods::COMMIT`).
 pub fn gen_commit(
     analysis: &analysis::Analysis,
@@ -1855,26 +2104,53 @@ pub fn gen_commit(
 
                     match field.field_ty {
                         sem::FieldType::Wire => {
-                            // Derive the fresh value
-                            gen!("    let {} = ", var_fresh_value);
-                            evalgen::gen_func_eval(
-                                field.value.as_ref().unwrap().func().unwrap(),
-                                analysis,
-                                ctx,
-                                item_meta2sem_map,
-                                &mut func_input_gen,
-                                out,
-                            );
-                            writeln!(out, ";\n").unwrap();
+                            // Derive the fresh value and an expression
+                            // indicating whether the value has changed
+                            let has_changed = match field.value.as_ref().unwrap() {
+                                sem::DynExpr::Func(func) => {
+                                    gen!("    let {} = ", var_fresh_value);
+                                    evalgen::gen_func_eval(
+                                        func,
+                                        analysis,
+                                        ctx,
+                                        item_meta2sem_map,
+                                        &mut func_input_gen,
+                                        out,
+                                    );
+                                    writeln!(out, ";\n").unwrap();
+
+                                    format!(
+                                        "!{}::shallow_eq(&{}, {})",
+                                        ctx.path_shallow_eq(),
+                                        var_fresh_value,
+                                        var_latest(*item_i)
+                                    )
+                                }
+                                sem::DynExpr::Mount(mount) => {
+                                    // Instances can't be compared, so use
+                                    // the flag returned by `mount_*` instead
+                                    let var_changed = TempVar("changed");
+                                    gen!("    let ({}, {}) = ", var_fresh_value, var_changed);
+                                    gen_mount_eval(
+                                        mount,
+                                        analysis.obj_inits[*item_i].as_ref().unwrap(),
+                                        &var_latest(*item_i),
+                                        analysis,
+                                        ctx,
+                                        item_meta2sem_map,
+                                        &mut func_input_gen,
+                                        out,
+                                    );
+                                    writeln!(out, ";\n").unwrap();
+
+                                    var_changed.to_string()
+                                }
+                                sem::DynExpr::ObjInit(_) => unreachable!(),
+                            };
 
                             if !bit_i_list.is_empty() {
                                 // Set CDFs if the value has changed
-                                genln!(
-                                    "    if !{}::shallow_eq(&{}, {}) {{",
-                                    ctx.path_shallow_eq(),
-                                    var_fresh_value,
-                                    var_latest(*item_i)
-                                );
+                                genln!("    if {} {{", has_changed);
                                 genln!(
                                     "        {};",
                                     cdf_ty.gen_insert(var_dirty, bit_i_list.iter().cloned())
@@ -1934,16 +2210,17 @@ pub fn gen_commit(
                 }
                 CommitNode::On { item_i } => {
                     let on = comp.items[*item_i].on().unwrap();
-                    gen!("(");
-                    evalgen::gen_func_eval(
-                        &on.func,
+                    gen!("");
+                    gen_on_func_eval(
+                        on,
+                        *item_i,
                         analysis,
                         ctx,
                         item_meta2sem_map,
                         &mut func_input_gen,
+                        &var_shared,
                         out,
                     );
-                    writeln!(out, ");\n").unwrap();
                 }
                 CommitNode::Event { .. } => {
                     // Events are raised after storing the final values
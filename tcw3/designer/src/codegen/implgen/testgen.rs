@@ -0,0 +1,152 @@
+//! Generates `#[cfg(test)]` helpers.
+use quote::ToTokens;
+use std::fmt::Write;
+
+use super::{
+    fields, known_fields, sem, CommaSeparated, CompBuilderTy, CompTy, Ctx, FactorySetterForField,
+    InnerValueField, RaiseMethod,
+};
+
+/// Generate `#[cfg(test)] impl ComponentType { ... }`.
+pub fn gen_test_harness(ctx: &Ctx<'_>, out: &mut String) {
+    let comp = ctx.cur_comp;
+    let comp_path = &comp.path;
+
+    let comp_fields = comp.items.iter().filter_map(|item| item.field());
+
+    writeln!(out, "#[cfg(test)]").unwrap();
+    writeln!(out, "#[allow(dead_code)]").unwrap();
+    writeln!(out, "impl {} {{", CompTy(comp_path)).unwrap();
+
+    // `new_for_testing` and `run_test_with` can be generated only if
+    // the builder doesn't require anything other than `wm`
+    let wm_field = comp_fields
+        .clone()
+        .find(|field| field.ident.sym == known_fields::WM && field.accessors.set.is_some());
+    let has_other_mandatory_fields = comp_fields.clone().any(|field| {
+        field.accessors.set.is_some()
+            && field.value.is_none()
+            && field.ident.sym != known_fields::WM
+    });
+
+    if !has_other_mandatory_fields {
+        writeln!(
+            out,
+            "    {}",
+            doc_attr!("Construct the component using the given `Wm`.")
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub(crate) fn new_for_testing({wm}: {tcw3}::pal::Wm) -> Self {{",
+            wm = if wm_field.is_some() { "wm" } else { "_wm" },
+            tcw3 = ctx.tcw3_path,
+        )
+        .unwrap();
+        write!(out, "        {}::new()", CompBuilderTy(comp_path)).unwrap();
+        if let Some(field) = wm_field {
+            write!(out, ".{}(wm)", FactorySetterForField(&field.ident.sym)).unwrap();
+        }
+        writeln!(out, ".build()").unwrap();
+        writeln!(out, "    }}").unwrap();
+
+        writeln!(
+            out,
+            "    {}",
+            doc_attr!(
+                "Construct the component using the testing backend and pass \
+                 it to the given function. See `tcw3::pal::testing::run_test`."
+            )
+        )
+        .unwrap();
+        writeln!(out, "    pub(crate) fn run_test_with(").unwrap();
+        writeln!(
+            out,
+            "        cb: impl FnOnce(&dyn {tcw3}::pal::testing::TestingWm, Self) \
+             + Send + ::std::panic::UnwindSafe + 'static,",
+            tcw3 = ctx.tcw3_path,
+        )
+        .unwrap();
+        writeln!(out, "    ) {{").unwrap();
+        writeln!(
+            out,
+            "        {tcw3}::pal::testing::run_test(move |twm| {{",
+            tcw3 = ctx.tcw3_path,
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "            let this = Self::new_for_testing(\
+             {tcw3}::pal::testing::TestingWm::wm(twm));",
+            tcw3 = ctx.tcw3_path,
+        )
+        .unwrap();
+        writeln!(out, "            cb(twm, this)").unwrap();
+        writeln!(out, "        }});").unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+
+    // Subcomponent accessors
+    let meta_comp = ctx.cur_meta_comp();
+    for field in comp_fields.filter(|field| field.field_ty == sem::FieldType::Const) {
+        let is_comp = meta_comp
+            .find_item_by_ident(&field.ident.sym)
+            .and_then(|(_, item)| item.field())
+            .map_or(false, |field| field.ty.is_some());
+        if !is_comp {
+            continue;
+        }
+
+        writeln!(
+            out,
+            "    {}",
+            doc_attr!("Get the subcomponent `{}`.", field.ident.sym)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub(crate) fn testing_{ident}(&self) -> &{ty} {{",
+            ident = field.ident.sym,
+            ty = field.ty.as_ref().unwrap().to_token_stream(),
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "        &self.{shared}.{field}",
+            shared = fields::SHARED,
+            field = InnerValueField(&field.ident.sym),
+        )
+        .unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+
+    // Event triggers
+    for event in comp.items.iter().filter_map(|item| item.event()) {
+        writeln!(
+            out,
+            "    {}",
+            doc_attr!("Raise the event `{}`.", event.ident.sym)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    pub(crate) fn testing_raise_{ident}(&self, {args}) {{",
+            ident = event.ident.sym,
+            args = CommaSeparated(event.inputs.iter().map(|arg| arg.to_token_stream())),
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "        self.{meth}({args})",
+            meth = RaiseMethod(&event.ident.sym),
+            args = CommaSeparated(event.inputs.iter().map(|arg| match arg {
+                syn::FnArg::Receiver(_) => unreachable!(),
+                syn::FnArg::Typed(pat) => pat.pat.to_token_stream(),
+            })),
+        )
+        .unwrap();
+        writeln!(out, "    }}").unwrap();
+    }
+
+    writeln!(out, "}}").unwrap();
+}
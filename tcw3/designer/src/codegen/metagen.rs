@@ -28,13 +28,37 @@ pub fn gen_and_push_crate(
 
     let new_crate = metadata::Crate {
         comps: comps.iter().map(|c| gen_comp(&mut ctx, c)).collect(),
-        // TODO: probably should use a hash for reproducible builds
-        uuid: uuid::Uuid::new_v4(),
+        // Will be derived from the signature hashes
+        uuid: uuid::Uuid::nil(),
         name: crate_name,
     };
 
-    out_repo.main_crate_i = out_repo.crates.len();
+    let crate_i = out_repo.crates.len();
+    out_repo.main_crate_i = crate_i;
     out_repo.crates.push(new_crate);
+
+    // Calculate the signature hashes. This must be done after the crate is
+    // added to `out_repo` because `comp_sig_hash` resolves paths by crate
+    // indices.
+    let sig_hashes: Vec<u64> = out_repo.crates[crate_i]
+        .comps
+        .iter()
+        .map(|comp| out_repo.comp_sig_hash(comp))
+        .collect();
+
+    // Derive the UUID from the crate name and the signatures so that
+    // builds are reproducible
+    let mut hasher = metadata::StableHasher::new();
+    hasher.write_str(&out_repo.crates[crate_i].name);
+    for &sig_hash in sig_hashes.iter() {
+        hasher.write_u64(sig_hash);
+    }
+
+    let new_crate = &mut out_repo.crates[crate_i];
+    for (comp, sig_hash) in new_crate.comps.iter_mut().zip(sig_hashes) {
+        comp.sig_hash = sig_hash;
+    }
+    new_crate.uuid = uuid::Uuid::from_u128(hasher.finish128());
 }
 
 /// Replaces `Visibility::Restricted` with `Visibility::Private`.
@@ -178,6 +202,7 @@ fn gen_comp(ctx: &mut Ctx<'_, '_>, comp: &sem::CompDef<'_>) -> metadata::CompDef
                 }
             })
             .collect(),
+        sig_hash: 0, // will be set by `gen_and_push_crate`
     };
 
     for reloc in relocs {
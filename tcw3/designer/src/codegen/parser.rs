@@ -6,7 +6,8 @@ use syn::{
     parse::{Parse, ParseStream, Result},
     parse_str,
     punctuated::Punctuated,
-    token, Attribute, Error, Expr, FnArg, Ident, ItemUse, LitStr, Path, Token, Type, Visibility,
+    token, Attribute, Error, Expr, FnArg, Ident, ItemUse, LitInt, LitStr, Path, Token, Type,
+    Visibility,
 };
 
 use super::{diag::Diag, EmittedError};
@@ -69,6 +70,8 @@ pub mod kw {
     syn::custom_keyword!(clone);
     syn::custom_keyword!(borrow);
     syn::custom_keyword!(event);
+    syn::custom_keyword!(debounce);
+    syn::custom_keyword!(throttle);
 }
 
 pub struct File {
@@ -435,11 +438,13 @@ impl Parse for FieldWatchMode {
 }
 
 /// - `on (this.const1.event, init, this.prop1) |this.prop| { statements... }`
+/// - `on (this.prop1, debounce = 300ms) { statements... }`
 pub struct CompItemOn {
     pub attrs: Vec<Attribute>,
     pub on_token: kw::on,
     pub paren_token: token::Paren,
     pub triggers: Punctuated<Trigger, Token![,]>,
+    pub modifiers: Vec<OnModifier>,
     pub func: Func,
     pub semi_token: Option<Token![;]>,
 }
@@ -452,7 +457,24 @@ impl Parse for CompItemOn {
 
         let content;
         let paren_token = syn::parenthesized!(content in input);
-        let triggers = content.parse_terminated(Trigger::parse)?;
+
+        let mut triggers = Punctuated::new();
+        let mut modifiers = Vec::new();
+        while !content.is_empty() {
+            if content.peek2(Token![=]) {
+                modifiers.push(content.parse()?);
+            } else {
+                triggers.push_value(content.parse()?);
+            }
+
+            if content.is_empty() {
+                break;
+            }
+            let comma: Token![,] = content.parse()?;
+            if !triggers.empty_or_trailing() {
+                triggers.push_punct(comma);
+            }
+        }
 
         let func: Func = input.parse()?;
 
@@ -478,12 +500,68 @@ impl Parse for CompItemOn {
             on_token,
             paren_token,
             triggers,
+            modifiers,
             func,
             semi_token,
         })
     }
 }
 
+/// - `debounce = 300ms`
+/// - `throttle = 1s`
+pub struct OnModifier {
+    pub kind: OnModifierKind,
+    pub eq_token: Token![=],
+    /// An integer literal with a suffix `ms` or `s`.
+    pub duration: LitInt,
+}
+
+pub enum OnModifierKind {
+    Debounce(kw::debounce),
+    Throttle(kw::throttle),
+}
+
+impl Parse for OnModifier {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let la = input.lookahead1();
+        let kind = if la.peek(kw::debounce) {
+            OnModifierKind::Debounce(input.parse()?)
+        } else if la.peek(kw::throttle) {
+            OnModifierKind::Throttle(input.parse()?)
+        } else {
+            return Err(la.error());
+        };
+
+        let eq_token = input.parse()?;
+        let duration: LitInt = input.parse()?;
+
+        match duration.suffix() {
+            "ms" | "s" => {}
+            _ => {
+                return Err(Error::new_spanned(
+                    duration,
+                    "expected a duration with a unit (e.g., `300ms` or `1s`)",
+                ))
+            }
+        }
+
+        Ok(Self {
+            kind,
+            eq_token,
+            duration,
+        })
+    }
+}
+
+impl OnModifier {
+    pub fn span(&self) -> proc_macro2::Span {
+        match &self.kind {
+            OnModifierKind::Debounce(t) => t.span,
+            OnModifierKind::Throttle(t) => t.span,
+        }
+    }
+}
+
 /// - `pub event activated(pal::Wm);`
 pub struct CompItemEvent {
     pub attrs: Vec<Attribute>,
@@ -523,11 +601,20 @@ impl Parse for CompItemEvent {
 pub enum DynExpr {
     Func(Func),
     ObjInit(ObjInit),
+    Mount(Mount),
 }
 
 impl Parse for DynExpr {
     fn parse(input: ParseStream) -> Result<Self> {
-        if input.peek(Ident) || input.peek(Token![crate]) {
+        if input.peek(Token![if]) || input.peek(Token![for]) {
+            // `if` and `for` are recognized only if they enclose an obj-init.
+            // Otherwise, they are just Rust expressions.
+            if Mount::peek(input) {
+                Ok(DynExpr::Mount(input.parse()?))
+            } else {
+                Ok(DynExpr::Func(input.parse()?))
+            }
+        } else if input.peek(Ident) || input.peek(Token![crate]) {
             // Recognize obj-init only at the top-level for now
             let is_obj_init = if let Ok(m) = input.fork().parse::<syn::Macro>() {
                 m.path.segments.len() > 1 && m.path.segments.last().unwrap().ident == "new"
@@ -744,7 +831,71 @@ impl Parse for ObjInit {
     }
 }
 
-/// `x = value` `x`
+/// `if cond { Comp::new! { ... } }`, `for item in iter { Comp::new! { ... } }`
+pub struct Mount {
+    pub kind: MountKind,
+    pub brace_token: token::Brace,
+    pub obj_init: ObjInit,
+}
+
+pub enum MountKind {
+    /// `if cond`
+    If { if_token: Token![if], cond: Func },
+    /// `for item in iter`
+    For {
+        for_token: Token![for],
+        ident: Ident,
+        in_token: Token![in],
+        iter: Func,
+    },
+}
+
+impl Mount {
+    /// Check if the input starts with `Mount`. `if` with an `else` branch is
+    /// not `Mount`.
+    fn peek(input: ParseStream) -> bool {
+        let fork = input.fork();
+        fork.parse::<Self>().is_ok() && !fork.peek(Token![else])
+    }
+}
+
+impl Parse for Mount {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let kind = if input.peek(Token![if]) {
+            MountKind::If {
+                if_token: input.parse()?,
+                cond: Func {
+                    body: input.call(Expr::parse_without_eager_brace)?,
+                },
+            }
+        } else {
+            MountKind::For {
+                for_token: input.parse()?,
+                ident: input.parse()?,
+                in_token: input.parse()?,
+                iter: Func {
+                    body: input.call(Expr::parse_without_eager_brace)?,
+                },
+            }
+        };
+
+        let content;
+        let brace_token = syn::braced!(content in input);
+        let obj_init = content.parse()?;
+
+        if !content.is_empty() {
+            return Err(content.error("Expected a single object initialization literal"));
+        }
+
+        Ok(Self {
+            kind,
+            brace_token,
+            obj_init,
+        })
+    }
+}
+
+/// `x = value` `x` `x <-> prop`
 pub struct ObjInitField {
     pub ident: Ident,
     pub value: Option<ObjInitFieldValue>,
@@ -754,7 +905,7 @@ impl Parse for ObjInitField {
     fn parse(input: ParseStream) -> Result<Self> {
         Ok(Self {
             ident: input.parse()?,
-            value: if input.peek(Token![=]) {
+            value: if input.peek(Token![=]) || input.peek(Token![<-]) {
                 Some(input.parse()?)
             } else {
                 None
@@ -763,17 +914,34 @@ impl Parse for ObjInitField {
     }
 }
 
-pub struct ObjInitFieldValue {
-    pub eq_token: Token![=],
-    pub dyn_expr: DynExpr,
+pub enum ObjInitFieldValue {
+    /// `= value`
+    Expr {
+        eq_token: Token![=],
+        dyn_expr: DynExpr,
+    },
+    /// `<-> prop`
+    Bind {
+        larrow_token: Token![<-],
+        gt_token: Token![>],
+        input: Box<Input>,
+    },
 }
 
 impl Parse for ObjInitFieldValue {
     fn parse(input: ParseStream) -> Result<Self> {
-        Ok(Self {
-            eq_token: input.parse()?,
-            dyn_expr: input.parse()?,
-        })
+        if input.peek(Token![<-]) {
+            Ok(ObjInitFieldValue::Bind {
+                larrow_token: input.parse()?,
+                gt_token: input.parse()?,
+                input: input.parse()?,
+            })
+        } else {
+            Ok(ObjInitFieldValue::Expr {
+                eq_token: input.parse()?,
+                dyn_expr: input.parse()?,
+            })
+        }
     }
 }
 
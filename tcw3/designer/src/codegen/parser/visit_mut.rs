@@ -59,6 +59,10 @@ pub trait TcwdlVisitMut: VisitMut {
         visit_input_selector_mut(self, i);
     }
 
+    fn visit_mount_mut(&mut self, i: &mut Mount) {
+        visit_mount_mut(self, i);
+    }
+
     fn visit_obj_init_mut(&mut self, i: &mut ObjInit) {
         visit_obj_init_mut(self, i);
     }
@@ -164,6 +168,7 @@ pub fn visit_dyn_expr_mut(v: &mut (impl TcwdlVisitMut + ?Sized), i: &mut DynExpr
     match i {
         DynExpr::Func(i) => v.visit_func_mut(i),
         DynExpr::ObjInit(i) => v.visit_obj_init_mut(i),
+        DynExpr::Mount(i) => v.visit_mount_mut(i),
     }
 }
 
@@ -195,6 +200,17 @@ pub fn visit_input_selector_mut(v: &mut (impl TcwdlVisitMut + ?Sized), i: &mut I
     }
 }
 
+pub fn visit_mount_mut(v: &mut (impl TcwdlVisitMut + ?Sized), i: &mut Mount) {
+    match &mut i.kind {
+        MountKind::If { cond, .. } => v.visit_func_mut(cond),
+        MountKind::For { ident, iter, .. } => {
+            v.visit_ident_mut(ident);
+            v.visit_func_mut(iter);
+        }
+    }
+    v.visit_obj_init_mut(&mut i.obj_init);
+}
+
 pub fn visit_obj_init_mut(v: &mut (impl TcwdlVisitMut + ?Sized), i: &mut ObjInit) {
     v.visit_path_mut(&mut i.path);
     i.fields
@@ -213,5 +229,8 @@ pub fn visit_obj_init_field_value_mut(
     v: &mut (impl TcwdlVisitMut + ?Sized),
     i: &mut ObjInitFieldValue,
 ) {
-    v.visit_dyn_expr_mut(&mut i.dyn_expr);
+    match i {
+        ObjInitFieldValue::Expr { dyn_expr, .. } => v.visit_dyn_expr_mut(dyn_expr),
+        ObjInitFieldValue::Bind { input, .. } => v.visit_input_mut(input),
+    }
 }
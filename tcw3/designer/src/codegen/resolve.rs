@@ -1,5 +1,6 @@
 use codemap_diagnostic::{Diagnostic, Level, SpanLabel, SpanStyle};
-use std::collections::HashMap;
+use quote::ToTokens;
+use std::collections::{HashMap, HashSet};
 use syn::{
     punctuated::Punctuated, spanned::Spanned, Ident, ItemUse, Path, PathArguments, PathSegment,
     Token, Type, UseTree,
@@ -9,6 +10,7 @@ use super::{
     diag::Diag,
     parser::{span_to_codemap, visit_mut, File, Func, Item},
 };
+use crate::metadata;
 
 /// Replace all `Path`s in the given AST with absolute paths
 /// (`::cratename::item`) or `crate::` paths.
@@ -19,18 +21,22 @@ use super::{
 ///
 /// A path inside `syn::Type::Path` might be left unprocessed if it resolves to
 /// a built-in type.
+///
+/// Glob imports (`use tcw3::ui::views::*`) are resolved using `globs`. They
+/// only import components.
 pub fn resolve_paths(
     file: &mut File,
     codemap_file: &codemap::File,
     diag: &mut Diag,
     prelude: &Prelude,
+    globs: &GlobScope<'_>,
 ) {
     // Import prelude
     let mut alias_map = prelude.alias_map.clone();
 
     for item in file.items.iter() {
         if let Item::Use(u) = item {
-            process_use(&mut alias_map, codemap_file, diag, u);
+            process_use(&mut alias_map, codemap_file, diag, Some(globs), u);
         }
     }
 
@@ -41,6 +47,17 @@ pub fn resolve_paths(
             aliases.swap_remove(0);
         }
 
+        // Glob imports may be overridden by explicit imports
+        if aliases.iter().any(|a| !a.from_glob) {
+            aliases.retain(|a| !a.from_glob);
+        }
+
+        // Glob imports may import the same item for multiple times
+        if aliases.len() > 1 {
+            let mut paths = HashSet::new();
+            aliases.retain(|a| !a.from_glob || paths.insert(a.path.to_token_stream().to_string()));
+        }
+
         if aliases.len() > 1 {
             diag.emit(&[Diagnostic {
                 level: Level::Error,
@@ -259,12 +276,50 @@ struct Alias {
     path: Path,
     span: Option<codemap::Span>,
     from_prelude: bool,
+    from_glob: bool,
+}
+
+/// The components in the dependencies, which can be imported by glob imports.
+pub struct GlobScope<'a> {
+    pub repo: &'a metadata::Repo,
+    /// A map from imported crate names to indices into `repo.crates`.
+    pub imports_crate_i: &'a HashMap<&'a str, usize>,
+}
+
+impl GlobScope<'_> {
+    /// Enumerate the names of public components (including aliases) directly
+    /// in the module specified by `path`. Returns `None` if `path` doesn't
+    /// start with the name of a dependency.
+    fn comp_names_in(&self, path: &Path) -> Option<Vec<Ident>> {
+        let crate_name = path.segments.first()?.ident.to_string();
+        let crate_i = *self.imports_crate_i.get(&*crate_name)?;
+        let module: Vec<String> = path
+            .segments
+            .iter()
+            .skip(1)
+            .map(|s| s.ident.to_string())
+            .collect();
+
+        let names = self.repo.crates[crate_i]
+            .comps
+            .iter()
+            .filter(|comp| matches!(comp.vis, metadata::Visibility::Public))
+            .flat_map(|comp| comp.paths.iter())
+            .filter(|p| {
+                p.idents.len() == module.len() + 1 && p.idents[..module.len()] == module[..]
+            })
+            .map(|p| Ident::new(p.idents.last().unwrap(), path.span()))
+            .collect();
+
+        Some(names)
+    }
 }
 
 fn process_use(
     out_aliases: &mut HashMap<Ident, Vec<Alias>>,
     codemap_file: &codemap::File,
     diag: &mut Diag,
+    globs: Option<&GlobScope<'_>>,
     item: &ItemUse,
 ) {
     let mut empty_path = Path {
@@ -277,6 +332,7 @@ fn process_use(
         &item.tree,
         codemap_file,
         diag,
+        globs,
         &mut |ident, alias| {
             out_aliases.entry(ident).or_default().push(alias);
         },
@@ -288,6 +344,7 @@ fn process_use_tree(
     use_tree: &UseTree,
     codemap_file: &codemap::File,
     diag: &mut Diag,
+    globs: Option<&GlobScope<'_>>,
     f: &mut impl FnMut(Ident, Alias),
 ) {
     match use_tree {
@@ -296,7 +353,7 @@ fn process_use_tree(
                 ident: t.ident.clone(),
                 arguments: PathArguments::None,
             });
-            process_use_tree(path, &t.tree, codemap_file, diag, f);
+            process_use_tree(path, &t.tree, codemap_file, diag, globs, f);
             path.segments.pop();
         }
         UseTree::Name(t) => {
@@ -321,6 +378,7 @@ fn process_use_tree(
                 }),
                 codemap_file,
                 diag,
+                globs,
                 f,
             );
         }
@@ -356,27 +414,77 @@ fn process_use_tree(
                     path,
                     span: span_to_codemap(t.rename.span(), codemap_file),
                     from_prelude: false,
+                    from_glob: false,
                 },
             );
         }
         UseTree::Glob(t) => {
-            diag.emit(&[Diagnostic {
-                level: Level::Error,
-                message: "`*` is not supported".to_string(),
-                code: None,
-                spans: span_to_codemap(t.star_token.span(), codemap_file)
-                    .map(|span| SpanLabel {
+            let span = span_to_codemap(t.star_token.span(), codemap_file);
+
+            // Glob imports are resolved using the metadata of dependencies
+            let names = globs.and_then(|globs| globs.comp_names_in(path));
+
+            let names = if let Some(names) = names {
+                names
+            } else {
+                diag.emit(&[Diagnostic {
+                    level: Level::Error,
+                    message: "`*` is only supported for importing components from \
+                              other crates (e.g., `use tcw3::ui::views::*`)"
+                        .to_string(),
+                    code: None,
+                    spans: span
+                        .map(|span| SpanLabel {
+                            span,
+                            label: None,
+                            style: SpanStyle::Primary,
+                        })
+                        .into_iter()
+                        .collect(),
+                }]);
+                return;
+            };
+
+            if names.is_empty() {
+                diag.emit(&[Diagnostic {
+                    level: Level::Warning,
+                    message: format!(
+                        "`{}` does not have public components to import",
+                        path.to_token_stream()
+                    ),
+                    code: None,
+                    spans: span
+                        .map(|span| SpanLabel {
+                            span,
+                            label: None,
+                            style: SpanStyle::Primary,
+                        })
+                        .into_iter()
+                        .collect(),
+                }]);
+            }
+
+            for name in names {
+                let mut path = path.clone();
+                path.segments.push(PathSegment {
+                    ident: name.clone(),
+                    arguments: PathArguments::None,
+                });
+
+                f(
+                    name,
+                    Alias {
+                        path,
                         span,
-                        label: None,
-                        style: SpanStyle::Primary,
-                    })
-                    .into_iter()
-                    .collect(),
-            }]);
+                        from_prelude: false,
+                        from_glob: true,
+                    },
+                );
+            }
         }
         UseTree::Group(t) => {
             for item in t.items.iter() {
-                process_use_tree(path, item, codemap_file, diag, f);
+                process_use_tree(path, item, codemap_file, diag, globs, f);
             }
         }
     }
@@ -399,7 +507,7 @@ impl Prelude {
         let mut alias_map = HashMap::new();
         for item in parsed_file.items.iter() {
             if let Item::Use(u) = item {
-                process_use(&mut alias_map, &diag_file, diag, u);
+                process_use(&mut alias_map, &diag_file, diag, None, u);
             }
         }
 
@@ -411,3 +519,127 @@ impl Prelude {
         Self { alias_map }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::{diag::DiagFormat, parser::CompItem};
+
+    fn mk_comp(vis: metadata::Visibility, idents: &[&str]) -> metadata::CompDef {
+        metadata::CompDef {
+            flags: metadata::CompFlags::empty(),
+            vis,
+            paths: vec![metadata::Path {
+                crate_i: 0,
+                idents: idents.iter().map(|&s| s.to_owned()).collect(),
+            }],
+            items: vec![],
+            sig_hash: 0,
+        }
+    }
+
+    /// Resolve the paths in `source` and return the type of the first field
+    /// of the first component.
+    fn resolve_field_ty(source: &str) -> Result<String, ()> {
+        let repo = metadata::Repo {
+            main_crate_i: 0,
+            crates: vec![metadata::Crate {
+                name: "tcw3".to_owned(),
+                uuid: Default::default(),
+                comps: vec![
+                    mk_comp(metadata::Visibility::Public, &["ui", "views", "Button"]),
+                    mk_comp(metadata::Visibility::Public, &["ui", "views", "Label"]),
+                    mk_comp(metadata::Visibility::Private, &["ui", "views", "Hidden"]),
+                    mk_comp(metadata::Visibility::Public, &["ui", "Other"]),
+                ],
+            }],
+        };
+        let imports_crate_i = std::iter::once(("tcw3", 0)).collect();
+        let globs = GlobScope {
+            repo: &repo,
+            imports_crate_i: &imports_crate_i,
+        };
+
+        let mut out_diag = Vec::new();
+        let mut diag = Diag::new(Some(&mut out_diag), DiagFormat::Human);
+        let prelude = Prelude::new(&mut diag);
+        let file = diag.add_file("test.tcwdl".to_owned(), source.to_owned());
+        let mut parsed_file = super::super::parser::parse_file(&file, &mut diag).unwrap();
+
+        resolve_paths(&mut parsed_file, &file, &mut diag, &prelude, &globs);
+
+        if diag.has_error() {
+            return Err(());
+        }
+
+        let comp = parsed_file
+            .items
+            .iter()
+            .find_map(|item| try_match::try_match!(Item::Comp(comp) = item).ok())
+            .unwrap();
+        let field = try_match::try_match!(CompItem::Field(field) = &comp.items[0])
+            .ok()
+            .unwrap();
+        Ok(field.ty.to_token_stream().to_string())
+    }
+
+    #[test]
+    fn glob_import() {
+        assert_eq!(
+            resolve_field_ty(
+                "use tcw3::ui::views::*;
+                comp crate::Comp { const button: Button; }"
+            ),
+            Ok(":: tcw3 :: ui :: views :: Button".to_owned())
+        );
+    }
+
+    #[test]
+    fn glob_import_private() {
+        // Private components are not imported. The path is interpreted as
+        // a crate name instead.
+        assert_eq!(
+            resolve_field_ty(
+                "use tcw3::ui::views::*;
+                comp crate::Comp { const hidden: Hidden; }"
+            ),
+            Ok(":: Hidden".to_owned())
+        );
+    }
+
+    #[test]
+    fn glob_import_shadowed() {
+        // Explicit imports take precedence over glob imports
+        assert_eq!(
+            resolve_field_ty(
+                "use tcw3::ui::views::*;
+                use tcw3::ui::Other as Button;
+                comp crate::Comp { const button: Button; }"
+            ),
+            Ok(":: tcw3 :: ui :: Other".to_owned())
+        );
+    }
+
+    #[test]
+    fn glob_import_twice() {
+        assert_eq!(
+            resolve_field_ty(
+                "use tcw3::ui::views::*;
+                use tcw3::ui::{views::*};
+                comp crate::Comp { const label: Label; }"
+            ),
+            Ok(":: tcw3 :: ui :: views :: Label".to_owned())
+        );
+    }
+
+    #[test]
+    fn glob_import_local() {
+        assert_eq!(
+            resolve_field_ty(
+                "use crate::views::*;
+                comp crate::Comp { const label: Label; }"
+            ),
+            Err(())
+        );
+    }
+}
@@ -207,6 +207,7 @@ pub struct FieldDef<'a> {
     /// - Can be `None` unless `field_ty` is `Wire`. `None` means
     ///   the value must be supplied via the constructor.
     /// - Can be `Some(ObjInit(_))` only if `field_ty` is `Const`.
+    /// - Can be `Some(Mount(_))` only if `field_ty` is `Wire`.
     ///
     /// For a `PROTOTYPE_ONLY` component, whether this is `Some` or `None` still
     /// matters, whereas the inner value of `Some` doesn't.
@@ -292,7 +293,28 @@ pub struct FieldWatcher {
 pub struct OnDef<'a> {
     pub triggers: Vec<Trigger>,
     pub func: Func,
-    pub syn: &'a parser::CompItemOn,
+    /// `true` if the handler body is an `async` block. The future is spawned
+    /// by `Wm::spawn_local` every time the handler is called.
+    pub is_async: bool,
+    /// Specified by `debounce = ...` or `throttle = ...`.
+    pub rate_limit: Option<RateLimit>,
+    /// `None` if the item was synthesized (e.g., from a two-way binding).
+    pub syn: Option<&'a parser::CompItemOn>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub kind: RateLimitKind,
+    pub duration: std::time::Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKind {
+    /// Call the handler only after the triggers have been quiet for the
+    /// specified duration.
+    Debounce,
+    /// Call the handler at most once in the specified duration.
+    Throttle,
 }
 
 pub struct EventDef<'a> {
@@ -306,6 +328,7 @@ pub struct EventDef<'a> {
 pub enum DynExpr {
     Func(Func),
     ObjInit(ObjInit),
+    Mount(Mount),
 }
 
 impl DynExpr {
@@ -316,6 +339,10 @@ impl DynExpr {
     pub fn obj_init(&self) -> Option<&ObjInit> {
         try_match!(Self::ObjInit(x) = self).ok()
     }
+
+    pub fn mount(&self) -> Option<&Mount> {
+        try_match!(Self::Mount(x) = self).ok()
+    }
 }
 
 pub struct Func {
@@ -375,6 +402,32 @@ pub struct ObjInitField {
     pub value: Func,
 }
 
+/// `if cond { Comp::new! { ... } }` or `for item in iter { Comp::new! { ... } }`
+pub struct Mount {
+    pub kind: MountKind,
+    /// The fields' values may refer to the loop variable of `MountKind::For`.
+    /// They never include an obj-init or a two-way binding.
+    pub obj_init: ObjInit,
+}
+
+pub enum MountKind {
+    If { cond: Func },
+    For { ident: Ident, iter: Func },
+}
+
+impl Mount {
+    /// Iterate over all `Func`s in `self`, i.e., the condition or the iterator
+    /// followed by the values of `obj_init.fields`.
+    pub fn funcs(&self) -> impl Iterator<Item = &Func> {
+        let head = match &self.kind {
+            MountKind::If { cond } => cond,
+            MountKind::For { iter, .. } => iter,
+        };
+
+        std::iter::once(head).chain(self.obj_init.fields.iter().map(|field| &field.value))
+    }
+}
+
 /// Convert the AST to a slightly-higher-level representation. See the code
 /// comments to figure out what is done and what is not.
 pub fn analyze_comp<'a>(
@@ -387,6 +440,8 @@ pub fn analyze_comp<'a>(
         file,
         diag,
         next_input_index: 0,
+        lifted_ons: Vec::new(),
+        bind_targets: Vec::new(),
     }
     .analyze_comp(comp, ImportScope { file: parser_file })
 }
@@ -396,6 +451,11 @@ struct AnalyzeCtx<'a, 'b> {
     diag: &'a mut Diag<'b>,
 
     next_input_index: usize,
+
+    /// `on` items synthesized from two-way bindings
+    lifted_ons: Vec<OnDef<'static>>,
+    /// The targets of two-way bindings, which must be `prop` fields
+    bind_targets: Vec<Ident>,
 }
 
 enum CompReloc {
@@ -456,6 +516,37 @@ impl AnalyzeCtx<'_, '_> {
 
         this.items
             .extend(lifted_fields.into_iter().map(CompItemDef::Field));
+        this.items
+            .extend(self.lifted_ons.drain(..).map(CompItemDef::On));
+
+        for target in std::mem::take(&mut self.bind_targets) {
+            let is_prop = this.items.iter().any(|item| match item {
+                CompItemDef::Field(field) => {
+                    field.ident.sym == target.sym && field.field_ty == FieldType::Prop
+                }
+                _ => false,
+            });
+
+            if !is_prop {
+                self.diag.emit(&[Diagnostic {
+                    level: Level::Error,
+                    message: format!(
+                        "The component does not have a `prop` named `{}`",
+                        target.sym
+                    ),
+                    code: None,
+                    spans: target
+                        .span
+                        .map(|span| SpanLabel {
+                            span,
+                            label: None,
+                            style: SpanStyle::Primary,
+                        })
+                        .into_iter()
+                        .collect(),
+                }]);
+            }
+        }
 
         for reloc in relocs {
             match reloc {
@@ -498,6 +589,8 @@ impl AnalyzeCtx<'_, '_> {
                 this.flags |= CompFlags::PROTOTYPE_ONLY;
             } else if attr.path.is_ident("widget") {
                 this.flags |= CompFlags::WIDGET;
+            } else if attr.path.is_ident("introspect") {
+                this.flags |= CompFlags::INTROSPECT;
             } else if attr.path.is_ident("doc") {
                 match DocAttr::from_syn(attr, self.file) {
                     Ok(da) => this.doc_attrs.push(da),
@@ -957,23 +1050,28 @@ impl AnalyzeCtx<'_, '_> {
             ty,
             accessors,
             value: item.dyn_expr.as_ref().map(|init| match init {
-                parser::FieldInit::Definite(d) => {
-                    if item.field_ty == FieldType::Const {
-                        match d {
-                            parser::DynExpr::Func(func) => DynExpr::Func(self.analyze_func(func)),
-                            parser::DynExpr::ObjInit(init) => DynExpr::ObjInit(
-                                self.analyze_obj_init(init, default_vis_path, out_lifted_fields),
-                            ),
-                        }
-                    } else {
-                        // `ObjInit` is not allowed for non-`const` fields
-                        DynExpr::Func(self.analyze_dyn_expr_as_func(
-                            d,
+                parser::FieldInit::Definite(d) => match d {
+                    parser::DynExpr::ObjInit(init) if item.field_ty == FieldType::Const => {
+                        let obj_init =
+                            self.analyze_obj_init(init, default_vis_path, out_lifted_fields);
+                        self.lift_obj_init_binds(init, &item.ident);
+                        DynExpr::ObjInit(obj_init)
+                    }
+                    parser::DynExpr::Mount(mount) if item.field_ty == FieldType::Wire => {
+                        DynExpr::Mount(self.analyze_mount(
+                            mount,
                             default_vis_path,
                             out_lifted_fields,
                         ))
                     }
-                }
+                    // `ObjInit` is not allowed for non-`const` fields, and
+                    // `Mount` is not allowed for non-`wire` fields
+                    _ => DynExpr::Func(self.analyze_dyn_expr_as_func(
+                        d,
+                        default_vis_path,
+                        out_lifted_fields,
+                    )),
+                },
                 parser::FieldInit::Indefinite { .. } => {
                     // Assign a dummy expression
                     DynExpr::Func(Func {
@@ -994,8 +1092,58 @@ impl AnalyzeCtx<'_, '_> {
                 .map(|tr| self.analyze_trigger(tr))
                 .collect(),
             func: self.analyze_func(&item.func),
-            syn: item,
+            is_async: matches!(item.func.body, syn::Expr::Async(_)),
+            rate_limit: self.analyze_on_modifiers(&item.modifiers),
+            syn: Some(item),
+        }
+    }
+
+    fn analyze_on_modifiers(&mut self, modifiers: &[parser::OnModifier]) -> Option<RateLimit> {
+        let mut rate_limit = None;
+
+        for modifier in modifiers.iter() {
+            if rate_limit.is_some() {
+                self.diag.emit(&[Diagnostic {
+                    level: Level::Error,
+                    message: "At most one of `debounce` and `throttle` can be specified"
+                        .to_string(),
+                    code: None,
+                    spans: span_to_codemap(modifier.span(), self.file)
+                        .map(|span| SpanLabel {
+                            span,
+                            label: None,
+                            style: SpanStyle::Primary,
+                        })
+                        .into_iter()
+                        .collect(),
+                }]);
+                continue;
+            }
+
+            let value: u64 = match modifier.duration.base10_parse() {
+                Ok(x) => x,
+                Err(e) => {
+                    emit_syn_errors_as_diag(e, self.diag, self.file);
+                    continue;
+                }
+            };
+
+            let duration = match modifier.duration.suffix() {
+                "ms" => std::time::Duration::from_millis(value),
+                "s" => std::time::Duration::from_secs(value),
+                // Rejected by the parser
+                _ => unreachable!(),
+            };
+
+            let kind = match modifier.kind {
+                parser::OnModifierKind::Debounce(_) => RateLimitKind::Debounce,
+                parser::OnModifierKind::Throttle(_) => RateLimitKind::Throttle,
+            };
+
+            rate_limit = Some(RateLimit { kind, duration });
         }
+
+        rate_limit
     }
 
     fn analyze_event<'a>(
@@ -1049,6 +1197,34 @@ impl AnalyzeCtx<'_, '_> {
             parser::DynExpr::ObjInit(init) => {
                 self.analyze_obj_init_as_func(init, default_vis_path, out_lifted_fields)
             }
+            parser::DynExpr::Mount(mount) => {
+                let span = match &mount.kind {
+                    parser::MountKind::If { if_token, .. } => if_token.span,
+                    parser::MountKind::For { for_token, .. } => for_token.span,
+                };
+
+                self.diag.emit(&[Diagnostic {
+                    level: Level::Error,
+                    message: "`if` and `for` enclosing an object literal are only \
+                              allowed as the value of a `wire`"
+                        .to_string(),
+                    code: None,
+                    spans: span_to_codemap(span, self.file)
+                        .map(|span| SpanLabel {
+                            span,
+                            label: None,
+                            style: SpanStyle::Primary,
+                        })
+                        .into_iter()
+                        .collect(),
+                }]);
+
+                // Assign a dummy expression
+                Func {
+                    inputs: Vec::new(),
+                    body: syn::Expr::Verbatim(proc_macro2::TokenStream::new()),
+                }
+            }
         }
     }
 
@@ -1315,20 +1491,74 @@ impl AnalyzeCtx<'_, '_> {
                 .iter()
                 .map(|field| ObjInitField {
                     ident: Ident::from_syn(&field.ident, self.file),
-                    value: if let Some(value) = &field.value {
-                        self.analyze_dyn_expr_as_func(
-                            &value.dyn_expr,
-                            default_vis_path,
-                            out_lifted_fields,
-                        )
-                    } else {
-                        self.mk_func_with_named_input(field.ident.clone())
+                    value: match &field.value {
+                        Some(parser::ObjInitFieldValue::Expr { dyn_expr, .. }) => self
+                            .analyze_dyn_expr_as_func(
+                                dyn_expr,
+                                default_vis_path,
+                                out_lifted_fields,
+                            ),
+                        Some(parser::ObjInitFieldValue::Bind { input, .. }) => {
+                            // The write-back half is created by `lift_obj_init_binds`
+                            self.mk_func_with_input(input)
+                        }
+                        None => self.mk_func_with_named_input(field.ident.clone()),
                     },
                 })
                 .collect(),
         }
     }
 
+    fn analyze_mount(
+        &mut self,
+        mount: &parser::Mount,
+        default_vis_path: &Path,
+        out_lifted_fields: &mut Vec<FieldDef<'_>>,
+    ) -> Mount {
+        // A nested obj-init would be lifted to a `const` field and shared by
+        // all instances, and a two-way binding needs a `const` field to
+        // subscribe to. Neither works for instances created on demand.
+        for field in mount.obj_init.fields.iter() {
+            match &field.value {
+                None
+                | Some(parser::ObjInitFieldValue::Expr {
+                    dyn_expr: parser::DynExpr::Func(_),
+                    ..
+                }) => continue,
+                Some(_) => {}
+            }
+
+            self.diag.emit(&[Diagnostic {
+                level: Level::Error,
+                message: "Object literals and two-way bindings are unsupported \
+                          inside `if` and `for`"
+                    .to_string(),
+                code: None,
+                spans: span_to_codemap(field.ident.span(), self.file)
+                    .map(|span| SpanLabel {
+                        span,
+                        label: None,
+                        style: SpanStyle::Primary,
+                    })
+                    .into_iter()
+                    .collect(),
+            }]);
+        }
+
+        Mount {
+            kind: match &mount.kind {
+                parser::MountKind::If { cond, .. } => MountKind::If {
+                    cond: self.analyze_func(cond),
+                },
+                parser::MountKind::For { ident, iter, .. } => MountKind::For {
+                    ident: Ident::from_syn(ident, self.file),
+                    iter: self.analyze_func(iter),
+                },
+            },
+            obj_init: self.analyze_obj_init(&mount.obj_init, default_vis_path, out_lifted_fields),
+        }
+    }
+
     fn analyze_obj_init_as_func(
         &mut self,
         init: &parser::ObjInit,
@@ -1348,6 +1578,11 @@ impl AnalyzeCtx<'_, '_> {
         //
         let lifted_field_name = format!("__lifted_{}", out_lifted_fields.len());
 
+        self.lift_obj_init_binds(
+            init,
+            &syn::Ident::new(&lifted_field_name, proc_macro2::Span::call_site()),
+        );
+
         let mut ty_path = init.path.clone();
         path_remove_trailing_new(&mut ty_path);
 
@@ -1398,6 +1633,93 @@ impl AnalyzeCtx<'_, '_> {
         }
     }
 
+    /// Create `on` items implementing the write-back half of the two-way
+    /// bindings in an object literal stored in the field named `owner`.
+    ///
+    ///   Before:
+    ///     const entry = Entry::new! { text <-> draft_text };
+    ///   After:
+    ///     const entry = Entry::new! { text = get!(draft_text) };
+    ///     on (entry.text) get!(&self).set_draft_text(get!(entry.text));
+    ///
+    /// The write-back doesn't loop forever because a commit operation doesn't
+    /// raise a `watch` event unless the value has actually changed.
+    fn lift_obj_init_binds(&mut self, init: &parser::ObjInit, owner: &syn::Ident) {
+        for field in init.fields.iter() {
+            let input = match &field.value {
+                Some(parser::ObjInitFieldValue::Bind { input, .. }) => input,
+                _ => continue,
+            };
+
+            // The target must be a field of the enclosing component
+            let mut selectors = &input.selectors[..];
+            if selectors[0].is_field_with_ident("self") {
+                selectors = &selectors[1..];
+            }
+            let target = match selectors {
+                [parser::InputSelector::Field { ident, .. }] if ident != "event" => ident,
+                _ => {
+                    self.diag.emit(&[Diagnostic {
+                        level: Level::Error,
+                        message: "The target of a two-way binding must be a `prop` of \
+                                  the enclosing component"
+                            .to_string(),
+                        code: None,
+                        spans: span_to_codemap(input.span(), self.file)
+                            .map(|span| SpanLabel {
+                                span,
+                                label: None,
+                                style: SpanStyle::Primary,
+                            })
+                            .into_iter()
+                            .collect(),
+                    }]);
+                    continue;
+                }
+            };
+
+            self.bind_targets.push(Ident::from_syn(target, self.file));
+
+            let sub_field = &field.ident;
+            let setter = syn::Ident::new(&format!("set_{}", target), target.span());
+
+            let trigger: parser::Trigger = syn::parse_quote! { #owner.#sub_field };
+            let func: parser::Func = syn::parse_quote! {
+                get!(&self).#setter(get!(#owner.#sub_field))
+            };
+
+            let on = OnDef {
+                triggers: vec![self.analyze_trigger(&trigger)],
+                func: self.analyze_func(&func),
+                is_async: false,
+                rate_limit: None,
+                syn: None,
+            };
+            self.lifted_ons.push(on);
+        }
+    }
+
+    /// Construct a `Func` that looks like it was created from `get!(input)`.
+    fn mk_func_with_input(&mut self, input: &parser::Input) -> Func {
+        let ident = match input.selectors.last().unwrap() {
+            parser::InputSelector::Field { ident, .. } => ident.clone(),
+        };
+
+        // `|input as ident| ident`
+        Func {
+            inputs: vec![FuncInput {
+                by_ref: false,
+                input: self.analyze_input(input),
+                ident: Ident::from_syn(&ident, self.file),
+            }],
+            body: syn::Expr::Path(syn::ExprPath {
+                attrs: vec![],
+                qself: None,
+                path: ident.into(),
+            }),
+        }
+    }
+
     /// Construct a `Func` that looks like it was created from `get!(ident)`.
     fn mk_func_with_named_input(&mut self, ident: syn::Ident) -> Func {
         // `|this.ident as ident| ident`
@@ -3,4 +3,4 @@
 mod codegen;
 mod metadata;
 
-pub use self::codegen::BuildScriptConfig;
+pub use self::codegen::{BuildScriptConfig, DiagFormat};
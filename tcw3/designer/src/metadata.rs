@@ -5,6 +5,58 @@ use uuid::Uuid;
 
 pub mod visit_mut;
 
+// Encoding
+// ---------------------------------------------------------------------------
+
+/// The magic bytes at the beginning of encoded metadata.
+const MAGIC: [u8; 8] = *b"TCW3DSGN";
+
+/// The version of the metadata format. Must be incremented whenever the
+/// definition of `Repo` changes in a way that breaks bincode compatibility.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Represents an error encountered while decoding metadata.
+///
+/// The doc comments for the variants are converted to a `Display`
+/// implementation by `displaydoc`.
+#[derive(Debug, displaydoc::Display)]
+pub enum DecodeError {
+    /// The data does not look like TCW3 Designer metadata
+    BadMagic,
+    /// The metadata format version {0} is not supported; the dependency
+    /// might have been built by a different version of TCW3 Designer
+    UnsupportedVersion(u32),
+    /// {0}
+    Bincode(bincode::Error),
+}
+
+impl Repo {
+    /// Encode `self` as a byte sequence prefixed with a version header.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut out, self).unwrap();
+        out
+    }
+
+    /// Decode a byte sequence produced by [`Repo::encode`].
+    pub fn decode(data: &[u8]) -> Result<Self, DecodeError> {
+        if data.len() < MAGIC.len() + 4 || data[..MAGIC.len()] != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&data[MAGIC.len()..][..4]);
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+
+        bincode::deserialize(&data[MAGIC.len() + 4..]).map_err(DecodeError::Bincode)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Repo {
     /// Specifies which element of `crates` is the main crate. Other elements
@@ -50,6 +102,9 @@ pub struct CompDef {
     /// multiple aliases.
     pub paths: Vec<Path>,
     pub items: Vec<CompItemDef>,
+    /// The stable hash of the component's signature. See
+    /// [`Repo::comp_sig_hash`].
+    pub sig_hash: u64,
 }
 
 bitflags::bitflags! {
@@ -64,6 +119,9 @@ bitflags::bitflags! {
         /// The component uses the simple builder API.
         /// Requires `PROTOTYPE_ONLY`.
         const SIMPLE_BUILDER = 1 << 2;
+
+        /// Generate the `introspect` method.
+        const INTROSPECT = 1 << 3;
     }
 }
 
@@ -382,3 +440,142 @@ impl CompDef {
             .find(|(_, item)| item.ident() == ident)
     }
 }
+
+// Signature Hashing
+// ---------------------------------------------------------------------------
+
+/// FNV-1a (128-bit). Unlike `std::collections::hash_map::DefaultHasher`, the
+/// output is guaranteed to be stable across compiler versions and platforms.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher(u128);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u128;
+            self.0 = self.0.wrapping_mul(0x0000000001000000000000000000013b);
+        }
+    }
+
+    pub fn write_u8(&mut self, x: u8) {
+        self.write(&[x]);
+    }
+
+    pub fn write_u64(&mut self, x: u64) {
+        self.write(&x.to_le_bytes());
+    }
+
+    /// Write a length-prefixed string.
+    pub fn write_str(&mut self, x: &str) {
+        self.write(&(x.len() as u64).to_le_bytes());
+        self.write(x.as_bytes());
+    }
+
+    pub fn finish(&self) -> u64 {
+        (self.0 ^ (self.0 >> 64)) as u64
+    }
+
+    pub fn finish128(&self) -> u128 {
+        self.0
+    }
+}
+
+impl Repo {
+    /// Calculate the stable hash of a component's signature, i.e., the parts
+    /// of `CompDef` that are visible to the users of the component.
+    ///
+    /// Crate references are hashed by crate names, not by crate indices, so the
+    /// result does not depend on how crates are arranged in `self`.
+    pub fn comp_sig_hash(&self, comp: &CompDef) -> u64 {
+        let mut h = StableHasher::new();
+
+        h.write_u8(comp.flags.bits());
+        self.hash_vis(&mut h, &comp.vis);
+
+        h.write_u64(comp.paths.len() as u64);
+        for path in comp.paths.iter() {
+            self.hash_path(&mut h, path);
+        }
+
+        h.write_u64(comp.items.len() as u64);
+        for item in comp.items.iter() {
+            match item {
+                CompItemDef::Field(field) => {
+                    h.write_u8(0);
+                    h.write_u8(field.field_ty as u8);
+                    h.write_u8(field.flags.bits());
+                    h.write_str(&field.ident);
+
+                    let accessors = &field.accessors;
+                    if let Some(set) = &accessors.set {
+                        h.write_u8(1);
+                        self.hash_vis(&mut h, &set.vis);
+                    } else {
+                        h.write_u8(0);
+                    }
+                    if let Some(get) = &accessors.get {
+                        h.write_u8(1);
+                        self.hash_vis(&mut h, &get.vis);
+                        h.write_u8(get.mode as u8);
+                    } else {
+                        h.write_u8(0);
+                    }
+                    if let Some(watch) = &accessors.watch {
+                        h.write_u8(1);
+                        self.hash_vis(&mut h, &watch.vis);
+                        h.write_u64(watch.event_item_i as u64);
+                    } else {
+                        h.write_u8(0);
+                    }
+
+                    if let Some(ty) = &field.ty {
+                        h.write_u8(1);
+                        self.hash_path(&mut h, &self.comp_by_ref(ty).paths[0]);
+                    } else {
+                        h.write_u8(0);
+                    }
+                }
+                CompItemDef::Event(event) => {
+                    h.write_u8(1);
+                    self.hash_vis(&mut h, &event.vis);
+                    h.write_str(&event.ident);
+                    h.write_u64(event.inputs.len() as u64);
+                    for input in event.inputs.iter() {
+                        h.write_str(input);
+                    }
+                }
+            }
+        }
+
+        h.finish()
+    }
+
+    fn hash_vis(&self, h: &mut StableHasher, vis: &Visibility) {
+        match vis {
+            Visibility::Private => h.write_u8(0),
+            Visibility::Restricted(path) => {
+                h.write_u8(1);
+                self.hash_path(h, path);
+            }
+            Visibility::Public => h.write_u8(2),
+        }
+    }
+
+    fn hash_path(&self, h: &mut StableHasher, path: &Path) {
+        h.write_str(&self.crates[path.crate_i].name);
+        h.write_u64(path.idents.len() as u64);
+        for ident in path.idents.iter() {
+            h.write_str(ident);
+        }
+    }
+}
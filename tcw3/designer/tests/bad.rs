@@ -81,6 +81,9 @@ should_error!(input_field_not_comp2, "input_field_not_comp2.tcwdl");
 should_error!(input_field_not_comp3, "input_field_not_comp3.tcwdl");
 should_error!(input_field_unknown, "input_field_unknown.tcwdl");
 should_error!(input_inline_unsyntactic, "input_inline_unsyntactic.tcwdl");
+should_error!(mount_not_wire, "mount_not_wire.tcwdl");
+should_error!(mount_objinit_nested, "mount_objinit_nested.tcwdl");
+should_error!(objinit_bind_nonprop, "objinit_bind_nonprop.tcwdl");
 should_error!(objinit_comp_unknown, "objinit_comp_unknown.tcwdl");
 should_error!(objinit_explicit_type, "objinit_explicit_type.tcwdl");
 should_error!(objinit_settable, "objinit_settable.tcwdl");
@@ -92,6 +95,7 @@ should_error!(
 );
 should_error!(objinit_field_unknown, "objinit_field_unknown.tcwdl");
 should_error!(objinit_field_wrong_ty, "objinit_field_wrong_ty.tcwdl");
+should_error!(on_ratelimit_twice, "on_ratelimit_twice.tcwdl");
 should_error!(prop_uninitable, "prop_uninitable.tcwdl");
 should_error!(prop_unsettable, "prop_unsettable.tcwdl");
 should_error!(use_dupe, "use_dupe.tcwdl");
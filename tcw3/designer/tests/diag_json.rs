@@ -0,0 +1,36 @@
+#[test]
+fn json_diag() {
+    let mut out_diag = Vec::<u8>::new();
+    let mut out_stream = Vec::new();
+    let e = tcw3_designer::BuildScriptConfig::new()
+        .root_source_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/bad/objinit_bind_nonprop.tcwdl"
+        ))
+        .out_source_stream(&mut out_stream)
+        .out_diag_stream(&mut out_diag)
+        .diag_format(tcw3_designer::DiagFormat::Json)
+        .crate_name("designer_test")
+        .run();
+    assert!(e.is_err(), "codegen did not fail");
+
+    let out_diag = std::str::from_utf8(&out_diag).unwrap();
+    eprintln!("{}", out_diag);
+
+    let msgs: Vec<serde_json::Value> = out_diag
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    let msg = msgs
+        .iter()
+        .find(|msg| msg["message"] == "The component does not have a `prop` named `const1`")
+        .expect("the expected error was not found");
+    assert_eq!(msg["level"], "error");
+    assert_eq!(msg["spans"][0]["line_start"], 8);
+    assert_eq!(msg["spans"][0]["column_start"], 19);
+    assert_eq!(msg["spans"][0]["is_primary"], true);
+
+    // The last message says the build failed
+    assert_eq!(msgs.last().unwrap()["spans"].as_array().unwrap().len(), 0);
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+fn temp_metadata_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "tcw3_designer_test_{}_{}.tcwdm",
+        std::process::id(),
+        name
+    ))
+}
+
+fn build_upstream(out_metadata_file: &std::path::Path) {
+    let mut out_diag = Vec::<u8>::new();
+    let mut out_stream = Vec::new();
+    let e = tcw3_designer::BuildScriptConfig::new()
+        .root_source_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/link/upstream.tcwdl"
+        ))
+        .out_source_stream(&mut out_stream)
+        .out_diag_stream(&mut out_diag)
+        .out_metadata_file(out_metadata_file)
+        .crate_name("designer_test_upstream")
+        .run();
+    eprintln!("{}", std::str::from_utf8(&out_diag).unwrap());
+    assert!(e.is_ok(), "codegen failed");
+}
+
+#[test]
+fn link_file() {
+    let metadata_path = temp_metadata_path("link_file");
+    build_upstream(&metadata_path);
+
+    let mut out_diag = Vec::<u8>::new();
+    let mut out_stream = Vec::new();
+    let e = tcw3_designer::BuildScriptConfig::new()
+        .root_source_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/link/downstream.tcwdl"
+        ))
+        .out_source_stream(&mut out_stream)
+        .out_diag_stream(&mut out_diag)
+        .link_file("designer_test_upstream", &metadata_path)
+        .crate_name("designer_test_downstream")
+        .run();
+    eprintln!("{}", std::str::from_utf8(&out_diag).unwrap());
+    let _ = std::fs::remove_file(&metadata_path);
+    assert!(e.is_ok(), "codegen failed");
+}
+
+#[test]
+fn reproducible_metadata() {
+    let path1 = temp_metadata_path("reproducible1");
+    let path2 = temp_metadata_path("reproducible2");
+    build_upstream(&path1);
+    build_upstream(&path2);
+
+    let metadata1 = std::fs::read(&path1).unwrap();
+    let metadata2 = std::fs::read(&path2).unwrap();
+    let _ = std::fs::remove_file(&path1);
+    let _ = std::fs::remove_file(&path2);
+    assert_eq!(metadata1, metadata2);
+}
+
+#[test]
+fn link_bad_header() {
+    let mut out_diag = Vec::<u8>::new();
+    let mut out_stream = Vec::new();
+    let e = tcw3_designer::BuildScriptConfig::new()
+        .root_source_file(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/link/downstream.tcwdl"
+        ))
+        .out_source_stream(&mut out_stream)
+        .out_diag_stream(&mut out_diag)
+        .link("designer_test_upstream", (&b"not metadata"[..]).into())
+        .crate_name("designer_test_downstream")
+        .run();
+    assert!(e.is_err(), "codegen did not fail");
+
+    let out_diag = std::str::from_utf8(&out_diag).unwrap();
+    eprintln!("{}", out_diag);
+    assert!(out_diag.contains("does not look like TCW3 Designer metadata"));
+}
@@ -1,5 +1,7 @@
 fn main() {
     tcw3_designer::BuildScriptConfig::new()
         .crate_name("tcw3_designer_tests_impl")
+        .test_harness(true)
+        .hot_reload(true)
         .run_and_exit_on_error();
 }
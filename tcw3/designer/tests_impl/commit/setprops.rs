@@ -0,0 +1,24 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::commit::setprops::Comp }
+
+#[use_testing_wm]
+#[test]
+fn set_props(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    assert_eq!(comp.sum(), 3);
+
+    comp.set_props(|comp| {
+        comp.set_prop1(10);
+        comp.set_prop2(20);
+    });
+
+    // The changes are committed without waiting for the next update
+    assert_eq!(comp.prop1(), 10);
+    assert_eq!(comp.sum(), 30);
+    assert_eq!(*comp.log().borrow(), [30]);
+
+    // The pending update does nothing
+    twm.step_unsend();
+    assert_eq!(*comp.log().borrow(), [30]);
+}
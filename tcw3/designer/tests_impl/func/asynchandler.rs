@@ -0,0 +1,42 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::func::asynchandler::Comp }
+
+#[use_testing_wm]
+#[test]
+fn init(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+
+    // The future is not polled until the main event loop gets a chance to
+    // run
+    assert!(comp.log().borrow().is_empty());
+
+    twm.step_unsend();
+    assert_eq!(*comp.log().borrow(), [1]);
+}
+
+#[use_testing_wm]
+#[test]
+fn event(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    comp.raise_trigger(21);
+    assert_eq!(0, comp.value());
+
+    twm.step_unsend();
+    assert_eq!(42, comp.value());
+    assert_eq!(*comp.log().borrow(), [1, 42]);
+}
+
+#[use_testing_wm]
+#[test]
+fn weak_capture(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    let weak = comp.downgrade();
+    comp.raise_trigger(21);
+
+    // The pending future must not keep the component alive
+    drop(comp);
+    assert!(weak.upgrade().is_none());
+
+    twm.step_unsend();
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::func::ratelimit::Comp }
+
+#[use_testing_wm]
+#[test]
+fn debounce(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+
+    comp.raise_debounced(1);
+    twm.advance_time(Duration::from_millis(50));
+    comp.raise_debounced(2);
+    twm.advance_time(Duration::from_millis(50));
+    assert!(comp.log().borrow().is_empty());
+
+    twm.advance_time(Duration::from_millis(100));
+    assert_eq!(*comp.log().borrow(), [2]);
+}
+
+#[use_testing_wm]
+#[test]
+fn throttle(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+
+    // The first call is not delayed
+    comp.raise_throttled(1);
+    assert_eq!(*comp.log().borrow(), [1]);
+
+    // The subsequent calls are deferred, and only the last one is made
+    comp.raise_throttled(2);
+    comp.raise_throttled(3);
+    assert_eq!(*comp.log().borrow(), [1]);
+    twm.advance_time(Duration::from_millis(200));
+    assert_eq!(*comp.log().borrow(), [1, 3]);
+
+    // The period has ended
+    twm.advance_time(Duration::from_millis(200));
+    comp.raise_throttled(4);
+    assert_eq!(*comp.log().borrow(), [1, 3, 4]);
+}
@@ -6,6 +6,7 @@ include!(concat!(env!("OUT_DIR"), "/designer.rs"));
 
 mod commit {
     mod remotetrigger;
+    mod setprops;
 }
 
 mod field {
@@ -16,7 +17,9 @@ mod field {
 }
 
 mod func {
+    mod asynchandler;
     mod inline;
+    mod ratelimit;
 }
 
 mod interop {
@@ -24,13 +27,23 @@ mod interop {
 }
 
 mod misc {
+    mod debug;
     mod exprpath;
     mod genericresolve;
+    mod hotreload;
+    mod largebitset;
     mod primitives;
+    mod testharness;
     mod weakref;
 }
 
+mod mount {
+    mod cond;
+    mod keyed;
+}
+
 mod objinit {
     mod alias;
+    mod bind;
     mod shorthand;
 }
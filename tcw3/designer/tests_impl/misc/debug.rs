@@ -0,0 +1,60 @@
+use tcw3::{
+    designer_runtime::FieldType,
+    testing::{prelude::*, use_testing_wm},
+};
+
+designer_impl! { crate::misc::debug::Comp }
+designer_impl! { crate::misc::debug::CompIntrospect }
+
+#[use_testing_wm]
+#[test]
+fn debug_comp(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    let s = format!("{:?}", comp);
+    assert!(s.starts_with("Comp {"), "{}", s);
+    assert!(s.contains("const1: 1"), "{}", s);
+    assert!(s.contains("prop1: 2"), "{}", s);
+    assert!(s.contains("wire1: 3"), "{}", s);
+}
+
+#[use_testing_wm]
+#[test]
+fn debug_builder(twm: &dyn TestingWm) {
+    let builder = CompBuilder::new();
+    let s = format!("{:?}", builder);
+    assert!(s.contains("Unset"), "{}", s);
+    assert!(s.contains("const1: None"), "{}", s);
+
+    let builder = builder.with_wm(twm.wm()).with_const1(4);
+    let s = format!("{:?}", builder);
+    assert!(!s.contains("Unset"), "{}", s);
+    assert!(s.contains("const1: Some(4)"), "{}", s);
+}
+
+#[use_testing_wm]
+#[test]
+fn introspect(twm: &dyn TestingWm) {
+    let comp = CompIntrospectBuilder::new()
+        .with_wm(twm.wm())
+        .with_const1(5)
+        .build();
+    comp.set_prop1(6);
+    twm.step_unsend();
+
+    let info = comp.introspect();
+    assert_eq!(info.name, "CompIntrospect");
+
+    let fields: Vec<_> = info
+        .fields
+        .iter()
+        .map(|f| (f.name, f.field_ty, &f.value[..]))
+        .filter(|f| f.0 != "wm")
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("const1", FieldType::Const, "5"),
+            ("prop1", FieldType::Prop, "6")
+        ]
+    );
+}
@@ -0,0 +1,18 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::misc::hotreload::Comp }
+designer_impl! { crate::misc::hotreload::CompChild }
+
+#[use_testing_wm]
+#[test]
+fn compiled_values(twm: &dyn TestingWm) {
+    // The source file is unchanged, so the compiled values are retained
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    twm.step_unsend();
+
+    let child = comp.child();
+    assert_eq!(child.text(), "hello");
+    assert_eq!(child.count(), 42);
+    assert_eq!(child.offset(), -3);
+    assert!(child.flag());
+}
@@ -0,0 +1,24 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::misc::largebitset::Comp }
+
+#[use_testing_wm]
+#[test]
+fn set_props(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    assert_eq!(comp.sum(), 70 + 139);
+
+    comp.set_p0(1000);
+    comp.set_p139(2000);
+    twm.step_unsend();
+
+    assert_eq!(comp.p0(), 1000);
+    assert_eq!(comp.p138(), 138);
+    assert_eq!(comp.p139(), 2000);
+    assert_eq!(comp.sum(), 1000 + 70 + 2000);
+
+    comp.set_p70(3000);
+    twm.step_unsend();
+
+    assert_eq!(comp.sum(), 1000 + 3000 + 2000);
+}
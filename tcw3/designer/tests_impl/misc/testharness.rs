@@ -0,0 +1,13 @@
+designer_impl! { crate::misc::testharness::Comp }
+designer_impl! { crate::misc::testharness::CompSub }
+
+#[test]
+fn run_test_with() {
+    Comp::run_test_with(|_, comp| {
+        assert_eq!(comp.testing_sub().const1(), 42);
+
+        comp.testing_raise_activated(1);
+        comp.testing_raise_activated(2);
+        assert_eq!(*comp.log().borrow(), [1, 2]);
+    });
+}
@@ -0,0 +1,54 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::mount::cond::Comp }
+designer_impl! { crate::mount::cond::CompChild }
+
+#[use_testing_wm]
+#[test]
+fn unmounted_initially(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    assert!(comp.child().is_none());
+}
+
+#[use_testing_wm]
+#[test]
+fn mount_unmount(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+
+    comp.set_visible(true);
+    twm.step_unsend();
+    let child = comp.child().unwrap();
+    assert_eq!(child.id(), 0);
+    assert_eq!(child.label(), 1);
+
+    let weak = child.downgrade();
+    drop(child);
+
+    comp.set_visible(false);
+    twm.step_unsend();
+    assert!(comp.child().is_none());
+    assert!(weak.upgrade().is_none());
+
+    // A new instance is created
+    comp.set_visible(true);
+    twm.step_unsend();
+    assert_eq!(comp.child().unwrap().id(), 1);
+}
+
+#[use_testing_wm]
+#[test]
+fn update_mounted(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new()
+        .with_wm(twm.wm())
+        .with_visible(true)
+        .build();
+    assert_eq!(comp.child().unwrap().label(), 1);
+
+    comp.set_label(2);
+    twm.step_unsend();
+
+    // The existing instance is reused
+    let child = comp.child().unwrap();
+    assert_eq!(child.id(), 0);
+    assert_eq!(child.label(), 2);
+}
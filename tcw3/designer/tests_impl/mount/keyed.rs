@@ -0,0 +1,72 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::mount::keyed::Comp }
+designer_impl! { crate::mount::keyed::CompChild }
+
+fn ids_and_labels(comp: &Comp) -> Vec<(u32, u32, String)> {
+    comp.children()
+        .iter()
+        .map(|(item, child)| (*item, child.id(), child.label()))
+        .collect()
+}
+
+#[use_testing_wm]
+#[test]
+fn initial(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new()
+        .with_wm(twm.wm())
+        .with_items(vec![3, 5])
+        .build();
+    assert_eq!(
+        ids_and_labels(&comp),
+        vec![(3, 0, "3".to_owned()), (5, 1, "5".to_owned())]
+    );
+}
+
+#[use_testing_wm]
+#[test]
+fn insert_remove_reorder(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new()
+        .with_wm(twm.wm())
+        .with_items(vec![3, 5])
+        .build();
+    let weak3 = comp.children()[0].1.downgrade();
+
+    // Existing instances are reused based on their keys
+    comp.set_items(vec![7, 5, 3]);
+    twm.step_unsend();
+    assert_eq!(
+        ids_and_labels(&comp),
+        vec![
+            (7, 2, "7".to_owned()),
+            (5, 1, "5".to_owned()),
+            (3, 0, "3".to_owned()),
+        ]
+    );
+    assert_eq!(comp.num_changes(), 1);
+
+    comp.set_items(vec![5]);
+    twm.step_unsend();
+    assert_eq!(ids_and_labels(&comp), vec![(5, 1, "5".to_owned())]);
+    assert_eq!(comp.num_changes(), 2);
+    assert!(weak3.upgrade().is_none());
+}
+
+#[use_testing_wm]
+#[test]
+fn update_props(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new()
+        .with_wm(twm.wm())
+        .with_items(vec![3, 5])
+        .build();
+
+    comp.set_suffix("px");
+    twm.step_unsend();
+    assert_eq!(
+        ids_and_labels(&comp),
+        vec![(3, 0, "3px".to_owned()), (5, 1, "5px".to_owned())]
+    );
+
+    // The set of instances didn't change, so `children_changed` wasn't raised
+    assert_eq!(comp.num_changes(), 0);
+}
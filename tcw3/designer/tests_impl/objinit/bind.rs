@@ -0,0 +1,31 @@
+use tcw3::testing::{prelude::*, use_testing_wm};
+
+designer_impl! { crate::objinit::bind::Comp }
+designer_impl! { crate::objinit::bind::CompOther }
+
+#[use_testing_wm]
+#[test]
+fn init_from_parent(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    assert_eq!(1, comp.other().prop1());
+}
+
+#[use_testing_wm]
+#[test]
+fn parent_to_child(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    comp.set_prop1(2);
+    twm.step_unsend();
+    assert_eq!(2, comp.prop1());
+    assert_eq!(2, comp.other().prop1());
+}
+
+#[use_testing_wm]
+#[test]
+fn child_to_parent(twm: &dyn TestingWm) {
+    let comp = CompBuilder::new().with_wm(twm.wm()).build();
+    comp.other().set_prop1(3);
+    twm.step_unsend();
+    assert_eq!(3, comp.other().prop1());
+    assert_eq!(3, comp.prop1());
+}
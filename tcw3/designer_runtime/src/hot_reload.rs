@@ -0,0 +1,543 @@
+//! Hot-reloading of literal values in `.tcwdl` files. Requires the
+//! `hot_reload` feature.
+//!
+//! When `BuildScriptConfig::hot_reload` is enabled, Designer generates a table
+//! of *sites* for each component. A site is a literal value of a `prop` in an
+//! object initialization literal, such as `"Hello"` in
+//! `const label = Label::new! { text = "Hello" }`. The component's instances
+//! are registered to this module on construction.
+//!
+//! The source file of a component is parsed when its first instance is
+//! registered, and the literals differing from the compiled ones are applied
+//! to the instance right away. After [`start`] is called, the source files are
+//! watched by [`tcw3_pal::fswatch`], and the changed literals are applied to
+//! all live instances by calling the subcomponents' setters. Changes are not
+//! detected on platforms where `fswatch` is unsupported.
+//!
+//! Component definitions are not reloaded. Existing instances are never
+//! rebuilt, so changes to anything other than these literals (e.g., adding a
+//! field or editing a dynamic expression, which is compiled Rust code)
+//! require recompilation.
+//!
+//! [`Literal::to_str`] leaks every distinct string it returns. This is fine
+//! for a development aid, but the `hot_reload` feature should not be enabled
+//! in release builds.
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    convert::TryFrom,
+    fs,
+    rc::Rc,
+};
+use tcw3_pal::{self as pal, fswatch};
+
+/// The sites of a component. Generated by Designer.
+pub struct CompSites<C: 'static> {
+    /// The path of the source file defining the component.
+    pub file: &'static str,
+    /// The path of the component as written in the source file, e.g.,
+    /// `crate::ui::Component`.
+    pub comp: &'static str,
+    pub sites: &'static [Site<C>],
+}
+
+/// A literal value of a `prop` in an object initialization literal.
+/// Generated by Designer.
+pub struct Site<C> {
+    /// The `const` field having the object initialization literal.
+    pub field: &'static str,
+    /// The `prop` of the subcomponent initialized by the literal.
+    pub subfield: &'static str,
+    /// The compiled literal expression.
+    pub compiled: &'static str,
+    /// Assign a new value to the subcomponent's `prop`. Values of an
+    /// incompatible kind are ignored.
+    pub apply: fn(&C, &Literal),
+}
+
+/// The value of a literal expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Str(String),
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+}
+
+impl Literal {
+    /// Get the value of a string literal.
+    ///
+    /// The string is leaked because setters may require `&'static str`.
+    /// Identical strings share the allocation, so the leak is bounded by the
+    /// number of distinct values that appeared in the source files during the
+    /// program's lifetime.
+    pub fn to_str(&self) -> Option<&'static str> {
+        thread_local! {
+            static INTERNED: RefCell<HashMap<String, &'static str>> = RefCell::new(HashMap::new());
+        }
+
+        if let Literal::Str(x) = self {
+            Some(INTERNED.with(|interned| {
+                *interned
+                    .borrow_mut()
+                    .entry(x.clone())
+                    .or_insert_with(|| Box::leak(x.clone().into_boxed_str()))
+            }))
+        } else {
+            None
+        }
+    }
+
+    pub fn to_bool(&self) -> Option<bool> {
+        if let Literal::Bool(x) = self {
+            Some(*x)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_char(&self) -> Option<char> {
+        if let Literal::Char(x) = self {
+            Some(*x)
+        } else {
+            None
+        }
+    }
+
+    /// Get the value of an integer or floating-point literal. Returns `None`
+    /// if the value does not fit in `T`.
+    pub fn to_num<T: Number>(&self) -> Option<T> {
+        T::from_literal(self)
+    }
+}
+
+/// Primitive numeric types that can be constructed from [`Literal`].
+pub trait Number: Sized {
+    fn from_literal(lit: &Literal) -> Option<Self>;
+}
+
+macro_rules! impl_number_int {
+    ($($ty:ty),*) => {$(
+        impl Number for $ty {
+            fn from_literal(lit: &Literal) -> Option<Self> {
+                if let Literal::Int(x) = lit {
+                    Self::try_from(*x).ok()
+                } else {
+                    None
+                }
+            }
+        }
+    )*};
+}
+
+impl_number_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_number_float {
+    ($($ty:ty),*) => {$(
+        impl Number for $ty {
+            fn from_literal(lit: &Literal) -> Option<Self> {
+                if let Literal::Float(x) = lit {
+                    Some(*x as $ty)
+                } else {
+                    None
+                }
+            }
+        }
+    )*};
+}
+
+impl_number_float!(f32, f64);
+
+/// Identifies a site by the component path, `Site::field`, and
+/// `Site::subfield`.
+type SiteKey = (String, String, String);
+
+fn site_key(comp: &str, field: &str, subfield: &str) -> SiteKey {
+    (comp.to_owned(), field.to_owned(), subfield.to_owned())
+}
+
+struct State {
+    /// Set by [`start`].
+    wm: Cell<Option<pal::Wm>>,
+    files: RefCell<HashMap<&'static str, SourceFile>>,
+}
+
+struct SourceFile {
+    /// The literals found in the last successful parse.
+    literals: HashMap<SiteKey, Literal>,
+    instances: Vec<Rc<dyn Instance>>,
+    /// `None` until [`start`] is called or if the file could not be watched.
+    watcher: Option<fswatch::Watcher>,
+}
+
+trait Instance {
+    fn is_alive(&self) -> bool;
+    /// Apply the literals of the matching sites.
+    fn apply(&self, literals: &HashMap<SiteKey, Literal>);
+}
+
+struct Registered<C: 'static, F> {
+    sites: &'static CompSites<C>,
+    upgrade: F,
+}
+
+impl<C: 'static, F: Fn() -> Option<C>> Instance for Registered<C, F> {
+    fn is_alive(&self) -> bool {
+        (self.upgrade)().is_some()
+    }
+
+    fn apply(&self, literals: &HashMap<SiteKey, Literal>) {
+        let this = if let Some(this) = (self.upgrade)() {
+            this
+        } else {
+            return;
+        };
+
+        for site in self.sites.sites.iter() {
+            let key = site_key(self.sites.comp, site.field, site.subfield);
+            if let Some(lit) = literals.get(&key) {
+                (site.apply)(&this, lit);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static STATE: State = State {
+        wm: Cell::new(None),
+        files: RefCell::new(HashMap::new()),
+    };
+}
+
+/// Register a component instance. Called by Designer-generated code.
+///
+/// The literals that were changed after the program was compiled are applied
+/// to the instance immediately.
+pub fn register<C: 'static>(
+    sites: &'static CompSites<C>,
+    upgrade: impl Fn() -> Option<C> + 'static,
+) {
+    let instance = Rc::new(Registered { sites, upgrade });
+
+    let outdated: HashMap<SiteKey, Literal> = STATE.with(|state| {
+        let mut files = state.files.borrow_mut();
+        let file = files.entry(sites.file).or_insert_with(|| {
+            let mut file = SourceFile::load(sites.file);
+            if let Some(wm) = state.wm.get() {
+                file.watcher = watch(wm, sites.file);
+            }
+            file
+        });
+        file.instances.push(instance.clone());
+
+        (sites.sites.iter())
+            .filter_map(|site| {
+                let key = site_key(sites.comp, site.field, site.subfield);
+                let lit = file.literals.get(&key)?;
+                if parse_literal_str(site.compiled).as_ref() == Some(lit) {
+                    None
+                } else {
+                    Some((key, lit.clone()))
+                }
+            })
+            .collect()
+    });
+
+    // Setters may call back into this module, so `STATE` must not be
+    // borrowed at this point
+    instance.apply(&outdated);
+}
+
+/// Start watching the registered source files for changes. The files of the
+/// components registered later are watched as well.
+///
+/// Does nothing if it has already been called.
+pub fn start(wm: pal::Wm) {
+    STATE.with(|state| {
+        if state.wm.replace(Some(wm)).is_some() {
+            return;
+        }
+
+        for (&path, file) in state.files.borrow_mut().iter_mut() {
+            file.watcher = watch(wm, path);
+        }
+    });
+}
+
+fn watch(wm: pal::Wm, path: &'static str) -> Option<fswatch::Watcher> {
+    // The events are only used as a hint to re-scan the file
+    fswatch::Watcher::new(wm, path, false, move |_, _| reload(path))
+        .map_err(|e| log::warn!("Could not watch {:?} for changes: {}", path, e))
+        .ok()
+}
+
+/// Re-scan the source file and apply the changed literals.
+fn reload(path: &'static str) {
+    let pending = STATE.with(|state| {
+        let mut files = state.files.borrow_mut();
+        let file = files.get_mut(path)?;
+
+        file.instances.retain(|instance| instance.is_alive());
+
+        let literals = match scan_file(path) {
+            Ok(literals) => literals,
+            Err(e) => {
+                // The file might be in the middle of being written. Keep the
+                // last good literals so that the changes are detected
+                // correctly on the next event.
+                log::warn!("Could not reload {:?}: {}", path, e);
+                return None;
+            }
+        };
+
+        let changed: HashMap<SiteKey, Literal> = (literals.iter())
+            .filter(|(key, lit)| file.literals.get(*key) != Some(*lit))
+            .map(|(key, lit)| (key.clone(), lit.clone()))
+            .collect();
+        file.literals = literals;
+
+        if changed.is_empty() {
+            None
+        } else {
+            log::info!("Reloading {} literal(s) from {:?}", changed.len(), path);
+            Some((changed, file.instances.clone()))
+        }
+    });
+
+    // Setters may call back into this module, so `STATE` must not be
+    // borrowed at this point
+    if let Some((changed, instances)) = pending {
+        for instance in instances {
+            instance.apply(&changed);
+        }
+    }
+}
+
+impl SourceFile {
+    fn load(path: &str) -> Self {
+        let literals = scan_file(path).unwrap_or_else(|e| {
+            log::warn!("Could not load {:?}: {}", path, e);
+            HashMap::new()
+        });
+        Self {
+            literals,
+            instances: Vec::new(),
+            watcher: None,
+        }
+    }
+}
+
+fn scan_file(path: &str) -> Result<HashMap<SiteKey, Literal>, String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let tokens: TokenStream = source
+        .parse()
+        .map_err(|e: proc_macro2::LexError| format!("{:?}", e))?;
+    Ok(scan_source(tokens))
+}
+
+/// Find sites in a source file.
+///
+/// This function does not parse the full syntax. It only looks for the
+/// pattern `comp path { ... const field = path::new! { subfield = literal,
+/// ... }; ... }`.
+fn scan_source(tokens: TokenStream) -> HashMap<SiteKey, Literal> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut out = HashMap::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            TokenTree::Ident(x) if x == "comp" => {}
+            _ => continue,
+        }
+
+        // `comp crate::Component { ... }`
+        let path_tokens = tokens[i + 1..]
+            .iter()
+            .take_while(|t| match t {
+                TokenTree::Ident(_) => true,
+                TokenTree::Punct(p) => p.as_char() == ':',
+                _ => false,
+            })
+            .collect::<Vec<_>>();
+        if !matches!(path_tokens.first(), Some(TokenTree::Ident(_))) {
+            continue;
+        }
+        let body = match tokens.get(i + 1 + path_tokens.len()) {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Brace => g,
+            _ => continue,
+        };
+
+        let path: String = path_tokens.iter().map(|t| t.to_string()).collect();
+        scan_comp(&path, body.stream(), &mut out);
+    }
+
+    out
+}
+
+fn scan_comp(comp: &str, body: TokenStream, out: &mut HashMap<SiteKey, Literal>) {
+    let tokens: Vec<TokenTree> = body.into_iter().collect();
+
+    // Items with an accessor block or an `on` item might not end with `;`, so
+    // an item found by this may include some preceding items
+    for item in tokens.split(|t| is_punct(t, ';')) {
+        // `path::new! { ... }`
+        let (new_i, obj_init) = match item.windows(4).enumerate().find_map(|(i, w)| match w {
+            [TokenTree::Punct(colon), TokenTree::Ident(new), TokenTree::Punct(bang), TokenTree::Group(g)]
+                if colon.as_char() == ':'
+                    && new == "new"
+                    && bang.as_char() == '!'
+                    && g.delimiter() == Delimiter::Brace =>
+            {
+                Some((i, g.stream()))
+            }
+            _ => None,
+        }) {
+            Some(x) => x,
+            None => continue,
+        };
+
+        // `const field = ...`, the last one preceding the object
+        // initialization literal
+        let const_i = match item[..new_i].iter().rposition(|t| is_ident(t, "const")) {
+            Some(i) => i,
+            None => continue,
+        };
+        let field = match item.get(const_i + 1) {
+            Some(TokenTree::Ident(x)) => x.to_string(),
+            _ => continue,
+        };
+
+        scan_obj_init(comp, &field, obj_init, out);
+    }
+}
+
+fn scan_obj_init(
+    comp: &str,
+    field: &str,
+    obj_init: TokenStream,
+    out: &mut HashMap<SiteKey, Literal>,
+) {
+    let tokens: Vec<TokenTree> = obj_init.into_iter().collect();
+
+    for entry in tokens.split(|t| is_punct(t, ',')) {
+        // `subfield = literal`
+        if let [TokenTree::Ident(subfield), TokenTree::Punct(eq), value @ ..] = entry {
+            if eq.as_char() != '=' || eq.spacing() != Spacing::Alone {
+                continue;
+            }
+            if let Some(lit) = parse_literal(value) {
+                out.insert(site_key(comp, field, &subfield.to_string()), lit);
+            }
+        }
+    }
+}
+
+fn parse_literal_str(source: &str) -> Option<Literal> {
+    let tokens: TokenStream = source.parse().ok()?;
+    parse_literal(&tokens.into_iter().collect::<Vec<_>>())
+}
+
+/// Interpret a literal expression, e.g., `"hello"`, `-42`, or `true`.
+fn parse_literal(tokens: &[TokenTree]) -> Option<Literal> {
+    match tokens {
+        [TokenTree::Ident(x)] if x == "true" => Some(Literal::Bool(true)),
+        [TokenTree::Ident(x)] if x == "false" => Some(Literal::Bool(false)),
+        [TokenTree::Literal(lit)] => match syn::Lit::new(lit.clone()) {
+            syn::Lit::Str(x) => Some(Literal::Str(x.value())),
+            syn::Lit::Int(x) => x.base10_parse().ok().map(Literal::Int),
+            syn::Lit::Float(x) => x.base10_parse().ok().map(Literal::Float),
+            syn::Lit::Char(x) => Some(Literal::Char(x.value())),
+            _ => None,
+        },
+        [TokenTree::Punct(minus), rest @ ..] if minus.as_char() == '-' => {
+            match parse_literal(rest)? {
+                Literal::Int(x) => Some(Literal::Int(-x)),
+                Literal::Float(x) => Some(Literal::Float(-x)),
+                _ => None,
+            }
+        }
+        [TokenTree::Group(g)] if g.delimiter() != Delimiter::Brace => {
+            parse_literal(&g.stream().into_iter().collect::<Vec<_>>())
+        }
+        _ => None,
+    }
+}
+
+fn is_punct(token: &TokenTree, ch: char) -> bool {
+    match token {
+        TokenTree::Punct(p) => p.as_char() == ch,
+        _ => false,
+    }
+}
+
+fn is_ident(token: &TokenTree, name: &str) -> bool {
+    match token {
+        TokenTree::Ident(x) => x == name,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(source: &str) -> HashMap<SiteKey, Literal> {
+        scan_source(source.parse().unwrap())
+    }
+
+    #[test]
+    fn scan_sites() {
+        let sites = scan(
+            r#"
+            use crate::comp::{Label, Slider};
+
+            comp crate::ui::Comp {
+                const wm: pal::Wm { set; }
+                const slider = crate::ui::Slider::new! {
+                    value <-> text,
+                    min = -1,
+                    max = (2.5),
+                };
+
+                prop text: String = "not a site".to_owned();
+
+                on (init) { println!("{}", get!(text)); }
+
+                const label = Label::new! {
+                    style_manager,
+                    text = "Hello",
+                    ellipsis = false,
+                    class_set = get!(class_set),
+                };
+            }
+            "#,
+        );
+
+        let key = |field: &str, subfield: &str| site_key("crate::ui::Comp", field, subfield);
+        let mut expected = HashMap::new();
+        expected.insert(key("label", "text"), Literal::Str("Hello".to_owned()));
+        expected.insert(key("label", "ellipsis"), Literal::Bool(false));
+        expected.insert(key("slider", "min"), Literal::Int(-1));
+        expected.insert(key("slider", "max"), Literal::Float(2.5));
+        assert_eq!(sites, expected);
+    }
+
+    #[test]
+    fn literal_conversion() {
+        assert_eq!(parse_literal_str("300").unwrap().to_num::<u16>(), Some(300));
+        assert_eq!(parse_literal_str("300").unwrap().to_num::<u8>(), None);
+        assert_eq!(parse_literal_str("300").unwrap().to_num::<f32>(), None);
+        assert_eq!(
+            parse_literal_str("- 1.5").unwrap().to_num::<f32>(),
+            Some(-1.5)
+        );
+        assert_eq!(parse_literal_str("'x'").unwrap().to_char(), Some('x'));
+        assert_eq!(
+            parse_literal_str(r#""a\nb""#).unwrap().to_str(),
+            Some("a\nb")
+        );
+        assert_eq!(parse_literal_str("get!(x)"), None);
+    }
+}
@@ -6,7 +6,10 @@
 //! This crate re-exports items from some crates so that the implementors
 //! of Designer components do not have to depend on `subscriber_list` by
 //! themselves.
-use std::{cell::Cell, mem::MaybeUninit};
+use std::{
+    cell::Cell, collections::HashMap, fmt, hash::Hash, mem::MaybeUninit, ops::Range, rc::Rc,
+    time::Duration,
+};
 use tcw3_pal as pal;
 use tcw3_pal::prelude::*;
 
@@ -19,6 +22,9 @@ pub use owning_ref::OwningRef;
 #[doc(no_inline)]
 pub use harmony::ShallowEq;
 
+#[cfg(feature = "hot_reload")]
+pub mod hot_reload;
+
 /// A placeholder value for unset mandatory parameters.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Unset;
@@ -57,3 +63,205 @@ pub unsafe fn unsubscribe_subs_unchecked(wm: pal::Wm, subs: &mut [Cell<MaybeUnin
         }
     });
 }
+
+/// Implements `if` in the value of a `wire`.
+///
+/// If `cond` is `true`, the instance in `old` is reused after updating it by
+/// `update`, or a new one is created by `create` if there is none. If `cond`
+/// is `false`, the instance is dropped when the old value is replaced.
+///
+/// Returns the new value and a flag indicating whether an instance was
+/// created or dropped.
+pub fn mount_if<T: Clone>(
+    old: &Option<T>,
+    cond: bool,
+    update: impl FnOnce(&T),
+    create: impl FnOnce() -> T,
+) -> (Option<T>, bool) {
+    match (old, cond) {
+        (Some(instance), true) => {
+            update(instance);
+            (Some(instance.clone()), false)
+        }
+        (None, true) => (Some(create()), true),
+        (Some(_), false) => (None, true),
+        (None, false) => (None, false),
+    }
+}
+
+/// Implements `for` in the value of a `wire`.
+///
+/// For each item produced by `items`, the instance associated with an equal
+/// item in `old` is reused after updating it by `update`, or a new one is
+/// created by `create` if there is none. Each instance in `old` is reused at
+/// most once. The instances not reused are dropped when the old value is
+/// replaced.
+///
+/// Returns the new value and a flag indicating whether any instances were
+/// created, dropped, or reordered.
+pub fn mount_for<K: Eq + Hash, T: Clone>(
+    old: &[(K, T)],
+    items: impl IntoIterator<Item = K>,
+    mut update: impl FnMut(&K, &T),
+    mut create: impl FnMut(&K) -> T,
+) -> (Vec<(K, T)>, bool) {
+    // Map each item to the first unused instance associated with it
+    let mut unused: HashMap<&K, usize> = HashMap::with_capacity(old.len());
+    for (i, (key, _)) in old.iter().enumerate().rev() {
+        unused.insert(key, i);
+    }
+
+    let mut changed = false;
+    let new: Vec<(K, T)> = items
+        .into_iter()
+        .enumerate()
+        .map(|(i, key)| {
+            if let Some(old_i) = unused.remove(&key) {
+                let instance = &old[old_i].1;
+                update(&key, instance);
+                changed |= old_i != i;
+                (key, instance.clone())
+            } else {
+                changed = true;
+                let instance = create(&key);
+                (key, instance)
+            }
+        })
+        .collect();
+
+    let changed = changed || new.len() != old.len();
+    (new, changed)
+}
+
+/// Get the delay range passed to `Wm::invoke_after` for rate-limiting event
+/// handlers. The system is allowed to delay a call by up to 25% for power
+/// efficiency.
+fn rate_limit_delay(duration: Duration) -> Range<Duration> {
+    duration..duration + duration / 4
+}
+
+/// Implements the `debounce` modifier of `on` items.
+#[derive(Default)]
+pub struct Debounce {
+    hinv: Cell<Option<pal::HInvoke>>,
+}
+
+impl Debounce {
+    /// Call `f` after `duration`. The call is cancelled if `call` is called
+    /// again before that.
+    pub fn call(&self, wm: pal::Wm, duration: Duration, f: impl FnOnce(pal::Wm) + 'static) {
+        if let Some(hinv) = self.hinv.take() {
+            wm.cancel_invoke(&hinv);
+        }
+        self.hinv
+            .set(Some(wm.invoke_after(rate_limit_delay(duration), f)));
+    }
+}
+
+/// Implements the `throttle` modifier of `on` items.
+#[derive(Default)]
+pub struct Throttle {
+    inner: Rc<ThrottleInner>,
+}
+
+#[derive(Default)]
+struct ThrottleInner {
+    /// `true` if `f` was called in the last `duration`.
+    active: Cell<bool>,
+    /// The call deferred until the end of the current period.
+    pending: Cell<Option<Box<dyn FnOnce(pal::Wm)>>>,
+}
+
+impl Throttle {
+    /// Call `f` immediately if no calls were made in the last `duration`.
+    /// Otherwise, defer the call until `duration` elapses since the last
+    /// call. A deferred call replaces the previously deferred one.
+    pub fn call(&self, wm: pal::Wm, duration: Duration, f: impl FnOnce(pal::Wm) + 'static) {
+        if self.inner.active.get() {
+            self.inner.pending.set(Some(Box::new(f)));
+        } else {
+            self.inner.active.set(true);
+            ThrottleInner::schedule_end(Rc::clone(&self.inner), wm, duration);
+            f(wm);
+        }
+    }
+}
+
+impl ThrottleInner {
+    fn schedule_end(this: Rc<Self>, wm: pal::Wm, duration: Duration) {
+        wm.invoke_after(rate_limit_delay(duration), move |wm| {
+            if let Some(f) = this.pending.take() {
+                Self::schedule_end(Rc::clone(&this), wm, duration);
+                f(wm);
+            } else {
+                this.active.set(false);
+            }
+        });
+    }
+}
+
+/// Wraps a reference to a value to be displayed by a generated `Debug`
+/// implementation.
+///
+/// Generated code calls `(&DebugIfAvailable(&x)).as_debug()` with both
+/// [`ViaDebug`] and [`ViaOpaque`] in scope. The method resolution picks
+/// `ViaDebug` if `x` implements `Debug`. Otherwise, it falls back to
+/// `ViaOpaque`, which only displays the type name.
+pub struct DebugIfAvailable<'a, T>(pub &'a T);
+
+impl<T> fmt::Debug for DebugIfAvailable<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}>", std::any::type_name::<T>())
+    }
+}
+
+/// See [`DebugIfAvailable`].
+pub trait ViaDebug {
+    fn as_debug(&self) -> &dyn fmt::Debug;
+}
+
+impl<T: fmt::Debug> ViaDebug for DebugIfAvailable<'_, T> {
+    #[inline]
+    fn as_debug(&self) -> &dyn fmt::Debug {
+        self.0
+    }
+}
+
+/// See [`DebugIfAvailable`].
+pub trait ViaOpaque {
+    fn as_debug(&self) -> &dyn fmt::Debug;
+}
+
+impl<T> ViaOpaque for &DebugIfAvailable<'_, T> {
+    #[inline]
+    fn as_debug(&self) -> &dyn fmt::Debug {
+        *self
+    }
+}
+
+/// The snapshot of a component instance's fields, returned by the
+/// `introspect` method generated for a component with `#[introspect]`.
+#[derive(Debug, Clone)]
+pub struct CompInfo {
+    /// The name of the component type.
+    pub name: &'static str,
+    pub fields: Vec<FieldInfo>,
+}
+
+/// A field in [`CompInfo`].
+#[derive(Debug, Clone)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    pub field_ty: FieldType,
+    /// The value formatted by `Debug`. If the field type does not implement
+    /// `Debug`, this is the type name enclosed in `<>`.
+    pub value: String,
+}
+
+/// The type of a field in [`FieldInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldType {
+    Const,
+    Prop,
+    Wire,
+}
@@ -7,7 +7,7 @@ use tcw3::{
         theming,
         views::{
             scrollbar::ScrollbarDragListener, Button, Checkbox, Entry, Label, RadioButton,
-            ScrollbarRaw, Slider,
+            RadioGroup, ScrollbarRaw, Slider,
         },
         AlignFlags,
     },
@@ -159,15 +159,8 @@ fn main() {
     button.set_caption("Please don't touch this button");
 
     let checkbox = Checkbox::new(style_manager);
-    let checkbox = Rc::new(checkbox);
     checkbox.set_caption("Milk");
-    {
-        let checkbox_weak = Rc::downgrade(&checkbox);
-        checkbox.subscribe_activated(Box::new(move |_| {
-            let checkbox = checkbox_weak.upgrade().unwrap();
-            checkbox.set_checked(!checkbox.checked());
-        }));
-    }
+    checkbox.set_auto_toggle(true);
 
     let v_layout1 = {
         let view = HView::new(Default::default());
@@ -183,18 +176,13 @@ fn main() {
         RadioButton::new(style_manager),
         RadioButton::new(style_manager),
     ];
-    let rbuttons = Rc::new(rbuttons);
     rbuttons[0].set_caption("Earth");
     rbuttons[1].set_caption("Pegasi");
     rbuttons[2].set_caption("Unicorn");
-    for i in 0..3 {
-        let rbuttons_weak = Rc::downgrade(&rbuttons);
-        rbuttons[i].subscribe_activated(Box::new(move |_| {
-            let rbuttons = rbuttons_weak.upgrade().unwrap();
-            for (j, b) in rbuttons.iter().enumerate() {
-                b.set_checked(i == j);
-            }
-        }));
+    let rgroup = RadioGroup::new();
+    for b in rbuttons.iter() {
+        b.set_auto_toggle(true);
+        b.set_group(Some(&rgroup));
     }
 
     let v_layout2 = {
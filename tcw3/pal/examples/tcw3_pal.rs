@@ -178,8 +178,15 @@ impl MouseDragListener<pal::Wm> for DragListener {
     fn mouse_motion(&self, _: pal::Wm, _: &pal::HWnd, loc: Point2<f32>) {
         info!("drag: mouse_motion {:?}", loc);
     }
-    fn mouse_down(&self, _: pal::Wm, _: &pal::HWnd, loc: Point2<f32>, button: u8) {
-        info!("drag: mouse_down {:?}", (loc, button));
+    fn mouse_down(
+        &self,
+        _: pal::Wm,
+        _: &pal::HWnd,
+        loc: Point2<f32>,
+        button: u8,
+        click_count: u32,
+    ) {
+        info!("drag: mouse_down {:?}", (loc, button, click_count));
     }
     fn mouse_up(&self, _: pal::Wm, _: &pal::HWnd, loc: Point2<f32>, button: u8) {
         info!("drag: mouse_up {:?}", (loc, button));
@@ -309,14 +316,14 @@ fn main() {
     let layer2 = wm.new_layer(pal::LayerAttrs {
         bounds: Some(box2! { min: [20.0, 120.0], max: [150.0, 250.0] }),
         bg_color: Some(pal::RGBAF32::new(0.5, 0.8, 0.5, 1.0)),
-        contents: Some(Some(bmp.clone())),
+        contents: Some(Some(bmp.clone().into())),
         ..Default::default()
     });
 
     let layer = wm.new_layer(pal::LayerAttrs {
         bounds: Some(box2! { min: [20.0, 20.0], max: [200.0, 100.0] }),
         bg_color: Some(pal::RGBAF32::new(0.8, 0.5, 0.5, 1.0)),
-        contents: Some(Some(bmp)),
+        contents: Some(Some(bmp.into())),
         sublayers: Some(vec![layer2.clone()]),
         transform: Some(cgmath::Matrix3::from_angle(cgmath::Deg(3.0))),
         ..Default::default()
@@ -132,7 +132,7 @@ impl State {
                         );
                         builder.fill();
 
-                        attrs.contents = Some(Some(builder.into_bitmap()));
+                        attrs.contents = Some(Some(builder.into_bitmap().into()));
                         attrs.contents_center = Some(box2! { point: [0.5, 0.5] });
                     }
                     Shape::Circle => {
@@ -146,7 +146,7 @@ impl State {
                         });
                         builder.fill();
 
-                        attrs.contents = Some(Some(builder.into_bitmap()));
+                        attrs.contents = Some(Some(builder.into_bitmap().into()));
                     }
                 }
 
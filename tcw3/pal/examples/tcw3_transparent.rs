@@ -0,0 +1,63 @@
+//! Displays a borderless, circular window using `WndFlags::TRANSPARENT`.
+//! Click the window to close it.
+use cggeom::box2;
+use cgmath::Point2;
+use tcw3_pal::{self as pal, prelude::*};
+
+const SIZE: u32 = 200;
+
+struct Listener;
+
+impl WndListener<pal::Wm> for Listener {
+    fn close_requested(&self, wm: pal::Wm, _: &pal::HWnd) {
+        wm.terminate();
+    }
+
+    fn mouse_drag(
+        &self,
+        wm: pal::Wm,
+        _: &pal::HWnd,
+        _: Point2<f32>,
+        _: u8,
+    ) -> Box<dyn MouseDragListener<pal::Wm>> {
+        wm.terminate();
+        Box::new(())
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let wm = pal::Wm::global();
+
+    // Draw a translucent disc with an opaque ring. The pixels outside the
+    // disc are left fully transparent.
+    let size = SIZE as f32;
+    let mut bmp_builder = pal::BitmapBuilder::new([SIZE, SIZE]);
+    bmp_builder.set_fill_rgb([0.2, 0.4, 0.8, 0.6].into());
+    bmp_builder.ellipse(box2! { min: [4.0, 4.0], max: [size - 4.0, size - 4.0] });
+    bmp_builder.fill();
+    bmp_builder.set_stroke_rgb([0.2, 0.4, 0.8, 1.0].into());
+    bmp_builder.set_line_width(6.0);
+    bmp_builder.ellipse(box2! { min: [4.0, 4.0], max: [size - 4.0, size - 4.0] });
+    bmp_builder.stroke();
+
+    let layer = wm.new_layer(pal::LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [size, size] }),
+        contents: Some(Some(bmp_builder.into_bitmap().into())),
+        ..Default::default()
+    });
+
+    let wnd = wm.new_wnd(pal::WndAttrs {
+        caption: Some("Transparent window".into()),
+        visible: Some(true),
+        layer: Some(Some(layer)),
+        size: Some([SIZE, SIZE]),
+        listener: Some(Box::new(Listener)),
+        flags: Some(pal::WndFlags::BORDERLESS | pal::WndFlags::TRANSPARENT),
+        ..Default::default()
+    });
+
+    wm.update_wnd(&wnd);
+    wm.enter_main_loop();
+}
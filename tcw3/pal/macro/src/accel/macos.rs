@@ -54,6 +54,7 @@ pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
         #crate_path::macos::AccelTable {
             key: &[#(#key_bindings),*],
             sel: &[#(#sel_bindings),*],
+            key_bindings: ::std::vec::Vec::new(),
         }
     }
 }
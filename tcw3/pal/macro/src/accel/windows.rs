@@ -25,6 +25,7 @@ pub(super) fn gen_accel_table(input: &MacroInput) -> proc_macro2::TokenStream {
     quote::quote! {
         #crate_path::windows::AccelTable {
             key: &[#(#key_bindings),*],
+            key_bindings: ::std::vec::Vec::new(),
         }
     }
 }
@@ -4,7 +4,8 @@
 //! They are not written in-line but instead defined here because they are
 //! lengthy and make `iface.rs` harder to read.
 use cggeom::{prelude::*, Box2};
-use cgmath::Point2;
+use cgmath::{vec2, Point2};
+use std::f32::consts::PI;
 
 use super::iface::Canvas;
 
@@ -143,3 +144,206 @@ pub fn canvas_ellipse(canvas: &mut (impl Canvas + ?Sized), bx: Box2<f32>) {
     let radius = [bx.size().x * 0.5, bx.size().y * 0.5];
     canvas_rounded_rect(canvas, bx, [radius; 4]);
 }
+
+pub fn canvas_arc_to(
+    canvas: &mut (impl Canvas + ?Sized),
+    center: Point2<f32>,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+) {
+    let sweep = end_angle - start_angle;
+
+    // Split the arc into segments, each spanning up to 90 degrees
+    let num_segments = (sweep.abs() * (2.0 / PI)).ceil().max(1.0) as usize;
+    let segment_sweep = sweep / num_segments as f32;
+
+    // The distance between an end point and the adjacent control point for
+    // approximating a circular arc of angle `θ` using a cubic Bézier curve is
+    // `4/3 * tan(θ/4)` times the radius. It's negative if `θ` is negative,
+    // making the tangent vectors below point to the right direction.
+    let cp_dist = radius * (4.0 / 3.0) * (segment_sweep * 0.25).tan();
+
+    let (sin, cos) = start_angle.sin_cos();
+    let mut p = center + vec2(cos, sin) * radius;
+    let mut tangent = vec2(-sin, cos);
+
+    canvas.line_to(p);
+
+    for i in 1..=num_segments {
+        let (sin, cos) = (start_angle + segment_sweep * i as f32).sin_cos();
+        let next_p = center + vec2(cos, sin) * radius;
+        let next_tangent = vec2(-sin, cos);
+
+        canvas.cubic_bezier_to(
+            p + tangent * cp_dist,
+            next_p - next_tangent * cp_dist,
+            next_p,
+        );
+
+        p = next_p;
+        tangent = next_tangent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{assert_abs_diff_eq, MetricSpace};
+
+    use super::*;
+    use crate::iface::{LineCap, LineJoin, RGBAF32};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Cmd {
+        LineTo(Point2<f32>),
+        CubicBezierTo(Point2<f32>, Point2<f32>, Point2<f32>),
+    }
+
+    /// Records path commands. The other methods are no-ops.
+    #[derive(Debug, Default)]
+    struct RecordingCanvas {
+        cmds: Vec<Cmd>,
+    }
+
+    impl Canvas for RecordingCanvas {
+        fn save(&mut self) {}
+        fn restore(&mut self) {}
+        fn begin_path(&mut self) {}
+        fn close_path(&mut self) {}
+        fn move_to(&mut self, _: Point2<f32>) {}
+        fn line_to(&mut self, p: Point2<f32>) {
+            self.cmds.push(Cmd::LineTo(p));
+        }
+        fn cubic_bezier_to(&mut self, cp1: Point2<f32>, cp2: Point2<f32>, p: Point2<f32>) {
+            self.cmds.push(Cmd::CubicBezierTo(cp1, cp2, p));
+        }
+        fn quad_bezier_to(&mut self, _: Point2<f32>, _: Point2<f32>) {
+            unreachable!()
+        }
+        fn fill(&mut self) {}
+        fn stroke(&mut self) {}
+        fn clip(&mut self) {}
+        fn set_fill_rgb(&mut self, _: RGBAF32) {}
+        fn set_stroke_rgb(&mut self, _: RGBAF32) {}
+        fn set_line_cap(&mut self, _: LineCap) {}
+        fn set_line_join(&mut self, _: LineJoin) {}
+        fn set_line_dash(&mut self, _: f32, _: &[f32]) {}
+        fn set_line_width(&mut self, _: f32) {}
+        fn set_line_miter_limit(&mut self, _: f32) {}
+        fn mult_transform(&mut self, _: cgmath::Matrix3<f32>) {}
+    }
+
+    fn eval_cubic_bezier(
+        p0: Point2<f32>,
+        cp1: Point2<f32>,
+        cp2: Point2<f32>,
+        p3: Point2<f32>,
+        t: f32,
+    ) -> Point2<f32> {
+        let s = 1.0 - t;
+        Point2::new(
+            s * s * s * p0.x + 3.0 * s * s * t * cp1.x + 3.0 * s * t * t * cp2.x + t * t * t * p3.x,
+            s * s * s * p0.y + 3.0 * s * s * t * cp1.y + 3.0 * s * t * t * cp2.y + t * t * t * p3.y,
+        )
+    }
+
+    /// Check that `cmds` describes an arc starting at `start` and ending at
+    /// `end`, lying on the circle within a tolerance. Returns the number of
+    /// Bézier segments.
+    fn check_arc(
+        cmds: &[Cmd],
+        center: Point2<f32>,
+        radius: f32,
+        start: Point2<f32>,
+        end: Point2<f32>,
+    ) -> usize {
+        let mut p = match cmds[0] {
+            Cmd::LineTo(p) => p,
+            cmd => panic!("{:?}", cmd),
+        };
+        assert_abs_diff_eq!(p, start, epsilon = 0.001);
+
+        for &cmd in &cmds[1..] {
+            let (cp1, cp2, next_p) = match cmd {
+                Cmd::CubicBezierTo(cp1, cp2, p) => (cp1, cp2, p),
+                cmd => panic!("{:?}", cmd),
+            };
+            for &t in &[0.0, 0.25, 0.5, 0.75, 1.0] {
+                let pt = eval_cubic_bezier(p, cp1, cp2, next_p, t);
+                assert_abs_diff_eq!(pt.distance(center), radius, epsilon = radius * 0.001);
+            }
+            p = next_p;
+        }
+        assert_abs_diff_eq!(p, end, epsilon = 0.001);
+
+        cmds.len() - 1
+    }
+
+    #[test]
+    fn arc_to_fallback() {
+        let center = Point2::new(20.0, 30.0);
+
+        // A half circle, clockwise on screen
+        let mut canvas = RecordingCanvas::default();
+        canvas_arc_to(&mut canvas, center, 10.0, 0.0, PI);
+        let num_segments = check_arc(
+            &canvas.cmds,
+            center,
+            10.0,
+            Point2::new(30.0, 30.0),
+            Point2::new(10.0, 30.0),
+        );
+        assert_eq!(num_segments, 2);
+
+        // The same half circle must pass through the bottom point
+        // (the positive Y direction)
+        match canvas.cmds[1] {
+            Cmd::CubicBezierTo(_, _, p) => {
+                assert_abs_diff_eq!(p, Point2::new(20.0, 40.0), epsilon = 0.001)
+            }
+            cmd => panic!("{:?}", cmd),
+        }
+
+        // Counter-clockwise, passing through the top point
+        let mut canvas = RecordingCanvas::default();
+        canvas_arc_to(&mut canvas, center, 10.0, 0.0, -PI);
+        let num_segments = check_arc(
+            &canvas.cmds,
+            center,
+            10.0,
+            Point2::new(30.0, 30.0),
+            Point2::new(10.0, 30.0),
+        );
+        assert_eq!(num_segments, 2);
+        match canvas.cmds[1] {
+            Cmd::CubicBezierTo(_, _, p) => {
+                assert_abs_diff_eq!(p, Point2::new(20.0, 20.0), epsilon = 0.001)
+            }
+            cmd => panic!("{:?}", cmd),
+        }
+
+        // A small arc is drawn as a single segment
+        let mut canvas = RecordingCanvas::default();
+        canvas_arc_to(&mut canvas, center, 10.0, 0.1, 0.5);
+        let num_segments = check_arc(
+            &canvas.cmds,
+            center,
+            10.0,
+            center + vec2(0.1f32.cos(), 0.1f32.sin()) * 10.0,
+            center + vec2(0.5f32.cos(), 0.5f32.sin()) * 10.0,
+        );
+        assert_eq!(num_segments, 1);
+
+        // A full circle
+        let mut canvas = RecordingCanvas::default();
+        canvas_arc_to(&mut canvas, center, 10.0, 0.0, PI * 2.0);
+        let num_segments = check_arc(
+            &canvas.cmds,
+            center,
+            10.0,
+            Point2::new(30.0, 30.0),
+            Point2::new(30.0, 30.0),
+        );
+        assert_eq!(num_segments, 4);
+    }
+}
@@ -0,0 +1,328 @@
+//! A multi-producer, single-consumer channel whose messages are delivered to
+//! the main thread in batches.
+//!
+//! Unlike [`Wm::invoke_on_main_thread`], which allocates a boxed closure for
+//! every call, a channel enqueues at most one call for each batch of messages.
+//! Messages are stored in a bounded queue, and senders are made to wait (or
+//! fail, in the case of [`Sender::try_send`]) when the main thread can't keep
+//! up with them. [`Sender::send_coalesced`] can be used to merge a message
+//! with a pending one, which is useful for a high-frequency stream of status
+//! updates (e.g., download progress) where only the latest value matters.
+//!
+//! Channels are created by [`Wm::channel`] or [`Wm::channel_with_capacity`].
+//!
+//! [`Wm::invoke_on_main_thread`]: crate::iface::Wm::invoke_on_main_thread
+//! [`Wm::channel`]: crate::iface::Wm::channel
+//! [`Wm::channel_with_capacity`]: crate::iface::Wm::channel_with_capacity
+use std::{
+    cell::RefCell,
+    fmt,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Condvar, Mutex, MutexGuard,
+    },
+    vec::Drain,
+};
+
+use crate::{prelude::WmTrait, MtSticky, Wm};
+
+/// The capacity of a channel created by [`Wm::channel`].
+///
+/// [`Wm::channel`]: crate::iface::Wm::channel
+pub const DEFAULT_CAPACITY: usize = 256;
+
+/// The sending half of a channel. Can be cloned and sent to other threads.
+pub struct Sender<T, TWM: WmTrait = Wm> {
+    shared: Arc<Shared<T, TWM>>,
+}
+
+/// The receiving half of a channel.
+///
+/// Messages are not delivered until a handler is registered by
+/// [`Receiver::set_handler`]. Dropping `Receiver` closes the channel and
+/// discards all pending messages.
+pub struct Receiver<T, TWM: WmTrait = Wm> {
+    shared: Arc<Shared<T, TWM>>,
+}
+
+/// The error type returned by [`Sender::send`] and [`Sender::send_coalesced`]
+/// when the receiver was dropped. Contains the message that couldn't be
+/// sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// The error type returned by [`Sender::try_send`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// The queue is full.
+    Full(T),
+    /// The receiver was dropped.
+    Disconnected(T),
+}
+
+type Handler<T, TWM> = Box<dyn FnMut(TWM, Drain<'_, T>)>;
+
+struct Shared<T, TWM: WmTrait> {
+    queue: Mutex<Vec<T>>,
+    /// Signalled when `queue` becomes non-full or the channel is closed.
+    not_full: Condvar,
+    capacity: usize,
+    /// `true` if a call to `deliver` is enqueued.
+    scheduled: AtomicBool,
+    closed: AtomicBool,
+    num_senders: AtomicUsize,
+    handler: MtSticky<RefCell<Option<Handler<T, TWM>>>, TWM>,
+}
+
+pub(crate) fn channel<T: Send + 'static, TWM: WmTrait>(
+    capacity: usize,
+) -> (Sender<T, TWM>, Receiver<T, TWM>) {
+    assert!(capacity > 0, "capacity must be non-zero");
+
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(Vec::new()),
+        not_full: Condvar::new(),
+        capacity,
+        scheduled: AtomicBool::new(false),
+        closed: AtomicBool::new(false),
+        num_senders: AtomicUsize::new(1),
+        // There is no handler at this point, so this is safe
+        handler: unsafe { MtSticky::new_unchecked(RefCell::new(None)) },
+    });
+
+    (
+        Sender {
+            shared: Arc::clone(&shared),
+        },
+        Receiver { shared },
+    )
+}
+
+impl<T: Send + 'static, TWM: WmTrait> Sender<T, TWM> {
+    /// Send a message, blocking the calling thread while the queue is full.
+    ///
+    /// This method must not be called by the main thread if the queue may
+    /// be full because it would never be emptied. Use [`Sender::try_send`]
+    /// instead.
+    pub fn send(&self, x: T) -> Result<(), SendError<T>> {
+        let mut queue = self.shared.lock_queue();
+
+        while queue.len() >= self.shared.capacity && !self.shared.is_closed() {
+            debug_assert!(
+                !TWM::is_main_thread(),
+                "`Sender::send` would block the main thread forever"
+            );
+            queue = self.shared.not_full.wait(queue).unwrap();
+        }
+
+        if self.shared.is_closed() {
+            return Err(SendError(x));
+        }
+
+        self.shared.push(queue, x);
+        Ok(())
+    }
+
+    /// Send a message without blocking.
+    pub fn try_send(&self, x: T) -> Result<(), TrySendError<T>> {
+        let queue = self.shared.lock_queue();
+
+        if self.shared.is_closed() {
+            Err(TrySendError::Disconnected(x))
+        } else if queue.len() >= self.shared.capacity {
+            Err(TrySendError::Full(x))
+        } else {
+            self.shared.push(queue, x);
+            Ok(())
+        }
+    }
+
+    /// Send a message, merging it with the last pending message (i.e., the
+    /// last one that hasn't been delivered to the handler yet) if there is
+    /// one.
+    ///
+    /// `merge` is called with the last pending message and `x`. It should
+    /// return `None` if `x` was merged into the first parameter, or
+    /// `Some(x)` if they can't be merged. In the latter case, `x` is sent in
+    /// the same way as [`Sender::send`].
+    ///
+    /// `merge` is called while the queue is locked, so it should return
+    /// quickly.
+    pub fn send_coalesced(
+        &self,
+        x: T,
+        merge: impl FnOnce(&mut T, T) -> Option<T>,
+    ) -> Result<(), SendError<T>> {
+        let x = {
+            let mut queue = self.shared.lock_queue();
+
+            if self.shared.is_closed() {
+                return Err(SendError(x));
+            }
+
+            if let Some(last) = queue.last_mut() {
+                match merge(last, x) {
+                    None => return Ok(()),
+                    Some(x) => x,
+                }
+            } else {
+                x
+            }
+        };
+
+        self.send(x)
+    }
+}
+
+impl<T, TWM: WmTrait> Clone for Sender<T, TWM> {
+    fn clone(&self) -> Self {
+        self.shared.num_senders.fetch_add(1, Ordering::Relaxed);
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T, TWM: WmTrait> Drop for Sender<T, TWM> {
+    fn drop(&mut self) {
+        self.shared.num_senders.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl<T, TWM: WmTrait> fmt::Debug for Sender<T, TWM> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Sender")
+            .field("capacity", &self.shared.capacity)
+            .field("closed", &self.shared.is_closed())
+            .finish()
+    }
+}
+
+impl<T: Send + 'static, TWM: WmTrait> Receiver<T, TWM> {
+    /// Register a function to be called with the pending messages.
+    ///
+    /// The handler is called by the main event loop (never from inside this
+    /// method) with one or more messages in the order they were sent.
+    /// Replaces the previous handler if there is one.
+    pub fn set_handler(&self, wm: TWM, handler: impl FnMut(TWM, Drain<'_, T>) + 'static) {
+        self.shared
+            .handler
+            .get_with_wm(wm)
+            .replace(Some(Box::new(handler)));
+
+        if !self.shared.lock_queue().is_empty() {
+            Shared::schedule(&self.shared);
+        }
+    }
+
+    /// Get a flag indicating whether all senders were dropped.
+    ///
+    /// Note that there may still be pending messages when this method returns
+    /// `true`.
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.num_senders.load(Ordering::Relaxed) == 0
+    }
+}
+
+impl<T, TWM: WmTrait> Drop for Receiver<T, TWM> {
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock_queue();
+        self.shared.closed.store(true, Ordering::Relaxed);
+        queue.clear();
+        drop(queue);
+
+        self.shared.not_full.notify_all();
+
+        // Release the handler now if possible. Otherwise, it's released
+        // along with `Shared` by `MtSticky`.
+        if let Ok(wm) = TWM::try_global() {
+            if let Ok(mut handler) = self.shared.handler.get_with_wm(wm).try_borrow_mut() {
+                *handler = None;
+            }
+        }
+    }
+}
+
+impl<T, TWM: WmTrait> fmt::Debug for Receiver<T, TWM> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Receiver")
+            .field("capacity", &self.shared.capacity)
+            .finish()
+    }
+}
+
+impl<T, TWM: WmTrait> Shared<T, TWM> {
+    fn lock_queue(&self) -> MutexGuard<'_, Vec<T>> {
+        self.queue.lock().unwrap()
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+}
+
+impl<T: Send + 'static, TWM: WmTrait> Shared<T, TWM> {
+    /// Push a message and enqueue a call to `deliver` if there isn't one
+    /// already.
+    fn push(self: &Arc<Self>, mut queue: MutexGuard<'_, Vec<T>>, x: T) {
+        queue.push(x);
+        drop(queue);
+
+        Shared::schedule(self);
+    }
+
+    fn schedule(this: &Arc<Self>) {
+        if this.scheduled.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let this = Arc::clone(this);
+        if let Ok(wm) = TWM::try_global() {
+            wm.invoke(move |wm| this.deliver(wm));
+        } else {
+            TWM::invoke_on_main_thread(move |wm| this.deliver(wm));
+        }
+    }
+
+    /// Deliver all pending messages to the handler.
+    fn deliver(self: Arc<Self>, wm: TWM) {
+        self.scheduled.store(false, Ordering::Relaxed);
+
+        if self.is_closed() {
+            return;
+        }
+
+        let handler_cell = self.handler.get_with_wm(wm);
+        let mut handler = match handler_cell.try_borrow_mut() {
+            Ok(handler) => handler,
+            Err(_) => {
+                // `deliver` was called inside the handler (e.g., by a nested
+                // event loop). The outer activation will reschedule it.
+                return;
+            }
+        };
+        let handler_fn = if let Some(handler_fn) = &mut *handler {
+            handler_fn
+        } else {
+            // The messages are delivered when a handler is registered.
+            return;
+        };
+
+        // Move the messages out of the queue so that the senders aren't
+        // blocked while the handler is running
+        let mut batch = std::mem::replace(&mut *self.lock_queue(), Vec::new());
+        if batch.is_empty() {
+            return;
+        }
+        self.not_full.notify_all();
+
+        handler_fn(wm, batch.drain(..));
+        drop(handler);
+
+        // Pick up the messages that arrived while the handler was running
+        // in case they were missed by a nested activation of `deliver`
+        if !self.is_closed() && !self.lock_queue().is_empty() {
+            Shared::schedule(&self);
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! Click-count tracking for `MouseDragListener::mouse_down`.
+//!
+//! The macOS backend uses `NSEvent.clickCount`, which is determined by the
+//! system. On Windows, the double-click time and distance are retrieved from
+//! the system (`GetDoubleClickTime` and `SM_CXDOUBLECLK`). The other backends
+//! use the thresholds configured by [`set_double_click_time`] and
+//! [`set_double_click_distance`].
+use cgmath::Point2;
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+static DOUBLE_CLICK_TIME_MS: AtomicU32 = AtomicU32::new(400);
+static DOUBLE_CLICK_DISTANCE: AtomicU32 = AtomicU32::new(5);
+
+/// Set the maximum interval between consecutive clicks that count as a
+/// multi-click. Defaults to 400 milliseconds.
+///
+/// This setting is ignored by the Windows and macOS backends, which use the
+/// system settings instead.
+pub fn set_double_click_time(time: Duration) {
+    let ms = time.as_millis().min(u32::max_value() as u128) as u32;
+    DOUBLE_CLICK_TIME_MS.store(ms, Ordering::Relaxed);
+}
+
+/// Get the value set by [`set_double_click_time`].
+pub fn double_click_time() -> Duration {
+    Duration::from_millis(DOUBLE_CLICK_TIME_MS.load(Ordering::Relaxed) as u64)
+}
+
+/// Set the maximum distance (measured along each axis, in logical pixels)
+/// between consecutive clicks that count as a multi-click. Defaults to 5.
+///
+/// This setting is ignored by the Windows and macOS backends, which use the
+/// system settings instead.
+pub fn set_double_click_distance(distance: u32) {
+    DOUBLE_CLICK_DISTANCE.store(distance, Ordering::Relaxed);
+}
+
+/// Get the value set by [`set_double_click_distance`].
+pub fn double_click_distance() -> u32 {
+    DOUBLE_CLICK_DISTANCE.load(Ordering::Relaxed)
+}
+
+/// Tracks consecutive clicks to compute click counts.
+#[derive(Debug, Default)]
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+pub(crate) struct ClickCounter {
+    last: Option<LastClick>,
+}
+
+#[derive(Debug)]
+struct LastClick {
+    time: Instant,
+    loc: Point2<f32>,
+    button: u8,
+    count: u32,
+}
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+impl ClickCounter {
+    /// Register a mouse button press using the configured thresholds and
+    /// return its click count.
+    pub fn click(&mut self, time: Instant, loc: Point2<f32>, button: u8) -> u32 {
+        let distance = double_click_distance() as f32;
+        self.click_with_thresholds(time, loc, button, double_click_time(), [distance, distance])
+    }
+
+    /// Register a mouse button press and return its click count (1 for a
+    /// single click, 2 for a double click, and so on).
+    ///
+    /// The press continues a multi-click if it uses the same button as the
+    /// previous one, happens within `max_interval`, and is no more than
+    /// `max_distance` away along each axis.
+    pub fn click_with_thresholds(
+        &mut self,
+        time: Instant,
+        loc: Point2<f32>,
+        button: u8,
+        max_interval: Duration,
+        max_distance: [f32; 2],
+    ) -> u32 {
+        let count = match &self.last {
+            Some(last)
+                if last.button == button
+                    && time
+                        .checked_duration_since(last.time)
+                        .map_or(false, |d| d <= max_interval)
+                    && (loc.x - last.loc.x).abs() <= max_distance[0]
+                    && (loc.y - last.loc.y).abs() <= max_distance[1] =>
+            {
+                last.count.saturating_add(1)
+            }
+            _ => 1,
+        };
+
+        self.last = Some(LastClick {
+            time,
+            loc,
+            button,
+            count,
+        });
+
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_click() {
+        let mut counter = ClickCounter::default();
+        let t = Instant::now();
+        let ms = Duration::from_millis;
+        let th = (ms(300), [4.0, 4.0]);
+        let mut click = |time: Instant, x: f32, button: u8| {
+            counter.click_with_thresholds(time, [x, 0.0].into(), button, th.0, th.1)
+        };
+
+        assert_eq!(click(t, 0.0, 0), 1);
+        assert_eq!(click(t + ms(100), 1.0, 0), 2);
+        assert_eq!(click(t + ms(200), 2.0, 0), 3);
+        // Too far
+        assert_eq!(click(t + ms(300), 10.0, 0), 1);
+        assert_eq!(click(t + ms(400), 10.0, 0), 2);
+        // Different button
+        assert_eq!(click(t + ms(500), 10.0, 1), 1);
+        // Too late
+        assert_eq!(click(t + ms(1000), 10.0, 1), 1);
+    }
+}
@@ -0,0 +1,251 @@
+//! Image frames produced by a worker thread and displayed by a layer without
+//! going through `Bitmap`.
+//!
+//! An [`ExternalTexture`] is a triple-buffered queue: the producer writes a
+//! frame into a private buffer and submits it as the *pending* frame, and the
+//! backend *latches* the pending frame as the *front* frame when a layer's
+//! [`LayerAttrs::contents`] is set to [`LayerContents::ExternalTexture`]. If
+//! the producer submits frames faster than they are latched, unlatched frames
+//! are dropped and their buffers are reused. The producer never waits for the
+//! main thread.
+//!
+//! Frames are premultiplied BGRA8888 images, i.e., each pixel is stored as
+//! `[b, g, r, a]` in memory, with rows laid out contiguously.
+//!
+//! [`LayerAttrs::contents`]: crate::iface::LayerAttrs::contents
+//! [`LayerContents::ExternalTexture`]: crate::iface::LayerContents::ExternalTexture
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// The maximum number of unused buffers kept for reuse.
+const MAX_FREE_BUFFERS: usize = 2;
+
+/// The consumer end of a frame queue, displayed by a layer through
+/// [`LayerContents::ExternalTexture`]. Can be cloned and sent to other
+/// threads.
+///
+/// [`LayerContents::ExternalTexture`]: crate::iface::LayerContents::ExternalTexture
+#[derive(Clone)]
+pub struct ExternalTexture {
+    shared: Arc<Shared>,
+}
+
+/// The producer end of a frame queue.
+pub struct ExternalTextureProducer {
+    shared: Arc<Shared>,
+}
+
+/// A frame latched by [`ExternalTexture::latch`].
+#[derive(Clone)]
+pub struct ExternalTextureFrame {
+    data: Arc<Vec<u8>>,
+    size: [u32; 2],
+}
+
+struct Shared {
+    size: [u32; 2],
+    queue: Mutex<Queue>,
+}
+
+#[derive(Default)]
+struct Queue {
+    /// The newest frame submitted by the producer and not latched yet.
+    pending: Option<Arc<Vec<u8>>>,
+    /// The frame latched most recently.
+    front: Option<Arc<Vec<u8>>>,
+    /// Buffers available for reuse. They might be still referenced by
+    /// backends (e.g., by a bitmap displayed on the screen).
+    free: Vec<Arc<Vec<u8>>>,
+}
+
+impl fmt::Debug for ExternalTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalTexture")
+            .field("shared", &(&*self.shared as *const Shared))
+            .field("size", &self.shared.size)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ExternalTextureProducer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalTextureProducer")
+            .field("shared", &(&*self.shared as *const Shared))
+            .field("size", &self.shared.size)
+            .finish()
+    }
+}
+
+impl fmt::Debug for ExternalTextureFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExternalTextureFrame")
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl PartialEq for ExternalTexture {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl Eq for ExternalTexture {}
+
+impl ExternalTexture {
+    /// Construct a frame queue for frames of the specified size (measured in
+    /// pixels).
+    pub fn new(size: [u32; 2]) -> (Self, ExternalTextureProducer) {
+        let shared = Arc::new(Shared {
+            size,
+            queue: Mutex::new(Queue::default()),
+        });
+        (
+            Self {
+                shared: Arc::clone(&shared),
+            },
+            ExternalTextureProducer { shared },
+        )
+    }
+
+    /// Get the frame size.
+    pub fn size(&self) -> [u32; 2] {
+        self.shared.size
+    }
+
+    /// Make the pending frame (if any) the front frame, and return the front
+    /// frame. Returns `None` if no frames have been submitted yet.
+    ///
+    /// This method is called by backends.
+    pub fn latch(&self) -> Option<ExternalTextureFrame> {
+        let mut queue = self.shared.queue.lock().unwrap();
+
+        if let Some(pending) = queue.pending.take() {
+            if let Some(old_front) = queue.front.replace(pending) {
+                recycle(&mut queue, old_front);
+            }
+        }
+
+        let data = Arc::clone(queue.front.as_ref()?);
+        Some(ExternalTextureFrame {
+            data,
+            size: self.shared.size,
+        })
+    }
+
+    /// Get a flag indicating whether a frame is waiting to be latched.
+    pub fn has_pending_frame(&self) -> bool {
+        self.shared.queue.lock().unwrap().pending.is_some()
+    }
+}
+
+impl ExternalTextureProducer {
+    /// Get the frame size.
+    pub fn size(&self) -> [u32; 2] {
+        self.shared.size
+    }
+
+    /// Write a new frame using the specified function and submit it.
+    ///
+    /// `write` receives the frame buffer and its stride (the byte offset
+    /// between adjacent rows). The initial contents of the buffer are
+    /// unspecified.
+    pub fn submit_with(&mut self, write: impl FnOnce(&mut [u8], usize)) {
+        let [width, height] = self.shared.size;
+        let stride = width as usize * 4;
+        let len = stride * height as usize;
+
+        // Find a buffer not used by anyone
+        let buffer = {
+            let mut queue = self.shared.queue.lock().unwrap();
+            let i = queue
+                .free
+                .iter()
+                .position(|buffer| Arc::strong_count(buffer) == 1);
+            i.map(|i| queue.free.swap_remove(i))
+        };
+
+        let mut buffer = buffer.unwrap_or_else(|| Arc::new(vec![0; len]));
+
+        // `free` only contains buffers not referenced by `ExternalTextureFrame`
+        // and the lock ensures nobody else obtains a reference to `buffer`
+        write(Arc::get_mut(&mut buffer).unwrap(), stride);
+
+        let mut queue = self.shared.queue.lock().unwrap();
+        if let Some(dropped_frame) = queue.pending.replace(buffer) {
+            recycle(&mut queue, dropped_frame);
+        }
+    }
+}
+
+fn recycle(queue: &mut Queue, buffer: Arc<Vec<u8>>) {
+    if queue.free.len() < MAX_FREE_BUFFERS {
+        queue.free.push(buffer);
+    }
+}
+
+impl ExternalTextureFrame {
+    /// Get the frame size.
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    /// Get the byte offset between adjacent rows.
+    pub fn stride(&self) -> usize {
+        self.size[0] as usize * 4
+    }
+
+    /// Get the pixel data.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Get the reference-counted pixel data.
+    pub fn data_arc(&self) -> &Arc<Vec<u8>> {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latch_newest_frame() {
+        let (tex, mut producer) = ExternalTexture::new([2, 1]);
+        assert!(tex.latch().is_none());
+
+        producer.submit_with(|data, stride| {
+            assert_eq!(stride, 8);
+            data[0] = 1;
+        });
+        producer.submit_with(|data, _| data[0] = 2);
+        assert!(tex.has_pending_frame());
+
+        let frame = tex.latch().unwrap();
+        assert_eq!(frame.size(), [2, 1]);
+        assert_eq!(frame.data()[0], 2);
+        assert!(!tex.has_pending_frame());
+
+        // The front frame stays until a new frame is submitted
+        assert_eq!(tex.latch().unwrap().data()[0], 2);
+    }
+
+    #[test]
+    fn reuse_buffers() {
+        let (tex, mut producer) = ExternalTexture::new([1, 1]);
+        let mut ptrs = Vec::new();
+        for i in 0..10 {
+            producer.submit_with(|data, _| data[0] = i);
+            let frame = tex.latch().unwrap();
+            assert_eq!(frame.data()[0], i);
+            ptrs.push(frame.data().as_ptr());
+        }
+
+        ptrs.sort();
+        ptrs.dedup();
+        assert!(ptrs.len() <= 3, "{:?}", ptrs);
+    }
+}
@@ -0,0 +1,194 @@
+//! Watches file system paths for changes.
+//!
+//! The changes are detected by a background thread and delivered to the main
+//! thread via [`Wm::invoke_on_main_thread`].
+//!
+//! | Backend           | Implementation           |
+//! | ----------------- | ------------------------ |
+//! | Windows           | `ReadDirectoryChangesW`  |
+//! | GTK (Linux, etc.) | inotify                  |
+//! | macOS             | (unsupported)            |
+//!
+//! The native implementations are used by the testing backend as well.
+//!
+//! [`Wm::invoke_on_main_thread`]: crate::iface::Wm::invoke_on_main_thread
+use std::{
+    cell::RefCell,
+    fmt, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{prelude::*, MtSticky, Wm};
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+use self::windows as imp;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod inotify;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use self::inotify as imp;
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::{io, path::Path};
+
+    pub struct Watcher;
+
+    impl Watcher {
+        pub fn new(_: &Path, _: bool, _: super::Sink) -> io::Result<Self> {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                "file system watching is not supported on this platform",
+            ))
+        }
+    }
+}
+
+/// Represents a change in the file system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub kind: EventKind,
+    /// The affected path. This is the watched path itself if `kind` is
+    /// [`EventKind::Overflow`].
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// A file or directory was created.
+    Create,
+    /// A file or directory was removed.
+    Remove,
+    /// A file's contents or metadata were modified.
+    Modify,
+    /// A file or directory was renamed, and `path` is the old name.
+    RenameFrom,
+    /// A file or directory was renamed, and `path` is the new name.
+    RenameTo,
+    /// Some events were lost because the system's event buffer overflowed.
+    /// The watched directory should be rescanned.
+    Overflow,
+}
+
+/// Watches a path for changes. The watch stops when `Watcher` is dropped.
+pub struct Watcher {
+    _inner: imp::Watcher,
+    dispatcher: Arc<Dispatcher>,
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("path", &self.dispatcher.path)
+            .finish()
+    }
+}
+
+impl Watcher {
+    /// Start watching `path`, which can be a file or a directory.
+    ///
+    /// If `recursive` is `true` and `path` is a directory, its descendants
+    /// are watched as well.
+    ///
+    /// `handler` is called by the main event loop with one or more events.
+    /// It's never called after `Watcher` is dropped. Events caused by a
+    /// single operation may be reported in a slightly different way depending
+    /// on the platform, so it's recommended to treat them as hints for
+    /// reloading the relevant files.
+    pub fn new(
+        wm: Wm,
+        path: impl AsRef<Path>,
+        recursive: bool,
+        handler: impl FnMut(Wm, &[Event]) + 'static,
+    ) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        let dispatcher = Arc::new(Dispatcher {
+            path: path.to_owned(),
+            active: AtomicBool::new(true),
+            handler: MtSticky::with_wm(wm, RefCell::new(Box::new(handler))),
+        });
+
+        let inner = imp::Watcher::new(
+            path,
+            recursive,
+            Sink {
+                dispatcher: Arc::clone(&dispatcher),
+            },
+        )?;
+
+        Ok(Self {
+            _inner: inner,
+            dispatcher,
+        })
+    }
+
+    /// Get the watched path.
+    pub fn path(&self) -> &Path {
+        &self.dispatcher.path
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.dispatcher.active.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Forwards events from a background thread to the main thread.
+struct Dispatcher {
+    path: PathBuf,
+    /// Cleared when `Watcher` is dropped. Pending events are discarded after
+    /// that.
+    active: AtomicBool,
+    handler: MtSticky<RefCell<Box<dyn FnMut(Wm, &[Event])>>>,
+}
+
+/// Used by the implementations to report events.
+pub(crate) struct Sink {
+    dispatcher: Arc<Dispatcher>,
+}
+
+impl Sink {
+    /// Get the watched path.
+    fn path(&self) -> &Path {
+        &self.dispatcher.path
+    }
+
+    /// Send events to the main thread. Returns `false` if `Watcher` was
+    /// dropped, in which case the background thread should stop.
+    fn send(&self, events: Vec<Event>) -> bool {
+        if !self.dispatcher.active.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        if events.is_empty() {
+            return true;
+        }
+
+        let dispatcher = Arc::clone(&self.dispatcher);
+        Wm::invoke_on_main_thread(move |wm| {
+            if !dispatcher.active.load(Ordering::Relaxed) {
+                return;
+            }
+
+            if let Ok(mut handler) = dispatcher.handler.get_with_wm(wm).try_borrow_mut() {
+                handler(wm, &events);
+            } else {
+                log::warn!(
+                    "Dropping file system events for {:?} because the handler is \
+                     already running",
+                    dispatcher.path
+                );
+            }
+        });
+
+        true
+    }
+}
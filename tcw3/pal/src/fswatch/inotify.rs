@@ -0,0 +1,301 @@
+//! The inotify-based implementation of `fswatch`.
+use std::{
+    collections::HashMap,
+    ffi::{CString, OsStr, OsString},
+    io,
+    mem::size_of,
+    os::{raw::c_int, unix::ffi::OsStrExt},
+    path::{Path, PathBuf},
+    ptr::read_unaligned,
+    thread::JoinHandle,
+};
+
+use super::{Event, EventKind, Sink};
+
+const MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_DELETE_SELF
+    | libc::IN_MODIFY
+    | libc::IN_ATTRIB
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_MOVE_SELF;
+
+pub struct Watcher {
+    /// The write end of a pipe used to stop the background thread.
+    stop_fd: c_int,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct State {
+    fd: c_int,
+    recursive: bool,
+    /// If the watched path is a file, only the events for the file are
+    /// reported.
+    file_name: Option<OsString>,
+    /// Maps watch descriptors to the watched paths.
+    watches: HashMap<c_int, PathBuf>,
+}
+
+impl Watcher {
+    pub fn new(path: &Path, recursive: bool, sink: Sink) -> io::Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // A watch on a file follows the inode, so it would stop working when
+        // the file is replaced atomically (i.e., another file is renamed over
+        // it). To watch a file, watch the containing directory and filter the
+        // events.
+        let (dir, file_name) = if path.is_dir() {
+            (path.to_owned(), None)
+        } else {
+            let file_name = match path.file_name() {
+                Some(x) => x,
+                None => {
+                    unsafe { libc::close(fd) };
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "path has no file name",
+                    ));
+                }
+            };
+            let dir = match path.parent() {
+                Some(dir) if dir != Path::new("") => dir.to_owned(),
+                _ => PathBuf::from("."),
+            };
+            (dir, Some(file_name.to_owned()))
+        };
+
+        let mut state = State {
+            fd,
+            recursive: recursive && file_name.is_none(),
+            file_name,
+            watches: HashMap::new(),
+        };
+
+        let result = if state.recursive {
+            state.add_watch_recursive(&dir)
+        } else {
+            state.add_watch(&dir)
+        };
+        if let Err(e) = result {
+            unsafe { libc::close(fd) };
+            return Err(e);
+        }
+
+        let mut pipe_fds = [0; 2];
+        if unsafe { libc::pipe2(pipe_fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+            let e = io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(e);
+        }
+        let [stop_read_fd, stop_fd] = pipe_fds;
+
+        let thread = std::thread::Builder::new()
+            .name("fswatch".to_owned())
+            .spawn(move || {
+                state.run(stop_read_fd, &sink);
+
+                unsafe {
+                    libc::close(state.fd);
+                    libc::close(stop_read_fd);
+                }
+            });
+
+        match thread {
+            Ok(thread) => Ok(Self {
+                stop_fd,
+                thread: Some(thread),
+            }),
+            Err(e) => {
+                // `state` was dropped along with the closure
+                unsafe {
+                    libc::close(fd);
+                    libc::close(stop_read_fd);
+                    libc::close(stop_fd);
+                }
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        // Wake up the background thread
+        unsafe {
+            libc::write(self.stop_fd, [0u8].as_ptr() as _, 1);
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+
+        unsafe {
+            libc::close(self.stop_fd);
+        }
+    }
+}
+
+impl State {
+    fn add_watch(&mut self, path: &Path) -> io::Result<()> {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let wd = unsafe { libc::inotify_add_watch(self.fd, c_path.as_ptr(), MASK) };
+        if wd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        self.watches.insert(wd, path.to_owned());
+        Ok(())
+    }
+
+    /// Watch `path` and its descendant directories. Errors in the
+    /// descendants are logged and ignored.
+    fn add_watch_recursive(&mut self, path: &Path) -> io::Result<()> {
+        self.add_watch(path)?;
+
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                let subdir = entry.path();
+                if let Err(e) = self.add_watch_recursive(&subdir) {
+                    log::warn!("Could not watch {:?}: {}", subdir, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run(&mut self, stop_read_fd: c_int, sink: &Sink) {
+        let mut buf = [0u8; 4096];
+
+        let mut poll_fds = [
+            libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: stop_read_fd,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+
+        loop {
+            if unsafe { libc::poll(poll_fds.as_mut_ptr(), 2, -1) } < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                log::error!("poll failed: {}", e);
+                return;
+            }
+
+            if poll_fds[1].revents != 0 {
+                // `Watcher` was dropped
+                return;
+            }
+
+            if poll_fds[0].revents == 0 {
+                continue;
+            }
+
+            let len = unsafe { libc::read(self.fd, buf.as_mut_ptr() as _, buf.len()) };
+            if len < 0 {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                log::error!("Could not read inotify events: {}", e);
+                return;
+            }
+
+            let events = self.parse_events(&buf[..len as usize], sink);
+
+            if !sink.send(events) {
+                return;
+            }
+        }
+    }
+
+    fn parse_events(&mut self, mut data: &[u8], sink: &Sink) -> Vec<Event> {
+        const HEADER_LEN: usize = size_of::<libc::inotify_event>();
+
+        let mut events = Vec::new();
+
+        while data.len() >= HEADER_LEN {
+            let ev: libc::inotify_event = unsafe { read_unaligned(data.as_ptr() as *const _) };
+            let name_end = (HEADER_LEN + ev.len as usize).min(data.len());
+            let name = &data[HEADER_LEN..name_end];
+            data = &data[name_end..];
+
+            // `name` is padded with NUL bytes
+            let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+            let name = OsStr::from_bytes(&name[..name_len]);
+
+            if ev.mask & libc::IN_Q_OVERFLOW != 0 {
+                events.push(Event {
+                    kind: EventKind::Overflow,
+                    path: sink.path().to_owned(),
+                });
+                continue;
+            }
+
+            if ev.mask & libc::IN_IGNORED != 0 {
+                // The watch was removed
+                self.watches.remove(&ev.wd);
+                continue;
+            }
+
+            if let Some(file_name) = &self.file_name {
+                if name != file_name.as_os_str() {
+                    continue;
+                }
+            }
+
+            let path = if let Some(watched_path) = self.watches.get(&ev.wd) {
+                if name.is_empty() {
+                    watched_path.clone()
+                } else {
+                    watched_path.join(name)
+                }
+            } else {
+                continue;
+            };
+
+            let kind = if ev.mask & libc::IN_CREATE != 0 {
+                EventKind::Create
+            } else if ev.mask & (libc::IN_DELETE | libc::IN_DELETE_SELF) != 0 {
+                EventKind::Remove
+            } else if ev.mask & (libc::IN_MODIFY | libc::IN_ATTRIB) != 0 {
+                EventKind::Modify
+            } else if ev.mask & (libc::IN_MOVED_FROM | libc::IN_MOVE_SELF) != 0 {
+                EventKind::RenameFrom
+            } else if ev.mask & libc::IN_MOVED_TO != 0 {
+                EventKind::RenameTo
+            } else {
+                continue;
+            };
+
+            // Watch new subdirectories
+            if self.recursive
+                && ev.mask & libc::IN_ISDIR != 0
+                && (kind == EventKind::Create || kind == EventKind::RenameTo)
+            {
+                if let Err(e) = self.add_watch_recursive(&path) {
+                    log::warn!("Could not watch {:?}: {}", path, e);
+                }
+            }
+
+            events.push(Event { kind, path });
+        }
+
+        events
+    }
+}
@@ -0,0 +1,267 @@
+//! The `ReadDirectoryChangesW`-based implementation of `fswatch`.
+use std::{
+    ffi::OsString,
+    io,
+    mem::{size_of, zeroed},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    ptr::null_mut,
+    thread::JoinHandle,
+};
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, TRUE},
+        winerror::ERROR_OPERATION_ABORTED,
+    },
+    um::{
+        fileapi::{CreateFileW, OPEN_EXISTING},
+        handleapi::{CloseHandle, INVALID_HANDLE_VALUE},
+        ioapiset::{CancelIoEx, GetOverlappedResult},
+        minwinbase::OVERLAPPED,
+        synchapi::{CreateEventW, SetEvent, WaitForMultipleObjects},
+        winbase::{
+            ReadDirectoryChangesW, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OVERLAPPED, INFINITE,
+            WAIT_OBJECT_0,
+        },
+        winnt::{
+            FILE_ACTION_ADDED, FILE_ACTION_MODIFIED, FILE_ACTION_REMOVED,
+            FILE_ACTION_RENAMED_NEW_NAME, FILE_ACTION_RENAMED_OLD_NAME, FILE_LIST_DIRECTORY,
+            FILE_NOTIFY_CHANGE_ATTRIBUTES, FILE_NOTIFY_CHANGE_DIR_NAME,
+            FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE, FILE_NOTIFY_CHANGE_SIZE,
+            FILE_NOTIFY_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, HANDLE,
+        },
+    },
+};
+
+use super::{Event, EventKind, Sink};
+
+const NOTIFY_FILTER: DWORD = FILE_NOTIFY_CHANGE_FILE_NAME
+    | FILE_NOTIFY_CHANGE_DIR_NAME
+    | FILE_NOTIFY_CHANGE_ATTRIBUTES
+    | FILE_NOTIFY_CHANGE_SIZE
+    | FILE_NOTIFY_CHANGE_LAST_WRITE;
+
+pub struct Watcher {
+    /// An event object used to stop the background thread.
+    stop_event: OwnedHandle,
+    thread: Option<JoinHandle<()>>,
+}
+
+struct OwnedHandle(HANDLE);
+
+// `HANDLE`s can be used from any thread
+unsafe impl Send for OwnedHandle {}
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+struct State {
+    dir_handle: OwnedHandle,
+    io_event: OwnedHandle,
+    stop_event: HANDLE,
+    /// The watched directory.
+    dir: PathBuf,
+    recursive: bool,
+    /// If the watched path is a file, only the events for the file are
+    /// reported.
+    file_name: Option<OsString>,
+}
+
+unsafe impl Send for State {}
+
+impl Watcher {
+    pub fn new(path: &Path, recursive: bool, sink: Sink) -> io::Result<Self> {
+        // `ReadDirectoryChangesW` only can watch a directory. To watch a file,
+        // watch the containing directory and filter the events.
+        let (dir, file_name) = if path.is_dir() {
+            (path.to_owned(), None)
+        } else {
+            let file_name = path.file_name().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+            })?;
+            let dir = match path.parent() {
+                Some(dir) if dir != Path::new("") => dir.to_owned(),
+                _ => PathBuf::from("."),
+            };
+            (dir, Some(file_name.to_owned()))
+        };
+
+        let dir_handle = unsafe { open_dir(&dir)? };
+        let io_event = unsafe { create_event()? };
+        let stop_event = unsafe { create_event()? };
+
+        let state = State {
+            dir_handle,
+            io_event,
+            stop_event: stop_event.0,
+            dir,
+            recursive: recursive && file_name.is_none(),
+            file_name,
+        };
+
+        let thread = std::thread::Builder::new()
+            .name("fswatch".to_owned())
+            .spawn(move || {
+                let mut state = state;
+                unsafe { state.run(&sink) };
+            })?;
+
+        Ok(Self {
+            stop_event,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        unsafe {
+            SetEvent(self.stop_event.0);
+        }
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+unsafe fn open_dir(dir: &Path) -> io::Result<OwnedHandle> {
+    let wpath: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+
+    let handle = CreateFileW(
+        wpath.as_ptr(),
+        FILE_LIST_DIRECTORY,
+        FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+        null_mut(),
+        OPEN_EXISTING,
+        FILE_FLAG_BACKUP_SEMANTICS | FILE_FLAG_OVERLAPPED,
+        null_mut(),
+    );
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(OwnedHandle(handle))
+}
+
+unsafe fn create_event() -> io::Result<OwnedHandle> {
+    let handle = CreateEventW(null_mut(), TRUE, FALSE, null_mut());
+    if handle.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(OwnedHandle(handle))
+}
+
+impl State {
+    unsafe fn run(&mut self, sink: &Sink) {
+        // `FILE_NOTIFY_INFORMATION` must be `DWORD`-aligned
+        let mut buf = vec![0u32; 4096];
+        let buf_len = (buf.len() * size_of::<u32>()) as DWORD;
+
+        loop {
+            let mut overlapped: OVERLAPPED = zeroed();
+            overlapped.hEvent = self.io_event.0;
+
+            let ok = ReadDirectoryChangesW(
+                self.dir_handle.0,
+                buf.as_mut_ptr() as _,
+                buf_len,
+                self.recursive as _,
+                NOTIFY_FILTER,
+                null_mut(),
+                &mut overlapped,
+                None,
+            );
+            if ok == 0 {
+                log::error!(
+                    "ReadDirectoryChangesW failed: {}",
+                    io::Error::last_os_error()
+                );
+                return;
+            }
+
+            let handles = [self.io_event.0, self.stop_event];
+            let wait_result = WaitForMultipleObjects(2, handles.as_ptr(), FALSE, INFINITE);
+
+            if wait_result != WAIT_OBJECT_0 {
+                // `Watcher` was dropped (or the wait failed). Cancel the
+                // pending operation and wait for its completion because it
+                // refers to `buf` and `overlapped`.
+                CancelIoEx(self.dir_handle.0, &mut overlapped);
+                let mut num_bytes = 0;
+                GetOverlappedResult(self.dir_handle.0, &mut overlapped, &mut num_bytes, TRUE);
+                return;
+            }
+
+            let mut num_bytes = 0;
+            if GetOverlappedResult(self.dir_handle.0, &mut overlapped, &mut num_bytes, TRUE) == 0 {
+                let e = io::Error::last_os_error();
+                if e.raw_os_error() != Some(ERROR_OPERATION_ABORTED as i32) {
+                    log::error!("ReadDirectoryChangesW failed: {}", e);
+                }
+                return;
+            }
+
+            let events = if num_bytes == 0 {
+                // The buffer overflowed
+                vec![Event {
+                    kind: EventKind::Overflow,
+                    path: sink.path().to_owned(),
+                }]
+            } else {
+                self.parse_events(buf.as_ptr() as *const u8)
+            };
+
+            if !sink.send(events) {
+                return;
+            }
+        }
+    }
+
+    unsafe fn parse_events(&self, mut p: *const u8) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        loop {
+            let info = &*(p as *const FILE_NOTIFY_INFORMATION);
+            let name = std::slice::from_raw_parts(
+                info.FileName.as_ptr(),
+                info.FileNameLength as usize / size_of::<u16>(),
+            );
+            let name = OsString::from_wide(name);
+
+            let kind = match info.Action {
+                FILE_ACTION_ADDED => Some(EventKind::Create),
+                FILE_ACTION_REMOVED => Some(EventKind::Remove),
+                FILE_ACTION_MODIFIED => Some(EventKind::Modify),
+                FILE_ACTION_RENAMED_OLD_NAME => Some(EventKind::RenameFrom),
+                FILE_ACTION_RENAMED_NEW_NAME => Some(EventKind::RenameTo),
+                _ => None,
+            };
+
+            let is_relevant = match &self.file_name {
+                Some(file_name) => *file_name == name,
+                None => true,
+            };
+
+            if let (Some(kind), true) = (kind, is_relevant) {
+                events.push(Event {
+                    kind,
+                    path: self.dir.join(name),
+                });
+            }
+
+            if info.NextEntryOffset == 0 {
+                break;
+            }
+            p = p.add(info.NextEntryOffset as usize);
+        }
+
+        events
+    }
+}
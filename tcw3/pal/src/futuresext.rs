@@ -1,15 +1,17 @@
 //! Extends `Wm` for interoperability with futures (`std::future::Future`).
-use futures::task::{FutureObj, LocalFutureObj, LocalSpawn, Spawn, SpawnError};
-use leakypool::{LeakyPool, PoolPtr};
+use futures::task::{ArcWake, FutureObj, LocalFutureObj, LocalSpawn, Spawn, SpawnError};
 use std::{
-    cell::{Cell, RefCell, UnsafeCell},
+    cell::{Cell, UnsafeCell},
     fmt,
     future::Future,
     ops::Range,
     pin::Pin,
-    ptr::NonNull,
     rc::Rc,
-    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll, Waker},
     time::Duration,
 };
 
@@ -49,13 +51,6 @@ impl WmFuturesExt for Wm {
 
 // ============================================================================
 
-static TASKS: MtSticky<RefCell<LeakyPool<Task>>> = Init::INIT;
-
-struct Task {
-    /// This future is moved out only when `pend_task` is making a progress.
-    future: Cell<Option<LocalFutureObj<'static, ()>>>,
-}
-
 #[derive(Debug, Copy, Clone)]
 pub struct WmSpawner {
     wm: Wm,
@@ -69,76 +64,67 @@ impl Spawn for WmSpawner {
 
 impl LocalSpawn for WmSpawner {
     fn spawn_local_obj(&self, future: LocalFutureObj<'static, ()>) -> Result<(), SpawnError> {
-        let mut tasks = TASKS.get_with_wm(self.wm).borrow_mut();
-
-        let task_id = tasks.allocate(Task {
-            future: Cell::new(Some(future)),
-        });
-
-        self.wm.invoke(move |wm| {
-            pend_task(wm, task_id);
-        });
-
+        self.wm.spawn_local(future);
         Ok(())
     }
 }
 
-/// Progress a task. The call stack should not include another activation of
-/// `pend_task`.
-fn pend_task(wm: Wm, task_id: PoolPtr<Task>) {
-    let mut future: LocalFutureObj<'static, ()>;
+// ============================================================================
 
-    if let Some(task) = TASKS.get_with_wm(wm).borrow_mut().get_mut(task_id) {
-        future = task.future.take().unwrap();
-    } else {
-        // already-completed task, do not enqueue
-        return;
-    }
+/// The implementation of [`Wm::spawn_local`](crate::iface::Wm::spawn_local).
+pub(crate) fn spawn_local<TWM: WmTrait>(wm: TWM, future: impl Future<Output = ()> + 'static) {
+    let task = Arc::new(Task {
+        future: MtSticky::with_wm(wm, Cell::new(Some(Box::pin(future)))),
+        scheduled: AtomicBool::new(true),
+    });
 
-    let waker = wm_waker(task_id);
-    let result = Pin::new(&mut future).poll(&mut Context::from_waker(&waker));
+    wm.invoke(move |wm| task.poll(wm));
+}
 
-    let mut tasks = TASKS.get_with_wm(wm).borrow_mut();
+struct Task<TWM: WmTrait> {
+    /// This future is moved out only when `poll` is making a progress, and is
+    /// set to `None` when the task is complete.
+    future: MtSticky<Cell<Option<Pin<Box<dyn Future<Output = ()>>>>>, TWM>,
+    /// `true` if a call to `poll` is enqueued. This is used to avoid
+    /// enqueueing redundant calls when the waker is woken up many times.
+    scheduled: AtomicBool,
+}
 
-    match result {
-        Poll::Ready(()) => {
-            // Delete the completed task
-            tasks.deallocate(task_id).unwrap();
-        }
-        Poll::Pending => {
-            // Put `future` back
-            tasks[task_id].future.set(Some(future));
+impl<TWM: WmTrait> Task<TWM> {
+    /// Progress the task. The call stack should not include another
+    /// activation of `poll` for the same task.
+    fn poll(self: Arc<Self>, wm: TWM) {
+        self.scheduled.store(false, Ordering::Relaxed);
 
-            // `pend_task` will be called again sometime...
+        let cell = self.future.get_with_wm(wm);
+        let mut future = if let Some(future) = cell.take() {
+            future
+        } else {
+            // already-completed task
+            return;
+        };
+
+        let waker = futures::task::waker(Arc::clone(&self));
+        if let Poll::Pending = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            // Put `future` back. `poll` will be called again sometime...
+            cell.set(Some(future));
         }
     }
 }
 
-fn wm_waker(task_id: PoolPtr<Task>) -> Waker {
-    fn wm_raw_waker(task_id: PoolPtr<Task>) -> RawWaker {
-        let vtable: &'static RawWakerVTable =
-            &RawWakerVTable::new(wm_waker_clone, wm_waker_wake, wm_waker_wake, wm_waker_drop);
-
-        RawWaker::new(task_id.into_raw().as_ptr() as *const (), vtable)
-    }
-
-    unsafe fn data_to_task_id(data: *const ()) -> PoolPtr<Task> {
-        PoolPtr::from_raw(NonNull::new_unchecked(data as *mut ()))
-    }
-
-    unsafe fn wm_waker_clone(data: *const ()) -> RawWaker {
-        wm_raw_waker(data_to_task_id(data))
-    }
-    unsafe fn wm_waker_wake(data: *const ()) {
-        let task_id = data_to_task_id(data);
+impl<TWM: WmTrait> ArcWake for Task<TWM> {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        if arc_self.scheduled.swap(true, Ordering::Relaxed) {
+            return;
+        }
 
-        Wm::invoke_on_main_thread(move |wm| {
-            pend_task(wm, task_id);
-        });
+        let task = Arc::clone(arc_self);
+        if let Ok(wm) = TWM::try_global() {
+            wm.invoke(move |wm| task.poll(wm));
+        } else {
+            TWM::invoke_on_main_thread(move |wm| task.poll(wm));
+        }
     }
-    unsafe fn wm_waker_drop(_: *const ()) {}
-
-    unsafe { Waker::from_raw(wm_raw_waker(task_id)) }
 }
 
 // ============================================================================
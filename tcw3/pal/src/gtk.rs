@@ -1,8 +1,22 @@
 //! The GTK backend.
+//!
+//! # Backend-specific Features
+//!
+//! Child windows created by `Wm::new_child_wnd` are `GtkBox` widgets placed
+//! over the window's contents. Third-party widgets can be added to the
+//! container returned by [`HChildWnd::container`].
 use super::iface;
-use std::{cell::RefCell, marker::PhantomData, mem::MaybeUninit, ops::Range, time::Duration};
+use cggeom::Box2;
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use crate::MtLock;
+use crate::{layertxn::LayerTxnQueue, MtLock};
 
 pub type WndAttrs<'a> = iface::WndAttrs<'a, Wm, HLayer>;
 pub type LayerAttrs = iface::LayerAttrs<Bitmap, HLayer>;
@@ -18,11 +32,19 @@ pub use self::{
     text::{CharStyle, TextLayout},
 };
 
+mod appearance;
+mod childwnd;
+mod clipboard;
 mod comp;
+mod filedialog;
+mod notification;
+mod sound;
 mod textinput;
 mod timer;
+mod webview;
 mod window;
 pub use self::{
+    childwnd::HChildWnd,
     comp::HLayer,
     textinput::HTextInputCtx,
     timer::HInvoke,
@@ -41,10 +63,14 @@ pub struct Wm {
 static TIMER_POOL: MtLock<RefCell<timer::TimerPool>, Wm> =
     MtLock::new(RefCell::new(timer::TimerPool::new()));
 
+static LAYER_TXN: MtLock<RefCell<LayerTxnQueue<HWnd>>, Wm> =
+    MtLock::new(RefCell::new(LayerTxnQueue::new()));
+
 impl iface::Wm for Wm {
     type HWnd = HWnd;
     type HLayer = HLayer;
     type HInvoke = HInvoke;
+    type HChildWnd = HChildWnd;
     type HTextInputCtx = HTextInputCtx;
     type AccelTable = AccelTable;
     type Bitmap = Bitmap;
@@ -163,17 +189,48 @@ impl iface::Wm for Wm {
     }
 
     fn remove_wnd(self, window: &Self::HWnd) {
+        LAYER_TXN.get_with_wm(self).borrow_mut().remove_wnd(window);
         window.remove_wnd(self)
     }
 
     fn update_wnd(self, window: &Self::HWnd) {
-        window.update_wnd(self)
+        if LAYER_TXN
+            .get_with_wm(self)
+            .borrow_mut()
+            .defer_update_wnd(window, None)
+        {
+            return;
+        }
+        window.update_wnd(self, None)
+    }
+
+    fn update_wnd_with_damage(self, window: &Self::HWnd, damage: Box2<f32>) {
+        if LAYER_TXN
+            .get_with_wm(self)
+            .borrow_mut()
+            .defer_update_wnd(window, Some(damage))
+        {
+            return;
+        }
+        window.update_wnd(self, Some(damage))
+    }
+
+    fn render_wnd_to_bitmap(self, window: &Self::HWnd) -> Option<Self::Bitmap> {
+        window.render_wnd_to_bitmap(self)
     }
 
     fn get_wnd_size(self, window: &Self::HWnd) -> [u32; 2] {
         window.get_wnd_size(self)
     }
 
+    fn get_wnd_position(self, window: &Self::HWnd) -> Option<[i32; 2]> {
+        Some(window.get_wnd_position(self))
+    }
+
+    fn is_wnd_maximized(self, window: &Self::HWnd) -> bool {
+        window.is_wnd_maximized(self)
+    }
+
     fn get_wnd_dpi_scale(self, window: &Self::HWnd) -> f32 {
         window.get_wnd_dpi_scale(self)
     }
@@ -182,6 +239,38 @@ impl iface::Wm for Wm {
         window.is_wnd_focused(self)
     }
 
+    fn new_child_wnd(self, parent: &Self::HWnd, bounds: Box2<f32>) -> Option<Self::HChildWnd> {
+        Some(childwnd::new_child_wnd(self, parent, bounds))
+    }
+
+    fn set_child_wnd_bounds(self, child: &Self::HChildWnd, bounds: Box2<f32>) {
+        childwnd::set_child_wnd_bounds(self, child, bounds)
+    }
+
+    fn remove_child_wnd(self, child: &Self::HChildWnd) {
+        childwnd::remove_child_wnd(self, child)
+    }
+
+    fn attach_web_view(
+        self,
+        child: &Self::HChildWnd,
+        listener: Box<dyn iface::WebViewListener<Self>>,
+    ) -> bool {
+        webview::attach_web_view(self, child, listener)
+    }
+
+    fn web_view_navigate(self, child: &Self::HChildWnd, url: &str) {
+        webview::web_view_navigate(self, child, url)
+    }
+
+    fn web_view_load_html(self, child: &Self::HChildWnd, html: &str, base_url: Option<&str>) {
+        webview::web_view_load_html(self, child, html, base_url)
+    }
+
+    fn web_view_eval_script(self, child: &Self::HChildWnd, script: &str) {
+        webview::web_view_eval_script(self, child, script)
+    }
+
     fn request_update_ready_wnd(self, window: &Self::HWnd) {
         window.request_update_ready_wnd(self)
     }
@@ -205,6 +294,49 @@ impl iface::Wm for Wm {
             .remove_layer(layer)
     }
 
+    fn begin_layer_txn(self) {
+        LAYER_TXN.get_with_wm(self).borrow_mut().begin();
+    }
+
+    fn commit_layer_txn(self) {
+        let wnds = LAYER_TXN.get_with_wm(self).borrow_mut().commit();
+        for (window, damage) in wnds {
+            window.update_wnd(self, damage);
+        }
+    }
+
+    fn set_layer_swapchain(self, layer: &Self::HLayer, size: Option<[u32; 2]>) -> bool {
+        window::COMPOSITOR
+            .get_with_wm(self)
+            .borrow_mut()
+            .set_layer_swapchain(layer, size);
+        true
+    }
+
+    fn present_layer_swapchain(
+        self,
+        layer: &Self::HLayer,
+        render: &mut dyn FnMut(iface::SwapchainTarget<'_>),
+    ) {
+        let size = window::COMPOSITOR
+            .get_with_wm(self)
+            .borrow()
+            .layer_swapchain_size(layer);
+        let size = match size {
+            Some(size) if size[0] > 0 && size[1] > 0 => size,
+            _ => return,
+        };
+
+        // `render` may call `Wm`'s methods, so don't borrow `COMPOSITOR`
+        // while calling it
+        let image = Bitmap::from_swapchain_render(size, render);
+
+        let mut compositor = window::COMPOSITOR.get_with_wm(self).borrow_mut();
+        if compositor.layer_swapchain_size(layer).is_some() {
+            compositor.set_layer_swapchain_image(layer, Some(image));
+        }
+    }
+
     fn new_text_input_ctx(
         self,
         hwnd: &Self::HWnd,
@@ -230,6 +362,47 @@ impl iface::Wm for Wm {
     fn remove_text_input_ctx(self, htictx: &Self::HTextInputCtx) {
         htictx.remove(self);
     }
+
+    fn set_clipboard_text(self, text: &str) {
+        clipboard::set_clipboard_text(self, text);
+    }
+
+    fn clipboard_text(self) -> Option<String> {
+        clipboard::clipboard_text(self)
+    }
+
+    fn show_file_dialog(
+        self,
+        parent: Option<&Self::HWnd>,
+        options: iface::FileDialogOptions,
+        f: impl FnOnce(Self, Option<Vec<PathBuf>>) + 'static,
+    ) {
+        filedialog::show_file_dialog(self, parent, options, f);
+    }
+
+    fn show_notification(
+        self,
+        attrs: iface::NotificationAttrs,
+        f: impl FnOnce(Self, Option<usize>) + 'static,
+    ) {
+        notification::show_notification(self, attrs, f);
+    }
+
+    fn play_system_sound(self, sound: crate::sound::SystemSound) {
+        sound::play_system_sound(self, sound);
+    }
+
+    fn play_sound_file(self, path: &Path) {
+        sound::play_sound_file(self, path);
+    }
+
+    fn system_appearance(self) -> iface::SystemAppearance {
+        appearance::system_appearance(self)
+    }
+
+    fn default_ui_direction(self) -> iface::UiDirection {
+        appearance::default_ui_direction(self)
+    }
 }
 
 struct AssertSend<T>(T);
@@ -0,0 +1,39 @@
+use gtk::prelude::*;
+
+use super::Wm;
+use crate::iface;
+
+pub(super) fn settings() -> Option<gtk::Settings> {
+    gtk::Settings::get_default()
+}
+
+pub fn system_appearance(_: Wm) -> iface::SystemAppearance {
+    let settings = if let Some(settings) = settings() {
+        settings
+    } else {
+        return iface::SystemAppearance::default();
+    };
+
+    let prefer_dark = settings.get_property_gtk_application_prefer_dark_theme();
+    let theme_name = settings
+        .get_property_gtk_theme_name()
+        .map(|s| s.as_str().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    // GTK doesn't have a dedicated setting for these. Infer them from the
+    // theme name instead (e.g., "Adwaita-dark", "HighContrast").
+    iface::SystemAppearance {
+        dark_mode: prefer_dark || theme_name.ends_with("-dark"),
+        high_contrast: theme_name.contains("highcontrast"),
+        accent_color: None,
+    }
+}
+
+pub fn default_ui_direction(_: Wm) -> iface::UiDirection {
+    // GTK derives the default direction from the current locale during
+    // initialization
+    match gtk::Widget::get_default_direction() {
+        gtk::TextDirection::Rtl => iface::UiDirection::Rtl,
+        _ => iface::UiDirection::Ltr,
+    }
+}
@@ -0,0 +1,107 @@
+//! Native child windows
+use cggeom::Box2;
+use gtk::prelude::*;
+use std::{cell::RefCell, fmt, hash, rc::Rc};
+
+use super::{webview, HWnd, Wm};
+
+/// A handle to a native child window created by `Wm::new_child_wnd`.
+///
+/// A child window is implemented as a `GtkBox` placed over the window's
+/// contents by `GtkOverlay`.
+#[derive(Clone)]
+pub struct HChildWnd {
+    pub(super) inner: Rc<ChildWnd>,
+}
+
+pub(super) struct ChildWnd {
+    container: gtk::Box,
+    pub(super) web_view: RefCell<Option<webview::WebView>>,
+}
+
+impl fmt::Debug for HChildWnd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("HChildWnd")
+            .field(&(&*self.inner as *const ChildWnd))
+            .finish()
+    }
+}
+
+impl PartialEq for HChildWnd {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Eq for HChildWnd {}
+
+impl hash::Hash for HChildWnd {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        (&*self.inner as *const ChildWnd).hash(state);
+    }
+}
+
+impl HChildWnd {
+    /// Get the container widget. Third-party widgets can be added to this
+    /// container.
+    pub fn container(&self) -> &gtk::Box {
+        &self.inner.container
+    }
+
+    pub(super) fn from_inner(inner: Rc<ChildWnd>) -> Self {
+        Self { inner }
+    }
+}
+
+pub fn new_child_wnd(wm: Wm, parent: &HWnd, bounds: Box2<f32>) -> HChildWnd {
+    let overlay = parent.gtk_overlay(wm).expect("invalid hwnd");
+
+    let container = gtk::Box::new(gtk::Orientation::Vertical, 0);
+
+    // `GtkOverlay` positions the child by its alignment and margins. Make
+    // them independent of the text direction.
+    container.set_direction(gtk::TextDirection::Ltr);
+    container.set_halign(gtk::Align::Start);
+    container.set_valign(gtk::Align::Start);
+    set_container_bounds(&container, bounds);
+
+    overlay.add_overlay(&container);
+    container.show_all();
+
+    HChildWnd {
+        inner: Rc::new(ChildWnd {
+            container,
+            web_view: RefCell::new(None),
+        }),
+    }
+}
+
+pub fn set_child_wnd_bounds(_: Wm, child: &HChildWnd, bounds: Box2<f32>) {
+    set_container_bounds(&child.inner.container, bounds);
+}
+
+pub fn remove_child_wnd(_: Wm, child: &HChildWnd) {
+    // Disconnect the web view's signal handlers so that the listener is not
+    // called anymore
+    drop(child.inner.web_view.borrow_mut().take());
+
+    // The container is detached when the parent window is destroyed. The
+    // parent window's handle might be reused by then, so check the container's
+    // actual parent.
+    let container = &child.inner.container;
+    if let Some(overlay) = container.get_parent() {
+        overlay
+            .downcast::<gtk::Container>()
+            .unwrap()
+            .remove(container);
+    }
+}
+
+fn set_container_bounds(container: &gtk::Box, bounds: Box2<f32>) {
+    let min = bounds.min.map(|x| x.round() as i32);
+    let size = bounds.max.map(|x| x.round() as i32) - min;
+
+    container.set_margin_start(min.x);
+    container.set_margin_top(min.y);
+    container.set_size_request(size.x.max(0), size.y.max(0));
+}
@@ -0,0 +1,71 @@
+//! Implements the clipboard operations.
+//!
+//! `gtk::Clipboard::wait_for_text` runs a nested main loop, which can re-enter
+//! event handlers (e.g., when `clipboard_text` is called by `validate_action`).
+//! Instead, the text is requested asynchronously whenever the owner of the
+//! clipboard changes, and `clipboard_text` returns the cached result.
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+
+use super::Wm;
+use crate::cells::MtLazyStatic;
+
+struct State {
+    /// `true` if we are watching the owner changes of the clipboard.
+    watching: Cell<bool>,
+    /// The last known text in the clipboard.
+    text: RefCell<Option<String>>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref STATE: State => |_| State {
+        watching: Cell::new(false),
+        text: RefCell::new(None),
+    };
+}
+
+fn clipboard() -> gtk::Clipboard {
+    gtk::Clipboard::get(&gdk::SELECTION_CLIPBOARD)
+}
+
+/// Get `STATE`, starting to watch the clipboard if we haven't yet.
+fn state(wm: Wm) -> &'static State {
+    let state = STATE.get_with_wm(wm);
+
+    if !state.watching.replace(true) {
+        let clipboard = clipboard();
+        let result = clipboard.connect_local("owner-change", false, move |_| {
+            request_text(wm);
+            None
+        });
+        if let Err(e) = result {
+            log::warn!("Could not watch the clipboard: {}", e);
+        }
+
+        request_text(wm);
+    }
+
+    state
+}
+
+/// Start caching the clipboard contents if we haven't yet.
+pub(super) fn watch(wm: Wm) {
+    state(wm);
+}
+
+fn request_text(wm: Wm) {
+    clipboard().request_text(move |_, text| {
+        log::trace!("The clipboard text has been updated");
+        STATE.get_with_wm(wm).text.replace(text.map(Into::into));
+    });
+}
+
+pub fn set_clipboard_text(wm: Wm, text: &str) {
+    let state = state(wm);
+    clipboard().set_text(text);
+    state.text.replace(Some(text.to_owned()));
+}
+
+pub fn clipboard_text(wm: Wm) -> Option<String> {
+    state(wm).text.borrow().clone()
+}
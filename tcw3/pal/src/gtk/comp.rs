@@ -15,6 +15,8 @@ use crate::{iface, swrast};
 pub(super) struct Compositor {
     binner: swrast::Binner<Bitmap>,
     sr_scrn: swrast::Screen<Bitmap>,
+    /// The layers having a swapchain and the sizes of the swapchains.
+    swapchains: Vec<(HLayer, [u32; 2])>,
 }
 
 pub struct Wnd {
@@ -37,6 +39,7 @@ impl Compositor {
         Self {
             binner: swrast::Binner::new(),
             sr_scrn: swrast::Screen::new(),
+            swapchains: Vec::new(),
         }
     }
 
@@ -71,9 +74,43 @@ impl Compositor {
     }
 
     pub(super) fn remove_layer(&mut self, layer: &HLayer) {
+        self.set_layer_swapchain(layer, None);
         self.sr_scrn.remove_layer(&layer.sr_layer);
     }
 
+    pub(super) fn set_layer_swapchain(&mut self, layer: &HLayer, size: Option<[u32; 2]>) {
+        let i = self.swapchains.iter().position(|(l, _)| l == layer);
+
+        match (i, size) {
+            (Some(i), Some(size)) => self.swapchains[i].1 = size,
+            (None, Some(size)) => self.swapchains.push((layer.clone(), size)),
+            (Some(i), None) => {
+                self.swapchains.swap_remove(i);
+                self.set_layer_swapchain_image(layer, None);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Get the size of the swapchain attached to a layer.
+    pub(super) fn layer_swapchain_size(&self, layer: &HLayer) -> Option<[u32; 2]> {
+        self.swapchains
+            .iter()
+            .find(|(l, _)| l == layer)
+            .map(|&(_, size)| size)
+    }
+
+    /// Display a frame presented to the swapchain attached to a layer.
+    pub(super) fn set_layer_swapchain_image(&mut self, layer: &HLayer, image: Option<Bitmap>) {
+        self.sr_scrn.set_layer_attr(
+            &layer.sr_layer,
+            iface::LayerAttrs {
+                contents: Some(image.map(Into::into)),
+                ..Default::default()
+            },
+        );
+    }
+
     pub(super) fn set_wnd_layer(&mut self, wnd: &Wnd, layer: Option<HLayer>) {
         self.sr_scrn
             .set_wnd_layer(&wnd.sr_wnd, layer.map(|hl| hl.sr_layer));
@@ -84,12 +121,14 @@ impl Compositor {
     /// specified size.
     ///
     /// `surf_size_sz` and `surf_dpi_scale` specify the desired properties of
-    /// the backing store.
+    /// the backing store. `damage` is passed to
+    /// `swrast::Screen::update_wnd_with_damage`.
     pub(super) fn update_wnd(
         &mut self,
         wnd: &mut Wnd,
         surf_size_sz: [usize; 2],
         surf_dpi_scale: f32,
+        damage: Option<Box2<f32>>,
     ) -> Option<Box2<usize>> {
         // Check the surface size
         let [size_w, size_h] = surf_size_sz;
@@ -120,7 +159,7 @@ impl Compositor {
         }
 
         // Compute the dirty region
-        let new_dirty = self.sr_scrn.update_wnd(&wnd.sr_wnd);
+        let new_dirty = self.sr_scrn.update_wnd_with_damage(&wnd.sr_wnd, damage);
 
         if let Some(new_dirty) = new_dirty {
             if let Some(x) = &mut wnd.dirty_rect {
@@ -153,6 +192,31 @@ impl Compositor {
             None
         }
     }
+
+    /// Render the contents of a window to a new `Bitmap`. Returns `None` if
+    /// the backing store is empty.
+    pub(super) fn render_wnd_to_bitmap(&mut self, wnd: &Wnd) -> Option<Bitmap> {
+        let [size_w, size_h] = wnd.surf_size;
+        if size_w == 0 || size_h == 0 {
+            return None;
+        }
+
+        let stride = size_w * 4;
+        let mut data = vec![0u8; stride * size_h].into_boxed_slice();
+        self.sr_scrn.render_wnd(
+            &wnd.sr_wnd,
+            &mut data,
+            stride,
+            box2! { min: [0, 0].into(), max: wnd.surf_size.into() },
+            &mut self.binner,
+        );
+
+        Some(Bitmap::from_argb_data(
+            data,
+            [size_w as u32, size_h as u32],
+            stride,
+        ))
+    }
 }
 
 /// Convert the `LayerAttrs` of `Wm` to the `LayerAttrs` of `swrast`.
@@ -161,7 +225,9 @@ fn layer_attrs_to_sr_layer_attrs(
 ) -> iface::LayerAttrs<Bitmap, swrast::HLayer<Bitmap>> {
     iface::LayerAttrs {
         transform: attrs.transform,
-        contents: attrs.contents,
+        contents: attrs
+            .contents
+            .map(|contents| contents.and_then(Bitmap::latch_layer_contents)),
         bounds: attrs.bounds,
         contents_center: attrs.contents_center,
         contents_scale: attrs.contents_scale,
@@ -174,6 +240,10 @@ fn layer_attrs_to_sr_layer_attrs(
         }),
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radius: attrs.corner_radius,
+        mask: attrs.mask,
+        shadow: attrs.shadow,
+        backdrop_blur_radius: attrs.backdrop_blur_radius,
     }
 }
 
@@ -0,0 +1,85 @@
+//! Implements `Wm::show_file_dialog` using `GtkFileChooserNative`, which uses
+//! the desktop portal when it's available (e.g., inside a Flatpak sandbox) and
+//! falls back to `GtkFileChooserDialog` otherwise.
+use gtk::prelude::*;
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use super::{HWnd, Wm};
+use crate::iface;
+
+pub fn show_file_dialog(
+    wm: Wm,
+    parent: Option<&HWnd>,
+    options: iface::FileDialogOptions,
+    f: impl FnOnce(Wm, Option<Vec<PathBuf>>) + 'static,
+) {
+    let parent = parent.map(|hwnd| hwnd.gtk_wnd(wm));
+
+    let action = match options.kind {
+        iface::FileDialogKind::Open => gtk::FileChooserAction::Open,
+        iface::FileDialogKind::Save => gtk::FileChooserAction::Save,
+    };
+
+    let dialog = gtk::FileChooserNative::new(
+        options.title.as_deref(),
+        parent.as_ref(),
+        action,
+        None,
+        None,
+    );
+    dialog.set_modal(true);
+
+    match options.kind {
+        iface::FileDialogKind::Open => {
+            dialog.set_select_multiple(options.multi_select);
+        }
+        iface::FileDialogKind::Save => {
+            dialog.set_do_overwrite_confirmation(true);
+            if let Some(name) = &options.default_name {
+                dialog.set_current_name(name);
+            }
+        }
+    }
+
+    for filter in options.filters.iter() {
+        let gtk_filter = gtk::FileFilter::new();
+        gtk_filter.set_name(Some(&filter.name));
+        if filter.extensions.is_empty() {
+            gtk_filter.add_pattern("*");
+        }
+        for ext in filter.extensions.iter() {
+            gtk_filter.add_pattern(&format!("*.{}", ext));
+        }
+        dialog.add_filter(&gtk_filter);
+    }
+
+    if let Some(dir) = &options.default_dir {
+        if !dialog.set_current_folder(dir) {
+            log::warn!("Could not set the current folder to {:?}", dir);
+        }
+    }
+
+    // `GtkNativeDialog` isn't kept alive by GTK while it's shown. The
+    // response handler holds a reference to the dialog until it's dismissed.
+    let state = Rc::new(RefCell::new(Some((dialog.clone(), f))));
+
+    dialog.connect_response(move |dialog, response| {
+        let (_dialog, f) = if let Some(x) = state.borrow_mut().take() {
+            x
+        } else {
+            return;
+        };
+
+        let paths = if response == gtk::ResponseType::Accept {
+            Some(dialog.get_filenames())
+        } else {
+            None
+        };
+
+        dialog.destroy();
+
+        f(wm, paths);
+    });
+
+    dialog.show();
+}
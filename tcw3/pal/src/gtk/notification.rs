@@ -0,0 +1,248 @@
+//! Implements `Wm::show_notification` using the D-Bus interface
+//! `org.freedesktop.Notifications`.
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_uint},
+    ptr::{null, null_mut},
+};
+
+use super::Wm;
+use crate::{cells::MtLazyStatic, iface, iface::Wm as _};
+
+type Callback = Box<dyn FnOnce(Wm, Option<usize>)>;
+
+const BUS_NAME: &[u8] = b"org.freedesktop.Notifications\0";
+const OBJECT_PATH: &[u8] = b"/org/freedesktop/Notifications\0";
+const INTERFACE_NAME: &[u8] = b"org.freedesktop.Notifications\0";
+
+/// The action key representing the notification itself.
+const DEFAULT_ACTION_KEY: &str = "default";
+
+struct State {
+    conn: *mut gio_sys::GDBusConnection,
+    /// The completion handlers of the notifications being displayed, keyed
+    /// by the notification IDs assigned by the notification server.
+    callbacks: HashMap<u32, Callback>,
+}
+
+mt_lazy_static! {
+    static <Wm> ref STATE: Option<RefCell<State>> => |_| unsafe { State::new() };
+}
+
+impl State {
+    unsafe fn new() -> Option<RefCell<Self>> {
+        let mut error = null_mut();
+        let conn = gio_sys::g_bus_get_sync(gio_sys::G_BUS_TYPE_SESSION, null_mut(), &mut error);
+        if conn.is_null() {
+            log::warn!(
+                "Could not connect to the session bus: {}",
+                take_error_message(error)
+            );
+            return None;
+        }
+
+        gio_sys::g_dbus_connection_signal_subscribe(
+            conn,
+            BUS_NAME.as_ptr() as _,
+            INTERFACE_NAME.as_ptr() as _,
+            null(), // any member
+            OBJECT_PATH.as_ptr() as _,
+            null(), // any arg0
+            gio_sys::G_DBUS_SIGNAL_FLAGS_NONE,
+            Some(handle_signal),
+            null_mut(),
+            None,
+        );
+
+        Some(RefCell::new(Self {
+            conn,
+            callbacks: HashMap::new(),
+        }))
+    }
+}
+
+unsafe fn take_error_message(error: *mut glib_sys::GError) -> String {
+    let message = CStr::from_ptr((*error).message)
+        .to_string_lossy()
+        .into_owned();
+    glib_sys::g_error_free(error);
+    message
+}
+
+/// Construct a `CString`, removing interior nul bytes.
+fn c_string_lossy(s: &str) -> CString {
+    CString::new(s.replace('\0', "")).unwrap()
+}
+
+pub fn show_notification(
+    wm: Wm,
+    attrs: iface::NotificationAttrs,
+    f: impl FnOnce(Wm, Option<usize>) + 'static,
+) {
+    let conn = if let Some(state) = STATE.get_with_wm(wm) {
+        state.borrow().conn
+    } else {
+        return;
+    };
+
+    let title = c_string_lossy(&attrs.title);
+    let body = c_string_lossy(&attrs.body);
+
+    // Actions are specified as a flattened list of (key, caption) pairs.
+    // The key of each action is its index.
+    let mut actions = vec![c_string_lossy(DEFAULT_ACTION_KEY), c_string_lossy("")];
+    for (i, caption) in attrs.actions.iter().enumerate() {
+        actions.push(c_string_lossy(&i.to_string()));
+        actions.push(c_string_lossy(caption));
+    }
+    let action_ptrs: Vec<*const c_char> = actions
+        .iter()
+        .map(|s| s.as_ptr())
+        .chain(std::iter::once(null()))
+        .collect();
+
+    let image_path = attrs
+        .image
+        .as_ref()
+        .map(|path| c_string_lossy(&path.to_string_lossy()));
+
+    // The ownership is transferred to `handle_notify_reply`
+    let f: Box<Callback> = Box::new(Box::new(f));
+
+    unsafe {
+        let app_name = glib_sys::g_get_prgname();
+
+        let hints = glib_sys::g_variant_builder_new(b"a{sv}\0".as_ptr() as _);
+        if let Some(image_path) = &image_path {
+            glib_sys::g_variant_builder_add(
+                hints,
+                b"{sv}\0".as_ptr() as *const c_char,
+                b"image-path\0".as_ptr() as *const c_char,
+                glib_sys::g_variant_new_string(image_path.as_ptr()),
+            );
+        }
+
+        // `Notify(app_name, replaces_id, app_icon, summary, body, actions,
+        // hints, expire_timeout) -> id`
+        let params = glib_sys::g_variant_new(
+            b"(susss^asa{sv}i)\0".as_ptr() as *const c_char,
+            if app_name.is_null() {
+                b"\0".as_ptr() as *const c_char
+            } else {
+                app_name
+            },
+            0 as c_uint,
+            b"\0".as_ptr() as *const c_char,
+            title.as_ptr(),
+            body.as_ptr(),
+            action_ptrs.as_ptr(),
+            hints,
+            -1 as c_int,
+        );
+        glib_sys::g_variant_builder_unref(hints);
+
+        gio_sys::g_dbus_connection_call(
+            conn,
+            BUS_NAME.as_ptr() as _,
+            OBJECT_PATH.as_ptr() as _,
+            INTERFACE_NAME.as_ptr() as _,
+            b"Notify\0".as_ptr() as _,
+            params,
+            b"(u)\0".as_ptr() as _,
+            gio_sys::G_DBUS_CALL_FLAGS_NONE,
+            -1,
+            null_mut(),
+            Some(handle_notify_reply),
+            Box::into_raw(f) as glib_sys::gpointer,
+        );
+    }
+}
+
+unsafe extern "C" fn handle_notify_reply(
+    source: *mut gobject_sys::GObject,
+    result: *mut gio_sys::GAsyncResult,
+    user_data: glib_sys::gpointer,
+) {
+    let f: Box<Callback> = Box::from_raw(user_data as *mut Callback);
+
+    let mut error = null_mut();
+    let reply = gio_sys::g_dbus_connection_call_finish(source as _, result, &mut error);
+    if reply.is_null() {
+        log::warn!(
+            "Could not show a notification: {}",
+            take_error_message(error)
+        );
+        return;
+    }
+
+    let mut id: u32 = 0;
+    glib_sys::g_variant_get(
+        reply,
+        b"(u)\0".as_ptr() as *const c_char,
+        &mut id as *mut u32,
+    );
+    glib_sys::g_variant_unref(reply);
+
+    let wm = Wm::global_unchecked();
+    if let Some(state) = STATE.get_with_wm(wm) {
+        state.borrow_mut().callbacks.insert(id, *f);
+    }
+}
+
+unsafe extern "C" fn handle_signal(
+    _: *mut gio_sys::GDBusConnection,
+    _sender_name: *const c_char,
+    _object_path: *const c_char,
+    _interface_name: *const c_char,
+    signal_name: *const c_char,
+    parameters: *mut glib_sys::GVariant,
+    _: glib_sys::gpointer,
+) {
+    let wm = Wm::global_unchecked();
+    let state = if let Some(state) = STATE.get_with_wm(wm) {
+        state
+    } else {
+        return;
+    };
+
+    let is_of_type = |ty: &[u8]| glib_sys::g_variant_is_of_type(parameters, ty.as_ptr() as _) != 0;
+
+    match CStr::from_ptr(signal_name).to_bytes() {
+        b"ActionInvoked" if is_of_type(b"(us)\0") => {
+            let mut id: u32 = 0;
+            let mut action_key: *mut c_char = null_mut();
+            glib_sys::g_variant_get(
+                parameters,
+                b"(us)\0".as_ptr() as *const c_char,
+                &mut id as *mut u32,
+                &mut action_key as *mut *mut c_char,
+            );
+            let action_key_str = CStr::from_ptr(action_key).to_string_lossy().into_owned();
+            glib_sys::g_free(action_key as _);
+
+            // Release the borrow before calling the handler
+            let f = state.borrow_mut().callbacks.remove(&id);
+
+            if let Some(f) = f {
+                // `DEFAULT_ACTION_KEY` maps to `None`
+                f(wm, action_key_str.parse().ok());
+            }
+        }
+        b"NotificationClosed" if is_of_type(b"(uu)\0") => {
+            let mut id: u32 = 0;
+            let mut reason: u32 = 0;
+            glib_sys::g_variant_get(
+                parameters,
+                b"(uu)\0".as_ptr() as *const c_char,
+                &mut id as *mut u32,
+                &mut reason as *mut u32,
+            );
+
+            let f = state.borrow_mut().callbacks.remove(&id);
+            drop(f);
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,128 @@
+//! Implements `pal::sound` using libcanberra.
+//!
+//! libcanberra is loaded at runtime so that it doesn't become a hard
+//! dependency of applications.
+use std::{
+    ffi::{CStr, CString},
+    os::{
+        raw::{c_char, c_int, c_void},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+    ptr::{null, null_mut},
+};
+
+use super::Wm;
+use crate::{cells::MtLazyStatic, sound::SystemSound};
+
+type CaContext = c_void;
+type CaContextCreateFn = unsafe extern "C" fn(*mut *mut CaContext) -> c_int;
+type CaContextPlayFn = unsafe extern "C" fn(*mut CaContext, u32, ...) -> c_int;
+type CaStrerrorFn = unsafe extern "C" fn(c_int) -> *const c_char;
+
+const CA_PROP_EVENT_ID: &[u8] = b"event.id\0";
+const CA_PROP_MEDIA_FILENAME: &[u8] = b"media.filename\0";
+
+struct Canberra {
+    ctx: *mut CaContext,
+    play: CaContextPlayFn,
+    strerror: CaStrerrorFn,
+}
+
+mt_lazy_static! {
+    static <Wm> ref CANBERRA: Option<Canberra> => |_| unsafe { Canberra::load() };
+}
+
+impl Canberra {
+    unsafe fn load() -> Option<Self> {
+        let handle = libc::dlopen(
+            b"libcanberra.so.0\0".as_ptr() as *const c_char,
+            libc::RTLD_NOW | libc::RTLD_LOCAL,
+        );
+        if handle.is_null() {
+            log::warn!("Could not load libcanberra. Sounds will not be played.");
+            return None;
+        }
+
+        let sym = |name: &[u8]| libc::dlsym(handle, name.as_ptr() as *const c_char);
+        let create = sym(b"ca_context_create\0");
+        let play = sym(b"ca_context_play\0");
+        let strerror = sym(b"ca_strerror\0");
+        if create.is_null() || play.is_null() || strerror.is_null() {
+            log::warn!("Could not find libcanberra's functions");
+            return None;
+        }
+
+        let create: CaContextCreateFn = std::mem::transmute(create);
+        let this = Self {
+            ctx: null_mut(),
+            play: std::mem::transmute(play),
+            strerror: std::mem::transmute(strerror),
+        };
+
+        let mut ctx = null_mut();
+        let e = create(&mut ctx);
+        if e != 0 {
+            log::warn!("ca_context_create failed: {}", this.error_message(e));
+            return None;
+        }
+
+        Some(Self { ctx, ..this })
+    }
+
+    fn error_message(&self, e: c_int) -> String {
+        unsafe { CStr::from_ptr((self.strerror)(e)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Play a sound described by a single property.
+    fn play(&self, prop_key: &[u8], prop_value: &CStr) {
+        debug_assert_eq!(prop_key.last(), Some(&0));
+
+        let e = unsafe {
+            (self.play)(
+                self.ctx,
+                0,
+                prop_key.as_ptr() as *const c_char,
+                prop_value.as_ptr(),
+                null::<c_char>(),
+            )
+        };
+        if e != 0 {
+            log::warn!("ca_context_play failed: {}", self.error_message(e));
+        }
+    }
+}
+
+pub fn play_system_sound(wm: Wm, sound: SystemSound) {
+    // The names defined by the freedesktop.org Sound Naming Specification
+    let event_id: &[u8] = match sound {
+        SystemSound::Beep => b"bell\0",
+        SystemSound::Information => b"dialog-information\0",
+        SystemSound::Warning => b"dialog-warning\0",
+        SystemSound::Error => b"dialog-error\0",
+        SystemSound::MessageReceived => b"message-new-instant\0",
+    };
+
+    if let Some(canberra) = CANBERRA.get_with_wm(wm) {
+        canberra.play(
+            CA_PROP_EVENT_ID,
+            CStr::from_bytes_with_nul(event_id).unwrap(),
+        );
+    }
+}
+
+pub fn play_sound_file(wm: Wm, path: &Path) {
+    let path = match CString::new(path.as_os_str().as_bytes()) {
+        Ok(x) => x,
+        Err(_) => {
+            log::warn!("Invalid path: {:?}", path);
+            return;
+        }
+    };
+
+    if let Some(canberra) = CANBERRA.get_with_wm(wm) {
+        canberra.play(CA_PROP_MEDIA_FILENAME, &path);
+    }
+}
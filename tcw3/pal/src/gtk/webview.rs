@@ -0,0 +1,435 @@
+//! Implements web views using WebKitGTK.
+//!
+//! WebKitGTK is loaded at runtime so that it doesn't become a hard dependency
+//! of applications.
+use glib::translate::{FromGlibPtrNone, ToGlibPtr};
+use gtk::prelude::*;
+use std::{
+    cell::Cell,
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_ulong, c_void},
+    ptr::{null, null_mut},
+    rc::{Rc, Weak},
+};
+
+use super::{
+    childwnd::{ChildWnd, HChildWnd},
+    Wm,
+};
+use crate::{cells::MtLazyStatic, iface};
+
+type GObject = c_void;
+type WebKitWebView = c_void;
+type WebKitUserContentManager = c_void;
+type WebKitUserScript = c_void;
+type WebKitJavascriptResult = c_void;
+type JscValue = c_void;
+
+const WEBKIT_USER_CONTENT_INJECT_TOP_FRAME: c_int = 1;
+const WEBKIT_USER_SCRIPT_INJECT_AT_DOCUMENT_START: c_int = 0;
+const WEBKIT_LOAD_STARTED: c_int = 0;
+const WEBKIT_LOAD_FINISHED: c_int = 3;
+
+/// The name of the script message handler used by `window.tcw3.postMessage`.
+const MESSAGE_HANDLER_NAME: &[u8] = b"tcw3\0";
+
+/// Defines `window.tcw3.postMessage`.
+const BRIDGE_SCRIPT: &[u8] = b"window.tcw3 = { postMessage: function (m) { \
+    window.webkit.messageHandlers.tcw3.postMessage(String(m)); } };\0";
+
+struct WebKit {
+    user_content_manager_new: unsafe extern "C" fn() -> *mut WebKitUserContentManager,
+    register_script_message_handler:
+        unsafe extern "C" fn(*mut WebKitUserContentManager, *const c_char) -> glib_sys::gboolean,
+    user_script_new: unsafe extern "C" fn(
+        *const c_char,
+        c_int,
+        c_int,
+        *const *const c_char,
+        *const *const c_char,
+    ) -> *mut WebKitUserScript,
+    user_script_unref: unsafe extern "C" fn(*mut WebKitUserScript),
+    add_script: unsafe extern "C" fn(*mut WebKitUserContentManager, *mut WebKitUserScript),
+    web_view_new_with_user_content_manager:
+        unsafe extern "C" fn(*mut WebKitUserContentManager) -> *mut gtk_sys::GtkWidget,
+    load_uri: unsafe extern "C" fn(*mut WebKitWebView, *const c_char),
+    load_html: unsafe extern "C" fn(*mut WebKitWebView, *const c_char, *const c_char),
+    run_javascript: unsafe extern "C" fn(
+        *mut WebKitWebView,
+        *const c_char,
+        *mut gio_sys::GCancellable,
+        gio_sys::GAsyncReadyCallback,
+        glib_sys::gpointer,
+    ),
+    get_uri: unsafe extern "C" fn(*mut WebKitWebView) -> *const c_char,
+    javascript_result_get_js_value:
+        unsafe extern "C" fn(*mut WebKitJavascriptResult) -> *mut JscValue,
+    jsc_value_to_string: unsafe extern "C" fn(*mut JscValue) -> *mut c_char,
+}
+
+mt_lazy_static! {
+    static <Wm> ref WEBKIT: Option<WebKit> => |_| unsafe { WebKit::load() };
+}
+
+impl WebKit {
+    unsafe fn load() -> Option<Self> {
+        // WebKitGTK 4.1 only differs from 4.0 in the libsoup version it uses
+        let handle = [
+            &b"libwebkit2gtk-4.0.so.37\0"[..],
+            &b"libwebkit2gtk-4.1.so.0\0"[..],
+        ]
+        .iter()
+        .map(|name| {
+            libc::dlopen(
+                name.as_ptr() as *const c_char,
+                libc::RTLD_NOW | libc::RTLD_LOCAL,
+            )
+        })
+        .find(|handle| !handle.is_null());
+
+        let handle = if let Some(handle) = handle {
+            handle
+        } else {
+            log::warn!("Could not load WebKitGTK. Web views will not be available.");
+            return None;
+        };
+
+        // `dlsym` also searches the libraries loaded as dependencies of
+        // `handle`, which include JavaScriptCore
+        let mut missing = false;
+        let mut sym = |name: &[u8]| {
+            let p = libc::dlsym(handle, name.as_ptr() as *const c_char);
+            if p.is_null() {
+                log::warn!(
+                    "Could not find WebKitGTK's function {:?}",
+                    CStr::from_bytes_with_nul(name).unwrap()
+                );
+                missing = true;
+            }
+            p
+        };
+
+        let syms = [
+            sym(b"webkit_user_content_manager_new\0"),
+            sym(b"webkit_user_content_manager_register_script_message_handler\0"),
+            sym(b"webkit_user_script_new\0"),
+            sym(b"webkit_user_script_unref\0"),
+            sym(b"webkit_user_content_manager_add_script\0"),
+            sym(b"webkit_web_view_new_with_user_content_manager\0"),
+            sym(b"webkit_web_view_load_uri\0"),
+            sym(b"webkit_web_view_load_html\0"),
+            sym(b"webkit_web_view_run_javascript\0"),
+            sym(b"webkit_web_view_get_uri\0"),
+            sym(b"webkit_javascript_result_get_js_value\0"),
+            sym(b"jsc_value_to_string\0"),
+        ];
+        if missing {
+            return None;
+        }
+
+        use std::mem::transmute;
+        Some(Self {
+            user_content_manager_new: transmute(syms[0]),
+            register_script_message_handler: transmute(syms[1]),
+            user_script_new: transmute(syms[2]),
+            user_script_unref: transmute(syms[3]),
+            add_script: transmute(syms[4]),
+            web_view_new_with_user_content_manager: transmute(syms[5]),
+            load_uri: transmute(syms[6]),
+            load_html: transmute(syms[7]),
+            run_javascript: transmute(syms[8]),
+            get_uri: transmute(syms[9]),
+            javascript_result_get_js_value: transmute(syms[10]),
+            jsc_value_to_string: transmute(syms[11]),
+        })
+    }
+}
+
+/// A web view attached to a child window.
+pub(super) struct WebView {
+    widget: gtk::Widget,
+    user_content_manager: *mut WebKitUserContentManager,
+    /// Signal handlers: `(instance, handler_id)`
+    handlers: Vec<(*mut GObject, c_ulong)>,
+}
+
+/// The user data of signal handlers.
+struct SignalData {
+    wm: Wm,
+    child: Weak<ChildWnd>,
+    listener: Box<dyn iface::WebViewListener<Wm>>,
+    /// Set by `load-failed`, which precedes `load-changed` with
+    /// `WEBKIT_LOAD_FINISHED`.
+    load_failed: Cell<bool>,
+}
+
+impl Drop for WebView {
+    fn drop(&mut self) {
+        unsafe {
+            for &(instance, handler_id) in self.handlers.iter() {
+                // Destroying a widget disconnects its signal handlers. This
+                // happens when the parent window is destroyed.
+                if gobject_sys::g_signal_handler_is_connected(instance as _, handler_id) != 0 {
+                    gobject_sys::g_signal_handler_disconnect(instance as _, handler_id);
+                }
+            }
+            gobject_sys::g_object_unref(self.user_content_manager as _);
+        }
+        self.widget.destroy();
+    }
+}
+
+pub fn attach_web_view(
+    wm: Wm,
+    child: &HChildWnd,
+    listener: Box<dyn iface::WebViewListener<Wm>>,
+) -> bool {
+    let webkit = if let Some(webkit) = WEBKIT.get_with_wm(wm) {
+        webkit
+    } else {
+        return false;
+    };
+
+    assert!(
+        child.inner.web_view.borrow().is_none(),
+        "already has a web view"
+    );
+
+    let web_view = unsafe {
+        let ucm = (webkit.user_content_manager_new)();
+        (webkit.register_script_message_handler)(ucm, MESSAGE_HANDLER_NAME.as_ptr() as _);
+
+        let script = (webkit.user_script_new)(
+            BRIDGE_SCRIPT.as_ptr() as _,
+            WEBKIT_USER_CONTENT_INJECT_TOP_FRAME,
+            WEBKIT_USER_SCRIPT_INJECT_AT_DOCUMENT_START,
+            null(),
+            null(),
+        );
+        (webkit.add_script)(ucm, script);
+        (webkit.user_script_unref)(script);
+
+        // The returned reference is floating. `from_glib_none` sinks it.
+        let widget_ptr = (webkit.web_view_new_with_user_content_manager)(ucm);
+        let widget = gtk::Widget::from_glib_none(widget_ptr);
+
+        let data = Rc::new(SignalData {
+            wm,
+            child: Rc::downgrade(&child.inner),
+            listener,
+            load_failed: Cell::new(false),
+        });
+
+        let handlers = vec![
+            (
+                ucm,
+                connect(
+                    ucm,
+                    b"script-message-received::tcw3\0",
+                    handle_script_message_received as *const (),
+                    &data,
+                ),
+            ),
+            (
+                widget_ptr as *mut GObject,
+                connect(
+                    widget_ptr as _,
+                    b"load-changed\0",
+                    handle_load_changed as *const (),
+                    &data,
+                ),
+            ),
+            (
+                widget_ptr as *mut GObject,
+                connect(
+                    widget_ptr as _,
+                    b"load-failed\0",
+                    handle_load_failed as *const (),
+                    &data,
+                ),
+            ),
+        ];
+
+        WebView {
+            widget,
+            user_content_manager: ucm,
+            handlers,
+        }
+    };
+
+    web_view.widget.set_hexpand(true);
+    web_view.widget.set_vexpand(true);
+    child.container().add(&web_view.widget);
+    web_view.widget.show();
+
+    *child.inner.web_view.borrow_mut() = Some(web_view);
+
+    true
+}
+
+/// Connect a signal handler. Each handler holds a strong reference to `data`,
+/// which is released when the handler is disconnected.
+unsafe fn connect(
+    instance: *mut GObject,
+    signal: &[u8],
+    handler: *const (),
+    data: &Rc<SignalData>,
+) -> c_ulong {
+    unsafe extern "C" fn destroy_data(data: glib_sys::gpointer, _: *mut gobject_sys::GClosure) {
+        drop(Rc::from_raw(data as *const SignalData));
+    }
+
+    gobject_sys::g_signal_connect_data(
+        instance as _,
+        signal.as_ptr() as *const c_char,
+        Some(std::mem::transmute(handler)),
+        Rc::into_raw(Rc::clone(data)) as glib_sys::gpointer,
+        Some(destroy_data),
+        0,
+    )
+}
+
+/// Get the web view's widget pointer (as `WebKitWebView *`) to call WebKitGTK
+/// functions with it.
+fn with_web_view(wm: Wm, child: &HChildWnd, f: impl FnOnce(&WebKit, *mut WebKitWebView)) {
+    let webkit = if let Some(webkit) = WEBKIT.get_with_wm(wm) {
+        webkit
+    } else {
+        return;
+    };
+
+    // Clone the widget so that `web_view` is not borrowed while WebKitGTK
+    // (which might emit signals synchronously) is running
+    let widget = if let Some(web_view) = &*child.inner.web_view.borrow() {
+        web_view.widget.clone()
+    } else {
+        return;
+    };
+
+    let widget_ptr: *mut gtk_sys::GtkWidget = widget.to_glib_none().0;
+    f(webkit, widget_ptr as _);
+}
+
+fn to_cstring(s: &str) -> Option<CString> {
+    CString::new(s)
+        .map_err(|_| log::warn!("A string passed to the web view contains a NUL character"))
+        .ok()
+}
+
+pub fn web_view_navigate(wm: Wm, child: &HChildWnd, url: &str) {
+    let url = if let Some(x) = to_cstring(url) {
+        x
+    } else {
+        return;
+    };
+    with_web_view(wm, child, |webkit, web_view| unsafe {
+        (webkit.load_uri)(web_view, url.as_ptr());
+    });
+}
+
+pub fn web_view_load_html(wm: Wm, child: &HChildWnd, html: &str, base_url: Option<&str>) {
+    let html = if let Some(x) = to_cstring(html) {
+        x
+    } else {
+        return;
+    };
+    let base_url = match base_url.map(to_cstring) {
+        Some(None) => return,
+        Some(Some(x)) => Some(x),
+        None => None,
+    };
+    with_web_view(wm, child, |webkit, web_view| unsafe {
+        (webkit.load_html)(
+            web_view,
+            html.as_ptr(),
+            base_url.as_ref().map(|x| x.as_ptr()).unwrap_or(null()),
+        );
+    });
+}
+
+pub fn web_view_eval_script(wm: Wm, child: &HChildWnd, script: &str) {
+    let script = if let Some(x) = to_cstring(script) {
+        x
+    } else {
+        return;
+    };
+    with_web_view(wm, child, |webkit, web_view| unsafe {
+        (webkit.run_javascript)(web_view, script.as_ptr(), null_mut(), None, null_mut());
+    });
+}
+
+/// Call `f` with the listener and the child window handle if the child
+/// window is still alive.
+unsafe fn with_listener(
+    data: glib_sys::gpointer,
+    f: impl FnOnce(&SignalData, &dyn iface::WebViewListener<Wm>, &HChildWnd),
+) {
+    let data = &*(data as *const SignalData);
+    if let Some(child) = data.child.upgrade() {
+        f(data, &*data.listener, &HChildWnd::from_inner(child));
+    }
+}
+
+unsafe fn web_view_uri(webkit: &WebKit, web_view: *mut WebKitWebView) -> String {
+    let uri = (webkit.get_uri)(web_view);
+    if uri.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(uri).to_string_lossy().into_owned()
+    }
+}
+
+unsafe extern "C" fn handle_script_message_received(
+    _: *mut WebKitUserContentManager,
+    js_result: *mut WebKitJavascriptResult,
+    data: glib_sys::gpointer,
+) {
+    with_listener(data, |data, listener, child| {
+        let webkit = WEBKIT.get_with_wm(data.wm).as_ref().unwrap();
+
+        let value = (webkit.javascript_result_get_js_value)(js_result);
+        let message_ptr = (webkit.jsc_value_to_string)(value);
+        let message = CStr::from_ptr(message_ptr).to_string_lossy().into_owned();
+        glib_sys::g_free(message_ptr as _);
+
+        listener.message_received(data.wm, child, &message);
+    });
+}
+
+unsafe extern "C" fn handle_load_changed(
+    web_view: *mut WebKitWebView,
+    load_event: c_int,
+    data: glib_sys::gpointer,
+) {
+    with_listener(data, |data, listener, child| {
+        let webkit = WEBKIT.get_with_wm(data.wm).as_ref().unwrap();
+
+        match load_event {
+            WEBKIT_LOAD_STARTED => {
+                data.load_failed.set(false);
+                let uri = web_view_uri(webkit, web_view);
+                listener.navigation_started(data.wm, child, &uri);
+            }
+            WEBKIT_LOAD_FINISHED => {
+                let success = !data.load_failed.get();
+                let uri = web_view_uri(webkit, web_view);
+                listener.navigation_completed(data.wm, child, &uri, success);
+            }
+            _ => {}
+        }
+    });
+}
+
+unsafe extern "C" fn handle_load_failed(
+    _: *mut WebKitWebView,
+    _: c_int,
+    _failing_uri: *const c_char,
+    _: *mut glib_sys::GError,
+    data: glib_sys::gpointer,
+) -> glib_sys::gboolean {
+    let data = &*(data as *const SignalData);
+    data.load_failed.set(true);
+
+    // Let WebKitGTK display an error page
+    glib_sys::GFALSE
+}
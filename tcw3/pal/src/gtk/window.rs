@@ -1,3 +1,4 @@
+use cggeom::Box2;
 use cgmath::{Point2, Vector2};
 use gdk::prelude::*;
 use glib::{
@@ -12,10 +13,11 @@ use std::{
     os::raw::{c_int, c_uint},
     ptr::{null_mut, NonNull},
     rc::Rc,
+    time::Instant,
 };
 
-use super::{comp, Wm, WndAttrs};
-use crate::{actions, iface, prelude::*, MtSticky};
+use super::{appearance, clipboard, comp, Wm, WndAttrs};
+use crate::{actions, clickcount::ClickCounter, iface, prelude::*, MtSticky};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct HWnd {
@@ -33,8 +35,24 @@ pub(super) static COMPOSITOR: MtSticky<RefCell<comp::Compositor>, Wm> =
 
 static DRAWING_WND: MtSticky<Cell<Option<WndPoolPtr>>, Wm> = MtSticky::new(Cell::new(None));
 
+mt_lazy_static! {
+    /// Removes the theme's background color from `GtkWindow`. `GtkWindow`
+    /// reports itself as opaque to the compositing manager (by
+    /// `gdk_window_set_opaque_region`) if the background color is opaque,
+    /// which defeats `WndFlags::TRANSPARENT`.
+    static <Wm> ref TRANSPARENT_CSS: gtk::CssProvider => |_| {
+        let provider = gtk::CssProvider::new();
+        provider
+            .load_from_data(b"window { background-color: transparent; }")
+            .unwrap();
+        provider
+    };
+}
+
 struct Wnd {
     gtk_wnd: gtk::Window,
+    /// Hosts `gtk_widget` and child windows.
+    overlay: gtk::Overlay,
     gtk_widget: WndWidget,
     comp_wnd: comp::Wnd,
     listener: Rc<dyn iface::WndListener<Wm>>,
@@ -43,11 +61,25 @@ struct Wnd {
     /// The last known size of the window.
     size: [i32; 2],
 
+    /// `true` if `update_wnd` returned without analyzing layer changes, in
+    /// which case the next update must not be limited by a damage region.
+    update_skipped: bool,
+
     tick_callback_active: bool,
     tick_callback_continue: bool,
 
     drag_state: Option<MouseDragState>,
+    click_counter: ClickCounter,
     scroll_state: Option<ScrollState>,
+    gesture_state: Option<GestureState>,
+    pointer_grab: Option<PointerGrabState>,
+    /// The last requested maximization state. Used while the window is not
+    /// mapped.
+    maximized: bool,
+
+    /// Signal handlers connected to `gtk::Settings` to detect appearance
+    /// changes. They are disconnected when the window is removed.
+    settings_handlers: Vec<glib::SignalHandlerId>,
 }
 
 struct MouseDragState {
@@ -55,6 +87,23 @@ struct MouseDragState {
     pressed_buttons: u32,
 }
 
+struct GestureState {
+    listener: Rc<dyn iface::GestureListener<Wm>>,
+    /// The scale factor (relative to the start of the gesture) in the last
+    /// event.
+    last_scale: f32,
+}
+
+#[derive(Default)]
+struct PointerGrabState {
+    /// The mouse pointer location in the last motion event.
+    last_loc: Option<Point2<f32>>,
+}
+
+/// In the pointer grab mode, the mouse pointer is moved back to the center
+/// of the window when it gets closer to the window's edges than this.
+const POINTER_GRAB_MARGIN: f32 = 16.0;
+
 struct ScrollState {
     listener: Rc<dyn iface::ScrollListener<Wm>>,
     history: [ScrollEvent; SCROLL_HISTORY_LEN],
@@ -86,16 +135,21 @@ impl HWnd {
 
         let gtk_widget = WndWidget::new(wm);
 
-        gtk_wnd.add(&gtk_widget);
+        // Child windows (`Wm::new_child_wnd`) are placed over `gtk_widget`
+        let overlay = gtk::Overlay::new();
+        overlay.add(&gtk_widget);
+        gtk_wnd.add(&overlay);
         gtk_widget.set_hexpand(true);
         gtk_widget.set_vexpand(true);
 
-        // Do not automatically fill the background
+        // Do not automatically fill the background. The rendered image has an
+        // alpha channel, which is used for `WndFlags::TRANSPARENT`.
         // TODO: Use `gdk_window_set_opaque_region` to optimize
         //       system-level compositing
         gtk_wnd.set_app_paintable(true);
 
-        // On X11, we also have to request an RGBA visual
+        // On X11, we also have to request an RGBA visual. It's not available
+        // if there's no compositing manager.
         if let Some(vis) = gtk_wnd.get_screen().unwrap().get_rgba_visual() {
             gtk_wnd.set_visual(Some(&vis));
         }
@@ -107,15 +161,22 @@ impl HWnd {
 
         let wnd = Wnd {
             gtk_wnd,
+            overlay,
             gtk_widget,
             comp_wnd,
             flags: iface::WndFlags::default(),
             listener: Rc::new(()),
             size: [0, 0],
+            update_skipped: false,
             tick_callback_active: false,
             tick_callback_continue: false,
             drag_state: None,
+            click_counter: ClickCounter::default(),
             scroll_state: None,
+            gesture_state: None,
+            pointer_grab: None,
+            maximized: false,
+            settings_handlers: Vec::new(),
         };
 
         let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
@@ -145,9 +206,31 @@ impl HWnd {
             listener.focus(wm, &Self { ptr });
         });
 
+        if let Some(settings) = appearance::settings() {
+            let handler = move || {
+                let listener = {
+                    let wnds = WNDS.get_with_wm(wm).borrow();
+                    Rc::clone(&wnds[ptr].listener)
+                };
+
+                listener.system_appearance_changed(wm, &Self { ptr });
+            };
+            let handler2 = handler.clone();
+
+            wnds[ptr].settings_handlers = vec![
+                settings.connect_property_gtk_theme_name_notify(move |_| handler()),
+                settings
+                    .connect_property_gtk_application_prefer_dark_theme_notify(move |_| handler2()),
+            ];
+        }
+
         // `set_wnd_attr` borrows `WNDS`, so unborrow it before calling that
         drop(wnds);
 
+        // Make the clipboard contents available by the time `validate_action`
+        // asks for them
+        clipboard::watch(wm);
+
         let this = Self { ptr };
         this.set_wnd_attr(wm, attrs);
         this
@@ -172,9 +255,10 @@ impl HWnd {
         //       include the window title bar and the border
 
         if let Some(flags) = attrs.flags {
-            // TODO: BORDERLESS
             wnd.gtk_wnd
                 .set_resizable(flags.contains(iface::WndFlags::RESIZABLE));
+            wnd.gtk_wnd
+                .set_decorated(!flags.contains(iface::WndFlags::BORDERLESS));
 
             if (wnd.flags ^ flags).contains(iface::WndFlags::FULL_SIZE_CONTENT) {
                 let titlebar_widget;
@@ -187,6 +271,14 @@ impl HWnd {
                     });
             }
 
+            if (wnd.flags ^ flags).contains(iface::WndFlags::TRANSPARENT) {
+                set_gtk_wnd_transparent(
+                    wm,
+                    &wnd.gtk_wnd,
+                    flags.contains(iface::WndFlags::TRANSPARENT),
+                );
+            }
+
             wnd.flags = flags;
         }
 
@@ -254,6 +346,31 @@ impl HWnd {
             wnd.gtk_wnd.set_title(&caption);
         }
 
+        if let Some(pos) = attrs.position {
+            wnd.gtk_wnd.move_(pos[0], pos[1]);
+        }
+
+        if let Some(maximized) = attrs.maximized {
+            // GTK remembers the request if the window is not mapped yet
+            if maximized {
+                wnd.gtk_wnd.maximize();
+            } else {
+                wnd.gtk_wnd.unmaximize();
+            }
+            wnd.maximized = maximized;
+        }
+
+        if let Some(grab) = attrs.pointer_grab {
+            // GTK doesn't provide raw mouse input. Instead, we calculate the
+            // movement from motion events and keep the mouse pointer inside
+            // the window by warping it. (Warping doesn't work on Wayland.)
+            wnd.pointer_grab = if grab {
+                Some(wnd.pointer_grab.take().unwrap_or_default())
+            } else {
+                None
+            };
+        }
+
         if let Some(visible) = attrs.visible {
             if visible {
                 wnd.gtk_wnd.show_all();
@@ -290,6 +407,12 @@ impl HWnd {
         // Suppress further callbacks
         wnd.gtk_widget.wnd_ptr().set(None);
 
+        if let Some(settings) = appearance::settings() {
+            for handler_id in wnd.settings_handlers {
+                settings.disconnect(handler_id);
+            }
+        }
+
         // Destroy the window
         wnd.gtk_wnd.destroy();
 
@@ -299,25 +422,26 @@ impl HWnd {
             .remove_wnd(&wnd.comp_wnd);
     }
 
-    /// Implements `Wm::update_wnd`.
-    pub(super) fn update_wnd(&self, wm: Wm) {
-        if DRAWING_WND.get_with_wm(wm).get() == Some(self.ptr) {
-            return;
-        }
-
+    /// Implements `Wm::update_wnd` and `Wm::update_wnd_with_damage`.
+    pub(super) fn update_wnd(&self, wm: Wm, damage: Option<Box2<f32>>) {
         let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
         let wnd = &mut wnds[self.ptr];
 
-        if !wnd.gtk_wnd.is_visible() {
+        if DRAWING_WND.get_with_wm(wm).get() == Some(self.ptr) || !wnd.gtk_wnd.is_visible() {
+            wnd.update_skipped = true;
             return;
         }
 
+        let damage = if wnd.update_skipped { None } else { damage };
+        wnd.update_skipped = false;
+
         let (surf_size, dpi_scale) = comp_surf_props_for_widget(&wnd.gtk_widget);
 
         let added_dirty_rect = COMPOSITOR.get_with_wm(wm).borrow_mut().update_wnd(
             &mut wnd.comp_wnd,
             surf_size,
             dpi_scale,
+            damage,
         );
 
         if let Some(r) = added_dirty_rect {
@@ -330,6 +454,23 @@ impl HWnd {
         }
     }
 
+    /// Implements `Wm::render_wnd_to_bitmap`.
+    pub(super) fn render_wnd_to_bitmap(&self, wm: Wm) -> Option<super::Bitmap> {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        let wnd = &wnds[self.ptr];
+
+        COMPOSITOR
+            .get_with_wm(wm)
+            .borrow_mut()
+            .render_wnd_to_bitmap(&wnd.comp_wnd)
+    }
+
+    /// Get the `gtk::Window` representing the window.
+    pub(super) fn gtk_wnd(&self, wm: Wm) -> gtk::Window {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        wnds[self.ptr].gtk_wnd.clone()
+    }
+
     /// Implements `Wm::get_wnd_size`.
     pub(super) fn get_wnd_size(&self, wm: Wm) -> [u32; 2] {
         let wnds = WNDS.get_with_wm(wm).borrow();
@@ -340,6 +481,25 @@ impl HWnd {
         ]
     }
 
+    /// Implements `Wm::get_wnd_position`.
+    pub(super) fn get_wnd_position(&self, wm: Wm) -> [i32; 2] {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        let (x, y) = wnds[self.ptr].gtk_wnd.get_position();
+        [x, y]
+    }
+
+    /// Implements `Wm::is_wnd_maximized`.
+    pub(super) fn is_wnd_maximized(&self, wm: Wm) -> bool {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        let wnd = &wnds[self.ptr];
+        match wnd.gtk_wnd.get_window() {
+            Some(gdk_wnd) if wnd.gtk_wnd.is_visible() => {
+                gdk_wnd.get_state().contains(gdk::WindowState::MAXIMIZED)
+            }
+            _ => wnd.maximized,
+        }
+    }
+
     /// Implements `Wm::get_wnd_dpi_scale`.
     pub(super) fn get_wnd_dpi_scale(&self, wm: Wm) -> f32 {
         let wnds = WNDS.get_with_wm(wm).borrow();
@@ -347,6 +507,13 @@ impl HWnd {
         gtk_wnd.get_scale_factor() as f32
     }
 
+    /// Get the `GtkOverlay` hosting child windows. Returns `None` if the
+    /// window has been removed.
+    pub(super) fn gtk_overlay(&self, wm: Wm) -> Option<gtk::Overlay> {
+        let wnds = WNDS.get_with_wm(wm).borrow();
+        wnds.get(self.ptr).map(|wnd| wnd.overlay.clone())
+    }
+
     // Implements `Wm::is_wnd_focused`.
     pub(super) fn is_wnd_focused(&self, wm: Wm) -> bool {
         let wnds = WNDS.get_with_wm(wm).borrow();
@@ -472,6 +639,27 @@ fn with_wnd_mut<R>(wm: Wm, wnd_ptr: WndPtr, f: impl FnOnce(&mut Wnd, HWnd, Wm) -
     Some(f(wnd, HWnd { ptr }, wm))
 }
 
+/// Apply or unapply `WndFlags::TRANSPARENT` to a `GtkWindow`. The window
+/// already has an RGBA visual (if available) and doesn't fill its background,
+/// so this only has to keep `GtkWindow` from marking itself as opaque.
+fn set_gtk_wnd_transparent(wm: Wm, gtk_wnd: &gtk::Window, transparent: bool) {
+    let style_context = gtk_wnd.get_style_context();
+    let provider = TRANSPARENT_CSS.get_with_wm(wm);
+
+    if transparent {
+        style_context.add_provider(provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+        if !gtk_wnd.get_screen().map_or(false, |s| s.is_composited()) {
+            log::warn!(
+                "WndFlags::TRANSPARENT was specified, but the screen is not \
+                 composited. The transparent regions will be displayed in black."
+            );
+        }
+    } else {
+        style_context.remove_provider(provider);
+    }
+}
+
 /// Handles `GtkWidgetClass::draw`. `wnd_ptr` is retrieved from
 /// `TcwWndWidget::wnd_ptr`.
 #[no_mangle]
@@ -506,7 +694,8 @@ extern "C" fn tcw_wnd_widget_draw_handler(wnd_ptr: WndPtr, cairo_ctx: *mut cairo
         let mut compositor = COMPOSITOR.get_with_wm(wm).borrow_mut();
 
         let (surf_size, dpi_scale) = comp_surf_props_for_widget(&wnd.gtk_widget);
-        compositor.update_wnd(&mut wnd.comp_wnd, surf_size, dpi_scale);
+        compositor.update_wnd(&mut wnd.comp_wnd, surf_size, dpi_scale, None);
+        wnd.update_skipped = false;
 
         compositor.paint_wnd(&mut wnd.comp_wnd);
 
@@ -585,6 +774,111 @@ impl iface::KeyEvent<AccelTable> for KeyEvent {
     fn translate_accel(&self, accel_table: &AccelTable) -> Option<iface::ActionId> {
         accel_table.find_action_with_key(self.keyval, self.mod_flags)
     }
+
+    fn key(&self) -> iface::Key {
+        key_from_keyval(self.keyval)
+    }
+
+    fn modifiers(&self) -> iface::ModifierState {
+        modifiers_from_mod_flags(self.mod_flags)
+    }
+}
+
+/// Convert compressed modifier flags (see `AccelTable::compress_mod_flags`)
+/// to `iface::ModifierState`.
+fn modifiers_from_mod_flags(mod_flags: u8) -> iface::ModifierState {
+    let mut state = iface::ModifierState::empty();
+    let flags = [
+        (AccelTable::MOD_SHIFT, iface::ModifierState::SHIFT),
+        (AccelTable::MOD_CONTROL, iface::ModifierState::CONTROL),
+        (AccelTable::MOD_META, iface::ModifierState::ALT),
+        (AccelTable::MOD_SUPER, iface::ModifierState::SUPER),
+    ];
+    for &(mod_flag, flag) in flags.iter() {
+        state.set(flag, mod_flags & mod_flag != 0);
+    }
+    state
+}
+
+/// Convert a GDK key value to `iface::Key`.
+fn key_from_keyval(keyval: u32) -> iface::Key {
+    use gdk::enums::key as k;
+    use iface::Key;
+
+    match keyval {
+        k::BackSpace => Key::Backspace,
+        k::Tab | k::ISO_Left_Tab => Key::Tab,
+        k::Return => Key::Return,
+        k::Escape => Key::Escape,
+        k::Page_Up => Key::PageUp,
+        k::Page_Down => Key::PageDown,
+        k::End => Key::End,
+        k::Home => Key::Home,
+        k::Left => Key::Left,
+        k::Up => Key::Up,
+        k::Right => Key::Right,
+        k::Down => Key::Down,
+        k::Insert => Key::Insert,
+        k::Delete => Key::Delete,
+        k::KP_0 => Key::Numpad0,
+        k::KP_1 => Key::Numpad1,
+        k::KP_2 => Key::Numpad2,
+        k::KP_3 => Key::Numpad3,
+        k::KP_4 => Key::Numpad4,
+        k::KP_5 => Key::Numpad5,
+        k::KP_6 => Key::Numpad6,
+        k::KP_7 => Key::Numpad7,
+        k::KP_8 => Key::Numpad8,
+        k::KP_9 => Key::Numpad9,
+        k::KP_Multiply => Key::NumpadMultiply,
+        k::KP_Add => Key::NumpadAdd,
+        k::KP_Separator => Key::NumpadSeparator,
+        k::KP_Subtract => Key::NumpadSubtract,
+        k::KP_Decimal => Key::NumpadDecimal,
+        k::KP_Divide => Key::NumpadDivide,
+        k::F1 => Key::F1,
+        k::F2 => Key::F2,
+        k::F3 => Key::F3,
+        k::F4 => Key::F4,
+        k::F5 => Key::F5,
+        k::F6 => Key::F6,
+        k::F7 => Key::F7,
+        k::F8 => Key::F8,
+        k::F9 => Key::F9,
+        k::F10 => Key::F10,
+        k::F11 => Key::F11,
+        k::F12 => Key::F12,
+        k::F13 => Key::F13,
+        k::F14 => Key::F14,
+        k::F15 => Key::F15,
+        k::F16 => Key::F16,
+        k::F17 => Key::F17,
+        k::F18 => Key::F18,
+        k::F19 => Key::F19,
+        k::F20 => Key::F20,
+        k::F21 => Key::F21,
+        k::F22 => Key::F22,
+        k::F23 => Key::F23,
+        k::F24 => Key::F24,
+        k::AudioMute => Key::VolumeMute,
+        k::AudioLowerVolume => Key::VolumeDown,
+        k::AudioRaiseVolume => Key::VolumeUp,
+        k::AudioPlay | k::AudioPause => Key::MediaPlayPause,
+        k::AudioStop => Key::MediaStop,
+        k::AudioNext => Key::MediaNextTrack,
+        k::AudioPrev => Key::MediaPrevTrack,
+        _ => {
+            // Letters are upper/lower cased depending on the state of the
+            // CapsLock and Shift keys. Note that other characters affected by
+            // the Shift key (e.g., `!` on US keyboards) are reported as they
+            // are.
+            let c = unsafe { gdk_sys::gdk_keyval_to_unicode(gdk_sys::gdk_keyval_to_lower(keyval)) };
+            match std::char::from_u32(c) {
+                Some(c) if c != '\0' && !c.is_control() => Key::Char(c),
+                _ => Key::Unknown,
+            }
+        }
+    }
 }
 
 #[no_mangle]
@@ -736,8 +1030,10 @@ extern "C" fn tcw_wnd_widget_button_handler(
             // Call `MouseDragListener::mouse_down`
             let drag_listener = Rc::clone(&drag_state.listener);
 
+            let click_count = wnd.click_counter.click(Instant::now(), loc, button as u8);
+
             drop(wnds);
-            drag_listener.mouse_down(wm, &hwnd, loc, button as u8);
+            drag_listener.mouse_down(wm, &hwnd, loc, button as u8, click_count);
         } else {
             // Mouse button released
             let drag_state = wnd.drag_state.as_mut()?;
@@ -777,7 +1073,35 @@ extern "C" fn tcw_wnd_widget_motion_handler(wnd_ptr: WndPtr, x: f32, y: f32) {
         // Stop any ongoing scroll gesture (just in case)
         wnds = stop_scroll(wm, wnds, hwnd.clone());
 
-        let wnd = wnds.get(ptr)?;
+        let wnd = wnds.get_mut(ptr)?;
+
+        // Calculate the relative motion in the pointer grab mode
+        let mut relative_motion = None;
+        if let Some(grab) = &mut wnd.pointer_grab {
+            if let Some(last_loc) = grab.last_loc {
+                let delta = loc - last_loc;
+                if delta != Vector2::new(0.0, 0.0) {
+                    relative_motion = Some((Rc::clone(&wnd.listener), delta));
+                }
+            }
+            grab.last_loc = Some(loc);
+
+            let size = [
+                wnd.gtk_widget.get_allocated_width() as f32,
+                wnd.gtk_widget.get_allocated_height() as f32,
+            ];
+            if loc.x < POINTER_GRAB_MARGIN
+                || loc.y < POINTER_GRAB_MARGIN
+                || loc.x > size[0] - POINTER_GRAB_MARGIN
+                || loc.y > size[1] - POINTER_GRAB_MARGIN
+            {
+                let center = Point2::new(size[0] * 0.5, size[1] * 0.5);
+                if warp_pointer(&wnd.gtk_wnd, &wnd.gtk_widget, center) {
+                    // Don't report the motion caused by warping
+                    grab.last_loc = Some(center);
+                }
+            }
+        }
 
         if let Some(drag_state) = wnd.drag_state.as_ref() {
             // `MouseDragListener::mouse_motion`
@@ -793,10 +1117,32 @@ extern "C" fn tcw_wnd_widget_motion_handler(wnd_ptr: WndPtr, x: f32, y: f32) {
             listener.mouse_motion(wm, &hwnd, loc);
         }
 
+        if let Some((listener, delta)) = relative_motion {
+            listener.mouse_relative_motion(wm, &hwnd, delta);
+        }
+
         Some(())
     })();
 }
 
+/// Move the mouse pointer to the point `loc` in `gtk_widget`'s coordinate
+/// space. Returns `false` on failure.
+fn warp_pointer(gtk_wnd: &gtk::Window, gtk_widget: &WndWidget, loc: Point2<f32>) -> bool {
+    (|| {
+        let (x, y) = gtk_widget.translate_coordinates(gtk_wnd, loc.x as i32, loc.y as i32)?;
+        let (x, y) = gtk_wnd.get_window()?.get_root_coords(x, y);
+
+        let device = gtk_widget
+            .get_display()?
+            .get_default_seat()?
+            .get_pointer()?;
+        device.warp(&gtk_wnd.get_screen()?, x, y);
+
+        Some(())
+    })()
+    .is_some()
+}
+
 #[no_mangle]
 extern "C" fn tcw_wnd_widget_leave_handler(wnd_ptr: WndPtr) {
     log::debug!("leave{:?}", (wnd_ptr,));
@@ -1165,6 +1511,8 @@ fn stop_scroll(wm: Wm, mut wnds: RefMut<'_, WndPool>, hwnd: HWnd) -> RefMut<'_,
 pub struct AccelTable {
     #[doc(hidden)]
     pub key: &'static [ActionKeyBinding],
+    #[doc(hidden)]
+    pub key_bindings: Vec<iface::KeyBinding>,
 }
 
 #[doc(hidden)]
@@ -1192,6 +1540,30 @@ impl AccelTable {
             .filter(move |binding| mod_flags == binding.mod_flags && keyval == binding.keyval)
             .map(|binding| binding.action)
             .nth(0)
+            .or_else(|| self.find_action_with_key_binding(keyval, mod_flags))
+    }
+
+    fn find_action_with_key_binding(&self, keyval: u32, mod_flags: u8) -> Option<iface::ActionId> {
+        if self.key_bindings.is_empty() {
+            return None;
+        }
+
+        let key = key_from_keyval(keyval);
+        let modifiers = modifiers_from_mod_flags(mod_flags);
+
+        self.key_bindings
+            .iter()
+            .find(|binding| binding.key == key && binding.modifiers == modifiers)
+            .map(|binding| binding.action)
+    }
+}
+
+impl iface::AccelTableNew for AccelTable {
+    fn new_with_key_bindings(key_bindings: Vec<iface::KeyBinding>) -> Self {
+        Self {
+            key: &[],
+            key_bindings,
+        }
     }
 }
 
@@ -1199,6 +1571,16 @@ static TEXT_INPUT_ACCEL: AccelTable = tcw3_pal_macro::accel_table_inner!(
     crate,
     "gtk",
     [
+        (actions::UNDO, gtk("Ctrl+Z")),
+        (actions::REDO, gtk("Shift+Ctrl+Z")),
+        (actions::REDO, gtk("Ctrl+Y")),
+        (actions::CUT, gtk("Ctrl+X")),
+        (actions::CUT, gtk("Shift+Delete")),
+        (actions::COPY, gtk("Ctrl+C")),
+        (actions::COPY, gtk("Ctrl+Insert")),
+        (actions::PASTE, gtk("Ctrl+V")),
+        (actions::PASTE, gtk("Shift+Insert")),
+        (actions::SELECT_ALL, gtk("Ctrl+A")),
         (actions::DELETE_BACKWARD, gtk("Backspace")),
         (actions::DELETE_BACKWARD_WORD, gtk("Ctrl+Backspace")),
         (actions::DELETE_FORWARD, gtk("Delete")),
@@ -1331,3 +1713,73 @@ impl WndWidget {
         }
     }
 }
+
+#[no_mangle]
+extern "C" fn tcw_wnd_widget_touchpad_pinch_handler(
+    wnd_ptr: WndPtr,
+    phase: c_int,
+    x: f32,
+    y: f32,
+    scale: f32,
+    angle_delta: f32,
+) {
+    (|| {
+        let wm = unsafe { Wm::global_unchecked() };
+        let ptr = wnd_ptr?;
+        let hwnd = HWnd { ptr };
+
+        let mut wnds = WNDS.get_with_wm(wm).borrow_mut();
+        let wnd = wnds.get_mut(ptr)?;
+
+        match phase {
+            gdk_sys::GDK_TOUCHPAD_GESTURE_PHASE_BEGIN => {
+                // Unborrow `WNDS` before calling into user code
+                let listener = Rc::clone(&wnd.listener);
+                drop(wnds);
+
+                let gesture_state = GestureState {
+                    listener: listener.gesture(wm, &hwnd, Point2::new(x, y)).into(),
+                    last_scale: 1.0,
+                };
+
+                // Re-borrow `WNDS` and set `gesture_state`
+                wnds = WNDS.get_with_wm(wm).borrow_mut();
+                let wnd = wnds.get_mut(ptr)?;
+                wnd.gesture_state = Some(gesture_state);
+            }
+            gdk_sys::GDK_TOUCHPAD_GESTURE_PHASE_UPDATE => {
+                let gesture_state = wnd.gesture_state.as_mut()?;
+
+                // `scale` is relative to the start of the gesture
+                let scale_delta = scale / gesture_state.last_scale;
+                gesture_state.last_scale = scale;
+
+                let gesture_listener = Rc::clone(&gesture_state.listener);
+
+                drop(wnds);
+                if scale_delta != 1.0 {
+                    gesture_listener.magnify(wm, &hwnd, scale_delta);
+                }
+                // `angle_delta` is positive for clockwise rotations
+                if angle_delta != 0.0 {
+                    gesture_listener.rotate(wm, &hwnd, angle_delta);
+                }
+            }
+            gdk_sys::GDK_TOUCHPAD_GESTURE_PHASE_END => {
+                let gesture_state = wnd.gesture_state.take()?;
+
+                drop(wnds);
+                gesture_state.listener.end(wm, &hwnd);
+            }
+            gdk_sys::GDK_TOUCHPAD_GESTURE_PHASE_CANCEL => {
+                let gesture_state = wnd.gesture_state.take()?;
+
+                drop(wnds);
+                gesture_state.listener.cancel(wm, &hwnd);
+            }
+            _ => {}
+        }
+
+        Some(())
+    })();
+}
@@ -47,6 +47,7 @@ static gboolean tcw_wnd_widget_leave_notify_event(GtkWidget *widget,
                                                   GdkEventCrossing *event);
 static gboolean tcw_wnd_widget_scroll_event(GtkWidget *widget,
                                             GdkEventScroll *event);
+static gboolean tcw_wnd_widget_event(GtkWidget *widget, GdkEvent *event);
 static gboolean tcw_wnd_widget_key_press_event(GtkWidget *widget,
                                                GdkEventKey *event);
 static gboolean tcw_wnd_widget_key_release_event(GtkWidget *widget,
@@ -60,6 +61,7 @@ static void tcw_wnd_widget_class_init(TcwWndWidgetClass *klass) {
     widget_class->motion_notify_event = tcw_wnd_widget_motion_notify_event;
     widget_class->leave_notify_event = tcw_wnd_widget_leave_notify_event;
     widget_class->scroll_event = tcw_wnd_widget_scroll_event;
+    widget_class->event = tcw_wnd_widget_event;
     widget_class->key_press_event = tcw_wnd_widget_key_press_event;
     widget_class->key_release_event = tcw_wnd_widget_key_release_event;
 }
@@ -78,7 +80,7 @@ static void tcw_wnd_widget_init(TcwWndWidget *self) {
         widget, gtk_widget_get_events(widget) | GDK_LEAVE_NOTIFY_MASK |
                     GDK_BUTTON_PRESS_MASK | GDK_BUTTON_RELEASE_MASK |
                     GDK_POINTER_MOTION_MASK | GDK_SCROLL_MASK |
-                    GDK_SMOOTH_SCROLL_MASK);
+                    GDK_SMOOTH_SCROLL_MASK | GDK_TOUCHPAD_GESTURE_MASK);
 
     gtk_widget_set_can_focus(widget, TRUE);
 }
@@ -183,6 +185,21 @@ static gboolean tcw_wnd_widget_scroll_event(GtkWidget *widget,
     return TRUE;
 }
 
+static gboolean tcw_wnd_widget_event(GtkWidget *widget, GdkEvent *event) {
+    TcwWndWidget *wnd_widget = TCW_WND_WIDGET(widget);
+
+    // `GtkWidgetClass` doesn't have a dedicated handler for touchpad gestures
+    if (event->type == GDK_TOUCHPAD_PINCH) {
+        GdkEventTouchpadPinch *pinch = &event->touchpad_pinch;
+        tcw_wnd_widget_touchpad_pinch_handler(
+            wnd_widget->wnd_ptr, (int)pinch->phase, (float)pinch->x,
+            (float)pinch->y, (float)pinch->scale, (float)pinch->angle_delta);
+        return TRUE;
+    }
+
+    return FALSE;
+}
+
 static gboolean tcw_wnd_widget_key_press_event(GtkWidget *widget,
                                                GdkEventKey *event) {
     TcwWndWidget *wnd_widget = TCW_WND_WIDGET(widget);
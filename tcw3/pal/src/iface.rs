@@ -10,7 +10,18 @@ use bitflags::bitflags;
 use cggeom::{box2, Box2};
 use cgmath::{Matrix3, Point2, Vector2};
 use rgb::RGBA;
-use std::{borrow::Cow, fmt, fmt::Debug, hash::Hash, ops::Range, time::Duration};
+use std::{
+    borrow::Cow,
+    fmt,
+    fmt::Debug,
+    future::Future,
+    hash::Hash,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use crate::{channel, exttex::ExternalTexture, sound};
 
 pub type RGBAF32 = RGBA<f32>;
 
@@ -31,6 +42,14 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// Represents a function call pended by `invoke_after`.
     type HInvoke: Debug + Clone + PartialEq + Eq + Hash + Send + Sync;
 
+    /// A native child window handle type. See [`new_child_wnd`].
+    ///
+    /// Backends not supporting child windows use an uninhabited type such as
+    /// `std::convert::Infallible`.
+    ///
+    /// [`new_child_wnd`]: Wm::new_child_wnd
+    type HChildWnd: Debug + Clone + PartialEq + Eq + Hash;
+
     /// A text input context handle type.
     type HTextInputCtx: Debug + Clone + PartialEq + Eq + Hash;
 
@@ -38,7 +57,9 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     ///
     /// `Wm` doesn't provide a method for constructing this type. You should use
     /// the [`accel_table!`](accel_table) macro to create an accelerator table.
-    type AccelTable: Debug + Send + Sync;
+    /// Accelerator tables with key bindings only known at runtime can be
+    /// created by [`AccelTableNew`].
+    type AccelTable: Debug + Send + Sync + AccelTableNew;
 
     /// A bitmap type.
     type Bitmap: Bitmap;
@@ -100,6 +121,59 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// associated function will never be called.
     fn cancel_invoke(self, hinv: &Self::HInvoke);
 
+    /// Get the current time of the clock used by
+    /// [`invoke_after`](Wm::invoke_after).
+    ///
+    /// Animations should use this instead of `Instant::now()` so that they
+    /// can be driven by the virtual clock of the testing backend.
+    fn now(self) -> Instant {
+        Instant::now()
+    }
+
+    /// Spawn a task on the main thread.
+    ///
+    /// The task is polled by the main event loop. The first poll is enqueued
+    /// by [`invoke`](Wm::invoke), and the subsequent ones are enqueued by
+    /// [`invoke_on_main_thread`](Wm::invoke_on_main_thread) whenever the
+    /// task's waker is woken up (which may happen on any thread). The task
+    /// is never polled from inside this method.
+    ///
+    /// A task that is never woken up again is dropped (on the main thread)
+    /// when all of its wakers are dropped.
+    fn spawn_local(self, future: impl Future<Output = ()> + 'static) {
+        crate::futuresext::spawn_local(self, future);
+    }
+
+    /// Spawn a task on the main thread. The calling thread can be any thread.
+    ///
+    /// This is a `Send` counterpart of [`spawn_local`](Wm::spawn_local).
+    ///
+    /// This method may panic if it is called before a main thread is
+    /// determined.
+    fn spawn(future: impl Future<Output = ()> + Send + 'static) {
+        Self::invoke_on_main_thread(move |wm| wm.spawn_local(future));
+    }
+
+    /// Create a channel for delivering messages from any thread to the main
+    /// thread in batches. The channel can hold up to
+    /// [`channel::DEFAULT_CAPACITY`] pending messages.
+    ///
+    /// See [`crate::channel`] for details.
+    ///
+    /// [`channel::DEFAULT_CAPACITY`]: crate::channel::DEFAULT_CAPACITY
+    fn channel<T: Send + 'static>() -> (channel::Sender<T, Self>, channel::Receiver<T, Self>) {
+        Self::channel_with_capacity(channel::DEFAULT_CAPACITY)
+    }
+
+    /// Create a channel that can hold up to `capacity` pending messages.
+    ///
+    /// See [`crate::channel`] for details. Panics if `capacity` is zero.
+    fn channel_with_capacity<T: Send + 'static>(
+        capacity: usize,
+    ) -> (channel::Sender<T, Self>, channel::Receiver<T, Self>) {
+        channel::channel(capacity)
+    }
+
     /// Enter the main loop. This method will never return.
     ///
     /// It's not allowed to call this method from a `WndListener`.
@@ -141,6 +215,21 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// loop, but the client must not assume that this will happen.
     fn update_wnd(self, window: &Self::HWnd);
 
+    /// Update a window's contents like [`update_wnd`] with a hint that only
+    /// the specified region (in window coordinates) has visually changed
+    /// since the last update.
+    ///
+    /// `damage` must cover every pixel affected by the changes made to the
+    /// window's layers since the last update. The backend may render only
+    /// the intersection of `damage` and the region it derives from the layer
+    /// changes, or ignore the hint. The default implementation calls
+    /// `update_wnd`.
+    ///
+    /// [`update_wnd`]: Wm::update_wnd
+    fn update_wnd_with_damage(self, window: &Self::HWnd, _damage: Box2<f32>) {
+        self.update_wnd(window);
+    }
+
     /// Request to have [`WndListener::update_ready`] called when the
     /// window is ready to accept a new update.
     ///
@@ -152,9 +241,35 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// to defer the update.
     fn request_update_ready_wnd(self, window: &Self::HWnd);
 
+    /// Render the current contents of a window to a bitmap without presenting
+    /// it.
+    ///
+    /// The bitmap uses the window's DPI scale. The layer tree is rendered as
+    /// of the last call to [`update_wnd`]. Returns `None` if the window has no
+    /// contents to render or the backend does not support this operation.
+    /// Currently only the backends based on the software compositor (namely,
+    /// GTK and the testing backend) support it.
+    ///
+    /// [`update_wnd`]: Wm::update_wnd
+    fn render_wnd_to_bitmap(self, _window: &Self::HWnd) -> Option<Self::Bitmap> {
+        None
+    }
+
     /// Get the size of a window's content region.
     fn get_wnd_size(self, window: &Self::HWnd) -> [u32; 2];
 
+    /// Get the position of a window (see [`WndAttrs::position`]). Returns
+    /// `None` if not supported by the system.
+    fn get_wnd_position(self, _window: &Self::HWnd) -> Option<[i32; 2]> {
+        None
+    }
+
+    /// Get a flag indicating whether a window is maximized. Returns `false`
+    /// if not supported by the system.
+    fn is_wnd_maximized(self, _window: &Self::HWnd) -> bool {
+        false
+    }
+
     /// Get the DPI scaling factor of a window.
     fn get_wnd_dpi_scale(self, _window: &Self::HWnd) -> f32 {
         1.0
@@ -163,12 +278,93 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// Get a flag indicating whether the specified window has focus.
     fn is_wnd_focused(self, window: &Self::HWnd) -> bool;
 
+    /// Create a native child window in the content region of a window.
+    ///
+    /// A child window is a platform-native window (e.g., `HWND` on Windows)
+    /// for hosting contents not managed by the layer tree, such as third-party
+    /// native controls. It's displayed over the window's layers. `bounds`
+    /// specifies the child window's region in the content coordinate space of
+    /// `parent`. A child window is visible as long as its parent is.
+    ///
+    /// Child windows are destroyed along with their parent window.
+    ///
+    /// Returns `None` if the backend does not support child windows. Currently
+    /// the Windows backend, the GTK backend, and the testing backend support
+    /// them.
+    fn new_child_wnd(self, _parent: &Self::HWnd, _bounds: Box2<f32>) -> Option<Self::HChildWnd> {
+        None
+    }
+
+    /// Move a child window.
+    ///
+    /// The behavior is unspecified if the child window has already been
+    /// removed.
+    fn set_child_wnd_bounds(self, child: &Self::HChildWnd, bounds: Box2<f32>);
+
+    /// Destroy a child window.
+    ///
+    /// It's safe to call this method for a child window whose parent window
+    /// has already been removed.
+    fn remove_child_wnd(self, child: &Self::HChildWnd);
+
+    /// Embed a web view in a child window.
+    ///
+    /// The web view fills the child window and is destroyed along with it.
+    /// A child window can host at most one web view. Events are delivered to
+    /// `listener`.
+    ///
+    /// The web view provides a JavaScript function `window.tcw3.postMessage`
+    /// that converts a given value to a string and sends it to
+    /// [`WebViewListener::message_received`].
+    ///
+    /// Returns `false` if the backend or the system does not support web
+    /// views. The web view engine (WebView2 on Windows, WebKitGTK on the GTK
+    /// backend) is loaded at runtime, so it's not a hard dependency of
+    /// applications.
+    fn attach_web_view(
+        self,
+        _child: &Self::HChildWnd,
+        _listener: Box<dyn WebViewListener<Self>>,
+    ) -> bool {
+        false
+    }
+
+    /// Start loading the specified URL in the web view attached to a child
+    /// window.
+    ///
+    /// Does nothing if the child window has no web view.
+    fn web_view_navigate(self, _child: &Self::HChildWnd, _url: &str) {}
+
+    /// Display the specified HTML document in the web view attached to a
+    /// child window. Relative URLs in the document are resolved against
+    /// `base_url`. The Windows backend ignores `base_url` because WebView2
+    /// doesn't support it.
+    ///
+    /// Does nothing if the child window has no web view.
+    fn web_view_load_html(self, _child: &Self::HChildWnd, _html: &str, _base_url: Option<&str>) {}
+
+    /// Evaluate a JavaScript code in the web view attached to a child window.
+    /// The result is discarded. Use `window.tcw3.postMessage` to return a
+    /// value.
+    ///
+    /// Does nothing if the child window has no web view.
+    fn web_view_eval_script(self, _child: &Self::HChildWnd, _script: &str) {}
+
     /// Create a layer.
     fn new_layer(self, attrs: LayerAttrs<Self::Bitmap, Self::HLayer>) -> Self::HLayer;
 
     /// Set the attributes of a layer.
     ///
+    /// Changes made by this method and [`new_layer`] don't have to be
+    /// presented until [`update_wnd`] is called for the window containing the
+    /// layer. Use [`begin_layer_txn`] to group the changes to multiple
+    /// windows.
+    ///
     /// The behavior is unspecified if the layer has already been removed.
+    ///
+    /// [`new_layer`]: Wm::new_layer
+    /// [`update_wnd`]: Wm::update_wnd
+    /// [`begin_layer_txn`]: Wm::begin_layer_txn
     fn set_layer_attr(self, layer: &Self::HLayer, attrs: LayerAttrs<Self::Bitmap, Self::HLayer>);
 
     /// Delete a layer.
@@ -178,6 +374,66 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     /// safe to call this method for a layer still in use.
     fn remove_layer(self, layer: &Self::HLayer);
 
+    /// Begin a layer transaction.
+    ///
+    /// Until the matching call to [`commit_layer_txn`], the backend may
+    /// withhold the changes made by the layer methods and [`update_wnd`] from
+    /// the screen. The changes are presented together when the outermost
+    /// transaction is committed. Transactions can be nested.
+    ///
+    /// The macOS backend maps transactions to `CATransaction`. The GTK and
+    /// testing backends postpone `update_wnd` until the commit. The Windows
+    /// backend does nothing because Windows.UI.Composition already commits
+    /// the changes made in an iteration of the event loop at once.
+    ///
+    /// [`commit_layer_txn`]: Wm::commit_layer_txn
+    /// [`update_wnd`]: Wm::update_wnd
+    fn begin_layer_txn(self) {}
+
+    /// Commit a layer transaction started by [`begin_layer_txn`].
+    ///
+    /// [`begin_layer_txn`]: Wm::begin_layer_txn
+    fn commit_layer_txn(self) {}
+
+    /// Attach a swapchain to a layer, resize the layer's swapchain, or detach
+    /// it.
+    ///
+    /// A swapchain is a set of image buffers for presenting contents rendered
+    /// by the client every frame, such as 3D graphics. `size` specifies the
+    /// dimensions of the buffers in physical pixels. `None` detaches the
+    /// swapchain.
+    ///
+    /// While a swapchain is attached, the layer displays the image most
+    /// recently presented by [`present_layer_swapchain`] in place of
+    /// [`LayerAttrs::contents`], which must be left unset. It's scaled to fit
+    /// `LayerAttrs::bounds`. The layer is transparent until the first frame
+    /// is presented.
+    ///
+    /// Returns `false` if the backend does not support swapchains. Currently
+    /// the Windows backend (using a DXGI swapchain for composition), the GTK
+    /// backend, and the testing backend support them.
+    ///
+    /// [`present_layer_swapchain`]: Wm::present_layer_swapchain
+    fn set_layer_swapchain(self, _layer: &Self::HLayer, _size: Option<[u32; 2]>) -> bool {
+        false
+    }
+
+    /// Render a new frame to the swapchain attached to a layer and present it.
+    ///
+    /// `render` is called with the back buffer to render the frame into. Its
+    /// initial contents are unspecified. The presented image is displayed
+    /// when [`update_wnd`] is called for the window containing the layer.
+    ///
+    /// Does nothing if the layer has no swapchain.
+    ///
+    /// [`update_wnd`]: Wm::update_wnd
+    fn present_layer_swapchain(
+        self,
+        _layer: &Self::HLayer,
+        _render: &mut dyn FnMut(SwapchainTarget<'_>),
+    ) {
+    }
+
     /// Create a text input context.
     ///
     /// This method may call [`TextInputCtxListener::edit`].
@@ -226,6 +482,257 @@ pub trait Wm: Clone + Copy + Sized + Debug + 'static {
     ///
     /// [`TextInputCtxListener::edit`] may be called in this method.
     fn remove_text_input_ctx(self, ctx: &Self::HTextInputCtx);
+
+    /// Replace the contents of the system clipboard with the specified text.
+    ///
+    /// The default implementation does nothing.
+    fn set_clipboard_text(self, _text: &str) {}
+
+    /// Get the text stored in the system clipboard. Returns `None` if the
+    /// clipboard doesn't contain a text.
+    ///
+    /// The default implementation always returns `None`.
+    fn clipboard_text(self) -> Option<String> {
+        None
+    }
+
+    /// Show a modal dialog for choosing files to open or a location to save a
+    /// file to.
+    ///
+    /// `parent` specifies the window that owns the dialog. The dialog may be
+    /// presented asynchronously, and `f` is called with the chosen paths when
+    /// the user dismisses the dialog. `f` receives `None` if the dialog was
+    /// cancelled. `f` is never called from inside this method.
+    ///
+    /// The default implementation calls `f` with `None` using [`invoke`].
+    /// Currently the GTK, Windows, and testing backends implement this method.
+    ///
+    /// [`invoke`]: Wm::invoke
+    fn show_file_dialog(
+        self,
+        _parent: Option<&Self::HWnd>,
+        _options: FileDialogOptions,
+        f: impl FnOnce(Self, Option<Vec<PathBuf>>) + 'static,
+    ) {
+        self.invoke(move |wm| f(wm, None));
+    }
+
+    /// Show a modal alert (a message box).
+    ///
+    /// `parent` specifies the window that owns the alert. `f` is called with
+    /// the index of the button chosen by the user (an index into
+    /// `options.buttons`) or `None` if the alert was dismissed in other ways,
+    /// e.g., by pressing Escape. `f` is never called from inside this method.
+    ///
+    /// Returns `Err(f)` without calling `f` if the backend doesn't provide
+    /// a native implementation. In this case, the caller is responsible for
+    /// presenting a substitute. (`tcw3::ui::alert::show_alert` does this
+    /// automatically.)
+    ///
+    /// The default implementation always returns `Err(f)`. Currently only the
+    /// Windows backend implements this method.
+    fn show_alert(
+        self,
+        _parent: Option<&Self::HWnd>,
+        _options: &AlertOptions,
+        f: AlertCallback<Self>,
+    ) -> Result<(), AlertCallback<Self>> {
+        Err(f)
+    }
+
+    /// Show a desktop notification (e.g., a toast notification on Windows).
+    ///
+    /// `f` is called when the user activates the notification, with `None`
+    /// if the notification itself was clicked or `Some(i)` if the `i`-th
+    /// element of `attrs.actions` was chosen. `f` might never be called, e.g.,
+    /// if the notification was dismissed or the system doesn't support
+    /// notifications. `f` is never called from inside this method.
+    ///
+    /// The default implementation does nothing. Currently only the Windows
+    /// and GTK backends implement this method.
+    fn show_notification(
+        self,
+        _attrs: NotificationAttrs,
+        _f: impl FnOnce(Self, Option<usize>) + 'static,
+    ) {
+    }
+
+    /// Play a sound provided by the system.
+    ///
+    /// The sound is played asynchronously. Errors are logged and otherwise
+    /// ignored.
+    ///
+    /// The default implementation does nothing.
+    fn play_system_sound(self, _sound: sound::SystemSound) {}
+
+    /// Play a sound file.
+    ///
+    /// The sound is played asynchronously. Errors are logged and otherwise
+    /// ignored. WAV files are supported by all backends implementing this
+    /// method. Some backends can only play one sound at a time, in which case
+    /// the currently playing sound is stopped.
+    ///
+    /// The default implementation does nothing.
+    fn play_sound_file(self, _path: &Path) {}
+
+    /// Get the operating system's current appearance settings.
+    ///
+    /// [`WndListener::system_appearance_changed`] is called when the returned
+    /// value may have changed.
+    ///
+    /// The default implementation returns `SystemAppearance::default()`.
+    fn system_appearance(self) -> SystemAppearance {
+        SystemAppearance::default()
+    }
+
+    /// Get the user interface direction of the user's current locale.
+    ///
+    /// The default implementation returns `UiDirection::Ltr`.
+    fn default_ui_direction(self) -> UiDirection {
+        UiDirection::Ltr
+    }
+}
+
+/// The operating system's appearance settings. Returned by
+/// [`Wm::system_appearance`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SystemAppearance {
+    /// The user prefers a dark color scheme.
+    pub dark_mode: bool,
+    /// A high-contrast mode is enabled. Applications should avoid
+    /// translucent backgrounds and use system colors.
+    pub high_contrast: bool,
+    /// The user-selected accent color, if the system has one.
+    pub accent_color: Option<RGBAF32>,
+}
+
+/// The direction in which user interface elements are laid out. Returned by
+/// [`Wm::default_ui_direction`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiDirection {
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left (e.g., Arabic and Hebrew).
+    Rtl,
+}
+
+impl Default for UiDirection {
+    fn default() -> Self {
+        UiDirection::Ltr
+    }
+}
+
+impl UiDirection {
+    /// Return `true` if `self` is `Rtl`.
+    pub fn is_rtl(self) -> bool {
+        self == UiDirection::Rtl
+    }
+}
+
+/// Specifies the behavior of a file dialog shown by [`Wm::show_file_dialog`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileDialogOptions {
+    /// Specifies whether the dialog is used to open files or to save a file.
+    pub kind: FileDialogKind,
+    /// The window title. If `None`, a platform-specific default is used.
+    pub title: Option<String>,
+    /// Allow selecting more than one file. Only effective for
+    /// `FileDialogKind::Open`.
+    pub multi_select: bool,
+    /// The list of file type filters. The first one is selected initially.
+    /// If empty, all files can be chosen.
+    pub filters: Vec<FileTypeFilter>,
+    /// The directory initially displayed by the dialog.
+    pub default_dir: Option<PathBuf>,
+    /// The file name initially entered in the dialog. Only effective for
+    /// `FileDialogKind::Save`.
+    pub default_name: Option<String>,
+}
+
+/// Specifies the type of a file dialog. Used by [`FileDialogOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileDialogKind {
+    /// Choose one or more existing files to open.
+    Open,
+    /// Choose a location to save a file to.
+    Save,
+}
+
+impl Default for FileDialogKind {
+    fn default() -> Self {
+        FileDialogKind::Open
+    }
+}
+
+/// A file type filter used by [`FileDialogOptions`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileTypeFilter {
+    /// The name displayed to the user, e.g., `"Images"`.
+    pub name: String,
+    /// The list of accepted file name extensions without leading periods,
+    /// e.g., `["png", "jpg"]`.
+    pub extensions: Vec<String>,
+}
+
+impl FileTypeFilter {
+    /// Construct a `FileTypeFilter`.
+    pub fn new(name: impl Into<String>, extensions: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            extensions: extensions.iter().map(|&x| x.to_owned()).collect(),
+        }
+    }
+}
+
+/// Describes an alert shown by [`Wm::show_alert`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AlertOptions {
+    /// The window title.
+    pub title: String,
+    /// The message text.
+    pub message: String,
+    /// The captions of the buttons, from left to right. If empty, a single
+    /// button labeled "OK" (or its localized equivalent) is displayed, and
+    /// choosing it is reported as index `0`.
+    pub buttons: Vec<String>,
+    /// The severity of the message, which determines the icon.
+    pub severity: AlertSeverity,
+}
+
+/// The severity of an alert. Used by [`AlertOptions`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertSeverity {
+    /// Provides information.
+    Info,
+    /// Warns about a potential problem or asks for a confirmation.
+    Warning,
+    /// Reports an error.
+    Error,
+}
+
+impl Default for AlertSeverity {
+    fn default() -> Self {
+        AlertSeverity::Info
+    }
+}
+
+/// The completion handler passed to [`Wm::show_alert`].
+pub type AlertCallback<T> = Box<dyn FnOnce(T, Option<usize>)>;
+
+/// Describes a notification shown by [`Wm::show_notification`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NotificationAttrs {
+    /// The title text.
+    pub title: String,
+    /// The body text.
+    pub body: String,
+    /// The path of an image file displayed along with the text. The
+    /// supported formats depend on the system, but PNG is supported by all
+    /// backends implementing notifications.
+    pub image: Option<PathBuf>,
+    /// The captions of the action buttons. Some systems ignore them or limit
+    /// the number of buttons that can be displayed.
+    pub actions: Vec<String>,
 }
 
 /// Returned when a function/method is called from an invalid thread.
@@ -246,12 +753,27 @@ pub struct WndAttrs<'a, T: Wm, TLayer> {
     pub size: Option<[u32; 2]>,
     pub min_size: Option<[u32; 2]>,
     pub max_size: Option<[u32; 2]>,
+    /// The position of the window's top-left corner (including the window
+    /// decoration), measured in logical pixels relative to the top-left
+    /// corner of the primary screen. Ignored if not supported by the system.
+    pub position: Option<[i32; 2]>,
+    /// Maximizes or restores the window. Ignored if not supported by the
+    /// system.
+    pub maximized: Option<bool>,
     pub flags: Option<WndFlags>,
     pub caption: Option<Cow<'a, str>>,
     pub visible: Option<bool>,
     pub listener: Option<Box<dyn WndListener<T>>>,
     pub layer: Option<Option<TLayer>>,
     pub cursor_shape: Option<CursorShape>,
+    /// The progress indicator displayed in the taskbar button (or its
+    /// equivalent). Ignored if not supported by the system.
+    pub progress: Option<Option<ProgressState>>,
+    /// Enables the pointer grab mode. While the window is focused and this
+    /// mode is enabled, the mouse pointer is confined to the content region,
+    /// and [`WndListener::mouse_relative_motion`] is called for every mouse
+    /// movement. Ignored if not supported by the system.
+    pub pointer_grab: Option<bool>,
 }
 
 impl<'a, T: Wm, TLayer> Default for WndAttrs<'a, T, TLayer> {
@@ -260,16 +782,36 @@ impl<'a, T: Wm, TLayer> Default for WndAttrs<'a, T, TLayer> {
             size: None,
             min_size: None,
             max_size: None,
+            position: None,
+            maximized: None,
             flags: None,
             caption: None,
             visible: None,
             listener: None,
             layer: None,
             cursor_shape: None,
+            progress: None,
+            pointer_grab: None,
         }
     }
 }
 
+/// The state of a progress indicator associated with a window.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressState {
+    /// An operation is in progress, but its progress is unknown.
+    Indeterminate,
+    /// An operation is in progress. The value represents the progress in
+    /// range `[0, 1]`.
+    Normal(f32),
+    /// An operation is paused. The value represents the progress in range
+    /// `[0, 1]`.
+    Paused(f32),
+    /// An operation has encountered an error. The value represents the
+    /// progress in range `[0, 1]`.
+    Error(f32),
+}
+
 bitflags! {
     pub struct WndFlags: u32 {
         const RESIZABLE = 1;
@@ -282,9 +824,10 @@ bitflags! {
         /// behind" effect if supported by the system.
         ///
         /// In general, every pixel of the window must be covered by fully
-        /// opaque layer contents (including the background color). If this
-        /// flag is set, layers with a `BACKDROP_BLUR` flag also count as
-        /// opaque contents (even if they don't have actual contents).
+        /// opaque layer contents (including the background color) unless
+        /// `TRANSPARENT` is set. If this flag is set, layers with a
+        /// `BACKDROP_BLUR` flag also count as opaque contents (even if they
+        /// don't have actual contents).
         const TRANSPARENT_BACKDROP_BLUR = 1 << 2;
 
         /// Hides the titlebar, but preserves the window border and its
@@ -298,6 +841,19 @@ bitflags! {
         /// On macOS, the standard window buttons (a.k.a. “stoplight”) are
         /// displayed.
         const FULL_SIZE_CONTENT = 1 << 3;
+
+        /// Makes the window background transparent and composites the layer
+        /// contents with what's behind the window using their alpha values
+        /// (per-pixel alpha). Combined with `BORDERLESS`, this can be used to
+        /// create non-rectangular windows such as splash screens and toast
+        /// notifications.
+        ///
+        /// Mouse events are still delivered to the transparent regions.
+        /// `TRANSPARENT_BACKDROP_BLUR` takes precedence if both flags are set.
+        ///
+        /// On X11, this requires a compositing manager. Without one, the
+        /// transparent regions are displayed in black.
+        const TRANSPARENT = 1 << 4;
     }
 }
 
@@ -343,7 +899,9 @@ pub struct LayerAttrs<TBitmap, TLayer> {
     pub transform: Option<Matrix3<f32>>,
 
     /// Specifies the content image of the layer.
-    pub contents: Option<Option<TBitmap>>,
+    ///
+    /// A `TBitmap` can be converted to `LayerContents` by `into()`.
+    pub contents: Option<Option<LayerContents<TBitmap>>>,
     /// Specifies the bounds of the content image.
     ///
     /// Because of how the anchor point is calculated in the macOS bakcend, it
@@ -383,6 +941,12 @@ pub struct LayerAttrs<TBitmap, TLayer> {
     /// Specifies the solid color underlaid to the content image.
     pub bg_color: Option<RGBAF32>,
 
+    /// Specifies the sublayers.
+    ///
+    /// A layer can have up to one superlayer at a time, and a layer tree can
+    /// be attached to up to one window at a time. To display the same image
+    /// in multiple windows, create a layer for each window and share the
+    /// `Bitmap` among them instead.
     pub sublayers: Option<Vec<TLayer>>,
 
     /// Specifies the opacity value.
@@ -394,6 +958,75 @@ pub struct LayerAttrs<TBitmap, TLayer> {
 
     /// Specifies additional options on the layer.
     pub flags: Option<LayerFlags>,
+
+    /// Specifies the radii of the rounded corners of the layer, in the order
+    /// of the top-left, top-right, bottom-right, and bottom-left corners.
+    ///
+    /// Defaults to `[0.0; 4]`. The radii are measured in the same coordinate
+    /// space as `bounds`. They are scaled down proportionally if the arcs on
+    /// any edge would overlap. The rounded rectangle is transformed by
+    /// `transform`.
+    ///
+    /// A layer having rounded corners or a `mask` clips its content image,
+    /// background color, and sublayers to its shape, as if
+    /// `LayerFlags::MASK_TO_BOUNDS` were set.
+    pub corner_radius: Option<[f32; 4]>,
+
+    /// Specifies an image whose alpha channel is stretched over `bounds` and
+    /// multiplied with the layer's shape (see `corner_radius`).
+    ///
+    /// The Windows backend only applies the mask to the content image and
+    /// background color, not to sublayers.
+    pub mask: Option<Option<TBitmap>>,
+
+    /// Specifies a drop shadow drawn behind the layer.
+    ///
+    /// The shadow is cast by the layer's shape (`bounds` and `corner_radius`)
+    /// regardless of the content image, `mask`, and sublayers, and it's not
+    /// clipped by the layer's shape. `opacity` applies to the shadow as well.
+    pub shadow: Option<Option<LayerShadow>>,
+
+    /// Specifies the radius of the Gaussian blur applied to the contents
+    /// behind the layer.
+    ///
+    /// Defaults to `0.0` (disabled). The radius is measured in the same
+    /// coordinate space as `bounds`, and the standard deviation of the blur
+    /// kernel is half of it. The blurred backdrop is clipped to the layer's
+    /// shape and drawn behind the content image and background color, so a
+    /// translucent `bg_color` can be used to tint it.
+    pub backdrop_blur_radius: Option<f32>,
+}
+
+/// A drop shadow. See [`LayerAttrs::shadow`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerShadow {
+    /// The offset of the shadow, measured in the same coordinate space as
+    /// `LayerAttrs::bounds`.
+    pub offset: Vector2<f32>,
+    /// The blur radius, measured in the same coordinate space as
+    /// `LayerAttrs::bounds`. The standard deviation of the blur kernel is
+    /// half of this value.
+    pub radius: f32,
+    /// The color of the shadow.
+    pub color: RGBAF32,
+}
+
+/// The content image of a layer. See [`LayerAttrs::contents`].
+#[derive(Debug, Clone)]
+pub enum LayerContents<TBitmap> {
+    /// A static image.
+    Bitmap(TBitmap),
+    /// Frames produced by another thread. The newest frame is latched (see
+    /// [`ExternalTexture::latch`]) every time `LayerAttrs::contents` is set
+    /// to this value, so the client should set it again whenever a new frame
+    /// is submitted.
+    ExternalTexture(ExternalTexture),
+}
+
+impl<TBitmap> From<TBitmap> for LayerContents<TBitmap> {
+    fn from(x: TBitmap) -> Self {
+        LayerContents::Bitmap(x)
+    }
 }
 
 impl<TBitmap, TLayer> LayerAttrs<TBitmap, TLayer> {
@@ -415,6 +1048,10 @@ impl<TBitmap, TLayer> LayerAttrs<TBitmap, TLayer> {
         process_one!(sublayers);
         process_one!(opacity);
         process_one!(flags);
+        process_one!(corner_radius);
+        process_one!(mask);
+        process_one!(shadow);
+        process_one!(backdrop_blur_radius);
     }
 }
 
@@ -430,6 +1067,10 @@ impl<TBitmap, TLayer> Default for LayerAttrs<TBitmap, TLayer> {
             bg_color: None,
             opacity: None,
             flags: None,
+            corner_radius: None,
+            mask: None,
+            shadow: None,
+            backdrop_blur_radius: None,
         }
     }
 }
@@ -467,6 +1108,46 @@ impl Default for LayerFlags {
     }
 }
 
+/// The back buffer of a swapchain, passed to the callback function of
+/// [`Wm::present_layer_swapchain`].
+///
+/// The variant depends on the backend. The backends based on the software
+/// compositor (GTK and the testing backend) use `Memory`. Clients rendering
+/// on a GPU can read back the result into it.
+#[derive(Debug)]
+pub enum SwapchainTarget<'a> {
+    /// An image buffer in the system memory.
+    ///
+    /// The pixel format is premultiplied ARGB8888 in the native byte order
+    /// (the same as `CAIRO_FORMAT_ARGB32`).
+    Memory {
+        data: &'a mut [u8],
+        /// The byte offset between rows.
+        stride: usize,
+        size: [u32; 2],
+    },
+    /// A Direct3D 11 texture.
+    ///
+    /// `device` and `texture` point to `ID3D11Device` and `ID3D11Texture2D`,
+    /// respectively. They are valid only during the callback. The texture's
+    /// format is `DXGI_FORMAT_B8G8R8A8_UNORM` with premultiplied alpha.
+    D3D11 {
+        device: *mut std::ffi::c_void,
+        texture: *mut std::ffi::c_void,
+        size: [u32; 2],
+    },
+}
+
+impl SwapchainTarget<'_> {
+    /// Get the dimensions of the back buffer.
+    pub fn size(&self) -> [u32; 2] {
+        match self {
+            SwapchainTarget::Memory { size, .. } => *size,
+            SwapchainTarget::D3D11 { size, .. } => *size,
+        }
+    }
+}
+
 /// Window event handlers.
 ///
 /// The receiver is immutable because event handlers may manipulate windows,
@@ -501,6 +1182,14 @@ pub trait WndListener<T: Wm> {
     /// The DPI scaling factor of a window has been updated.
     fn dpi_scale_changed(&self, _: T, _: &T::HWnd) {}
 
+    /// The operating system's appearance settings (e.g., the dark mode) may
+    /// have been updated.
+    ///
+    /// The new settings can be retrieved using [`Wm::system_appearance`].
+    /// This method is called for every open window, and may be called even if
+    /// the settings haven't actually changed.
+    fn system_appearance_changed(&self, _: T, _: &T::HWnd) {}
+
     /// Interpret a (prospective) input event using accelerator tables.
     ///
     /// The implementation doesn't inspect the event by itself. Instead, it
@@ -545,6 +1234,16 @@ pub trait WndListener<T: Wm> {
     /// The mouse pointer has left a window.
     fn mouse_leave(&self, _: T, _: &T::HWnd) {}
 
+    /// The mouse was moved while the pointer grab mode is enabled (see
+    /// [`WndAttrs::pointer_grab`]).
+    ///
+    /// `delta` is the movement amount measured in logical pixels. Unlike
+    /// `mouse_motion`, this method keeps being called even if the mouse
+    /// pointer can't move any further because it has hit the edge of the
+    /// content region. `mouse_motion` and `MouseDragListener::mouse_motion`
+    /// are still called as usual.
+    fn mouse_relative_motion(&self, _: T, _: &T::HWnd, _delta: Vector2<f32>) {}
+
     /// Determine how to respond to the mouse drag gesture that started at
     /// `loc`.
     ///
@@ -591,8 +1290,62 @@ pub trait WndListener<T: Wm> {
         Box::new(())
     }
 
+    /// Get event handlers for handling the magnification or rotation gesture
+    /// that started right now. `loc` is the center of the gesture.
+    fn gesture(&self, _: T, _: &T::HWnd, _loc: Point2<f32>) -> Box<dyn GestureListener<T>> {
+        Box::new(())
+    }
+
+    /// A finger has touched the window.
+    ///
+    /// Returns `true` if the event was handled. If it returns `false`,
+    /// `touch_move` and `touch_end` will not be called for the contact, and
+    /// the system may emulate mouse events for it if it's the primary contact
+    /// (i.e., the first finger that touched the surface).
+    fn touch_begin(&self, _: T, _: &T::HWnd, _touch: &TouchPoint) -> bool {
+        false
+    }
+
+    /// A finger has moved on the window.
+    ///
+    /// Returns `true` if the event was handled.
+    fn touch_move(&self, _: T, _: &T::HWnd, _touch: &TouchPoint) -> bool {
+        false
+    }
+
+    /// A finger has been lifted from the window, or the contact was
+    /// cancelled (`TouchPoint::cancelled`).
+    ///
+    /// Returns `true` if the event was handled.
+    fn touch_end(&self, _: T, _: &T::HWnd, _touch: &TouchPoint) -> bool {
+        false
+    }
+
+    /// A pen has touched the window.
+    ///
+    /// Returns `true` if the event was handled. If it returns `false`,
+    /// `pen_move` and `pen_up` will not be called until the pen touches the
+    /// window again, and the system may emulate mouse events for the stroke.
+    fn pen_down(&self, _: T, _: &T::HWnd, _pen: &PenState) -> bool {
+        false
+    }
+
+    /// A pen has moved while touching the window.
+    ///
+    /// Returns `true` if the event was handled.
+    fn pen_move(&self, _: T, _: &T::HWnd, _pen: &PenState) -> bool {
+        false
+    }
+
+    /// A pen has been lifted from the window.
+    ///
+    /// Returns `true` if the event was handled.
+    fn pen_up(&self, _: T, _: &T::HWnd, _pen: &PenState) -> bool {
+        false
+    }
+
     // TODO: more events
-    //  - Pointer device gestures (swipe, zoom, rotate)
+    //  - Pointer device gestures (swipe)
 }
 
 /// A default implementation of [`WndListener`].
@@ -602,6 +1355,183 @@ impl<T: Wm> WndListener<T> for () {}
 pub trait KeyEvent<AccelTable> {
     /// Interpret the event using an accelerator table.
     fn translate_accel(&self, accel_table: &AccelTable) -> Option<ActionId>;
+
+    /// Get the key that was pressed or released, or [`Key::Unknown`] if the
+    /// key can't be represented by `Key`.
+    fn key(&self) -> Key;
+
+    /// Get the state of the modifier keys at the time the event was
+    /// generated.
+    fn modifiers(&self) -> ModifierState;
+}
+
+macro_rules! define_keys {
+    (
+        $( #[$meta:meta] )*
+        pub enum $ty:ident {
+            $( #[$char_meta:meta] )*
+            Char(char),
+            $(
+                $( #[$var_meta:meta] )*
+                $var:ident,
+            )*
+        }
+    ) => {
+        $( #[$meta] )*
+        pub enum $ty {
+            $( #[$char_meta] )*
+            Char(char),
+            $(
+                $( #[$var_meta] )*
+                $var,
+            )*
+        }
+
+        /// The names of `Key`'s variants except `Char`.
+        const KEY_NAMES: &[(&str, $ty)] = &[$( (stringify!($var), $ty::$var) ),*];
+    };
+}
+
+define_keys! {
+    /// Identifies a key in a platform-independent way.
+    ///
+    /// `Key` implements `FromStr`, which accepts the variant names (e.g.,
+    /// `"PageUp"`) and single characters (e.g., `"s"`), using the same notation
+    /// as `accel_table!`. This can be used to define application-specific key
+    /// bindings in a portable way.
+    ///
+    /// Some backends can't distinguish between some keys. For example, the
+    /// macOS backend never reports media keys.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum Key {
+        /// A character without any key modifiers applied, in lower case.
+        /// Does not include the inputs by a numeric keypad.
+        Char(char),
+        Backspace,
+        Tab,
+        Return,
+        Escape,
+        PageUp,
+        PageDown,
+        End,
+        Home,
+        Left,
+        Up,
+        Right,
+        Down,
+        Insert,
+        Delete,
+        Numpad0,
+        Numpad1,
+        Numpad2,
+        Numpad3,
+        Numpad4,
+        Numpad5,
+        Numpad6,
+        Numpad7,
+        Numpad8,
+        Numpad9,
+        NumpadMultiply,
+        NumpadAdd,
+        NumpadSeparator,
+        NumpadSubtract,
+        NumpadDecimal,
+        NumpadDivide,
+        F1,
+        F2,
+        F3,
+        F4,
+        F5,
+        F6,
+        F7,
+        F8,
+        F9,
+        F10,
+        F11,
+        F12,
+        F13,
+        F14,
+        F15,
+        F16,
+        F17,
+        F18,
+        F19,
+        F20,
+        F21,
+        F22,
+        F23,
+        F24,
+        VolumeMute,
+        VolumeDown,
+        VolumeUp,
+        MediaPlayPause,
+        MediaStop,
+        MediaNextTrack,
+        MediaPrevTrack,
+        /// A key not covered by the other variants.
+        Unknown,
+    }
+}
+
+impl std::str::FromStr for Key {
+    type Err = ParseKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(&(_, key)) = KEY_NAMES.iter().find(|(name, _)| *name == s) {
+            return Ok(key);
+        }
+
+        // If it's exactly a single character, treat it as a character key
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(Key::Char(c.to_ascii_lowercase())),
+            _ => Err(ParseKeyError),
+        }
+    }
+}
+
+/// The error type returned by `Key`'s implementation of `FromStr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ParseKeyError;
+
+impl std::fmt::Display for ParseKeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unknown key name")
+    }
+}
+
+impl std::error::Error for ParseKeyError {}
+
+bitflags! {
+    /// The state of the modifier keys.
+    pub struct ModifierState: u8 {
+        const SHIFT = 1;
+        const CONTROL = 1 << 1;
+        /// The Alt key (the Option key on macOS).
+        const ALT = 1 << 2;
+        /// The Super key (the Windows key on Windows, the Command key on
+        /// macOS).
+        const SUPER = 1 << 3;
+    }
+}
+
+/// A key binding of an accelerator table created by [`AccelTableNew`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub action: ActionId,
+    pub modifiers: ModifierState,
+    pub key: Key,
+}
+
+/// Provides a constructor for accelerator tables whose key bindings are
+/// determined at runtime (e.g., user-defined key bindings).
+pub trait AccelTableNew: Sized {
+    /// Construct an accelerator table from key bindings.
+    ///
+    /// A key event matches a binding only if the state of the modifier keys
+    /// exactly matches `KeyBinding::modifiers`. When more than one binding
+    /// matches, the first one is used.
+    fn new_with_key_bindings(bindings: Vec<KeyBinding>) -> Self;
 }
 
 /// Provides a callback method for [`WndListener::interpret_event`].
@@ -645,9 +1575,18 @@ pub type ActionId = u16;
 /// backend's behavior to test this.) On the other hand, re-mapping
 /// <kbd>Ctrl</kbd>+<kbd>Up</kbd> would be acceptable in most situations.
 ///
-/// **Caveat:** The system doesn't define a key binding for `SELECT_ALL` by
-/// default. The application **must include** one in its accelerator table and
-/// in the main main (on macOS).
+/// # Edit actions
+///
+/// `UNDO`, `REDO`, `CUT`, `COPY`, `PASTE`, and `SELECT_ALL` are bound to the
+/// platform-standard key bindings (e.g., <kbd>Ctrl</kbd>+<kbd>C</kbd>) on
+/// Windows and GTK when a text input context is active. Text input widgets
+/// (e.g., TCW3's `Entry`) handle them by implementing `validate_action` and
+/// `perform_action`.
+///
+/// **Caveat:** On macOS, these actions are delivered through the main menu.
+/// The application **must include** menu items for them (with the selectors
+/// `undo:`, `redo:`, `cut:`, `copy:`, `paste:`, and `selectAll:`) in the main
+/// menu and the corresponding bindings in its accelerator table.
 pub mod actions {
     use super::ActionId;
 
@@ -771,7 +1710,12 @@ pub trait MouseDragListener<T: Wm> {
     fn mouse_motion(&self, _: T, _: &T::HWnd, _loc: Point2<f32>) {}
 
     /// A mouse button was pressed inside a window.
-    fn mouse_down(&self, _: T, _: &T::HWnd, _loc: Point2<f32>, _button: u8) {}
+    ///
+    /// `click_count` is the number of consecutive clicks made in quick
+    /// succession at approximately the same location, including this one.
+    /// It's `1` for a single click, `2` for a double click, and so on. See
+    /// [`crate::clickcount`] for how the thresholds are determined.
+    fn mouse_down(&self, _: T, _: &T::HWnd, _loc: Point2<f32>, _button: u8, _click_count: u32) {}
 
     /// A mouse button was released inside a window.
     ///
@@ -810,6 +1754,68 @@ impl Default for ScrollDelta {
     }
 }
 
+/// Describes a touch contact. Used by [`WndListener::touch_begin`] and its
+/// family.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    /// Identifies the contact. The value is unique among the contacts
+    /// currently touching the surface, but may be reused afterwards.
+    pub id: u32,
+    /// The location of the contact.
+    pub loc: Point2<f32>,
+    /// The normalized pressure in range `[0, 1]`. `None` if the device does
+    /// not report pressure.
+    pub pressure: Option<f32>,
+    /// `true` if this is the primary contact.
+    pub primary: bool,
+    /// `true` if the contact was cancelled by the system. Only meaningful for
+    /// [`WndListener::touch_end`].
+    pub cancelled: bool,
+}
+
+impl Default for TouchPoint {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            loc: Point2::new(0.0, 0.0),
+            pressure: None,
+            primary: true,
+            cancelled: false,
+        }
+    }
+}
+
+/// Describes the state of a pen. Used by [`WndListener::pen_down`] and its
+/// family.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenState {
+    /// The location of the pen tip.
+    pub loc: Point2<f32>,
+    /// The normalized pressure in range `[0, 1]`. `1` if the device does not
+    /// report pressure.
+    pub pressure: f32,
+    /// The tilt angles in degrees along the X and Y axes, each in range
+    /// `[-90, 90]`. Positive values indicate a tilt to the right and towards
+    /// the user, respectively.
+    pub tilt: Vector2<f32>,
+    /// `true` if the eraser end of the pen is used.
+    pub eraser: bool,
+    /// `true` if the barrel button is pressed.
+    pub barrel_button: bool,
+}
+
+impl Default for PenState {
+    fn default() -> Self {
+        Self {
+            loc: Point2::new(0.0, 0.0),
+            pressure: 0.0,
+            tilt: Vector2::new(0.0, 0.0),
+            eraser: false,
+            barrel_button: false,
+        }
+    }
+}
+
 /// Event handlers for scroll gestures.
 ///
 /// A `ScrollListener` object lives until one of the following events occur:
@@ -840,6 +1846,34 @@ pub trait ScrollListener<T: Wm> {
 /// A default implementation of [`ScrollListener`].
 impl<T: Wm> ScrollListener<T> for () {}
 
+/// Event handlers for magnification and rotation gestures (e.g., pinching
+/// on a touch screen or a track pad).
+///
+/// A `GestureListener` object lives until one of the following events occur:
+///
+///  - `end` is called.
+///  - `cancel` is called.
+///
+pub trait GestureListener<T: Wm> {
+    /// The content should be magnified by the factor `scale` relative to the
+    /// previous event. For example, `2.0` means the content should appear
+    /// twice as large as it was before this event.
+    fn magnify(&self, _: T, _: &T::HWnd, _scale: f32) {}
+
+    /// The content should be rotated by `angle` radians relative to the
+    /// previous event. Positive values represent clockwise rotations.
+    fn rotate(&self, _: T, _: &T::HWnd, _angle: f32) {}
+
+    /// The gesture was completed.
+    fn end(&self, _: T, _: &T::HWnd) {}
+
+    /// The gesture was cancelled.
+    fn cancel(&self, _: T, _: &T::HWnd) {}
+}
+
+/// A default implementation of [`GestureListener`].
+impl<T: Wm> GestureListener<T> for () {}
+
 /// Describes the appearance of the mouse cursor.
 ///
 /// This type contains the same set of variants as `winit::window::CursorIcon`
@@ -892,6 +1926,24 @@ impl Default for CursorShape {
     }
 }
 
+/// Web view event handlers. See [`Wm::attach_web_view`].
+///
+/// The receiver is immutable because event handlers may manipulate windows,
+/// which in turn might cause other event handlers to be called.
+pub trait WebViewListener<T: Wm> {
+    /// A navigation to `url` has started.
+    fn navigation_started(&self, _: T, _: &T::HChildWnd, _url: &str) {}
+
+    /// A navigation has completed. `success` is `false` if the page could
+    /// not be loaded.
+    fn navigation_completed(&self, _: T, _: &T::HChildWnd, _url: &str, _success: bool) {}
+
+    /// The page called `window.tcw3.postMessage(message)`.
+    fn message_received(&self, _: T, _: &T::HChildWnd, _message: &str) {}
+}
+
+impl<T: Wm> WebViewListener<T> for () {}
+
 /// Text context event handlers.
 ///
 /// The receiver is immutable because event handlers may manipulate windows,
@@ -1053,6 +2105,9 @@ bitflags! {
 }
 
 /// A immutable, ref-counted bitmap image.
+///
+/// A bitmap can be used as the contents of any number of layers
+/// simultaneously, even if they belong to different windows.
 pub trait Bitmap: Clone + Sized + Send + Sync + Debug {
     /// Get the dimensions of a bitmap.
     fn size(&self) -> [u32; 2];
@@ -1119,6 +2174,33 @@ pub trait Canvas: Debug {
     fn ellipse(&mut self, bx: Box2<f32>) {
         super::canvas::canvas_ellipse(self, bx)
     }
+    /// Append a circular arc to the current subpath.
+    ///
+    /// The arc is centered at `center` and goes from the angle `start_angle` to
+    /// `end_angle` (both in radians, measured from the positive X axis towards
+    /// the positive Y axis). It's drawn in the direction of increasing angles
+    /// if `end_angle` is greater than `start_angle` and in the opposite
+    /// direction otherwise.
+    ///
+    /// A straight line is added from the current point to the starting point
+    /// of the arc. The current subpath must have a current point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cgmath::Point2;
+    /// # use tcw3_pal::iface::Canvas;
+    /// # fn test(canvas: &mut impl Canvas) {
+    /// use std::f32::consts::PI;
+    /// // A pie slice
+    /// canvas.move_to(Point2::new(20.0, 20.0));
+    /// canvas.arc_to(Point2::new(20.0, 20.0), 10.0, 0.0, PI * 0.5);
+    /// canvas.close_path();
+    /// # }
+    /// ```
+    fn arc_to(&mut self, center: Point2<f32>, radius: f32, start_angle: f32, end_angle: f32) {
+        super::canvas::canvas_arc_to(self, center, radius, start_angle, end_angle)
+    }
 
     /// Fill the area within the current path, using the non-zero winding number
     /// rule.
@@ -1172,8 +2254,21 @@ pub trait Canvas: Debug {
     fn set_stroke_rgb(&mut self, rgb: RGBAF32);
     // TODO: generic brush
 
+    /// Set the shape used at the end points of open subpaths and dashes.
+    /// Defaults to [`LineCap::Butt`].
     fn set_line_cap(&mut self, cap: LineCap);
+    /// Set the shape used at the corners of a stroked path. Defaults to
+    /// [`LineJoin::Miter`].
     fn set_line_join(&mut self, join: LineJoin);
+    /// Set the dash pattern used for stroking.
+    ///
+    /// `lengths` specifies the alternating lengths of the "on" and "off"
+    /// segments, starting with an "on" segment. `phase` specifies the offset
+    /// into the pattern at which the stroke starts. An empty `lengths`
+    /// disables dashing.
+    ///
+    /// The testing backend renders strokes using the same stroker as the `gtk`
+    /// backend (Cairo), so dashed strokes look the same under tests.
     fn set_line_dash(&mut self, phase: f32, lengths: &[f32]);
     /// Set the line width in pixels. Defaults to `1.0`.
     ///
@@ -1233,7 +2328,24 @@ pub trait BitmapBuilderNew: BitmapBuilder + Sized {
 pub trait TextLayout: Send + Sync + Sized {
     type CharStyle: CharStyle;
 
-    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self;
+    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+        Self::from_text_with_direction(text, style, width, ParagraphDirection::Auto)
+    }
+
+    /// Construct a `TextLayout` with the specified base (paragraph)
+    /// direction.
+    ///
+    /// The base direction determines the visual order of runs having
+    /// different directions, the alignment of lines, and which of the two
+    /// cursor locations returned by [`cursor_pos`] is the strong one.
+    ///
+    /// [`cursor_pos`]: TextLayout::cursor_pos
+    fn from_text_with_direction(
+        text: &str,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+        direction: ParagraphDirection,
+    ) -> Self;
     // TODO: construct a `TextLayout` from an attributed text
 
     /// Get the visual bounds of a `TextLayout`.
@@ -1245,6 +2357,13 @@ pub trait TextLayout: Send + Sync + Sized {
     /// point.
     fn cursor_index_from_point(&self, point: Point2<f32>) -> usize;
 
+    /// Find the grapheme cluster closest to the given point, and determine
+    /// which edge of the cluster is closer to the point.
+    ///
+    /// See [`HitTestResult`] for the definition of the leading and trailing
+    /// edges. For an empty text, this method returns `0..0` as the cluster.
+    fn hit_test(&self, point: Point2<f32>) -> HitTestResult;
+
     /// Determine the location of the cursor at the given UTF-8 offset.
     ///
     /// Two locations will be returned. One `Beam` represents the strong
@@ -1424,6 +2543,26 @@ pub trait TextLayout: Send + Sync + Sized {
     /// Similar to [`next_char`](TextLayout::next_char).
     fn next_word(&self, i: usize, forward: bool) -> usize;
 
+    /// Determine whether the UTF-8 offset range `i` is a single grapheme
+    /// cluster presented as an emoji.
+    ///
+    /// The endpoints of `i` must lie on character boundaries defined by
+    /// [`next_char`]. Returns `false` if `i` is empty or spans more than one
+    /// cluster.
+    ///
+    /// The presentation is determined from the characters by the rules of
+    /// [UTS #51] (e.g., U+2764 is presented as an emoji only if followed by
+    /// U+FE0F VARIATION SELECTOR-16). Whether the font has a color glyph for
+    /// the cluster is not taken into account.
+    ///
+    /// [`next_char`]: TextLayout::next_char
+    /// [UTS #51]: https://www.unicode.org/reports/tr51/
+    ///
+    /// # Complexity
+    ///
+    /// The time complexity of this method is `O(text_len)`.
+    fn is_emoji_cluster(&self, i: Range<usize>) -> bool;
+
     // TODO: alignment
     // TODO: inline/foreign object
 }
@@ -1467,6 +2606,54 @@ impl Beam {
     }
 }
 
+/// Specifies the base direction of a paragraph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParagraphDirection {
+    /// Determined from the first strong directional character in the text as
+    /// described in the Unicode Bidirectional Algorithm (rules P2 and P3).
+    /// The paragraph is left-to-right if there are no such characters.
+    Auto,
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left.
+    Rtl,
+}
+
+impl Default for ParagraphDirection {
+    fn default() -> Self {
+        ParagraphDirection::Auto
+    }
+}
+
+/// The result of [`TextLayout::hit_test`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitTestResult {
+    /// The UTF-8 offset range of the grapheme cluster.
+    pub index: Range<usize>,
+    /// The point is closer to the trailing edge of the cluster than to the
+    /// leading edge.
+    ///
+    /// The leading and trailing edges are defined in the logical order. The
+    /// leading edge of a cluster in a right-to-left run is on the right side,
+    /// and the trailing edge is on the left side.
+    pub trailing: bool,
+    /// The flags of the run containing the cluster.
+    pub flags: RunFlags,
+}
+
+impl HitTestResult {
+    /// Get the UTF-8 offset of the edge closer to the point, which can be
+    /// used as a cursor position.
+    #[inline]
+    pub fn cursor_index(&self) -> usize {
+        if self.trailing {
+            self.index.end
+        } else {
+            self.index.start
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct RunMetrics {
     pub flags: RunFlags,
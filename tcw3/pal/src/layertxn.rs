@@ -0,0 +1,105 @@
+//! Bookkeeping for layer transactions (`Wm::begin_layer_txn`) in backends
+//! that present layer changes in `Wm::update_wnd`.
+use cggeom::{prelude::*, Box2};
+
+/// Tracks the nesting level of layer transactions and the windows whose
+/// updates are postponed until the outermost transaction is committed.
+///
+/// Each postponed update has a damage region (see
+/// `Wm::update_wnd_with_damage`). `None` represents the whole window.
+#[derive(Debug)]
+pub(crate) struct LayerTxnQueue<T> {
+    depth: usize,
+    wnds: Vec<(T, Option<Box2<f32>>)>,
+}
+
+impl<T> LayerTxnQueue<T> {
+    pub const fn new() -> Self {
+        Self {
+            depth: 0,
+            wnds: Vec::new(),
+        }
+    }
+}
+
+impl<T: PartialEq + Clone> LayerTxnQueue<T> {
+    pub fn begin(&mut self) {
+        self.depth += 1;
+    }
+
+    /// End a transaction. Returns the windows to be updated and their damage
+    /// regions if the outermost transaction was committed.
+    pub fn commit(&mut self) -> Vec<(T, Option<Box2<f32>>)> {
+        assert_ne!(self.depth, 0, "no active layer transaction");
+        self.depth -= 1;
+        if self.depth == 0 {
+            std::mem::take(&mut self.wnds)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Postpone the update of the specified window if a transaction is
+    /// active. Returns `false` if the window should be updated immediately.
+    pub fn defer_update_wnd(&mut self, wnd: &T, damage: Option<Box2<f32>>) -> bool {
+        if self.depth == 0 {
+            return false;
+        }
+        if let Some((_, existing)) = self.wnds.iter_mut().find(|(x, _)| x == wnd) {
+            *existing = match (*existing, damage) {
+                (Some(x), Some(y)) => Some(x.union(&y)),
+                _ => None,
+            };
+        } else {
+            self.wnds.push((wnd.clone(), damage));
+        }
+        true
+    }
+
+    /// Cancel the postponed update of a removed window.
+    pub fn remove_wnd(&mut self, wnd: &T) {
+        self.wnds.retain(|(x, _)| x != wnd);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cggeom::box2;
+
+    #[test]
+    fn nested() {
+        let mut q = LayerTxnQueue::new();
+        assert!(!q.defer_update_wnd(&1, None));
+
+        q.begin();
+        assert!(q.defer_update_wnd(&1, None));
+        q.begin();
+        assert!(q.defer_update_wnd(&2, None));
+        assert!(q.defer_update_wnd(&1, None));
+        q.remove_wnd(&2);
+        assert_eq!(q.commit(), vec![]);
+        assert_eq!(q.commit(), vec![(1, None)]);
+
+        assert!(!q.defer_update_wnd(&1, None));
+    }
+
+    #[test]
+    fn merge_damage() {
+        let mut q = LayerTxnQueue::new();
+        q.begin();
+        let damage1 = box2! { min: [0.0, 0.0], max: [1.0, 1.0] };
+        let damage2 = box2! { min: [2.0, 0.0], max: [3.0, 1.0] };
+        q.defer_update_wnd(&1, Some(damage1));
+        q.defer_update_wnd(&1, Some(damage2));
+        q.defer_update_wnd(&2, Some(damage1));
+        q.defer_update_wnd(&2, None);
+        assert_eq!(
+            q.commit(),
+            vec![
+                (1, Some(box2! { min: [0.0, 0.0], max: [3.0, 1.0] })),
+                (2, None),
+            ]
+        );
+    }
+}
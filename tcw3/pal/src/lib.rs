@@ -11,8 +11,16 @@
 #![allow(clippy::let_unit_value)]
 
 mod canvas;
+pub mod channel;
+pub mod clickcount;
+mod emoji;
+pub mod exttex;
+pub mod fswatch;
 pub mod futuresext;
 pub mod iface;
+pub mod offscreen;
+pub mod secrets;
+pub mod sound;
 
 /// Re-exports traits from `iface`.
 ///
@@ -21,9 +29,9 @@ pub mod iface;
 pub mod prelude {
     pub use super::cells::{Init, MtLazyStatic, SendInit};
     pub use super::iface::{
-        Bitmap, BitmapBuilder, BitmapBuilderNew, Canvas, CanvasText, CharStyle, KeyEvent,
-        MouseDragListener, ScrollListener, TextInputCtxEdit, TextInputCtxListener, TextLayout,
-        Wm as WmTrait, WndListener,
+        AccelTableNew, Bitmap, BitmapBuilder, BitmapBuilderNew, Canvas, CanvasText, CharStyle,
+        GestureListener, KeyEvent, MouseDragListener, ScrollListener, TextInputCtxEdit,
+        TextInputCtxListener, TextLayout, WebViewListener, Wm as WmTrait, WndListener,
     };
 
     pub use super::futuresext::WmFuturesExt;
@@ -65,11 +73,23 @@ pub mod gtk;
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 pub use self::gtk as native;
 
+// `swrast` is used by `offscreen` on every platform, but some parts of it are
+// only used by the `gtk` and testing backends.
+#[cfg_attr(
+    all(
+        any(target_os = "macos", target_os = "windows"),
+        not(feature = "testing")
+    ),
+    allow(dead_code)
+)]
+mod swrast;
+
+// `layertxn` is used by the backends presenting layer changes in `update_wnd`.
 #[cfg(any(
     not(any(target_os = "macos", target_os = "windows")),
     feature = "testing"
 ))]
-mod swrast;
+mod layertxn;
 
 #[cfg(feature = "testing")]
 mod timerqueue;
@@ -205,9 +225,12 @@ pub type TextLayout = current::TextLayout;
 // the default backend.
 
 pub use self::iface::{
-    actions, ActionId, ActionStatus, BadThread, Beam, CursorShape, IndexFromPointFlags,
-    InterpretEventCtx, LayerFlags, LineCap, LineJoin, NcHit, RunFlags, RunMetrics, ScrollDelta,
-    SysFontType, TextDecorFlags, TextInputCtxEventFlags, WndFlags, RGBAF32,
+    actions, ActionId, ActionStatus, AlertOptions, AlertSeverity, BadThread, Beam, CursorShape,
+    FileDialogKind, FileDialogOptions, FileTypeFilter, HitTestResult, IndexFromPointFlags,
+    InterpretEventCtx, Key, KeyBinding, LayerFlags, LayerShadow, LineCap, LineJoin, ModifierState,
+    NcHit, NotificationAttrs, ParagraphDirection, ParseKeyError, PenState, ProgressState, RunFlags,
+    RunMetrics, ScrollDelta, SwapchainTarget, SysFontType, SystemAppearance, TextDecorFlags,
+    TextInputCtxEventFlags, TouchPoint, UiDirection, WndFlags, RGBAF32,
 };
 
 /// The window handle type of [`Wm`].
@@ -216,6 +239,9 @@ pub type HWnd = <Wm as iface::Wm>::HWnd;
 /// The layer handle type of [`Wm`].
 pub type HLayer = <Wm as iface::Wm>::HLayer;
 
+/// The native child window handle type of [`Wm`].
+pub type HChildWnd = <Wm as iface::Wm>::HChildWnd;
+
 /// The invocation handle type of [`Wm`].
 pub type HInvoke = <Wm as iface::Wm>::HInvoke;
 
@@ -231,6 +257,11 @@ pub type WndAttrs<'a> = iface::WndAttrs<'a, Wm, HLayer>;
 /// A specialization of `LayerAttrs` for the default backend.
 pub type LayerAttrs = iface::LayerAttrs<Bitmap, HLayer>;
 
+/// A specialization of `LayerContents` for the default backend.
+pub type LayerContents = iface::LayerContents<Bitmap>;
+
+pub use self::exttex::{ExternalTexture, ExternalTextureFrame, ExternalTextureProducer};
+
 /// A specialization of `CharStyleAttrs` for the default backend.
 pub type CharStyleAttrs = iface::CharStyleAttrs<CharStyle>;
 
@@ -1,4 +1,5 @@
 //! The backend for macOS, Cocoa, and Core Graphics.
+use cggeom::Box2;
 use std::{marker::PhantomData, ops::Range, time::Duration};
 
 use super::iface;
@@ -7,7 +8,9 @@ pub type WndAttrs<'a> = iface::WndAttrs<'a, Wm, HLayer>;
 pub type LayerAttrs = iface::LayerAttrs<Bitmap, HLayer>;
 pub type MtSticky<T> = super::MtSticky<T, Wm>;
 
+mod appearance;
 mod bitmap;
+mod clipboard;
 mod drawutils;
 mod layer;
 mod text;
@@ -46,6 +49,7 @@ impl iface::Wm for Wm {
     type HWnd = HWnd;
     type HLayer = HLayer;
     type HInvoke = HInvoke;
+    type HChildWnd = std::convert::Infallible;
     type HTextInputCtx = HTextInputCtx;
     type AccelTable = AccelTable;
     type Bitmap = Bitmap;
@@ -129,6 +133,14 @@ impl iface::Wm for Wm {
         window.get_size(self)
     }
 
+    fn get_wnd_position(self, window: &Self::HWnd) -> Option<[i32; 2]> {
+        Some(window.get_position(self))
+    }
+
+    fn is_wnd_maximized(self, window: &Self::HWnd) -> bool {
+        window.is_maximized(self)
+    }
+
     fn get_wnd_dpi_scale(self, window: &Self::HWnd) -> f32 {
         window.get_dpi_scale(self)
     }
@@ -137,6 +149,14 @@ impl iface::Wm for Wm {
         window.is_focused(self)
     }
 
+    fn set_child_wnd_bounds(self, child: &Self::HChildWnd, _: Box2<f32>) {
+        match *child {}
+    }
+
+    fn remove_child_wnd(self, child: &Self::HChildWnd) {
+        match *child {}
+    }
+
     fn new_layer(self, attrs: LayerAttrs) -> Self::HLayer {
         HLayer::new(self, attrs)
     }
@@ -147,6 +167,13 @@ impl iface::Wm for Wm {
         layer.remove(self);
     }
 
+    fn begin_layer_txn(self) {
+        layer::begin_txn(self);
+    }
+    fn commit_layer_txn(self) {
+        layer::commit_txn(self);
+    }
+
     fn new_text_input_ctx(
         self,
         hwnd: &Self::HWnd,
@@ -174,4 +201,20 @@ impl iface::Wm for Wm {
     fn remove_text_input_ctx(self, htictx: &Self::HTextInputCtx) {
         self.text_input_ctx_set_active(htictx, false)
     }
+
+    fn set_clipboard_text(self, text: &str) {
+        clipboard::set_clipboard_text(self, text);
+    }
+
+    fn clipboard_text(self) -> Option<String> {
+        clipboard::clipboard_text(self)
+    }
+
+    fn system_appearance(self) -> iface::SystemAppearance {
+        appearance::system_appearance(self)
+    }
+
+    fn default_ui_direction(self) -> iface::UiDirection {
+        appearance::default_ui_direction(self)
+    }
 }
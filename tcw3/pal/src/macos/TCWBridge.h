@@ -20,10 +20,12 @@ typedef uint8_t TCW3ActionStatus;
 typedef OPQAUE_HANDLE TCWListenerUserData;
 typedef OPQAUE_HANDLE TCWMouseDragListenerUserData;
 typedef OPQAUE_HANDLE TCWScrollListenerUserData;
+typedef OPQAUE_HANDLE TCWGestureListenerUserData;
 extern BOOL tcw_wndlistener_should_close(TCWListenerUserData ud);
 extern void tcw_wndlistener_close(TCWListenerUserData ud);
 extern void tcw_wndlistener_resize(TCWListenerUserData ud);
 extern void tcw_wndlistener_dpi_scale_changed(TCWListenerUserData ud);
+extern void tcw_wndlistener_system_appearance_changed(TCWListenerUserData ud);
 extern void tcw_wndlistener_update_ready(TCWListenerUserData ud);
 extern void tcw_wndlistener_focus(TCWListenerUserData ud);
 extern int tcw_wndlistener_key_down(TCWListenerUserData ud, uint16_t mod,
@@ -37,6 +39,9 @@ extern void tcw_wndlistener_perform_selector(TCWListenerUserData ud,
                                              const char *selector,
                                              size_t selector_len);
 extern void tcw_wndlistener_mouse_motion(TCWListenerUserData ud, NSPoint loc);
+extern void tcw_wndlistener_mouse_relative_motion(TCWListenerUserData ud,
+                                                  double delta_x,
+                                                  double delta_y);
 extern void tcw_wndlistener_mouse_leave(TCWListenerUserData ud);
 extern int tcw_wndlistener_nc_hit_test(TCWListenerUserData ud, NSPoint loc);
 extern TCWMouseDragListenerUserData
@@ -66,7 +71,8 @@ extern void tcw_mousedraglistener_cancel(TCWMouseDragListenerUserData ud);
 extern void tcw_mousedraglistener_mouse_motion(TCWMouseDragListenerUserData ud,
                                                NSPoint loc);
 extern void tcw_mousedraglistener_mouse_down(TCWMouseDragListenerUserData ud,
-                                             NSPoint loc, uint8_t button);
+                                             NSPoint loc, uint8_t button,
+                                             uint32_t click_count);
 extern void tcw_mousedraglistener_mouse_up(TCWMouseDragListenerUserData ud,
                                            NSPoint loc, uint8_t button);
 
@@ -85,11 +91,22 @@ extern void tcw_scrolllistener_motion(TCWScrollListenerUserData ud,
                                       double delta_y, double vel_x,
                                       double vel_y);
 
+extern TCWGestureListenerUserData
+tcw_wndlistener_gesture(TCWListenerUserData ud, NSPoint loc);
+extern void tcw_gesturelistener_release(TCWGestureListenerUserData ud);
+extern void tcw_gesturelistener_cancel(TCWGestureListenerUserData ud);
+extern void tcw_gesturelistener_end(TCWGestureListenerUserData ud);
+extern void tcw_gesturelistener_magnify(TCWGestureListenerUserData ud,
+                                        double scale);
+extern void tcw_gesturelistener_rotate(TCWGestureListenerUserData ud,
+                                       double angle);
+
 // These flags must be synchronized with `WndFlags`
 #define kTCW3WndFlagsResizable ((uint32_t)(1 << 0))
 #define kTCW3WndFlagsBorderless ((uint32_t)(1 << 1))
 #define kTCW3WndFlagsTransparentBackdropBlur ((uint32_t)(1 << 2))
 #define kTCW3WndFlagsFullSizeContent ((uint32_t)(1 << 3))
+#define kTCW3WndFlagsTransparent ((uint32_t)(1 << 4))
 
 // These callbacks are defined in `timer.rs`
 typedef struct _TraitObject {
@@ -59,6 +59,9 @@ static NSMutableSet<TCWGestureHandlerView *> *viewInstances = nil;
     BOOL momentumPhaseActive;
     TCWScrollEvent scrollEventHistory[kScrollEventHistoryLen];
     size_t scrollEventHistoryIndex;
+
+    TCWGestureListenerUserData gestureListener;
+    BOOL hasGestureListener;
 }
 
 - (id)initWithController:(TCWWindowController *)_controller {
@@ -73,6 +76,8 @@ static NSMutableSet<TCWGestureHandlerView *> *viewInstances = nil;
             self->scrollEventHistory[i].timestamp = -INFINITY;
         }
 
+        self->hasGestureListener = NO;
+
         self.autoresizingMask = NSViewWidthSizable | NSViewHeightSizable;
 
         if (!viewInstances) {
@@ -124,7 +129,8 @@ static NSMutableSet<TCWGestureHandlerView *> *viewInstances = nil;
     if (self->hasMouseDragListener) {
         self->pressedMouseButtons |= (uint64_t)1 << event.buttonNumber;
         tcw_mousedraglistener_mouse_down(self->mouseDragListener, loc,
-                                         (uint8_t)event.buttonNumber);
+                                         (uint8_t)event.buttonNumber,
+                                         (uint32_t)MAX(event.clickCount, 1));
     }
 }
 
@@ -139,6 +145,8 @@ static NSMutableSet<TCWGestureHandlerView *> *viewInstances = nil;
 
         tcw_mousedraglistener_mouse_motion(self->mouseDragListener, loc);
     }
+
+    [self->controller handleRelativeMotionOfEvent:event];
 }
 
 // Implements `NSResponder`
@@ -358,6 +366,59 @@ static NSMutableSet<TCWGestureHandlerView *> *viewInstances = nil;
     }
 }
 
+// Implements `NSResponder`
+- (void)magnifyWithEvent:(NSEvent *)event {
+    if ([self prepareGestureListenerForEvent:event]) {
+        // `magnification` is the amount to be added to the current scale
+        tcw_gesturelistener_magnify(self->gestureListener,
+                                    1.0 + event.magnification);
+    }
+}
+
+// Implements `NSResponder`
+- (void)rotateWithEvent:(NSEvent *)event {
+    if ([self prepareGestureListenerForEvent:event]) {
+        // `rotation` is measured in degrees, counterclockwise
+        tcw_gesturelistener_rotate(self->gestureListener,
+                                   event.rotation * (-M_PI / 180.0));
+    }
+}
+
+/**
+ * Start or end a magnification/rotation gesture based on the phase of `event`.
+ * Returns `YES` if `event` should be delivered to `gestureListener`.
+ */
+- (BOOL)prepareGestureListenerForEvent:(NSEvent *)event {
+    if (!self->controller) {
+        return NO;
+    }
+
+    NSEventPhase phase = event.phase;
+
+    if (phase == NSEventPhaseEnded || phase == NSEventPhaseCancelled) {
+        if (self->hasGestureListener) {
+            self->hasGestureListener = NO;
+            if (phase == NSEventPhaseEnded) {
+                tcw_gesturelistener_end(self->gestureListener);
+            } else {
+                tcw_gesturelistener_cancel(self->gestureListener);
+            }
+            tcw_gesturelistener_release(self->gestureListener);
+        }
+        return NO;
+    }
+
+    if (!self->hasGestureListener) {
+        // Start a new gesture
+        NSPoint loc = [self->controller locationOfEvent:event];
+        self->gestureListener =
+            tcw_wndlistener_gesture(self->controller.listenerUserData, loc);
+        self->hasGestureListener = YES;
+    }
+
+    return YES;
+}
+
 - (void)cancelGesture {
     self->controller = nil;
 
@@ -374,6 +435,12 @@ static NSMutableSet<TCWGestureHandlerView *> *viewInstances = nil;
         tcw_scrolllistener_cancel(self->scrollListener);
         tcw_scrolllistener_release(self->scrollListener);
     }
+
+    if (self->hasGestureListener) {
+        self->hasGestureListener = NO;
+        tcw_gesturelistener_cancel(self->gestureListener);
+        tcw_gesturelistener_release(self->gestureListener);
+    }
 }
 
 /// Overrides `NSResponder`'s method.
@@ -13,4 +13,10 @@
  * position to content view coordinates.
  */
 - (NSPoint)locationOfEvent:(NSEvent *)event;
+
+/**
+ * Report the relative motion of a mouse event if the pointer grab mode is
+ * enabled.
+ */
+- (void)handleRelativeMotionOfEvent:(NSEvent *)event;
 @end
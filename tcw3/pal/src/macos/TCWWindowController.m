@@ -21,6 +21,7 @@ extern CGSConnection CGSDefaultConnectionForThread();
     bool windowIsOnscreen;
     bool displayLinkIsRunning;
     bool wantsUpdateReadyCallback;
+    bool pointerGrab;
 
     TCWGestureHandlerView *gestureHandler;
 }
@@ -34,6 +35,7 @@ extern CGSConnection CGSDefaultConnectionForThread();
         self->windowIsOnscreen = false;
         self->displayLinkIsRunning = false;
         self->wantsUpdateReadyCallback = false;
+        self->pointerGrab = false;
 
         NSRect frame = NSMakeRect(0.0, 0.0, 800.0, 600.0);
 
@@ -54,6 +56,9 @@ extern CGSConnection CGSDefaultConnectionForThread();
             [[TCWWindowView alloc] initWithController:self];
         self->window.contentView.wantsLayer = YES;
         self->window.contentView.layer.masksToBounds = NO;
+        // Required by `CALayer.backgroundFilters`, which implements
+        // `LayerAttrs::backdrop_blur_radius`
+        self->window.contentView.layerUsesCoreImageFilters = YES;
 
         // Remove the titlebar's background.
         self->window.titlebarAppearsTransparent = YES;
@@ -71,6 +76,8 @@ extern CGSConnection CGSDefaultConnectionForThread();
 
 /** Called by `window.rs` */
 - (void)close {
+    self->pointerGrab = false;
+    [self updatePointerAssociation];
     [self->window close];
 }
 
@@ -129,6 +136,11 @@ extern CGSConnection CGSDefaultConnectionForThread();
         self->window.backgroundColor =
             [[NSColor clearColor] colorWithAlphaComponent:0.01];
         self->window.opaque = NO;
+    } else if (flags & kTCW3WndFlagsTransparent) {
+        // Composite the contents using their alpha values. The window shadow
+        // follows the shape of the contents.
+        self->window.backgroundColor = [NSColor clearColor];
+        self->window.opaque = NO;
     } else {
         // The API contract of `TRANSPARENT_BACKDROP_BLUR` requires that every
         // pixel is covered by something, so `backgroundColor` actually
@@ -159,6 +171,61 @@ extern CGSConnection CGSDefaultConnectionForThread();
     [view setCursorShape:(TCW3CursorShape)shape];
 }
 
+/** Called by `window.rs` */
+- (void)setPointerGrab:(BOOL)grab {
+    self->pointerGrab = grab;
+    [self updatePointerAssociation];
+}
+
+/**
+ * Freeze the mouse pointer if the pointer grab mode is enabled and the window
+ * is the key window. Mouse events still report movements via
+ * `NSEvent.deltaX` and `NSEvent.deltaY` while the pointer is frozen.
+ */
+- (void)updatePointerAssociation {
+    bool freeze = self->pointerGrab && self->window.keyWindow;
+    CGAssociateMouseAndMouseCursorPosition(!freeze);
+}
+
+- (void)handleRelativeMotionOfEvent:(NSEvent *)event {
+    if (!self->pointerGrab || (event.deltaX == 0.0 && event.deltaY == 0.0)) {
+        return;
+    }
+    tcw_wndlistener_mouse_relative_motion(self.listenerUserData, event.deltaX,
+                                          event.deltaY);
+}
+
+/**
+ * Called by `window.rs`. `point` is measured from the top-left corner of the
+ * primary screen, with the Y axis pointing down.
+ */
+- (void)setFrameTopLeft:(NSPoint)point {
+    CGFloat screenHeight = NSScreen.screens.firstObject.frame.size.height;
+    [self->window
+        setFrameTopLeftPoint:NSMakePoint(point.x, screenHeight - point.y)];
+}
+
+/** Called by `window.rs` */
+- (NSPoint)frameTopLeft {
+    CGFloat screenHeight = NSScreen.screens.firstObject.frame.size.height;
+    NSRect frame = self->window.frame;
+    return NSMakePoint(frame.origin.x,
+                       screenHeight - (frame.origin.y + frame.size.height));
+}
+
+/** Called by `window.rs` */
+- (void)setZoomed:(BOOL)zoomed {
+    // `zoom:` toggles the state
+    if (self->window.zoomed != zoomed) {
+        [self->window zoom:nil];
+    }
+}
+
+/** Called by `window.rs` */
+- (BOOL)isZoomed {
+    return self->window.zoomed;
+}
+
 /** Called by `window.rs` */
 - (void)makeKeyAndOrderFront {
     [self->window makeKeyAndOrderFront:nil];
@@ -361,12 +428,14 @@ extern CGSConnection CGSDefaultConnectionForThread();
 /** Implements `NSWindowDelegate`. */
 - (void)windowDidBecomeKey:(NSNotification *)notification {
     (void)notification;
+    [self updatePointerAssociation];
     tcw_wndlistener_focus(self.listenerUserData);
 }
 
 /** Implements `NSWindowDelegate`. */
 - (void)windowDidResignKey:(NSNotification *)notification {
     (void)notification;
+    [self updatePointerAssociation];
     tcw_wndlistener_focus(self.listenerUserData);
 }
 
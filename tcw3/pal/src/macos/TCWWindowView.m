@@ -25,6 +25,16 @@
     return YES;
 }
 
+// Overrides `NSView` (macOS 10.14 and later)
+- (void)viewDidChangeEffectiveAppearance {
+    if (!self->controller) {
+        return;
+    }
+
+    tcw_wndlistener_system_appearance_changed(
+        self->controller.listenerUserData);
+}
+
 // Overrides `NSView`
 - (void)updateTrackingAreas {
     if (self->trackingArea) {
@@ -52,6 +62,8 @@
     NSPoint loc = [self->controller locationOfEvent:event];
 
     tcw_wndlistener_mouse_motion(self->controller.listenerUserData, loc);
+
+    [self->controller handleRelativeMotionOfEvent:event];
 }
 
 // Implements `NSResponder`
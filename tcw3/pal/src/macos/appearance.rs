@@ -0,0 +1,84 @@
+use cocoa::{
+    base::{id, nil},
+    foundation::{NSInteger, NSString},
+};
+use objc::{
+    class, msg_send,
+    runtime::{BOOL, NO},
+    sel, sel_impl,
+};
+use std::ffi::CStr;
+
+use super::{
+    utils::{with_autorelease_pool, IdRef},
+    Wm,
+};
+use crate::iface;
+
+pub fn system_appearance(_: Wm) -> iface::SystemAppearance {
+    with_autorelease_pool(|| unsafe {
+        iface::SystemAppearance {
+            dark_mode: is_dark_mode(),
+            high_contrast: is_high_contrast(),
+            accent_color: accent_color(),
+        }
+    })
+}
+
+pub fn default_ui_direction(_: Wm) -> iface::UiDirection {
+    // `NSUserInterfaceLayoutDirectionRightToLeft`
+    const RIGHT_TO_LEFT: NSInteger = 1;
+
+    let direction: NSInteger = unsafe {
+        let app: id = msg_send![class!(NSApplication), sharedApplication];
+        msg_send![app, userInterfaceLayoutDirection]
+    };
+
+    if direction == RIGHT_TO_LEFT {
+        iface::UiDirection::Rtl
+    } else {
+        iface::UiDirection::Ltr
+    }
+}
+
+unsafe fn is_dark_mode() -> bool {
+    let defaults: id = msg_send![class!(NSUserDefaults), standardUserDefaults];
+    let key = IdRef::new(NSString::alloc(nil).init_str("AppleInterfaceStyle"));
+    let style: id = msg_send![defaults, stringForKey: *key];
+    if style == nil {
+        return false;
+    }
+
+    CStr::from_ptr(style.UTF8String()).to_bytes() == b"Dark"
+}
+
+unsafe fn is_high_contrast() -> bool {
+    let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+    let value: BOOL = msg_send![workspace, accessibilityDisplayShouldIncreaseContrast];
+    value != NO
+}
+
+unsafe fn accent_color() -> Option<iface::RGBAF32> {
+    // `controlAccentColor` is available since macOS 10.14
+    let responds: BOOL = msg_send![
+        class!(NSColor),
+        respondsToSelector: sel!(controlAccentColor)
+    ];
+    if responds == NO {
+        return None;
+    }
+
+    let color: id = msg_send![class!(NSColor), controlAccentColor];
+    let color_space: id = msg_send![class!(NSColorSpace), sRGBColorSpace];
+    let color: id = msg_send![color, colorUsingColorSpace: color_space];
+    if color == nil {
+        return None;
+    }
+
+    let mut rgba = [0.0f64; 4];
+    let p = rgba.as_mut_ptr();
+    let () = msg_send![color, getRed:p green:p.add(1) blue:p.add(2) alpha:p.add(3)];
+    let [r, g, b, a] = rgba;
+
+    Some(iface::RGBAF32::new(r as f32, g as f32, b as f32, a as f32))
+}
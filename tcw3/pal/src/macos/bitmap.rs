@@ -2,13 +2,17 @@ use cggeom::Box2;
 use cgmath::{Matrix3, Point2};
 use core_foundation::base::TCFType;
 use core_graphics::{
+    base::{
+        kCGBitmapByteOrder32Little, kCGImageAlphaPremultipliedFirst, kCGRenderingIntentDefault,
+    },
     color::SysCGColorRef,
     context::{CGContext, CGContextRef, CGLineCap, CGLineJoin},
+    data_provider::CGDataProvider,
     image::{CGImage, CGImageAlphaInfo},
 };
-use std::fmt;
+use std::{fmt, sync::Arc};
 
-use super::super::{iface, LineCap, LineJoin, RGBAF32};
+use super::super::{exttex::ExternalTextureFrame, iface, LineCap, LineJoin, RGBAF32};
 use super::drawutils::{
     cg_affine_transform_from_matrix3, cg_color_from_rgbaf32, cg_color_space_srgb, cg_rect_from_box2,
 };
@@ -30,6 +34,29 @@ impl fmt::Debug for Bitmap {
     }
 }
 
+impl Bitmap {
+    /// Construct a `Bitmap` referencing a frame of `ExternalTexture` without
+    /// copying.
+    pub(super) fn from_external_texture_frame(frame: ExternalTextureFrame) -> Self {
+        let [width, height] = frame.size();
+        let provider = CGDataProvider::from_buffer(Arc::clone(frame.data_arc()));
+        let cg_image = CGImage::new(
+            width as _,
+            height as _,
+            8,  // bits_per_component
+            32, // bits_per_pixel
+            frame.stride(),
+            &cg_color_space_srgb(),
+            // BGRA in memory order
+            kCGImageAlphaPremultipliedFirst | kCGBitmapByteOrder32Little,
+            &provider,
+            false, // should_interpolate
+            kCGRenderingIntentDefault,
+        );
+        Self { cg_image }
+    }
+}
+
 impl iface::Bitmap for Bitmap {
     fn size(&self) -> [u32; 2] {
         [self.cg_image.width() as u32, self.cg_image.height() as u32]
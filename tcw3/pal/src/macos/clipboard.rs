@@ -0,0 +1,33 @@
+use cocoa::{
+    appkit::{NSPasteboard, NSPasteboardTypeString},
+    base::nil,
+    foundation::NSString,
+};
+use std::ffi::CStr;
+
+use super::{
+    utils::{with_autorelease_pool, IdRef},
+    Wm,
+};
+
+pub fn set_clipboard_text(_: Wm, text: &str) {
+    with_autorelease_pool(|| unsafe {
+        let pb = NSPasteboard::generalPasteboard(nil);
+        let ns_text = IdRef::new(NSString::alloc(nil).init_str(text));
+        pb.clearContents();
+        pb.setString_forType(*ns_text, NSPasteboardTypeString);
+    })
+}
+
+pub fn clipboard_text(_: Wm) -> Option<String> {
+    with_autorelease_pool(|| unsafe {
+        let pb = NSPasteboard::generalPasteboard(nil);
+        let ns_text = pb.stringForType(NSPasteboardTypeString);
+        if ns_text == nil {
+            return None;
+        }
+
+        let st = CStr::from_ptr(ns_text.UTF8String());
+        Some(st.to_string_lossy().into_owned())
+    })
+}
@@ -1,20 +1,27 @@
-use cggeom::prelude::*;
-use cgmath::{prelude::*, Matrix4};
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{prelude::*, Matrix4, Point2, Vector2};
 use cocoa::{
     base::{id, nil},
+    foundation::NSString,
     quartzcore::{transaction, CALayer},
 };
-use core_graphics::geometry::CGPoint;
+use core_foundation::base::{CFRelease, TCFType};
+use core_graphics::{
+    base::CGFloat,
+    geometry::{CGAffineTransform, CGPoint},
+};
 use leakypool::{LazyToken, LeakyPool, PoolPtr, SingletonToken, SingletonTokenId};
 use objc::{class, msg_send, sel, sel_impl};
-use std::cell::RefCell;
+use std::{cell::RefCell, os::raw::c_void, ptr::null};
 
-use super::super::iface::LayerFlags;
+use super::super::iface::{LayerContents, LayerFlags, LayerShadow};
 use super::{
+    bitmap::Bitmap,
     drawutils::{
-        ca_transform_3d_from_matrix4, cg_color_from_rgbaf32, cg_rect_from_box2,
+        ca_transform_3d_from_matrix4, cg_color_from_rgbaf32, cg_rect_from_box2, cg_size_from_vec2,
         extend_matrix3_with_identity_z,
     },
+    utils::IdRef,
     LayerAttrs, MtSticky, Wm,
 };
 
@@ -32,6 +39,30 @@ pub struct HLayer {
 
 struct Layer {
     ca_layer: CALayer,
+    shape: RefCell<LayerShape>,
+    /// Implements `LayerAttrs::shadow`. This layer is placed right below
+    /// `ca_layer` in the same superlayer. (`ca_layer`'s own shadow can't be
+    /// used because `CALayer.mask` and `masksToBounds` clip it.) Its
+    /// geometry and opacity are synchronized with `ca_layer`'s.
+    shadow_layer: RefCell<Option<CALayer>>,
+}
+
+/// Implements `LayerAttrs::corner_radius` and `LayerAttrs::mask` using
+/// `CALayer.mask`.
+struct LayerShape {
+    /// `LayerAttrs::bounds`
+    bounds: Box2<f32>,
+    /// `LayerAttrs::corner_radius`
+    corner_radius: [f32; 4],
+    /// `LayerAttrs::mask`. Kept alive because `mask_layer` only holds an `id`
+    /// of its `CGImage`.
+    mask: Option<Bitmap>,
+    /// The layer assigned to `ca_layer.mask`. Displays `mask`, or an opaque
+    /// color if there's no `mask`. Created lazily.
+    mask_layer: Option<CALayer>,
+    /// A `CAShapeLayer` assigned to `mask_layer.mask`, representing the
+    /// rounded rectangle. Created lazily.
+    rrect_layer: Option<id>,
 }
 
 impl Layer {
@@ -43,10 +74,39 @@ impl Layer {
         let ca_layer = CALayer::new();
         let () = unsafe { msg_send![ca_layer.id(), retain] };
 
-        Self { ca_layer }
+        Self {
+            ca_layer,
+            shape: RefCell::new(LayerShape {
+                bounds: box2! { min: [0.0, 0.0], max: [0.0, 0.0] },
+                corner_radius: [0.0; 4],
+                mask: None,
+                mask_layer: None,
+                rrect_layer: None,
+            }),
+            shadow_layer: RefCell::new(None),
+        }
     }
 }
 
+impl Drop for Layer {
+    fn drop(&mut self) {
+        // `CALayer` releases itself on drop, but `rrect_layer` is just an `id`
+        if let Some(rrect_layer) = self.shape.get_mut().rrect_layer {
+            let () = unsafe { msg_send![rrect_layer, release] };
+        }
+    }
+}
+
+/// Begin a `CATransaction`. Layer changes made until the matching
+/// `commit_txn` are presented atomically.
+pub(super) fn begin_txn(_: Wm) {
+    transaction::begin();
+}
+
+pub(super) fn commit_txn(_: Wm) {
+    transaction::commit();
+}
+
 impl HLayer {
     pub(super) fn new(wm: Wm, attrs: LayerAttrs) -> Self {
         let layer = Layer::new(wm);
@@ -85,6 +145,13 @@ impl HLayer {
         }
 
         if let Some(value) = attrs.contents {
+            let value = value.and_then(|contents| match contents {
+                LayerContents::Bitmap(bitmap) => Some(bitmap),
+                LayerContents::ExternalTexture(tex) => {
+                    tex.latch().map(Bitmap::from_external_texture_frame)
+                }
+            });
+
             // Be careful - Do not drop `value` until `set_contents` because
             // the following `cg_image` is just a `id`, not a smart pointer
             let cg_image = if let Some(ref bitmap) = value {
@@ -131,10 +198,14 @@ impl HLayer {
         }
 
         if let Some(value) = attrs.sublayers {
-            let ca_sub_layers: Vec<_> = value
-                .iter()
-                .map(|hlayer| layer_pool[hlayer.ptr].ca_layer.id())
-                .collect();
+            let mut ca_sub_layers = Vec::with_capacity(value.len());
+            for hlayer in value.iter() {
+                let layer = &layer_pool[hlayer.ptr];
+                if let Some(shadow_layer) = &*layer.shadow_layer.borrow() {
+                    ca_sub_layers.push(shadow_layer.id());
+                }
+                ca_sub_layers.push(layer.ca_layer.id());
+            }
 
             // Autoreleased `NSArray`
             let array: id = unsafe {
@@ -157,6 +228,43 @@ impl HLayer {
                 .ca_layer
                 .set_masks_to_bounds(value.contains(LayerFlags::MASK_TO_BOUNDS));
         }
+
+        if attrs.bounds.is_some() || attrs.corner_radius.is_some() || attrs.mask.is_some() {
+            let mut shape = this_layer.shape.borrow_mut();
+            if let Some(value) = attrs.bounds {
+                shape.bounds = value;
+            }
+            if let Some(value) = attrs.corner_radius {
+                shape.corner_radius = value;
+            }
+            if let Some(value) = attrs.mask {
+                shape.mask = value;
+            }
+            update_layer_shape(&this_layer.ca_layer, &mut shape, attrs.mask.is_some());
+        }
+
+        if let Some(value) = attrs.backdrop_blur_radius {
+            update_layer_backdrop_blur(&this_layer.ca_layer, value);
+        }
+
+        if let Some(value) = attrs.shadow {
+            update_layer_shadow(this_layer, value);
+        }
+
+        if attrs.transform.is_some()
+            || attrs.bounds.is_some()
+            || attrs.corner_radius.is_some()
+            || attrs.opacity.is_some()
+            || matches!(attrs.shadow, Some(Some(_)))
+        {
+            if let Some(shadow_layer) = &*this_layer.shadow_layer.borrow() {
+                sync_shadow_layer(
+                    &this_layer.ca_layer,
+                    shadow_layer,
+                    &this_layer.shape.borrow(),
+                );
+            }
+        }
     }
 
     /// Get the `CALayer` of a layer.
@@ -166,3 +274,222 @@ impl HLayer {
         LAYER_POOL.get_with_wm(wm).borrow()[self.ptr].ca_layer.id()
     }
 }
+
+/// Update `ca_layer.mask` to reflect `shape`.
+fn update_layer_shape(ca_layer: &CALayer, shape: &mut LayerShape, mask_changed: bool) {
+    let has_corner_radius = shape.corner_radius.iter().any(|&r| r > 0.0);
+
+    if !has_corner_radius && shape.mask.is_none() {
+        if shape.mask_layer.take().is_some() {
+            let () = unsafe { msg_send![ca_layer.id(), setMask: nil] };
+        }
+        if let Some(rrect_layer) = shape.rrect_layer.take() {
+            let () = unsafe { msg_send![rrect_layer, release] };
+        }
+        return;
+    }
+
+    let is_new = shape.mask_layer.is_none();
+    let mask_layer = if let Some(x) = &shape.mask_layer {
+        x
+    } else {
+        // Retain the layer for the same reason as `Layer::new`
+        let mask_layer = CALayer::new();
+        let () = unsafe { msg_send![mask_layer.id(), retain] };
+        let () = unsafe { msg_send![ca_layer.id(), setMask: mask_layer.id()] };
+        shape.mask_layer = Some(mask_layer);
+        shape.mask_layer.as_ref().unwrap()
+    };
+
+    // The mask layer is placed in the layer's coordinate space
+    let frame = cg_rect_from_box2(shape.bounds.cast().unwrap());
+    let () = unsafe { msg_send![mask_layer.id(), setFrame: frame] };
+
+    if mask_changed || is_new {
+        if let Some(bitmap) = &shape.mask {
+            // `CGImageRef` → `id`
+            let cg_image = &*bitmap.cg_image as *const _ as id;
+            unsafe { mask_layer.set_contents(cg_image) };
+            mask_layer.set_background_color(None);
+        } else {
+            unsafe { mask_layer.set_contents(nil) };
+            let c = cg_color_from_rgbaf32([0.0, 0.0, 0.0, 1.0].into());
+            std::mem::forget(c.clone());
+            mask_layer.set_background_color(Some(c));
+        }
+    }
+
+    if has_corner_radius {
+        let rrect_layer = *shape.rrect_layer.get_or_insert_with(|| unsafe {
+            let rrect_layer: id = msg_send![class!(CAShapeLayer), new];
+            let () = msg_send![mask_layer.id(), setMask: rrect_layer];
+            rrect_layer
+        });
+
+        // `rrect_layer` is placed in `mask_layer`'s coordinate space
+        let size: Vector2<CGFloat> = shape.bounds.size().cast().unwrap();
+        let frame = cg_rect_from_box2(Box2::with_size(Point2::origin(), size));
+        unsafe {
+            let path = new_rounded_rect_path(size, shape.corner_radius);
+            let () = msg_send![rrect_layer, setFrame: frame];
+            let () = msg_send![rrect_layer, setPath: path];
+            CFRelease(path);
+        }
+    } else if let Some(rrect_layer) = shape.rrect_layer.take() {
+        let () = unsafe { msg_send![mask_layer.id(), setMask: nil] };
+        let () = unsafe { msg_send![rrect_layer, release] };
+    }
+}
+
+/// Create, update, or remove `Layer::shadow_layer` according to
+/// `LayerAttrs::shadow`.
+fn update_layer_shadow(layer: &Layer, shadow: Option<LayerShadow>) {
+    let mut shadow_layer_cell = layer.shadow_layer.borrow_mut();
+
+    let shadow = if let Some(x) = shadow {
+        x
+    } else {
+        if let Some(shadow_layer) = shadow_layer_cell.take() {
+            let () = unsafe { msg_send![shadow_layer.id(), removeFromSuperlayer] };
+        }
+        return;
+    };
+
+    let shadow_layer = shadow_layer_cell.get_or_insert_with(|| {
+        // Retain the layer for the same reason as `Layer::new`
+        let shadow_layer = CALayer::new();
+        let () = unsafe { msg_send![shadow_layer.id(), retain] };
+
+        // If `ca_layer` is already in a superlayer, insert `shadow_layer`
+        // right below it. Otherwise, this will be done when `ca_layer` is
+        // added to a superlayer.
+        unsafe {
+            let superlayer: id = msg_send![layer.ca_layer.id(), superlayer];
+            if superlayer != nil {
+                let () = msg_send![
+                    superlayer,
+                    insertSublayer:shadow_layer.id()
+                             below:layer.ca_layer.id()
+                ];
+            }
+        }
+
+        shadow_layer
+    });
+
+    // `shadowColor` is opaque. The alpha value is applied by `shadowOpacity`.
+    let mut opaque_color = shadow.color;
+    opaque_color.a = 1.0;
+    let cg_color = cg_color_from_rgbaf32(opaque_color);
+
+    // `shadowRadius` is roughly the standard deviation of the blur kernel
+    let offset = cg_size_from_vec2(shadow.offset.cast().unwrap());
+    let radius = (shadow.radius.max(0.0) * 0.5) as CGFloat;
+
+    unsafe {
+        let () = msg_send![shadow_layer.id(), setShadowColor: cg_color.as_concrete_TypeRef()];
+        let () = msg_send![shadow_layer.id(), setShadowOpacity: shadow.color.a];
+        let () = msg_send![shadow_layer.id(), setShadowOffset: offset];
+        let () = msg_send![shadow_layer.id(), setShadowRadius: radius];
+    }
+}
+
+/// Copy `ca_layer`'s geometry and opacity to `shadow_layer`, and update its
+/// `shadowPath` to reflect `shape`.
+fn sync_shadow_layer(ca_layer: &CALayer, shadow_layer: &CALayer, shape: &LayerShape) {
+    shadow_layer.set_transform(&ca_layer.transform());
+    shadow_layer.set_opacity(ca_layer.opacity());
+
+    // `shadow_layer`'s bounds start at `(0, 0)` so that the path created by
+    // `new_rounded_rect_path` can be used as-is. The anchor point is
+    // specified as a fraction, so the layer ends up in the same position as
+    // `ca_layer`.
+    let size: Vector2<CGFloat> = shape.bounds.size().cast().unwrap();
+    shadow_layer.set_bounds(&cg_rect_from_box2(Box2::with_size(Point2::origin(), size)));
+    shadow_layer.set_anchor_point(&ca_layer.anchor_point());
+
+    unsafe {
+        let path = new_rounded_rect_path(size, shape.corner_radius);
+        let () = msg_send![shadow_layer.id(), setShadowPath: path];
+        CFRelease(path);
+    }
+}
+
+/// Update `ca_layer.backgroundFilters` to implement
+/// `LayerAttrs::backdrop_blur_radius`.
+fn update_layer_backdrop_blur(ca_layer: &CALayer, radius: f32) {
+    unsafe {
+        if radius <= 0.0 {
+            let () = msg_send![ca_layer.id(), setBackgroundFilters: nil];
+            return;
+        }
+
+        let name = IdRef::new(NSString::alloc(nil).init_str("CIGaussianBlur"));
+        let key = IdRef::new(NSString::alloc(nil).init_str("inputRadius"));
+
+        // `inputRadius` is roughly the standard deviation of the blur kernel
+        let value: id = msg_send![class!(NSNumber), numberWithDouble: radius as f64 * 0.5];
+
+        // Autoreleased `CIFilter`
+        let filter: id = msg_send![class!(CIFilter), filterWithName: *name];
+        let () = msg_send![filter, setDefaults];
+        let () = msg_send![filter, setValue:value forKey:*key];
+
+        // Autoreleased `NSArray`
+        let array: id = msg_send![class!(NSArray), arrayWithObject: filter];
+        let () = msg_send![ca_layer.id(), setBackgroundFilters: array];
+    }
+}
+
+/// Create a `CGPath` representing a rectangle with rounded corners. The
+/// caller is responsible for releasing the returned path.
+///
+/// The rectangle spans from `(0, 0)` to `size`. See
+/// `LayerAttrs::corner_radius` for `radii`.
+unsafe fn new_rounded_rect_path(size: Vector2<CGFloat>, radii: [f32; 4]) -> *const c_void {
+    let (w, h) = (size.x, size.y);
+
+    let mut r = [0.0; 4];
+    for (r, &radius) in r.iter_mut().zip(radii.iter()) {
+        *r = (radius as CGFloat).max(0.0);
+    }
+
+    // Scale down the radii if the arcs on any edge overlap (as done by CSS)
+    let factor = [
+        w / (r[0] + r[1]),
+        h / (r[1] + r[2]),
+        w / (r[2] + r[3]),
+        h / (r[3] + r[0]),
+    ]
+    .iter()
+    .fold(1.0, |x: CGFloat, &y| x.min(y));
+    for r in r.iter_mut() {
+        *r *= factor;
+    }
+
+    let m: *const CGAffineTransform = null();
+    let path = CGPathCreateMutable();
+    CGPathMoveToPoint(path, m, r[0], 0.0);
+    CGPathAddArcToPoint(path, m, w, 0.0, w, h, r[1]);
+    CGPathAddArcToPoint(path, m, w, h, 0.0, h, r[2]);
+    CGPathAddArcToPoint(path, m, 0.0, h, 0.0, 0.0, r[3]);
+    CGPathAddArcToPoint(path, m, 0.0, 0.0, w, 0.0, r[0]);
+    CGPathCloseSubpath(path);
+    path
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPathCreateMutable() -> *mut c_void;
+    fn CGPathMoveToPoint(path: *mut c_void, m: *const CGAffineTransform, x: CGFloat, y: CGFloat);
+    fn CGPathAddArcToPoint(
+        path: *mut c_void,
+        m: *const CGAffineTransform,
+        x1: CGFloat,
+        y1: CGFloat,
+        x2: CGFloat,
+        y2: CGFloat,
+        radius: CGFloat,
+    );
+    fn CGPathCloseSubpath(path: *mut c_void);
+}
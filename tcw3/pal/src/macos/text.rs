@@ -6,7 +6,7 @@ use cocoa::{base::id, foundation::NSUInteger};
 use core_foundation::{
     array::{CFArray, CFArrayRef},
     attributed_string::{CFMutableAttributedString, CFMutableAttributedStringRef},
-    base::{CFIndex, CFRange, TCFType},
+    base::{CFIndex, CFRange, CFType, CFTypeRef, TCFType},
     number::CFNumber,
     string::{CFString, CFStringRef},
 };
@@ -40,7 +40,7 @@ use std::{
 use utf16count::{find_utf16_pos_in_utf8_str, rfind_utf16_pos_in_utf8_str, utf16_len_of_utf8_str};
 
 use super::super::{
-    iface,
+    emoji, iface,
     iface::{Canvas, RGBAF32},
 };
 use super::BitmapBuilder;
@@ -123,7 +123,12 @@ impl fmt::Debug for TextLayout {
 impl iface::TextLayout for TextLayout {
     type CharStyle = CharStyle;
 
-    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+    fn from_text_with_direction(
+        text: &str,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+        direction: iface::ParagraphDirection,
+    ) -> Self {
         let mut attr_str = CFMutableAttributedString::new();
 
         // Make sure the last line is not omitted
@@ -147,6 +152,21 @@ impl iface::TextLayout for TextLayout {
             1i32.into(),
         );
 
+        // Core Text uses the natural writing direction (determined from the
+        // first strong character) by default
+        let writing_dir = match direction {
+            iface::ParagraphDirection::Auto => None,
+            iface::ParagraphDirection::Ltr => Some(kCTWritingDirectionLeftToRight),
+            iface::ParagraphDirection::Rtl => Some(kCTWritingDirectionRightToLeft),
+        };
+        if let Some(writing_dir) = writing_dir {
+            attr_str.set_attribute(
+                text_range,
+                unsafe { string_attributes::kCTParagraphStyleAttributeName },
+                ctparagraphstyle_new_with_base_writing_direction(writing_dir),
+            );
+        }
+
         // TODO: other attributes
 
         let framesetter = CTFramesetter::new_with_attributed_string(attr_str.as_concrete_TypeRef());
@@ -270,44 +290,21 @@ impl iface::TextLayout for TextLayout {
 
     fn cursor_index_from_point(&self, point: Point2<f32>) -> usize {
         let lines = ctframe_get_lines(&self.frame);
-        let origins = &self.line_origins;
-
-        // See the comment in `visual_bounds`.
-        let layout_y = (self.height - point.y) as f64;
-
-        let get_bottom = |line_i: usize| {
-            let typo_bounds = ctline_get_typographic_bounds(&lines.get(line_i as _).unwrap());
-            let line_origin = origins[line_i];
-            line_origin.y - (typo_bounds.leading + typo_bounds.descent)
-        };
 
         // Find the line containing `point`
-        let line = {
-            let mut base = 0;
-            let mut size = self.num_lines();
-
-            while size > 1 {
-                let half = size / 2;
-                let mid = base + half;
-                base = if layout_y < get_bottom(mid - 1) {
-                    mid
-                } else {
-                    base
-                };
-                size -= half;
-            }
-
-            base
-        };
+        let line = self.line_from_point_y(point.y);
 
         let ctline = lines.get(line as _).unwrap();
 
         let line_start = self.line_index_range(line).start;
         let line_start_u16 = ctline_get_string_range(&ctline).location;
 
-        // Find the character position in the line
-        let i_u16 =
-            ctline_get_string_index_for_position(&ctline, CGPoint::new(point.x as f64, 0.0));
+        // Find the character position in the line. The position is relative
+        // to the line origin.
+        let i_u16 = ctline_get_string_index_for_position(
+            &ctline,
+            CGPoint::new(point.x as f64 - self.line_origins[line].x, 0.0),
+        );
 
         line_start
             + find_utf16_pos_in_utf8_str(
@@ -317,6 +314,61 @@ impl iface::TextLayout for TextLayout {
             .utf8_cursor
     }
 
+    fn hit_test(&self, point: Point2<f32>) -> iface::HitTestResult {
+        let line = self.line_from_point_y(point.y);
+        let line_range = self.line_index_range(line);
+
+        // Core Text doesn't tell which edge of a character is closer to the
+        // point. Instead, find the closest cursor position `i` and examine
+        // the clusters on both sides of it. `i` is the leading edge of the
+        // cluster starting at `i` and the trailing edge of the cluster ending
+        // at `i`.
+        let i = self.cursor_index_from_point(point);
+
+        let candidates = [
+            if i < line_range.end {
+                Some((i..self.next_char(i, true), false))
+            } else {
+                None
+            },
+            if i > line_range.start {
+                Some((self.next_char(i, false)..i, true))
+            } else {
+                None
+            },
+        ];
+
+        candidates
+            .iter()
+            .flatten()
+            .map(|(index, trailing)| {
+                let runs = self.run_metrics_of_range(index.clone());
+
+                // The distance from `point` to the cluster
+                let dist = runs
+                    .iter()
+                    .map(|run| (run.bounds.start - point.x).fmax(point.x - run.bounds.end))
+                    .fold(INFINITY, |x, y| x.fmin(y))
+                    .fmax(0.0);
+
+                let flags = runs.first().map(|run| run.flags).unwrap_or_default();
+
+                let result = iface::HitTestResult {
+                    index: index.clone(),
+                    trailing: *trailing,
+                    flags,
+                };
+                (dist, result)
+            })
+            .min_by(|x, y| x.0.partial_cmp(&y.0).unwrap())
+            .map(|(_, result)| result)
+            .unwrap_or(iface::HitTestResult {
+                index: i..i,
+                trailing: false,
+                flags: iface::RunFlags::empty(),
+            })
+    }
+
     fn cursor_pos(&self, i: usize) -> [iface::Beam; 2] {
         let lines = ctframe_get_lines(&self.frame);
 
@@ -327,6 +379,7 @@ impl iface::TextLayout for TextLayout {
         let line_start_u16 = ctline_get_string_range(&ctline).location;
         let line_vert_bounds = self.line_vertical_bounds(line);
 
+        let line_origin_x = self.line_origins[line].x;
         let rel_i_u16 = utf16_len_of_utf8_str(&self.text.as_bytes()[line_start..i]);
 
         let offsets =
@@ -344,7 +397,7 @@ impl iface::TextLayout for TextLayout {
 
         use array::Array2;
         offsets.map(|x| iface::Beam {
-            x: x as f32,
+            x: (x + line_origin_x) as f32,
             top: line_vert_bounds.start,
             bottom: line_vert_bounds.end,
         })
@@ -433,6 +486,9 @@ impl iface::TextLayout for TextLayout {
         let line_range_u16 = ctline_get_string_range(&ctline);
         let line_start = line_range.start;
 
+        // The positions in a line are relative to the line origin
+        let line_origin_x = self.line_origins[line].x;
+
         let text = self.text.as_bytes();
 
         let start_u16 = utf16_len_of_utf8_str(&text[line_start..range.start])
@@ -533,7 +589,7 @@ impl iface::TextLayout for TextLayout {
                 out_run_metrics.push(iface::RunMetrics {
                     flags,
                     index: glyph_str_start_u8..glyph_str_end_u8,
-                    bounds: run_left as f32..run_right as f32,
+                    bounds: (run_left + line_origin_x) as f32..(run_right + line_origin_x) as f32,
                 });
                 continue;
             }
@@ -582,8 +638,8 @@ impl iface::TextLayout for TextLayout {
             let glyph_str_start_u8 = utf16_to_utf8(out_start_u16);
             let glyph_str_end_u8 = utf16_to_utf8(out_end_u16);
 
-            let mut out_run_visual_start = out_run_visual_start as f32;
-            let mut out_run_visual_end = out_run_visual_end as f32;
+            let mut out_run_visual_start = (out_run_visual_start + line_origin_x) as f32;
+            let mut out_run_visual_end = (out_run_visual_end + line_origin_x) as f32;
 
             if is_run_rtl {
                 std::mem::swap(&mut out_run_visual_start, &mut out_run_visual_end);
@@ -663,6 +719,45 @@ impl iface::TextLayout for TextLayout {
             rfind_utf16_pos_in_utf8_str(i_u16 - next_i_u16, &text[..i]).utf8_cursor
         }
     }
+
+    fn is_emoji_cluster(&self, i: Range<usize>) -> bool {
+        i.start < i.end
+            && self.next_char(i.start, true) == i.end
+            && emoji::is_emoji_cluster(&self.text[i])
+    }
+}
+
+impl TextLayout {
+    /// Find the line containing the given Y coordinate.
+    fn line_from_point_y(&self, y: f32) -> usize {
+        let lines = ctframe_get_lines(&self.frame);
+        let origins = &self.line_origins;
+
+        // See the comment in `visual_bounds`.
+        let layout_y = (self.height - y) as f64;
+
+        let get_bottom = |line_i: usize| {
+            let typo_bounds = ctline_get_typographic_bounds(&lines.get(line_i as _).unwrap());
+            let line_origin = origins[line_i];
+            line_origin.y - (typo_bounds.leading + typo_bounds.descent)
+        };
+
+        let mut base = 0;
+        let mut size = self.num_lines();
+
+        while size > 1 {
+            let half = size / 2;
+            let mid = base + half;
+            base = if layout_y < get_bottom(mid - 1) {
+                mid
+            } else {
+                base
+            };
+            size -= half;
+        }
+
+        base
+    }
 }
 
 impl iface::CanvasText<TextLayout> for BitmapBuilder {
@@ -732,6 +827,11 @@ extern "C" {
     fn CTRunGetStringIndicesPtr(run: CTRunRef) -> *const CFIndex;
 
     fn CTRunGetStringIndices(run: CTRunRef, range: CFRange, buffer: *mut CFIndex);
+
+    fn CTParagraphStyleCreate(
+        settings: *const CTParagraphStyleSetting,
+        setting_count: usize,
+    ) -> CFTypeRef;
 }
 
 type CTRunStatus = u32;
@@ -743,6 +843,23 @@ const kCTRunStatusNonMonotonic: CTRunStatus = 1 << 1;
 #[allow(non_upper_case_globals)]
 const kCTRunStatusHasNonIdentityMatrix: CTRunStatus = 1 << 2;
 
+type CTParagraphStyleSpecifier = u32;
+type CTWritingDirection = i8;
+
+#[allow(non_upper_case_globals)]
+const kCTParagraphStyleSpecifierBaseWritingDirection: CTParagraphStyleSpecifier = 13;
+#[allow(non_upper_case_globals)]
+const kCTWritingDirectionLeftToRight: CTWritingDirection = 0;
+#[allow(non_upper_case_globals)]
+const kCTWritingDirectionRightToLeft: CTWritingDirection = 1;
+
+#[repr(C)]
+struct CTParagraphStyleSetting {
+    spec: CTParagraphStyleSpecifier,
+    value_size: usize,
+    value: *const c_void,
+}
+
 fn ctfont_new_ui(ty: ct_font::CTFontUIFontType, size: f64, language: Option<&str>) -> CTFont {
     unsafe {
         let name: Option<CFString> = language.map(|s| s.into());
@@ -757,6 +874,15 @@ fn ctfont_new_ui(ty: ct_font::CTFontUIFontType, size: f64, language: Option<&str
     }
 }
 
+fn ctparagraphstyle_new_with_base_writing_direction(dir: CTWritingDirection) -> CFType {
+    let setting = CTParagraphStyleSetting {
+        spec: kCTParagraphStyleSpecifierBaseWritingDirection,
+        value_size: std::mem::size_of::<CTWritingDirection>(),
+        value: &dir as *const CTWritingDirection as *const c_void,
+    };
+    unsafe { CFType::wrap_under_create_rule(CTParagraphStyleCreate(&setting, 1)) }
+}
+
 fn ctframesetter_suggest_frame_size(
     this: &CTFramesetter,
     string_range: CFRange,
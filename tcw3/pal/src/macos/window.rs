@@ -98,8 +98,11 @@ impl HWnd {
             // Attach `WndState`
             let () = unsafe { msg_send![*this.ctrler, setListenerUserData: Rc::into_raw(state)] };
 
+            let has_position = attrs.position.is_some();
             this.set_attrs(wm, attrs);
-            let () = unsafe { msg_send![*this.ctrler, center] };
+            if !has_position {
+                let () = unsafe { msg_send![*this.ctrler, center] };
+            }
 
             this
         })
@@ -139,6 +142,16 @@ impl HWnd {
             let () = unsafe { msg_send![*self.ctrler, setContentMaxSize: max_size] };
         }
 
+        if let Some(value) = attrs.position {
+            let point = NSPoint::new(value[0] as _, value[1] as _);
+            let () = unsafe { msg_send![*self.ctrler, setFrameTopLeft: point] };
+        }
+
+        if let Some(value) = attrs.maximized {
+            let value = value as BOOL;
+            let () = unsafe { msg_send![*self.ctrler, setZoomed: value] };
+        }
+
         if let Some(value) = &attrs.caption {
             let title = IdRef::new(unsafe { NSString::alloc(nil).init_str(&**value) });
             let () = unsafe { msg_send![*self.ctrler, setTitle:*title] };
@@ -172,6 +185,11 @@ impl HWnd {
             let value = value as u32;
             let () = unsafe { msg_send![*self.ctrler, setCursorShape: value] };
         }
+
+        if let Some(value) = attrs.pointer_grab {
+            let value = value as BOOL;
+            let () = unsafe { msg_send![*self.ctrler, setPointerGrab: value] };
+        }
     }
 
     pub(super) fn remove(&self, _: Wm) {
@@ -205,6 +223,16 @@ impl HWnd {
         [size.width as u32, size.height as u32]
     }
 
+    pub(super) fn get_position(&self, _: Wm) -> [i32; 2] {
+        let point: NSPoint = unsafe { msg_send![*self.ctrler, frameTopLeft] };
+        [point.x as i32, point.y as i32]
+    }
+
+    pub(super) fn is_maximized(&self, _: Wm) -> bool {
+        let value: BOOL = unsafe { msg_send![*self.ctrler, isZoomed] };
+        value != 0
+    }
+
     pub(super) fn get_dpi_scale(&self, _: Wm) -> f32 {
         unsafe { msg_send![*self.ctrler, dpiScale] }
     }
@@ -347,6 +375,8 @@ pub struct AccelTable {
     pub key: &'static [ActionKeyBinding],
     #[doc(hidden)]
     pub sel: &'static [ActionSelBinding],
+    #[doc(hidden)]
+    pub key_bindings: Vec<iface::KeyBinding>,
 }
 
 #[doc(hidden)]
@@ -375,6 +405,25 @@ impl AccelTable {
             })
             .map(|binding| binding.action)
             .next() // get the first matching one (if any)
+            .or_else(|| self.find_action_with_key_binding(mod_flags, charcode_unmod))
+    }
+
+    fn find_action_with_key_binding(
+        &self,
+        mod_flags: u16,
+        charcode_unmod: u16,
+    ) -> Option<iface::ActionId> {
+        if self.key_bindings.is_empty() {
+            return None;
+        }
+
+        let key = key_from_charcode(charcode_unmod, mod_flags);
+        let modifiers = modifiers_from_mod_flags(mod_flags);
+
+        self.key_bindings
+            .iter()
+            .find(|binding| binding.key == key && binding.modifiers == modifiers)
+            .map(|binding| binding.action)
     }
 
     fn find_action_with_sel(&self, sel: &[u8]) -> Option<iface::ActionId> {
@@ -386,6 +435,16 @@ impl AccelTable {
     }
 }
 
+impl iface::AccelTableNew for AccelTable {
+    fn new_with_key_bindings(key_bindings: Vec<iface::KeyBinding>) -> Self {
+        Self {
+            key: &[],
+            sel: &[],
+            key_bindings,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Utility functions
 
@@ -469,6 +528,16 @@ unsafe extern "C" fn tcw_wndlistener_dpi_scale_changed(ud: TCWListenerUserData)
     });
 }
 
+#[no_mangle]
+unsafe extern "C" fn tcw_wndlistener_system_appearance_changed(ud: TCWListenerUserData) {
+    method_impl(ud, |wm, state| {
+        state
+            .listener
+            .borrow()
+            .system_appearance_changed(wm, &state.hwnd);
+    });
+}
+
 #[no_mangle]
 unsafe extern "C" fn tcw_wndlistener_focus(ud: TCWListenerUserData) {
     method_impl(ud, |wm, state| {
@@ -494,6 +563,21 @@ unsafe extern "C" fn tcw_wndlistener_mouse_motion(ud: TCWListenerUserData, loc:
     });
 }
 
+#[no_mangle]
+unsafe extern "C" fn tcw_wndlistener_mouse_relative_motion(
+    ud: TCWListenerUserData,
+    delta_x: f64,
+    delta_y: f64,
+) {
+    method_impl(ud, |wm, state| {
+        state.listener.borrow().mouse_relative_motion(
+            wm,
+            &state.hwnd,
+            [delta_x as f32, delta_y as f32].into(),
+        );
+    });
+}
+
 #[no_mangle]
 unsafe extern "C" fn tcw_wndlistener_mouse_leave(ud: TCWListenerUserData) {
     method_impl(ud, |wm, state| {
@@ -580,6 +664,28 @@ unsafe extern "C" fn tcw_wndlistener_scroll_gesture(
     .unwrap_or(std::ptr::null())
 }
 
+#[no_mangle]
+unsafe extern "C" fn tcw_wndlistener_gesture(
+    ud: TCWListenerUserData,
+    loc: NSPoint,
+) -> TCWGestureListenerUserData {
+    method_impl(ud, |wm, state| {
+        let listener = state.listener.borrow().gesture(
+            wm,
+            &state.hwnd,
+            point2_from_ns_point(loc).cast().unwrap(),
+        );
+
+        let state = GestureState {
+            listener,
+            hwnd: state.hwnd.clone(),
+        };
+
+        Box::into_raw(Box::new(state)) as *const _
+    })
+    .unwrap_or(std::ptr::null())
+}
+
 // ---------------------------------------------------------------------------
 
 struct EnumAccel<F: FnMut(&AccelTable)>(F);
@@ -599,6 +705,122 @@ impl iface::KeyEvent<AccelTable> for KeyEvent {
     fn translate_accel(&self, accel_table: &AccelTable) -> Option<iface::ActionId> {
         accel_table.find_action_with_key(self.mod_flags, self.charcode_unmod)
     }
+
+    fn key(&self) -> iface::Key {
+        key_from_charcode(self.charcode_unmod, self.mod_flags)
+    }
+
+    fn modifiers(&self) -> iface::ModifierState {
+        modifiers_from_mod_flags(self.mod_flags)
+    }
+}
+
+/// Convert `NSEventModifierFlags >> 16` to `iface::ModifierState`.
+fn modifiers_from_mod_flags(mod_flags: u16) -> iface::ModifierState {
+    let mut state = iface::ModifierState::empty();
+    let flags = [
+        (1 << 1, iface::ModifierState::SHIFT),
+        (1 << 2, iface::ModifierState::CONTROL),
+        (1 << 3, iface::ModifierState::ALT),
+        (1 << 4, iface::ModifierState::SUPER),
+    ];
+    for &(mod_flag, flag) in flags.iter() {
+        state.set(flag, mod_flags & mod_flag != 0);
+    }
+    state
+}
+
+/// Convert a character code returned by `charactersIgnoringModifiers` to
+/// `iface::Key`. Media keys aren't delivered as key events, so they are never
+/// reported.
+fn key_from_charcode(charcode: u16, mod_flags: u16) -> iface::Key {
+    use iface::Key;
+
+    // `NSNumericPadKeyMask >> 16`
+    const NUMERIC_PAD: u16 = 1 << 5;
+
+    if mod_flags & NUMERIC_PAD != 0 {
+        match charcode {
+            0x30..=0x39 => {
+                const NUMPAD_KEYS: [Key; 10] = [
+                    Key::Numpad0,
+                    Key::Numpad1,
+                    Key::Numpad2,
+                    Key::Numpad3,
+                    Key::Numpad4,
+                    Key::Numpad5,
+                    Key::Numpad6,
+                    Key::Numpad7,
+                    Key::Numpad8,
+                    Key::Numpad9,
+                ];
+                return NUMPAD_KEYS[(charcode - 0x30) as usize];
+            }
+            0x2a => return Key::NumpadMultiply,
+            0x2b => return Key::NumpadAdd,
+            0x2c => return Key::NumpadSeparator,
+            0x2d => return Key::NumpadSubtract,
+            0x2e => return Key::NumpadDecimal,
+            0x2f => return Key::NumpadDivide,
+            _ => {}
+        }
+    }
+
+    match charcode {
+        // <https://developer.apple.com/documentation/appkit/1540619-common_unicode_characters>
+        0x0003 | 0x000d => Key::Return,
+        0x0008 | 0x007f => Key::Backspace,
+        0x0009 | 0x0019 => Key::Tab,
+        0x001b => Key::Escape,
+        // <https://developer.apple.com/documentation/appkit/1535851-function-key_unicodes>
+        0xf700 => Key::Up,
+        0xf701 => Key::Down,
+        0xf702 => Key::Left,
+        0xf703 => Key::Right,
+        0xf704..=0xf71b => {
+            const F_KEYS: [Key; 24] = [
+                Key::F1,
+                Key::F2,
+                Key::F3,
+                Key::F4,
+                Key::F5,
+                Key::F6,
+                Key::F7,
+                Key::F8,
+                Key::F9,
+                Key::F10,
+                Key::F11,
+                Key::F12,
+                Key::F13,
+                Key::F14,
+                Key::F15,
+                Key::F16,
+                Key::F17,
+                Key::F18,
+                Key::F19,
+                Key::F20,
+                Key::F21,
+                Key::F22,
+                Key::F23,
+                Key::F24,
+            ];
+            F_KEYS[(charcode - 0xf704) as usize]
+        }
+        0xf727 => Key::Insert,
+        0xf728 => Key::Delete,
+        0xf729 => Key::Home,
+        0xf72b => Key::End,
+        0xf72c => Key::PageUp,
+        0xf72d => Key::PageDown,
+        // The remaining function keys
+        0xf700..=0xf8ff => Key::Unknown,
+        _ => match std::char::from_u32(charcode as u32) {
+            // `charactersIgnoringModifiers` is still affected by the Shift
+            // key
+            Some(c) if !c.is_control() => Key::Char(c.to_ascii_lowercase()),
+            _ => Key::Unknown,
+        },
+    }
 }
 
 #[no_mangle]
@@ -1502,6 +1724,7 @@ unsafe extern "C" fn tcw_mousedraglistener_mouse_down(
     ud: TCWMouseDragListenerUserData,
     loc: NSPoint,
     button: u8,
+    click_count: u32,
 ) {
     drag_method_impl(ud, |wm, state| {
         state.listener.mouse_down(
@@ -1509,6 +1732,7 @@ unsafe extern "C" fn tcw_mousedraglistener_mouse_down(
             &state.hwnd,
             point2_from_ns_point(loc).cast().unwrap(),
             button,
+            click_count,
         );
     });
 }
@@ -1598,3 +1822,58 @@ unsafe extern "C" fn tcw_scrolllistener_start_momentum_phase(ud: TCWScrollListen
         state.listener.start_momentum_phase(wm, &state.hwnd);
     });
 }
+
+// ---------------------------------------------------------------------------
+
+type TCWGestureListenerUserData = *const GestureState;
+
+struct GestureState {
+    listener: Box<dyn iface::GestureListener<Wm>>,
+    hwnd: HWnd,
+}
+
+unsafe fn gesture_method_impl<T>(
+    ud: TCWGestureListenerUserData,
+    f: impl FnOnce(Wm, &GestureState) -> T,
+) -> Option<T> {
+    if ud.is_null() {
+        return None;
+    }
+    let wm = Wm::global_unchecked();
+    Some(f(wm, &*ud))
+}
+
+#[no_mangle]
+unsafe extern "C" fn tcw_gesturelistener_release(ud: TCWGestureListenerUserData) {
+    if !ud.is_null() {
+        Box::from_raw(ud as *mut GestureState);
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn tcw_gesturelistener_cancel(ud: TCWGestureListenerUserData) {
+    gesture_method_impl(ud, |wm, state| {
+        state.listener.cancel(wm, &state.hwnd);
+    });
+}
+
+#[no_mangle]
+unsafe extern "C" fn tcw_gesturelistener_end(ud: TCWGestureListenerUserData) {
+    gesture_method_impl(ud, |wm, state| {
+        state.listener.end(wm, &state.hwnd);
+    });
+}
+
+#[no_mangle]
+unsafe extern "C" fn tcw_gesturelistener_magnify(ud: TCWGestureListenerUserData, scale: f64) {
+    gesture_method_impl(ud, |wm, state| {
+        state.listener.magnify(wm, &state.hwnd, scale as f32);
+    });
+}
+
+#[no_mangle]
+unsafe extern "C" fn tcw_gesturelistener_rotate(ud: TCWGestureListenerUserData, angle: f64) {
+    gesture_method_impl(ud, |wm, state| {
+        state.listener.rotate(wm, &state.hwnd, angle as f32);
+    });
+}
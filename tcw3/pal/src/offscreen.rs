@@ -0,0 +1,204 @@
+//! Platform-independent off-screen rendering of layer trees.
+//!
+//! This module exposes the software-based compositor used by the GTK and
+//! testing backends as a standalone renderer. It does not interact with the
+//! active backend at all, so it's available even when the native backend is
+//! in use. This makes it possible to produce identical images on every
+//! platform, e.g., for generating screenshots for documentation and golden
+//! images for tests.
+//!
+//! Layer trees are described by plain values of type [`Layer`], which uses
+//! the same [`LayerAttrs`] as the backends. Bitmaps are represented by
+//! [`Bitmap`], a premultiplied ARGB8888 image.
+//!
+//! [`LayerAttrs`]: crate::iface::LayerAttrs
+//!
+//!     use tcw3_pal::offscreen::{render_layer_tree, Layer, LayerAttrs};
+//!     use cggeom::box2;
+//!
+//!     let layer = Layer::new(LayerAttrs {
+//!         bounds: Some(box2! { min: [10.0, 10.0], max: [30.0, 20.0] }),
+//!         bg_color: Some([0.2, 0.3, 0.4, 1.0].into()),
+//!         ..Default::default()
+//!     });
+//!
+//!     let bmp = render_layer_tree(&layer, [40, 30], 2.0);
+//!     assert_eq!(bmp.size(), [80, 60]);
+//!
+use cggeom::box2;
+use std::sync::Arc;
+
+use super::{iface, swrast};
+
+/// The attributes of a [`Layer`].
+pub type LayerAttrs = iface::LayerAttrs<Bitmap, Layer>;
+
+/// A node of a layer tree to be rendered by [`render_layer_tree`].
+///
+/// The sublayers are specified by `attrs.sublayers`.
+#[derive(Debug, Clone, Default)]
+pub struct Layer {
+    pub attrs: LayerAttrs,
+}
+
+impl Layer {
+    /// Construct a `Layer` with the specified attributes.
+    pub fn new(attrs: LayerAttrs) -> Self {
+        Self { attrs }
+    }
+}
+
+impl From<LayerAttrs> for Layer {
+    fn from(attrs: LayerAttrs) -> Self {
+        Self::new(attrs)
+    }
+}
+
+/// An immutable, clonable ARGB8888 image with premultiplied alpha.
+///
+/// Each pixel is stored as a native-endian `u32` value `0xAARRGGBB`. This is
+/// the format used by `cairo::Format::ARgb32`.
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    inner: Arc<BitmapInner>,
+}
+
+#[derive(Debug)]
+struct BitmapInner {
+    data: Box<[u8]>,
+    size: [u32; 2],
+    stride: usize,
+}
+
+impl Bitmap {
+    /// Construct a `Bitmap` from pixel data.
+    ///
+    /// Panics if `stride` is less than `size[0] * 4` or is not a multiple of
+    /// `4`, or if `data` is shorter than `stride * size[1]` bytes.
+    pub fn from_argb_data(data: Box<[u8]>, size: [u32; 2], stride: usize) -> Self {
+        assert!(stride >= size[0] as usize * 4);
+        assert!(stride % 4 == 0);
+        assert!(data.len() >= stride * size[1] as usize);
+
+        Self {
+            inner: Arc::new(BitmapInner { data, size, stride }),
+        }
+    }
+
+    /// Get the dimensions of the bitmap.
+    pub fn size(&self) -> [u32; 2] {
+        self.inner.size
+    }
+
+    /// Get the pixel data.
+    pub fn data(&self) -> &[u8] {
+        &self.inner.data
+    }
+
+    /// Get the byte offset between adjacent rows.
+    pub fn stride(&self) -> usize {
+        self.inner.stride
+    }
+}
+
+impl iface::Bitmap for Bitmap {
+    fn size(&self) -> [u32; 2] {
+        self.inner.size
+    }
+}
+
+impl swrast::Bmp for Bitmap {
+    fn data(&self) -> &[u8] {
+        &self.inner.data
+    }
+
+    fn size(&self) -> [usize; 2] {
+        let size = self.inner.size;
+        [size[0] as usize, size[1] as usize]
+    }
+
+    fn stride(&self) -> usize {
+        self.inner.stride
+    }
+}
+
+/// Render a layer tree into a bitmap.
+///
+/// `size` is specified in virtual pixels, in which the layer tree's
+/// coordinates are measured. The size of the returned bitmap is `size`
+/// multiplied by `dpi_scale`.
+///
+/// The restrictions of the software-based compositor apply. For example, the
+/// physical size of the bitmap must not exceed 16384×16384.
+pub fn render_layer_tree(layer: &Layer, size: [u32; 2], dpi_scale: f32) -> Bitmap {
+    let surf_size = [
+        (size[0] as f32 * dpi_scale) as usize,
+        (size[1] as f32 * dpi_scale) as usize,
+    ];
+    let stride = surf_size[0] * 4;
+    let mut data = vec![0u8; stride * surf_size[1]].into_boxed_slice();
+
+    if surf_size[0] > 0 && surf_size[1] > 0 {
+        // Layers and windows are released altogether when `sr_scrn` is
+        // dropped, so we don't have to remove them individually
+        let mut sr_scrn = swrast::Screen::new();
+        let mut binner = swrast::Binner::new();
+
+        let sr_layer = new_sr_layer(&mut sr_scrn, layer);
+
+        let sr_wnd = sr_scrn.new_wnd();
+        sr_scrn.set_wnd_size(&sr_wnd, surf_size);
+        sr_scrn.set_wnd_dpi_scale(&sr_wnd, dpi_scale);
+        sr_scrn.set_wnd_layer(&sr_wnd, Some(sr_layer));
+        sr_scrn.update_wnd(&sr_wnd);
+
+        sr_scrn.render_wnd(
+            &sr_wnd,
+            &mut data,
+            stride,
+            box2! { min: [0, 0].into(), max: surf_size.into() },
+            &mut binner,
+        );
+    }
+
+    Bitmap::from_argb_data(data, [surf_size[0] as u32, surf_size[1] as u32], stride)
+}
+
+/// Create a layer and its sublayers in `sr_scrn` based on `layer`.
+fn new_sr_layer(sr_scrn: &mut swrast::Screen<Bitmap>, layer: &Layer) -> swrast::HLayer<Bitmap> {
+    let attrs = &layer.attrs;
+
+    let sublayers = attrs.sublayers.as_ref().map(|sublayers| {
+        sublayers
+            .iter()
+            .map(|sublayer| new_sr_layer(sr_scrn, sublayer))
+            .collect()
+    });
+
+    // `swrast` only accepts bitmaps. The frame is copied because rendering
+    // happens only once.
+    let contents = attrs.contents.clone().map(|contents| {
+        contents.and_then(|contents| match contents {
+            iface::LayerContents::Bitmap(_) => Some(contents),
+            iface::LayerContents::ExternalTexture(tex) => tex.latch().map(|frame| {
+                Bitmap::from_argb_data(frame.data().into(), frame.size(), frame.stride()).into()
+            }),
+        })
+    });
+
+    sr_scrn.new_layer(iface::LayerAttrs {
+        transform: attrs.transform,
+        contents,
+        bounds: attrs.bounds,
+        contents_center: attrs.contents_center,
+        contents_scale: attrs.contents_scale,
+        bg_color: attrs.bg_color,
+        sublayers,
+        opacity: attrs.opacity,
+        flags: attrs.flags,
+        corner_radius: attrs.corner_radius,
+        mask: attrs.mask.clone(),
+        shadow: attrs.shadow,
+        backdrop_blur_radius: attrs.backdrop_blur_radius,
+    })
+}
@@ -0,0 +1,55 @@
+//! Stores small secrets, such as access tokens, in the platform's credential
+//! store.
+//!
+//! A secret is identified by a pair of a service name (e.g., the name of the
+//! application or the server) and an account name. The functions in this
+//! module block the calling thread while the system service processes the
+//! request (and possibly prompts the user to unlock the store), so they
+//! should be called from a background thread.
+//!
+//! | Backend           | Implementation                          |
+//! | ----------------- | --------------------------------------- |
+//! | Windows           | Credential Manager (`CredWriteW`, etc.) |
+//! | GTK (Linux, etc.) | libsecret (loaded at runtime)           |
+//! | macOS             | Keychain Services                       |
+//!
+//! The native implementations are used by the testing backend as well.
+use std::io;
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+use self::windows as imp;
+
+#[cfg(target_os = "macos")]
+mod keychain;
+#[cfg(target_os = "macos")]
+use self::keychain as imp;
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod libsecret;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+use self::libsecret as imp;
+
+/// Store a secret, replacing the existing one with the same service and
+/// account names.
+pub fn store(service: &str, account: &str, secret: &str) -> io::Result<()> {
+    imp::store(service, account, secret)
+}
+
+/// Retrieve a secret stored by [`store`]. Returns `Ok(None)` if there is no
+/// such secret.
+pub fn load(service: &str, account: &str) -> io::Result<Option<String>> {
+    imp::load(service, account)
+}
+
+/// Delete a secret stored by [`store`]. Deleting a nonexistent secret is not
+/// an error.
+pub fn delete(service: &str, account: &str) -> io::Result<()> {
+    imp::delete(service, account)
+}
+
+/// Convert a secret retrieved from the store to `String`.
+fn secret_from_bytes(bytes: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
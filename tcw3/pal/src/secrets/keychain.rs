@@ -0,0 +1,171 @@
+//! The Keychain Services-based implementation of `secrets`.
+use core_foundation::{
+    base::{CFRelease, CFTypeRef, TCFType},
+    string::{CFString, CFStringRef},
+};
+use std::{
+    io,
+    os::raw::{c_char, c_void},
+    ptr::{null, null_mut},
+    slice,
+};
+
+type OSStatus = i32;
+type SecKeychainItemRef = *mut c_void;
+
+const ERR_SEC_SUCCESS: OSStatus = 0;
+const ERR_SEC_ITEM_NOT_FOUND: OSStatus = -25300;
+
+#[link(name = "Security", kind = "framework")]
+extern "C" {
+    fn SecKeychainFindGenericPassword(
+        keychain_or_array: CFTypeRef,
+        service_name_length: u32,
+        service_name: *const c_char,
+        account_name_length: u32,
+        account_name: *const c_char,
+        password_length: *mut u32,
+        password_data: *mut *mut c_void,
+        item_ref: *mut SecKeychainItemRef,
+    ) -> OSStatus;
+    fn SecKeychainAddGenericPassword(
+        keychain: CFTypeRef,
+        service_name_length: u32,
+        service_name: *const c_char,
+        account_name_length: u32,
+        account_name: *const c_char,
+        password_length: u32,
+        password_data: *const c_void,
+        item_ref: *mut SecKeychainItemRef,
+    ) -> OSStatus;
+    fn SecKeychainItemModifyAttributesAndData(
+        item_ref: SecKeychainItemRef,
+        attr_list: *const c_void,
+        length: u32,
+        data: *const c_void,
+    ) -> OSStatus;
+    fn SecKeychainItemFreeContent(attr_list: *mut c_void, data: *mut c_void) -> OSStatus;
+    fn SecKeychainItemDelete(item_ref: SecKeychainItemRef) -> OSStatus;
+    fn SecCopyErrorMessageString(status: OSStatus, reserved: *mut c_void) -> CFStringRef;
+}
+
+fn status_to_result(status: OSStatus) -> io::Result<()> {
+    if status == ERR_SEC_SUCCESS {
+        return Ok(());
+    }
+
+    let message = unsafe { SecCopyErrorMessageString(status, null_mut()) };
+    let message = if message.is_null() {
+        format!("Keychain Services error {}", status)
+    } else {
+        unsafe { CFString::wrap_under_create_rule(message) }.to_string()
+    };
+
+    Err(io::Error::new(io::ErrorKind::Other, message))
+}
+
+/// Releases a keychain item on drop.
+struct ItemGuard(SecKeychainItemRef);
+
+impl Drop for ItemGuard {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0 as CFTypeRef) };
+    }
+}
+
+/// Find the keychain item for the specified service and account names.
+/// The password data is copied to `password` if it's `Some(_)`.
+fn find(
+    service: &str,
+    account: &str,
+    password: Option<&mut Vec<u8>>,
+) -> io::Result<Option<ItemGuard>> {
+    let mut item = null_mut();
+    let mut password_length = 0;
+    let mut password_data = null_mut();
+    let want_password = password.is_some();
+
+    let status = unsafe {
+        SecKeychainFindGenericPassword(
+            null(),
+            service.len() as u32,
+            service.as_ptr() as *const c_char,
+            account.len() as u32,
+            account.as_ptr() as *const c_char,
+            if want_password {
+                &mut password_length
+            } else {
+                null_mut()
+            },
+            if want_password {
+                &mut password_data
+            } else {
+                null_mut()
+            },
+            &mut item,
+        )
+    };
+
+    if status == ERR_SEC_ITEM_NOT_FOUND {
+        return Ok(None);
+    }
+    status_to_result(status)?;
+
+    let item = ItemGuard(item);
+
+    if let Some(password) = password {
+        unsafe {
+            if password_length > 0 {
+                *password =
+                    slice::from_raw_parts(password_data as *const u8, password_length as usize)
+                        .to_vec();
+            }
+            SecKeychainItemFreeContent(null_mut(), password_data);
+        }
+    }
+
+    Ok(Some(item))
+}
+
+pub fn store(service: &str, account: &str, secret: &str) -> io::Result<()> {
+    if let Some(item) = find(service, account, None)? {
+        return status_to_result(unsafe {
+            SecKeychainItemModifyAttributesAndData(
+                item.0,
+                null(),
+                secret.len() as u32,
+                secret.as_ptr() as *const c_void,
+            )
+        });
+    }
+
+    status_to_result(unsafe {
+        SecKeychainAddGenericPassword(
+            null(),
+            service.len() as u32,
+            service.as_ptr() as *const c_char,
+            account.len() as u32,
+            account.as_ptr() as *const c_char,
+            secret.len() as u32,
+            secret.as_ptr() as *const c_void,
+            null_mut(),
+        )
+    })
+}
+
+pub fn load(service: &str, account: &str) -> io::Result<Option<String>> {
+    let mut password = Vec::new();
+    if find(service, account, Some(&mut password))?.is_none() {
+        return Ok(None);
+    }
+
+    super::secret_from_bytes(password).map(Some)
+}
+
+pub fn delete(service: &str, account: &str) -> io::Result<()> {
+    if let Some(item) = find(service, account, None)? {
+        status_to_result(unsafe { SecKeychainItemDelete(item.0) })?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,224 @@
+//! The libsecret-based implementation of `secrets`.
+//!
+//! libsecret is loaded at runtime so that it doesn't become a hard dependency
+//! of applications. The secrets are stored with a schema having two string
+//! attributes, `service` and `account`.
+use lazy_static::lazy_static;
+use std::{
+    ffi::{CStr, CString},
+    io,
+    os::raw::{c_char, c_int, c_void},
+    ptr::{null, null_mut},
+};
+
+type GError = glib_sys::GError;
+
+#[repr(C)]
+struct SecretSchemaAttribute {
+    name: *const c_char,
+    ty: c_int,
+}
+
+#[repr(C)]
+struct SecretSchema {
+    name: *const c_char,
+    flags: c_int,
+    attributes: [SecretSchemaAttribute; 32],
+    reserved: c_int,
+    reserved_ptrs: [*mut c_void; 7],
+}
+
+const SECRET_SCHEMA_NONE: c_int = 0;
+const SECRET_SCHEMA_ATTRIBUTE_STRING: c_int = 0;
+
+type StoreSyncFn = unsafe extern "C" fn(
+    *const SecretSchema,
+    *const c_char,
+    *const c_char,
+    *const c_char,
+    *mut c_void,
+    *mut *mut GError,
+    ...
+) -> c_int;
+type LookupSyncFn =
+    unsafe extern "C" fn(*const SecretSchema, *mut c_void, *mut *mut GError, ...) -> *mut c_char;
+type ClearSyncFn =
+    unsafe extern "C" fn(*const SecretSchema, *mut c_void, *mut *mut GError, ...) -> c_int;
+type PasswordFreeFn = unsafe extern "C" fn(*mut c_char);
+
+const ATTR_SERVICE: &[u8] = b"service\0";
+const ATTR_ACCOUNT: &[u8] = b"account\0";
+
+struct LibSecret {
+    schema: Box<SecretSchema>,
+    store_sync: StoreSyncFn,
+    lookup_sync: LookupSyncFn,
+    clear_sync: ClearSyncFn,
+    password_free: PasswordFreeFn,
+}
+
+// `schema` only points to static strings, and libsecret's synchronous
+// functions can be called from any thread
+unsafe impl Send for LibSecret {}
+unsafe impl Sync for LibSecret {}
+
+lazy_static! {
+    static ref LIBSECRET: Option<LibSecret> = unsafe { LibSecret::load() };
+}
+
+impl LibSecret {
+    unsafe fn load() -> Option<Self> {
+        let handle = libc::dlopen(
+            b"libsecret-1.so.0\0".as_ptr() as *const c_char,
+            libc::RTLD_NOW | libc::RTLD_LOCAL,
+        );
+        if handle.is_null() {
+            log::warn!("Could not load libsecret. Secrets will not be stored.");
+            return None;
+        }
+
+        let sym = |name: &[u8]| libc::dlsym(handle, name.as_ptr() as *const c_char);
+        let store_sync = sym(b"secret_password_store_sync\0");
+        let lookup_sync = sym(b"secret_password_lookup_sync\0");
+        let clear_sync = sym(b"secret_password_clear_sync\0");
+        let password_free = sym(b"secret_password_free\0");
+        if store_sync.is_null()
+            || lookup_sync.is_null()
+            || clear_sync.is_null()
+            || password_free.is_null()
+        {
+            log::warn!("Could not find libsecret's functions");
+            return None;
+        }
+
+        let mut schema = Box::new(SecretSchema {
+            name: b"tcw3.pal.Secret\0".as_ptr() as *const c_char,
+            flags: SECRET_SCHEMA_NONE,
+            attributes: std::mem::zeroed(),
+            reserved: 0,
+            reserved_ptrs: [null_mut(); 7],
+        });
+        schema.attributes[0] = SecretSchemaAttribute {
+            name: ATTR_SERVICE.as_ptr() as *const c_char,
+            ty: SECRET_SCHEMA_ATTRIBUTE_STRING,
+        };
+        schema.attributes[1] = SecretSchemaAttribute {
+            name: ATTR_ACCOUNT.as_ptr() as *const c_char,
+            ty: SECRET_SCHEMA_ATTRIBUTE_STRING,
+        };
+
+        Some(Self {
+            schema,
+            store_sync: std::mem::transmute(store_sync),
+            lookup_sync: std::mem::transmute(lookup_sync),
+            clear_sync: std::mem::transmute(clear_sync),
+            password_free: std::mem::transmute(password_free),
+        })
+    }
+}
+
+fn libsecret() -> io::Result<&'static LibSecret> {
+    LIBSECRET
+        .as_ref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "libsecret is not available"))
+}
+
+fn c_string(s: &str) -> io::Result<CString> {
+    CString::new(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Convert a `GError` to `io::Error`, consuming it.
+unsafe fn take_error(error: *mut GError) -> io::Error {
+    let message = CStr::from_ptr((*error).message)
+        .to_string_lossy()
+        .into_owned();
+    glib_sys::g_error_free(error);
+    io::Error::new(io::ErrorKind::Other, message)
+}
+
+pub fn store(service: &str, account: &str, secret: &str) -> io::Result<()> {
+    let lib = libsecret()?;
+    let label = c_string(&format!("{} ({})", service, account))?;
+    let (service, account, secret) = (c_string(service)?, c_string(account)?, c_string(secret)?);
+    let mut error = null_mut();
+
+    unsafe {
+        (lib.store_sync)(
+            &*lib.schema,
+            null(), // the default collection
+            label.as_ptr(),
+            secret.as_ptr(),
+            null_mut(),
+            &mut error,
+            ATTR_SERVICE.as_ptr() as *const c_char,
+            service.as_ptr(),
+            ATTR_ACCOUNT.as_ptr() as *const c_char,
+            account.as_ptr(),
+            null::<c_char>(),
+        );
+
+        if !error.is_null() {
+            return Err(take_error(error));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn load(service: &str, account: &str) -> io::Result<Option<String>> {
+    let lib = libsecret()?;
+    let (service, account) = (c_string(service)?, c_string(account)?);
+    let mut error = null_mut();
+
+    unsafe {
+        let secret = (lib.lookup_sync)(
+            &*lib.schema,
+            null_mut(),
+            &mut error,
+            ATTR_SERVICE.as_ptr() as *const c_char,
+            service.as_ptr(),
+            ATTR_ACCOUNT.as_ptr() as *const c_char,
+            account.as_ptr(),
+            null::<c_char>(),
+        );
+
+        if !error.is_null() {
+            return Err(take_error(error));
+        }
+        if secret.is_null() {
+            return Ok(None);
+        }
+
+        let bytes = CStr::from_ptr(secret).to_bytes().to_vec();
+        (lib.password_free)(secret);
+
+        super::secret_from_bytes(bytes).map(Some)
+    }
+}
+
+pub fn delete(service: &str, account: &str) -> io::Result<()> {
+    let lib = libsecret()?;
+    let (service, account) = (c_string(service)?, c_string(account)?);
+    let mut error = null_mut();
+
+    unsafe {
+        // Returns `FALSE` without setting `error` if there was no matching
+        // secret
+        (lib.clear_sync)(
+            &*lib.schema,
+            null_mut(),
+            &mut error,
+            ATTR_SERVICE.as_ptr() as *const c_char,
+            service.as_ptr(),
+            ATTR_ACCOUNT.as_ptr() as *const c_char,
+            account.as_ptr(),
+            null::<c_char>(),
+        );
+
+        if !error.is_null() {
+            return Err(take_error(error));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! The Credential Manager-based implementation of `secrets`.
+use std::{
+    ffi::OsStr,
+    io,
+    mem::zeroed,
+    os::windows::ffi::OsStrExt,
+    ptr::{null_mut, NonNull},
+    slice,
+};
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, LPBYTE},
+        winerror::ERROR_NOT_FOUND,
+    },
+    um::wincred::{
+        CredDeleteW, CredFree, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE,
+        CRED_TYPE_GENERIC, PCREDENTIALW,
+    },
+};
+
+/// Construct the target name of a credential. Credential Manager identifies
+/// credentials by their target names, so both of the names are included.
+fn target_name(service: &str, account: &str) -> Vec<u16> {
+    wstr(&format!("{}/{}", service, account))
+}
+
+fn wstr(s: &str) -> Vec<u16> {
+    OsStr::new(s).encode_wide().chain(Some(0)).collect()
+}
+
+fn is_not_found(e: &io::Error) -> bool {
+    e.raw_os_error() == Some(ERROR_NOT_FOUND as i32)
+}
+
+pub fn store(service: &str, account: &str, secret: &str) -> io::Result<()> {
+    let mut target_name = target_name(service, account);
+    let mut user_name = wstr(account);
+    let mut blob = secret.as_bytes().to_vec();
+
+    let mut cred: CREDENTIALW = unsafe { zeroed() };
+    cred.Type = CRED_TYPE_GENERIC;
+    cred.TargetName = target_name.as_mut_ptr();
+    cred.UserName = user_name.as_mut_ptr();
+    cred.CredentialBlobSize = blob.len() as DWORD;
+    cred.CredentialBlob = blob.as_mut_ptr() as LPBYTE;
+    cred.Persist = CRED_PERSIST_LOCAL_MACHINE;
+
+    if unsafe { CredWriteW(&mut cred, 0) } == FALSE {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+pub fn load(service: &str, account: &str) -> io::Result<Option<String>> {
+    let target_name = target_name(service, account);
+    let mut cred: PCREDENTIALW = null_mut();
+
+    if unsafe { CredReadW(target_name.as_ptr(), CRED_TYPE_GENERIC, 0, &mut cred) } == FALSE {
+        let e = io::Error::last_os_error();
+        return if is_not_found(&e) { Ok(None) } else { Err(e) };
+    }
+
+    let cred = CredGuard(NonNull::new(cred).unwrap());
+    let bytes = unsafe {
+        let cred = cred.0.as_ref();
+        if cred.CredentialBlobSize == 0 {
+            Vec::new()
+        } else {
+            slice::from_raw_parts(cred.CredentialBlob, cred.CredentialBlobSize as usize).to_vec()
+        }
+    };
+
+    super::secret_from_bytes(bytes).map(Some)
+}
+
+pub fn delete(service: &str, account: &str) -> io::Result<()> {
+    let target_name = target_name(service, account);
+
+    if unsafe { CredDeleteW(target_name.as_ptr(), CRED_TYPE_GENERIC, 0) } == FALSE {
+        let e = io::Error::last_os_error();
+        if !is_not_found(&e) {
+            return Err(e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Releases a credential returned by `CredReadW` on drop.
+struct CredGuard(NonNull<CREDENTIALW>);
+
+impl Drop for CredGuard {
+    fn drop(&mut self) {
+        unsafe { CredFree(self.0.as_ptr() as _) };
+    }
+}
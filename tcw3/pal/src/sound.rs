@@ -0,0 +1,31 @@
+//! Plays short sounds for user interface feedback, e.g., to notify the user
+//! of an error or the arrival of a message.
+//!
+//! This is not a general-purpose audio API. Sounds are played asynchronously
+//! by [`Wm::play_system_sound`] or [`Wm::play_sound_file`], and there is no
+//! way to control their playback once started.
+//!
+//! | Backend           | Implementation                       |
+//! | ----------------- | ------------------------------------ |
+//! | Windows           | `PlaySoundW`                         |
+//! | GTK (Linux, etc.) | libcanberra (loaded at runtime)      |
+//! | macOS             | (unsupported)                        |
+//!
+//! [`Wm::play_system_sound`]: crate::iface::Wm::play_system_sound
+//! [`Wm::play_sound_file`]: crate::iface::Wm::play_sound_file
+
+/// A sound provided by the system. The actual sound depends on the system's
+/// settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemSound {
+    /// The generic alert sound.
+    Beep,
+    /// Accompanies an informational message.
+    Information,
+    /// Accompanies a warning message.
+    Warning,
+    /// Accompanies an error message.
+    Error,
+    /// Indicates the arrival of an instant message.
+    MessageReceived,
+}
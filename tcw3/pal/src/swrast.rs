@@ -33,6 +33,7 @@ const NUM_GROUPS: usize = 32;
 
 mod binner;
 mod binrast;
+mod effects;
 mod layers;
 mod rast;
 mod utils;
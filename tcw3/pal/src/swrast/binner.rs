@@ -9,9 +9,10 @@ use itertools::iproduct;
 use std::{
     cmp::{max, min},
     ops::Range,
+    sync::Arc,
 };
 
-use super::{CLIP_SUB, NUM_GROUPS, NUM_LAYERS, TILE, UV_SUB};
+use super::{effects::Raster, CLIP_SUB, NUM_GROUPS, NUM_LAYERS, TILE, UV_SUB};
 
 /// A temporary storage for binning.
 #[derive(Debug)]
@@ -82,6 +83,31 @@ pub(super) struct Elem<TBmp> {
 
     /// Clip planes (enabled by `ElemFlags::CLIP_PLANES`).
     pub clip_planes: [ClipPlanes; 2],
+
+    /// An additional mask shape. Only used by compositing elements.
+    pub shape: Option<Box<ElemShape<TBmp>>>,
+}
+
+/// A rounded rectangle and an alpha mask, applied in addition to clip planes.
+/// See [`GroupShape`].
+///
+/// The coverage value of a corner is calculated from the distance between the
+/// pixel center and the arc, which is a good approximation as long as the
+/// radius is not much smaller than a pixel.
+#[derive(Debug)]
+pub(super) struct ElemShape<TBmp> {
+    /// Transforms window pixel coordinates to the shape's local coordinates.
+    pub inv_xform: Matrix3<f32>,
+    /// The size of the shape in the local coordinate space.
+    pub size: Vector2<f32>,
+    /// The corner radii in the local coordinate space, in the same order as
+    /// `GroupShape::corner_radius`. They are scaled down so that the arcs do
+    /// not overlap.
+    pub corner_radius: [f32; 4],
+    /// The number of pixels per local unit, used for antialiasing.
+    pub aa_scale: f32,
+    /// The alpha mask stretched over the shape.
+    pub mask: Option<TBmp>,
 }
 
 /// Clip planes (enabled by `ElemFlags::CLIP_PLANES`). Given window
@@ -170,6 +196,10 @@ pub(super) enum Content<TBmp> {
     ///
     /// The layer number `x` must be greater than `Elem::layer`.
     Layer(u8),
+
+    /// An image produced by a layer effect, placed in the render target's
+    /// pixel coordinate space.
+    Raster(Arc<Raster>),
 }
 
 bitflags! {
@@ -306,6 +336,21 @@ impl<TBmp: Bmp> Binner<TBmp> {
     }
 }
 
+/// The mask shape of a group in addition to the parallelogram given by
+/// `mask_xform`. See [`BinnerBuilder::open_group`].
+#[derive(Debug, Clone)]
+pub(super) struct GroupShape<TBmp> {
+    /// The size of the parallelogram in the local coordinate space, in which
+    /// `corner_radius` is measured.
+    pub size: Vector2<f32>,
+    /// The corner radii of the top-left, top-right, bottom-right, and
+    /// bottom-left corners.
+    pub corner_radius: [f32; 4],
+    /// An image whose alpha channel is stretched over the parallelogram and
+    /// used as a mask.
+    pub mask: Option<TBmp>,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub(super) struct ElemInfo<TBmp> {
     pub xform: Matrix3<f32>,
@@ -389,17 +434,32 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
     /// `opacity` is the opacity of the group in range `0.0..=1.0`. The opacity
     /// is applied after all elements in the group are flattened into a single
     /// image.
-    pub(super) fn open_group(&mut self, mask_xform: Option<Matrix3<f32>>, opacity: f32) {
+    ///
+    /// `shape` further restricts the mask shape to a rounded rectangle and/or
+    /// an alpha mask. It requires `mask_xform`.
+    pub(super) fn open_group(
+        &mut self,
+        mask_xform: Option<Matrix3<f32>>,
+        opacity: f32,
+        shape: Option<GroupShape<TBmp>>,
+    ) {
         // The last row of the matrix must be `[0 0 1]`.
         if let Some(xform) = mask_xform {
             debug_assert!(is_affine_xform(xform));
         }
+        debug_assert!(shape.is_none() || mask_xform.is_some());
 
         assert!(
             self.groups.len() < self.groups.capacity(),
             "Too many groups"
         );
 
+        // `Err(())` if the shape is degenerate
+        let shape = match (mask_xform, shape) {
+            (Some(xform), Some(shape)) => elem_shape(xform, shape).map(Some).ok_or(()),
+            _ => Ok(None),
+        };
+
         // Closure as a scope for `?`
         let new_scissor: Option<Box2<u16>> = (|| {
             let old_scissor = self.scissor?;
@@ -417,6 +477,11 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
             }
         })();
 
+        let (new_scissor, shape) = match shape {
+            Ok(shape) => (new_scissor, shape),
+            Err(()) => (None, None),
+        };
+
         if new_scissor.is_none() {
             // If the new scissor rectangle is empty, the group doesn't need a
             // layer.
@@ -439,8 +504,9 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
             false
         };
 
-        // f `opacity` is less than `1`, the group needs a layer.
-        let needs_layer = needs_clip_planes || opacity < 1.0;
+        // If `opacity` is less than `1` or the group has a shape, the group
+        // needs a layer.
+        let needs_layer = needs_clip_planes || opacity < 1.0 || shape.is_some();
 
         // Create a compositing `Elem` if needed
         let layer = if needs_layer {
@@ -459,6 +525,7 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                 content: Content::Layer(self.layer as u8),
                 scissor: new_scissor.unwrap(),
                 clip_planes: clip_planes.unwrap_or_default(),
+                shape: shape.map(Box::new),
             });
 
             // We don't generate compositing `Frag`s at this point. That happens
@@ -901,6 +968,7 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                         content,
                         scissor,
                         clip_planes,
+                        shape: None,
                     });
                 }
             } else if !use_slicing {
@@ -916,6 +984,7 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                     content,
                     scissor,
                     clip_planes: clip_planes.clone(),
+                    shape: None,
                 });
             } else {
                 // `slice_by_clip == false && use_slicing == true`
@@ -1012,13 +1081,16 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                         content,
                         scissor,
                         clip_planes: clip_planes.clone(),
+                        shape: None,
                     });
                 }
             } // endif slice_by_clip, !use_slicing
         }
 
         if info.bg_color[3] > 0 {
+            // Map the alpha value from `0..=255` to `0..=256`
             let mut bg_op = info.bg_color[3] as u32;
+            bg_op += bg_op / 128;
 
             if !use_proxy {
                 bg_op = (bg_op as f32 * info.opacity) as u32;
@@ -1034,52 +1106,111 @@ impl<TBmp: Bmp> BinnerBuilder<'_, TBmp> {
                 content: Content::Solid(info.bg_color.into()),
                 scissor: bb,
                 clip_planes: clip_planes.clone(),
+                shape: None,
             });
         }
 
         // -------------------------------------------------------------------
         // Generate fragments
         if use_proxy {
-            self.open_group(Some(par), info.opacity);
+            self.open_group(Some(par), info.opacity, None);
         }
 
         for elem in elems.into_iter() {
-            let elem_i = self.binner.elems.len() as u32;
-            let scissor = elem.scissor;
+            self.emit_elem(elem);
+        }
 
-            let sci_min = scissor.min.cast::<usize>().unwrap();
-            let sci_max = scissor.max.cast::<usize>().unwrap();
-            let bin_xs = sci_min.x / TILE..(sci_max.x + TILE - 1) / TILE;
-            let bin_ys = sci_min.y / TILE..(sci_max.y + TILE - 1) / TILE;
+        if use_proxy {
+            self.close_group();
+        }
+    }
 
-            for (bin_x, bin_y) in iproduct!(bin_xs, bin_ys) {
-                // TODO: Clip plane cull
-                let bin_i = bin_x + bin_y * self.binner.bin_count[0];
+    /// Insert an element displaying a `Raster`.
+    ///
+    /// `mask_xform` describes the mask shape of the element in the same way as
+    /// `open_group`. `opacity` is in range `0.0..=1.0`.
+    pub(super) fn push_raster(
+        &mut self,
+        raster: Arc<Raster>,
+        mask_xform: Option<Matrix3<f32>>,
+        opacity: f32,
+    ) {
+        let scissor = if let Some(x) = self.scissor {
+            x
+        } else {
+            return;
+        };
 
-                self.prepare_bin(bin_i);
+        let bx = raster.bx;
+        let bb = box2! {
+            min: [max(bx.min.x, 0) as f32, max(bx.min.y, 0) as f32],
+            max: [max(bx.max.x, 0) as f32, max(bx.max.y, 0) as f32],
+        };
+        let bb = if let Some(xform) = mask_xform {
+            debug_assert!(is_affine_xform(xform));
+            let unit_sq = box2! { min: [0.0, 0.0], max: [1.0, 1.0] };
+            let mask_bb = round_aabb_conservative(xform_aabb(xform, unit_sq));
+            bb.intersection(&mask_bb)
+        } else {
+            Some(bb)
+        };
 
-                let frag_i = self.binner.frags.len() as u32;
+        let scissor = if let Some(x) = bb
+            .and_then(saturating_aabb_f32_to_u16)
+            .and_then(|bb| bb.intersection(&scissor))
+        {
+            x
+        } else {
+            return;
+        };
 
-                let bin = &mut self.binner.bins[bin_i];
-                self.binner.frags.push(Frag {
-                    elem_i,
-                    next_frag_i: bin.frag_first_i,
-                    layer: self.layer,
-                });
+        self.emit_elem(Elem {
+            flags: if mask_xform.is_some() {
+                flags![ElemFlags::{CLIP_PLANES | CLIP_PLANES_ANTIALIASED}]
+            } else {
+                flags![ElemFlags::{}]
+            },
+            opacity: (opacity * 256.0) as u16,
+            content: Content::Raster(raster),
+            scissor,
+            clip_planes: mask_xform.map(xform_to_clip_planes).unwrap_or_default(),
+            shape: None,
+        });
+    }
 
-                // Link the new fragment to the front of the fragment list
-                bin.frag_first_i = frag_i;
-                if bin.frag_last_i == NONE {
-                    bin.frag_last_i = frag_i;
-                }
-            }
+    /// Add an element and its fragments to the current layer.
+    fn emit_elem(&mut self, elem: Elem<TBmp>) {
+        let elem_i = self.binner.elems.len() as u32;
+        let scissor = elem.scissor;
 
-            self.binner.elems.push(elem);
-        }
+        let sci_min = scissor.min.cast::<usize>().unwrap();
+        let sci_max = scissor.max.cast::<usize>().unwrap();
+        let bin_xs = sci_min.x / TILE..(sci_max.x + TILE - 1) / TILE;
+        let bin_ys = sci_min.y / TILE..(sci_max.y + TILE - 1) / TILE;
 
-        if use_proxy {
-            self.close_group();
+        for (bin_x, bin_y) in iproduct!(bin_xs, bin_ys) {
+            // TODO: Clip plane cull
+            let bin_i = bin_x + bin_y * self.binner.bin_count[0];
+
+            self.prepare_bin(bin_i);
+
+            let frag_i = self.binner.frags.len() as u32;
+
+            let bin = &mut self.binner.bins[bin_i];
+            self.binner.frags.push(Frag {
+                elem_i,
+                next_frag_i: bin.frag_first_i,
+                layer: self.layer,
+            });
+
+            // Link the new fragment to the front of the fragment list
+            bin.frag_first_i = frag_i;
+            if bin.frag_last_i == NONE {
+                bin.frag_last_i = frag_i;
+            }
         }
+
+        self.binner.elems.push(elem);
     }
 
     /// Prepare the specified bin for adding fragments to a layer `self.layer`.
@@ -1131,12 +1262,68 @@ fn is_affine_xform(xform: Matrix3<f32>) -> bool {
     xform.x.z == 0.0 && xform.y.z == 0.0 && xform.z.z == 1.0
 }
 
+/// Construct an `ElemShape` for a group having the mask shape `mask_xform`
+/// (see `BinnerBuilder::open_group`). Returns `None` if the shape is
+/// degenerate.
+pub(super) fn elem_shape<TBmp>(
+    mask_xform: Matrix3<f32>,
+    shape: GroupShape<TBmp>,
+) -> Option<ElemShape<TBmp>> {
+    let GroupShape {
+        size,
+        mut corner_radius,
+        mask,
+    } = shape;
+
+    if !(size.x > 0.0 && size.y > 0.0) {
+        return None;
+    }
+
+    // window pixel coordinates → `0 ≤ x, y ≤ 1` → local coordinates
+    #[rustfmt::skip]
+    let local_xform = Matrix3::new(
+        size.x, 0.0,    0.0,
+        0.0,    size.y, 0.0,
+        0.0,    0.0,    1.0,
+    );
+    let inv_xform = local_xform * mask_xform.invert()?;
+
+    // The area of the parallelogram in pixels divided by that in the local
+    // coordinate space
+    let aa_scale = (mask_xform.determinant().abs() / (size.x * size.y)).sqrt();
+
+    // Scale down the radii if the arcs on any edge overlap (as done by CSS)
+    for r in corner_radius.iter_mut() {
+        *r = r.fmax(0.0);
+    }
+    let [r0, r1, r2, r3] = corner_radius;
+    let factor = [
+        size.x / (r0 + r1),
+        size.y / (r1 + r2),
+        size.x / (r2 + r3),
+        size.y / (r3 + r0),
+    ]
+    .iter()
+    .fold(1.0f32, |x, &y| x.fmin(y));
+    for r in corner_radius.iter_mut() {
+        *r *= factor;
+    }
+
+    Some(ElemShape {
+        inv_xform,
+        size,
+        corner_radius,
+        aa_scale,
+        mask,
+    })
+}
+
 /// Transform an axis-aligned box, returning the AABB of the transformed box.
 pub(super) fn xform_aabb(xform: Matrix3<f32>, bx: Box2<f32>) -> Box2<f32> {
     parallelogram_aabb(xform_and_aabb_to_parallelogram(xform, bx))
 }
 
-fn parallelogram_aabb(xform: Matrix3<f32>) -> Box2<f32> {
+pub(super) fn parallelogram_aabb(xform: Matrix3<f32>) -> Box2<f32> {
     box2! {
         min: [
             xform.z.x + xform.x.x.fmin(0.0) + xform.y.x.fmin(0.0),
@@ -1574,7 +1761,7 @@ mod tests {
         ) {
             dbg!((xform, gr_xform, gr_op, ct_center));
             let mut builder = binner.build([200, 100]);
-            builder.open_group(gr_xform, gr_op);
+            builder.open_group(gr_xform, gr_op, None);
 
             builder.push_elem(ElemInfo {
                 xform,
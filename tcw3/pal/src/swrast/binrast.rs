@@ -1,12 +1,15 @@
 //! A bin rasterizer.
+use alt_fp::FloatOrd;
 use arrayvec::ArrayVec;
-use cgmath::vec2;
+use cgmath::{prelude::*, vec2, Point2};
 use itertools::izip;
+use packed_simd::u32x4;
 use std::cmp::{max, min};
 use zerocopy::LayoutVerified;
 
 use super::{
-    binner::{Binner, Bmp, Content, Elem, ElemFlags},
+    binner::{Binner, Bmp, Content, Elem, ElemFlags, ElemShape},
+    effects::Raster,
     CLIP_SUB, CLIP_SUB_SHIFT, NUM_LAYERS, TILE, UV_SUB, UV_SUB_SHIFT,
 };
 
@@ -119,6 +122,7 @@ impl BinRast {
                 bmp_stride: usize,
             },
             Layer(&'a mut [[u8; TILE * TILE]; 4]),
+            Raster(&'a Raster),
         }
 
         let [mut uv_origin, mut duv_dx, mut duv_dy] = [vec2(0, 0); 3];
@@ -154,11 +158,16 @@ impl BinRast {
             Content::Layer(src_layer) => {
                 RastContent::Layer(&mut rest_layers[src_layer as usize - layer - 1])
             }
+
+            Content::Raster(ref raster) => RastContent::Raster(raster),
         };
 
         // Clip planes
         let cps = elem.clip_planes.clone();
 
+        // Shape
+        let shape = elem.shape.as_ref().map(|shape| RastShape::new(shape));
+
         // TODO: Optimize
 
         // Draw pixels
@@ -204,6 +213,11 @@ impl BinRast {
                         .collect::<ArrayVec<[_; 4]>>()
                         .into_inner()
                         .unwrap(),
+
+                    RastContent::Raster(raster) => {
+                        let [c0, c1, c2, c3] = raster.get(x_g as i32, y_g as i32);
+                        [c0 as u32, c1 as u32, c2 as u32, c3 as u32]
+                    }
                 };
 
                 // Mask
@@ -233,23 +247,26 @@ impl BinRast {
                     mask = elem.opacity as u32;
                 }
 
+                if let Some(shape) = &shape {
+                    mask = mask * shape.coverage(x_g as i32, y_g as i32) / 256;
+                }
+
                 debug_assert!(mask <= 256);
 
                 // Apply the mask
-                let c = [
-                    (c[0] as u32 * mask) / 256,
-                    (c[1] as u32 * mask) / 256,
-                    (c[2] as u32 * mask) / 256,
-                    (c[3] as u32 * mask) / 256,
-                ];
+                let c = u32x4::from(c) * mask / 256;
 
                 // Map the alpha value from `0..=255` to `0..=256`
-                let alpha = c[3] + c[3] / 128;
+                let alpha = c.extract(3);
+                let alpha = alpha + alpha / 128;
 
                 // Blend over (with premultiplied alpha)
-                for (d, c) in izip!(&mut [d0, d1, d2, d3], &c) {
-                    **d = min(*c + **d as u32 * (256 - alpha) / 256, 255) as u8;
-                }
+                let d = u32x4::new(*d0 as u32, *d1 as u32, *d2 as u32, *d3 as u32);
+                let d = (c + d * (256 - alpha) / 256).min(u32x4::splat(255));
+                *d0 = d.extract(0) as u8;
+                *d1 = d.extract(1) as u8;
+                *d2 = d.extract(2) as u8;
+                *d3 = d.extract(3) as u8;
             }
         }
 
@@ -264,6 +281,81 @@ impl BinRast {
     }
 }
 
+/// `ElemShape` prepared for rasterization.
+pub(super) struct RastShape<'a, TBmp> {
+    shape: &'a ElemShape<TBmp>,
+    mask: Option<RastMask<'a>>,
+}
+
+/// The mask image's data, size, and stride (measured in pixels).
+type RastMask<'a> = (&'a [[u8; 4]], [usize; 2], usize);
+
+impl<'a, TBmp: Bmp> RastShape<'a, TBmp> {
+    pub(super) fn new(shape: &'a ElemShape<TBmp>) -> Self {
+        let mask = shape.mask.as_ref().map(|bmp| {
+            let data = LayoutVerified::new_slice_unaligned(bmp.data())
+                .unwrap()
+                .into_slice();
+            (data, bmp.size(), bmp.stride() / 4)
+        });
+        Self { shape, mask }
+    }
+
+    /// Calculate the coverage value in range `0..=256` of the pixel at the
+    /// specified window pixel coordinates. Only the corners and the alpha mask
+    /// are taken into account.
+    pub(super) fn coverage(&self, x: i32, y: i32) -> u32 {
+        let shape = self.shape;
+        let size = shape.size;
+        let p = shape
+            .inv_xform
+            .transform_point(Point2::new(x as f32 + 0.5, y as f32 + 0.5));
+
+        // Rounded corners
+        let [r0, r1, r2, r3] = shape.corner_radius;
+        let corners = [
+            (r0, Point2::new(r0, r0)),
+            (r1, Point2::new(size.x - r1, r1)),
+            (r2, Point2::new(size.x - r2, size.y - r2)),
+            (r3, Point2::new(r3, size.y - r3)),
+        ];
+        let mut coverage = 1.0f32;
+        for (i, &(r, center)) in corners.iter().enumerate() {
+            // Is `p` in the corner's quadrant?
+            let outside_x = if i == 0 || i == 3 {
+                p.x < center.x
+            } else {
+                p.x > center.x
+            };
+            let outside_y = if i < 2 {
+                p.y < center.y
+            } else {
+                p.y > center.y
+            };
+            if r > 0.0 && outside_x && outside_y {
+                let d = (p - center).magnitude();
+                coverage *= ((r - d) * shape.aa_scale + 0.5).fmax(0.0).fmin(1.0);
+            }
+        }
+        let mut coverage = (coverage * 256.0) as u32;
+
+        // Alpha mask
+        if let Some((data, bmp_size, stride)) = self.mask {
+            if bmp_size[0] == 0 || bmp_size[1] == 0 {
+                return 0;
+            }
+            let uv = [
+                ((p.x / size.x * bmp_size[0] as f32 - 0.5) * UV_SUB as f32) as i32,
+                ((p.y / size.y * bmp_size[1] as f32 - 0.5) * UV_SUB as f32) as i32,
+            ];
+            let alpha = sample_bilinear(data, bmp_size, stride, uv)[3];
+            coverage = coverage * (alpha + alpha / 128) / 256;
+        }
+
+        coverage
+    }
+}
+
 /// Integrate `step(x)` over `x..x + CLIP_SUB`.
 fn integrate_step(x: i32) -> i32 {
     max(x + CLIP_SUB, 0) - max(x, 0)
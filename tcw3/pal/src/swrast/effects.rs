@@ -0,0 +1,299 @@
+//! Layer effects: drop shadows (`LayerAttrs::shadow`) and backdrop blur
+//! (`LayerAttrs::backdrop_blur_radius`).
+//!
+//! Both effects produce a [`Raster`], which is displayed by an element having
+//! `Content::Raster`.
+use alt_fp::FloatOrd;
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{prelude::*, Matrix3, Point2};
+use std::fmt;
+
+use super::super::iface;
+use super::{
+    binner::{
+        elem_shape, parallelogram_aabb, round_aabb_conservative, xform_and_aabb_to_parallelogram,
+        Bmp, GroupShape,
+    },
+    binrast::RastShape,
+};
+
+/// A premultiplied BGRA image placed in a render target's pixel coordinate
+/// space.
+pub(super) struct Raster {
+    /// The region covered by the image. It may extend beyond the render
+    /// target.
+    pub bx: Box2<i32>,
+    /// The pixels of the image in the row-major order. The stride is
+    /// `bx.size().x`.
+    pub data: Vec<[u8; 4]>,
+}
+
+impl fmt::Debug for Raster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Raster").field("bx", &self.bx).finish()
+    }
+}
+
+impl Raster {
+    /// Get the pixel at the specified render target pixel coordinates.
+    /// Returns a transparent pixel if they are outside `bx`.
+    pub fn get(&self, x: i32, y: i32) -> [u8; 4] {
+        let bx = self.bx;
+        if x < bx.min.x || y < bx.min.y || x >= bx.max.x || y >= bx.max.y {
+            [0; 4]
+        } else {
+            let stride = (bx.max.x - bx.min.x) as usize;
+            self.data[(x - bx.min.x) as usize + (y - bx.min.y) as usize * stride]
+        }
+    }
+}
+
+/// Calculate the standard deviation of a Gaussian blur in pixels from a blur
+/// radius measured in a layer's local coordinate space, which is transformed
+/// to pixels by `xform`.
+pub(super) fn blur_sigma(xform: Matrix3<f32>, radius: f32) -> f32 {
+    let scale = (xform.x.x * xform.y.y - xform.x.y * xform.y.x).abs().sqrt();
+    radius.fmax(0.0) * 0.5 * scale
+}
+
+/// Get the number of pixels by which a Gaussian blur spreads an image.
+pub(super) fn blur_margin(sigma: f32) -> i32 {
+    (sigma * 3.0).ceil() as i32
+}
+
+/// Expand a box by the specified number of pixels in every direction.
+pub(super) fn expand_box(bx: Box2<i32>, margin: i32) -> Box2<i32> {
+    box2! {
+        min: [bx.min.x - margin, bx.min.y - margin],
+        max: [bx.max.x + margin, bx.max.y + margin],
+    }
+}
+
+/// Render the drop shadow of a layer. Only the portion inside `clip` is
+/// rendered.
+///
+/// `xform` transforms the layer's local coordinates to the render target's
+/// pixel coordinates.
+pub(super) fn render_shadow<TBmp: Bmp>(
+    xform: Matrix3<f32>,
+    bounds: Box2<f32>,
+    corner_radius: [f32; 4],
+    shadow: &iface::LayerShadow,
+    clip: Box2<i32>,
+) -> Option<Raster> {
+    if shadow.color.a <= 0.0 {
+        return None;
+    }
+
+    let bounds = bounds.translate(shadow.offset);
+    let par = xform_and_aabb_to_parallelogram(xform, bounds);
+    let shape = elem_shape::<TBmp>(
+        par,
+        GroupShape {
+            size: bounds.size(),
+            corner_radius,
+            mask: None,
+        },
+    )?;
+
+    let sigma = blur_sigma(xform, shadow.radius);
+    let margin = blur_margin(sigma);
+
+    // The region affected by the shadow
+    let bb = round_aabb_conservative(parallelogram_aabb(par));
+    let bb = box2! {
+        min: [bb.min.x.fmax(-65536.0) as i32, bb.min.y.fmax(-65536.0) as i32],
+        max: [bb.max.x.fmin(65536.0) as i32, bb.max.y.fmin(65536.0) as i32],
+    };
+    let out_bx = expand_box(bb, margin).intersection(&clip)?;
+
+    // Calculate the coverage of the shape in `out_bx` and the region that
+    // can affect `out_bx`
+    let src_bx = expand_box(out_bx, margin).intersection(&bb)?;
+    let src_bx = src_bx.union(&out_bx);
+    let src_size = src_bx.size().cast::<usize>().unwrap();
+    let rast_shape = RastShape::new(&shape);
+
+    let mut plane = Vec::with_capacity(src_size.x * src_size.y);
+    for y in src_bx.min.y..src_bx.max.y {
+        for x in src_bx.min.x..src_bx.max.x {
+            // `RastShape` handles the corners. Handle the edges here.
+            let p = shape
+                .inv_xform
+                .transform_point(Point2::new(x as f32 + 0.5, y as f32 + 0.5));
+            let edge = |d: f32| (d * shape.aa_scale + 0.5).fmax(0.0).fmin(1.0);
+            let coverage = edge(p.x.fmin(shape.size.x - p.x))
+                * edge(p.y.fmin(shape.size.y - p.y))
+                * (rast_shape.coverage(x, y) as f32 / 256.0);
+            plane.push(coverage);
+        }
+    }
+
+    gaussian_blur(&mut plane, src_size.into(), sigma, false);
+
+    // Colorize and crop the result
+    let color = shadow.color;
+    let to_u8 = |x: f32| (x.fmax(0.0).fmin(1.0) * 255.0 + 0.5) as u8;
+    let data = crop_rows(src_bx, out_bx)
+        .flat_map(|range| plane[range].iter())
+        .map(|&coverage| {
+            let a = color.a * coverage;
+            [
+                to_u8(color.b * a),
+                to_u8(color.g * a),
+                to_u8(color.r * a),
+                to_u8(a),
+            ]
+        })
+        .collect();
+
+    Some(Raster { bx: out_bx, data })
+}
+
+/// Apply a Gaussian blur with the standard deviation `sigma` (measured in
+/// pixels) to `src`, and return the portion inside `out_bx`, which must be
+/// a subset of `src.bx`. The pixels outside `src` are assumed to have the same
+/// values as the nearest edge pixels.
+pub(super) fn blur_raster(src: &Raster, sigma: f32, out_bx: Box2<i32>) -> Raster {
+    let size = src.bx.size().cast::<usize>().unwrap();
+
+    let mut planes: Vec<Vec<f32>> = (0..4)
+        .map(|i| src.data.iter().map(|px| px[i] as f32).collect())
+        .collect();
+
+    // Pixels outside the window are unknown, so extend the edges
+    for plane in planes.iter_mut() {
+        gaussian_blur(plane, size.into(), sigma, true);
+    }
+
+    let to_u8 = |x: f32| (x.fmax(0.0).fmin(255.0) + 0.5) as u8;
+    let data = crop_rows(src.bx, out_bx)
+        .flatten()
+        .map(|i| {
+            [
+                to_u8(planes[0][i]),
+                to_u8(planes[1][i]),
+                to_u8(planes[2][i]),
+                to_u8(planes[3][i]),
+            ]
+        })
+        .collect();
+
+    Raster { bx: out_bx, data }
+}
+
+/// Enumerate the index ranges of the rows of `inner` in an image covering
+/// `outer`.
+fn crop_rows(outer: Box2<i32>, inner: Box2<i32>) -> impl Iterator<Item = std::ops::Range<usize>> {
+    debug_assert!(outer.contains_box(&inner));
+
+    let stride = (outer.max.x - outer.min.x) as usize;
+    let x_range = (inner.min.x - outer.min.x) as usize..(inner.max.x - outer.min.x) as usize;
+
+    (inner.min.y - outer.min.y..inner.max.y - outer.min.y).map(move |y| {
+        let start = y as usize * stride;
+        start + x_range.start..start + x_range.end
+    })
+}
+
+/// Apply a Gaussian blur with the standard deviation `sigma` (measured in
+/// pixels) to a single-channel image. Pixels outside the image are assumed to
+/// be zero or, if `clamp` is `true`, the same as the nearest edge pixels.
+fn gaussian_blur(data: &mut [f32], size: [usize; 2], sigma: f32, clamp: bool) {
+    let [w, h] = size;
+    debug_assert_eq!(data.len(), w * h);
+
+    // A narrow kernel doesn't have a visible effect
+    if sigma <= 0.1 {
+        return;
+    }
+
+    let radius = blur_margin(sigma) as usize;
+    let mut kernel: Vec<f32> = (0..=radius * 2)
+        .map(|i| {
+            let x = i as f32 - radius as f32;
+            (-x * x / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let sum: f32 = kernel.iter().sum();
+    for x in kernel.iter_mut() {
+        *x /= sum;
+    }
+
+    // The blur is separable - blur the rows and then the columns
+    let mut tmp = vec![0.0; data.len()];
+    blur_lines(data, &mut tmp, w, h, [1, w], &kernel, clamp);
+    blur_lines(&tmp, data, h, w, [w, 1], &kernel, clamp);
+}
+
+/// Convolve `count` lines of `len` elements with `kernel`. `strides` specifies
+/// the distance between adjacent elements in a line and that between adjacent
+/// lines. See `gaussian_blur` for `clamp`.
+fn blur_lines(
+    src: &[f32],
+    dst: &mut [f32],
+    len: usize,
+    count: usize,
+    strides: [usize; 2],
+    kernel: &[f32],
+    clamp: bool,
+) {
+    let radius = kernel.len() / 2;
+
+    for line in 0..count {
+        let base = line * strides[1];
+        let get = |j: usize| src[base + j * strides[0]];
+
+        for i in 0..len {
+            // The kernel's range clipped by the line's ends
+            let start = i.saturating_sub(radius);
+            let end = (i + radius + 1).min(len);
+
+            let mut sum: f32 = (start..end).map(|j| get(j) * kernel[j + radius - i]).sum();
+
+            if clamp {
+                // Add the weights of the clipped parts to the edge pixels
+                let weight_before: f32 = kernel[..start + radius - i].iter().sum();
+                let weight_after: f32 = kernel[end + radius - i..].iter().sum();
+                sum += get(0) * weight_before + get(len - 1) * weight_after;
+            }
+
+            dst[base + i * strides[0]] = sum;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_preserves_sum() {
+        let size = [31, 31];
+        let mut data = vec![0.0; size[0] * size[1]];
+        data[15 + 15 * size[0]] = 1.0;
+
+        gaussian_blur(&mut data, size, 2.0, false);
+
+        let sum: f32 = data.iter().sum();
+        assert!((sum - 1.0).abs() < 1.0e-3, "{}", sum);
+
+        // The result is symmetric and peaks at the center
+        let center = data[15 + 15 * size[0]];
+        assert!(data.iter().all(|&x| x <= center));
+        assert!((data[14 + 15 * size[0]] - data[16 + 15 * size[0]]).abs() < 1.0e-6);
+        assert!((data[15 + 14 * size[0]] - data[15 + 16 * size[0]]).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn blur_clamp_preserves_constant() {
+        let size = [7, 5];
+        let mut data = vec![0.5; size[0] * size[1]];
+
+        gaussian_blur(&mut data, size, 3.0, true);
+
+        for &x in data.iter() {
+            assert!((x - 0.5).abs() < 1.0e-4, "{}", x);
+        }
+    }
+}
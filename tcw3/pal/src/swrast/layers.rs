@@ -14,15 +14,16 @@ use bitflags::bitflags;
 use cggeom::{box2, prelude::*, Box2};
 use cgmath::{prelude::*, Matrix3, Vector2};
 use leakypool::{LeakyPool, PoolPtr};
-use std::fmt;
+use std::{cell::Cell, fmt, sync::Arc};
 
 use super::super::iface;
 
 use super::{
     binner::{
         round_aabb_conservative, xform_aabb, xform_and_aabb_to_parallelogram, Binner,
-        BinnerBuilder, Bmp, ElemInfo,
+        BinnerBuilder, Bmp, ElemInfo, GroupShape,
     },
+    effects::{blur_margin, blur_raster, blur_sigma, expand_box, render_shadow, Raster},
     rast::rasterize,
     utils::Box2UsizeUnion,
 };
@@ -133,6 +134,10 @@ struct Layer<TBmp: 'static> {
     /// Valid only if `MASK_TO_BOUNDS` is set.
     bbox_mask: Option<Box2<usize>>,
 
+    /// The union of the regions whose changes affect the blurred backdrop of
+    /// this layer or its descendants (see `LayerAttrs::backdrop_blur_radius`).
+    bbox_backdrop: Option<Box2<usize>>,
+
     // ----------- The following fields are only used during an update -----------
     sublayers_i: usize,
     dirty_rect: Option<Box2<usize>>,
@@ -179,6 +184,10 @@ bitflags! {
         /// contents (`LayerDirtyFlags::CONTENT`), opacity (`LayerDirtyFlags::OPACITY`)
         /// and/or sublayers (`Layer::new_sublayers`).
         const DESCENDANT = 1 << 2;
+
+        /// Whether the layer masks the sublayers or not has uncommited
+        /// changes (see `LayerAttrs::masks_sublayers`).
+        const MASKING = 1 << 3;
     }
 }
 
@@ -192,6 +201,10 @@ struct LayerAttrs<TBmp> {
     bg_color: iface::RGBAF32,
     opacity: f32,
     flags: iface::LayerFlags,
+    corner_radius: [f32; 4],
+    mask: Option<TBmp>,
+    shadow: Option<iface::LayerShadow>,
+    backdrop_blur_radius: f32,
 }
 
 impl<TBmp> Default for LayerAttrs<TBmp> {
@@ -205,6 +218,10 @@ impl<TBmp> Default for LayerAttrs<TBmp> {
             bg_color: [0.0; 4].into(),
             opacity: 1.0,
             flags: iface::LayerFlags::empty(),
+            corner_radius: [0.0; 4],
+            mask: None,
+            shadow: None,
+            backdrop_blur_radius: 0.0,
         }
     }
 }
@@ -215,7 +232,12 @@ impl<TBmp> LayerAttrs<TBmp> {
             self.transform = x;
         }
         if let Some(x) = attrs.contents {
-            self.contents = x;
+            self.contents = x.map(|contents| match contents {
+                iface::LayerContents::Bitmap(bmp) => bmp,
+                iface::LayerContents::ExternalTexture(_) => {
+                    panic!("`ExternalTexture` must be latched into a bitmap by the backend")
+                }
+            });
         }
         if let Some(x) = attrs.bounds {
             self.bounds = x;
@@ -235,6 +257,53 @@ impl<TBmp> LayerAttrs<TBmp> {
         if let Some(x) = attrs.flags {
             self.flags = x;
         }
+        if let Some(x) = attrs.corner_radius {
+            self.corner_radius = x;
+        }
+        if let Some(x) = attrs.mask {
+            self.mask = x;
+        }
+        if let Some(x) = attrs.shadow {
+            self.shadow = x;
+        }
+        if let Some(x) = attrs.backdrop_blur_radius {
+            self.backdrop_blur_radius = x;
+        }
+    }
+
+    /// Get the drop shadow of the layer if it's visible.
+    fn visible_shadow(&self) -> Option<&iface::LayerShadow> {
+        self.shadow.as_ref().filter(|shadow| shadow.color.a > 0.0)
+    }
+
+    /// Get a flag indicating whether the contents behind the layer are
+    /// blurred.
+    fn has_backdrop_blur(&self) -> bool {
+        self.backdrop_blur_radius > 0.0
+    }
+
+    /// Get the shape of the layer if it's not a plain rectangle.
+    fn shape(&self) -> Option<GroupShape<TBmp>>
+    where
+        TBmp: Clone,
+    {
+        if self.corner_radius.iter().any(|&r| r > 0.0) || self.mask.is_some() {
+            Some(GroupShape {
+                size: self.bounds.size(),
+                corner_radius: self.corner_radius,
+                mask: self.mask.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Get a flag indicating whether the sublayers are clipped by the layer's
+    /// bounds or shape.
+    fn masks_sublayers(&self) -> bool {
+        self.flags.contains(iface::LayerFlags::MASK_TO_BOUNDS)
+            || self.corner_radius.iter().any(|&r| r > 0.0)
+            || self.mask.is_some()
     }
 }
 
@@ -322,6 +391,7 @@ impl<TBmp: Bmp> Screen<TBmp> {
             bbox_content: None,
             bbox_sublayers: None,
             bbox_mask: None,
+            bbox_backdrop: None,
             old_bbox: None,
             new_sublayers: None,
             sublayers: Vec::new(),
@@ -396,12 +466,20 @@ impl<TBmp: Bmp> Screen<TBmp> {
             | attrs.contents_scale.is_some()
             | attrs.bg_color.is_some()
             | attrs.opacity.is_some()
-            | attrs.flags.is_some();
+            | attrs.flags.is_some()
+            | attrs.corner_radius.is_some()
+            | attrs.mask.is_some()
+            | attrs.shadow.is_some()
+            | attrs.backdrop_blur_radius.is_some();
 
         let opacity_modified = attrs.opacity.is_some();
 
+        let mut masking_modified = false;
+
         if content_modified {
+            let old_masks_sublayers = layer.attrs.masks_sublayers();
             layer.attrs.assign(attrs);
+            masking_modified = layer.attrs.masks_sublayers() != old_masks_sublayers;
         }
 
         // Update dirty flags
@@ -411,6 +489,9 @@ impl<TBmp: Bmp> Screen<TBmp> {
         if opacity_modified {
             layer.dirty |= LayerDirtyFlags::OPACITY;
         }
+        if masking_modified {
+            layer.dirty |= LayerDirtyFlags::MASKING;
+        }
         if descendant_dirty {
             layer.dirty |= LayerDirtyFlags::DESCENDANT;
         }
@@ -470,6 +551,17 @@ impl<TBmp: Bmp> Screen<TBmp> {
     /// Calculate the portion of a window which has been updated since the last
     /// time `update_wnd` was called.
     pub fn update_wnd(&mut self, hwnd: &HWnd<TBmp>) -> Option<Box2<usize>> {
+        self.update_wnd_with_damage(hwnd, None)
+    }
+
+    /// Like `update_wnd`, but limits the returned region to `damage`
+    /// (measured in points) if it's specified. See
+    /// `iface::Wm::update_wnd_with_damage`.
+    pub fn update_wnd_with_damage(
+        &mut self,
+        hwnd: &HWnd<TBmp>,
+        damage: Option<Box2<f32>>,
+    ) -> Option<Box2<usize>> {
         let wnd = &mut self.wnds[hwnd.ptr];
         let root = wnd.root.clone();
         let ctx = UpdateCtx {
@@ -480,8 +572,8 @@ impl<TBmp: Bmp> Screen<TBmp> {
 
         let mut dirty_region = None;
 
-        if let Some(hlayer) = root {
-            self.update_layer(&hlayer, &ctx);
+        if let Some(hlayer) = &root {
+            self.update_layer(hlayer, &ctx);
 
             let layer = &self.layers[hlayer.ptr];
             dirty_region = layer.dirty_rect;
@@ -491,6 +583,32 @@ impl<TBmp: Bmp> Screen<TBmp> {
         if wnd.dirty {
             wnd.dirty = false;
             dirty_region = Some(box2! { min: [0, 0].into(), max: wnd.size.into() });
+        } else if let (Some(region), Some(damage)) = (dirty_region, damage) {
+            // Convert `damage` to pixels. Expand it by one pixel because
+            // bilinear filtering spreads a change to adjacent pixels.
+            let scale = wnd.dpi_scale;
+            let to_px = |x: f32| x.max(0.0) as usize;
+            let damage_px = box2! {
+                min: [
+                    to_px((damage.min.x * scale).floor() - 1.0),
+                    to_px((damage.min.y * scale).floor() - 1.0),
+                ],
+                max: [
+                    to_px((damage.max.x * scale).ceil() + 1.0),
+                    to_px((damage.max.y * scale).ceil() + 1.0),
+                ],
+            };
+            dirty_region = region.intersection(&damage_px);
+        }
+
+        // A change behind a layer with a blurred backdrop spreads across the
+        // layer
+        if let (Some(region), Some(hlayer)) = (dirty_region, &root) {
+            if let Some(bbox_backdrop) = self.layers[hlayer.ptr].bbox_backdrop {
+                if region.intersection(&bbox_backdrop).is_some() {
+                    dirty_region = Some(region.union(&bbox_backdrop));
+                }
+            }
         }
 
         dirty_region
@@ -617,6 +735,23 @@ impl<TBmp: Bmp> Screen<TBmp> {
             sublayers_change = None;
         }
 
+        let should_check_content = self.layers[hlayer.ptr]
+            .dirty
+            .contains(LayerDirtyFlags::CONTENT)
+            | ctx.full_update;
+
+        // Collect the sublayers' `bbox_backdrop` while `self.layers` isn't
+        // mutably borrowed
+        let bbox_backdrop_sublayers: Box2UsizeUnion =
+            if should_check_content | should_check_sublayers {
+                let layer = &self.layers[hlayer.ptr];
+                (layer.sublayers.iter())
+                    .map(|hlayer| self.layers[hlayer.ptr].bbox_backdrop)
+                    .collect()
+            } else {
+                Box2UsizeUnion::new()
+            };
+
         // Borrow again
         let layer = &mut self.layers[hlayer.ptr];
 
@@ -673,24 +808,27 @@ impl<TBmp: Bmp> Screen<TBmp> {
             dirty_sublayers = None;
         }
 
-        let should_check_content = layer.dirty.contains(LayerDirtyFlags::CONTENT) | ctx.full_update;
-
         if should_check_content {
             let tx = scale_mat3(layer.attrs.transform, ctx.dpi_scale);
-            let bx = xform_aabb(tx, layer.attrs.bounds);
-            let bx = round_aabb_conservative(bx);
-            let size = ctx.wnd_size_f32;
-            let bx = box2! {
-                min: [bx.min.x.fmax(0.0) as usize, bx.min.y.fmax(0.0) as usize],
-                max: [bx.max.x.fmin(size[0]) as usize, bx.max.y.fmin(size[1]) as usize],
-            };
-            let bx = if bx.is_empty() { None } else { Some(bx) };
+            let bx = ctx.wnd_bbox(xform_aabb(tx, layer.attrs.bounds));
 
             // Does this layer has a content? But even if it doesn't, `bx` is
             // used for sublayer masking.
-            let has_content = layer.attrs.contents.is_some() || layer.attrs.bg_color.a > 0.0;
+            let has_content = layer.attrs.contents.is_some()
+                || layer.attrs.bg_color.a > 0.0
+                || layer.attrs.has_backdrop_blur();
+
+            // The drop shadow extends beyond the layer's bounds
+            let bx_shadow = layer.attrs.visible_shadow().and_then(|shadow| {
+                let bx = xform_aabb(tx, layer.attrs.bounds.translate(shadow.offset));
+                let margin = blur_margin(blur_sigma(tx, shadow.radius)) as f32;
+                ctx.wnd_bbox(box2! {
+                    min: [bx.min.x - margin, bx.min.y - margin],
+                    max: [bx.max.x + margin, bx.max.y + margin],
+                })
+            });
 
-            let new_bbox_content = bx.filter(|_| has_content);
+            let new_bbox_content = bbox2_union(bx.filter(|_| has_content), bx_shadow);
             let new_bbox_mask = bx;
 
             let dirty_content = bbox2_union(new_bbox_content, layer.bbox_content);
@@ -704,16 +842,22 @@ impl<TBmp: Bmp> Screen<TBmp> {
             dirty_mask = None;
         }
 
-        let mask_to_bounds = (layer.attrs.flags).contains(iface::LayerFlags::MASK_TO_BOUNDS);
+        // `MASK_TO_BOUNDS` or a non-rectangular shape
+        let mask_to_bounds = layer.attrs.masks_sublayers();
 
         if mask_to_bounds {
             uni_dirty_rect.insert(bbox2_intersect(dirty_sublayers, layer.bbox_mask));
-            uni_dirty_rect.insert(bbox2_intersect(layer.bbox_mask, dirty_sublayers));
+            uni_dirty_rect.insert(bbox2_intersect(layer.bbox_sublayers, dirty_mask));
             uni_dirty_rect.insert(bbox2_intersect(dirty_mask, dirty_sublayers));
         } else {
             uni_dirty_rect.insert(dirty_sublayers);
         }
 
+        if layer.dirty.contains(LayerDirtyFlags::MASKING) {
+            // The sublayers outside the mask appeared or disappeared
+            uni_dirty_rect.insert(layer.bbox_sublayers);
+        }
+
         let dirty_opacity = layer.dirty.contains(LayerDirtyFlags::OPACITY);
 
         if dirty_opacity {
@@ -740,6 +884,23 @@ impl<TBmp: Bmp> Screen<TBmp> {
                 }
                 uni.into_box2()
             };
+
+            layer.bbox_backdrop = {
+                let mut uni = bbox_backdrop_sublayers;
+                if layer.attrs.has_backdrop_blur() {
+                    // The blurred backdrop depends on the pixels within the
+                    // blur kernel's reach
+                    let tx = scale_mat3(layer.attrs.transform, ctx.dpi_scale);
+                    let margin = blur_margin(blur_sigma(tx, layer.attrs.backdrop_blur_radius));
+                    let bx = xform_aabb(tx, layer.attrs.bounds);
+                    let margin = margin as f32;
+                    uni.insert(ctx.wnd_bbox(box2! {
+                        min: [bx.min.x - margin, bx.min.y - margin],
+                        max: [bx.max.x + margin, bx.max.y + margin],
+                    }));
+                }
+                uni.into_box2()
+            };
         }
     }
 }
@@ -760,6 +921,24 @@ struct UpdateCtx {
     full_update: bool,
 }
 
+impl UpdateCtx {
+    /// Round a box in window pixel coordinates outwards and clip it by the
+    /// window's bounds.
+    fn wnd_bbox(&self, bx: Box2<f32>) -> Option<Box2<usize>> {
+        let bx = round_aabb_conservative(bx);
+        let size = self.wnd_size_f32;
+        let bx = box2! {
+            min: [bx.min.x.fmax(0.0) as usize, bx.min.y.fmax(0.0) as usize],
+            max: [bx.max.x.fmin(size[0]) as usize, bx.max.y.fmin(size[1]) as usize],
+        };
+        if bx.is_empty() {
+            None
+        } else {
+            Some(bx)
+        }
+    }
+}
+
 impl<TBmp: Bmp> Screen<TBmp> {
     /// Render the content of a window to the specified image buffer.
     /// The rendered area is limited to `rect`.
@@ -772,6 +951,9 @@ impl<TBmp: Bmp> Screen<TBmp> {
     /// `out_stride * (size[1] - 1) + size[0] * 4`.
     ///
     /// `binner` is used as a temporary storage.
+    ///
+    /// Binning is done on the calling thread, but rows of tiles are
+    /// rasterized in parallel on the rayon global thread pool.
     pub fn render_wnd(
         &mut self,
         hwnd: &HWnd<TBmp>,
@@ -789,8 +971,13 @@ impl<TBmp: Bmp> Screen<TBmp> {
             let ctx = RenderCtx {
                 dpi_scale: wnd.dpi_scale,
                 offset: [bx.min.x as f32, bx.min.y as f32].into(),
+                target_size: bx.size().into(),
+                wnd_size: wnd.size,
+                root: &self.layers[root.ptr],
+                backdrop_of: None,
+                backdrop_found: Cell::new(false),
             };
-            self.binner_build_layer(&mut builder, &ctx, &self.layers[root.ptr]);
+            self.binner_build_layer(&mut builder, &ctx, ctx.root);
         }
         builder.finish();
 
@@ -800,40 +987,69 @@ impl<TBmp: Bmp> Screen<TBmp> {
     fn binner_build_layer(
         &self,
         builder: &mut BinnerBuilder<'_, TBmp>,
-        ctx: &RenderCtx,
+        ctx: &RenderCtx<'_, TBmp>,
         layer: &Layer<TBmp>,
     ) {
+        if let Some(backdrop_of) = ctx.backdrop_of {
+            if std::ptr::eq(layer, backdrop_of) {
+                // Everything after this point is behind `backdrop_of`
+                ctx.backdrop_found.set(true);
+                return;
+            }
+        }
+
         // If the layer has both of a content and sublayers, and it's translucent,
         // then we have to create an outer group for group opacity effect.
+        // If the layer has a non-rectangular shape, the outer group is masked
+        // by the shape.
         // TODO: Actually, `push_elem` creates an implicit group under a variety of
         //       situations. This could be avoided if `layer` has `MASK_TO_BOUNDS`,
         //       i.e., sublayers are masked by this layer's bounds.
         let attrs = &layer.attrs;
         let has_sublayers = layer.sublayers.len() > 0;
-        let has_content = attrs.bg_color.a > 0.0 || attrs.contents.is_some();
+        let has_image = attrs.bg_color.a > 0.0 || attrs.contents.is_some();
+        let has_backdrop_blur = attrs.has_backdrop_blur();
+        let has_content = has_image || has_backdrop_blur;
+        let shape = attrs.shape();
+        let has_shape = shape.is_some();
+        let shadow = attrs.visible_shadow();
 
-        let use_opacity_group = has_sublayers && has_content && attrs.opacity < 1.0;
+        // The shadow isn't masked by the shape, so it needs its own group for
+        // group opacity effect.
+        let use_shadow_group = shadow.is_some() && attrs.opacity < 1.0;
 
-        let inner_opacity = if use_opacity_group {
-            1.0
-        } else {
-            attrs.opacity
-        };
+        let opacity = if use_shadow_group { 1.0 } else { attrs.opacity };
 
-        if use_opacity_group {
-            builder.open_group(None, attrs.opacity);
-        }
+        let use_outer_group = has_shape || has_sublayers && has_content && opacity < 1.0;
+
+        let inner_opacity = if use_outer_group { 1.0 } else { opacity };
 
         let transform = scale_mat3(attrs.transform, ctx.dpi_scale);
         let transform = translate_neg_mat3(transform, ctx.offset);
 
-        if has_sublayers {
-            let mask_xform = if (attrs.flags).contains(iface::LayerFlags::MASK_TO_BOUNDS) {
+        if use_shadow_group {
+            builder.open_group(None, attrs.opacity, None);
+        }
+
+        if use_outer_group {
+            let mask_xform = if has_shape {
                 Some(xform_and_aabb_to_parallelogram(transform, attrs.bounds))
             } else {
                 None
             };
-            builder.open_group(mask_xform, inner_opacity);
+            builder.open_group(mask_xform, opacity, shape);
+        }
+
+        if has_sublayers {
+            // The outer group already masks the sublayers if the layer has a
+            // shape
+            let mask_xform =
+                if (attrs.flags).contains(iface::LayerFlags::MASK_TO_BOUNDS) && !has_shape {
+                    Some(xform_and_aabb_to_parallelogram(transform, attrs.bounds))
+                } else {
+                    None
+                };
+            builder.open_group(mask_xform, inner_opacity, None);
 
             for hlayer in layer.sublayers.iter().rev() {
                 self.binner_build_layer(builder, ctx, &self.layers[hlayer.ptr]);
@@ -842,7 +1058,11 @@ impl<TBmp: Bmp> Screen<TBmp> {
             builder.close_group();
         }
 
-        if has_content {
+        // When rendering a backdrop, skip the elements in front of
+        // `backdrop_of`
+        let skip_elems = ctx.backdrop_of.is_some() && !ctx.backdrop_found.get();
+
+        if has_image && !skip_elems {
             let bg_color = attrs.bg_color;
             let to_u8 = |x: f32| (x.fmax(0.0).fmin(1.0) * 255.0 + 0.5) as u8;
 
@@ -863,15 +1083,122 @@ impl<TBmp: Bmp> Screen<TBmp> {
             });
         }
 
-        if use_opacity_group {
+        if has_backdrop_blur && !skip_elems {
+            if let Some(raster) = self.render_backdrop(ctx, layer, transform) {
+                // The outer group already masks the backdrop if the layer has
+                // a shape
+                let mask_xform = if has_shape {
+                    None
+                } else {
+                    Some(xform_and_aabb_to_parallelogram(transform, attrs.bounds))
+                };
+                builder.push_raster(Arc::new(raster), mask_xform, inner_opacity);
+            }
+        }
+
+        if use_outer_group {
             builder.close_group();
         }
+
+        if let (Some(shadow), false) = (shadow, skip_elems) {
+            let target_bx = box2! {
+                min: [0, 0],
+                max: [ctx.target_size[0] as i32, ctx.target_size[1] as i32],
+            };
+            if let Some(raster) = render_shadow::<TBmp>(
+                transform,
+                attrs.bounds,
+                attrs.corner_radius,
+                shadow,
+                target_bx,
+            ) {
+                builder.push_raster(Arc::new(raster), None, opacity);
+            }
+        }
+
+        if use_shadow_group {
+            builder.close_group();
+        }
+    }
+
+    /// Render the contents behind `layer` and blur them as specified by
+    /// `LayerAttrs::backdrop_blur_radius`.
+    ///
+    /// `transform` transforms `layer`'s local coordinates to the render
+    /// target's pixel coordinates. The returned `Raster` covers the AABB of
+    /// `layer`'s bounds.
+    fn render_backdrop(
+        &self,
+        ctx: &RenderCtx<'_, TBmp>,
+        layer: &Layer<TBmp>,
+        transform: Matrix3<f32>,
+    ) -> Option<Raster> {
+        let attrs = &layer.attrs;
+        let sigma = blur_sigma(transform, attrs.backdrop_blur_radius);
+        let margin = blur_margin(sigma);
+
+        // The window's bounds in the render target's pixel coordinates
+        let offset = [ctx.offset.x as i32, ctx.offset.y as i32];
+        let wnd_bx = box2! {
+            min: [-offset[0], -offset[1]],
+            max: [
+                ctx.wnd_size[0] as i32 - offset[0],
+                ctx.wnd_size[1] as i32 - offset[1],
+            ],
+        };
+
+        let bx = round_aabb_conservative(xform_aabb(transform, attrs.bounds));
+        let bx = box2! {
+            min: [bx.min.x.fmax(-65536.0) as i32, bx.min.y.fmax(-65536.0) as i32],
+            max: [bx.max.x.fmin(65536.0) as i32, bx.max.y.fmin(65536.0) as i32],
+        };
+        let out_bx = bx.intersection(&wnd_bx)?;
+
+        // Render the region that can affect `out_bx`
+        let src_bx = expand_box(out_bx, margin).intersection(&wnd_bx)?;
+        let src_size: [usize; 2] = src_bx.size().cast::<usize>().unwrap().into();
+
+        let mut binner = Binner::new();
+        let mut builder = binner.build(src_size);
+        let src_ctx = RenderCtx {
+            dpi_scale: ctx.dpi_scale,
+            offset: ctx.offset + Vector2::new(src_bx.min.x as f32, src_bx.min.y as f32),
+            target_size: src_size,
+            wnd_size: ctx.wnd_size,
+            root: ctx.root,
+            backdrop_of: Some(layer),
+            backdrop_found: Cell::new(false),
+        };
+        self.binner_build_layer(&mut builder, &src_ctx, ctx.root);
+        builder.finish();
+
+        let mut data = vec![0u8; src_size[0] * src_size[1] * 4];
+        rasterize(&binner, &mut data, src_size[0] * 4);
+
+        let src = Raster {
+            bx: src_bx,
+            data: data
+                .chunks_exact(4)
+                .map(|px| [px[0], px[1], px[2], px[3]])
+                .collect(),
+        };
+
+        Some(blur_raster(&src, sigma, out_bx))
     }
 }
 
-struct RenderCtx {
+struct RenderCtx<'a, TBmp: 'static> {
     dpi_scale: f32,
     offset: Vector2<f32>,
+    /// The size of the render target.
+    target_size: [usize; 2],
+    wnd_size: [usize; 2],
+    root: &'a Layer<TBmp>,
+    /// If set, only the contents behind this layer are rendered. This is
+    /// used to implement `LayerAttrs::backdrop_blur_radius`.
+    backdrop_of: Option<&'a Layer<TBmp>>,
+    /// Set when `backdrop_of` is encountered during traversal.
+    backdrop_found: Cell<bool>,
 }
 
 fn bbox2_intersect(x: Option<Box2<usize>>, y: Option<Box2<usize>>) -> Option<Box2<usize>> {
@@ -1073,7 +1400,7 @@ mod tests {
         screen.set_layer_attr(
             &layer1,
             iface::LayerAttrs {
-                contents: Some(Some(TestBmp)),
+                contents: Some(Some(TestBmp.into())),
                 ..Default::default()
             },
         );
@@ -1153,7 +1480,7 @@ mod tests {
         screen.set_layer_attr(
             &layer2,
             iface::LayerAttrs {
-                contents: Some(Some(TestBmp)),
+                contents: Some(Some(TestBmp.into())),
                 ..Default::default()
             },
         );
@@ -1252,7 +1579,7 @@ mod tests {
         screen.set_layer_attr(
             &layer2,
             iface::LayerAttrs {
-                contents: Some(Some(TestBmp)),
+                contents: Some(Some(TestBmp.into())),
                 ..Default::default()
             },
         );
@@ -1449,7 +1776,7 @@ mod tests {
 
         let layer2 = screen.new_layer(iface::LayerAttrs {
             bounds: Some(box2! { min: [20.0, 30.0], max: [80.0, 50.0] }),
-            contents: Some(Some(TestBmp)),
+            contents: Some(Some(TestBmp.into())),
             ..Default::default()
         });
         let layer1 = screen.new_layer(iface::LayerAttrs {
@@ -1483,4 +1810,104 @@ mod tests {
             Some(box2! { min: [20, 30], max: [80, 50] })
         );
     }
+
+    #[test]
+    fn sublayer_update_shadow() {
+        let mut screen: Screen<TestBmp> = Screen::new();
+
+        let layer2 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [20.0, 30.0], max: [80.0, 50.0] }),
+            ..Default::default()
+        });
+        let layer1 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [100.0, 100.0] }),
+            sublayers: Some(vec![layer2.clone()]),
+            ..Default::default()
+        });
+
+        let wnd = screen.new_wnd();
+        screen.set_wnd_size(&wnd, [100, 100]);
+        screen.set_wnd_layer(&wnd, Some(layer1.clone()));
+
+        debug_assert_eq!(
+            screen.update_wnd(&wnd),
+            Some(box2! { min: [0, 0], max: [100, 100] })
+        );
+        debug_assert_eq!(screen.update_wnd(&wnd), None);
+
+        screen.set_layer_attr(
+            &layer2,
+            iface::LayerAttrs {
+                shadow: Some(Some(iface::LayerShadow {
+                    offset: [0.0, 2.0].into(),
+                    radius: 4.0,
+                    color: [0.0, 0.0, 0.0, 0.5].into(),
+                })),
+                ..Default::default()
+            },
+        );
+
+        // The shadow's standard deviation is 2px. The blur spreads it by 6px.
+        debug_assert_eq!(
+            screen.update_wnd(&wnd),
+            Some(box2! { min: [14, 26], max: [86, 58] })
+        );
+    }
+
+    #[test]
+    fn backdrop_blur_update_behind() {
+        let mut screen: Screen<TestBmp> = Screen::new();
+
+        let layer2 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [10.0, 30.0], max: [20.0, 50.0] }),
+            bg_color: Some([0.5, 0.6, 0.7, 0.8].into()),
+            ..Default::default()
+        });
+        let layer3 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [50.0, 30.0], max: [80.0, 50.0] }),
+            backdrop_blur_radius: Some(4.0),
+            ..Default::default()
+        });
+        let layer1 = screen.new_layer(iface::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [100.0, 100.0] }),
+            sublayers: Some(vec![layer2.clone(), layer3.clone()]),
+            ..Default::default()
+        });
+
+        let wnd = screen.new_wnd();
+        screen.set_wnd_size(&wnd, [100, 100]);
+        screen.set_wnd_layer(&wnd, Some(layer1.clone()));
+
+        debug_assert_eq!(
+            screen.update_wnd(&wnd),
+            Some(box2! { min: [0, 0], max: [100, 100] })
+        );
+        debug_assert_eq!(screen.update_wnd(&wnd), None);
+
+        // Out of the blur kernel's reach
+        screen.set_layer_attr(
+            &layer2,
+            iface::LayerAttrs {
+                bg_color: Some([0.1, 0.6, 0.7, 0.8].into()),
+                ..Default::default()
+            },
+        );
+        debug_assert_eq!(
+            screen.update_wnd(&wnd),
+            Some(box2! { min: [10, 30], max: [20, 50] })
+        );
+
+        // Within the blur kernel's reach, which is 6px
+        screen.set_layer_attr(
+            &layer2,
+            iface::LayerAttrs {
+                bounds: Some(box2! { min: [10.0, 30.0], max: [46.0, 50.0] }),
+                ..Default::default()
+            },
+        );
+        debug_assert_eq!(
+            screen.update_wnd(&wnd),
+            Some(box2! { min: [10, 24], max: [86, 56] })
+        );
+    }
 }
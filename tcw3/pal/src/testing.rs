@@ -101,14 +101,16 @@
 //!
 use atom2::SetOnceAtom;
 use cggeom::Box2;
-use cgmath::{Matrix3, Point2};
+use cgmath::{Matrix3, Point2, Vector2};
 use lazy_static::lazy_static;
 use log::{debug, trace};
 use std::{
+    cell::RefCell,
     fmt,
     marker::PhantomData,
     ops::Range,
     panic,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{
         mpsc::{channel, sync_channel},
@@ -123,9 +125,11 @@ use super::{iface, native, prelude::MtLazyStatic, prelude::*};
 mod eventloop;
 mod logging;
 mod screen;
+mod snapshotcmp;
 mod textinput;
 mod tictxlistenershim;
 mod uniqpool;
+mod webviewlistenershim;
 pub mod wmapi;
 mod wndlistenershim;
 pub use self::{logging::Logger, wmapi::TestingWm};
@@ -331,12 +335,27 @@ fn try_start_testing_main_thread() {
 
 mt_lazy_static! {
     static <Wm> ref SCREEN: screen::Screen => |_| screen::Screen::new();
+    static <Wm> ref CLIPBOARD: RefCell<Option<String>> => |_| RefCell::new(None);
+    static <Wm> ref FILE_DIALOG_HANDLER: RefCell<Option<Box<wmapi::FileDialogHandler>>> =>
+        |_| RefCell::new(None);
+    static <Wm> ref NOTIFICATIONS: RefCell<Vec<Notification>> => |_| RefCell::new(Vec::new());
+}
+
+/// A notification recorded by `Wm::show_notification`.
+struct Notification {
+    attrs: iface::NotificationAttrs,
+    /// The completion handler. Taken when the notification is activated.
+    f: Option<Box<dyn FnOnce(Wm, Option<usize>)>>,
 }
 
 impl Wm {
     fn reset(self) {
         self.eradicate_events();
+        self.reset_clock();
         SCREEN.get_with_wm(self).reset();
+        CLIPBOARD.get_with_wm(self).replace(None);
+        FILE_DIALOG_HANDLER.get_with_wm(self).replace(None);
+        NOTIFICATIONS.get_with_wm(self).replace(Vec::new());
         textinput::reset(self);
     }
 }
@@ -361,6 +380,15 @@ impl wmapi::TestingWm for Wm {
         self.step_timeout(Some(duration));
     }
 
+    fn now(&self) -> std::time::Instant {
+        self.clock_now()
+    }
+
+    fn advance_time(&self, duration: Duration) {
+        trace!("advance_time({:?})", duration);
+        (*self).advance_time(duration);
+    }
+
     fn hwnds(&self) -> Vec<HWnd> {
         (SCREEN.get_with_wm(*self).hwnds())
             .iter()
@@ -373,6 +401,45 @@ impl wmapi::TestingWm for Wm {
         SCREEN.get_with_wm(*self).wnd_attrs(hwnd)
     }
 
+    fn child_wnds(&self, hwnd: &HWnd) -> Vec<HChildWnd> {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        (SCREEN.get_with_wm(*self).child_wnds(hwnd))
+            .iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn child_wnd_bounds(&self, child: &HChildWnd) -> Option<Box2<f32>> {
+        let child = child.testing_child_wnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).child_wnd_bounds(child)
+    }
+
+    fn web_view_state(&self, child: &HChildWnd) -> Option<wmapi::WebViewState> {
+        let child = child.testing_child_wnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).web_view_state(child)
+    }
+
+    fn raise_web_view_navigation_started(&self, child: &HChildWnd, url: &str) {
+        let child = child.testing_child_wnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_web_view_navigation_started(*self, child, url)
+    }
+
+    fn raise_web_view_navigation_completed(&self, child: &HChildWnd, url: &str, success: bool) {
+        let child = child.testing_child_wnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_web_view_navigation_completed(*self, child, url, success)
+    }
+
+    fn raise_web_view_message(&self, child: &HChildWnd, message: &str) {
+        let child = child.testing_child_wnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_web_view_message(*self, child, message)
+    }
+
     fn raise_close_requested(&self, hwnd: &HWnd) {
         let hwnd = hwnd.testing_hwnd_ref().unwrap();
         SCREEN.get_with_wm(*self).raise_close_requested(*self, hwnd)
@@ -397,11 +464,52 @@ impl wmapi::TestingWm for Wm {
             .set_wnd_focused(*self, hwnd, focused)
     }
 
+    fn set_system_appearance(&self, appearance: iface::SystemAppearance) {
+        SCREEN
+            .get_with_wm(*self)
+            .set_system_appearance(*self, appearance)
+    }
+
+    fn set_default_ui_direction(&self, dir: iface::UiDirection) {
+        SCREEN.get_with_wm(*self).set_default_ui_direction(dir)
+    }
+
+    fn set_file_dialog_handler(&self, handler: Option<Box<wmapi::FileDialogHandler>>) {
+        FILE_DIALOG_HANDLER.get_with_wm(*self).replace(handler);
+    }
+
+    fn notifications(&self) -> Vec<iface::NotificationAttrs> {
+        (NOTIFICATIONS.get_with_wm(*self).borrow())
+            .iter()
+            .map(|n| n.attrs.clone())
+            .collect()
+    }
+
+    fn activate_notification(&self, i: usize, action: Option<usize>) {
+        let f = NOTIFICATIONS.get_with_wm(*self).borrow_mut()[i]
+            .f
+            .take()
+            .expect("the notification has already been activated");
+        debug!("activate_notification({:?}, {:?})", i, action);
+        f(*self, action);
+    }
+
     fn read_wnd_snapshot(&self, hwnd: &HWnd, out: &mut wmapi::WndSnapshot) {
         let hwnd = hwnd.testing_hwnd_ref().unwrap();
         SCREEN.get_with_wm(*self).read_wnd_snapshot(hwnd, out)
     }
 
+    fn assert_snapshot_matches(
+        &self,
+        hwnd: &HWnd,
+        name: &str,
+        options: &wmapi::SnapshotMatchOptions,
+    ) {
+        let mut snapshot = wmapi::WndSnapshot::new();
+        self.read_wnd_snapshot(hwnd, &mut snapshot);
+        snapshotcmp::assert_snapshot_matches(&snapshot, name, options);
+    }
+
     fn raise_mouse_motion(&self, hwnd: &HWnd, loc: Point2<f32>) {
         let hwnd = hwnd.testing_hwnd_ref().unwrap();
         SCREEN
@@ -414,6 +522,13 @@ impl wmapi::TestingWm for Wm {
         SCREEN.get_with_wm(*self).raise_mouse_leave(*self, hwnd)
     }
 
+    fn raise_mouse_relative_motion(&self, hwnd: &HWnd, delta: Vector2<f32>) {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_mouse_relative_motion(*self, hwnd, delta)
+    }
+
     fn raise_mouse_drag(
         &self,
         hwnd: &HWnd,
@@ -440,6 +555,47 @@ impl wmapi::TestingWm for Wm {
             .raise_scroll_gesture(*self, hwnd, loc)
     }
 
+    fn raise_gesture(&self, hwnd: &HWnd, loc: Point2<f32>) -> Box<dyn wmapi::Gesture> {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).raise_gesture(*self, hwnd, loc)
+    }
+
+    fn raise_touch_begin(&self, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_touch_begin(*self, hwnd, touch)
+    }
+
+    fn raise_touch_move(&self, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_touch_move(*self, hwnd, touch)
+    }
+
+    fn raise_touch_end(&self, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN
+            .get_with_wm(*self)
+            .raise_touch_end(*self, hwnd, touch)
+    }
+
+    fn raise_pen_down(&self, hwnd: &HWnd, pen: &iface::PenState) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).raise_pen_down(*self, hwnd, pen)
+    }
+
+    fn raise_pen_move(&self, hwnd: &HWnd, pen: &iface::PenState) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).raise_pen_move(*self, hwnd, pen)
+    }
+
+    fn raise_pen_up(&self, hwnd: &HWnd, pen: &iface::PenState) -> bool {
+        let hwnd = hwnd.testing_hwnd_ref().unwrap();
+        SCREEN.get_with_wm(*self).raise_pen_up(*self, hwnd, pen)
+    }
+
     fn active_text_input_ctxs(&self) -> Vec<HTextInputCtx> {
         textinput::HTextInputCtx::active_ctxs(*self)
             .into_iter()
@@ -532,6 +688,7 @@ impl iface::Wm for Wm {
     type HWnd = HWnd;
     type HLayer = HLayer;
     type HInvoke = HInvoke;
+    type HChildWnd = HChildWnd;
     type HTextInputCtx = HTextInputCtx;
     type AccelTable = AccelTable;
     type Bitmap = Bitmap;
@@ -604,6 +761,13 @@ impl iface::Wm for Wm {
         }
     }
 
+    fn now(self) -> std::time::Instant {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { .. } => std::time::Instant::now(),
+            BackendAndWm::Testing => self.clock_now(),
+        }
+    }
+
     fn enter_main_loop(self) -> ! {
         match self.backend_and_wm() {
             BackendAndWm::Native { wm } => wm.enter_main_loop(),
@@ -680,7 +844,20 @@ impl iface::Wm for Wm {
             }
             (BackendAndWm::Testing, HWndInner::Testing(ts_hwnd)) => {
                 debug!("update_wnd({:?})", hwnd);
-                SCREEN.get_with_wm(self).update_wnd(ts_hwnd);
+                SCREEN.get_with_wm(self).update_wnd(ts_hwnd, None);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn update_wnd_with_damage(self, hwnd: &Self::HWnd, damage: Box2<f32>) {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                wm.update_wnd_with_damage(hwnd, damage);
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(ts_hwnd)) => {
+                debug!("update_wnd_with_damage({:?}, {:?})", hwnd, damage);
+                SCREEN.get_with_wm(self).update_wnd(ts_hwnd, Some(damage));
             }
             _ => unreachable!(),
         }
@@ -696,8 +873,7 @@ impl iface::Wm for Wm {
 
                 let hwnd = hwnd.clone();
                 let ts_hwnd = ts_hwnd.clone();
-                // TODO: Add methods to `TestingWm` to customize this behavior
-                self.invoke_unsend(move |_| {
+                let raise = move |_| {
                     // TODO: Bail out if `ts_hwnd` is not valid anymore
                     trace!(
                         "Automatically calling raise_update_ready({:?}) \
@@ -705,7 +881,39 @@ impl iface::Wm for Wm {
                         hwnd
                     );
                     SCREEN.get_with_wm(self).raise_update_ready(self, &ts_hwnd);
-                });
+                };
+
+                // TODO: Add methods to `TestingWm` to customize this behavior
+                if self.is_virtual_clock_active() {
+                    // Simulate the display refresh. Otherwise, an animation
+                    // would request frames forever without the clock advancing.
+                    let interval = Duration::from_micros(16_667);
+                    self.invoke_after(interval..interval, raise);
+                } else {
+                    self.invoke_unsend(raise);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn render_wnd_to_bitmap(self, hwnd: &Self::HWnd) -> Option<Self::Bitmap> {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => {
+                wm.render_wnd_to_bitmap(hwnd).map(|bmp| Bitmap {
+                    inner: BitmapInner::Native(bmp),
+                })
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(ts_hwnd)) => {
+                let bmp = SCREEN.get_with_wm(self).render_wnd_to_bitmap(ts_hwnd);
+                trace!(
+                    "render_wnd_to_bitmap({:?}) -> size = {:?}",
+                    hwnd,
+                    bmp.as_ref().map(iface::Bitmap::size)
+                );
+                bmp.map(|bmp| Bitmap {
+                    inner: BitmapInner::Testing(bmp),
+                })
             }
             _ => unreachable!(),
         }
@@ -723,6 +931,30 @@ impl iface::Wm for Wm {
         }
     }
 
+    fn get_wnd_position(self, hwnd: &Self::HWnd) -> Option<[i32; 2]> {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => wm.get_wnd_position(hwnd),
+            (BackendAndWm::Testing, HWndInner::Testing(tc_hwnd)) => {
+                let position = SCREEN.get_with_wm(self).get_wnd_position(tc_hwnd);
+                trace!("get_wnd_position({:?}) -> {:?}", hwnd, position);
+                Some(position)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn is_wnd_maximized(self, hwnd: &Self::HWnd) -> bool {
+        match (self.backend_and_wm(), &hwnd.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => wm.is_wnd_maximized(hwnd),
+            (BackendAndWm::Testing, HWndInner::Testing(tc_hwnd)) => {
+                let maximized = SCREEN.get_with_wm(self).is_wnd_maximized(tc_hwnd);
+                trace!("is_wnd_maximized({:?}) -> {:?}", hwnd, maximized);
+                maximized
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn get_wnd_dpi_scale(self, hwnd: &Self::HWnd) -> f32 {
         match (self.backend_and_wm(), &hwnd.inner) {
             (BackendAndWm::Native { wm }, HWndInner::Native(hwnd)) => wm.get_wnd_dpi_scale(hwnd),
@@ -747,6 +979,113 @@ impl iface::Wm for Wm {
         }
     }
 
+    fn new_child_wnd(self, parent: &Self::HWnd, bounds: Box2<f32>) -> Option<Self::HChildWnd> {
+        match (self.backend_and_wm(), &parent.inner) {
+            (BackendAndWm::Native { wm }, HWndInner::Native(parent)) => {
+                wm.new_child_wnd(parent, bounds).map(|child| HChildWnd {
+                    inner: HChildWndInner::Native(child),
+                })
+            }
+            (BackendAndWm::Testing, HWndInner::Testing(ts_parent)) => {
+                debug!("new_child_wnd({:?}, {:?})", parent, bounds);
+                let child = HChildWnd {
+                    inner: HChildWndInner::Testing(
+                        SCREEN.get_with_wm(self).new_child_wnd(ts_parent, bounds),
+                    ),
+                };
+                debug!("... -> {:?}", child);
+                Some(child)
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn set_child_wnd_bounds(self, child: &Self::HChildWnd, bounds: Box2<f32>) {
+        match (self.backend_and_wm(), &child.inner) {
+            (BackendAndWm::Native { wm }, HChildWndInner::Native(child)) => {
+                wm.set_child_wnd_bounds(child, bounds);
+            }
+            (BackendAndWm::Testing, HChildWndInner::Testing(ts_child)) => {
+                debug!("set_child_wnd_bounds({:?}, {:?})", child, bounds);
+                SCREEN
+                    .get_with_wm(self)
+                    .set_child_wnd_bounds(ts_child, bounds);
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn remove_child_wnd(self, child: &Self::HChildWnd) {
+        match (self.backend_and_wm(), &child.inner) {
+            (BackendAndWm::Native { wm }, HChildWndInner::Native(child)) => {
+                wm.remove_child_wnd(child);
+            }
+            (BackendAndWm::Testing, HChildWndInner::Testing(ts_child)) => {
+                debug!("remove_child_wnd({:?})", child);
+                SCREEN.get_with_wm(self).remove_child_wnd(ts_child);
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn attach_web_view(
+        self,
+        child: &Self::HChildWnd,
+        listener: Box<dyn iface::WebViewListener<Self>>,
+    ) -> bool {
+        match (self.backend_and_wm(), &child.inner) {
+            (BackendAndWm::Native { wm }, HChildWndInner::Native(child)) => {
+                let listener = Box::new(webviewlistenershim::NativeWebViewListener(listener));
+                wm.attach_web_view(child, listener)
+            }
+            (BackendAndWm::Testing, HChildWndInner::Testing(ts_child)) => {
+                debug!("attach_web_view({:?})", child);
+                SCREEN.get_with_wm(self).attach_web_view(ts_child, listener)
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn web_view_navigate(self, child: &Self::HChildWnd, url: &str) {
+        match (self.backend_and_wm(), &child.inner) {
+            (BackendAndWm::Native { wm }, HChildWndInner::Native(child)) => {
+                wm.web_view_navigate(child, url);
+            }
+            (BackendAndWm::Testing, HChildWndInner::Testing(ts_child)) => {
+                debug!("web_view_navigate({:?}, {:?})", child, url);
+                SCREEN.get_with_wm(self).web_view_navigate(ts_child, url);
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn web_view_load_html(self, child: &Self::HChildWnd, html: &str, base_url: Option<&str>) {
+        match (self.backend_and_wm(), &child.inner) {
+            (BackendAndWm::Native { wm }, HChildWndInner::Native(child)) => {
+                wm.web_view_load_html(child, html, base_url);
+            }
+            (BackendAndWm::Testing, HChildWndInner::Testing(ts_child)) => {
+                debug!(
+                    "web_view_load_html({:?}, {:?}, {:?})",
+                    child, html, base_url
+                );
+                SCREEN
+                    .get_with_wm(self)
+                    .web_view_load_html(ts_child, html, base_url);
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn web_view_eval_script(self, child: &Self::HChildWnd, script: &str) {
+        match (self.backend_and_wm(), &child.inner) {
+            (BackendAndWm::Native { wm }, HChildWndInner::Native(child)) => {
+                wm.web_view_eval_script(child, script);
+            }
+            (BackendAndWm::Testing, HChildWndInner::Testing(ts_child)) => {
+                debug!("web_view_eval_script({:?}, {:?})", child, script);
+                SCREEN
+                    .get_with_wm(self)
+                    .web_view_eval_script(ts_child, script);
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn new_layer(self, attrs: LayerAttrs) -> Self::HLayer {
         match self.backend_and_wm() {
             BackendAndWm::Native { wm } => {
@@ -794,6 +1133,60 @@ impl iface::Wm for Wm {
         }
     }
 
+    fn begin_layer_txn(self) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.begin_layer_txn(),
+            BackendAndWm::Testing => {
+                debug!("begin_layer_txn()");
+                SCREEN.get_with_wm(self).begin_layer_txn();
+            }
+        }
+    }
+
+    fn commit_layer_txn(self) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.commit_layer_txn(),
+            BackendAndWm::Testing => {
+                debug!("commit_layer_txn()");
+                SCREEN.get_with_wm(self).commit_layer_txn();
+            }
+        }
+    }
+
+    fn set_layer_swapchain(self, hlayer: &Self::HLayer, size: Option<[u32; 2]>) -> bool {
+        match (self.backend_and_wm(), &hlayer.inner) {
+            (BackendAndWm::Native { wm }, HLayerInner::Native(hlayer)) => {
+                wm.set_layer_swapchain(hlayer, size)
+            }
+            (BackendAndWm::Testing, HLayerInner::Testing(tc_hlayer)) => {
+                debug!("set_layer_swapchain({:?}, {:?})", hlayer, size);
+                SCREEN
+                    .get_with_wm(self)
+                    .set_layer_swapchain(tc_hlayer, size);
+                true
+            }
+            _ => unreachable!(),
+        }
+    }
+    fn present_layer_swapchain(
+        self,
+        hlayer: &Self::HLayer,
+        render: &mut dyn FnMut(iface::SwapchainTarget<'_>),
+    ) {
+        match (self.backend_and_wm(), &hlayer.inner) {
+            (BackendAndWm::Native { wm }, HLayerInner::Native(hlayer)) => {
+                wm.present_layer_swapchain(hlayer, render);
+            }
+            (BackendAndWm::Testing, HLayerInner::Testing(tc_hlayer)) => {
+                debug!("present_layer_swapchain({:?})", hlayer);
+                SCREEN
+                    .get_with_wm(self)
+                    .present_layer_swapchain(tc_hlayer, render);
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn new_text_input_ctx(
         self,
         hwnd: &Self::HWnd,
@@ -884,6 +1277,134 @@ impl iface::Wm for Wm {
             _ => unreachable!(),
         }
     }
+
+    fn set_clipboard_text(self, text: &str) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.set_clipboard_text(text),
+            BackendAndWm::Testing => {
+                debug!("set_clipboard_text({:?})", text);
+                CLIPBOARD.get_with_wm(self).replace(Some(text.to_owned()));
+            }
+        }
+    }
+
+    fn clipboard_text(self) -> Option<String> {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.clipboard_text(),
+            BackendAndWm::Testing => {
+                let text = CLIPBOARD.get_with_wm(self).borrow().clone();
+                trace!("clipboard_text() = {:?}", text);
+                text
+            }
+        }
+    }
+
+    fn show_file_dialog(
+        self,
+        parent: Option<&Self::HWnd>,
+        options: iface::FileDialogOptions,
+        f: impl FnOnce(Self, Option<Vec<PathBuf>>) + 'static,
+    ) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => {
+                let parent = parent.map(|hwnd| match &hwnd.inner {
+                    HWndInner::Native(hwnd) => hwnd,
+                    HWndInner::Testing(_) => unreachable!(),
+                });
+                wm.show_file_dialog(parent, options, move |_, paths| f(self, paths));
+            }
+            BackendAndWm::Testing => {
+                debug!("show_file_dialog({:?}, {:?})", parent, options);
+                self.invoke(move |wm| {
+                    let paths = FILE_DIALOG_HANDLER
+                        .get_with_wm(wm)
+                        .borrow_mut()
+                        .as_mut()
+                        .and_then(|handler| handler(&options));
+                    debug!("show_file_dialog: dismissed with {:?}", paths);
+                    f(wm, paths);
+                });
+            }
+        }
+    }
+
+    fn show_alert(
+        self,
+        parent: Option<&Self::HWnd>,
+        options: &iface::AlertOptions,
+        f: iface::AlertCallback<Self>,
+    ) -> Result<(), iface::AlertCallback<Self>> {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => {
+                let parent = parent.map(|hwnd| match &hwnd.inner {
+                    HWndInner::Native(hwnd) => hwnd,
+                    HWndInner::Testing(_) => unreachable!(),
+                });
+                wm.show_alert(parent, options, Box::new(move |_, i| f(self, i)))
+                    .map_err(|f| -> iface::AlertCallback<Self> { Box::new(move |_, i| f(wm, i)) })
+            }
+            BackendAndWm::Testing => {
+                // Let the caller present a substitute, which can be examined
+                // by a test
+                trace!("show_alert({:?}, {:?}) -> Err(_)", parent, options);
+                Err(f)
+            }
+        }
+    }
+
+    fn show_notification(
+        self,
+        attrs: iface::NotificationAttrs,
+        f: impl FnOnce(Self, Option<usize>) + 'static,
+    ) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => {
+                wm.show_notification(attrs, move |_, action| f(self, action));
+            }
+            BackendAndWm::Testing => {
+                debug!("show_notification({:?})", attrs);
+                NOTIFICATIONS
+                    .get_with_wm(self)
+                    .borrow_mut()
+                    .push(Notification {
+                        attrs,
+                        f: Some(Box::new(f)),
+                    });
+            }
+        }
+    }
+
+    fn play_system_sound(self, sound: crate::sound::SystemSound) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.play_system_sound(sound),
+            BackendAndWm::Testing => debug!("play_system_sound({:?})", sound),
+        }
+    }
+
+    fn play_sound_file(self, path: &Path) {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.play_sound_file(path),
+            BackendAndWm::Testing => debug!("play_sound_file({:?})", path),
+        }
+    }
+
+    fn system_appearance(self) -> iface::SystemAppearance {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.system_appearance(),
+            BackendAndWm::Testing => {
+                let value = SCREEN.get_with_wm(self).system_appearance();
+                trace!("system_appearance() = {:?}", value);
+                value
+            }
+        }
+    }
+
+    fn default_ui_direction(self) -> iface::UiDirection {
+        match self.backend_and_wm() {
+            BackendAndWm::Native { wm } => wm.default_ui_direction(),
+            BackendAndWm::Testing => SCREEN.get_with_wm(self).default_ui_direction(),
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -923,6 +1444,52 @@ enum HWndInner {
     Testing(screen::HWnd),
 }
 
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct HChildWnd {
+    inner: HChildWndInner,
+}
+
+impl fmt::Debug for HChildWnd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.inner {
+            HChildWndInner::Native(imp) => write!(f, "{:?}", imp),
+            HChildWndInner::Testing(imp) => write!(f, "{:?}", imp),
+        }
+    }
+}
+
+impl From<&screen::HChildWnd> for HChildWnd {
+    fn from(child: &screen::HChildWnd) -> HChildWnd {
+        HChildWnd {
+            inner: HChildWndInner::Testing(child.clone()),
+        }
+    }
+}
+
+impl HChildWnd {
+    /// Get the native child window handle. Returns `None` if the testing
+    /// backend is active.
+    pub fn native_child_wnd(&self) -> Option<&native::HChildWnd> {
+        match &self.inner {
+            HChildWndInner::Native(imp) => Some(imp),
+            HChildWndInner::Testing(_) => None,
+        }
+    }
+
+    fn testing_child_wnd_ref(&self) -> Option<&screen::HChildWnd> {
+        match &self.inner {
+            HChildWndInner::Native(_) => None,
+            HChildWndInner::Testing(imp) => Some(imp),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum HChildWndInner {
+    Native(native::HChildWnd),
+    Testing(screen::HChildWnd),
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct HLayer {
     inner: HLayerInner,
@@ -969,6 +1536,8 @@ fn wnd_attrs_to_native(attrs: WndAttrs<'_>) -> native::WndAttrs<'_> {
         size: attrs.size,
         min_size: attrs.min_size,
         max_size: attrs.max_size,
+        position: attrs.position,
+        maximized: attrs.maximized,
         flags: attrs.flags,
         caption: attrs.caption,
         visible: attrs.visible,
@@ -977,6 +1546,8 @@ fn wnd_attrs_to_native(attrs: WndAttrs<'_>) -> native::WndAttrs<'_> {
             .map(|listener| Box::new(wndlistenershim::NativeWndListener(listener)) as _),
         layer,
         cursor_shape: attrs.cursor_shape,
+        progress: attrs.progress,
+        pointer_grab: attrs.pointer_grab,
     }
 }
 
@@ -1040,6 +1611,7 @@ enum HTextInputCtxInner {
 #[derive(Debug)]
 pub struct AccelTable {
     testing: &'static [wmapi::ActionBinding],
+    key_bindings: Vec<iface::KeyBinding>,
     native: native::AccelTable,
 }
 
@@ -1049,7 +1621,21 @@ impl AccelTable {
         testing: &'static [wmapi::ActionBinding],
         native: native::AccelTable,
     ) -> Self {
-        Self { testing, native }
+        Self {
+            testing,
+            key_bindings: Vec::new(),
+            native,
+        }
+    }
+}
+
+impl iface::AccelTableNew for AccelTable {
+    fn new_with_key_bindings(key_bindings: Vec<iface::KeyBinding>) -> Self {
+        Self {
+            testing: &[],
+            native: native::AccelTable::new_with_key_bindings(key_bindings.clone()),
+            key_bindings,
+        }
     }
 }
 
@@ -1063,12 +1649,16 @@ fn wnd_attrs_to_testing(attrs: WndAttrs<'_>) -> screen::WndAttrs<'_> {
         size: attrs.size,
         min_size: attrs.min_size,
         max_size: attrs.max_size,
+        position: attrs.position,
+        maximized: attrs.maximized,
         flags: attrs.flags,
         caption: attrs.caption,
         visible: attrs.visible,
         listener: attrs.listener,
         layer,
         cursor_shape: attrs.cursor_shape,
+        progress: attrs.progress,
+        pointer_grab: attrs.pointer_grab,
     }
 }
 
@@ -1083,7 +1673,18 @@ fn layer_attrs_to_native(attrs: LayerAttrs) -> native::LayerAttrs {
             .collect()
     });
     let contents = attrs.contents.map(|contents_or_none| {
-        contents_or_none.map(|contents| match contents.inner {
+        contents_or_none.map(|contents| match contents {
+            iface::LayerContents::Bitmap(bitmap) => match bitmap.inner {
+                BitmapInner::Native(bitmap) => bitmap.into(),
+                BitmapInner::Testing(_) => panic!("Bitmap was created by the wrong backend"),
+            },
+            iface::LayerContents::ExternalTexture(tex) => {
+                iface::LayerContents::ExternalTexture(tex)
+            }
+        })
+    });
+    let mask = attrs.mask.map(|mask_or_none| {
+        mask_or_none.map(|bitmap| match bitmap.inner {
             BitmapInner::Native(bitmap) => bitmap,
             BitmapInner::Testing(_) => panic!("Bitmap was created by the wrong backend"),
         })
@@ -1098,6 +1699,10 @@ fn layer_attrs_to_native(attrs: LayerAttrs) -> native::LayerAttrs {
         sublayers,
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radius: attrs.corner_radius,
+        mask,
+        shadow: attrs.shadow,
+        backdrop_blur_radius: attrs.backdrop_blur_radius,
     }
 }
 
@@ -1112,7 +1717,18 @@ fn layer_attrs_to_testing(attrs: LayerAttrs) -> screen::LayerAttrs {
             .collect()
     });
     let contents = attrs.contents.map(|contents_or_none| {
-        contents_or_none.map(|contents| match contents.inner {
+        contents_or_none.map(|contents| match contents {
+            iface::LayerContents::Bitmap(bitmap) => match bitmap.inner {
+                BitmapInner::Native(_) => panic!("Bitmap was created by the wrong backend"),
+                BitmapInner::Testing(bitmap) => bitmap.into(),
+            },
+            iface::LayerContents::ExternalTexture(tex) => {
+                iface::LayerContents::ExternalTexture(tex)
+            }
+        })
+    });
+    let mask = attrs.mask.map(|mask_or_none| {
+        mask_or_none.map(|bitmap| match bitmap.inner {
             BitmapInner::Native(_) => panic!("Bitmap was created by the wrong backend"),
             BitmapInner::Testing(bitmap) => bitmap,
         })
@@ -1127,6 +1743,10 @@ fn layer_attrs_to_testing(attrs: LayerAttrs) -> screen::LayerAttrs {
         sublayers,
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radius: attrs.corner_radius,
+        mask,
+        shadow: attrs.shadow,
+        backdrop_blur_radius: attrs.backdrop_blur_radius,
     }
 }
 
@@ -1251,6 +1871,8 @@ impl iface::Canvas for BitmapBuilder {
         fn line_to(&mut self, p: Point2<f32>);
         fn cubic_bezier_to(&mut self, cp1: Point2<f32>, cp2: Point2<f32>, p: Point2<f32>);
         fn quad_bezier_to(&mut self, cp: Point2<f32>, p: Point2<f32>);
+        fn ellipse(&mut self, bx: Box2<f32>);
+        fn arc_to(&mut self, center: Point2<f32>, radius: f32, start_angle: f32, end_angle: f32);
         fn fill(&mut self);
         fn stroke(&mut self);
         fn clip(&mut self);
@@ -1369,13 +1991,22 @@ enum TextLayoutInner {
 impl iface::TextLayout for TextLayout {
     type CharStyle = CharStyle;
 
-    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+    fn from_text_with_direction(
+        text: &str,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+        direction: iface::ParagraphDirection,
+    ) -> Self {
         match &style.inner {
             CharStyleInner::Native(style) => Self {
-                inner: TextLayoutInner::Native(native::TextLayout::from_text(text, style, width)),
+                inner: TextLayoutInner::Native(native::TextLayout::from_text_with_direction(
+                    text, style, width, direction,
+                )),
             },
             CharStyleInner::Testing(style) => Self {
-                inner: TextLayoutInner::Testing(text::TextLayout::from_text(text, style, width)),
+                inner: TextLayoutInner::Testing(text::TextLayout::from_text_with_direction(
+                    text, style, width, direction,
+                )),
             },
         }
     }
@@ -1385,6 +2016,7 @@ impl iface::TextLayout for TextLayout {
         fn visual_bounds(&self) -> Box2<f32>;
         fn layout_bounds(&self) -> Box2<f32>;
         fn cursor_index_from_point(&self, point: Point2<f32>) -> usize;
+        fn hit_test(&self, point: Point2<f32>) -> iface::HitTestResult;
         fn cursor_pos(&self, i: usize) -> [iface::Beam; 2];
         fn num_lines(&self) -> usize;
         fn line_index_range(&self, i: usize) -> Range<usize>;
@@ -1393,5 +2025,6 @@ impl iface::TextLayout for TextLayout {
         fn run_metrics_of_range(&self, i: Range<usize>) -> Vec<iface::RunMetrics>;
         fn next_char(&self, i: usize, forward: bool) -> usize;
         fn next_word(&self, i: usize, forward: bool) -> usize;
+        fn is_emoji_cluster(&self, i: Range<usize>) -> bool;
     }
 }
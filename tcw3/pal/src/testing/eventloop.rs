@@ -1,7 +1,7 @@
 use log::{trace, warn};
 use neo_linked_list::{linked_list::Node, AssertUnpin, LinkedListCell};
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     ops::Range,
     pin::Pin,
     sync::{
@@ -26,6 +26,10 @@ static DISPATCH_RECV: MtLock<RefCell<Option<Receiver<Dispatch>>>> = MtLock::new(
 mt_lazy_static! {
     static <Wm> ref TIMER_QUEUE: RefCell<TimerQueue<Box<dyn FnOnce(Wm)>>> =>
         |_| RefCell::new(TimerQueue::new());
+
+    /// The current time of the virtual clock. `None` if the real-time clock is
+    /// in use.
+    static <Wm> ref VIRTUAL_NOW: Cell<Option<Instant>> => |_| Cell::new(None);
 }
 
 type Dispatch = Box<dyn FnOnce(Wm) + Send>;
@@ -83,7 +87,7 @@ impl Wm {
         TIMER_QUEUE
             .get_with_wm(self)
             .borrow_mut()
-            .insert(delay, boxed)
+            .insert_at(self.clock_now(), delay, boxed)
             .map_err(|e| {
                 warn!(
                     "invoke_after failed because \
@@ -147,7 +151,9 @@ impl Wm {
             // And then check the thread-local delayed invocations for the same reason
             let runnable_tasks: Vec<_> = {
                 let mut timer_queue = TIMER_QUEUE.get_with_wm(self).borrow_mut();
-                timer_queue.drain_runnable_tasks().collect()
+                timer_queue
+                    .drain_runnable_tasks_at(self.clock_now())
+                    .collect()
             };
             if !runnable_tasks.is_empty() {
                 for (_, e) in runnable_tasks {
@@ -158,12 +164,18 @@ impl Wm {
 
             // Maybe we have a runnable delayed invocation if we wait long enough...
             // But we shouldn't wait longer than the given `timeout`.
+            // (This doesn't apply to the virtual clock, which only advances
+            // when `advance_time` is called.)
             let recv_timeout = {
-                let next = TIMER_QUEUE
-                    .get_with_wm(self)
-                    .borrow()
-                    .suggest_next_wakeup()
-                    .map(|instant| instant.saturating_duration_since(Instant::now()));
+                let next = if self.is_virtual_clock_active() {
+                    None
+                } else {
+                    TIMER_QUEUE
+                        .get_with_wm(self)
+                        .borrow()
+                        .suggest_next_wakeup()
+                        .map(|instant| instant.saturating_duration_since(Instant::now()))
+                };
 
                 match (timeout, next) {
                     (Some(x), Some(y)) => Some(x.min(y)),
@@ -217,6 +229,69 @@ impl Wm {
         }
     }
 
+    /// Get the current time of the clock used for delayed invocations.
+    pub(super) fn clock_now(self) -> Instant {
+        VIRTUAL_NOW
+            .get_with_wm(self)
+            .get()
+            .unwrap_or_else(Instant::now)
+    }
+
+    pub(super) fn is_virtual_clock_active(self) -> bool {
+        VIRTUAL_NOW.get_with_wm(self).get().is_some()
+    }
+
+    /// Switch to the virtual clock (if it's not active yet) and advance it by
+    /// the specified duration, running delayed invocations in a chronological
+    /// order as they become due.
+    pub(super) fn advance_time(self, duration: Duration) {
+        let virtual_now = VIRTUAL_NOW.get_with_wm(self);
+        let start = self.clock_now();
+        virtual_now.set(Some(start));
+        let till = start + duration;
+
+        loop {
+            // Process the `!Send` dispatches (including `update_ready`
+            // events) generated so far
+            self.step_unsend();
+
+            // Move the clock to the earliest deadline not exceeding `till`
+            let next = TIMER_QUEUE.get_with_wm(self).borrow().suggest_next_wakeup();
+            let now = match next {
+                Some(next) if next < till => next.max(self.clock_now()),
+                _ => till,
+            };
+            virtual_now.set(Some(now));
+
+            let runnable_tasks: Vec<_> = {
+                let mut timer_queue = TIMER_QUEUE.get_with_wm(self).borrow_mut();
+                timer_queue.drain_runnable_tasks_at(now).collect()
+            };
+
+            if runnable_tasks.is_empty() {
+                if now == till {
+                    break;
+                }
+                continue;
+            }
+
+            trace!(
+                "advance_time: running {} delayed invocation(s) at {:?}",
+                runnable_tasks.len(),
+                now
+            );
+
+            for (_, e) in runnable_tasks {
+                e(self);
+            }
+        }
+    }
+
+    /// Switch back to the real-time clock.
+    pub(super) fn reset_clock(self) {
+        VIRTUAL_NOW.get_with_wm(self).set(None);
+    }
+
     pub(super) fn eradicate_events(self) {
         loop {
             let timer_queue = TIMER_QUEUE.get_with_wm(self);
@@ -2,9 +2,13 @@
 use cggeom::{box2, prelude::*, Box2};
 use cgmath::{Point2, Vector2};
 use log::warn;
-use std::{cell::RefCell, fmt, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt, rc::Rc};
 
-use super::super::{iface, swrast};
+use super::super::{
+    iface::{self, KeyEvent as _},
+    layertxn::LayerTxnQueue,
+    swrast,
+};
 use super::{
     bitmap::Bitmap,
     uniqpool::{PoolPtr, UniqPool},
@@ -30,6 +34,18 @@ impl fmt::Debug for HWnd {
     }
 }
 
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct HChildWnd {
+    /// A pointer into `State::child_wnds`.
+    ptr: PoolPtr,
+}
+
+impl fmt::Debug for HChildWnd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("HChildWnd").field(&self.ptr).finish()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct HLayer {
     sr_layer: swrast::HLayer<Bitmap>,
@@ -45,6 +61,12 @@ struct State {
     binner: swrast::Binner<Bitmap>,
     sr_scrn: swrast::Screen<Bitmap>,
     wnds: UniqPool<Wnd>,
+    child_wnds: UniqPool<ChildWnd>,
+    /// The sizes of the swapchains attached to layers.
+    swapchains: HashMap<HLayer, [u32; 2]>,
+    layer_txn: LayerTxnQueue<HWnd>,
+    system_appearance: iface::SystemAppearance,
+    default_ui_direction: iface::UiDirection,
 }
 
 pub struct Wnd {
@@ -61,12 +83,28 @@ pub struct Wnd {
     img_dpi_scale: f32,
 }
 
+struct ChildWnd {
+    parent: HWnd,
+    bounds: Box2<f32>,
+    web_view: Option<WebView>,
+}
+
+struct WebView {
+    listener: Rc<dyn iface::WebViewListener<Wm>>,
+    state: wmapi::WebViewState,
+}
+
 impl Screen {
     pub(super) fn new() -> Self {
         let state = State {
             binner: swrast::Binner::new(),
             sr_scrn: swrast::Screen::new(),
             wnds: UniqPool::new(),
+            child_wnds: UniqPool::new(),
+            swapchains: HashMap::new(),
+            layer_txn: LayerTxnQueue::new(),
+            system_appearance: iface::SystemAppearance::default(),
+            default_ui_direction: iface::UiDirection::default(),
         };
 
         Self {
@@ -83,6 +121,11 @@ impl Screen {
 
         state.sr_scrn = swrast::Screen::new();
         state.wnds = UniqPool::new();
+        state.child_wnds = UniqPool::new();
+        state.swapchains = HashMap::new();
+        state.layer_txn = LayerTxnQueue::new();
+        state.system_appearance = iface::SystemAppearance::default();
+        state.default_ui_direction = iface::UiDirection::default();
     }
 
     pub(super) fn new_wnd(&self, attrs: WndAttrs<'_>) -> HWnd {
@@ -99,10 +142,14 @@ impl Screen {
                 size: attrs.size.unwrap_or([100, 100]),
                 min_size: attrs.min_size.unwrap_or([0; 2]),
                 max_size: attrs.max_size.unwrap_or([u32::max_value(); 2]),
+                position: attrs.position.unwrap_or([0, 0]),
+                maximized: attrs.maximized.unwrap_or(false),
                 flags: attrs.flags.unwrap_or(iface::WndFlags::default()),
                 caption: attrs.caption.unwrap_or("Default title".into()).into_owned(),
                 visible: attrs.visible.unwrap_or(false),
                 cursor_shape: attrs.cursor_shape.unwrap_or_default(),
+                progress: attrs.progress.unwrap_or(None),
+                pointer_grab: attrs.pointer_grab.unwrap_or(false),
             },
             listener: Rc::from(attrs.listener.unwrap_or_else(|| Box::new(()))),
             img_size: [0, 0],
@@ -134,10 +181,14 @@ impl Screen {
         apply!(size);
         apply!(min_size);
         apply!(max_size);
+        apply!(position);
+        apply!(maximized);
         apply!(flags);
         apply!(caption);
         apply!(visible);
         apply!(cursor_shape);
+        apply!(progress);
+        apply!(pointer_grab);
 
         if let Some(layer) = attrs.layer {
             state
@@ -156,14 +207,29 @@ impl Screen {
         let wnd = state.wnds.deallocate(hwnd.ptr).expect("invalid hwnd");
 
         state.sr_scrn.remove_wnd(&wnd.sr_wnd);
+        state.layer_txn.remove_wnd(hwnd);
+
+        // Child windows are destroyed along with their parent
+        let child_ptrs: Vec<_> = (state.child_wnds.ptr_iter())
+            .filter(|(_, child)| child.parent == *hwnd)
+            .map(|(ptr, _)| ptr)
+            .collect();
+        for ptr in child_ptrs {
+            state.child_wnds.deallocate(ptr);
+        }
     }
-    pub(super) fn update_wnd(&self, hwnd: &HWnd) {
+    pub(super) fn update_wnd(&self, hwnd: &HWnd, damage: Option<Box2<f32>>) {
         let mut state = self.state.borrow_mut();
         let state = &mut *state; // enable split borrow
+
+        if state.layer_txn.defer_update_wnd(hwnd, damage) {
+            return;
+        }
+
         let wnd: &mut Wnd = &mut state.wnds[hwnd.ptr];
 
         // Apply deferred changes and compute the dirty region
-        if let Some(new_dirty) = state.sr_scrn.update_wnd(&wnd.sr_wnd) {
+        if let Some(new_dirty) = state.sr_scrn.update_wnd_with_damage(&wnd.sr_wnd, damage) {
             if let Some(x) = &mut wnd.dirty_rect {
                 x.union_assign(&new_dirty);
             } else {
@@ -171,10 +237,27 @@ impl Screen {
             }
         }
     }
+    pub(super) fn begin_layer_txn(&self) {
+        self.state.borrow_mut().layer_txn.begin();
+    }
+    pub(super) fn commit_layer_txn(&self) {
+        let hwnds = self.state.borrow_mut().layer_txn.commit();
+        for (hwnd, damage) in hwnds {
+            self.update_wnd(&hwnd, damage);
+        }
+    }
     pub(super) fn get_wnd_size(&self, hwnd: &HWnd) -> [u32; 2] {
         let state = self.state.borrow();
         state.wnds[hwnd.ptr].attrs.size
     }
+    pub(super) fn get_wnd_position(&self, hwnd: &HWnd) -> [i32; 2] {
+        let state = self.state.borrow();
+        state.wnds[hwnd.ptr].attrs.position
+    }
+    pub(super) fn is_wnd_maximized(&self, hwnd: &HWnd) -> bool {
+        let state = self.state.borrow();
+        state.wnds[hwnd.ptr].attrs.maximized
+    }
     pub(super) fn get_wnd_dpi_scale(&self, hwnd: &HWnd) -> f32 {
         let state = self.state.borrow();
         state.wnds[hwnd.ptr].dpi_scale
@@ -184,6 +267,73 @@ impl Screen {
         state.wnds[hwnd.ptr].focused
     }
 
+    pub(super) fn new_child_wnd(&self, parent: &HWnd, bounds: Box2<f32>) -> HChildWnd {
+        let mut state = self.state.borrow_mut();
+
+        assert!(state.wnds.get(parent.ptr).is_some(), "invalid hwnd");
+
+        let ptr = state.child_wnds.allocate(ChildWnd {
+            parent: parent.clone(),
+            bounds,
+            web_view: None,
+        });
+        HChildWnd { ptr }
+    }
+    pub(super) fn set_child_wnd_bounds(&self, child: &HChildWnd, bounds: Box2<f32>) {
+        let mut state = self.state.borrow_mut();
+
+        state.child_wnds[child.ptr].bounds = bounds;
+    }
+    pub(super) fn remove_child_wnd(&self, child: &HChildWnd) {
+        let mut state = self.state.borrow_mut();
+
+        // The child window might have been removed along with its parent
+        state.child_wnds.deallocate(child.ptr);
+    }
+
+    pub(super) fn attach_web_view(
+        &self,
+        child: &HChildWnd,
+        listener: Box<dyn iface::WebViewListener<Wm>>,
+    ) -> bool {
+        let mut state = self.state.borrow_mut();
+
+        let child = &mut state.child_wnds[child.ptr];
+        assert!(child.web_view.is_none(), "already has a web view");
+        child.web_view = Some(WebView {
+            listener: listener.into(),
+            state: wmapi::WebViewState::default(),
+        });
+        true
+    }
+    pub(super) fn web_view_navigate(&self, child: &HChildWnd, url: &str) {
+        self.with_web_view_state(child, |state| {
+            state.url = Some(url.to_owned());
+            state.html = None;
+            state.base_url = None;
+        });
+    }
+    pub(super) fn web_view_load_html(&self, child: &HChildWnd, html: &str, base_url: Option<&str>) {
+        self.with_web_view_state(child, |state| {
+            state.url = None;
+            state.html = Some(html.to_owned());
+            state.base_url = base_url.map(ToOwned::to_owned);
+        });
+    }
+    pub(super) fn web_view_eval_script(&self, child: &HChildWnd, script: &str) {
+        self.with_web_view_state(child, |state| {
+            state.scripts.push(script.to_owned());
+        });
+    }
+
+    fn with_web_view_state(&self, child: &HChildWnd, f: impl FnOnce(&mut wmapi::WebViewState)) {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(web_view) = &mut state.child_wnds[child.ptr].web_view {
+            f(&mut web_view.state);
+        }
+    }
+
     pub(super) fn new_layer(&self, attrs: LayerAttrs) -> HLayer {
         let mut state = self.state.borrow_mut();
 
@@ -203,9 +353,53 @@ impl Screen {
     pub(super) fn remove_layer(&self, layer: &HLayer) {
         let mut state = self.state.borrow_mut();
 
+        state.swapchains.remove(layer);
         state.sr_scrn.remove_layer(&layer.sr_layer);
     }
 
+    pub(super) fn set_layer_swapchain(&self, layer: &HLayer, size: Option<[u32; 2]>) {
+        let mut state = self.state.borrow_mut();
+
+        if let Some(size) = size {
+            state.swapchains.insert(layer.clone(), size);
+        } else if state.swapchains.remove(layer).is_some() {
+            state.sr_scrn.set_layer_attr(
+                &layer.sr_layer,
+                iface::LayerAttrs {
+                    contents: Some(None),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    pub(super) fn present_layer_swapchain(
+        &self,
+        layer: &HLayer,
+        render: &mut dyn FnMut(iface::SwapchainTarget<'_>),
+    ) {
+        let size = self.state.borrow().swapchains.get(layer).cloned();
+        let size = match size {
+            Some(size) if size[0] > 0 && size[1] > 0 => size,
+            _ => return,
+        };
+
+        // `render` may call `Wm`'s methods, so don't borrow `state` while
+        // calling it
+        let image = Bitmap::from_swapchain_render(size, render);
+
+        let mut state = self.state.borrow_mut();
+        if state.swapchains.contains_key(layer) {
+            state.sr_scrn.set_layer_attr(
+                &layer.sr_layer,
+                iface::LayerAttrs {
+                    contents: Some(Some(image.into())),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
     /// Implements `TestingWm::hwnds`.
     pub(super) fn hwnds(&self) -> Vec<HWnd> {
         let state = self.state.borrow();
@@ -220,6 +414,42 @@ impl Screen {
         state.wnds.get(hwnd.ptr).map(|wnd| wnd.attrs.clone())
     }
 
+    /// Implements `TestingWm::child_wnds`.
+    pub(super) fn child_wnds(&self, hwnd: &HWnd) -> Vec<HChildWnd> {
+        let state = self.state.borrow();
+
+        (state.child_wnds.ptr_iter())
+            .filter(|(_, child)| child.parent == *hwnd)
+            .map(|(ptr, _)| HChildWnd { ptr })
+            .collect()
+    }
+
+    /// Implements `TestingWm::child_wnd_bounds`.
+    pub(super) fn child_wnd_bounds(&self, child: &HChildWnd) -> Option<Box2<f32>> {
+        let state = self.state.borrow();
+
+        state.child_wnds.get(child.ptr).map(|child| child.bounds)
+    }
+
+    /// Implements `TestingWm::web_view_state`.
+    pub(super) fn web_view_state(&self, child: &HChildWnd) -> Option<wmapi::WebViewState> {
+        let state = self.state.borrow();
+
+        (state.child_wnds.get(child.ptr))
+            .and_then(|child| child.web_view.as_ref())
+            .map(|web_view| web_view.state.clone())
+    }
+
+    /// Get a `WebViewListener`. Panics if the child window has no web view.
+    fn web_view_listener(&self, child: &HChildWnd) -> Rc<dyn iface::WebViewListener<Wm>> {
+        let state = self.state.borrow();
+
+        let web_view = (state.child_wnds.get(child.ptr))
+            .and_then(|child| child.web_view.as_ref())
+            .expect("the child window has no web view");
+        Rc::clone(&web_view.listener)
+    }
+
     /// Get a `WndListener`.
     fn wnd_listener(&self, hwnd: &HWnd) -> Result<Rc<dyn iface::WndListener<Wm>>, BadHWndError> {
         let state = self.state.borrow();
@@ -237,6 +467,33 @@ impl Screen {
         listener.close_requested(wm, &hwnd.into());
     }
 
+    /// Implements `TestingWm::raise_web_view_navigation_started`.
+    pub(super) fn raise_web_view_navigation_started(&self, wm: Wm, child: &HChildWnd, url: &str) {
+        let listener = self.web_view_listener(child);
+
+        listener.navigation_started(wm, &child.into(), url);
+    }
+
+    /// Implements `TestingWm::raise_web_view_navigation_completed`.
+    pub(super) fn raise_web_view_navigation_completed(
+        &self,
+        wm: Wm,
+        child: &HChildWnd,
+        url: &str,
+        success: bool,
+    ) {
+        let listener = self.web_view_listener(child);
+
+        listener.navigation_completed(wm, &child.into(), url, success);
+    }
+
+    /// Implements `TestingWm::raise_web_view_message`.
+    pub(super) fn raise_web_view_message(&self, wm: Wm, child: &HChildWnd, message: &str) {
+        let listener = self.web_view_listener(child);
+
+        listener.message_received(wm, &child.into(), message);
+    }
+
     pub(super) fn raise_update_ready(&self, wm: Wm, hwnd: &HWnd) {
         let listener = self.wnd_listener(hwnd).unwrap();
 
@@ -276,6 +533,31 @@ impl Screen {
         listener.focus(wm, &hwnd.into());
     }
 
+    pub(super) fn system_appearance(&self) -> iface::SystemAppearance {
+        self.state.borrow().system_appearance
+    }
+
+    /// Implements `TestingWm::set_system_appearance`.
+    pub(super) fn set_system_appearance(&self, wm: Wm, appearance: iface::SystemAppearance) {
+        self.state.borrow_mut().system_appearance = appearance;
+
+        for hwnd in self.hwnds() {
+            // A listener may close windows
+            if let Ok(listener) = self.wnd_listener(&hwnd) {
+                listener.system_appearance_changed(wm, &(&hwnd).into());
+            }
+        }
+    }
+
+    pub(super) fn default_ui_direction(&self) -> iface::UiDirection {
+        self.state.borrow().default_ui_direction
+    }
+
+    /// Implements `TestingWm::set_default_ui_direction`.
+    pub(super) fn set_default_ui_direction(&self, dir: iface::UiDirection) {
+        self.state.borrow_mut().default_ui_direction = dir;
+    }
+
     /// Implements `TestingWm::read_wnd_snapshot`.
     pub(super) fn read_wnd_snapshot(&self, hwnd: &HWnd, out: &mut wmapi::WndSnapshot) {
         let mut state = self.state.borrow_mut();
@@ -329,6 +611,23 @@ impl Screen {
         out.data.extend(&wnd.img_data[..]);
     }
 
+    /// Implements `Wm::render_wnd_to_bitmap`.
+    pub(super) fn render_wnd_to_bitmap(&self, hwnd: &HWnd) -> Option<Bitmap> {
+        let mut snapshot = wmapi::WndSnapshot::new();
+        self.read_wnd_snapshot(hwnd, &mut snapshot);
+
+        let [size_w, size_h] = snapshot.size;
+        if size_w == 0 || size_h == 0 {
+            return None;
+        }
+
+        Some(Bitmap::from_argb_data(
+            snapshot.data.into_boxed_slice(),
+            [size_w as u32, size_h as u32],
+            snapshot.stride,
+        ))
+    }
+
     /// Implements `TestingWm::raise_mouse_motion`.
     pub(super) fn raise_mouse_motion(&self, wm: Wm, hwnd: &HWnd, loc: Point2<f32>) {
         let listener = self.wnd_listener(hwnd).unwrap();
@@ -343,6 +642,13 @@ impl Screen {
         listener.mouse_leave(wm, &hwnd.into());
     }
 
+    /// Implements `TestingWm::raise_mouse_relative_motion`.
+    pub(super) fn raise_mouse_relative_motion(&self, wm: Wm, hwnd: &HWnd, delta: Vector2<f32>) {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.mouse_relative_motion(wm, &hwnd.into(), delta);
+    }
+
     /// Implements `TestingWm::raise_mouse_drag`.
     pub(super) fn raise_mouse_drag(
         &self,
@@ -393,6 +699,66 @@ impl Screen {
         })
     }
 
+    /// Implements `TestingWm::raise_gesture`.
+    pub(super) fn raise_gesture(
+        &self,
+        wm: Wm,
+        hwnd: &HWnd,
+        loc: Point2<f32>,
+    ) -> Box<dyn wmapi::Gesture> {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        let inner = listener.gesture(wm, &hwnd.into(), loc);
+
+        Box::new(Gesture {
+            wm,
+            hwnd: hwnd.into(),
+            inner,
+        })
+    }
+
+    /// Implements `TestingWm::raise_touch_begin`.
+    pub(super) fn raise_touch_begin(&self, wm: Wm, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.touch_begin(wm, &hwnd.into(), touch)
+    }
+
+    /// Implements `TestingWm::raise_touch_move`.
+    pub(super) fn raise_touch_move(&self, wm: Wm, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.touch_move(wm, &hwnd.into(), touch)
+    }
+
+    /// Implements `TestingWm::raise_touch_end`.
+    pub(super) fn raise_touch_end(&self, wm: Wm, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.touch_end(wm, &hwnd.into(), touch)
+    }
+
+    /// Implements `TestingWm::raise_pen_down`.
+    pub(super) fn raise_pen_down(&self, wm: Wm, hwnd: &HWnd, pen: &iface::PenState) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.pen_down(wm, &hwnd.into(), pen)
+    }
+
+    /// Implements `TestingWm::raise_pen_move`.
+    pub(super) fn raise_pen_move(&self, wm: Wm, hwnd: &HWnd, pen: &iface::PenState) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.pen_move(wm, &hwnd.into(), pen)
+    }
+
+    /// Implements `TestingWm::raise_pen_up`.
+    pub(super) fn raise_pen_up(&self, wm: Wm, hwnd: &HWnd, pen: &iface::PenState) -> bool {
+        let listener = self.wnd_listener(hwnd).unwrap();
+
+        listener.pen_up(wm, &hwnd.into(), pen)
+    }
+
     /// Implements `TestingWm::translate_action`.
     pub(super) fn translate_action(
         &self,
@@ -416,11 +782,7 @@ impl Screen {
             &hwnd.into(),
             &mut EnumAccel(|accel_table| {
                 if action.is_none() {
-                    action = accel_table
-                        .testing
-                        .iter()
-                        .find(|binding| (binding.source, binding.pattern) == (source, pattern))
-                        .map(|binding| binding.action);
+                    action = SimulatedKeyEvent { source, pattern }.translate_accel(accel_table);
                 }
             }),
         );
@@ -468,7 +830,9 @@ fn layer_attrs_to_sr_layer_attrs(
 ) -> iface::LayerAttrs<Bitmap, swrast::HLayer<Bitmap>> {
     iface::LayerAttrs {
         transform: attrs.transform,
-        contents: attrs.contents,
+        contents: attrs
+            .contents
+            .map(|contents| contents.and_then(Bitmap::latch_layer_contents)),
         bounds: attrs.bounds,
         contents_center: attrs.contents_center,
         contents_scale: attrs.contents_scale,
@@ -481,6 +845,10 @@ fn layer_attrs_to_sr_layer_attrs(
         }),
         opacity: attrs.opacity,
         flags: attrs.flags,
+        corner_radius: attrs.corner_radius,
+        mask: attrs.mask,
+        shadow: attrs.shadow,
+        backdrop_blur_radius: attrs.backdrop_blur_radius,
     }
 }
 
@@ -494,8 +862,9 @@ impl wmapi::MouseDrag for MouseDrag {
     fn mouse_motion(&self, loc: Point2<f32>) {
         self.inner.mouse_motion(self.wm, &self.hwnd, loc)
     }
-    fn mouse_down(&self, loc: Point2<f32>, button: u8) {
-        self.inner.mouse_down(self.wm, &self.hwnd, loc, button)
+    fn mouse_down_with_click_count(&self, loc: Point2<f32>, button: u8, click_count: u32) {
+        self.inner
+            .mouse_down(self.wm, &self.hwnd, loc, button, click_count)
     }
     fn mouse_up(&self, loc: Point2<f32>, button: u8) {
         self.inner.mouse_up(self.wm, &self.hwnd, loc, button)
@@ -526,6 +895,27 @@ impl wmapi::ScrollGesture for ScrollGesture {
     }
 }
 
+struct Gesture {
+    wm: Wm,
+    hwnd: super::HWnd,
+    inner: Box<dyn iface::GestureListener<Wm>>,
+}
+
+impl wmapi::Gesture for Gesture {
+    fn magnify(&self, scale: f32) {
+        self.inner.magnify(self.wm, &self.hwnd, scale)
+    }
+    fn rotate(&self, angle: f32) {
+        self.inner.rotate(self.wm, &self.hwnd, angle)
+    }
+    fn end(&self) {
+        self.inner.end(self.wm, &self.hwnd)
+    }
+    fn cancel(&self) {
+        self.inner.cancel(self.wm, &self.hwnd)
+    }
+}
+
 struct SimulatedKeyEvent<'a> {
     source: &'a str,
     pattern: &'a str,
@@ -538,5 +928,52 @@ impl iface::KeyEvent<AccelTable> for SimulatedKeyEvent<'_> {
             .iter()
             .find(|binding| (binding.source, binding.pattern) == (self.source, self.pattern))
             .map(|binding| binding.action)
+            .or_else(|| {
+                let (modifiers, key) = self.parse_pattern()?;
+                accel_table
+                    .key_bindings
+                    .iter()
+                    .find(|binding| binding.key == key && binding.modifiers == modifiers)
+                    .map(|binding| binding.action)
+            })
+    }
+
+    fn key(&self) -> iface::Key {
+        self.parse_pattern()
+            .map_or(iface::Key::Unknown, |(_, key)| key)
+    }
+
+    fn modifiers(&self) -> iface::ModifierState {
+        self.parse_pattern()
+            .map_or(iface::ModifierState::empty(), |(modifiers, _)| modifiers)
+    }
+}
+
+impl SimulatedKeyEvent<'_> {
+    /// Parse `pattern` as a key pattern of `accel_table!` (e.g., `Ctrl+S`).
+    /// Returns `None` if `source` is not one of the sources using the
+    /// notation.
+    fn parse_pattern(&self) -> Option<(iface::ModifierState, iface::Key)> {
+        if !["windows", "macos", "gtk"].contains(&self.source) {
+            return None;
+        }
+
+        let prefixes = [
+            ("Shift+", iface::ModifierState::SHIFT),
+            ("Ctrl+", iface::ModifierState::CONTROL),
+            ("Alt+", iface::ModifierState::ALT),
+            ("Super+", iface::ModifierState::SUPER),
+        ];
+
+        let mut modifiers = iface::ModifierState::empty();
+        let mut pattern = self.pattern;
+        while let Some(&(prefix, flag)) = prefixes.iter().find(|(p, _)| pattern.starts_with(p)) {
+            modifiers |= flag;
+            pattern = &pattern[prefix.len()..];
+        }
+
+        let key = pattern.parse().unwrap_or(iface::Key::Unknown);
+
+        Some((modifiers, key))
     }
 }
@@ -0,0 +1,197 @@
+//! Implements `TestingWm::assert_snapshot_matches`.
+use cairo::{Format, ImageSurface};
+use std::{env, fs::File, path::Path};
+
+use super::wmapi::{SnapshotMatchOptions, WndSnapshot};
+
+/// When this environment variable is set to a non-empty value,
+/// `assert_snapshot_matches` overwrites golden images instead of comparing
+/// against them.
+const UPDATE_ENV_VAR: &str = "TCW3_UPDATE_SNAPSHOTS";
+
+pub(super) fn assert_snapshot_matches(
+    actual: &WndSnapshot,
+    name: &str,
+    options: &SnapshotMatchOptions,
+) {
+    let path_for = |suffix: &str| options.golden_dir.join(format!("{}{}.png", name, suffix));
+    let golden_path = path_for("");
+    let actual_path = path_for(".actual");
+    let diff_path = path_for(".diff");
+
+    if env::var_os(UPDATE_ENV_VAR).map_or(false, |x| !x.is_empty()) {
+        write_png(actual, &golden_path);
+        return;
+    }
+
+    let golden = match read_png(&golden_path) {
+        Ok(x) => x,
+        Err(e) => {
+            write_png(actual, &actual_path);
+            panic!(
+                "Could not read the golden image {:?}: {}\n\
+                 The actual image was written to {:?}. \
+                 Set {}=1 to create the golden image.",
+                golden_path, e, actual_path, UPDATE_ENV_VAR
+            );
+        }
+    };
+
+    if golden.size != actual.size {
+        write_png(actual, &actual_path);
+        panic!(
+            "Snapshot {:?} has an unexpected size: expected {:?}, got {:?}\n\
+             The actual image was written to {:?}.",
+            name, golden.size, actual.size, actual_path
+        );
+    }
+
+    let (num_mismatches, diff) = diff_snapshots(&golden, actual, options.tolerance);
+
+    if num_mismatches > 0 {
+        write_png(actual, &actual_path);
+        write_png(&diff, &diff_path);
+        panic!(
+            "Snapshot {:?} does not match the golden image {:?} \
+             ({} pixel(s) exceed the tolerance of {})\n\
+             The actual image and the difference were written to {:?} and {:?}.",
+            name, golden_path, num_mismatches, options.tolerance, actual_path, diff_path
+        );
+    }
+}
+
+/// Compare two images of the same size. Returns the number of mismatching
+/// pixels and an image highlighting them in red.
+fn diff_snapshots(
+    expected: &WndSnapshot,
+    actual: &WndSnapshot,
+    tolerance: u8,
+) -> (usize, WndSnapshot) {
+    debug_assert_eq!(expected.size, actual.size);
+
+    let [size_w, size_h] = actual.size;
+    let stride = size_w * 4;
+    let mut diff = WndSnapshot {
+        size: actual.size,
+        data: vec![0; stride * size_h],
+        stride,
+    };
+    let mut num_mismatches = 0;
+
+    // Premultiplied opaque red in `ARgb32`
+    let mismatch_pixel = 0xffff0000u32.to_ne_bytes();
+
+    for y in 0..size_h {
+        let row_e = &expected.data[y * expected.stride..][..stride];
+        let row_a = &actual.data[y * actual.stride..][..stride];
+        let row_d = &mut diff.data[y * stride..][..stride];
+
+        for ((pe, pa), pd) in (row_e.chunks_exact(4))
+            .zip(row_a.chunks_exact(4))
+            .zip(row_d.chunks_exact_mut(4))
+        {
+            let mismatch = pe
+                .iter()
+                .zip(pa.iter())
+                .any(|(&e, &a)| (e as i16 - a as i16).abs() > tolerance as i16);
+
+            if mismatch {
+                num_mismatches += 1;
+                pd.copy_from_slice(&mismatch_pixel);
+            } else {
+                // Fade matching pixels so that mismatches stand out. Scaling
+                // all channels uniformly keeps them premultiplied.
+                for (d, &a) in pd.iter_mut().zip(pa.iter()) {
+                    *d = a / 4;
+                }
+            }
+        }
+    }
+
+    (num_mismatches, diff)
+}
+
+fn write_png(ss: &WndSnapshot, path: &Path) {
+    let surface = ImageSurface::create_for_data(
+        ss.data.clone().into_boxed_slice(),
+        Format::ARgb32,
+        ss.size[0] as i32,
+        ss.size[1] as i32,
+        ss.stride as i32,
+    )
+    .expect("failed to create a Cairo surface");
+
+    let mut file =
+        File::create(path).unwrap_or_else(|e| panic!("Could not create {:?}: {}", path, e));
+
+    surface
+        .write_to_png(&mut file)
+        .unwrap_or_else(|e| panic!("Could not write {:?}: {:?}", path, e));
+}
+
+fn read_png(path: &Path) -> Result<WndSnapshot, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+
+    let mut surface = ImageSurface::create_from_png(&mut file).map_err(|e| format!("{:?}", e))?;
+
+    let size = [surface.get_width() as usize, surface.get_height() as usize];
+    let stride = surface.get_stride() as usize;
+    let format = surface.get_format();
+
+    let mut data = surface.get_data().map_err(|e| format!("{:?}", e))?.to_vec();
+
+    match format {
+        Format::ARgb32 => {}
+        Format::Rgb24 => {
+            // The upper 8 bits are unused. Make them opaque.
+            for y in 0..size[1] {
+                for pixel in data[y * stride..][..size[0] * 4].chunks_exact_mut(4) {
+                    let value = u32::from_ne_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                    pixel.copy_from_slice(&(value | 0xff000000).to_ne_bytes());
+                }
+            }
+        }
+        _ => return Err(format!("unsupported pixel format: {:?}", format)),
+    }
+
+    Ok(WndSnapshot { size, data, stride })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot_from_pixels(size: [usize; 2], pixels: &[u32]) -> WndSnapshot {
+        WndSnapshot {
+            size,
+            data: pixels
+                .iter()
+                .flat_map(|p| p.to_ne_bytes().to_vec())
+                .collect(),
+            stride: size[0] * 4,
+        }
+    }
+
+    #[test]
+    fn diff_within_tolerance() {
+        let e = snapshot_from_pixels([2, 1], &[0xff102030, 0x00000000]);
+        let a = snapshot_from_pixels([2, 1], &[0xff121f30, 0x01000001]);
+
+        assert_eq!(diff_snapshots(&e, &a, 2).0, 0);
+        assert_eq!(diff_snapshots(&e, &a, 1).0, 1);
+        assert_eq!(diff_snapshots(&e, &a, 0).0, 2);
+    }
+
+    #[test]
+    fn diff_image() {
+        let e = snapshot_from_pixels([2, 1], &[0xff000000, 0xffffffff]);
+        let a = snapshot_from_pixels([2, 1], &[0xff000000, 0xff000000]);
+
+        let (num_mismatches, diff) = diff_snapshots(&e, &a, 0);
+        assert_eq!(num_mismatches, 1);
+        assert_eq!(
+            diff.data,
+            snapshot_from_pixels([2, 1], &[0x3f000000, 0xffff0000]).data
+        );
+    }
+}
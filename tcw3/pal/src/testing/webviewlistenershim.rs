@@ -0,0 +1,41 @@
+use super::{native, HChildWnd, HChildWndInner, Wm};
+use crate::iface;
+
+/// Wraps `WebViewListener<Wm>` to create a `WebViewListener<native::Wm>`.
+pub struct NativeWebViewListener(pub Box<dyn iface::WebViewListener<Wm>>);
+
+fn from_native_child_wnd(child: &native::HChildWnd) -> HChildWnd {
+    HChildWnd {
+        inner: HChildWndInner::Native(child.clone()),
+    }
+}
+
+impl iface::WebViewListener<native::Wm> for NativeWebViewListener {
+    fn navigation_started(&self, wm: native::Wm, child: &native::HChildWnd, url: &str) {
+        self.0
+            .navigation_started(Wm::from_native_wm(wm), &from_native_child_wnd(child), url)
+    }
+
+    fn navigation_completed(
+        &self,
+        wm: native::Wm,
+        child: &native::HChildWnd,
+        url: &str,
+        success: bool,
+    ) {
+        self.0.navigation_completed(
+            Wm::from_native_wm(wm),
+            &from_native_child_wnd(child),
+            url,
+            success,
+        )
+    }
+
+    fn message_received(&self, wm: native::Wm, child: &native::HChildWnd, message: &str) {
+        self.0.message_received(
+            Wm::from_native_wm(wm),
+            &from_native_child_wnd(child),
+            message,
+        )
+    }
+}
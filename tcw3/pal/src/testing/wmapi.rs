@@ -1,7 +1,11 @@
+use cggeom::Box2;
 use cgmath::{Point2, Vector2};
-use std::time::Instant;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-use crate::{iface, HTextInputCtx, HWnd};
+use crate::{iface, HChildWnd, HTextInputCtx, HWnd};
 
 /// Provides access to a virtual environment.
 ///
@@ -23,14 +27,69 @@ pub trait TestingWm: 'static {
 
     /// Process events until at least one event is processed or
     /// until the specified instant.
+    ///
+    /// `till` is measured by the real-time clock. Delayed invocations are not
+    /// processed by waiting if the virtual clock is active (see
+    /// [`advance_time`]).
+    ///
+    /// [`advance_time`]: TestingWm::advance_time
     fn step_until(&self, till: Instant);
 
+    /// Get the current time of the clock used for delayed invocations
+    /// (`Wm::invoke_after`).
+    ///
+    /// This is the real-time clock (`Instant::now()`) until [`advance_time`]
+    /// is called for the first time.
+    ///
+    /// [`advance_time`]: TestingWm::advance_time
+    fn now(&self) -> Instant;
+
+    /// Switch to the virtual clock and advance it by the specified duration.
+    ///
+    /// Once the virtual clock is activated, it only advances when this method
+    /// is called, making tests involving timing deterministic. The virtual
+    /// clock is reset to the real-time clock when the test ends.
+    ///
+    /// Delayed invocations (`Wm::invoke_after`) that become due are run in a
+    /// chronological order, with the clock set to the time at which each of
+    /// them is run. Pending `!Send` dispatches (`Wm::invoke`) are flushed
+    /// before and after each step, in the same way as [`step_unsend`].
+    ///
+    /// While the virtual clock is active, `update_ready` events requested by
+    /// `Wm::request_update_ready_wnd` are delivered at the simulated display
+    /// refresh rate (60Hz), so animations progress along with the clock.
+    ///
+    /// [`step_unsend`]: TestingWm::step_unsend
+    fn advance_time(&self, duration: Duration);
+
     /// Get a list of currently open windows.
     fn hwnds(&self) -> Vec<HWnd>;
 
     /// Get the attributes of a window.
     fn wnd_attrs(&self, hwnd: &HWnd) -> Option<WndAttrs>;
 
+    /// Get a list of the child windows of a window, created by
+    /// `Wm::new_child_wnd`.
+    fn child_wnds(&self, hwnd: &HWnd) -> Vec<HChildWnd>;
+
+    /// Get the bounds of a child window. Returns `None` if the child window
+    /// has been removed.
+    fn child_wnd_bounds(&self, child: &HChildWnd) -> Option<Box2<f32>>;
+
+    /// Get the state of the web view attached to a child window by
+    /// `Wm::attach_web_view`. Returns `None` if the child window has no web
+    /// view or has been removed.
+    fn web_view_state(&self, child: &HChildWnd) -> Option<WebViewState>;
+
+    /// Trigger `WebViewListener::navigation_started`.
+    fn raise_web_view_navigation_started(&self, child: &HChildWnd, url: &str);
+
+    /// Trigger `WebViewListener::navigation_completed`.
+    fn raise_web_view_navigation_completed(&self, child: &HChildWnd, url: &str, success: bool);
+
+    /// Trigger `WebViewListener::message_received`.
+    fn raise_web_view_message(&self, child: &HChildWnd, message: &str);
+
     /// Trigger `WndListener::close_requested`.
     fn raise_close_requested(&self, hwnd: &HWnd);
 
@@ -50,15 +109,57 @@ pub trait TestingWm: 'static {
     /// Set the focus state of a given window and trigger `WndListener::focus`.
     fn set_wnd_focused(&self, hwnd: &HWnd, focused: bool);
 
+    /// Set the value returned by `Wm::system_appearance` and trigger
+    /// `WndListener::system_appearance_changed` for every open window.
+    fn set_system_appearance(&self, appearance: iface::SystemAppearance);
+
+    /// Set the value returned by `Wm::default_ui_direction`.
+    fn set_default_ui_direction(&self, dir: iface::UiDirection);
+
+    /// Set the function that decides the outcome of file dialogs shown by
+    /// `Wm::show_file_dialog`.
+    ///
+    /// The function is called when a file dialog is dismissed, and its return
+    /// value is passed to the completion handler. If no function is set (this
+    /// is the default), every file dialog is cancelled.
+    fn set_file_dialog_handler(&self, handler: Option<Box<FileDialogHandler>>);
+
+    /// Get the notifications shown by `Wm::show_notification` so far, in the
+    /// order they were shown.
+    fn notifications(&self) -> Vec<iface::NotificationAttrs>;
+
+    /// Simulate the activation of a notification and call its completion
+    /// handler with `action`. `i` is an index into the list returned by
+    /// [`TestingWm::notifications`].
+    ///
+    /// Panics if the notification has already been activated.
+    fn activate_notification(&self, i: usize, action: Option<usize>);
+
     /// Render the content of a given window and update `out` with it.
     fn read_wnd_snapshot(&self, hwnd: &HWnd, out: &mut WndSnapshot);
 
+    /// Compare the contents of a window against the golden image
+    /// `<name>.png` stored in `options.golden_dir` and panic if they don't
+    /// match.
+    ///
+    /// On failure, the actual image and an image highlighting mismatching
+    /// pixels are written to `<name>.actual.png` and `<name>.diff.png` in the
+    /// same directory, respectively.
+    ///
+    /// If the environment variable `TCW3_UPDATE_SNAPSHOTS` is set to a
+    /// non-empty value, this method overwrites the golden image with the
+    /// window's contents instead of comparing them.
+    fn assert_snapshot_matches(&self, hwnd: &HWnd, name: &str, options: &SnapshotMatchOptions);
+
     /// Trigger `WndListener::mouse_motion`.
     fn raise_mouse_motion(&self, hwnd: &HWnd, loc: Point2<f32>);
 
     /// Trigger `WndListener::mouse_leave`.
     fn raise_mouse_leave(&self, hwnd: &HWnd);
 
+    /// Trigger `WndListener::mouse_relative_motion`.
+    fn raise_mouse_relative_motion(&self, hwnd: &HWnd, delta: Vector2<f32>);
+
     /// Trigger `WndListener::mouse_drag`.
     fn raise_mouse_drag(&self, hwnd: &HWnd, loc: Point2<f32>, button: u8) -> Box<dyn MouseDrag>;
 
@@ -70,6 +171,27 @@ pub trait TestingWm: 'static {
     /// Trigger `WndListener::scroll_gesture`.
     fn raise_scroll_gesture(&self, hwnd: &HWnd, loc: Point2<f32>) -> Box<dyn ScrollGesture>;
 
+    /// Trigger `WndListener::gesture`.
+    fn raise_gesture(&self, hwnd: &HWnd, loc: Point2<f32>) -> Box<dyn Gesture>;
+
+    /// Trigger `WndListener::touch_begin`.
+    fn raise_touch_begin(&self, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool;
+
+    /// Trigger `WndListener::touch_move`.
+    fn raise_touch_move(&self, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool;
+
+    /// Trigger `WndListener::touch_end`.
+    fn raise_touch_end(&self, hwnd: &HWnd, touch: &iface::TouchPoint) -> bool;
+
+    /// Trigger `WndListener::pen_down`.
+    fn raise_pen_down(&self, hwnd: &HWnd, pen: &iface::PenState) -> bool;
+
+    /// Trigger `WndListener::pen_move`.
+    fn raise_pen_move(&self, hwnd: &HWnd, pen: &iface::PenState) -> bool;
+
+    /// Trigger `WndListener::pen_up`.
+    fn raise_pen_up(&self, hwnd: &HWnd, pen: &iface::PenState) -> bool;
+
     /// Get the list of currently active text input contexts.
     fn active_text_input_ctxs(&self) -> Vec<HTextInputCtx>;
 
@@ -115,16 +237,39 @@ pub trait TestingWm: 'static {
     fn simulate_key(&self, hwnd: &HWnd, source: &str, pattern: &str);
 }
 
+/// A function passed to [`TestingWm::set_file_dialog_handler`].
+pub type FileDialogHandler = dyn FnMut(&iface::FileDialogOptions) -> Option<Vec<PathBuf>>;
+
 /// A snapshot of window attributes.
 #[derive(Debug, Clone)]
 pub struct WndAttrs {
     pub size: [u32; 2],
     pub min_size: [u32; 2],
     pub max_size: [u32; 2],
+    pub position: [i32; 2],
+    pub maximized: bool,
     pub flags: iface::WndFlags,
     pub caption: String,
     pub visible: bool,
     pub cursor_shape: iface::CursorShape,
+    pub progress: Option<iface::ProgressState>,
+    pub pointer_grab: bool,
+}
+
+/// The state of a web view attached by `Wm::attach_web_view`.
+///
+/// The testing backend doesn't load web pages. It only records the requests
+/// made to the web view.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebViewState {
+    /// The URL specified by the last call to `Wm::web_view_navigate`.
+    pub url: Option<String>,
+    /// The document specified by the last call to `Wm::web_view_load_html`.
+    pub html: Option<String>,
+    /// The base URL specified by the last call to `Wm::web_view_load_html`.
+    pub base_url: Option<String>,
+    /// The scripts passed to `Wm::web_view_eval_script` so far.
+    pub scripts: Vec<String>,
 }
 
 /// Provides an interface for simulating a mouse drag geature.
@@ -135,8 +280,12 @@ pub struct WndAttrs {
 pub trait MouseDrag {
     /// Trigger `MouseDragListener::mouse_motion`.
     fn mouse_motion(&self, _loc: Point2<f32>);
-    /// Trigger `MouseDragListener::mouse_down`.
-    fn mouse_down(&self, _loc: Point2<f32>, _button: u8);
+    /// Trigger `MouseDragListener::mouse_down` with `click_count == 1`.
+    fn mouse_down(&self, loc: Point2<f32>, button: u8) {
+        self.mouse_down_with_click_count(loc, button, 1);
+    }
+    /// Trigger `MouseDragListener::mouse_down` with the specified click count.
+    fn mouse_down_with_click_count(&self, _loc: Point2<f32>, _button: u8, _click_count: u32);
     /// Trigger `MouseDragListener::mouse_up`.
     fn mouse_up(&self, _loc: Point2<f32>, _button: u8);
     /// Trigger `MouseDragListener::cancel`.
@@ -159,6 +308,22 @@ pub trait ScrollGesture {
     fn cancel(&self);
 }
 
+/// Provides an interface for simulating a magnification or rotation gesture.
+///
+/// See [`GestureListener`] for the semantics of the methods.
+///
+/// [`GestureListener`]: crate::iface::GestureListener
+pub trait Gesture {
+    /// Trigger `GestureListener::magnify`.
+    fn magnify(&self, scale: f32);
+    /// Trigger `GestureListener::rotate`.
+    fn rotate(&self, angle: f32);
+    /// Trigger `GestureListener::end`.
+    fn end(&self);
+    /// Trigger `GestureListener::cancel`.
+    fn cancel(&self);
+}
+
 /// An RGBA8 image created from the contents of a window.
 #[derive(Debug, Clone, Default)]
 pub struct WndSnapshot {
@@ -177,6 +342,35 @@ impl WndSnapshot {
     }
 }
 
+/// Options for [`TestingWm::assert_snapshot_matches`].
+#[derive(Debug, Clone)]
+pub struct SnapshotMatchOptions {
+    /// The directory containing golden images.
+    pub golden_dir: PathBuf,
+    /// The maximum difference allowed for each channel of a pixel.
+    ///
+    /// A small non-zero value can be used to accommodate subtle differences
+    /// in anti-aliasing and text rendering between environments. Defaults to
+    /// `0`.
+    pub tolerance: u8,
+}
+
+impl SnapshotMatchOptions {
+    /// Construct a `SnapshotMatchOptions` with the specified golden image
+    /// directory and the default tolerance.
+    pub fn new(golden_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            golden_dir: golden_dir.into(),
+            tolerance: 0,
+        }
+    }
+
+    /// Update `tolerance`, returning the modified `self`.
+    pub fn with_tolerance(self, tolerance: u8) -> Self {
+        Self { tolerance, ..self }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ActionBinding {
     pub source: &'static str,
@@ -47,6 +47,10 @@ impl iface::WndListener<native::Wm> for NativeWndListener {
         forward!(self.0, dpi_scale_changed, [wm: wm], [hwnd: hwnd])
     }
 
+    fn system_appearance_changed(&self, wm: native::Wm, hwnd: &native::HWnd) {
+        forward!(self.0, system_appearance_changed, [wm: wm], [hwnd: hwnd])
+    }
+
     fn key_down(
         &self,
         wm: native::Wm,
@@ -75,6 +79,10 @@ impl iface::WndListener<native::Wm> for NativeWndListener {
         forward!(self.0, mouse_leave, [wm: wm], [hwnd: hwnd])
     }
 
+    fn mouse_relative_motion(&self, wm: native::Wm, hwnd: &native::HWnd, delta: Vector2<f32>) {
+        forward!(self.0, mouse_relative_motion, [wm: wm], [hwnd: hwnd], delta)
+    }
+
     fn nc_hit_test(&self, wm: native::Wm, hwnd: &native::HWnd, loc: Point2<f32>) -> iface::NcHit {
         forward!(self.0, nc_hit_test, [wm: wm], [hwnd: hwnd], loc)
     }
@@ -134,6 +142,41 @@ impl iface::WndListener<native::Wm> for NativeWndListener {
 
         Box::new(NativeScrollListener(scroll_listener))
     }
+
+    fn gesture(
+        &self,
+        wm: native::Wm,
+        hwnd: &native::HWnd,
+        loc: Point2<f32>,
+    ) -> Box<dyn iface::GestureListener<native::Wm>> {
+        let gesture_listener = forward!(self.0, gesture, [wm: wm], [hwnd: hwnd], loc);
+
+        Box::new(NativeGestureListener(gesture_listener))
+    }
+
+    fn touch_begin(&self, wm: native::Wm, hwnd: &native::HWnd, touch: &iface::TouchPoint) -> bool {
+        forward!(self.0, touch_begin, [wm: wm], [hwnd: hwnd], touch)
+    }
+
+    fn touch_move(&self, wm: native::Wm, hwnd: &native::HWnd, touch: &iface::TouchPoint) -> bool {
+        forward!(self.0, touch_move, [wm: wm], [hwnd: hwnd], touch)
+    }
+
+    fn touch_end(&self, wm: native::Wm, hwnd: &native::HWnd, touch: &iface::TouchPoint) -> bool {
+        forward!(self.0, touch_end, [wm: wm], [hwnd: hwnd], touch)
+    }
+
+    fn pen_down(&self, wm: native::Wm, hwnd: &native::HWnd, pen: &iface::PenState) -> bool {
+        forward!(self.0, pen_down, [wm: wm], [hwnd: hwnd], pen)
+    }
+
+    fn pen_move(&self, wm: native::Wm, hwnd: &native::HWnd, pen: &iface::PenState) -> bool {
+        forward!(self.0, pen_move, [wm: wm], [hwnd: hwnd], pen)
+    }
+
+    fn pen_up(&self, wm: native::Wm, hwnd: &native::HWnd, pen: &iface::PenState) -> bool {
+        forward!(self.0, pen_up, [wm: wm], [hwnd: hwnd], pen)
+    }
 }
 
 /// Wraps `InterpretEventCtx<native::AccelTable>` to create a `InterpretEventCtx<AccelTable>`.
@@ -152,6 +195,14 @@ impl iface::KeyEvent<AccelTable> for TestingKeyEvent<'_> {
     fn translate_accel(&self, accel_table: &AccelTable) -> Option<iface::ActionId> {
         self.0.translate_accel(&accel_table.native)
     }
+
+    fn key(&self) -> iface::Key {
+        self.0.key()
+    }
+
+    fn modifiers(&self) -> iface::ModifierState {
+        self.0.modifiers()
+    }
 }
 
 /// Wraps `MouseDragListener<Wm>` to create a `MouseDragListener<native::Wm>`.
@@ -162,8 +213,23 @@ impl iface::MouseDragListener<native::Wm> for NativeMouseDragListener {
         forward!(self.0, mouse_motion, [wm: wm], [hwnd: hwnd], loc)
     }
 
-    fn mouse_down(&self, wm: native::Wm, hwnd: &native::HWnd, loc: Point2<f32>, button: u8) {
-        forward!(self.0, mouse_down, [wm: wm], [hwnd: hwnd], loc, button)
+    fn mouse_down(
+        &self,
+        wm: native::Wm,
+        hwnd: &native::HWnd,
+        loc: Point2<f32>,
+        button: u8,
+        click_count: u32,
+    ) {
+        forward!(
+            self.0,
+            mouse_down,
+            [wm: wm],
+            [hwnd: hwnd],
+            loc,
+            button,
+            click_count
+        )
     }
 
     fn mouse_up(&self, wm: native::Wm, hwnd: &native::HWnd, loc: Point2<f32>, button: u8) {
@@ -201,3 +267,24 @@ impl iface::ScrollListener<native::Wm> for NativeScrollListener {
         forward!(self.0, cancel, [wm: wm], [hwnd: hwnd])
     }
 }
+
+/// Wraps `GestureListener<Wm>` to create a `GestureListener<native::Wm>`.
+struct NativeGestureListener(Box<dyn iface::GestureListener<Wm>>);
+
+impl iface::GestureListener<native::Wm> for NativeGestureListener {
+    fn magnify(&self, wm: native::Wm, hwnd: &native::HWnd, scale: f32) {
+        forward!(self.0, magnify, [wm: wm], [hwnd: hwnd], scale)
+    }
+
+    fn rotate(&self, wm: native::Wm, hwnd: &native::HWnd, angle: f32) {
+        forward!(self.0, rotate, [wm: wm], [hwnd: hwnd], angle)
+    }
+
+    fn end(&self, wm: native::Wm, hwnd: &native::HWnd) {
+        forward!(self.0, end, [wm: wm], [hwnd: hwnd])
+    }
+
+    fn cancel(&self, wm: native::Wm, hwnd: &native::HWnd) {
+        forward!(self.0, cancel, [wm: wm], [hwnd: hwnd])
+    }
+}
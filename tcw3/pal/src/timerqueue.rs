@@ -82,7 +82,18 @@ impl<T> TimerQueue<T> {
         self.core.len() == 0
     }
 
+    #[allow(dead_code)]
     pub fn insert(&mut self, delay: Range<Duration>, payload: T) -> Result<HTask, CapacityError> {
+        self.insert_at(Instant::now(), delay, payload)
+    }
+
+    /// Like `insert`, but uses the specified instant as the current time.
+    pub fn insert_at(
+        &mut self,
+        now: Instant,
+        delay: Range<Duration>,
+        payload: T,
+    ) -> Result<HTask, CapacityError> {
         // Allocate a task ID
         let id = self.next_id;
         let new_next_id = self
@@ -91,7 +102,7 @@ impl<T> TimerQueue<T> {
             .expect("Task ID exhausted");
         self.next_id = new_next_id;
 
-        let offset = self.origin.elapsed();
+        let offset = now.saturating_duration_since(self.origin);
 
         // Convert `Duration`s to `FixTime`s
         let time: Range<FixTime> = map_range(delay, |dur| (dur + offset).into());
@@ -114,9 +125,20 @@ impl<T> TimerQueue<T> {
         }
     }
 
+    #[allow(dead_code)]
     pub fn drain_runnable_tasks(&mut self) -> impl Iterator<Item = (HTask, T)> + '_ {
+        self.drain_runnable_tasks_at(Instant::now())
+    }
+
+    /// Like `drain_runnable_tasks`, but uses the specified instant as the
+    /// current time.
+    pub fn drain_runnable_tasks_at(
+        &mut self,
+        now: Instant,
+    ) -> impl Iterator<Item = (HTask, T)> + '_ {
+        let offset = now.saturating_duration_since(self.origin);
         self.core
-            .drain_runnable_tasks(self.origin.elapsed().into())
+            .drain_runnable_tasks(offset.into())
             .map(|(htask_core, (id, payload))| (HTask::new(htask_core, id), payload))
     }
 
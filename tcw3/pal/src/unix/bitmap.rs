@@ -1,8 +1,9 @@
 use cairo::{Context, ImageSurface};
+use cggeom::{prelude::*, Box2};
 use cgmath::{Matrix3, Point2};
-use std::{cell::UnsafeCell, sync::Arc};
+use std::{cell::UnsafeCell, f64::consts::PI, sync::Arc};
 
-use super::super::{iface, swrast};
+use super::super::{exttex::ExternalTextureFrame, iface, swrast};
 use super::text::TextLayout;
 
 #[derive(Debug, Clone)]
@@ -12,11 +13,93 @@ pub struct Bitmap {
 
 #[derive(Debug)]
 struct BitmapInner {
-    data: Box<[u8]>,
+    data: BitmapData,
     size: [u32; 2],
     stride: usize,
 }
 
+#[derive(Debug)]
+enum BitmapData {
+    Owned(Box<[u8]>),
+    /// A frame of `ExternalTexture`, which is shared to avoid copying
+    ExternalTexture(ExternalTextureFrame),
+}
+
+impl std::ops::Deref for BitmapData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            BitmapData::Owned(data) => data,
+            BitmapData::ExternalTexture(frame) => frame.data(),
+        }
+    }
+}
+
+impl Bitmap {
+    /// Construct a `Bitmap` from premultiplied ARGB8888 pixel data, which is
+    /// the format used by `cairo::Format::ARgb32` and `swrast`.
+    pub(crate) fn from_argb_data(data: Box<[u8]>, size: [u32; 2], stride: usize) -> Self {
+        assert!(stride >= size[0] as usize * 4);
+        assert!(data.len() >= stride * size[1] as usize);
+
+        Self {
+            inner: Arc::new(BitmapInner {
+                data: BitmapData::Owned(data),
+                size,
+                stride,
+            }),
+        }
+    }
+
+    /// Construct a `Bitmap` referencing a frame of `ExternalTexture` without
+    /// copying. The frame's BGRA8888 layout matches premultiplied ARGB8888 on
+    /// little-endian hosts.
+    pub(crate) fn from_external_texture_frame(frame: ExternalTextureFrame) -> Self {
+        let size = frame.size();
+        let stride = frame.stride();
+        Self {
+            inner: Arc::new(BitmapInner {
+                data: BitmapData::ExternalTexture(frame),
+                size,
+                stride,
+            }),
+        }
+    }
+
+    /// Latch `LayerContents::ExternalTexture` into a `Bitmap` because `swrast`
+    /// only accepts bitmaps. Returns `None` if the texture has no frames yet.
+    pub(crate) fn latch_layer_contents(
+        contents: iface::LayerContents<Self>,
+    ) -> Option<iface::LayerContents<Self>> {
+        match contents {
+            iface::LayerContents::Bitmap(_) => Some(contents),
+            iface::LayerContents::ExternalTexture(tex) => tex
+                .latch()
+                .map(|frame| Self::from_external_texture_frame(frame).into()),
+        }
+    }
+
+    /// Construct a `Bitmap` by having `render` draw into a cleared image
+    /// buffer. This is how the swrast-based backends implement
+    /// `Wm::present_layer_swapchain`.
+    pub(crate) fn from_swapchain_render(
+        size: [u32; 2],
+        render: &mut dyn FnMut(iface::SwapchainTarget<'_>),
+    ) -> Self {
+        let stride = size[0] as usize * 4;
+        let mut data = vec![0u8; stride * size[1] as usize].into_boxed_slice();
+
+        render(iface::SwapchainTarget::Memory {
+            data: &mut data,
+            stride,
+            size,
+        });
+
+        Self::from_argb_data(data, size, stride)
+    }
+}
+
 impl iface::Bitmap for Bitmap {
     fn size(&self) -> [u32; 2] {
         self.inner.size
@@ -139,7 +222,7 @@ impl iface::BitmapBuilder for BitmapBuilder {
 
         Bitmap {
             inner: Arc::new(BitmapInner {
-                data,
+                data: BitmapData::Owned(data),
                 size: self.size,
                 stride: self.stride,
             }),
@@ -198,6 +281,33 @@ impl iface::Canvas for BitmapBuilder {
         self.cairo_ctx
             .curve_to(cp1.x, cp1.y, cp2.x, cp2.y, p2.x, p2.y);
     }
+    fn ellipse(&mut self, bx: Box2<f32>) {
+        let size = bx.size();
+        if size.x <= 0.0 || size.y <= 0.0 {
+            // `scale` would make the CTM non-invertible
+            return;
+        }
+
+        let center = bx.mid();
+        self.cairo_ctx.save();
+        self.cairo_ctx.translate(center.x as f64, center.y as f64);
+        self.cairo_ctx
+            .scale(size.x as f64 * 0.5, size.y as f64 * 0.5);
+        self.cairo_ctx.new_sub_path();
+        self.cairo_ctx.arc(0.0, 0.0, 1.0, 0.0, 2.0 * PI);
+        self.cairo_ctx.close_path();
+        self.cairo_ctx.restore();
+    }
+    fn arc_to(&mut self, center: Point2<f32>, radius: f32, start_angle: f32, end_angle: f32) {
+        let (x, y, r) = (center.x as f64, center.y as f64, radius as f64);
+        if end_angle >= start_angle {
+            self.cairo_ctx
+                .arc(x, y, r, start_angle as f64, end_angle as f64);
+        } else {
+            self.cairo_ctx
+                .arc_negative(x, y, r, start_angle as f64, end_angle as f64);
+        }
+    }
     fn fill(&mut self) {
         let col = self.state_top.state.fill_col;
         self.cairo_ctx
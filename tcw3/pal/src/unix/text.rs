@@ -9,7 +9,7 @@ use std::{
 };
 use unicount::{num_scalars_in_utf8_str, str_next, str_prev};
 
-use super::super::iface;
+use super::super::{emoji, iface};
 
 type CharStyleAttrs = iface::CharStyleAttrs<CharStyle>;
 
@@ -145,16 +145,36 @@ impl TextLayout {
 impl iface::TextLayout for TextLayout {
     type CharStyle = CharStyle;
 
-    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+    fn from_text_with_direction(
+        text: &str,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+        direction: iface::ParagraphDirection,
+    ) -> Self {
         let font_map = pangocairo::FontMap::get_default().expect("failed to get a Pango font map");
 
         let ctx = font_map
             .create_context()
             .expect("failed to create pango context");
 
+        // Pango determines the direction of each paragraph by default
+        // (`auto_dir`). The context's base direction is used if it's disabled.
+        let base_dir = match direction {
+            iface::ParagraphDirection::Auto => None,
+            iface::ParagraphDirection::Ltr => Some(pango::Direction::Ltr),
+            iface::ParagraphDirection::Rtl => Some(pango::Direction::Rtl),
+        };
+        if let Some(base_dir) = base_dir {
+            ctx.set_base_dir(base_dir);
+        }
+
         // Create `Layout`
         let layout = pango::Layout::new(&ctx);
 
+        if base_dir.is_some() {
+            layout.set_auto_dir(false);
+        }
+
         layout.set_font_description(Some(&style.pango_font_desc.inner));
 
         if let Some(x) = width {
@@ -255,6 +275,38 @@ impl iface::TextLayout for TextLayout {
         i
     }
 
+    fn hit_test(&self, point: Point2<f32>) -> iface::HitTestResult {
+        let [x, y] = point_to_pango_xy(point);
+
+        // `index` is the start of the grapheme cluster closest to the point.
+        // `trailing` is non-zero if the point is on the trailing half of the
+        // cluster (this is in the logical order).
+        let (_, index, trailing) = self.lock_layout().xy_to_index(x, y);
+        let start = index as usize;
+
+        if start >= self.text_len {
+            return iface::HitTestResult {
+                index: start..start,
+                trailing: false,
+                flags: iface::RunFlags::empty(),
+            };
+        }
+
+        let end = self.next_char_with_log_attr(start, true, LogAttrFlags::CURSOR_POSITION);
+
+        let flags = self
+            .run_metrics_of_range(start..end)
+            .first()
+            .map(|run| run.flags)
+            .unwrap_or_default();
+
+        iface::HitTestResult {
+            index: start..end,
+            trailing: trailing > 0,
+            flags,
+        }
+    }
+
     fn cursor_pos(&self, i: usize) -> [iface::Beam; 2] {
         let pango_layout = self.lock_layout();
 
@@ -448,6 +500,16 @@ impl iface::TextLayout for TextLayout {
             },
         )
     }
+
+    fn is_emoji_cluster(&self, i: Range<usize>) -> bool {
+        if i.start >= i.end || self.next_char(i.start, true) != i.end {
+            return false;
+        }
+
+        let layout = self.lock_layout();
+        let text = layout.get_text().unwrap();
+        emoji::is_emoji_cluster(&text.as_str()[i])
+    }
 }
 
 impl TextLayout {
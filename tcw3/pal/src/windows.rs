@@ -5,26 +5,49 @@
 //! This backend provides a function named [`set_app_hicon`] that allows
 //! an application to provide its own window icon. The icon is applied to all
 //! windows created by `Wm::new_wnd`.
+//!
+//! The raw window handle of a child window created by `Wm::new_child_wnd`
+//! can be retrieved by [`HChildWnd::hwnd`].
+//!
+//! Web views (`Wm::attach_web_view`) require `WebView2Loader.dll` to be
+//! placed alongside the executable.
 use super::iface;
-use std::{cell::Cell, marker::PhantomData, ops::Range, time::Duration};
+use cggeom::Box2;
+use std::{
+    cell::Cell,
+    marker::PhantomData,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 mod acceltable;
+mod alert;
+mod appearance;
 mod bitmap;
+mod childwnd;
+mod clipboard;
 mod codecvt;
 mod comp;
 mod drawutils;
 mod eventloop;
+mod filedialog;
 mod frameclock;
+mod notification;
+mod sound;
 mod surface;
+mod taskbar;
 mod text;
 mod textinput;
 mod utils;
+mod webview;
 mod winapiext;
 mod window;
 
 pub use self::{
     acceltable::{AccelTable, ActionKeyBinding},
     bitmap::{Bitmap, BitmapBuilder},
+    childwnd::HChildWnd,
     comp::HLayer,
     eventloop::HInvoke,
     text::{CharStyle, CharStyleAttrs, TextLayout},
@@ -52,6 +75,7 @@ impl iface::Wm for Wm {
     type HWnd = HWnd;
     type HLayer = HLayer;
     type HInvoke = HInvoke;
+    type HChildWnd = HChildWnd;
     type HTextInputCtx = HTextInputCtx;
     type AccelTable = AccelTable;
     type Bitmap = Bitmap;
@@ -113,6 +137,14 @@ impl iface::Wm for Wm {
         window::get_wnd_size(self, window)
     }
 
+    fn get_wnd_position(self, window: &Self::HWnd) -> Option<[i32; 2]> {
+        Some(window::get_wnd_position(self, window))
+    }
+
+    fn is_wnd_maximized(self, window: &Self::HWnd) -> bool {
+        window::is_wnd_maximized(self, window)
+    }
+
     fn get_wnd_dpi_scale(self, window: &Self::HWnd) -> f32 {
         window::get_wnd_dpi_scale(self, window)
     }
@@ -121,6 +153,38 @@ impl iface::Wm for Wm {
         window::is_wnd_focused(self, window)
     }
 
+    fn new_child_wnd(self, parent: &Self::HWnd, bounds: Box2<f32>) -> Option<Self::HChildWnd> {
+        Some(childwnd::new_child_wnd(self, parent, bounds))
+    }
+
+    fn set_child_wnd_bounds(self, child: &Self::HChildWnd, bounds: Box2<f32>) {
+        childwnd::set_child_wnd_bounds(self, child, bounds)
+    }
+
+    fn remove_child_wnd(self, child: &Self::HChildWnd) {
+        childwnd::remove_child_wnd(self, child)
+    }
+
+    fn attach_web_view(
+        self,
+        child: &Self::HChildWnd,
+        listener: Box<dyn iface::WebViewListener<Self>>,
+    ) -> bool {
+        webview::attach_web_view(self, child, listener)
+    }
+
+    fn web_view_navigate(self, child: &Self::HChildWnd, url: &str) {
+        webview::web_view_navigate(self, child, url)
+    }
+
+    fn web_view_load_html(self, child: &Self::HChildWnd, html: &str, base_url: Option<&str>) {
+        webview::web_view_load_html(self, child, html, base_url)
+    }
+
+    fn web_view_eval_script(self, child: &Self::HChildWnd, script: &str) {
+        webview::web_view_eval_script(self, child, script)
+    }
+
     fn request_update_ready_wnd(self, window: &Self::HWnd) {
         window::request_update_ready_wnd(self, window)
     }
@@ -135,6 +199,18 @@ impl iface::Wm for Wm {
         comp::remove_layer(self, layer)
     }
 
+    fn set_layer_swapchain(self, layer: &Self::HLayer, size: Option<[u32; 2]>) -> bool {
+        comp::set_layer_swapchain(self, layer, size)
+    }
+
+    fn present_layer_swapchain(
+        self,
+        layer: &Self::HLayer,
+        render: &mut dyn FnMut(iface::SwapchainTarget<'_>),
+    ) {
+        comp::present_layer_swapchain(self, layer, render)
+    }
+
     fn new_text_input_ctx(
         self,
         hwnd: &Self::HWnd,
@@ -160,6 +236,56 @@ impl iface::Wm for Wm {
     fn remove_text_input_ctx(self, htictx: &Self::HTextInputCtx) {
         textinput::remove_text_input_ctx(self, htictx);
     }
+
+    fn set_clipboard_text(self, text: &str) {
+        clipboard::set_clipboard_text(self, text);
+    }
+
+    fn clipboard_text(self) -> Option<String> {
+        clipboard::clipboard_text(self)
+    }
+
+    fn show_file_dialog(
+        self,
+        parent: Option<&Self::HWnd>,
+        options: iface::FileDialogOptions,
+        f: impl FnOnce(Self, Option<Vec<PathBuf>>) + 'static,
+    ) {
+        filedialog::show_file_dialog(self, parent, options, f);
+    }
+
+    fn show_alert(
+        self,
+        parent: Option<&Self::HWnd>,
+        options: &iface::AlertOptions,
+        f: iface::AlertCallback<Self>,
+    ) -> Result<(), iface::AlertCallback<Self>> {
+        alert::show_alert(self, parent, options, f)
+    }
+
+    fn show_notification(
+        self,
+        attrs: iface::NotificationAttrs,
+        f: impl FnOnce(Self, Option<usize>) + 'static,
+    ) {
+        notification::show_notification(self, attrs, f);
+    }
+
+    fn play_system_sound(self, sound: crate::sound::SystemSound) {
+        sound::play_system_sound(self, sound);
+    }
+
+    fn play_sound_file(self, path: &Path) {
+        sound::play_sound_file(self, path);
+    }
+
+    fn system_appearance(self) -> iface::SystemAppearance {
+        appearance::system_appearance(self)
+    }
+
+    fn default_ui_direction(self) -> iface::UiDirection {
+        appearance::default_ui_direction(self)
+    }
 }
 
 struct AssertSend<T>(T);
@@ -10,6 +10,8 @@ use crate::{actions, iface};
 pub struct AccelTable {
     #[doc(hidden)]
     pub key: &'static [ActionKeyBinding],
+    #[doc(hidden)]
+    pub key_bindings: Vec<iface::KeyBinding>,
 }
 
 #[doc(hidden)]
@@ -51,13 +53,155 @@ impl AccelTable {
             .filter(move |binding| flags == binding.flags && key == binding.key)
             .map(|binding| binding.action)
             .nth(0)
+            .or_else(|| self.find_action_with_key_binding(key, flags))
+    }
+
+    fn find_action_with_key_binding(&self, key: u16, flags: u8) -> Option<iface::ActionId> {
+        if self.key_bindings.is_empty() {
+            return None;
+        }
+
+        let key = key_from_vk(key);
+        let modifiers = modifiers_from_mod_flags(flags);
+
+        self.key_bindings
+            .iter()
+            .find(|binding| binding.key == key && binding.modifiers == modifiers)
+            .map(|binding| binding.action)
+    }
+}
+
+impl iface::AccelTableNew for AccelTable {
+    fn new_with_key_bindings(key_bindings: Vec<iface::KeyBinding>) -> Self {
+        Self {
+            key: &[],
+            key_bindings,
+        }
     }
 }
 
+/// Convert a virtual key code to `iface::Key`.
+pub(super) fn key_from_vk(vk: u16) -> iface::Key {
+    use iface::Key;
+
+    const VK_A: i32 = b'A' as i32;
+    const VK_Z: i32 = b'Z' as i32;
+    const VK_0: i32 = b'0' as i32;
+    const VK_9: i32 = b'9' as i32;
+
+    match vk as i32 {
+        x @ VK_A..=VK_Z | x @ VK_0..=VK_9 => Key::Char((x as u8).to_ascii_lowercase() as char),
+        winuser::VK_SPACE => Key::Char(' '),
+        winuser::VK_BACK => Key::Backspace,
+        winuser::VK_TAB => Key::Tab,
+        winuser::VK_RETURN => Key::Return,
+        winuser::VK_ESCAPE => Key::Escape,
+        winuser::VK_PRIOR => Key::PageUp,
+        winuser::VK_NEXT => Key::PageDown,
+        winuser::VK_END => Key::End,
+        winuser::VK_HOME => Key::Home,
+        winuser::VK_LEFT => Key::Left,
+        winuser::VK_UP => Key::Up,
+        winuser::VK_RIGHT => Key::Right,
+        winuser::VK_DOWN => Key::Down,
+        winuser::VK_INSERT => Key::Insert,
+        winuser::VK_DELETE => Key::Delete,
+        winuser::VK_NUMPAD0 => Key::Numpad0,
+        winuser::VK_NUMPAD1 => Key::Numpad1,
+        winuser::VK_NUMPAD2 => Key::Numpad2,
+        winuser::VK_NUMPAD3 => Key::Numpad3,
+        winuser::VK_NUMPAD4 => Key::Numpad4,
+        winuser::VK_NUMPAD5 => Key::Numpad5,
+        winuser::VK_NUMPAD6 => Key::Numpad6,
+        winuser::VK_NUMPAD7 => Key::Numpad7,
+        winuser::VK_NUMPAD8 => Key::Numpad8,
+        winuser::VK_NUMPAD9 => Key::Numpad9,
+        winuser::VK_MULTIPLY => Key::NumpadMultiply,
+        winuser::VK_ADD => Key::NumpadAdd,
+        winuser::VK_SEPARATOR => Key::NumpadSeparator,
+        winuser::VK_SUBTRACT => Key::NumpadSubtract,
+        winuser::VK_DECIMAL => Key::NumpadDecimal,
+        winuser::VK_DIVIDE => Key::NumpadDivide,
+        winuser::VK_F1 => Key::F1,
+        winuser::VK_F2 => Key::F2,
+        winuser::VK_F3 => Key::F3,
+        winuser::VK_F4 => Key::F4,
+        winuser::VK_F5 => Key::F5,
+        winuser::VK_F6 => Key::F6,
+        winuser::VK_F7 => Key::F7,
+        winuser::VK_F8 => Key::F8,
+        winuser::VK_F9 => Key::F9,
+        winuser::VK_F10 => Key::F10,
+        winuser::VK_F11 => Key::F11,
+        winuser::VK_F12 => Key::F12,
+        winuser::VK_F13 => Key::F13,
+        winuser::VK_F14 => Key::F14,
+        winuser::VK_F15 => Key::F15,
+        winuser::VK_F16 => Key::F16,
+        winuser::VK_F17 => Key::F17,
+        winuser::VK_F18 => Key::F18,
+        winuser::VK_F19 => Key::F19,
+        winuser::VK_F20 => Key::F20,
+        winuser::VK_F21 => Key::F21,
+        winuser::VK_F22 => Key::F22,
+        winuser::VK_F23 => Key::F23,
+        winuser::VK_F24 => Key::F24,
+        winuser::VK_VOLUME_MUTE => Key::VolumeMute,
+        winuser::VK_VOLUME_DOWN => Key::VolumeDown,
+        winuser::VK_VOLUME_UP => Key::VolumeUp,
+        winuser::VK_MEDIA_PLAY_PAUSE => Key::MediaPlayPause,
+        winuser::VK_MEDIA_STOP => Key::MediaStop,
+        winuser::VK_MEDIA_NEXT_TRACK => Key::MediaNextTrack,
+        winuser::VK_MEDIA_PREV_TRACK => Key::MediaPrevTrack,
+        _ => {
+            // Punctuation keys (`VK_OEM_*`) depend on the keyboard layout
+            let c = unsafe { winuser::MapVirtualKeyW(vk as u32, winuser::MAPVK_VK_TO_CHAR) };
+            // The highest bit indicates a dead key
+            match std::char::from_u32(c & 0x7fffffff) {
+                Some(c) if c != '\0' && !c.is_control() => Key::Char(c.to_ascii_lowercase()),
+                _ => Key::Unknown,
+            }
+        }
+    }
+}
+
+/// Convert `AccelTable::MOD_*` to `iface::ModifierState`. The state of the
+/// Windows keys is queried from the system because `mod_flags` doesn't include
+/// it.
+pub(super) fn modifiers_from_mod_flags(mod_flags: u8) -> iface::ModifierState {
+    use iface::ModifierState;
+
+    let mut state = ModifierState::empty();
+    state.set(ModifierState::SHIFT, mod_flags & AccelTable::MOD_SHIFT != 0);
+    state.set(
+        ModifierState::CONTROL,
+        mod_flags & AccelTable::MOD_CONTROL != 0,
+    );
+    state.set(ModifierState::ALT, mod_flags & AccelTable::MOD_MENU != 0);
+
+    let is_down = |vk| unsafe { winuser::GetKeyState(vk) } as u16 & 0x8000 != 0;
+    state.set(
+        ModifierState::SUPER,
+        is_down(winuser::VK_LWIN) || is_down(winuser::VK_RWIN),
+    );
+
+    state
+}
+
 pub(super) static TEXT_INPUT_ACCEL: AccelTable = tcw3_pal_macro::accel_table_inner!(
     crate,
     "windows",
     [
+        (actions::UNDO, windows("Ctrl+Z")),
+        (actions::REDO, windows("Ctrl+Y")),
+        (actions::REDO, windows("Shift+Ctrl+Z")),
+        (actions::CUT, windows("Ctrl+X")),
+        (actions::CUT, windows("Shift+Delete")),
+        (actions::COPY, windows("Ctrl+C")),
+        (actions::COPY, windows("Ctrl+Insert")),
+        (actions::PASTE, windows("Ctrl+V")),
+        (actions::PASTE, windows("Shift+Insert")),
+        (actions::SELECT_ALL, windows("Ctrl+A")),
         (actions::DELETE_BACKWARD, windows("Backspace")),
         (actions::DELETE_BACKWARD_WORD, windows("Ctrl+Backspace")),
         (actions::DELETE_FORWARD, windows("Delete")),
@@ -0,0 +1,161 @@
+//! Implements `Wm::show_alert` using `TaskDialogIndirect`.
+use std::{
+    mem::{size_of, zeroed},
+    os::raw::c_int,
+    ptr::null_mut,
+};
+use winapi::{
+    shared::{minwindef::BOOL, ntdef::HRESULT},
+    um::{
+        commctrl::{
+            TASKDIALOGCONFIG_u1, TASKDIALOGCONFIG, TASKDIALOG_BUTTON, TDCBF_OK_BUTTON,
+            TDF_ALLOW_DIALOG_CANCELLATION, TDF_POSITION_RELATIVE_TO_WINDOW, TD_ERROR_ICON,
+            TD_INFORMATION_ICON, TD_WARNING_ICON,
+        },
+        libloaderapi::{GetProcAddress, LoadLibraryW},
+        winuser::{IDCANCEL, IDOK},
+    },
+};
+
+use super::{codecvt::str_to_c_wstr, utils::result_from_hresult, HWnd, Wm};
+use crate::{cells::MtLazyStatic, iface, iface::Wm as _};
+
+type TaskDialogIndirectFn = unsafe extern "system" fn(
+    *const TASKDIALOGCONFIG,
+    *mut c_int,
+    *mut c_int,
+    *mut BOOL,
+) -> HRESULT;
+
+mt_lazy_static! {
+    static <Wm> ref TASK_DIALOG_INDIRECT: Option<TaskDialogIndirectFn> =>
+        |_| unsafe { load_task_dialog_indirect() };
+}
+
+/// `TaskDialogIndirect` is only available in Common Controls version 6, which
+/// must be activated by an application manifest. Look it up dynamically so
+/// that the executable can be loaded even without it.
+unsafe fn load_task_dialog_indirect() -> Option<TaskDialogIndirectFn> {
+    let hmodule = LoadLibraryW(str_to_c_wstr("comctl32.dll").as_ptr());
+    if hmodule.is_null() {
+        log::warn!("Could not load comctl32.dll");
+        return None;
+    }
+
+    let proc = GetProcAddress(hmodule, b"TaskDialogIndirect\0".as_ptr() as _);
+    if proc.is_null() {
+        log::warn!(
+            "Could not find TaskDialogIndirect. Make sure the application \
+             manifest specifies Common Controls version 6"
+        );
+        return None;
+    }
+
+    Some(std::mem::transmute(proc))
+}
+
+/// The button IDs of custom buttons start from this value so that they don't
+/// collide with the standard ones like `IDOK` and `IDCANCEL`.
+const BUTTON_ID_BASE: c_int = 100;
+
+pub fn show_alert(
+    wm: Wm,
+    parent: Option<&HWnd>,
+    options: &iface::AlertOptions,
+    f: iface::AlertCallback<Wm>,
+) -> Result<(), iface::AlertCallback<Wm>> {
+    let task_dialog_indirect = if let Some(x) = *TASK_DIALOG_INDIRECT.get_with_wm(wm) {
+        x
+    } else {
+        return Err(f);
+    };
+
+    let parent = parent.cloned();
+    let options = options.clone();
+
+    // `TaskDialogIndirect` runs a nested message loop and doesn't return
+    // until the dialog is dismissed. Defer it so that `f` isn't called from
+    // inside `show_alert`.
+    wm.invoke(move |wm| {
+        let hwnd_owner = parent.as_ref().map_or(null_mut(), HWnd::expect_hwnd);
+        let result = unsafe { run_task_dialog(task_dialog_indirect, hwnd_owner, &options) };
+        f(wm, result);
+    });
+
+    Ok(())
+}
+
+unsafe fn run_task_dialog(
+    task_dialog_indirect: TaskDialogIndirectFn,
+    hwnd_owner: winapi::shared::windef::HWND,
+    options: &iface::AlertOptions,
+) -> Option<usize> {
+    let title = str_to_c_wstr(&options.title);
+    let message = str_to_c_wstr(&options.message);
+
+    // `TASKDIALOG_BUTTON` borrows the strings, so they must outlive the call
+    // to `TaskDialogIndirect`.
+    let captions: Vec<Box<[u16]>> = options
+        .buttons
+        .iter()
+        .map(|caption| str_to_c_wstr(caption))
+        .collect();
+    let buttons: Vec<TASKDIALOG_BUTTON> = captions
+        .iter()
+        .enumerate()
+        .map(|(i, caption)| TASKDIALOG_BUTTON {
+            nButtonID: BUTTON_ID_BASE + i as c_int,
+            pszButtonText: caption.as_ptr(),
+        })
+        .collect();
+
+    let mut u1: TASKDIALOGCONFIG_u1 = zeroed();
+    *u1.pszMainIcon_mut() = match options.severity {
+        iface::AlertSeverity::Info => TD_INFORMATION_ICON,
+        iface::AlertSeverity::Warning => TD_WARNING_ICON,
+        iface::AlertSeverity::Error => TD_ERROR_ICON,
+    };
+
+    let mut flags = TDF_ALLOW_DIALOG_CANCELLATION;
+    if !hwnd_owner.is_null() {
+        flags |= TDF_POSITION_RELATIVE_TO_WINDOW;
+    }
+
+    let config = TASKDIALOGCONFIG {
+        cbSize: size_of::<TASKDIALOGCONFIG>() as _,
+        hwndParent: hwnd_owner,
+        dwFlags: flags,
+        dwCommonButtons: if buttons.is_empty() {
+            TDCBF_OK_BUTTON
+        } else {
+            0
+        },
+        pszWindowTitle: title.as_ptr(),
+        u1,
+        pszContent: message.as_ptr(),
+        cButtons: buttons.len() as _,
+        pButtons: buttons.as_ptr(),
+        nDefaultButton: if buttons.is_empty() {
+            IDOK
+        } else {
+            BUTTON_ID_BASE
+        },
+        ..zeroed()
+    };
+
+    let mut button_id = 0;
+    let hr = task_dialog_indirect(&config, &mut button_id, null_mut(), null_mut());
+    if let Err(hr) = result_from_hresult(hr) {
+        log::warn!("TaskDialogIndirect failed: 0x{:08x}", hr);
+        return None;
+    }
+
+    match button_id {
+        IDCANCEL => None,
+        IDOK if buttons.is_empty() => Some(0),
+        id if id >= BUTTON_ID_BASE && id < BUTTON_ID_BASE + buttons.len() as c_int => {
+            Some((id - BUTTON_ID_BASE) as usize)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    mem::{size_of, MaybeUninit},
+    ptr::{null, null_mut},
+};
+use wchar::wch_c;
+use winapi::{
+    shared::{
+        minwindef::{DWORD, FALSE, UINT},
+        winerror::{ERROR_SUCCESS, S_OK},
+    },
+    um::{dwmapi, winnls, winnt::LPCWSTR, winreg, winuser},
+};
+
+use super::Wm;
+use crate::iface;
+
+/// The `lParam` of `WM_SETTINGCHANGE` sent when the user switches between
+/// the light and dark color schemes or changes the accent color.
+const IMMERSIVE_COLOR_SET: &[u16] = wch_c!("ImmersiveColorSet");
+
+pub fn system_appearance(_: Wm) -> iface::SystemAppearance {
+    iface::SystemAppearance {
+        dark_mode: is_dark_mode(),
+        high_contrast: is_high_contrast(),
+        accent_color: accent_color(),
+    }
+}
+
+pub fn default_ui_direction(_: Wm) -> iface::UiDirection {
+    // With `LOCALE_RETURN_NUMBER`, the value is written as a `DWORD`
+    let mut value: DWORD = 0;
+    let len = unsafe {
+        winnls::GetLocaleInfoEx(
+            null(), // `LOCALE_NAME_USER_DEFAULT`
+            winnls::LOCALE_IREADINGLAYOUT | winnls::LOCALE_RETURN_NUMBER,
+            &mut value as *mut DWORD as _,
+            (size_of::<DWORD>() / size_of::<u16>()) as _,
+        )
+    };
+
+    // `1` means right-to-left. Other values are variants of left-to-right
+    // or top-to-bottom layouts.
+    if len != 0 && value == 1 {
+        iface::UiDirection::Rtl
+    } else {
+        iface::UiDirection::Ltr
+    }
+}
+
+/// Check whether a `WM_SETTINGCHANGE` message with the given parameters may
+/// indicate a change in the value returned by `system_appearance`.
+pub fn is_appearance_setting_change(wparam: UINT, lparam: LPCWSTR) -> bool {
+    if wparam == winuser::SPI_SETHIGHCONTRAST {
+        return true;
+    }
+
+    if lparam.is_null() {
+        return false;
+    }
+
+    // Compare the null-terminated string with `IMMERSIVE_COLOR_SET`
+    // (including the terminator). Stop at the first mismatch so that we don't
+    // read past the end of the string.
+    IMMERSIVE_COLOR_SET
+        .iter()
+        .enumerate()
+        .all(|(i, &expected)| unsafe { *lparam.add(i) } == expected)
+}
+
+fn is_dark_mode() -> bool {
+    let mut value: DWORD = 1;
+    let mut size = size_of::<DWORD>() as DWORD;
+
+    let result = unsafe {
+        winreg::RegGetValueW(
+            winreg::HKEY_CURRENT_USER,
+            wch_c!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize").as_ptr(),
+            wch_c!("AppsUseLightTheme").as_ptr(),
+            winreg::RRF_RT_REG_DWORD,
+            null_mut(),
+            &mut value as *mut DWORD as _,
+            &mut size,
+        )
+    };
+
+    // The value doesn't exist on older versions of Windows, which only have
+    // the light color scheme
+    result == ERROR_SUCCESS as _ && value == 0
+}
+
+fn is_high_contrast() -> bool {
+    let mut hc = MaybeUninit::<winuser::HIGHCONTRASTW>::zeroed();
+    unsafe {
+        (*hc.as_mut_ptr()).cbSize = size_of::<winuser::HIGHCONTRASTW>() as UINT;
+    }
+
+    let ok = unsafe {
+        winuser::SystemParametersInfoW(
+            winuser::SPI_GETHIGHCONTRAST,
+            size_of::<winuser::HIGHCONTRASTW>() as UINT,
+            hc.as_mut_ptr() as _,
+            0,
+        )
+    };
+
+    if ok == FALSE {
+        log::warn!("SystemParametersInfoW(SPI_GETHIGHCONTRAST) failed");
+        return false;
+    }
+
+    let hc = unsafe { hc.assume_init() };
+    (hc.dwFlags & winuser::HCF_HIGHCONTRASTON) != 0
+}
+
+fn accent_color() -> Option<iface::RGBAF32> {
+    let mut color: DWORD = 0;
+    let mut opaque_blend = FALSE;
+
+    if unsafe { dwmapi::DwmGetColorizationColor(&mut color, &mut opaque_blend) } != S_OK {
+        return None;
+    }
+
+    // `color` is in the `0xAARRGGBB` format. The alpha value is not really
+    // meaningful for our use, so we ignore it.
+    let [_, r, g, b] = color.to_be_bytes();
+    Some(iface::RGBAF32::new(
+        r as f32 / 255.0,
+        g as f32 / 255.0,
+        b as f32 / 255.0,
+        1.0,
+    ))
+}
@@ -1,6 +1,7 @@
 use arrayvec::ArrayVec;
-use cgmath::{Matrix3, Point2};
-use std::{convert::TryInto, fmt, mem::MaybeUninit, ptr::null_mut, sync::Arc};
+use cggeom::{prelude::*, Box2};
+use cgmath::{vec2, Matrix3, Point2};
+use std::{convert::TryInto, f32::consts::PI, fmt, mem::MaybeUninit, ptr::null_mut, sync::Arc};
 use winapi::{
     shared::minwindef::INT,
     um::{
@@ -488,6 +489,50 @@ impl iface::Canvas for BitmapBuilder {
 
         self.cubic_bezier_to(cp1, cp2, p);
     }
+    fn ellipse(&mut self, bx: Box2<f32>) {
+        let size = bx.size();
+        if size.x <= 0.0 || size.y <= 0.0 {
+            // GDI+ rejects an empty ellipse
+            return;
+        }
+        unsafe {
+            assert_gp_ok(gp::GdipAddPathEllipse(
+                self.path.gp_path,
+                bx.min.x,
+                bx.min.y,
+                size.x,
+                size.y,
+            ));
+        }
+        // The figure starts and ends at the angle 0, and `GdipAddPathEllipse`
+        // closes it
+        self.cur_pt = [bx.max.x, bx.mid().y];
+    }
+    fn arc_to(&mut self, center: Point2<f32>, radius: f32, start_angle: f32, end_angle: f32) {
+        let point_at = |angle: f32| {
+            let (sin, cos) = angle.sin_cos();
+            center + vec2(cos, sin) * radius
+        };
+
+        // GDI+ doesn't connect the arc to the point specified by `move_to`
+        self.line_to(point_at(start_angle));
+
+        if radius > 0.0 {
+            const TO_DEG: f32 = 180.0 / PI;
+            unsafe {
+                assert_gp_ok(gp::GdipAddPathArc(
+                    self.path.gp_path,
+                    center.x - radius,
+                    center.y - radius,
+                    radius * 2.0,
+                    radius * 2.0,
+                    start_angle * TO_DEG,
+                    (end_angle - start_angle) * TO_DEG,
+                ));
+            }
+        }
+        self.cur_pt = point_at(end_angle).into();
+    }
     fn fill(&mut self) {
         unsafe {
             assert_gp_ok(gp::GdipFillPath(
@@ -5,27 +5,33 @@ use directwrite::{
 };
 use std::{
     ffi::c_void,
-    mem::{transmute, MaybeUninit},
+    mem::MaybeUninit,
+    ptr::{null, null_mut},
 };
 use winapi::{
     shared::{
         basetsd::UINT32,
         guiddef::{IsEqualGUID, REFIID},
-        minwindef::ULONG,
-        winerror::{E_NOTIMPL, HRESULT, S_OK},
+        minwindef::{BOOL, ULONG},
+        winerror::{DWRITE_E_NOCOLOR, E_NOTIMPL, FAILED, HRESULT, S_OK},
     },
     um::{
         d2d1::{self, ID2D1SimplifiedGeometrySink, ID2D1SimplifiedGeometrySinkVtbl},
-        dwrite::DWRITE_MATRIX,
+        dwrite::{DWRITE_GLYPH_RUN, DWRITE_MATRIX},
+        dwrite_2::{IDWriteColorGlyphRunEnumerator, IDWriteFactory2},
         gdiplusenums, gdiplusflat as gp,
         gdiplusgpstubs::{GpGraphics, GpPath, GpSolidFill},
+        gdipluspixelformats::ARGB,
         gdiplustypes::REAL,
         unknwnbase::{IUnknown, IUnknownVtbl},
     },
     Interface,
 };
 
-use super::super::text::TextLayout;
+use super::super::{
+    text::{dwrite_factory2, TextLayout},
+    utils::ComPtr,
+};
 use super::{assert_gp_ok, create_gp_obj_with, rgbaf32_to_argb, BitmapBuilder};
 use crate::iface;
 
@@ -54,11 +60,10 @@ impl iface::CanvasText<TextLayout> for BitmapBuilder {
             dy: mat_elems[5] + 0.5,
         };
 
+        let color = rgbaf32_to_argb(layout.color.unwrap_or(color));
+
         unsafe {
-            assert_gp_ok(gp::GdipSetSolidFillColor(
-                self.brush2.gp_solid_fill,
-                rgbaf32_to_argb(layout.color.unwrap_or(color)),
-            ));
+            assert_gp_ok(gp::GdipSetSolidFillColor(self.brush2.gp_solid_fill, color));
         }
 
         layout
@@ -68,6 +73,8 @@ impl iface::CanvasText<TextLayout> for BitmapBuilder {
                     gp_gr: self.gr.gp_gr,
                     gp_path: self.path.gp_path,
                     gp_brush: self.brush2.gp_solid_fill,
+                    color,
+                    dwrite2: dwrite_factory2(),
                     mat,
                 },
                 origin.x,
@@ -81,49 +88,55 @@ impl iface::CanvasText<TextLayout> for BitmapBuilder {
 struct TextRenderer {
     gp_gr: *mut GpGraphics,
     gp_path: *mut GpPath,
+    /// The brush used to fill glyphs. Its color is `color` except while
+    /// drawing color glyphs.
     gp_brush: *mut GpSolidFill,
+    /// The text color.
+    color: ARGB,
+    /// Used to decompose color glyphs (e.g., emoji) into layers. `None` if
+    /// the system doesn't support color fonts.
+    dwrite2: Option<ComPtr<IDWriteFactory2>>,
     mat: DWRITE_MATRIX,
 }
 
-impl directwrite::text_renderer::TextRenderer for TextRenderer {
-    fn current_transform(&self, _context: Context) -> DWResult<DWRITE_MATRIX> {
-        Ok(self.mat)
-    }
-
-    fn pixels_per_dip(&self, _context: Context) -> DWResult<f32> {
-        Ok(1.0)
-    }
-
-    fn is_pixel_snapping_disabled(&self, _context: Context) -> DWResult<bool> {
-        Ok(false)
-    }
-
-    fn draw_glyph_run(&mut self, ctx: &DrawGlyphRun) -> DWResult<()> {
+impl TextRenderer {
+    /// Convert a glyph run to outlines and fill them with `gp_brush`.
+    fn fill_glyph_run(
+        &mut self,
+        glyph_run: &DWRITE_GLYPH_RUN,
+        baseline_origin: [f32; 2],
+    ) -> DWResult<()> {
         unsafe {
             assert_gp_ok(gp::GdipResetPath(self.gp_path));
         }
 
-        ctx.font_face.get_glyph_run_outline(
-            ctx.font_em_size,
-            ctx.glyph_indices,
-            Some(ctx.glyph_advances),
-            Some(unsafe { transmute(ctx.glyph_offsets) }),
-            ctx.is_sideways,
-            ctx.bidi_level % 2 != 0,
-            (&mut SinkComRef {
-                _vtbl: &SINK_VTBL,
-                gp_path: self.gp_path,
-                cur_pt: None,
-            }) as *mut _ as *mut ID2D1SimplifiedGeometrySink,
-        )?;
+        let hr = unsafe {
+            (*glyph_run.fontFace).GetGlyphRunOutline(
+                glyph_run.fontEmSize,
+                glyph_run.glyphIndices,
+                glyph_run.glyphAdvances,
+                glyph_run.glyphOffsets,
+                glyph_run.glyphCount,
+                glyph_run.isSideways,
+                (glyph_run.bidiLevel % 2 != 0) as BOOL,
+                (&mut SinkComRef {
+                    _vtbl: &SINK_VTBL,
+                    gp_path: self.gp_path,
+                    cur_pt: None,
+                }) as *mut _ as *mut ID2D1SimplifiedGeometrySink,
+            )
+        };
+        if FAILED(hr) {
+            return Err(DWriteError(hr));
+        }
 
         let st = unsafe { create_gp_obj_with(|out| gp::GdipSaveGraphics(self.gp_gr, out)) };
 
         unsafe {
             gp::GdipTranslateWorldTransform(
                 self.gp_gr,
-                ctx.baseline_origin_x,
-                ctx.baseline_origin_y,
+                baseline_origin[0],
+                baseline_origin[1],
                 gdiplusenums::MatrixOrderPrepend,
             );
         }
@@ -142,6 +155,122 @@ impl directwrite::text_renderer::TextRenderer for TextRenderer {
         Ok(())
     }
 
+    /// Decompose a glyph run into color layers. Returns `Ok(None)` if the run
+    /// doesn't include color glyphs.
+    fn translate_color_glyph_run(
+        &self,
+        ctx: &DrawGlyphRun,
+        glyph_run: &DWRITE_GLYPH_RUN,
+    ) -> DWResult<Option<ComPtr<IDWriteColorGlyphRunEnumerator>>> {
+        let dwrite2 = if let Some(x) = &self.dwrite2 {
+            x
+        } else {
+            return Ok(None);
+        };
+
+        let mut out = null_mut();
+        let hr = unsafe {
+            dwrite2.TranslateColorGlyphRun(
+                ctx.baseline_origin_x,
+                ctx.baseline_origin_y,
+                glyph_run,
+                null(),
+                ctx.measuring_mode as u32,
+                &self.mat,
+                0,
+                &mut out,
+            )
+        };
+
+        if hr == DWRITE_E_NOCOLOR {
+            Ok(None)
+        } else if FAILED(hr) {
+            Err(DWriteError(hr))
+        } else {
+            Ok(Some(unsafe { ComPtr::from_ptr_unchecked(out) }))
+        }
+    }
+}
+
+impl directwrite::text_renderer::TextRenderer for TextRenderer {
+    fn current_transform(&self, _context: Context) -> DWResult<DWRITE_MATRIX> {
+        Ok(self.mat)
+    }
+
+    fn pixels_per_dip(&self, _context: Context) -> DWResult<f32> {
+        Ok(1.0)
+    }
+
+    fn is_pixel_snapping_disabled(&self, _context: Context) -> DWResult<bool> {
+        Ok(false)
+    }
+
+    fn draw_glyph_run(&mut self, ctx: &DrawGlyphRun) -> DWResult<()> {
+        let glyph_run = DWRITE_GLYPH_RUN {
+            fontFace: unsafe { ctx.font_face.get_raw() },
+            fontEmSize: ctx.font_em_size,
+            glyphCount: ctx.glyph_count,
+            glyphIndices: ctx.glyph_indices.as_ptr(),
+            glyphAdvances: ctx.glyph_advances.as_ptr(),
+            glyphOffsets: ctx.glyph_offsets.as_ptr() as _,
+            isSideways: ctx.is_sideways as BOOL,
+            bidiLevel: ctx.bidi_level,
+        };
+
+        let color_layers = if let Some(x) = self.translate_color_glyph_run(ctx, &glyph_run)? {
+            x
+        } else {
+            return self.fill_glyph_run(&glyph_run, [ctx.baseline_origin_x, ctx.baseline_origin_y]);
+        };
+
+        // A color glyph run consists of monochrome layers, each of which is
+        // filled with a color from the font's palette or the text color.
+        // (This is what `ID2D1DeviceContext4::DrawColorGlyphRun` does.)
+        loop {
+            let mut has_run = 0;
+            let hr = unsafe { color_layers.MoveNext(&mut has_run) };
+            if FAILED(hr) {
+                return Err(DWriteError(hr));
+            }
+            if has_run == 0 {
+                break;
+            }
+
+            let layer = unsafe {
+                let mut out = null();
+                let hr = color_layers.GetCurrentRun(&mut out);
+                if FAILED(hr) {
+                    return Err(DWriteError(hr));
+                }
+                &*out
+            };
+
+            let color = if layer.paletteIndex == 0xffff {
+                self.color
+            } else {
+                let c = layer.runColor;
+                rgbaf32_to_argb(iface::RGBAF32::new(c.r, c.g, c.b, c.a))
+            };
+
+            unsafe {
+                assert_gp_ok(gp::GdipSetSolidFillColor(self.gp_brush, color));
+            }
+
+            let result = self.fill_glyph_run(
+                &layer.glyphRun,
+                [layer.baselineOriginX, layer.baselineOriginY],
+            );
+
+            unsafe {
+                assert_gp_ok(gp::GdipSetSolidFillColor(self.gp_brush, self.color));
+            }
+
+            result?;
+        }
+
+        Ok(())
+    }
+
     fn draw_inline_object(&mut self, _context: &DrawInlineObject) -> DWResult<()> {
         Err(DWriteError(E_NOTIMPL))
     }
@@ -0,0 +1,100 @@
+//! Native child windows
+use cggeom::Box2;
+use std::ptr::null_mut;
+use wchar::wch_c;
+use winapi::{
+    shared::windef::HWND,
+    um::{libloaderapi, winuser},
+};
+
+use super::{
+    utils::{assert_win32_nonnull, assert_win32_ok},
+    webview,
+    window::log_client_box2_to_phy_client_rect,
+    HWnd, Wm,
+};
+
+/// A handle to a native child window created by `Wm::new_child_wnd`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HChildWnd {
+    hwnd: HWND,
+    parent: HWnd,
+}
+
+impl HChildWnd {
+    /// Get the raw window handle. Third-party controls can be created as
+    /// children of this window.
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd
+    }
+}
+
+pub fn new_child_wnd(_: Wm, parent: &HWnd, bounds: Box2<f32>) -> HChildWnd {
+    let parent_hwnd = parent.expect_hwnd();
+    let hinstance = unsafe { libloaderapi::GetModuleHandleW(null_mut()) };
+    let rect = log_client_box2_to_phy_client_rect(parent_hwnd, bounds);
+
+    let hwnd = assert_win32_nonnull(unsafe {
+        winuser::CreateWindowExW(
+            0,
+            wch_c!("Static").as_ptr(),
+            null_mut(), // title
+            winuser::WS_CHILD | winuser::WS_VISIBLE | winuser::WS_CLIPCHILDREN,
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            parent_hwnd,
+            null_mut(),
+            hinstance,
+            null_mut(),
+        )
+    });
+
+    HChildWnd {
+        hwnd,
+        parent: parent.clone(),
+    }
+}
+
+pub fn set_child_wnd_bounds(wm: Wm, child: &HChildWnd, bounds: Box2<f32>) {
+    let parent_hwnd = unsafe { winuser::GetParent(child.hwnd) };
+    assert_win32_nonnull(parent_hwnd);
+
+    let rect = log_client_box2_to_phy_client_rect(parent_hwnd, bounds);
+
+    assert_win32_ok(unsafe {
+        winuser::SetWindowPos(
+            child.hwnd,
+            null_mut(),
+            rect.left,
+            rect.top,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            winuser::SWP_NOZORDER | winuser::SWP_NOACTIVATE,
+        )
+    });
+
+    webview::update_bounds(wm, child);
+}
+
+pub fn remove_child_wnd(wm: Wm, child: &HChildWnd) {
+    webview::remove_web_view(wm, child);
+
+    // The child window is destroyed along with the parent window. In that
+    // case, the handle may have been reused for an unrelated window, so we
+    // must not call `DestroyWindow` on it.
+    let parent_hwnd = if let Some(hwnd) = child.parent.hwnd_if_alive() {
+        hwnd
+    } else {
+        return;
+    };
+
+    // The parent window is alive, so the handle still refers to our child
+    // window unless someone else has destroyed it
+    if unsafe { winuser::GetParent(child.hwnd) } != parent_hwnd {
+        return;
+    }
+
+    assert_win32_ok(unsafe { winuser::DestroyWindow(child.hwnd) });
+}
@@ -0,0 +1,92 @@
+use std::ptr::null_mut;
+use winapi::{
+    shared::minwindef::FALSE,
+    um::{
+        winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE},
+        winuser::{
+            CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+            CF_UNICODETEXT,
+        },
+    },
+};
+
+use super::{
+    codecvt::{str_to_c_wstr, wstr_to_str},
+    Wm,
+};
+
+/// Opens the clipboard and closes it when dropped.
+struct ClipboardGuard;
+
+impl ClipboardGuard {
+    fn open() -> Option<Self> {
+        if unsafe { OpenClipboard(null_mut()) } == FALSE {
+            log::warn!("OpenClipboard failed");
+            None
+        } else {
+            Some(Self)
+        }
+    }
+}
+
+impl Drop for ClipboardGuard {
+    fn drop(&mut self) {
+        unsafe { CloseClipboard() };
+    }
+}
+
+pub fn set_clipboard_text(_: Wm, text: &str) {
+    let wstr = str_to_c_wstr(text);
+
+    let _guard = if let Some(guard) = ClipboardGuard::open() {
+        guard
+    } else {
+        return;
+    };
+
+    unsafe {
+        EmptyClipboard();
+
+        let hmem = GlobalAlloc(GMEM_MOVEABLE, wstr.len() * 2);
+        if hmem.is_null() {
+            log::warn!("GlobalAlloc failed");
+            return;
+        }
+
+        let ptr = GlobalLock(hmem) as *mut u16;
+        std::ptr::copy_nonoverlapping(wstr.as_ptr(), ptr, wstr.len());
+        GlobalUnlock(hmem);
+
+        // On success, the system takes the ownership of `hmem`
+        if SetClipboardData(CF_UNICODETEXT, hmem).is_null() {
+            log::warn!("SetClipboardData failed");
+            GlobalFree(hmem);
+        }
+    }
+}
+
+pub fn clipboard_text(_: Wm) -> Option<String> {
+    let _guard = ClipboardGuard::open()?;
+
+    unsafe {
+        let hmem = GetClipboardData(CF_UNICODETEXT);
+        if hmem.is_null() {
+            return None;
+        }
+
+        let ptr = GlobalLock(hmem) as *const u16;
+        if ptr.is_null() {
+            return None;
+        }
+
+        // The data is null-terminated, but don't read past the allocation
+        // even if it isn't
+        let max_len = GlobalSize(hmem) / 2;
+        let len = (0..max_len).find(|&i| *ptr.add(i) == 0).unwrap_or(max_len);
+        let text = wstr_to_str(std::slice::from_raw_parts(ptr, len));
+
+        GlobalUnlock(hmem);
+
+        Some(text.into())
+    }
+}
@@ -1,8 +1,20 @@
+// Enables the classic COM interface support of C++/WinRT
+#include <unknwn.h>
+
 #include <winrt/Windows.System.h>
+#include <winrt/Windows.Graphics.h>
+#include <winrt/Windows.UI.Composition.h>
 #include <DispatcherQueue.h>
+#include <d2d1.h>
+#include <windows.graphics.interop.h>
+
+#include <algorithm>
+
+#pragma comment(lib, "d2d1.lib")
 
 using namespace winrt;
 using namespace Windows::System;
+using namespace Windows::UI::Composition;
 
 namespace abi = ABI::Windows::System;
 
@@ -22,3 +34,102 @@ extern "C" HRESULT tcw_comp_init() {
 		reinterpret_cast<abi::IDispatcherQueueController**>(put_abi(ctrler))
 	);
 }
+
+/// Wraps a Direct2D geometry to be consumed by `CompositionPath`.
+struct GeometrySource : implements<
+	GeometrySource,
+	Windows::Graphics::IGeometrySource2D,
+	ABI::Windows::Graphics::IGeometrySource2DInterop
+> {
+	com_ptr<ID2D1Geometry> geometry;
+
+	GeometrySource(com_ptr<ID2D1Geometry> geometry) : geometry(std::move(geometry)) {}
+
+	IFACEMETHODIMP GetGeometry(ID2D1Geometry **value) override {
+		geometry.copy_to(value);
+		return S_OK;
+	}
+
+	IFACEMETHODIMP TryGetGeometryUsingFactory(ID2D1Factory *, ID2D1Geometry **result) override {
+		*result = nullptr;
+		return E_NOTIMPL;
+	}
+};
+
+/// Create a `CompositionPath` representing a rectangle with rounded corners.
+///
+/// `rect` specifies the rectangle in the form `[x, y, width, height]`.
+/// `radii` specifies the radii of the top-left, top-right, bottom-right, and
+/// bottom-left corners. They are scaled down proportionally if the arcs on
+/// any edge would overlap. On success, an `ICompositionPath` is stored to
+/// `out`.
+extern "C" HRESULT tcw_comp_new_rounded_rect_path(
+	const float rect[4],
+	const float radii[4],
+	void **out
+) noexcept {
+	try {
+		static com_ptr<ID2D1Factory> factory = [] {
+			com_ptr<ID2D1Factory> factory;
+			check_hresult(D2D1CreateFactory(
+				D2D1_FACTORY_TYPE_SINGLE_THREADED,
+				factory.put()
+			));
+			return factory;
+		}();
+
+		float x0 = rect[0], y0 = rect[1], w = rect[2], h = rect[3];
+		float x1 = x0 + w, y1 = y0 + h;
+
+		float r[4];
+		for (int i = 0; i < 4; ++i) {
+			r[i] = std::max(radii[i], 0.0f);
+		}
+
+		// Scale down the radii if the arcs on any edge overlap (as done by CSS)
+		float factor = 1.0f;
+		if (r[0] + r[1] > w) factor = std::min(factor, w / (r[0] + r[1]));
+		if (r[1] + r[2] > h) factor = std::min(factor, h / (r[1] + r[2]));
+		if (r[2] + r[3] > w) factor = std::min(factor, w / (r[2] + r[3]));
+		if (r[3] + r[0] > h) factor = std::min(factor, h / (r[3] + r[0]));
+		for (float &ri : r) {
+			ri *= factor;
+		}
+
+		com_ptr<ID2D1PathGeometry> geometry;
+		check_hresult(factory->CreatePathGeometry(geometry.put()));
+
+		com_ptr<ID2D1GeometrySink> sink;
+		check_hresult(geometry->Open(sink.put()));
+
+		auto arc_to = [&](float x, float y, float radius) {
+			if (radius > 0.0f) {
+				sink->AddArc(D2D1::ArcSegment(
+					D2D1::Point2F(x, y),
+					D2D1::SizeF(radius, radius),
+					0.0f,
+					D2D1_SWEEP_DIRECTION_CLOCKWISE,
+					D2D1_ARC_SIZE_SMALL
+				));
+			}
+		};
+
+		sink->BeginFigure(D2D1::Point2F(x0 + r[0], y0), D2D1_FIGURE_BEGIN_FILLED);
+		sink->AddLine(D2D1::Point2F(x1 - r[1], y0));
+		arc_to(x1, y0 + r[1], r[1]);
+		sink->AddLine(D2D1::Point2F(x1, y1 - r[2]));
+		arc_to(x1 - r[2], y1, r[2]);
+		sink->AddLine(D2D1::Point2F(x0 + r[3], y1));
+		arc_to(x0, y1 - r[3], r[3]);
+		sink->AddLine(D2D1::Point2F(x0, y0 + r[0]));
+		arc_to(x0 + r[0], y0, r[0]);
+		sink->EndFigure(D2D1_FIGURE_END_CLOSED);
+		check_hresult(sink->Close());
+
+		CompositionPath path{make<GeometrySource>(geometry.as<ID2D1Geometry>())};
+		*out = detach_abi(path);
+		return S_OK;
+	} catch (...) {
+		return to_hresult();
+	}
+}
@@ -3,23 +3,44 @@ use cggeom::{box2, prelude::*, Box2};
 use cgmath::{prelude::*, Matrix3, Matrix4};
 use std::{
     cell::{Cell, RefCell},
+    collections::HashMap,
     fmt,
     mem::MaybeUninit,
+    ptr::{null, null_mut},
     rc::Rc,
 };
 use winapi::{
-    shared::{ntdef::HRESULT, windef::HWND},
-    um::winuser,
+    shared::{
+        dxgi::{IDXGIAdapter, IDXGIDevice, DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL},
+        dxgi1_2::{
+            IDXGIFactory2, IDXGISwapChain1, DXGI_ALPHA_MODE_PREMULTIPLIED, DXGI_SCALING_STRETCH,
+            DXGI_SWAP_CHAIN_DESC1,
+        },
+        dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM,
+        dxgitype::{DXGI_SAMPLE_DESC, DXGI_USAGE_RENDER_TARGET_OUTPUT},
+        ntdef::HRESULT,
+        windef::HWND,
+        winerror,
+    },
+    um::{
+        d3d11::{self, ID3D11Device, ID3D11DeviceContext, ID3D11Texture2D},
+        d3dcommon,
+        unknwnbase::IUnknown,
+        winuser,
+    },
+    Interface,
 };
 use winrt::{
     windows::foundation::numerics::{Matrix3x2, Matrix4x4, Vector2, Vector3},
     windows::ui::composition::{
         desktop::IDesktopWindowTarget, CompositionBitmapInterpolationMode, CompositionBrush,
         CompositionClip, CompositionColorBrush, CompositionEffectBrush, CompositionEffectFactory,
-        CompositionEffectSourceParameter, CompositionGeometry, CompositionNineGridBrush,
-        CompositionRectangleGeometry, CompositionStretch, CompositionSurfaceBrush, Compositor,
-        ContainerVisual, ICompositionClip2, ICompositionSurface, ICompositionSurfaceBrush2,
-        ICompositionTarget, ICompositor2, ICompositor5, ICompositor6, SpriteVisual, Visual,
+        CompositionEffectSourceParameter, CompositionGeometricClip, CompositionGeometry,
+        CompositionNineGridBrush, CompositionPath, CompositionPathGeometry,
+        CompositionRectangleGeometry, CompositionShadow, CompositionStretch,
+        CompositionSurfaceBrush, Compositor, ContainerVisual, DropShadow, ICompositionClip2,
+        ICompositionSurface, ICompositionSurfaceBrush2, ICompositionTarget, ICompositor2,
+        ICompositor5, ICompositor6, ISpriteVisual2, SpriteVisual, Visual,
     },
     ComPtr, FastHString, RtDefaultConstructible, RtType,
 };
@@ -31,11 +52,17 @@ use super::{
         winrt_m4x4_from_cgmath, winrt_v2_from_cgmath_pt, winrt_v2_from_cgmath_vec,
     },
     surface,
-    utils::{assert_hresult_ok, assert_win32_ok, ComPtr as MyComPtr},
-    winapiext::ICompositorDesktopInterop,
-    LayerAttrs, Wm,
+    utils::{
+        assert_hresult_ok, assert_win32_ok, panic_hresult, result_from_hresult, ComPtr as MyComPtr,
+    },
+    winapiext::{ICompositorDesktopInterop, ICompositorInterop},
+    BitmapBuilder, LayerAttrs, Wm,
+};
+use crate::{
+    exttex::ExternalTextureFrame,
+    iface::{LayerContents, LayerFlags, LayerShadow, SwapchainTarget, RGBAF32},
+    prelude::MtLazyStatic,
 };
-use crate::{iface::LayerFlags, prelude::MtLazyStatic};
 
 mod effects;
 
@@ -47,6 +74,9 @@ struct CompState {
     comp_desktop: MyComPtr<ICompositorDesktopInterop>,
     backdrop_brush: ComPtr<CompositionBrush>,
     fx_factory: ComPtr<CompositionEffectFactory>,
+    /// The effect factories for `LayerAttrs::backdrop_blur_radius`, keyed by
+    /// the bit pattern of the standard deviation.
+    blur_fx_factories: RefCell<HashMap<u32, ComPtr<CompositionEffectFactory>>>,
     surface_map: surface::SurfaceMap,
     noise_surf: ComPtr<ICompositionSurface>,
 }
@@ -68,12 +98,13 @@ impl CompState {
         let surface_map = surface::SurfaceMap::new(&comp);
 
         // We need `ICompositor2` for `CreateLayerVisual`,
-        // `CreateNineGridBrush`, and `CreateBackdropBrush`
+        // `CreateNineGridBrush`, `CreateBackdropBrush`, and `CreateMaskBrush`
         let comp2: ComPtr<ICompositor2> = comp
             .query_interface()
             .expect("Could not obtain ICompositor2");
 
-        // We need `ICompositor5` for `CreateRectangleGeometry`
+        // We need `ICompositor5` for `CreateRectangleGeometry` and
+        // `CreatePathGeometry`
         let comp5: ComPtr<ICompositor5> = comp
             .query_interface()
             .expect("Could not obtain ICompositor5");
@@ -96,7 +127,8 @@ impl CompState {
             CompositionEffectSourceParameter::create(&FastHString::new("noise")).unwrap();
         let color_src =
             CompositionEffectSourceParameter::create(&FastHString::new("color")).unwrap();
-        let blur_fx = effects::GaussianBlurEffect::new([backdrop_src.query_interface().unwrap()]);
+        let blur_fx =
+            effects::GaussianBlurEffect::new([backdrop_src.query_interface().unwrap()], 15.0);
         let sat_fx = effects::SaturationEffect::new([blur_fx.query_interface().unwrap()]);
         let tint_fx = effects::CompositeEffect::new([
             sat_fx.query_interface().unwrap(),
@@ -130,6 +162,7 @@ impl CompState {
             backdrop_brush,
             noise_surf,
             fx_factory,
+            blur_fx_factories: RefCell::new(HashMap::new()),
             surface_map,
         }
     }
@@ -142,6 +175,11 @@ mt_lazy_static! {
 // Defined in `comp.cpp`
 extern "C" {
     fn tcw_comp_init() -> HRESULT;
+    fn tcw_comp_new_rounded_rect_path(
+        rect: *const f32,
+        radii: *const f32,
+        out: *mut *mut CompositionPath,
+    ) -> HRESULT;
 }
 
 pub(super) struct CompWnd {
@@ -252,17 +290,22 @@ impl std::hash::Hash for HLayer {
 }
 
 struct Layer {
-    // container_vis ~ clip, opacity
+    // container_vis ~ clip (if there's no layer_cvis), opacity
+    // |
+    // +-- shadow.vis (optional) ~ shadow
     // |
-    // +-- layer_cvis (optional)
+    // +-- layer_cvis (optional; required by shadow) ~ clip
+    //     |
+    //     +-- backdrop_blur.vis (optional) ~ backdrop_blur_radius, mask
     //     |
-    //     +-- solid.0 (optional) ~ bg_color
+    //     +-- solid.0 (optional) ~ bg_color, mask
     //	   |
-    //	   +-- image.0 (optional) ~ contents
+    //	   +-- image.0 (optional) ~ contents, swapchain, mask
     //     |
     //	   +-- (sublayers)
     //
-    // - transform is applied to clip, bg_color, and contents
+    // - transform is applied to clip, shadow, backdrop_blur, bg_color, and
+    //   contents
     container_cvis: ComPtr<ContainerVisual>,
     container_vis: ComPtr<Visual>,
     state: RefCell<LayerState>,
@@ -287,21 +330,19 @@ impl fmt::Debug for Layer {
 struct LayerState {
     layer_cvis: Option<ComPtr<ContainerVisual>>,
     solid: Option<Solid>,
-    image: Option<(
-        ComPtr<Visual>,
-        ComPtr<CompositionNineGridBrush>,
-        ComPtr<CompositionSurfaceBrush>,
-    )>,
-    clip: Option<(
-        ComPtr<ICompositionClip2>,
-        ComPtr<CompositionRectangleGeometry>,
-    )>,
+    image: Option<LayerImage>,
+    clip: Option<LayerClip>,
+    mask: Option<LayerMask>,
+    shadow: Option<ShadowVis>,
+    backdrop_blur: Option<BackdropBlurVis>,
     nonopaque: bool,
     flags: LayerFlags,
     sublayers: Vec<HLayer>,
     xform4x4: Matrix4x4,
     xform3x2: Matrix3x2,
     bounds: Box2<f32>,
+    /// `LayerAttrs::corner_radius`
+    corner_radius: [f32; 4],
     /// The pixel size of `LayerAttrs::contents`.
     contents_size: [f32; 2],
     /// `LayerAttrs::contents_center`
@@ -311,19 +352,65 @@ struct LayerState {
     /// `LayerAttrs::contents`. `comp.rs` doesn't read this but needs to keep
     /// it alive so that composition surfaces can be repainted on device lost.
     _contents: Option<Bitmap>,
+    /// The swapchain attached by `set_layer_swapchain`. Supersedes
+    /// `LayerAttrs::contents` while present.
+    swapchain: Option<Swapchain>,
 }
 
+type LayerImage = (
+    ComPtr<Visual>,
+    ComPtr<CompositionNineGridBrush>,
+    ComPtr<CompositionSurfaceBrush>,
+);
+
 struct Solid {
     vis: ComPtr<Visual>,
     svis: ComPtr<SpriteVisual>,
     cbrush: ComPtr<CompositionColorBrush>,
+    /// The brush assigned to `svis` (before applying `LayerState::mask`).
+    /// Either `cbrush` or the backdrop blur effect brush.
+    brush: ComPtr<CompositionBrush>,
     backdrop: Option<BackdropBlurLayer>,
 }
 
+/// The geometric clip applied to `Layer::container_vis`.
+struct LayerClip {
+    clip: ComPtr<ICompositionClip2>,
+    gclip: ComPtr<CompositionGeometricClip>,
+    rect: ComPtr<CompositionRectangleGeometry>,
+    /// Used in place of `rect` if the layer has rounded corners. Created
+    /// lazily.
+    path: Option<ComPtr<CompositionPathGeometry>>,
+}
+
+/// `LayerAttrs::mask`. `comp.rs` needs to keep the bitmap alive for the same
+/// reason as `LayerState::_contents`.
+type LayerMask = (ComPtr<CompositionBrush>, Bitmap);
+
 struct BackdropBlurLayer {
     noise_sbrush2: ComPtr<ICompositionSurfaceBrush2>,
 }
 
+/// A sprite visual casting `LayerAttrs::shadow`. It doesn't have a brush, so
+/// only the shadow is visible.
+struct ShadowVis {
+    vis: ComPtr<Visual>,
+    shadow: ComPtr<DropShadow>,
+    /// The mask defining the shape of the shadow. `comp.rs` needs to keep the
+    /// bitmap alive for the same reason as `LayerState::_contents`.
+    mask: Option<LayerMask>,
+}
+
+/// A sprite visual displaying the blurred backdrop
+/// (`LayerAttrs::backdrop_blur_radius`).
+struct BackdropBlurVis {
+    vis: ComPtr<Visual>,
+    svis: ComPtr<SpriteVisual>,
+    /// The brush assigned to `svis` (before applying `LayerState::mask`).
+    brush: ComPtr<CompositionBrush>,
+    radius: f32,
+}
+
 pub fn new_layer(wm: Wm, attrs: LayerAttrs) -> HLayer {
     let cs = CS.get_with_wm(wm);
 
@@ -338,16 +425,21 @@ pub fn new_layer(wm: Wm, attrs: LayerAttrs) -> HLayer {
             solid: None,
             image: None,
             clip: None,
+            mask: None,
+            shadow: None,
+            backdrop_blur: None,
             nonopaque: false,
             flags: LayerFlags::empty(),
             sublayers: Vec::new(),
             xform4x4: winrt_m4x4_from_cgmath(Matrix4::identity()),
             xform3x2: winrt_m3x2_from_cgmath(Matrix3::identity()),
             bounds: box2! { min: [0.0, 0.0], max: [0.0, 0.0] },
+            corner_radius: [0.0; 4],
             contents_size: [0.0; 2],
             contents_center: box2! { min: [0.0, 0.0], max: [1.0, 1.0] },
             contents_scale: 1.0,
             _contents: None,
+            swapchain: None,
         }),
         dpi_iscale: Cell::new(1.0),
         tmp: Cell::new(NONE),
@@ -378,47 +470,27 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
     }
 
     // Insert `layer_cvis`
-    if state.layer_cvis.is_none() {
-        let needs_layer = state.nonopaque && {
-            let has_solid = state.solid.is_some() | attrs.bg_color.is_some();
-            let has_image = matches!(attrs.contents, Some(Some(_)));
-            let num_sublayers = if let Some(sublayers) = &attrs.sublayers {
-                sublayers.len()
-            } else {
-                state.sublayers.len()
-            };
-
-            has_solid as usize + has_image as usize + num_sublayers > 1
+    {
+        let has_solid = state.solid.is_some() | attrs.bg_color.is_some();
+        let has_image = state.image.is_some() | matches!(attrs.contents, Some(Some(_)));
+        let num_sublayers = if let Some(sublayers) = &attrs.sublayers {
+            sublayers.len()
+        } else {
+            state.sublayers.len()
         };
-
-        if needs_layer {
-            // Construct a `LayerVisual`
-            let layer_lvis = cs.comp2.create_layer_visual().unwrap().unwrap();
-            let layer_cvis: ComPtr<ContainerVisual> = layer_lvis.query_interface().unwrap();
-            let layer_vis: ComPtr<Visual> = layer_lvis.query_interface().unwrap();
-
-            // Move everything from `container_cvis` to `layer_lvis`.
-            let container_cvis: &ComPtr<ContainerVisual> = &layer.container_cvis;
-
-            let layer_children = layer_cvis.get_children().unwrap().unwrap();
-            let container_children = container_cvis.get_children().unwrap().unwrap();
-
-            container_children.remove_all().unwrap();
-            container_children.insert_at_top(&layer_vis).unwrap();
-
-            if let Some(Solid { vis, .. }) = &state.solid {
-                layer_children.insert_at_top(&vis).unwrap();
-            }
-            if let Some((vis, _, _)) = &state.image {
-                layer_children.insert_at_top(&vis).unwrap();
-            }
-            for sublayer in state.sublayers.iter() {
-                layer_children
-                    .insert_at_top(&sublayer.layer.container_vis)
-                    .unwrap();
-            }
-            state.layer_cvis = Some(layer_cvis);
-        }
+        let has_shadow = match attrs.shadow {
+            Some(shadow) => shadow.is_some(),
+            None => state.shadow.is_some(),
+        };
+        ensure_layer_cvis(
+            cs,
+            layer,
+            state,
+            has_solid,
+            has_image,
+            num_sublayers,
+            has_shadow,
+        );
     }
 
     // The existence or lack of `state.layer_cvis` is immutable beyond this
@@ -432,7 +504,7 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
     if let Some(mat) = attrs.transform {
         state.xform4x4 = winrt_m4x4_from_cgmath(extend_matrix3_with_identity_z(mat));
         state.xform3x2 = winrt_m3x2_from_cgmath(mat);
-        if let Some((clip, _)) = &state.clip {
+        if let Some(LayerClip { clip, .. }) = &state.clip {
             clip.set_transform_matrix(state.xform3x2).unwrap();
         }
         if let Some(Solid { vis, .. }) = &state.solid {
@@ -441,28 +513,35 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
         if let Some((vis, _, _)) = &state.image {
             vis.set_transform_matrix(state.xform4x4).unwrap();
         }
+        if let Some(ShadowVis { vis, .. }) = &state.shadow {
+            vis.set_transform_matrix(state.xform4x4).unwrap();
+        }
+        if let Some(BackdropBlurVis { vis, .. }) = &state.backdrop_blur {
+            vis.set_transform_matrix(state.xform4x4).unwrap();
+        }
     }
 
-    let bounds_to_anchor = |b: Box2<f32>| Vector2 {
-        X: -b.min.x / b.size().x,
-        Y: -b.min.y / b.size().y,
-    };
-
     if let Some(bounds) = attrs.bounds {
         state.bounds = bounds;
-        if let Some((_, rect)) = &state.clip {
-            rect.set_offset(winrt_v2_from_cgmath_pt(state.bounds.min))
+        if let Some(Solid { vis, .. }) = &state.solid {
+            vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
                 .unwrap();
-            rect.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
+            vis.set_anchor_point(bounds_to_anchor(state.bounds))
                 .unwrap();
         }
-        if let Some(Solid { vis, .. }) = &state.solid {
+        if let Some((vis, _, _)) = &state.image {
             vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
                 .unwrap();
             vis.set_anchor_point(bounds_to_anchor(state.bounds))
                 .unwrap();
         }
-        if let Some((vis, _, _)) = &state.image {
+        if let Some(ShadowVis { vis, .. }) = &state.shadow {
+            vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
+                .unwrap();
+            vis.set_anchor_point(bounds_to_anchor(state.bounds))
+                .unwrap();
+        }
+        if let Some(BackdropBlurVis { vis, .. }) = &state.backdrop_blur {
             vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
                 .unwrap();
             vis.set_anchor_point(bounds_to_anchor(state.bounds))
@@ -470,56 +549,88 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
         }
     }
 
-    // The parameters for 9-grid scaling are dependent on various inputs
-    let update_slicing =
-        attrs.contents.is_some() | attrs.contents_center.is_some() | attrs.contents_scale.is_some();
-
-    if let Some(contents) = attrs.contents {
-        let (_, _, sbrush) = if let Some(x) = &state.image {
-            x
-        } else {
-            // Create `state.image` and set properties
+    if let Some(mask) = attrs.mask {
+        state.mask = mask.map(|bitmap| {
+            let surface = cs.surface_map.get_surface_for_bitmap(wm, &bitmap);
             let sbrush = cs.comp.create_surface_brush().unwrap().unwrap();
             sbrush.set_stretch(CompositionStretch::Fill).unwrap();
+            sbrush.set_surface(&surface).unwrap();
+            (sbrush.query_interface().unwrap(), bitmap)
+        });
 
-            let nbrush = cs.comp2.create_nine_grid_brush().unwrap().unwrap();
-            nbrush
-                .set_source(&sbrush.query_interface::<CompositionBrush>().unwrap())
-                .unwrap();
+        // Re-assign the brushes to apply the new mask
+        if let Some(Solid { svis, brush, .. }) = &state.solid {
+            set_sprite_brush(cs, svis, brush, &state.mask);
+        }
+        if let Some((vis, nbrush, _)) = &state.image {
+            set_sprite_brush(
+                cs,
+                &vis.query_interface::<SpriteVisual>().unwrap(),
+                &nbrush.query_interface::<CompositionBrush>().unwrap(),
+                &state.mask,
+            );
+        }
+        if let Some(BackdropBlurVis { svis, brush, .. }) = &state.backdrop_blur {
+            set_sprite_brush(cs, svis, brush, &state.mask);
+        }
+    }
 
-            let svis = cs.comp.create_sprite_visual().unwrap().unwrap();
-            let vis: ComPtr<Visual> = svis.query_interface().unwrap();
+    if let Some(radius) = attrs.backdrop_blur_radius {
+        update_layer_backdrop_blur(cs, state, visuals_container_cvis, radius);
+    }
 
-            svis.set_brush(&nbrush.query_interface::<CompositionBrush>().unwrap())
-                .unwrap();
+    // The parameters for 9-grid scaling are dependent on various inputs
+    let update_slicing =
+        attrs.contents.is_some() | attrs.contents_center.is_some() | attrs.contents_scale.is_some();
 
-            vis.set_transform_matrix(state.xform4x4).unwrap();
-            vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
-                .unwrap();
-            vis.set_anchor_point(bounds_to_anchor(state.bounds))
-                .unwrap();
+    // `Some(frame)` if `contents` is set to `LayerContents::ExternalTexture`.
+    // It's processed at last because it needs the entirety of `state`.
+    let mut external_frame = None;
 
-            // Insert the newly created visual to the correct position
-            let children = visuals_container_cvis.get_children().unwrap().unwrap();
-            if let Some(Solid { vis: solid_vis, .. }) = &state.solid {
-                children.insert_above(&vis, solid_vis).unwrap();
-            } else {
-                children.insert_at_bottom(&vis).unwrap();
-            }
+    let contents = match attrs.contents {
+        Some(Some(LayerContents::ExternalTexture(tex))) => {
+            external_frame = Some(tex.latch());
+            None
+        }
+        Some(Some(LayerContents::Bitmap(bitmap))) => Some(Some(bitmap)),
+        Some(None) => Some(None),
+        None => None,
+    };
 
-            state.image = Some((vis, nbrush, sbrush));
-            state.image.as_ref().unwrap()
-        };
+    if external_frame.is_some() || contents.is_some() {
+        debug_assert!(
+            state.swapchain.as_ref().map_or(true, |sc| sc.external),
+            "`contents` can't be set while a swapchain is attached"
+        );
+    }
+
+    if let Some(contents) = contents {
+        // Detach the swapchain used to display `ExternalTexture`
+        state.swapchain = None;
+
+        let (vis, _, sbrush) = ensure_layer_image(
+            cs,
+            &mut state.image,
+            &state.solid,
+            &state.backdrop_blur,
+            &state.mask,
+            state.xform4x4,
+            state.bounds,
+            visuals_container_cvis,
+        );
 
         if let Some(bitmap) = &contents {
             let surface = cs.surface_map.get_surface_for_bitmap(wm, bitmap);
             sbrush.set_surface(&surface).unwrap();
+            vis.set_is_visible(true).unwrap();
 
             use crate::iface::Bitmap;
             use array::Array2;
             state.contents_size = bitmap.size().map(|i| i as f32);
         } else {
-            // TODO: Clear the contents
+            // `CompositionSurfaceBrush` doesn't let us unset the surface, so
+            // hide the visual instead
+            vis.set_is_visible(false).unwrap();
         }
 
         state._contents = contents;
@@ -533,27 +644,8 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
         state.contents_scale = scale;
     }
 
-    if let (Some((_, nbrush, _)), true) = (&state.image, update_slicing) {
-        // Update the 9-grid slicing parameters if any of relevant
-        // inputs have changed
-        let scale = 1.0 / state.contents_scale;
-        nbrush.set_top_inset_scale(scale).unwrap();
-        nbrush.set_right_inset_scale(scale).unwrap();
-        nbrush.set_bottom_inset_scale(scale).unwrap();
-        nbrush.set_left_inset_scale(scale).unwrap();
-
-        let center = state.contents_center;
-        let csize = state.contents_size;
-        let margins = [
-            center.min.y * csize[1],
-            (1.0 - center.max.x) * csize[0],
-            (1.0 - center.max.y) * csize[1],
-            center.min.x * csize[0],
-        ];
-        nbrush.set_top_inset(margins[0]).unwrap();
-        nbrush.set_right_inset(margins[1]).unwrap();
-        nbrush.set_bottom_inset(margins[2]).unwrap();
-        nbrush.set_left_inset(margins[3]).unwrap();
+    if update_slicing {
+        update_layer_image_slicing(state);
     }
 
     let new_flags = attrs.flags.unwrap_or(state.flags);
@@ -565,6 +657,7 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
         let Solid {
             svis,
             cbrush,
+            brush,
             backdrop,
             ..
         } = if let Some(x) = &mut state.solid {
@@ -573,11 +666,12 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
             // Create `state.solid` and set properties
             let cbrush = cs.comp.create_color_brush().unwrap().unwrap();
 
+            let brush: ComPtr<CompositionBrush> = cbrush.query_interface().unwrap();
+
             let svis = cs.comp.create_sprite_visual().unwrap().unwrap();
             let vis: ComPtr<Visual> = svis.query_interface().unwrap();
 
-            svis.set_brush(&cbrush.query_interface::<CompositionBrush>().unwrap())
-                .unwrap();
+            set_sprite_brush(cs, &svis, &brush, &state.mask);
 
             vis.set_transform_matrix(state.xform4x4).unwrap();
             vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
@@ -587,12 +681,17 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
 
             // Insert the newly created visual to the correct position
             let children = visuals_container_cvis.get_children().unwrap().unwrap();
-            children.insert_at_bottom(&vis).unwrap();
+            if let Some(BackdropBlurVis { vis: bd_vis, .. }) = &state.backdrop_blur {
+                children.insert_above(&vis, bd_vis).unwrap();
+            } else {
+                children.insert_at_bottom(&vis).unwrap();
+            }
 
             state.solid = Some(Solid {
                 vis,
                 svis,
                 cbrush,
+                brush,
                 backdrop: None,
             });
             state.solid.as_mut().unwrap()
@@ -641,14 +740,13 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
                     )
                     .unwrap();
 
-                let fx_brush: ComPtr<CompositionBrush> = fx_ebrush.query_interface().unwrap();
-
-                svis.set_brush(&fx_brush).unwrap();
-
+                *brush = fx_ebrush.query_interface().unwrap();
                 *backdrop = Some(BackdropBlurLayer { noise_sbrush2 });
             } else {
-                svis.set_brush(&cbrush.query_interface().unwrap()).unwrap();
+                *brush = cbrush.query_interface().unwrap();
             }
+
+            set_sprite_brush(cs, svis, brush, &state.mask);
         }
 
         if let Some(color) = attrs.bg_color {
@@ -688,6 +786,8 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
             Some(&**vis)
         } else if let Some(Solid { vis, .. }) = &state.solid {
             Some(&**vis)
+        } else if let Some(BackdropBlurVis { vis, .. }) = &state.backdrop_blur {
+            Some(&**vis)
         } else {
             None
         };
@@ -745,40 +845,524 @@ pub fn set_layer_attr(wm: Wm, hlayer: &HLayer, attrs: LayerAttrs) {
         state.sublayers = sublayers;
     }
 
-    if (new_flags ^ state.flags).contains(LayerFlags::MASK_TO_BOUNDS) {
-        if new_flags.contains(LayerFlags::MASK_TO_BOUNDS) {
-            let (clip, _) = if let Some(x) = &state.clip {
-                x
-            } else {
-                // Create `state.clip` and set properties
-                let rect = cs.comp5.create_rectangle_geometry().unwrap().unwrap();
+    if let Some(corner_radius) = attrs.corner_radius {
+        state.corner_radius = corner_radius;
+    }
 
-                rect.set_offset(winrt_v2_from_cgmath_pt(state.bounds.min))
-                    .unwrap();
-                rect.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
-                    .unwrap();
+    if let Some(shadow) = attrs.shadow {
+        update_layer_shadow(cs, wm, layer, state, shadow);
+    }
+    if state.shadow.is_some() && (attrs.bounds.is_some() || attrs.corner_radius.is_some()) {
+        update_layer_shadow_mask(cs, wm, state);
+    }
 
-                let gclip = cs.comp6.create_geometric_clip().unwrap().unwrap();
-                gclip
-                    .set_geometry(&rect.query_interface::<CompositionGeometry>().unwrap())
-                    .unwrap();
+    // Rounded corners and a mask imply `MASK_TO_BOUNDS`
+    let needs_clip = new_flags.contains(LayerFlags::MASK_TO_BOUNDS)
+        || has_corner_radius(state.corner_radius)
+        || state.mask.is_some();
+    let update_clip = attrs.bounds.is_some() || attrs.corner_radius.is_some();
 
-                let clip: ComPtr<ICompositionClip2> = gclip.query_interface().unwrap();
-                clip.set_transform_matrix(state.xform3x2).unwrap();
+    // TODO: Remove the clip when it's no longer needed
+    //       (`layer.container_vis.set_clip(None)`)
+    if needs_clip && state.clip.is_none() || state.clip.is_some() && update_clip {
+        update_layer_clip(cs, layer, state);
+    }
+    state.flags = new_flags;
 
-                state.clip = Some((clip, rect));
-                state.clip.as_ref().unwrap()
-            };
+    match external_frame {
+        Some(Some(frame)) => {
+            present_external_texture_frame(cs, state, layer, &frame);
+            state._contents = None;
+        }
+        Some(None) => {
+            // No frames have been submitted yet
+            detach_layer_swapchain(state);
+            state._contents = None;
+        }
+        None => {}
+    }
+}
+
+fn bounds_to_anchor(b: Box2<f32>) -> Vector2 {
+    Vector2 {
+        X: -b.min.x / b.size().x,
+        Y: -b.min.y / b.size().y,
+    }
+}
+
+fn has_corner_radius(corner_radius: [f32; 4]) -> bool {
+    corner_radius.iter().any(|&r| r > 0.0)
+}
+
+/// Create `LayerState::clip` if it doesn't exist yet, and update its geometry
+/// to match `LayerState::bounds` and `LayerState::corner_radius`.
+fn update_layer_clip(cs: &CompState, layer: &Layer, state: &mut LayerState) {
+    if state.clip.is_none() {
+        // Create `state.clip` and set properties
+        let rect = cs.comp5.create_rectangle_geometry().unwrap().unwrap();
+        let gclip = cs.comp6.create_geometric_clip().unwrap().unwrap();
+
+        let clip: ComPtr<ICompositionClip2> = gclip.query_interface().unwrap();
+        clip.set_transform_matrix(state.xform3x2).unwrap();
+
+        // The clip must not apply to the shadow, so it's applied to
+        // `layer_cvis` if there is one
+        let clip_vis: ComPtr<Visual> = if let Some(layer_cvis) = &state.layer_cvis {
+            layer_cvis.query_interface().unwrap()
+        } else {
+            layer.container_vis.clone()
+        };
+        clip_vis
+            .set_clip(&clip.query_interface::<CompositionClip>().unwrap())
+            .unwrap();
+
+        state.clip = Some(LayerClip {
+            clip,
+            gclip,
+            rect,
+            path: None,
+        });
+    }
+
+    let LayerClip {
+        gclip, rect, path, ..
+    } = state.clip.as_mut().unwrap();
 
-            layer
-                .container_vis
-                .set_clip(&clip.query_interface::<CompositionClip>().unwrap())
+    let bounds = state.bounds;
+
+    if has_corner_radius(state.corner_radius) {
+        let new_path = unsafe {
+            let rect = [bounds.min.x, bounds.min.y, bounds.size().x, bounds.size().y];
+            let mut out = MaybeUninit::uninit();
+            assert_hresult_ok(tcw_comp_new_rounded_rect_path(
+                rect.as_ptr(),
+                state.corner_radius.as_ptr(),
+                out.as_mut_ptr(),
+            ));
+            ComPtr::wrap(out.assume_init())
+        };
+
+        if path.is_none() {
+            *path = Some(cs.comp5.create_path_geometry().unwrap().unwrap());
+        }
+        let path = path.as_ref().unwrap();
+        path.set_path(&new_path).unwrap();
+
+        gclip
+            .set_geometry(&path.query_interface::<CompositionGeometry>().unwrap())
+            .unwrap();
+    } else {
+        rect.set_offset(winrt_v2_from_cgmath_pt(bounds.min))
+            .unwrap();
+        rect.set_size(winrt_v2_from_cgmath_vec(bounds.size()))
+            .unwrap();
+
+        gclip
+            .set_geometry(&rect.query_interface::<CompositionGeometry>().unwrap())
+            .unwrap();
+    }
+}
+
+/// Assign `brush` to `svis`, masked by `mask` if any.
+fn set_sprite_brush(
+    cs: &CompState,
+    svis: &SpriteVisual,
+    brush: &CompositionBrush,
+    mask: &Option<LayerMask>,
+) {
+    if let Some((mask_brush, _)) = mask {
+        let mbrush = cs.comp2.create_mask_brush().unwrap().unwrap();
+        mbrush.set_source(brush).unwrap();
+        mbrush.set_mask(mask_brush).unwrap();
+        svis.set_brush(&mbrush.query_interface::<CompositionBrush>().unwrap())
+            .unwrap();
+    } else {
+        svis.set_brush(brush).unwrap();
+    }
+}
+
+/// Insert `LayerState::layer_cvis` if the layer is going to need one to
+/// apply the opacity to its contents as a group or to separate its contents
+/// from its shadow.
+fn ensure_layer_cvis(
+    cs: &CompState,
+    layer: &Layer,
+    state: &mut LayerState,
+    has_solid: bool,
+    has_image: bool,
+    num_sublayers: usize,
+    has_shadow: bool,
+) {
+    if state.layer_cvis.is_some() {
+        return;
+    }
+
+    let needs_layer = has_shadow
+        || state.nonopaque && has_solid as usize + has_image as usize + num_sublayers > 1;
+
+    if needs_layer {
+        // Construct a `LayerVisual`
+        let layer_lvis = cs.comp2.create_layer_visual().unwrap().unwrap();
+        let layer_cvis: ComPtr<ContainerVisual> = layer_lvis.query_interface().unwrap();
+        let layer_vis: ComPtr<Visual> = layer_lvis.query_interface().unwrap();
+
+        // Move everything from `container_cvis` to `layer_lvis`.
+        let container_cvis: &ComPtr<ContainerVisual> = &layer.container_cvis;
+
+        let layer_children = layer_cvis.get_children().unwrap().unwrap();
+        let container_children = container_cvis.get_children().unwrap().unwrap();
+
+        container_children.remove_all().unwrap();
+        container_children.insert_at_top(&layer_vis).unwrap();
+
+        // Move the clip as well. `Visual::Clip` can't be unset, so replace
+        // the old one with a clip that doesn't clip anything.
+        if let Some(LayerClip { clip, .. }) = &state.clip {
+            let clip: ComPtr<CompositionClip> = clip.query_interface().unwrap();
+            layer_vis.set_clip(&clip).unwrap();
+            layer.container_vis.set_clip(&new_noop_clip(cs)).unwrap();
+        }
+
+        if let Some(BackdropBlurVis { vis, .. }) = &state.backdrop_blur {
+            layer_children.insert_at_top(&vis).unwrap();
+        }
+        if let Some(Solid { vis, .. }) = &state.solid {
+            layer_children.insert_at_top(&vis).unwrap();
+        }
+        if let Some((vis, _, _)) = &state.image {
+            layer_children.insert_at_top(&vis).unwrap();
+        }
+        for sublayer in state.sublayers.iter() {
+            layer_children
+                .insert_at_top(&sublayer.layer.container_vis)
                 .unwrap();
+        }
+        state.layer_cvis = Some(layer_cvis);
+    }
+}
+
+/// Create `LayerState::image` if it doesn't exist yet.
+fn ensure_layer_image<'a>(
+    cs: &CompState,
+    image: &'a mut Option<LayerImage>,
+    solid: &Option<Solid>,
+    backdrop_blur: &Option<BackdropBlurVis>,
+    mask: &Option<LayerMask>,
+    xform4x4: Matrix4x4,
+    bounds: Box2<f32>,
+    visuals_container_cvis: &ContainerVisual,
+) -> &'a LayerImage {
+    if image.is_none() {
+        let sbrush = cs.comp.create_surface_brush().unwrap().unwrap();
+        sbrush.set_stretch(CompositionStretch::Fill).unwrap();
+
+        let nbrush = cs.comp2.create_nine_grid_brush().unwrap().unwrap();
+        nbrush
+            .set_source(&sbrush.query_interface::<CompositionBrush>().unwrap())
+            .unwrap();
+
+        let svis = cs.comp.create_sprite_visual().unwrap().unwrap();
+        let vis: ComPtr<Visual> = svis.query_interface().unwrap();
+
+        set_sprite_brush(
+            cs,
+            &svis,
+            &nbrush.query_interface::<CompositionBrush>().unwrap(),
+            mask,
+        );
+
+        vis.set_transform_matrix(xform4x4).unwrap();
+        vis.set_size(winrt_v2_from_cgmath_vec(bounds.size()))
+            .unwrap();
+        vis.set_anchor_point(bounds_to_anchor(bounds)).unwrap();
+
+        // Insert the newly created visual to the correct position
+        let children = visuals_container_cvis.get_children().unwrap().unwrap();
+        if let Some(Solid { vis: solid_vis, .. }) = solid {
+            children.insert_above(&vis, solid_vis).unwrap();
+        } else if let Some(BackdropBlurVis { vis: bd_vis, .. }) = backdrop_blur {
+            children.insert_above(&vis, bd_vis).unwrap();
         } else {
-            // TODO: layer.container_vis.set_clip(None);
+            children.insert_at_bottom(&vis).unwrap();
         }
+
+        *image = Some((vis, nbrush, sbrush));
     }
-    state.flags = new_flags;
+
+    image.as_ref().unwrap()
+}
+
+/// Create a clip that doesn't clip anything in practice.
+fn new_noop_clip(cs: &CompState) -> ComPtr<CompositionClip> {
+    const EXTENT: f32 = 1.0e7;
+    let rect = cs.comp5.create_rectangle_geometry().unwrap().unwrap();
+    rect.set_offset(Vector2 {
+        X: -EXTENT,
+        Y: -EXTENT,
+    })
+    .unwrap();
+    rect.set_size(Vector2 {
+        X: EXTENT * 2.0,
+        Y: EXTENT * 2.0,
+    })
+    .unwrap();
+
+    let gclip = cs
+        .comp6
+        .create_geometric_clip_with_geometry(
+            &rect.query_interface::<CompositionGeometry>().unwrap(),
+        )
+        .unwrap()
+        .unwrap();
+    gclip.query_interface().unwrap()
+}
+
+/// Create, update, or remove `LayerState::shadow` according to
+/// `LayerAttrs::shadow`. `LayerState::layer_cvis` must exist if `shadow` is
+/// `Some(_)`.
+fn update_layer_shadow(
+    cs: &CompState,
+    wm: Wm,
+    layer: &Layer,
+    state: &mut LayerState,
+    shadow: Option<LayerShadow>,
+) {
+    let container_children = layer.container_cvis.get_children().unwrap().unwrap();
+
+    let shadow = if let Some(x) = shadow {
+        x
+    } else {
+        if let Some(ShadowVis { vis, .. }) = state.shadow.take() {
+            container_children.remove(&vis).unwrap();
+        }
+        return;
+    };
+
+    if state.shadow.is_none() {
+        debug_assert!(state.layer_cvis.is_some());
+
+        let drop_shadow = cs.comp2.create_drop_shadow().unwrap().unwrap();
+
+        let svis = cs.comp.create_sprite_visual().unwrap().unwrap();
+        let svis2: ComPtr<ISpriteVisual2> = svis.query_interface().unwrap();
+        svis2
+            .set_shadow(&drop_shadow.query_interface::<CompositionShadow>().unwrap())
+            .unwrap();
+
+        let vis: ComPtr<Visual> = svis.query_interface().unwrap();
+        vis.set_transform_matrix(state.xform4x4).unwrap();
+        vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
+            .unwrap();
+        vis.set_anchor_point(bounds_to_anchor(state.bounds))
+            .unwrap();
+
+        // Place it below `layer_cvis`
+        container_children.insert_at_bottom(&vis).unwrap();
+
+        state.shadow = Some(ShadowVis {
+            vis,
+            shadow: drop_shadow,
+            mask: None,
+        });
+
+        update_layer_shadow_mask(cs, wm, state);
+    }
+
+    let drop_shadow = &state.shadow.as_ref().unwrap().shadow;
+    drop_shadow
+        .set_offset(Vector3 {
+            X: shadow.offset.x,
+            Y: shadow.offset.y,
+            Z: 0.0,
+        })
+        .unwrap();
+    drop_shadow.set_blur_radius(shadow.radius.max(0.0)).unwrap();
+    drop_shadow
+        .set_color(winrt_color_from_rgbaf32(shadow.color))
+        .unwrap();
+}
+
+/// Update the mask of `LayerState::shadow` to match `LayerState::bounds` and
+/// `LayerState::corner_radius`.
+fn update_layer_shadow_mask(cs: &CompState, wm: Wm, state: &mut LayerState) {
+    let ShadowVis { shadow, mask, .. } = if let Some(x) = &mut state.shadow {
+        x
+    } else {
+        return;
+    };
+
+    if !has_corner_radius(state.corner_radius) {
+        // An opaque brush casts a rectangular shadow
+        let cbrush = cs.comp.create_color_brush().unwrap().unwrap();
+        cbrush
+            .set_color(winrt_color_from_rgbaf32(RGBAF32::new(1.0, 1.0, 1.0, 1.0)))
+            .unwrap();
+        shadow
+            .set_mask(&cbrush.query_interface::<CompositionBrush>().unwrap())
+            .unwrap();
+        *mask = None;
+        return;
+    }
+
+    // Scale down the radii in the same way as `canvas_rounded_rect` does
+    let size = state.bounds.size();
+    let r = state.corner_radius;
+    let factor = [
+        1.0,
+        size.x / (r[0] + r[1]),
+        size.x / (r[3] + r[2]),
+        size.y / (r[1] + r[2]),
+        size.y / (r[0] + r[3]),
+    ]
+    .iter()
+    .fold(f32::INFINITY, |x, &y| x.min(y));
+    let r = [r[0] * factor, r[1] * factor, r[2] * factor, r[3] * factor];
+
+    // Draw a rounded rectangle with a 1x1 center region, which is stretched
+    // by a nine-grid brush
+    let insets = [
+        r[0].max(r[3]).ceil(), // left
+        r[0].max(r[1]).ceil(), // top
+        r[1].max(r[2]).ceil(), // right
+        r[2].max(r[3]).ceil(), // bottom
+    ];
+    let bmp_size = [insets[0] + insets[2] + 1.0, insets[1] + insets[3] + 1.0];
+
+    let bmp = {
+        use crate::iface::{BitmapBuilder as _, BitmapBuilderNew, Canvas};
+        let mut builder = BitmapBuilder::new([bmp_size[0] as u32, bmp_size[1] as u32]);
+        builder.set_fill_rgb(RGBAF32::new(1.0, 1.0, 1.0, 1.0));
+        builder.rounded_rect(
+            box2! { min: [0.0, 0.0], max: [bmp_size[0], bmp_size[1]] },
+            [[r[0]; 2], [r[1]; 2], [r[2]; 2], [r[3]; 2]],
+        );
+        builder.fill();
+        builder.into_bitmap()
+    };
+
+    let surface = cs.surface_map.get_surface_for_bitmap(wm, &bmp);
+    let sbrush = cs.comp.create_surface_brush().unwrap().unwrap();
+    sbrush.set_stretch(CompositionStretch::Fill).unwrap();
+    sbrush.set_surface(&surface).unwrap();
+
+    let nbrush = cs.comp2.create_nine_grid_brush().unwrap().unwrap();
+    nbrush
+        .set_source(&sbrush.query_interface::<CompositionBrush>().unwrap())
+        .unwrap();
+    nbrush.set_left_inset(insets[0]).unwrap();
+    nbrush.set_top_inset(insets[1]).unwrap();
+    nbrush.set_right_inset(insets[2]).unwrap();
+    nbrush.set_bottom_inset(insets[3]).unwrap();
+
+    let brush: ComPtr<CompositionBrush> = nbrush.query_interface().unwrap();
+    shadow.set_mask(&brush).unwrap();
+    *mask = Some((brush, bmp));
+}
+
+/// Create, update, or remove `LayerState::backdrop_blur` according to
+/// `LayerAttrs::backdrop_blur_radius`.
+fn update_layer_backdrop_blur(
+    cs: &CompState,
+    state: &mut LayerState,
+    visuals_container_cvis: &ContainerVisual,
+    radius: f32,
+) {
+    let children = visuals_container_cvis.get_children().unwrap().unwrap();
+
+    if radius <= 0.0 {
+        if let Some(BackdropBlurVis { vis, .. }) = state.backdrop_blur.take() {
+            children.remove(&vis).unwrap();
+        }
+        return;
+    }
+
+    if let Some(bd) = &state.backdrop_blur {
+        if bd.radius == radius {
+            return;
+        }
+    }
+
+    // Create an effect brush. The effect factory is shared by all layers
+    // having the same radius.
+    let std_dev = radius * 0.5;
+    let brush: ComPtr<CompositionBrush> = {
+        let mut factories = cs.blur_fx_factories.borrow_mut();
+        let fx_factory = factories.entry(std_dev.to_bits()).or_insert_with(|| {
+            let backdrop_src =
+                CompositionEffectSourceParameter::create(&FastHString::new("backdrop")).unwrap();
+            let blur_fx = effects::GaussianBlurEffect::new(
+                [backdrop_src.query_interface().unwrap()],
+                std_dev,
+            );
+            cs.comp
+                .create_effect_factory(&blur_fx.query_interface().unwrap())
+                .unwrap()
+                .unwrap()
+        });
+
+        let fx_ebrush: ComPtr<CompositionEffectBrush> = fx_factory.create_brush().unwrap().unwrap();
+        fx_ebrush
+            .set_source_parameter(&FastHString::new("backdrop"), &cs.backdrop_brush)
+            .unwrap();
+        fx_ebrush.query_interface().unwrap()
+    };
+
+    if let Some(bd) = &mut state.backdrop_blur {
+        set_sprite_brush(cs, &bd.svis, &brush, &state.mask);
+        bd.brush = brush;
+        bd.radius = radius;
+        return;
+    }
+
+    let svis = cs.comp.create_sprite_visual().unwrap().unwrap();
+    let vis: ComPtr<Visual> = svis.query_interface().unwrap();
+
+    set_sprite_brush(cs, &svis, &brush, &state.mask);
+
+    vis.set_transform_matrix(state.xform4x4).unwrap();
+    vis.set_size(winrt_v2_from_cgmath_vec(state.bounds.size()))
+        .unwrap();
+    vis.set_anchor_point(bounds_to_anchor(state.bounds))
+        .unwrap();
+
+    // It's the bottommost visual of the layer
+    children.insert_at_bottom(&vis).unwrap();
+
+    state.backdrop_blur = Some(BackdropBlurVis {
+        vis,
+        svis,
+        brush,
+        radius,
+    });
+}
+
+/// Update the 9-grid slicing parameters of `LayerState::image`. Must be
+/// called whenever any of the relevant inputs change.
+fn update_layer_image_slicing(state: &LayerState) {
+    let nbrush = if let Some((_, nbrush, _)) = &state.image {
+        nbrush
+    } else {
+        return;
+    };
+
+    let scale = 1.0 / state.contents_scale;
+    nbrush.set_top_inset_scale(scale).unwrap();
+    nbrush.set_right_inset_scale(scale).unwrap();
+    nbrush.set_bottom_inset_scale(scale).unwrap();
+    nbrush.set_left_inset_scale(scale).unwrap();
+
+    let center = state.contents_center;
+    let csize = state.contents_size;
+    let margins = [
+        center.min.y * csize[1],
+        (1.0 - center.max.x) * csize[0],
+        (1.0 - center.max.y) * csize[1],
+        center.min.x * csize[0],
+    ];
+    nbrush.set_top_inset(margins[0]).unwrap();
+    nbrush.set_right_inset(margins[1]).unwrap();
+    nbrush.set_bottom_inset(margins[2]).unwrap();
+    nbrush.set_left_inset(margins[3]).unwrap();
 }
 
 fn set_layer_dpi_scale(hlayer: &HLayer, new_dpi_iscale: f32) {
@@ -830,6 +1414,359 @@ fn is_layer_list_unique(layers: &[HLayer]) -> bool {
     is_unique
 }
 
+/// A DXGI swapchain attached to a layer by `set_layer_swapchain`.
+struct Swapchain {
+    device: MyComPtr<ID3D11Device>,
+    swapchain: MyComPtr<IDXGISwapChain1>,
+    size: [u32; 2],
+    /// `true` if the swapchain displays `LayerContents::ExternalTexture`
+    /// rather than being attached by `set_layer_swapchain`.
+    external: bool,
+}
+
+impl Swapchain {
+    /// Create a swapchain and a composition surface presenting its contents.
+    ///
+    /// Each swapchain gets its own Direct3D device so that the application's
+    /// rendering code can't interfere with `surface::SurfaceMap`'s device.
+    fn new(
+        cs: &CompState,
+        size: [u32; 2],
+        external: bool,
+    ) -> Result<(Self, ComPtr<ICompositionSurface>), HRESULT> {
+        let device: MyComPtr<ID3D11Device> = unsafe {
+            let mut out = MaybeUninit::uninit();
+            result_from_hresult(d3d11::D3D11CreateDevice(
+                null_mut(), // default adapter
+                d3dcommon::D3D_DRIVER_TYPE_HARDWARE,
+                null_mut(), // not asking for a SW driver, so not passing a module to one
+                d3d11::D3D11_CREATE_DEVICE_BGRA_SUPPORT, // needed for Direct2D interop
+                null(),     // default feature levels
+                0,
+                d3d11::D3D11_SDK_VERSION,
+                out.as_mut_ptr(),
+                null_mut(), // not interested in which feature level is chosen
+                null_mut(), // not interested in `ID3D11DeviceContext`
+            ))?;
+            MyComPtr::from_ptr_unchecked(out.assume_init())
+        };
+
+        // Find the `IDXGIFactory2` the device was created from
+        let dxgi_device: MyComPtr<IDXGIDevice> = device.query_interface().unwrap();
+        let adapter: MyComPtr<IDXGIAdapter> = unsafe {
+            let mut out = MaybeUninit::uninit();
+            result_from_hresult(dxgi_device.GetAdapter(out.as_mut_ptr()))?;
+            MyComPtr::from_ptr_unchecked(out.assume_init())
+        };
+        let factory: MyComPtr<IDXGIFactory2> = unsafe {
+            let mut out = MaybeUninit::uninit();
+            result_from_hresult(adapter.GetParent(&IDXGIFactory2::uuidof(), out.as_mut_ptr()))?;
+            MyComPtr::from_ptr_unchecked(out.assume_init() as *mut IDXGIFactory2)
+        };
+
+        let desc = DXGI_SWAP_CHAIN_DESC1 {
+            Width: size[0],
+            Height: size[1],
+            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            Stereo: 0,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            BufferCount: 2,
+            Scaling: DXGI_SCALING_STRETCH,
+            SwapEffect: DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL,
+            AlphaMode: DXGI_ALPHA_MODE_PREMULTIPLIED,
+            Flags: 0,
+        };
+
+        let swapchain: MyComPtr<IDXGISwapChain1> = unsafe {
+            let mut out = MaybeUninit::uninit();
+            result_from_hresult(factory.CreateSwapChainForComposition(
+                device.as_ptr() as *mut IUnknown,
+                &desc,
+                null_mut(), // don't restrict to an output
+                out.as_mut_ptr(),
+            ))?;
+            MyComPtr::from_ptr_unchecked(out.assume_init())
+        };
+
+        // Wrap the swapchain with `ICompositionSurface`
+        let comp_interop: MyComPtr<ICompositorInterop> =
+            MyComPtr::iunknown_from_winrt_comptr(cs.comp.clone())
+                .query_interface()
+                .unwrap();
+        let surface = unsafe {
+            let mut out = MaybeUninit::uninit();
+            result_from_hresult(comp_interop.CreateCompositionSurfaceForSwapChain(
+                swapchain.as_ptr() as *mut IUnknown,
+                out.as_mut_ptr(),
+            ))?;
+            ComPtr::wrap(out.assume_init())
+        };
+
+        Ok((
+            Self {
+                device,
+                swapchain,
+                size,
+                external,
+            },
+            surface,
+        ))
+    }
+
+    fn resize(&mut self, size: [u32; 2]) -> Result<(), HRESULT> {
+        if self.size == size {
+            return Ok(());
+        }
+
+        unsafe {
+            result_from_hresult(self.swapchain.ResizeBuffers(
+                0, // preserve the buffer count
+                size[0],
+                size[1],
+                DXGI_FORMAT_B8G8R8A8_UNORM,
+                0,
+            ))?;
+        }
+        self.size = size;
+        Ok(())
+    }
+}
+
+fn is_hresult_devlost(e: HRESULT) -> bool {
+    matches!(
+        e,
+        winerror::DXGI_ERROR_DEVICE_REMOVED | winerror::DXGI_ERROR_DEVICE_RESET
+    )
+}
+
+/// Create a swapchain for `layer` and bind it to the layer's image visual,
+/// retrying once if the device is lost in the process.
+fn new_layer_swapchain(
+    cs: &CompState,
+    state: &mut LayerState,
+    layer: &Layer,
+    size: [u32; 2],
+    external: bool,
+) -> Swapchain {
+    let (swapchain, surface) = match Swapchain::new(cs, size, external) {
+        Err(e) if is_hresult_devlost(e) => Swapchain::new(cs, size, external),
+        x => x,
+    }
+    .unwrap_or_else(|e| panic_hresult(e));
+
+    // Bind the swapchain to the image visual
+    let has_solid = state.solid.is_some();
+    let num_sublayers = state.sublayers.len();
+    ensure_layer_cvis(cs, layer, state, has_solid, true, num_sublayers);
+
+    let visuals_container_cvis = state
+        .layer_cvis
+        .as_deref()
+        .unwrap_or(&*layer.container_cvis);
+    let (vis, _, sbrush) = ensure_layer_image(
+        cs,
+        &mut state.image,
+        &state.solid,
+        &state.mask,
+        state.xform4x4,
+        state.bounds,
+        visuals_container_cvis,
+    );
+    sbrush.set_surface(&surface).unwrap();
+    vis.set_is_visible(true).unwrap();
+
+    swapchain
+}
+
+/// Attach a swapchain of the specified size to `layer`, reusing the existing
+/// one if possible.
+fn ensure_layer_swapchain(
+    cs: &CompState,
+    state: &mut LayerState,
+    layer: &Layer,
+    size: [u32; 2],
+    external: bool,
+) {
+    // Swapchains can't have an empty buffer
+    let size = [size[0].max(1), size[1].max(1)];
+
+    let swapchain = match state.swapchain.take() {
+        Some(mut swapchain) if swapchain.external == external => match swapchain.resize(size) {
+            Ok(()) => swapchain,
+            Err(e) if is_hresult_devlost(e) => {
+                drop(swapchain);
+                new_layer_swapchain(cs, state, layer, size, external)
+            }
+            Err(e) => panic_hresult(e),
+        },
+        _ => new_layer_swapchain(cs, state, layer, size, external),
+    };
+    state.swapchain = Some(swapchain);
+
+    // `contents_size` is used for 9-grid slicing
+    state.contents_size = [size[0] as f32, size[1] as f32];
+    update_layer_image_slicing(state);
+}
+
+fn detach_layer_swapchain(state: &mut LayerState) {
+    if state.swapchain.take().is_some() {
+        if let Some((vis, _, _)) = &state.image {
+            vis.set_is_visible(false).unwrap();
+        }
+    }
+}
+
+/// Display a frame of `LayerContents::ExternalTexture` by copying it to a
+/// swapchain.
+fn present_external_texture_frame(
+    cs: &CompState,
+    state: &mut LayerState,
+    layer: &Layer,
+    frame: &ExternalTextureFrame,
+) {
+    let size = frame.size();
+    if size[0] == 0 || size[1] == 0 {
+        detach_layer_swapchain(state);
+        return;
+    }
+
+    ensure_layer_swapchain(cs, state, layer, size, true);
+
+    let upload = |swapchain: &Swapchain| -> Result<HRESULT, HRESULT> {
+        unsafe {
+            let texture: MyComPtr<ID3D11Texture2D> = {
+                let mut out = MaybeUninit::uninit();
+                result_from_hresult(swapchain.swapchain.GetBuffer(
+                    0,
+                    &ID3D11Texture2D::uuidof(),
+                    out.as_mut_ptr(),
+                ))?;
+                MyComPtr::from_ptr_unchecked(out.assume_init() as *mut ID3D11Texture2D)
+            };
+
+            let context: MyComPtr<ID3D11DeviceContext> = {
+                let mut out = MaybeUninit::uninit();
+                swapchain.device.GetImmediateContext(out.as_mut_ptr());
+                MyComPtr::from_ptr_unchecked(out.assume_init())
+            };
+
+            // The frame is BGRA8888, which matches the swapchain's format
+            context.UpdateSubresource(
+                texture.as_ptr() as _,
+                0,
+                null(), // entire the subresource
+                frame.data().as_ptr() as _,
+                frame.stride() as u32,
+                0,
+            );
+
+            // The buffer must be released before presenting
+            drop(texture);
+
+            result_from_hresult(swapchain.swapchain.Present(0, 0))
+        }
+    };
+
+    match upload(state.swapchain.as_ref().unwrap()) {
+        Ok(_) => {}
+        Err(e) if is_hresult_devlost(e) => {
+            log::warn!("Swapchain device was lost (0x{:08x}), recreating it", e);
+
+            state.swapchain = None;
+            ensure_layer_swapchain(cs, state, layer, size, true);
+
+            // If this fails, the next frame will try again
+            if let Err(e) = upload(state.swapchain.as_ref().unwrap()) {
+                log::warn!("Could not present a frame (0x{:08x})", e);
+            }
+        }
+        Err(e) => panic_hresult(e),
+    }
+}
+
+pub fn set_layer_swapchain(wm: Wm, hlayer: &HLayer, size: Option<[u32; 2]>) -> bool {
+    let cs = CS.get_with_wm(wm);
+
+    let layer = &*hlayer.layer;
+
+    let mut state = layer.state.borrow_mut();
+    let state = &mut *state; // enable split borrow
+
+    if let Some(size) = size {
+        ensure_layer_swapchain(cs, state, layer, size, false);
+    } else {
+        detach_layer_swapchain(state);
+    }
+
+    true
+}
+
+pub fn present_layer_swapchain(
+    wm: Wm,
+    hlayer: &HLayer,
+    render: &mut dyn FnMut(SwapchainTarget<'_>),
+) {
+    let layer = &*hlayer.layer;
+
+    // `render` may call `Wm`'s methods, so don't borrow `state` while
+    // calling it
+    let (device, swapchain, size) = match &layer.state.borrow().swapchain {
+        Some(sc) if !sc.external => (sc.device.clone(), sc.swapchain.clone(), sc.size),
+        _ => return,
+    };
+
+    let result = (|| -> Result<HRESULT, HRESULT> {
+        unsafe {
+            let texture: MyComPtr<ID3D11Texture2D> = {
+                let mut out = MaybeUninit::uninit();
+                result_from_hresult(swapchain.GetBuffer(
+                    0,
+                    &ID3D11Texture2D::uuidof(),
+                    out.as_mut_ptr(),
+                ))?;
+                MyComPtr::from_ptr_unchecked(out.assume_init() as *mut ID3D11Texture2D)
+            };
+
+            render(SwapchainTarget::D3D11 {
+                device: device.as_ptr() as _,
+                texture: texture.as_ptr() as _,
+                size,
+            });
+
+            // The buffer must be released before presenting
+            drop(texture);
+
+            result_from_hresult(swapchain.Present(0, 0))
+        }
+    })();
+
+    match result {
+        Ok(_) => {}
+        Err(e) if is_hresult_devlost(e) => {
+            // Recreate the swapchain. The contents will be supplied by the
+            // next call to `present_layer_swapchain`.
+            log::warn!("Swapchain device was lost (0x{:08x}), recreating it", e);
+
+            let cs = CS.get_with_wm(wm);
+            let mut state = layer.state.borrow_mut();
+            let state = &mut *state;
+            let is_current = matches!(
+                &state.swapchain,
+                Some(sc) if sc.swapchain.as_ptr() == swapchain.as_ptr()
+            );
+            if is_current {
+                state.swapchain = None;
+                ensure_layer_swapchain(cs, state, layer, size, false);
+            }
+        }
+        Err(e) => panic_hresult(e),
+    }
+}
+
 pub fn remove_layer(_: Wm, _: &HLayer) {
     // `Layer` is ref-counted, there's nothing to do here
 }
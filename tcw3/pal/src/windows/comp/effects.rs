@@ -40,7 +40,7 @@ macro_rules! define_effect {
         effect_id: $effect_id:expr;
         num_sources: $num_sources:expr;
         num_props: $num_props:expr;
-        props_map: |$index:ident| $props_map:expr;
+        props_map: |$this:ident, $index:ident| $props_map:expr;
     ) => {
         #[repr(C)]
         pub struct $Name {
@@ -197,8 +197,9 @@ macro_rules! define_effect {
                 index: UINT,
                 out_value: *mut *mut IPropertyValue,
             ) -> HRESULT {
-                let $index = index; // input to `$props_map`
-                let this = &*vtbl2_to_1(this);
+                // inputs to `$props_map`
+                let $index = index;
+                let $this = &*vtbl2_to_1(this);
                 let value = $props_map;
 
                 let value: ComPtr<IPropertyValue> =
@@ -267,16 +268,18 @@ macro_rules! define_effect {
 } // macro_rules! define_effect
 
 define_effect! {
-    pub struct GaussianBlurEffect {}
+    pub struct GaussianBlurEffect {
+        std_dev: f32,
+    }
 
     static GAUSSIAN_BLUR_EFFECT_VTBL;
 
     effect_id: winapi::um::d2d1effects::CLSID_D2D1GaussianBlur;
     num_sources: 1;
     num_props: 3;
-    props_map: |index| match index {
+    props_map: |this, index| match index {
         d2d1effects::D2D1_GAUSSIANBLUR_PROP_STANDARD_DEVIATION => {
-            PropertyValue::create_single(15.0)
+            PropertyValue::create_single(this.std_dev)
         }
         d2d1effects::D2D1_GAUSSIANBLUR_PROP_OPTIMIZATION => {
             PropertyValue::create_uint32(d2d1effects::D2D1_GAUSSIANBLUR_OPTIMIZATION_BALANCED)
@@ -296,7 +299,7 @@ define_effect! {
     effect_id: winapi::um::d2d1effects::CLSID_D2D1Blend;
     num_sources: 2;
     num_props: 1;
-    props_map: |index| match index {
+    props_map: |this, index| match index {
         d2d1effects::D2D1_BLEND_PROP_MODE => {
             PropertyValue::create_uint32(d2d1effects::D2D1_BLEND_MODE_OVERLAY)
         }
@@ -312,7 +315,7 @@ define_effect! {
     effect_id: winapi::um::d2d1effects::CLSID_D2D1Composite;
     num_sources: 2;
     num_props: 1;
-    props_map: |index| match index {
+    props_map: |this, index| match index {
         d2d1effects::D2D1_COMPOSITE_PROP_MODE => {
             PropertyValue::create_uint32(d2d1_1::D2D1_COMPOSITE_MODE_SOURCE_OVER)
         }
@@ -328,7 +331,7 @@ define_effect! {
     effect_id: winapi::um::d2d1effects::CLSID_D2D1Saturation;
     num_sources: 1;
     num_props: 1;
-    props_map: |index| match index {
+    props_map: |this, index| match index {
         d2d1effects::D2D1_SATURATION_PROP_SATURATION => {
             PropertyValue::create_single(1.2)
         }
@@ -352,7 +355,7 @@ define_effect! {
     effect_id: d2d1effects_2_win10_rs1::CLSID_D2D1Opacity;
     num_sources: 1;
     num_props: 1;
-    props_map: |index| match index {
+    props_map: |this, index| match index {
         d2d1effects_2_win10_rs1::D2D1_OPACITY_PROP_OPACITY => {
             PropertyValue::create_single(0.02)
         }
@@ -368,7 +371,7 @@ define_effect! {
     effect_id: winapi::um::d2d1effects::CLSID_D2D1Border;
     num_sources: 1;
     num_props: 2;
-    props_map: |index| match index {
+    props_map: |this, index| match index {
         d2d1effects::D2D1_BORDER_PROP_EDGE_MODE_X => {
             PropertyValue::create_uint32(d2d1effects::D2D1_BORDER_EDGE_MODE_WRAP)
         }
@@ -0,0 +1,219 @@
+//! Implements `Wm::show_file_dialog` using `IFileOpenDialog` and
+//! `IFileSaveDialog`.
+use std::{
+    ffi::OsString,
+    mem::MaybeUninit,
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    ptr::null_mut,
+};
+use winapi::{
+    shared::{
+        winerror::{ERROR_CANCELLED, HRESULT_FROM_WIN32},
+        wtypesbase::CLSCTX_INPROC_SERVER,
+    },
+    um::{
+        combaseapi::{CoCreateInstance, CoTaskMemFree},
+        shobjidl::{
+            IFileDialog, IFileOpenDialog, FOS_ALLOWMULTISELECT, FOS_FORCEFILESYSTEM,
+            FOS_OVERWRITEPROMPT,
+        },
+        shobjidl_core::{
+            CLSID_FileOpenDialog, CLSID_FileSaveDialog, IShellItem, IShellItemArray,
+            SHCreateItemFromParsingName, SIGDN_FILESYSPATH,
+        },
+        shtypes::COMDLG_FILTERSPEC,
+    },
+    Interface,
+};
+
+use super::{
+    codecvt::str_to_c_wstr,
+    utils::{assert_hresult_ok, result_from_hresult, ComPtr},
+    HWnd, Wm,
+};
+use crate::{iface, iface::Wm as _};
+
+pub fn show_file_dialog(
+    wm: Wm,
+    parent: Option<&HWnd>,
+    options: iface::FileDialogOptions,
+    f: impl FnOnce(Wm, Option<Vec<PathBuf>>) + 'static,
+) {
+    let parent = parent.cloned();
+
+    // `IModalWindow::Show` runs a nested message loop and doesn't return until
+    // the dialog is dismissed. Defer it so that `f` isn't called from inside
+    // `show_file_dialog`.
+    wm.invoke(move |wm| {
+        let hwnd_owner = parent.as_ref().map_or(null_mut(), HWnd::expect_hwnd);
+        let paths = unsafe { run_file_dialog(hwnd_owner, &options) };
+        f(wm, paths);
+    });
+}
+
+unsafe fn run_file_dialog(
+    hwnd_owner: winapi::shared::windef::HWND,
+    options: &iface::FileDialogOptions,
+) -> Option<Vec<PathBuf>> {
+    let is_open = options.kind == iface::FileDialogKind::Open;
+
+    let dialog: ComPtr<IFileDialog> = {
+        let clsid = if is_open {
+            &CLSID_FileOpenDialog
+        } else {
+            &CLSID_FileSaveDialog
+        };
+        let mut out = MaybeUninit::uninit();
+        assert_hresult_ok(CoCreateInstance(
+            clsid,
+            null_mut(),
+            CLSCTX_INPROC_SERVER,
+            &IFileDialog::uuidof(),
+            out.as_mut_ptr(),
+        ));
+        ComPtr::from_ptr_unchecked(out.assume_init() as _)
+    };
+
+    // Options
+    let mut fos = MaybeUninit::uninit();
+    assert_hresult_ok(dialog.GetOptions(fos.as_mut_ptr()));
+    let mut fos = fos.assume_init() | FOS_FORCEFILESYSTEM;
+    if is_open {
+        if options.multi_select {
+            fos |= FOS_ALLOWMULTISELECT;
+        }
+    } else {
+        fos |= FOS_OVERWRITEPROMPT;
+    }
+    assert_hresult_ok(dialog.SetOptions(fos));
+
+    if let Some(title) = &options.title {
+        assert_hresult_ok(dialog.SetTitle(str_to_c_wstr(title).as_ptr()));
+    }
+
+    // File type filters. `COMDLG_FILTERSPEC` borrows the strings, so they
+    // must outlive the call to `SetFileTypes`.
+    let filter_strs: Vec<(Box<[u16]>, Box<[u16]>)> = options
+        .filters
+        .iter()
+        .map(|filter| {
+            let pattern = if filter.extensions.is_empty() {
+                "*.*".to_owned()
+            } else {
+                filter
+                    .extensions
+                    .iter()
+                    .map(|ext| format!("*.{}", ext))
+                    .collect::<Vec<_>>()
+                    .join(";")
+            };
+            (str_to_c_wstr(&filter.name), str_to_c_wstr(&pattern))
+        })
+        .collect();
+    let filter_specs: Vec<COMDLG_FILTERSPEC> = filter_strs
+        .iter()
+        .map(|(name, spec)| COMDLG_FILTERSPEC {
+            pszName: name.as_ptr(),
+            pszSpec: spec.as_ptr(),
+        })
+        .collect();
+    if !filter_specs.is_empty() {
+        assert_hresult_ok(dialog.SetFileTypes(filter_specs.len() as _, filter_specs.as_ptr()));
+        assert_hresult_ok(dialog.SetFileTypeIndex(1));
+
+        // Append the first extension of the selected filter to a file name
+        // without one
+        if let Some(ext) = options.filters[0].extensions.first() {
+            assert_hresult_ok(dialog.SetDefaultExtension(str_to_c_wstr(ext).as_ptr()));
+        }
+    }
+
+    if let Some(dir) = &options.default_dir {
+        if let Some(item) = shell_item_from_path(dir) {
+            assert_hresult_ok(dialog.SetFolder(item.as_ptr()));
+        }
+    }
+
+    if let (iface::FileDialogKind::Save, Some(name)) = (options.kind, &options.default_name) {
+        assert_hresult_ok(dialog.SetFileName(str_to_c_wstr(name).as_ptr()));
+    }
+
+    match result_from_hresult(dialog.Show(hwnd_owner)) {
+        Ok(_) => {}
+        Err(hr) if hr == HRESULT_FROM_WIN32(ERROR_CANCELLED) => return None,
+        Err(hr) => {
+            log::warn!("IModalWindow::Show failed: 0x{:08x}", hr);
+            return None;
+        }
+    }
+
+    if is_open {
+        let dialog: ComPtr<IFileOpenDialog> = dialog.query_interface().unwrap();
+
+        let items: ComPtr<IShellItemArray> = {
+            let mut out = MaybeUninit::uninit();
+            assert_hresult_ok(dialog.GetResults(out.as_mut_ptr()));
+            ComPtr::from_ptr_unchecked(out.assume_init())
+        };
+
+        let mut count = MaybeUninit::uninit();
+        assert_hresult_ok(items.GetCount(count.as_mut_ptr()));
+
+        let paths = (0..count.assume_init())
+            .filter_map(|i| {
+                let mut out = MaybeUninit::uninit();
+                assert_hresult_ok(items.GetItemAt(i, out.as_mut_ptr()));
+                let item = ComPtr::from_ptr_unchecked(out.assume_init());
+                path_from_shell_item(&item)
+            })
+            .collect();
+
+        Some(paths)
+    } else {
+        let item: ComPtr<IShellItem> = {
+            let mut out = MaybeUninit::uninit();
+            assert_hresult_ok(dialog.GetResult(out.as_mut_ptr()));
+            ComPtr::from_ptr_unchecked(out.assume_init())
+        };
+
+        Some(path_from_shell_item(&item).into_iter().collect())
+    }
+}
+
+unsafe fn shell_item_from_path(path: &Path) -> Option<ComPtr<IShellItem>> {
+    let wpath: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+
+    let mut out = MaybeUninit::uninit();
+    let hr = SHCreateItemFromParsingName(
+        wpath.as_ptr(),
+        null_mut(),
+        &IShellItem::uuidof(),
+        out.as_mut_ptr(),
+    );
+    if let Err(hr) = result_from_hresult(hr) {
+        log::warn!(
+            "SHCreateItemFromParsingName({:?}) failed: 0x{:08x}",
+            path,
+            hr
+        );
+        return None;
+    }
+
+    ComPtr::from_ptr(out.assume_init() as _)
+}
+
+unsafe fn path_from_shell_item(item: &IShellItem) -> Option<PathBuf> {
+    let mut out = MaybeUninit::uninit();
+    if let Err(hr) = result_from_hresult(item.GetDisplayName(SIGDN_FILESYSPATH, out.as_mut_ptr())) {
+        log::warn!("IShellItem::GetDisplayName failed: 0x{:08x}", hr);
+        return None;
+    }
+
+    let ptr = out.assume_init();
+    let len = (0..).find(|&i| *ptr.add(i) == 0).unwrap();
+    let path = OsString::from_wide(std::slice::from_raw_parts(ptr, len));
+    CoTaskMemFree(ptr as _);
+
+    Some(path.into())
+}
@@ -0,0 +1,223 @@
+//! Implements `Wm::show_notification` using toast notifications.
+use std::{
+    cell::Cell,
+    fmt::Write,
+    mem::{ManuallyDrop, MaybeUninit},
+    path::Path,
+    sync::Arc,
+};
+use winapi::um::combaseapi::CoTaskMemFree;
+use winrt::{
+    windows::data::xml::dom::XmlDocument,
+    windows::foundation::TypedEventHandler,
+    windows::ui::notifications::{
+        IToastActivatedEventArgs, ToastNotification, ToastNotificationManager, ToastNotifier,
+    },
+    ComPtr, FastHString, IInspectable, RtDefaultConstructible,
+};
+
+use super::{
+    codecvt::wstr_to_str, utils::result_from_hresult,
+    winapiext::GetCurrentProcessExplicitAppUserModelID, Wm,
+};
+use crate::{cells::MtLazyStatic, iface, iface::Wm as _, MtSticky};
+
+type Callback = Box<dyn FnOnce(Wm, Option<usize>)>;
+
+mt_lazy_static! {
+    static <Wm> ref TOAST_NOTIFIER: Option<ComPtr<ToastNotifier>> =>
+        |_| new_toast_notifier();
+}
+
+fn new_toast_notifier() -> Option<ComPtr<ToastNotifier>> {
+    let app_id = FastHString::new(&app_user_model_id());
+
+    match ToastNotificationManager::create_toast_notifier_with_id(&app_id) {
+        Ok(Some(notifier)) => Some(notifier),
+        Ok(None) => {
+            log::warn!("CreateToastNotifier returned null");
+            None
+        }
+        Err(e) => {
+            log::warn!("Could not create a toast notifier: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Get the application user model ID of the current process.
+///
+/// Toast notifications sent by a desktop application are displayed only if
+/// the ID is associated with a shortcut in the Start menu. The application is
+/// responsible for setting it up by calling
+/// `SetCurrentProcessExplicitAppUserModelID`. Otherwise, the executable name
+/// is used, which most likely won't work.
+fn app_user_model_id() -> String {
+    unsafe {
+        let mut out = MaybeUninit::uninit();
+        if result_from_hresult(GetCurrentProcessExplicitAppUserModelID(out.as_mut_ptr())).is_ok() {
+            let ptr = out.assume_init();
+            let len = (0..).find(|&i| *ptr.add(i) == 0).unwrap();
+            let app_id = wstr_to_str(std::slice::from_raw_parts(ptr, len));
+            CoTaskMemFree(ptr as _);
+            return app_id.into();
+        }
+    }
+
+    log::warn!(
+        "The application user model ID is not set. Toast notifications may \
+         not be displayed."
+    );
+
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "tcw3".to_owned())
+}
+
+pub fn show_notification(
+    wm: Wm,
+    attrs: iface::NotificationAttrs,
+    f: impl FnOnce(Wm, Option<usize>) + 'static,
+) {
+    let notifier = if let Some(x) = TOAST_NOTIFIER.get_with_wm(wm) {
+        x
+    } else {
+        return;
+    };
+
+    if let Err(e) = show_toast(wm, notifier, &attrs, Box::new(f)) {
+        log::warn!("Could not show a toast notification: {:?}", e);
+    }
+}
+
+fn show_toast(
+    wm: Wm,
+    notifier: &ToastNotifier,
+    attrs: &iface::NotificationAttrs,
+    f: Callback,
+) -> winrt::Result<()> {
+    let doc = XmlDocument::new();
+    doc.load_xml(&FastHString::new(&toast_xml(attrs)))?;
+
+    let toast = ToastNotification::create_toast_notification(&doc)?;
+
+    // The event handler is called from a background thread
+    let f = Arc::new(MtSticky::with_wm(wm, Cell::new(Some(f))));
+    let handler =
+        TypedEventHandler::new(move |_: *mut ToastNotification, args: *mut IInspectable| {
+            // The action index is encoded in the activation arguments
+            let action = unsafe { activation_arguments(args) }.and_then(|x| x.parse().ok());
+
+            let f = Arc::clone(&f);
+            Wm::invoke_on_main_thread(move |wm| {
+                if let Some(f) = f.get_with_wm(wm).take() {
+                    f(wm, action);
+                }
+            });
+
+            Ok(())
+        });
+    toast.add_activated(&handler)?;
+
+    notifier.show(&toast)
+}
+
+/// Get the activation arguments from `ToastActivatedEventArgs`.
+unsafe fn activation_arguments(args: *mut IInspectable) -> Option<String> {
+    if args.is_null() {
+        return None;
+    }
+
+    // `args` is borrowed, so don't release it
+    let args = ManuallyDrop::new(ComPtr::wrap(args));
+    let args = args.query_interface::<IToastActivatedEventArgs>()?;
+
+    args.get_arguments().ok().map(|x| x.to_string())
+}
+
+/// Construct the XML representation of a toast notification.
+///
+/// The activation arguments are empty for the notification itself and
+/// contain the index for each action.
+fn toast_xml(attrs: &iface::NotificationAttrs) -> String {
+    let mut xml = String::new();
+
+    xml.push_str("<toast launch=\"\"><visual><binding template=\"ToastGeneric\">");
+    write!(
+        xml,
+        "<text>{}</text><text>{}</text>",
+        escape_xml(&attrs.title),
+        escape_xml(&attrs.body)
+    )
+    .unwrap();
+    if let Some(image) = &attrs.image {
+        write!(
+            xml,
+            "<image placement=\"appLogoOverride\" src=\"{}\"/>",
+            escape_xml(&file_uri(image))
+        )
+        .unwrap();
+    }
+    xml.push_str("</binding></visual>");
+
+    if !attrs.actions.is_empty() {
+        xml.push_str("<actions>");
+        for (i, caption) in attrs.actions.iter().enumerate() {
+            write!(
+                xml,
+                "<action content=\"{}\" arguments=\"{}\"/>",
+                escape_xml(caption),
+                i
+            )
+            .unwrap();
+        }
+        xml.push_str("</actions>");
+    }
+
+    xml.push_str("</toast>");
+    xml
+}
+
+fn file_uri(path: &Path) -> String {
+    format!("file:///{}", path.to_string_lossy().replace('\\', "/"))
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toast_xml() {
+        let xml = toast_xml(&iface::NotificationAttrs {
+            title: "a < b".to_owned(),
+            body: "\"c\" & 'd'".to_owned(),
+            image: None,
+            actions: vec!["Reply".to_owned(), "Mute".to_owned()],
+        });
+        assert_eq!(
+            xml,
+            "<toast launch=\"\"><visual><binding template=\"ToastGeneric\">\
+             <text>a &lt; b</text><text>&quot;c&quot; &amp; &apos;d&apos;</text>\
+             </binding></visual><actions>\
+             <action content=\"Reply\" arguments=\"0\"/>\
+             <action content=\"Mute\" arguments=\"1\"/>\
+             </actions></toast>"
+        );
+    }
+}
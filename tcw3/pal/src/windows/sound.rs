@@ -0,0 +1,47 @@
+//! Implements `pal::sound` using `PlaySoundW`.
+use std::{os::windows::ffi::OsStrExt, path::Path, ptr::null_mut};
+use wchar::wch_c;
+use winapi::{
+    shared::minwindef::DWORD,
+    um::playsoundapi::{PlaySoundW, SND_ALIAS, SND_ASYNC, SND_FILENAME, SND_NODEFAULT},
+};
+
+use super::Wm;
+use crate::sound::SystemSound;
+
+pub fn play_system_sound(_: Wm, sound: SystemSound) {
+    // The names of the sound events registered under
+    // `HKEY_CURRENT_USER\AppEvents\EventLabels`
+    let alias: &[u16] = match sound {
+        SystemSound::Beep => wch_c!("SystemDefault"),
+        SystemSound::Information => wch_c!("SystemAsterisk"),
+        SystemSound::Warning => wch_c!("SystemExclamation"),
+        SystemSound::Error => wch_c!("SystemHand"),
+        SystemSound::MessageReceived => wch_c!("Notification.IM"),
+    };
+
+    play_sound(alias, SND_ALIAS);
+}
+
+pub fn play_sound_file(_: Wm, path: &Path) {
+    let wpath: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+
+    play_sound(&wpath, SND_FILENAME);
+}
+
+fn play_sound(sound: &[u16], flags: DWORD) {
+    debug_assert_eq!(sound.last(), Some(&0));
+
+    // `SND_ASYNC` makes `PlaySoundW` return immediately. The sound data is
+    // loaded before that, so `sound` doesn't have to outlive the playback.
+    let ok = unsafe {
+        PlaySoundW(
+            sound.as_ptr(),
+            null_mut(),
+            flags | SND_ASYNC | SND_NODEFAULT,
+        )
+    };
+    if ok == 0 {
+        log::warn!("PlaySoundW failed");
+    }
+}
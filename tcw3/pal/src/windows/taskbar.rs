@@ -0,0 +1,95 @@
+//! Displays progress indicators in taskbar buttons using `ITaskbarList3`.
+use std::{mem::MaybeUninit, ptr::null_mut};
+use wchar::wch_c;
+use winapi::{
+    shared::{minwindef::UINT, windef::HWND, wtypesbase::CLSCTX_INPROC_SERVER},
+    um::{
+        combaseapi::CoCreateInstance,
+        shobjidl_core::{
+            CLSID_TaskbarList, ITaskbarList3, TBPF_ERROR, TBPF_INDETERMINATE, TBPF_NOPROGRESS,
+            TBPF_NORMAL, TBPF_PAUSED,
+        },
+        winuser,
+    },
+    Interface,
+};
+
+use super::{
+    utils::{result_from_hresult, ComPtr},
+    Wm,
+};
+use crate::{cells::MtLazyStatic, iface};
+
+mt_lazy_static! {
+    static <Wm> ref TASKBAR_LIST: Option<ComPtr<ITaskbarList3>> =>
+        |_| unsafe { new_taskbar_list() };
+}
+
+// The message sent to a window when its taskbar button is created.
+// `ITaskbarList3`'s methods fail if called before that.
+mt_lazy_static! {
+    static <Wm> ref WM_TASKBAR_BUTTON_CREATED: UINT =>
+        |_| unsafe { winuser::RegisterWindowMessageW(wch_c!("TaskbarButtonCreated").as_ptr()) };
+}
+
+unsafe fn new_taskbar_list() -> Option<ComPtr<ITaskbarList3>> {
+    let mut out = MaybeUninit::uninit();
+    let hr = CoCreateInstance(
+        &CLSID_TaskbarList,
+        null_mut(),
+        CLSCTX_INPROC_SERVER,
+        &ITaskbarList3::uuidof(),
+        out.as_mut_ptr(),
+    );
+    if let Err(hr) = result_from_hresult(hr) {
+        log::warn!("Could not create ITaskbarList3: 0x{:08x}", hr);
+        return None;
+    }
+
+    let taskbar_list: ComPtr<ITaskbarList3> = ComPtr::from_ptr_unchecked(out.assume_init() as _);
+
+    if let Err(hr) = result_from_hresult(taskbar_list.HrInit()) {
+        log::warn!("ITaskbarList::HrInit failed: 0x{:08x}", hr);
+        return None;
+    }
+
+    Some(taskbar_list)
+}
+
+/// Get the message ID of `TaskbarButtonCreated`. The window should call
+/// [`set_progress`] again when it receives this message.
+pub fn wm_taskbar_button_created(wm: Wm) -> UINT {
+    *WM_TASKBAR_BUTTON_CREATED.get_with_wm(wm)
+}
+
+/// Update the progress indicator of the taskbar button of the specified
+/// window.
+pub fn set_progress(wm: Wm, hwnd: HWND, progress: Option<iface::ProgressState>) {
+    let taskbar_list = if let Some(x) = TASKBAR_LIST.get_with_wm(wm) {
+        x
+    } else {
+        return;
+    };
+
+    use iface::ProgressState;
+    let (flags, value) = match progress {
+        None => (TBPF_NOPROGRESS, None),
+        Some(ProgressState::Indeterminate) => (TBPF_INDETERMINATE, None),
+        Some(ProgressState::Normal(x)) => (TBPF_NORMAL, Some(x)),
+        Some(ProgressState::Paused(x)) => (TBPF_PAUSED, Some(x)),
+        Some(ProgressState::Error(x)) => (TBPF_ERROR, Some(x)),
+    };
+
+    const TOTAL: u64 = 10000;
+
+    // The taskbar button might not be created yet, so ignore errors. The
+    // state will be set again when `WM_TASKBAR_BUTTON_CREATED` is received.
+    unsafe {
+        taskbar_list.SetProgressState(hwnd, flags);
+
+        if let Some(value) = value {
+            let completed = (value.max(0.0).min(1.0) * TOTAL as f32) as u64;
+            taskbar_list.SetProgressValue(hwnd, completed, TOTAL);
+        }
+    }
+}
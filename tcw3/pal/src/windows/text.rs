@@ -17,15 +17,18 @@ use utf16count::{
     find_utf16_pos, find_utf16_pos_in_utf8_str, rfind_utf16_pos_in_utf8_str, utf16_len_of_utf8_str,
 };
 use winapi::{
-    shared::{minwindef::BYTE, winerror::S_OK},
-    um::usp10,
+    shared::{
+        minwindef::{BYTE, DWORD, WORD},
+        winerror::S_OK,
+    },
+    um::{dwrite, dwrite_2::IDWriteFactory2, unknwnbase::IUnknown, usp10, winnls},
 };
 
 use super::{
     codecvt::str_to_c_wstr,
-    utils::{assert_hresult_ok, panic_hresult},
+    utils::{assert_hresult_ok, panic_hresult, query_interface, ComPtr},
 };
-use crate::iface;
+use crate::{emoji, iface};
 
 lazy_static::lazy_static! {
     static ref G: Global = Global::new();
@@ -43,6 +46,15 @@ impl Global {
     }
 }
 
+/// Get `IDWriteFactory2` of the shared DirectWrite factory. Returns `None` if
+/// the system doesn't support it (it requires Windows 8.1 or later).
+pub(super) fn dwrite_factory2() -> Option<ComPtr<IDWriteFactory2>> {
+    unsafe {
+        let factory = std::ptr::NonNull::new(G.dwrite.get_raw() as *mut IUnknown).unwrap();
+        query_interface(factory)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct CharStyle {
     size: f32,
@@ -291,26 +303,57 @@ impl TextLayout {
 impl iface::TextLayout for TextLayout {
     type CharStyle = CharStyle;
 
-    fn from_text(text: &str, style: &Self::CharStyle, width: Option<f32>) -> Self {
+    fn from_text_with_direction(
+        text: &str,
+        style: &Self::CharStyle,
+        width: Option<f32>,
+        direction: iface::ParagraphDirection,
+    ) -> Self {
         assert!(u32::try_from(text.len()).is_ok(), "string too long");
 
         let text_u16 = str_to_c_wstr(text);
 
+        let is_rtl = match direction {
+            iface::ParagraphDirection::Auto => is_text_rtl(&text_u16[..text_u16.len() - 1]),
+            iface::ParagraphDirection::Ltr => false,
+            iface::ParagraphDirection::Rtl => true,
+        };
+
         let dwrite_layout = unsafe {
             let mut dwrite_layout = MaybeUninit::uninit();
-            let width = width.unwrap_or(std::f32::INFINITY).fmax(0.0);
+            let max_width = width.unwrap_or(std::f32::INFINITY).fmax(0.0);
             let height = 0.0;
             assert_hresult_ok((&*G.dwrite.get_raw()).CreateTextLayout(
                 text_u16.as_ptr(),
                 (text_u16.len() - 1).try_into().expect("string too long"),
                 style.to_dwrite_format().get_raw(),
-                width,
+                max_width,
                 height,
                 dwrite_layout.as_mut_ptr(),
             ));
             directwrite::TextLayout::from_raw(dwrite_layout.assume_init())
         };
 
+        if is_rtl {
+            unsafe {
+                assert_hresult_ok(
+                    (&*dwrite_layout.get_raw())
+                        .SetReadingDirection(dwrite::DWRITE_READING_DIRECTION_RIGHT_TO_LEFT),
+                );
+            }
+
+            if width.is_none() {
+                // Lines are aligned to the right edge of the layout box, which
+                // is at infinity if the width is unspecified. Shrink the box
+                // to fit the text so that the lines are placed in a finite
+                // region starting from `x = 0`.
+                let met = dwrite_layout.get_metrics();
+                dwrite_layout
+                    .set_max_width(met.width_including_trailing_whitespace())
+                    .unwrap();
+            }
+        }
+
         if style.decor.contains(iface::TextDecorFlags::UNDERLINE) {
             dwrite_layout.set_underline(true, ..).unwrap();
         }
@@ -372,6 +415,29 @@ impl iface::TextLayout for TextLayout {
         find_utf16_pos(pos as usize, &self.text).utf8_cursor
     }
 
+    fn hit_test(&self, point: Point2<f32>) -> iface::HitTestResult {
+        let result = self.dwrite_layout.hit_test_point(point.x, point.y);
+        let metrics = &result.metrics;
+
+        let mut flags = iface::RunFlags::empty();
+        if metrics.bidi_level() % 2 != 0 {
+            flags |= iface::RunFlags::RIGHT_TO_LEFT;
+        }
+
+        // Convert the cluster's range to UTF-8
+        let start = find_utf16_pos(metrics.text_position() as usize, &self.text);
+        let len = find_utf16_pos_in_utf8_str(
+            metrics.length() as usize,
+            &self.text.as_bytes()[start.utf8_cursor..],
+        );
+
+        iface::HitTestResult {
+            index: start.utf8_cursor..start.utf8_cursor + len.utf8_cursor,
+            trailing: result.is_trailing_hit,
+            flags,
+        }
+    }
+
     fn cursor_pos(&self, i: usize) -> [iface::Beam; 2] {
         // Convert `i` to UTF-16
         let i = utf16_len_of_utf8_str(&self.text.as_bytes()[0..i]) as u32;
@@ -616,11 +682,49 @@ impl iface::TextLayout for TextLayout {
     fn next_word(&self, i: usize, forward: bool) -> usize {
         self.next_char_with_logattr(i, forward, SCRIPT_LOGATTR_WORD_STOP)
     }
+
+    fn is_emoji_cluster(&self, i: Range<usize>) -> bool {
+        i.start < i.end
+            && self.next_char(i.start, true) == i.end
+            && emoji::is_emoji_cluster(&self.text[i])
+    }
 }
 
 const SCRIPT_LOGATTR_CHAR_STOP: BYTE = 1 << 2;
 const SCRIPT_LOGATTR_WORD_STOP: BYTE = 1 << 3;
 
+const CT_CTYPE2: DWORD = 0x2;
+const C2_LEFTTORIGHT: WORD = 0x1;
+const C2_RIGHTTOLEFT: WORD = 0x2;
+
+/// Determine the paragraph direction from the first strong directional
+/// character in the given UTF-16 string. DirectWrite doesn't do this by
+/// itself and always uses the reading direction set on the text format.
+fn is_text_rtl(text: &[u16]) -> bool {
+    if text.is_empty() {
+        return false;
+    }
+
+    let mut types: Vec<WORD> = vec![0; text.len()];
+    unsafe {
+        if winnls::GetStringTypeW(
+            CT_CTYPE2,
+            text.as_ptr(),
+            text.len().try_into().expect("string too long"),
+            types.as_mut_ptr(),
+        ) == 0
+        {
+            return false;
+        }
+    }
+
+    types
+        .iter()
+        .find(|&&ty| ty == C2_LEFTTORIGHT || ty == C2_RIGHTTOLEFT)
+        .map(|&ty| ty == C2_RIGHTTOLEFT)
+        .unwrap_or(false)
+}
+
 trait TextLayoutExt {
     fn hit_test_text_range_2(
         &self,
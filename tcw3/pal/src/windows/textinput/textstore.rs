@@ -55,9 +55,15 @@ pub(super) struct TextStore {
     hwnd: HWND,
     sink: Cell<Option<ComPtr<ITextStoreACPSink>>>,
     sink_id: Cell<*mut IUnknown>,
+    /// The event mask (`TS_AS_*`) specified by the sink.
+    sink_mask: Cell<DWORD>,
     /// This references `TextStore::listener`
     edit: RefCell<Option<(TextInputCtxEdit<'static>, bool)>>,
     pending_lock_upgrade: Cell<bool>,
+    /// `OnLayoutChange` was requested while the document was locked.
+    pending_layout_change: Cell<bool>,
+    /// `OnSelectionChange` was requested while the document was locked.
+    pending_selection_change: Cell<bool>,
 }
 
 impl Drop for TextStore {
@@ -129,8 +135,11 @@ impl TextStore {
             htictx: Cell::new(None),
             sink: Cell::new(None),
             sink_id: Cell::new(null_mut()),
+            sink_mask: Cell::new(0),
             edit: RefCell::new(None),
             pending_lock_upgrade: Cell::new(false),
+            pending_layout_change: Cell::new(false),
+            pending_selection_change: Cell::new(false),
         });
 
         (
@@ -208,22 +217,61 @@ impl TextStore {
     }
 
     pub(super) fn on_layout_change(&self) {
+        if (self.sink_mask.get() & tsf::TS_AS_LAYOUT_CHANGE) == 0 {
+            return;
+        }
+        // The sink methods must not be called while the document is locked.
+        // The notification is delivered when the lock is released.
+        if self.is_locked() {
+            log::trace!("on_layout_change: The document is locked, deferring the notification");
+            self.pending_layout_change.set(true);
+            return;
+        }
         if let Some(sink) = cell_get_by_clone(&self.sink) {
             assert_hresult_ok(unsafe { sink.OnLayoutChange(tsf::TS_LC_CHANGE, VIEW_COOKIE) });
         }
     }
 
     pub(super) fn on_selection_change(&self) {
+        if (self.sink_mask.get() & tsf::TS_AS_SEL_CHANGE) == 0 {
+            return;
+        }
+        if self.is_locked() {
+            log::trace!("on_selection_change: The document is locked, deferring the notification");
+            self.pending_selection_change.set(true);
+            return;
+        }
         if let Some(sink) = cell_get_by_clone(&self.sink) {
             assert_hresult_ok(unsafe { sink.OnSelectionChange() });
         }
     }
 
+    /// Return `true` if the document is currently locked.
+    fn is_locked(&self) -> bool {
+        self.edit
+            .try_borrow()
+            .map(|edit| edit.is_some())
+            .unwrap_or(true)
+    }
+
+    /// Deliver the notifications deferred by `on_layout_change` and
+    /// `on_selection_change`.
+    fn flush_pending_notifications(&self) {
+        if self.pending_selection_change.replace(false) {
+            self.on_selection_change();
+        }
+        if self.pending_layout_change.replace(false) {
+            self.on_layout_change();
+        }
+    }
+
     fn expect_htictx(&self) -> HTextInputCtx {
         cell_get_by_clone(&self.htictx).unwrap()
     }
 
     fn emit_set_event_mask(&self, mask: DWORD) {
+        self.sink_mask.set(mask);
+
         let mut event_mask = iface::TextInputCtxEventFlags::empty();
 
         if (mask & tsf::TS_AS_ALL_SINKS) != 0 {
@@ -568,7 +616,7 @@ unsafe extern "system" fn impl_request_lock(
             );
         }
 
-        // TODO: Call `OnLayoutChange` here if needed
+        this.flush_pending_notifications();
 
         Ok(S_OK)
     })
@@ -675,6 +723,12 @@ fn edit_convert_range_to_utf8(
     (range_u8, range_u16)
 }
 
+/// Get the length of the document in UTF-16 units.
+fn edit_len_utf16(edit: &mut dyn iface::TextInputCtxEdit<Wm>) -> usize {
+    let len = edit.len();
+    utf16_len(&edit.slice(0..len))
+}
+
 fn edit_convert_range_to_utf16(
     edit: &mut dyn iface::TextInputCtxEdit<Wm>,
     range: Range<usize>,
@@ -796,6 +850,16 @@ unsafe extern "system" fn impl_set_selection(
             return Err(tsf::TS_E_INVALIDPOS);
         }
 
+        let len_u16 = edit_len_utf16(&mut **edit);
+        if range.end > len_u16 {
+            log::debug!(
+                "... The range {:?} is out of bounds (len = {:?}), returning `TS_E_INVALIDPOS`",
+                range,
+                len_u16
+            );
+            return Err(tsf::TS_E_INVALIDPOS);
+        }
+
         // Convert `range` to UTF-8
         let mut range = edit_convert_range_to_utf8(&mut **edit, range).0;
 
@@ -918,7 +982,15 @@ unsafe extern "system" fn impl_get_text(
             return Err(tsf::TS_E_INVALIDPOS);
         }
 
-        // TODO: Report `TS_E_INVALIDPOS` when `acpEnd` is past the end
+        let len_u16 = edit_len_utf16(&mut **edit);
+        if acp_start > len_u16 || (acpEnd != -1 && acp_end > len_u16) {
+            log::debug!(
+                "... The range {:?} is out of bounds (len = {:?}), returning `TS_E_INVALIDPOS`",
+                acpStart..acpEnd,
+                len_u16
+            );
+            return Err(tsf::TS_E_INVALIDPOS);
+        }
 
         // Convert `acp_start..acp_end` to UTF-16/ACP.
         //
@@ -1272,7 +1344,10 @@ unsafe extern "system" fn impl_get_end_a_c_p(this: *mut ITextStoreACP, pacp: *mu
 
         let mut edit = this.expect_edit(false)?;
 
-        *pacp = edit.len().try_into().map_err(|_| E_UNEXPECTED)?;
+        // `acp` is measured in UTF-16 units
+        *pacp = edit_len_utf16(&mut **edit)
+            .try_into()
+            .map_err(|_| E_UNEXPECTED)?;
 
         Ok(S_OK)
     })
@@ -99,14 +99,38 @@ macro_rules! unsafe_impl_object {
 unsafe_impl_object! {
     winapi::um::unknwnbase::IUnknown,
     winapi::um::d3d11::ID3D11Device,
+    winapi::um::d3d11::ID3D11DeviceContext,
     winapi::um::d2d1_1::ID2D1Device,
     winapi::um::d2d1_1::ID2D1DeviceContext,
+    winapi::um::d3d11::ID3D11Texture2D,
+    winapi::um::dwrite_2::IDWriteColorGlyphRunEnumerator,
+    winapi::um::dwrite_2::IDWriteFactory2,
+    winapi::shared::dxgi::IDXGIAdapter,
     winapi::shared::dxgi::IDXGIDevice,
+    winapi::shared::dxgi1_2::IDXGIFactory2,
+    winapi::shared::dxgi1_2::IDXGISwapChain1,
+    winapi::um::shobjidl::IFileDialog,
+    winapi::um::shobjidl::IFileOpenDialog,
+    winapi::um::shobjidl_core::IShellItem,
+    winapi::um::shobjidl_core::IShellItemArray,
+    winapi::um::shobjidl_core::ITaskbarList3,
     winapiext::ID3D11Device4,
     winapiext::ICompositorDesktopInterop,
     winapiext::ICompositorInterop,
     winapiext::ICompositionGraphicsDeviceInterop,
     winapiext::ICompositionDrawingSurfaceInterop,
+    winapiext::ICoreWebView2Environment,
+    winapiext::ICoreWebView2Controller,
+    winapiext::ICoreWebView2,
+    winapiext::ICoreWebView2NavigationStartingEventArgs,
+    winapiext::ICoreWebView2NavigationCompletedEventArgs,
+    winapiext::ICoreWebView2WebMessageReceivedEventArgs,
+    winapiext::ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandler,
+    winapiext::ICoreWebView2CreateCoreWebView2ControllerCompletedHandler,
+    winapiext::ICoreWebView2NavigationStartingEventHandler,
+    winapiext::ICoreWebView2NavigationCompletedEventHandler,
+    winapiext::ICoreWebView2WebMessageReceivedEventHandler,
+    winapiext::ICoreWebView2StringCompletedHandler,
     tsf::ITfContext,
     tsf::ITfDocumentMgr,
     tsf::ITfThreadMgr,
@@ -0,0 +1,514 @@
+//! Implements web views using WebView2.
+//!
+//! `WebView2Loader.dll` is loaded at runtime so that it doesn't become a hard
+//! dependency of applications. It must be placed alongside the executable,
+//! and the WebView2 runtime must be installed on the system.
+#![allow(bad_style)]
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    mem::MaybeUninit,
+    os::raw::c_void,
+    ptr::null_mut,
+    rc::Rc,
+};
+use winapi::{
+    shared::{
+        guiddef::{IsEqualGUID, GUID, REFIID},
+        minwindef::BOOL,
+        ntdef::{LPCWSTR, LPWSTR, ULONG},
+        windef::{HWND, RECT},
+        winerror::{E_NOINTERFACE, S_OK},
+    },
+    um::{
+        combaseapi::CoTaskMemFree,
+        libloaderapi::{GetProcAddress, LoadLibraryW},
+        unknwnbase::{IUnknown, IUnknownVtbl},
+        winnt::HRESULT,
+        winuser::GetClientRect,
+    },
+    Interface,
+};
+
+use super::{
+    codecvt::{str_to_c_wstr, wstr_to_str},
+    utils::{assert_win32_ok, result_from_hresult, ComPtr},
+    winapiext::{
+        CreateCoreWebView2EnvironmentWithOptionsFn, EventRegistrationToken, ICoreWebView2,
+        ICoreWebView2Controller, ICoreWebView2CreateCoreWebView2ControllerCompletedHandler,
+        ICoreWebView2CreateCoreWebView2ControllerCompletedHandlerVtbl,
+        ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandler,
+        ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandlerVtbl, ICoreWebView2Environment,
+        ICoreWebView2NavigationCompletedEventArgs, ICoreWebView2NavigationCompletedEventHandler,
+        ICoreWebView2NavigationCompletedEventHandlerVtbl, ICoreWebView2NavigationStartingEventArgs,
+        ICoreWebView2NavigationStartingEventHandler,
+        ICoreWebView2NavigationStartingEventHandlerVtbl, ICoreWebView2StringCompletedHandler,
+        ICoreWebView2StringCompletedHandlerVtbl, ICoreWebView2WebMessageReceivedEventArgs,
+        ICoreWebView2WebMessageReceivedEventHandler,
+        ICoreWebView2WebMessageReceivedEventHandlerVtbl,
+    },
+    HChildWnd, Wm,
+};
+use crate::{cells::MtLazyStatic, iface};
+
+mt_lazy_static! {
+    static <Wm> ref CREATE_ENVIRONMENT: Option<CreateCoreWebView2EnvironmentWithOptionsFn> =>
+        |_| unsafe { load_create_environment() };
+
+    /// The web views attached to child windows, indexed by the child windows'
+    /// `HWND`s.
+    static <Wm> ref WEB_VIEWS: RefCell<HashMap<HWND, Rc<WebView>>> =>
+        |_| RefCell::new(HashMap::new());
+}
+
+unsafe fn load_create_environment() -> Option<CreateCoreWebView2EnvironmentWithOptionsFn> {
+    let hmodule = LoadLibraryW(str_to_c_wstr("WebView2Loader.dll").as_ptr());
+    if hmodule.is_null() {
+        log::warn!("Could not load WebView2Loader.dll. Web views will not be available.");
+        return None;
+    }
+
+    let proc = GetProcAddress(
+        hmodule,
+        b"CreateCoreWebView2EnvironmentWithOptions\0".as_ptr() as _,
+    );
+    if proc.is_null() {
+        log::warn!("Could not find CreateCoreWebView2EnvironmentWithOptions");
+        return None;
+    }
+
+    Some(std::mem::transmute(proc))
+}
+
+/// The IID of `ICoreWebView2AddScriptToExecuteOnDocumentCreatedCompletedHandler`,
+/// which we implement by `ICoreWebView2StringCompletedHandler`.
+const IID_ADD_SCRIPT_COMPLETED_HANDLER: GUID = GUID {
+    Data1: 0xb99369f3,
+    Data2: 0x9b11,
+    Data3: 0x47b5,
+    Data4: [0xbc, 0x6f, 0x8e, 0x78, 0x95, 0xfc, 0xea, 0x17],
+};
+
+/// Defines `window.tcw3.postMessage`.
+const BRIDGE_SCRIPT: &str = "window.tcw3 = { postMessage: function (m) { \
+                             window.chrome.webview.postMessage(String(m)); } };";
+
+struct WebView {
+    wm: Wm,
+    child: HChildWnd,
+    listener: Box<dyn iface::WebViewListener<Wm>>,
+    /// Set when the child window is removed while the controller is being
+    /// created.
+    closed: Cell<bool>,
+    core: RefCell<Option<Core>>,
+    /// Operations requested before the controller is created.
+    pending_ops: RefCell<Vec<Op>>,
+}
+
+struct Core {
+    controller: ComPtr<ICoreWebView2Controller>,
+    web_view: ComPtr<ICoreWebView2>,
+}
+
+enum Op {
+    Navigate(Box<[u16]>),
+    NavigateToString(Box<[u16]>),
+    ExecuteScript(Box<[u16]>),
+}
+
+pub fn attach_web_view(
+    wm: Wm,
+    child: &HChildWnd,
+    listener: Box<dyn iface::WebViewListener<Wm>>,
+) -> bool {
+    let create_environment = if let Some(x) = *CREATE_ENVIRONMENT.get_with_wm(wm) {
+        x
+    } else {
+        return false;
+    };
+
+    let hwnd = child.hwnd();
+    assert!(
+        !WEB_VIEWS.get_with_wm(wm).borrow().contains_key(&hwnd),
+        "already has a web view"
+    );
+
+    let web_view = Rc::new(WebView {
+        wm,
+        child: child.clone(),
+        listener,
+        closed: Cell::new(false),
+        core: RefCell::new(None),
+        pending_ops: RefCell::new(Vec::new()),
+    });
+
+    let web_view_weak = Rc::downgrade(&web_view);
+    let handler = EnvironmentCompletedHandler::new(move |hr, env| {
+        if let Some(web_view) = web_view_weak.upgrade() {
+            web_view.environment_created(hr, env);
+        }
+        S_OK
+    });
+
+    // Use the default browser and user data folder
+    let hr = unsafe { create_environment(null_mut(), null_mut(), null_mut(), handler.as_ptr()) };
+    if let Err(hr) = result_from_hresult(hr) {
+        log::warn!(
+            "CreateCoreWebView2EnvironmentWithOptions failed (0x{:08x}). \
+             Make sure the WebView2 runtime is installed.",
+            hr
+        );
+        return false;
+    }
+
+    WEB_VIEWS
+        .get_with_wm(wm)
+        .borrow_mut()
+        .insert(hwnd, web_view);
+
+    true
+}
+
+impl WebView {
+    fn environment_created(self: Rc<Self>, hr: HRESULT, env: *mut ICoreWebView2Environment) {
+        if self.closed.get() {
+            return;
+        }
+        if let Err(hr) = result_from_hresult(hr) {
+            log::warn!("Could not create a WebView2 environment (0x{:08x})", hr);
+            return;
+        }
+
+        let this_weak = Rc::downgrade(&self);
+        let handler = ControllerCompletedHandler::new(move |hr, controller| {
+            if let Some(this) = this_weak.upgrade() {
+                this.controller_created(hr, controller);
+            }
+            S_OK
+        });
+
+        let hr =
+            unsafe { (*env).CreateCoreWebView2Controller(self.child.hwnd(), handler.as_ptr()) };
+        if let Err(hr) = result_from_hresult(hr) {
+            log::warn!("CreateCoreWebView2Controller failed (0x{:08x})", hr);
+        }
+    }
+
+    fn controller_created(self: Rc<Self>, hr: HRESULT, controller: *mut ICoreWebView2Controller) {
+        if let Err(hr) = result_from_hresult(hr) {
+            log::warn!("Could not create a WebView2 controller (0x{:08x})", hr);
+            return;
+        }
+
+        let controller = unsafe {
+            (*controller).AddRef();
+            ComPtr::from_ptr_unchecked(controller)
+        };
+
+        if self.closed.get() {
+            unsafe { controller.Close() };
+            return;
+        }
+
+        let web_view = unsafe {
+            let mut out = MaybeUninit::uninit();
+            if let Err(hr) = result_from_hresult(controller.get_CoreWebView2(out.as_mut_ptr())) {
+                log::warn!(
+                    "ICoreWebView2Controller::get_CoreWebView2 failed (0x{:08x})",
+                    hr
+                );
+                return;
+            }
+            ComPtr::from_ptr_unchecked(out.assume_init())
+        };
+
+        self.register_event_handlers(&web_view);
+
+        unsafe {
+            web_view.AddScriptToExecuteOnDocumentCreated(
+                str_to_c_wstr(BRIDGE_SCRIPT).as_ptr(),
+                new_ignore_result_handler().as_ptr(),
+            );
+        }
+
+        *self.core.borrow_mut() = Some(Core {
+            controller,
+            web_view,
+        });
+        self.update_bounds();
+
+        let ops = std::mem::replace(&mut *self.pending_ops.borrow_mut(), Vec::new());
+        for op in ops {
+            self.run_op(op);
+        }
+    }
+
+    fn register_event_handlers(self: &Rc<Self>, web_view: &ComPtr<ICoreWebView2>) {
+        let mut token = EventRegistrationToken::default();
+
+        let this_weak = Rc::downgrade(self);
+        let handler = NavigationStartingHandler::new(move |_, args| {
+            if let Some(this) = this_weak.upgrade() {
+                let url = unsafe { take_co_task_wstr(|out| (*args).get_Uri(out)) };
+                if let Some(url) = url {
+                    this.listener.navigation_started(this.wm, &this.child, &url);
+                }
+            }
+            S_OK
+        });
+        unsafe { web_view.add_NavigationStarting(handler.as_ptr(), &mut token) };
+
+        let this_weak = Rc::downgrade(self);
+        let handler = NavigationCompletedHandler::new(move |sender, args| {
+            if let Some(this) = this_weak.upgrade() {
+                let mut success: BOOL = 0;
+                unsafe { (*args).get_IsSuccess(&mut success) };
+                let url = unsafe { take_co_task_wstr(|out| (*sender).get_Source(out)) };
+                let url = url.unwrap_or_default();
+                this.listener
+                    .navigation_completed(this.wm, &this.child, &url, success != 0);
+            }
+            S_OK
+        });
+        unsafe { web_view.add_NavigationCompleted(handler.as_ptr(), &mut token) };
+
+        let this_weak = Rc::downgrade(self);
+        let handler = WebMessageReceivedHandler::new(move |_, args| {
+            if let Some(this) = this_weak.upgrade() {
+                // `window.tcw3.postMessage` always sends a string. Fall back
+                // to JSON if the page called `chrome.webview.postMessage`
+                // directly.
+                let message = unsafe {
+                    take_co_task_wstr(|out| (*args).TryGetWebMessageAsString(out))
+                        .or_else(|| take_co_task_wstr(|out| (*args).get_WebMessageAsJson(out)))
+                };
+                if let Some(message) = message {
+                    this.listener
+                        .message_received(this.wm, &this.child, &message);
+                }
+            }
+            S_OK
+        });
+        unsafe { web_view.add_WebMessageReceived(handler.as_ptr(), &mut token) };
+    }
+
+    fn run_op(&self, op: Op) {
+        // Clone the pointer so that `core` is not borrowed while WebView2
+        // (which might call the event handlers synchronously) is running
+        let web_view = if let Some(core) = &*self.core.borrow() {
+            core.web_view.clone()
+        } else {
+            self.pending_ops.borrow_mut().push(op);
+            return;
+        };
+
+        let hr = unsafe {
+            match &op {
+                Op::Navigate(url) => web_view.Navigate(url.as_ptr()),
+                Op::NavigateToString(html) => web_view.NavigateToString(html.as_ptr()),
+                Op::ExecuteScript(script) => {
+                    web_view.ExecuteScript(script.as_ptr(), new_ignore_result_handler().as_ptr())
+                }
+            }
+        };
+        if let Err(hr) = result_from_hresult(hr) {
+            log::warn!("A WebView2 operation failed (0x{:08x})", hr);
+        }
+    }
+
+    /// Make the web view fill the child window.
+    fn update_bounds(&self) {
+        if let Some(core) = &*self.core.borrow() {
+            let mut rect = MaybeUninit::<RECT>::uninit();
+            unsafe {
+                assert_win32_ok(GetClientRect(self.child.hwnd(), rect.as_mut_ptr()));
+                core.controller.put_Bounds(rect.assume_init());
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.closed.set(true);
+        self.pending_ops.borrow_mut().clear();
+        let core = self.core.borrow_mut().take();
+        if let Some(core) = core {
+            unsafe { core.controller.Close() };
+        }
+    }
+}
+
+fn with_web_view(wm: Wm, child: &HChildWnd, f: impl FnOnce(&WebView)) {
+    let web_view = WEB_VIEWS
+        .get_with_wm(wm)
+        .borrow()
+        .get(&child.hwnd())
+        .cloned();
+    if let Some(web_view) = web_view {
+        f(&web_view);
+    }
+}
+
+pub fn web_view_navigate(wm: Wm, child: &HChildWnd, url: &str) {
+    with_web_view(wm, child, |web_view| {
+        web_view.run_op(Op::Navigate(str_to_c_wstr(url)));
+    });
+}
+
+pub fn web_view_load_html(wm: Wm, child: &HChildWnd, html: &str, _base_url: Option<&str>) {
+    // `NavigateToString` does not support base URLs
+    with_web_view(wm, child, |web_view| {
+        web_view.run_op(Op::NavigateToString(str_to_c_wstr(html)));
+    });
+}
+
+pub fn web_view_eval_script(wm: Wm, child: &HChildWnd, script: &str) {
+    with_web_view(wm, child, |web_view| {
+        web_view.run_op(Op::ExecuteScript(str_to_c_wstr(script)));
+    });
+}
+
+/// Called when the child window is resized.
+pub fn update_bounds(wm: Wm, child: &HChildWnd) {
+    with_web_view(wm, child, |web_view| web_view.update_bounds());
+}
+
+/// Called when the child window is removed.
+pub fn remove_web_view(wm: Wm, child: &HChildWnd) {
+    let web_view = WEB_VIEWS.get_with_wm(wm).borrow_mut().remove(&child.hwnd());
+    if let Some(web_view) = web_view {
+        web_view.close();
+    }
+}
+
+/// Call a getter returning a string allocated by `CoTaskMemAlloc` and convert
+/// the string to `String`.
+unsafe fn take_co_task_wstr(f: impl FnOnce(*mut LPWSTR) -> HRESULT) -> Option<String> {
+    let mut p: LPWSTR = null_mut();
+    if result_from_hresult(f(&mut p)).is_err() || p.is_null() {
+        return None;
+    }
+
+    let len = (0..).find(|&i| *p.offset(i) == 0).unwrap() as usize;
+    let s = wstr_to_str(std::slice::from_raw_parts(p, len)).into();
+    CoTaskMemFree(p as _);
+    Some(s)
+}
+
+fn new_ignore_result_handler() -> ComPtr<ICoreWebView2StringCompletedHandler> {
+    StringCompletedHandler::new(|_, _| S_OK)
+}
+
+/// Define a type implementing a COM interface with a single method `Invoke`
+/// by a closure.
+macro_rules! closure_handler {
+    (
+        $(#[$meta:meta])*
+        struct $name:ident: $iface:ident($vtbl:ident)
+            $(+ $extra_iid:ident)*;
+        fn Invoke($a:ty, $b:ty);
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        struct $name {
+            _vtbl: &'static $vtbl,
+            f: Box<dyn Fn($a, $b) -> HRESULT>,
+        }
+
+        impl $name {
+            fn new(f: impl Fn($a, $b) -> HRESULT + 'static) -> ComPtr<$iface> {
+                static VTBL: $vtbl = $vtbl {
+                    parent: IUnknownVtbl {
+                        QueryInterface: impl_query_interface,
+                        AddRef: impl_add_ref,
+                        Release: impl_release,
+                    },
+                    Invoke: impl_invoke,
+                };
+
+                unsafe extern "system" fn impl_query_interface(
+                    this: *mut IUnknown,
+                    iid: REFIID,
+                    ppv: *mut *mut c_void,
+                ) -> HRESULT {
+                    let iid = &*iid;
+                    if IsEqualGUID(iid, &IUnknown::uuidof())
+                        || IsEqualGUID(iid, &$iface::uuidof())
+                        $(|| IsEqualGUID(iid, &$extra_iid))*
+                    {
+                        impl_add_ref(this);
+                        *ppv = this as *mut _;
+                        S_OK
+                    } else {
+                        *ppv = null_mut();
+                        E_NOINTERFACE
+                    }
+                }
+
+                unsafe extern "system" fn impl_add_ref(this: *mut IUnknown) -> ULONG {
+                    let rc = Rc::from_raw(this as *const $name);
+                    std::mem::forget(Rc::clone(&rc));
+                    std::mem::forget(rc);
+                    2
+                }
+
+                unsafe extern "system" fn impl_release(this: *mut IUnknown) -> ULONG {
+                    Rc::from_raw(this as *const $name);
+                    1
+                }
+
+                unsafe extern "system" fn impl_invoke(this: *mut $iface, a: $a, b: $b) -> HRESULT {
+                    let this = &*(this as *const $name);
+                    (this.f)(a, b)
+                }
+
+                let this = Rc::new($name {
+                    _vtbl: &VTBL,
+                    f: Box::new(f),
+                });
+                unsafe { ComPtr::from_ptr_unchecked(Rc::into_raw(this) as *mut $iface) }
+            }
+        }
+    };
+}
+
+closure_handler! {
+    struct EnvironmentCompletedHandler:
+        ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandler(
+            ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandlerVtbl);
+    fn Invoke(HRESULT, *mut ICoreWebView2Environment);
+}
+
+closure_handler! {
+    struct ControllerCompletedHandler:
+        ICoreWebView2CreateCoreWebView2ControllerCompletedHandler(
+            ICoreWebView2CreateCoreWebView2ControllerCompletedHandlerVtbl);
+    fn Invoke(HRESULT, *mut ICoreWebView2Controller);
+}
+
+closure_handler! {
+    struct NavigationStartingHandler:
+        ICoreWebView2NavigationStartingEventHandler(
+            ICoreWebView2NavigationStartingEventHandlerVtbl);
+    fn Invoke(*mut ICoreWebView2, *mut ICoreWebView2NavigationStartingEventArgs);
+}
+
+closure_handler! {
+    struct NavigationCompletedHandler:
+        ICoreWebView2NavigationCompletedEventHandler(
+            ICoreWebView2NavigationCompletedEventHandlerVtbl);
+    fn Invoke(*mut ICoreWebView2, *mut ICoreWebView2NavigationCompletedEventArgs);
+}
+
+closure_handler! {
+    struct WebMessageReceivedHandler:
+        ICoreWebView2WebMessageReceivedEventHandler(
+            ICoreWebView2WebMessageReceivedEventHandlerVtbl);
+    fn Invoke(*mut ICoreWebView2, *mut ICoreWebView2WebMessageReceivedEventArgs);
+}
+
+closure_handler! {
+    struct StringCompletedHandler:
+        ICoreWebView2StringCompletedHandler(ICoreWebView2StringCompletedHandlerVtbl)
+            + IID_ADD_SCRIPT_COMPLETED_HANDLER;
+    fn Invoke(HRESULT, LPCWSTR);
+}
@@ -5,8 +5,8 @@ use winapi::{
     shared::{
         guiddef::{GUID, REFIID},
         minwindef::{BOOL, DWORD, UINT},
-        ntdef::LPCWSTR,
-        windef::{HWND, POINT, RECT, SIZE},
+        ntdef::{LPCWSTR, LPWSTR, PWSTR, ULONGLONG},
+        windef::{HWND, POINT, POINTS, RECT, SIZE},
     },
     um::{
         d3d11_2::{ID3D11Device2, ID3D11Device2Vtbl},
@@ -17,7 +17,9 @@ use winapi::{
 };
 use winrt::{
     windows::graphics::effects::IGraphicsEffectSource,
-    windows::ui::composition::{desktop::IDesktopWindowTarget, ICompositionGraphicsDevice},
+    windows::ui::composition::{
+        desktop::IDesktopWindowTarget, ICompositionGraphicsDevice, ICompositionSurface,
+    },
 };
 
 pub enum Never {}
@@ -80,8 +82,9 @@ interface ICompositorInterop(ICompositorInteropVtbl):
         dummy: Never,
     ) -> (),
     fn CreateCompositionSurfaceForSwapChain(
-        dummy: Never,
-    ) -> (),
+        swapChain: *mut IUnknown,
+        result: *mut *mut ICompositionSurface,
+    ) -> HRESULT,
     fn CreateGraphicsDevice(
         renderingDevice: *mut IUnknown,
         result: *mut *mut ICompositionGraphicsDevice,
@@ -179,3 +182,350 @@ pub enum GRAPHICS_EFFECT_PROPERTY_MAPPING {
     GRAPHICS_EFFECT_PROPERTY_MAPPING_COLOR_TO_VECTOR3,
     GRAPHICS_EFFECT_PROPERTY_MAPPING_COLOR_TO_VECTOR4,
 }
+
+// `WebView2.h` (WebView2 SDK)
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventRegistrationToken {
+    pub value: i64,
+}
+
+pub type CreateCoreWebView2EnvironmentWithOptionsFn = unsafe extern "system" fn(
+    browserExecutableFolder: LPCWSTR,
+    userDataFolder: LPCWSTR,
+    environmentOptions: *mut IUnknown,
+    environmentCreatedHandler: *mut ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandler,
+) -> HRESULT;
+
+RIDL! {#[uuid(0xb96d755e, 0x0319, 0x4e92, 0xa2, 0x96, 0x23, 0x43, 0x6f, 0x46, 0xa1, 0xfc)]
+interface ICoreWebView2Environment(ICoreWebView2EnvironmentVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn CreateCoreWebView2Controller(
+        parentWindow: HWND,
+        handler: *mut ICoreWebView2CreateCoreWebView2ControllerCompletedHandler,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x4d00c0d1, 0x9434, 0x4eb6, 0x80, 0x78, 0x86, 0x97, 0xa5, 0x60, 0x33, 0x4f)]
+interface ICoreWebView2Controller(ICoreWebView2ControllerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn get_IsVisible(
+        dummy: Never,
+    ) -> (),
+    fn put_IsVisible(
+        dummy: Never,
+    ) -> (),
+    fn get_Bounds(
+        dummy: Never,
+    ) -> (),
+    fn put_Bounds(
+        bounds: RECT,
+    ) -> HRESULT,
+    fn get_ZoomFactor(
+        dummy: Never,
+    ) -> (),
+    fn put_ZoomFactor(
+        dummy: Never,
+    ) -> (),
+    fn add_ZoomFactorChanged(
+        dummy: Never,
+    ) -> (),
+    fn remove_ZoomFactorChanged(
+        dummy: Never,
+    ) -> (),
+    fn SetBoundsAndZoomFactor(
+        dummy: Never,
+    ) -> (),
+    fn MoveFocus(
+        dummy: Never,
+    ) -> (),
+    fn add_MoveFocusRequested(
+        dummy: Never,
+    ) -> (),
+    fn remove_MoveFocusRequested(
+        dummy: Never,
+    ) -> (),
+    fn add_GotFocus(
+        dummy: Never,
+    ) -> (),
+    fn remove_GotFocus(
+        dummy: Never,
+    ) -> (),
+    fn add_LostFocus(
+        dummy: Never,
+    ) -> (),
+    fn remove_LostFocus(
+        dummy: Never,
+    ) -> (),
+    fn add_AcceleratorKeyPressed(
+        dummy: Never,
+    ) -> (),
+    fn remove_AcceleratorKeyPressed(
+        dummy: Never,
+    ) -> (),
+    fn get_ParentWindow(
+        dummy: Never,
+    ) -> (),
+    fn put_ParentWindow(
+        dummy: Never,
+    ) -> (),
+    fn NotifyParentWindowPositionChanged(
+        dummy: Never,
+    ) -> (),
+    fn Close() -> HRESULT,
+    fn get_CoreWebView2(
+        coreWebView2: *mut *mut ICoreWebView2,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x76eceacb, 0x0462, 0x4d94, 0xac, 0x83, 0x42, 0x3a, 0x67, 0x93, 0x77, 0x5e)]
+interface ICoreWebView2(ICoreWebView2Vtbl):
+    IUnknown(IUnknownVtbl) {
+    fn get_Settings(
+        dummy: Never,
+    ) -> (),
+    fn get_Source(
+        uri: *mut LPWSTR,
+    ) -> HRESULT,
+    fn Navigate(
+        uri: LPCWSTR,
+    ) -> HRESULT,
+    fn NavigateToString(
+        htmlContent: LPCWSTR,
+    ) -> HRESULT,
+    fn add_NavigationStarting(
+        eventHandler: *mut ICoreWebView2NavigationStartingEventHandler,
+        token: *mut EventRegistrationToken,
+    ) -> HRESULT,
+    fn remove_NavigationStarting(
+        dummy: Never,
+    ) -> (),
+    fn add_ContentLoading(
+        dummy: Never,
+    ) -> (),
+    fn remove_ContentLoading(
+        dummy: Never,
+    ) -> (),
+    fn add_SourceChanged(
+        dummy: Never,
+    ) -> (),
+    fn remove_SourceChanged(
+        dummy: Never,
+    ) -> (),
+    fn add_HistoryChanged(
+        dummy: Never,
+    ) -> (),
+    fn remove_HistoryChanged(
+        dummy: Never,
+    ) -> (),
+    fn add_NavigationCompleted(
+        eventHandler: *mut ICoreWebView2NavigationCompletedEventHandler,
+        token: *mut EventRegistrationToken,
+    ) -> HRESULT,
+    fn remove_NavigationCompleted(
+        dummy: Never,
+    ) -> (),
+    fn add_FrameNavigationStarting(
+        dummy: Never,
+    ) -> (),
+    fn remove_FrameNavigationStarting(
+        dummy: Never,
+    ) -> (),
+    fn add_FrameNavigationCompleted(
+        dummy: Never,
+    ) -> (),
+    fn remove_FrameNavigationCompleted(
+        dummy: Never,
+    ) -> (),
+    fn add_ScriptDialogOpening(
+        dummy: Never,
+    ) -> (),
+    fn remove_ScriptDialogOpening(
+        dummy: Never,
+    ) -> (),
+    fn add_PermissionRequested(
+        dummy: Never,
+    ) -> (),
+    fn remove_PermissionRequested(
+        dummy: Never,
+    ) -> (),
+    fn add_ProcessFailed(
+        dummy: Never,
+    ) -> (),
+    fn remove_ProcessFailed(
+        dummy: Never,
+    ) -> (),
+    fn AddScriptToExecuteOnDocumentCreated(
+        javaScript: LPCWSTR,
+        handler: *mut ICoreWebView2StringCompletedHandler,
+    ) -> HRESULT,
+    fn RemoveScriptToExecuteOnDocumentCreated(
+        dummy: Never,
+    ) -> (),
+    fn ExecuteScript(
+        javaScript: LPCWSTR,
+        handler: *mut ICoreWebView2StringCompletedHandler,
+    ) -> HRESULT,
+    fn CapturePreview(
+        dummy: Never,
+    ) -> (),
+    fn Reload(
+        dummy: Never,
+    ) -> (),
+    fn PostWebMessageAsJson(
+        dummy: Never,
+    ) -> (),
+    fn PostWebMessageAsString(
+        dummy: Never,
+    ) -> (),
+    fn add_WebMessageReceived(
+        handler: *mut ICoreWebView2WebMessageReceivedEventHandler,
+        token: *mut EventRegistrationToken,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x5b495469, 0xe119, 0x438a, 0x9b, 0x18, 0x76, 0x04, 0xf2, 0x5f, 0x2e, 0x49)]
+interface ICoreWebView2NavigationStartingEventArgs(ICoreWebView2NavigationStartingEventArgsVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn get_Uri(
+        uri: *mut LPWSTR,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x30d68b7d, 0x20d9, 0x4752, 0xa9, 0xca, 0xec, 0x84, 0x48, 0xfb, 0xb5, 0xc1)]
+interface ICoreWebView2NavigationCompletedEventArgs(ICoreWebView2NavigationCompletedEventArgsVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn get_IsSuccess(
+        isSuccess: *mut BOOL,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x0f99a40c, 0xe962, 0x4207, 0x9e, 0x92, 0xe3, 0xd5, 0x42, 0xef, 0xf8, 0x49)]
+interface ICoreWebView2WebMessageReceivedEventArgs(ICoreWebView2WebMessageReceivedEventArgsVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn get_Source(
+        source: *mut LPWSTR,
+    ) -> HRESULT,
+    fn get_WebMessageAsJson(
+        webMessageAsJson: *mut LPWSTR,
+    ) -> HRESULT,
+    fn TryGetWebMessageAsString(
+        webMessageAsString: *mut LPWSTR,
+    ) -> HRESULT,
+}}
+
+// The following interfaces are implemented by the client. The completion
+// handlers with the signature `Invoke(HRESULT, LPCWSTR)`
+// (`ICoreWebView2AddScriptToExecuteOnDocumentCreatedCompletedHandler` and
+// `ICoreWebView2ExecuteScriptCompletedHandler`) share the same definition
+// `ICoreWebView2StringCompletedHandler`.
+
+RIDL! {#[uuid(0x4e8a3389, 0xc9d8, 0x4bd2, 0xb6, 0xb5, 0x12, 0x4f, 0xee, 0x6c, 0xc1, 0x4d)]
+interface ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandler(
+    ICoreWebView2CreateCoreWebView2EnvironmentCompletedHandlerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn Invoke(
+        errorCode: HRESULT,
+        createdEnvironment: *mut ICoreWebView2Environment,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x6c4819f3, 0xc9b7, 0x4260, 0x81, 0x27, 0xc9, 0xf5, 0xbd, 0xe7, 0xf6, 0x8c)]
+interface ICoreWebView2CreateCoreWebView2ControllerCompletedHandler(
+    ICoreWebView2CreateCoreWebView2ControllerCompletedHandlerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn Invoke(
+        errorCode: HRESULT,
+        createdController: *mut ICoreWebView2Controller,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x9adbe429, 0xf36d, 0x432b, 0x9d, 0xdc, 0xf8, 0x88, 0x1f, 0xbd, 0x76, 0xe3)]
+interface ICoreWebView2NavigationStartingEventHandler(
+    ICoreWebView2NavigationStartingEventHandlerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn Invoke(
+        sender: *mut ICoreWebView2,
+        args: *mut ICoreWebView2NavigationStartingEventArgs,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0xd33a35bf, 0x1c49, 0x4f98, 0x93, 0xab, 0x00, 0x6e, 0x05, 0x33, 0xfe, 0x1c)]
+interface ICoreWebView2NavigationCompletedEventHandler(
+    ICoreWebView2NavigationCompletedEventHandlerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn Invoke(
+        sender: *mut ICoreWebView2,
+        args: *mut ICoreWebView2NavigationCompletedEventArgs,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x57213f19, 0x00e6, 0x49fa, 0x8e, 0x07, 0x89, 0x8e, 0xa0, 0x1e, 0xcb, 0xd2)]
+interface ICoreWebView2WebMessageReceivedEventHandler(
+    ICoreWebView2WebMessageReceivedEventHandlerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn Invoke(
+        sender: *mut ICoreWebView2,
+        args: *mut ICoreWebView2WebMessageReceivedEventArgs,
+    ) -> HRESULT,
+}}
+
+RIDL! {#[uuid(0x49511172, 0xcc67, 0x4bca, 0x99, 0x23, 0x13, 0x71, 0x12, 0xf4, 0xc4, 0xcc)]
+interface ICoreWebView2StringCompletedHandler(ICoreWebView2StringCompletedHandlerVtbl):
+    IUnknown(IUnknownVtbl) {
+    fn Invoke(
+        errorCode: HRESULT,
+        result: LPCWSTR,
+    ) -> HRESULT,
+}}
+
+#[link(name = "shell32")]
+extern "system" {
+    pub fn GetCurrentProcessExplicitAppUserModelID(AppID: *mut PWSTR) -> HRESULT;
+}
+
+pub type HGESTUREINFO = HANDLE;
+
+#[repr(C)]
+pub struct GESTUREINFO {
+    pub cbSize: UINT,
+    pub dwFlags: DWORD,
+    pub dwID: DWORD,
+    pub hwndTarget: HWND,
+    pub ptsLocation: POINTS,
+    pub dwInstanceID: DWORD,
+    pub dwSequenceID: DWORD,
+    pub ullArguments: ULONGLONG,
+    pub cbExtraArgs: UINT,
+}
+
+#[repr(C)]
+pub struct GESTURECONFIG {
+    pub dwID: DWORD,
+    pub dwWant: DWORD,
+    pub dwBlock: DWORD,
+}
+
+pub const GID_BEGIN: DWORD = 1;
+pub const GID_END: DWORD = 2;
+pub const GID_ZOOM: DWORD = 3;
+pub const GID_ROTATE: DWORD = 5;
+
+pub const GF_BEGIN: DWORD = 0x1;
+
+pub const GC_ZOOM: DWORD = 0x1;
+pub const GC_ROTATE: DWORD = 0x1;
+
+#[link(name = "user32")]
+extern "system" {
+    pub fn GetGestureInfo(hGestureInfo: HGESTUREINFO, pGestureInfo: *mut GESTUREINFO) -> BOOL;
+    pub fn CloseGestureInfoHandle(hGestureInfo: HGESTUREINFO) -> BOOL;
+    pub fn SetGestureConfig(
+        hwnd: HWND,
+        dwReserved: DWORD,
+        cIDs: UINT,
+        pGestureConfig: *const GESTURECONFIG,
+        cbSize: UINT,
+    ) -> BOOL;
+}
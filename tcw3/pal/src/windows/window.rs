@@ -4,10 +4,11 @@ use std::{
     cell::{Cell, RefCell},
     convert::TryInto,
     fmt,
-    mem::{size_of, MaybeUninit},
+    mem::{size_of, zeroed, MaybeUninit},
     ptr::null_mut,
     rc::Rc,
     sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
 };
 use wchar::wch_c;
 use winapi::{
@@ -20,14 +21,14 @@ use winapi::{
 };
 
 use super::{
-    acceltable,
+    acceltable, appearance,
     codecvt::str_to_c_wstr,
-    comp, frameclock,
+    comp, frameclock, taskbar,
     textinput::TextInputWindow,
     utils::{assert_win32_nonnull, assert_win32_ok},
-    AccelTable, Wm, WndAttrs,
+    winapiext, AccelTable, Wm, WndAttrs,
 };
-use crate::{iface, prelude::*};
+use crate::{clickcount::ClickCounter, iface, prelude::*};
 
 const WND_CLASS: &[u16] = wch_c!("TcwAppWnd");
 
@@ -71,8 +72,22 @@ struct Wnd {
     update_ready_pending: Cell<bool>,
 
     drag_state: RefCell<Option<MouseDragState>>,
+    click_counter: RefCell<ClickCounter>,
+    /// The IDs of touch and pen pointers for which `WM_POINTERDOWN` was not
+    /// handled by the listener.
+    unhandled_pointers: RefCell<Vec<u32>>,
+
+    gesture_state: RefCell<Option<GestureState>>,
 
     text_input_wnd: TextInputWindow,
+
+    progress: Cell<Option<iface::ProgressState>>,
+
+    pointer_grab: Cell<bool>,
+
+    /// `WndAttrs::maximized` set while the window is hidden. Applied when
+    /// the window is shown.
+    maximize_on_show: Cell<bool>,
 }
 
 impl fmt::Debug for Wnd {
@@ -94,6 +109,14 @@ struct MouseDragState {
     pressed_buttons: u8,
 }
 
+struct GestureState {
+    listener: Rc<dyn iface::GestureListener<Wm>>,
+    /// The distance between the two contacts in the last `GID_ZOOM` event.
+    last_distance: f64,
+    /// The rotation angle in the last `GID_ROTATE` event.
+    last_angle: f64,
+}
+
 /// Hard-coded limit for window size for various calculations not to overflow
 const MAX_WND_SIZE: u32 = 0x10000;
 
@@ -104,6 +127,12 @@ impl HWnd {
         hwnd
     }
 
+    /// Get the raw window handle, or `None` if the window has already been
+    /// destroyed.
+    pub(super) fn hwnd_if_alive(&self) -> Option<HWND> {
+        Some(self.wnd.hwnd.get()).filter(|hwnd| !hwnd.is_null())
+    }
+
     pub(super) fn text_input_wnd(&self) -> &TextInputWindow {
         &self.wnd.text_input_wnd
     }
@@ -176,7 +205,13 @@ pub fn new_wnd(wm: Wm, attrs: WndAttrs<'_>) -> HWnd {
             flags: Cell::new(iface::WndFlags::default()),
             update_ready_pending: Cell::new(false),
             drag_state: RefCell::new(None),
+            click_counter: RefCell::new(ClickCounter::default()),
+            unhandled_pointers: RefCell::new(Vec::new()),
+            gesture_state: RefCell::new(None),
             text_input_wnd: TextInputWindow::new(),
+            progress: Cell::new(None),
+            pointer_grab: Cell::new(false),
+            maximize_on_show: Cell::new(false),
         }),
     };
 
@@ -189,12 +224,35 @@ pub fn new_wnd(wm: Wm, attrs: WndAttrs<'_>) -> HWnd {
         );
     }
 
+    // Enable the rotation gesture, which is disabled by default
+    let gesture_configs = [
+        winapiext::GESTURECONFIG {
+            dwID: winapiext::GID_ZOOM,
+            dwWant: winapiext::GC_ZOOM,
+            dwBlock: 0,
+        },
+        winapiext::GESTURECONFIG {
+            dwID: winapiext::GID_ROTATE,
+            dwWant: winapiext::GC_ROTATE,
+            dwBlock: 0,
+        },
+    ];
+    unsafe {
+        winapiext::SetGestureConfig(
+            hwnd,
+            0,
+            gesture_configs.len() as _,
+            gesture_configs.as_ptr(),
+            size_of::<winapiext::GESTURECONFIG>() as _,
+        );
+    }
+
     set_wnd_attr(wm, &pal_hwnd, attrs);
 
     pal_hwnd
 }
 
-pub fn set_wnd_attr(_: Wm, pal_hwnd: &HWnd, attrs: WndAttrs<'_>) {
+pub fn set_wnd_attr(wm: Wm, pal_hwnd: &HWnd, attrs: WndAttrs<'_>) {
     let hwnd = pal_hwnd.expect_hwnd();
 
     if let Some(shape) = attrs.cursor_shape {
@@ -259,7 +317,11 @@ pub fn set_wnd_attr(_: Wm, pal_hwnd: &HWnd, attrs: WndAttrs<'_>) {
             winuser::SetWindowLongW(hwnd, winuser::GWL_STYLE, new_style as _);
         }
 
-        if diff.contains(iface::WndFlags::FULL_SIZE_CONTENT) {
+        // The window doesn't have a redirection surface
+        // (`WS_EX_NOREDIRECTIONBITMAP`), and the composition surfaces use
+        // premultiplied alpha, so DWM composites the visuals using their alpha
+        // values. `TRANSPARENT` only has to remove the DWM frame behind them.
+        if diff.intersects(iface::WndFlags::FULL_SIZE_CONTENT | iface::WndFlags::TRANSPARENT) {
             update_wnd_frame(pal_hwnd);
         }
     }
@@ -341,6 +403,26 @@ pub fn set_wnd_attr(_: Wm, pal_hwnd: &HWnd, attrs: WndAttrs<'_>) {
         }
     }
 
+    if let Some(new_pos) = attrs.position {
+        let dpi = unsafe { winuser::GetDpiForWindow(hwnd) } as i32;
+        assert_win32_ok(dpi);
+
+        unsafe {
+            assert_win32_ok(winuser::SetWindowPos(
+                hwnd,
+                null_mut(),
+                new_pos[0] * dpi / 96,
+                new_pos[1] * dpi / 96,
+                0, // ignored
+                0, // ignored
+                winuser::SWP_NOZORDER
+                    | winuser::SWP_NOSIZE
+                    | winuser::SWP_NOACTIVATE
+                    | winuser::SWP_NOOWNERZORDER,
+            ));
+        }
+    }
+
     if let Some(caption) = attrs.caption {
         let caption_w = str_to_c_wstr(&caption);
         unsafe {
@@ -348,6 +430,19 @@ pub fn set_wnd_attr(_: Wm, pal_hwnd: &HWnd, attrs: WndAttrs<'_>) {
         }
     }
 
+    if let Some(progress) = attrs.progress {
+        pal_hwnd.wnd.progress.set(progress);
+        taskbar::set_progress(wm, hwnd, progress);
+    }
+
+    if let Some(grab) = attrs.pointer_grab {
+        if grab != pal_hwnd.wnd.pointer_grab.get() {
+            pal_hwnd.wnd.pointer_grab.set(grab);
+            set_raw_mouse_input_enabled(hwnd, grab);
+            update_pointer_clip(pal_hwnd);
+        }
+    }
+
     if let Some(listener) = attrs.listener {
         pal_hwnd.wnd.listener.replace(Rc::from(listener));
     }
@@ -356,15 +451,36 @@ pub fn set_wnd_attr(_: Wm, pal_hwnd: &HWnd, attrs: WndAttrs<'_>) {
         pal_hwnd.wnd.comp_wnd.set_layer(hwnd, layer);
     }
 
+    if let Some(maximized) = attrs.maximized {
+        if unsafe { winuser::IsWindowVisible(hwnd) } != 0 {
+            let is_zoomed = unsafe { winuser::IsZoomed(hwnd) } != 0;
+            if maximized != is_zoomed {
+                let cmd = if maximized {
+                    winuser::SW_MAXIMIZE
+                } else {
+                    winuser::SW_RESTORE
+                };
+                unsafe {
+                    winuser::ShowWindow(hwnd, cmd);
+                }
+            }
+        } else {
+            // `ShowWindow(SW_MAXIMIZE)` would show the window
+            pal_hwnd.wnd.maximize_on_show.set(maximized);
+        }
+    }
+
     if let Some(visible) = attrs.visible {
         // Note: `ShowWindow` ignores the command and uses the value specified
         // by the program that launched the current application when it's
         // called for the first time. It's usually (but not always) a desired
         // behavior.
-        let cmd = if visible {
-            winuser::SW_SHOW
-        } else {
+        let cmd = if !visible {
             winuser::SW_HIDE
+        } else if pal_hwnd.wnd.maximize_on_show.replace(false) {
+            winuser::SW_SHOWMAXIMIZED
+        } else {
+            winuser::SW_SHOW
         };
         unsafe {
             winuser::ShowWindow(hwnd, cmd);
@@ -408,10 +524,78 @@ fn is_mouse_in_wnd(hwnd: HWND) -> bool {
     te.dwFlags & winuser::TME_LEAVE != 0
 }
 
+/// Register or unregister `hwnd` as the receiver of raw mouse input
+/// (`WM_INPUT`).
+fn set_raw_mouse_input_enabled(hwnd: HWND, enable: bool) {
+    let device = winuser::RAWINPUTDEVICE {
+        usUsagePage: 0x01, // `HID_USAGE_PAGE_GENERIC`
+        usUsage: 0x02,     // `HID_USAGE_GENERIC_MOUSE`
+        dwFlags: if enable { 0 } else { winuser::RIDEV_REMOVE },
+        hwndTarget: if enable { hwnd } else { null_mut() },
+    };
+    unsafe {
+        assert_win32_ok(winuser::RegisterRawInputDevices(
+            &device,
+            1,
+            size_of::<winuser::RAWINPUTDEVICE>() as _,
+        ));
+    }
+}
+
+/// Confine the mouse pointer to the client region if the pointer grab mode
+/// is enabled and the window is active. Release the confinement otherwise.
+///
+/// The confinement is released by the system when the window is deactivated,
+/// so this should be called whenever the window is activated, moved, or
+/// resized.
+fn update_pointer_clip(pal_hwnd: &HWnd) {
+    let hwnd = pal_hwnd.expect_hwnd();
+    let is_active = unsafe { winuser::GetActiveWindow() } == hwnd;
+
+    if pal_hwnd.wnd.pointer_grab.get() && is_active {
+        let mut rect = MaybeUninit::uninit();
+        assert_win32_ok(unsafe { winuser::GetClientRect(hwnd, rect.as_mut_ptr()) });
+        let rect = unsafe { rect.assume_init() };
+
+        let mut corners = [
+            POINT {
+                x: rect.left,
+                y: rect.top,
+            },
+            POINT {
+                x: rect.right,
+                y: rect.bottom,
+            },
+        ];
+        for pt in corners.iter_mut() {
+            assert_win32_ok(unsafe { winuser::ClientToScreen(hwnd, pt) });
+        }
+
+        let screen_rect = RECT {
+            left: corners[0].x,
+            top: corners[0].y,
+            right: corners[1].x,
+            bottom: corners[1].y,
+        };
+        unsafe {
+            winuser::ClipCursor(&screen_rect);
+        }
+    } else if is_active {
+        unsafe {
+            winuser::ClipCursor(std::ptr::null());
+        }
+    }
+}
+
 pub fn remove_wnd(wm: Wm, pal_hwnd: &HWnd) {
     // Invalidate all text input contexts associated with the window
     pal_hwnd.wnd.text_input_wnd.invalidate(wm);
 
+    if pal_hwnd.wnd.pointer_grab.replace(false) {
+        set_raw_mouse_input_enabled(pal_hwnd.expect_hwnd(), false);
+        update_pointer_clip(pal_hwnd);
+    }
+
     let hwnd = pal_hwnd.expect_hwnd();
     unsafe {
         winuser::DestroyWindow(hwnd);
@@ -444,6 +628,26 @@ pub fn get_wnd_size(_: Wm, pal_hwnd: &HWnd) -> [u32; 2] {
     size.map(|i| phy_to_log(i, dpi))
 }
 
+pub fn get_wnd_position(_: Wm, pal_hwnd: &HWnd) -> [i32; 2] {
+    let hwnd = pal_hwnd.expect_hwnd();
+
+    // Get the bounding rectangle of the window, including the frame
+    let mut rect = MaybeUninit::uninit();
+    assert_win32_ok(unsafe { winuser::GetWindowRect(hwnd, rect.as_mut_ptr()) });
+    let rect = unsafe { rect.assume_init() };
+
+    let dpi = unsafe { winuser::GetDpiForWindow(hwnd) } as i32;
+    assert_win32_ok(dpi);
+
+    [rect.left, rect.top].map(|i| (i * 96).div_euclid(dpi))
+}
+
+pub fn is_wnd_maximized(_: Wm, pal_hwnd: &HWnd) -> bool {
+    let hwnd = pal_hwnd.expect_hwnd();
+
+    (unsafe { winuser::IsZoomed(hwnd) } != 0) || pal_hwnd.wnd.maximize_on_show.get()
+}
+
 pub fn get_wnd_dpi_scale(_: Wm, pal_hwnd: &HWnd) -> f32 {
     let hwnd = pal_hwnd.expect_hwnd();
 
@@ -513,30 +717,37 @@ fn update_wnd_frame(pal_hwnd: &HWnd) {
 
 fn adjust_dwm_frame(pal_hwnd: &HWnd) {
     let hwnd = pal_hwnd.expect_hwnd();
+    let flags = pal_hwnd.wnd.flags.get();
+
+    // The DWM frame would show through the transparent regions of a
+    // `TRANSPARENT` window, so don't extend it into the client area. The
+    // non-client rendering (including the shadow, which doesn't follow the
+    // shape of the contents) is disabled altogether.
+    let transparent = flags.contains(iface::WndFlags::TRANSPARENT);
+
+    // The margins must be at least 1 pixel for the shadow to appear
+    let margin = (flags.contains(iface::WndFlags::FULL_SIZE_CONTENT) && !transparent) as i32;
+    let margins = uxtheme::MARGINS {
+        cxLeftWidth: margin,
+        cxRightWidth: margin,
+        cyBottomHeight: margin,
+        cyTopHeight: margin,
+    };
 
-    let margins = if pal_hwnd
-        .wnd
-        .flags
-        .get()
-        .contains(iface::WndFlags::FULL_SIZE_CONTENT)
-    {
-        // The margins must be at least 1 pixel for the shadow to appear
-        uxtheme::MARGINS {
-            cxLeftWidth: 1,
-            cxRightWidth: 1,
-            cyBottomHeight: 1,
-            cyTopHeight: 1,
-        }
+    let policy: dwmapi::DWMNCRENDERINGPOLICY = if transparent {
+        dwmapi::DWMNCRP_DISABLED
     } else {
-        uxtheme::MARGINS {
-            cxLeftWidth: 0,
-            cxRightWidth: 0,
-            cyBottomHeight: 0,
-            cyTopHeight: 0,
-        }
+        dwmapi::DWMNCRP_USEWINDOWSTYLE
     };
+
     unsafe {
         dwmapi::DwmExtendFrameIntoClientArea(hwnd, &margins);
+        dwmapi::DwmSetWindowAttribute(
+            hwnd,
+            dwmapi::DWMWA_NCRENDERING_POLICY,
+            &policy as *const _ as _,
+            size_of::<dwmapi::DWMNCRENDERINGPOLICY>() as _,
+        );
     }
 }
 
@@ -557,6 +768,14 @@ impl iface::KeyEvent<AccelTable> for KeyEvent {
     fn translate_accel(&self, accel_table: &AccelTable) -> Option<iface::ActionId> {
         accel_table.find_action_with_key(self.key, self.mod_flags)
     }
+
+    fn key(&self) -> iface::Key {
+        acceltable::key_from_vk(self.key)
+    }
+
+    fn modifiers(&self) -> iface::ModifierState {
+        acceltable::modifiers_from_mod_flags(self.mod_flags)
+    }
 }
 
 extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
@@ -601,6 +820,10 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
             // `DwmExtendFrameIntoClientArea` should be called every time
             // `WM_ACTIVATE` is sent
             adjust_dwm_frame(&pal_hwnd);
+
+            if pal_hwnd.wnd.pointer_grab.get() {
+                update_pointer_clip(&pal_hwnd);
+            }
         } // WM_ACTIVATE
 
         winuser::WM_CLOSE => {
@@ -643,10 +866,22 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
 
             pal_hwnd.wnd.comp_wnd.handle_dpi_change(hwnd);
 
+            // The physical positions of text input contexts have changed
+            pal_hwnd.wnd.text_input_wnd.on_move(wm);
+
             let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
             listener.dpi_scale_changed(wm, &pal_hwnd);
         } // WM_DPICHANGED
 
+        winuser::WM_SETTINGCHANGE => {
+            if appearance::is_appearance_setting_change(wparam as _, lparam as _) {
+                trace!("Received WM_SETTINGCHANGE for the system appearance");
+
+                let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
+                listener.system_appearance_changed(wm, &pal_hwnd);
+            }
+        } // WM_SETTINGCHANGE
+
         winuser::WM_GETDPISCALEDSIZE => {
             let new_dpi = wparam as u32;
             let size_result = unsafe { &mut *(lparam as *mut SIZE) };
@@ -731,10 +966,24 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
             return 0;
         } // WM_UNICHAR
 
-        winuser::WM_KEYDOWN => {
+        // Key strokes involving the Alt key (and F10) are delivered as
+        // `WM_SYS*` messages. They are handled in the same way as the normal
+        // ones so that they can be bound to actions. If they are left
+        // unhandled, they are passed to `DefWindowProc`, which provides the
+        // system key bindings such as Alt+F4.
+        winuser::WM_KEYDOWN | winuser::WM_SYSKEYDOWN => {
             let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
 
-            log::trace!("WM_KEYDOWN(0x{:x}, 0x{:x})", wparam, lparam);
+            log::trace!(
+                "{}(0x{:x}, 0x{:x})",
+                if msg == winuser::WM_KEYDOWN {
+                    "WM_KEYDOWN"
+                } else {
+                    "WM_SYSKEYDOWN"
+                },
+                wparam,
+                lparam
+            );
 
             // Check the state of the modifier keys
             let mod_flags = AccelTable::query_mod_flags();
@@ -786,12 +1035,21 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
             if handled {
                 return 0;
             }
-        } // WM_KEYDOWN
+        } // WM_KEYDOWN | WM_SYSKEYDOWN
 
-        winuser::WM_KEYUP => {
+        winuser::WM_KEYUP | winuser::WM_SYSKEYUP => {
             let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
 
-            log::trace!("WM_KEYUP(0x{:x}, 0x{:x})", wparam, lparam);
+            log::trace!(
+                "{}(0x{:x}, 0x{:x})",
+                if msg == winuser::WM_KEYUP {
+                    "WM_KEYUP"
+                } else {
+                    "WM_SYSKEYUP"
+                },
+                wparam,
+                lparam
+            );
 
             // Check the state of the modifier keys
             let mod_flags = AccelTable::query_mod_flags();
@@ -812,7 +1070,7 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
             if handled {
                 return 0;
             }
-        } // WM_KEYUP
+        } // WM_KEYUP | WM_SYSKEYUP
 
         winuser::WM_SETCURSOR => {
             if lparam & 0xffff == winuser::HTCLIENT {
@@ -859,7 +1117,33 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
             listener.mouse_leave(wm, &pal_hwnd);
         } // WM_MOUSELEAVE
 
-        // TODO: Use the pointer API (https://docs.microsoft.com/en-us/previous-versions/windows/desktop/inputmsg/messages-and-notifications)
+        winuser::WM_INPUT => {
+            if pal_hwnd.wnd.pointer_grab.get() {
+                handle_raw_input(wm, &pal_hwnd, lparam as winuser::HRAWINPUT);
+            }
+            // `DefWindowProcW` must be called to perform cleanup
+        } // WM_INPUT
+
+        winuser::WM_POINTERDOWN | winuser::WM_POINTERUPDATE | winuser::WM_POINTERUP => {
+            // `GET_POINTERID_WPARAM(wparam)`
+            let pointer_id = LOWORD(wparam as _) as u32;
+            let loc = lparam_to_mouse_loc(hwnd, lparam, true);
+
+            // If unhandled, `DefWindowProcW` emulates mouse input
+            if handle_pointer(wm, &pal_hwnd, msg, pointer_id, loc) {
+                return 0;
+            }
+        } // WM_POINTERDOWN | ...
+
+        winuser::WM_GESTURE => {
+            let hgi = lparam as winapiext::HGESTUREINFO;
+            if handle_gesture(wm, &pal_hwnd, hgi) {
+                unsafe { winapiext::CloseGestureInfoHandle(hgi) };
+                return 0;
+            }
+        } // WM_GESTURE
+
+        // TODO: Use the pointer API for mouse input as well (https://docs.microsoft.com/en-us/previous-versions/windows/desktop/inputmsg/messages-and-notifications)
         winuser::WM_LBUTTONDOWN
         | winuser::WM_RBUTTONDOWN
         | winuser::WM_MBUTTONDOWN
@@ -911,7 +1195,9 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
             let drag_listener = Rc::clone(&drag_state.listener);
 
             drop(drag_state_cell);
-            drag_listener.mouse_down(wm, &pal_hwnd, loc, button);
+
+            let click_count = count_click(&pal_hwnd, loc, button);
+            drag_listener.mouse_down(wm, &pal_hwnd, loc, button, click_count);
 
             return 0;
         } // WM_LBUTTONDOWN | ...
@@ -1118,12 +1404,26 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
 
             let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
             listener.resize(wm, &pal_hwnd);
+
+            if pal_hwnd.wnd.pointer_grab.get() {
+                update_pointer_clip(&pal_hwnd);
+            }
         } // WM_SIZE
 
         winuser::WM_MOVE => {
             pal_hwnd.wnd.text_input_wnd.on_move(wm);
+
+            if pal_hwnd.wnd.pointer_grab.get() {
+                update_pointer_clip(&pal_hwnd);
+            }
         } // WM_MOVE
 
+        _ if msg == taskbar::wm_taskbar_button_created(wm) => {
+            // The progress state couldn't be applied before the taskbar
+            // button was created
+            taskbar::set_progress(wm, hwnd, pal_hwnd.wnd.progress.get());
+        } // WM_TASKBAR_BUTTON_CREATED
+
         _ => {}
     }
 
@@ -1131,6 +1431,272 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
     unsafe { winuser::DefWindowProcW(hwnd, msg, wparam, lparam) }
 }
 
+/// Handle `WM_POINTER{DOWN, UPDATE, UP}` for touch and pen input. Returns
+/// `true` if the message was handled by the listener.
+fn handle_pointer(
+    wm: Wm,
+    pal_hwnd: &HWnd,
+    msg: UINT,
+    pointer_id: u32,
+    loc: cgmath::Point2<f32>,
+) -> bool {
+    let mut ty = 0;
+    if unsafe { winuser::GetPointerType(pointer_id, &mut ty) } == 0 {
+        return false;
+    }
+
+    // Don't report the remaining events of a pointer if its first event
+    // wasn't handled
+    {
+        let mut unhandled_pointers = pal_hwnd.wnd.unhandled_pointers.borrow_mut();
+        if let Some(i) = unhandled_pointers.iter().position(|&id| id == pointer_id) {
+            if msg == winuser::WM_POINTERUPDATE {
+                return false;
+            }
+            unhandled_pointers.swap_remove(i);
+            if msg == winuser::WM_POINTERUP {
+                return false;
+            }
+        }
+    }
+
+    let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
+
+    let handled = match ty {
+        winuser::PT_TOUCH => {
+            let mut info: winuser::POINTER_TOUCH_INFO = unsafe { zeroed() };
+            if unsafe { winuser::GetPointerTouchInfo(pointer_id, &mut info) } == 0 {
+                return false;
+            }
+
+            let flags = info.pointerInfo.pointerFlags;
+            let touch = iface::TouchPoint {
+                id: pointer_id,
+                loc,
+                pressure: if (info.touchMask & winuser::TOUCH_MASK_PRESSURE) != 0 {
+                    Some(info.pressure as f32 / 1024.0)
+                } else {
+                    None
+                },
+                primary: (flags & winuser::POINTER_FLAG_PRIMARY) != 0,
+                cancelled: (flags & winuser::POINTER_FLAG_CANCELED) != 0,
+            };
+
+            match msg {
+                winuser::WM_POINTERDOWN => listener.touch_begin(wm, pal_hwnd, &touch),
+                winuser::WM_POINTERUPDATE => listener.touch_move(wm, pal_hwnd, &touch),
+                _ => listener.touch_end(wm, pal_hwnd, &touch),
+            }
+        }
+        winuser::PT_PEN => {
+            let mut info: winuser::POINTER_PEN_INFO = unsafe { zeroed() };
+            if unsafe { winuser::GetPointerPenInfo(pointer_id, &mut info) } == 0 {
+                return false;
+            }
+
+            // Hovering pens aren't reported
+            let flags = info.pointerInfo.pointerFlags;
+            if msg == winuser::WM_POINTERUPDATE && (flags & winuser::POINTER_FLAG_INCONTACT) == 0 {
+                return false;
+            }
+
+            let mask = info.penMask;
+            let pen = iface::PenState {
+                loc,
+                pressure: if (mask & winuser::PEN_MASK_PRESSURE) != 0 {
+                    info.pressure as f32 / 1024.0
+                } else {
+                    1.0
+                },
+                tilt: [
+                    if (mask & winuser::PEN_MASK_TILT_X) != 0 {
+                        info.tiltX as f32
+                    } else {
+                        0.0
+                    },
+                    if (mask & winuser::PEN_MASK_TILT_Y) != 0 {
+                        info.tiltY as f32
+                    } else {
+                        0.0
+                    },
+                ]
+                .into(),
+                eraser: (info.penFlags & (winuser::PEN_FLAG_ERASER | winuser::PEN_FLAG_INVERTED))
+                    != 0,
+                barrel_button: (info.penFlags & winuser::PEN_FLAG_BARREL) != 0,
+            };
+
+            match msg {
+                winuser::WM_POINTERDOWN => listener.pen_down(wm, pal_hwnd, &pen),
+                winuser::WM_POINTERUPDATE => listener.pen_move(wm, pal_hwnd, &pen),
+                _ => listener.pen_up(wm, pal_hwnd, &pen),
+            }
+        }
+        _ => return false,
+    };
+
+    if !handled && msg == winuser::WM_POINTERDOWN {
+        pal_hwnd
+            .wnd
+            .unhandled_pointers
+            .borrow_mut()
+            .push(pointer_id);
+    }
+
+    handled
+}
+
+/// Handle `WM_INPUT` and call `mouse_relative_motion` if it represents a
+/// relative mouse movement.
+fn handle_raw_input(wm: Wm, pal_hwnd: &HWnd, hri: winuser::HRAWINPUT) {
+    let mut input = MaybeUninit::<winuser::RAWINPUT>::uninit();
+    let mut size = size_of::<winuser::RAWINPUT>() as UINT;
+
+    let result = unsafe {
+        winuser::GetRawInputData(
+            hri,
+            winuser::RID_INPUT,
+            input.as_mut_ptr() as _,
+            &mut size,
+            size_of::<winuser::RAWINPUTHEADER>() as _,
+        )
+    };
+    if result == UINT::max_value() || (result as usize) < size_of::<winuser::RAWINPUTHEADER>() {
+        return;
+    }
+
+    let input = unsafe { input.assume_init() };
+    if input.header.dwType != winuser::RIM_TYPEMOUSE {
+        return;
+    }
+
+    let mouse = unsafe { input.data.mouse() };
+    if (mouse.usFlags & winuser::MOUSE_MOVE_ABSOLUTE) != 0 {
+        // Absolute positions are reported by some devices such as pen
+        // tablets and remote desktop sessions. `WM_MOUSEMOVE` should suffice
+        // for them.
+        return;
+    }
+    if mouse.lLastX == 0 && mouse.lLastY == 0 {
+        return;
+    }
+
+    let dpi = unsafe { winuser::GetDpiForWindow(pal_hwnd.expect_hwnd()) } as u32;
+    let delta = cgmath::Vector2::new(
+        phy_to_log_f32(mouse.lLastX as f32, dpi),
+        phy_to_log_f32(mouse.lLastY as f32, dpi),
+    );
+
+    let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
+    listener.mouse_relative_motion(wm, pal_hwnd, delta);
+}
+
+/// Handle `WM_GESTURE`. Returns `true` if the message was handled.
+fn handle_gesture(wm: Wm, pal_hwnd: &HWnd, hgi: winapiext::HGESTUREINFO) -> bool {
+    let mut gi: winapiext::GESTUREINFO = unsafe { zeroed() };
+    gi.cbSize = size_of::<winapiext::GESTUREINFO>() as _;
+    if unsafe { winapiext::GetGestureInfo(hgi, &mut gi) } == 0 {
+        return false;
+    }
+
+    let hwnd = pal_hwnd.wnd.hwnd.get();
+    let is_begin = (gi.dwFlags & winapiext::GF_BEGIN) != 0;
+
+    match gi.dwID {
+        winapiext::GID_ZOOM | winapiext::GID_ROTATE => {}
+        winapiext::GID_END => {
+            let gesture_state = pal_hwnd.wnd.gesture_state.borrow_mut().take();
+            if let Some(gesture_state) = gesture_state {
+                gesture_state.listener.end(wm, pal_hwnd);
+                return true;
+            }
+            return false;
+        }
+        _ => return false,
+    }
+
+    if pal_hwnd.wnd.gesture_state.borrow().is_none() {
+        let loc = phy_to_mouse_loc(
+            hwnd,
+            POINT {
+                x: gi.ptsLocation.x as LONG,
+                y: gi.ptsLocation.y as LONG,
+            },
+            true,
+        );
+
+        let listener = Rc::clone(&pal_hwnd.wnd.listener.borrow());
+        let gesture_listener = listener.gesture(wm, pal_hwnd, loc).into();
+
+        *pal_hwnd.wnd.gesture_state.borrow_mut() = Some(GestureState {
+            listener: gesture_listener,
+            last_distance: 0.0,
+            last_angle: 0.0,
+        });
+    }
+
+    let mut gesture_state_cell = pal_hwnd.wnd.gesture_state.borrow_mut();
+    let gesture_state = gesture_state_cell.as_mut().unwrap();
+    let gesture_listener = Rc::clone(&gesture_state.listener);
+
+    if gi.dwID == winapiext::GID_ZOOM {
+        // `ullArguments` is the distance between the two contacts
+        let distance = gi.ullArguments as f64;
+        let last_distance = std::mem::replace(&mut gesture_state.last_distance, distance);
+        drop(gesture_state_cell);
+
+        if !is_begin && last_distance > 0.0 && distance > 0.0 {
+            gesture_listener.magnify(wm, pal_hwnd, (distance / last_distance) as f32);
+        }
+    } else {
+        // `GID_ROTATE_ANGLE_FROM_ARGUMENT(ullArguments)` is the rotation
+        // angle (counterclockwise) since the gesture has started. The value
+        // is meaningless in the first event.
+        let angle = if is_begin {
+            0.0
+        } else {
+            use std::f64::consts::PI;
+            (gi.ullArguments as f64 / 65535.0) * 4.0 * PI - 2.0 * PI
+        };
+        let last_angle = std::mem::replace(&mut gesture_state.last_angle, angle);
+        drop(gesture_state_cell);
+
+        if !is_begin {
+            gesture_listener.rotate(wm, pal_hwnd, (last_angle - angle) as f32);
+        }
+    }
+
+    true
+}
+
+/// Register a mouse button press to `Wnd::click_counter` using the system
+/// double-click settings and return its click count.
+fn count_click(pal_hwnd: &HWnd, loc: cgmath::Point2<f32>, button: u8) -> u32 {
+    let hwnd = pal_hwnd.wnd.hwnd.get();
+
+    let dpi = unsafe { winuser::GetDpiForWindow(hwnd) } as u32;
+    assert_win32_ok(dpi);
+
+    let max_interval = Duration::from_millis(unsafe { winuser::GetDoubleClickTime() } as u64);
+
+    // `SM_C[XY]DOUBLECLK` specify the size of a rectangle centered around
+    // the first click
+    let half_size = |metric| {
+        let size = unsafe { winuser::GetSystemMetrics(metric) } as f32;
+        phy_to_log_f32(size * 0.5, dpi)
+    };
+    let max_distance = [
+        half_size(winuser::SM_CXDOUBLECLK),
+        half_size(winuser::SM_CYDOUBLECLK),
+    ];
+
+    pal_hwnd
+        .wnd
+        .click_counter
+        .borrow_mut()
+        .click_with_thresholds(Instant::now(), loc, button, max_interval, max_distance)
+}
+
 /// Extract x- and y- coordinates from `LPARAM`. This is used by most types of
 /// mouse input events.
 ///
@@ -1138,11 +1704,19 @@ extern "system" fn wnd_proc(hwnd: HWND, msg: UINT, wparam: WPARAM, lparam: LPARA
 /// coordinates, which will be converted to client coordinates by this function.
 fn lparam_to_mouse_loc(hwnd: HWND, lparam: LPARAM, is_screen: bool) -> cgmath::Point2<f32> {
     let lparam = lparam as DWORD;
-    let mut loc_phy = POINT {
+    let loc_phy = POINT {
         x: LOWORD(lparam) as i16 as LONG, // `GET_X_LPARAM(lparam) as LONG`
         y: HIWORD(lparam) as i16 as LONG, // `GET_Y_LPARAM(lparam) as LONG`
     };
 
+    phy_to_mouse_loc(hwnd, loc_phy, is_screen)
+}
+
+/// Convert a point in physical pixels to logical pixels in the client
+/// coordinate space.
+///
+/// If `is_screen` is `true`, `loc_phy` is interpreted as screen coordinates.
+fn phy_to_mouse_loc(hwnd: HWND, mut loc_phy: POINT, is_screen: bool) -> cgmath::Point2<f32> {
     if is_screen {
         assert_win32_ok(unsafe { winuser::ScreenToClient(hwnd, &mut loc_phy) });
     }
@@ -1219,6 +1793,19 @@ pub(super) fn log_client_box2_to_phy_screen_rect(hwnd: HWND, p: cggeom::Box2<f32
     }
 }
 
+/// Convert logical client coordinates to physical client coordinates.
+pub(super) fn log_client_box2_to_phy_client_rect(hwnd: HWND, p: cggeom::Box2<f32>) -> RECT {
+    let dpi = unsafe { winuser::GetDpiForWindow(hwnd) } as u32;
+    assert_win32_ok(dpi);
+
+    RECT {
+        left: log_to_phy_f32(p.min.x, dpi).round() as LONG,
+        top: log_to_phy_f32(p.min.y, dpi).round() as LONG,
+        right: log_to_phy_f32(p.max.x, dpi).round() as LONG,
+        bottom: log_to_phy_f32(p.max.y, dpi).round() as LONG,
+    }
+}
+
 fn phy_to_log(x: u32, dpi: u32) -> u32 {
     // Must be rounded up so that the drawn region (which is sized according to
     // the logical size because the user only knows the logical size) completely
@@ -0,0 +1,171 @@
+use cggeom::box2;
+use tcw3_pal::{
+    offscreen::{render_layer_tree, Bitmap, Layer, LayerAttrs},
+    LayerShadow,
+};
+
+fn pixel_at(bmp: &Bitmap, [x, y]: [usize; 2]) -> u32 {
+    let i = y * bmp.stride() + x * 4;
+    let data = bmp.data();
+    u32::from_ne_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+}
+
+#[test]
+fn render_bg_color() {
+    let layer = Layer::new(LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [40.0, 30.0] }),
+        sublayers: Some(vec![Layer::new(LayerAttrs {
+            bounds: Some(box2! { min: [10.0, 10.0], max: [20.0, 20.0] }),
+            bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+            ..Default::default()
+        })]),
+        ..Default::default()
+    });
+
+    let bmp = render_layer_tree(&layer, [40, 30], 2.0);
+    assert_eq!(bmp.size(), [80, 60]);
+
+    // Inside the sublayer
+    assert_eq!(pixel_at(&bmp, [30, 30]), 0xffff0000);
+
+    // Outside the sublayer
+    assert_eq!(pixel_at(&bmp, [10, 10]), 0);
+    assert_eq!(pixel_at(&bmp, [50, 50]), 0);
+}
+
+#[test]
+fn render_contents() {
+    let contents = Bitmap::from_argb_data(
+        [0x80004000u32; 4]
+            .iter()
+            .flat_map(|p| p.to_ne_bytes().to_vec())
+            .collect(),
+        [2, 2],
+        8,
+    );
+
+    let layer = Layer::new(LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [4.0, 4.0] }),
+        contents: Some(Some(contents.into())),
+        ..Default::default()
+    });
+
+    let bmp = render_layer_tree(&layer, [4, 4], 1.0);
+    assert_eq!(pixel_at(&bmp, [2, 2]), 0x80004000);
+}
+
+#[test]
+fn render_rounded_corners() {
+    let layer = Layer::new(LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [20.0, 20.0] }),
+        corner_radius: Some([8.0, 0.0, 8.0, 0.0]),
+        sublayers: Some(vec![Layer::new(LayerAttrs {
+            bounds: Some(box2! { min: [-10.0, -10.0], max: [30.0, 30.0] }),
+            bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+            ..Default::default()
+        })]),
+        ..Default::default()
+    });
+
+    let bmp = render_layer_tree(&layer, [20, 20], 1.0);
+
+    // The sublayer is clipped by the rounded corners
+    assert_eq!(pixel_at(&bmp, [0, 0]), 0);
+    assert_eq!(pixel_at(&bmp, [19, 19]), 0);
+
+    // The rest of the layer is not affected
+    assert_ne!(pixel_at(&bmp, [19, 0]), 0);
+    assert_ne!(pixel_at(&bmp, [0, 19]), 0);
+    assert_ne!(pixel_at(&bmp, [10, 10]), 0);
+}
+
+#[test]
+fn render_mask() {
+    let mask = Bitmap::from_argb_data(
+        [0xff000000u32, 0]
+            .iter()
+            .flat_map(|p| p.to_ne_bytes().to_vec())
+            .collect(),
+        [2, 1],
+        8,
+    );
+
+    let layer = Layer::new(LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [20.0, 10.0] }),
+        bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+        mask: Some(Some(mask)),
+        ..Default::default()
+    });
+
+    let bmp = render_layer_tree(&layer, [20, 10], 1.0);
+
+    // The left half is opaque, and the right half is transparent
+    assert_ne!(pixel_at(&bmp, [2, 5]), 0);
+    assert_eq!(pixel_at(&bmp, [17, 5]), 0);
+}
+
+#[test]
+fn render_shadow() {
+    let layer = Layer::new(LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [60.0, 60.0] }),
+        sublayers: Some(vec![Layer::new(LayerAttrs {
+            bounds: Some(box2! { min: [20.0, 20.0], max: [40.0, 40.0] }),
+            bg_color: Some([1.0, 1.0, 1.0, 1.0].into()),
+            shadow: Some(Some(LayerShadow {
+                offset: [0.0, 4.0].into(),
+                radius: 8.0,
+                color: [0.0, 0.0, 0.0, 1.0].into(),
+            })),
+            ..Default::default()
+        })]),
+        ..Default::default()
+    });
+
+    let bmp = render_layer_tree(&layer, [60, 60], 1.0);
+    let alpha_at = |p| pixel_at(&bmp, p) >> 24;
+
+    // The layer is drawn over the shadow
+    assert_eq!(pixel_at(&bmp, [30, 30]), 0xffffffff);
+
+    // The shadow spreads outside the layer, more so in the offset direction
+    assert_ne!(alpha_at([30, 43]), 0);
+    assert_ne!(alpha_at([30, 17]), 0);
+    assert!(alpha_at([30, 43]) > alpha_at([30, 17]));
+    assert_eq!(pixel_at(&bmp, [30, 43]) & 0xffffff, 0);
+
+    // Far from the layer
+    assert_eq!(pixel_at(&bmp, [5, 5]), 0);
+}
+
+#[test]
+fn render_backdrop_blur() {
+    let layer = Layer::new(LayerAttrs {
+        bounds: Some(box2! { min: [0.0, 0.0], max: [60.0, 20.0] }),
+        sublayers: Some(vec![
+            Layer::new(LayerAttrs {
+                bounds: Some(box2! { min: [0.0, 0.0], max: [30.0, 20.0] }),
+                bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+                ..Default::default()
+            }),
+            Layer::new(LayerAttrs {
+                bounds: Some(box2! { min: [10.0, 0.0], max: [60.0, 20.0] }),
+                backdrop_blur_radius: Some(8.0),
+                ..Default::default()
+            }),
+        ]),
+        ..Default::default()
+    });
+
+    let bmp = render_layer_tree(&layer, [60, 20], 1.0);
+
+    // Outside the blurring layer
+    assert_eq!(pixel_at(&bmp, [5, 10]), 0xffff0000);
+
+    // The edge of the red layer is blurred
+    let px = pixel_at(&bmp, [33, 10]);
+    assert!(px >> 24 > 0 && px >> 24 < 0xff, "{:08x}", px);
+
+    // Out of the blur kernel's reach
+    assert_eq!(pixel_at(&bmp, [15, 10]), 0xffff0000);
+    assert_eq!(pixel_at(&bmp, [50, 10]), 0);
+}
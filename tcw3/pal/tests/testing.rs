@@ -2,8 +2,10 @@ use cggeom::{box2, prelude::*, Box2};
 use cgmath::{Deg, Matrix3, Point2, Vector2};
 use log::info;
 use std::{
-    cell::Cell,
+    cell::{Cell, RefCell},
     ops::Range,
+    panic,
+    path::PathBuf,
     rc::Rc,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -165,6 +167,260 @@ fn invoke_on_main_thread() {
     });
 }
 
+#[test]
+fn spawn_local() {
+    init_logger();
+    testing::run_test(|twm| {
+        let (send, recv) = futures::channel::oneshot::channel();
+        let flag = Rc::new(Cell::new(false));
+
+        {
+            let flag = Rc::clone(&flag);
+            twm.wm().spawn_local(async move {
+                let () = recv.await.unwrap();
+                flag.set(true);
+            });
+        }
+
+        // The task is not polled by `spawn_local` itself
+        assert!(!flag.get());
+
+        // Wake up the task from another thread
+        spawn(move || {
+            send.send(()).unwrap();
+        });
+
+        // Wait until the task completes
+        while !flag.get() {
+            twm.step();
+        }
+    });
+}
+
+#[test]
+fn spawn_from_other_thread() {
+    init_logger();
+    testing::run_test(|twm| {
+        let flag = Arc::new(MtLock::<_, Wm>::new(Cell::new(false)));
+
+        {
+            let flag = Arc::clone(&flag);
+            spawn(move || {
+                Wm::spawn(async move {
+                    let wm = Wm::global();
+                    flag.get_with_wm(wm).set(true);
+                });
+            });
+        }
+
+        // Wait until the task completes
+        while !flag.get_with_wm(twm.wm()).get() {
+            twm.step();
+        }
+    });
+}
+
+#[test]
+fn channel_batch() {
+    init_logger();
+    testing::run_test(|twm| {
+        let (send, recv) = Wm::channel::<u32>();
+        let received = Rc::new(RefCell::new(Vec::new()));
+
+        {
+            let received = Rc::clone(&received);
+            recv.set_handler(twm.wm(), move |_, batch| {
+                received.borrow_mut().push(batch.collect::<Vec<_>>());
+            });
+        }
+
+        spawn(move || {
+            for i in 0..3 {
+                send.send(i).unwrap();
+            }
+        })
+        .join()
+        .unwrap();
+
+        while received.borrow().is_empty() {
+            twm.step();
+        }
+
+        // All messages should be delivered in a single batch
+        assert_eq!(*received.borrow(), vec![vec![0, 1, 2]]);
+        assert!(recv.is_disconnected());
+    });
+}
+
+#[test]
+fn channel_coalesce() {
+    init_logger();
+    testing::run_test(|twm| {
+        let (send, recv) = Wm::channel::<u32>();
+        let received = Rc::new(RefCell::new(Vec::new()));
+
+        for i in 0..10 {
+            send.send_coalesced(i, |last, new| {
+                *last = new;
+                None
+            })
+            .unwrap();
+        }
+
+        {
+            let received = Rc::clone(&received);
+            recv.set_handler(twm.wm(), move |_, batch| {
+                received.borrow_mut().extend(batch);
+            });
+        }
+
+        while received.borrow().is_empty() {
+            twm.step();
+        }
+
+        assert_eq!(*received.borrow(), vec![9]);
+    });
+}
+
+#[test]
+fn channel_backpressure() {
+    init_logger();
+    testing::run_test(|twm| {
+        let (send, recv) = Wm::channel_with_capacity::<u32>(2);
+
+        assert_eq!(send.try_send(0), Ok(()));
+        assert_eq!(send.try_send(1), Ok(()));
+        assert_eq!(send.try_send(2), Err(pal::channel::TrySendError::Full(2)));
+
+        let received = Rc::new(RefCell::new(Vec::new()));
+        {
+            let received = Rc::clone(&received);
+            recv.set_handler(twm.wm(), move |_, batch| {
+                received.borrow_mut().extend(batch);
+            });
+        }
+
+        while received.borrow().is_empty() {
+            twm.step();
+        }
+        assert_eq!(*received.borrow(), vec![0, 1]);
+
+        // The queue has been emptied
+        assert_eq!(send.try_send(2), Ok(()));
+
+        drop(recv);
+        assert_eq!(
+            send.try_send(3),
+            Err(pal::channel::TrySendError::Disconnected(3))
+        );
+        assert_eq!(send.send(4), Err(pal::channel::SendError(4)));
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn fswatch() {
+    use pal::fswatch::{EventKind, Watcher};
+
+    init_logger();
+    testing::run_test(|twm| {
+        let dir = std::env::temp_dir().join(format!("tcw3_pal-fswatch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let watcher = {
+            let events = Rc::clone(&events);
+            Watcher::new(twm.wm(), &dir, false, move |_, new_events| {
+                events.borrow_mut().extend_from_slice(new_events);
+            })
+            .unwrap()
+        };
+
+        let file_path = dir.join("hello.txt");
+        std::fs::write(&file_path, "hello").unwrap();
+
+        // Wait until the creation is reported
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !events
+            .borrow()
+            .iter()
+            .any(|e| e.kind == EventKind::Create && e.path == file_path)
+        {
+            assert!(
+                Instant::now() < deadline,
+                "timed out; events = {:?}",
+                events
+            );
+            twm.step_until(Instant::now() + Duration::from_millis(50));
+        }
+
+        // No events are delivered after dropping `Watcher`
+        drop(watcher);
+        events.borrow_mut().clear();
+        std::fs::remove_file(&file_path).unwrap();
+        twm.step_until(Instant::now() + Duration::from_millis(100));
+        assert!(events.borrow().is_empty());
+
+        std::fs::remove_dir(&dir).unwrap();
+    });
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn fswatch_atomic_replace() {
+    use pal::fswatch::Watcher;
+
+    init_logger();
+    testing::run_test(|twm| {
+        let dir = std::env::temp_dir().join(format!(
+            "tcw3_pal-fswatch_atomic_replace-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let file_path = dir.join("settings.json");
+        let temp_path = dir.join("settings.json.tmp");
+        std::fs::write(&file_path, "1").unwrap();
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let watcher = {
+            let events = Rc::clone(&events);
+            Watcher::new(twm.wm(), &file_path, false, move |_, new_events| {
+                events.borrow_mut().extend_from_slice(new_events);
+            })
+            .unwrap()
+        };
+
+        // Replace the file twice. The watch must survive the first replacement.
+        for &contents in &["2", "3"] {
+            events.borrow_mut().clear();
+            std::fs::write(&temp_path, contents).unwrap();
+            std::fs::rename(&temp_path, &file_path).unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !events.borrow().iter().any(|e| e.path == file_path) {
+                assert!(
+                    Instant::now() < deadline,
+                    "timed out; events = {:?}",
+                    events
+                );
+                twm.step_until(Instant::now() + Duration::from_millis(50));
+            }
+
+            // Events for other files in the directory are not reported
+            assert!(
+                events.borrow().iter().all(|e| e.path == file_path),
+                "events = {:?}",
+                events
+            );
+        }
+
+        drop(watcher);
+        std::fs::remove_file(&file_path).unwrap();
+        std::fs::remove_dir(&dir).unwrap();
+    });
+}
+
 #[test]
 fn invoke_after() {
     init_logger();
@@ -192,6 +448,47 @@ fn invoke_after() {
     });
 }
 
+#[test]
+fn advance_time() {
+    init_logger();
+    testing::run_test(|twm| {
+        let d_100_ms = Duration::from_millis(100);
+        let d_500_ms = Duration::from_millis(500);
+        let d_600_ms = Duration::from_millis(600);
+        let d_1200_ms = Duration::from_millis(1200);
+
+        // Activate the virtual clock
+        twm.advance_time(Duration::from_secs(0));
+        let start = twm.now();
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        {
+            let log = Rc::clone(&log);
+            twm.wm().invoke_after(d_600_ms..d_1200_ms, move |wm| {
+                log.borrow_mut().push("a");
+
+                // `invoke` and `invoke_after` called from here should also
+                // be processed by the same `advance_time` call if possible
+                let log2 = Rc::clone(&log);
+                wm.invoke(move |_| log2.borrow_mut().push("b"));
+                wm.invoke_after(d_100_ms..d_100_ms, move |_| log.borrow_mut().push("c"));
+            });
+        }
+
+        twm.advance_time(d_500_ms);
+        assert_eq!(twm.now() - start, d_500_ms);
+        assert!(log.borrow().is_empty());
+
+        // The virtual clock doesn't advance by itself
+        twm.step_until(Instant::now() + d_100_ms);
+        assert!(log.borrow().is_empty());
+
+        twm.advance_time(d_1200_ms);
+        assert_eq!(twm.now() - start, d_500_ms + d_1200_ms);
+        assert_eq!(*log.borrow(), vec!["a", "b", "c"]);
+    });
+}
+
 #[test]
 fn invoke_after_cancel() {
     init_logger();
@@ -397,6 +694,58 @@ fn empty_wnd() {
     });
 }
 
+#[test]
+fn wnd_transparent() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        // The layer covers the left half of the window with a translucent
+        // color
+        let hlayer = wm.new_layer(pal::LayerAttrs {
+            bounds: Some(box2! { min: [0.0, 0.0], max: [50.0, 100.0] }),
+            bg_color: Some([0.2, 0.3, 0.4, 0.5].into()),
+            ..Default::default()
+        });
+
+        let flags = pal::WndFlags::BORDERLESS | pal::WndFlags::TRANSPARENT;
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100, 100]),
+            flags: Some(flags),
+            layer: Some(Some(hlayer.clone())),
+            ..Default::default()
+        });
+        assert_eq!(twm.wnd_attrs(&hwnd).unwrap().flags, flags);
+
+        wm.update_wnd(&hwnd);
+
+        let mut ss = wmapi::WndSnapshot::new();
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+
+        // The alpha values are preserved
+        let alpha_at = |[x, y]: [usize; 2]| ss.data[y * ss.stride + x * 4 + 3];
+        let alpha = alpha_at([25, 50]);
+        assert!(alpha > 96 && alpha < 160, "alpha = {}", alpha);
+        assert_eq!(alpha_at([75, 50]), 0);
+
+        wm.set_wnd_attr(
+            &hwnd,
+            pal::WndAttrs {
+                flags: Some(pal::WndFlags::BORDERLESS),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            twm.wnd_attrs(&hwnd).unwrap().flags,
+            pal::WndFlags::BORDERLESS
+        );
+
+        wm.remove_wnd(&hwnd);
+        wm.remove_layer(&hlayer);
+    });
+}
+
 fn snapshot_find_nontransparent_pixel(
     wmapi::WndSnapshot { size, data, stride }: &wmapi::WndSnapshot,
 ) -> Option<[usize; 2]> {
@@ -463,13 +812,13 @@ fn bitmap_layer() {
         };
 
         let hlayer = wm.new_layer(pal::LayerAttrs {
-            contents: Some(Some(bmp.clone())),
+            contents: Some(Some(bmp.clone().into())),
             ..Default::default()
         });
         wm.remove_layer(&hlayer);
 
         let hlayer = wm.new_layer(pal::LayerAttrs {
-            contents: Some(Some(bmp.clone())),
+            contents: Some(Some(bmp.clone().into())),
             bg_color: Some([0.2, 0.3, 0.4, 0.8].into()),
             flags: Some(pal::LayerFlags::MASK_TO_BOUNDS),
             contents_scale: Some(1.5),
@@ -542,6 +891,64 @@ fn wnd_with_layer() {
     });
 }
 
+#[test]
+fn snapshot_matches() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let dir = std::env::temp_dir().join(format!("tcw3_pal-snapshot-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let options = wmapi::SnapshotMatchOptions::new(&dir).with_tolerance(1);
+
+        let hlayer = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([0.2, 0.3, 0.4, 0.8].into()),
+            bounds: Some(box2! { top_left: [10.0, 10.0], size: [30.0, 30.0] }),
+            ..Default::default()
+        });
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100, 100]),
+            layer: Some(Some(hlayer.clone())),
+            ..Default::default()
+        });
+        wm.update_wnd(&hwnd);
+
+        let assert_matches = |name| {
+            panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                twm.assert_snapshot_matches(&hwnd, name, &options);
+            }))
+            .is_ok()
+        };
+
+        // There's no golden image yet, so this should fail. The actual image
+        // is written to `layer.actual.png`.
+        assert!(!assert_matches("layer"));
+        std::fs::rename(dir.join("layer.actual.png"), dir.join("layer.png")).unwrap();
+
+        assert!(assert_matches("layer"));
+
+        // Change the contents
+        wm.set_layer_attr(
+            &hlayer,
+            pal::LayerAttrs {
+                bg_color: Some([0.8, 0.3, 0.4, 0.8].into()),
+                ..Default::default()
+            },
+        );
+        wm.update_wnd(&hwnd);
+
+        assert!(!assert_matches("layer"));
+        assert!(dir.join("layer.diff.png").exists());
+
+        wm.remove_wnd(&hwnd);
+        wm.remove_layer(&hlayer);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    });
+}
+
 #[test]
 fn defer_layer_changes_until_update_wnd() {
     init_logger();
@@ -567,29 +974,134 @@ fn defer_layer_changes_until_update_wnd() {
         wm.update_wnd(&hwnd);
 
         let mut ss = wmapi::WndSnapshot::new();
-
-        // The layer is off-screen
+
+        // The layer is off-screen
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+        assert_snapshot_empty(&ss);
+
+        // Move the layer to inside the window
+        wm.set_layer_attr(
+            &hlayer,
+            pal::LayerAttrs {
+                bounds: Some(box2! { top_left: [10.0, 10.0], size: [30.0, 30.0] }),
+                ..Default::default()
+            },
+        );
+
+        // The layer is still off-screen since we haven't called `update_wnd` yet
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+        assert_snapshot_empty(&ss);
+
+        wm.update_wnd(&hwnd);
+
+        // Now the layer is visible
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+        assert_snapshot_nonempty(&ss);
+
+        wm.remove_wnd(&hwnd);
+        wm.remove_layer(&hlayer);
+    });
+}
+
+#[test]
+fn defer_update_wnd_until_txn_commit() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let hlayer = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([0.2, 0.3, 0.4, 0.8].into()),
+            // Off-screen
+            bounds: Some(box2! { top_left: [-40.0, -40.0], size: [30.0, 30.0] }),
+            ..Default::default()
+        });
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100, 100]),
+            layer: Some(Some(hlayer.clone())),
+            ..Default::default()
+        });
+
+        wm.update_wnd(&hwnd);
+
+        let mut ss = wmapi::WndSnapshot::new();
+
+        wm.begin_layer_txn();
+        wm.begin_layer_txn();
+
+        // Move the layer to inside the window
+        wm.set_layer_attr(
+            &hlayer,
+            pal::LayerAttrs {
+                bounds: Some(box2! { top_left: [10.0, 10.0], size: [30.0, 30.0] }),
+                ..Default::default()
+            },
+        );
+        wm.update_wnd(&hwnd);
+
+        // `update_wnd` is postponed until the outermost transaction is
+        // committed
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+        assert_snapshot_empty(&ss);
+
+        wm.commit_layer_txn();
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+        assert_snapshot_empty(&ss);
+
+        wm.commit_layer_txn();
+        twm.read_wnd_snapshot(&hwnd, &mut ss);
+        assert_snapshot_nonempty(&ss);
+
+        wm.remove_wnd(&hwnd);
+        wm.remove_layer(&hlayer);
+    });
+}
+
+#[test]
+fn update_wnd_with_damage() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        let hlayer = wm.new_layer(pal::LayerAttrs {
+            bg_color: Some([1.0, 0.0, 0.0, 1.0].into()),
+            bounds: Some(box2! { top_left: [0.0, 0.0], size: [100.0, 100.0] }),
+            ..Default::default()
+        });
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100, 100]),
+            layer: Some(Some(hlayer.clone())),
+            ..Default::default()
+        });
+
+        wm.update_wnd(&hwnd);
+
+        let mut ss = wmapi::WndSnapshot::new();
         twm.read_wnd_snapshot(&hwnd, &mut ss);
-        assert_snapshot_empty(&ss);
+        let red_at = |ss: &wmapi::WndSnapshot, [x, y]: [usize; 2]| ss.data[y * ss.stride + x * 4];
+        assert_eq!(red_at(&ss, [25, 50]), 255);
+        assert_eq!(red_at(&ss, [75, 50]), 255);
+
+        // Allocating the backing store invalidates the whole window. Flush
+        // this so that it doesn't override the damage region
+        wm.update_wnd(&hwnd);
 
-        // Move the layer to inside the window
         wm.set_layer_attr(
             &hlayer,
             pal::LayerAttrs {
-                bounds: Some(box2! { top_left: [10.0, 10.0], size: [30.0, 30.0] }),
+                bg_color: Some([0.0, 0.0, 1.0, 1.0].into()),
                 ..Default::default()
             },
         );
 
-        // The layer is still off-screen since we haven't called `update_wnd` yet
-        twm.read_wnd_snapshot(&hwnd, &mut ss);
-        assert_snapshot_empty(&ss);
-
-        wm.update_wnd(&hwnd);
-
-        // Now the layer is visible
+        // Only the left half is redrawn
+        wm.update_wnd_with_damage(&hwnd, box2! { min: [0.0, 0.0], max: [50.0, 100.0] });
         twm.read_wnd_snapshot(&hwnd, &mut ss);
-        assert_snapshot_nonempty(&ss);
+        assert_eq!(red_at(&ss, [25, 50]), 0);
+        assert_eq!(red_at(&ss, [75, 50]), 255);
 
         wm.remove_wnd(&hwnd);
         wm.remove_layer(&hlayer);
@@ -725,7 +1237,15 @@ fn wnd_mouse_events() {
                 assert_eq!(self.0.get(), 4);
                 self.0.set(5);
             }
-            fn mouse_down(&self, _: pal::Wm, _: &pal::HWnd, _loc: Point2<f32>, _button: u8) {
+            fn mouse_down(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                _loc: Point2<f32>,
+                _button: u8,
+                click_count: u32,
+            ) {
+                assert_eq!(click_count, 1);
                 assert_eq!(self.0.get(), 3);
                 self.0.set(4);
             }
@@ -830,6 +1350,254 @@ fn wnd_mouse_events() {
     });
 }
 
+#[test]
+fn wnd_mouse_click_count() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        struct Listener(Rc<RefCell<Vec<u32>>>);
+
+        impl WndListener<pal::Wm> for Listener {
+            fn mouse_drag(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                _loc: Point2<f32>,
+                _button: u8,
+            ) -> Box<dyn MouseDragListener<pal::Wm>> {
+                Box::new(Listener(Rc::clone(&self.0)))
+            }
+        }
+
+        impl MouseDragListener<pal::Wm> for Listener {
+            fn mouse_down(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                _loc: Point2<f32>,
+                _button: u8,
+                click_count: u32,
+            ) {
+                self.0.borrow_mut().push(click_count);
+            }
+        }
+
+        let click_counts = Rc::new(RefCell::new(Vec::new()));
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100; 2]),
+            listener: Some(Box::new(Listener(Rc::clone(&click_counts)))),
+            ..Default::default()
+        });
+
+        for &click_count in &[1, 2, 3] {
+            let drag = twm.raise_mouse_drag(&hwnd, [20.0; 2].into(), 0);
+            drag.mouse_down_with_click_count([20.0; 2].into(), 0, click_count);
+            drag.mouse_up([20.0; 2].into(), 0);
+        }
+
+        assert_eq!(*click_counts.borrow(), vec![1, 2, 3]);
+    });
+}
+
+#[test]
+fn wnd_touch_and_pen_events() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        struct Listener(Rc<RefCell<Vec<String>>>);
+
+        impl WndListener<pal::Wm> for Listener {
+            fn touch_begin(&self, _: pal::Wm, _: &pal::HWnd, touch: &pal::TouchPoint) -> bool {
+                self.0
+                    .borrow_mut()
+                    .push(format!("touch_begin {}", touch.id));
+                touch.primary
+            }
+            fn touch_move(&self, _: pal::Wm, _: &pal::HWnd, touch: &pal::TouchPoint) -> bool {
+                self.0.borrow_mut().push(format!("touch_move {}", touch.id));
+                true
+            }
+            fn touch_end(&self, _: pal::Wm, _: &pal::HWnd, touch: &pal::TouchPoint) -> bool {
+                self.0.borrow_mut().push(format!("touch_end {}", touch.id));
+                true
+            }
+            fn pen_down(&self, _: pal::Wm, _: &pal::HWnd, pen: &pal::PenState) -> bool {
+                self.0
+                    .borrow_mut()
+                    .push(format!("pen_down {} {}", pen.pressure, pen.eraser));
+                true
+            }
+            fn pen_up(&self, _: pal::Wm, _: &pal::HWnd, _: &pal::PenState) -> bool {
+                self.0.borrow_mut().push("pen_up".to_owned());
+                true
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100; 2]),
+            listener: Some(Box::new(Listener(Rc::clone(&events)))),
+            ..Default::default()
+        });
+
+        let touch = pal::TouchPoint {
+            id: 1,
+            loc: [20.0; 2].into(),
+            pressure: Some(0.5),
+            ..Default::default()
+        };
+        assert!(twm.raise_touch_begin(&hwnd, &touch));
+        assert!(!twm.raise_touch_begin(
+            &hwnd,
+            &pal::TouchPoint {
+                id: 2,
+                primary: false,
+                ..touch
+            }
+        ));
+        assert!(twm.raise_touch_move(&hwnd, &touch));
+        assert!(twm.raise_touch_end(&hwnd, &touch));
+
+        let pen = pal::PenState {
+            loc: [30.0; 2].into(),
+            pressure: 0.25,
+            eraser: true,
+            ..Default::default()
+        };
+        assert!(twm.raise_pen_down(&hwnd, &pen));
+        // The default implementation doesn't handle the event
+        assert!(!twm.raise_pen_move(&hwnd, &pen));
+        assert!(twm.raise_pen_up(&hwnd, &pen));
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "touch_begin 1",
+                "touch_begin 2",
+                "touch_move 1",
+                "touch_end 1",
+                "pen_down 0.25 true",
+                "pen_up",
+            ]
+        );
+    });
+}
+
+#[test]
+fn wnd_gesture_events() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        struct Listener(Rc<RefCell<Vec<String>>>);
+
+        impl WndListener<pal::Wm> for Listener {
+            fn gesture(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                loc: Point2<f32>,
+            ) -> Box<dyn pal::iface::GestureListener<pal::Wm>> {
+                self.0
+                    .borrow_mut()
+                    .push(format!("gesture {:?}", (loc.x, loc.y)));
+                Box::new(GestureListener(Rc::clone(&self.0)))
+            }
+        }
+
+        struct GestureListener(Rc<RefCell<Vec<String>>>);
+
+        impl pal::iface::GestureListener<pal::Wm> for GestureListener {
+            fn magnify(&self, _: pal::Wm, _: &pal::HWnd, scale: f32) {
+                self.0.borrow_mut().push(format!("magnify {}", scale));
+            }
+            fn rotate(&self, _: pal::Wm, _: &pal::HWnd, angle: f32) {
+                self.0.borrow_mut().push(format!("rotate {}", angle));
+            }
+            fn end(&self, _: pal::Wm, _: &pal::HWnd) {
+                self.0.borrow_mut().push("end".to_owned());
+            }
+            fn cancel(&self, _: pal::Wm, _: &pal::HWnd) {
+                self.0.borrow_mut().push("cancel".to_owned());
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            size: Some([100; 2]),
+            listener: Some(Box::new(Listener(Rc::clone(&events)))),
+            ..Default::default()
+        });
+
+        let gesture = twm.raise_gesture(&hwnd, [20.0; 2].into());
+        gesture.magnify(1.5);
+        gesture.rotate(0.25);
+        gesture.end();
+
+        let gesture = twm.raise_gesture(&hwnd, [30.0; 2].into());
+        gesture.magnify(0.5);
+        gesture.cancel();
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "gesture (20.0, 20.0)",
+                "magnify 1.5",
+                "rotate 0.25",
+                "end",
+                "gesture (30.0, 30.0)",
+                "magnify 0.5",
+                "cancel",
+            ]
+        );
+    });
+}
+
+#[test]
+fn wnd_pointer_grab() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        struct Listener(Rc<RefCell<Vec<Vector2<f32>>>>);
+
+        impl WndListener<pal::Wm> for Listener {
+            fn mouse_relative_motion(&self, _: pal::Wm, _: &pal::HWnd, delta: Vector2<f32>) {
+                self.0.borrow_mut().push(delta);
+            }
+        }
+
+        let events = Rc::new(RefCell::new(Vec::new()));
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            listener: Some(Box::new(Listener(Rc::clone(&events)))),
+            ..Default::default()
+        });
+        assert!(!twm.wnd_attrs(&hwnd).unwrap().pointer_grab);
+
+        wm.set_wnd_attr(
+            &hwnd,
+            pal::WndAttrs {
+                pointer_grab: Some(true),
+                ..Default::default()
+            },
+        );
+        assert!(twm.wnd_attrs(&hwnd).unwrap().pointer_grab);
+
+        twm.raise_mouse_relative_motion(&hwnd, [3.0, -4.0].into());
+        assert_eq!(*events.borrow(), vec![Vector2::new(3.0, -4.0)]);
+    });
+}
+
 #[test]
 fn wnd_focus_event() {
     init_logger();
@@ -985,6 +1753,46 @@ fn wnd_accel_tables() {
     });
 }
 
+#[test]
+fn wnd_accel_tables_with_key_bindings() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+
+        struct Listener(pal::AccelTable);
+        impl WndListener<pal::Wm> for Listener {
+            fn interpret_event(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                ctx: &mut dyn pal::iface::InterpretEventCtx<pal::AccelTable>,
+            ) {
+                ctx.use_accel(&self.0);
+            }
+        }
+
+        let accel_table = pal::AccelTable::new_with_key_bindings(vec![pal::KeyBinding {
+            action: 42,
+            modifiers: pal::ModifierState::ALT,
+            key: pal::Key::Down,
+        }]);
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            listener: Some(Box::new(Listener(accel_table))),
+            ..Default::default()
+        });
+
+        assert_eq!(twm.translate_action(&hwnd, "windows", "Alt+Down"), Some(42));
+        assert_eq!(twm.translate_action(&hwnd, "gtk", "Alt+Down"), Some(42));
+        assert_eq!(twm.translate_action(&hwnd, "windows", "Down"), None);
+        assert_eq!(
+            twm.translate_action(&hwnd, "windows", "Shift+Alt+Down"),
+            None
+        );
+    });
+}
+
 #[test]
 fn wnd_actions() {
     init_logger();
@@ -1083,3 +1891,163 @@ fn wnd_key_events() {
         assert_eq!(state.get(), 3);
     });
 }
+
+#[test]
+fn wnd_key_codes() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+        let keys = Rc::new(RefCell::new(Vec::new()));
+
+        struct Listener(Rc<RefCell<Vec<(pal::ModifierState, pal::Key)>>>);
+        impl WndListener<pal::Wm> for Listener {
+            fn key_down(
+                &self,
+                _: pal::Wm,
+                _: &pal::HWnd,
+                e: &dyn KeyEvent<pal::AccelTable>,
+            ) -> bool {
+                self.0.borrow_mut().push((e.modifiers(), e.key()));
+                true
+            }
+        }
+
+        let hwnd = wm.new_wnd(pal::WndAttrs {
+            visible: Some(true),
+            listener: Some(Box::new(Listener(Rc::clone(&keys)))),
+            ..Default::default()
+        });
+
+        twm.simulate_key(&hwnd, "windows", "Ctrl+S");
+        twm.simulate_key(&hwnd, "gtk", "Shift+Alt+PageUp");
+        twm.simulate_key(&hwnd, "macos", "F12");
+        twm.simulate_key(&hwnd, "windows", "VolumeMute");
+
+        assert_eq!(
+            *keys.borrow(),
+            [
+                (pal::ModifierState::CONTROL, pal::Key::Char('s')),
+                (
+                    pal::ModifierState::SHIFT | pal::ModifierState::ALT,
+                    pal::Key::PageUp
+                ),
+                (pal::ModifierState::empty(), pal::Key::F12),
+                (pal::ModifierState::empty(), pal::Key::VolumeMute),
+            ]
+        );
+    });
+}
+
+#[test]
+fn parse_key() {
+    assert_eq!("PageUp".parse(), Ok(pal::Key::PageUp));
+    assert_eq!("MediaPlayPause".parse(), Ok(pal::Key::MediaPlayPause));
+    assert_eq!("A".parse(), Ok(pal::Key::Char('a')));
+    assert_eq!("/".parse(), Ok(pal::Key::Char('/')));
+    assert_eq!("Hoge".parse::<pal::Key>(), Err(pal::ParseKeyError));
+}
+
+#[test]
+fn file_dialog() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+        let result = Rc::new(RefCell::new(None));
+
+        // File dialogs are cancelled by default
+        {
+            let result = Rc::clone(&result);
+            wm.show_file_dialog(None, Default::default(), move |_, paths| {
+                *result.borrow_mut() = Some(paths);
+            });
+        }
+        assert_eq!(*result.borrow(), None, "the handler must be called later");
+        twm.step_unsend();
+        assert_eq!(result.borrow_mut().take(), Some(None));
+
+        twm.set_file_dialog_handler(Some(Box::new(|options| {
+            assert_eq!(options.kind, pal::FileDialogKind::Save);
+            assert_eq!(options.filters[0].extensions, ["txt"]);
+            let name = options.default_name.as_ref().unwrap();
+            Some(vec![PathBuf::from("/tmp").join(name)])
+        })));
+
+        {
+            let result = Rc::clone(&result);
+            let options = pal::FileDialogOptions {
+                kind: pal::FileDialogKind::Save,
+                filters: vec![pal::FileTypeFilter::new("Text", &["txt"])],
+                default_name: Some("hoge.txt".to_owned()),
+                ..Default::default()
+            };
+            wm.show_file_dialog(None, options, move |_, paths| {
+                *result.borrow_mut() = Some(paths);
+            });
+        }
+        twm.step_unsend();
+        assert_eq!(
+            result.borrow_mut().take(),
+            Some(Some(vec![PathBuf::from("/tmp/hoge.txt")]))
+        );
+    });
+}
+
+#[test]
+fn notification() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+        let result = Rc::new(RefCell::new(Vec::new()));
+
+        for i in 0..2 {
+            let result = Rc::clone(&result);
+            let attrs = pal::NotificationAttrs {
+                title: format!("Message {}", i),
+                body: "Hello".to_owned(),
+                actions: vec!["Reply".to_owned()],
+                ..Default::default()
+            };
+            wm.show_notification(attrs, move |_, action| {
+                result.borrow_mut().push((i, action));
+            });
+        }
+
+        let notifications = twm.notifications();
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications[0].title, "Message 0");
+        assert_eq!(notifications[1].title, "Message 1");
+        assert_eq!(notifications[1].actions, ["Reply"]);
+        assert!(result.borrow().is_empty());
+
+        twm.activate_notification(1, Some(0));
+        twm.activate_notification(0, None);
+        assert_eq!(*result.borrow(), [(1, Some(0)), (0, None)]);
+    });
+}
+
+#[test]
+fn child_wnd() {
+    init_logger();
+    testing::run_test(|twm| {
+        let wm = twm.wm();
+        let hwnd = wm.new_wnd(Default::default());
+        let bounds = box2! { min: [10.0, 20.0], max: [30.0, 40.0] };
+
+        let child = wm
+            .new_child_wnd(&hwnd, bounds)
+            .expect("the testing backend should support child windows");
+        assert_eq!(twm.child_wnds(&hwnd), vec![child.clone()]);
+        assert_eq!(twm.child_wnd_bounds(&child), Some(bounds));
+
+        let bounds = box2! { min: [0.0, 0.0], max: [5.0, 5.0] };
+        wm.set_child_wnd_bounds(&child, bounds);
+        assert_eq!(twm.child_wnd_bounds(&child), Some(bounds));
+
+        // Child windows are destroyed along with their parent
+        wm.remove_wnd(&hwnd);
+        assert_eq!(twm.child_wnd_bounds(&child), None);
+
+        // ... and removing them afterward is harmless
+        wm.remove_child_wnd(&child);
+    });
+}
@@ -26,16 +26,21 @@ fn test_text_layout_invariants() {
         " 'book' translates \r to 'كِتَاب‎'.",
     ];
 
+    let directions = [
+        pal::ParagraphDirection::Auto,
+        pal::ParagraphDirection::Ltr,
+        pal::ParagraphDirection::Rtl,
+    ];
+
     let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
         ..Default::default()
     });
 
-    for text in patterns.iter() {
-        log::info!("{:?}", text);
-
-        // TODO: try the right-to-left primary directionality
+    for (text, &direction) in iproduct!(patterns.iter(), directions.iter()) {
+        log::info!("{:?} ({:?})", text, direction);
 
-        let text_layout = pal::TextLayout::from_text(text, &char_style, None);
+        let text_layout =
+            pal::TextLayout::from_text_with_direction(text, &char_style, None, direction);
         log::debug!("  text_layout = {:?}", text_layout);
 
         let visual_bounds = text_layout.visual_bounds();
@@ -271,6 +276,24 @@ fn test_text_layout_invariants() {
                     beams[1].x,
                 );
 
+                // `hit_test` must be consistent with `cursor_index_from_point`
+                let hit0 = text_layout.hit_test([beams[0].x, y].into());
+                let hit1 = text_layout.hit_test([beams[1].x, y].into());
+                log::trace!("    hit_test = {:?}", [&hit0, &hit1]);
+                for hit in [&hit0, &hit1].iter() {
+                    assert!(line_grapheme_boundary_indices.contains(&hit.index.start));
+                    assert!(line_grapheme_boundary_indices.contains(&hit.index.end));
+                }
+                assert!(
+                    i_range.contains(&hit0.cursor_index())
+                        || i_range.contains(&hit1.cursor_index()),
+                    "{:?} ∉ {:?} && {:?} ∉ {:?}",
+                    hit0,
+                    i_range,
+                    hit1,
+                    i_range,
+                );
+
                 i = i_end;
             }
         } // line_ranges.iter().enumerate()
@@ -419,6 +442,107 @@ fn is_disjoint_ranges_subset_of<T: PartialOrd + std::fmt::Debug>(
     })
 }
 
+#[test]
+fn hit_test_distinguishes_leading_and_trailing_edges() {
+    common::try_init_logger_for_default_harness();
+
+    let patterns = [
+        ("good apple cider", pal::ParagraphDirection::Auto),
+        ("שלום עולם", pal::ParagraphDirection::Auto),
+        ("كتاب", pal::ParagraphDirection::Rtl),
+        ("book - כתב", pal::ParagraphDirection::Rtl),
+    ];
+
+    let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+        ..Default::default()
+    });
+
+    for &(text, direction) in patterns.iter() {
+        log::info!("{:?} ({:?})", text, direction);
+
+        let text_layout =
+            pal::TextLayout::from_text_with_direction(text, &char_style, None, direction);
+        assert_eq!(text_layout.num_lines(), 1);
+
+        let y = {
+            let bounds = text_layout.line_vertical_bounds(0);
+            (bounds.start + bounds.end) / 2.0
+        };
+
+        let boundaries: Vec<usize> = text
+            .grapheme_indices(false)
+            .map(|(i, _char)| i)
+            .chain(once(text.len()))
+            .collect();
+
+        for cluster in boundaries.windows(2).map(|w| w[0]..w[1]) {
+            let runs = text_layout.run_metrics_of_range(cluster.clone());
+            log::debug!("  runs({:?}) = {:?}", cluster, runs);
+            assert_eq!(runs.len(), 1);
+
+            let run = &runs[0];
+            let width = run.bounds.end - run.bounds.start;
+            if width < 2.0 {
+                continue;
+            }
+            let is_rtl = run.flags.contains(pal::RunFlags::RIGHT_TO_LEFT);
+
+            let left = text_layout.hit_test([run.bounds.start + width * 0.25, y].into());
+            let right = text_layout.hit_test([run.bounds.end - width * 0.25, y].into());
+            log::debug!("  hit_test = {:?}", [&left, &right]);
+
+            assert_eq!(left.index, cluster);
+            assert_eq!(right.index, cluster);
+            assert_eq!(left.flags, run.flags);
+
+            // The leading edge of an RTL cluster is on the right side
+            assert_eq!(left.trailing, !is_rtl);
+            assert_eq!(right.trailing, is_rtl);
+        }
+    }
+}
+
+#[test]
+fn paragraph_direction_determines_run_order() {
+    common::try_init_logger_for_default_harness();
+
+    let text = "book כתב";
+    let hebrew_start = text.find('כ').unwrap();
+
+    let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+        ..Default::default()
+    });
+
+    let run_x = |direction| {
+        let text_layout =
+            pal::TextLayout::from_text_with_direction(text, &char_style, None, direction);
+        let runs = text_layout.run_metrics_of_range(0..text.len());
+        log::debug!("runs({:?}) = {:?}", direction, runs);
+
+        let x_of = |i: usize| {
+            runs.iter()
+                .find(|run| run.index.contains(&i))
+                .unwrap()
+                .bounds
+                .start
+        };
+        (x_of(0), x_of(hebrew_start))
+    };
+
+    // "book" comes first in a left-to-right paragraph...
+    let (latin_x, hebrew_x) = run_x(pal::ParagraphDirection::Ltr);
+    assert!(latin_x < hebrew_x);
+
+    // ...and so does in an auto-directional paragraph because the first
+    // strong character is left-to-right...
+    let (latin_x, hebrew_x) = run_x(pal::ParagraphDirection::Auto);
+    assert!(latin_x < hebrew_x);
+
+    // ...but is placed on the right side in a right-to-left paragraph
+    let (latin_x, hebrew_x) = run_x(pal::ParagraphDirection::Rtl);
+    assert!(latin_x > hebrew_x);
+}
+
 #[test]
 fn empty_layout_should_have_sane_height() {
     common::try_init_logger_for_default_harness();
@@ -474,6 +598,50 @@ fn empty_layout_should_have_sane_height() {
     }
 }
 
+#[test]
+fn emoji_clusters() {
+    common::try_init_logger_for_default_harness();
+
+    let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+        ..Default::default()
+    });
+
+    let text = "a✨🇳🇮\u{2764}\u{2764}\u{fe0f}1\u{fe0f}\u{20e3}\u{1f984}\u{fe0e}";
+    let text_layout = pal::TextLayout::from_text(text, &char_style, None);
+    log::debug!("text_layout = {:?}", text_layout);
+
+    // Split `text` into clusters
+    let mut clusters = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let next_i = text_layout.next_char(i, true);
+        clusters.push(i..next_i);
+        i = next_i;
+    }
+    log::debug!("clusters = {:?}", clusters);
+
+    let flags: Vec<_> = clusters
+        .iter()
+        .map(|r| (&text[r.clone()], text_layout.is_emoji_cluster(r.clone())))
+        .collect();
+    assert_eq!(
+        flags,
+        [
+            ("a", false),
+            ("✨", true),
+            ("🇳🇮", true),
+            ("\u{2764}", false),
+            ("\u{2764}\u{fe0f}", true),
+            ("1\u{fe0f}\u{20e3}", true),
+            ("\u{1f984}\u{fe0e}", false),
+        ]
+    );
+
+    // Empty ranges and ranges spanning multiple clusters are rejected
+    assert!(!text_layout.is_emoji_cluster(clusters[1].start..clusters[1].start));
+    assert!(!text_layout.is_emoji_cluster(clusters[1].start..clusters[2].end));
+}
+
 #[test]
 fn draw_text_should_not_change_layout_properties() {
     let patterns = [
@@ -18,6 +18,7 @@ pub use tcw3_testing as testing;
 #[doc(hidden)]
 pub use rob;
 
+pub mod profiling;
 pub mod ui;
 pub mod uicore;
 pub mod utils {
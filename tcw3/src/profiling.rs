@@ -0,0 +1,120 @@
+//! Performance instrumentation hooks.
+//!
+//! `uicore` measures the major phases of window updates and reports them to
+//! the listener registered by [`set_listener`]. This makes it possible to find
+//! out which phase (and, with the help of [`HWndRef::dump_view_tree`], which
+//! views) causes jank. Nothing is measured while no listener is registered.
+//!
+//! [`LogListener`] is a ready-made listener that outputs spans via `log`.
+//! Alternatively, you can implement [`ProfilingListener`] to forward spans to
+//! a profiler of your choice.
+//!
+//! For a visual indication, see also [`DebugOverlayFlags::FRAME_TIMES`].
+//!
+//! [`HWndRef::dump_view_tree`]: crate::uicore::HWndRef::dump_view_tree
+//! [`DebugOverlayFlags::FRAME_TIMES`]: crate::uicore::DebugOverlayFlags::FRAME_TIMES
+//!
+//!     use tcw3::{pal, profiling};
+//!     # fn test(wm: pal::Wm) {
+//!     profiling::set_listener(wm, Some(Box::new(profiling::LogListener)));
+//!     # }
+//!
+use log::debug;
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use tcw3_pal::mt_lazy_static;
+
+use crate::{pal::Wm, uicore::HWndRef};
+
+/// Identifies a phase of a window update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpanKind {
+    /// An entire window update, enclosing all other spans.
+    Frame,
+    /// A layout pass, i.e., the calculation of size traits and frames.
+    Layout,
+    /// A batch of calls to [`ViewListener::update`].
+    ///
+    /// [`ViewListener::update`]: crate::uicore::ViewListener::update
+    ViewUpdate,
+    /// The submission of a window's attributes and layers to the backend.
+    Commit,
+}
+
+/// A measured span reported to [`ProfilingListener`].
+#[derive(Debug, Clone, Copy)]
+pub struct Span<'a> {
+    pub kind: SpanKind,
+    /// The window being updated.
+    pub wnd: HWndRef<'a>,
+    pub start: Instant,
+    pub duration: Duration,
+}
+
+/// Receives spans measured by `uicore`. See [the module-level
+/// documentation](self).
+pub trait ProfilingListener {
+    /// Called when a span ends. Spans are reported in the order they end, so
+    /// nested spans are reported before the enclosing ones.
+    ///
+    /// It's not allowed to call [`set_listener`] from this method.
+    fn span_finished(&self, wm: Wm, span: &Span<'_>);
+}
+
+/// A [`ProfilingListener`] that outputs spans via `log` at the `Debug` level.
+#[derive(Debug, Clone, Copy)]
+pub struct LogListener;
+
+impl ProfilingListener for LogListener {
+    fn span_finished(&self, _: Wm, span: &Span<'_>) {
+        debug!("{:?} of {:?} took {:?}", span.kind, span.wnd, span.duration);
+    }
+}
+
+mt_lazy_static! {
+    static ref LISTENER: RefCell<Option<Rc<dyn ProfilingListener>>> => |_| RefCell::new(None);
+}
+
+/// Set the listener receiving spans. `None` disables measurement, which is
+/// the default state.
+pub fn set_listener(wm: Wm, listener: Option<Box<dyn ProfilingListener>>) {
+    *LISTENER.get_with_wm(wm).borrow_mut() = listener.map(Rc::from);
+}
+
+/// Start measuring a span. The span ends when the returned guard is dropped.
+pub(crate) fn begin_span(wm: Wm, kind: SpanKind, wnd: HWndRef<'_>) -> SpanGuard<'_> {
+    let listener = LISTENER.get_with_wm(wm).borrow().clone();
+    SpanGuard {
+        wm,
+        kind,
+        wnd,
+        state: listener.map(|listener| (listener, Instant::now())),
+    }
+}
+
+pub(crate) struct SpanGuard<'a> {
+    wm: Wm,
+    kind: SpanKind,
+    wnd: HWndRef<'a>,
+    /// `None` if no listener is registered.
+    state: Option<(Rc<dyn ProfilingListener>, Instant)>,
+}
+
+impl Drop for SpanGuard<'_> {
+    fn drop(&mut self) {
+        if let Some((listener, start)) = self.state.take() {
+            listener.span_finished(
+                self.wm,
+                &Span {
+                    kind: self.kind,
+                    wnd: self.wnd,
+                    start,
+                    duration: start.elapsed(),
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Message boxes (alerts).
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use crate::{
+    pal,
+    pal::{iface::AlertCallback, prelude::*, AlertOptions, AlertSeverity},
+    ui::{
+        layouts::{FillLayout, TableLayout},
+        theming::{elem_id, roles, Manager, StyledBox},
+        views::{Button, Label},
+        AlignFlags,
+    },
+    uicore::{
+        ActionId, ActionStatus, HView, HWnd, HWndRef, InterpretEventCtx, ViewFlags, WndListener,
+        WndStyleFlags,
+    },
+};
+
+/// Show a modal alert (a message box).
+///
+/// `f` is called with the index of the button chosen by the user (an index
+/// into `options.buttons`) or `None` if the alert was dismissed in other ways,
+/// e.g., by pressing Escape or closing the window. If `options.buttons` is
+/// empty, a single "OK" button is displayed.
+///
+/// This function uses [`Wm::show_alert`] if the backend supports it.
+/// Otherwise, it displays a substitute window rendered by TCW3. In the latter
+/// case, the alert is not actually modal — `parent` is not disabled while the
+/// alert is open.
+///
+/// [`Wm::show_alert`]: crate::pal::iface::Wm::show_alert
+pub fn show_alert(
+    wm: pal::Wm,
+    parent: Option<HWndRef<'_>>,
+    options: AlertOptions,
+    f: impl FnOnce(pal::Wm, Option<usize>) + 'static,
+) {
+    let pal_parent = parent.and_then(|hwnd| hwnd.pal_hwnd());
+
+    if let Err(f) = wm.show_alert(pal_parent.as_ref(), &options, Box::new(f)) {
+        show_fallback_alert(wm, options, f);
+    }
+}
+
+const ACTION_CANCEL: ActionId = 0;
+const ACTION_DEFAULT: ActionId = 1;
+
+static ACCEL_TABLE: pal::AccelTable = pal::accel_table![
+    (
+        ACTION_CANCEL,
+        windows("Escape"),
+        gtk("Escape"),
+        macos_sel("cancelOperation:")
+    ),
+    (
+        ACTION_DEFAULT,
+        windows("Return"),
+        gtk("Return"),
+        macos_sel("insertNewline:")
+    ),
+];
+
+/// The state of an alert displayed by [`show_fallback_alert`].
+struct FallbackAlert {
+    /// Keeps the window open until the alert is dismissed.
+    wnd: RefCell<Option<HWnd>>,
+    f: Cell<Option<AlertCallback<pal::Wm>>>,
+}
+
+impl FallbackAlert {
+    fn dismiss(&self, wm: pal::Wm, result: Option<usize>) {
+        let f = if let Some(f) = self.f.take() {
+            f
+        } else {
+            return;
+        };
+
+        if let Some(wnd) = self.wnd.borrow_mut().take() {
+            wnd.close();
+        }
+
+        f(wm, result);
+    }
+}
+
+fn show_fallback_alert(wm: pal::Wm, options: AlertOptions, f: AlertCallback<pal::Wm>) {
+    let style_manager = Manager::global(wm);
+
+    let alert = Rc::new(FallbackAlert {
+        wnd: RefCell::new(None),
+        f: Cell::new(Some(f)),
+    });
+
+    let mut captions = options.buttons;
+    if captions.is_empty() {
+        captions.push("OK".to_owned());
+    }
+
+    let buttons: Vec<Button> = captions
+        .into_iter()
+        .enumerate()
+        .map(|(i, caption)| {
+            let button = Button::new(style_manager);
+            button.set_caption(caption);
+
+            let alert = Rc::clone(&alert);
+            button.subscribe_activated(Box::new(move |wm| alert.dismiss(wm, Some(i))));
+
+            button
+        })
+        .collect();
+
+    let button_bar = HView::new(ViewFlags::default());
+    button_bar.set_layout(
+        TableLayout::stack_horz(
+            buttons
+                .iter()
+                .map(|button| (button.view(), AlignFlags::VERT_JUSTIFY)),
+        )
+        .with_uniform_spacing(8.0),
+    );
+
+    let icon = StyledBox::new(style_manager, ViewFlags::default());
+    icon.set_class_set(match options.severity {
+        AlertSeverity::Info => elem_id::ALERT_INFO,
+        AlertSeverity::Warning => elem_id::ALERT_WARNING,
+        AlertSeverity::Error => elem_id::ALERT_ERROR,
+    });
+
+    let label = Label::new(style_manager);
+    label.set_text(options.message);
+
+    let root = StyledBox::new(style_manager, ViewFlags::default());
+    root.set_class_set(elem_id::ALERT);
+    root.set_subview(roles::ALERT_ICON, Some(icon.view()));
+    root.set_subelement(roles::ALERT_ICON, Some(icon.style_elem()));
+    root.set_child(roles::GENERIC, Some(&label));
+    root.set_subview(roles::ALERT_BUTTONS, Some(button_bar));
+
+    let wnd = HWnd::new(wm);
+    wnd.set_caption(options.title);
+    wnd.set_style_flags(WndStyleFlags::empty());
+    wnd.content_view().set_layout(FillLayout::new(root.view()));
+    wnd.set_listener(AlertWndListener {
+        alert: Rc::clone(&alert),
+    });
+    wnd.set_focused_view(buttons.first().map(Button::view));
+    wnd.set_visibility(true);
+
+    *alert.wnd.borrow_mut() = Some(wnd);
+}
+
+struct AlertWndListener {
+    alert: Rc<FallbackAlert>,
+}
+
+impl WndListener for AlertWndListener {
+    fn close(&self, wm: pal::Wm, _: HWndRef<'_>) {
+        self.alert.dismiss(wm, None);
+    }
+
+    fn interpret_event(&self, _: pal::Wm, _: HWndRef<'_>, ctx: &mut InterpretEventCtx<'_>) {
+        ctx.use_accel(&ACCEL_TABLE);
+    }
+
+    fn validate_action(&self, _: pal::Wm, _: HWndRef<'_>, action: ActionId) -> ActionStatus {
+        match action {
+            ACTION_CANCEL | ACTION_DEFAULT => ActionStatus::VALID | ActionStatus::ENABLED,
+            _ => ActionStatus::empty(),
+        }
+    }
+
+    fn perform_action(&self, wm: pal::Wm, _: HWndRef<'_>, action: ActionId) {
+        match action {
+            ACTION_CANCEL => self.alert.dismiss(wm, None),
+            ACTION_DEFAULT => self.alert.dismiss(wm, Some(0)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use try_match::try_match;
+
+    use super::*;
+    use crate::testing::{prelude::*, use_testing_wm};
+
+    fn show_test_alert(twm: &dyn TestingWm) -> (pal::HWnd, Rc<Cell<Option<Option<usize>>>>) {
+        let wm = twm.wm();
+        let result = Rc::new(Cell::new(None));
+
+        {
+            let result = Rc::clone(&result);
+            show_alert(
+                wm,
+                None,
+                AlertOptions {
+                    title: "Confirm".to_owned(),
+                    message: "Discard changes?".to_owned(),
+                    buttons: vec!["Discard".to_owned(), "Cancel".to_owned()],
+                    severity: AlertSeverity::Warning,
+                },
+                move |_, i| result.set(Some(i)),
+            );
+        }
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+
+        let attrs = twm.wnd_attrs(&pal_hwnd).unwrap();
+        assert_eq!(attrs.caption, "Confirm");
+        assert!(attrs.visible);
+
+        (pal_hwnd, result)
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn fallback_close(twm: &dyn TestingWm) {
+        let (pal_hwnd, result) = show_test_alert(twm);
+
+        twm.raise_close_requested(&pal_hwnd);
+        twm.step_unsend();
+
+        assert_eq!(result.get(), Some(None));
+        assert_eq!(twm.hwnds().len(), 0);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn fallback_escape(twm: &dyn TestingWm) {
+        let (pal_hwnd, result) = show_test_alert(twm);
+
+        twm.simulate_key(&pal_hwnd, "windows", "Escape");
+        twm.step_unsend();
+
+        assert_eq!(result.get(), Some(None));
+        assert_eq!(twm.hwnds().len(), 0);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn fallback_default_button(twm: &dyn TestingWm) {
+        let (pal_hwnd, result) = show_test_alert(twm);
+
+        twm.simulate_key(&pal_hwnd, "windows", "Return");
+        twm.step_unsend();
+
+        assert_eq!(result.get(), Some(Some(0)));
+        assert_eq!(twm.hwnds().len(), 0);
+    }
+}
@@ -0,0 +1,96 @@
+use std::{cell::RefCell, fmt};
+use subscriber_list::SubscriberList;
+
+use crate::pal;
+
+pub use crate::uicore::Sub;
+
+/// A list of event handlers.
+///
+/// This type is used by widgets to implement `subscribe_*` methods. Any number
+/// of handlers can be registered by [`subscribe`]. Each call returns a [`Sub`],
+/// which can be used to remove the handler later.
+///
+/// `Sub` only holds a weak reference to the `EventSource`. Dropping `Sub` does
+/// not remove the handler, and `Sub` does not keep the `EventSource` (and the
+/// widget owning it) alive. Handlers are dropped along with the
+/// `EventSource`.
+///
+/// `F` is the type of handlers. It's usually an unsized type like
+/// `dyn Fn(pal::Wm)`.
+///
+/// [`subscribe`]: EventSource::subscribe
+///
+/// # Examples
+///
+/// ```
+/// use std::{cell::Cell, rc::Rc};
+/// use tcw3::ui::EventSource;
+///
+/// let source: EventSource<dyn Fn(u32)> = EventSource::new();
+/// let sum = Rc::new(Cell::new(0));
+/// let sub = {
+///     let sum = Rc::clone(&sum);
+///     source.subscribe(Box::new(move |x| sum.set(sum.get() + x)))
+/// };
+///
+/// // Call the handlers. The closure takes care of supplying arguments.
+/// source.raise_with(|handler| handler(42));
+/// assert_eq!(sum.get(), 42);
+///
+/// // Remove the handler
+/// sub.unsubscribe().unwrap();
+/// source.raise_with(|handler| handler(42));
+/// assert_eq!(sum.get(), 42);
+/// ```
+pub struct EventSource<F: ?Sized = dyn Fn(pal::Wm)> {
+    handlers: RefCell<SubscriberList<Box<F>>>,
+}
+
+impl<F: ?Sized + 'static> Default for EventSource<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for EventSource<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventSource").finish()
+    }
+}
+
+impl<F: ?Sized + 'static> EventSource<F> {
+    /// Construct an empty `EventSource`.
+    pub fn new() -> Self {
+        Self {
+            handlers: RefCell::new(SubscriberList::new()),
+        }
+    }
+
+    /// Add an event handler.
+    ///
+    /// It's not allowed to call this method from one of the handlers of the
+    /// same `EventSource`.
+    pub fn subscribe(&self, cb: Box<F>) -> Sub {
+        self.handlers.borrow_mut().insert(cb).untype()
+    }
+
+    /// Call `f` for each registered handler.
+    ///
+    /// Handlers are not allowed to add a new handler to the same
+    /// `EventSource`. Removing a handler from a handler fails with
+    /// [`IterationActive`](subscriber_list::IterationActive).
+    pub fn raise_with(&self, mut f: impl FnMut(&F)) {
+        let handlers = self.handlers.borrow();
+        for handler in handlers.iter() {
+            f(handler);
+        }
+    }
+}
+
+impl EventSource<dyn Fn(pal::Wm)> {
+    /// Call all registered handlers.
+    pub fn raise(&self, wm: pal::Wm) {
+        self.raise_with(|handler| handler(wm));
+    }
+}
@@ -0,0 +1,664 @@
+use cggeom::box2;
+use cgmath::Vector2;
+use std::{
+    cell::RefCell,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use crate::uicore::{HView, Layout, LayoutCtx, SizeTraits};
+
+mod solver;
+
+/// A `Layout` that positions subviews based on linear constraints between
+/// the edges of the subviews and the container.
+///
+/// The constraints are solved by an incremental constraint solver based on
+/// the Cassowary algorithm. The solver state is preserved between layout
+/// passes, and only the constraints derived from subviews' changed
+/// [`SizeTraits`] are updated when `size_traits` or `arrange` is called.
+///
+/// In addition to the user-supplied constraints, each subview's size is
+/// constrained by its `SizeTraits`: `min` and `max` with [`Strength::STRONG`]
+/// and `preferred` with [`Strength::WEAK`]. The container's `SizeTraits` is
+/// calculated by solving the constraints with the container's size pulled
+/// toward zero (for `min` and `preferred`) or infinity (for `max`).
+///
+/// # Examples
+///
+/// ```
+/// use tcw3::{ui::layouts::{Constraint, ConstraintLayout, Target}, uicore::HView};
+///
+/// let (sv1, sv2) = (HView::new(Default::default()), HView::new(Default::default()));
+/// let (c, a, b) = (Target::Container, Target::Subview(0), Target::Subview(1));
+///
+/// let layout = ConstraintLayout::new(vec![sv1, sv2])
+///     .with_constraint(Constraint::eq(a.left(), c.left() + 10.0))
+///     .with_constraint(Constraint::eq(b.left(), a.right() + 5.0))
+///     .with_constraint(Constraint::eq(c.right(), b.right() + 10.0))
+///     .with_constraint(Constraint::eq(a.width(), b.width()))
+///     .with_constraint(Constraint::eq(a.top(), c.top() + 10.0))
+///     .with_constraint(Constraint::eq(a.center_y(), b.center_y()))
+///     .with_constraint(Constraint::ge(c.bottom(), a.bottom() + 10.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConstraintLayout {
+    subviews: Box<[HView]>,
+    state: RefCell<State>,
+}
+
+/// Refers to the container or a subview of [`ConstraintLayout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// The view associated with the layout.
+    Container,
+    /// The subview with the specified index.
+    Subview(usize),
+}
+
+/// An attribute of a [`Target`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attr {
+    Left,
+    Top,
+    Right,
+    Bottom,
+    Width,
+    Height,
+    CenterX,
+    CenterY,
+}
+
+/// A reference to an attribute of a [`Target`], which can be used as a term
+/// of [`LinearExpr`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Anchor {
+    pub target: Target,
+    pub attr: Attr,
+}
+
+/// A linear expression `Σ coef·anchor + constant`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearExpr {
+    pub terms: Vec<(Anchor, f32)>,
+    pub constant: f32,
+}
+
+/// The strength of a [`Constraint`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Strength(f64);
+
+/// A linear constraint used by [`ConstraintLayout`].
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    /// `lhs - rhs`
+    expr: LinearExpr,
+    op: solver::RelOp,
+    strength: Strength,
+}
+
+impl Target {
+    /// Get an `Anchor` referring to the specified attribute of `self`.
+    pub fn attr(self, attr: Attr) -> Anchor {
+        Anchor { target: self, attr }
+    }
+
+    pub fn left(self) -> Anchor {
+        self.attr(Attr::Left)
+    }
+
+    pub fn top(self) -> Anchor {
+        self.attr(Attr::Top)
+    }
+
+    pub fn right(self) -> Anchor {
+        self.attr(Attr::Right)
+    }
+
+    pub fn bottom(self) -> Anchor {
+        self.attr(Attr::Bottom)
+    }
+
+    pub fn width(self) -> Anchor {
+        self.attr(Attr::Width)
+    }
+
+    pub fn height(self) -> Anchor {
+        self.attr(Attr::Height)
+    }
+
+    pub fn center_x(self) -> Anchor {
+        self.attr(Attr::CenterX)
+    }
+
+    pub fn center_y(self) -> Anchor {
+        self.attr(Attr::CenterY)
+    }
+}
+
+impl From<Anchor> for LinearExpr {
+    fn from(x: Anchor) -> Self {
+        Self {
+            terms: vec![(x, 1.0)],
+            constant: 0.0,
+        }
+    }
+}
+
+impl From<f32> for LinearExpr {
+    fn from(x: f32) -> Self {
+        Self {
+            terms: Vec::new(),
+            constant: x,
+        }
+    }
+}
+
+fn into_expr(x: impl Into<LinearExpr>) -> LinearExpr {
+    x.into()
+}
+
+/// Implement `Add` and `Sub` for `$lhs` with concrete right-hand side types.
+/// (A blanket implementation over `T: Into<LinearExpr>` would prevent float
+/// literals from being inferred as `f32`.)
+macro_rules! impl_add_sub {
+    ($lhs:ty) => {
+        impl_add_sub!($lhs, f32);
+        impl_add_sub!($lhs, Anchor);
+        impl_add_sub!($lhs, LinearExpr);
+    };
+    ($lhs:ty, $rhs:ty) => {
+        impl Add<$rhs> for $lhs {
+            type Output = LinearExpr;
+
+            fn add(self, rhs: $rhs) -> Self::Output {
+                let mut out = into_expr(self);
+                let rhs = into_expr(rhs);
+                out.terms.extend(rhs.terms);
+                out.constant += rhs.constant;
+                out
+            }
+        }
+
+        impl Sub<$rhs> for $lhs {
+            type Output = LinearExpr;
+
+            fn sub(self, rhs: $rhs) -> Self::Output {
+                self + (-into_expr(rhs))
+            }
+        }
+    };
+}
+
+impl_add_sub!(LinearExpr);
+impl_add_sub!(Anchor);
+
+impl Mul<f32> for LinearExpr {
+    type Output = LinearExpr;
+
+    fn mul(mut self, rhs: f32) -> Self::Output {
+        for (_, coef) in self.terms.iter_mut() {
+            *coef *= rhs;
+        }
+        self.constant *= rhs;
+        self
+    }
+}
+
+impl Neg for LinearExpr {
+    type Output = LinearExpr;
+
+    fn neg(self) -> Self::Output {
+        self * -1.0
+    }
+}
+
+impl Mul<f32> for Anchor {
+    type Output = LinearExpr;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        LinearExpr::from(self) * rhs
+    }
+}
+
+impl Neg for Anchor {
+    type Output = LinearExpr;
+
+    fn neg(self) -> Self::Output {
+        -LinearExpr::from(self)
+    }
+}
+
+impl Strength {
+    /// A constraint that must be satisfied.
+    pub const REQUIRED: Self = Self(solver::REQUIRED);
+    pub const STRONG: Self = Self(1.0e6);
+    pub const MEDIUM: Self = Self(1.0e3);
+    pub const WEAK: Self = Self(1.0);
+
+    /// Construct a non-required `Strength` from the weights of the three
+    /// levels. Each weight is clamped to the range `[0, 1000]`.
+    pub fn new(strong: f64, medium: f64, weak: f64) -> Self {
+        let clamp = |x: f64| x.max(0.0).min(1000.0);
+        Self(
+            (clamp(strong) * 1.0e6 + clamp(medium) * 1.0e3 + clamp(weak))
+                .min(solver::REQUIRED - 1.0),
+        )
+    }
+}
+
+impl Default for Strength {
+    fn default() -> Self {
+        Self::REQUIRED
+    }
+}
+
+impl Constraint {
+    /// Construct a constraint `lhs == rhs` with [`Strength::REQUIRED`].
+    pub fn eq(lhs: impl Into<LinearExpr>, rhs: impl Into<LinearExpr>) -> Self {
+        Self::new(into_expr(lhs) - into_expr(rhs), solver::RelOp::Eq)
+    }
+
+    /// Construct a constraint `lhs <= rhs` with [`Strength::REQUIRED`].
+    pub fn le(lhs: impl Into<LinearExpr>, rhs: impl Into<LinearExpr>) -> Self {
+        Self::new(into_expr(lhs) - into_expr(rhs), solver::RelOp::Le)
+    }
+
+    /// Construct a constraint `lhs >= rhs` with [`Strength::REQUIRED`].
+    pub fn ge(lhs: impl Into<LinearExpr>, rhs: impl Into<LinearExpr>) -> Self {
+        Self::new(into_expr(lhs) - into_expr(rhs), solver::RelOp::Ge)
+    }
+
+    fn new(expr: LinearExpr, op: solver::RelOp) -> Self {
+        Self {
+            expr,
+            op,
+            strength: Strength::REQUIRED,
+        }
+    }
+
+    /// Update the strength and return a new `Constraint`, consuming `self`.
+    pub fn with_strength(self, strength: Strength) -> Self {
+        Self { strength, ..self }
+    }
+}
+
+/// The strength of the container's size in the `arrange` pass. It must be
+/// stronger than any constraints derived from subviews' `SizeTraits`.
+const ARRANGE_STRENGTH: f64 = 1.0e8;
+/// The strength of the container's size when calculating `SizeTraits::min`
+/// and `max`. It must be stronger than subviews' preferred sizes but weaker
+/// than their minimum and maximum sizes.
+const MIN_MAX_STRENGTH: f64 = 1.0e3;
+/// The strength of the container's size when calculating
+/// `SizeTraits::preferred`. It must be weaker than subviews' preferred sizes.
+const PREFERRED_STRENGTH: f64 = 1.0e-3;
+
+/// The value suggested for the container's size when calculating
+/// `SizeTraits::max`. Any results close to this value are considered
+/// unbounded.
+const LARGE: f64 = 1.0e7;
+
+#[derive(Debug, Clone)]
+struct State {
+    solver: solver::Solver,
+    /// The width and height of the container.
+    container_vars: [solver::Var; 2],
+    /// The current strength of the edit variables in `container_vars`.
+    container_strength: Option<f64>,
+    subviews: Box<[SubviewState]>,
+}
+
+#[derive(Debug, Clone)]
+struct SubviewState {
+    /// The left, top, width, and height of the subview.
+    vars: [solver::Var; 4],
+    /// The `SizeTraits` from which `cns` was derived.
+    size_traits: Option<SizeTraits>,
+    cns: Vec<solver::ConstraintId>,
+}
+
+impl ConstraintLayout {
+    /// Construct a `ConstraintLayout` with no constraints.
+    pub fn new(subviews: impl IntoIterator<Item = HView>) -> Self {
+        let subviews: Box<[HView]> = subviews.into_iter().collect();
+
+        let mut solver = solver::Solver::new();
+        let container_vars = [solver.new_var(), solver.new_var()];
+        let subview_states = (0..subviews.len())
+            .map(|_| SubviewState {
+                vars: [
+                    solver.new_var(),
+                    solver.new_var(),
+                    solver.new_var(),
+                    solver.new_var(),
+                ],
+                size_traits: None,
+                cns: Vec::new(),
+            })
+            .collect();
+
+        Self {
+            subviews,
+            state: RefCell::new(State {
+                solver,
+                container_vars,
+                container_strength: None,
+                subviews: subview_states,
+            }),
+        }
+    }
+
+    /// Add a constraint and return a new `ConstraintLayout`, consuming `self`.
+    ///
+    /// Panics if the constraint refers to a non-existent subview or
+    /// the constraint is required and conflicts with other required
+    /// constraints.
+    pub fn with_constraint(self, cns: Constraint) -> Self {
+        {
+            let mut state = self.state.borrow_mut();
+            let cns = state.lower_constraint(&cns);
+            state
+                .solver
+                .add_constraint(cns)
+                .expect("unsatisfiable constraint");
+        }
+        self
+    }
+
+    /// Add constraints and return a new `ConstraintLayout`, consuming `self`.
+    ///
+    /// See [`ConstraintLayout::with_constraint`].
+    pub fn with_constraints(self, cns: impl IntoIterator<Item = Constraint>) -> Self {
+        cns.into_iter().fold(self, Self::with_constraint)
+    }
+}
+
+impl State {
+    /// Convert `Constraint` to `solver::Constraint`.
+    fn lower_constraint(&self, cns: &Constraint) -> solver::Constraint {
+        let mut out = solver::Constraint {
+            terms: Vec::with_capacity(cns.expr.terms.len() * 2),
+            constant: cns.expr.constant as f64,
+            op: cns.op,
+            strength: cns.strength.0,
+        };
+
+        for &(anchor, coef) in cns.expr.terms.iter() {
+            let coef = coef as f64;
+
+            // `[left, top, width, height]`; `None` means zero
+            let vars = match anchor.target {
+                Target::Container => [
+                    None,
+                    None,
+                    Some(self.container_vars[0]),
+                    Some(self.container_vars[1]),
+                ],
+                Target::Subview(i) => {
+                    let sv = self
+                        .subviews
+                        .get(i)
+                        .unwrap_or_else(|| panic!("subview index out of range: {}", i));
+                    [
+                        Some(sv.vars[0]),
+                        Some(sv.vars[1]),
+                        Some(sv.vars[2]),
+                        Some(sv.vars[3]),
+                    ]
+                }
+            };
+
+            let (axis, pos_coef, size_coef) = match anchor.attr {
+                Attr::Left => (0, 1.0, 0.0),
+                Attr::Top => (1, 1.0, 0.0),
+                Attr::Right => (0, 1.0, 1.0),
+                Attr::Bottom => (1, 1.0, 1.0),
+                Attr::Width => (0, 0.0, 1.0),
+                Attr::Height => (1, 0.0, 1.0),
+                Attr::CenterX => (0, 1.0, 0.5),
+                Attr::CenterY => (1, 1.0, 0.5),
+            };
+
+            if let (Some(var), true) = (vars[axis], pos_coef != 0.0) {
+                out.terms.push((var, coef * pos_coef));
+            }
+            if let (Some(var), true) = (vars[axis + 2], size_coef != 0.0) {
+                out.terms.push((var, coef * size_coef));
+            }
+        }
+
+        out
+    }
+
+    /// Update the constraints derived from subviews' `SizeTraits`.
+    fn update_subviews(&mut self, ctx: &LayoutCtx<'_>, subviews: &[HView]) {
+        let solver = &mut self.solver;
+
+        for (sv, sv_state) in subviews.iter().zip(self.subviews.iter_mut()) {
+            let st = ctx.subview_size_traits(sv.as_ref());
+            if sv_state.size_traits == Some(st) {
+                continue;
+            }
+
+            for cns in sv_state.cns.drain(..) {
+                solver.remove_constraint(cns);
+            }
+
+            for axis in 0..2 {
+                let var = sv_state.vars[axis + 2];
+                let mut add = |value: f32, op, strength| {
+                    let id = solver
+                        .add_constraint(solver::Constraint {
+                            terms: vec![(var, 1.0)],
+                            constant: -(value as f64),
+                            op,
+                            strength,
+                        })
+                        .expect("a non-required constraint can't be unsatisfiable");
+                    sv_state.cns.push(id);
+                };
+
+                add(st.min[axis], solver::RelOp::Ge, Strength::STRONG.0);
+                if st.max[axis].is_finite() {
+                    add(st.max[axis], solver::RelOp::Le, Strength::STRONG.0);
+                }
+                add(st.preferred[axis], solver::RelOp::Eq, Strength::WEAK.0);
+            }
+
+            sv_state.size_traits = Some(st);
+        }
+    }
+
+    /// Suggest the container size with the specified strength and return
+    /// the solution.
+    fn solve_container_size(&mut self, size: [f64; 2], strength: f64) -> [f64; 2] {
+        if self.container_strength != Some(strength) {
+            for &var in self.container_vars.iter() {
+                if self.container_strength.is_some() {
+                    self.solver.remove_edit_var(var);
+                }
+                self.solver.add_edit_var(var, strength);
+            }
+            self.container_strength = Some(strength);
+        }
+
+        for (&var, &value) in self.container_vars.iter().zip(size.iter()) {
+            self.solver.suggest_value(var, value);
+        }
+
+        [
+            self.solver.value(self.container_vars[0]),
+            self.solver.value(self.container_vars[1]),
+        ]
+    }
+}
+
+impl Layout for ConstraintLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        let mut state = self.state.borrow_mut();
+        state.update_subviews(ctx, &self.subviews);
+
+        let min = state.solve_container_size([0.0; 2], MIN_MAX_STRENGTH);
+        let max = state.solve_container_size([LARGE; 2], MIN_MAX_STRENGTH);
+        let preferred = state.solve_container_size([0.0; 2], PREFERRED_STRENGTH);
+
+        let min = Vector2::new(min[0].max(0.0) as f32, min[1].max(0.0) as f32);
+        let max = Vector2::new(
+            if max[0] >= LARGE * 0.5 {
+                std::f32::INFINITY
+            } else {
+                (max[0] as f32).max(min.x)
+            },
+            if max[1] >= LARGE * 0.5 {
+                std::f32::INFINITY
+            } else {
+                (max[1] as f32).max(min.y)
+            },
+        );
+        let preferred = Vector2::new(
+            (preferred[0] as f32).max(min.x).min(max.x),
+            (preferred[1] as f32).max(min.y).min(max.y),
+        );
+
+        SizeTraits {
+            min,
+            max,
+            preferred,
+        }
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let mut state = self.state.borrow_mut();
+        state.update_subviews(ctx, &self.subviews);
+
+        state.solve_container_size([size.x as f64, size.y as f64], ARRANGE_STRENGTH);
+
+        for (sv, sv_state) in self.subviews.iter().zip(state.subviews.iter()) {
+            let [left, top, width, height] = sv_state.vars;
+            let left = state.solver.value(left) as f32;
+            let top = state.solver.value(top) as f32;
+            let width = (state.solver.value(width) as f32).max(0.0);
+            let height = (state.solver.value(height) as f32).max(0.0);
+
+            ctx.set_subview_frame(
+                sv.as_ref(),
+                box2! {
+                    min: [left, top],
+                    max: [left + width, top + height],
+                },
+            );
+        }
+    }
+
+    fn has_same_subviews(&self, other: &dyn Layout) -> bool {
+        if let Some(other) = as_any::Downcast::downcast_ref::<Self>(other) {
+            self.subviews == other.subviews
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cggeom::box2;
+    use try_match::try_match;
+
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::EmptyLayout,
+        uicore::HWnd,
+    };
+
+    fn new_subview(min: f32, preferred: f32) -> HView {
+        let sv = HView::new(Default::default());
+        sv.set_layout(EmptyLayout::new(SizeTraits {
+            min: [min; 2].into(),
+            max: [std::f32::INFINITY; 2].into(),
+            preferred: [preferred; 2].into(),
+        }));
+        sv
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn row(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let sv1 = new_subview(10.0, 20.0);
+        let sv2 = new_subview(10.0, 30.0);
+        let (c, a, b) = (Target::Container, Target::Subview(0), Target::Subview(1));
+
+        let layout = ConstraintLayout::new(vec![sv1.clone(), sv2.clone()]).with_constraints(vec![
+            Constraint::eq(a.left(), c.left() + 10.0),
+            Constraint::eq(b.left(), a.right() + 5.0),
+            Constraint::eq(c.right(), b.right() + 10.0),
+            Constraint::eq(a.top(), c.top() + 10.0),
+            Constraint::eq(c.bottom(), a.bottom() + 10.0),
+            Constraint::eq(a.center_y(), b.center_y()),
+        ]);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(layout);
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        // Everything is in their preferred sizes
+        assert_eq!(
+            sv1.global_frame(),
+            box2! { min: [10.0, 10.0], max: [30.0, 30.0] }
+        );
+        assert_eq!(
+            sv2.global_frame(),
+            box2! { min: [35.0, 5.0], max: [65.0, 35.0] }
+        );
+
+        // The minimum width is `10 + 10 + 5 + 10 + 10`
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        assert_eq!(twm.wnd_attrs(&pal_hwnd).unwrap().min_size, [45, 30]);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn size_traits_update(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let sv = new_subview(10.0, 20.0);
+        let (c, a) = (Target::Container, Target::Subview(0));
+
+        let layout = ConstraintLayout::new(vec![sv.clone()]).with_constraints(vec![
+            Constraint::eq(a.left(), c.left()),
+            Constraint::eq(a.top(), c.top()),
+            Constraint::eq(a.right(), c.right()),
+            Constraint::eq(a.bottom(), c.bottom()),
+        ]);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(layout);
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [0.0, 0.0], max: [20.0, 20.0] }
+        );
+
+        // Raise the minimum size. The window is enlarged to accommodate it.
+        sv.set_layout(EmptyLayout::new(SizeTraits {
+            min: [40.0; 2].into(),
+            max: [std::f32::INFINITY; 2].into(),
+            preferred: [40.0; 2].into(),
+        }));
+        twm.step_unsend();
+
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [0.0, 0.0], max: [40.0, 40.0] }
+        );
+    }
+}
@@ -0,0 +1,798 @@
+//! An incremental linear constraint solver based on the Cassowary algorithm.
+//!
+//! The implementation closely follows [Kiwi], an efficient implementation of
+//! the algorithm.
+//!
+//! [Kiwi]: https://github.com/nucleic/kiwi
+use std::collections::{BTreeMap, HashMap};
+
+/// A variable in [`Solver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var(usize);
+
+/// Identifies a constraint added to [`Solver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ConstraintId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelOp {
+    /// `expr <= 0`
+    Le,
+    /// `expr >= 0`
+    Ge,
+    /// `expr == 0`
+    Eq,
+}
+
+/// The strength of a required constraint.
+pub const REQUIRED: f64 = 1_001_001_000.0;
+
+/// A linear constraint `Σ coef·var + constant (op) 0`.
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub terms: Vec<(Var, f64)>,
+    pub constant: f64,
+    pub op: RelOp,
+    pub strength: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsatisfiableError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum SymbolKind {
+    External,
+    Slack,
+    Error,
+    Dummy,
+}
+
+/// The symbols are ordered by their IDs, which makes the pivoting order
+/// deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Symbol {
+    id: u32,
+    kind: SymbolKind,
+}
+
+impl Symbol {
+    fn is_pivotable(self) -> bool {
+        self.kind == SymbolKind::Slack || self.kind == SymbolKind::Error
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tag {
+    marker: Symbol,
+    other: Option<Symbol>,
+}
+
+#[derive(Debug, Clone)]
+struct EditInfo {
+    cns: ConstraintId,
+    tag: Tag,
+    constant: f64,
+}
+
+fn near_zero(x: f64) -> bool {
+    x.abs() < 1.0e-8
+}
+
+/// A row of the simplex tableau, representing `basic = constant + Σ cells`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Row {
+    constant: f64,
+    cells: BTreeMap<Symbol, f64>,
+}
+
+impl Row {
+    fn new(constant: f64) -> Self {
+        Self {
+            constant,
+            cells: BTreeMap::new(),
+        }
+    }
+
+    /// Add a constant value and return the new constant.
+    fn add(&mut self, value: f64) -> f64 {
+        self.constant += value;
+        self.constant
+    }
+
+    fn insert_symbol(&mut self, sym: Symbol, coef: f64) {
+        let cell = self.cells.entry(sym).or_insert(0.0);
+        *cell += coef;
+        if near_zero(*cell) {
+            self.cells.remove(&sym);
+        }
+    }
+
+    fn insert_row(&mut self, other: &Row, coef: f64) {
+        self.constant += other.constant * coef;
+        for (&sym, &c) in other.cells.iter() {
+            self.insert_symbol(sym, c * coef);
+        }
+    }
+
+    fn remove(&mut self, sym: Symbol) {
+        self.cells.remove(&sym);
+    }
+
+    fn reverse_sign(&mut self) {
+        self.constant = -self.constant;
+        for c in self.cells.values_mut() {
+            *c = -*c;
+        }
+    }
+
+    /// Solve the row for `sym`, assuming the row represents `0 = ...`.
+    fn solve_for(&mut self, sym: Symbol) {
+        let coef = -1.0 / self.cells.remove(&sym).unwrap();
+        self.constant *= coef;
+        for c in self.cells.values_mut() {
+            *c *= coef;
+        }
+    }
+
+    /// Solve the row for `rhs`, assuming the row represents `lhs = ...`.
+    fn solve_for_pair(&mut self, lhs: Symbol, rhs: Symbol) {
+        self.insert_symbol(lhs, -1.0);
+        self.solve_for(rhs);
+    }
+
+    fn coefficient_for(&self, sym: Symbol) -> f64 {
+        self.cells.get(&sym).cloned().unwrap_or(0.0)
+    }
+
+    fn substitute(&mut self, sym: Symbol, row: &Row) {
+        if let Some(coef) = self.cells.remove(&sym) {
+            self.insert_row(row, coef);
+        }
+    }
+}
+
+/// Selects the objective function used by [`Solver::optimize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Objective {
+    Main,
+    Artificial,
+}
+
+/// Records the changes made to the tableau so that they can be reverted by
+/// [`Solver::rollback`].
+#[derive(Debug, Clone)]
+struct UndoLog {
+    /// The old contents of the modified rows, in the modification order.
+    rows: Vec<(Symbol, Option<Row>)>,
+    /// The objective function before the first modification.
+    objective: Option<Row>,
+    num_infeasible_rows: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Solver {
+    cns: HashMap<ConstraintId, (Constraint, Tag)>,
+    next_cns_id: usize,
+    rows: BTreeMap<Symbol, Row>,
+    /// The symbol of each variable, indexed by `Var`.
+    var_syms: Vec<Symbol>,
+    edits: HashMap<Var, EditInfo>,
+    infeasible_rows: Vec<Symbol>,
+    objective: Row,
+    artificial: Option<Row>,
+    next_sym_id: u32,
+    /// Active while a required constraint is being added.
+    undo_log: Option<UndoLog>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn new_symbol(&mut self, kind: SymbolKind) -> Symbol {
+        let id = self.next_sym_id;
+        self.next_sym_id += 1;
+        Symbol { id, kind }
+    }
+
+    pub fn new_var(&mut self) -> Var {
+        let sym = self.new_symbol(SymbolKind::External);
+        self.var_syms.push(sym);
+        Var(self.var_syms.len() - 1)
+    }
+
+    /// Get the current value of a variable.
+    pub fn value(&self, var: Var) -> f64 {
+        let sym = self.var_syms[var.0];
+        self.rows.get(&sym).map(|row| row.constant).unwrap_or(0.0)
+    }
+
+    /// Add a constraint.
+    ///
+    /// If the constraint is unsatisfiable, this method returns
+    /// `Err(UnsatisfiableError)`, leaving the solver unchanged.
+    pub fn add_constraint(&mut self, cns: Constraint) -> Result<ConstraintId, UnsatisfiableError> {
+        // Only required constraints can fail. Record the changes so that we
+        // can roll back the partially-applied changes in that case.
+        if cns.strength >= REQUIRED {
+            self.undo_log = Some(UndoLog {
+                rows: Vec::new(),
+                objective: None,
+                num_infeasible_rows: self.infeasible_rows.len(),
+            });
+        }
+
+        let result = self.add_constraint_row(&cns);
+
+        let undo_log = self.undo_log.take();
+        let tag = match result {
+            Ok(tag) => tag,
+            Err(e) => {
+                self.rollback(undo_log.unwrap());
+                return Err(e);
+            }
+        };
+
+        let id = self.insert_cns(cns, tag);
+        self.optimize(Objective::Main);
+
+        Ok(id)
+    }
+
+    /// Add the row representing a constraint to the tableau.
+    fn add_constraint_row(&mut self, cns: &Constraint) -> Result<Tag, UnsatisfiableError> {
+        let (mut row, tag) = self.create_row(cns);
+
+        let mut subject = choose_subject(&row, &tag);
+
+        if subject.is_none() && row.cells.keys().all(|s| s.kind == SymbolKind::Dummy) {
+            if near_zero(row.constant) {
+                subject = Some(tag.marker);
+            } else {
+                return Err(UnsatisfiableError);
+            }
+        }
+
+        if let Some(subject) = subject {
+            row.solve_for(subject);
+            self.substitute(subject, &row);
+            self.insert_row(subject, row);
+        } else if !self.add_with_artificial_variable(row) {
+            return Err(UnsatisfiableError);
+        }
+
+        Ok(tag)
+    }
+
+    /// Revert the changes recorded in `undo_log`.
+    ///
+    /// `create_row` modifies the objective function only for non-required
+    /// constraints, which never reach here, so the changes made by it don't
+    /// need to be recorded.
+    fn rollback(&mut self, undo_log: UndoLog) {
+        for (sym, row) in undo_log.rows.into_iter().rev() {
+            if let Some(row) = row {
+                self.rows.insert(sym, row);
+            } else {
+                self.rows.remove(&sym);
+            }
+        }
+        if let Some(objective) = undo_log.objective {
+            self.objective = objective;
+        }
+        self.infeasible_rows.truncate(undo_log.num_infeasible_rows);
+        self.artificial = None;
+    }
+
+    /// Record the current contents of a row to the undo log (if any).
+    fn log_row(&mut self, sym: Symbol) {
+        if let Some(undo_log) = &mut self.undo_log {
+            undo_log.rows.push((sym, self.rows.get(&sym).cloned()));
+        }
+    }
+
+    /// Record the current objective function to the undo log (if any) if it
+    /// hasn't been recorded yet.
+    fn log_objective(&mut self) {
+        if let Some(undo_log) = &mut self.undo_log {
+            if undo_log.objective.is_none() {
+                undo_log.objective = Some(self.objective.clone());
+            }
+        }
+    }
+
+    fn insert_row(&mut self, sym: Symbol, row: Row) {
+        self.log_row(sym);
+        self.rows.insert(sym, row);
+    }
+
+    fn remove_row(&mut self, sym: Symbol) -> Option<Row> {
+        self.log_row(sym);
+        self.rows.remove(&sym)
+    }
+
+    fn insert_cns(&mut self, cns: Constraint, tag: Tag) -> ConstraintId {
+        let id = ConstraintId(self.next_cns_id);
+        self.next_cns_id += 1;
+        self.cns.insert(id, (cns, tag));
+        id
+    }
+
+    /// Remove a constraint.
+    ///
+    /// Panics if `id` does not refer to an existing constraint.
+    pub fn remove_constraint(&mut self, id: ConstraintId) {
+        let (cns, tag) = self.cns.remove(&id).expect("unknown constraint");
+
+        self.remove_error_effects(&cns, &tag);
+
+        if self.remove_row(tag.marker).is_none() {
+            let leaving = self
+                .marker_leaving_row(tag.marker)
+                .expect("failed to find a leaving row");
+            let mut row = self.remove_row(leaving).unwrap();
+            row.solve_for_pair(leaving, tag.marker);
+            self.substitute(tag.marker, &row);
+        }
+
+        self.optimize(Objective::Main);
+    }
+
+    /// Add an edit variable, which can be updated by `suggest_value`.
+    ///
+    /// `strength` must be weaker than `REQUIRED`. Panics if `var` is already
+    /// an edit variable.
+    pub fn add_edit_var(&mut self, var: Var, strength: f64) {
+        assert!(strength < REQUIRED);
+        assert!(!self.edits.contains_key(&var), "already an edit variable");
+
+        let cns = self
+            .add_constraint(Constraint {
+                terms: vec![(var, 1.0)],
+                constant: 0.0,
+                op: RelOp::Eq,
+                strength,
+            })
+            .expect("a non-required constraint can't be unsatisfiable");
+
+        let tag = self.cns[&cns].1;
+        self.edits.insert(
+            var,
+            EditInfo {
+                cns,
+                tag,
+                constant: 0.0,
+            },
+        );
+    }
+
+    /// Remove an edit variable. Panics if `var` is not an edit variable.
+    pub fn remove_edit_var(&mut self, var: Var) {
+        let info = self.edits.remove(&var).expect("not an edit variable");
+        self.remove_constraint(info.cns);
+    }
+
+    /// Suggest a value for an edit variable. Panics if `var` is not an edit
+    /// variable.
+    pub fn suggest_value(&mut self, var: Var, value: f64) {
+        let info = self.edits.get_mut(&var).expect("not an edit variable");
+        let delta = value - info.constant;
+        info.constant = value;
+        let tag = info.tag;
+
+        if let Some(row) = self.rows.get_mut(&tag.marker) {
+            if row.add(-delta) < 0.0 {
+                self.infeasible_rows.push(tag.marker);
+            }
+        } else if let Some(row) = tag.other.and_then(|other| self.rows.get_mut(&other)) {
+            if row.add(delta) < 0.0 {
+                self.infeasible_rows.push(tag.other.unwrap());
+            }
+        } else {
+            for (&sym, row) in self.rows.iter_mut() {
+                let coef = row.coefficient_for(tag.marker);
+                if coef != 0.0 && row.add(delta * coef) < 0.0 && sym.kind != SymbolKind::External {
+                    self.infeasible_rows.push(sym);
+                }
+            }
+        }
+
+        self.dual_optimize();
+    }
+
+    fn create_row(&mut self, cns: &Constraint) -> (Row, Tag) {
+        let mut row = Row::new(cns.constant);
+
+        for &(var, coef) in cns.terms.iter() {
+            if near_zero(coef) {
+                continue;
+            }
+            let sym = self.var_syms[var.0];
+            if let Some(basic_row) = self.rows.get(&sym) {
+                row.insert_row(basic_row, coef);
+            } else {
+                row.insert_symbol(sym, coef);
+            }
+        }
+
+        let tag = match cns.op {
+            RelOp::Le | RelOp::Ge => {
+                let coef = if cns.op == RelOp::Le { 1.0 } else { -1.0 };
+                let slack = self.new_symbol(SymbolKind::Slack);
+                row.insert_symbol(slack, coef);
+
+                if cns.strength < REQUIRED {
+                    let error = self.new_symbol(SymbolKind::Error);
+                    row.insert_symbol(error, -coef);
+                    self.objective.insert_symbol(error, cns.strength);
+                    Tag {
+                        marker: slack,
+                        other: Some(error),
+                    }
+                } else {
+                    Tag {
+                        marker: slack,
+                        other: None,
+                    }
+                }
+            }
+            RelOp::Eq => {
+                if cns.strength < REQUIRED {
+                    let err_plus = self.new_symbol(SymbolKind::Error);
+                    let err_minus = self.new_symbol(SymbolKind::Error);
+                    row.insert_symbol(err_plus, -1.0);
+                    row.insert_symbol(err_minus, 1.0);
+                    self.objective.insert_symbol(err_plus, cns.strength);
+                    self.objective.insert_symbol(err_minus, cns.strength);
+                    Tag {
+                        marker: err_plus,
+                        other: Some(err_minus),
+                    }
+                } else {
+                    let dummy = self.new_symbol(SymbolKind::Dummy);
+                    row.insert_symbol(dummy, 1.0);
+                    Tag {
+                        marker: dummy,
+                        other: None,
+                    }
+                }
+            }
+        };
+
+        if row.constant < 0.0 {
+            row.reverse_sign();
+        }
+
+        (row, tag)
+    }
+
+    /// Remove the error variables of a constraint from the objective function.
+    fn remove_error_effects(&mut self, cns: &Constraint, tag: &Tag) {
+        let markers = std::iter::once(tag.marker).chain(tag.other);
+        for marker in markers.filter(|s| s.kind == SymbolKind::Error) {
+            if let Some(row) = self.rows.get(&marker) {
+                self.objective.insert_row(row, -cns.strength);
+            } else {
+                self.objective.insert_symbol(marker, -cns.strength);
+            }
+        }
+    }
+
+    fn add_with_artificial_variable(&mut self, row: Row) -> bool {
+        let art = self.new_symbol(SymbolKind::Slack);
+        self.insert_row(art, row.clone());
+        self.artificial = Some(row);
+
+        self.optimize(Objective::Artificial);
+        let success = near_zero(self.artificial.take().unwrap().constant);
+
+        if let Some(mut row) = self.remove_row(art) {
+            if row.cells.is_empty() {
+                return success;
+            }
+            let entering = match row.cells.keys().cloned().find(|s| s.is_pivotable()) {
+                Some(x) => x,
+                None => return false,
+            };
+            row.solve_for_pair(art, entering);
+            self.substitute(entering, &row);
+            self.insert_row(entering, row);
+        }
+
+        for (&sym, row) in self.rows.iter_mut() {
+            if row.cells.contains_key(&art) {
+                if let Some(undo_log) = &mut self.undo_log {
+                    undo_log.rows.push((sym, Some(row.clone())));
+                }
+                row.remove(art);
+            }
+        }
+        if self.objective.cells.contains_key(&art) {
+            self.log_objective();
+            self.objective.remove(art);
+        }
+
+        success
+    }
+
+    fn substitute(&mut self, sym: Symbol, row: &Row) {
+        for (&basic, basic_row) in self.rows.iter_mut() {
+            if !basic_row.cells.contains_key(&sym) {
+                continue;
+            }
+            if let Some(undo_log) = &mut self.undo_log {
+                undo_log.rows.push((basic, Some(basic_row.clone())));
+            }
+            basic_row.substitute(sym, row);
+            if basic.kind != SymbolKind::External && basic_row.constant < 0.0 {
+                self.infeasible_rows.push(basic);
+            }
+        }
+        if self.objective.cells.contains_key(&sym) {
+            self.log_objective();
+            self.objective.substitute(sym, row);
+        }
+        if let Some(artificial) = &mut self.artificial {
+            artificial.substitute(sym, row);
+        }
+    }
+
+    fn optimize(&mut self, objective: Objective) {
+        loop {
+            let objective_row = match objective {
+                Objective::Main => &self.objective,
+                Objective::Artificial => self.artificial.as_ref().unwrap(),
+            };
+
+            // Find the entering symbol
+            let entering = objective_row
+                .cells
+                .iter()
+                .find(|(s, &c)| s.kind != SymbolKind::Dummy && c < 0.0)
+                .map(|(&s, _)| s);
+            let entering = match entering {
+                Some(x) => x,
+                None => return,
+            };
+
+            // Find the leaving row
+            let mut ratio = std::f64::MAX;
+            let mut leaving = None;
+            for (&sym, row) in self.rows.iter() {
+                if sym.kind == SymbolKind::External {
+                    continue;
+                }
+                let coef = row.coefficient_for(entering);
+                if coef < 0.0 {
+                    let r = -row.constant / coef;
+                    if r < ratio {
+                        ratio = r;
+                        leaving = Some(sym);
+                    }
+                }
+            }
+            let leaving = leaving.expect("the objective function is unbounded");
+
+            let mut row = self.remove_row(leaving).unwrap();
+            row.solve_for_pair(leaving, entering);
+            self.substitute(entering, &row);
+            self.insert_row(entering, row);
+        }
+    }
+
+    fn dual_optimize(&mut self) {
+        while let Some(leaving) = self.infeasible_rows.pop() {
+            let row = match self.rows.get(&leaving) {
+                Some(row) if row.constant < 0.0 => row,
+                _ => continue,
+            };
+
+            // Find the entering symbol
+            let mut ratio = std::f64::MAX;
+            let mut entering = None;
+            for (&sym, &coef) in row.cells.iter() {
+                if coef > 0.0 && sym.kind != SymbolKind::Dummy {
+                    let r = self.objective.coefficient_for(sym) / coef;
+                    if r < ratio {
+                        ratio = r;
+                        entering = Some(sym);
+                    }
+                }
+            }
+            let entering = entering.expect("dual optimization failed");
+
+            let mut row = self.remove_row(leaving).unwrap();
+            row.solve_for_pair(leaving, entering);
+            self.substitute(entering, &row);
+            self.insert_row(entering, row);
+        }
+    }
+
+    /// Find the row to pivot on when removing a constraint whose marker is
+    /// not basic.
+    fn marker_leaving_row(&self, marker: Symbol) -> Option<Symbol> {
+        let (mut r1, mut r2) = (std::f64::MAX, std::f64::MAX);
+        let (mut first, mut second, mut third) = (None, None, None);
+
+        for (&sym, row) in self.rows.iter() {
+            let coef = row.coefficient_for(marker);
+            if coef == 0.0 {
+                continue;
+            }
+            if sym.kind == SymbolKind::External {
+                third = Some(sym);
+            } else if coef < 0.0 {
+                let r = -row.constant / coef;
+                if r < r1 {
+                    r1 = r;
+                    first = Some(sym);
+                }
+            } else {
+                let r = row.constant / coef;
+                if r < r2 {
+                    r2 = r;
+                    second = Some(sym);
+                }
+            }
+        }
+
+        first.or(second).or(third)
+    }
+}
+
+fn choose_subject(row: &Row, tag: &Tag) -> Option<Symbol> {
+    if let Some(&sym) = row.cells.keys().find(|s| s.kind == SymbolKind::External) {
+        return Some(sym);
+    }
+
+    std::iter::once(tag.marker)
+        .chain(tag.other)
+        .find(|&s| s.is_pivotable() && row.coefficient_for(s) < 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cns(terms: &[(Var, f64)], constant: f64, op: RelOp, strength: f64) -> Constraint {
+        Constraint {
+            terms: terms.to_vec(),
+            constant,
+            op,
+            strength,
+        }
+    }
+
+    fn assert_approx_eq(x: f64, y: f64) {
+        assert!((x - y).abs() < 1.0e-6, "{} != {}", x, y);
+    }
+
+    #[test]
+    fn simple() {
+        let mut solver = Solver::new();
+        let x = solver.new_var();
+        let y = solver.new_var();
+
+        // x + y == 100
+        solver
+            .add_constraint(cns(&[(x, 1.0), (y, 1.0)], -100.0, RelOp::Eq, REQUIRED))
+            .unwrap();
+        // x >= 70
+        solver
+            .add_constraint(cns(&[(x, 1.0)], -70.0, RelOp::Ge, REQUIRED))
+            .unwrap();
+        // x == 50 (weak)
+        solver
+            .add_constraint(cns(&[(x, 1.0)], -50.0, RelOp::Eq, 1.0))
+            .unwrap();
+
+        assert_approx_eq(solver.value(x), 70.0);
+        assert_approx_eq(solver.value(y), 30.0);
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        let mut solver = Solver::new();
+        let x = solver.new_var();
+
+        solver
+            .add_constraint(cns(&[(x, 1.0)], -10.0, RelOp::Ge, REQUIRED))
+            .unwrap();
+        assert_eq!(
+            solver
+                .add_constraint(cns(&[(x, 1.0)], -5.0, RelOp::Le, REQUIRED))
+                .unwrap_err(),
+            UnsatisfiableError
+        );
+
+        // The solver should be still usable
+        solver
+            .add_constraint(cns(&[(x, 1.0)], -20.0, RelOp::Eq, 1.0))
+            .unwrap();
+        assert_approx_eq(solver.value(x), 20.0);
+    }
+
+    #[test]
+    fn unsatisfiable_rollback() {
+        let mut solver = Solver::new();
+        let x = solver.new_var();
+        let y = solver.new_var();
+        let z = solver.new_var();
+
+        // x + y == 100
+        solver
+            .add_constraint(cns(&[(x, 1.0), (y, 1.0)], -100.0, RelOp::Eq, REQUIRED))
+            .unwrap();
+        // x >= 70
+        solver
+            .add_constraint(cns(&[(x, 1.0)], -70.0, RelOp::Ge, REQUIRED))
+            .unwrap();
+        // z == x (weak)
+        solver
+            .add_constraint(cns(&[(z, 1.0), (x, -1.0)], 0.0, RelOp::Eq, 1.0))
+            .unwrap();
+
+        let rows = solver.rows.clone();
+        let objective = solver.objective.clone();
+
+        // y >= 40 conflicts with `x + y == 100` and `x >= 70`
+        assert_eq!(
+            solver
+                .add_constraint(cns(&[(y, 1.0)], -40.0, RelOp::Ge, REQUIRED))
+                .unwrap_err(),
+            UnsatisfiableError
+        );
+        // x + y == 0 conflicts with `x + y == 100`
+        assert_eq!(
+            solver
+                .add_constraint(cns(&[(y, 1.0), (x, 1.0)], 0.0, RelOp::Eq, REQUIRED))
+                .unwrap_err(),
+            UnsatisfiableError
+        );
+
+        // The tableau is restored
+        assert_eq!(solver.rows, rows);
+        assert_eq!(solver.objective, objective);
+        assert!(solver.undo_log.is_none());
+
+        solver
+            .add_constraint(cns(&[(y, 1.0)], -20.0, RelOp::Eq, REQUIRED))
+            .unwrap();
+        assert_approx_eq(solver.value(x), 80.0);
+        assert_approx_eq(solver.value(y), 20.0);
+        assert_approx_eq(solver.value(z), 80.0);
+    }
+
+    #[test]
+    fn edit_and_remove() {
+        let mut solver = Solver::new();
+        let x = solver.new_var();
+        let y = solver.new_var();
+
+        // y == 2x + 1
+        solver
+            .add_constraint(cns(&[(y, 1.0), (x, -2.0)], -1.0, RelOp::Eq, REQUIRED))
+            .unwrap();
+        // y <= 50
+        let limit = solver
+            .add_constraint(cns(&[(y, 1.0)], -50.0, RelOp::Le, 1000.0))
+            .unwrap();
+
+        solver.add_edit_var(x, 1.0e6);
+        solver.suggest_value(x, 10.0);
+        assert_approx_eq(solver.value(y), 21.0);
+
+        // The edit variable wins over the weaker constraint
+        solver.suggest_value(x, 40.0);
+        assert_approx_eq(solver.value(y), 81.0);
+
+        solver.remove_edit_var(x);
+        solver.add_edit_var(x, 1.0);
+        solver.suggest_value(x, 40.0);
+        assert_approx_eq(solver.value(y), 50.0);
+
+        solver.remove_constraint(limit);
+        assert_approx_eq(solver.value(y), 81.0);
+    }
+}
@@ -13,6 +13,9 @@ macro_rules! shuf_f32x4 {
 }
 
 /// A `Layout` that overlaps a subview to fill the owning view.
+///
+/// The left and right margins are swapped if the user interface direction is
+/// right-to-left.
 #[derive(Debug, Clone)]
 pub struct FillLayout {
     subview: [HView; 1],
@@ -147,13 +150,15 @@ impl Layout for FillLayout {
             )
             .write_to_slice_unaligned(&mut margin);
 
-        ctx.set_subview_frame(
-            self.subview[0].as_ref(),
+        let frame = ctx.mirror_frame_if_rtl(
             box2! {
                 min: [margin[3], margin[0]],
                 max: [size.x - margin[1], size.y - margin[2]],
             },
+            size.x,
         );
+
+        ctx.set_subview_frame(self.subview[0].as_ref(), frame);
     }
 
     fn has_same_subviews(&self, other: &dyn Layout) -> bool {
@@ -173,7 +178,7 @@ mod tests {
     use crate::{
         testing::{prelude::*, use_testing_wm},
         ui::layouts::EmptyLayout,
-        uicore::HWnd,
+        uicore::{HWnd, UiDirection},
     };
 
     #[use_testing_wm(testing = "crate::testing")]
@@ -204,4 +209,98 @@ mod tests {
             box2! { min: [0.0, 0.0], max: [50.0, 50.0] }
         );
     }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn rtl(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let sv = HView::new(Default::default());
+        sv.set_layout(EmptyLayout::new(SizeTraits {
+            min: [20.0; 2].into(),
+            max: [20.0; 2].into(),
+            preferred: [20.0; 2].into(),
+        }));
+
+        // Follow the system setting
+        twm.set_default_ui_direction(UiDirection::Rtl);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(sv.clone()).with_margin([1.0, 2.0, 3.0, 4.0]));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        assert_eq!(wnd.ui_direction(), UiDirection::Rtl);
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [2.0, 1.0], max: [22.0, 21.0] }
+        );
+
+        // Override the window's direction
+        wnd.set_ui_direction(Some(UiDirection::Ltr));
+        twm.step_unsend();
+
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [4.0, 1.0], max: [24.0, 21.0] }
+        );
+
+        // Override the view's direction
+        wnd.content_view().set_ui_direction(Some(UiDirection::Rtl));
+        twm.step_unsend();
+
+        assert_eq!(sv.ui_direction(), UiDirection::Rtl);
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [2.0, 1.0], max: [22.0, 21.0] }
+        );
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn rtl_move_to_another_superview(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let sv = HView::new(Default::default());
+        sv.set_layout(EmptyLayout::new(SizeTraits {
+            min: [20.0; 2].into(),
+            max: [20.0; 2].into(),
+            preferred: [20.0; 2].into(),
+        }));
+
+        let inner = HView::new(Default::default());
+        inner.set_layout(FillLayout::new(sv.clone()).with_margin([1.0, 2.0, 3.0, 4.0]));
+
+        let parent_ltr = HView::new(Default::default());
+        parent_ltr.set_ui_direction(Some(UiDirection::Ltr));
+        parent_ltr.set_layout(FillLayout::new(inner.clone()));
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(parent_ltr.clone()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        assert_eq!(sv.ui_direction(), UiDirection::Ltr);
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [4.0, 1.0], max: [24.0, 21.0] }
+        );
+
+        // Move `inner` to a superview with the opposite direction
+        let parent_rtl = HView::new(Default::default());
+        parent_rtl.set_ui_direction(Some(UiDirection::Rtl));
+        parent_ltr.set_layout(EmptyLayout::new(SizeTraits::default()));
+        parent_rtl.set_layout(FillLayout::new(inner.clone()));
+        wnd.content_view()
+            .set_layout(FillLayout::new(parent_rtl.clone()));
+        twm.step_unsend();
+
+        assert_eq!(sv.ui_direction(), UiDirection::Rtl);
+        assert_eq!(
+            sv.global_frame(),
+            box2! { min: [2.0, 1.0], max: [22.0, 21.0] }
+        );
+    }
 }
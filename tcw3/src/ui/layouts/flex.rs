@@ -0,0 +1,740 @@
+use alt_fp::FloatOrd;
+use cggeom::box2;
+use cgmath::Vector2;
+
+use crate::uicore::{HView, Layout, LayoutCtx, SizeTraits};
+
+/// A `Layout` that arranges subviews along a main axis, distributing
+/// the available space according to grow and shrink factors, similarly to
+/// CSS's flexible box layout.
+///
+/// The arrangement is mirrored horizontally if the user interface direction is
+/// right-to-left.
+///
+/// `size_traits` assumes all items fit in a single line. If wrapping is
+/// enabled and the items don't fit in the main axis, the wrapped lines might
+/// overflow the container along the cross axis.
+#[derive(Debug, Clone)]
+pub struct FlexLayout {
+    /// Each element corresponds to the element in `items` with an identical
+    /// index. `HView`s are stored in a separate `Vec` because
+    /// `Layout::subviews` wants `&[HView]`.
+    subviews: Box<[HView]>,
+    items: Box<[FlexItem]>,
+    direction: FlexDirection,
+    wrap: bool,
+    justify: FlexJustify,
+    align: FlexAlign,
+    margin: [f32; 4],
+    spacing: f32,
+    line_spacing: f32,
+}
+
+/// The main axis of [`FlexLayout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlexDirection {
+    /// Items are placed from left to right.
+    Row,
+    /// Items are placed from top to bottom.
+    Column,
+}
+
+/// Specifies how [`FlexLayout`] distributes the remaining space along the main
+/// axis after growing items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlexJustify {
+    /// Items are packed toward the start of the line.
+    Start,
+    /// Items are packed toward the end of the line.
+    End,
+    /// Items are packed toward the center of the line.
+    Center,
+    /// The remaining space is distributed between items.
+    SpaceBetween,
+    /// The remaining space is distributed around items. The spaces at the
+    /// both ends are half as large as the ones between items.
+    SpaceAround,
+    /// The remaining space is distributed evenly between items and the both
+    /// ends.
+    SpaceEvenly,
+}
+
+/// Specifies how [`FlexLayout`] positions an item along the cross axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlexAlign {
+    /// The item is aligned to the start of the line.
+    Start,
+    /// The item is aligned to the end of the line.
+    End,
+    /// The item is centered in the line.
+    Center,
+    /// The item is stretched to fill the line.
+    Stretch,
+}
+
+/// The per-item parameters of [`FlexLayout`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexItem {
+    /// The factor by which the item grows relative to the other items when
+    /// there's extra space in the line. Defaults to `0`.
+    pub grow: f32,
+    /// The factor by which the item shrinks relative to the other items when
+    /// there's not enough space in the line. The factor is weighted by
+    /// the item's preferred size. Defaults to `1`. Items with zero shrink
+    /// factors never get smaller than their preferred sizes.
+    pub shrink: f32,
+    /// The margin around the item. Edges are specified in the clock-wise
+    /// order, starting from top.
+    pub margin: [f32; 4],
+    /// Overrides [`FlexLayout`]'s cross axis alignment for this item.
+    pub align: Option<FlexAlign>,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlexItem {
+    /// Construct a `FlexItem` with the default parameters.
+    pub const fn new() -> Self {
+        Self {
+            grow: 0.0,
+            shrink: 1.0,
+            margin: [0.0; 4],
+            align: None,
+        }
+    }
+
+    /// Update the grow factor and return a new `FlexItem`, consuming `self`.
+    pub const fn with_grow(self, grow: f32) -> Self {
+        Self { grow, ..self }
+    }
+
+    /// Update the shrink factor and return a new `FlexItem`, consuming `self`.
+    pub const fn with_shrink(self, shrink: f32) -> Self {
+        Self { shrink, ..self }
+    }
+
+    /// Update the margin value with a single value used for all four edges and
+    /// return a new `FlexItem`, consuming `self`.
+    pub const fn with_uniform_margin(self, margin: f32) -> Self {
+        Self {
+            margin: [margin; 4],
+            ..self
+        }
+    }
+
+    /// Update the margin value with four values used for respective edges and
+    /// return a new `FlexItem`, consuming `self`.
+    pub const fn with_margin(self, margin: [f32; 4]) -> Self {
+        Self { margin, ..self }
+    }
+
+    /// Update the cross axis alignment and return a new `FlexItem`, consuming
+    /// `self`.
+    pub const fn with_align(self, align: FlexAlign) -> Self {
+        Self {
+            align: Some(align),
+            ..self
+        }
+    }
+}
+
+impl FlexLayout {
+    /// Construct a `FlexLayout` from a set of tuples `(view, item)`.
+    pub fn new(
+        direction: FlexDirection,
+        items: impl IntoIterator<Item = (HView, FlexItem)>,
+    ) -> Self {
+        let (subviews, items): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+
+        Self {
+            subviews: subviews.into(),
+            items: items.into(),
+            direction,
+            wrap: false,
+            justify: FlexJustify::Start,
+            align: FlexAlign::Stretch,
+            margin: [0.0; 4],
+            spacing: 0.0,
+            line_spacing: 0.0,
+        }
+    }
+
+    /// Construct a `FlexLayout` that places items from left to right.
+    pub fn row(items: impl IntoIterator<Item = (HView, FlexItem)>) -> Self {
+        Self::new(FlexDirection::Row, items)
+    }
+
+    /// Construct a `FlexLayout` that places items from top to bottom.
+    pub fn column(items: impl IntoIterator<Item = (HView, FlexItem)>) -> Self {
+        Self::new(FlexDirection::Column, items)
+    }
+
+    /// Enable or disable wrapping and return a new `FlexLayout`, consuming
+    /// `self`. Wrapping is disabled by default.
+    pub fn with_wrap(self, wrap: bool) -> Self {
+        Self { wrap, ..self }
+    }
+
+    /// Update the main axis alignment and return a new `FlexLayout`, consuming
+    /// `self`. Defaults to `FlexJustify::Start`.
+    pub fn with_justify(self, justify: FlexJustify) -> Self {
+        Self { justify, ..self }
+    }
+
+    /// Update the cross axis alignment and return a new `FlexLayout`,
+    /// consuming `self`. Defaults to `FlexAlign::Stretch`.
+    pub fn with_align(self, align: FlexAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    /// Update the margin value with a single value used for all four edges and
+    /// return a new `FlexLayout`, consuming `self`.
+    pub fn with_uniform_margin(self, margin: f32) -> Self {
+        Self {
+            margin: [margin; 4],
+            ..self
+        }
+    }
+
+    /// Update the margin value with four values used for respective edges and
+    /// return a new `FlexLayout`, consuming `self`.
+    pub fn with_margin(self, margin: [f32; 4]) -> Self {
+        Self { margin, ..self }
+    }
+
+    /// Update the spacing between adjacent items in a line and return a new
+    /// `FlexLayout`, consuming `self`.
+    pub fn with_spacing(self, spacing: f32) -> Self {
+        Self { spacing, ..self }
+    }
+
+    /// Update the spacing between wrapped lines and return a new
+    /// `FlexLayout`, consuming `self`.
+    pub fn with_line_spacing(self, line_spacing: f32) -> Self {
+        Self {
+            line_spacing,
+            ..self
+        }
+    }
+
+    /// Get the index of the main axis (`0` = x, `1` = y).
+    fn main_axis(&self) -> usize {
+        match self.direction {
+            FlexDirection::Row => 0,
+            FlexDirection::Column => 1,
+        }
+    }
+}
+
+/// Get the sum of the margins along the specified axis.
+fn margin_sum(margin: &[f32; 4], axis: usize) -> f32 {
+    if axis == 0 {
+        margin[1] + margin[3]
+    } else {
+        margin[0] + margin[2]
+    }
+}
+
+/// Get the margin at the start edge (left or top) of the specified axis.
+fn margin_start(margin: &[f32; 4], axis: usize) -> f32 {
+    if axis == 0 {
+        margin[3]
+    } else {
+        margin[0]
+    }
+}
+
+/// Distribute `amount` among `sizes` in proportion to `weights`. Each element
+/// of `sizes` is clamped by the corresponding element of `limits`, in which
+/// case the remaining amount is redistributed among the other elements.
+///
+/// `limits` must be upper bounds if `amount` is positive; lower bounds
+/// otherwise.
+fn distribute(sizes: &mut [f32], weights: &[f32], limits: &[f32], mut amount: f32) {
+    let growing = amount > 0.0;
+    let exceeds = |size: f32, limit: f32| {
+        if growing {
+            size > limit
+        } else {
+            size < limit
+        }
+    };
+
+    let mut frozen: Vec<bool> = weights.iter().map(|&w| w.is_nan() || w <= 0.0).collect();
+
+    // Each iteration either finishes or freezes at least one element, so
+    // this terminates
+    loop {
+        let total_weight: f32 = (weights.iter().zip(frozen.iter()))
+            .filter(|(_, &frozen)| !frozen)
+            .map(|(&w, _)| w)
+            .sum();
+
+        if total_weight <= 0.0 || amount == 0.0 {
+            break;
+        }
+
+        let factor = amount / total_weight;
+
+        // Clamp the violating elements
+        let mut new_amount = amount;
+        let mut any_frozen = false;
+        for (((size, &weight), &limit), frozen) in (sizes.iter_mut())
+            .zip(weights.iter())
+            .zip(limits.iter())
+            .zip(frozen.iter_mut())
+        {
+            if !*frozen && exceeds(*size + weight * factor, limit) {
+                new_amount -= limit - *size;
+                *size = limit;
+                *frozen = true;
+                any_frozen = true;
+            }
+        }
+
+        // Even if the frozen elements didn't take any amount (because they
+        // were already at their limits), `factor` has to be recalculated
+        // without their weights
+        if !any_frozen {
+            // No violations; distribute the amount
+            for ((size, &weight), &frozen) in
+                (sizes.iter_mut()).zip(weights.iter()).zip(frozen.iter())
+            {
+                if !frozen {
+                    *size += weight * factor;
+                }
+            }
+            break;
+        }
+
+        if (new_amount > 0.0) != growing {
+            break;
+        }
+        amount = new_amount;
+    }
+}
+
+impl Layout for FlexLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        let main = self.main_axis();
+        let cross = 1 - main;
+
+        let mut traits = SizeTraits {
+            min: Vector2::new(0.0, 0.0),
+            max: Vector2::new(std::f32::INFINITY, std::f32::INFINITY),
+            preferred: Vector2::new(0.0, 0.0),
+        };
+
+        for (subview, item) in self.subviews.iter().zip(self.items.iter()) {
+            let st = ctx.subview_size_traits(subview.as_ref());
+
+            let main_margin = margin_sum(&item.margin, main);
+            let cross_margin = margin_sum(&item.margin, cross);
+
+            // Items that can't shrink can't get smaller than their preferred
+            // sizes
+            let main_min = if item.shrink > 0.0 {
+                st.min[main]
+            } else {
+                st.preferred[main]
+            } + main_margin;
+
+            if self.wrap {
+                // Each item can be placed in its own line
+                traits.min[main] = traits.min[main].fmax(main_min);
+            } else {
+                traits.min[main] += main_min;
+            }
+            traits.preferred[main] += st.preferred[main] + main_margin;
+
+            traits.min[cross] = traits.min[cross].fmax(st.min[cross] + cross_margin);
+            traits.preferred[cross] =
+                traits.preferred[cross].fmax(st.preferred[cross] + cross_margin);
+        }
+
+        let total_spacing = self.spacing * self.items.len().saturating_sub(1) as f32;
+        if !self.wrap {
+            traits.min[main] += total_spacing;
+        }
+        traits.preferred[main] += total_spacing;
+
+        for axis in 0..2 {
+            let m = margin_sum(&self.margin, axis);
+            traits.min[axis] += m;
+            traits.preferred[axis] += m;
+        }
+
+        traits.preferred.x = traits.preferred.x.fmax(traits.min.x);
+        traits.preferred.y = traits.preferred.y.fmax(traits.min.y);
+
+        traits
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let main = self.main_axis();
+        let cross = 1 - main;
+
+        let inner_main = size[main] - margin_sum(&self.margin, main);
+        let inner_cross = size[cross] - margin_sum(&self.margin, cross);
+
+        let traits: Vec<SizeTraits> = (self.subviews.iter())
+            .map(|sv| ctx.subview_size_traits(sv.as_ref()))
+            .collect();
+
+        // The outer preferred size (including margins) of each item along the
+        // main axis
+        let outer_pref =
+            |i: usize| traits[i].preferred[main] + margin_sum(&self.items[i].margin, main);
+
+        // Break the items into lines
+        let mut lines: Vec<std::ops::Range<usize>> = Vec::new();
+        if self.wrap {
+            let mut start = 0;
+            let mut used = 0.0;
+            for i in 0..self.items.len() {
+                let new_used = if i == start {
+                    outer_pref(i)
+                } else {
+                    used + self.spacing + outer_pref(i)
+                };
+
+                if i > start && new_used > inner_main {
+                    lines.push(start..i);
+                    start = i;
+                    used = outer_pref(i);
+                } else {
+                    used = new_used;
+                }
+            }
+            if start < self.items.len() {
+                lines.push(start..self.items.len());
+            }
+        } else if !self.items.is_empty() {
+            lines.push(0..self.items.len());
+        }
+
+        // Calculate the cross size of each line
+        let mut line_cross_sizes: Vec<f32> = if lines.len() == 1 {
+            vec![inner_cross]
+        } else {
+            (lines.iter())
+                .map(|range| {
+                    range.clone().fold(0.0f32, |acc, i| {
+                        acc.fmax(
+                            traits[i].preferred[cross] + margin_sum(&self.items[i].margin, cross),
+                        )
+                    })
+                })
+                .collect()
+        };
+
+        // Distribute the extra space along the cross axis evenly
+        if lines.len() > 1 {
+            let used: f32 =
+                line_cross_sizes.iter().sum::<f32>() + self.line_spacing * (lines.len() - 1) as f32;
+            let extra = inner_cross - used;
+            if extra > 0.0 {
+                let extra_per_line = extra / lines.len() as f32;
+                for line_cross_size in line_cross_sizes.iter_mut() {
+                    *line_cross_size += extra_per_line;
+                }
+            }
+        }
+
+        let mut cross_pos = margin_start(&self.margin, cross);
+
+        for (range, &line_cross_size) in lines.iter().zip(line_cross_sizes.iter()) {
+            let range = range.clone();
+            let num_items = range.len();
+
+            // Determine the main sizes of the items
+            let mut sizes: Vec<f32> = range.clone().map(|i| traits[i].preferred[main]).collect();
+            let used: f32 =
+                range.clone().map(outer_pref).sum::<f32>() + self.spacing * (num_items - 1) as f32;
+            let free = inner_main - used;
+
+            if free > 0.0 {
+                let weights: Vec<f32> = range.clone().map(|i| self.items[i].grow).collect();
+                let limits: Vec<f32> = range.clone().map(|i| traits[i].max[main]).collect();
+                distribute(&mut sizes, &weights, &limits, free);
+            } else if free < 0.0 {
+                let weights: Vec<f32> = (range.clone())
+                    .map(|i| self.items[i].shrink * traits[i].preferred[main])
+                    .collect();
+                let limits: Vec<f32> = range.clone().map(|i| traits[i].min[main]).collect();
+                distribute(&mut sizes, &weights, &limits, free);
+            }
+
+            // Distribute the remaining space
+            let used: f32 = range
+                .clone()
+                .zip(sizes.iter())
+                .map(|(i, &size)| size + margin_sum(&self.items[i].margin, main))
+                .sum::<f32>()
+                + self.spacing * (num_items - 1) as f32;
+            let remaining = (inner_main - used).fmax(0.0);
+
+            let (mut main_pos, gap) = match self.justify {
+                FlexJustify::Start => (0.0, 0.0),
+                FlexJustify::End => (remaining, 0.0),
+                FlexJustify::Center => (remaining * 0.5, 0.0),
+                FlexJustify::SpaceBetween if num_items > 1 => {
+                    (0.0, remaining / (num_items - 1) as f32)
+                }
+                FlexJustify::SpaceBetween => (0.0, 0.0),
+                FlexJustify::SpaceAround => {
+                    let gap = remaining / num_items as f32;
+                    (gap * 0.5, gap)
+                }
+                FlexJustify::SpaceEvenly => {
+                    let gap = remaining / (num_items + 1) as f32;
+                    (gap, gap)
+                }
+            };
+            main_pos += margin_start(&self.margin, main);
+
+            for (i, &main_size) in range.zip(sizes.iter()) {
+                let item = &self.items[i];
+                let st = &traits[i];
+
+                main_pos += margin_start(&item.margin, main);
+
+                // Determine the cross size and position
+                let available = line_cross_size - margin_sum(&item.margin, cross);
+                let align = item.align.unwrap_or(self.align);
+
+                let cross_size = if align == FlexAlign::Stretch {
+                    available
+                } else {
+                    st.preferred[cross].fmin(available)
+                };
+                let cross_size = cross_size.fmin(st.max[cross]).fmax(st.min[cross]);
+
+                let cross_offset = match align {
+                    FlexAlign::Start | FlexAlign::Stretch => 0.0,
+                    FlexAlign::End => available - cross_size,
+                    FlexAlign::Center => (available - cross_size) * 0.5,
+                };
+                let item_cross_pos = cross_pos + margin_start(&item.margin, cross) + cross_offset;
+
+                let mut min = [0.0; 2];
+                let mut frame_size = [0.0; 2];
+                min[main] = main_pos;
+                min[cross] = item_cross_pos;
+                frame_size[main] = main_size;
+                frame_size[cross] = cross_size;
+
+                let frame = ctx.mirror_frame_if_rtl(
+                    box2! {
+                        min: [min[0], min[1]],
+                        max: [min[0] + frame_size[0], min[1] + frame_size[1]],
+                    },
+                    size.x,
+                );
+
+                ctx.set_subview_frame(self.subviews[i].as_ref(), frame);
+
+                main_pos += main_size + margin_sum(&item.margin, main)
+                    - margin_start(&item.margin, main)
+                    + self.spacing
+                    + gap;
+            }
+
+            cross_pos += line_cross_size + self.line_spacing;
+        }
+    }
+
+    fn has_same_subviews(&self, other: &dyn Layout) -> bool {
+        if let Some(other) = as_any::Downcast::downcast_ref::<Self>(other) {
+            self.subviews == other.subviews
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cggeom::{box2, Box2};
+
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::EmptyLayout,
+        uicore::HWnd,
+    };
+
+    fn new_item_view(min: f32, preferred: f32, max: f32) -> HView {
+        let sv = HView::new(Default::default());
+        sv.set_layout(EmptyLayout::new(SizeTraits {
+            min: [min, 10.0].into(),
+            max: [max, 20.0].into(),
+            preferred: [preferred, 10.0].into(),
+        }));
+        sv
+    }
+
+    /// Arrange `layout` in a window of the specified size.
+    fn arrange(twm: &dyn TestingWm, layout: FlexLayout, size: [f32; 2]) -> Vec<Box2<f32>> {
+        let wm = twm.wm();
+        let subviews = layout.subviews.clone();
+
+        let container = HView::new(Default::default());
+        container.set_layout(layout);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(crate::ui::layouts::AbsLayout::new(
+                SizeTraits {
+                    min: size.into(),
+                    max: size.into(),
+                    preferred: size.into(),
+                },
+                Some((
+                    container,
+                    box2! { min: [0.0, 0.0], max: [size[0], size[1]] },
+                    crate::ui::AlignFlags::JUSTIFY,
+                )),
+            ));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        subviews.iter().map(|sv| sv.global_frame()).collect()
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn grow(twm: &dyn TestingWm) {
+        let layout = FlexLayout::row(vec![
+            (new_item_view(10.0, 20.0, 100.0), FlexItem::new()),
+            (
+                new_item_view(10.0, 20.0, 100.0),
+                FlexItem::new().with_grow(1.0),
+            ),
+            (
+                new_item_view(10.0, 20.0, 30.0),
+                FlexItem::new().with_grow(1.0).with_uniform_margin(5.0),
+            ),
+        ])
+        .with_spacing(2.0)
+        .with_align(FlexAlign::Start);
+
+        let frames = arrange(twm, layout, [100.0, 40.0]);
+
+        // 100 - (20 + 20 + 30 + 2 * 2) = 26 is distributed between the second
+        // and the third items, but the third one can grow only by 10
+        assert_eq!(frames[0], box2! { min: [0.0, 0.0], max: [20.0, 10.0] });
+        assert_eq!(frames[1], box2! { min: [22.0, 0.0], max: [58.0, 10.0] });
+        assert_eq!(frames[2], box2! { min: [65.0, 5.0], max: [95.0, 15.0] });
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn grow_next_to_clamped(twm: &dyn TestingWm) {
+        let layout = FlexLayout::row(vec![
+            (
+                new_item_view(10.0, 20.0, 100.0),
+                FlexItem::new().with_grow(1.0),
+            ),
+            (
+                new_item_view(10.0, 20.0, 20.0),
+                FlexItem::new().with_grow(1.0),
+            ),
+            (
+                new_item_view(10.0, 20.0, 100.0),
+                FlexItem::new().with_grow(1.0),
+            ),
+        ])
+        .with_align(FlexAlign::Start);
+
+        let frames = arrange(twm, layout, [100.0, 40.0]);
+
+        // The second item is already at its maximum size, so the free space
+        // (40) is entirely given to the other two items
+        assert_eq!(frames[0], box2! { min: [0.0, 0.0], max: [40.0, 10.0] });
+        assert_eq!(frames[1], box2! { min: [40.0, 0.0], max: [60.0, 10.0] });
+        assert_eq!(frames[2], box2! { min: [60.0, 0.0], max: [100.0, 10.0] });
+    }
+
+    #[test]
+    fn distribute_redistributes_after_clamping() {
+        // The second element is already at its limit
+        let mut sizes = [0.0, 5.0, 0.0];
+        distribute(&mut sizes, &[1.0, 1.0, 1.0], &[100.0, 5.0, 100.0], 30.0);
+        assert_eq!(sizes, [15.0, 5.0, 15.0]);
+
+        // The second element reaches its limit midway
+        let mut sizes = [0.0, 0.0, 0.0];
+        distribute(&mut sizes, &[1.0, 1.0, 1.0], &[100.0, 4.0, 100.0], 30.0);
+        assert_eq!(sizes, [13.0, 4.0, 13.0]);
+
+        // Shrinking
+        let mut sizes = [20.0, 10.0, 20.0];
+        distribute(&mut sizes, &[1.0, 1.0, 1.0], &[0.0, 10.0, 0.0], -20.0);
+        assert_eq!(sizes, [10.0, 10.0, 10.0]);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn shrink(twm: &dyn TestingWm) {
+        let layout = FlexLayout::row(vec![
+            (new_item_view(10.0, 40.0, 100.0), FlexItem::new()),
+            (
+                new_item_view(10.0, 40.0, 100.0),
+                FlexItem::new().with_shrink(0.0),
+            ),
+        ]);
+
+        let frames = arrange(twm, layout, [60.0, 20.0]);
+
+        // Only the first item shrinks; the items are stretched along the
+        // cross axis
+        assert_eq!(frames[0], box2! { min: [0.0, 0.0], max: [20.0, 20.0] });
+        assert_eq!(frames[1], box2! { min: [20.0, 0.0], max: [60.0, 20.0] });
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn justify_center(twm: &dyn TestingWm) {
+        let layout = FlexLayout::column(vec![
+            (new_item_view(10.0, 20.0, 100.0), FlexItem::new()),
+            (
+                new_item_view(10.0, 20.0, 100.0),
+                FlexItem::new().with_align(FlexAlign::Center),
+            ),
+        ])
+        .with_justify(FlexJustify::Center)
+        .with_align(FlexAlign::End);
+
+        let frames = arrange(twm, layout, [40.0, 100.0]);
+
+        // The subviews are 20 × 10 (width × height) in their preferred sizes
+        assert_eq!(frames[0], box2! { min: [20.0, 40.0], max: [40.0, 50.0] });
+        assert_eq!(frames[1], box2! { min: [10.0, 50.0], max: [30.0, 60.0] });
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn wrap(twm: &dyn TestingWm) {
+        let layout =
+            FlexLayout::row((0..3).map(|_| (new_item_view(10.0, 30.0, 100.0), FlexItem::new())))
+                .with_wrap(true)
+                .with_spacing(5.0)
+                .with_line_spacing(4.0)
+                .with_align(FlexAlign::Start);
+
+        let frames = arrange(twm, layout, [70.0, 24.0]);
+
+        assert_eq!(frames[0], box2! { min: [0.0, 0.0], max: [30.0, 10.0] });
+        assert_eq!(frames[1], box2! { min: [35.0, 0.0], max: [65.0, 10.0] });
+        assert_eq!(frames[2], box2! { min: [0.0, 14.0], max: [30.0, 24.0] });
+    }
+}
@@ -10,6 +10,9 @@ use crate::{
 };
 
 /// A `Layout` that arranges subviews on imaginary table cells.
+///
+/// The arrangement is mirrored horizontally (i.e., the first column is placed
+/// on the right) if the user interface direction is right-to-left.
 #[derive(Debug, Clone)]
 pub struct TableLayout {
     /// Each element corresponds to the element in `items` with an identical
@@ -369,6 +372,7 @@ impl Layout for TableLayout {
             let st = ctx.subview_size_traits(view.as_ref());
 
             let subview_frame = item.align.arrange_child(&cell_box, &st);
+            let subview_frame = ctx.mirror_frame_if_rtl(subview_frame, size.x);
 
             ctx.set_subview_frame(view.as_ref(), subview_frame);
         }
@@ -174,7 +174,14 @@ impl MouseDragListener for MouseDragListenerImpl {
         }
     }
 
-    fn mouse_down(&self, wm: Wm, view: HViewRef<'_>, _loc: Point2<f32>, button: u8) {
+    fn mouse_down(
+        &self,
+        wm: Wm,
+        view: HViewRef<'_>,
+        _loc: Point2<f32>,
+        button: u8,
+        _click_count: u32,
+    ) {
         if button != 0 {
             return;
         }
@@ -196,7 +196,7 @@ impl CanvasMixin {
         wm.set_layer_attr(
             layer,
             pal::LayerAttrs {
-                contents: bmp.map(Some),
+                contents: bmp.map(|bmp| Some(bmp.into())),
                 bounds: Some(bounds),
                 ..Default::default()
             },
@@ -275,7 +275,7 @@ impl CanvasMixin {
         wm.set_layer_attr(
             layer,
             pal::LayerAttrs {
-                contents: bmp.map(Some),
+                contents: bmp.map(|bmp| Some(bmp.into())),
                 bounds: Some(view_frame),
                 contents_scale: Some(dpi_scale),
                 contents_center: Some(box2! { point: [0.5, 0.5] }),
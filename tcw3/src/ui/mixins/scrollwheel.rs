@@ -11,6 +11,7 @@ use std::{cell::Cell, rc::Rc};
 use crate::{
     pal,
     prelude::*,
+    ui::animation::{eval_cubic_bezier, eval_cubic_bezier_d, start_animation_timer},
     uicore::{HViewRef, HWndRef, ScrollDelta, ScrollListener},
 };
 
@@ -708,31 +709,6 @@ fn start_transition(
     });
 }
 
-// TODO: This utility function is convenient and should be moved to something
-//       like a utility module.
-/// Call the given function each frame until it returns `false`. `hwnd` is used
-/// to decide the display refresh rate to synchronize.
-fn start_animation_timer(hwnd: HWndRef, f: impl FnMut(pal::Wm) -> bool + 'static) {
-    struct TimerState<T: ?Sized>(T);
-
-    impl<T: ?Sized + FnMut(pal::Wm) -> bool + 'static> TimerState<T> {
-        fn enqueue(hwnd: HWndRef, mut this: Box<Self>) {
-            hwnd.invoke_on_next_frame(move |wm, hwnd| {
-                let keep_running = (this.0)(wm);
-
-                if keep_running {
-                    // Try to simulate `requestAnimationFrame`
-                    Self::enqueue(hwnd, this);
-                }
-            });
-        }
-    }
-
-    let st: Box<TimerState<dyn FnMut(pal::Wm) -> bool>> = Box::new(TimerState(f));
-
-    TimerState::enqueue(hwnd, st);
-}
-
 /// Numerically solve an equation `f(x) = 0` using the Newton's method.
 fn solve_newton(start: f32, mut f: impl FnMut(f32) -> f32, mut f_d: impl FnMut(f32) -> f32) -> f32 {
     (0..12).fold(start, |x, _| x - f(x) / f_d(x))
@@ -756,21 +732,6 @@ fn eval_bezier_bouncing_timing_func(x1: f32, y1: f32, x2: f32, y2: f32, t: f32)
     fn_y(p)
 }
 
-#[inline]
-fn eval_cubic_bezier(x: f32, y1: f32, y2: f32, y3: f32) -> f32 {
-    // 3y1 * (1-x)²x + 3y2 * (1-x)x² + y3 * x³
-    //  = 3y1(x-2x²+x³) + 3y2(x²-x³) + y3x³
-    //  = x³(3(y1-y2)+y3) + x²(3(y2-y1)-3y1) + 3xy1
-    x * (3.0 * y1 + x * ((3.0 * (y2 - y1) - 3.0 * y1) + x * (y3 - 3.0 * (y2 - y1))))
-}
-
-#[inline]
-fn eval_cubic_bezier_d(x: f32, y1: f32, y2: f32, y3: f32) -> f32 {
-    // (3y1 * (1-x)²x + 3y2 * (1-x)x² + y3 * x³)'
-    //  = 3x²(3(y1-y2)+y3) + 2x(3(y2-y1)-3y1) + 3y1
-    3.0 * y1 + x * ((3.0 * (y2 - y1) - 3.0 * y1) * 2.0 + 3.0 * x * (y3 - 3.0 * (y2 - y1)))
-}
-
 #[cfg(test)]
 mod tests {
     use cggeom::box2;
@@ -1,10 +1,12 @@
 //! Provides standard UI components (views, layouts, ...).
 pub mod layouts {
     mod abs;
+    mod constraint;
     mod empty;
     mod fill;
+    mod flex;
     mod table;
-    pub use self::{abs::*, empty::*, fill::*, table::*};
+    pub use self::{abs::*, constraint::*, empty::*, fill::*, flex::*, table::*};
 }
 
 /// Reusable building blocks for creating UI components.
@@ -17,24 +19,52 @@ pub mod mixins {
 
 pub mod views {
     mod button;
+    mod canvasview;
     mod checkbox;
     mod entry;
+    mod gpusurface;
+    mod imageview;
     mod label;
+    pub mod list;
+    mod nativehost;
+    mod progressbar;
+    mod richlabel;
     pub mod scrollbar;
+    mod scrollview;
+    pub mod selection;
     pub mod slider;
     mod spacer;
     pub mod split;
     pub mod table;
+    mod textarea;
+    mod toast;
+    mod videoview;
+    #[cfg(feature = "webview")]
+    mod webview;
+    #[cfg(feature = "webview")]
+    pub use self::webview::WebView;
     pub use self::{
         button::Button,
-        checkbox::{Checkbox, RadioButton},
+        canvasview::CanvasView,
+        checkbox::{CheckState, Checkbox, RadioButton, RadioGroup},
         entry::{Entry, EntryCore},
-        label::Label,
+        gpusurface::{FrameCtx, GpuSurfaceView},
+        imageview::{ImageView, ScaleMode},
+        label::{Label, LineBreakMode},
+        list::ListView,
+        nativehost::NativeHost,
+        progressbar::ProgressBar,
+        richlabel::{RichLabel, TextLink},
         scrollbar::ScrollbarRaw,
+        scrollview::ScrollView,
+        selection::SelectionModel,
         slider::{Slider, SliderRaw},
         spacer::{new_spacer, Spacer},
         split::Split,
         table::{ScrollableTable, Table},
+        textarea::TextArea,
+        toast::{Toast, ToastHost, ToastId},
+        videoview::{VideoFrameSender, VideoView},
     };
     tcw3_meta::designer_impl! { crate::ui::views::SpacerWidget }
     tcw3_meta::designer_impl! { crate::ui::views::FixedSpacer }
@@ -45,25 +75,38 @@ pub mod theming {
     mod manager;
     mod style;
     mod stylesheet;
+    mod textsheet;
     mod view;
     mod widget;
 
     pub use self::{
-        manager::{Elem, ElemChangeCb, HElem, Manager, PropKindFlags},
+        manager::{Elem, ElemChangeCb, HElem, Manager, PropKindFlags, StylesheetSet},
         style::{
             elem_id, mk_prop_by_snake_name, mk_prop_value_by_prop_snake_name,
             mk_wrap_dynvalue_by_prop_snake_name, mk_wrap_value_by_prop_snake_name, roles, ClassSet,
             Col, ElemClassPath, GetPropValue, LayerId, LayerXform, Layouter, Metrics, Prop,
-            PropValue, Role, Row,
+            PropValue, Role, Row, TimingFunction, Transition,
         },
         stylesheet::*,
+        textsheet::{ParseError, TextStylesheet, TextStylesheetParser},
         view::{ModifyArrangementArgs, StyledBox, StyledBoxOverride},
         widget::Widget,
     };
 }
 
+/// Alerts (message boxes)
+pub mod alert;
+
+pub mod session;
+
+mod event;
 mod types;
-pub use self::types::AlignFlags;
+pub use self::{
+    event::{EventSource, Sub},
+    types::AlignFlags,
+};
+
+mod animation;
 
 mod scrolling {
     pub mod lineset;
@@ -71,10 +114,11 @@ mod scrolling {
     pub mod tableremap;
 }
 
+/// Shaped text cache
+pub mod text;
+
 /// Text editing support
-pub mod editing {
-    pub mod history;
-}
+pub mod text_editing;
 
 /// Re-exports some traits from the `ui` module.
 pub mod prelude {
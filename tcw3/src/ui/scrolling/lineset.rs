@@ -2179,6 +2179,38 @@ impl Lineset {
         )
     }
 
+    /// Find a set of consecutive line groups including the lines in the
+    /// specified index range.
+    ///
+    /// This is similar to [`range`], but takes a range of line indices instead
+    /// of a range of line coordinates.
+    ///
+    /// [`range`]: crate::ui::scrolling::lineset::Lineset::range
+    pub fn range_by_index(
+        &self,
+        range: Range<Index>,
+    ) -> (
+        impl Iterator<Item = (Size, Index)> + DoubleEndedIterator + '_,
+        Range<Index>,
+        Range<Size>,
+    ) {
+        use rope::{
+            range_by_key,
+            Edge::{Ceil, Floor},
+        };
+
+        let (iter, range) = self.line_grs.range(range_by_key(
+            LineOff::index,
+            Floor(range.start)..Ceil(range.end),
+        ));
+
+        (
+            iter.map(|line_gr| (line_gr.size, line_gr.num_lines)),
+            range.start.index..range.end.index,
+            range.start.pos..range.end.pos,
+        )
+    }
+
     /// Check if the lineset is well-grouped for `vp_by_pos`. In addition,
     /// returns the line index range `vp` encompasses.
     pub fn is_well_grouped(&self, vp_by_pos: Range<Size>) -> (bool, Range<Index>) {
@@ -2629,6 +2661,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_range_by_index() {
+        const NUM_LINES: Index = 100;
+
+        let mut lineset = Lineset::new();
+        lineset.insert(&TestModel, 0..NUM_LINES);
+
+        // Make the first few lines precise
+        let vp = 0..TestModel.pos(10);
+        while !lineset.is_well_grouped(vp.clone()).0 {
+            lineset.regroup(&TestModel, &[vp.clone()], &mut ());
+        }
+        lineset.validate();
+
+        let (lines, index_range, pos_range) = lineset.range_by_index(0..3);
+        assert_eq!(index_range, 0..3);
+        assert_eq!(pos_range, 0..TestModel.pos(3));
+        assert!({ lines }.all(|(_size, num_lines)| num_lines == 1));
+
+        // An empty range
+        let (_, index_range, pos_range) = lineset.range_by_index(0..0);
+        assert_eq!(index_range, 0..0);
+        assert_eq!(pos_range, 0..0);
+    }
+
     #[test]
     fn test_regroup2() {
         const NUM_LINES: Index = 100;
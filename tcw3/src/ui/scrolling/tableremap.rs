@@ -163,6 +163,15 @@ impl LineIdxMap {
         self.new_line_idx.extend(range);
     }
 
+    /// Reinitialize `LineIdxMap` with an identity transform for the specified
+    /// set of line indices, which must be sorted in the ascending order.
+    ///
+    /// This is used when the line list represents non-consecutive lines.
+    pub fn set_identity_lines(&mut self, lines: impl IntoIterator<Item = i64>) {
+        self.new_line_idx.clear();
+        self.new_line_idx.extend(lines);
+    }
+
     pub fn insert(&mut self, range: Range<i64>) {
         for line_idx in self.new_line_idx.iter_mut() {
             if *line_idx >= range.start {
@@ -201,21 +210,35 @@ impl LineIdxMap {
         &self,
         vp: Range<i64>,
     ) -> impl Iterator<Item = usize> + ExactSizeIterator + Clone + '_ {
-        const NONE_LIST_INDEX: usize = <usize>::max_value();
-
         let count = vp.end.checked_sub(vp.start).expect("count overflow");
         assert!(count < <isize>::max_value() as i64, "count overflow");
 
         let line_idx_start = vp.start;
 
+        self.invert_lines((0..count as usize).map(move |i| line_idx_start + i as i64))
+    }
+
+    /// Construct an inverse map for an arbitrary set of post-transformation
+    /// line indices.
+    ///
+    /// This is a generalized version of [`invert`]. The `i`-th element of the
+    /// returned iterator tells the original list index corresponding to the
+    /// `i`-th element of `lines`. `lines` must be sorted in the ascending
+    /// order.
+    ///
+    /// [`invert`]: crate::ui::scrolling::tableremap::LineIdxMap::invert
+    pub fn invert_lines<'a>(
+        &'a self,
+        lines: impl Iterator<Item = i64> + ExactSizeIterator + Clone + 'a,
+    ) -> impl Iterator<Item = usize> + ExactSizeIterator + Clone + 'a {
+        const NONE_LIST_INDEX: usize = <usize>::max_value();
+
         let new_line_idx_it = self.new_line_idx.iter().cloned().enumerate().peekable();
 
         MapWithState {
-            inner: 0..count as usize,
+            inner: lines,
             state: new_line_idx_it,
-            mapper: move |i, new_line_idx_it: &mut std::iter::Peekable<_>| {
-                let out_line_idx = line_idx_start + i as i64;
-
+            mapper: move |out_line_idx, new_line_idx_it: &mut std::iter::Peekable<_>| {
                 // `new_line_idx` is monotonically increasing (except for `NONE`
                 // elements). This fact tells us that a single sweep is
                 // sufficient to find an element `(i, line_idx)`in `new_line_idx`
@@ -424,4 +447,18 @@ mod tests {
         let inv_map: Vec<_> = line_idx_map.invert(4..12).collect();
         assert_eq!(inv_map, (0..8).collect::<Vec<_>>());
     }
+
+    #[test]
+    fn inverse_non_consecutive() {
+        let mut line_idx_map = LineIdxMap::new(0..0);
+        line_idx_map.set_identity_lines((0..2).chain(5..8));
+        line_idx_map.insert(1..2);
+        line_idx_map.remove(6..7);
+
+        // The lines `[0, 1, 5, 6, 7]` are mapped to `[0, 2, -, 6, 7]`
+        let inv_map: Vec<_> = line_idx_map
+            .invert_lines([0, 1, 2, 6, 7].iter().cloned())
+            .collect();
+        assert_eq!(inv_map, vec![0, usize::max_value(), 1, 3, 4]);
+    }
 }
@@ -0,0 +1,314 @@
+//! Session persistence
+//!
+//! This module provides a way to save the transient states of windows and
+//! widgets (e.g., window sizes, split positions, and scroll offsets) and
+//! restore them on the next launch.
+//!
+//! [`Session`] is a string-keyed collection of states, which can be
+//! converted from/to a JSON string. Windows and widgets implementing
+//! [`Persist`] store their states in a `Session` under a key specified by the
+//! application.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use tcw3::{pal, uicore::HWnd, ui::session::{Persist, Session}};
+//! # fn load() -> Option<String> { None }
+//! # fn store(_: &str) {}
+//!
+//! # let wm: pal::Wm = unimplemented!();
+//! let wnd = HWnd::new(wm);
+//!
+//! // Restore the window geometry from the last session
+//! let mut session = load()
+//!     .and_then(|json| Session::from_json(&json).ok())
+//!     .unwrap_or_default();
+//! wnd.restore_session(&session, "main_wnd");
+//!
+//! wnd.set_visibility(true);
+//!
+//! // ... later, before quitting the application:
+//! wnd.save_session(&mut session, "main_wnd");
+//! store(&session.to_json());
+//! ```
+use cgmath::Point2;
+use miniserde::{json, Deserialize, Serialize};
+use std::fmt;
+
+use crate::{
+    ui::views::{ScrollView, Split},
+    uicore::{HWnd, HWndRef},
+};
+
+/// A collection of persisted states, each identified by a string key.
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    entries: json::Object,
+}
+
+/// The error type returned by [`Session::from_json`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionParseError;
+
+impl fmt::Display for SessionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the session data is malformed")
+    }
+}
+
+impl std::error::Error for SessionParseError {}
+
+impl Session {
+    /// Construct an empty `Session`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a JSON string produced by [`to_json`](Self::to_json).
+    pub fn from_json(json: &str) -> Result<Self, SessionParseError> {
+        let entries = json::from_str(json).map_err(|_| SessionParseError)?;
+        Ok(Self { entries })
+    }
+
+    /// Serialize the contents as a JSON string.
+    pub fn to_json(&self) -> String {
+        json::to_string(&self.entries)
+    }
+
+    /// Get the state stored under `key`.
+    ///
+    /// Returns `None` if there's no such state or it has an unexpected type.
+    pub fn get<T: Deserialize>(&self, key: &str) -> Option<T> {
+        let value = self.entries.get(key)?;
+
+        // `miniserde` only deserializes from strings
+        json::from_str(&json::to_string(value)).ok()
+    }
+
+    /// Store a state under `key`, replacing the existing one (if any).
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) {
+        let value: json::Value = json::from_str(&json::to_string(value)).unwrap();
+        self.entries.insert(key.to_owned(), value);
+    }
+
+    /// Remove the state stored under `key`.
+    pub fn remove(&mut self, key: &str) {
+        self.entries.remove(key);
+    }
+
+    /// Return `true` if there's a state stored under `key`.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+}
+
+/// Implemented by objects whose states can be stored in a [`Session`].
+pub trait Persist {
+    /// Store the current state in `session` under `key`.
+    fn save_session(&self, session: &mut Session, key: &str);
+
+    /// Restore the state stored in `session` under `key`. Does nothing if
+    /// there's no valid state stored under `key`.
+    fn restore_session(&self, session: &Session, key: &str);
+}
+
+#[derive(Serialize, Deserialize)]
+struct WndSession {
+    width: u32,
+    height: u32,
+    x: Option<i32>,
+    y: Option<i32>,
+    maximized: Option<bool>,
+}
+
+/// Persists the size of the window's content area, the window position, and
+/// the maximized state.
+///
+/// While the window is maximized, the size and position already stored in
+/// `session` (if any) are retained so that the window can be restored to its
+/// normal geometry in the next session. Thus, the window should be saved to
+/// the `Session` it was restored from.
+impl Persist for HWndRef<'_> {
+    fn save_session(&self, session: &mut Session, key: &str) {
+        let maximized = self.is_maximized();
+
+        let prev = if maximized {
+            session.get::<WndSession>(key)
+        } else {
+            None
+        };
+
+        let st = if let Some(prev) = prev {
+            WndSession {
+                maximized: Some(true),
+                ..prev
+            }
+        } else if let Some([width, height]) = self.size() {
+            let position = self.position();
+            WndSession {
+                width,
+                height,
+                x: position.map(|p| p[0]),
+                y: position.map(|p| p[1]),
+                maximized: Some(maximized),
+            }
+        } else {
+            return;
+        };
+
+        session.set(key, &st);
+    }
+
+    fn restore_session(&self, session: &Session, key: &str) {
+        if let Some(st) = session.get::<WndSession>(key) {
+            if st.width > 0 && st.height > 0 {
+                self.set_size([st.width, st.height]);
+            }
+            if let (Some(x), Some(y)) = (st.x, st.y) {
+                self.set_position([x, y]);
+            }
+            if st.maximized == Some(true) {
+                self.set_maximized(true);
+            }
+        }
+    }
+}
+
+impl Persist for HWnd {
+    fn save_session(&self, session: &mut Session, key: &str) {
+        self.as_ref().save_session(session, key)
+    }
+
+    fn restore_session(&self, session: &Session, key: &str) {
+        self.as_ref().restore_session(session, key)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SplitSession {
+    value: f32,
+    collapsed: Option<u8>,
+}
+
+/// Persists the split position and the collapsed state.
+impl Persist for Split {
+    fn save_session(&self, session: &mut Session, key: &str) {
+        session.set(
+            key,
+            &SplitSession {
+                value: self.value(),
+                collapsed: self.collapsed(),
+            },
+        );
+    }
+
+    fn restore_session(&self, session: &Session, key: &str) {
+        if let Some(st) = session.get::<SplitSession>(key) {
+            if st.value.is_finite() {
+                self.set_value(st.value);
+            }
+            if st.collapsed.map_or(true, |i| i < 2) {
+                self.set_collapsed(st.collapsed);
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScrollViewSession {
+    x: f64,
+    y: f64,
+}
+
+/// Persists the scroll position.
+impl Persist for ScrollView {
+    fn save_session(&self, session: &mut Session, key: &str) {
+        let pos = self.scroll_pos();
+        session.set(key, &ScrollViewSession { x: pos.x, y: pos.y });
+    }
+
+    fn restore_session(&self, session: &Session, key: &str) {
+        if let Some(st) = session.get::<ScrollViewSession>(key) {
+            if st.x.is_finite() && st.y.is_finite() {
+                self.set_scroll_pos(Point2::new(st.x, st.y));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{prelude::*, use_testing_wm};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut session = Session::new();
+        session.set("a", &Point { x: 1, y: -2 });
+        session.set("b", &42u32);
+
+        let session = Session::from_json(&session.to_json()).unwrap();
+        assert_eq!(session.get::<Point>("a"), Some(Point { x: 1, y: -2 }));
+        assert_eq!(session.get::<u32>("b"), Some(42));
+        assert_eq!(session.get::<Point>("b"), None);
+        assert_eq!(session.get::<u32>("c"), None);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(Session::from_json("[]").err(), Some(SessionParseError));
+        assert_eq!(Session::from_json("{").err(), Some(SessionParseError));
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn wnd_geometry(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let wnd = HWnd::new(wm);
+        wnd.set_size([300, 200]);
+        wnd.set_position([40, 50]);
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = wnd.pal_hwnd().unwrap();
+        let attrs = twm.wnd_attrs(&pal_hwnd).unwrap();
+        assert_eq!(attrs.size, [300, 200]);
+        assert_eq!(attrs.position, [40, 50]);
+        assert!(!attrs.maximized);
+
+        let mut session = Session::new();
+        wnd.save_session(&mut session, "wnd");
+
+        // The geometry of the maximized window should not overwrite the
+        // normal geometry
+        wnd.set_maximized(true);
+        wnd.set_position([0, 0]);
+        wnd.set_size([1000, 800]);
+        twm.step_unsend();
+        assert!(twm.wnd_attrs(&pal_hwnd).unwrap().maximized);
+
+        wnd.save_session(&mut session, "wnd");
+        wnd.close();
+
+        let session = Session::from_json(&session.to_json()).unwrap();
+
+        let wnd = HWnd::new(wm);
+        wnd.restore_session(&session, "wnd");
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = wnd.pal_hwnd().unwrap();
+        let attrs = twm.wnd_attrs(&pal_hwnd).unwrap();
+        assert_eq!(attrs.size, [300, 200]);
+        assert_eq!(attrs.position, [40, 50]);
+        assert!(attrs.maximized);
+        assert!(wnd.is_maximized());
+    }
+}
@@ -0,0 +1,318 @@
+//! Provides a cache of shaped text shared among widgets.
+//!
+//! Constructing a `TextLayout` is relatively expensive. Widgets displaying
+//! a static text (such as [`Label`]) get `TextLayout`s through
+//! [`shaped_text`] so that identical strings are not shaped over and over
+//! again, e.g., when a table is scrolled and its cells are re-created.
+//!
+//! `TextLayout` is resolution-independent, so the DPI scale is not a part of
+//! the cache key.
+//!
+//! This module also provides functions for shortening a text with an ellipsis
+//! to fit in a given space ([`truncated_text`] and [`wrapped_text`]).
+//!
+//! [`Label`]: crate::ui::views::Label
+use cggeom::{prelude::*, Box2};
+use cgmath::Point2;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use tcw3_pal::mt_lazy_static;
+
+use crate::{pal, pal::prelude::*};
+
+/// A `TextLayout` and its metrics, returned by [`shaped_text`].
+#[derive(Debug)]
+pub struct ShapedText {
+    pub text_layout: pal::TextLayout,
+    /// The cached value of `text_layout.layout_bounds()`.
+    pub layout_bounds: Box2<f32>,
+    /// The cached value of `text_layout.visual_bounds()`.
+    pub visual_bounds: Box2<f32>,
+}
+
+/// The maximum number of entries retained by the cache.
+const CAPACITY: usize = 512;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Key {
+    text: String,
+    font: pal::SysFontType,
+    /// The bit pattern of the wrap width.
+    width: Option<u32>,
+}
+
+struct Cache {
+    entries: HashMap<Key, Entry>,
+    /// Incremented on every access. Used to find least recently used entries.
+    clock: u64,
+}
+
+struct Entry {
+    shaped_text: Rc<ShapedText>,
+    last_used: u64,
+}
+
+mt_lazy_static! {
+    static ref CACHE: RefCell<Cache> => |_| RefCell::new(Cache {
+        entries: HashMap::new(),
+        clock: 0,
+    });
+}
+
+/// Get a `TextLayout` for the specified text, font, and wrap width.
+///
+/// The returned `ShapedText` may be shared with other callers. Least recently
+/// used entries are evicted when the cache grows too large.
+pub fn shaped_text(
+    wm: pal::Wm,
+    text: &str,
+    font: pal::SysFontType,
+    width: Option<f32>,
+) -> Rc<ShapedText> {
+    let mut cache = CACHE.get_with_wm(wm).borrow_mut();
+    let cache = &mut *cache; // enable split borrow
+
+    cache.clock += 1;
+    let clock = cache.clock;
+
+    let key = Key {
+        text: text.to_owned(),
+        font,
+        width: width.map(f32::to_bits),
+    };
+
+    if let Some(entry) = cache.entries.get_mut(&key) {
+        entry.last_used = clock;
+        return Rc::clone(&entry.shaped_text);
+    }
+
+    if cache.entries.len() >= CAPACITY {
+        evict(&mut cache.entries);
+    }
+
+    let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+        sys: Some(font),
+        ..Default::default()
+    });
+    let text_layout = pal::TextLayout::from_text(text, &char_style, width);
+
+    let shaped_text = Rc::new(ShapedText {
+        layout_bounds: text_layout.layout_bounds(),
+        visual_bounds: text_layout.visual_bounds(),
+        text_layout,
+    });
+
+    cache.entries.insert(
+        key,
+        Entry {
+            shaped_text: Rc::clone(&shaped_text),
+            last_used: clock,
+        },
+    );
+
+    shaped_text
+}
+
+/// The string inserted in place of removed characters by [`truncated_text`]
+/// and [`wrapped_text`].
+pub const ELLIPSIS: &str = "\u{2026}";
+
+/// Specifies where [`truncated_text`] removes characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TruncationMode {
+    /// Remove characters from the end of the text.
+    Tail,
+    /// Remove characters from the middle of the text, preserving both ends.
+    Middle,
+}
+
+/// Get a single-line `ShapedText` of `text`, replacing some characters with
+/// [`ELLIPSIS`] if it doesn't fit in `width`.
+///
+/// If `width` is too narrow even for `ELLIPSIS` alone, the returned
+/// `ShapedText` still includes `ELLIPSIS`.
+pub fn truncated_text(
+    wm: pal::Wm,
+    text: &str,
+    font: pal::SysFontType,
+    width: f32,
+    mode: TruncationMode,
+) -> Rc<ShapedText> {
+    let full = shaped_text(wm, text, font, None);
+    if fits(&full, width) {
+        return full;
+    }
+
+    let ellipsis = shaped_text(wm, ELLIPSIS, font, None);
+    let budget = (width - ellipsis.layout_bounds.size().x).max(0.0);
+
+    // Make initial guesses using the untruncated layout, and then remove
+    // characters until the result fits in `width`
+    let layout = &full.text_layout;
+    let y = layout.line_baseline(0);
+    let full_width = full.layout_bounds.size().x;
+
+    match mode {
+        TruncationMode::Tail => {
+            let mut end = layout.cursor_index_from_point(Point2::new(budget, y));
+            loop {
+                let candidate = format!("{}{}", text[..end].trim_end(), ELLIPSIS);
+                let shaped = shaped_text(wm, &candidate, font, None);
+                if end == 0 || fits(&shaped, width) {
+                    return shaped;
+                }
+                end = layout.next_char(end, false);
+            }
+        }
+        TruncationMode::Middle => {
+            let mut start = layout.cursor_index_from_point(Point2::new(budget * 0.5, y));
+            let mut end = layout.cursor_index_from_point(Point2::new(full_width - budget * 0.5, y));
+            if end < start {
+                end = start;
+            }
+
+            let mut shrink_head = true;
+            loop {
+                let candidate = format!("{}{}{}", &text[..start], ELLIPSIS, &text[end..]);
+                let shaped = shaped_text(wm, &candidate, font, None);
+                if (start == 0 && end == text.len()) || fits(&shaped, width) {
+                    return shaped;
+                }
+
+                // Remove characters from both ends alternately
+                if (shrink_head && start > 0) || end == text.len() {
+                    start = layout.next_char(start, false);
+                } else {
+                    end = layout.next_char(end, true);
+                }
+                shrink_head = !shrink_head;
+            }
+        }
+    }
+}
+
+/// Get a `ShapedText` of `text` wrapped at `width`. If it has more than
+/// `max_lines` lines, the text is cut off, and [`ELLIPSIS`] is appended to the
+/// last line.
+///
+/// `max_lines` must not be zero.
+pub fn wrapped_text(
+    wm: pal::Wm,
+    text: &str,
+    font: pal::SysFontType,
+    width: f32,
+    max_lines: Option<usize>,
+) -> Rc<ShapedText> {
+    let full = shaped_text(wm, text, font, Some(width));
+    let layout = &full.text_layout;
+
+    let max_lines = match max_lines {
+        Some(max_lines) if layout.num_lines() > max_lines => max_lines,
+        _ => return full,
+    };
+    assert_ne!(max_lines, 0);
+
+    let mut end = layout.line_index_range(max_lines - 1).end;
+    loop {
+        let candidate = format!("{}{}", text[..end].trim_end(), ELLIPSIS);
+        let shaped = shaped_text(wm, &candidate, font, Some(width));
+        if end == 0 || shaped.text_layout.num_lines() <= max_lines {
+            return shaped;
+        }
+        end = layout.next_char(end, false);
+    }
+}
+
+fn fits(shaped: &ShapedText, width: f32) -> bool {
+    shaped.layout_bounds.size().x <= width
+}
+
+/// Remove the least recently used half of the entries. Evicting in batches
+/// amortizes the cost of finding the entries to remove.
+fn evict(entries: &mut HashMap<Key, Entry>) {
+    let mut last_used: Vec<u64> = entries.values().map(|e| e.last_used).collect();
+    last_used.sort_unstable();
+    let threshold = last_used[last_used.len() / 2];
+    entries.retain(|_, e| e.last_used >= threshold);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{prelude::*, use_testing_wm};
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn shared_among_callers(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let font = pal::SysFontType::Normal;
+
+        let st1 = shaped_text(wm, "hello", font, None);
+        let st2 = shaped_text(wm, "hello", font, None);
+        assert!(Rc::ptr_eq(&st1, &st2));
+
+        let st3 = shaped_text(wm, "hello", font, Some(100.0));
+        assert!(!Rc::ptr_eq(&st1, &st3));
+
+        let st4 = shaped_text(wm, "hello", pal::SysFontType::Small, None);
+        assert!(!Rc::ptr_eq(&st1, &st4));
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn truncation(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let font = pal::SysFontType::Normal;
+        let text = "The quick brown fox jumps over the lazy dog";
+
+        let full = shaped_text(wm, text, font, None);
+        let full_width = full.layout_bounds.size().x;
+
+        for &mode in &[TruncationMode::Tail, TruncationMode::Middle] {
+            // The text fits; it's returned as it is
+            let st = truncated_text(wm, text, font, full_width, mode);
+            assert!(Rc::ptr_eq(&st, &full));
+
+            let st = truncated_text(wm, text, font, full_width * 0.5, mode);
+            let width = st.layout_bounds.size().x;
+            assert!(width <= full_width * 0.5, "{:?}: {:?}", mode, width);
+            assert!(width > 0.0, "{:?}: {:?}", mode, width);
+        }
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn wrapping_with_max_lines(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let font = pal::SysFontType::Normal;
+        let text = "The quick brown fox jumps over the lazy dog";
+
+        let word_width = shaped_text(wm, "quick brown", font, None)
+            .layout_bounds
+            .size()
+            .x;
+
+        let st = wrapped_text(wm, text, font, word_width, None);
+        assert!(st.text_layout.num_lines() > 2);
+
+        let st = wrapped_text(wm, text, font, word_width, Some(2));
+        assert_eq!(st.text_layout.num_lines(), 2);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn eviction(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let font = pal::SysFontType::Normal;
+
+        let st1 = shaped_text(wm, "first", font, None);
+
+        for i in 0..CAPACITY * 2 {
+            shaped_text(wm, &format!("{}", i), font, None);
+        }
+
+        assert!(CACHE.get_with_wm(wm).borrow().entries.len() <= CAPACITY);
+
+        let st2 = shaped_text(wm, "first", font, None);
+        assert!(!Rc::ptr_eq(&st1, &st2));
+    }
+}
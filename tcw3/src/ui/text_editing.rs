@@ -21,6 +21,7 @@
 //! adapted to [the text input interface] of TCW3.
 //!
 //! [the text input interface]: tcw3_pal::iface::TextInputCtxListener
+use neo_linked_list::LinkedList;
 use std::ops::Range;
 
 /// Represents a single replacement operation.
@@ -424,6 +425,376 @@ impl<Text: TextTrait> CoalescingStateTx<Text> {
         }
     }
 }
+/// An undo history built on top of [the history coalescing algorithm].
+///
+/// `UndoStack` records edits made to a text storage (whose type is
+/// represented by `Text`), coalescing consecutive typing. The client applies
+/// the edits returned by [`undo`] and [`redo`] to the text storage.
+///
+/// Multiple edits can be combined into a single undoable operation by
+/// enclosing them with [`begin_undo_group`] and [`end_undo_group`].
+///
+/// The memory consumption is bounded by trimming the oldest operations.
+///
+/// [the history coalescing algorithm]: self
+/// [`undo`]: UndoStack::undo
+/// [`redo`]: UndoStack::redo
+/// [`begin_undo_group`]: UndoStack::begin_undo_group
+/// [`end_undo_group`]: UndoStack::end_undo_group
+#[derive(Debug)]
+pub struct UndoStack<Text> {
+    coalescing_state: CoalescingState<Text>,
+    op_list: OpList<Text>,
+}
+
+/// Represents a transaction in [`UndoStack`].
+///
+/// Use [`UndoStack::start_transaction`] to start a transaction.
+pub struct UndoStackTx<Text> {
+    coalescing_state_tx: CoalescingStateTx<Text>,
+}
+
+#[derive(Debug)]
+struct OpList<Text> {
+    ops: LinkedList<Op<Text>>,
+    /// The estimated memory consumption by `ops`.
+    size: usize,
+    /// The maximum value of `size`.
+    max_size: usize,
+    /// The number of redoable operations. Such operations are found at the
+    /// front of `ops` so that the next operation to undo always appears at the
+    /// back.
+    ///
+    /// Invariant: `redo_depth` is `0` when a transaction is active.
+    redo_depth: usize,
+    /// The nesting level of `begin_undo_group`.
+    group_depth: usize,
+    /// `true` if the last element of `ops` belongs to the current group and
+    /// should receive new `Edit`s.
+    group_open: bool,
+}
+
+/// An undoable operation, which consists of one or more `Edit`s.
+#[derive(Debug)]
+struct Op<Text> {
+    edits: Vec<Edit<Text>>,
+}
+
+/// The default value of [`UndoStack::set_max_size`].
+const DEFAULT_MAX_SIZE: usize = 2048;
+
+impl<Text: TextTrait> Default for UndoStack<Text> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Text: TextTrait> UndoStack<Text> {
+    /// Construct an empty `UndoStack`.
+    pub fn new() -> Self {
+        Self {
+            coalescing_state: CoalescingState::new(),
+            op_list: OpList {
+                ops: LinkedList::new(),
+                size: 0,
+                max_size: DEFAULT_MAX_SIZE,
+                redo_depth: 0,
+                group_depth: 0,
+                group_open: false,
+            },
+        }
+    }
+
+    /// Set the approximate upper limit of the memory consumption, measured in
+    /// the total length of the texts stored in the history. The oldest
+    /// operations are forgotten when the limit is exceeded. The latest
+    /// operation is always retained.
+    pub fn set_max_size(&mut self, max_size: usize) {
+        self.op_list.max_size = max_size;
+    }
+
+    /// Forget all operations. An ongoing group remains open.
+    pub fn clear(&mut self) {
+        log::trace!("Clearing the history");
+        self.mark_logical_op_break();
+        self.op_list.ops = LinkedList::new();
+        self.op_list.size = 0;
+        self.op_list.redo_depth = 0;
+        self.op_list.group_open = false;
+    }
+
+    /// Start a transaction. Redoable operations are forgotten.
+    pub fn start_transaction(&mut self) -> UndoStackTx<Text> {
+        self.op_list.clear_redo();
+        debug_assert_eq!(self.op_list.redo_depth, 0);
+
+        log::trace!("Creating `CoalescingStateTx`");
+
+        UndoStackTx {
+            coalescing_state_tx: self.coalescing_state.start_transaction(),
+        }
+    }
+
+    /// Introduce a breakpoint in history coalescing. The next edit will not
+    /// be merged into the preceding ones.
+    pub fn mark_logical_op_break(&mut self) {
+        log::trace!("Resetting `CoalescingState`");
+        self.coalescing_state.reset();
+    }
+
+    /// Start a group. The edits recorded until the matching call to
+    /// [`end_undo_group`] will be undone and redone as a single operation.
+    ///
+    /// Groups can be nested. Only the outermost group has an effect.
+    ///
+    /// [`end_undo_group`]: UndoStack::end_undo_group
+    pub fn begin_undo_group(&mut self) {
+        if self.op_list.group_depth == 0 {
+            log::trace!("Starting a group");
+            // Don't merge the group's first edit into a preceding operation
+            self.mark_logical_op_break();
+        }
+        self.op_list.group_depth += 1;
+    }
+
+    /// End a group started by [`begin_undo_group`].
+    ///
+    /// [`begin_undo_group`]: UndoStack::begin_undo_group
+    pub fn end_undo_group(&mut self) {
+        assert!(self.op_list.group_depth > 0, "unbalanced `end_undo_group`");
+        self.op_list.group_depth -= 1;
+        if self.op_list.group_depth == 0 {
+            log::trace!("Ending the group");
+            self.op_list.group_open = false;
+            // Don't merge the next edit into the group
+            self.mark_logical_op_break();
+        }
+    }
+
+    /// Return `true` if there is an operation to undo.
+    pub fn can_undo(&self) -> bool {
+        // Complexity note: `len()` takes an `O(len())` time because
+        // `neo_linked_list::LinkedList` doesn't track the element count
+        self.op_list.ops.len() - self.op_list.redo_depth > 0
+    }
+
+    /// Return `true` if there is an operation to redo.
+    pub fn can_redo(&self) -> bool {
+        self.op_list.redo_depth > 0
+    }
+
+    /// Get the edits comprising the first undoable operation. The history
+    /// state is modified assuming that the caller reverts the edits **in the
+    /// reverse order**.
+    ///
+    /// An ongoing group is implicitly closed.
+    pub fn undo(&mut self) -> Option<&[Edit<Text>]> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        self.mark_logical_op_break();
+        self.op_list.group_open = false;
+
+        // The first undoable operation
+        let node = self.op_list.ops.pop_back_node()?;
+        // ... is now the first redoable operation
+        self.op_list.ops.push_front_node(node);
+        self.op_list.redo_depth += 1;
+
+        self.op_list.ops.front().map(|op| &op.edits[..])
+    }
+
+    /// Get the edits comprising the first redoable operation. The history
+    /// state is modified assuming that the caller re-applies the edits in
+    /// order.
+    pub fn redo(&mut self) -> Option<&[Edit<Text>]> {
+        if !self.can_redo() {
+            return None;
+        }
+
+        self.mark_logical_op_break();
+        self.op_list.group_open = false;
+
+        // The first redoable operation
+        let node = self.op_list.ops.pop_front_node()?;
+        // ... is now the first undoable operation
+        self.op_list.ops.push_back_node(node);
+        self.op_list.redo_depth -= 1;
+
+        self.op_list.ops.back().map(|op| &op.edits[..])
+    }
+}
+
+impl<Text: TextTrait> UndoStackTx<Text> {
+    /// End the current transaction.
+    ///
+    /// `stack` must be the `UndoStack` from which this transaction was
+    /// started. `text` is the current contents of the text storage.
+    pub fn finish(self, stack: &mut UndoStack<Text>, text: &Text) {
+        log::trace!("Finishing `CoalescingStateTx`");
+
+        self.coalescing_state_tx.finish(
+            &mut stack.coalescing_state,
+            UndoStackCb::new(text, &mut stack.op_list),
+        );
+    }
+
+    /// See [`CoalescingStateTx::set_composition_active`].
+    pub fn set_composition_active(&mut self, active: bool) {
+        self.coalescing_state_tx.set_composition_active(active);
+    }
+
+    /// Record a text replacement action. This must be called before the caller
+    /// modifies the text storage.
+    pub fn replace_range(
+        &mut self,
+        stack: &mut UndoStack<Text>,
+        text: &Text,
+        range: Range<usize>,
+        new_text: Text,
+    ) {
+        self.coalescing_state_tx.replace_range(
+            range,
+            new_text,
+            UndoStackCb::new(text, &mut stack.op_list),
+        );
+    }
+}
+
+impl<Text: TextTrait> OpList<Text> {
+    /// Forget all redoable operations.
+    fn clear_redo(&mut self) {
+        log::trace!("Forgetting {:?} redoable operation(s)", self.redo_depth);
+
+        for _ in 0..self.redo_depth {
+            let op = self.ops.pop_front().unwrap();
+            self.size -= op.size();
+        }
+        self.redo_depth = 0;
+    }
+}
+
+/// Implements `CoalescingCb` for `UndoStack`.
+struct UndoStackCb<'a, Text: TextTrait> {
+    text: &'a Text,
+    op_list: &'a mut OpList<Text>,
+    /// `true` if the size of the last `Op` is not included in
+    /// `op_list.size`.
+    pending_last_op_size_accounting: bool,
+}
+
+impl<'a, Text: TextTrait> UndoStackCb<'a, Text> {
+    fn new(text: &'a Text, op_list: &'a mut OpList<Text>) -> Self {
+        debug_assert_eq!(op_list.redo_depth, 0);
+        Self {
+            text,
+            op_list,
+            pending_last_op_size_accounting: false,
+        }
+    }
+
+    fn clear_pending_last_op_size_accounting(&mut self) {
+        if self.pending_last_op_size_accounting {
+            let op = self.op_list.ops.back().unwrap();
+            self.op_list.size += op.size();
+            self.pending_last_op_size_accounting = false;
+
+            // Trim the history to limit the memory consumption
+            while self.op_list.size > self.op_list.max_size {
+                let has_one_or_less_items = {
+                    let ops = &self.op_list.ops;
+                    let null = std::ptr::null();
+                    let front = ops.front().map(|x| x as *const _).unwrap_or(null);
+                    let back = ops.back().map(|x| x as *const _).unwrap_or(null);
+                    front == back
+                };
+
+                // `CoalescingCb` has to remember at least the last change
+                if has_one_or_less_items {
+                    break;
+                }
+
+                let op = self.op_list.ops.pop_front().unwrap();
+                self.op_list.size -= op.size();
+                log::trace!(
+                    "Trimmed the history by removing an operation of size {:?}. \
+                    The current history size is {:?}",
+                    op.size(),
+                    self.op_list.size
+                );
+            }
+        }
+    }
+
+    /// Exclude the last `Op` from `op_list.size` until
+    /// `clear_pending_last_op_size_accounting` is called. The caller is about
+    /// to modify the last `Op`.
+    fn defer_last_op_size_accounting(&mut self) {
+        if !self.pending_last_op_size_accounting {
+            if let Some(op) = self.op_list.ops.back() {
+                self.op_list.size -= op.size();
+                self.pending_last_op_size_accounting = true;
+            }
+        }
+    }
+}
+
+impl<Text: TextTrait> Drop for UndoStackCb<'_, Text> {
+    fn drop(&mut self) {
+        self.clear_pending_last_op_size_accounting();
+    }
+}
+
+impl<Text: TextTrait> CoalescingCb<Text> for UndoStackCb<'_, Text> {
+    fn slice(&mut self, range: Range<usize>) -> Text {
+        self.text.slice(range)
+    }
+
+    fn push_edit(&mut self, edit: Edit<Text>) {
+        if self.op_list.group_open {
+            // Append `edit` to the current group
+            self.defer_last_op_size_accounting();
+            self.op_list.ops.back_mut().unwrap().edits.push(edit);
+        } else {
+            self.clear_pending_last_op_size_accounting();
+            self.op_list.ops.push_back(Op { edits: vec![edit] });
+            self.pending_last_op_size_accounting = true;
+            self.op_list.group_open = self.op_list.group_depth > 0;
+        }
+    }
+
+    fn pop_edit(&mut self) -> Option<Edit<Text>> {
+        self.defer_last_op_size_accounting();
+
+        let op = self.op_list.ops.back_mut()?;
+        let edit = op.edits.pop();
+
+        if op.edits.is_empty() {
+            self.op_list.ops.pop_back();
+            self.pending_last_op_size_accounting = false;
+            self.op_list.group_open = false;
+        }
+
+        edit
+    }
+
+    fn last_edit_mut(&mut self) -> Option<&mut Edit<Text>> {
+        self.defer_last_op_size_accounting();
+
+        self.op_list.ops.back_mut()?.edits.last_mut()
+    }
+}
+
+impl<Text: TextTrait> Op<Text> {
+    fn size(&self) -> usize {
+        self.edits
+            .iter()
+            .map(|edit| edit.old.len() + edit.new.len())
+            .sum()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -772,4 +1143,141 @@ mod tests {
             assert_eq!(*replayed, *expected_history);
         }
     }
+
+    /// Apply a replacement to `text` and record it in `stack`.
+    fn stack_replace(
+        stack: &mut UndoStack<String>,
+        text: &mut String,
+        range: Range<usize>,
+        new_text: &str,
+    ) {
+        let mut tx = stack.start_transaction();
+        tx.replace_range(stack, text, range.clone(), new_text.to_owned());
+        text.replace_range(range, new_text);
+        tx.finish(stack, text);
+    }
+
+    fn stack_undo(stack: &mut UndoStack<String>, text: &mut String) -> bool {
+        if let Some(edits) = stack.undo() {
+            for edit in edits.iter().rev() {
+                text.replace_range(edit.range_new(), &edit.old);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn stack_redo(stack: &mut UndoStack<String>, text: &mut String) -> bool {
+        if let Some(edits) = stack.redo() {
+            for edit in edits.iter() {
+                text.replace_range(edit.range_old(), &edit.new);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    #[test]
+    fn undo_stack_coalescing() {
+        let mut stack = UndoStack::new();
+        let mut text = String::new();
+
+        stack_replace(&mut stack, &mut text, 0..0, "h");
+        stack_replace(&mut stack, &mut text, 1..1, "i");
+        stack.mark_logical_op_break();
+        stack_replace(&mut stack, &mut text, 2..2, "!");
+        assert_eq!(text, "hi!");
+
+        assert!(stack_undo(&mut stack, &mut text));
+        assert_eq!(text, "hi");
+        assert!(stack_undo(&mut stack, &mut text));
+        assert_eq!(text, "");
+        assert!(!stack.can_undo());
+
+        assert!(stack_redo(&mut stack, &mut text));
+        assert_eq!(text, "hi");
+        assert!(stack.can_redo());
+
+        // A new edit forgets the redoable operations
+        stack_replace(&mut stack, &mut text, 0..0, "o");
+        assert_eq!(text, "ohi");
+        assert!(!stack.can_redo());
+    }
+
+    #[test]
+    fn undo_stack_group() {
+        let mut stack = UndoStack::new();
+        let mut text = String::new();
+
+        stack_replace(&mut stack, &mut text, 0..0, "world");
+
+        // Disjoint edits in a group are undone at once
+        stack.begin_undo_group();
+        stack_replace(&mut stack, &mut text, 0..0, "hello ");
+        stack.begin_undo_group();
+        stack_replace(&mut stack, &mut text, 11..11, "!");
+        stack.end_undo_group();
+        stack_replace(&mut stack, &mut text, 0..1, "H");
+        stack.end_undo_group();
+        assert_eq!(text, "Hello world!");
+
+        // This shouldn't be merged into the group
+        stack_replace(&mut stack, &mut text, 12..12, "?");
+        assert_eq!(text, "Hello world!?");
+
+        assert!(stack_undo(&mut stack, &mut text));
+        assert_eq!(text, "Hello world!");
+        assert!(stack_undo(&mut stack, &mut text));
+        assert_eq!(text, "world");
+        assert!(stack_redo(&mut stack, &mut text));
+        assert_eq!(text, "Hello world!");
+        assert!(stack_undo(&mut stack, &mut text));
+        assert!(stack_undo(&mut stack, &mut text));
+        assert_eq!(text, "");
+        assert!(!stack.can_undo());
+    }
+
+    #[test]
+    fn undo_stack_clear_in_group() {
+        let mut stack = UndoStack::new();
+        let mut text = String::new();
+
+        stack.begin_undo_group();
+        stack_replace(&mut stack, &mut text, 0..0, "hello");
+        stack.clear();
+        assert!(!stack.can_undo());
+
+        // The group is still open
+        stack_replace(&mut stack, &mut text, 5..5, " world");
+        stack_replace(&mut stack, &mut text, 0..1, "H");
+        stack.end_undo_group();
+        assert_eq!(text, "Hello world");
+
+        assert!(stack_undo(&mut stack, &mut text));
+        assert_eq!(text, "hello");
+        assert!(!stack.can_undo());
+    }
+
+    #[test]
+    fn undo_stack_max_size() {
+        let mut stack = UndoStack::new();
+        stack.set_max_size(2);
+        let mut text = String::new();
+
+        for i in 0..4 {
+            stack.mark_logical_op_break();
+            stack_replace(&mut stack, &mut text, i..i, "a");
+        }
+        assert_eq!(text, "aaaa");
+
+        // Only the two most recent operations are retained
+        let mut num_undos = 0;
+        while stack_undo(&mut stack, &mut text) {
+            num_undos += 1;
+        }
+        assert_eq!(num_undos, 2);
+        assert_eq!(text, "aa");
+    }
 }
@@ -6,6 +6,7 @@ use sorted_diff::{sorted_diff, In};
 use std::{
     cell::{Cell, RefCell},
     fmt,
+    rc::Rc,
 };
 use subscriber_list::{SubscriberList, UntypedSubscription as Sub};
 use tcw3_pal::mt_lazy_static;
@@ -32,6 +33,8 @@ pub const MAX_ELEM_DEPTH: usize = crate::uicore::MAX_VIEW_DEPTH;
 pub struct Manager {
     wm: pal::Wm,
     sheet_set: RefCell<SheetSet>,
+    /// The stylesheets set by `set_stylesheet_set`.
+    app_sheets: RefCell<StylesheetSet>,
     new_set_handlers: RefCell<SubscriberList<ManagerNewSheetSetCb>>,
     elems: RefCell<ElemPool>,
     /// All elements in `elems`.
@@ -57,6 +60,7 @@ impl fmt::Debug for Manager {
         f.debug_struct("Manager")
             .field("wm", &self.wm)
             .field("sheet_set", &())
+            .field("app_sheets", &self.app_sheets)
             .field("set_change_handlers", &())
             .field("new_set_handlers", &())
             .field("elems", &self.elems)
@@ -107,6 +111,7 @@ impl Manager {
         let this = Self {
             wm,
             sheet_set: RefCell::new(SheetSet { sheets: Vec::new() }),
+            app_sheets: RefCell::new(StylesheetSet::new()),
             new_set_handlers: RefCell::new(SubscriberList::new()),
             elems: RefCell::new(LeakyPool::with_token_store(SingletonToken::new())),
             all_elems: Cell::new(ListHead::new()),
@@ -120,6 +125,12 @@ impl Manager {
         let sheet_set = this.new_sheet_set();
         *this.sheet_set.borrow_mut() = sheet_set;
 
+        // Recreate the `SheetSet` when the system appearance changes so that
+        // stylesheets can follow it (e.g., the dark mode)
+        wm.subscribe_system_appearance_changed(Box::new(|wm| {
+            Manager::global(wm).update_sheet_set();
+        }));
+
         this
     }
 
@@ -133,11 +144,33 @@ impl Manager {
     ///
     /// The specified function is called when the stylesheet is updated for the
     /// next time, i.e., when the operating system's apperance setting is
-    /// updated or `update_sheet_set` is called.
+    /// updated or `update_sheet_set` is called. The function can use
+    /// `Wm::system_appearance` to choose stylesheets based on the current
+    /// appearance setting.
     pub fn subscribe_new_sheet_set(&self, cb: ManagerNewSheetSetCb) -> Sub {
         self.new_set_handlers.borrow_mut().insert(cb).untype()
     }
 
+    /// Replace the stylesheets supplied by the application (e.g., a theme
+    /// chosen by the user) and re-style all elements.
+    ///
+    /// The new stylesheets take effect all at once on the next screen update.
+    /// They are placed after the default stylesheet and before the ones
+    /// inserted by the handlers registered with `subscribe_new_sheet_set`.
+    /// Rules are ordered by their priorities regardless of their positions,
+    /// though.
+    ///
+    /// The total number of stylesheets must not exceed 16.
+    pub fn set_stylesheet_set(&'static self, sheets: StylesheetSet) {
+        *self.app_sheets.borrow_mut() = sheets;
+        self.update_sheet_set();
+    }
+
+    /// Get the stylesheets set by `set_stylesheet_set`.
+    pub fn stylesheet_set(&self) -> StylesheetSet {
+        self.app_sheets.borrow().clone()
+    }
+
     /// Force the recreation the stylesheet set.
     pub fn update_sheet_set(&'static self) {
         let sheet_set = self.new_sheet_set();
@@ -165,9 +198,13 @@ impl Manager {
     /// `new_set_handlers`.
     fn new_sheet_set(&self) -> SheetSet {
         let mut sheet_set = SheetSet {
-            sheets: vec![Box::new(DefaultStylesheet)],
+            sheets: vec![Rc::new(DefaultStylesheet)],
         };
 
+        sheet_set
+            .sheets
+            .extend(self.app_sheets.borrow().sheets.iter().cloned());
+
         for handler in self.new_set_handlers.borrow().iter() {
             handler(
                 self.wm,
@@ -270,7 +307,52 @@ pub struct NewSheetSetCtx<'a> {
 impl NewSheetSetCtx<'_> {
     /// Insert a new `Stylesheet`.
     pub fn insert_stylesheet(&mut self, stylesheet: impl Stylesheet + 'static) {
-        self.sheet_set.sheets.push(Box::new(stylesheet));
+        self.sheet_set.sheets.push(Rc::new(stylesheet));
+    }
+}
+
+/// A list of stylesheets that can be passed to
+/// [`Manager::set_stylesheet_set`].
+///
+/// Cloning a `StylesheetSet` is cheap because the stylesheets are shared.
+#[derive(Clone, Default)]
+pub struct StylesheetSet {
+    sheets: Vec<Rc<dyn StylesheetWrap>>,
+}
+
+impl fmt::Debug for StylesheetSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StylesheetSet")
+            .field("len", &self.sheets.len())
+            .finish()
+    }
+}
+
+impl StylesheetSet {
+    /// Construct an empty `StylesheetSet`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a `Stylesheet`.
+    pub fn push(&mut self, stylesheet: impl Stylesheet + 'static) {
+        self.sheets.push(Rc::new(stylesheet));
+    }
+
+    /// Append a `Stylesheet`, returning `self`.
+    pub fn with(mut self, stylesheet: impl Stylesheet + 'static) -> Self {
+        self.push(stylesheet);
+        self
+    }
+
+    /// Get the number of the stylesheets.
+    pub fn len(&self) -> usize {
+        self.sheets.len()
+    }
+
+    /// Get a flag indicating whether the set contains no stylesheets.
+    pub fn is_empty(&self) -> bool {
+        self.sheets.is_empty()
     }
 }
 
@@ -363,7 +445,7 @@ impl RuleTag {
 
 /// A stylesheet set.
 pub(crate) struct SheetSet {
-    sheets: Vec<Box<dyn StylesheetWrap>>,
+    sheets: Vec<Rc<dyn StylesheetWrap>>,
 }
 
 impl SheetSet {
@@ -412,6 +494,7 @@ bitflags! {
         const LAYER_CENTER = 1 << 5;
         const LAYER_XFORM = 1 << 6;
         const LAYER_FLAGS = 1 << 7;
+        const LAYER_SHAPE = 1 << 15;
         /// Any properties of decorative layers.
         const LAYER_ALL = Self::NUM_LAYERS.bits |
             Self::LAYER_IMG.bits |
@@ -420,13 +503,17 @@ bitflags! {
             Self::LAYER_OPACITY.bits |
             Self::LAYER_CENTER.bits |
             Self::LAYER_XFORM.bits |
-            Self::LAYER_FLAGS.bits;
+            Self::LAYER_FLAGS.bits |
+            Self::LAYER_SHAPE.bits;
         const CLIP_LAYER = 1 << 8;
         const LAYOUT = 1 << 9;
         const FONT = 1 << 10;
         const FG_COLOR = 1 << 11;
         const BG_COLOR = 1 << 12;
         const PADDING = 1 << 13;
+        /// Transition parameters. They don't affect the current appearance
+        /// and take effect when other properties change.
+        const TRANSITION = 1 << 14;
     }
 }
 
@@ -438,9 +525,17 @@ impl Prop {
             Prop::LayerBgColor(_) => PropKindFlags::LAYER_BG_COLOR,
             Prop::LayerMetrics(_) => PropKindFlags::LAYER_BOUNDS,
             Prop::LayerOpacity(_) => PropKindFlags::LAYER_OPACITY,
+            Prop::LayerBgColorTransition(_) => PropKindFlags::TRANSITION,
+            Prop::LayerOpacityTransition(_) => PropKindFlags::TRANSITION,
             Prop::LayerCenter(_) => PropKindFlags::LAYER_CENTER,
             Prop::LayerXform(_) => PropKindFlags::LAYER_XFORM,
             Prop::LayerFlags(_) => PropKindFlags::LAYER_FLAGS,
+            Prop::LayerCornerRadius(_) => PropKindFlags::LAYER_SHAPE,
+            Prop::LayerMask(_) => PropKindFlags::LAYER_SHAPE,
+            Prop::LayerShadowOffset(_) => PropKindFlags::LAYER_SHAPE,
+            Prop::LayerShadowRadius(_) => PropKindFlags::LAYER_SHAPE,
+            Prop::LayerShadowColor(_) => PropKindFlags::LAYER_SHAPE,
+            Prop::LayerBackdropBlurRadius(_) => PropKindFlags::LAYER_SHAPE,
             Prop::SubviewLayouter => PropKindFlags::LAYOUT,
             Prop::SubviewPadding => PropKindFlags::LAYOUT,
             Prop::SubviewMetrics(_) => PropKindFlags::LAYOUT,
@@ -789,3 +884,62 @@ impl ElemRules {
         flags
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        stylesheet,
+        testing::{prelude::*, use_testing_wm},
+    };
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn set_stylesheet_set(twm: &dyn TestingWm) {
+        const CUSTOM_ID: ClassSet = ClassSet::id(42);
+
+        let wm = twm.wm();
+        let manager = Manager::global(wm);
+
+        let elem = Elem::new(manager);
+        elem.set_class_set(CUSTOM_ID);
+
+        let change_count = Rc::new(Cell::new(0));
+        {
+            let change_count = Rc::clone(&change_count);
+            elem.set_on_change(Box::new(move |_, _| {
+                change_count.set(change_count.get() + 1);
+            }));
+        }
+        twm.step_unsend();
+        assert!(matches!(
+            elem.compute_prop(Prop::NumLayers),
+            PropValue::Usize(0)
+        ));
+        let initial_change_count = change_count.get();
+
+        manager.set_stylesheet_set(StylesheetSet::new().with(stylesheet! {
+            ([#CUSTOM_ID]) (priority = 100) {
+                num_layers: 2,
+            },
+        }));
+        assert_eq!(manager.stylesheet_set().len(), 1);
+
+        // The change is applied on the next update
+        twm.step_unsend();
+        assert!(matches!(
+            elem.compute_prop(Prop::NumLayers),
+            PropValue::Usize(2)
+        ));
+        assert_eq!(change_count.get(), initial_change_count + 1);
+
+        // Switch back
+        manager.set_stylesheet_set(StylesheetSet::new());
+        twm.step_unsend();
+        assert!(matches!(
+            elem.compute_prop(Prop::NumLayers),
+            PropValue::Usize(0)
+        ));
+        assert_eq!(change_count.get(), initial_change_count + 2);
+    }
+}
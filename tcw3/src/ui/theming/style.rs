@@ -14,7 +14,7 @@ bitflags! {
     /// Note that they are all normal styling classes. For example, `HOVER`
     /// does not get applied automatically like CSS's `:hover` pseudo
     /// selector.
-    pub struct ClassSet: u32 {
+    pub struct ClassSet: u64 {
         /// The mouse pointer inside the element.
         const HOVER = 1;
         /// The element is active, e.g., a button is being pressed down.
@@ -48,8 +48,14 @@ bitflags! {
 
         const USER1 = 1 << 15;
 
+        /// The element is selected, e.g., a selected row in a list view.
+        const SELECTED = 1 << 16;
+        /// The element is in an indeterminate state, e.g., a tri-state
+        /// checkbox that is neither checked nor unchecked.
+        const INDETERMINATE = 1 << 17;
+
         /// The bit mask for ID values. See [`ClassSet::id`] for more.
-        const ID_MASK = 0xffff_0000;
+        const ID_MASK = 0xffff_0000_0000_0000;
     }
 }
 
@@ -96,7 +102,7 @@ impl ClassSet {
     /// ```
     pub const fn id(id: u16) -> Self {
         // Use multiplication to detect overflow at compile time
-        Self::from_bits_truncate((id as u32 + 1) * (1u32 << 16))
+        Self::from_bits_truncate((id as u64 + 1) * (1u64 << 48))
     }
 }
 
@@ -112,6 +118,17 @@ pub mod elem_id {
                 , SLIDER_KNOB
                 , SLIDER_TICKS
                 , SLIDER_LABELS
+                , TEXT_AREA
+                , TABLE_HEADER
+                , TABLE_HEADER_CELL
+                , PROGRESS_BAR
+                , PROGRESS_BAR_FILL
+                , TOAST
+                , LINK
+                , ALERT
+                , ALERT_INFO
+                , ALERT_WARNING
+                , ALERT_ERROR
     }
 }
 
@@ -139,6 +156,9 @@ pub mod roles {
                 , SLIDER_KNOB
                 , SLIDER_TICKS
                 , SLIDER_LABELS
+                , TOAST_ACTION
+                , ALERT_ICON
+                , ALERT_BUTTONS
     }
 }
 
@@ -167,6 +187,7 @@ def_prop_value! {
         LayerFlags(LayerFlags),
         Layouter(Layouter),
         AlignFlags(AlignFlags),
+        Transition(Transition),
     }
 }
 
@@ -207,6 +228,13 @@ def_prop! {
         #[default(PropValue::Rgbaf32(RGBAF32::new(0.0, 0.0, 0.0, 0.0)))]
         LayerBgColor(LayerId),
 
+        /// The [`Transition`] of [`LayerBgColor`] of the `n`-th layer.
+        ///
+        /// [`LayerBgColor`]: self::Prop::LayerBgColor
+        #[snake_case(layer_bg_color_transition)]
+        #[default(PropValue::Transition(Transition::NONE))]
+        LayerBgColorTransition(LayerId),
+
         /// The [`Metrics`] of the `n`-th layer.
         #[snake_case(layer_metrics)]
         #[default(PropValue::Metrics(Rob::from_ref(&DEFAULT_METRICS)))]
@@ -217,6 +245,13 @@ def_prop! {
         #[default(PropValue::Float(1.0))]
         LayerOpacity(LayerId),
 
+        /// The [`Transition`] of [`LayerOpacity`] of the `n`-th layer.
+        ///
+        /// [`LayerOpacity`]: self::Prop::LayerOpacity
+        #[snake_case(layer_opacity_transition)]
+        #[default(PropValue::Transition(Transition::NONE))]
+        LayerOpacityTransition(LayerId),
+
         /// The `content_center` of the `n`-th layer.
         #[snake_case(layer_center)]
         #[default(PropValue::Box2(box2! {
@@ -237,6 +272,57 @@ def_prop! {
         #[default(PropValue::LayerFlags(LayerFlags::default()))]
         LayerFlags(LayerId),
 
+        /// The corner radii of the `n`-th layer, in the order of the
+        /// top-left, top-right, bottom-right, and bottom-left corners.
+        ///
+        /// See [`LayerAttrs::corner_radius`].
+        ///
+        /// [`LayerAttrs::corner_radius`]: crate::pal::iface::LayerAttrs::corner_radius
+        #[snake_case(layer_corner_radius)]
+        #[default(PropValue::F32x4([0.0; 4]))]
+        LayerCornerRadius(LayerId),
+
+        /// The [`HImg`] used as the mask of the `n`-th layer.
+        ///
+        /// See [`LayerAttrs::mask`].
+        ///
+        /// [`HImg`]: crate::images::HImg
+        /// [`LayerAttrs::mask`]: crate::pal::iface::LayerAttrs::mask
+        #[snake_case(layer_mask)]
+        #[default(PropValue::Himg(None))]
+        LayerMask(LayerId),
+
+        /// The offset of the drop shadow of the `n`-th layer.
+        ///
+        /// See [`LayerAttrs::shadow`].
+        ///
+        /// [`LayerAttrs::shadow`]: crate::pal::iface::LayerAttrs::shadow
+        #[snake_case(layer_shadow_offset)]
+        #[default(PropValue::Vector2(Vector2::new(0.0, 0.0)))]
+        LayerShadowOffset(LayerId),
+
+        /// The blur radius of the drop shadow of the `n`-th layer.
+        #[snake_case(layer_shadow_radius)]
+        #[default(PropValue::Float(0.0))]
+        LayerShadowRadius(LayerId),
+
+        /// The color of the drop shadow of the `n`-th layer. The layer doesn't
+        /// have a shadow if the color is fully transparent, which is the
+        /// default value.
+        #[snake_case(layer_shadow_color)]
+        #[default(PropValue::Rgbaf32(RGBAF32::new(0.0, 0.0, 0.0, 0.0)))]
+        LayerShadowColor(LayerId),
+
+        /// The radius of the Gaussian blur applied to the contents behind the
+        /// `n`-th layer.
+        ///
+        /// See [`LayerAttrs::backdrop_blur_radius`].
+        ///
+        /// [`LayerAttrs::backdrop_blur_radius`]: crate::pal::iface::LayerAttrs::backdrop_blur_radius
+        #[snake_case(layer_backdrop_blur_radius)]
+        #[default(PropValue::Float(0.0))]
+        LayerBackdropBlurRadius(LayerId),
+
         /// The layout algorithm for subviews. Defaults to [`Layouter::Abs`].
         #[snake_case(subview_layouter)]
         #[default(PropValue::Layouter(Layouter::Abs))]
@@ -468,3 +554,119 @@ impl LayerXform {
             * Matrix3::from_translation(-anchor)
     }
 }
+
+/// Specifies how a styling property animates when its computed value changes.
+///
+/// The computed value changes, for example, when a [`ClassSet`] bit such as
+/// `HOVER` or `ACTIVE` is added to or removed from a styled element.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    /// The duration of the transition, measured in seconds. The new value
+    /// takes effect immediately if this is zero.
+    pub duration: f32,
+    /// The easing function of the transition.
+    pub timing: TimingFunction,
+}
+
+impl Transition {
+    /// A `Transition` indicating that changes take effect immediately.
+    pub const NONE: Self = Self::new(0.0, TimingFunction::Linear);
+
+    pub const fn new(duration: f32, timing: TimingFunction) -> Self {
+        Self { duration, timing }
+    }
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// An easing function used by [`Transition`]. They are defined in the same
+/// way as CSS's `<easing-function>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimingFunction {
+    Linear,
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    /// A cubic Bézier curve with control points `(0, 0)`, `(x1, y1)`,
+    /// `(x2, y2)`, and `(1, 1)`, specified as `[x1, y1, x2, y2]`. `x1` and
+    /// `x2` must be in range `[0, 1]`.
+    CubicBezier([f32; 4]),
+}
+
+impl TimingFunction {
+    /// Get the control points `[x1, y1, x2, y2]` of the cubic Bézier curve
+    /// representing the easing function.
+    pub fn control_points(&self) -> [f32; 4] {
+        match *self {
+            TimingFunction::Linear => [0.0, 0.0, 1.0, 1.0],
+            TimingFunction::Ease => [0.25, 0.1, 0.25, 1.0],
+            TimingFunction::EaseIn => [0.42, 0.0, 1.0, 1.0],
+            TimingFunction::EaseOut => [0.0, 0.0, 0.58, 1.0],
+            TimingFunction::EaseInOut => [0.42, 0.0, 0.58, 1.0],
+            TimingFunction::CubicBezier(points) => points,
+        }
+    }
+
+    /// Evaluate the easing function at the input progress value `t`, which is
+    /// clamped to range `[0, 1]`.
+    pub fn eval(&self, t: f32) -> f32 {
+        use crate::ui::animation::eval_cubic_bezier;
+
+        let t = t.max(0.0).min(1.0);
+        if let TimingFunction::Linear = self {
+            return t;
+        }
+
+        let [x1, y1, x2, y2] = self.control_points();
+
+        // Find the curve parameter `p` such that `x(p) = t`. `x(p)` is
+        // monotonic in range `[0, 1]` because `x1, x2 ∈ [0, 1]`, so bisection
+        // always converges.
+        let (mut lo, mut hi) = (0.0f32, 1.0f32);
+        for _ in 0..24 {
+            let mid = (lo + hi) * 0.5;
+            if eval_cubic_bezier(mid, x1, x2, 1.0) < t {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        eval_cubic_bezier((lo + hi) * 0.5, y1, y2, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_function_endpoints() {
+        for timing in &[
+            TimingFunction::Linear,
+            TimingFunction::Ease,
+            TimingFunction::EaseIn,
+            TimingFunction::EaseOut,
+            TimingFunction::EaseInOut,
+            TimingFunction::CubicBezier([0.0, 1.5, 1.0, -0.5]),
+        ] {
+            assert!(timing.eval(0.0).abs() < 1.0e-4, "{:?}", timing);
+            assert!((timing.eval(1.0) - 1.0).abs() < 1.0e-4, "{:?}", timing);
+            assert!(timing.eval(-1.0).abs() < 1.0e-4, "{:?}", timing);
+            assert!((timing.eval(2.0) - 1.0).abs() < 1.0e-4, "{:?}", timing);
+        }
+    }
+
+    #[test]
+    fn timing_function_shape() {
+        assert_eq!(TimingFunction::Linear.eval(0.3), 0.3);
+        assert!((TimingFunction::EaseInOut.eval(0.5) - 0.5).abs() < 1.0e-4);
+        assert!(TimingFunction::EaseIn.eval(0.3) < 0.3);
+        assert!(TimingFunction::EaseOut.eval(0.3) > 0.3);
+    }
+}
@@ -3,7 +3,10 @@ use std::ops::Range;
 
 use super::{
     manager::PropKindFlags,
-    style::{elem_id, roles, ClassSet, ElemClassPath, Metrics, Prop, PropValue},
+    style::{
+        elem_id, roles, ClassSet, ElemClassPath, Metrics, Prop, PropValue, TimingFunction,
+        Transition,
+    },
 };
 
 /// Represents a single stylesheet rule in [`Stylesheet`].
@@ -115,41 +118,51 @@ impl Rule {
 
 impl Selector {
     fn matches(&self, path: &ElemClassPath) -> bool {
-        let mut it = path.iter().rev();
-        if !self.target.matches(&it.next().unwrap()) {
-            return false;
-        }
+        selector_matches(&self.target, self.ancestors, path)
+    }
+}
 
-        let mut cur_maybe = it.next();
+/// Check if a selector specified by `target` and `ancestors` (see
+/// [`Selector`]) matches the given `ElemClassPath`.
+pub(crate) fn selector_matches(
+    target: &ElemCriteria,
+    ancestors: &[(bool, ElemCriteria)],
+    path: &ElemClassPath,
+) -> bool {
+    let mut it = path.iter().rev();
+    if !target.matches(&it.next().unwrap()) {
+        return false;
+    }
 
-        for (direct, criteria) in self.ancestors.iter() {
-            if *direct {
-                if let Some(cur) = cur_maybe {
-                    if !criteria.matches(&cur) {
-                        return false;
-                    }
-                    cur_maybe = it.next();
-                } else {
+    let mut cur_maybe = it.next();
+
+    for (direct, criteria) in ancestors.iter() {
+        if *direct {
+            if let Some(cur) = cur_maybe {
+                if !criteria.matches(&cur) {
                     return false;
                 }
+                cur_maybe = it.next();
             } else {
-                loop {
-                    if let Some(cur) = cur_maybe {
-                        if criteria.matches(&cur) {
-                            cur_maybe = it.next();
-                            break;
-                        } else {
-                            cur_maybe = it.next();
-                        }
+                return false;
+            }
+        } else {
+            loop {
+                if let Some(cur) = cur_maybe {
+                    if criteria.matches(&cur) {
+                        cur_maybe = it.next();
+                        break;
                     } else {
-                        return false;
+                        cur_maybe = it.next();
                     }
+                } else {
+                    return false;
                 }
             }
         }
-
-        true
     }
+
+    true
 }
 
 impl ElemCriteria {
@@ -514,6 +527,7 @@ use crate::{
     images::{figures, himg_figures, himg_from_figures_with_size, HImg},
     pal::RGBAF32,
     stvg::StvgImg,
+    ui::{theming::Layouter, AlignFlags},
 };
 use cggeom::box2;
 use cgmath::Vector2;
@@ -532,6 +546,8 @@ mod assets {
     pub static CHECKBOX_LIGHT_ACT: Stvg = stvg!("assets/checkbox_light_act.svg");
     pub static CHECKBOX_LIGHT_CHECKED: Stvg = stvg!("assets/checkbox_light_checked.svg");
     pub static CHECKBOX_LIGHT_CHECKED_ACT: Stvg = stvg!("assets/checkbox_light_checked_act.svg");
+    pub static CHECKBOX_LIGHT_MIXED: Stvg = stvg!("assets/checkbox_light_mixed.svg");
+    pub static CHECKBOX_LIGHT_MIXED_ACT: Stvg = stvg!("assets/checkbox_light_mixed_act.svg");
 
     pub static RADIO_LIGHT: Stvg = stvg!("assets/radio_light.svg");
     pub static RADIO_LIGHT_ACT: Stvg = stvg!("assets/radio_light_act.svg");
@@ -566,6 +582,16 @@ const SLIDER_LABELS_DISTANCE: f32 = SLIDER_TICKS_DISTANCE + SLIDER_TICKS_SIZE +
 /// The margin between custom label views and the slider's frame.
 const SLIDER_LABELS_MARGIN: f32 = 2.0;
 
+const PROGRESS_BAR_VISUAL_WIDTH: f32 = 6.0;
+const PROGRESS_BAR_VISUAL_RADIUS: f32 = PROGRESS_BAR_VISUAL_WIDTH / 2.0;
+const PROGRESS_BAR_MARGIN: f32 = 4.0;
+const PROGRESS_BAR_WIDTH: f32 = PROGRESS_BAR_VISUAL_WIDTH + PROGRESS_BAR_MARGIN * 2.0;
+const PROGRESS_BAR_LEN_MIN: f32 = 40.0;
+
+const TOAST_CORNER_RADIUS: f32 = 6.0;
+
+const ALERT_ICON_SIZE: f32 = 32.0;
+
 const FIELD_HEIGHT: f32 = 20.0;
 
 /// Replace blue with a global tint color, and create a `HImg`.
@@ -611,6 +637,7 @@ lazy_static! {
             ]),
             layer_center[0]: box2! { point: [0.5, 0.5] },
             layer_opacity[0]: 0.0,
+            layer_opacity_transition[0]: Transition::new(0.15, TimingFunction::EaseOut),
             layer_metrics[0]: Metrics {
                 margin: [-2.0; 4],
                 ..Metrics::default()
@@ -638,6 +665,7 @@ lazy_static! {
             },
             layer_center[1]: box2! { point: [0.5, 0.5] },
             layer_opacity[1]: 0.8,
+            layer_opacity_transition[1]: Transition::new(0.1, TimingFunction::Ease),
 
             subview_metrics[roles::GENERIC]: Metrics {
                 margin: [3.0, 8.0, 3.0, 8.0],
@@ -727,6 +755,12 @@ lazy_static! {
         ([.CHECKBOX.ACTIVE.CHECKED]) (priority = 400) {
             #[dyn] layer_img[1]: Some(recolor_tint(&assets::CHECKBOX_LIGHT_CHECKED_ACT)),
         },
+        ([.CHECKBOX.INDETERMINATE]) (priority = 300) {
+            #[dyn] layer_img[1]: Some(recolor_tint(&assets::CHECKBOX_LIGHT_MIXED)),
+        },
+        ([.CHECKBOX.ACTIVE.INDETERMINATE]) (priority = 400) {
+            #[dyn] layer_img[1]: Some(recolor_tint(&assets::CHECKBOX_LIGHT_MIXED_ACT)),
+        },
 
         // Radio button (identical to checkbox except for images)
         ([.RADIO_BUTTON]) (priority = 100) {
@@ -815,11 +849,45 @@ lazy_static! {
             fg_color: RGBAF32::new(0.0, 0.0, 0.0, 1.0),
             padding: [0.0, 3.0, 0.0, 3.0],
         },
+        // Multi-line entry wrapper (`TextArea`)
+        ([#TEXT_AREA.ENTRY]) (priority = 150) {
+            min_size: Vector2::new(0.0, FIELD_HEIGHT * 3.0),
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [0.0; 4],
+                ..Metrics::default()
+            },
+        },
+        // Entry text in `TextArea`
+        ([] < [#TEXT_AREA.ENTRY]) (priority = 150) {
+            padding: [3.0; 4],
+        },
         // Text selection rectangle in a text entry widget
         ([#TEXT_SELECTION]) (priority = 100) {
             bg_color: RGBAF32::new(0.3, 0.6, 1.0, 0.5),
         },
 
+        // Table header
+        ([#TABLE_HEADER]) (priority = 100) {
+            num_layers: 1,
+            layer_bg_color[0]: RGBAF32::new(0.93, 0.93, 0.93, 1.0),
+        },
+        ([#TABLE_HEADER_CELL.BUTTON]) (priority = 150) {
+            layer_opacity[1]: 0.0,
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [2.0, 6.0, 2.0, 6.0],
+                .. Metrics::default()
+            },
+        },
+        ([#TABLE_HEADER_CELL.BUTTON.HOVER]) (priority = 250) {
+            layer_opacity[1]: 0.6,
+        },
+
+        // Selected row in a list or table
+        ([.SELECTED]) (priority = 100) {
+            num_layers: 1,
+            layer_bg_color[0]: RGBAF32::new(0.3, 0.6, 1.0, 0.3),
+        },
+
         // Scrollbar
         ([.SCROLLBAR]) (priority = 100) {
             num_layers: 1,
@@ -1049,12 +1117,152 @@ lazy_static! {
             fg_color: RGBAF32::new(0.5, 0.5, 0.5, 1.0),
         },
 
+        // Progress bar
+        ([#PROGRESS_BAR]) (priority = 100) {
+            num_layers: 1,
+            layer_metrics[0]: Metrics {
+                margin: [PROGRESS_BAR_MARGIN; 4],
+                .. Metrics::default()
+            },
+            subview_metrics[roles::GENERIC]: Metrics {
+                margin: [PROGRESS_BAR_MARGIN; 4],
+                .. Metrics::default()
+            },
+        },
+        ([#PROGRESS_BAR:not(.VERTICAL)]) (priority = 100) {
+            #[dyn] layer_img[0]: Some(himg_from_figures_with_size(
+                figures![
+                    rect([0.5, 0.5, 0.5, 0.2]).radius(PROGRESS_BAR_VISUAL_RADIUS)
+                ],
+                [PROGRESS_BAR_VISUAL_WIDTH + 2.0, PROGRESS_BAR_VISUAL_WIDTH],
+            )),
+            layer_center[0]: box2! { min: [0.5, 0.0], max: [0.5, 1.0] },
+            allow_grow: [true, false],
+            min_size: Vector2::new(PROGRESS_BAR_LEN_MIN, PROGRESS_BAR_WIDTH),
+        },
+        ([#PROGRESS_BAR.VERTICAL]) (priority = 100) {
+            #[dyn] layer_img[0]: Some(himg_from_figures_with_size(
+                figures![
+                    rect([0.5, 0.5, 0.5, 0.2]).radius(PROGRESS_BAR_VISUAL_RADIUS)
+                ],
+                [PROGRESS_BAR_VISUAL_WIDTH, PROGRESS_BAR_VISUAL_WIDTH + 2.0],
+            )),
+            layer_center[0]: box2! { min: [0.0, 0.5], max: [1.0, 0.5] },
+            allow_grow: [false, true],
+            min_size: Vector2::new(PROGRESS_BAR_WIDTH, PROGRESS_BAR_LEN_MIN),
+        },
+
+        // Progress bar filled portion
+        ([#PROGRESS_BAR_FILL] < [#PROGRESS_BAR:not(.VERTICAL)]) (priority = 100) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_from_figures_with_size(
+                figures![
+                    rect([0.2, 0.5, 0.9, 1.0]).radius(PROGRESS_BAR_VISUAL_RADIUS)
+                ],
+                [PROGRESS_BAR_VISUAL_WIDTH + 2.0, PROGRESS_BAR_VISUAL_WIDTH],
+            )),
+            layer_center[0]: box2! { min: [0.5, 0.0], max: [0.5, 1.0] },
+        },
+        ([#PROGRESS_BAR_FILL] < [#PROGRESS_BAR.VERTICAL]) (priority = 100) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_from_figures_with_size(
+                figures![
+                    rect([0.2, 0.5, 0.9, 1.0]).radius(PROGRESS_BAR_VISUAL_RADIUS)
+                ],
+                [PROGRESS_BAR_VISUAL_WIDTH, PROGRESS_BAR_VISUAL_WIDTH + 2.0],
+            )),
+            layer_center[0]: box2! { min: [0.0, 0.5], max: [1.0, 0.5] },
+        },
+
+        // Toast banner
+        ([#TOAST]) (priority = 100) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_figures![
+                rect([0.2, 0.2, 0.2, 0.8]).radius(TOAST_CORNER_RADIUS)
+            ]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            layer_corner_radius[0]: [TOAST_CORNER_RADIUS; 4],
+            layer_shadow_offset[0]: Vector2::new(0.0, 2.0),
+            layer_shadow_radius[0]: 12.0,
+            layer_shadow_color[0]: RGBAF32::new(0.0, 0.0, 0.0, 0.3),
+            layer_backdrop_blur_radius[0]: 20.0,
+
+            subview_layouter: Layouter::Table,
+            subview_padding: [10.0, 14.0, 10.0, 14.0],
+            subview_table_cell[roles::GENERIC]: [0, 0],
+            subview_table_align[roles::GENERIC]: AlignFlags::LEFT,
+            subview_table_col_spacing[0]: 12.0,
+            subview_table_cell[roles::TOAST_ACTION]: [1, 0],
+            subview_table_align[roles::TOAST_ACTION]: AlignFlags::RIGHT,
+        },
+        ([.LABEL] < [#TOAST]) (priority = 100) {
+            fg_color: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
+        },
+
+        // Hyperlinks in `RichLabel`
+        ([#LINK]) (priority = 100) {
+            fg_color: RGBAF32::new(0.1, 0.4, 0.9, 1.0),
+        },
+        ([#LINK.HOVER]) (priority = 150) {
+            fg_color: RGBAF32::new(0.2, 0.5, 1.0, 1.0),
+        },
+        ([#LINK.ACTIVE]) (priority = 200) {
+            fg_color: RGBAF32::new(0.05, 0.25, 0.6, 1.0),
+        },
+
+        // Fallback alert window (`ui::alert`)
+        ([#ALERT]) (priority = 100) {
+            num_layers: 1,
+            layer_bg_color[0]: RGBAF32::new(0.93, 0.93, 0.93, 1.0),
+
+            subview_layouter: Layouter::Table,
+            subview_padding: [20.0, 20.0, 20.0, 20.0],
+            subview_table_cell[roles::ALERT_ICON]: [0, 0],
+            subview_table_align[roles::ALERT_ICON]: AlignFlags::TOP,
+            subview_table_cell[roles::GENERIC]: [1, 0],
+            subview_table_align[roles::GENERIC]: AlignFlags::LEFT,
+            subview_table_cell[roles::ALERT_BUTTONS]: [1, 1],
+            subview_table_align[roles::ALERT_BUTTONS]: AlignFlags::RIGHT,
+            subview_table_col_spacing[0]: 16.0,
+            subview_table_row_spacing[0]: 20.0,
+        },
+        ([.LABEL] < [#ALERT]) (priority = 100) {
+            fg_color: RGBAF32::new(0.0, 0.0, 0.0, 1.0),
+        },
+        ([#ALERT_INFO]) (priority = 100) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_figures![
+                rect([0.1, 0.4, 0.9, 1.0]).radius(ALERT_ICON_SIZE / 2.0)
+            ]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            min_size: Vector2::new(ALERT_ICON_SIZE, ALERT_ICON_SIZE),
+        },
+        ([#ALERT_WARNING]) (priority = 100) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_figures![
+                rect([1.0, 0.6, 0.0, 1.0]).radius(ALERT_ICON_SIZE / 2.0)
+            ]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            min_size: Vector2::new(ALERT_ICON_SIZE, ALERT_ICON_SIZE),
+        },
+        ([#ALERT_ERROR]) (priority = 100) {
+            num_layers: 1,
+            #[dyn] layer_img[0]: Some(himg_figures![
+                rect([0.9, 0.15, 0.1, 1.0]).radius(ALERT_ICON_SIZE / 2.0)
+            ]),
+            layer_center[0]: box2! { point: [0.5, 0.5] },
+            min_size: Vector2::new(ALERT_ICON_SIZE, ALERT_ICON_SIZE),
+        },
+
         // Splitter
         ([#SPLITTER]) (priority = 100) {
             num_layers: 1,
             layer_bg_color[0]: RGBAF32::new(0.5, 0.5, 0.5, 0.8),
             min_size: Vector2::new(1.0, 1.0),
         },
+        ([#SPLITTER.FOCUS]) (priority = 150) {
+            layer_bg_color[0]: FOCUS_RING_COLOR,
+        },
     };
 }
 
@@ -0,0 +1,1139 @@
+//! A text representation of stylesheets, parsed at runtime.
+//!
+//! The syntax closely follows that of the [`stylesheet!`] macro:
+//!
+//! ```text
+//! // Line comments and /* block comments */ are allowed.
+//! [#MY_LABEL.LABEL] < [.BUTTON.ACTIVE] (priority = 100) {
+//!     fg_color: #ffffff;
+//! }
+//!
+//! [.BUTTON:not(.ACTIVE)] .. [#TOOLBAR] (priority = 100) {
+//!     num_layers: 1;
+//!     layer_img[0]: image(button_bg);
+//!     layer_bg_color[0]: rgba(0.2, 0.2, 0.2, 0.5);
+//!     layer_metrics[0]: margin(2 2 2 2);
+//!     subview_metrics[generic]: margin(4 8 4 8) size(nan 20);
+//!     font: small_emph;
+//! }
+//! ```
+//!
+//! Element ID values (e.g., `#MY_LABEL`) and images (e.g., `button_bg`) are
+//! referred to by names, which must be registered to [`TextStylesheetParser`]
+//! before parsing. The system element IDs ([`elem_id`]) are registered by
+//! default.
+//!
+//! Property values are written as follows:
+//!
+//!  - Colors (`layer_bg_color`, `layer_shadow_color`, `fg_color`,
+//!    `bg_color`): `#rgb`,
+//!    `#rrggbb`, `#rrggbbaa`, or `rgba(r, g, b, a)` where each component is
+//!    in range `[0, 1]`.
+//!  - [`Metrics`]: `margin(top right bottom left)` and/or `size(w h)`.
+//!    Omitted parts take the default values of [`Metrics::default`].
+//!  - Images (`layer_img`, `layer_mask`): `none` or `image(name)`.
+//!  - Flags (`layer_flags`, `subview_table_align`): flag names in snake case
+//!    separated by `|` (e.g., `left | top`), or `none`.
+//!  - Enumerations (`subview_layouter`, `font`): variant names in snake case.
+//!  - [`Transition`]s (`layer_bg_color_transition`, etc.): `none` or a
+//!    duration (`150ms` or `0.15s`) optionally followed by a timing function
+//!    (`linear`, `ease` (default), `ease_in`, `ease_out`, `ease_in_out`, or
+//!    `cubic_bezier(x1, y1, x2, y2)`).
+//!  - Other values: whitespace-separated lists of numbers or booleans (e.g.,
+//!    `padding: 4 8 4 8;`, `allow_grow: true false;`).
+//!
+//! Roles (the parameter of `subview_*` properties) can be specified either by
+//! a number or by a snake-case name of a constant in [`roles`].
+//!
+//! [`stylesheet!`]: crate::stylesheet
+//! [`elem_id`]: crate::ui::theming::elem_id
+//! [`roles`]: crate::ui::theming::roles
+use cggeom::box2;
+use cgmath::Vector2;
+use rob::Rob;
+use std::{collections::HashMap, fmt};
+
+use super::{
+    manager::PropKindFlags,
+    style::{
+        elem_id, roles, ClassSet, ElemClassPath, Layouter, Metrics, Prop, PropValue, Role,
+        TimingFunction, Transition,
+    },
+    stylesheet::{selector_matches, ElemCriteria, RuleId, Stylesheet},
+};
+use crate::{
+    images::HImg,
+    pal::{LayerFlags, SysFontType, RGBAF32},
+    ui::AlignFlags,
+};
+
+/// The maximum number of rules in a single stylesheet, which is imposed by
+/// [`Manager`](super::Manager).
+const MAX_NUM_RULES: usize = 0x1000;
+
+/// A [`Stylesheet`] constructed from a text representation by
+/// [`TextStylesheetParser`].
+#[derive(Debug)]
+pub struct TextStylesheet {
+    rules: Vec<TextRule>,
+}
+
+#[derive(Debug)]
+struct TextRule {
+    priority: i16,
+    prop_kinds: PropKindFlags,
+    target: ElemCriteria,
+    ancestors: Vec<(bool, ElemCriteria)>,
+    props: Vec<(Prop, PropValue)>,
+}
+
+impl TextStylesheet {
+    /// Construct a `TextStylesheetParser` with the default configuration.
+    pub fn parser() -> TextStylesheetParser {
+        TextStylesheetParser::new()
+    }
+
+    /// Get the number of rules in the stylesheet.
+    pub fn num_rules(&self) -> usize {
+        self.rules.len()
+    }
+}
+
+impl Stylesheet for TextStylesheet {
+    fn match_rules(&self, path: &ElemClassPath, out_rules: &mut dyn FnMut(RuleId)) {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if selector_matches(&rule.target, &rule.ancestors, path) {
+                out_rules(i);
+            }
+        }
+    }
+
+    fn get_rule_priority(&self, id: RuleId) -> Option<i16> {
+        self.rules.get(id).map(|r| r.priority)
+    }
+    fn get_rule_prop_kinds(&self, id: RuleId) -> Option<PropKindFlags> {
+        self.rules.get(id).map(|r| r.prop_kinds)
+    }
+    fn get_rule_prop_value(&self, id: RuleId, prop: &Prop) -> Option<Option<&PropValue>> {
+        self.rules
+            .get(id)
+            .map(|r| r.props.iter().find(|p| p.0 == *prop).map(|p| &p.1))
+    }
+}
+
+/// Parses the text representation of a stylesheet into [`TextStylesheet`].
+///
+/// See [the module-level documentation](self) for the syntax.
+#[derive(Debug, Clone)]
+pub struct TextStylesheetParser {
+    ids: HashMap<String, ClassSet>,
+    imgs: HashMap<String, HImg>,
+}
+
+impl Default for TextStylesheetParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextStylesheetParser {
+    /// Construct a `TextStylesheetParser`. The system element IDs
+    /// ([`elem_id`]) are registered by their constant names.
+    ///
+    /// [`elem_id`]: crate::ui::theming::elem_id
+    pub fn new() -> Self {
+        Self {
+            ids: SYS_ELEM_IDS
+                .iter()
+                .map(|&(name, id)| (name.to_owned(), id))
+                .collect(),
+            imgs: HashMap::new(),
+        }
+    }
+
+    /// Register an element ID value, which can be referred to by `#name` in
+    /// selectors.
+    pub fn with_id(mut self, name: impl Into<String>, id: ClassSet) -> Self {
+        debug_assert_eq!(id & ClassSet::ID_MASK, id, "not an ID value");
+        self.ids.insert(name.into(), id);
+        self
+    }
+
+    /// Register an image, which can be referred to by `image(name)` in
+    /// property values.
+    pub fn with_img(mut self, name: impl Into<String>, img: HImg) -> Self {
+        self.imgs.insert(name.into(), img);
+        self
+    }
+
+    /// Parse the text representation of a stylesheet.
+    pub fn parse(&self, source: &str) -> Result<TextStylesheet, ParseError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            cfg: self,
+            tokens: &tokens,
+            i: 0,
+            eof_pos: end_pos(source),
+        };
+
+        let mut rules = Vec::new();
+        while parser.peek().is_some() {
+            if rules.len() >= MAX_NUM_RULES {
+                return Err(parser.error("too many rules"));
+            }
+            rules.push(parser.rule()?);
+        }
+
+        Ok(TextStylesheet { rules })
+    }
+}
+
+static SYS_ELEM_IDS: &[(&str, ClassSet)] = &[
+    ("SPLITTER", elem_id::SPLITTER),
+    ("TEXT_SELECTION", elem_id::TEXT_SELECTION),
+    ("SLIDER_KNOB", elem_id::SLIDER_KNOB),
+    ("SLIDER_TICKS", elem_id::SLIDER_TICKS),
+    ("SLIDER_LABELS", elem_id::SLIDER_LABELS),
+    ("TEXT_AREA", elem_id::TEXT_AREA),
+    ("TABLE_HEADER", elem_id::TABLE_HEADER),
+    ("TABLE_HEADER_CELL", elem_id::TABLE_HEADER_CELL),
+    ("PROGRESS_BAR", elem_id::PROGRESS_BAR),
+    ("PROGRESS_BAR_FILL", elem_id::PROGRESS_BAR_FILL),
+    ("TOAST", elem_id::TOAST),
+    ("LINK", elem_id::LINK),
+    ("ALERT", elem_id::ALERT),
+    ("ALERT_INFO", elem_id::ALERT_INFO),
+    ("ALERT_WARNING", elem_id::ALERT_WARNING),
+    ("ALERT_ERROR", elem_id::ALERT_ERROR),
+];
+
+/// Indicates a syntax error or a semantic error found in the text
+/// representation of a stylesheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The one-based line number where the error was found.
+    pub line: usize,
+    /// The one-based column number (in `char`s) where the error was found.
+    pub column: usize,
+    /// The description of the error.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// -----------------------------------------------------------------------------
+//  Tokenizer
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pos {
+    line: usize,
+    column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    /// `#` followed by an identifier or hexadecimal digits
+    Hash(&'a str),
+    Number(f32),
+    /// `..`
+    DotDot,
+    Punct(char),
+}
+
+fn end_pos(source: &str) -> Pos {
+    let mut pos = Pos { line: 1, column: 1 };
+    for ch in source.chars() {
+        advance(&mut pos, ch);
+    }
+    pos
+}
+
+fn advance(pos: &mut Pos, ch: char) {
+    if ch == '\n' {
+        pos.line += 1;
+        pos.column = 1;
+    } else {
+        pos.column += 1;
+    }
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Pos, Token<'_>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut pos = Pos { line: 1, column: 1 };
+    let mut rest = source;
+
+    let error = |pos: Pos, message: &str| ParseError {
+        line: pos.line,
+        column: pos.column,
+        message: message.to_owned(),
+    };
+
+    // Consume `len` bytes from `rest` and return them
+    fn take<'a>(rest: &mut &'a str, pos: &mut Pos, len: usize) -> &'a str {
+        let (taken, new_rest) = rest.split_at(len);
+        for ch in taken.chars() {
+            advance(pos, ch);
+        }
+        *rest = new_rest;
+        taken
+    }
+
+    while let Some(ch) = rest.chars().next() {
+        let start = pos;
+
+        if ch.is_whitespace() {
+            take(&mut rest, &mut pos, ch.len_utf8());
+        } else if rest.starts_with("//") {
+            let len = rest.find('\n').unwrap_or_else(|| rest.len());
+            take(&mut rest, &mut pos, len);
+        } else if rest.starts_with("/*") {
+            let len = rest[2..]
+                .find("*/")
+                .ok_or_else(|| error(start, "unterminated block comment"))?;
+            take(&mut rest, &mut pos, len + 4);
+        } else if ch.is_ascii_alphabetic() || ch == '_' {
+            let len = rest
+                .find(|c| !is_ident_char(c))
+                .unwrap_or_else(|| rest.len());
+            tokens.push((start, Token::Ident(take(&mut rest, &mut pos, len))));
+        } else if ch == '#' {
+            take(&mut rest, &mut pos, 1);
+            let len = rest
+                .find(|c| !is_ident_char(c))
+                .unwrap_or_else(|| rest.len());
+            if len == 0 {
+                return Err(error(pos, "expected an identifier after '#'"));
+            }
+            tokens.push((start, Token::Hash(take(&mut rest, &mut pos, len))));
+        } else if ch.is_ascii_digit() || ch == '-' || ch == '+' {
+            let bytes = rest.as_bytes();
+            let mut len = 1;
+            while len < bytes.len() {
+                let b = bytes[len];
+                let is_exp_sign = (b == b'-' || b == b'+') && matches!(bytes[len - 1], b'e' | b'E');
+                if b.is_ascii_digit() || b == b'.' || b == b'e' || b == b'E' || is_exp_sign {
+                    len += 1;
+                } else {
+                    break;
+                }
+            }
+            let text = take(&mut rest, &mut pos, len);
+            let value = text
+                .parse()
+                .map_err(|_| error(start, &format!("invalid number: '{}'", text)))?;
+            tokens.push((start, Token::Number(value)));
+        } else if rest.starts_with("..") {
+            take(&mut rest, &mut pos, 2);
+            tokens.push((start, Token::DotDot));
+        } else if "[](){}<.:;,=|".contains(ch) {
+            take(&mut rest, &mut pos, 1);
+            tokens.push((start, Token::Punct(ch)));
+        } else {
+            return Err(error(start, &format!("unexpected character: {:?}", ch)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+// -----------------------------------------------------------------------------
+//  Parser
+
+struct Parser<'a, 'b> {
+    cfg: &'a TextStylesheetParser,
+    tokens: &'a [(Pos, Token<'b>)],
+    i: usize,
+    eof_pos: Pos,
+}
+
+type Result<T, E = ParseError> = std::result::Result<T, E>;
+
+impl<'b> Parser<'_, 'b> {
+    fn peek(&self) -> Option<&Token<'b>> {
+        self.tokens.get(self.i).map(|t| &t.1)
+    }
+
+    fn next(&mut self) -> Option<Token<'b>> {
+        let token = self.tokens.get(self.i).map(|t| t.1.clone());
+        if token.is_some() {
+            self.i += 1;
+        }
+        token
+    }
+
+    /// Construct a `ParseError` pointing at the current token.
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        let pos = self.tokens.get(self.i).map(|t| t.0).unwrap_or(self.eof_pos);
+        ParseError {
+            line: pos.line,
+            column: pos.column,
+            message: message.into(),
+        }
+    }
+
+    /// Construct a `ParseError` pointing at the last consumed token.
+    fn error_prev(&self, message: impl Into<String>) -> ParseError {
+        let pos = self.tokens[self.i - 1].0;
+        ParseError {
+            line: pos.line,
+            column: pos.column,
+            message: message.into(),
+        }
+    }
+
+    fn eat_punct(&mut self, ch: char) -> bool {
+        if self.peek() == Some(&Token::Punct(ch)) {
+            self.i += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_punct(&mut self, ch: char) -> Result<()> {
+        if self.eat_punct(ch) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{}'", ch)))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<&'b str> {
+        match self.peek() {
+            Some(&Token::Ident(name)) => {
+                self.i += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<f32> {
+        match self.peek() {
+            Some(&Token::Number(x)) => {
+                self.i += 1;
+                Ok(x)
+            }
+            Some(&Token::Ident(name)) if name == "nan" => {
+                self.i += 1;
+                Ok(std::f32::NAN)
+            }
+            Some(&Token::Ident(name)) if name == "inf" => {
+                self.i += 1;
+                Ok(std::f32::INFINITY)
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    fn expect_uint(&mut self) -> Result<u32> {
+        let x = self.expect_number()?;
+        if x >= 0.0 && x <= std::u32::MAX as f32 && x.fract() == 0.0 {
+            Ok(x as u32)
+        } else {
+            Err(self.error_prev("expected a non-negative integer"))
+        }
+    }
+
+    fn expect_bool(&mut self) -> Result<bool> {
+        match self.peek() {
+            Some(Token::Ident("true")) => {
+                self.i += 1;
+                Ok(true)
+            }
+            Some(Token::Ident("false")) => {
+                self.i += 1;
+                Ok(false)
+            }
+            _ => Err(self.error("expected 'true' or 'false'")),
+        }
+    }
+
+    /// `selector ('(' 'priority' '=' number ')')? '{' (prop ';')* '}' ','?`
+    fn rule(&mut self) -> Result<TextRule> {
+        let target = self.elem_criteria()?;
+
+        let mut ancestors = Vec::new();
+        loop {
+            let direct = match self.peek() {
+                Some(Token::Punct('<')) => true,
+                Some(Token::DotDot) => false,
+                _ => break,
+            };
+            self.i += 1;
+            ancestors.push((direct, self.elem_criteria()?));
+        }
+
+        let mut priority = 0;
+        if self.eat_punct('(') {
+            if self.expect_ident()? != "priority" {
+                return Err(self.error_prev("expected 'priority'"));
+            }
+            self.expect_punct('=')?;
+            let x = self.expect_number()?;
+            if x.fract() != 0.0 || x < i16::min_value() as f32 || x > i16::max_value() as f32 {
+                return Err(self.error_prev("priority must be an integer in the range of `i16`"));
+            }
+            priority = x as i16;
+            self.expect_punct(')')?;
+        }
+
+        self.expect_punct('{')?;
+        let mut props: Vec<(Prop, PropValue)> = Vec::new();
+        while !self.eat_punct('}') {
+            let (prop, value) = self.prop()?;
+            if props.iter().any(|p| p.0 == prop) {
+                return Err(self.error_prev(format!("duplicate property: {:?}", prop)));
+            }
+            props.push((prop, value));
+
+            if !self.eat_punct(';') {
+                self.expect_punct('}')?;
+                break;
+            }
+        }
+
+        // Allow a trailing comma for consistency with `stylesheet!`
+        self.eat_punct(',');
+
+        let prop_kinds = props
+            .iter()
+            .fold(PropKindFlags::empty(), |acc, p| acc | p.0.kind_flags());
+
+        Ok(TextRule {
+            priority,
+            prop_kinds,
+            target,
+            ancestors,
+            props,
+        })
+    }
+
+    /// `'[' ('#' ID | '.' CLASS | ':not(' '.' CLASS ')')* ']'`
+    fn elem_criteria(&mut self) -> Result<ElemCriteria> {
+        self.expect_punct('[')?;
+
+        let mut crit = ElemCriteria {
+            pos: ClassSet::empty(),
+            neg: ClassSet::empty(),
+        };
+
+        loop {
+            match self.next() {
+                Some(Token::Punct(']')) => break,
+                Some(Token::Hash(name)) => {
+                    let id = *self
+                        .cfg
+                        .ids
+                        .get(name)
+                        .ok_or_else(|| self.error_prev(format!("unknown ID: '{}'", name)))?;
+                    crit.pos |= id;
+                    crit.neg |= ClassSet::ID_MASK ^ id;
+                }
+                Some(Token::Punct('.')) => {
+                    crit.pos |= self.class()?;
+                }
+                Some(Token::Punct(':')) => {
+                    if self.expect_ident()? != "not" {
+                        return Err(self.error_prev("expected 'not'"));
+                    }
+                    self.expect_punct('(')?;
+                    self.expect_punct('.')?;
+                    crit.neg |= self.class()?;
+                    self.expect_punct(')')?;
+                }
+                Some(_) => return Err(self.error_prev("expected '#', '.', ':not', or ']'")),
+                None => return Err(self.error("expected '#', '.', ':not', or ']'")),
+            }
+        }
+
+        Ok(crit)
+    }
+
+    fn class(&mut self) -> Result<ClassSet> {
+        let name = self.expect_ident()?;
+        class_by_name(name).ok_or_else(|| self.error_prev(format!("unknown class: '{}'", name)))
+    }
+
+    /// `name ('[' param ']')? ':' value`
+    fn prop(&mut self) -> Result<(Prop, PropValue)> {
+        let name = self.expect_ident()?;
+
+        let param = if self.eat_punct('[') {
+            let param = match self.peek() {
+                Some(&Token::Ident(role_name)) => {
+                    self.i += 1;
+                    role_by_name(role_name)
+                        .ok_or_else(|| self.error_prev(format!("unknown role: '{}'", role_name)))?
+                }
+                _ => self.expect_uint()?,
+            };
+            self.expect_punct(']')?;
+            Some(param)
+        } else {
+            None
+        };
+
+        let prop = prop_by_name(name, param).ok_or_else(|| {
+            if param.is_some() {
+                self.error_prev(format!(
+                    "unknown property or unexpected parameter: '{}'",
+                    name
+                ))
+            } else {
+                self.error_prev(format!("unknown property or missing parameter: '{}'", name))
+            }
+        })?;
+
+        self.expect_punct(':')?;
+
+        let value = self.prop_value(&prop)?;
+
+        Ok((prop, value))
+    }
+
+    fn prop_value(&mut self, prop: &Prop) -> Result<PropValue> {
+        Ok(match prop {
+            Prop::NumLayers => PropValue::Usize(self.expect_uint()? as usize),
+            Prop::LayerImg(_) | Prop::LayerMask(_) => PropValue::Himg(self.img()?),
+            Prop::LayerBgColor(_) | Prop::LayerShadowColor(_) | Prop::FgColor | Prop::BgColor => {
+                PropValue::Rgbaf32(self.color()?)
+            }
+            Prop::LayerMetrics(_) | Prop::SubviewMetrics(_) | Prop::ClipMetrics => {
+                PropValue::Metrics(Rob::from_box(Box::new(self.metrics()?)))
+            }
+            Prop::LayerOpacity(_)
+            | Prop::LayerShadowRadius(_)
+            | Prop::LayerBackdropBlurRadius(_)
+            | Prop::SubviewTableColSpacing(_)
+            | Prop::SubviewTableRowSpacing(_) => PropValue::Float(self.expect_number()?),
+            Prop::LayerBgColorTransition(_) | Prop::LayerOpacityTransition(_) => {
+                PropValue::Transition(self.transition()?)
+            }
+            Prop::LayerCenter(_) => {
+                let [min_x, min_y, max_x, max_y] = self.f32x4()?;
+                PropValue::Box2(box2! { min: [min_x, min_y], max: [max_x, max_y] })
+            }
+            Prop::LayerXform(_) => {
+                return Err(self.error("layer_xform is not supported"));
+            }
+            Prop::LayerFlags(_) => {
+                PropValue::LayerFlags(self.flags(LayerFlags::empty(), |name| match name {
+                    "mask_to_bounds" => Some(LayerFlags::MASK_TO_BOUNDS),
+                    "backdrop_blur" => Some(LayerFlags::BACKDROP_BLUR),
+                    _ => None,
+                })?)
+            }
+            Prop::SubviewLayouter => PropValue::Layouter(self.keyword(|name| match name {
+                "abs" => Some(Layouter::Abs),
+                "table" => Some(Layouter::Table),
+                _ => None,
+            })?),
+            Prop::SubviewPadding | Prop::Padding | Prop::LayerCornerRadius(_) => {
+                PropValue::F32x4(self.f32x4()?)
+            }
+            Prop::SubviewTableCell(_) => {
+                PropValue::U32x2([self.expect_uint()?, self.expect_uint()?])
+            }
+            Prop::SubviewTableAlign(_) => {
+                PropValue::AlignFlags(self.flags(AlignFlags::empty(), |name| match name {
+                    "left" => Some(AlignFlags::LEFT),
+                    "right" => Some(AlignFlags::RIGHT),
+                    "horz_center" => Some(AlignFlags::HORZ_CENTER),
+                    "horz_justify" => Some(AlignFlags::HORZ_JUSTIFY),
+                    "top" => Some(AlignFlags::TOP),
+                    "bottom" => Some(AlignFlags::BOTTOM),
+                    "vert_center" => Some(AlignFlags::VERT_CENTER),
+                    "vert_justify" => Some(AlignFlags::VERT_JUSTIFY),
+                    "center" => Some(AlignFlags::CENTER),
+                    "justify" => Some(AlignFlags::JUSTIFY),
+                    _ => None,
+                })?)
+            }
+            Prop::SubviewVisibility(_) => PropValue::Bool(self.expect_bool()?),
+            Prop::MinSize | Prop::LayerShadowOffset(_) => {
+                PropValue::Vector2(Vector2::new(self.expect_number()?, self.expect_number()?))
+            }
+            Prop::AllowGrow => PropValue::Bool2([self.expect_bool()?, self.expect_bool()?]),
+            Prop::Font => PropValue::SysFontType(self.keyword(|name| match name {
+                "normal" => Some(SysFontType::Normal),
+                "emph" => Some(SysFontType::Emph),
+                "small" => Some(SysFontType::Small),
+                "small_emph" => Some(SysFontType::SmallEmph),
+                "user" => Some(SysFontType::User),
+                "user_monospace" => Some(SysFontType::UserMonospace),
+                _ => None,
+            })?),
+        })
+    }
+
+    fn f32x4(&mut self) -> Result<[f32; 4]> {
+        Ok([
+            self.expect_number()?,
+            self.expect_number()?,
+            self.expect_number()?,
+            self.expect_number()?,
+        ])
+    }
+
+    fn keyword<T>(&mut self, f: impl FnOnce(&str) -> Option<T>) -> Result<T> {
+        let name = self.expect_ident()?;
+        f(name).ok_or_else(|| self.error_prev(format!("unknown keyword: '{}'", name)))
+    }
+
+    /// `'none' | name ('|' name)*`
+    fn flags<T: std::ops::BitOr<Output = T>>(
+        &mut self,
+        none: T,
+        f: impl Fn(&str) -> Option<T>,
+    ) -> Result<T> {
+        if self.peek() == Some(&Token::Ident("none")) {
+            self.i += 1;
+            return Ok(none);
+        }
+
+        let mut flags = self.keyword(&f)?;
+        while self.eat_punct('|') {
+            flags = flags | self.keyword(&f)?;
+        }
+        Ok(flags)
+    }
+
+    /// `'none' | 'image' '(' name ')'`
+    fn img(&mut self) -> Result<Option<HImg>> {
+        match self.expect_ident()? {
+            "none" => Ok(None),
+            "image" => {
+                self.expect_punct('(')?;
+                let name = self.expect_ident()?;
+                let img = self
+                    .cfg
+                    .imgs
+                    .get(name)
+                    .ok_or_else(|| self.error_prev(format!("unknown image: '{}'", name)))?
+                    .clone();
+                self.expect_punct(')')?;
+                Ok(Some(img))
+            }
+            _ => Err(self.error_prev("expected 'none' or 'image'")),
+        }
+    }
+
+    /// `'#' hex | 'rgba' '(' number ',' number ',' number ',' number ')'`
+    fn color(&mut self) -> Result<RGBAF32> {
+        match self.next() {
+            Some(Token::Hash(hex)) => parse_hex_color(hex)
+                .ok_or_else(|| self.error_prev(format!("invalid color: '#{}'", hex))),
+            Some(Token::Ident("rgba")) => {
+                self.expect_punct('(')?;
+                let r = self.expect_number()?;
+                self.expect_punct(',')?;
+                let g = self.expect_number()?;
+                self.expect_punct(',')?;
+                let b = self.expect_number()?;
+                self.expect_punct(',')?;
+                let a = self.expect_number()?;
+                self.expect_punct(')')?;
+                Ok(RGBAF32::new(r, g, b, a))
+            }
+            Some(_) => Err(self.error_prev("expected a color")),
+            None => Err(self.error("expected a color")),
+        }
+    }
+
+    /// `'none' | number ('ms' | 's') timing_function?`
+    fn transition(&mut self) -> Result<Transition> {
+        if self.peek() == Some(&Token::Ident("none")) {
+            self.i += 1;
+            return Ok(Transition::NONE);
+        }
+
+        let value = self.expect_number()?;
+        let duration = match self.expect_ident()? {
+            "ms" => value / 1000.0,
+            "s" => value,
+            _ => return Err(self.error_prev("expected 'ms' or 's'")),
+        };
+        if !duration.is_finite() || duration < 0.0 {
+            return Err(self.error_prev("invalid duration"));
+        }
+
+        let timing = match self.peek() {
+            Some(Token::Ident("cubic_bezier")) => {
+                self.i += 1;
+                self.expect_punct('(')?;
+                let mut points = [0.0; 4];
+                for (i, p) in points.iter_mut().enumerate() {
+                    if i > 0 {
+                        self.expect_punct(',')?;
+                    }
+                    *p = self.expect_number()?;
+                    if i % 2 == 0 && !(0.0..=1.0).contains(&*p) {
+                        return Err(self.error_prev("x coordinates must be in range [0, 1]"));
+                    }
+                }
+                self.expect_punct(')')?;
+                TimingFunction::CubicBezier(points)
+            }
+            Some(Token::Ident(_)) => self.keyword(|name| match name {
+                "linear" => Some(TimingFunction::Linear),
+                "ease" => Some(TimingFunction::Ease),
+                "ease_in" => Some(TimingFunction::EaseIn),
+                "ease_out" => Some(TimingFunction::EaseOut),
+                "ease_in_out" => Some(TimingFunction::EaseInOut),
+                _ => None,
+            })?,
+            _ => TimingFunction::Ease,
+        };
+
+        Ok(Transition::new(duration, timing))
+    }
+
+    /// `('margin' '(' f32x4 ')' | 'size' '(' number number ')')+`
+    fn metrics(&mut self) -> Result<Metrics> {
+        let mut metrics = Metrics::default();
+        let mut any = false;
+
+        loop {
+            match self.peek() {
+                Some(Token::Ident("margin")) => {
+                    self.i += 1;
+                    self.expect_punct('(')?;
+                    metrics.margin = self.f32x4()?;
+                    self.expect_punct(')')?;
+                }
+                Some(Token::Ident("size")) => {
+                    self.i += 1;
+                    self.expect_punct('(')?;
+                    metrics.size = Vector2::new(self.expect_number()?, self.expect_number()?);
+                    self.expect_punct(')')?;
+                }
+                _ if any => break,
+                _ => return Err(self.error("expected 'margin' or 'size'")),
+            }
+            any = true;
+        }
+
+        Ok(metrics)
+    }
+}
+
+fn parse_hex_color(hex: &str) -> Option<RGBAF32> {
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap() as f32;
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32;
+
+    Some(match hex.len() {
+        3 => RGBAF32::new(digit(0) / 15.0, digit(1) / 15.0, digit(2) / 15.0, 1.0),
+        6 => RGBAF32::new(byte(0) / 255.0, byte(2) / 255.0, byte(4) / 255.0, 1.0),
+        8 => RGBAF32::new(
+            byte(0) / 255.0,
+            byte(2) / 255.0,
+            byte(4) / 255.0,
+            byte(6) / 255.0,
+        ),
+        _ => return None,
+    })
+}
+
+fn class_by_name(name: &str) -> Option<ClassSet> {
+    Some(match name {
+        "HOVER" => ClassSet::HOVER,
+        "ACTIVE" => ClassSet::ACTIVE,
+        "FOCUS" => ClassSet::FOCUS,
+        "BUTTON" => ClassSet::BUTTON,
+        "LABEL" => ClassSet::LABEL,
+        "SCROLLBAR" => ClassSet::SCROLLBAR,
+        "VERTICAL" => ClassSet::VERTICAL,
+        "SCROLL_CONTAINER" => ClassSet::SCROLL_CONTAINER,
+        "HAS_HORIZONTAL_SCROLLBAR" => ClassSet::HAS_HORIZONTAL_SCROLLBAR,
+        "HAS_VERTICAL_SCROLLBAR" => ClassSet::HAS_VERTICAL_SCROLLBAR,
+        "SLIDER" => ClassSet::SLIDER,
+        "ENTRY" => ClassSet::ENTRY,
+        "CHECKBOX" => ClassSet::CHECKBOX,
+        "CHECKED" => ClassSet::CHECKED,
+        "RADIO_BUTTON" => ClassSet::RADIO_BUTTON,
+        "SELECTED" => ClassSet::SELECTED,
+        "INDETERMINATE" => ClassSet::INDETERMINATE,
+        "USER1" => ClassSet::USER1,
+        _ => return None,
+    })
+}
+
+fn role_by_name(name: &str) -> Option<Role> {
+    Some(match name {
+        "generic" => roles::GENERIC,
+        "horz_scrollbar" => roles::HORZ_SCROLLBAR,
+        "vert_scrollbar" => roles::VERT_SCROLLBAR,
+        "slider_knob" => roles::SLIDER_KNOB,
+        "slider_ticks" => roles::SLIDER_TICKS,
+        "slider_labels" => roles::SLIDER_LABELS,
+        "toast_action" => roles::TOAST_ACTION,
+        "alert_icon" => roles::ALERT_ICON,
+        "alert_buttons" => roles::ALERT_BUTTONS,
+        _ => return None,
+    })
+}
+
+fn prop_by_name(name: &str, param: Option<u32>) -> Option<Prop> {
+    Some(match (name, param) {
+        ("num_layers", None) => Prop::NumLayers,
+        ("layer_img", Some(i)) => Prop::LayerImg(i),
+        ("layer_bg_color", Some(i)) => Prop::LayerBgColor(i),
+        ("layer_bg_color_transition", Some(i)) => Prop::LayerBgColorTransition(i),
+        ("layer_metrics", Some(i)) => Prop::LayerMetrics(i),
+        ("layer_opacity", Some(i)) => Prop::LayerOpacity(i),
+        ("layer_opacity_transition", Some(i)) => Prop::LayerOpacityTransition(i),
+        ("layer_center", Some(i)) => Prop::LayerCenter(i),
+        ("layer_xform", Some(i)) => Prop::LayerXform(i),
+        ("layer_flags", Some(i)) => Prop::LayerFlags(i),
+        ("layer_corner_radius", Some(i)) => Prop::LayerCornerRadius(i),
+        ("layer_mask", Some(i)) => Prop::LayerMask(i),
+        ("layer_shadow_offset", Some(i)) => Prop::LayerShadowOffset(i),
+        ("layer_shadow_radius", Some(i)) => Prop::LayerShadowRadius(i),
+        ("layer_shadow_color", Some(i)) => Prop::LayerShadowColor(i),
+        ("layer_backdrop_blur_radius", Some(i)) => Prop::LayerBackdropBlurRadius(i),
+        ("subview_layouter", None) => Prop::SubviewLayouter,
+        ("subview_padding", None) => Prop::SubviewPadding,
+        ("subview_metrics", Some(i)) => Prop::SubviewMetrics(i),
+        ("subview_table_cell", Some(i)) => Prop::SubviewTableCell(i),
+        ("subview_table_align", Some(i)) => Prop::SubviewTableAlign(i),
+        ("subview_table_col_spacing", Some(i)) => Prop::SubviewTableColSpacing(i),
+        ("subview_table_row_spacing", Some(i)) => Prop::SubviewTableRowSpacing(i),
+        ("subview_visibility", Some(i)) => Prop::SubviewVisibility(i),
+        ("clip_metrics", None) => Prop::ClipMetrics,
+        ("min_size", None) => Prop::MinSize,
+        ("allow_grow", None) => Prop::AllowGrow,
+        ("fg_color", None) => Prop::FgColor,
+        ("bg_color", None) => Prop::BgColor,
+        ("font", None) => Prop::Font,
+        ("padding", None) => Prop::Padding,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUSTOM_ID: ClassSet = ClassSet::id(42);
+
+    fn parse(source: &str) -> Result<TextStylesheet, ParseError> {
+        TextStylesheet::parser()
+            .with_id("CUSTOM_ID", CUSTOM_ID)
+            .parse(source)
+    }
+
+    fn matched_rules(sheet: &TextStylesheet, path: &ElemClassPath) -> Vec<RuleId> {
+        let mut rules = Vec::new();
+        sheet.match_rules(path, &mut |id| rules.push(id));
+        rules
+    }
+
+    #[test]
+    fn selectors() {
+        let sheet = parse(
+            "
+            // A comment
+            [#CUSTOM_ID.LABEL] < [.BUTTON.ACTIVE] (priority = 100) {}
+            [.BUTTON:not(.ACTIVE)] .. [#SPLITTER] (priority = -5) {},
+            /* A block
+               comment */
+            [] {}
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(sheet.num_rules(), 3);
+        assert_eq!(sheet.get_rule_priority(0), Some(100));
+        assert_eq!(sheet.get_rule_priority(1), Some(-5));
+        assert_eq!(sheet.get_rule_priority(2), Some(0));
+        assert_eq!(sheet.get_rule_priority(3), None);
+
+        assert_eq!(
+            matched_rules(
+                &sheet,
+                &[
+                    ClassSet::BUTTON | ClassSet::ACTIVE,
+                    CUSTOM_ID | ClassSet::LABEL
+                ]
+            ),
+            vec![0, 2]
+        );
+        assert_eq!(
+            matched_rules(&sheet, &[ClassSet::BUTTON, CUSTOM_ID | ClassSet::LABEL]),
+            vec![2]
+        );
+        assert_eq!(
+            matched_rules(
+                &sheet,
+                &[elem_id::SPLITTER, ClassSet::LABEL, ClassSet::BUTTON]
+            ),
+            vec![1, 2]
+        );
+        assert_eq!(
+            matched_rules(
+                &sheet,
+                &[elem_id::SPLITTER, ClassSet::BUTTON | ClassSet::ACTIVE]
+            ),
+            vec![2]
+        );
+    }
+
+    #[test]
+    fn prop_values() {
+        let sheet = parse(
+            "[.BUTTON] (priority = 1) {
+                num_layers: 2;
+                layer_img[0]: none;
+                layer_bg_color[1]: #ff000080;
+                layer_metrics[1]: size(10 nan) margin(1 2 3 4);
+                layer_flags[1]: mask_to_bounds;
+                layer_corner_radius[1]: 4 4 0 0;
+                layer_shadow_offset[1]: 0 2;
+                layer_shadow_color[1]: #00000044;
+                layer_backdrop_blur_radius[1]: 12;
+                subview_layouter: table;
+                subview_table_align[generic]: left | top;
+                subview_visibility[3]: false;
+                fg_color: rgba(0.5, 0.25, 0, 1);
+                bg_color: #fff;
+                font: small_emph;
+                layer_bg_color_transition[1]: 150ms ease_out;
+                layer_opacity_transition[0]: 0.5s;
+                padding: 1 2 3.5 -4;
+                allow_grow: true false
+            }",
+        )
+        .unwrap();
+
+        let value = |prop| sheet.get_rule_prop_value(0, &prop).unwrap();
+
+        assert!(matches!(value(Prop::NumLayers), Some(PropValue::Usize(2))));
+        assert!(matches!(
+            value(Prop::LayerImg(0)),
+            Some(PropValue::Himg(None))
+        ));
+        assert!(matches!(
+            value(Prop::LayerBgColor(1)),
+            Some(PropValue::Rgbaf32(c))
+                if *c == RGBAF32::new(1.0, 0.0, 0.0, 128.0 / 255.0)
+        ));
+        assert!(matches!(
+            value(Prop::LayerMetrics(1)),
+            Some(PropValue::Metrics(m))
+                if m.margin == [1.0, 2.0, 3.0, 4.0] && m.size.x == 10.0 && m.size.y.is_nan()
+        ));
+        assert!(matches!(
+            value(Prop::LayerFlags(1)),
+            Some(PropValue::LayerFlags(LayerFlags::MASK_TO_BOUNDS))
+        ));
+        assert!(matches!(
+            value(Prop::LayerCornerRadius(1)),
+            Some(PropValue::F32x4(x)) if *x == [4.0, 4.0, 0.0, 0.0]
+        ));
+        assert!(matches!(
+            value(Prop::LayerShadowOffset(1)),
+            Some(PropValue::Vector2(v)) if *v == Vector2::new(0.0, 2.0)
+        ));
+        assert!(matches!(
+            value(Prop::LayerShadowColor(1)),
+            Some(PropValue::Rgbaf32(c)) if *c == RGBAF32::new(0.0, 0.0, 0.0, 68.0 / 255.0)
+        ));
+        assert!(matches!(
+            value(Prop::LayerBackdropBlurRadius(1)),
+            Some(PropValue::Float(x)) if *x == 12.0
+        ));
+        assert!(matches!(
+            value(Prop::SubviewLayouter),
+            Some(PropValue::Layouter(Layouter::Table))
+        ));
+        assert!(matches!(
+            value(Prop::SubviewTableAlign(roles::GENERIC)),
+            Some(PropValue::AlignFlags(f)) if *f == AlignFlags::LEFT | AlignFlags::TOP
+        ));
+        assert!(matches!(
+            value(Prop::SubviewVisibility(3)),
+            Some(PropValue::Bool(false))
+        ));
+        assert!(matches!(
+            value(Prop::FgColor),
+            Some(PropValue::Rgbaf32(c)) if *c == RGBAF32::new(0.5, 0.25, 0.0, 1.0)
+        ));
+        assert!(matches!(
+            value(Prop::BgColor),
+            Some(PropValue::Rgbaf32(c)) if *c == RGBAF32::new(1.0, 1.0, 1.0, 1.0)
+        ));
+        assert!(matches!(
+            value(Prop::Font),
+            Some(PropValue::SysFontType(SysFontType::SmallEmph))
+        ));
+        assert!(matches!(
+            value(Prop::Padding),
+            Some(PropValue::F32x4(x)) if *x == [1.0, 2.0, 3.5, -4.0]
+        ));
+        assert!(matches!(
+            value(Prop::AllowGrow),
+            Some(PropValue::Bool2([true, false]))
+        ));
+        assert!(matches!(
+            value(Prop::LayerBgColorTransition(1)),
+            Some(PropValue::Transition(t))
+                if *t == Transition::new(0.15, TimingFunction::EaseOut)
+        ));
+        assert!(matches!(
+            value(Prop::LayerOpacityTransition(0)),
+            Some(PropValue::Transition(t)) if *t == Transition::new(0.5, TimingFunction::Ease)
+        ));
+        assert!(value(Prop::MinSize).is_none());
+
+        let kinds = sheet.get_rule_prop_kinds(0).unwrap();
+        assert!(kinds.contains(Prop::NumLayers.kind_flags()));
+        assert!(kinds.contains(Prop::Font.kind_flags()));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |source: &str| parse(source).unwrap_err();
+
+        let e = error("[.BUTTON] {\n    fg_color: #12345;\n}");
+        assert_eq!((e.line, e.column), (2, 15));
+
+        let e = error("[.NO_SUCH_CLASS] {}");
+        assert_eq!((e.line, e.column), (1, 3));
+
+        let e = error("[#NO_SUCH_ID] {}");
+        assert_eq!((e.line, e.column), (1, 2));
+
+        let e = error("[] { layer_img[0]: image(no_such_image); }");
+        assert_eq!((e.line, e.column), (1, 26));
+
+        // Missing a parameter
+        error("[] { layer_opacity: 1; }");
+        // Unexpected parameter
+        error("[] { fg_color[0]: #fff; }");
+        // Duplicate property
+        error("[] { font: user; font: normal; }");
+        // Out-of-range priority
+        error("[] (priority = 40000) {}");
+        // Unterminated rule
+        let e = error("[] { font: user;");
+        assert_eq!((e.line, e.column), (1, 17));
+        // Unterminated comment
+        error("/* [] {}");
+    }
+}
@@ -1,6 +1,6 @@
 use alt_fp::FloatOrd;
 use cggeom::{box2, prelude::*, Box2};
-use cgmath::Vector2;
+use cgmath::{Point2, Vector2};
 use flags_macro::flags;
 use log::trace;
 use momo::momo;
@@ -8,20 +8,22 @@ use std::{
     cell::{Cell, RefCell},
     fmt,
     rc::{Rc, Weak},
+    time::Instant,
 };
 
 use super::{
     manager::{Elem, HElem, Manager, PropKindFlags},
-    style::{roles, ClassSet, GetPropValue, Layouter, Metrics, Role},
+    style::{roles, ClassSet, GetPropValue, Layouter, Metrics, Role, Transition},
     widget::Widget,
 };
 use crate::{
     pal,
     pal::prelude::*,
-    ui::layouts::TableLayout,
+    pal::RGBAF32,
+    ui::{animation::start_animation_timer, layouts::TableLayout},
     uicore::{
-        HView, HViewRef, HWndRef, Layout, LayoutCtx, SizeTraits, Sub, UpdateCtx, ViewFlags,
-        ViewListener,
+        HView, HViewRef, HWndRef, Layout, LayoutCtx, MouseDragListener, SizeTraits, Sub, UpdateCtx,
+        ViewFlags, ViewListener,
     },
 };
 
@@ -32,10 +34,18 @@ use crate::{
 ///  - `NumLayers`
 ///  - `LayerImg`
 ///  - `LayerBgColor`
+///  - `LayerBgColorTransition`
 ///  - `LayerMetrics`
 ///  - `LayerOpacity`
+///  - `LayerOpacityTransition`
 ///  - `LayerCenter`
 ///  - `LayerXform`
+///  - `LayerCornerRadius`
+///  - `LayerMask`
+///  - `LayerShadowOffset`
+///  - `LayerShadowRadius`
+///  - `LayerShadowColor`
+///  - `LayerBackdropBlurRadius`
 ///  - `SubviewLayouter`
 ///  - `SubviewPadding`
 ///  - `SubviewMetrics`
@@ -103,6 +113,13 @@ struct Shared {
     overrider: RefCell<Rc<dyn StyledBoxOverride>>,
 
     has_layer_group: bool,
+
+    /// `true` if some layer properties are in transition. Updated by
+    /// `SbListener::update`.
+    transitions_running: Cell<bool>,
+    /// `true` if there's an animation timer driving the transitions. Cleared
+    /// when the timer is dropped.
+    transition_timer_running: Cell<bool>,
 }
 
 impl fmt::Debug for Shared {
@@ -116,6 +133,8 @@ impl fmt::Debug for Shared {
             .field("subelems", &self.subelems)
             .field("overrider", &())
             .field("has_layer_group", &self.has_layer_group)
+            .field("transitions_running", &self.transitions_running)
+            .field("transition_timer_running", &self.transition_timer_running)
             .finish()
     }
 }
@@ -146,6 +165,8 @@ impl StyledBox {
             // the dirty flags
             dirty: Cell::new(PropKindFlags::all() - PropKindFlags::LAYOUT),
             has_layer_group: view_flags.contains(ViewFlags::LAYER_GROUP),
+            transitions_running: Cell::new(false),
+            transition_timer_running: Cell::new(false),
         });
 
         view.set_listener(SbListener::new(Rc::downgrade(&shared)));
@@ -288,8 +309,14 @@ impl StyledBox {
     /// Set the auto class set.
     ///
     /// The auto class set is a set of styling classes controlled by
-    /// `StyledBox`. The following classes are supported: `HOVER` and `FOCUS`.
-    ////
+    /// `StyledBox`. The following classes are supported:
+    ///
+    ///  - `HOVER` is set while the mouse pointer is inside the view.
+    ///  - `FOCUS` is set while the view has the keyboard focus.
+    ///  - `ACTIVE` is set while the view is being pressed down by the primary
+    ///    mouse button and the mouse pointer is inside the view. This requires
+    ///    [`ViewFlags::ACCEPT_MOUSE_DRAG`].
+    ///
     /// The auto class set defaults to empty.
     pub fn set_auto_class_set(&self, class_set: ClassSet) {
         self.shared.auto_class_set.set(class_set);
@@ -362,6 +389,65 @@ impl Shared {
 
         dirty.set(diff - PropKindFlags::LAYOUT);
     }
+
+    /// Toggle the auto class `andn_mask` if it's included in
+    /// `auto_class_set`.
+    fn toggle_auto_class(&self, andn_mask: ClassSet, or_mask: ClassSet) {
+        if self.auto_class_set.get().contains(andn_mask) {
+            trace!(
+                "Toggling the auto class {:?} of {:?} with OR mask {:?}",
+                andn_mask,
+                self.view,
+                or_mask,
+            );
+            let elem = &self.style_elem;
+            elem.set_class_set((elem.class_set() - andn_mask) | or_mask);
+        } else {
+            trace!(
+                "Not toggling the auto class {:?} of {:?} because it's not in `auto_class_set`",
+                andn_mask,
+                self.view,
+            );
+        }
+    }
+
+    /// Start an animation timer to drive the transitions of layer properties
+    /// if it's not running yet.
+    fn ensure_transition_timer(self: &Rc<Self>, hwnd: HWndRef<'_>) {
+        if self.transition_timer_running.get() {
+            return;
+        }
+        self.transition_timer_running.set(true);
+
+        /// Clears `transition_timer_running` when the timer stops, including
+        /// when it's dropped without being called (e.g., the window is closed).
+        struct TimerGuard(Weak<Shared>);
+
+        impl Drop for TimerGuard {
+            fn drop(&mut self) {
+                if let Some(shared) = self.0.upgrade() {
+                    shared.transition_timer_running.set(false);
+                }
+            }
+        }
+
+        let guard = TimerGuard(Rc::downgrade(self));
+        start_animation_timer(hwnd, move |_| {
+            let shared = if let Some(shared) = guard.0.upgrade() {
+                shared
+            } else {
+                return false;
+            };
+
+            if shared.transitions_running.get() {
+                // Recalculate the interpolated values in `SbListener::update`
+                shared.set_dirty(PropKindFlags::LAYER_BG_COLOR | PropKindFlags::LAYER_OPACITY);
+                true
+            } else {
+                false
+            }
+        });
+    }
 }
 
 struct SbLayout {
@@ -526,7 +612,8 @@ impl Layout for AbsInnerLayout {
             let sv_traits = ctx.subview_size_traits(sv.as_ref());
             let container = box2! {top_left: [0.0, 0.0].into(), size: size};
 
-            let mut frame = metrics.arrange(container, sv_traits.preferred);
+            let frame = metrics.arrange(container, sv_traits.preferred);
+            let mut frame = ctx.mirror_frame_if_rtl(frame, size.x);
 
             self.overrider.modify_arrangement(ModifyArrangementArgs {
                 role,
@@ -640,9 +727,115 @@ struct SbListener {
 struct Layers {
     clip: Option<pal::HLayer>,
     styled: Vec<pal::HLayer>,
+    /// The transition states of `styled`'s elements.
+    transitions: Vec<LayerTransitions>,
     sub: Option<Sub>,
 }
 
+/// The transition states of the animatable properties of a styled layer.
+#[derive(Default)]
+struct LayerTransitions {
+    bg_color: TransitionState<RGBAF32>,
+    opacity: TransitionState<f32>,
+}
+
+impl LayerTransitions {
+    fn is_running(&self) -> bool {
+        self.bg_color.running.is_some() || self.opacity.running.is_some()
+    }
+}
+
+/// Tracks the value of an animatable layer property presented on the screen.
+struct TransitionState<T> {
+    /// The value most recently assigned to the layer. `None` if no value has
+    /// been assigned yet, in which case the next value is applied without a
+    /// transition.
+    presented: Option<T>,
+    running: Option<RunningTransition<T>>,
+}
+
+impl<T> Default for TransitionState<T> {
+    fn default() -> Self {
+        Self {
+            presented: None,
+            running: None,
+        }
+    }
+}
+
+struct RunningTransition<T> {
+    from: T,
+    to: T,
+    start: Instant,
+    transition: Transition,
+}
+
+impl<T: Lerp + PartialEq + Copy> TransitionState<T> {
+    /// Update the target value and calculate the value to be presented at
+    /// `now`.
+    fn update(&mut self, target: T, transition: Transition, now: Instant) -> T {
+        let retarget = match (&self.running, self.presented) {
+            (Some(running), _) => running.to != target,
+            (None, Some(presented)) => presented != target,
+            (None, None) => false,
+        };
+
+        if retarget {
+            self.running = if transition.duration > 0.0 {
+                Some(RunningTransition {
+                    // Start from the currently presented value so that
+                    // interrupting a transition doesn't cause a jump
+                    from: self.presented.unwrap(),
+                    to: target,
+                    start: now,
+                    transition,
+                })
+            } else {
+                None
+            };
+        }
+
+        let value = if let Some(running) = &self.running {
+            let elapsed = now.saturating_duration_since(running.start).as_secs_f32();
+            let progress = elapsed / running.transition.duration;
+            if progress >= 1.0 {
+                self.running = None;
+                target
+            } else {
+                let t = running.transition.timing.eval(progress);
+                running.from.lerp(running.to, t)
+            }
+        } else {
+            target
+        };
+
+        self.presented = Some(value);
+        value
+    }
+}
+
+/// Linear interpolation.
+trait Lerp {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for RGBAF32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        RGBAF32::new(
+            self.r.lerp(to.r, t),
+            self.g.lerp(to.g, t),
+            self.b.lerp(to.b, t),
+            self.a.lerp(to.a, t),
+        )
+    }
+}
+
 impl SbListener {
     fn new(shared: Weak<Shared>) -> Self {
         Self {
@@ -653,22 +846,7 @@ impl SbListener {
 
     fn toggle_auto_class(&self, andn_mask: ClassSet, or_mask: ClassSet) {
         if let Some(shared) = self.shared.upgrade() {
-            if shared.auto_class_set.get().contains(andn_mask) {
-                trace!(
-                    "Toggling the auto class {:?} of {:?} with OR mask {:?}",
-                    andn_mask,
-                    shared.view,
-                    or_mask,
-                );
-                let elem = &shared.style_elem;
-                elem.set_class_set((elem.class_set() - andn_mask) | or_mask);
-            } else {
-                trace!(
-                    "Not toggling the auto class {:?} of {:?} because it's not in `auto_class_set`",
-                    andn_mask,
-                    shared.view,
-                );
-            }
+            shared.toggle_auto_class(andn_mask, or_mask);
         }
     }
 
@@ -700,7 +878,7 @@ impl ViewListener for SbListener {
                 let shared = self.shared.clone();
                 wnd.subscribe_dpi_scale_changed(Box::new(move |_, _| {
                     if let Some(shared) = shared.upgrade() {
-                        shared.set_dirty(PropKindFlags::LAYER_IMG);
+                        shared.set_dirty(PropKindFlags::LAYER_IMG | PropKindFlags::LAYER_SHAPE);
                     }
                 }))
             };
@@ -717,6 +895,7 @@ impl ViewListener for SbListener {
                     None
                 },
                 styled: Vec::new(),
+                transitions: Vec::new(),
                 sub: Some(sub),
             });
 
@@ -727,6 +906,7 @@ impl ViewListener for SbListener {
 
         self.remove_auto_class(ClassSet::HOVER);
         self.remove_auto_class(ClassSet::FOCUS);
+        self.remove_auto_class(ClassSet::ACTIVE);
     }
 
     fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
@@ -742,6 +922,14 @@ impl ViewListener for SbListener {
         if let Some(sub) = layers.sub {
             sub.unsubscribe().unwrap();
         }
+
+        // Stop the animation timer (if any) on the next frame. Transitions
+        // will not resume when the view is mounted again. The timer may still
+        // be alive until then, so `transition_timer_running` is left to the
+        // timer to clear.
+        if let Some(shared) = self.shared.upgrade() {
+            shared.transitions_running.set(false);
+        }
     }
 
     fn mouse_enter(&self, _: pal::Wm, _: HViewRef<'_>) {
@@ -752,6 +940,19 @@ impl ViewListener for SbListener {
         self.remove_auto_class(ClassSet::HOVER);
     }
 
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        Box::new(SbMouseDragListener {
+            shared: self.shared.clone(),
+            pressed: Cell::new(false),
+        })
+    }
+
     fn focus_enter(&self, _: pal::Wm, _: HViewRef<'_>) {
         self.add_auto_class(ClassSet::FOCUS);
     }
@@ -794,14 +995,18 @@ impl ViewListener for SbListener {
                 wm.remove_layer(&styled.pop().unwrap());
             }
             styled.resize_with(num_layers, || wm.new_layer(pal::LayerAttrs::default()));
+            layers.transitions.resize_with(num_layers, Default::default);
         }
 
+        let now = wm.now();
+
         let container = view.global_frame();
 
         // Update layer properties
         let prop_flags = PropKindFlags::LAYER_ALL - PropKindFlags::NUM_LAYERS;
         if dirty.intersects(prop_flags) {
-            for (i, layer) in layers.styled.iter().enumerate() {
+            let iter = layers.styled.iter().zip(layers.transitions.iter_mut());
+            for (i, (layer, transitions)) in iter.enumerate() {
                 let layer_id = i as u32;
                 let mut layer_attrs = pal::LayerAttrs::default();
 
@@ -817,7 +1022,7 @@ impl ViewListener for SbListener {
                     if let Some(img) = img {
                         let (bmp, content_scale) = img.new_bmp(wm, ctx.hwnd().dpi_scale());
 
-                        layer_attrs.contents = Some(Some(bmp));
+                        layer_attrs.contents = Some(Some(bmp.into()));
                         layer_attrs.contents_scale = Some(content_scale);
                     } else {
                         layer_attrs.contents = Some(None);
@@ -825,11 +1030,21 @@ impl ViewListener for SbListener {
                 }
 
                 if dirty.intersects(PropKindFlags::LAYER_BG_COLOR) {
-                    layer_attrs.bg_color = Some(props.layer_bg_color(layer_id));
+                    let value = transitions.bg_color.update(
+                        props.layer_bg_color(layer_id),
+                        props.layer_bg_color_transition(layer_id),
+                        now,
+                    );
+                    layer_attrs.bg_color = Some(value);
                 }
 
                 if dirty.intersects(PropKindFlags::LAYER_OPACITY) {
-                    layer_attrs.opacity = Some(props.layer_opacity(layer_id));
+                    let value = transitions.opacity.update(
+                        props.layer_opacity(layer_id),
+                        props.layer_opacity_transition(layer_id),
+                        now,
+                    );
+                    layer_attrs.opacity = Some(value);
                 }
 
                 if dirty.intersects(PropKindFlags::LAYER_CENTER) {
@@ -840,6 +1055,29 @@ impl ViewListener for SbListener {
                     layer_attrs.flags = Some(props.layer_flags(layer_id));
                 }
 
+                if dirty.intersects(PropKindFlags::LAYER_SHAPE) {
+                    layer_attrs.corner_radius = Some(props.layer_corner_radius(layer_id));
+
+                    layer_attrs.mask = Some(props.layer_mask(layer_id).map(|img| {
+                        let (bmp, _) = img.new_bmp(wm, ctx.hwnd().dpi_scale());
+                        bmp
+                    }));
+
+                    let shadow_color = props.layer_shadow_color(layer_id);
+                    layer_attrs.shadow = Some(if shadow_color.a > 0.0 {
+                        Some(pal::LayerShadow {
+                            offset: props.layer_shadow_offset(layer_id),
+                            radius: props.layer_shadow_radius(layer_id),
+                            color: shadow_color,
+                        })
+                    } else {
+                        None
+                    });
+
+                    layer_attrs.backdrop_blur_radius =
+                        Some(props.layer_backdrop_blur_radius(layer_id));
+                }
+
                 if dirty.intersects(PropKindFlags::LAYER_XFORM | PropKindFlags::LAYER_BOUNDS) {
                     let xform = props.layer_xform(layer_id);
 
@@ -855,6 +1093,13 @@ impl ViewListener for SbListener {
             }
         }
 
+        // Keep redrawing while some properties are in transition
+        let transitions_running = layers.transitions.iter().any(LayerTransitions::is_running);
+        shared.transitions_running.set(transitions_running);
+        if transitions_running {
+            shared.ensure_transition_timer(ctx.hwnd());
+        }
+
         // Update the clip layer's properties
         if let Some(clip) = &layers.clip {
             if dirty.intersects(PropKindFlags::CLIP_LAYER) {
@@ -883,3 +1128,205 @@ impl ViewListener for SbListener {
         }
     }
 }
+
+/// Toggles the auto class `ACTIVE` in response to a mouse drag gesture.
+struct SbMouseDragListener {
+    shared: Weak<Shared>,
+    /// `true` if the primary mouse button is being held down.
+    pressed: Cell<bool>,
+}
+
+impl SbMouseDragListener {
+    fn set_active(&self, active: bool) {
+        if let Some(shared) = self.shared.upgrade() {
+            let or_mask = if active {
+                ClassSet::ACTIVE
+            } else {
+                ClassSet::empty()
+            };
+            shared.toggle_auto_class(ClassSet::ACTIVE, or_mask);
+        }
+    }
+}
+
+impl MouseDragListener for SbMouseDragListener {
+    fn mouse_motion(&self, _: pal::Wm, view: HViewRef<'_>, loc: Point2<f32>) {
+        if self.pressed.get() {
+            // Display the pressed state only if the mouse pointer is inside
+            self.set_active(view.global_frame().contains_point(&loc));
+        }
+    }
+
+    fn mouse_down(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        button: u8,
+        _click_count: u32,
+    ) {
+        if button == 0 {
+            self.pressed.set(true);
+            self.set_active(true);
+        }
+    }
+
+    fn mouse_up(&self, _: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, button: u8) {
+        if button == 0 {
+            self.pressed.set(false);
+            self.set_active(false);
+        }
+    }
+
+    fn cancel(&self, _: pal::Wm, _: HViewRef<'_>) {
+        self.pressed.set(false);
+        self.set_active(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use try_match::try_match;
+
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{
+        pal::{testing::wmapi::WndSnapshot, RGBAF32},
+        stylesheet,
+        testing::{prelude::*, use_testing_wm},
+        ui::{
+            layouts::{EmptyLayout, FillLayout},
+            theming::{StylesheetSet, TimingFunction},
+        },
+        uicore::{HWnd, SizeTraits},
+    };
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn auto_class_active(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let sb = StyledBox::new(Manager::global(wm), ViewFlags::ACCEPT_MOUSE_DRAG);
+        sb.set_auto_class_set(ClassSet::ACTIVE);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(sb.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+
+        let frame = sb.view().global_frame();
+        let inside = frame.mid();
+        let outside = frame.max + Vector2::new(10.0, 10.0);
+
+        assert!(!sb.class_set().contains(ClassSet::ACTIVE));
+
+        let drag = twm.raise_mouse_drag(&pal_hwnd, inside, 0);
+        drag.mouse_down(inside, 0);
+        assert!(sb.class_set().contains(ClassSet::ACTIVE));
+
+        drag.mouse_motion(outside);
+        assert!(!sb.class_set().contains(ClassSet::ACTIVE));
+
+        drag.mouse_motion(inside);
+        assert!(sb.class_set().contains(ClassSet::ACTIVE));
+
+        drag.mouse_up(inside, 0);
+        assert!(!sb.class_set().contains(ClassSet::ACTIVE));
+
+        // `ACTIVE` isn't touched if it's not in the auto class set
+        sb.set_auto_class_set(ClassSet::empty());
+        let drag = twm.raise_mouse_drag(&pal_hwnd, inside, 0);
+        drag.mouse_down(inside, 0);
+        assert!(!sb.class_set().contains(ClassSet::ACTIVE));
+        drag.mouse_up(inside, 0);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn transition(twm: &dyn TestingWm) {
+        const CUSTOM_ID: ClassSet = ClassSet::id(43);
+
+        let wm = twm.wm();
+
+        // Use the virtual clock to make the animation deterministic
+        twm.advance_time(Duration::from_millis(0));
+
+        let manager = Manager::global(wm);
+        manager.set_stylesheet_set(StylesheetSet::new().with(stylesheet! {
+            ([#CUSTOM_ID]) (priority = 10000) {
+                num_layers: 1,
+                layer_bg_color[0]: RGBAF32::new(1.0, 1.0, 1.0, 1.0),
+                layer_bg_color_transition[0]: Transition::new(1.0, TimingFunction::Linear),
+            },
+            ([#CUSTOM_ID.ACTIVE]) (priority = 10001) {
+                layer_bg_color[0]: RGBAF32::new(0.0, 0.0, 0.0, 1.0),
+            },
+        }));
+
+        let sb = StyledBox::new(manager, ViewFlags::default());
+        sb.set_auto_class_set(ClassSet::empty());
+        sb.set_class_set(CUSTOM_ID);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(sb.view()));
+        wnd.set_size([20, 20]);
+        wnd.set_visibility(true);
+        twm.advance_time(Duration::from_millis(100));
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+
+        // The layer is grayscale, so the channel order doesn't matter
+        let read_gray = || {
+            let mut snapshot = WndSnapshot::new();
+            twm.read_wnd_snapshot(&pal_hwnd, &mut snapshot);
+            let [w, h] = snapshot.size;
+            snapshot.data[(h / 2) * snapshot.stride + (w / 2) * 4 + 1]
+        };
+
+        // The initial value doesn't animate
+        assert_eq!(read_gray(), 255);
+        assert!(!sb.shared.transition_timer_running.get());
+
+        // Halfway through the transition
+        sb.set_class_set(CUSTOM_ID | ClassSet::ACTIVE);
+        twm.advance_time(Duration::from_millis(500));
+        let gray = read_gray();
+        assert!(gray > 64 && gray < 192, "gray = {}", gray);
+        assert!(sb.shared.transition_timer_running.get());
+
+        // The transition is complete, and the timer stops
+        twm.advance_time(Duration::from_millis(600));
+        assert_eq!(read_gray(), 0);
+        assert!(!sb.shared.transition_timer_running.get());
+
+        // Unmount the view during a transition and mount it again
+        sb.set_class_set(CUSTOM_ID);
+        twm.advance_time(Duration::from_millis(100));
+        assert!(sb.shared.transition_timer_running.get());
+        wnd.content_view()
+            .set_layout(EmptyLayout::new(SizeTraits::default()));
+        twm.advance_time(Duration::from_millis(100));
+        wnd.content_view().set_layout(FillLayout::new(sb.view()));
+        twm.advance_time(Duration::from_millis(100));
+        assert_eq!(read_gray(), 255);
+        assert!(!sb.shared.transition_timer_running.get());
+
+        // Transitions still work after remounting
+        sb.set_class_set(CUSTOM_ID | ClassSet::ACTIVE);
+        twm.advance_time(Duration::from_millis(500));
+        let gray = read_gray();
+        assert!(gray > 64 && gray < 192, "gray = {}", gray);
+        assert!(sb.shared.transition_timer_running.get());
+
+        twm.advance_time(Duration::from_millis(600));
+        assert_eq!(read_gray(), 0);
+        assert!(!sb.shared.transition_timer_running.get());
+
+        manager.set_stylesheet_set(StylesheetSet::new());
+    }
+}
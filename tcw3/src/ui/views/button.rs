@@ -1,6 +1,5 @@
 use cgmath::Point2;
-use std::{cell::RefCell, fmt, rc::Rc};
-use subscriber_list::SubscriberList;
+use std::{fmt, rc::Rc};
 
 use crate::{
     pal,
@@ -10,6 +9,7 @@ use crate::{
         mixins::ButtonMixin,
         theming::{roles, ClassSet, HElem, Manager, StyledBox, Widget},
         views::Label,
+        EventSource,
     },
     uicore::{HView, HViewRef, KeyEvent, Sub, ViewFlags, ViewListener},
 };
@@ -25,7 +25,7 @@ struct Inner {
     button_mixin: ButtonMixin,
     styled_box: StyledBox,
     label: Label,
-    activate_handlers: RefCell<SubscriberList<Box<dyn Fn(pal::Wm)>>>,
+    activate_handlers: EventSource,
 }
 
 impl fmt::Debug for Inner {
@@ -59,7 +59,7 @@ impl Button {
             button_mixin: ButtonMixin::new(),
             styled_box,
             label,
-            activate_handlers: RefCell::new(SubscriberList::new()),
+            activate_handlers: EventSource::new(),
         });
 
         view.set_listener(ButtonViewListener {
@@ -114,11 +114,7 @@ impl Button {
     /// view hierarchy and view attributes. However, it's not allowed to call
     /// `subscribe_activate` when one of the handlers is being called.
     pub fn subscribe_activated(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
-        self.inner
-            .activate_handlers
-            .borrow_mut()
-            .insert(cb)
-            .untype()
+        self.inner.activate_handlers.subscribe(cb)
     }
 }
 
@@ -192,10 +188,7 @@ impl crate::ui::mixins::button::ButtonListener for ButtonMixinListener {
     fn activate(&self, wm: pal::Wm, _: HViewRef<'_>) {
         let inner = Rc::clone(&self.inner);
         wm.invoke(move |wm| {
-            let handlers = inner.activate_handlers.borrow();
-            for handler in handlers.iter() {
-                handler(wm);
-            }
+            inner.activate_handlers.raise(wm);
         });
     }
 }
@@ -0,0 +1,203 @@
+//! Implements a widget for custom 2D drawing.
+use std::{cell::RefCell, fmt, rc::Rc};
+
+use crate::{
+    pal,
+    ui::{
+        layouts::EmptyLayout,
+        mixins::{canvas::PaintContext, CanvasMixin},
+        theming::{HElem, Widget},
+    },
+    uicore::{HView, HViewRef, HWndRef, SizeTraits, UpdateCtx, ViewFlags, ViewListener},
+};
+
+type PaintFn = Box<dyn FnMut(&mut PaintContext<'_>)>;
+
+/// A widget that displays the contents drawn by a caller-supplied paint
+/// function.
+///
+/// `CanvasView` manages a backing layer and bitmap using [`CanvasMixin`].
+/// The paint function is called when the view is mounted, resized, or moved to
+/// a window with a different DPI scale, or when [`pend_draw`] is called.
+///
+/// [`CanvasMixin`]: crate::ui::mixins::CanvasMixin
+/// [`pend_draw`]: CanvasView::pend_draw
+///
+/// # Examples
+///
+/// ```no_run
+/// use tcw3::{pal, pal::prelude::*, ui::views::CanvasView};
+/// use cggeom::Box2;
+/// use cgmath::Point2;
+///
+/// let view = CanvasView::new();
+/// view.set_painter(|ctx| {
+///     ctx.canvas.set_fill_rgb(pal::RGBAF32::new(1.0, 0.0, 0.0, 1.0));
+///     ctx.canvas.fill_rect(Box2::with_size(Point2::new(0.0, 0.0), ctx.size));
+/// });
+/// ```
+#[derive(Debug)]
+pub struct CanvasView {
+    view: HView,
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    canvas: RefCell<CanvasMixin>,
+    painter: RefCell<PaintFn>,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("canvas", &self.canvas)
+            .field("painter", &())
+            .finish()
+    }
+}
+
+impl Default for CanvasView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CanvasView {
+    /// Construct a `CanvasView`.
+    pub fn new() -> Self {
+        let inner = Rc::new(Inner {
+            canvas: RefCell::new(CanvasMixin::new()),
+            painter: RefCell::new(Box::new(|_| {})),
+        });
+
+        let view = HView::new(ViewFlags::default());
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+        view.set_listener(CanvasViewListener {
+            inner: Rc::clone(&inner),
+        });
+
+        Self { view, inner }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.view.as_ref()
+    }
+
+    /// Set the function used to draw the contents, and redraw the contents.
+    ///
+    /// When the function is called, `ctx.canvas` is configured to use the
+    /// view's coordinate space, and `ctx.size` indicates the view's size. The
+    /// function must not call methods of this `CanvasView`.
+    pub fn set_painter(&self, painter: impl FnMut(&mut PaintContext<'_>) + 'static) {
+        *self.inner.painter.borrow_mut() = Box::new(painter);
+        self.pend_draw();
+    }
+
+    /// Set the size traits of the widget.
+    ///
+    /// It defaults to `SizeTraits::default()`.
+    pub fn set_size_traits(&self, size_traits: SizeTraits) {
+        self.view.set_layout(EmptyLayout::new(size_traits));
+    }
+
+    /// Request the contents to be redrawn.
+    pub fn pend_draw(&self) {
+        self.inner.canvas.borrow_mut().pend_draw(self.view.as_ref());
+    }
+}
+
+impl Widget for CanvasView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        None
+    }
+}
+
+struct CanvasViewListener {
+    inner: Rc<Inner>,
+}
+
+impl ViewListener for CanvasViewListener {
+    fn mount(&self, wm: pal::Wm, view: HViewRef<'_>, wnd: HWndRef<'_>) {
+        self.inner.canvas.borrow_mut().mount(wm, view, wnd);
+    }
+
+    fn unmount(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        self.inner.canvas.borrow_mut().unmount(wm, view);
+    }
+
+    fn position(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        self.inner.canvas.borrow_mut().position(wm, view);
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let mut painter = self.inner.painter.borrow_mut();
+        self.inner
+            .canvas
+            .borrow_mut()
+            .update(wm, view, ctx, |paint_ctx| painter(paint_ctx));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+    use cgmath::Vector2;
+    use std::cell::Cell;
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn repaint_on_resize(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let canvas = CanvasView::new();
+
+        let num_paints = Rc::new(Cell::new(0));
+        let last_size = Rc::new(Cell::new(Vector2::new(0.0, 0.0)));
+        {
+            let num_paints = Rc::clone(&num_paints);
+            let last_size = Rc::clone(&last_size);
+            canvas.set_painter(move |ctx| {
+                num_paints.set(num_paints.get() + 1);
+                last_size.set(ctx.size);
+            });
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(canvas.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        twm.set_wnd_size(&pal_hwnd, [100, 50]);
+        twm.step_unsend();
+
+        let count = num_paints.get();
+        assert!(count >= 1);
+        assert_eq!(last_size.get(), Vector2::new(100.0, 50.0));
+
+        // Nothing to repaint
+        twm.step_unsend();
+        assert_eq!(num_paints.get(), count);
+
+        // Explicit redraw
+        canvas.pend_draw();
+        twm.step_unsend();
+        assert_eq!(num_paints.get(), count + 1);
+    }
+}
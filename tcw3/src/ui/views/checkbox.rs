@@ -1,22 +1,125 @@
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::{Rc, Weak},
+};
+
 use crate::{
     pal,
     ui::{
         theming::{ClassSet, HElem, Manager, Widget},
         views::Button,
+        EventSource,
     },
     uicore::{HView, HViewRef, Sub},
 };
 
+/// The state of [`Checkbox`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckState {
+    Unchecked,
+    Checked,
+    /// Neither checked nor unchecked, e.g., because the checkbox represents
+    /// a set of options having different states.
+    Indeterminate,
+}
+
+impl Default for CheckState {
+    fn default() -> Self {
+        Self::Unchecked
+    }
+}
+
+impl CheckState {
+    fn class_set(self) -> ClassSet {
+        match self {
+            Self::Unchecked => ClassSet::empty(),
+            Self::Checked => ClassSet::CHECKED,
+            Self::Indeterminate => ClassSet::INDETERMINATE,
+        }
+    }
+}
+
+/// The bits of `ClassSet` internally enforced by `Checkbox` and `RadioButton`.
+const PROTECTED_CLASSES: ClassSet =
+    ClassSet::from_bits_truncate(ClassSet::CHECKED.bits() | ClassSet::INDETERMINATE.bits());
+
 /// A checkbox widget (with a label).
+///
+/// By default, the checkbox doesn't change its state by itself. The
+/// application is responsible for updating the state in response to
+/// activation (see [`subscribe_activated`]). If [`set_auto_toggle`] is used to
+/// enable automatic toggling, the checkbox toggles its state when activated by
+/// a mouse click or the space key, and then calls the handler functions added
+/// by [`subscribe_toggled`].
+///
+/// [`subscribe_activated`]: crate::ui::views::Checkbox::subscribe_activated
+/// [`set_auto_toggle`]: crate::ui::views::Checkbox::set_auto_toggle
+/// [`subscribe_toggled`]: crate::ui::views::Checkbox::subscribe_toggled
 #[derive(Debug)]
 pub struct Checkbox {
+    inner: Rc<CheckboxInner>,
+}
+
+struct CheckboxInner {
     button: Button,
+    auto_toggle: Cell<bool>,
+    tristate: Cell<bool>,
+    toggle_handlers: EventSource,
+}
+
+impl fmt::Debug for CheckboxInner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CheckboxInner")
+            .field("button", &self.button)
+            .field("auto_toggle", &self.auto_toggle)
+            .field("tristate", &self.tristate)
+            .field("toggle_handlers", &self.toggle_handlers)
+            .finish()
+    }
 }
 
 /// A radio button widget (with a label).
+///
+/// By default, the radio button doesn't change its state by itself (see
+/// [`Checkbox`]). If [`set_auto_toggle`] is used to enable automatic toggling,
+/// the radio button becomes checked when activated by a mouse click or the
+/// space key. If the radio button belongs to a [`RadioGroup`], other radio
+/// buttons in the group are unchecked at the same time. The handler functions
+/// added by [`subscribe_toggled`] are called for every radio button whose
+/// state was changed as a result.
+///
+/// [`set_auto_toggle`]: crate::ui::views::RadioButton::set_auto_toggle
+/// [`subscribe_toggled`]: crate::ui::views::RadioButton::subscribe_toggled
 #[derive(Debug)]
 pub struct RadioButton {
+    inner: Rc<RadioInner>,
+}
+
+struct RadioInner {
     button: Button,
+    auto_toggle: Cell<bool>,
+    group: RefCell<Option<RadioGroup>>,
+    toggle_handlers: EventSource,
+}
+
+impl fmt::Debug for RadioInner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RadioInner")
+            .field("button", &self.button)
+            .field("auto_toggle", &self.auto_toggle)
+            .field("group", &self.group)
+            .field("toggle_handlers", &self.toggle_handlers)
+            .finish()
+    }
+}
+
+/// A set of mutually exclusive [`RadioButton`]s.
+///
+/// At most one radio button in a group can be checked at the same time.
+#[derive(Debug, Clone, Default)]
+pub struct RadioGroup {
+    members: Rc<RefCell<Vec<Weak<RadioInner>>>>,
 }
 
 impl Checkbox {
@@ -25,7 +128,103 @@ impl Checkbox {
 
         button.set_class_set(ClassSet::CHECKBOX);
 
-        Self { button }
+        let inner = Rc::new(CheckboxInner {
+            button,
+            auto_toggle: Cell::new(false),
+            tristate: Cell::new(false),
+            toggle_handlers: EventSource::new(),
+        });
+
+        let inner_weak = Rc::downgrade(&inner);
+        inner.button.subscribe_activated(Box::new(move |wm| {
+            if let Some(inner) = inner_weak.upgrade() {
+                if !inner.auto_toggle.get() {
+                    return;
+                }
+
+                let new_state = match (inner.state(), inner.tristate.get()) {
+                    (CheckState::Unchecked, _) => CheckState::Checked,
+                    (CheckState::Checked, true) => CheckState::Indeterminate,
+                    (CheckState::Checked, false) => CheckState::Unchecked,
+                    (CheckState::Indeterminate, true) => CheckState::Unchecked,
+                    (CheckState::Indeterminate, false) => CheckState::Checked,
+                };
+                inner.set_state(new_state);
+
+                inner.toggle_handlers.raise(wm);
+            }
+        }));
+
+        Self { inner }
+    }
+
+    /// Set the state of the checkbox.
+    ///
+    /// This does not call the handler functions added by [`subscribe_toggled`].
+    ///
+    /// [`subscribe_toggled`]: crate::ui::views::Checkbox::subscribe_toggled
+    pub fn set_state(&self, value: CheckState) {
+        self.inner.set_state(value);
+    }
+
+    /// Get the state of the checkbox.
+    pub fn state(&self) -> CheckState {
+        self.inner.state()
+    }
+
+    /// Set a flag indicating whether the user can put the checkbox into the
+    /// indeterminate state. Defaults to `false`.
+    ///
+    /// When this flag is set, activating the checkbox cycles through
+    /// unchecked, checked, and indeterminate. Regardless of this flag, the
+    /// application can always call [`set_state`] to put the checkbox into the
+    /// indeterminate state.
+    ///
+    /// [`set_state`]: crate::ui::views::Checkbox::set_state
+    pub fn set_tristate(&self, value: bool) {
+        self.inner.tristate.set(value);
+    }
+
+    /// Get a flag indicating whether the user can put the checkbox into the
+    /// indeterminate state.
+    pub fn tristate(&self) -> bool {
+        self.inner.tristate.get()
+    }
+
+    /// Check or uncheck the checkbox.
+    pub fn set_checked(&self, value: bool) {
+        self.set_state(if value {
+            CheckState::Checked
+        } else {
+            CheckState::Unchecked
+        });
+    }
+
+    /// Get a flag indicating whether the checkbox is checked.
+    ///
+    /// Returns `false` if the checkbox is in the indeterminate state.
+    pub fn checked(&self) -> bool {
+        self.state() == CheckState::Checked
+    }
+}
+
+impl CheckboxInner {
+    fn set_state(&self, value: CheckState) {
+        let mut class_set = self.button.class_set();
+        class_set -= PROTECTED_CLASSES;
+        class_set |= value.class_set();
+        self.button.set_class_set(class_set);
+    }
+
+    fn state(&self) -> CheckState {
+        let class_set = self.button.class_set();
+        if class_set.contains(ClassSet::CHECKED) {
+            CheckState::Checked
+        } else if class_set.contains(ClassSet::INDETERMINATE) {
+            CheckState::Indeterminate
+        } else {
+            CheckState::Unchecked
+        }
     }
 }
 
@@ -35,7 +234,135 @@ impl RadioButton {
 
         button.set_class_set(ClassSet::RADIO_BUTTON);
 
-        Self { button }
+        let inner = Rc::new(RadioInner {
+            button,
+            auto_toggle: Cell::new(false),
+            group: RefCell::new(None),
+            toggle_handlers: EventSource::new(),
+        });
+
+        let inner_weak = Rc::downgrade(&inner);
+        inner.button.subscribe_activated(Box::new(move |wm| {
+            if let Some(inner) = inner_weak.upgrade() {
+                if !inner.auto_toggle.get() || inner.checked() {
+                    return;
+                }
+
+                let unchecked = RadioInner::check(&inner);
+
+                inner.toggle_handlers.raise(wm);
+                for other in unchecked {
+                    other.toggle_handlers.raise(wm);
+                }
+            }
+        }));
+
+        Self { inner }
+    }
+
+    /// Set the group the radio button belongs to.
+    ///
+    /// If the radio button is checked, other radio buttons in the new group
+    /// are unchecked.
+    pub fn set_group(&self, group: Option<&RadioGroup>) {
+        if let Some(old_group) = self.inner.group.borrow_mut().take() {
+            let this = Rc::downgrade(&self.inner);
+            (old_group.members.borrow_mut()).retain(|m| !Weak::ptr_eq(m, &this));
+        }
+
+        if let Some(group) = group {
+            group.members.borrow_mut().push(Rc::downgrade(&self.inner));
+            self.inner.group.replace(Some(group.clone()));
+
+            if self.inner.checked() {
+                RadioInner::check(&self.inner);
+            }
+        }
+    }
+
+    /// Get the group the radio button belongs to.
+    pub fn group(&self) -> Option<RadioGroup> {
+        self.inner.group.borrow().clone()
+    }
+
+    /// Check or uncheck the radio button.
+    ///
+    /// If `value` is `true`, other radio buttons in the same group are
+    /// unchecked. This does not call the handler functions added by
+    /// [`subscribe_toggled`].
+    ///
+    /// [`subscribe_toggled`]: crate::ui::views::RadioButton::subscribe_toggled
+    pub fn set_checked(&self, value: bool) {
+        if value {
+            RadioInner::check(&self.inner);
+        } else {
+            self.inner.set_checked(false);
+        }
+    }
+
+    /// Get a flag indicating whether the radio button is checked.
+    pub fn checked(&self) -> bool {
+        self.inner.checked()
+    }
+}
+
+impl RadioInner {
+    fn set_checked(&self, value: bool) {
+        let mut class_set = self.button.class_set();
+        class_set.set(ClassSet::CHECKED, value);
+        self.button.set_class_set(class_set);
+    }
+
+    fn checked(&self) -> bool {
+        self.button.class_set().contains(ClassSet::CHECKED)
+    }
+
+    /// Check `this` and uncheck all other members of the group. Returns the
+    /// radio buttons that were unchecked.
+    fn check(this: &Rc<Self>) -> Vec<Rc<Self>> {
+        this.set_checked(true);
+
+        let group = this.group.borrow().clone();
+        let unchecked: Vec<_> = group
+            .iter()
+            .flat_map(|group| group.members())
+            .filter(|m| !Rc::ptr_eq(m, this) && m.checked())
+            .collect();
+
+        for other in unchecked.iter() {
+            other.set_checked(false);
+        }
+
+        unchecked
+    }
+}
+
+impl RadioGroup {
+    /// Construct an empty `RadioGroup`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn members(&self) -> Vec<Rc<RadioInner>> {
+        let mut members = self.members.borrow_mut();
+        members.retain(|m| m.strong_count() > 0);
+        members.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    /// Get the number of radio buttons in the group.
+    pub fn len(&self) -> usize {
+        self.members().len()
+    }
+
+    /// Get a flag indicating whether the group has no radio buttons.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the index of the checked radio button in the group. The radio
+    /// buttons are ordered by the time they were added to the group.
+    pub fn checked_index(&self) -> Option<usize> {
+        self.members().iter().position(|m| m.checked())
     }
 }
 
@@ -44,22 +371,22 @@ macro_rules! imp {
         impl $t {
             /// Get an owned handle to the view representing the widget.
             pub fn view(&self) -> HView {
-                self.button.view()
+                self.inner.button.view()
             }
 
             /// Borrow the handle to the view representing the widget.
             pub fn view_ref(&self) -> HViewRef<'_> {
-                self.button.view_ref()
+                self.inner.button.view_ref()
             }
 
             /// Get the styling element representing the widget.
             pub fn style_elem(&self) -> HElem {
-                self.button.style_elem()
+                self.inner.button.style_elem()
             }
 
             /// Set the text displayed in the widget.
             pub fn set_caption(&self, value: impl Into<String>) {
-                self.button.set_caption(value);
+                self.inner.button.set_caption(value);
             }
 
             /// Set the class set of the inner `StyledBox`.
@@ -67,17 +394,18 @@ macro_rules! imp {
             /// Some bits (e.g., `ACTIVE` and `CHECKED`) are internally enforced
             /// and cannot be modified.
             pub fn set_class_set(&self, mut class_set: ClassSet) {
+                let button = &self.inner.button;
+
                 // Protected bits
-                let protected = ClassSet::CHECKED;
-                class_set -= protected;
-                class_set |= self.button.class_set() & protected;
+                class_set -= PROTECTED_CLASSES;
+                class_set |= button.class_set() & PROTECTED_CLASSES;
 
-                self.button.set_class_set(class_set);
+                button.set_class_set(class_set);
             }
 
             /// Get the class set of the inner `StyledBox`.
             pub fn class_set(&self) -> ClassSet {
-                self.button.class_set()
+                self.inner.button.class_set()
             }
 
             /// Add a function called when the widget is activated.
@@ -86,19 +414,35 @@ macro_rules! imp {
             /// view hierarchy and view attributes. However, it's not allowed to call
             /// `subscribe_activated` when one of the handlers is being called.
             pub fn subscribe_activated(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
-                self.button.subscribe_activated(cb)
+                self.inner.button.subscribe_activated(cb)
             }
 
-            /// Check or uncheck the checkbox.
-            pub fn set_checked(&self, value: bool) {
-                let mut class_set = self.button.class_set();
-                class_set.set(ClassSet::CHECKED, value);
-                self.button.set_class_set(class_set);
+            /// Set a flag indicating whether the widget changes its state by
+            /// itself when activated. Defaults to `false`.
+            ///
+            /// When this flag is not set, the widget only calls the handlers
+            /// added by `subscribe_activated`, and the application is
+            /// responsible for updating the state.
+            pub fn set_auto_toggle(&self, value: bool) {
+                self.inner.auto_toggle.set(value);
             }
 
-            /// Get a flag indicating whether the checkbox is checked.
-            pub fn checked(&self) -> bool {
-                self.button.class_set().contains(ClassSet::CHECKED)
+            /// Get a flag indicating whether the widget changes its state by
+            /// itself when activated.
+            pub fn auto_toggle(&self) -> bool {
+                self.inner.auto_toggle.get()
+            }
+
+            /// Add a function called when the state of the widget is changed
+            /// by user interaction. This only happens if automatic toggling is
+            /// enabled by `set_auto_toggle`.
+            ///
+            /// The function is called via `Wm::invoke`, thus allowed to modify
+            /// view hierarchy and view attributes. However, it's not allowed to
+            /// call `subscribe_toggled` when one of the handlers is being
+            /// called.
+            pub fn subscribe_toggled(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
+                self.inner.toggle_handlers.subscribe(cb)
             }
         }
 
@@ -116,3 +460,143 @@ macro_rules! imp {
 
 imp!(Checkbox);
 imp!(RadioButton);
+
+#[cfg(test)]
+mod tests {
+    use cggeom::prelude::*;
+    use try_match::try_match;
+
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+
+    /// Simulate a mouse click at the center of `view`.
+    fn click(twm: &dyn TestingWm, view: HViewRef<'_>) {
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+        let loc = view.global_frame().mid();
+
+        let drag = twm.raise_mouse_drag(&pal_hwnd, loc, 0);
+        drag.mouse_down(loc, 0);
+        drag.mouse_up(loc, 0);
+        twm.step_unsend();
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn checkbox_toggle(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let checkbox = Checkbox::new(Manager::global(wm));
+        let num_toggles = Rc::new(Cell::new(0));
+        {
+            let num_toggles = Rc::clone(&num_toggles);
+            checkbox.subscribe_toggled(Box::new(move |_| num_toggles.set(num_toggles.get() + 1)));
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(checkbox.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+
+        // Automatic toggling is disabled by default
+        let num_activations = Rc::new(Cell::new(0));
+        let sub = {
+            let num_activations = Rc::clone(&num_activations);
+            checkbox.subscribe_activated(Box::new(move |_| {
+                num_activations.set(num_activations.get() + 1)
+            }))
+        };
+        click(twm, checkbox.view_ref());
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+        assert_eq!(num_activations.get(), 1);
+        assert_eq!(num_toggles.get(), 0);
+        sub.unsubscribe().unwrap();
+
+        checkbox.set_auto_toggle(true);
+
+        click(twm, checkbox.view_ref());
+        assert_eq!(checkbox.state(), CheckState::Checked);
+        assert_eq!(num_toggles.get(), 1);
+
+        click(twm, checkbox.view_ref());
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+        assert_eq!(num_toggles.get(), 2);
+
+        // Tri-state
+        checkbox.set_tristate(true);
+        checkbox.set_checked(true);
+        click(twm, checkbox.view_ref());
+        assert_eq!(checkbox.state(), CheckState::Indeterminate);
+        assert!(checkbox.class_set().contains(ClassSet::INDETERMINATE));
+        assert!(!checkbox.class_set().contains(ClassSet::CHECKED));
+
+        click(twm, checkbox.view_ref());
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+        assert_eq!(num_toggles.get(), 4);
+
+        // The state can't be modified by `set_class_set`
+        checkbox.set_class_set(ClassSet::CHECKBOX | ClassSet::CHECKED);
+        assert_eq!(checkbox.state(), CheckState::Unchecked);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn radio_group(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let style_manager = Manager::global(wm);
+
+        let group = RadioGroup::new();
+        let buttons: Vec<_> = (0..3)
+            .map(|_| {
+                let button = RadioButton::new(style_manager);
+                button.set_auto_toggle(true);
+                button.set_group(Some(&group));
+                button
+            })
+            .collect();
+        assert_eq!(group.len(), 3);
+        assert_eq!(group.checked_index(), None);
+
+        let num_toggles = Rc::new(Cell::new(0));
+        for button in buttons.iter() {
+            let num_toggles = Rc::clone(&num_toggles);
+            button.subscribe_toggled(Box::new(move |_| num_toggles.set(num_toggles.get() + 1)));
+        }
+
+        buttons[0].set_checked(true);
+        assert_eq!(group.checked_index(), Some(0));
+
+        buttons[2].set_checked(true);
+        assert_eq!(group.checked_index(), Some(2));
+        assert!(!buttons[0].checked());
+        assert_eq!(num_toggles.get(), 0);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(buttons[1].view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        // `buttons[1]` is checked, and `buttons[2]` is unchecked
+        click(twm, buttons[1].view_ref());
+        assert_eq!(group.checked_index(), Some(1));
+        assert_eq!(num_toggles.get(), 2);
+
+        // Clicking a checked radio button does nothing
+        click(twm, buttons[1].view_ref());
+        assert_eq!(group.checked_index(), Some(1));
+        assert_eq!(num_toggles.get(), 2);
+
+        // Leave the group
+        buttons[1].set_group(None);
+        assert_eq!(group.len(), 2);
+        assert_eq!(group.checked_index(), None);
+    }
+}
@@ -12,7 +12,6 @@ use std::{
     ops::Range,
     rc::Rc,
 };
-use subscriber_list::SubscriberList;
 use unicount::{str_ceil, str_floor, str_prev};
 
 use crate::{
@@ -20,18 +19,20 @@ use crate::{
     pal::prelude::*,
     ui::{
         layouts::EmptyLayout,
-        mixins::CanvasMixin,
+        mixins::{scrollwheel::ScrollModel, CanvasMixin, ScrollWheelMixin},
+        text_editing,
         theming::{
             self, elem_id, roles, ClassSet, GetPropValue, HElem, Prop, PropKindFlags, Widget,
         },
+        EventSource,
     },
     uicore::{
         actions, ActionId, ActionStatus, CursorShape, HView, HViewRef, HWndRef, MouseDragListener,
-        SizeTraits, Sub, UpdateCtx, ViewFlags, ViewListener, WeakHView, WmExt,
+        ScrollDelta, ScrollListener, SizeTraits, Sub, UpdateCtx, ViewFlags, ViewListener,
+        WeakHView, WmExt,
     },
 };
 
-mod history;
 #[cfg(test)]
 mod tests;
 
@@ -121,6 +122,16 @@ impl Widget for Entry {
 
 /// A widget implementing the core functionality of a text input field.
 ///
+/// `EntryCore` operates in one of the two modes chosen at construction time:
+/// the single-line mode ([`EntryCore::new`]) and the multi-line mode
+/// ([`EntryCore::new_multiline`]). In the multi-line mode, the text is wrapped
+/// to the view's width and the widget scrolls vertically.
+///
+/// The whole text is laid out as a single `TextLayout`, which is recreated
+/// whenever the text or the wrapping width changes. Only drawing is limited to
+/// the visible lines, so the multi-line mode is not suitable for large
+/// documents.
+///
 /// # Styling
 ///
 ///  - `style_elem` - `FgColor`, `Padding`
@@ -135,15 +146,19 @@ pub struct EntryCore {
 struct Inner {
     wm: pal::Wm,
     view: WeakHView,
+    multiline: bool,
     state: RefCell<State>,
     style_elem: theming::Elem,
     style_sel_elem: theming::Elem,
     tictx_event_mask: Cell<pal::TextInputCtxEventFlags>,
 
     /// The list of subscribers of the `change` event.
-    change_handlers: RefCell<SubscriberList<Box<dyn Fn(pal::Wm)>>>,
+    change_handlers: EventSource,
     /// `true` means the calls to `change_handlers` are pended.
     pending_change_handler: Cell<bool>,
+
+    /// Handles scroll events. Only used in the multi-line mode.
+    scroll_mixin: ScrollWheelMixin,
 }
 
 impl fmt::Debug for Inner {
@@ -151,6 +166,7 @@ impl fmt::Debug for Inner {
         f.debug_struct("Inner")
             .field("wm", &self.wm)
             .field("view", &self.view)
+            .field("multiline", &self.multiline)
             .field("state", &self.state)
             .field("style_elem", &self.style_elem)
             .field("style_sel_elem", &self.style_sel_elem)
@@ -164,7 +180,11 @@ impl fmt::Debug for Inner {
 struct State {
     text: String,
     text_layout_info: Option<TextLayoutInfo>,
+    /// The scroll position. It's horizontal in the single-line mode and
+    /// vertical in the multi-line mode.
     scroll: f32,
+    /// The wrapping width of the text. `None` in the single-line mode.
+    wrap_width: Option<f32>,
     canvas: CanvasMixin,
     tictx: Option<pal::HTextInputCtx>,
     sel_range: [usize; 2],
@@ -175,13 +195,14 @@ struct State {
     caret_layers: Option<[pal::HLayer; 2]>,
     caret_blink: bool,
     caret_blink_timer: Option<pal::HInvoke>,
-    history: history::History,
+    history: text_editing::UndoStack<String>,
 }
 
 #[derive(Debug)]
 struct TextLayoutInfo {
     text_layout: pal::TextLayout,
     layout_bounds: Box2<f32>,
+    multiline: bool,
 
     line_height: f32,
 
@@ -194,19 +215,36 @@ struct TextLayoutInfo {
 }
 
 impl EntryCore {
+    /// Construct a single-line `EntryCore`.
     pub fn new(wm: pal::Wm, style_manager: &'static theming::Manager) -> Self {
+        Self::with_multiline(wm, style_manager, false)
+    }
+
+    /// Construct a multi-line `EntryCore`.
+    pub fn new_multiline(wm: pal::Wm, style_manager: &'static theming::Manager) -> Self {
+        Self::with_multiline(wm, style_manager, true)
+    }
+
+    fn with_multiline(
+        wm: pal::Wm,
+        style_manager: &'static theming::Manager,
+        multiline: bool,
+    ) -> Self {
         let style_elem = theming::Elem::new(style_manager);
         let style_sel_elem = theming::Elem::new(style_manager);
         style_sel_elem.set_class_set(elem_id::TEXT_SELECTION);
         style_elem.insert_child(style_sel_elem.helem());
 
-        let view = HView::new(
-            ViewFlags::default()
-                | ViewFlags::ACCEPT_MOUSE_OVER
-                | ViewFlags::ACCEPT_MOUSE_DRAG
-                | ViewFlags::TAB_STOP
-                | ViewFlags::STRONG_FOCUS,
-        );
+        let mut view_flags = ViewFlags::default()
+            | ViewFlags::ACCEPT_MOUSE_OVER
+            | ViewFlags::ACCEPT_MOUSE_DRAG
+            | ViewFlags::TAB_STOP
+            | ViewFlags::STRONG_FOCUS;
+        if multiline {
+            view_flags |= ViewFlags::ACCEPT_SCROLL;
+        }
+
+        let view = HView::new(view_flags);
         let weak_view = view.downgrade();
 
         let this = Self {
@@ -214,10 +252,12 @@ impl EntryCore {
             inner: Rc::new(Inner {
                 wm,
                 view: weak_view,
+                multiline,
                 state: RefCell::new(State {
                     text: String::new(),
                     text_layout_info: None,
                     scroll: 0.0,
+                    wrap_width: if multiline { Some(0.0) } else { None },
                     canvas: CanvasMixin::new(),
                     tictx: None,
                     sel_range: [0; 2],
@@ -226,13 +266,14 @@ impl EntryCore {
                     caret_layers: None,
                     caret_blink: true,
                     caret_blink_timer: None,
-                    history: history::History::new(),
+                    history: text_editing::UndoStack::new(),
                 }),
                 style_elem,
                 style_sel_elem,
                 tictx_event_mask: Cell::new(pal::TextInputCtxEventFlags::empty()),
-                change_handlers: RefCell::new(SubscriberList::new()),
+                change_handlers: EventSource::new(),
                 pending_change_handler: Cell::new(false),
+                scroll_mixin: ScrollWheelMixin::new(),
             }),
         };
 
@@ -304,13 +345,32 @@ impl EntryCore {
             &mut |state| {
                 state.text = value.take().unwrap();
                 state.sel_range = [0, 0];
-                state.history = history::History::new();
+                state.history.clear();
 
                 UpdateStateFlags::ANY
             },
         );
     }
 
+    /// Start an undo group. The modifications made until the matching call to
+    /// [`end_undo_group`] are undone and redone as a single operation.
+    ///
+    /// Groups can be nested. Only the outermost group has an effect.
+    ///
+    /// [`end_undo_group`]: EntryCore::end_undo_group
+    pub fn begin_undo_group(&self) {
+        self.inner.state.borrow_mut().history.begin_undo_group();
+    }
+
+    /// End an undo group started by [`begin_undo_group`].
+    ///
+    /// Panics if there's no matching call to `begin_undo_group`.
+    ///
+    /// [`begin_undo_group`]: EntryCore::begin_undo_group
+    pub fn end_undo_group(&self) {
+        self.inner.state.borrow_mut().history.end_undo_group();
+    }
+
     /// Add a function called when the text content is modified.
     ///
     /// The function may be called spuriously, i.e., even when the text content
@@ -320,7 +380,7 @@ impl EntryCore {
     /// view hierarchy and view attributes. However, it's not allowed to call
     /// `subscribe_changed` when one of the handlers is being called.
     pub fn subscribe_changed(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
-        self.inner.change_handlers.borrow_mut().insert(cb).untype()
+        self.inner.change_handlers.subscribe(cb)
     }
 }
 
@@ -333,13 +393,16 @@ impl State {
                 sys: Some(font_type),
                 ..Default::default()
             });
-            let text_layout = pal::TextLayout::from_text(&self.text, &char_style, None);
+            // This shapes the whole text even in the multi-line mode. See the
+            // documentation of `EntryCore`.
+            let text_layout = pal::TextLayout::from_text(&self.text, &char_style, self.wrap_width);
 
             let layout_bounds = text_layout.layout_bounds();
 
             self.text_layout_info = Some(TextLayoutInfo {
                 text_layout,
                 layout_bounds,
+                multiline: self.wrap_width.is_some(),
                 runs: Vec::new(),
                 line_vertical_bounds: 0.0..0.0,
                 line_height: char_style.size(),
@@ -349,6 +412,15 @@ impl State {
         self.text_layout_info.as_mut().unwrap()
     }
 
+    /// Get the currently selected text.
+    fn selected_text(&self) -> &str {
+        let [mut start, mut end] = self.sel_range;
+        if start > end {
+            std::mem::swap(&mut start, &mut end);
+        }
+        &self.text[start..end]
+    }
+
     /// Delete the cached `TextLayout` (if any).
     fn invalidate_text_layout(&mut self) {
         self.text_layout_info = None;
@@ -423,16 +495,27 @@ impl State {
     fn scroll_cursor_into_view(&mut self, hview: HViewRef<'_>, elem: &theming::Elem) -> bool {
         let cursor_i = self.sel_range[1];
         let layout_info = self.ensure_text_layout(elem);
-        let cursor_x = layout_info.text_layout.cursor_pos(cursor_i)[0].x;
-        let [_, padding_right, _, padding_left] = elem.computed_values().padding();
-        let text_width = layout_info.layout_bounds.max.x;
-        let viewport_width = hview.frame().size().x - (padding_right + padding_left);
+        let cursor = layout_info.text_layout.cursor_pos(cursor_i)[0];
+        let [padding_top, padding_right, padding_bottom, padding_left] =
+            elem.computed_values().padding();
+
+        let new_scroll = if layout_info.multiline {
+            let text_height = layout_info.layout_bounds.max.y;
+            let viewport_height = hview.frame().size().y - (padding_top + padding_bottom);
+
+            self.scroll
+                .fmax(cursor.bottom - viewport_height)
+                .fmin(cursor.top)
+                .fmin((text_height - viewport_height).fmax(0.0))
+        } else {
+            let text_width = layout_info.layout_bounds.max.x;
+            let viewport_width = hview.frame().size().x - (padding_right + padding_left);
 
-        let new_scroll = self
-            .scroll
-            .fmax(cursor_x - viewport_width)
-            .fmin(cursor_x)
-            .fmin((text_width - viewport_width).fmax(0.0));
+            self.scroll
+                .fmax(cursor.x - viewport_width)
+                .fmin(cursor.x)
+                .fmin((text_width - viewport_width).fmax(0.0))
+        };
 
         if new_scroll != self.scroll {
             self.scroll = new_scroll;
@@ -441,13 +524,55 @@ impl State {
             false
         }
     }
+
+    /// Get the maximum scroll position.
+    fn max_scroll(&mut self, hview: HViewRef<'_>, elem: &theming::Elem) -> f32 {
+        let layout_info = self.ensure_text_layout(elem);
+        let [padding_top, padding_right, padding_bottom, padding_left] =
+            elem.computed_values().padding();
+        let size = hview.frame().size();
+
+        if layout_info.multiline {
+            let text_height = layout_info.layout_bounds.max.y;
+            (text_height - (size.y - (padding_top + padding_bottom))).fmax(0.0)
+        } else {
+            let text_width = layout_info.layout_bounds.max.x;
+            (text_width - (size.x - (padding_right + padding_left))).fmax(0.0)
+        }
+    }
+
+    /// Update `wrap_width` based on the view's current size. Returns `true` if
+    /// the text layout was invalidated as a result.
+    ///
+    /// Does nothing in the single-line mode.
+    fn update_wrap_width(&mut self, hview: HViewRef<'_>, elem: &theming::Elem) -> bool {
+        if self.wrap_width.is_none() {
+            return false;
+        }
+
+        let [_, padding_right, _, padding_left] = elem.computed_values().padding();
+        let wrap_width = (hview.frame().size().x - (padding_right + padding_left)).fmax(0.0);
+
+        if self.wrap_width != Some(wrap_width) {
+            self.wrap_width = Some(wrap_width);
+            self.invalidate_text_layout();
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl TextLayoutInfo {
     fn text_origin(&self, view: HViewRef<'_>, scroll: f32, elem: &theming::Elem) -> Vector2<f32> {
+        let [padding_top, _, padding_bottom, padding_left] = elem.computed_values().padding();
+
+        if self.multiline {
+            return [padding_left, padding_top - scroll].into();
+        }
+
         let baseline = self.text_layout.line_baseline(0);
         let height = view.frame().size().y;
-        let [padding_top, _, padding_bottom, padding_left] = elem.computed_values().padding();
         [
             padding_left - scroll,
             (height + self.line_height + padding_top - padding_bottom) * 0.5 - baseline,
@@ -470,11 +595,27 @@ impl TextLayoutInfo {
         view: HViewRef<'_>,
         scroll: f32,
         elem: &theming::Elem,
-        x: f32,
+        p: Point2<f32>,
     ) -> usize {
-        self.text_layout.cursor_index_from_point(
-            [x - self.text_origin_global(view, scroll, elem).x, 0.0].into(),
-        )
+        let origin = self.text_origin_global(view, scroll, elem);
+
+        // In the single-line mode, ignore the Y coordinate so that the user
+        // can point any character even if they are not being precise
+        let y = if self.multiline { p.y - origin.y } else { 0.0 };
+
+        self.text_layout
+            .cursor_index_from_point([p.x - origin.x, y].into())
+    }
+
+    /// Get the range of lines intersecting with the given vertical range
+    /// (in the text layout's coordinate space).
+    fn lines_in_vertical_range(&self, y: Range<f32>) -> Range<usize> {
+        let text_layout = &self.text_layout;
+        let line_at = |y: f32| {
+            let i = text_layout.cursor_index_from_point([0.0, y].into());
+            text_layout.line_from_index(i)
+        };
+        line_at(y.start)..line_at(y.end) + 1
     }
 }
 
@@ -495,6 +636,10 @@ fn reapply_style(inner: &Rc<Inner>, view: HViewRef<'_>, kind_flags: PropKindFlag
         state.canvas.pend_draw(view);
     }
 
+    if kind_flags.intersects(Prop::Padding.kind_flags()) {
+        state.update_wrap_width(view, &inner.style_elem);
+    }
+
     if kind_flags.intersects(Prop::Font.kind_flags()) {
         state.invalidate_text_layout();
         state.canvas.pend_draw(view);
@@ -570,6 +715,36 @@ impl EntryCoreListener {
         });
     }
 
+    /// Replace the current selection with `text`.
+    fn handle_insert(&self, view: HViewRef<'_>, text: &str) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            let [mut start, mut end] = state.sel_range;
+            if start > end {
+                std::mem::swap(&mut start, &mut end);
+            }
+
+            log::trace!("... replacing {:?} with {:?}", start..end, text);
+
+            if start == end && text.is_empty() {
+                // There's nothing to change
+                return UpdateStateFlags::empty();
+            }
+
+            // Record the change to the undo history
+            {
+                let mut tx = state.history.start_transaction();
+                tx.replace_range(&mut state.history, &state.text, start..end, text.to_owned());
+                tx.finish(&mut state.history, &state.text);
+            }
+
+            // Update `text`
+            state.text.replace_range(start..end, text);
+            state.sel_range = [start + text.len(); 2];
+
+            UpdateStateFlags::ANY
+        });
+    }
+
     fn handle_move(&self, view: HViewRef<'_>, selecting: bool, get_new_pos: MoveHandler) {
         update_state(view, RcBorrow::from(&self.inner), &mut |state| {
             log::trace!("... original sel_range = {:?}", state.sel_range);
@@ -600,17 +775,179 @@ impl EntryCoreListener {
         });
     }
 
+    /// Move the cursor vertically by `num_lines` lines (multi-line mode only).
+    /// If `num_lines` is `None`, moves the cursor by a page.
+    fn handle_move_lines(
+        &self,
+        view: HViewRef<'_>,
+        selecting: bool,
+        up: bool,
+        num_lines: Option<usize>,
+    ) {
+        update_state(view, RcBorrow::from(&self.inner), &mut |state| {
+            log::trace!("... original sel_range = {:?}", state.sel_range);
+
+            let [padding_top, _, padding_bottom, _] =
+                self.inner.style_elem.computed_values().padding();
+            let viewport_height = view.frame().size().y - (padding_top + padding_bottom);
+
+            state.ensure_text_layout(&self.inner.style_elem);
+            let layout_info = state.text_layout_info.as_ref().unwrap();
+            let num_lines = num_lines
+                .unwrap_or_else(|| ((viewport_height / layout_info.line_height) as usize).max(1));
+            let layout = &layout_info.text_layout;
+
+            let [mut start, mut end] = state.sel_range;
+            if start > end {
+                std::mem::swap(&mut start, &mut end);
+            }
+
+            let i = if selecting {
+                state.sel_range[1]
+            } else if up {
+                start
+            } else {
+                end
+            };
+
+            let line = layout.line_from_index(i);
+            let new_i = if up && line < num_lines {
+                0
+            } else if !up && line + num_lines >= layout.num_lines() {
+                state.text.len()
+            } else {
+                let new_line = if up {
+                    line - num_lines
+                } else {
+                    line + num_lines
+                };
+
+                // Preserve the X coordinate
+                let x = layout.cursor_pos(i)[0].x;
+                let vert_bounds = layout.line_vertical_bounds(new_line);
+                let y = (vert_bounds.start + vert_bounds.end) * 0.5;
+                layout.cursor_index_from_point([x, y].into())
+            };
+
+            if selecting {
+                state.sel_range[1] = new_i;
+            } else {
+                state.sel_range = [new_i; 2];
+            }
+
+            state.history.mark_logical_op_break();
+
+            log::trace!("... new sel_range = {:?}", state.sel_range);
+            UpdateStateFlags::SEL
+        });
+    }
+
+    /// Perform an action specific to the multi-line mode. Returns `false` if
+    /// the action should be handled by the common code.
+    fn perform_multiline_action(&self, view: HViewRef<'_>, action: ActionId) -> bool {
+        let move_start_of_line: MoveHandler = |sel, layout, _| {
+            let line = layout.line_from_index(sel[0]);
+            layout.line_index_range(line).start
+        };
+        let move_end_of_line: MoveHandler = |sel, layout, text| {
+            let line = layout.line_from_index(sel[1]);
+            let range = layout.line_index_range(line);
+
+            // Exclude the trailing line break
+            let line_text = &text[range.clone()];
+            let trimmed = line_text.trim_end_matches(&['\r', '\n'][..]);
+            if trimmed.len() < line_text.len() {
+                range.start + trimmed.len()
+            } else if line + 1 < layout.num_lines() {
+                // The line was wrapped. `range.end` refers to the start of
+                // the next line, so stop before that.
+                layout.next_char(range.end, false).max(range.start)
+            } else {
+                range.end
+            }
+        };
+        let move_start_of_paragraph: MoveHandler =
+            |sel, _, text| text[..sel[0]].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let move_end_of_paragraph: MoveHandler = |sel, _, text| {
+            text[sel[1]..]
+                .find(&['\r', '\n'][..])
+                .map(|i| sel[1] + i)
+                .unwrap_or_else(|| text.len())
+        };
+
+        match action {
+            actions::MOVE_UP => self.handle_move_lines(view, false, true, Some(1)),
+            actions::MOVE_UP_SELECTING => self.handle_move_lines(view, true, true, Some(1)),
+            actions::MOVE_DOWN => self.handle_move_lines(view, false, false, Some(1)),
+            actions::MOVE_DOWN_SELECTING => self.handle_move_lines(view, true, false, Some(1)),
+            actions::MOVE_UP_PAGE => self.handle_move_lines(view, false, true, None),
+            actions::MOVE_UP_PAGE_SELECTING => self.handle_move_lines(view, true, true, None),
+            actions::MOVE_DOWN_PAGE => self.handle_move_lines(view, false, false, None),
+            actions::MOVE_DOWN_PAGE_SELECTING => self.handle_move_lines(view, true, false, None),
+
+            // TODO: Use the primary writing direction
+            actions::MOVE_START_OF_LINE | actions::MOVE_LEFT_END_OF_LINE => {
+                self.handle_move(view, false, move_start_of_line)
+            }
+            actions::MOVE_START_OF_LINE_SELECTING | actions::MOVE_LEFT_END_OF_LINE_SELECTING => {
+                self.handle_move(view, true, move_start_of_line)
+            }
+            actions::MOVE_END_OF_LINE | actions::MOVE_RIGHT_END_OF_LINE => {
+                self.handle_move(view, false, move_end_of_line)
+            }
+            actions::MOVE_END_OF_LINE_SELECTING | actions::MOVE_RIGHT_END_OF_LINE_SELECTING => {
+                self.handle_move(view, true, move_end_of_line)
+            }
+
+            actions::MOVE_START_OF_PARAGRAPH => {
+                self.handle_move(view, false, move_start_of_paragraph)
+            }
+            actions::MOVE_START_OF_PARAGRAPH_SELECTING => {
+                self.handle_move(view, true, move_start_of_paragraph)
+            }
+            actions::MOVE_END_OF_PARAGRAPH => self.handle_move(view, false, move_end_of_paragraph),
+            actions::MOVE_END_OF_PARAGRAPH_SELECTING => {
+                self.handle_move(view, true, move_end_of_paragraph)
+            }
+
+            actions::INSERT_LINE_BREAK | actions::INSERT_PARAGRAPH_BREAK => {
+                log::trace!("Handling INSERT_LINE_BREAK or INSERT_PARAGRAPH_BREAK");
+                self.handle_insert(view, "\n");
+            }
+
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Construct a closure that creates a `ScrollModel` for this widget.
+    fn scroll_model_getter(&self) -> impl Fn() -> Box<dyn ScrollModel> + 'static {
+        let inner_weak = Rc::downgrade(&self.inner);
+        move || {
+            let inner = inner_weak.upgrade();
+            let view = inner.as_ref().and_then(|inner| inner.view.upgrade());
+            if let (Some(inner), Some(view)) = (inner, view) {
+                Box::new(EntryCoreScrollModel::new(view, inner))
+            } else {
+                Box::new(())
+            }
+        }
+    }
+
     fn handle_undo(&self, view: HViewRef<'_>) {
         update_state(view, RcBorrow::from(&self.inner), &mut |state| {
-            if let Some(edit) = state.history.undo() {
-                log::debug!("Undoing {:?}", edit);
+            if let Some(edits) = state.history.undo() {
+                log::debug!("Undoing {:?}", edits);
 
-                // Revert `edit`
-                debug_assert_eq!(state.text[edit.range_new()], edit.new[..]);
-                state.text.replace_range(edit.range_new(), &edit.old);
+                // Revert `edits` in the reverse order
+                for edit in edits.iter().rev() {
+                    debug_assert_eq!(state.text[edit.range_new()], edit.new[..]);
+                    state.text.replace_range(edit.range_new(), &edit.old);
 
-                let sel_range = edit.range_old();
-                state.sel_range = [sel_range.start, sel_range.end];
+                    let sel_range = edit.range_old();
+                    state.sel_range = [sel_range.start, sel_range.end];
+                }
 
                 UpdateStateFlags::ANY
             } else {
@@ -621,15 +958,17 @@ impl EntryCoreListener {
 
     fn handle_redo(&self, view: HViewRef<'_>) {
         update_state(view, RcBorrow::from(&self.inner), &mut |state| {
-            if let Some(edit) = state.history.redo() {
-                log::debug!("Redoing {:?}", edit);
+            if let Some(edits) = state.history.redo() {
+                log::debug!("Redoing {:?}", edits);
 
-                // Re-apply `edit`
-                debug_assert_eq!(state.text[edit.range_old()], edit.old[..]);
-                state.text.replace_range(edit.range_old(), &edit.new);
+                // Re-apply `edits`
+                for edit in edits.iter() {
+                    debug_assert_eq!(state.text[edit.range_old()], edit.old[..]);
+                    state.text.replace_range(edit.range_old(), &edit.new);
 
-                let sel_range = edit.range_new();
-                state.sel_range = [sel_range.start, sel_range.end];
+                    let sel_range = edit.range_new();
+                    state.sel_range = [sel_range.start, sel_range.end];
+                }
 
                 UpdateStateFlags::ANY
             } else {
@@ -710,7 +1049,7 @@ impl ViewListener for EntryCoreListener {
         state.reset_timer(hview, RcBorrow::from(&self.inner), Some(false));
     }
 
-    fn validate_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
+    fn validate_action(&self, wm: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
         let mut status = ActionStatus::empty();
         match action {
             actions::SELECT_ALL
@@ -764,6 +1103,11 @@ impl ViewListener for EntryCoreListener {
             | actions::MOVE_DOWN_PAGE_SELECTING => {
                 status |= ActionStatus::VALID | ActionStatus::ENABLED;
             }
+            actions::INSERT_LINE_BREAK | actions::INSERT_PARAGRAPH_BREAK => {
+                if self.inner.multiline {
+                    status |= ActionStatus::VALID | ActionStatus::ENABLED;
+                }
+            }
             actions::COPY | actions::CUT => {
                 let state = self.inner.state.borrow();
                 if state.sel_range[0] != state.sel_range[1] {
@@ -772,7 +1116,9 @@ impl ViewListener for EntryCoreListener {
                 status |= ActionStatus::VALID;
             }
             actions::PASTE => {
-                // TODO: Check if the clipboard contains a text
+                if wm.clipboard_text().is_some() {
+                    status |= ActionStatus::ENABLED;
+                }
                 status |= ActionStatus::VALID;
             }
             actions::UNDO => {
@@ -792,7 +1138,7 @@ impl ViewListener for EntryCoreListener {
         status
     }
 
-    fn perform_action(&self, _: pal::Wm, view: HViewRef<'_>, action: ActionId) {
+    fn perform_action(&self, wm: pal::Wm, view: HViewRef<'_>, action: ActionId) {
         let move_backward: MoveHandler = |sel, layout, _| {
             if sel[0] == sel[1] {
                 layout.next_char(sel[0], false)
@@ -821,6 +1167,10 @@ impl ViewListener for EntryCoreListener {
         let move_left_end = move_start;
         let move_right_end = move_end;
 
+        if self.inner.multiline && self.perform_multiline_action(view, action) {
+            return;
+        }
+
         match action {
             actions::SELECT_ALL | actions::SELECT_LINE | actions::SELECT_PARAGRAPH => {
                 log::trace!("Handling a 'select all' command (SELECT_ALL, etc.)");
@@ -851,14 +1201,28 @@ impl ViewListener for EntryCoreListener {
                     UpdateStateFlags::SEL
                 });
             }
-            actions::COPY => {
-                log::warn!("TODO: Copy");
-            }
-            actions::CUT => {
-                log::warn!("TODO: Cut");
+            actions::COPY | actions::CUT => {
+                log::trace!("Handling COPY or CUT");
+                let text = self.inner.state.borrow().selected_text().to_owned();
+                if !text.is_empty() {
+                    wm.set_clipboard_text(&text);
+
+                    if action == actions::CUT {
+                        // Delete the selection. `get_range` is never called
+                        // because the selection is not empty.
+                        self.handle_delete(view, |i, _, _| i);
+                    }
+                }
             }
             actions::PASTE => {
-                log::warn!("TODO: Paste");
+                log::trace!("Handling PASTE");
+                if let Some(mut text) = wm.clipboard_text() {
+                    if !self.inner.multiline {
+                        // Join the lines into one
+                        text = text.lines().collect::<Vec<_>>().join(" ");
+                    }
+                    self.handle_insert(view, &text);
+                }
             }
             actions::DELETE_BACKWARD => {
                 log::trace!("Handling DELETE_BACKWARD");
@@ -1043,11 +1407,39 @@ impl ViewListener for EntryCoreListener {
         }
     }
 
+    fn scroll_motion(&self, wm: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, delta: &ScrollDelta) {
+        if self.inner.multiline {
+            self.inner
+                .scroll_mixin
+                .scroll_motion(wm, delta, self.scroll_model_getter());
+        }
+    }
+
+    fn scroll_gesture(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+    ) -> Box<dyn ScrollListener> {
+        if self.inner.multiline {
+            self.inner
+                .scroll_mixin
+                .scroll_gesture(self.scroll_model_getter())
+        } else {
+            Box::new(())
+        }
+    }
+
     fn position(&self, wm: pal::Wm, view: HViewRef<'_>) {
         let mut state = self.inner.state.borrow_mut();
         state.canvas.position(wm, view);
 
-        if state.scroll_cursor_into_view(view, &self.inner.style_elem) {
+        // In the multi-line mode, the text is re-wrapped to the new width
+        let mut redraw = state.update_wrap_width(view, &self.inner.style_elem);
+
+        redraw |= state.scroll_cursor_into_view(view, &self.inner.style_elem);
+
+        if redraw {
             state.canvas.pend_draw(view);
         }
 
@@ -1092,27 +1484,29 @@ impl ViewListener for EntryCoreListener {
                 c.save();
                 c.mult_transform(Matrix3::from_translation(text_origin));
 
+                // Only the visible lines have to be processed
+                let visible_lines = text_layout_info
+                    .lines_in_vertical_range(-text_origin.y..visual_bounds.max.y - text_origin.y);
+
                 if is_focused && sel_range[0] != sel_range[1] {
                     if sel_range[1] < sel_range[0] {
                         sel_range.reverse();
                     }
-                    // TODO: Make sure the text is really single-lined. Otherwise,
-                    //       we might break the contract of `run_metrics_of_range`
-                    //       when the range is in a different line
-                    let line = 0;
-                    let vert_bounds = text_layout.line_vertical_bounds(line);
-                    let runs = text_layout.run_metrics_of_range(sel_range[0]..sel_range[1]);
                     log::trace!("sel_range = {:?}", sel_range[0]..sel_range[1]);
-                    log::trace!("runs({:?}) = {:?}", sel_range[0]..sel_range[1], runs);
 
                     // Fill the selection
                     c.set_fill_rgb(sel_color);
-                    for run in runs.iter() {
-                        c.fill_rect(box2! {
-                            min: [run.bounds.start, vert_bounds.start],
-                            max: [run.bounds.end, vert_bounds.end],
-                        });
-                    }
+                    for_each_run_in_range(
+                        text_layout,
+                        sel_range[0]..sel_range[1],
+                        visible_lines.clone(),
+                        |run, vert_bounds| {
+                            c.fill_rect(box2! {
+                                min: [run.bounds.start, vert_bounds.start],
+                                max: [run.bounds.end, vert_bounds.end],
+                            });
+                        },
+                    );
                 }
 
                 c.draw_text(&text_layout, Point2::new(0.0, 0.0), color);
@@ -1121,20 +1515,22 @@ impl ViewListener for EntryCoreListener {
                     // Draw an underline below the preedit text
                     // TODO: The backend shouldn't give a zero-length composition range
                     if comp_range[1] > comp_range[0] {
-                        // TODO: See the above TODO regarding `line`
-                        let line = 0;
-                        let y = text_layout.line_baseline(line);
-                        let runs = text_layout.run_metrics_of_range(comp_range[0]..comp_range[1]);
                         log::trace!("comp_range = {:?}", comp_range[0]..comp_range[1]);
-                        log::trace!("runs({:?}) = {:?}", comp_range[0]..comp_range[1], runs);
 
                         c.set_fill_rgb([color.r, color.g, color.b, color.a * 0.6].into());
-                        for run in runs.iter() {
-                            c.fill_rect(box2! {
-                                min: [run.bounds.start, y + 1.0],
-                                max: [run.bounds.end, y + 2.0],
-                            });
-                        }
+                        for_each_run_in_range(
+                            text_layout,
+                            comp_range[0]..comp_range[1],
+                            visible_lines,
+                            |run, _| {
+                                let line = text_layout.line_from_index(run.index.start);
+                                let y = text_layout.line_baseline(line);
+                                c.fill_rect(box2! {
+                                    min: [run.bounds.start, y + 1.0],
+                                    max: [run.bounds.end, y + 2.0],
+                                });
+                            },
+                        );
                     }
                 }
 
@@ -1179,9 +1575,12 @@ impl ViewListener for EntryCoreListener {
             layer_attrs[0].bounds = Some(rect0);
 
             // Hide the caret if it's out of view or `caret_blink == false`
+            let size = global_frame.size();
             for i in 0..2 {
                 if !state.caret_blink
-                    || !(0.0..global_frame.size().x).contains(&(pos[i].x + text_origin.x))
+                    || !(0.0..size.x).contains(&(pos[i].x + text_origin.x))
+                    || pos[i].bottom + text_origin.y <= 0.0
+                    || pos[i].top + text_origin.y >= size.y
                 {
                     layer_attrs[i].opacity = Some(0.0);
                 }
@@ -1223,11 +1622,14 @@ impl pal::iface::TextInputCtxListener<pal::Wm> for EntryCoreListener {
         _: &pal::HTextInputCtx,
         _mutating: bool,
     ) -> Box<dyn pal::iface::TextInputCtxEdit<pal::Wm> + '_> {
+        let state = self.inner.state.borrow_mut();
+        let orig_sel_range = state.sel_range;
         Box::new(Edit {
-            state: self.inner.state.borrow_mut(),
+            state,
             view: self.inner.view.upgrade().unwrap(),
             inner: RcBorrow::from(&self.inner),
             history_tx: None,
+            orig_sel_range,
         })
     }
 
@@ -1246,7 +1648,9 @@ struct Edit<'a> {
     state: RefMut<'a, State>,
     inner: RcBorrow<'a, Inner>,
     view: HView,
-    history_tx: Option<history::HistoryTx>,
+    history_tx: Option<text_editing::UndoStackTx<String>>,
+    /// The selection range at the point when `Edit` was created.
+    orig_sel_range: [usize; 2],
 }
 
 impl Edit<'_> {
@@ -1273,19 +1677,44 @@ impl Drop for Edit<'_> {
     fn drop(&mut self) {
         let state = &mut *self.state; // enable split borrow
 
+        let mut changed = state.sel_range != self.orig_sel_range;
+
         if let Some(history_tx) = self.history_tx.take() {
             history_tx.finish(&mut state.history, &state.text);
 
             // `text` might have changed, so raise `changed`
             // (False positives are positive because of `set_composition_range`)
             pend_raise_change(self.inner);
+
+            changed = true;
         }
 
-        if self
-            .state
-            .scroll_cursor_into_view(self.view.as_ref(), &self.inner.style_elem)
+        // Don't scroll if nothing has changed. Otherwise, read-only accesses
+        // would revert the scroll position changed by the user.
+        if changed
+            && self
+                .state
+                .scroll_cursor_into_view(self.view.as_ref(), &self.inner.style_elem)
         {
             self.state.canvas.pend_draw(self.view.as_ref());
+
+            // The text has moved, so the IME candidate window must be
+            // repositioned. We can't do it here because the document is still
+            // locked and `state` is borrowed.
+            if (self.inner.tictx_event_mask.get())
+                .contains(pal::TextInputCtxEventFlags::LAYOUT_CHANGE)
+            {
+                let inner_weak = RcBorrow::to_weak(self.inner);
+                self.inner.wm.invoke_on_update(move |wm| {
+                    // The text input context might have been removed by now
+                    if let Some(inner) = inner_weak.upgrade() {
+                        let tictx = inner.state.borrow().tictx.clone();
+                        if let Some(tictx) = tictx {
+                            wm.text_input_ctx_on_layout_change(&tictx);
+                        }
+                    }
+                });
+            }
         }
     }
 }
@@ -1437,7 +1866,6 @@ impl pal::iface::TextInputCtxEdit<pal::Wm> for Edit<'_> {
         // for a given string range is known)
         let run_i: usize = run_i.unwrap_or_else(|| {
             // Find the line contianing `range.start`.
-            // (Note: `EntryCore` is supposed to be a single-line input widget)
             let line = text_layout.line_from_index(range.start);
             let line_end = text_layout.line_index_range(line).end;
 
@@ -1463,6 +1891,39 @@ impl pal::iface::TextInputCtxEdit<pal::Wm> for Edit<'_> {
     }
 }
 
+/// Call `f` for each run in `range`, limiting the search to `lines`. `f`
+/// receives a run and the vertical bounds of the line containing the run.
+fn for_each_run_in_range(
+    text_layout: &pal::TextLayout,
+    range: Range<usize>,
+    lines: Range<usize>,
+    mut f: impl FnMut(&pal::RunMetrics, &Range<f32>),
+) {
+    let first_line = text_layout.line_from_index(range.start).max(lines.start);
+    let last_line = text_layout
+        .line_from_index(range.end)
+        .min(lines.end.saturating_sub(1));
+
+    for line in first_line..=last_line {
+        // `run_metrics_of_range` doesn't accept a range spanning across
+        // multiple lines, so clip `range` by the line
+        let line_range = text_layout.line_index_range(line);
+        let start = range.start.max(line_range.start);
+        let end = range.end.min(line_range.end);
+        if start >= end {
+            continue;
+        }
+
+        let vert_bounds = text_layout.line_vertical_bounds(line);
+        let runs = text_layout.run_metrics_of_range(start..end);
+        log::trace!("runs({:?}) = {:?}", start..end, runs);
+
+        for run in runs.iter() {
+            f(run, &vert_bounds);
+        }
+    }
+}
+
 bitflags::bitflags! {
     struct UpdateStateFlags: u8 {
         /// The selection might have changed.
@@ -1566,14 +2027,80 @@ fn pend_raise_change(inner: RcBorrow<'_, Inner>) {
         if let Some(inner) = inner_weak.upgrade() {
             inner.pending_change_handler.set(false);
 
-            let handlers = inner.change_handlers.borrow();
-            for handler in handlers.iter() {
-                handler(wm);
-            }
+            inner.change_handlers.raise(wm);
         }
     });
 }
 
+/// Implements [`ScrollModel`] for the multi-line mode.
+struct EntryCoreScrollModel {
+    view: HView,
+    inner: Rc<Inner>,
+    orig_scroll: f32,
+}
+
+impl EntryCoreScrollModel {
+    fn new(view: HView, inner: Rc<Inner>) -> Self {
+        let orig_scroll = inner.state.borrow().scroll;
+        Self {
+            view,
+            inner,
+            orig_scroll,
+        }
+    }
+
+    fn set_scroll(&self, value: f32) {
+        let mut state = self.inner.state.borrow_mut();
+        let max_scroll = state.max_scroll(self.view.as_ref(), &self.inner.style_elem);
+        let value = value.fmax(0.0).fmin(max_scroll);
+
+        if value == state.scroll {
+            return;
+        }
+
+        state.scroll = value;
+        state.caret = None;
+        state.canvas.pend_draw(self.view.as_ref());
+
+        // Unborrow `state` before calling `text_input_ctx_on_layout_change`
+        let tictx = state.tictx.clone();
+        drop(state);
+
+        if (self.inner.tictx_event_mask.get()).contains(pal::TextInputCtxEventFlags::LAYOUT_CHANGE)
+        {
+            if let Some(tictx) = tictx {
+                self.inner.wm.text_input_ctx_on_layout_change(&tictx);
+            }
+        }
+    }
+}
+
+impl ScrollModel for EntryCoreScrollModel {
+    fn bounds(&mut self) -> Box2<f64> {
+        let mut state = self.inner.state.borrow_mut();
+        let max_scroll = state.max_scroll(self.view.as_ref(), &self.inner.style_elem);
+        box2! { min: [0.0, 0.0], max: [0.0, max_scroll as f64] }
+    }
+
+    fn pos(&mut self) -> Point2<f64> {
+        Point2::new(0.0, self.inner.state.borrow().scroll as f64)
+    }
+
+    fn set_pos(&mut self, value: Point2<f64>) {
+        self.set_scroll(value.y as f32);
+    }
+
+    fn line_size(&mut self) -> [f64; 2] {
+        let mut state = self.inner.state.borrow_mut();
+        let line_height = state.ensure_text_layout(&self.inner.style_elem).line_height;
+        [line_height as f64; 2]
+    }
+
+    fn cancel(&mut self) {
+        self.set_scroll(self.orig_scroll);
+    }
+}
+
 struct EntryCoreDragListener {
     view: HView,
     inner: Rc<Inner>,
@@ -1604,14 +2131,21 @@ impl EntryCoreDragListener {
 }
 
 impl MouseDragListener for EntryCoreDragListener {
-    fn mouse_down(&self, _: pal::Wm, hview: HViewRef<'_>, loc: Point2<f32>, _button: u8) {
+    fn mouse_down(
+        &self,
+        _: pal::Wm,
+        hview: HViewRef<'_>,
+        loc: Point2<f32>,
+        _button: u8,
+        _click_count: u32,
+    ) {
         self.update_selection(|state| {
             if let Some(text_layout_info) = &state.text_layout_info {
                 let i = text_layout_info.cursor_index_from_global_point(
                     hview,
                     state.scroll,
                     &self.inner.style_elem,
-                    loc.x,
+                    loc,
                 );
                 state.sel_range = [i, i];
             }
@@ -1625,7 +2159,7 @@ impl MouseDragListener for EntryCoreDragListener {
                     hview,
                     state.scroll,
                     &self.inner.style_elem,
-                    loc.x,
+                    loc,
                 );
                 state.sel_range[1] = i;
             }
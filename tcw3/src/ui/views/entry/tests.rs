@@ -4,10 +4,10 @@ use crate::{
     ui::{
         layouts::{EmptyLayout, TableLayout},
         theming::Manager,
-        views::Spacer,
+        views::{Spacer, TextArea},
         AlignFlags,
     },
-    uicore::{HView, HWnd, SizeTraits, ViewFlags},
+    uicore::{actions, ActionStatus, HView, HWnd, SizeTraits, ViewFlags},
 };
 use cggeom::prelude::*;
 use enclose::enc;
@@ -166,3 +166,172 @@ fn set_text(twm: &dyn TestingWm) {
     // .. and a `changed` event should be generated
     assert_eq!(changed_events.borrow()[..], ["hello", "world"][..]);
 }
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn clipboard(twm: &dyn TestingWm) {
+    let TestWithOneEntry {
+        wm,
+        entry,
+        hwnd: _hwnd,
+        pal_hwnd,
+        changed_events,
+        ..
+    } = init_test_with_one_entry(twm);
+
+    // Focus the text field by clicking it
+    let bounds = entry.view_ref().global_frame();
+    simulate_click(twm, &pal_hwnd, bounds.min.average2(&bounds.min));
+
+    // Type something and select a part of it
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        edit.replace(0..0, "hello world");
+        edit.set_selected_range(0..5);
+    }
+    twm.step_unsend();
+
+    // Copy the selection
+    twm.raise_perform_action(&pal_hwnd, actions::COPY);
+    twm.step_unsend();
+    assert_eq!(wm.clipboard_text().as_deref(), Some("hello"));
+    assert_eq!(entry.text(), "hello world");
+
+    // Cut the selection
+    twm.raise_perform_action(&pal_hwnd, actions::CUT);
+    twm.step_unsend();
+    assert_eq!(wm.clipboard_text().as_deref(), Some("hello"));
+    assert_eq!(entry.text(), " world");
+
+    // Paste a multi-line text. The line breaks should be replaced with spaces.
+    wm.set_clipboard_text("big\nbright");
+    assert!(twm
+        .raise_validate_action(&pal_hwnd, actions::PASTE)
+        .contains(ActionStatus::VALID | ActionStatus::ENABLED));
+    twm.raise_perform_action(&pal_hwnd, actions::PASTE);
+    twm.step_unsend();
+    assert_eq!(entry.text(), "big bright world");
+
+    assert_eq!(
+        changed_events.borrow()[..],
+        ["hello world", " world", "big bright world"][..]
+    );
+
+    // The paste operation can be undone
+    twm.raise_perform_action(&pal_hwnd, actions::UNDO);
+    twm.step_unsend();
+    assert_eq!(entry.text(), " world");
+}
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn undo_group(twm: &dyn TestingWm) {
+    let TestWithOneEntry {
+        entry,
+        hwnd: _hwnd,
+        pal_hwnd,
+        ..
+    } = init_test_with_one_entry(twm);
+
+    entry.set_text("start");
+    twm.step_unsend();
+
+    // Focus the text field by clicking it
+    let bounds = entry.view_ref().global_frame();
+    simulate_click(twm, &pal_hwnd, bounds.min.average2(&bounds.min));
+
+    // Type something and cut a part of the text in a single group
+    entry.core().begin_undo_group();
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        edit.replace(5..5, " one");
+        edit.set_selected_range(0..5);
+    }
+    twm.step_unsend();
+    twm.raise_perform_action(&pal_hwnd, actions::CUT);
+    twm.step_unsend();
+    entry.core().end_undo_group();
+    assert_eq!(entry.text(), " one");
+
+    // Both modifications are undone at once
+    twm.raise_perform_action(&pal_hwnd, actions::UNDO);
+    twm.step_unsend();
+    assert_eq!(entry.text(), "start");
+
+    twm.raise_perform_action(&pal_hwnd, actions::REDO);
+    twm.step_unsend();
+    assert_eq!(entry.text(), " one");
+}
+
+#[use_testing_wm(testing = "crate::testing")]
+#[test]
+fn multiline_edit(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+
+    let style_manager = Manager::global(wm);
+
+    let text_area = TextArea::new(wm, style_manager);
+
+    let wnd = HWnd::new(wm);
+    wnd.content_view().set_layout(TableLayout::stack_vert(vec![
+        (text_area.view(), AlignFlags::JUSTIFY),
+        (
+            Spacer::new().with_min([100.0, 100.0]).into_view(),
+            AlignFlags::JUSTIFY,
+        ),
+    ]));
+    wnd.set_visibility(true);
+
+    twm.step_unsend();
+
+    // Focus the window
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    twm.set_wnd_focused(&pal_hwnd, true);
+    twm.step_unsend();
+
+    // Focus the text field by clicking it
+    let bounds = text_area.view_ref().global_frame();
+    simulate_click(twm, &pal_hwnd, bounds.min.average2(&bounds.min));
+
+    let sel_range = || text_area.core().inner.state.borrow().sel_range;
+
+    // Type something
+    {
+        let mut edit = twm.raise_edit(&twm.expect_unique_active_text_input_ctx().unwrap(), true);
+        edit.replace(0..0, "hello\nworld");
+        edit.set_selected_range(6..6);
+    }
+    twm.step_unsend();
+    assert_eq!(text_area.text(), "hello\nworld");
+
+    // Move to the first line. The cursor is at the start of the second line,
+    // so it should land on the start of the first line regardless of the
+    // glyph widths.
+    twm.raise_perform_action(&pal_hwnd, actions::MOVE_UP);
+    twm.step_unsend();
+    assert_eq!(sel_range(), [0, 0]);
+
+    // Move to the end of the first line. The cursor should be placed before
+    // the line break.
+    twm.raise_perform_action(&pal_hwnd, actions::MOVE_END_OF_LINE);
+    twm.step_unsend();
+    assert_eq!(sel_range(), [5, 5]);
+
+    // Insert a line break
+    assert!(twm
+        .raise_validate_action(&pal_hwnd, actions::INSERT_PARAGRAPH_BREAK)
+        .contains(ActionStatus::VALID | ActionStatus::ENABLED));
+    twm.raise_perform_action(&pal_hwnd, actions::INSERT_PARAGRAPH_BREAK);
+    twm.step_unsend();
+    assert_eq!(text_area.text(), "hello\n\nworld");
+    assert_eq!(sel_range(), [6, 6]);
+
+    // Move to the end of the document
+    twm.raise_perform_action(&pal_hwnd, actions::MOVE_DOWN);
+    twm.step_unsend();
+    twm.raise_perform_action(&pal_hwnd, actions::MOVE_DOWN);
+    twm.step_unsend();
+    assert_eq!(sel_range(), [12, 12]);
+}
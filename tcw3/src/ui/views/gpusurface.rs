@@ -0,0 +1,385 @@
+//! Implements a widget for rendering with a GPU API.
+use cgmath::Vector2;
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::Rc,
+    time::Instant,
+};
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::{
+        layouts::EmptyLayout,
+        theming::{HElem, Widget},
+    },
+    uicore::{HView, HViewRef, HWndRef, SizeTraits, Sub, UpdateCtx, ViewFlags, ViewListener},
+};
+
+type RenderFn = Box<dyn FnMut(&mut FrameCtx<'_>)>;
+
+/// A widget whose contents are rendered directly into a swapchain by a
+/// caller-supplied render function.
+///
+/// The backing layer's swapchain is sized to the view's frame in physical
+/// pixels. The render function is called from the window's frame clock (see
+/// [`HWndRef::invoke_on_next_frame`]) when the view is mounted or resized,
+/// when [`request_frame`] is called, or every frame while
+/// [`set_continuous`]`(true)` is in effect.
+///
+/// If the backend doesn't support swapchains ([`Wm::set_layer_swapchain`]
+/// returns `false`), the view displays nothing and the render function is
+/// never called.
+///
+/// [`HWndRef::invoke_on_next_frame`]: crate::uicore::HWndRef::invoke_on_next_frame
+/// [`request_frame`]: GpuSurfaceView::request_frame
+/// [`set_continuous`]: GpuSurfaceView::set_continuous
+/// [`Wm::set_layer_swapchain`]: crate::pal::iface::Wm::set_layer_swapchain
+#[derive(Debug)]
+pub struct GpuSurfaceView {
+    view: HView,
+    inner: Rc<Inner>,
+}
+
+/// The context passed to a [`GpuSurfaceView`]'s render function.
+#[derive(Debug)]
+pub struct FrameCtx<'a> {
+    pub wm: pal::Wm,
+    /// The swapchain buffer to render into. Its size is the view's size
+    /// measured in physical pixels.
+    pub target: pal::SwapchainTarget<'a>,
+    /// The size of the view measured in points (virtual pixels).
+    pub size: Vector2<f32>,
+    /// The DPI scaling ratio.
+    pub dpi_scale: f32,
+    /// The time of the frame, measured by [`Wm::now`].
+    ///
+    /// [`Wm::now`]: crate::pal::iface::Wm::now
+    pub time: Instant,
+}
+
+struct Inner {
+    render: RefCell<RenderFn>,
+    continuous: Cell<bool>,
+    state: RefCell<Option<MountState>>,
+}
+
+struct MountState {
+    layer: pal::HLayer,
+    sub: Sub,
+    /// The current swapchain size, or `None` if it hasn't been created yet.
+    swapchain_size: Option<[u32; 2]>,
+    /// `false` if the backend doesn't support swapchains.
+    supported: bool,
+    /// `true` if a call to `render_frame` is enqueued.
+    frame_pending: bool,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("render", &())
+            .field("continuous", &self.continuous)
+            .finish()
+    }
+}
+
+impl Default for GpuSurfaceView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GpuSurfaceView {
+    /// Construct a `GpuSurfaceView`.
+    pub fn new() -> Self {
+        let inner = Rc::new(Inner {
+            render: RefCell::new(Box::new(|_| {})),
+            continuous: Cell::new(false),
+            state: RefCell::new(None),
+        });
+
+        let view = HView::new(ViewFlags::default());
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+        view.set_listener(GpuSurfaceViewListener {
+            inner: Rc::clone(&inner),
+        });
+
+        Self { view, inner }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.view.as_ref()
+    }
+
+    /// Set the function used to render the contents, and request a new frame.
+    ///
+    /// The function must fill the entirety of `ctx.target`; its initial
+    /// contents are unspecified. The function must not call methods of this
+    /// `GpuSurfaceView`.
+    pub fn set_render(&self, render: impl FnMut(&mut FrameCtx<'_>) + 'static) {
+        *self.inner.render.borrow_mut() = Box::new(render);
+        self.request_frame();
+    }
+
+    /// Set the size traits of the widget.
+    ///
+    /// It defaults to `SizeTraits::default()`.
+    pub fn set_size_traits(&self, size_traits: SizeTraits) {
+        self.view.set_layout(EmptyLayout::new(size_traits));
+    }
+
+    /// Set whether the render function should be called every frame.
+    ///
+    /// It defaults to `false`.
+    pub fn set_continuous(&self, continuous: bool) {
+        self.inner.continuous.set(continuous);
+        if continuous {
+            self.request_frame();
+        }
+    }
+
+    /// Get a flag indicating whether the render function is called every
+    /// frame.
+    pub fn continuous(&self) -> bool {
+        self.inner.continuous.get()
+    }
+
+    /// Request the render function to be called on the next frame.
+    pub fn request_frame(&self) {
+        Inner::request_frame(&self.inner, self.view.as_ref());
+    }
+}
+
+impl Widget for GpuSurfaceView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        None
+    }
+}
+
+impl Inner {
+    fn request_frame(this: &Rc<Self>, view: HViewRef<'_>) {
+        let mut state = this.state.borrow_mut();
+        let state = if let Some(state) = &mut *state {
+            state
+        } else {
+            return;
+        };
+
+        // The first frame is requested by `update` after the swapchain is
+        // created
+        if state.frame_pending || !state.supported || state.swapchain_size.is_none() {
+            return;
+        }
+        let wnd = if let Some(wnd) = view.containing_wnd() {
+            wnd
+        } else {
+            return;
+        };
+        state.frame_pending = true;
+
+        let this = Rc::clone(this);
+        let view = view.cloned();
+        wnd.invoke_on_next_frame(move |wm, wnd| {
+            Self::render_frame(&this, wm, view.as_ref(), wnd);
+        });
+    }
+
+    fn render_frame(this: &Rc<Self>, wm: pal::Wm, view: HViewRef<'_>, wnd: HWndRef<'_>) {
+        let layer = {
+            let mut state = this.state.borrow_mut();
+            let state = if let Some(state) = &mut *state {
+                state
+            } else {
+                // Unmounted since the frame was requested
+                return;
+            };
+            state.frame_pending = false;
+            state.layer.clone()
+        };
+
+        let size = view.frame().size();
+        let dpi_scale = wnd.dpi_scale();
+        let time = wm.now();
+
+        {
+            let mut render = this.render.borrow_mut();
+            wm.present_layer_swapchain(&layer, &mut |target| {
+                render(&mut FrameCtx {
+                    wm,
+                    target,
+                    size,
+                    dpi_scale,
+                    time,
+                });
+            });
+        }
+
+        // The new frame is displayed when the window is updated. Frame
+        // handlers are called before the window's views are updated, so this
+        // doesn't delay the presentation.
+        view.pend_update();
+
+        if this.continuous.get() {
+            Self::request_frame(this, view);
+        }
+    }
+}
+
+struct GpuSurfaceViewListener {
+    inner: Rc<Inner>,
+}
+
+impl ViewListener for GpuSurfaceViewListener {
+    fn mount(&self, wm: pal::Wm, view: HViewRef<'_>, wnd: HWndRef<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        assert!(state.is_none());
+
+        let layer = wm.new_layer(pal::LayerAttrs::default());
+
+        let sub = {
+            let view = view.cloned();
+            wnd.subscribe_dpi_scale_changed(Box::new(move |_, _| {
+                view.pend_update();
+            }))
+        };
+
+        *state = Some(MountState {
+            layer,
+            sub,
+            swapchain_size: None,
+            supported: true,
+            frame_pending: false,
+        });
+
+        view.pend_update();
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        let state = self.inner.state.borrow_mut().take().expect("not mounted");
+        wm.set_layer_swapchain(&state.layer, None);
+        wm.remove_layer(&state.layer);
+        state.sub.unsubscribe().unwrap();
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let resized = {
+            let mut state = self.inner.state.borrow_mut();
+            let state = state.as_mut().expect("not mounted");
+
+            let frame = view.global_frame();
+            let dpi_scale = ctx.hwnd().dpi_scale();
+
+            wm.set_layer_attr(
+                &state.layer,
+                pal::LayerAttrs {
+                    bounds: Some(frame),
+                    ..Default::default()
+                },
+            );
+
+            let size = frame.size() * dpi_scale;
+            let size = [(size.x.ceil() as u32).max(1), (size.y.ceil() as u32).max(1)];
+
+            let resized = state.supported && state.swapchain_size != Some(size);
+            if resized {
+                state.supported = wm.set_layer_swapchain(&state.layer, Some(size));
+                state.swapchain_size = Some(size);
+            }
+
+            if ctx.layers().len() != 1 {
+                ctx.set_layers(vec![state.layer.clone()]);
+            }
+
+            resized && state.supported
+        };
+
+        // The swapchain's contents are undefined after resizing
+        if resized {
+            Inner::request_frame(&self.inner, view);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pal::testing::wmapi::WndSnapshot,
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+    use std::cell::Cell;
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn render_on_resize_and_request(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let view = GpuSurfaceView::new();
+
+        let num_frames = Rc::new(Cell::new(0));
+        let last_size = Rc::new(Cell::new([0u32; 2]));
+        {
+            let num_frames = Rc::clone(&num_frames);
+            let last_size = Rc::clone(&last_size);
+            view.set_render(move |ctx| {
+                num_frames.set(num_frames.get() + 1);
+                last_size.set(ctx.target.size());
+
+                // Fill the buffer with opaque white
+                if let pal::SwapchainTarget::Memory { data, .. } = &mut ctx.target {
+                    for x in data.iter_mut() {
+                        *x = 0xff;
+                    }
+                }
+            });
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(view.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        twm.set_wnd_size(&pal_hwnd, [100, 50]);
+        twm.step_unsend();
+
+        let count = num_frames.get();
+        assert!(count >= 1);
+        assert_eq!(last_size.get(), [100, 50]);
+
+        let mut snapshot = WndSnapshot::new();
+        twm.read_wnd_snapshot(&pal_hwnd, &mut snapshot);
+        let [w, h] = snapshot.size;
+        assert_eq!(
+            snapshot.data[(h / 2) * snapshot.stride + (w / 2) * 4 + 1],
+            0xff
+        );
+
+        // Nothing to render
+        twm.step_unsend();
+        assert_eq!(num_frames.get(), count);
+
+        // Explicit request
+        view.request_frame();
+        twm.step_unsend();
+        assert_eq!(num_frames.get(), count + 1);
+    }
+}
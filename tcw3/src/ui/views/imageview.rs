@@ -0,0 +1,439 @@
+//! Implements a widget displaying an image.
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{Point2, Vector2};
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    images::{BitmapImg, Bmp, HImg},
+    pal,
+    pal::prelude::*,
+    ui::{
+        layouts::EmptyLayout,
+        theming::{HElem, Widget},
+    },
+    uicore::{HView, HViewRef, HWndRef, SizeTraits, Sub, UpdateCtx, ViewFlags, ViewListener},
+};
+
+/// The maximum number of tiles created in [`ScaleMode::Tile`]. Tiles beyond
+/// this limit are not displayed.
+const MAX_TILES: usize = 1024;
+
+/// Specifies how [`ImageView`] fits an image into its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScaleMode {
+    /// Scale the image uniformly so that it fits within the bounds. The image
+    /// is centered.
+    Fit,
+    /// Scale the image uniformly so that it covers the bounds. The image is
+    /// centered, and the parts outside the bounds are clipped.
+    Fill,
+    /// Display the image at its intrinsic size. The image is centered, and the
+    /// parts outside the bounds are clipped.
+    Center,
+    /// Repeat the image at its intrinsic size, starting from the upper-left
+    /// corner.
+    Tile,
+}
+
+impl Default for ScaleMode {
+    fn default() -> Self {
+        ScaleMode::Fit
+    }
+}
+
+/// A widget displaying an image.
+///
+/// The image is provided as [`HImg`] and rasterized for the DPI scale of the
+/// containing window. The widget's preferred size is set to the intrinsic size
+/// of the image, which is the size of the rasterized bitmap divided by its
+/// actual DPI scale.
+#[derive(Debug)]
+pub struct ImageView {
+    shared: Rc<Shared>,
+}
+
+struct Shared {
+    wm: pal::Wm,
+    view: HView,
+    img: RefCell<Option<HImg>>,
+    scale_mode: Cell<ScaleMode>,
+    /// The bitmap created from `img` for the current DPI scale.
+    bmp: RefCell<Option<Bmp>>,
+    /// The DPI scale used to create `bmp`.
+    dpi_scale: Cell<f32>,
+}
+
+impl fmt::Debug for Shared {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Shared")
+            .field("view", &self.view)
+            .field("img", &self.img)
+            .field("scale_mode", &self.scale_mode)
+            .field("bmp", &self.bmp)
+            .field("dpi_scale", &self.dpi_scale)
+            .finish()
+    }
+}
+
+impl ImageView {
+    /// Construct an `ImageView` displaying no image.
+    pub fn new(wm: pal::Wm) -> Self {
+        let shared = Rc::new(Shared {
+            wm,
+            view: HView::new(ViewFlags::default()),
+            img: RefCell::new(None),
+            scale_mode: Cell::new(ScaleMode::default()),
+            bmp: RefCell::new(None),
+            dpi_scale: Cell::new(1.0),
+        });
+
+        shared.view.set_listener(IvListener {
+            shared: Rc::downgrade(&shared),
+            layers: RefCell::new(None),
+        });
+
+        shared.update_layout();
+
+        Self { shared }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.shared.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.shared.view.as_ref()
+    }
+
+    /// Set the displayed image.
+    pub fn set_image(&self, img: Option<HImg>) {
+        self.shared.img.replace(img);
+        self.shared.refresh_bmp();
+    }
+
+    /// Set the displayed image to a raw bitmap. `dpi_scale` specifies the
+    /// ratio of the bitmap's pixel size to its intrinsic size.
+    ///
+    /// This is a shorthand for `set_image(Some(BitmapImg::new(..).into_hbmp()))`.
+    pub fn set_bitmap(&self, bitmap: pal::Bitmap, dpi_scale: f32) {
+        self.set_image(Some(BitmapImg::new(bitmap, dpi_scale).into_hbmp()));
+    }
+
+    /// Get the displayed image.
+    pub fn image(&self) -> Option<HImg> {
+        self.shared.img.borrow().clone()
+    }
+
+    /// Set the scaling mode. Defaults to `ScaleMode::Fit`.
+    pub fn set_scale_mode(&self, mode: ScaleMode) {
+        if self.shared.scale_mode.get() != mode {
+            self.shared.scale_mode.set(mode);
+            self.shared.view.pend_update();
+        }
+    }
+
+    /// Get the scaling mode.
+    pub fn scale_mode(&self) -> ScaleMode {
+        self.shared.scale_mode.get()
+    }
+
+    /// Get the intrinsic size of the displayed image. Returns `(0, 0)` if
+    /// there is no image.
+    pub fn intrinsic_size(&self) -> Vector2<f32> {
+        self.shared.intrinsic_size()
+    }
+}
+
+impl Widget for ImageView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        None
+    }
+}
+
+impl Shared {
+    fn intrinsic_size(&self) -> Vector2<f32> {
+        if let Some((bitmap, scale)) = &*self.bmp.borrow() {
+            let [w, h] = bitmap.size();
+            Vector2::new(w as f32 / scale, h as f32 / scale)
+        } else {
+            Vector2::new(0.0, 0.0)
+        }
+    }
+
+    /// Recreate `bmp` for the current DPI scale and update the layout if the
+    /// intrinsic size has changed.
+    fn refresh_bmp(&self) {
+        let old_size = self.intrinsic_size();
+
+        let bmp =
+            (self.img.borrow().as_ref()).map(|img| img.new_bmp(self.wm, self.dpi_scale.get()));
+        self.bmp.replace(bmp);
+
+        if self.intrinsic_size() != old_size {
+            self.update_layout();
+        }
+
+        self.view.pend_update();
+    }
+
+    fn update_layout(&self) {
+        self.view.set_layout(EmptyLayout::new(SizeTraits {
+            preferred: self.intrinsic_size(),
+            ..SizeTraits::default()
+        }));
+    }
+}
+
+/// Calculate the rectangles in which the image is drawn.
+fn image_rects(mode: ScaleMode, bounds: Box2<f32>, img_size: Vector2<f32>) -> Vec<Box2<f32>> {
+    if img_size.x <= 0.0 || img_size.y <= 0.0 || bounds.is_empty() {
+        return Vec::new();
+    }
+
+    let size = bounds.size();
+    let center = bounds.min + size * 0.5;
+
+    let centered = |s: Vector2<f32>| {
+        let min = center - s * 0.5;
+        box2! { min: min, max: min + s }
+    };
+
+    match mode {
+        ScaleMode::Fit => {
+            let scale = (size.x / img_size.x).min(size.y / img_size.y);
+            vec![centered(img_size * scale)]
+        }
+        ScaleMode::Fill => {
+            let scale = (size.x / img_size.x).max(size.y / img_size.y);
+            vec![centered(img_size * scale)]
+        }
+        ScaleMode::Center => vec![centered(img_size)],
+        ScaleMode::Tile => {
+            let num_x = (size.x / img_size.x).ceil() as usize;
+            let num_y = (size.y / img_size.y).ceil() as usize;
+
+            (0..num_y)
+                .flat_map(|y| (0..num_x).map(move |x| (x, y)))
+                .take(MAX_TILES)
+                .map(|(x, y)| {
+                    let min = Point2::new(
+                        bounds.min.x + img_size.x * x as f32,
+                        bounds.min.y + img_size.y * y as f32,
+                    );
+                    box2! { min: min, max: min + img_size }
+                })
+                .collect()
+        }
+    }
+}
+
+struct IvListener {
+    shared: Weak<Shared>,
+    layers: RefCell<Option<IvLayers>>,
+}
+
+struct IvLayers {
+    /// Clips the image to the view's bounds.
+    clip: pal::HLayer,
+    /// The layers displaying the image. There are more than one layer only in
+    /// `ScaleMode::Tile`.
+    img: Vec<pal::HLayer>,
+    sub: Sub,
+}
+
+impl ViewListener for IvListener {
+    fn mount(&self, wm: pal::Wm, _: HViewRef<'_>, wnd: HWndRef<'_>) {
+        let shared = if let Some(shared) = self.shared.upgrade() {
+            shared
+        } else {
+            return;
+        };
+
+        // Rasterize the image for the window's DPI scale, and do so again
+        // whenever the DPI scale changes
+        let sub = {
+            let shared = self.shared.clone();
+            wnd.subscribe_dpi_scale_changed(Box::new(move |_, hwnd| {
+                if let Some(shared) = shared.upgrade() {
+                    shared.dpi_scale.set(hwnd.dpi_scale());
+                    shared.refresh_bmp();
+                }
+            }))
+        };
+
+        if shared.dpi_scale.get() != wnd.dpi_scale() {
+            shared.dpi_scale.set(wnd.dpi_scale());
+            shared.refresh_bmp();
+        }
+
+        let clip = wm.new_layer(pal::LayerAttrs {
+            flags: Some(pal::LayerFlags::MASK_TO_BOUNDS),
+            ..pal::LayerAttrs::default()
+        });
+
+        let old_layers = self.layers.replace(Some(IvLayers {
+            clip,
+            img: Vec::new(),
+            sub,
+        }));
+        assert!(old_layers.is_none());
+
+        shared.view.pend_update();
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if let Some(layers) = self.layers.replace(None) {
+            wm.remove_layer(&layers.clip);
+            for layer in layers.img.iter() {
+                wm.remove_layer(layer);
+            }
+            layers.sub.unsubscribe().unwrap();
+        }
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let shared = if let Some(shared) = self.shared.upgrade() {
+            shared
+        } else {
+            return;
+        };
+
+        let mut layers = self.layers.borrow_mut();
+        let layers = layers.as_mut().expect("not mounted");
+
+        let frame = view.global_frame();
+        let bmp = shared.bmp.borrow();
+
+        let rects = if bmp.is_some() {
+            image_rects(shared.scale_mode.get(), frame, shared.intrinsic_size())
+        } else {
+            Vec::new()
+        };
+
+        // Adjust the layer count
+        while layers.img.len() > rects.len() {
+            wm.remove_layer(&layers.img.pop().unwrap());
+        }
+        layers
+            .img
+            .resize_with(rects.len(), || wm.new_layer(pal::LayerAttrs::default()));
+
+        for (layer, rect) in layers.img.iter().zip(rects.iter()) {
+            let (bitmap, _) = bmp.as_ref().unwrap();
+            wm.set_layer_attr(
+                layer,
+                pal::LayerAttrs {
+                    bounds: Some(*rect),
+                    contents: Some(Some(bitmap.clone().into())),
+                    ..pal::LayerAttrs::default()
+                },
+            );
+        }
+
+        wm.set_layer_attr(
+            &layers.clip,
+            pal::LayerAttrs {
+                bounds: Some(frame),
+                sublayers: Some(layers.img.clone()),
+                ..pal::LayerAttrs::default()
+            },
+        );
+
+        if ctx.layers().len() != 1 {
+            ctx.set_layers(vec![layers.clip.clone()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        uicore::HWnd,
+    };
+    use try_match::try_match;
+
+    fn bounds() -> Box2<f32> {
+        box2! { min: [10.0, 20.0], max: [50.0, 40.0] }
+    }
+
+    #[test]
+    fn rects_fit() {
+        let rects = image_rects(ScaleMode::Fit, bounds(), Vector2::new(10.0, 10.0));
+        assert_eq!(rects, vec![box2! { min: [20.0, 20.0], max: [40.0, 40.0] }]);
+    }
+
+    #[test]
+    fn rects_fill() {
+        let rects = image_rects(ScaleMode::Fill, bounds(), Vector2::new(10.0, 10.0));
+        assert_eq!(rects, vec![box2! { min: [10.0, 10.0], max: [50.0, 50.0] }]);
+    }
+
+    #[test]
+    fn rects_center() {
+        let rects = image_rects(ScaleMode::Center, bounds(), Vector2::new(10.0, 30.0));
+        assert_eq!(rects, vec![box2! { min: [25.0, 15.0], max: [35.0, 45.0] }]);
+    }
+
+    #[test]
+    fn rects_tile() {
+        let rects = image_rects(ScaleMode::Tile, bounds(), Vector2::new(15.0, 15.0));
+        assert_eq!(rects.len(), 6);
+        assert_eq!(rects[0], box2! { min: [10.0, 20.0], max: [25.0, 35.0] });
+        assert_eq!(rects[5], box2! { min: [40.0, 35.0], max: [55.0, 50.0] });
+    }
+
+    #[test]
+    fn rects_empty_image() {
+        for &mode in &[
+            ScaleMode::Fit,
+            ScaleMode::Fill,
+            ScaleMode::Center,
+            ScaleMode::Tile,
+        ] {
+            assert!(image_rects(mode, bounds(), Vector2::new(0.0, 10.0)).is_empty());
+        }
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn size_follows_image(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let iv = ImageView::new(wm);
+        iv.set_scale_mode(ScaleMode::Tile);
+        iv.set_bitmap(pal::BitmapBuilder::new([40, 20]).into_bitmap(), 2.0);
+        assert_eq!(iv.intrinsic_size(), Vector2::new(20.0, 10.0));
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(crate::ui::layouts::FillLayout::new(iv.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        assert_eq!(twm.wnd_attrs(&pal_hwnd).unwrap().size, [20, 10]);
+
+        // Resizing exercises the tile layers
+        twm.set_wnd_size(&pal_hwnd, [100, 100]);
+        twm.step_unsend();
+
+        iv.set_image(None);
+        assert_eq!(iv.intrinsic_size(), Vector2::new(0.0, 0.0));
+        twm.step_unsend();
+    }
+}
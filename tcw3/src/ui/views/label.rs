@@ -1,4 +1,4 @@
-use cggeom::{prelude::*, Box2};
+use cggeom::prelude::*;
 use cgmath::{Point2, Vector2};
 use momo::momo;
 use std::{cell::RefCell, rc::Rc};
@@ -7,6 +7,7 @@ use crate::{
     pal,
     pal::prelude::*,
     ui::mixins::CanvasMixin,
+    ui::text::{shaped_text, truncated_text, wrapped_text, ShapedText, TruncationMode, ELLIPSIS},
     ui::theming::{ClassSet, Elem, GetPropValue, HElem, Manager, PropKindFlags, Widget},
     uicore::{
         HView, HViewRef, HWndRef, Layout, LayoutCtx, SizeTraits, UpdateCtx, ViewFlags, ViewListener,
@@ -29,15 +30,41 @@ struct Inner {
 #[derive(Debug)]
 struct State {
     text: String,
-    text_layout_info: Option<TextLayoutInfo>,
+    line_break_mode: LineBreakMode,
+    text_layout_info: Option<Rc<ShapedText>>,
+    /// The `ShapedText` drawn most recently. This differs from
+    /// `text_layout_info` if the text is truncated to fit in the frame.
+    displayed_text_layout: Option<Rc<ShapedText>>,
     canvas: CanvasMixin,
 }
 
-#[derive(Debug)]
-struct TextLayoutInfo {
-    text_layout: pal::TextLayout,
-    layout_bounds: Box2<f32>,
-    visual_bounds: Box2<f32>,
+/// Specifies how a [`Label`] lays out a text not fitting in a single line of
+/// the available width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineBreakMode {
+    /// The label is sized to fit the text, which is laid out without
+    /// wrapping. The text overflows if the label is given a narrower frame.
+    Clip,
+    /// The text is laid out in a single line. If it doesn't fit in the
+    /// label's frame, characters at the end are replaced with an ellipsis.
+    TruncateTail,
+    /// The text is laid out in a single line. If it doesn't fit in the
+    /// label's frame, characters in the middle are replaced with an ellipsis.
+    TruncateMiddle,
+    /// The text is wrapped at `width`. If it has more than `max_lines`
+    /// lines, the rest is replaced with an ellipsis.
+    ///
+    /// `max_lines` must not be zero.
+    Wrap {
+        width: f32,
+        max_lines: Option<usize>,
+    },
+}
+
+impl Default for LineBreakMode {
+    fn default() -> Self {
+        Self::Clip
+    }
 }
 
 impl Label {
@@ -50,7 +77,9 @@ impl Label {
             inner: Rc::new(Inner {
                 state: RefCell::new(State {
                     text: String::new(),
+                    line_break_mode: LineBreakMode::default(),
                     text_layout_info: None,
+                    displayed_text_layout: None,
                     canvas: CanvasMixin::new(),
                 }),
                 style_elem,
@@ -115,6 +144,30 @@ impl Label {
             .set_layout(LabelListener::new(Rc::clone(&self.inner)));
     }
 
+    /// Set the line break mode.
+    ///
+    /// It defaults to `LineBreakMode::Clip`.
+    pub fn set_line_break_mode(&self, value: LineBreakMode) {
+        {
+            let mut state = self.inner.state.borrow_mut();
+            if state.line_break_mode == value {
+                return;
+            }
+            state.line_break_mode = value;
+            state.invalidate_text_layout();
+            state.canvas.pend_draw(self.view.as_ref());
+        }
+
+        // Invalidate the layout, since the label size might be changed
+        self.view
+            .set_layout(LabelListener::new(Rc::clone(&self.inner)));
+    }
+
+    /// Get the line break mode.
+    pub fn line_break_mode(&self) -> LineBreakMode {
+        self.inner.state.borrow().line_break_mode
+    }
+
     /// Set the styling class set.
     ///
     /// It defaults to `ClassSet::LABEL`.
@@ -166,29 +219,33 @@ impl State {
         if self.text_layout_info.is_none() {
             let font_type = elem.computed_values().font();
 
-            let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
-                sys: Some(font_type),
-                ..Default::default()
-            });
-            let text_layout = pal::TextLayout::from_text(&self.text, &char_style, None);
+            let wm = pal::Wm::global();
 
-            let visual_bounds = text_layout.visual_bounds();
-            let layout_bounds = text_layout.layout_bounds();
-
-            self.text_layout_info = Some(TextLayoutInfo {
-                text_layout,
-                visual_bounds,
-                layout_bounds,
+            self.text_layout_info = Some(match self.line_break_mode {
+                LineBreakMode::Wrap { width, max_lines } => {
+                    wrapped_text(wm, &self.text, font_type, width, max_lines)
+                }
+                _ => shaped_text(wm, &self.text, font_type, None),
             });
         }
     }
 
-    /// Delete the cached `TextLayout` (if any).
+    /// Delete the reference to the `TextLayout` (if any).
     ///
     /// After calling this, you probably want to call `HView::set_layout` again
     /// because the API contract of `Layout` requires immutability.
     fn invalidate_text_layout(&mut self) {
         self.text_layout_info = None;
+        self.displayed_text_layout = None;
+    }
+
+    /// Get the truncation mode corresponding to `line_break_mode`.
+    fn truncation_mode(&self) -> Option<TruncationMode> {
+        match self.line_break_mode {
+            LineBreakMode::TruncateTail => Some(TruncationMode::Tail),
+            LineBreakMode::TruncateMiddle => Some(TruncationMode::Middle),
+            _ => None,
+        }
     }
 }
 
@@ -219,8 +276,17 @@ impl Layout for LabelListener {
             .layout_bounds
             .size();
 
+        // A truncated text can be as narrow as an ellipsis
+        let min_width = if state.truncation_mode().is_some() {
+            let font_type = self.inner.style_elem.computed_values().font();
+            let ellipsis = shaped_text(pal::Wm::global(), ELLIPSIS, font_type, None);
+            size.x.min(ellipsis.layout_bounds.size().x)
+        } else {
+            size.x
+        };
+
         SizeTraits {
-            min: size,
+            min: Vector2::new(min_width, size.y),
             max: size,
             preferred: size,
         }
@@ -257,7 +323,23 @@ impl ViewListener for LabelListener {
 
         let color = self.inner.style_elem.computed_values().fg_color();
 
-        let text_layout_info: &TextLayoutInfo = state.text_layout_info.as_ref().unwrap();
+        let mut text_layout_info = Rc::clone(state.text_layout_info.as_ref().unwrap());
+
+        if let Some(mode) = state.truncation_mode() {
+            let width = view.frame().size().x;
+            if text_layout_info.layout_bounds.size().x > width {
+                let font_type = self.inner.style_elem.computed_values().font();
+                text_layout_info = truncated_text(wm, &state.text, font_type, width, mode);
+            }
+        }
+
+        // The text might have changed without changing the visual bounds
+        if let Some(displayed) = &state.displayed_text_layout {
+            if !Rc::ptr_eq(displayed, &text_layout_info) {
+                state.canvas.pend_draw(view);
+            }
+        }
+        state.displayed_text_layout = Some(Rc::clone(&text_layout_info));
 
         state.canvas.update_layer(
             wm,
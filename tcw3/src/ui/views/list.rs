@@ -0,0 +1,467 @@
+//! Implements the list view, a virtualized scrollable widget that displays
+//! subviews in a vertical stack of rows.
+//!
+//! `ListView` is a thin layer over [`ScrollableTable`] having exactly one
+//! column, so it inherits the table view's properties. Views are only
+//! instantiated for the visible rows, rows can have different heights, and
+//! rows can be inserted, removed, or resized incrementally. See [the `table`
+//! module documentation](crate::ui::views::table) for more.
+//!
+//! [`ScrollableTable`]: crate::ui::views::ScrollableTable
+use as_any::AsAny;
+use std::{any::Any, ops::Range};
+
+use crate::{
+    ui::{
+        mixins::scrollwheel::ScrollAxisFlags,
+        theming::{ClassSet, HElem, Manager, Widget},
+        views::{
+            selection::SelectionModel,
+            table::{
+                CellCtrler, CellIdx, EditLockError, LineTy, ScrollableTable, TableEdit, TableFlags,
+                TableModelEdit, TableModelEditExt, TableModelQuery,
+            },
+        },
+    },
+    uicore::{HView, HViewRef, SizeTraits},
+};
+
+/// A trait for objects that allow [`ListView`] to query properties of a list
+/// model.
+///
+/// This is the list counterpart of [`TableModelQuery`]. The same guidelines
+/// apply to the implementors.
+///
+/// [`TableModelQuery`]: crate::ui::views::table::TableModelQuery
+pub trait ListModelQuery: AsAny + Any {
+    /// Create a subview for the specified row.
+    fn new_view(&mut self, row: u64) -> (HView, Box<dyn CellCtrler>);
+
+    /// Get the total height of the rows in the specified range. The result
+    /// may be approximate if `approx` is `true`.
+    ///
+    /// If `approx` is `false`, `range.end - range.start` must be equal to `1`.
+    fn range_height(&mut self, range: Range<u64>, approx: bool) -> f64;
+}
+
+/// The default implementation of `ListModelQuery` that produces sane default
+/// values.
+impl ListModelQuery for () {
+    fn new_view(&mut self, _row: u64) -> (HView, Box<dyn CellCtrler>) {
+        (HView::new(Default::default()), Box::new(()))
+    }
+
+    fn range_height(&mut self, range: Range<u64>, _approx: bool) -> f64 {
+        10.0 * (range.end - range.start) as f64
+    }
+}
+
+/// Presents a `ListModelQuery` as a single-column `TableModelQuery`.
+struct TableModelAdapter {
+    list_model: Box<dyn ListModelQuery>,
+}
+
+impl TableModelQuery for TableModelAdapter {
+    fn new_view(&mut self, cell: CellIdx) -> (HView, Box<dyn CellCtrler>) {
+        self.list_model.new_view(cell[1])
+    }
+
+    fn range_size(&mut self, line_ty: LineTy, range: Range<u64>, approx: bool) -> f64 {
+        match line_ty {
+            LineTy::Row => self.list_model.range_height(range, approx),
+
+            // `TableFlags::GROW_LAST_COL` expands the column to cover the region.
+            // The column needs some width for this flag to work.
+            LineTy::Col => (range.end - range.start) as f64,
+        }
+    }
+}
+
+/// A virtualized scrollable widget displaying subviews in a vertical stack of
+/// rows.
+///
+/// See [the module documentation](index.html) for more.
+#[derive(Debug)]
+pub struct ListView {
+    table: ScrollableTable,
+}
+
+impl ListView {
+    /// Construct a `ListView`.
+    pub fn new(style_manager: &'static Manager) -> Self {
+        let table = ScrollableTable::new(style_manager);
+        table.set_flags(TableFlags::GROW_LAST_COL);
+        table.set_scrollable_axes(ScrollAxisFlags::VERTICAL);
+
+        {
+            let mut edit = table.table().edit().unwrap();
+            edit.set_model(TableModelAdapter {
+                list_model: Box::new(()),
+            });
+            edit.insert(LineTy::Col, 0..1);
+        }
+
+        Self { table }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.table.view()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.table.view_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.table.style_elem()
+    }
+
+    /// Get a reference to the inner `ScrollableTable`.
+    pub fn table(&self) -> &ScrollableTable {
+        &self.table
+    }
+
+    /// Attempt to acquire a lock to update and/or examine the list model and
+    /// the scroll position.
+    ///
+    /// See [`Table::edit`] for the conditions under which this fails.
+    ///
+    /// [`Table::edit`]: crate::ui::views::table::Table::edit
+    pub fn edit(&self) -> Result<ListEdit<'_>, EditLockError> {
+        Ok(ListEdit {
+            edit: self.table.table().edit()?,
+        })
+    }
+
+    /// Set new size traits (delegated to the inner `Table`).
+    ///
+    /// Must not have an active edit (the list model must be in the unlocked
+    /// state).
+    pub fn set_size_traits(&self, value: SizeTraits) {
+        self.table.set_size_traits(value);
+    }
+
+    /// Set a selection model tracking the selected rows.
+    ///
+    /// See [`ScrollableTable::set_selection`].
+    ///
+    /// [`ScrollableTable::set_selection`]: crate::ui::views::ScrollableTable::set_selection
+    pub fn set_selection(&self, selection: Option<SelectionModel>) {
+        self.table.set_selection(selection);
+    }
+
+    /// Get the selection model set by [`set_selection`].
+    ///
+    /// [`set_selection`]: crate::ui::views::ListView::set_selection
+    pub fn selection(&self) -> Option<SelectionModel> {
+        self.table.selection()
+    }
+
+    /// Set the class set of the inner `StyledBox`.
+    ///
+    /// See [`ScrollableTable::set_class_set`].
+    ///
+    /// [`ScrollableTable::set_class_set`]: crate::ui::views::ScrollableTable::set_class_set
+    pub fn set_class_set(&self, class_set: ClassSet) {
+        self.table.set_class_set(class_set);
+    }
+}
+
+impl Widget for ListView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+/// A lock guard type for updating and/or examining the list model and the
+/// scroll position of [`ListView`].
+///
+/// When inserting rows, the application must first update `ListModelQuery` so
+/// that when [`insert`] looks at it, all new rows are already
+/// inserted at the intended position. The same goes for the other operations.
+///
+/// [`insert`]: crate::ui::views::list::ListEdit::insert
+#[derive(Debug)]
+pub struct ListEdit<'a> {
+    edit: TableEdit<'a>,
+}
+
+impl<'a> ListEdit<'a> {
+    /// Get a mutable reference to the current `ListModelQuery` object.
+    pub fn model_mut(&mut self) -> &mut dyn ListModelQuery {
+        &mut *self.model_downcast_table_adapter().list_model
+    }
+
+    /// Downcast the result of `self.model_mut()`.
+    pub fn model_downcast_mut<T: ListModelQuery>(&mut self) -> Option<&mut T> {
+        (*self.model_mut()).as_any_mut().downcast_mut()
+    }
+
+    /// Set a new `ListModelQuery` object.
+    ///
+    /// Like [`TableModelEdit::set_model_boxed`], this does not modify or
+    /// remove any rows associated with the old `ListModelQuery`.
+    ///
+    /// [`TableModelEdit::set_model_boxed`]: crate::ui::views::table::TableModelEdit::set_model_boxed
+    pub fn set_model(&mut self, new_model: impl ListModelQuery) {
+        self.model_downcast_table_adapter().list_model = Box::new(new_model);
+    }
+
+    fn model_downcast_table_adapter(&mut self) -> &mut TableModelAdapter {
+        self.edit.model_downcast_mut().unwrap()
+    }
+
+    /// State that zero or more rows were inserted at the specified range.
+    pub fn insert(&mut self, range: Range<u64>) {
+        self.edit.insert(LineTy::Row, range);
+    }
+
+    /// State that zero or more rows are going to be removed from the specified
+    /// range.
+    pub fn remove(&mut self, range: Range<u64>) {
+        self.edit.remove(LineTy::Row, range);
+    }
+
+    /// State that zero or more rows in the specified range were resized.
+    pub fn resize(&mut self, range: Range<u64>) {
+        self.edit.resize(LineTy::Row, range);
+    }
+
+    /// Instruct to re-create subviews in the specified range of rows, e.g.,
+    /// because their contents have changed.
+    pub fn renew_subviews(&mut self, range: Range<u64>) {
+        self.edit.renew_subviews(LineTy::Row, range);
+    }
+
+    /// Get the current vertical scroll position.
+    pub fn scroll_pos(&self) -> f64 {
+        self.edit.scroll_pos()[1]
+    }
+
+    /// Set the current vertical scroll position.
+    ///
+    /// The value is automatically clamped to range `0.0..scroll_limit()`.
+    pub fn set_scroll_pos(&mut self, pos: f64) {
+        self.edit.set_scroll_pos([0.0, pos]);
+    }
+
+    /// Get the upper bound of the vertical scroll position.
+    pub fn scroll_limit(&self) -> f64 {
+        self.edit.scroll_limit()[1]
+    }
+
+    /// Get a mutable reference to the underlying `TableEdit`.
+    pub fn table_edit(&mut self) -> &mut TableEdit<'a> {
+        &mut self.edit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cggeom::{box2, prelude::*};
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+    use try_match::try_match;
+
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::{layouts::AbsLayout, views::selection::SelectionMode, AlignFlags},
+        uicore::{actions, ActionStatus, HWnd},
+    };
+
+    struct CountingModel {
+        num_views: Rc<Cell<usize>>,
+    }
+
+    impl ListModelQuery for CountingModel {
+        fn new_view(&mut self, _row: u64) -> (HView, Box<dyn CellCtrler>) {
+            self.num_views.set(self.num_views.get() + 1);
+            (HView::new(Default::default()), Box::new(()))
+        }
+
+        fn range_height(&mut self, range: Range<u64>, _approx: bool) -> f64 {
+            20.0 * (range.end - range.start) as f64
+        }
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn virtualize(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let num_views = Rc::new(Cell::new(0));
+        let list = ListView::new(Manager::global(wm));
+        {
+            let mut edit = list.edit().unwrap();
+            edit.set_model(CountingModel {
+                num_views: Rc::clone(&num_views),
+            });
+            edit.insert(0..100_000);
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(AbsLayout::new(
+            SizeTraits {
+                min: [100.0; 2].into(),
+                max: [100.0; 2].into(),
+                preferred: [100.0; 2].into(),
+            },
+            Some((
+                list.view(),
+                box2! { min: [0.0, 0.0], max: [100.0, 100.0] },
+                AlignFlags::JUSTIFY,
+            )),
+        ));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        // Only the visible rows should be realized
+        assert_ne!(num_views.get(), 0);
+        assert!(num_views.get() < 20, "{} < 20", num_views.get());
+
+        {
+            let mut edit = list.edit().unwrap();
+            assert!(edit.scroll_limit() > 1_000_000.0);
+
+            edit.set_scroll_pos(50_000.0);
+            assert_eq!(edit.scroll_pos(), 50_000.0);
+
+            // Remove all rows but one
+            edit.remove(1..100_000);
+            assert_eq!(edit.scroll_limit(), 0.0);
+        }
+        twm.step_unsend();
+    }
+
+    struct SelectionModelQuery {
+        selection: SelectionModel,
+        /// The class sets of the most recently realized row views
+        class_sets: Rc<RefCell<Vec<Option<ClassSet>>>>,
+    }
+
+    impl ListModelQuery for SelectionModelQuery {
+        fn new_view(&mut self, row: u64) -> (HView, Box<dyn CellCtrler>) {
+            self.class_sets.borrow_mut()[row as usize] = Some(self.selection.class_set(row));
+            (HView::new(Default::default()), Box::new(()))
+        }
+
+        fn range_height(&mut self, range: Range<u64>, _approx: bool) -> f64 {
+            20.0 * (range.end - range.start) as f64
+        }
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn selection(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let selection = SelectionModel::new(SelectionMode::Multiple);
+        let class_sets = Rc::new(RefCell::new(vec![None; 10]));
+
+        let list = ListView::new(Manager::global(wm));
+        {
+            let mut edit = list.edit().unwrap();
+            edit.set_model(SelectionModelQuery {
+                selection: selection.clone(),
+                class_sets: Rc::clone(&class_sets),
+            });
+            edit.insert(0..10);
+        }
+        list.set_selection(Some(selection.clone()));
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(AbsLayout::new(
+            SizeTraits {
+                min: [100.0, 300.0].into(),
+                max: [100.0, 300.0].into(),
+                preferred: [100.0, 300.0].into(),
+            },
+            Some((
+                list.view(),
+                box2! { min: [0.0, 0.0], max: [100.0, 300.0] },
+                AlignFlags::JUSTIFY,
+            )),
+        ));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+
+        let is_selected_view = |row: usize| {
+            let class_set = class_sets.borrow()[row].expect("the row is not realized");
+            class_set.contains(ClassSet::SELECTED)
+        };
+
+        let click = |row: u64, modifiers: &str| {
+            if !modifiers.is_empty() {
+                twm.raise_key_down(&pal_hwnd, "windows", modifiers);
+            }
+
+            let table_frame = list.table().table().view_ref().global_frame();
+            let loc = [
+                table_frame.mid().x,
+                table_frame.min.y + 20.0 * row as f32 + 10.0,
+            ];
+            let drag = twm.raise_mouse_drag(&pal_hwnd, loc.into(), 0);
+            drag.mouse_down(loc.into(), 0);
+            drag.mouse_up(loc.into(), 0);
+
+            if !modifiers.is_empty() {
+                twm.raise_key_up(&pal_hwnd, "windows", modifiers);
+            }
+            twm.step_unsend();
+        };
+
+        // Plain click
+        click(2, "");
+        assert_eq!(selection.selected_ranges(), vec![2..3]);
+        assert!(list.view().is_focused());
+        assert!(is_selected_view(2));
+        assert!(!is_selected_view(3));
+
+        // Shift-click
+        click(4, "Shift+");
+        assert_eq!(selection.selected_ranges(), vec![2..5]);
+        assert!((2..5).all(is_selected_view));
+
+        // Ctrl-click (Command-click on macOS)
+        let toggle_modifier = if cfg!(target_os = "macos") {
+            "Super+"
+        } else {
+            "Ctrl+"
+        };
+        click(3, toggle_modifier);
+        assert_eq!(selection.selected_ranges(), vec![2..3, 4..5]);
+        assert!(!is_selected_view(3));
+
+        // Keyboard
+        assert!(twm
+            .raise_validate_action(&pal_hwnd, actions::MOVE_DOWN)
+            .contains(ActionStatus::VALID | ActionStatus::ENABLED));
+        twm.raise_perform_action(&pal_hwnd, actions::MOVE_DOWN);
+        twm.step_unsend();
+        assert_eq!(selection.selected_ranges(), vec![4..5]);
+        assert!(is_selected_view(4));
+        assert!(!is_selected_view(2));
+
+        twm.raise_perform_action(&pal_hwnd, actions::MOVE_DOWN_SELECTING);
+        twm.step_unsend();
+        assert_eq!(selection.selected_ranges(), vec![4..6]);
+        assert!(is_selected_view(5));
+
+        // Clicking the empty space clears the selection
+        click(12, "");
+        assert!(selection.is_empty());
+        assert!(!is_selected_view(5));
+    }
+}
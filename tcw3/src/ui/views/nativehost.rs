@@ -0,0 +1,210 @@
+//! Implements a widget for hosting a native child window.
+use std::{
+    cell::RefCell,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::{
+        layouts::EmptyLayout,
+        theming::{HElem, Widget},
+        EventSource,
+    },
+    uicore::{HView, HViewRef, HWndRef, SizeTraits, Sub, ViewFlags, ViewListener},
+};
+
+/// A widget that hosts a native child window, in which third-party native
+/// controls (e.g., a web view or a video surface) can be embedded.
+///
+/// The child window is created by [`Wm::new_child_wnd`] when the view is
+/// mounted and destroyed when the view is unmounted. Its bounds follow the
+/// view's frame. Note that a child window is always displayed over the other
+/// views in the same window.
+///
+/// If the backend does not support child windows, `NativeHost` behaves like
+/// an empty view.
+///
+/// [`Wm::new_child_wnd`]: crate::pal::iface::Wm::new_child_wnd
+#[derive(Debug)]
+pub struct NativeHost {
+    view: HView,
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    child_wnd: RefCell<Option<pal::HChildWnd>>,
+    change_handlers: EventSource,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("child_wnd", &self.child_wnd)
+            .field("change_handlers", &())
+            .finish()
+    }
+}
+
+impl Default for NativeHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NativeHost {
+    /// Construct a `NativeHost`.
+    pub fn new() -> Self {
+        let inner = Rc::new(Inner {
+            child_wnd: RefCell::new(None),
+            change_handlers: EventSource::new(),
+        });
+
+        let view = HView::new(ViewFlags::default());
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+        view.set_listener(NativeHostListener {
+            inner: Rc::clone(&inner),
+        });
+
+        Self { view, inner }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.view.as_ref()
+    }
+
+    /// Set the size traits of the widget.
+    ///
+    /// It defaults to `SizeTraits::default()`.
+    pub fn set_size_traits(&self, size_traits: SizeTraits) {
+        self.view.set_layout(EmptyLayout::new(size_traits));
+    }
+
+    /// Get the handle of the hosted child window.
+    ///
+    /// Returns `None` if the view is not mounted or the backend does not
+    /// support child windows.
+    pub fn child_wnd(&self) -> Option<pal::HChildWnd> {
+        self.inner.child_wnd.borrow().clone()
+    }
+
+    /// Add a function called after the child window is created or destroyed.
+    /// Use [`child_wnd`] to get the new child window.
+    ///
+    /// The function is called via `Wm::invoke`, thus allowed to modify
+    /// view hierarchy and view attributes. However, it's not allowed to call
+    /// `subscribe_child_wnd_changed` when one of the handlers is being called.
+    ///
+    /// [`child_wnd`]: NativeHost::child_wnd
+    pub fn subscribe_child_wnd_changed(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
+        self.inner.change_handlers.subscribe(cb)
+    }
+}
+
+impl Widget for NativeHost {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        None
+    }
+}
+
+struct NativeHostListener {
+    inner: Rc<Inner>,
+}
+
+impl ViewListener for NativeHostListener {
+    fn mount(&self, wm: pal::Wm, view: HViewRef<'_>, wnd: HWndRef<'_>) {
+        let pal_hwnd = wnd.pal_hwnd().unwrap();
+        let child_wnd = wm.new_child_wnd(&pal_hwnd, view.global_frame());
+
+        if child_wnd.is_some() {
+            *self.inner.child_wnd.borrow_mut() = child_wnd;
+            raise_change_handlers(wm, Rc::downgrade(&self.inner));
+        }
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if let Some(child_wnd) = self.inner.child_wnd.borrow_mut().take() {
+            wm.remove_child_wnd(&child_wnd);
+            raise_change_handlers(wm, Rc::downgrade(&self.inner));
+        }
+    }
+
+    fn position(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        if let Some(child_wnd) = &*self.inner.child_wnd.borrow() {
+            wm.set_child_wnd_bounds(child_wnd, view.global_frame());
+        }
+    }
+}
+
+fn raise_change_handlers(wm: pal::Wm, inner_weak: Weak<Inner>) {
+    wm.invoke(move |wm| {
+        if let Some(inner) = inner_weak.upgrade() {
+            inner.change_handlers.raise(wm);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+    use cggeom::box2;
+    use std::cell::Cell;
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn child_wnd_follows_view(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let host = NativeHost::new();
+
+        let num_changes = Rc::new(Cell::new(0));
+        let _sub = host.subscribe_child_wnd_changed({
+            let num_changes = Rc::clone(&num_changes);
+            Box::new(move |_| num_changes.set(num_changes.get() + 1))
+        });
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(host.view()).with_uniform_margin(10.0));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        twm.set_wnd_size(&pal_hwnd, [100, 50]);
+        twm.step_unsend();
+
+        let child_wnd = host.child_wnd().expect("child window was not created");
+        assert_eq!(twm.child_wnds(&pal_hwnd), vec![child_wnd.clone()]);
+        assert_eq!(
+            twm.child_wnd_bounds(&child_wnd),
+            Some(box2! { min: [10.0, 10.0], max: [90.0, 40.0] })
+        );
+        assert_eq!(num_changes.get(), 1);
+
+        // Unmounting the view destroys the child window
+        wnd.content_view()
+            .set_layout(EmptyLayout::new(SizeTraits::default()));
+        twm.step_unsend();
+
+        assert!(host.child_wnd().is_none());
+        assert_eq!(twm.child_wnd_bounds(&child_wnd), None);
+        assert_eq!(num_changes.get(), 2);
+    }
+}
@@ -0,0 +1,404 @@
+//! Implements the progress bar.
+use alt_fp::FloatOrd;
+use std::{
+    cell::Cell,
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    pal,
+    ui::{
+        animation::start_animation_timer,
+        layouts::FillLayout,
+        theming::{
+            elem_id, roles, ClassSet, HElem, Manager, ModifyArrangementArgs, PropKindFlags,
+            StyledBox, StyledBoxOverride, Widget,
+        },
+    },
+    uicore::{HView, HViewRef, HWndRef, ViewFlags, ViewListener},
+};
+
+/// The length of one cycle of the indeterminate animation, measured in
+/// seconds.
+const INDETERMINATE_PERIOD: f64 = 1.5;
+
+/// The length of the moving segment displayed in the indeterminate mode,
+/// relative to the length of the bar.
+const INDETERMINATE_SEGMENT_LEN: f64 = 0.3;
+
+/// A progress bar widget.
+///
+/// A progress bar is either *determinate*, displaying a fraction specified by
+/// [`ProgressBar::set_value`], or *indeterminate*, displaying a segment moving
+/// back and forth to indicate that an operation of an unknown length is in
+/// progress. The indeterminate animation runs only while the widget is
+/// mounted to a window.
+///
+/// # Styling
+///
+///  - `style_elem` - See [`StyledBox`](crate::ui::theming::StyledBox)
+///     - `subviews[`[`roles::GENERIC`]`]`: The filled portion. `ProgressBar`
+///       overrides its `frame` using the current value. The original `frame`
+///       represents the range in which the filled portion can extend.
+///
+///  - `style_elem > #`[`PROGRESS_BAR_FILL`] - The filled portion. See
+///    [`StyledBox`](crate::ui::theming::StyledBox)
+///
+/// The class set of `style_elem` includes [`ClassSet::INDETERMINATE`] while
+/// the progress bar is in the indeterminate mode.
+///
+/// [`roles::GENERIC`]: crate::ui::theming::roles::GENERIC
+/// [`PROGRESS_BAR_FILL`]: crate::ui::theming::elem_id::PROGRESS_BAR_FILL
+#[derive(Debug)]
+pub struct ProgressBar {
+    shared: Rc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    vertical: bool,
+    value: Cell<f64>,
+    indeterminate: Cell<bool>,
+    /// The current position in the indeterminate animation cycle, in range
+    /// `[0, 1)`.
+    phase: Cell<f64>,
+    /// Incremented to stop the running animation timer.
+    anim_ticket: Cell<usize>,
+    wrapper: HView,
+    frame: StyledBox,
+    fill: StyledBox,
+}
+
+impl ProgressBar {
+    /// Construct a `ProgressBar`.
+    pub fn new(style_manager: &'static Manager, vertical: bool) -> Self {
+        let frame = StyledBox::new(style_manager, ViewFlags::default());
+        frame.set_class_set(if vertical {
+            elem_id::PROGRESS_BAR | ClassSet::VERTICAL
+        } else {
+            elem_id::PROGRESS_BAR
+        });
+
+        let fill = StyledBox::new(style_manager, ViewFlags::default());
+        fill.set_class_set(elem_id::PROGRESS_BAR_FILL);
+        frame.set_child(roles::GENERIC, Some(&fill));
+
+        let wrapper = HView::new(ViewFlags::default());
+        wrapper.set_layout(FillLayout::new(frame.view()));
+
+        let shared = Rc::new(Shared {
+            vertical,
+            value: Cell::new(0.0),
+            indeterminate: Cell::new(false),
+            phase: Cell::new(0.0),
+            anim_ticket: Cell::new(0),
+            wrapper,
+            frame,
+            fill,
+        });
+
+        shared.update_sb_override();
+
+        shared.wrapper.set_listener(PbViewListener {
+            shared: Rc::downgrade(&shared),
+        });
+
+        Self { shared }
+    }
+
+    /// Set the class set of the inner `StyledBox`.
+    ///
+    /// It defaults to `elem_id::PROGRESS_BAR`. Some bits (e.g.,
+    /// `INDETERMINATE`) are internally enforced and cannot be modified.
+    pub fn set_class_set(&self, mut class_set: ClassSet) {
+        let frame = &self.shared.frame;
+
+        // Protected bits
+        let protected = ClassSet::INDETERMINATE;
+        class_set -= protected;
+        class_set |= frame.class_set() & protected;
+        frame.set_class_set(class_set);
+    }
+
+    /// Get the class set of the inner `StyledBox`.
+    pub fn class_set(&self) -> ClassSet {
+        self.shared.frame.class_set()
+    }
+
+    /// Get the current value.
+    pub fn value(&self) -> f64 {
+        self.shared.value.get()
+    }
+
+    /// Set the current value in range `[0, 1]`.
+    ///
+    /// The value is retained but not displayed while the progress bar is in
+    /// the indeterminate mode.
+    pub fn set_value(&self, new_value: f64) {
+        debug_assert!(new_value >= 0.0, "{} >= 0.0", new_value);
+        debug_assert!(new_value <= 1.0, "{} <= 1.0", new_value);
+
+        if new_value == self.shared.value.get() {
+            return;
+        }
+
+        self.shared.value.set(new_value);
+        self.shared.update_sb_override();
+    }
+
+    /// Get a flag indicating whether the progress bar is in the indeterminate
+    /// mode.
+    pub fn indeterminate(&self) -> bool {
+        self.shared.indeterminate.get()
+    }
+
+    /// Switch between the determinate and indeterminate modes.
+    pub fn set_indeterminate(&self, new_value: bool) {
+        let shared = &self.shared;
+        if new_value == shared.indeterminate.get() {
+            return;
+        }
+
+        shared.indeterminate.set(new_value);
+
+        let mut class_set = shared.frame.class_set();
+        class_set.set(ClassSet::INDETERMINATE, new_value);
+        shared.frame.set_class_set(class_set);
+
+        if new_value {
+            shared.phase.set(0.0);
+            if let Some(hwnd) = shared.wrapper.containing_wnd() {
+                Shared::start_animation(&self.shared, hwnd.as_ref());
+            }
+        } else {
+            shared.stop_animation();
+        }
+
+        shared.update_sb_override();
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.shared.wrapper.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.shared.wrapper.as_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.shared.frame.style_elem()
+    }
+}
+
+impl Widget for ProgressBar {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+impl Shared {
+    fn update_sb_override(&self) {
+        self.frame.set_override(PbStyledBoxOverride {
+            vertical: self.vertical,
+            range: if self.indeterminate.get() {
+                indeterminate_range(self.phase.get())
+            } else {
+                [0.0, self.value.get()]
+            },
+        });
+    }
+
+    fn start_animation(this: &Rc<Self>, hwnd: HWndRef<'_>) {
+        this.stop_animation();
+
+        let ticket = this.anim_ticket.get();
+        let shared_weak = Rc::downgrade(this);
+        // Resume from the current phase
+        let start =
+            Instant::now() - Duration::from_secs_f64(this.phase.get() * INDETERMINATE_PERIOD);
+
+        start_animation_timer(hwnd, move |_: pal::Wm| {
+            let shared = if let Some(shared) = shared_weak.upgrade() {
+                shared
+            } else {
+                return false;
+            };
+
+            if shared.anim_ticket.get() != ticket {
+                return false;
+            }
+
+            let phase = start.elapsed().as_secs_f64() / INDETERMINATE_PERIOD;
+            shared.phase.set(phase.fract());
+            shared.update_sb_override();
+
+            true
+        });
+    }
+
+    fn stop_animation(&self) {
+        self.anim_ticket.set(self.anim_ticket.get().wrapping_add(1));
+    }
+}
+
+/// Calculate the range of the moving segment at the specified phase of the
+/// indeterminate animation.
+fn indeterminate_range(phase: f64) -> [f64; 2] {
+    // Move back and forth
+    let t = 1.0 - (phase * 2.0 - 1.0).abs();
+    let start = t * (1.0 - INDETERMINATE_SEGMENT_LEN);
+    [start, start + INDETERMINATE_SEGMENT_LEN]
+}
+
+struct PbViewListener {
+    shared: Weak<Shared>,
+}
+
+impl ViewListener for PbViewListener {
+    fn mount(&self, _: pal::Wm, _: HViewRef<'_>, hwnd: HWndRef<'_>) {
+        if let Some(shared) = self.shared.upgrade() {
+            if shared.indeterminate.get() {
+                Shared::start_animation(&shared, hwnd);
+            }
+        }
+    }
+
+    fn unmount(&self, _: pal::Wm, _: HViewRef<'_>) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.stop_animation();
+        }
+    }
+}
+
+/// Implements `StyledBoxOverride` for `ProgressBar`.
+struct PbStyledBoxOverride {
+    vertical: bool,
+    /// The filled range, relative to the bar's length.
+    range: [f64; 2],
+}
+
+impl StyledBoxOverride for PbStyledBoxOverride {
+    fn modify_arrangement(
+        &self,
+        ModifyArrangementArgs { frame, role, .. }: ModifyArrangementArgs<'_>,
+    ) {
+        if role != roles::GENERIC {
+            return;
+        }
+
+        let pri = self.vertical as usize;
+
+        let bar_len = frame.size()[pri] as f64;
+        let bar_start = frame.min[pri] as f64;
+        let bar_end = frame.max[pri] as f64;
+
+        let [start, end] = self.range;
+        let (start, end) = (start.fmax(0.0).fmin(1.0), end.fmax(0.0).fmin(1.0));
+
+        if self.vertical {
+            // Vertical progress bars fill from bottom to top
+            frame.min[pri] = (bar_end - end * bar_len) as f32;
+            frame.max[pri] = (bar_end - start * bar_len) as f32;
+        } else {
+            frame.min[pri] = (bar_start + start * bar_len) as f32;
+            frame.max[pri] = (bar_start + end * bar_len) as f32;
+        }
+    }
+
+    fn dirty_flags(&self, other: &dyn StyledBoxOverride) -> PropKindFlags {
+        use as_any::Downcast;
+        if let Some(other) = (*other).downcast_ref::<Self>() {
+            if self.vertical == other.vertical && self.range == other.range {
+                PropKindFlags::empty()
+            } else {
+                PropKindFlags::LAYOUT
+            }
+        } else {
+            PropKindFlags::all()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cggeom::prelude::*;
+    use try_match::try_match;
+
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+
+    #[test]
+    fn indeterminate_range_stays_within_bar() {
+        for i in 0..=100 {
+            let [start, end] = indeterminate_range(i as f64 / 100.0);
+            assert!(start >= 0.0, "{} >= 0.0", start);
+            assert!(end <= 1.0 + 1.0e-9, "{} <= 1.0", end);
+            assert!(start < end, "{} < {}", start, end);
+        }
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn toggle_indeterminate(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let bar = ProgressBar::new(Manager::global(wm), false);
+
+        bar.set_value(0.5);
+        assert_eq!(bar.value(), 0.5);
+
+        bar.set_indeterminate(true);
+        assert!(bar.indeterminate());
+        assert!(bar.class_set().contains(ClassSet::INDETERMINATE));
+
+        // The protected bit can't be cleared by `set_class_set`
+        bar.set_class_set(elem_id::PROGRESS_BAR);
+        assert!(bar.class_set().contains(ClassSet::INDETERMINATE));
+
+        bar.set_indeterminate(false);
+        assert!(!bar.class_set().contains(ClassSet::INDETERMINATE));
+        assert_eq!(bar.value(), 0.5);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn fill_follows_value(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let bar = ProgressBar::new(Manager::global(wm), false);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(bar.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+        let min_size = twm.wnd_attrs(&pal_hwnd).unwrap().min_size;
+        twm.set_wnd_size(&pal_hwnd, [400, min_size[1]]);
+        twm.step_unsend();
+
+        let fill_width = || bar.shared.fill.view().global_frame().size().x;
+
+        assert_eq!(fill_width(), 0.0);
+
+        bar.set_value(1.0);
+        twm.step_unsend();
+        let full_width = fill_width();
+        assert!(full_width > 300.0, "{} > 300.0", full_width);
+
+        bar.set_value(0.5);
+        twm.step_unsend();
+        assert!((fill_width() - full_width * 0.5).abs() < 1.0);
+    }
+}
@@ -0,0 +1,769 @@
+//! Implements a label widget with inline hyperlinks.
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{Point2, Vector2};
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    ops::Range,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::{
+        mixins::{button::ButtonListener, ButtonMixin, CanvasMixin},
+        theming::{elem_id, ClassSet, Elem, GetPropValue, HElem, Manager, PropKindFlags, Widget},
+        EventSource,
+    },
+    uicore::{
+        CursorShape, HView, HViewRef, HWndRef, Layout, LayoutCtx, MouseDragListener, SizeTraits,
+        Sub, UpdateCtx, ViewFlags, ViewListener,
+    },
+};
+
+/// The distance between the baseline and the underline of a link.
+const UNDERLINE_OFFSET: f32 = 1.5;
+
+/// Describes a span of text in [`RichLabel`] that behaves as a hyperlink.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLink {
+    /// The UTF-8 offset range of the link text.
+    pub range: Range<usize>,
+    /// The URL passed to the handlers of [`RichLabel::subscribe_link_activated`].
+    pub url: String,
+}
+
+impl TextLink {
+    /// Construct a `TextLink`.
+    pub fn new(range: Range<usize>, url: impl Into<String>) -> Self {
+        Self {
+            range,
+            url: url.into(),
+        }
+    }
+}
+
+/// A widget for displaying a static text with inline hyperlinks.
+///
+/// The text is drawn using the styling element representing the widget
+/// (`ClassSet::LABEL` by default). Each link is represented by a child styling
+/// element having `elem_id::LINK`, which receives `ClassSet::HOVER` and
+/// `ClassSet::ACTIVE` when the link is hovered over and pressed, respectively.
+#[derive(Debug)]
+pub struct RichLabel {
+    view: HView,
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    style_manager: &'static Manager,
+    style_elem: Elem,
+    state: RefCell<State>,
+    link_activated_handlers: EventSource<dyn Fn(pal::Wm, &str)>,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("style_manager", &(self.style_manager as *const _))
+            .field("style_elem", &self.style_elem)
+            .field("state", &self.state)
+            .field("link_activated_handlers", &())
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    text: String,
+    links: Vec<Rc<Link>>,
+    text_layout_info: Option<TextLayoutInfo>,
+    canvas: CanvasMixin,
+    /// The hot zones created by the last call to `update_layout`.
+    hot_zones: Vec<Rc<HotZone>>,
+}
+
+#[derive(Debug)]
+struct Link {
+    range: Range<usize>,
+    url: String,
+    elem: Elem,
+    /// The number of the link's hot zones under the mouse pointer.
+    num_hovered: Cell<usize>,
+    /// The number of the link's hot zones being pressed.
+    num_pressed: Cell<usize>,
+}
+
+#[derive(Debug)]
+struct TextLayoutInfo {
+    text_layout: pal::TextLayout,
+    layout_bounds: Box2<f32>,
+    visual_bounds: Box2<f32>,
+    /// The visual runs of the links.
+    link_runs: Vec<LinkRun>,
+}
+
+#[derive(Debug)]
+struct LinkRun {
+    /// An index into `State::links`.
+    link_i: usize,
+    bounds: Box2<f32>,
+    baseline: f32,
+}
+
+impl RichLabel {
+    /// Construct a `RichLabel` with an empty text.
+    pub fn new(style_manager: &'static Manager) -> Self {
+        let style_elem = Elem::new(style_manager);
+        style_elem.set_class_set(ClassSet::LABEL);
+
+        let this = Self {
+            view: HView::new(ViewFlags::default()),
+            inner: Rc::new(Inner {
+                style_manager,
+                style_elem,
+                state: RefCell::new(State {
+                    text: String::new(),
+                    links: Vec::new(),
+                    text_layout_info: None,
+                    canvas: CanvasMixin::new(),
+                    hot_zones: Vec::new(),
+                }),
+                link_activated_handlers: EventSource::new(),
+            }),
+        };
+
+        // Get notified when a styling property changes
+        let view = this.view.downgrade();
+        let inner = Rc::downgrade(&this.inner);
+        this.inner
+            .style_elem
+            .set_on_change(Box::new(move |_, kind_flags| {
+                if let (Some(inner), Some(view)) = (inner.upgrade(), view.upgrade()) {
+                    reapply_style(&inner, view.as_ref(), kind_flags);
+                }
+            }));
+
+        this.view.set_listener(RlListener {
+            inner: Rc::clone(&this.inner),
+        });
+        update_layout(&this.inner, this.view.as_ref());
+
+        this
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.view.as_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.inner.style_elem.helem()
+    }
+
+    /// Set the text displayed in the widget, removing all links.
+    pub fn set_text(&self, value: impl Into<String>) {
+        self.set_text_with_links(value, Vec::new());
+    }
+
+    /// Set the text displayed in the widget and the links in the text.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of `links[i].range` is out of bounds or does not lie on
+    /// UTF-8 character boundaries.
+    pub fn set_text_with_links(&self, value: impl Into<String>, links: Vec<TextLink>) {
+        let value = value.into();
+
+        for link in links.iter() {
+            assert!(
+                value.get(link.range.clone()).is_some(),
+                "link range {:?} is invalid",
+                link.range
+            );
+        }
+
+        {
+            let mut state = self.inner.state.borrow_mut();
+
+            state.text = value;
+            state.links = links
+                .into_iter()
+                .map(|link| Rc::new(self.new_link(link)))
+                .collect();
+            state.invalidate_text_layout();
+            state.canvas.pend_draw(self.view.as_ref());
+        }
+
+        // Invalidate the layout, since the label size and the hot zones might
+        // be changed
+        update_layout(&self.inner, self.view.as_ref());
+    }
+
+    fn new_link(&self, link: TextLink) -> Link {
+        let elem = Elem::new(self.inner.style_manager);
+        elem.set_class_set(elem_id::LINK);
+        self.inner.style_elem.insert_child(elem.helem());
+
+        // Redraw the label when the link's appearance changes
+        let view = self.view.downgrade();
+        let inner = Rc::downgrade(&self.inner);
+        elem.set_on_change(Box::new(move |_, kind_flags| {
+            if let (Some(inner), Some(view)) = (inner.upgrade(), view.upgrade()) {
+                if kind_flags.intersects(PropKindFlags::FG_COLOR) {
+                    inner.state.borrow_mut().canvas.pend_draw(view.as_ref());
+                }
+            }
+        }));
+
+        Link {
+            range: link.range,
+            url: link.url,
+            elem,
+            num_hovered: Cell::new(0),
+            num_pressed: Cell::new(0),
+        }
+    }
+
+    /// Set the styling class set.
+    ///
+    /// It defaults to `ClassSet::LABEL`.
+    pub fn set_class_set(&self, class_set: ClassSet) {
+        self.inner.style_elem.set_class_set(class_set);
+    }
+
+    /// Get the styling class set.
+    pub fn class_set(&self) -> ClassSet {
+        self.inner.style_elem.class_set()
+    }
+
+    /// Add a function called when a link is clicked. The function receives
+    /// the link's URL.
+    ///
+    /// The function is called via `Wm::invoke`, thus allowed to modify
+    /// view hierarchy and view attributes. However, it's not allowed to call
+    /// `subscribe_link_activated` when one of the handlers is being called.
+    pub fn subscribe_link_activated(&self, cb: Box<dyn Fn(pal::Wm, &str)>) -> Sub {
+        self.inner.link_activated_handlers.subscribe(cb)
+    }
+}
+
+impl Widget for RichLabel {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+fn reapply_style(inner: &Rc<Inner>, view: HViewRef<'_>, kind_flags: PropKindFlags) {
+    let mut state = inner.state.borrow_mut();
+
+    if kind_flags.intersects(PropKindFlags::FG_COLOR) {
+        state.canvas.pend_draw(view);
+    }
+
+    if kind_flags.intersects(PropKindFlags::FONT) {
+        state.invalidate_text_layout();
+        state.canvas.pend_draw(view);
+        drop(state);
+        update_layout(inner, view);
+    }
+}
+
+/// Recreate the hot zones and call `set_layout`.
+fn update_layout(inner: &Rc<Inner>, view: HViewRef<'_>) {
+    let mut state = inner.state.borrow_mut();
+    state.ensure_text_layout(&inner.style_elem);
+
+    let info = state.text_layout_info.as_ref().unwrap();
+
+    let mut hot_zones = Vec::with_capacity(info.link_runs.len());
+    let (subviews, frames) = info
+        .link_runs
+        .iter()
+        .map(|run| {
+            let hot_zone = Rc::new(HotZone {
+                inner: Rc::downgrade(inner),
+                link: Rc::clone(&state.links[run.link_i]),
+                button_mixin: ButtonMixin::new(),
+                pressed: Cell::new(false),
+                hovered: Cell::new(false),
+                detached: Cell::new(false),
+            });
+            hot_zones.push(Rc::clone(&hot_zone));
+
+            let view = HView::new(ViewFlags::ACCEPT_MOUSE_OVER | ViewFlags::ACCEPT_MOUSE_DRAG);
+            view.set_cursor_shape(Some(CursorShape::Hand));
+            view.set_listener(HotZoneListener { hot_zone });
+            (view, run.bounds)
+        })
+        .unzip();
+
+    let layout = RlLayout {
+        size: info.layout_bounds.size(),
+        subviews,
+        frames,
+    };
+
+    let old_hot_zones = std::mem::replace(&mut state.hot_zones, hot_zones);
+    drop(state);
+
+    // The old hot zones won't receive `mouse_out` when they are removed, so
+    // withdraw their contribution to the links' states here
+    for hot_zone in old_hot_zones {
+        hot_zone.detach();
+    }
+
+    view.set_layout(layout);
+}
+
+impl State {
+    fn ensure_text_layout(&mut self, elem: &Elem) {
+        if self.text_layout_info.is_none() {
+            let font_type = elem.computed_values().font();
+
+            let char_style = pal::CharStyle::new(pal::CharStyleAttrs {
+                sys: Some(font_type),
+                ..Default::default()
+            });
+            let text_layout = pal::TextLayout::from_text(&self.text, &char_style, None);
+
+            let visual_bounds = text_layout.visual_bounds();
+            let layout_bounds = text_layout.layout_bounds();
+
+            let mut link_runs = Vec::new();
+            for (link_i, link) in self.links.iter().enumerate() {
+                push_link_runs(
+                    &text_layout,
+                    &self.text,
+                    link_i,
+                    &link.range,
+                    &mut link_runs,
+                );
+            }
+
+            self.text_layout_info = Some(TextLayoutInfo {
+                text_layout,
+                visual_bounds,
+                layout_bounds,
+                link_runs,
+            });
+        }
+    }
+
+    /// Delete the cached `TextLayout` (if any).
+    ///
+    /// After calling this, you probably want to call `update_layout` again
+    /// because the API contract of `Layout` requires immutability.
+    fn invalidate_text_layout(&mut self) {
+        self.text_layout_info = None;
+    }
+}
+
+/// Calculate the visual runs of the link text `range` and append them to `out`.
+fn push_link_runs(
+    text_layout: &pal::TextLayout,
+    text: &str,
+    link_i: usize,
+    range: &Range<usize>,
+    out: &mut Vec<LinkRun>,
+) {
+    if range.start >= range.end {
+        return;
+    }
+
+    let first_line = text_layout.line_from_index(range.start);
+    let last_line = text_layout.line_from_index(range.end - 1);
+
+    for line in first_line..=last_line {
+        let line_range = text_layout.line_index_range(line);
+
+        // Exclude the trailing line break
+        let line_end = line_range.start
+            + text[line_range.clone()]
+                .trim_end_matches(&['\r', '\n'][..])
+                .len();
+
+        let start = range.start.max(line_range.start);
+        let end = range.end.min(line_end);
+        if start >= end {
+            continue;
+        }
+
+        let vert = text_layout.line_vertical_bounds(line);
+        let baseline = text_layout.line_baseline(line);
+
+        for run in text_layout.run_metrics_of_range(start..end) {
+            if run.bounds.start >= run.bounds.end {
+                continue;
+            }
+            out.push(LinkRun {
+                link_i,
+                bounds: box2! {
+                    min: [run.bounds.start, vert.start],
+                    max: [run.bounds.end, vert.end],
+                },
+                baseline,
+            });
+        }
+    }
+}
+
+struct RlLayout {
+    size: Vector2<f32>,
+    /// The hot zones of the links.
+    subviews: Vec<HView>,
+    frames: Vec<Box2<f32>>,
+}
+
+impl Layout for RlLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, _: &LayoutCtx<'_>) -> SizeTraits {
+        SizeTraits {
+            min: self.size,
+            max: self.size,
+            preferred: self.size,
+        }
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, _: Vector2<f32>) {
+        for (subview, frame) in self.subviews.iter().zip(self.frames.iter()) {
+            ctx.set_subview_frame(subview.as_ref(), *frame);
+        }
+    }
+}
+
+struct RlListener {
+    inner: Rc<Inner>,
+}
+
+impl ViewListener for RlListener {
+    fn mount(&self, wm: pal::Wm, view: HViewRef<'_>, wnd: HWndRef<'_>) {
+        self.inner.state.borrow_mut().canvas.mount(wm, view, wnd);
+    }
+
+    fn unmount(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        self.inner.state.borrow_mut().canvas.unmount(wm, view);
+    }
+
+    fn position(&self, wm: pal::Wm, view: HViewRef<'_>) {
+        self.inner.state.borrow_mut().canvas.position(wm, view);
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        let state = &mut *state; // enable split borrow
+
+        state.ensure_text_layout(&self.inner.style_elem);
+
+        let color = self.inner.style_elem.computed_values().fg_color();
+        let link_colors: Vec<_> = (state.links.iter())
+            .map(|link| link.elem.computed_values().fg_color())
+            .collect();
+
+        let text_layout_info: &TextLayoutInfo = state.text_layout_info.as_ref().unwrap();
+
+        state.canvas.update_layer(
+            wm,
+            view,
+            ctx.hwnd(),
+            text_layout_info.visual_bounds,
+            |draw_ctx| {
+                let c = &mut draw_ctx.canvas;
+                let text_layout = &text_layout_info.text_layout;
+
+                c.draw_text(text_layout, Point2::new(0.0, 0.0), color);
+
+                // Redraw the links in their own colors
+                for run in text_layout_info.link_runs.iter() {
+                    let link_color = link_colors[run.link_i];
+
+                    c.save();
+                    c.clip_rect(run.bounds);
+                    c.draw_text(text_layout, Point2::new(0.0, 0.0), link_color);
+                    c.restore();
+
+                    let y = run.baseline + UNDERLINE_OFFSET;
+                    c.begin_path();
+                    c.move_to(Point2::new(run.bounds.min.x, y));
+                    c.line_to(Point2::new(run.bounds.max.x, y));
+                    c.set_stroke_rgb(link_color);
+                    c.set_line_width(1.0);
+                    c.stroke();
+                }
+            },
+        );
+
+        if ctx.layers().len() != 1 {
+            ctx.set_layers(vec![state.canvas.layer().unwrap().clone()]);
+        }
+    }
+}
+
+/// The view listener of a transparent view placed on a visual run of a link.
+struct HotZoneListener {
+    hot_zone: Rc<HotZone>,
+}
+
+#[derive(Debug)]
+struct HotZone {
+    inner: Weak<Inner>,
+    link: Rc<Link>,
+    button_mixin: ButtonMixin,
+    /// The pressed state last reflected to `Link::num_pressed`.
+    pressed: Cell<bool>,
+    /// The hover state last reflected to `Link::num_hovered`.
+    hovered: Cell<bool>,
+    /// `true` if the hot zone was replaced by `update_layout`. A detached hot
+    /// zone doesn't affect the link's state anymore.
+    detached: Cell<bool>,
+}
+
+impl HotZone {
+    fn set_hovered(&self, value: bool) {
+        if self.detached.get() || value == self.hovered.replace(value) {
+            return;
+        }
+
+        let link = &self.link;
+        if value {
+            link.num_hovered.set(link.num_hovered.get() + 1);
+        } else {
+            link.num_hovered.set(link.num_hovered.get() - 1);
+        }
+        link.update_class_set();
+    }
+
+    fn set_pressed(&self, value: bool) {
+        if self.detached.get() || value == self.pressed.replace(value) {
+            return;
+        }
+
+        let link = &self.link;
+        if value {
+            link.num_pressed.set(link.num_pressed.get() + 1);
+        } else {
+            link.num_pressed.set(link.num_pressed.get() - 1);
+        }
+        link.update_class_set();
+    }
+
+    fn detach(&self) {
+        self.set_hovered(false);
+        self.set_pressed(false);
+        self.detached.set(true);
+    }
+}
+
+impl ViewListener for HotZoneListener {
+    fn mouse_over(&self, _: pal::Wm, _: HViewRef<'_>) {
+        self.hot_zone.set_hovered(true);
+    }
+
+    fn mouse_out(&self, _: pal::Wm, _: HViewRef<'_>) {
+        self.hot_zone.set_hovered(false);
+    }
+
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        self.hot_zone
+            .button_mixin
+            .mouse_drag(Box::new(HotZoneButtonListener {
+                hot_zone: Rc::clone(&self.hot_zone),
+            }))
+    }
+}
+
+struct HotZoneButtonListener {
+    hot_zone: Rc<HotZone>,
+}
+
+impl ButtonListener for HotZoneButtonListener {
+    fn update(&self, _: pal::Wm, _: HViewRef<'_>) {
+        let hot_zone = &self.hot_zone;
+        hot_zone.set_pressed(hot_zone.button_mixin.is_pressed());
+    }
+
+    fn activate(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        let inner = if let Some(inner) = self.hot_zone.inner.upgrade() {
+            inner
+        } else {
+            return;
+        };
+        let url = self.hot_zone.link.url.clone();
+        wm.invoke(move |wm| {
+            inner
+                .link_activated_handlers
+                .raise_with(|handler| handler(wm, &url));
+        });
+    }
+}
+
+impl Link {
+    fn update_class_set(&self) {
+        let mut class_set = self.elem.class_set();
+        class_set.set(ClassSet::HOVER, self.num_hovered.get() > 0);
+        class_set.set(ClassSet::ACTIVE, self.num_pressed.get() > 0);
+        self.elem.set_class_set(class_set);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+    use cgmath::EuclideanSpace;
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn link_activation(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let label = RichLabel::new(Manager::global(wm));
+        label.set_text_with_links(
+            "see https://example.com now",
+            vec![TextLink::new(4..23, "https://example.com")],
+        );
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(label.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let activated = Rc::new(RefCell::new(Vec::new()));
+        {
+            let activated = Rc::clone(&activated);
+            label.subscribe_link_activated(Box::new(move |_, url| {
+                activated.borrow_mut().push(url.to_owned());
+            }));
+        }
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+
+        let link_frame = {
+            let state = label.inner.state.borrow();
+            let info = state.text_layout_info.as_ref().unwrap();
+            assert!(!info.link_runs.is_empty());
+            info.link_runs[0].bounds
+        };
+        let label_frame = label.view().global_frame();
+        let link_center = label_frame.min + (link_frame.min + link_frame.size() * 0.5).to_vec();
+
+        // Clicking the link activates it
+        let drag = twm.raise_mouse_drag(&pal_hwnd, link_center, 0);
+        drag.mouse_down(link_center, 0);
+        assert!(label.inner.state.borrow().links[0]
+            .elem
+            .class_set()
+            .contains(ClassSet::ACTIVE));
+        drag.mouse_up(link_center, 0);
+        twm.step_unsend();
+
+        assert_eq!(*activated.borrow(), vec!["https://example.com".to_owned()]);
+        assert!(!label.inner.state.borrow().links[0]
+            .elem
+            .class_set()
+            .contains(ClassSet::ACTIVE));
+
+        // Clicking outside the link doesn't
+        activated.borrow_mut().clear();
+        let outside = Point2::new(label_frame.min.x + 1.0, link_center.y);
+        let drag = twm.raise_mouse_drag(&pal_hwnd, outside, 0);
+        drag.mouse_down(outside, 0);
+        drag.mouse_up(outside, 0);
+        twm.step_unsend();
+
+        assert!(activated.borrow().is_empty());
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn hover_survives_relayout(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let label = RichLabel::new(Manager::global(wm));
+        label.set_text_with_links(
+            "see https://example.com now",
+            vec![TextLink::new(4..23, "https://example.com")],
+        );
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(label.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+
+        let link_frame = label
+            .inner
+            .state
+            .borrow()
+            .text_layout_info
+            .as_ref()
+            .unwrap()
+            .link_runs[0]
+            .bounds;
+        let label_frame = label.view().global_frame();
+        let link_center = label_frame.min + (link_frame.min + link_frame.size() * 0.5).to_vec();
+        let outside = Point2::new(label_frame.min.x + 1.0, link_center.y);
+
+        let link = Rc::clone(&label.inner.state.borrow().links[0]);
+        let is_hovered = || link.elem.class_set().contains(ClassSet::HOVER);
+
+        twm.raise_mouse_motion(&pal_hwnd, link_center);
+        assert!(is_hovered());
+
+        // Recreate the hot zones (this happens when the font changes) while
+        // the link is hovered over
+        update_layout(&label.inner, label.view_ref());
+        twm.step_unsend();
+        assert!(!is_hovered());
+        assert_eq!(link.num_hovered.get(), 0);
+
+        // The new hot zones work
+        twm.raise_mouse_motion(&pal_hwnd, outside);
+        twm.raise_mouse_motion(&pal_hwnd, link_center);
+        assert!(is_hovered());
+        assert_eq!(link.num_hovered.get(), 1);
+
+        twm.raise_mouse_motion(&pal_hwnd, outside);
+        assert!(!is_hovered());
+        assert_eq!(link.num_hovered.get(), 0);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn multiline_link_runs(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let label = RichLabel::new(Manager::global(wm));
+        label.set_text_with_links("one\ntwo", vec![TextLink::new(0..7, "x")]);
+
+        let state = label.inner.state.borrow();
+        let runs = &state.text_layout_info.as_ref().unwrap().link_runs;
+
+        // One run per line; the line break is excluded
+        assert_eq!(runs.len(), 2);
+        assert!(runs[0].bounds.max.y <= runs[1].bounds.min.y);
+    }
+}
@@ -386,7 +386,14 @@ impl MouseDragListener for SbMouseDragListener {
             }
         }
     }
-    fn mouse_down(&self, wm: pal::Wm, view: HViewRef<'_>, loc: Point2<f32>, button: u8) {
+    fn mouse_down(
+        &self,
+        wm: pal::Wm,
+        view: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+        _click_count: u32,
+    ) {
         if button == 0 {
             let pri = self.shared.vertical as usize;
             let loc = loc[pri];
@@ -0,0 +1,669 @@
+use alt_fp::FloatOrd;
+use cggeom::{box2, prelude::*, Box2};
+use cgmath::{Point2, Vector2};
+use flags_macro::flags;
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    pal,
+    prelude::*,
+    ui::{
+        layouts::FillLayout,
+        mixins::scrollwheel::{ScrollAxisFlags, ScrollModel, ScrollWheelMixin},
+        theming::{roles, ClassSet, HElem, Manager, StyledBox, Widget},
+        views::scrollbar::{ScrollbarDragListener, ScrollbarRaw},
+    },
+    uicore::{
+        HView, HViewRef, HWndRef, Layout, LayoutCtx, ScrollDelta, ScrollListener, SizeTraits,
+        UpdateCtx, ViewFlags, ViewListener,
+    },
+};
+
+/// A container widget that displays a portion of a content view larger than
+/// itself and allows the user to scroll through it.
+///
+/// The content view is arranged based on its size traits. Along each scrollable
+/// axis, it's given its preferred size or the size of the viewport, whichever
+/// is larger. Along the other axes, it's stretched to fit the viewport.
+///
+/// The content is not mirrored in a right-to-left context. The scroll position
+/// is always measured from the top-left corner of the content view.
+#[derive(Debug)]
+pub struct ScrollView {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    wrapper: HView,
+    styled_box: StyledBox,
+    viewport: HView,
+    content: RefCell<HView>,
+    scrollbars: [ScrollbarRaw; 2],
+    drag_active: [Cell<bool>; 2],
+    scroll_mixin: ScrollWheelMixin,
+    axes: Cell<ScrollAxisFlags>,
+    /// The current scroll position. Can be temporarily out of bounds while
+    /// `ScrollWheelMixin` is displaying an over-scroll animation.
+    pos: Cell<Point2<f64>>,
+    /// The viewport size measured by the last `arrange`.
+    viewport_size: Cell<[f64; 2]>,
+    /// The content size calculated by the last `arrange`.
+    content_size: Cell<[f64; 2]>,
+}
+
+impl ScrollView {
+    /// Construct a `ScrollView`.
+    pub fn new(style_manager: &'static Manager) -> Self {
+        let styled_box = StyledBox::new(style_manager, ViewFlags::default());
+        let viewport = HView::new(ViewFlags::LAYER_GROUP | ViewFlags::CLIP_VISIBLE_FRAME);
+        let scrollbars = [
+            ScrollbarRaw::new(style_manager, false),
+            ScrollbarRaw::new(style_manager, true),
+        ];
+
+        styled_box.set_subview(roles::GENERIC, Some(viewport.clone()));
+        styled_box.set_child(roles::HORZ_SCROLLBAR, Some(&scrollbars[0]));
+        styled_box.set_child(roles::VERT_SCROLLBAR, Some(&scrollbars[1]));
+
+        styled_box.set_class_set(ClassSet::SCROLL_CONTAINER);
+
+        // Create a view for receiving scroll wheel events
+        let wrapper = HView::new(ViewFlags::ACCEPT_SCROLL);
+        wrapper.set_layout(FillLayout::new(styled_box.view()));
+
+        viewport.set_listener(ViewportListener::new());
+
+        let this = Self {
+            inner: Rc::new(Inner {
+                wrapper,
+                styled_box,
+                viewport,
+                content: RefCell::new(HView::new(ViewFlags::default())),
+                scrollbars,
+                drag_active: [Cell::new(false), Cell::new(false)],
+                scroll_mixin: ScrollWheelMixin::new(),
+                axes: Cell::new(ScrollAxisFlags::BOTH),
+                pos: Cell::new(Point2::new(0.0, 0.0)),
+                viewport_size: Cell::new([0.0; 2]),
+                content_size: Cell::new([0.0; 2]),
+            }),
+        };
+
+        this.inner.update_layout();
+        this.inner.update_class_set();
+        this.inner.update_scrollbar_value();
+
+        // Register event handlers
+        for axis in 0..2 {
+            let inner_weak = Rc::downgrade(&this.inner);
+            this.inner.scrollbars[axis].set_on_drag(move |_| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    // Steal the control from `ScrollWheelMixin`
+                    inner.scroll_mixin.stop();
+
+                    Box::new(SbDragListener {
+                        inner: inner_weak.clone(),
+                        axis,
+                        orig_pos: Cell::new(inner.pos.get()),
+                    }) as _
+                } else {
+                    // The owner is gone, return a no-op listener
+                    Box::new(()) as _
+                }
+            });
+
+            let inner_weak = Rc::downgrade(&this.inner);
+            this.inner.scrollbars[axis].set_on_page_step(move |_, dir| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.scroll_mixin.stop();
+
+                    let mut pos = inner.pos.get();
+                    let step = inner.viewport_size.get()[axis] * (dir as i8 as f64);
+                    pos[axis] += step;
+                    inner.set_pos(inner.clamp_pos(pos));
+                }
+            });
+        }
+
+        // Watch scroll wheel events
+        this.inner.wrapper.set_listener(WrapperViewListener {
+            inner: Rc::downgrade(&this.inner),
+        });
+
+        this
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.inner.wrapper.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.inner.wrapper.as_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.inner.styled_box.style_elem()
+    }
+
+    /// Get the content view.
+    pub fn content(&self) -> HView {
+        self.inner.content.borrow().clone()
+    }
+
+    /// Set the content view.
+    pub fn set_content(&self, view: HView) {
+        self.inner.content.replace(view);
+        self.inner.update_layout();
+    }
+
+    /// Set the axes for which scrolling is allowed.
+    ///
+    /// This might not take effect for an ongoing scroll gesture (if any).
+    pub fn set_scrollable_axes(&self, axes: ScrollAxisFlags) {
+        self.inner.axes.set(axes);
+        self.inner.scroll_mixin.set_axes(axes);
+
+        let mut pos = self.inner.pos.get();
+        if !axes.contains(ScrollAxisFlags::HORIZONTAL) {
+            pos.x = 0.0;
+        }
+        if !axes.contains(ScrollAxisFlags::VERTICAL) {
+            pos.y = 0.0;
+        }
+        self.inner.update_layout();
+        self.inner.set_pos(pos);
+    }
+
+    /// Get the axes for which scrolling is allowed.
+    pub fn scrollable_axes(&self) -> ScrollAxisFlags {
+        self.inner.axes.get()
+    }
+
+    /// Get the current scroll position, i.e., the point in the content view's
+    /// coordinate space displayed at the top-left corner of the viewport.
+    pub fn scroll_pos(&self) -> Point2<f64> {
+        self.inner.pos.get()
+    }
+
+    /// Set the current scroll position. The value is clamped to the scrollable
+    /// range calculated by the last layout pass.
+    ///
+    /// This cancels an ongoing scroll animation (if any).
+    pub fn set_scroll_pos(&self, pos: Point2<f64>) {
+        self.inner.scroll_mixin.stop();
+        self.inner.set_pos(self.inner.clamp_pos(pos));
+    }
+
+    /// Scroll the minimum amount required to make the specified rectangle
+    /// (in the content view's coordinate space) visible.
+    ///
+    /// If the rectangle is larger than the viewport, its top-left corner is
+    /// prioritized. Like `set_scroll_pos`, this is based on the result of the
+    /// last layout pass and cancels an ongoing scroll animation (if any).
+    pub fn scroll_to(&self, rect: Box2<f32>) {
+        let inner = &self.inner;
+        let vp_size = inner.viewport_size.get();
+        let mut pos = inner.pos.get();
+
+        for i in 0..2 {
+            let (min, max) = (rect.min[i] as f64, rect.max[i] as f64);
+            if max > pos[i] + vp_size[i] {
+                pos[i] = max - vp_size[i];
+            }
+            if min < pos[i] {
+                pos[i] = min;
+            }
+        }
+
+        inner.scroll_mixin.stop();
+        inner.set_pos(inner.clamp_pos(pos));
+    }
+
+    /// Set the class set of the inner `StyledBox`.
+    ///
+    /// It defaults to `ClassSet::SCROLL_CONTAINER`. Some bits (e.g.,
+    /// `HAS_HORIZONTAL_SCROLLBAR`) are internally enforced and cannot be
+    /// modified.
+    pub fn set_class_set(&self, mut class_set: ClassSet) {
+        let styled_box = &self.inner.styled_box;
+
+        // Protected bits
+        let protected = flags![ClassSet::{HAS_HORIZONTAL_SCROLLBAR | HAS_VERTICAL_SCROLLBAR}];
+        class_set -= protected;
+        class_set |= styled_box.class_set() & protected;
+        styled_box.set_class_set(class_set);
+    }
+}
+
+impl Widget for ScrollView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+impl Inner {
+    /// Get the upper bound of the scroll position.
+    fn scroll_limit(&self) -> [f64; 2] {
+        let vp_size = self.viewport_size.get();
+        let content_size = self.content_size.get();
+        [
+            (content_size[0] - vp_size[0]).fmax(0.0),
+            (content_size[1] - vp_size[1]).fmax(0.0),
+        ]
+    }
+
+    fn clamp_pos(&self, pos: Point2<f64>) -> Point2<f64> {
+        let limit = self.scroll_limit();
+        Point2::new(
+            pos.x.fmin(limit[0]).fmax(0.0),
+            pos.y.fmin(limit[1]).fmax(0.0),
+        )
+    }
+
+    fn set_pos(self: &Rc<Self>, pos: Point2<f64>) {
+        if pos == self.pos.get() {
+            return;
+        }
+        self.pos.set(pos);
+        self.update_layout();
+        self.update_scrollbar_value();
+    }
+
+    /// Re-create the viewport's layout to reflect the current state.
+    fn update_layout(self: &Rc<Self>) {
+        self.viewport.set_layout(ViewportLayout {
+            content: [self.content.borrow().clone()],
+            pos: self.pos.get(),
+            axes: self.axes.get(),
+            inner: Rc::downgrade(self),
+        });
+    }
+
+    /// Called through `invoke_on_update` when `ViewportLayout` detected
+    /// a change in the viewport or content size.
+    fn handle_resize(self: &Rc<Self>) {
+        // Keep the scroll position in bounds unless it's being controlled by
+        // a scrollbar
+        if !self.drag_active.iter().any(|x| x.get()) {
+            // Only pull back the content if it's past the end. Over-scroll
+            // toward the start is managed by `ScrollWheelMixin`.
+            let pos = self.pos.get();
+            let limit = self.scroll_limit();
+            self.set_pos(Point2::new(pos.x.fmin(limit[0]), pos.y.fmin(limit[1])));
+        }
+
+        self.update_class_set();
+        self.update_scrollbar_value();
+    }
+
+    /// Update the internally enforced class sets.
+    fn update_class_set(&self) {
+        let limit = self.scroll_limit();
+
+        let styled_box = &self.styled_box;
+        let mut class_set = styled_box.class_set();
+        class_set.set(ClassSet::HAS_HORIZONTAL_SCROLLBAR, limit[0] > 0.0);
+        class_set.set(ClassSet::HAS_VERTICAL_SCROLLBAR, limit[1] > 0.0);
+        if class_set == styled_box.class_set() {
+            // no change
+            return;
+        }
+        styled_box.set_class_set(class_set);
+    }
+
+    /// Set scrollbar values. Does nothing if a scroll operation is active for
+    /// an axis.
+    fn update_scrollbar_value(&self) {
+        let limit = self.scroll_limit();
+        let vp_size = self.viewport_size.get();
+        let pos = self.clamp_pos(self.pos.get());
+
+        for i in 0..2 {
+            if !self.drag_active[i].get() {
+                self.scrollbars[i].set_value(pos[i] / limit[i].fmax(std::f64::MIN_POSITIVE));
+            }
+
+            // The page step can be infinity if the content is not scrollable
+            // for a given axis. However, it must not be NaN.
+            self.scrollbars[i].set_page_step(vp_size[i].fmax(std::f64::MIN_POSITIVE) / limit[i]);
+        }
+    }
+}
+
+/// Arranges the content view inside the viewport.
+#[derive(Debug)]
+struct ViewportLayout {
+    content: [HView; 1],
+    pos: Point2<f64>,
+    axes: ScrollAxisFlags,
+    inner: Weak<Inner>,
+}
+
+impl ViewportLayout {
+    fn is_scrollable(&self, axis: usize) -> bool {
+        self.axes
+            .contains([ScrollAxisFlags::HORIZONTAL, ScrollAxisFlags::VERTICAL][axis])
+    }
+}
+
+impl Layout for ViewportLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.content
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        let st = ctx.subview_size_traits(self.content[0].as_ref());
+        let mut out = st;
+
+        for i in 0..2 {
+            if self.is_scrollable(i) {
+                out.min[i] = 0.0;
+                out.max[i] = f32::INFINITY;
+            }
+        }
+
+        out
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let st = ctx.subview_size_traits(self.content[0].as_ref());
+
+        let mut content_size = size;
+        for i in 0..2 {
+            if self.is_scrollable(i) {
+                content_size[i] = content_size[i].fmax(st.preferred[i]);
+            }
+            content_size[i] = content_size[i].fmin(st.max[i]).fmax(st.min[i]);
+        }
+
+        let origin = Point2::new(-self.pos.x as f32, -self.pos.y as f32);
+        ctx.set_subview_frame(
+            self.content[0].as_ref(),
+            box2! { min: origin, max: origin + content_size },
+        );
+
+        // Report the new sizes to `ScrollView`
+        if let Some(inner) = self.inner.upgrade() {
+            let vp_size = [size.x as f64, size.y as f64];
+            let content_size = [content_size.x as f64, content_size.y as f64];
+
+            if inner.viewport_size.get() != vp_size || inner.content_size.get() != content_size {
+                inner.viewport_size.set(vp_size);
+                inner.content_size.set(content_size);
+
+                // Most actions are restricted in `Layout`
+                pal::Wm::global().invoke_on_update(move |_| {
+                    inner.handle_resize();
+                });
+            }
+        }
+    }
+
+    fn has_same_subviews(&self, other: &dyn Layout) -> bool {
+        if let Some(other) = as_any::Downcast::downcast_ref::<Self>(other) {
+            self.content[0] == other.content[0]
+        } else {
+            false
+        }
+    }
+}
+
+/// Clips the content view using a layer.
+#[derive(Debug)]
+struct ViewportListener {
+    layer: RefCell<Option<pal::HLayer>>,
+}
+
+impl ViewportListener {
+    fn new() -> Self {
+        Self {
+            layer: RefCell::new(None),
+        }
+    }
+}
+
+impl ViewListener for ViewportListener {
+    fn mount(&self, wm: pal::Wm, _: HViewRef<'_>, _: HWndRef<'_>) {
+        let layer = wm.new_layer(pal::LayerAttrs {
+            flags: Some(pal::LayerFlags::MASK_TO_BOUNDS),
+            ..Default::default()
+        });
+
+        let old_layer = self.layer.replace(Some(layer));
+
+        assert!(old_layer.is_none());
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if let Some(layer) = self.layer.replace(None) {
+            wm.remove_layer(&layer);
+        }
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let layer = self.layer.borrow();
+        let layer = layer.as_ref().expect("not mounted");
+
+        let mut new_attrs = pal::LayerAttrs {
+            bounds: Some(view.global_frame()),
+            ..Default::default()
+        };
+
+        if let Some(sublayers) = ctx.sublayers().take() {
+            new_attrs.sublayers = Some(sublayers);
+        }
+        wm.set_layer_attr(&layer, new_attrs);
+
+        if ctx.layers().len() != 1 {
+            ctx.set_layers(vec![(*layer).clone()]);
+        }
+    }
+}
+
+/// Translates a scrollbar drag operation into scrolling.
+struct SbDragListener {
+    inner: Weak<Inner>,
+    axis: usize,
+    orig_pos: Cell<Point2<f64>>,
+}
+
+impl ScrollbarDragListener for SbDragListener {
+    fn down(&self, _: pal::Wm, _new_value: f64) {
+        if let Some(inner) = self.inner.upgrade() {
+            // Temporarily give the control of the scrollbar's value to
+            // `SbDragListener`. This flag is reset by `up` or `cancel`.
+            inner.drag_active[self.axis].set(true);
+            self.orig_pos.set(inner.pos.get());
+        }
+    }
+
+    fn motion(&self, _: pal::Wm, new_value: f64) {
+        if let Some(inner) = self.inner.upgrade() {
+            let mut pos = inner.pos.get();
+            pos[self.axis] = new_value * inner.scroll_limit()[self.axis];
+            inner.set_pos(pos);
+
+            inner.scrollbars[self.axis].set_value(new_value);
+        }
+    }
+
+    fn up(&self, _: pal::Wm) {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.drag_active[self.axis].set(false);
+            inner.update_scrollbar_value();
+        }
+    }
+
+    fn cancel(&self, _: pal::Wm) {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.drag_active[self.axis].set(false);
+            inner.set_pos(self.orig_pos.get());
+            inner.update_scrollbar_value();
+        }
+    }
+}
+
+/// Implements [`ScrollModel`] to bridge between `ScrollView` and
+/// `ScrollWheelMixin`.
+///
+/// [`ScrollModel`]: crate::ui::mixins::scrollwheel::ScrollModel
+struct ScrollViewModel {
+    inner: Rc<Inner>,
+    orig_pos: Point2<f64>,
+}
+
+impl ScrollModel for ScrollViewModel {
+    fn bounds(&mut self) -> Box2<f64> {
+        box2! {
+            min: [0.0, 0.0].into(),
+            max: self.inner.scroll_limit().into(),
+        }
+    }
+
+    fn pos(&mut self) -> Point2<f64> {
+        self.inner.pos.get()
+    }
+
+    fn set_pos(&mut self, value: Point2<f64>) {
+        // An out-of-bounds value is displayed as it is for over-scrolling
+        self.inner.set_pos(value);
+    }
+
+    fn cancel(&mut self) {
+        self.inner.set_pos(self.orig_pos);
+    }
+}
+
+struct WrapperViewListener {
+    inner: Weak<Inner>,
+}
+
+impl WrapperViewListener {
+    fn scroll_model_getter(&self) -> impl Fn() -> Box<dyn ScrollModel> + 'static {
+        let inner_weak = self.inner.clone();
+        move || {
+            if let Some(inner) = inner_weak.upgrade() {
+                let orig_pos = inner.pos.get();
+                Box::new(ScrollViewModel { inner, orig_pos })
+            } else {
+                Box::new(())
+            }
+        }
+    }
+}
+
+impl ViewListener for WrapperViewListener {
+    fn scroll_motion(&self, wm: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, delta: &ScrollDelta) {
+        if let Some(inner) = self.inner.upgrade() {
+            // Do not allow scrolling in two ways at the same time
+            if inner.drag_active.iter().any(|x| x.get()) {
+                return;
+            }
+
+            inner
+                .scroll_mixin
+                .scroll_motion(wm, delta, self.scroll_model_getter())
+        }
+    }
+
+    fn scroll_gesture(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+    ) -> Box<dyn ScrollListener> {
+        if let Some(inner) = self.inner.upgrade() {
+            // Do not allow scrolling in two ways at the same time
+            if inner.drag_active.iter().any(|x| x.get()) {
+                return Box::new(());
+            }
+
+            inner
+                .scroll_mixin
+                .scroll_gesture(self.scroll_model_getter())
+        } else {
+            Box::new(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::{layouts::AbsLayout, layouts::EmptyLayout, AlignFlags},
+        uicore::HWnd,
+    };
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn scroll_to(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let content = HView::new(Default::default());
+        content.set_layout(EmptyLayout::new(SizeTraits {
+            preferred: [400.0, 300.0].into(),
+            ..Default::default()
+        }));
+
+        let style_manager = Manager::global(wm);
+        let sv = ScrollView::new(style_manager);
+        sv.set_content(content.clone());
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(AbsLayout::new(
+            SizeTraits {
+                min: [100.0; 2].into(),
+                max: [100.0; 2].into(),
+                preferred: [100.0; 2].into(),
+            },
+            Some((
+                sv.view(),
+                box2! { min: [0.0, 0.0], max: [100.0, 100.0] },
+                AlignFlags::JUSTIFY,
+            )),
+        ));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let vp_frame = sv.inner.viewport.global_frame();
+        let vp_size = vp_frame.size();
+        assert_eq!(content.global_frame().min, vp_frame.min);
+        assert_eq!(content.global_frame().size(), [400.0, 300.0].into());
+
+        // Scroll to the bottom-right corner
+        sv.scroll_to(box2! { min: [350.0, 250.0], max: [360.0, 260.0] });
+        twm.step_unsend();
+
+        let expected_pos = Point2::new(360.0 - vp_size.x as f64, 260.0 - vp_size.y as f64);
+        assert_eq!(sv.scroll_pos(), expected_pos);
+        assert_eq!(
+            content.global_frame().min,
+            vp_frame.min - Vector2::new(expected_pos.x as f32, expected_pos.y as f32)
+        );
+
+        // Already visible, so this shouldn't change the position
+        sv.scroll_to(box2! { min: [355.0, 255.0], max: [356.0, 256.0] });
+        assert_eq!(sv.scroll_pos(), expected_pos);
+
+        // Clamped to the scrollable range
+        sv.set_scroll_pos(Point2::new(1000.0, -10.0));
+        assert_eq!(sv.scroll_pos(), Point2::new(400.0 - vp_size.x as f64, 0.0));
+    }
+}
@@ -0,0 +1,696 @@
+//! Implements a selection model shared by the list-like widgets such as
+//! [`Table`] and [`ListView`].
+//!
+//! [`Table`]: crate::ui::views::table::Table
+//! [`ListView`]: crate::ui::views::ListView
+//!
+//! `SelectionModel` only tracks which items (usually rows) are selected. It
+//! doesn't know how they are displayed. The owner is responsible for
+//! forwarding user inputs (e.g., [`handle_click`] and [`perform_action`]) and
+//! for keeping the item indices in sync with the underlying data model (e.g.,
+//! [`insert`] and [`remove`]). [`ScrollableTable::set_selection`] and
+//! [`ListView::set_selection`] take care of the former.
+//!
+//! Selected items are supposed to be displayed with [`ClassSet::SELECTED`]
+//! (see [`SelectionModel::class_set`]). Since table cells are realized by
+//! [`TableModelQuery::new_view`], the usual way to reflect a change in the
+//! selection is to call [`TableModelEdit::renew_subviews`] with the range
+//! reported by the `changed` event, which `ScrollableTable` does
+//! automatically.
+//!
+//! [`ScrollableTable::set_selection`]: crate::ui::views::ScrollableTable::set_selection
+//! [`ListView::set_selection`]: crate::ui::views::ListView::set_selection
+//! [`handle_click`]: crate::ui::views::selection::SelectionModel::handle_click
+//! [`perform_action`]: crate::ui::views::selection::SelectionModel::perform_action
+//! [`insert`]: crate::ui::views::selection::SelectionModel::insert
+//! [`remove`]: crate::ui::views::selection::SelectionModel::remove
+//! [`ClassSet::SELECTED`]: crate::ui::theming::ClassSet::SELECTED
+//! [`SelectionModel::class_set`]: crate::ui::views::selection::SelectionModel::class_set
+//! [`TableModelQuery::new_view`]: crate::ui::views::table::TableModelQuery::new_view
+//! [`TableModelEdit::renew_subviews`]: crate::ui::views::table::TableModelEdit::renew_subviews
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    ops::Range,
+    rc::Rc,
+};
+
+use crate::{
+    pal,
+    pal::{actions, ActionId, ActionStatus},
+    ui::{theming::ClassSet, EventSource},
+    uicore::Sub,
+};
+
+/// Specifies how many items can be selected at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionMode {
+    /// Items can't be selected.
+    None,
+    /// At most one item can be selected.
+    Single,
+    /// Any number of items can be selected. Contiguous ranges can be selected
+    /// by extending the selection from the anchor item.
+    Multiple,
+}
+
+impl Default for SelectionMode {
+    fn default() -> Self {
+        Self::Single
+    }
+}
+
+/// Specifies the modifier key state of a click gesture passed to
+/// [`SelectionModel::handle_click`].
+///
+/// [`SelectionModel::handle_click`]: crate::ui::views::selection::SelectionModel::handle_click
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClickModifier {
+    /// Select the clicked item only.
+    None,
+    /// Select the items between the anchor and the clicked item.
+    /// Usually mapped to <kbd>Shift</kbd>.
+    Extend,
+    /// Toggle the selection state of the clicked item. Usually mapped to
+    /// <kbd>Ctrl</kbd> (<kbd>Command</kbd> on macOS).
+    Toggle,
+}
+
+/// Tracks the set of selected items in a list-like widget.
+///
+/// `SelectionModel` is a cheaply cloneable handle. Clones refer to the same
+/// selection state.
+///
+/// See [the module documentation](index.html) for more.
+#[derive(Clone)]
+pub struct SelectionModel {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    mode: Cell<SelectionMode>,
+    state: RefCell<State>,
+    changed_handlers: EventSource<dyn Fn(pal::Wm, Range<u64>)>,
+    /// The range of items affected by the changes that are not reported yet.
+    /// `Some(_)` means the calls to `changed_handlers` are pended.
+    pending_change: Cell<Option<Range<u64>>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct State {
+    /// The selected ranges. Sorted, non-empty, and non-adjacent.
+    ranges: Vec<Range<u64>>,
+    /// The starting point of a range selection.
+    anchor: Option<u64>,
+    /// The item that has moved most recently by a user interaction.
+    cursor: Option<u64>,
+}
+
+impl fmt::Debug for SelectionModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SelectionModel")
+            .field("mode", &self.inner.mode.get())
+            .field("state", &*self.inner.state.borrow())
+            .finish()
+    }
+}
+
+impl Default for SelectionModel {
+    fn default() -> Self {
+        Self::new(SelectionMode::default())
+    }
+}
+
+impl SelectionModel {
+    /// Construct a `SelectionModel` with no items selected.
+    pub fn new(mode: SelectionMode) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                mode: Cell::new(mode),
+                state: RefCell::new(State::default()),
+                changed_handlers: EventSource::new(),
+                pending_change: Cell::new(None),
+            }),
+        }
+    }
+
+    /// Get the selection mode.
+    pub fn mode(&self) -> SelectionMode {
+        self.inner.mode.get()
+    }
+
+    /// Set the selection mode. Excess selected items are deselected.
+    pub fn set_mode(&self, mode: SelectionMode) {
+        self.inner.mode.set(mode);
+        self.update(|state| match mode {
+            SelectionMode::None => *state = State::default(),
+            SelectionMode::Single => {
+                if state.len() > 1 {
+                    let keep = state.cursor.filter(|&i| state.contains(i));
+                    let keep = keep.unwrap_or(state.ranges[0].start);
+                    state.set_single(keep);
+                }
+            }
+            SelectionMode::Multiple => {}
+        });
+    }
+
+    /// Get a flag indicating whether the specified item is selected.
+    pub fn is_selected(&self, index: u64) -> bool {
+        self.inner.state.borrow().contains(index)
+    }
+
+    /// Get the styling classes for displaying the specified item.
+    ///
+    /// Returns `ClassSet::SELECTED` if the item is selected, or an empty set
+    /// otherwise.
+    pub fn class_set(&self, index: u64) -> ClassSet {
+        if self.is_selected(index) {
+            ClassSet::SELECTED
+        } else {
+            ClassSet::empty()
+        }
+    }
+
+    /// Get the selected ranges, sorted in ascending order.
+    pub fn selected_ranges(&self) -> Vec<Range<u64>> {
+        self.inner.state.borrow().ranges.clone()
+    }
+
+    /// Get the number of the selected items.
+    pub fn len(&self) -> u64 {
+        self.inner.state.borrow().len()
+    }
+
+    /// Get a flag indicating whether no items are selected.
+    pub fn is_empty(&self) -> bool {
+        self.inner.state.borrow().ranges.is_empty()
+    }
+
+    /// Get the index of the item that was interacted most recently, if any.
+    ///
+    /// The owner should scroll this item into view after performing
+    /// a keyboard action.
+    pub fn cursor(&self) -> Option<u64> {
+        self.inner.state.borrow().cursor
+    }
+
+    /// Get the starting point of a range selection, if any.
+    pub fn anchor(&self) -> Option<u64> {
+        self.inner.state.borrow().anchor
+    }
+
+    /// Deselect all items.
+    pub fn clear(&self) {
+        self.update(|state| *state = State::default());
+    }
+
+    /// Select the specified item only.
+    pub fn select(&self, index: u64) {
+        if self.mode() == SelectionMode::None {
+            return;
+        }
+        self.update(|state| state.set_single(index));
+    }
+
+    /// Select the specified range of items only. In `SelectionMode::Single`,
+    /// only the first item is selected.
+    pub fn select_range(&self, range: Range<u64>) {
+        if range.start >= range.end {
+            self.clear();
+            return;
+        }
+
+        match self.mode() {
+            SelectionMode::None => {}
+            SelectionMode::Single => self.select(range.start),
+            SelectionMode::Multiple => self.update(|state| {
+                state.anchor = Some(range.start);
+                state.cursor = Some(range.end - 1);
+                state.ranges = vec![range];
+            }),
+        }
+    }
+
+    /// Select all items. `len` specifies the number of items in the data
+    /// model. Does nothing unless the mode is `SelectionMode::Multiple`.
+    pub fn select_all(&self, len: u64) {
+        if self.mode() == SelectionMode::Multiple && len > 0 {
+            self.update(|state| {
+                state.ranges = vec![0..len];
+                if state.anchor.is_none() {
+                    state.anchor = Some(0);
+                }
+            });
+        }
+    }
+
+    /// Toggle the selection state of the specified item.
+    pub fn toggle(&self, index: u64) {
+        match self.mode() {
+            SelectionMode::None => {}
+            SelectionMode::Single => self.update(|state| {
+                if state.contains(index) {
+                    *state = State::default();
+                } else {
+                    state.set_single(index);
+                }
+            }),
+            SelectionMode::Multiple => self.update(|state| {
+                if state.contains(index) {
+                    state.remove_range(index..index + 1);
+                } else {
+                    state.add_range(index..index + 1);
+                }
+                state.anchor = Some(index);
+                state.cursor = Some(index);
+            }),
+        }
+    }
+
+    /// Select the items between the anchor and the specified item (inclusive),
+    /// replacing the current selection.
+    ///
+    /// If there's no anchor or the mode isn't `SelectionMode::Multiple`, this
+    /// behaves like [`select`].
+    ///
+    /// [`select`]: crate::ui::views::selection::SelectionModel::select
+    pub fn extend_to(&self, index: u64) {
+        let anchor = match (self.mode(), self.anchor()) {
+            (SelectionMode::Multiple, Some(anchor)) => anchor,
+            _ => return self.select(index),
+        };
+
+        self.update(|state| {
+            state.ranges = vec![anchor.min(index)..anchor.max(index) + 1];
+            state.cursor = Some(index);
+        });
+    }
+
+    /// Update the selection in response to a click on the specified item.
+    pub fn handle_click(&self, index: u64, modifier: ClickModifier) {
+        match modifier {
+            ClickModifier::None => self.select(index),
+            ClickModifier::Extend => self.extend_to(index),
+            ClickModifier::Toggle => self.toggle(index),
+        }
+    }
+
+    /// Check if the specified action can be handled by [`perform_action`].
+    /// The owner can forward [`ViewListener::validate_action`] to this
+    /// method.
+    ///
+    /// [`perform_action`]: crate::ui::views::selection::SelectionModel::perform_action
+    /// [`ViewListener::validate_action`]: crate::uicore::ViewListener::validate_action
+    pub fn validate_action(&self, action: ActionId) -> ActionStatus {
+        let mode = self.mode();
+        let valid = match action {
+            actions::MOVE_UP
+            | actions::MOVE_DOWN
+            | actions::MOVE_START_OF_DOCUMENT
+            | actions::MOVE_END_OF_DOCUMENT => mode != SelectionMode::None,
+            actions::MOVE_UP_SELECTING
+            | actions::MOVE_DOWN_SELECTING
+            | actions::MOVE_START_OF_DOCUMENT_SELECTING
+            | actions::MOVE_END_OF_DOCUMENT_SELECTING
+            | actions::SELECT_ALL => mode == SelectionMode::Multiple,
+            _ => false,
+        };
+
+        if valid {
+            ActionStatus::VALID | ActionStatus::ENABLED
+        } else {
+            ActionStatus::empty()
+        }
+    }
+
+    /// Update the selection in response to a keyboard action such as
+    /// `MOVE_DOWN` (<kbd>↓</kbd>) or `MOVE_DOWN_SELECTING`
+    /// (<kbd>Shift</kbd>+<kbd>↓</kbd>). `len` specifies the number of items in
+    /// the data model.
+    ///
+    /// Returns `false` if the action was not handled.
+    pub fn perform_action(&self, action: ActionId, len: u64) -> bool {
+        if !self.validate_action(action).contains(ActionStatus::ENABLED) {
+            return false;
+        }
+
+        if action == actions::SELECT_ALL {
+            self.select_all(len);
+            return true;
+        }
+
+        if len == 0 {
+            return true;
+        }
+
+        let cursor = self.cursor();
+        let last = len - 1;
+
+        let (target, extend) = match action {
+            actions::MOVE_UP => (cursor.map(|i| i.saturating_sub(1)).unwrap_or(last), false),
+            actions::MOVE_DOWN => (cursor.map(|i| (i + 1).min(last)).unwrap_or(0), false),
+            actions::MOVE_START_OF_DOCUMENT => (0, false),
+            actions::MOVE_END_OF_DOCUMENT => (last, false),
+            actions::MOVE_UP_SELECTING => {
+                (cursor.map(|i| i.saturating_sub(1)).unwrap_or(last), true)
+            }
+            actions::MOVE_DOWN_SELECTING => (cursor.map(|i| (i + 1).min(last)).unwrap_or(0), true),
+            actions::MOVE_START_OF_DOCUMENT_SELECTING => (0, true),
+            actions::MOVE_END_OF_DOCUMENT_SELECTING => (last, true),
+            _ => unreachable!(),
+        };
+
+        if extend {
+            if self.anchor().is_none() {
+                self.update(|state| state.anchor = cursor.or(Some(target)));
+            }
+            self.extend_to(target);
+        } else {
+            self.select(target);
+        }
+
+        true
+    }
+
+    /// Shift the item indices to reflect the insertion of new items at the
+    /// specified range of the data model. The new items are not selected.
+    pub fn insert(&self, range: Range<u64>) {
+        let count = range.end - range.start;
+        if count == 0 {
+            return;
+        }
+
+        let shift = |i: u64| if i >= range.start { i + count } else { i };
+
+        let mut state = self.inner.state.borrow_mut();
+        let mut new_ranges = Vec::with_capacity(state.ranges.len() + 1);
+        for r in state.ranges.iter() {
+            if r.start < range.start && r.end > range.start {
+                // The insertion point is inside this range - split it
+                new_ranges.push(r.start..range.start);
+                new_ranges.push(range.end..r.end + count);
+            } else if r.start >= range.start {
+                new_ranges.push(r.start + count..r.end + count);
+            } else {
+                new_ranges.push(r.clone());
+            }
+        }
+        state.ranges = new_ranges;
+        state.anchor = state.anchor.map(shift);
+        state.cursor = state.cursor.map(shift);
+    }
+
+    /// Shift the item indices to reflect the removal of the items in the
+    /// specified range of the data model.
+    ///
+    /// Raises the `changed` event if any of the removed items were selected.
+    pub fn remove(&self, range: Range<u64>) {
+        let count = range.end - range.start;
+        if count == 0 {
+            return;
+        }
+
+        let was_selected = {
+            let mut state = self.inner.state.borrow_mut();
+            let len_before = state.len();
+            state.remove_range(range.clone());
+            let was_selected = state.len() != len_before;
+
+            let shift = |i: u64| {
+                if i >= range.end {
+                    Some(i - count)
+                } else if i >= range.start {
+                    None
+                } else {
+                    Some(i)
+                }
+            };
+
+            let mut new_ranges: Vec<Range<u64>> = Vec::with_capacity(state.ranges.len());
+            for r in state.ranges.iter() {
+                let r = shift(r.start).unwrap()..shift(r.end).unwrap_or(range.start);
+                match new_ranges.last_mut() {
+                    // Two ranges became adjacent
+                    Some(last) if last.end == r.start => last.end = r.end,
+                    _ => new_ranges.push(r),
+                }
+            }
+            state.ranges = new_ranges;
+            state.anchor = state.anchor.and_then(shift);
+            state.cursor = state.cursor.and_then(shift);
+
+            was_selected
+        };
+
+        if was_selected {
+            // The removed items no longer exist, so report the item that took
+            // their place
+            self.pend_raise_changed(range.start..range.start + 1);
+        }
+    }
+
+    /// Register a handler function called when the selection is changed by
+    /// one of `SelectionModel`'s methods.
+    ///
+    /// The handler receives a range containing all items whose selection
+    /// states may have changed. The handler is called through
+    /// `invoke_on_update` and multiple changes may be reported at once.
+    pub fn subscribe_changed(&self, cb: Box<dyn Fn(pal::Wm, Range<u64>)>) -> Sub {
+        self.inner.changed_handlers.subscribe(cb)
+    }
+
+    /// Update `State` and raise the `changed` event if necessary.
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let changed = {
+            let mut state = self.inner.state.borrow_mut();
+            let old_ranges = state.ranges.clone();
+            let old_bounds = state.bounds();
+
+            f(&mut state);
+
+            if state.ranges == old_ranges {
+                None
+            } else {
+                union_range(old_bounds, state.bounds())
+            }
+        };
+
+        if let Some(changed) = changed {
+            self.pend_raise_changed(changed);
+        }
+    }
+
+    /// Pend calls to the `changed` event handlers.
+    fn pend_raise_changed(&self, range: Range<u64>) {
+        let pending = self.inner.pending_change.take();
+        let is_pending = pending.is_some();
+        self.inner
+            .pending_change
+            .set(union_range(pending, Some(range)));
+
+        if is_pending {
+            return;
+        }
+
+        let inner = Rc::downgrade(&self.inner);
+        pal::Wm::global().invoke_on_update(move |wm| {
+            if let Some(inner) = inner.upgrade() {
+                if let Some(range) = inner.pending_change.take() {
+                    inner
+                        .changed_handlers
+                        .raise_with(|handler| handler(wm, range.clone()));
+                }
+            }
+        });
+    }
+}
+
+fn union_range(x: Option<Range<u64>>, y: Option<Range<u64>>) -> Option<Range<u64>> {
+    match (x, y) {
+        (Some(x), Some(y)) => Some(x.start.min(y.start)..x.end.max(y.end)),
+        (x, None) => x,
+        (None, y) => y,
+    }
+}
+
+impl State {
+    fn len(&self) -> u64 {
+        self.ranges.iter().map(|r| r.end - r.start).sum()
+    }
+
+    fn bounds(&self) -> Option<Range<u64>> {
+        Some(self.ranges.first()?.start..self.ranges.last()?.end)
+    }
+
+    fn contains(&self, index: u64) -> bool {
+        match self.ranges.binary_search_by_key(&index, |r| r.start) {
+            Ok(_) => true,
+            // `self.ranges[i - 1]` is the last range starting before `index`
+            Err(i) => i > 0 && self.ranges[i - 1].end > index,
+        }
+    }
+
+    fn set_single(&mut self, index: u64) {
+        self.ranges = vec![index..index + 1];
+        self.anchor = Some(index);
+        self.cursor = Some(index);
+    }
+
+    fn add_range(&mut self, range: Range<u64>) {
+        let mut new_ranges = Vec::with_capacity(self.ranges.len() + 1);
+        let mut range = range;
+        let mut inserted = false;
+
+        for r in self.ranges.drain(..) {
+            if r.end < range.start {
+                new_ranges.push(r);
+            } else if r.start > range.end {
+                if !inserted {
+                    new_ranges.push(range.clone());
+                    inserted = true;
+                }
+                new_ranges.push(r);
+            } else {
+                // Overlapping or adjacent - merge
+                range = range.start.min(r.start)..range.end.max(r.end);
+            }
+        }
+
+        if !inserted {
+            new_ranges.push(range);
+        }
+
+        self.ranges = new_ranges;
+    }
+
+    fn remove_range(&mut self, range: Range<u64>) {
+        let mut new_ranges = Vec::with_capacity(self.ranges.len() + 1);
+
+        for r in self.ranges.drain(..) {
+            if r.start < range.start {
+                new_ranges.push(r.start..r.end.min(range.start));
+            }
+            if r.end > range.end {
+                new_ranges.push(r.start.max(range.end)..r.end);
+            }
+        }
+
+        self.ranges = new_ranges;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{prelude::*, use_testing_wm};
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn click(twm: &dyn TestingWm) {
+        let _ = twm;
+        let sel = SelectionModel::new(SelectionMode::Multiple);
+
+        sel.handle_click(3, ClickModifier::None);
+        assert_eq!(sel.selected_ranges(), vec![3..4]);
+
+        sel.handle_click(6, ClickModifier::Extend);
+        assert_eq!(sel.selected_ranges(), vec![3..7]);
+
+        sel.handle_click(1, ClickModifier::Extend);
+        assert_eq!(sel.selected_ranges(), vec![1..4]);
+
+        sel.handle_click(2, ClickModifier::Toggle);
+        assert_eq!(sel.selected_ranges(), vec![1..2, 3..4]);
+
+        sel.handle_click(2, ClickModifier::Toggle);
+        assert_eq!(sel.selected_ranges(), vec![1..4]);
+        assert_eq!(sel.len(), 3);
+
+        // Switching to the single selection mode keeps the cursor
+        sel.set_mode(SelectionMode::Single);
+        assert_eq!(sel.selected_ranges(), vec![2..3]);
+
+        sel.handle_click(5, ClickModifier::Extend);
+        assert_eq!(sel.selected_ranges(), vec![5..6]);
+        assert_eq!(sel.class_set(5), ClassSet::SELECTED);
+        assert_eq!(sel.class_set(4), ClassSet::empty());
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn keyboard(twm: &dyn TestingWm) {
+        let _ = twm;
+        let sel = SelectionModel::new(SelectionMode::Multiple);
+
+        assert!(sel.perform_action(actions::MOVE_DOWN, 10));
+        assert_eq!(sel.selected_ranges(), vec![0..1]);
+
+        assert!(sel.perform_action(actions::MOVE_DOWN_SELECTING, 10));
+        assert!(sel.perform_action(actions::MOVE_DOWN_SELECTING, 10));
+        assert_eq!(sel.selected_ranges(), vec![0..3]);
+
+        assert!(sel.perform_action(actions::MOVE_END_OF_DOCUMENT, 10));
+        assert_eq!(sel.selected_ranges(), vec![9..10]);
+
+        assert!(sel.perform_action(actions::MOVE_DOWN, 10));
+        assert_eq!(sel.selected_ranges(), vec![9..10]);
+
+        assert!(sel.perform_action(actions::SELECT_ALL, 10));
+        assert_eq!(sel.selected_ranges(), vec![0..10]);
+
+        assert!(!sel.perform_action(actions::COPY, 10));
+
+        sel.set_mode(SelectionMode::Single);
+        assert!(!sel.perform_action(actions::MOVE_DOWN_SELECTING, 10));
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn insert_remove(twm: &dyn TestingWm) {
+        let _ = twm;
+        let sel = SelectionModel::new(SelectionMode::Multiple);
+        sel.select_range(2..6);
+        sel.toggle(8);
+
+        sel.insert(4..6);
+        assert_eq!(sel.selected_ranges(), vec![2..4, 6..8, 10..11]);
+        assert_eq!(sel.cursor(), Some(10));
+
+        sel.remove(3..7);
+        assert_eq!(sel.selected_ranges(), vec![2..4, 6..7]);
+        assert_eq!(sel.cursor(), Some(6));
+
+        sel.remove(4..6);
+        assert_eq!(sel.selected_ranges(), vec![2..5]);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn changed_event(twm: &dyn TestingWm) {
+        let sel = SelectionModel::new(SelectionMode::Multiple);
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        {
+            let log = Rc::clone(&log);
+            sel.subscribe_changed(Box::new(move |_, range| {
+                log.borrow_mut().push(range);
+            }));
+        }
+
+        sel.select(3);
+        sel.select(5);
+        twm.step_unsend();
+
+        // The changes are coalesced
+        assert_eq!(*log.borrow(), vec![3..6]);
+
+        // No change
+        sel.clear();
+        twm.step_unsend();
+        log.borrow_mut().clear();
+        sel.clear();
+        twm.step_unsend();
+        assert!(log.borrow().is_empty());
+    }
+}
@@ -5,12 +5,14 @@ use std::{
     fmt,
     rc::{Rc, Weak},
 };
-use subscriber_list::SubscriberList;
 
 use super::{Dir, ScrollbarDragListener};
 use crate::{
     pal,
-    ui::theming::{self, ClassSet, HElem, Role, Widget},
+    ui::{
+        theming::{self, ClassSet, HElem, Role, Widget},
+        EventSource,
+    },
     uicore::{HView, HViewRef, Sub},
     utils::resetiter,
 };
@@ -80,6 +82,62 @@ impl SliderTraits for UniformStepSliderTraits {
     }
 }
 
+/// An implementation of `SliderTraits` that maps the slider's range `[0, 1]`
+/// to an application-defined range `[min, max]` and makes the value “snap” to
+/// multiples of `step` (counted from `min`) in that range.
+///
+/// `Slider`'s value is always in range `[0, 1]`. Use [`to_normalized`] and
+/// [`from_normalized`] to convert between the slider's value and the
+/// application's value.
+///
+/// [`to_normalized`]: StepSliderTraits::to_normalized
+/// [`from_normalized`]: StepSliderTraits::from_normalized
+#[derive(Debug, Clone, Copy)]
+pub struct StepSliderTraits {
+    min: f64,
+    max: f64,
+    step: f64,
+}
+
+impl StepSliderTraits {
+    /// Construct a `StepSliderTraits`.
+    ///
+    /// `min` must be less than `max`. `step` must be positive. A keyboard
+    /// increment moves the value by `step`.
+    pub fn new(min: f64, max: f64, step: f64) -> Self {
+        debug_assert!(min < max, "{} < {}", min, max);
+        debug_assert!(step > 0.0, "{} > 0.0", step);
+        Self { min, max, step }
+    }
+
+    /// Convert a value in range `[min, max]` to the slider's value in range
+    /// `[0, 1]`.
+    pub fn to_normalized(&self, value: f64) -> f64 {
+        ((value - self.min) / (self.max - self.min))
+            .fmax(0.0)
+            .fmin(1.0)
+    }
+
+    /// Convert the slider's value in range `[0, 1]` to a value in range
+    /// `[min, max]`.
+    pub fn from_normalized(&self, value: f64) -> f64 {
+        self.min + value * (self.max - self.min)
+    }
+}
+
+impl SliderTraits for StepSliderTraits {
+    fn filter_value(&self, value: f64) -> f64 {
+        let value = self.from_normalized(value);
+        let snapped = self.min + ((value - self.min) / self.step).round() * self.step;
+        self.to_normalized(snapped)
+    }
+
+    fn step(&self, value: f64, dir: Dir) -> f64 {
+        let value = self.from_normalized(value) + dir as i8 as f64 * self.step;
+        self.to_normalized(value)
+    }
+}
+
 // ---------------------------------------------------------------------------
 
 /// A high-level interface for [`SliderRaw`].
@@ -102,9 +160,9 @@ impl SliderTraits for UniformStepSliderTraits {
 ///
 /// Certain aspects of `Slider`'s behavior are specified by an associated
 /// [`SliderTraits`]. The default value [`SmoothSliderTraits`]`::new()` allows
-/// it to take any fractional value, whereas [`UniformStepSliderTraits`]
-/// restricts the movement to uniformly arranged points. `SliderTraits` can be
-/// assigned to a `Slider` by [`Slider::set_traits`].
+/// it to take any fractional value, whereas [`UniformStepSliderTraits`] and
+/// [`StepSliderTraits`] restrict the movement to uniformly arranged points.
+/// `SliderTraits` can be assigned to a `Slider` by [`Slider::set_traits`].
 #[derive(Debug)]
 pub struct Slider {
     shared: Rc<Shared>,
@@ -116,8 +174,8 @@ struct Shared {
 
     traits: RefCell<Box<dyn SliderTraits>>,
 
-    changed_handlers: RefCell<SubscriberList<Box<dyn Fn(pal::Wm)>>>,
-    changing_handlers: RefCell<SubscriberList<Box<dyn Fn(pal::Wm)>>>,
+    changed_handlers: EventSource,
+    changing_handlers: EventSource,
 
     /// The committed value.
     ///
@@ -152,8 +210,8 @@ impl Slider {
             wm,
             slider_raw,
             traits: RefCell::new(Box::new(SmoothSliderTraits::new())),
-            changed_handlers: RefCell::new(SubscriberList::new()),
-            changing_handlers: RefCell::new(SubscriberList::new()),
+            changed_handlers: EventSource::new(),
+            changing_handlers: EventSource::new(),
             value: Cell::new(0.0),
             drag_ticket: Cell::new(0),
         });
@@ -223,20 +281,12 @@ impl Slider {
 
     /// Add a function to be called whenever `value` changes.
     pub fn subscribe_changed(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
-        self.shared
-            .changed_handlers
-            .borrow_mut()
-            .insert(cb)
-            .untype()
+        self.shared.changed_handlers.subscribe(cb)
     }
 
     /// Add a function to be called whenever `uncommitted_value` changes.
     pub fn subscribe_changing(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
-        self.shared
-            .changing_handlers
-            .borrow_mut()
-            .insert(cb)
-            .untype()
+        self.shared.changing_handlers.subscribe(cb)
     }
 
     /// Set the tick mark positions.
@@ -324,16 +374,10 @@ impl Shared {
     }
 
     fn raise_changed(&self) {
-        let handlers = self.changed_handlers.borrow();
-        for handler in handlers.iter() {
-            handler(self.wm);
-        }
+        self.changed_handlers.raise(self.wm);
     }
     fn raise_changing(&self) {
-        let handlers = self.changing_handlers.borrow();
-        for handler in handlers.iter() {
-            handler(self.wm);
-        }
+        self.changing_handlers.raise(self.wm);
     }
 }
 
@@ -563,4 +607,31 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn step_traits() {
+        let t = StepSliderTraits::new(10.0, 20.0, 2.5);
+
+        assert_eq!(t.to_normalized(15.0), 0.5);
+        assert_eq!(t.from_normalized(0.5), 15.0);
+
+        // 13.0 snaps to 12.5
+        assert_eq!(
+            t.from_normalized(t.filter_value(t.to_normalized(13.0))),
+            12.5
+        );
+
+        assert_eq!(
+            t.from_normalized(t.step(t.to_normalized(15.0), Dir::Incr)),
+            17.5
+        );
+        assert_eq!(
+            t.from_normalized(t.step(t.to_normalized(15.0), Dir::Decr)),
+            12.5
+        );
+
+        // Clamped to `[min, max]`
+        assert_eq!(t.step(1.0, Dir::Incr), 1.0);
+        assert_eq!(t.step(0.0, Dir::Decr), 0.0);
+    }
 }
@@ -5,6 +5,7 @@ use cgmath::{Point2, Vector2};
 use std::{
     cell::{Cell, RefCell},
     fmt,
+    iter::once,
     rc::{Rc, Weak},
 };
 
@@ -14,15 +15,19 @@ use crate::{
     ui::{
         layouts::FillLayout,
         theming::{elem_id, ClassSet, Elem, HElem, Manager, StyledBox, Widget},
+        EventSource,
     },
     uicore::{
-        CursorShape, HView, HViewRef, Layout, LayoutCtx, MouseDragListener, SizeTraits, ViewFlags,
-        ViewListener,
+        CursorShape, HView, HViewRef, KeyEvent, Layout, LayoutCtx, MouseDragListener, SizeTraits,
+        Sub, ViewFlags, ViewListener,
     },
 };
 
 const SPLITTER_TOLERANCE: f32 = 5.0;
 
+/// The distance by which an arrow key moves the splitter.
+const KEY_STEP: f32 = 10.0;
+
 /// A widget dividing a rectangle into two resizable panels.
 ///
 /// # Rounding
@@ -31,12 +36,30 @@ const SPLITTER_TOLERANCE: f32 = 5.0;
 /// Consequently, the same goes for the second panel, provided that the overall
 /// size is an integer.
 ///
+/// # Collapsible Panels
+///
+/// A panel can be made *collapsible* by [`Split::set_collapsible`]. When the
+/// user drags the splitter past a collapsible panel's minimum size by more
+/// than [the snap threshold], the panel collapses (is hidden) while the
+/// splitter stays visible so that the user can drag it back out. A collapsed
+/// panel also can be expanded or collapsed programmatically by
+/// [`Split::set_collapsed`].
+///
+/// [the snap threshold]: Split::set_snap_threshold
+///
+/// # Keyboard
+///
+/// The splitter can receive a keyboard focus. The arrow keys along the split
+/// direction move the splitter, collapsing or expanding collapsible panels
+/// when the splitter reaches the limit.
+///
 /// # Styling
 ///
 ///  - `style_elem` - The wrapper. It doesn't support styling.
 ///  - `style_elem > #SPLITTER` — The splitter. The width is controlled by
 ///    `min_size`. The element has `.VERTICAL` if `vertical` is `true` (i.e.,
-///    the region is separated by a horizontal line).
+///    the region is separated by a horizontal line), and `.FOCUS` if the
+///    splitter has a keyboard focus.
 ///
 #[derive(Debug)]
 pub struct Split {
@@ -78,6 +101,15 @@ struct Shared {
     fix: Option<u8>,
     value: Cell<f32>,
     zoom: Cell<Option<u8>>,
+    collapsible: Cell<[bool; 2]>,
+    collapsed: Cell<Option<u8>>,
+    snap_threshold: Cell<f32>,
+    /// Layout feedback: the minimum sizes of the panels along the primary
+    /// axis. The value for a collapsed panel is the last known one.
+    panel_min: Rc<Cell<[f32; 2]>>,
+    /// Layout feedback: whether the splitter is at the minimum or maximum
+    /// position.
+    at_limit: Rc<Cell<[bool; 2]>>,
     elem: Elem,
     container: HView,
     splitter: HView,
@@ -85,6 +117,7 @@ struct Shared {
     subviews: RefCell<[HView; 2]>,
     subelements: Cell<[Option<HElem>; 2]>,
     on_drag: RefCell<DragHandler>,
+    changed_handlers: EventSource,
 }
 
 type DragHandler = Box<dyn Fn(pal::Wm) -> Box<dyn SplitDragListener>>;
@@ -96,12 +129,18 @@ impl fmt::Debug for Shared {
             .field("fix", &self.fix)
             .field("value", &self.value)
             .field("zoom", &self.zoom)
+            .field("collapsible", &self.collapsible)
+            .field("collapsed", &self.collapsed)
+            .field("snap_threshold", &self.snap_threshold)
+            .field("panel_min", &self.panel_min)
+            .field("at_limit", &self.at_limit)
             .field("elem", &self.elem)
             .field("container", &self.container)
             .field("splitter", &self.splitter)
             .field("subviews", &self.subviews)
             .field("subelements", &self.subelements)
             .field("on_drag", &())
+            .field("changed_handlers", &())
             .finish()
     }
 }
@@ -114,7 +153,9 @@ impl Split {
     /// `Some(0)`, `Some(1)`, and `None`.
     pub fn new(style_manager: &'static Manager, vertical: bool, fix: Option<usize>) -> Self {
         let container = HView::new(ViewFlags::default());
-        let splitter = HView::new(ViewFlags::ACCEPT_MOUSE_DRAG | ViewFlags::ACCEPT_MOUSE_OVER);
+        let splitter = HView::new(
+            ViewFlags::ACCEPT_MOUSE_DRAG | ViewFlags::ACCEPT_MOUSE_OVER | ViewFlags::TAB_STOP,
+        );
 
         let splitter_sb = StyledBox::new(style_manager, ViewFlags::default());
 
@@ -137,6 +178,11 @@ impl Split {
             },
             value: Cell::new(0.5),
             zoom: Cell::new(None),
+            collapsible: Cell::new([false; 2]),
+            collapsed: Cell::new(None),
+            snap_threshold: Cell::new(30.0),
+            panel_min: Rc::new(Cell::new([0.0; 2])),
+            at_limit: Rc::new(Cell::new([false; 2])),
             elem,
             container: container.clone(),
             splitter: splitter.clone(),
@@ -148,6 +194,7 @@ impl Split {
             ]),
             subelements: Cell::new([None, None]),
             on_drag: RefCell::new(Box::new(|_| Box::new(()))),
+            changed_handlers: EventSource::new(),
         });
 
         splitter.set_listener(SplitterListener {
@@ -220,6 +267,54 @@ impl Split {
         self.shared.set_zoom(new_zoom);
     }
 
+    /// Get the panel to zoom into.
+    pub fn zoom(&self) -> Option<u8> {
+        self.shared.zoom.get()
+    }
+
+    /// Specify which panels can be collapsed by the user. Defaults to
+    /// `[false, false]`.
+    ///
+    /// Making a panel non-collapsible doesn't expand it if it's already
+    /// collapsed.
+    pub fn set_collapsible(&self, new_value: [bool; 2]) {
+        self.shared.collapsible.set(new_value);
+    }
+
+    /// Get a flag array indicating which panels can be collapsed by the user.
+    pub fn collapsible(&self) -> [bool; 2] {
+        self.shared.collapsible.get()
+    }
+
+    /// Set the distance by which the user has to drag the splitter past a
+    /// collapsible panel's minimum size to collapse it. Defaults to `30.0`.
+    pub fn set_snap_threshold(&self, new_value: f32) {
+        self.shared.snap_threshold.set(new_value);
+    }
+
+    /// Get the snap threshold.
+    pub fn snap_threshold(&self) -> f32 {
+        self.shared.snap_threshold.get()
+    }
+
+    /// Collapse the specified panel. Defaults to `None` (no panels are
+    /// collapsed). The value must be one of `Some(0)`, `Some(1)`, and `None`.
+    ///
+    /// Unlike [`set_zoom`], the splitter remains visible. This method works
+    /// regardless of the value of [`collapsible`]. `value` is preserved, so the
+    /// panel regains the original size when expanded.
+    ///
+    /// [`set_zoom`]: self::Split::set_zoom
+    /// [`collapsible`]: self::Split::collapsible
+    pub fn set_collapsed(&self, new_collapsed: Option<u8>) {
+        self.shared.set_collapsed(new_collapsed);
+    }
+
+    /// Get the collapsed panel.
+    pub fn collapsed(&self) -> Option<u8> {
+        self.shared.collapsed.get()
+    }
+
     /// Set the views placed in the panels.
     pub fn set_subviews(&self, subviews: [HView; 2]) {
         *self.shared.subviews.borrow_mut() = subviews;
@@ -253,6 +348,18 @@ impl Split {
     pub fn set_on_drag(&self, handler: impl Fn(pal::Wm) -> Box<dyn SplitDragListener> + 'static) {
         *self.shared.on_drag.borrow_mut() = Box::new(handler);
     }
+
+    /// Add a function to be called when the user changes [`value`] or
+    /// [`collapsed`] by dragging the splitter or by using a keyboard.
+    ///
+    /// For a mouse drag gesture, the function is called once after the
+    /// gesture is complete. This can be used to persist the split position.
+    ///
+    /// [`value`]: self::Split::value
+    /// [`collapsed`]: self::Split::collapsed
+    pub fn subscribe_changed(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
+        self.shared.changed_handlers.subscribe(cb)
+    }
 }
 
 impl Widget for Split {
@@ -272,12 +379,18 @@ impl Shared {
         size - SPLITTER_TOLERANCE * 2.0
     }
 
+    /// Get the actual position of the splitter (the size of the first panel).
+    fn actual_pos(&self) -> f32 {
+        let axis_pri = self.vertical as usize;
+        self.splitter.frame().min[axis_pri] + SPLITTER_TOLERANCE
+    }
+
     /// Get `value` based on the actual position of the splitter.
     fn actual_value(&self) -> f32 {
         let axis_pri = self.vertical as usize;
 
         let size = self.container.frame().size()[axis_pri];
-        let pos = self.splitter.frame().min[axis_pri] + SPLITTER_TOLERANCE;
+        let pos = self.actual_pos();
 
         let splitter_width = self.splitter_width();
 
@@ -308,6 +421,70 @@ impl Shared {
         self.container.set_layout(self.layout());
     }
 
+    fn set_collapsed(&self, new_collapsed: Option<u8>) {
+        assert!(
+            matches!(new_collapsed, None | Some(0) | Some(1)),
+            "collapsed: index out of range"
+        );
+
+        if new_collapsed == self.collapsed.get() {
+            return;
+        }
+        self.collapsed.set(new_collapsed);
+
+        self.container.set_layout(self.layout());
+    }
+
+    /// Find a collapsible panel that should be collapsed if the splitter were
+    /// moved to `pos`.
+    fn panel_to_collapse(&self, pos: f32, threshold: f32) -> Option<u8> {
+        let axis_pri = self.vertical as usize;
+        let size = self.container.frame().size()[axis_pri];
+        let splitter_width = self.splitter_width();
+
+        let collapsible = self.collapsible.get();
+        let panel_min = self.panel_min.get();
+
+        if collapsible[0] && pos < panel_min[0] - threshold {
+            Some(0)
+        } else if collapsible[1] && size - splitter_width - pos < panel_min[1] - threshold {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    /// Move the splitter by one step in response to a key press. Returns
+    /// `false` if the splitter couldn't be moved any further.
+    fn step(&self, incr: bool) -> bool {
+        match (self.collapsed.get(), incr) {
+            (Some(0), true) | (Some(1), false) => {
+                // Expand the collapsed panel
+                self.set_collapsed(None);
+                true
+            }
+            (Some(_), _) => false,
+            (None, _) => {
+                let delta = if incr { KEY_STEP } else { -KEY_STEP };
+
+                if let Some(panel) = self.panel_to_collapse(self.actual_pos() + delta, 0.0) {
+                    self.set_collapsed(Some(panel));
+                    true
+                } else if self.at_limit.get()[incr as usize] {
+                    // The splitter is clamped by the panels' size traits
+                    false
+                } else {
+                    self.set_value(self.actual_value() + delta * self.dvalue_dposition());
+                    true
+                }
+            }
+        }
+    }
+
+    fn raise_changed(&self, wm: pal::Wm) {
+        self.changed_handlers.raise(wm);
+    }
+
     /// Calcuate the increase in `value` corresponding to a unit increase in
     /// X or Y coordinates.
     fn dvalue_dposition(&self) -> f32 {
@@ -333,15 +510,19 @@ impl Shared {
         if let Some(zoom) = self.zoom.get() {
             Box::new(FillLayout::new(subviews[zoom as usize].clone()))
         } else {
+            let collapsed = self.collapsed.get();
             Box::new(SplitLayout {
                 vertical: self.vertical,
                 fix: self.fix,
                 value: self.value.get(),
-                subviews: [
-                    subviews[0].clone(),
-                    subviews[1].clone(),
-                    self.splitter.clone(),
-                ],
+                collapsed,
+                subviews: (0..2)
+                    .filter(|&i| collapsed != Some(i as u8))
+                    .map(|i| subviews[i].clone())
+                    .chain(once(self.splitter.clone()))
+                    .collect(),
+                panel_min: Rc::clone(&self.panel_min),
+                at_limit: Rc::clone(&self.at_limit),
             })
         }
     }
@@ -380,7 +561,17 @@ struct SplitLayout {
     vertical: bool,
     fix: Option<u8>,
     value: f32,
-    subviews: [HView; 3],
+    collapsed: Option<u8>,
+    /// The visible panels followed by the splitter.
+    subviews: Vec<HView>,
+    panel_min: Rc<Cell<[f32; 2]>>,
+    at_limit: Rc<Cell<[bool; 2]>>,
+}
+
+impl SplitLayout {
+    fn splitter(&self) -> HViewRef<'_> {
+        self.subviews.last().unwrap().as_ref()
+    }
 }
 
 impl Layout for SplitLayout {
@@ -389,15 +580,30 @@ impl Layout for SplitLayout {
     }
 
     fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
-        let st1 = ctx.subview_size_traits(self.subviews[0].as_ref());
-        let st2 = ctx.subview_size_traits(self.subviews[1].as_ref());
-        let st_spl = ctx.subview_size_traits(self.subviews[2].as_ref());
+        let st_spl = ctx.subview_size_traits(self.splitter());
 
         let axis_pri = self.vertical as usize;
         let axis_sec = axis_pri ^ 1;
 
         let splitter_width = st_spl.min[axis_pri] - SPLITTER_TOLERANCE * 2.0;
 
+        if self.collapsed.is_some() {
+            let st1 = ctx.subview_size_traits(self.subviews[0].as_ref());
+
+            let mut st = st1;
+            st.min[axis_pri] = st1.min[axis_pri] + splitter_width;
+            st.max[axis_pri] = st1.max[axis_pri] + splitter_width;
+            st.preferred[axis_pri] = st1.preferred[axis_pri] + splitter_width;
+
+            st.min[axis_sec] = [st1.min[axis_sec], st_spl.min[axis_sec]].fmax();
+            st.max[axis_sec] = [st1.max[axis_sec], st_spl.max[axis_sec]].fmin();
+
+            return st;
+        }
+
+        let st1 = ctx.subview_size_traits(self.subviews[0].as_ref());
+        let st2 = ctx.subview_size_traits(self.subviews[1].as_ref());
+
         let mut st = st1;
         let extra = splitter_width;
         st.min[axis_pri] = st1.min[axis_pri].ceil() + st2.min[axis_pri] + extra;
@@ -414,39 +620,71 @@ impl Layout for SplitLayout {
     }
 
     fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
-        let st1 = ctx.subview_size_traits(self.subviews[0].as_ref());
-        let st2 = ctx.subview_size_traits(self.subviews[1].as_ref());
-        let st_spl = ctx.subview_size_traits(self.subviews[2].as_ref());
+        let st_spl = ctx.subview_size_traits(self.splitter());
 
         let axis_pri = self.vertical as usize;
 
         let splitter_width = st_spl.min[axis_pri] - SPLITTER_TOLERANCE * 2.0;
 
-        let (pos, at_min, at_max) = get_split_position(
-            size[axis_pri],
-            self.fix,
-            self.value,
-            [st1.min[axis_pri], st2.min[axis_pri]],
-            [st1.max[axis_pri], st2.max[axis_pri]],
-            splitter_width,
-        );
-
-        // Arrange the panels
         let mut frame1 = box2! { top_left: [0.0, 0.0].into(), size: size };
-        let mut frame2 = frame1;
         let mut spl_frame = frame1;
 
-        frame1.max[axis_pri] = pos;
-        frame2.min[axis_pri] = pos + splitter_width;
+        let (pos, at_min, at_max);
+
+        if let Some(collapsed) = self.collapsed {
+            // Only one panel is visible
+            let st = ctx.subview_size_traits(self.subviews[0].as_ref());
+
+            let mut panel_min = self.panel_min.get();
+            panel_min[collapsed as usize ^ 1] = st.min[axis_pri];
+            self.panel_min.set(panel_min);
 
-        ctx.set_subview_frame(self.subviews[0].as_ref(), frame1);
-        ctx.set_subview_frame(self.subviews[1].as_ref(), frame2);
+            if collapsed == 0 {
+                pos = 0.0;
+                frame1.min[axis_pri] = splitter_width;
+            } else {
+                pos = size[axis_pri] - splitter_width;
+                frame1.max[axis_pri] = pos;
+            }
+            at_min = collapsed == 0;
+            at_max = collapsed == 1;
+
+            ctx.set_subview_frame(self.subviews[0].as_ref(), frame1);
+        } else {
+            let st1 = ctx.subview_size_traits(self.subviews[0].as_ref());
+            let st2 = ctx.subview_size_traits(self.subviews[1].as_ref());
+
+            self.panel_min.set([st1.min[axis_pri], st2.min[axis_pri]]);
+
+            let result = get_split_position(
+                size[axis_pri],
+                self.fix,
+                self.value,
+                [st1.min[axis_pri], st2.min[axis_pri]],
+                [st1.max[axis_pri], st2.max[axis_pri]],
+                splitter_width,
+            );
+            pos = result.0;
+            at_min = result.1;
+            at_max = result.2;
+
+            // Arrange the panels
+            let mut frame2 = frame1;
+
+            frame1.max[axis_pri] = pos;
+            frame2.min[axis_pri] = pos + splitter_width;
+
+            ctx.set_subview_frame(self.subviews[0].as_ref(), frame1);
+            ctx.set_subview_frame(self.subviews[1].as_ref(), frame2);
+        }
 
         // Arrange the splitter
         spl_frame.min[axis_pri] = pos - SPLITTER_TOLERANCE;
         spl_frame.max[axis_pri] = pos + splitter_width + SPLITTER_TOLERANCE;
 
-        ctx.set_subview_frame(self.subviews[2].as_ref(), spl_frame);
+        ctx.set_subview_frame(self.splitter(), spl_frame);
+
+        self.at_limit.set([at_min, at_max]);
 
         // Set the cursor shape. It's dependent on whether the splitter position
         // is at a limit or not.
@@ -462,7 +700,7 @@ impl Layout for SplitLayout {
                 [CursorShape::SResize, CursorShape::Default],
             ],
         ];
-        self.subviews[2].set_cursor_shape(Some(
+        self.splitter().set_cursor_shape(Some(
             shape_map[self.vertical as usize][at_min as usize][at_max as usize],
         ));
     }
@@ -481,6 +719,43 @@ struct SplitterListener {
 }
 
 impl ViewListener for SplitterListener {
+    fn key_down(&self, wm: pal::Wm, _: HViewRef<'_>, e: &KeyEvent<'_>) -> bool {
+        let shared = if let Some(shared) = self.shared.upgrade() {
+            shared
+        } else {
+            return false;
+        };
+
+        let is_view_vertical = if shared.vertical {
+            ACTION_BIT_VERTICAL
+        } else {
+            0
+        };
+        match e.translate_accel(&ACCEL_TABLE) {
+            Some(action) if (action & ACTION_BIT_VERTICAL) == is_view_vertical => {
+                if shared.step((action & ACTION_BIT_INCR) != 0) {
+                    shared.raise_changed(wm);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn focus_enter(&self, _: pal::Wm, _: HViewRef<'_>) {
+        if let Some(shared) = self.shared.upgrade() {
+            let sb = &shared.splitter_sb;
+            sb.set_class_set(sb.class_set() | ClassSet::FOCUS);
+        }
+    }
+
+    fn focus_leave(&self, _: pal::Wm, _: HViewRef<'_>) {
+        if let Some(shared) = self.shared.upgrade() {
+            let sb = &shared.splitter_sb;
+            sb.set_class_set(sb.class_set() - ClassSet::FOCUS);
+        }
+    }
+
     fn mouse_drag(
         &self,
         wm: pal::Wm,
@@ -497,6 +772,7 @@ impl ViewListener for SplitterListener {
                 shared: Weak::clone(&self.shared),
                 drag: RefCell::new(None),
                 orig_value: shared.value.get(),
+                orig_collapsed: shared.collapsed.get(),
                 user_listener,
             })
         } else {
@@ -505,21 +781,44 @@ impl ViewListener for SplitterListener {
     }
 }
 
+const ACTION_BIT_VERTICAL: pal::ActionId = 1;
+const ACTION_BIT_INCR: pal::ActionId = 1 << 1;
+const ACTION_LEFT: pal::ActionId = 0;
+const ACTION_RIGHT: pal::ActionId = ACTION_BIT_INCR;
+const ACTION_UP: pal::ActionId = ACTION_BIT_VERTICAL;
+const ACTION_DOWN: pal::ActionId = ACTION_BIT_VERTICAL | ACTION_BIT_INCR;
+
+static ACCEL_TABLE: pal::AccelTable = pal::accel_table![
+    (ACTION_LEFT, windows("Left"), macos("Left"), gtk("Left")),
+    (ACTION_RIGHT, windows("Right"), macos("Right"), gtk("Right")),
+    (ACTION_UP, windows("Up"), macos("Up"), gtk("Up")),
+    (ACTION_DOWN, windows("Down"), macos("Down"), gtk("Down")),
+];
+
 struct SplitterDragListener {
     shared: Weak<Shared>,
     drag: RefCell<Option<DragState>>,
     orig_value: f32,
+    orig_collapsed: Option<u8>,
     user_listener: Box<dyn SplitDragListener>,
 }
 
 #[derive(Clone)]
 struct DragState {
     start_value: f32,
+    start_pos: f32,
     start_mouse_loc: f32,
 }
 
 impl MouseDragListener for SplitterDragListener {
-    fn mouse_down(&self, wm: pal::Wm, _: HViewRef<'_>, loc: Point2<f32>, button: u8) {
+    fn mouse_down(
+        &self,
+        wm: pal::Wm,
+        _: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+        _click_count: u32,
+    ) {
         if let Some(shared) = self.shared.upgrade() {
             if button == 0 {
                 let axis_pri = shared.vertical as usize;
@@ -528,6 +827,7 @@ impl MouseDragListener for SplitterDragListener {
 
                 *self.drag.borrow_mut() = Some(DragState {
                     start_value: actual_value,
+                    start_pos: shared.actual_pos(),
                     start_mouse_loc: loc[axis_pri],
                 });
 
@@ -539,13 +839,28 @@ impl MouseDragListener for SplitterDragListener {
     fn mouse_motion(&self, wm: pal::Wm, _: HViewRef<'_>, loc: Point2<f32>) {
         if let (Some(shared), Some(drag)) = (self.shared.upgrade(), self.drag.borrow().clone()) {
             let axis_pri = shared.vertical as usize;
+            let delta = loc[axis_pri] - drag.start_mouse_loc;
+
+            let threshold = shared.snap_threshold.get();
+            if let Some(panel) = shared.panel_to_collapse(drag.start_pos + delta, threshold) {
+                // Snap to the collapsed state. Revert `value` so that the panel
+                // regains the original size when expanded.
+                let orig_value = self.orig_value;
+
+                wm.invoke(move |_| {
+                    shared.set_value(orig_value);
+                    shared.set_collapsed(Some(panel));
+                });
+                return;
+            }
 
             let dfdx = shared.dvalue_dposition();
-            let new_value = drag.start_value + (loc[axis_pri] - drag.start_mouse_loc) * dfdx;
+            let new_value = drag.start_value + delta * dfdx;
 
             let new_value = self.user_listener.motion(wm, new_value);
 
             wm.invoke(move |_| {
+                shared.set_collapsed(None);
                 shared.set_value(new_value);
             });
         }
@@ -556,6 +871,18 @@ impl MouseDragListener for SplitterDragListener {
             *self.drag.borrow_mut() = None;
 
             self.user_listener.up(wm);
+
+            if let Some(shared) = self.shared.upgrade() {
+                let (orig_value, orig_collapsed) = (self.orig_value, self.orig_collapsed);
+
+                // Wait until the changes made by `mouse_motion` are applied
+                wm.invoke(move |wm| {
+                    if shared.value.get() != orig_value || shared.collapsed.get() != orig_collapsed
+                    {
+                        shared.raise_changed(wm);
+                    }
+                });
+            }
         }
     }
 
@@ -564,11 +891,150 @@ impl MouseDragListener for SplitterDragListener {
             self.user_listener.cancel(wm);
 
             // Restore the original value
-            let orig_value = self.orig_value;
+            let (orig_value, orig_collapsed) = (self.orig_value, self.orig_collapsed);
 
             wm.invoke(move |_| {
                 shared.set_value(orig_value);
+                shared.set_collapsed(orig_collapsed);
             });
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use try_match::try_match;
+
+    use super::*;
+    use crate::{
+        pal,
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::EmptyLayout,
+        uicore::HWnd,
+    };
+
+    fn make_wnd(twm: &dyn TestingWm) -> (Split, HWnd, pal::HWnd, Rc<Cell<usize>>) {
+        let wm = twm.wm();
+
+        let split = Split::new(Manager::global(wm), false, Some(0));
+        split.set_value(100.0);
+
+        let new_panel = || {
+            let view = HView::new(ViewFlags::default());
+            view.set_layout(EmptyLayout::new(SizeTraits {
+                min: [50.0; 2].into(),
+                ..SizeTraits::default()
+            }));
+            view
+        };
+        split.set_subviews([new_panel(), new_panel()]);
+
+        let num_changed = Rc::new(Cell::new(0));
+        {
+            let num_changed = Rc::clone(&num_changed);
+            split.subscribe_changed(Box::new(move |_| num_changed.set(num_changed.get() + 1)));
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(split.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+            .expect("could not get a single window");
+        twm.set_wnd_size(&pal_hwnd, [400, 100]);
+        twm.step_unsend();
+
+        (split, wnd, pal_hwnd, num_changed)
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn keyboard_step(twm: &dyn TestingWm) {
+        let (split, _hwnd, pal_hwnd, num_changed) = make_wnd(twm);
+
+        split.shared.splitter.focus();
+        twm.step_unsend();
+
+        twm.simulate_key(&pal_hwnd, "windows", "Right");
+        twm.step_unsend();
+        assert_eq!(split.value(), 110.0);
+        assert_eq!(num_changed.get(), 1);
+
+        twm.simulate_key(&pal_hwnd, "windows", "Left");
+        twm.step_unsend();
+        assert_eq!(split.value(), 100.0);
+        assert_eq!(num_changed.get(), 2);
+
+        // Up/Down are ignored by a horizontal split
+        twm.simulate_key(&pal_hwnd, "windows", "Down");
+        twm.step_unsend();
+        assert_eq!(split.value(), 100.0);
+        assert_eq!(num_changed.get(), 2);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn keyboard_step_clamped(twm: &dyn TestingWm) {
+        let (split, _hwnd, pal_hwnd, num_changed) = make_wnd(twm);
+
+        split.shared.splitter.focus();
+        twm.step_unsend();
+
+        // Move the splitter until the first panel reaches its minimum size
+        for _ in 0..5 {
+            twm.simulate_key(&pal_hwnd, "windows", "Left");
+            twm.step_unsend();
+        }
+        assert_eq!(split.value(), 50.0);
+        assert_eq!(num_changed.get(), 5);
+
+        // The splitter can't move any further, so `changed` isn't raised
+        twm.simulate_key(&pal_hwnd, "windows", "Left");
+        twm.step_unsend();
+        assert_eq!(split.value(), 50.0);
+        assert_eq!(num_changed.get(), 5);
+
+        // It can still move in the other direction
+        twm.simulate_key(&pal_hwnd, "windows", "Right");
+        twm.step_unsend();
+        assert_eq!(split.value(), 60.0);
+        assert_eq!(num_changed.get(), 6);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn collapse_by_drag(twm: &dyn TestingWm) {
+        let (split, _hwnd, pal_hwnd, num_changed) = make_wnd(twm);
+        split.set_collapsible([true, false]);
+
+        let [x, y]: [f32; 2] = split.shared.splitter.global_frame().mid().into();
+
+        // Drag the splitter past the first panel's minimum size by more than
+        // the snap threshold
+        let drag = twm.raise_mouse_drag(&pal_hwnd, [x, y].into(), 0);
+        drag.mouse_down([x, y].into(), 0);
+        twm.step_unsend();
+        drag.mouse_motion([x - 90.0, y].into());
+        twm.step_unsend();
+        drag.mouse_up([x - 90.0, y].into(), 0);
+        twm.step_unsend();
+
+        assert_eq!(split.collapsed(), Some(0));
+        assert_eq!(num_changed.get(), 1);
+
+        // The original size is preserved
+        assert_eq!(split.value(), 100.0);
+
+        // Expand it using a keyboard
+        split.shared.splitter.focus();
+        twm.step_unsend();
+        twm.simulate_key(&pal_hwnd, "windows", "Right");
+        twm.step_unsend();
+
+        assert_eq!(split.collapsed(), None);
+        assert_eq!(split.value(), 100.0);
+        assert_eq!(num_changed.get(), 2);
+    }
+}
@@ -99,6 +99,21 @@
 //! [`Table::edit`]: crate::ui::views::table::Table::edit
 //! [`TableEdit`]: crate::ui::views::table::TableEdit
 //!
+//! ## Frozen lines
+//!
+//! A specified number of leading rows and/or columns can be *frozen* by
+//! [`TableEdit::set_num_frozen_lines`]. Frozen lines are always displayed at
+//! the left/top edge of the table view regardless of the scroll position, and
+//! other lines scroll under them. For this reason, the cells in frozen lines
+//! are drawn over other cells and should have an opaque background. This is
+//! useful for implementing column headers and spreadsheet-like user
+//! interfaces.
+//!
+//! Frozen lines are not excluded from the scrollable region. When the scroll
+//! position is zero, every line is displayed at its natural position.
+//!
+//! [`TableEdit::set_num_frozen_lines`]: crate::ui::views::table::TableEdit::set_num_frozen_lines
+//!
 use as_any::AsAny;
 use bitflags::bitflags;
 use cgmath::Vector2;
@@ -212,6 +227,15 @@ struct State {
     /// has cells from the 0–3rd columns.
     cells_ranges: [Range<Index>; 2],
 
+    /// The number of frozen lines represented by `cells`.
+    ///
+    /// In addition to `cells_ranges`, `cells` includes frozen lines, which
+    /// precede the lines in `cells_ranges`. E.g., if `cells_ranges` is
+    /// `[0..4, 3..7]` and `cells_frozen` is `[0, 1]`, `cells` has cells from
+    /// the 0th, 3rd–6th rows. `cells_ranges[i].start >= cells_frozen[i]`
+    /// always holds.
+    cells_frozen: [Index; 2],
+
     /// Used during remapping (the change of the range represented by `cells`).
     /// Logically it only lives during each run of remapping, but is stored
     /// as a part of `State` for optimization.
@@ -229,6 +253,10 @@ struct State {
 
     /// Display offset - see `TableEdit::set_display_offset`.
     display_offset: VpPos,
+
+    /// The requested number of frozen lines - see
+    /// `TableEdit::set_num_frozen_lines`.
+    num_frozen: [u64; 2],
 }
 
 impl fmt::Debug for State {
@@ -236,10 +264,12 @@ impl fmt::Debug for State {
         f.debug_struct("State")
             .field("model_query", &((&*self.model_query) as *const _))
             .field("cells", &self.cells)
+            .field("cells_frozen", &self.cells_frozen)
             .field("line_idx_maps", &self.line_idx_maps)
             .field("linesets", &self.linesets)
             .field("vp_set", &self.vp_set)
             .field("display_offset", &self.display_offset)
+            .field("num_frozen", &self.num_frozen)
             .finish()
     }
 }
@@ -500,10 +530,12 @@ impl Table {
                 model_query: Box::new(()),
                 cells: Array2::from_shape_fn((0, 0), |_| unreachable!()),
                 cells_ranges: [0..0, 0..0],
+                cells_frozen: [0, 0],
                 line_idx_maps: [LineIdxMap::new(0..0), LineIdxMap::new(0..0)],
                 linesets: [Lineset::new(), Lineset::new()],
                 vp_set: VpSet::new(),
                 display_offset: [0.0; 2],
+                num_frozen: [0, 0],
             }),
             size: Cell::new(Vector2::new(0, 0)),
             size_traits: Cell::new(SizeTraits::default()),
@@ -624,6 +656,7 @@ impl Table {
 
 mod edit;
 mod fixedpoint;
+mod header;
 mod listener;
 mod scrollable;
 pub mod scrollbar;
@@ -631,4 +664,113 @@ pub mod scrollwheel;
 mod update;
 
 pub use self::edit::TableEdit;
+pub use self::header::{HeaderColumn, SortOrder, TableHeader};
 pub use self::scrollable::ScrollableTable;
+
+#[cfg(test)]
+mod tests {
+    use cggeom::box2;
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::{layouts::AbsLayout, AlignFlags},
+        uicore::HWnd,
+    };
+
+    /// Remembers the most recently created view for each cell.
+    struct RecordingModel {
+        views: Rc<RefCell<HashMap<CellIdx, HView>>>,
+    }
+
+    impl TableModelQuery for RecordingModel {
+        fn new_view(&mut self, cell: CellIdx) -> (HView, Box<dyn CellCtrler>) {
+            let view = HView::new(Default::default());
+            self.views.borrow_mut().insert(cell, view.clone());
+            (view, Box::new(()))
+        }
+
+        fn range_size(&mut self, _line_ty: LineTy, range: Range<u64>, _approx: bool) -> f64 {
+            10.0 * (range.end - range.start) as f64
+        }
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn frozen_lines(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let views = Rc::new(RefCell::new(HashMap::new()));
+        let table = Table::new();
+        {
+            let mut edit = table.edit().unwrap();
+            edit.set_model(RecordingModel {
+                views: Rc::clone(&views),
+            });
+            edit.insert(LineTy::Col, 0..100);
+            edit.insert(LineTy::Row, 0..100);
+            edit.set_num_frozen_lines(LineTy::Col, 2);
+            edit.set_num_frozen_lines(LineTy::Row, 1);
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(AbsLayout::new(
+            SizeTraits {
+                min: [50.0; 2].into(),
+                max: [50.0; 2].into(),
+                preferred: [50.0; 2].into(),
+            },
+            Some((
+                table.view(),
+                box2! { min: [0.0, 0.0], max: [50.0, 50.0] },
+                AlignFlags::JUSTIFY,
+            )),
+        ));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        {
+            let mut edit = table.edit().unwrap();
+            assert_eq!(edit.num_frozen_lines(LineTy::Col), 2);
+            assert_eq!(edit.num_frozen_lines(LineTy::Row), 1);
+            edit.set_scroll_pos([100.0, 200.0]);
+        }
+        twm.step_unsend();
+
+        let frame = |cell: CellIdx| views.borrow()[&cell].global_frame();
+
+        // Hit testing
+        {
+            let edit = table.edit().unwrap();
+            assert_eq!(edit.num_lines(LineTy::Col), 100);
+            assert_eq!(edit.line_at_pos(LineTy::Col, 5.0), Some(0));
+            assert_eq!(edit.line_at_pos(LineTy::Col, 15.0), Some(1));
+            assert_eq!(edit.line_at_pos(LineTy::Col, 25.0), Some(12));
+            assert_eq!(edit.line_at_pos(LineTy::Row, 5.0), Some(0));
+            assert_eq!(edit.line_at_pos(LineTy::Row, 25.0), Some(22));
+            assert_eq!(edit.line_at_pos(LineTy::Row, -1.0), None);
+            assert_eq!(edit.line_at_pos(LineTy::Row, 1000.0), None);
+        }
+
+        // Frozen in both axes
+        assert_eq!(frame([0, 0]), box2! { min: [0.0, 0.0], max: [10.0, 10.0] });
+        assert_eq!(frame([1, 0]), box2! { min: [10.0, 0.0], max: [20.0, 10.0] });
+
+        // Frozen in one axis
+        assert_eq!(
+            frame([12, 0]),
+            box2! { min: [20.0, 0.0], max: [30.0, 10.0] }
+        );
+        assert_eq!(
+            frame([0, 22]),
+            box2! { min: [0.0, 20.0], max: [10.0, 30.0] }
+        );
+
+        // Not frozen
+        assert_eq!(
+            frame([12, 22]),
+            box2! { min: [20.0, 20.0], max: [30.0, 30.0] }
+        );
+    }
+}
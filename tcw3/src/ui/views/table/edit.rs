@@ -81,6 +81,26 @@ impl TableEdit<'_> {
         self.inner.set_dirty_flags(DirtyFlags::LAYOUT);
     }
 
+    /// Get the number of frozen lines for the specified axis.
+    pub fn num_frozen_lines(&self, line_ty: LineTy) -> u64 {
+        self.state.num_frozen[line_ty.i()]
+    }
+
+    /// Set the number of frozen lines for the specified axis.
+    ///
+    /// The first `count` lines along the axis stay visible regardless of the
+    /// scroll position, and the other lines scroll under them. See [the module
+    /// documentation](crate::ui::views::table) for more.
+    ///
+    /// `count` may exceed the number of lines, in which case all lines are
+    /// frozen.
+    pub fn set_num_frozen_lines(&mut self, line_ty: LineTy, count: u64) {
+        if self.state.num_frozen[line_ty.i()] != count {
+            self.state.num_frozen[line_ty.i()] = count;
+            self.inner.set_dirty_flags(DirtyFlags::CELLS);
+        }
+    }
+
     /// Add a new pinned viewport.
     ///
     /// `pos[i]` is automatically clamped to range `0.0..scroll_limit()[i]`.
@@ -153,7 +173,58 @@ impl TableEdit<'_> {
         max(0, content_size - vp_size)
     }
 
-    // TODO: Methods for querying the position of lines
+    /// Get the number of lines.
+    pub fn num_lines(&self, line_ty: LineTy) -> u64 {
+        self.state.linesets[line_ty.i()].num_lines() as u64
+    }
+
+    /// Find the line at the specified coordinate, measured from the
+    /// left/top edge of the table view. The scroll position, the display
+    /// offset, and frozen lines are taken into account.
+    ///
+    /// Returns `None` if there's no line at the coordinate. The result is only
+    /// accurate for the lines in the viewport because the sizes of off-screen
+    /// lines may be approximate.
+    pub fn line_at_pos(&self, line_ty: LineTy, pos: f64) -> Option<u64> {
+        let lineset = &self.state.linesets[line_ty.i()];
+
+        let num_frozen = min(
+            self.state.num_frozen[line_ty.i()],
+            lineset.num_lines() as u64,
+        );
+        let frozen_end = if num_frozen > 0 {
+            lineset.range_by_index(0..num_frozen as Index).2.end
+        } else {
+            0
+        };
+
+        let mut pos = fp_to_fix(pos);
+        if pos < 0 {
+            return None;
+        }
+        if pos >= frozen_end {
+            pos += fp_to_fix(self.scroll_pos()[line_ty.i()] + self.display_offset()[line_ty.i()]);
+        }
+        if pos >= lineset.total_size() {
+            return None;
+        }
+
+        let (line_grs, line_grs_range_idx, line_grs_range_pos) = lineset.range(pos..pos + 1);
+
+        let mut i = line_grs_range_idx.start;
+        let mut gr_pos = line_grs_range_pos.start;
+        for (size, num_lines) in line_grs {
+            if pos < gr_pos + size {
+                // Line groups outside the viewport may contain multiple lines
+                let offset = ((pos - gr_pos) * num_lines / max(size, 1)).min(num_lines - 1);
+                return Some((i + offset) as u64);
+            }
+            gr_pos += size;
+            i += num_lines;
+        }
+
+        None
+    }
 }
 
 impl TableModelEdit for TableEdit<'_> {
@@ -0,0 +1,652 @@
+//! Column headers for `Table`.
+use alt_fp::FloatOrd;
+use cggeom::box2;
+use cgmath::{Point2, Vector2};
+use std::{
+    cell::{Cell, RefCell},
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    pal,
+    pal::CursorShape,
+    ui::{
+        theming::{elem_id, roles, ClassSet, HElem, Manager, Role, StyledBox, Widget},
+        views::Button,
+        EventSource,
+    },
+    uicore::{
+        HView, HViewRef, Layout, LayoutCtx, MouseDragListener, SizeTraits, Sub, ViewFlags,
+        ViewListener,
+    },
+};
+
+/// The width of the drag handle placed at the right edge of each column.
+const HANDLE_WIDTH: f32 = 6.0;
+
+/// The styling element role of the first column header.
+const COLUMN_ROLE_START: Role = 0x1000;
+
+/// Specifies the sorting order indicated by [`TableHeader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+impl SortOrder {
+    /// Get the opposite order.
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Ascending => Self::Descending,
+            Self::Descending => Self::Ascending,
+        }
+    }
+}
+
+/// Describes a column displayed by [`TableHeader`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderColumn {
+    pub title: String,
+    pub width: f32,
+    pub min_width: f32,
+}
+
+impl HeaderColumn {
+    /// Construct a `HeaderColumn`.
+    pub fn new(title: impl Into<String>, width: f32) -> Self {
+        Self {
+            title: title.into(),
+            width,
+            min_width: HANDLE_WIDTH,
+        }
+    }
+
+    /// Update `min_width` with a new value, consuming `self`.
+    pub fn with_min_width(self, min_width: f32) -> Self {
+        Self { min_width, ..self }
+    }
+}
+
+/// A row of clickable column headers displayed above a [`Table`].
+///
+/// `TableHeader` doesn't own the table model. The application is responsible
+/// for making the column widths in `TableModelQuery` match the widths
+/// returned by [`column_widths`]. When the user resizes a column, the
+/// `column_resized` event is raised, and the application should respond by
+/// updating `TableModelQuery` and calling [`TableModelEdit::resize`].
+///
+/// The header doesn't scroll vertically. When attached to a
+/// [`ScrollableTable`] by [`ScrollableTable::set_header`], it follows the
+/// horizontal scroll position of the table.
+///
+/// [`Table`]: crate::ui::views::table::Table
+/// [`column_widths`]: crate::ui::views::table::TableHeader::column_widths
+/// [`TableModelEdit::resize`]: crate::ui::views::table::TableModelEdit::resize
+/// [`ScrollableTable`]: crate::ui::views::table::ScrollableTable
+/// [`ScrollableTable::set_header`]: crate::ui::views::table::ScrollableTable::set_header
+#[derive(Debug, Clone)]
+pub struct TableHeader {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    style_manager: &'static Manager,
+    styled_box: StyledBox,
+    cells_view: HView,
+    columns: RefCell<Vec<Column>>,
+    scroll_offset: Cell<f32>,
+    sort_indicator: Cell<Option<(usize, SortOrder)>>,
+    sort_handlers: EventSource<dyn Fn(pal::Wm, usize, SortOrder)>,
+    resize_handlers: EventSource<dyn Fn(pal::Wm, usize)>,
+}
+
+struct Column {
+    button: Button,
+    handle: HView,
+    title: String,
+    width: Cell<f32>,
+    min_width: f32,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("styled_box", &self.styled_box)
+            .field("cells_view", &self.cells_view)
+            .field("scroll_offset", &self.scroll_offset)
+            .field("sort_indicator", &self.sort_indicator)
+            .field("sort_handlers", &())
+            .field("resize_handlers", &())
+            .finish()
+    }
+}
+
+impl TableHeader {
+    /// Construct a `TableHeader` with no columns.
+    pub fn new(style_manager: &'static Manager) -> Self {
+        let styled_box = StyledBox::new(
+            style_manager,
+            ViewFlags::LAYER_GROUP | ViewFlags::CLIP_VISIBLE_FRAME,
+        );
+        styled_box.set_class_set(elem_id::TABLE_HEADER);
+
+        let cells_view = HView::new(ViewFlags::default());
+        styled_box.set_subview(roles::GENERIC, Some(cells_view.clone()));
+
+        let this = Self {
+            inner: Rc::new(Inner {
+                style_manager,
+                styled_box,
+                cells_view,
+                columns: RefCell::new(Vec::new()),
+                scroll_offset: Cell::new(0.0),
+                sort_indicator: Cell::new(None),
+                sort_handlers: EventSource::new(),
+                resize_handlers: EventSource::new(),
+            }),
+        };
+        this.inner.update_layout();
+        this
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.inner.styled_box.view()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.inner.styled_box.view_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.inner.styled_box.style_elem()
+    }
+
+    /// Replace the columns.
+    pub fn set_columns(&self, columns: impl IntoIterator<Item = HeaderColumn>) {
+        let inner = &self.inner;
+
+        // Detach the styling elements of the old columns
+        for i in 0..inner.columns.borrow().len() {
+            (inner.styled_box).set_subelement(COLUMN_ROLE_START + i as Role, None);
+        }
+
+        let new_columns = columns
+            .into_iter()
+            .enumerate()
+            .map(|(i, col)| inner.new_column(i, col))
+            .collect();
+
+        inner.columns.replace(new_columns);
+        inner.sort_indicator.set(None);
+        inner.update_layout();
+    }
+
+    /// Get the number of columns.
+    pub fn num_columns(&self) -> usize {
+        self.inner.columns.borrow().len()
+    }
+
+    /// Get the current widths of the columns.
+    ///
+    /// The returned value can be saved and later passed to
+    /// [`set_column_widths`] to restore the widths.
+    ///
+    /// [`set_column_widths`]: crate::ui::views::table::TableHeader::set_column_widths
+    pub fn column_widths(&self) -> Vec<f32> {
+        (self.inner.columns.borrow().iter())
+            .map(|col| col.width.get())
+            .collect()
+    }
+
+    /// Set the widths of the columns. Excess elements are ignored.
+    ///
+    /// This method does not raise the `column_resized` event.
+    pub fn set_column_widths(&self, widths: &[f32]) {
+        for (col, &width) in self.inner.columns.borrow().iter().zip(widths.iter()) {
+            col.width.set(width.fmax(col.min_width));
+        }
+        self.inner.update_layout();
+    }
+
+    /// Get the current sort indicator.
+    pub fn sort_indicator(&self) -> Option<(usize, SortOrder)> {
+        self.inner.sort_indicator.get()
+    }
+
+    /// Set the sort indicator.
+    ///
+    /// The indicator is automatically updated when the user clicks a column
+    /// header. This method does not raise the `sort_requested` event.
+    pub fn set_sort_indicator(&self, value: Option<(usize, SortOrder)>) {
+        self.inner.set_sort_indicator(value);
+    }
+
+    /// Get the horizontal offset of the columns.
+    pub fn scroll_offset(&self) -> f32 {
+        self.inner.scroll_offset.get()
+    }
+
+    /// Set the horizontal offset of the columns. Usually this is equal to the
+    /// horizontal scroll position of the table.
+    pub fn set_scroll_offset(&self, value: f32) {
+        if value == self.inner.scroll_offset.get() {
+            return;
+        }
+        self.inner.scroll_offset.set(value);
+        self.inner.update_layout();
+    }
+
+    /// Add a function called when the user clicks a column header.
+    ///
+    /// The function receives the column index and the new sorting order.
+    /// The sort indicator is updated before the function is called.
+    ///
+    /// The function is called via `Wm::invoke`.
+    pub fn subscribe_sort_requested(&self, cb: Box<dyn Fn(pal::Wm, usize, SortOrder)>) -> Sub {
+        self.inner.sort_handlers.subscribe(cb)
+    }
+
+    /// Add a function called while the user is resizing a column.
+    ///
+    /// The function receives the column index. The new width can be retrieved
+    /// by [`column_widths`].
+    ///
+    /// The function is called via `Wm::invoke`.
+    ///
+    /// [`column_widths`]: crate::ui::views::table::TableHeader::column_widths
+    pub fn subscribe_column_resized(&self, cb: Box<dyn Fn(pal::Wm, usize)>) -> Sub {
+        self.inner.resize_handlers.subscribe(cb)
+    }
+}
+
+impl Widget for TableHeader {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+impl Inner {
+    fn new_column(self: &Rc<Self>, i: usize, col: HeaderColumn) -> Column {
+        let button = Button::new(self.style_manager);
+        button.set_class_set(ClassSet::BUTTON | elem_id::TABLE_HEADER_CELL);
+        button.set_caption(col.title.clone());
+        (self.styled_box).set_subelement(COLUMN_ROLE_START + i as Role, Some(button.style_elem()));
+
+        let inner_weak = Rc::downgrade(self);
+        button.subscribe_activated(Box::new(move |wm| {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.handle_click(wm, i);
+            }
+        }));
+
+        let handle = HView::new(ViewFlags::ACCEPT_MOUSE_DRAG);
+        handle.set_cursor_shape(Some(CursorShape::EwResize));
+        handle.set_listener(HandleListener {
+            inner: Rc::downgrade(self),
+            col: i,
+        });
+
+        Column {
+            button,
+            handle,
+            title: col.title,
+            width: Cell::new(col.width.fmax(col.min_width)),
+            min_width: col.min_width,
+        }
+    }
+
+    fn handle_click(&self, wm: pal::Wm, i: usize) {
+        let order = match self.sort_indicator.get() {
+            Some((col, order)) if col == i => order.reverse(),
+            _ => SortOrder::Ascending,
+        };
+
+        self.set_sort_indicator(Some((i, order)));
+
+        self.sort_handlers
+            .raise_with(|handler| handler(wm, i, order));
+    }
+
+    fn set_sort_indicator(&self, value: Option<(usize, SortOrder)>) {
+        self.sort_indicator.set(value);
+
+        for (i, col) in self.columns.borrow().iter().enumerate() {
+            let caption = match value {
+                Some((c, SortOrder::Ascending)) if c == i => format!("{} \u{25b2}", col.title),
+                Some((c, SortOrder::Descending)) if c == i => format!("{} \u{25bc}", col.title),
+                _ => col.title.clone(),
+            };
+            col.button.set_caption(caption);
+        }
+    }
+
+    fn update_layout(&self) {
+        let columns = self.columns.borrow();
+
+        let subviews = (columns.iter().map(|col| col.button.view()))
+            .chain(columns.iter().map(|col| col.handle.clone()))
+            .collect();
+
+        self.cells_view.set_layout(HeaderLayout {
+            subviews,
+            widths: columns.iter().map(|col| col.width.get()).collect(),
+            offset: self.scroll_offset.get(),
+        });
+    }
+}
+
+/// Places column headers (`subviews[0..n]`) and drag handles
+/// (`subviews[n..n * 2]`) side by side.
+#[derive(Debug)]
+struct HeaderLayout {
+    subviews: Box<[HView]>,
+    widths: Box<[f32]>,
+    offset: f32,
+}
+
+impl Layout for HeaderLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        let num_cols = self.widths.len();
+        let (min, preferred) = self.subviews[0..num_cols]
+            .iter()
+            .map(|view| ctx.subview_size_traits(view.as_ref()))
+            .fold((0.0f32, 0.0f32), |(min, preferred), st| {
+                (min.fmax(st.min.y), preferred.fmax(st.preferred.y))
+            });
+
+        SizeTraits {
+            min: Vector2::new(0.0, min),
+            max: Vector2::new(f32::INFINITY, f32::INFINITY),
+            preferred: Vector2::new(self.widths.iter().sum(), preferred),
+        }
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let num_cols = self.widths.len();
+        let mut x = -self.offset;
+
+        for (i, &width) in self.widths.iter().enumerate() {
+            ctx.set_subview_frame(
+                self.subviews[i].as_ref(),
+                box2! { min: [x, 0.0], max: [x + width, size.y] },
+            );
+
+            x += width;
+
+            ctx.set_subview_frame(
+                self.subviews[num_cols + i].as_ref(),
+                box2! {
+                    min: [x - HANDLE_WIDTH * 0.5, 0.0],
+                    max: [x + HANDLE_WIDTH * 0.5, size.y],
+                },
+            );
+        }
+    }
+
+    fn has_same_subviews(&self, other: &dyn Layout) -> bool {
+        if let Some(other) = as_any::Downcast::downcast_ref::<Self>(other) {
+            self.subviews == other.subviews
+        } else {
+            false
+        }
+    }
+}
+
+/// Handles mouse drag gestures on a column's drag handle.
+struct HandleListener {
+    inner: Weak<Inner>,
+    col: usize,
+}
+
+impl ViewListener for HandleListener {
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        if let Some(inner) = self.inner.upgrade() {
+            let orig_width = inner.columns.borrow()[self.col].width.get();
+            Box::new(HandleDragListener {
+                inner: Weak::clone(&self.inner),
+                col: self.col,
+                orig_width,
+                start_x: Cell::new(None),
+            })
+        } else {
+            Box::new(())
+        }
+    }
+}
+
+struct HandleDragListener {
+    inner: Weak<Inner>,
+    col: usize,
+    orig_width: f32,
+    start_x: Cell<Option<f32>>,
+}
+
+impl HandleDragListener {
+    fn set_width(&self, wm: pal::Wm, width: f32) {
+        let col = self.col;
+        if let Some(inner) = self.inner.upgrade() {
+            wm.invoke(move |wm| {
+                {
+                    let columns = inner.columns.borrow();
+                    let column = if let Some(column) = columns.get(col) {
+                        column
+                    } else {
+                        // The columns were replaced
+                        return;
+                    };
+
+                    let width = width.fmax(column.min_width);
+                    if width == column.width.get() {
+                        return;
+                    }
+                    column.width.set(width);
+                }
+
+                inner.update_layout();
+
+                inner.resize_handlers.raise_with(|handler| handler(wm, col));
+            });
+        }
+    }
+}
+
+impl MouseDragListener for HandleDragListener {
+    fn mouse_down(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+        _click_count: u32,
+    ) {
+        if button == 0 {
+            self.start_x.set(Some(loc.x));
+        }
+    }
+
+    fn mouse_motion(&self, wm: pal::Wm, _: HViewRef<'_>, loc: Point2<f32>) {
+        if let Some(start_x) = self.start_x.get() {
+            self.set_width(wm, self.orig_width + (loc.x - start_x));
+        }
+    }
+
+    fn mouse_up(&self, _: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, button: u8) {
+        if button == 0 {
+            self.start_x.set(None);
+        }
+    }
+
+    fn cancel(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if self.start_x.take().is_some() {
+            // Restore the original width
+            self.set_width(wm, self.orig_width);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+    use cggeom::prelude::*;
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn columns(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let header = TableHeader::new(Manager::global(wm));
+        header.set_columns(vec![
+            HeaderColumn::new("Name", 100.0),
+            HeaderColumn::new("Size", 50.0).with_min_width(30.0),
+        ]);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(header.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let frames = || -> Vec<_> {
+            let columns = header.inner.columns.borrow();
+            (columns.iter())
+                .map(|col| col.button.view().global_frame())
+                .collect()
+        };
+
+        let f = frames();
+        assert_eq!(f[0].max.x - f[0].min.x, 100.0);
+        assert_eq!(f[1].min.x, f[0].max.x);
+        assert_eq!(f[1].max.x - f[1].min.x, 50.0);
+
+        // Restore persisted widths. `min_width` is respected.
+        header.set_column_widths(&[80.0, 10.0]);
+        assert_eq!(header.column_widths(), vec![80.0, 30.0]);
+
+        // Follow the table's horizontal scroll position
+        let x0 = frames()[0].min.x;
+        header.set_scroll_offset(20.0);
+        twm.step_unsend();
+        assert_eq!(frames()[0].min.x, x0 - 20.0);
+
+        // Clicking a column header toggles the sorting order
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        let click = |i: usize| {
+            let loc = frames()[i].mid();
+            let drag = twm.raise_mouse_drag(&pal_hwnd, loc, 0);
+            drag.mouse_down(loc, 0);
+            drag.mouse_up(loc, 0);
+            twm.step_unsend();
+        };
+
+        let sort_requests = Rc::new(RefCell::new(Vec::new()));
+        {
+            let sort_requests = Rc::clone(&sort_requests);
+            header.subscribe_sort_requested(Box::new(move |_, i, order| {
+                sort_requests.borrow_mut().push((i, order));
+            }));
+        }
+
+        click(1);
+        assert_eq!(header.sort_indicator(), Some((1, SortOrder::Ascending)));
+        click(1);
+        assert_eq!(header.sort_indicator(), Some((1, SortOrder::Descending)));
+        click(0);
+        assert_eq!(header.sort_indicator(), Some((0, SortOrder::Ascending)));
+        assert_eq!(
+            *sort_requests.borrow(),
+            vec![
+                (1, SortOrder::Ascending),
+                (1, SortOrder::Descending),
+                (0, SortOrder::Ascending),
+            ]
+        );
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn resize_by_drag(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let header = TableHeader::new(Manager::global(wm));
+        header.set_columns(vec![
+            HeaderColumn::new("Name", 100.0),
+            HeaderColumn::new("Size", 50.0).with_min_width(30.0),
+        ]);
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(header.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let resized = Rc::new(RefCell::new(Vec::new()));
+        {
+            let resized = Rc::clone(&resized);
+            header.subscribe_column_resized(Box::new(move |_, i| {
+                resized.borrow_mut().push(i);
+            }));
+        }
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        let handle_loc = |i: usize| header.inner.columns.borrow()[i].handle.global_frame().mid();
+
+        // Widen the first column
+        let loc = handle_loc(0);
+        let drag = twm.raise_mouse_drag(&pal_hwnd, loc, 0);
+        drag.mouse_down(loc, 0);
+        drag.mouse_motion(loc + Vector2::new(20.0, 0.0));
+        twm.step_unsend();
+        assert_eq!(header.column_widths(), vec![120.0, 50.0]);
+        assert_eq!(*resized.borrow(), vec![0]);
+
+        // The handle follows the column's edge
+        assert_eq!(handle_loc(0).x, loc.x + 20.0);
+
+        // `min_width` is respected
+        drag.mouse_motion(loc + Vector2::new(-200.0, 0.0));
+        twm.step_unsend();
+        assert_eq!(header.column_widths(), vec![HANDLE_WIDTH, 50.0]);
+
+        drag.mouse_up(loc, 0);
+        twm.step_unsend();
+        resized.borrow_mut().clear();
+
+        // Cancelling the drag restores the original width
+        let loc = handle_loc(1);
+        let drag = twm.raise_mouse_drag(&pal_hwnd, loc, 0);
+        drag.mouse_down(loc, 0);
+        drag.mouse_motion(loc + Vector2::new(-10.0, 0.0));
+        twm.step_unsend();
+        assert_eq!(header.column_widths(), vec![HANDLE_WIDTH, 40.0]);
+
+        drag.cancel();
+        twm.step_unsend();
+        assert_eq!(header.column_widths(), vec![HANDLE_WIDTH, 50.0]);
+        assert_eq!(*resized.borrow(), vec![1, 1]);
+    }
+}
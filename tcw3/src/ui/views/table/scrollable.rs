@@ -2,7 +2,8 @@ use cgmath::Point2;
 use flags_macro::flags;
 use owning_ref::OwningRef;
 use std::{
-    cell::Cell,
+    cell::{Cell, RefCell},
+    ops::Range,
     rc::{Rc, Weak},
 };
 
@@ -12,18 +13,24 @@ use super::{
         TableScrollbarDragListener,
     },
     scrollwheel::TableScrollModel,
-    LineTy, Table, TableFlags,
+    LineTy, Table, TableFlags, TableHeader,
 };
 use crate::{
     pal,
     prelude::*,
     ui::{
-        layouts::FillLayout,
+        layouts::{FillLayout, FlexItem, FlexLayout},
         mixins::scrollwheel::{ScrollAxisFlags, ScrollModel, ScrollWheelMixin},
         theming::{roles, ClassSet, HElem, Manager, StyledBox, Widget},
-        views::ScrollbarRaw,
+        views::{
+            selection::{ClickModifier, SelectionModel},
+            ScrollbarRaw,
+        },
+    },
+    uicore::{
+        ActionId, ActionStatus, HView, HViewRef, KeyEvent, MouseDragListener, ScrollDelta,
+        ScrollListener, SizeTraits, Sub, ViewFlags, ViewListener,
     },
-    uicore::{HView, HViewRef, ScrollDelta, ScrollListener, SizeTraits, ViewFlags, ViewListener},
 };
 
 /// Wraps [`Table`] to support scrolling.
@@ -36,10 +43,16 @@ pub struct ScrollableTable {
 struct Inner {
     wrapper: HView,
     styled_box: StyledBox,
+    /// Contains `table` and `header`.
+    content: HView,
     table: Table,
+    header: RefCell<Option<TableHeader>>,
     scrollbars: [ScrollbarRaw; 2],
     drag_active: [Cell<bool>; 2],
     scroll_mixin: ScrollWheelMixin,
+    selection: RefCell<Option<(SelectionModel, Sub)>>,
+    /// The modifier key state last reported by a key event.
+    modifiers: Cell<pal::ModifierState>,
 }
 
 impl ScrollableTable {
@@ -52,7 +65,10 @@ impl ScrollableTable {
             ScrollbarRaw::new(style_manager, true),
         ];
 
-        styled_box.set_subview(roles::GENERIC, Some(table.view()));
+        let content = HView::new(ViewFlags::default());
+        content.set_layout(FillLayout::new(table.view()));
+
+        styled_box.set_subview(roles::GENERIC, Some(content.clone()));
         styled_box.set_child(roles::HORZ_SCROLLBAR, Some(&scrollbars[0]));
         styled_box.set_child(roles::VERT_SCROLLBAR, Some(&scrollbars[1]));
 
@@ -66,10 +82,14 @@ impl ScrollableTable {
             inner: Rc::new(Inner {
                 wrapper,
                 styled_box,
+                content,
                 table,
+                header: RefCell::new(None),
                 scrollbars,
                 drag_active: [Cell::new(false), Cell::new(false)],
                 scroll_mixin: ScrollWheelMixin::new(),
+                selection: RefCell::new(None),
+                modifiers: Cell::new(pal::ModifierState::empty()),
             }),
         };
 
@@ -85,6 +105,7 @@ impl ScrollableTable {
                 pal::Wm::global().invoke_on_update(move |_| {
                     inner.update_class_set();
                     inner.update_scrollbar_value();
+                    inner.update_header_offset();
                 });
             }
         }));
@@ -164,6 +185,46 @@ impl ScrollableTable {
         &self.inner.table
     }
 
+    /// Set a header displayed above the table, replacing the current one (if
+    /// any).
+    ///
+    /// The header stays at the top while the table is scrolled vertically, and
+    /// follows the table's horizontal scroll position. Column widths are not
+    /// synchronized automatically; the application should subscribe to
+    /// [`TableHeader::subscribe_column_resized`] and update the table model.
+    ///
+    /// Must not have an active edit (the table model must be in the unlocked
+    /// state).
+    ///
+    /// [`TableHeader::subscribe_column_resized`]: crate::ui::views::table::TableHeader::subscribe_column_resized
+    pub fn set_header(&self, header: Option<TableHeader>) {
+        let inner = &self.inner;
+
+        if let Some(header) = &header {
+            inner.content.set_layout(FlexLayout::column(vec![
+                (header.view(), FlexItem::new()),
+                (inner.table.view(), FlexItem::new().with_grow(1.0)),
+            ]));
+        } else {
+            inner
+                .content
+                .set_layout(FillLayout::new(inner.table.view()));
+        }
+
+        let elem = header.as_ref().map(|h| h.style_elem());
+        inner.styled_box.set_subelement(roles::GENERIC, elem);
+
+        inner.header.replace(header);
+        inner.update_header_offset();
+    }
+
+    /// Get the header set by [`set_header`].
+    ///
+    /// [`set_header`]: crate::ui::views::table::ScrollableTable::set_header
+    pub fn header(&self) -> Option<TableHeader> {
+        self.inner.header.borrow().clone()
+    }
+
     /// Set new size traits (delegated to the inner `Table`).
     ///
     /// Must not have an active edit (the table model must be in the unlocked
@@ -172,6 +233,60 @@ impl ScrollableTable {
         self.inner.table.set_size_traits(value);
     }
 
+    /// Set a selection model tracking the selected rows, replacing the current
+    /// one (if any).
+    ///
+    /// When a selection model is set, the table accepts a keyboard focus,
+    /// and the clicks and keyboard actions such as `MOVE_DOWN` are forwarded
+    /// to the selection model. <kbd>Shift</kbd>-click and
+    /// <kbd>Ctrl</kbd>-click (<kbd>Command</kbd>-click on macOS) extend and
+    /// toggle the selection, respectively. When the selection changes, the
+    /// affected rows are re-created by [`TableModelEdit::renew_subviews`], so
+    /// [`TableModelQuery::new_view`] should use
+    /// [`SelectionModel::class_set`] to style the row.
+    ///
+    /// The application is still responsible for calling
+    /// [`SelectionModel::insert`] and [`SelectionModel::remove`] when it
+    /// inserts or removes rows.
+    ///
+    /// [`TableModelEdit::renew_subviews`]: crate::ui::views::table::TableModelEdit::renew_subviews
+    /// [`TableModelQuery::new_view`]: crate::ui::views::table::TableModelQuery::new_view
+    /// [`SelectionModel::class_set`]: crate::ui::views::selection::SelectionModel::class_set
+    /// [`SelectionModel::insert`]: crate::ui::views::selection::SelectionModel::insert
+    /// [`SelectionModel::remove`]: crate::ui::views::selection::SelectionModel::remove
+    pub fn set_selection(&self, selection: Option<SelectionModel>) {
+        let inner = &self.inner;
+
+        if let Some((_, sub)) = inner.selection.replace(None) {
+            sub.unsubscribe().unwrap();
+        }
+
+        let mut flags = inner.wrapper.flags();
+        flags.set(
+            flags![ViewFlags::{ACCEPT_MOUSE_DRAG | TAB_STOP | STRONG_FOCUS}],
+            selection.is_some(),
+        );
+        inner.wrapper.set_flags(flags);
+
+        if let Some(selection) = selection {
+            let inner_weak = Rc::downgrade(inner);
+            let sub = selection.subscribe_changed(Box::new(move |_, range| {
+                if let Some(inner) = inner_weak.upgrade() {
+                    inner.renew_rows(range);
+                }
+            }));
+            inner.selection.replace(Some((selection, sub)));
+        }
+    }
+
+    /// Get the selection model set by [`set_selection`].
+    ///
+    /// [`set_selection`]: crate::ui::views::table::ScrollableTable::set_selection
+    pub fn selection(&self) -> Option<SelectionModel> {
+        let selection = self.inner.selection.borrow();
+        selection.as_ref().map(|(selection, _)| selection.clone())
+    }
+
     /// Set new table flags (delegated to the inner `Table`).
     ///
     /// Must not have an active edit (the table model must be in the unlocked
@@ -259,6 +374,48 @@ impl Inner {
             self.scrollbars[i].set_page_step(page_steps[i]);
         }
     }
+
+    /// Re-create the subviews of the specified rows to reflect their new
+    /// selection states.
+    ///
+    /// If the table is currently being edited, the update is deferred until
+    /// the edit is complete.
+    fn renew_rows(self: &Rc<Self>, range: Range<u64>) {
+        if let Ok(mut edit) = self.table.edit() {
+            let num_rows = edit.num_lines(LineTy::Row);
+            edit.renew_subviews(
+                LineTy::Row,
+                range.start.min(num_rows)..range.end.min(num_rows),
+            );
+        } else {
+            let this = Rc::clone(self);
+            pal::Wm::global().invoke(move |_| this.renew_rows(range));
+        }
+    }
+
+    /// Get the number of rows, or `None` if the table is currently being
+    /// edited.
+    fn num_rows(&self) -> Option<u64> {
+        Some(self.table.edit().ok()?.num_lines(LineTy::Row))
+    }
+
+    /// Scroll the header (if any) horizontally to match the table.
+    ///
+    /// If the table is currently being edited, the update is deferred until
+    /// the edit is complete.
+    fn update_header_offset(self: &Rc<Self>) {
+        if let Some(header) = &*self.header.borrow() {
+            let offset = if let Ok(edit) = self.table.edit() {
+                edit.scroll_pos()[0] + edit.display_offset()[0]
+            } else {
+                let this = Rc::clone(self);
+                pal::Wm::global().invoke(move |_| this.update_header_offset());
+                return;
+            };
+
+            header.set_scroll_offset(offset as f32);
+        }
+    }
 }
 
 struct WrapperViewListener {
@@ -280,6 +437,72 @@ impl WrapperViewListener {
 }
 
 impl ViewListener for WrapperViewListener {
+    fn key_down(&self, _: pal::Wm, _: HViewRef<'_>, e: &KeyEvent<'_>) -> bool {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.modifiers.set(e.modifiers());
+        }
+        false
+    }
+
+    fn key_up(&self, _: pal::Wm, _: HViewRef<'_>, e: &KeyEvent<'_>) -> bool {
+        if let Some(inner) = self.inner.upgrade() {
+            inner.modifiers.set(e.modifiers());
+        }
+        false
+    }
+
+    fn focus_lost(&self, _: pal::Wm, _: HViewRef<'_>) {
+        if let Some(inner) = self.inner.upgrade() {
+            // We won't receive `key_up` anymore
+            inner.modifiers.set(pal::ModifierState::empty());
+        }
+    }
+
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        Box::new(SelectionDragListener {
+            inner: Weak::clone(&self.inner),
+        })
+    }
+
+    fn validate_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) -> ActionStatus {
+        let inner = if let Some(inner) = self.inner.upgrade() {
+            inner
+        } else {
+            return ActionStatus::empty();
+        };
+
+        let selection = inner.selection.borrow();
+        if let Some((selection, _)) = &*selection {
+            selection.validate_action(action)
+        } else {
+            ActionStatus::empty()
+        }
+    }
+
+    fn perform_action(&self, _: pal::Wm, _: HViewRef<'_>, action: ActionId) {
+        let inner = if let Some(inner) = self.inner.upgrade() {
+            inner
+        } else {
+            return;
+        };
+
+        let selection = if let Some((selection, _)) = &*inner.selection.borrow() {
+            selection.clone()
+        } else {
+            return;
+        };
+
+        if let Some(num_rows) = inner.num_rows() {
+            selection.perform_action(action, num_rows);
+        }
+    }
+
     fn scroll_motion(&self, wm: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>, delta: &ScrollDelta) {
         if let Some(inner) = self.inner.upgrade() {
             // Do not allow scrolling in two ways at the same time
@@ -314,14 +537,88 @@ impl ViewListener for WrapperViewListener {
     }
 }
 
+/// Forwards clicks to the `SelectionModel` of `ScrollableTable`.
+struct SelectionDragListener {
+    inner: Weak<Inner>,
+}
+
+impl MouseDragListener for SelectionDragListener {
+    fn mouse_down(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        loc: Point2<f32>,
+        button: u8,
+        _click_count: u32,
+    ) {
+        if button != 0 {
+            return;
+        }
+
+        let inner = if let Some(inner) = self.inner.upgrade() {
+            inner
+        } else {
+            return;
+        };
+
+        let selection = if let Some((selection, _)) = &*inner.selection.borrow() {
+            selection.clone()
+        } else {
+            return;
+        };
+
+        let y = loc.y - inner.table.view_ref().global_frame().min.y;
+        let row = if let Ok(edit) = inner.table.edit() {
+            edit.line_at_pos(LineTy::Row, y as f64)
+        } else {
+            return;
+        };
+
+        let modifiers = inner.modifiers.get();
+        let toggle_modifier = if cfg!(target_os = "macos") {
+            pal::ModifierState::SUPER
+        } else {
+            pal::ModifierState::CONTROL
+        };
+
+        if let Some(row) = row {
+            let modifier = if modifiers.contains(pal::ModifierState::SHIFT) {
+                ClickModifier::Extend
+            } else if modifiers.contains(toggle_modifier) {
+                ClickModifier::Toggle
+            } else {
+                ClickModifier::None
+            };
+            selection.handle_click(row, modifier);
+        } else if modifiers.is_empty() {
+            // Clicked the empty space below the rows
+            selection.clear();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
         testing::{prelude::*, use_testing_wm},
+        ui::views::table::{CellCtrler, CellIdx, TableModelQuery},
         uicore::HWnd,
     };
     use cggeom::prelude::*;
+    use std::ops::Range;
+
+    struct TestModel;
+
+    impl TableModelQuery for TestModel {
+        fn new_view(&mut self, _: CellIdx) -> (HView, Box<dyn CellCtrler>) {
+            (HView::new(Default::default()), Box::new(()))
+        }
+
+        fn range_size(&mut self, _line_ty: LineTy, range: Range<u64>, _approx: bool) -> f64 {
+            20.0 * (range.end - range.start) as f64
+        }
+    }
 
     #[use_testing_wm(testing = "crate::testing")]
     #[test]
@@ -337,4 +634,43 @@ mod tests {
 
         twm.step_unsend();
     }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn set_header_while_editing(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+
+        let style_manager = Manager::global(wm);
+        let table = ScrollableTable::new(style_manager);
+        {
+            let mut edit = table.table().edit().unwrap();
+            edit.set_model(TestModel);
+            edit.insert(LineTy::Col, 0..100);
+            edit.insert(LineTy::Row, 0..100);
+        }
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(table.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        table.table().edit().unwrap().set_scroll_pos([30.0, 0.0]);
+        twm.step_unsend();
+
+        // The table is locked, so the header's scroll offset can't be updated
+        // immediately. This must not panic.
+        let header = TableHeader::new(style_manager);
+        {
+            let _edit = table.table().edit().unwrap();
+            table.set_header(Some(header.clone()));
+        }
+        twm.step_unsend();
+
+        let offset = {
+            let edit = table.table().edit().unwrap();
+            edit.scroll_pos()[0] + edit.display_offset()[0]
+        };
+        assert_ne!(offset, 0.0);
+        assert_eq!(header.scroll_offset(), offset as f32);
+    }
 }
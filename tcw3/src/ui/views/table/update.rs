@@ -3,7 +3,6 @@ use arrayvec::ArrayVec;
 use cggeom::Box2;
 use cgmath::{Point2, Vector2};
 use iterpool::Pool;
-use ndarray::Array2;
 use std::{
     cell::RefCell,
     cmp::{max, min},
@@ -18,7 +17,7 @@ use super::{
 };
 use crate::{
     ui::scrolling::{
-        lineset::{DispCb, Index, LinesetModel, Size},
+        lineset::{DispCb, Index, Lineset, LinesetModel, Size},
         tableremap::shuffle2d,
     },
     uicore::{HView, HViewRef, Layout, LayoutCtx, SizeTraits},
@@ -75,6 +74,8 @@ impl Inner {
             let size = self.size.get()[ty.i()];
             let lineset = &mut state.linesets[ty.i()];
 
+            let num_frozen = min(state.num_frozen[ty.i()], lineset.num_lines() as u64) as Index;
+
             // Regrouping might shrink some line groups. A set of line groups
             // that covered the viewport might no longer after regrouping. If
             // this happens, we try regrouping again.
@@ -82,8 +83,10 @@ impl Inner {
                 // Bound the viewport offset first
                 state.vp_set.bound_by(ty, lineset.total_size(), size);
 
-                // Calculate the viewport range
-                let vp_ranges = state.vp_set.vp_ranges(ty, size);
+                // Calculate the viewport range. Frozen lines are always
+                // displayed, so they are treated like a viewport.
+                let mut vp_ranges = state.vp_set.vp_ranges(ty, size).to_vec();
+                vp_ranges.extend(frozen_pos_range(lineset, num_frozen));
 
                 struct DispCbImpl<'a> {
                     line_ty: LineTy,
@@ -117,7 +120,8 @@ impl Inner {
 
                 lineset.regroup(&lineset_model, &vp_ranges, &mut disp_cb);
 
-                let new_vp_ranges = state.vp_set.vp_ranges(ty, size);
+                let mut new_vp_ranges = state.vp_set.vp_ranges(ty, size).to_vec();
+                new_vp_ranges.extend(frozen_pos_range(lineset, num_frozen));
 
                 if new_vp_ranges
                     .iter()
@@ -131,18 +135,35 @@ impl Inner {
         // Calculate the range of visible lines
         #[allow(clippy::reversed_empty_ranges)]
         let mut new_cells_ranges = [0..0, 0..0];
+        let mut new_cells_frozen = [0; 2];
         for &ty in &[LineTy::Col, LineTy::Row] {
             let size = self.size.get()[ty.i()];
+            let lineset = &state.linesets[ty.i()];
 
             let vp = state.vp_set.primary_vp_range(ty, size);
 
-            let (_line_grs, line_grs_range_idx, _line_grs_range_pos) =
-                state.linesets[ty.i()].range(vp);
+            let (_line_grs, line_grs_range_idx, _line_grs_range_pos) = lineset.range(vp);
+
+            // Frozen lines are represented separately. Exclude them from the
+            // visible range.
+            let num_frozen = min(state.num_frozen[ty.i()], lineset.num_lines() as u64) as Index;
 
-            new_cells_ranges[ty.i()] = line_grs_range_idx;
+            new_cells_ranges[ty.i()] =
+                max(line_grs_range_idx.start, num_frozen)..max(line_grs_range_idx.end, num_frozen);
+            new_cells_frozen[ty.i()] = num_frozen;
         }
 
-        // Remap `cells` using the new `cells_ranges`.
+        // The line indices of the elements in the new `cells`
+        let new_cells_lines: ArrayVec<[Vec<Index>; 2]> = [LineTy::Col, LineTy::Row]
+            .iter()
+            .map(|&ty| {
+                (0..new_cells_frozen[ty.i()])
+                    .chain(new_cells_ranges[ty.i()].clone())
+                    .collect()
+            })
+            .collect();
+
+        // Remap `cells` using the new `cells_ranges` and `cells_frozen`.
         //
         // We do not wish to re-create `cells` from scratch. We should be able
         // to simply move elements from the old `cells` for table cells that
@@ -151,8 +172,8 @@ impl Inner {
         let model_query = &mut state.model_query;
         let new_cells = shuffle2d(
             state.cells.view_mut(),
-            state.line_idx_maps[0].invert(new_cells_ranges[0].clone()),
-            state.line_idx_maps[1].invert(new_cells_ranges[1].clone()),
+            state.line_idx_maps[0].invert_lines(new_cells_lines[0].iter().cloned()),
+            state.line_idx_maps[1].invert_lines(new_cells_lines[1].iter().cloned()),
             // Map function (for existing cells)
             |old_cell: &mut TableCell| TableCell {
                 view: old_cell.view.clone(),
@@ -160,8 +181,8 @@ impl Inner {
             },
             // Factory function (for new cells)
             |[col, row]| {
-                let col = col as u64 + new_cells_ranges[0].start as u64;
-                let row = row as u64 + new_cells_ranges[1].start as u64;
+                let col = new_cells_lines[0][col] as u64;
+                let row = new_cells_lines[1][row] as u64;
                 let (view, ctrler) = model_query.new_view([col, row]);
                 TableCell { view, ctrler }
             },
@@ -169,14 +190,11 @@ impl Inner {
 
         state.cells = new_cells;
         state.cells_ranges = new_cells_ranges;
+        state.cells_frozen = new_cells_frozen;
 
         // Reset `line_idx_maps`.
-        for (line_idx_map, cells_range) in state
-            .line_idx_maps
-            .iter_mut()
-            .zip(state.cells_ranges.iter())
-        {
-            line_idx_map.set_identity(cells_range.clone());
+        for (line_idx_map, cells_lines) in state.line_idx_maps.iter_mut().zip(new_cells_lines) {
+            line_idx_map.set_identity_lines(cells_lines);
         }
 
         true
@@ -283,6 +301,16 @@ impl VpSet {
     }
 }
 
+/// Get the line coordinate range of the first `num_frozen` lines. Returns
+/// `None` if `num_frozen` is zero.
+fn frozen_pos_range(lineset: &Lineset, num_frozen: Index) -> Option<Range<Size>> {
+    if num_frozen == 0 {
+        None
+    } else {
+        Some(lineset.range_by_index(0..num_frozen).2)
+    }
+}
+
 /// Exposes `TableModelQuery` as a `LinesetModel`.
 pub(super) struct LinesetModelImpl<'a> {
     // TODO: Modify `LinesetModel::line_total_size` to accept `&mut self` so
@@ -325,14 +353,21 @@ impl LinesetModel for LinesetModelImpl<'_> {
 /// scratch on many occasions, even if none of `TableLayout`'s fields have to
 /// be updated.
 pub(super) struct TableLayout {
-    subviews: Array2<HView>,
+    /// The subviews in `cells`, ordered so that frozen cells are placed above
+    /// other cells.
+    subviews: Vec<HView>,
+    /// The indices into `cells` of the elements of `subviews`.
+    subview_cells: Vec<[usize; 2]>,
     inner: Rc<Inner>,
+    /// The line coordinates of the lines in `cells_ranges`.
     pos_lists: [Vec<f32>; 2],
+    /// The line coordinates of the frozen lines.
+    frozen_pos_lists: [Vec<f32>; 2],
 }
 
 impl Layout for TableLayout {
     fn subviews(&self) -> &[HView] {
-        self.subviews.as_slice().unwrap()
+        &self.subviews
     }
 
     fn size_traits(&self, _ctx: &LayoutCtx) -> SizeTraits {
@@ -378,15 +413,23 @@ impl Layout for TableLayout {
         }
 
         // Arrange subviews
-        for ((col, row), view) in self.subviews.indexed_iter() {
-            let cell = [col, row];
-
+        for (view, &cell) in self.subviews.iter().zip(self.subview_cells.iter()) {
             // Get the corner coordinates
             let mut min = Point2::new(0.0, 0.0);
             let mut max = Point2::new(0.0, 0.0);
             for &ty in &[LineTy::Col, LineTy::Row] {
-                min[ty.i()] = self.pos_lists[ty.i()][cell[ty.i()]];
-                max[ty.i()] = self.pos_lists[ty.i()][cell[ty.i()] + 1];
+                let frozen_pos_list = &self.frozen_pos_lists[ty.i()];
+                let num_frozen = frozen_pos_list.len() - 1;
+                let k = cell[ty.i()];
+
+                let (pos_list, k) = if k < num_frozen {
+                    (frozen_pos_list, k)
+                } else {
+                    (&self.pos_lists[ty.i()], k - num_frozen)
+                };
+
+                min[ty.i()] = pos_list[k];
+                max[ty.i()] = pos_list[k + 1];
             }
 
             ctx.set_subview_frame(view.as_ref(), Box2::new(min, max));
@@ -433,8 +476,10 @@ impl TableLayout {
                 let mut i = line_grs_range_idx.start;
                 let mut pos = line_grs_range_pos.start - vp.start;
 
-                // Skip some lines if `line_grs` extra lines
-                while i < cells_range.start {
+                // Skip some lines if `line_grs` extra lines. If all visible
+                // lines are frozen, `cells_range` may be located past the end
+                // of `line_grs`, but then there's nothing to calculate.
+                while i < cells_range.start && cells_range.start < cells_range.end {
                     let (size, num_lines) = line_grs.next().unwrap();
                     pos += size;
                     i += num_lines;
@@ -478,16 +523,60 @@ impl TableLayout {
             })
             .collect();
 
-        // Create an array of `HView` (`subviews()` needs a slice of them)
+        // Get coordinates of the frozen lines. They are not affected by the
+        // scroll position or the display offset.
+        let frozen_pos_lists: ArrayVec<[_; 2]> = [LineTy::Col, LineTy::Row]
+            .iter()
+            .map(|&ty| {
+                let num_frozen = state.cells_frozen[ty.i()];
+                let mut pos_list = Vec::with_capacity(num_frozen as usize + 1);
+                pos_list.push(0.0);
+
+                if num_frozen > 0 {
+                    let (line_grs, line_grs_range_idx, line_grs_range_pos) =
+                        state.linesets[ty.i()].range_by_index(0..num_frozen);
+
+                    assert_eq!(line_grs_range_idx, 0..num_frozen);
+
+                    let mut pos = line_grs_range_pos.start;
+                    for (size, num_lines) in line_grs {
+                        assert_eq!(num_lines, 1);
+                        pos += size;
+                        pos_list.push(fix_to_f32(pos));
+                    }
+                }
+
+                assert_eq!(pos_list.len(), pos_list.capacity());
+
+                pos_list
+            })
+            .collect();
+
+        // Create a list of `HView` (`subviews()` needs a slice of them).
+        // Subviews are drawn in order, so the cells in frozen lines must come
+        // last. The cells frozen in both axes are drawn over all others.
         // TODO: We could skip if this was in `State`. But then `shuffle2d`
         //       won't do anymore...
         let cells = &state.cells;
-        let subviews = Array2::from_shape_fn(cells.dim(), |i| cells[i].view.clone());
+        let cells_frozen = state.cells_frozen;
+        let mut subview_cells: Vec<[usize; 2]> = cells
+            .indexed_iter()
+            .map(|((col, row), _)| [col, row])
+            .collect();
+        subview_cells.sort_by_key(|cell| {
+            (cell[0] < cells_frozen[0] as usize) as u8 + (cell[1] < cells_frozen[1] as usize) as u8
+        });
+        let subviews = subview_cells
+            .iter()
+            .map(|&[col, row]| cells[(col, row)].view.clone())
+            .collect();
 
         Self {
             subviews,
+            subview_cells,
             inner,
             pos_lists: pos_lists.into_inner().unwrap(),
+            frozen_pos_lists: frozen_pos_lists.into_inner().unwrap(),
         }
     }
 }
@@ -0,0 +1,99 @@
+use crate::{
+    pal,
+    ui::theming::{self, elem_id, roles, ClassSet, HElem, Widget},
+    uicore::{HView, HViewRef, Sub, ViewFlags},
+};
+
+use super::EntryCore;
+
+/// A multi-line text entry widget.
+///
+/// The text is wrapped to the widget's width. The widget scrolls vertically
+/// when the text doesn't fit in it.
+///
+/// Editing is backed by [`EntryCore`], which lays out the whole text at once.
+/// It's meant for short texts such as messages, not for large documents.
+///
+/// [`EntryCore`]: crate::ui::views::EntryCore
+#[derive(Debug)]
+pub struct TextArea {
+    styled_box: theming::StyledBox,
+    core: EntryCore,
+}
+
+impl TextArea {
+    pub fn new(wm: pal::Wm, style_manager: &'static theming::Manager) -> Self {
+        let core = EntryCore::new_multiline(wm, style_manager);
+
+        let styled_box = theming::StyledBox::new(style_manager, ViewFlags::default());
+        styled_box.set_class_set(ClassSet::ENTRY | elem_id::TEXT_AREA);
+        styled_box.set_auto_class_set(ClassSet::HOVER | ClassSet::FOCUS);
+        styled_box.set_subview(roles::GENERIC, Some(core.view()));
+        styled_box.set_subelement(roles::GENERIC, Some(core.style_elem()));
+
+        Self { styled_box, core }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.styled_box.view()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.styled_box.view_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> theming::HElem {
+        self.styled_box.style_elem()
+    }
+
+    /// Get the inner `EntryCore`.
+    pub fn core(&self) -> &EntryCore {
+        &self.core
+    }
+
+    /// Set the class set of the inner `StyledBox`.
+    ///
+    /// It defaults to `ClassSet::ENTRY | elem_id::TEXT_AREA`. Some bits (e.g.,
+    /// `ACTIVE`) are internally enforced and cannot be modified.
+    pub fn set_class_set(&self, class_set: ClassSet) {
+        self.styled_box.set_class_set(class_set);
+    }
+
+    /// Get the class set of the inner `StyledBox`.
+    pub fn class_set(&self) -> ClassSet {
+        self.styled_box.class_set()
+    }
+
+    /// Get the text content.
+    pub fn text(&self) -> String {
+        self.core.text()
+    }
+
+    /// Set the text content.
+    ///
+    /// If the new value is different from the current one, it resets various
+    /// internal states such as an undo history. Otherwise, it does nothing.
+    pub fn set_text(&self, value: impl Into<String>) {
+        self.core.set_text(value)
+    }
+
+    /// Add a function called after the text content is modified.
+    ///
+    /// See [`EntryCore::subscribe_changed`].
+    pub fn subscribe_changed(&self, cb: Box<dyn Fn(pal::Wm)>) -> Sub {
+        self.core.subscribe_changed(cb)
+    }
+}
+
+impl Widget for TextArea {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
@@ -0,0 +1,673 @@
+//! Implements in-window notification banners (toasts).
+use alt_fp::FloatOrd;
+use cggeom::box2;
+use cgmath::Vector2;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fmt,
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::{
+        animation::start_animation_timer,
+        layouts::FillLayout,
+        theming::{elem_id, roles, ClassSet, Elem, HElem, Manager, StyledBox, Widget},
+        views::{Button, Label},
+    },
+    uicore::{
+        HView, HViewRef, HWndRef, Layout, LayoutCtx, SizeTraits, UpdateCtx, ViewFlags, ViewListener,
+    },
+};
+
+/// The duration of the fade-in and fade-out animations, measured in seconds.
+const FADE_DURATION: f32 = 0.2;
+
+/// The distance between the banner and the bottom edge of the host.
+const BANNER_MARGIN: f32 = 16.0;
+
+/// The default value of [`Toast`]'s timeout.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(4);
+
+/// The tolerance given to `Wm::invoke_after` for dismissing a toast.
+const TIMEOUT_TOLERANCE: Duration = Duration::from_millis(100);
+
+/// Describes a notification displayed by [`ToastHost`].
+pub struct Toast {
+    message: String,
+    action: Option<(String, Box<dyn Fn(pal::Wm)>)>,
+    timeout: Option<Duration>,
+}
+
+impl fmt::Debug for Toast {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Toast")
+            .field("message", &self.message)
+            .field("action", &self.action.as_ref().map(|(caption, _)| caption))
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Toast {
+    /// Construct a `Toast` displaying the specified message.
+    ///
+    /// The toast is automatically dismissed after four seconds by default.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            action: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+        }
+    }
+
+    /// Add an action button. `handler` is called when the button is pressed,
+    /// after which the toast is dismissed.
+    pub fn with_action(
+        self,
+        caption: impl Into<String>,
+        handler: impl Fn(pal::Wm) + 'static,
+    ) -> Self {
+        Self {
+            action: Some((caption.into(), Box::new(handler))),
+            ..self
+        }
+    }
+
+    /// Set the duration for which the toast is displayed. `None` means the
+    /// toast stays until it's dismissed by [`ToastHost::dismiss`] or the action
+    /// button.
+    pub fn with_timeout(self, timeout: Option<Duration>) -> Self {
+        Self { timeout, ..self }
+    }
+}
+
+/// Identifies a toast shown by [`ToastHost::show`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ToastId(u64);
+
+/// A container widget that displays a content view and overlays notification
+/// banners (toasts) on top of it.
+///
+/// Toasts are displayed one at a time near the bottom edge of the host. When
+/// [`show`] is called while another toast is visible, the new toast is queued
+/// and displayed after the preceding ones are dismissed. Toasts fade in and
+/// out while the host is mounted to a window.
+///
+/// [`show`]: ToastHost::show
+///
+/// # Styling
+///
+///  - `style_elem` - The wrapper. It doesn't support styling.
+///  - `style_elem > #`[`TOAST`] - The banner. See
+///    [`StyledBox`](crate::ui::theming::StyledBox)
+///     - `subviews[`[`roles::GENERIC`]`]`: The message label.
+///     - `subviews[`[`roles::TOAST_ACTION`]`]`: The action button. Absent if the
+///       toast doesn't have an action.
+///
+/// [`TOAST`]: crate::ui::theming::elem_id::TOAST
+/// [`roles::GENERIC`]: crate::ui::theming::roles::GENERIC
+/// [`roles::TOAST_ACTION`]: crate::ui::theming::roles::TOAST_ACTION
+#[derive(Debug)]
+pub struct ToastHost {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    wm: pal::Wm,
+    elem: Elem,
+    view: HView,
+    content: RefCell<Option<(HView, Option<HElem>)>>,
+
+    /// Applies a group opacity to `banner`.
+    fade_view: HView,
+    opacity: Rc<Cell<f32>>,
+    /// Incremented to stop the running fade animation.
+    fade_ticket: Cell<usize>,
+
+    banner: StyledBox,
+    label: Label,
+    button: Button,
+
+    next_id: Cell<u64>,
+    queue: RefCell<VecDeque<(ToastId, Toast)>>,
+    current: RefCell<Option<Current>>,
+}
+
+/// The state of the currently displayed toast.
+struct Current {
+    id: ToastId,
+    action: Option<Rc<dyn Fn(pal::Wm)>>,
+    timer: Option<pal::HInvoke>,
+    dismissing: bool,
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("wm", &self.wm)
+            .field("elem", &self.elem)
+            .field("view", &self.view)
+            .field("content", &self.content)
+            .field("fade_view", &self.fade_view)
+            .field("opacity", &self.opacity)
+            .field("fade_ticket", &self.fade_ticket)
+            .field("banner", &self.banner)
+            .field("next_id", &self.next_id)
+            .field("queue", &self.queue)
+            .field("current", &self.current.borrow().as_ref().map(|c| c.id))
+            .finish()
+    }
+}
+
+impl ToastHost {
+    /// Construct a `ToastHost`.
+    pub fn new(wm: pal::Wm, style_manager: &'static Manager) -> Self {
+        let elem = Elem::new(style_manager);
+
+        let banner = StyledBox::new(style_manager, ViewFlags::default());
+        banner.set_class_set(elem_id::TOAST);
+        elem.insert_child(banner.style_elem());
+
+        let label = Label::new(style_manager);
+        banner.set_child(roles::GENERIC, Some(&label));
+
+        let button = Button::new(style_manager);
+
+        let opacity = Rc::new(Cell::new(0.0));
+        let fade_view = HView::new(ViewFlags::LAYER_GROUP);
+        fade_view.set_layout(FillLayout::new(banner.view()));
+        fade_view.set_listener(FadeListener {
+            layer: RefCell::new(None),
+            opacity: Rc::clone(&opacity),
+        });
+
+        let inner = Rc::new(Inner {
+            wm,
+            elem,
+            view: HView::new(ViewFlags::default()),
+            content: RefCell::new(None),
+            fade_view,
+            opacity,
+            fade_ticket: Cell::new(0),
+            banner,
+            label,
+            button,
+            next_id: Cell::new(0),
+            queue: RefCell::new(VecDeque::new()),
+            current: RefCell::new(None),
+        });
+
+        let inner_weak = Rc::downgrade(&inner);
+        inner.button.subscribe_activated(Box::new(move |wm| {
+            if let Some(inner) = inner_weak.upgrade() {
+                Inner::handle_action(&inner, wm);
+            }
+        }));
+
+        inner.update_layout();
+
+        Self { inner }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.inner.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.inner.view.as_ref()
+    }
+
+    /// Get the styling element representing the widget.
+    pub fn style_elem(&self) -> HElem {
+        self.inner.elem.helem()
+    }
+
+    /// Set the styling class set of the wrapper.
+    ///
+    /// It defaults to `ClassSet::empty()`.
+    pub fn set_class_set(&self, class_set: ClassSet) {
+        self.inner.elem.set_class_set(class_set);
+    }
+
+    /// Get the styling class set of the wrapper.
+    pub fn class_set(&self) -> ClassSet {
+        self.inner.elem.class_set()
+    }
+
+    /// Set the content widget, which fills the host and is covered by toasts.
+    pub fn set_content(&self, content: Option<&dyn Widget>) {
+        let inner = &self.inner;
+
+        let new_content = content.map(|c| (c.view_ref().cloned(), c.style_elem()));
+
+        let old_content = inner.content.replace(new_content.clone());
+        if let Some((_, Some(old_elem))) = old_content {
+            inner.elem.remove_child(old_elem);
+        }
+        if let Some((_, Some(new_elem))) = new_content {
+            inner.elem.insert_child(new_elem);
+        }
+
+        inner.update_layout();
+    }
+
+    /// Display a toast, or queue it if another toast is being displayed.
+    pub fn show(&self, toast: Toast) -> ToastId {
+        let inner = &self.inner;
+
+        let id = ToastId(inner.next_id.get());
+        inner.next_id.set(id.0 + 1);
+
+        inner.queue.borrow_mut().push_back((id, toast));
+
+        if inner.current.borrow().is_none() {
+            Inner::present_next(inner);
+        }
+
+        id
+    }
+
+    /// Dismiss the specified toast. If it's being displayed, it fades out and
+    /// the next queued toast (if any) is displayed. If it's queued, it's removed
+    /// from the queue. Otherwise, this method does nothing.
+    pub fn dismiss(&self, id: ToastId) {
+        Inner::dismiss(&self.inner, id);
+    }
+
+    /// Get the toast being displayed.
+    pub fn current(&self) -> Option<ToastId> {
+        self.inner.current.borrow().as_ref().map(|c| c.id)
+    }
+
+    /// Get the number of toasts waiting to be displayed.
+    pub fn num_queued(&self) -> usize {
+        self.inner.queue.borrow().len()
+    }
+}
+
+impl Widget for ToastHost {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        Some(self.style_elem())
+    }
+}
+
+impl Inner {
+    fn update_layout(&self) {
+        let content = self.content.borrow().as_ref().map(|(view, _)| view.clone());
+        let banner = if self.current.borrow().is_some() {
+            Some(self.fade_view.clone())
+        } else {
+            None
+        };
+
+        self.view.set_layout(HostLayout {
+            has_content: content.is_some(),
+            subviews: content.into_iter().chain(banner).collect(),
+        });
+    }
+
+    /// Display the next toast in the queue.
+    fn present_next(this: &Rc<Self>) {
+        let (id, toast) = if let Some(x) = this.queue.borrow_mut().pop_front() {
+            x
+        } else {
+            this.update_layout();
+            return;
+        };
+
+        this.label.set_text(toast.message);
+
+        let action = if let Some((caption, handler)) = toast.action {
+            this.button.set_caption(caption);
+            this.banner
+                .set_child(roles::TOAST_ACTION, Some(&this.button));
+            Some(Rc::from(handler))
+        } else {
+            this.banner.set_child(roles::TOAST_ACTION, None);
+            None
+        };
+
+        let timer = toast.timeout.map(|timeout| {
+            let this_weak = Rc::downgrade(this);
+            this.wm
+                .invoke_after(timeout..timeout + TIMEOUT_TOLERANCE, move |_| {
+                    if let Some(this) = this_weak.upgrade() {
+                        // The timer is done, don't cancel it
+                        if let Some(current) = &mut *this.current.borrow_mut() {
+                            current.timer = None;
+                        }
+                        Inner::dismiss(&this, id);
+                    }
+                })
+        });
+
+        *this.current.borrow_mut() = Some(Current {
+            id,
+            action,
+            timer,
+            dismissing: false,
+        });
+
+        this.update_layout();
+        Inner::fade(this, 1.0, |_| {});
+    }
+
+    fn dismiss(this: &Rc<Self>, id: ToastId) {
+        {
+            let mut current = this.current.borrow_mut();
+            match &mut *current {
+                Some(current) if current.id == id => {
+                    if current.dismissing {
+                        return;
+                    }
+                    current.dismissing = true;
+                    if let Some(timer) = current.timer.take() {
+                        this.wm.cancel_invoke(&timer);
+                    }
+                }
+                _ => {
+                    this.queue.borrow_mut().retain(|(i, _)| *i != id);
+                    return;
+                }
+            }
+        }
+
+        Inner::fade(this, 0.0, |this| {
+            *this.current.borrow_mut() = None;
+            Inner::present_next(this);
+        });
+    }
+
+    fn handle_action(this: &Rc<Self>, wm: pal::Wm) {
+        let (id, action) = match &*this.current.borrow() {
+            Some(current) if !current.dismissing => (current.id, current.action.clone()),
+            _ => return,
+        };
+
+        // The handler may call `ToastHost`'s methods, so `current` must not be
+        // borrowed at this point
+        if let Some(action) = action {
+            action(wm);
+        }
+
+        Inner::dismiss(this, id);
+    }
+
+    /// Animate the banner's opacity toward `target` and call `on_complete`
+    /// when done. Supersedes the running animation, if any.
+    fn fade(this: &Rc<Self>, target: f32, on_complete: impl FnOnce(&Rc<Self>) + 'static) {
+        this.fade_ticket.set(this.fade_ticket.get().wrapping_add(1));
+        let ticket = this.fade_ticket.get();
+
+        let hwnd = if let Some(hwnd) = this.fade_view.containing_wnd() {
+            hwnd
+        } else {
+            // Not mounted, skip the animation
+            this.opacity.set(target);
+            let this = Rc::clone(this);
+            this.wm.invoke(move |_| {
+                if this.fade_ticket.get() == ticket {
+                    on_complete(&this);
+                }
+            });
+            return;
+        };
+
+        let source = this.opacity.get();
+        let start = this.wm.now();
+        let this_weak = Rc::downgrade(this);
+        let mut on_complete = Some(on_complete);
+
+        start_animation_timer(hwnd.as_ref(), move |wm| {
+            let this = if let Some(this) = this_weak.upgrade() {
+                this
+            } else {
+                return false;
+            };
+
+            if this.fade_ticket.get() != ticket {
+                return false;
+            }
+
+            let elapsed = wm.now().saturating_duration_since(start);
+            let progress = (elapsed.as_secs_f32() / FADE_DURATION).fmin(1.0);
+            this.opacity.set(source + (target - source) * progress);
+            this.fade_view.pend_update();
+
+            if progress < 1.0 {
+                true
+            } else {
+                (on_complete.take().unwrap())(&this);
+                false
+            }
+        });
+    }
+}
+
+/// Fills the host with the content view (`subviews[0]` if `has_content`) and
+/// places the banner (the last subview, if any) near the bottom edge.
+#[derive(Debug)]
+struct HostLayout {
+    has_content: bool,
+    subviews: Vec<HView>,
+}
+
+impl Layout for HostLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        if self.has_content {
+            ctx.subview_size_traits(self.subviews[0].as_ref())
+        } else {
+            SizeTraits::default()
+        }
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        let mut subviews = self.subviews.iter();
+
+        if self.has_content {
+            let content = subviews.next().unwrap();
+            ctx.set_subview_frame(
+                content.as_ref(),
+                box2! { top_left: [0.0, 0.0], size: [size.x, size.y] },
+            );
+        }
+
+        if let Some(banner) = subviews.next() {
+            let st = ctx.subview_size_traits(banner.as_ref());
+
+            let width = st
+                .preferred
+                .x
+                .fmin(st.max.x)
+                .fmin(size.x - BANNER_MARGIN * 2.0)
+                .fmax(st.min.x)
+                .round();
+            let height = st.preferred.y.fmin(st.max.y).fmax(st.min.y).round();
+
+            let x = ((size.x - width) * 0.5).round();
+            let y = size.y - BANNER_MARGIN - height;
+
+            ctx.set_subview_frame(
+                banner.as_ref(),
+                box2! { top_left: [x, y], size: [width, height] },
+            );
+        }
+    }
+
+    fn has_same_subviews(&self, other: &dyn Layout) -> bool {
+        if let Some(other) = as_any::Downcast::downcast_ref::<Self>(other) {
+            self.subviews == other.subviews
+        } else {
+            false
+        }
+    }
+}
+
+/// Applies a group opacity to the subviews.
+struct FadeListener {
+    layer: RefCell<Option<pal::HLayer>>,
+    opacity: Rc<Cell<f32>>,
+}
+
+impl ViewListener for FadeListener {
+    fn mount(&self, wm: pal::Wm, _: HViewRef<'_>, _: HWndRef<'_>) {
+        let layer = wm.new_layer(pal::LayerAttrs::default());
+
+        let old_layer = self.layer.replace(Some(layer));
+
+        assert!(old_layer.is_none());
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        if let Some(layer) = self.layer.replace(None) {
+            wm.remove_layer(&layer);
+        }
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let layer = self.layer.borrow();
+        let layer = layer.as_ref().expect("not mounted");
+
+        let mut new_attrs = pal::LayerAttrs {
+            bounds: Some(view.global_frame()),
+            opacity: Some(self.opacity.get()),
+            ..Default::default()
+        };
+
+        if let Some(sublayers) = ctx.sublayers().take() {
+            new_attrs.sublayers = Some(sublayers);
+        }
+        wm.set_layer_attr(&layer, new_attrs);
+
+        if ctx.layers().len() != 1 {
+            ctx.set_layers(vec![(*layer).clone()]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        uicore::HWnd,
+    };
+    use cggeom::prelude::*;
+    use try_match::try_match;
+
+    fn new_host(twm: &dyn TestingWm) -> (ToastHost, HWnd) {
+        let wm = twm.wm();
+
+        // Use the virtual clock to make the animations and timeouts
+        // deterministic
+        twm.advance_time(Duration::from_millis(0));
+
+        let host = ToastHost::new(wm, Manager::global(wm));
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(host.view()));
+        wnd.set_size([400, 300]);
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        (host, wnd)
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn queue(twm: &dyn TestingWm) {
+        let (host, _wnd) = new_host(twm);
+
+        let toast1 = host.show(Toast::new("first").with_timeout(None));
+        let toast2 = host.show(Toast::new("second").with_timeout(None));
+        let toast3 = host.show(Toast::new("third").with_timeout(None));
+        assert_eq!(host.current(), Some(toast1));
+        assert_eq!(host.num_queued(), 2);
+
+        // Remove a queued toast
+        host.dismiss(toast2);
+        assert_eq!(host.num_queued(), 1);
+
+        // Dismiss the visible toast. The next one appears after the fade-out
+        // animation.
+        host.dismiss(toast1);
+        twm.advance_time(Duration::from_millis(100));
+        assert_eq!(host.current(), Some(toast1));
+
+        twm.advance_time(Duration::from_millis(200));
+        assert_eq!(host.current(), Some(toast3));
+        assert_eq!(host.num_queued(), 0);
+
+        host.dismiss(toast3);
+        twm.advance_time(Duration::from_millis(300));
+        assert_eq!(host.current(), None);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn action(twm: &dyn TestingWm) {
+        let (host, _wnd) = new_host(twm);
+
+        let activated = Rc::new(Cell::new(false));
+        let toast = host.show(Toast::new("reconnecting").with_action("Retry", {
+            let activated = Rc::clone(&activated);
+            move |_| activated.set(true)
+        }));
+        assert_eq!(host.current(), Some(toast));
+
+        // Wait for the fade-in animation to complete
+        twm.advance_time(Duration::from_millis(300));
+
+        // Click the action button
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        let loc = host.inner.button.view().global_frame().mid();
+        let drag = twm.raise_mouse_drag(&pal_hwnd, loc, 0);
+        drag.mouse_down(loc, 0);
+        drag.mouse_up(loc, 0);
+        twm.step_unsend();
+        assert!(activated.get());
+
+        twm.advance_time(Duration::from_millis(300));
+        assert_eq!(host.current(), None);
+    }
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn timeout(twm: &dyn TestingWm) {
+        let (host, _wnd) = new_host(twm);
+
+        let toast = host.show(Toast::new("hello"));
+        assert_eq!(host.current(), Some(toast));
+
+        twm.advance_time(DEFAULT_TIMEOUT - Duration::from_millis(100));
+        assert_eq!(host.current(), Some(toast));
+
+        // The timeout plus the fade-out animation
+        twm.advance_time(Duration::from_millis(500));
+        assert_eq!(host.current(), None);
+
+        // A toast without a timeout stays
+        let toast = host.show(Toast::new("hello").with_timeout(None));
+        twm.advance_time(DEFAULT_TIMEOUT * 2);
+        assert_eq!(host.current(), Some(toast));
+    }
+}
@@ -0,0 +1,287 @@
+//! Implements a widget for displaying video frames produced by another thread.
+use cggeom::{prelude::*, Box2};
+use std::{
+    cell::RefCell,
+    fmt,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{
+    pal,
+    pal::{prelude::*, MtSticky},
+    ui::{
+        layouts::EmptyLayout,
+        theming::{HElem, Widget},
+    },
+    uicore::{HView, HViewRef, HWndRef, SizeTraits, UpdateCtx, ViewFlags, ViewListener, WeakHView},
+};
+
+/// A widget displaying frames submitted through a [`VideoFrameSender`].
+///
+/// Frames are passed to the backend through [`pal::ExternalTexture`], which
+/// keeps at most one frame waiting to be displayed. A sender never blocks,
+/// and frames submitted faster than the window is updated are dropped.
+///
+/// The frames are stretched to fill the view.
+#[derive(Debug)]
+pub struct VideoView {
+    view: HView,
+    inner: Rc<Inner>,
+}
+
+/// The sending end of a [`VideoView`]'s frame queue. Can be sent to a worker
+/// thread.
+#[derive(Debug)]
+pub struct VideoFrameSender {
+    producer: pal::ExternalTextureProducer,
+    notify: Arc<Notify>,
+}
+
+struct Notify {
+    /// `true` if the main thread is going to be notified of a new frame.
+    pending: AtomicBool,
+    view: MtSticky<WeakHView>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    texture: RefCell<Option<pal::ExternalTexture>>,
+    state: RefCell<Option<MountState>>,
+}
+
+#[derive(Debug)]
+struct MountState {
+    layer: pal::HLayer,
+    /// `true` if a texture is assigned to `layer`.
+    has_contents: bool,
+    /// The last known bounds of `layer`.
+    bounds: Option<Box2<f32>>,
+}
+
+impl fmt::Debug for Notify {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Notify")
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
+
+impl Default for VideoView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VideoView {
+    /// Construct a `VideoView`.
+    pub fn new() -> Self {
+        let inner = Rc::new(Inner {
+            texture: RefCell::new(None),
+            state: RefCell::new(None),
+        });
+
+        let view = HView::new(ViewFlags::default());
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+        view.set_listener(VideoViewListener {
+            inner: Rc::clone(&inner),
+        });
+
+        Self { view, inner }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.view.clone()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.view.as_ref()
+    }
+
+    /// Set the size traits of the widget.
+    ///
+    /// It defaults to `SizeTraits::default()`.
+    pub fn set_size_traits(&self, size_traits: SizeTraits) {
+        self.view.set_layout(EmptyLayout::new(size_traits));
+    }
+
+    /// Create a frame queue for frames of the specified size (measured in
+    /// pixels), and return its sending end.
+    ///
+    /// The view displays frames from the most recently created queue. The
+    /// previous frame stays on the screen until the new queue receives its
+    /// first frame.
+    pub fn new_frame_sender(&self, size: [u32; 2]) -> VideoFrameSender {
+        let wm = pal::Wm::global();
+        let (texture, producer) = pal::ExternalTexture::new(size);
+
+        *self.inner.texture.borrow_mut() = Some(texture);
+
+        VideoFrameSender {
+            producer,
+            notify: Arc::new(Notify {
+                pending: AtomicBool::new(false),
+                view: MtSticky::with_wm(wm, self.view.downgrade()),
+            }),
+        }
+    }
+
+    /// Stop displaying frames. Frames sent by existing senders are ignored.
+    pub fn clear(&self) {
+        *self.inner.texture.borrow_mut() = None;
+        self.view.pend_update();
+    }
+}
+
+impl Widget for VideoView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        None
+    }
+}
+
+impl VideoFrameSender {
+    /// Get the frame size.
+    pub fn size(&self) -> [u32; 2] {
+        self.producer.size()
+    }
+
+    /// Write a new frame using the specified function and submit it.
+    ///
+    /// See [`pal::ExternalTextureProducer::submit_with`] for the parameters of
+    /// `write`.
+    pub fn submit_with(&mut self, write: impl FnOnce(&mut [u8], usize)) {
+        self.producer.submit_with(write);
+
+        if !self.notify.pending.swap(true, Ordering::AcqRel) {
+            let notify = Arc::clone(&self.notify);
+            pal::Wm::invoke_on_main_thread(move |wm| {
+                notify.pending.store(false, Ordering::Release);
+                if let Some(view) = notify.view.get_with_wm(wm).upgrade() {
+                    view.pend_update();
+                }
+            });
+        }
+    }
+}
+
+struct VideoViewListener {
+    inner: Rc<Inner>,
+}
+
+impl ViewListener for VideoViewListener {
+    fn mount(&self, wm: pal::Wm, view: HViewRef<'_>, _: HWndRef<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        assert!(state.is_none());
+
+        *state = Some(MountState {
+            layer: wm.new_layer(pal::LayerAttrs::default()),
+            has_contents: false,
+            bounds: None,
+        });
+
+        view.pend_update();
+    }
+
+    fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+        let state = self.inner.state.borrow_mut().take().expect("not mounted");
+        wm.remove_layer(&state.layer);
+    }
+
+    fn position(&self, _: pal::Wm, view: HViewRef<'_>) {
+        view.pend_update();
+    }
+
+    fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+        let mut state = self.inner.state.borrow_mut();
+        let state = state.as_mut().expect("not mounted");
+        let texture = self.inner.texture.borrow();
+
+        let bounds = view.global_frame();
+        let mut attrs = pal::LayerAttrs {
+            bounds: Some(bounds),
+            ..Default::default()
+        };
+
+        if let Some(texture) = &*texture {
+            // Assigning the texture makes the backend latch the newest frame.
+            // The previous frame stays until the new queue receives a frame.
+            if texture.has_pending_frame() {
+                attrs.contents = Some(Some(pal::LayerContents::ExternalTexture(texture.clone())));
+                state.has_contents = true;
+            }
+        } else if state.has_contents {
+            attrs.contents = Some(None);
+            state.has_contents = false;
+        }
+
+        wm.set_layer_attr(&state.layer, attrs);
+
+        if ctx.layers().len() != 1 {
+            ctx.set_layers(vec![state.layer.clone()]);
+        }
+
+        ctx.set_damage(match state.bounds.replace(bounds) {
+            Some(old_bounds) => old_bounds.union(&bounds),
+            None => bounds,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pal::testing::wmapi::WndSnapshot,
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::FillLayout,
+        uicore::HWnd,
+    };
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn display_frame_from_thread(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let view = VideoView::new();
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view().set_layout(FillLayout::new(view.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        twm.set_wnd_size(&pal_hwnd, [40, 20]);
+        twm.step_unsend();
+
+        let mut sender = view.new_frame_sender([4, 2]);
+        std::thread::spawn(move || {
+            // Fill the frame with opaque white
+            sender.submit_with(|data, _| {
+                for x in data.iter_mut() {
+                    *x = 0xff;
+                }
+            });
+        })
+        .join()
+        .unwrap();
+
+        twm.step_unsend();
+
+        let mut snapshot = WndSnapshot::new();
+        twm.read_wnd_snapshot(&pal_hwnd, &mut snapshot);
+        let [w, h] = snapshot.size;
+        assert_eq!(
+            snapshot.data[(h / 2) * snapshot.stride + (w / 2) * 4 + 1],
+            0xff
+        );
+    }
+}
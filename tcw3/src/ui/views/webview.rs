@@ -0,0 +1,413 @@
+//! Implements a widget for embedding a web view.
+use std::{
+    cell::RefCell,
+    fmt,
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    pal,
+    pal::prelude::*,
+    ui::{
+        theming::{HElem, Widget},
+        views::NativeHost,
+        EventSource,
+    },
+    uicore::{HView, HViewRef, SizeTraits, Sub},
+};
+
+/// A widget that embeds a web view (WebView2 on Windows, WebKitGTK on the GTK
+/// backend).
+///
+/// The web view is created in a native child window hosted by
+/// [`NativeHost`], so it's subject to the same restrictions. In particular, it
+/// only exists while the widget is mounted. The last content requested by
+/// [`navigate`] or [`load_html`] is reloaded when the web view is recreated.
+///
+/// Pages can send a string to the application by calling
+/// `window.tcw3.postMessage(message)`. The message is delivered to the
+/// handlers registered by [`subscribe_message`].
+///
+/// If the backend or the system does not support web views, `WebView` behaves
+/// like an empty view.
+///
+/// [`navigate`]: WebView::navigate
+/// [`load_html`]: WebView::load_html
+/// [`subscribe_message`]: WebView::subscribe_message
+#[derive(Debug)]
+pub struct WebView {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    host: NativeHost,
+    state: RefCell<State>,
+    message_handlers: EventSource<dyn Fn(pal::Wm, &str)>,
+    navigation_handlers: EventSource<dyn Fn(pal::Wm, &str, bool)>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// The child window to which a web view is attached.
+    child_wnd: Option<pal::HChildWnd>,
+    content: Content,
+    url: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+enum Content {
+    Blank,
+    Url(String),
+    Html {
+        html: String,
+        base_url: Option<String>,
+    },
+}
+
+impl fmt::Debug for Inner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inner")
+            .field("host", &self.host)
+            .field("state", &self.state)
+            .field("message_handlers", &())
+            .field("navigation_handlers", &())
+            .finish()
+    }
+}
+
+impl Default for WebView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebView {
+    /// Construct a `WebView`.
+    pub fn new() -> Self {
+        let inner = Rc::new(Inner {
+            host: NativeHost::new(),
+            state: RefCell::new(State {
+                child_wnd: None,
+                content: Content::Blank,
+                url: None,
+            }),
+            message_handlers: EventSource::new(),
+            navigation_handlers: EventSource::new(),
+        });
+
+        let inner_weak = Rc::downgrade(&inner);
+        inner.host.subscribe_child_wnd_changed(Box::new(move |wm| {
+            if let Some(inner) = inner_weak.upgrade() {
+                inner.update_child_wnd(wm);
+            }
+        }));
+
+        Self { inner }
+    }
+
+    /// Get an owned handle to the view representing the widget.
+    pub fn view(&self) -> HView {
+        self.inner.host.view()
+    }
+
+    /// Borrow the handle to the view representing the widget.
+    pub fn view_ref(&self) -> HViewRef<'_> {
+        self.inner.host.view_ref()
+    }
+
+    /// Set the size traits of the widget.
+    ///
+    /// It defaults to `SizeTraits::default()`.
+    pub fn set_size_traits(&self, size_traits: SizeTraits) {
+        self.inner.host.set_size_traits(size_traits);
+    }
+
+    /// Get a flag indicating whether a web view is currently active.
+    ///
+    /// Returns `false` if the view is not mounted or the backend does not
+    /// support web views.
+    pub fn has_web_view(&self) -> bool {
+        self.inner.state.borrow().child_wnd.is_some()
+    }
+
+    /// Start loading the specified URL.
+    pub fn navigate(&self, url: &str) {
+        self.inner.set_content(Content::Url(url.to_owned()));
+    }
+
+    /// Display the specified HTML document. Relative URLs in the document are
+    /// resolved against `base_url`.
+    pub fn load_html(&self, html: &str, base_url: Option<&str>) {
+        self.inner.set_content(Content::Html {
+            html: html.to_owned(),
+            base_url: base_url.map(ToOwned::to_owned),
+        });
+    }
+
+    /// Evaluate a JavaScript code in the current page.
+    ///
+    /// The code is discarded if there is no active web view (see
+    /// [`has_web_view`]).
+    ///
+    /// [`has_web_view`]: WebView::has_web_view
+    pub fn eval_script(&self, script: &str) {
+        let child_wnd = self.inner.state.borrow().child_wnd.clone();
+        if let Some(child_wnd) = child_wnd {
+            pal::Wm::global().web_view_eval_script(&child_wnd, script);
+        }
+    }
+
+    /// Get the URL of the page being displayed or loaded.
+    ///
+    /// This is the URL reported by the last navigation event, or the URL
+    /// passed to [`navigate`] if there have been no navigation events since
+    /// then.
+    ///
+    /// [`navigate`]: WebView::navigate
+    pub fn url(&self) -> Option<String> {
+        self.inner.state.borrow().url.clone()
+    }
+
+    /// Add a function called when the page calls
+    /// `window.tcw3.postMessage(message)`. The function receives `message`
+    /// converted to a string.
+    ///
+    /// The function is called via `Wm::invoke`, thus allowed to modify
+    /// view hierarchy and view attributes. However, it's not allowed to call
+    /// `subscribe_message` when one of the handlers is being called.
+    pub fn subscribe_message(&self, cb: Box<dyn Fn(pal::Wm, &str)>) -> Sub {
+        self.inner.message_handlers.subscribe(cb)
+    }
+
+    /// Add a function called when a navigation has completed. The function
+    /// receives the page's URL and a flag indicating whether the page was
+    /// loaded successfully.
+    ///
+    /// The function is called via `Wm::invoke`, thus allowed to modify
+    /// view hierarchy and view attributes. However, it's not allowed to call
+    /// `subscribe_navigation_completed` when one of the handlers is being
+    /// called.
+    pub fn subscribe_navigation_completed(&self, cb: Box<dyn Fn(pal::Wm, &str, bool)>) -> Sub {
+        self.inner.navigation_handlers.subscribe(cb)
+    }
+}
+
+impl Widget for WebView {
+    fn view_ref(&self) -> HViewRef<'_> {
+        self.view_ref()
+    }
+
+    fn style_elem(&self) -> Option<HElem> {
+        None
+    }
+}
+
+impl Inner {
+    fn set_content(&self, content: Content) {
+        let child_wnd = {
+            let mut state = self.state.borrow_mut();
+            if let Content::Url(url) = &content {
+                state.url = Some(url.clone());
+            }
+            state.content = content.clone();
+            state.child_wnd.clone()
+        };
+
+        if let Some(child_wnd) = child_wnd {
+            load_content(pal::Wm::global(), &child_wnd, &content);
+        }
+    }
+
+    /// Attach a web view to a newly created child window.
+    fn update_child_wnd(self: Rc<Self>, wm: pal::Wm) {
+        let new_child_wnd = self.host.child_wnd();
+
+        let content = {
+            let mut state = self.state.borrow_mut();
+            if state.child_wnd == new_child_wnd {
+                return;
+            }
+            // The old child window's web view was destroyed along with it
+            state.child_wnd = None;
+            state.content.clone()
+        };
+
+        let child_wnd = if let Some(x) = new_child_wnd {
+            x
+        } else {
+            return;
+        };
+
+        let listener = WebViewListener {
+            inner: Rc::downgrade(&self),
+        };
+        if !wm.attach_web_view(&child_wnd, Box::new(listener)) {
+            log::warn!("Web views are not supported by the backend or the system");
+            return;
+        }
+
+        self.state.borrow_mut().child_wnd = Some(child_wnd.clone());
+        load_content(wm, &child_wnd, &content);
+    }
+}
+
+fn load_content(wm: pal::Wm, child_wnd: &pal::HChildWnd, content: &Content) {
+    match content {
+        Content::Blank => {}
+        Content::Url(url) => wm.web_view_navigate(child_wnd, url),
+        Content::Html { html, base_url } => {
+            wm.web_view_load_html(child_wnd, html, base_url.as_deref())
+        }
+    }
+}
+
+struct WebViewListener {
+    inner: Weak<Inner>,
+}
+
+impl WebViewListener {
+    /// Check if `child_wnd` is the one currently used by the widget and call
+    /// `f` via `Wm::invoke` if it is.
+    fn invoke_if_current(
+        &self,
+        wm: pal::Wm,
+        child_wnd: &pal::HChildWnd,
+        f: impl FnOnce(pal::Wm, &Inner) + 'static,
+    ) {
+        let inner_weak = self.inner.clone();
+        let child_wnd = child_wnd.clone();
+        wm.invoke(move |wm| {
+            if let Some(inner) = inner_weak.upgrade() {
+                if inner.state.borrow().child_wnd.as_ref() == Some(&child_wnd) {
+                    f(wm, &inner);
+                }
+            }
+        });
+    }
+}
+
+impl pal::iface::WebViewListener<pal::Wm> for WebViewListener {
+    fn navigation_started(&self, wm: pal::Wm, child_wnd: &pal::HChildWnd, url: &str) {
+        let url = url.to_owned();
+        self.invoke_if_current(wm, child_wnd, move |_, inner| {
+            inner.state.borrow_mut().url = Some(url);
+        });
+    }
+
+    fn navigation_completed(
+        &self,
+        wm: pal::Wm,
+        child_wnd: &pal::HChildWnd,
+        url: &str,
+        success: bool,
+    ) {
+        let url = url.to_owned();
+        self.invoke_if_current(wm, child_wnd, move |wm, inner| {
+            inner.state.borrow_mut().url = Some(url.clone());
+            inner
+                .navigation_handlers
+                .raise_with(|handler| handler(wm, &url, success));
+        });
+    }
+
+    fn message_received(&self, wm: pal::Wm, child_wnd: &pal::HChildWnd, message: &str) {
+        let message = message.to_owned();
+        self.invoke_if_current(wm, child_wnd, move |wm, inner| {
+            inner
+                .message_handlers
+                .raise_with(|handler| handler(wm, &message));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{prelude::*, use_testing_wm},
+        ui::layouts::{EmptyLayout, FillLayout},
+        uicore::HWnd,
+    };
+    use std::cell::RefCell;
+    use try_match::try_match;
+
+    #[use_testing_wm(testing = "crate::testing")]
+    #[test]
+    fn load_and_receive_messages(twm: &dyn TestingWm) {
+        let wm = twm.wm();
+        let web_view = WebView::new();
+
+        // The content is remembered until a web view is created
+        web_view.load_html("<p>hello</p>", Some("https://example.com/"));
+        assert!(!web_view.has_web_view());
+
+        let wnd = HWnd::new(wm);
+        wnd.content_view()
+            .set_layout(FillLayout::new(web_view.view()));
+        wnd.set_visibility(true);
+        twm.step_unsend();
+
+        let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone()).unwrap();
+        let child_wnd =
+            try_match!([x] = twm.child_wnds(&pal_hwnd).as_slice() => x.clone()).unwrap();
+        assert!(web_view.has_web_view());
+
+        let state = twm.web_view_state(&child_wnd).unwrap();
+        assert_eq!(state.html.as_deref(), Some("<p>hello</p>"));
+        assert_eq!(state.base_url.as_deref(), Some("https://example.com/"));
+
+        web_view.navigate("https://example.com/a");
+        web_view.eval_script("tcw3.postMessage(1 + 1)");
+        let state = twm.web_view_state(&child_wnd).unwrap();
+        assert_eq!(state.url.as_deref(), Some("https://example.com/a"));
+        assert_eq!(state.html, None);
+        assert_eq!(state.scripts, vec!["tcw3.postMessage(1 + 1)".to_owned()]);
+
+        // Events from the web view
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let _sub1 = web_view.subscribe_message({
+            let events = Rc::clone(&events);
+            Box::new(move |_, msg| events.borrow_mut().push(format!("message {}", msg)))
+        });
+        let _sub2 = web_view.subscribe_navigation_completed({
+            let events = Rc::clone(&events);
+            Box::new(move |_, url, success| {
+                events
+                    .borrow_mut()
+                    .push(format!("completed {} {}", url, success))
+            })
+        });
+
+        twm.raise_web_view_navigation_started(&child_wnd, "https://example.com/b");
+        twm.step_unsend();
+        assert_eq!(web_view.url().as_deref(), Some("https://example.com/b"));
+
+        twm.raise_web_view_navigation_completed(&child_wnd, "https://example.com/b", true);
+        twm.raise_web_view_message(&child_wnd, "2");
+        twm.step_unsend();
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                "completed https://example.com/b true".to_owned(),
+                "message 2".to_owned(),
+            ]
+        );
+
+        // Unmounting the view destroys the web view. The content is restored
+        // when the view is mounted again.
+        wnd.content_view()
+            .set_layout(EmptyLayout::new(SizeTraits::default()));
+        twm.step_unsend();
+        assert!(!web_view.has_web_view());
+        assert_eq!(twm.web_view_state(&child_wnd), None);
+
+        wnd.content_view()
+            .set_layout(FillLayout::new(web_view.view()));
+        twm.step_unsend();
+
+        let child_wnd =
+            try_match!([x] = twm.child_wnds(&pal_hwnd).as_slice() => x.clone()).unwrap();
+        let state = twm.web_view_state(&child_wnd).unwrap();
+        assert_eq!(state.url.as_deref(), Some("https://example.com/a"));
+    }
+}
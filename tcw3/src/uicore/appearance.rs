@@ -0,0 +1,63 @@
+//! Tracks the operating system's appearance settings.
+use log::debug;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+use subscriber_list::SubscriberList;
+use tcw3_pal::mt_lazy_static;
+
+use super::Sub;
+use crate::pal::{self, prelude::*, Wm};
+
+/// The boxed function type for `WmExt::subscribe_system_appearance_changed`.
+pub type SystemAppearanceCb = Box<dyn Fn(Wm)>;
+
+struct State {
+    /// The last known value of `Wm::system_appearance`. Used to filter out
+    /// redundant notifications (the backend sends one for every window).
+    ///
+    /// The backend can't send notifications while there are no windows, so
+    /// this value can be out of date at that point. It's re-validated
+    /// whenever a window is materialized.
+    last: Cell<Option<pal::SystemAppearance>>,
+    handlers: RefCell<SubscriberList<Rc<dyn Fn(Wm)>>>,
+}
+
+mt_lazy_static! {
+    static ref STATE: State => |_| State {
+        last: Cell::new(None),
+        handlers: RefCell::new(SubscriberList::new()),
+    };
+}
+
+/// Implements `WmExt::subscribe_system_appearance_changed`.
+pub fn subscribe_system_appearance_changed(wm: Wm, cb: SystemAppearanceCb) -> Sub {
+    let state = STATE.get_with_wm(wm);
+
+    if state.last.get().is_none() {
+        state.last.set(Some(wm.system_appearance()));
+    }
+
+    state.handlers.borrow_mut().insert(Rc::from(cb)).untype()
+}
+
+/// Called by `PalWndListener::system_appearance_changed`. Also called when a
+/// window is materialized to catch up on the changes made while there were no
+/// windows to receive notifications.
+pub fn handle_system_appearance_changed(wm: Wm) {
+    let state = STATE.get_with_wm(wm);
+
+    let new_value = wm.system_appearance();
+    if state.last.replace(Some(new_value)) == Some(new_value) {
+        return;
+    }
+
+    debug!("The system appearance has changed: {:?}", new_value);
+
+    // Clone the list so that the handlers can subscribe or unsubscribe
+    let handlers: Vec<_> = state.handlers.borrow().iter().cloned().collect();
+    for handler in handlers {
+        handler(wm);
+    }
+}
@@ -0,0 +1,159 @@
+//! User interface direction
+use flags_macro::flags;
+
+use super::{HViewRef, HWndRef, SuperviewStrong, ViewDirtyFlags};
+use crate::pal::{prelude::*, UiDirection};
+
+impl HWndRef<'_> {
+    /// Set the user interface direction of a window.
+    ///
+    /// `None` means the window follows [`Wm::default_ui_direction`], which is
+    /// the default value.
+    ///
+    /// [`Wm::default_ui_direction`]: crate::pal::iface::Wm::default_ui_direction
+    pub fn set_ui_direction(self, dir: Option<UiDirection>) {
+        if self.wnd.ui_direction.get() == dir {
+            return;
+        }
+
+        let old_dir = self.ui_direction();
+        self.wnd.ui_direction.set(dir);
+
+        if self.ui_direction() != old_dir {
+            if let Some(view) = &*self.wnd.content_view.borrow() {
+                view.as_ref().invalidate_ui_direction();
+            }
+        }
+    }
+
+    /// Get the user interface direction of a window, taking the default value
+    /// into account.
+    pub fn ui_direction(self) -> UiDirection {
+        self.wnd
+            .ui_direction
+            .get()
+            .unwrap_or_else(|| self.wnd.wm.default_ui_direction())
+    }
+}
+
+impl HViewRef<'_> {
+    /// Set the user interface direction of a view and its descendants.
+    ///
+    /// `None` means the view inherits the direction from the superview or the
+    /// containing window, which is the default value.
+    ///
+    /// Layouts can retrieve the direction by [`LayoutCtx::ui_direction`] and
+    /// should mirror horizontal arrangements if it's `UiDirection::Rtl`.
+    ///
+    /// [`LayoutCtx::ui_direction`]: crate::uicore::LayoutCtx::ui_direction
+    pub fn set_ui_direction(self, dir: Option<UiDirection>) {
+        if self.view.ui_direction.get() == dir {
+            return;
+        }
+
+        let old_dir = self.ui_direction();
+        self.view.ui_direction.set(dir);
+
+        if self.ui_direction() != old_dir {
+            self.invalidate_ui_direction();
+        }
+    }
+
+    /// Get the value set by [`set_ui_direction`].
+    ///
+    /// [`set_ui_direction`]: crate::uicore::HView::set_ui_direction
+    pub fn ui_direction_override(self) -> Option<UiDirection> {
+        self.view.ui_direction.get()
+    }
+
+    /// Get the effective user interface direction of a view.
+    ///
+    /// Returns `UiDirection::default()` if the view is not associated with
+    /// a window and neither it nor its ancestors have a direction set.
+    ///
+    /// The result is cached until the direction of the view or its ancestors
+    /// changes or the view is moved to another superview. Changes in
+    /// [`Wm::default_ui_direction`] are not detected.
+    ///
+    /// [`Wm::default_ui_direction`]: crate::pal::iface::Wm::default_ui_direction
+    pub fn ui_direction(self) -> UiDirection {
+        if let Some(dir) = self.view.ui_direction.get() {
+            return dir;
+        }
+
+        if let Some(dir) = self.view.ui_direction_cache.get() {
+            return dir;
+        }
+
+        // Since the cache is filled from the root, a view with a cached
+        // direction always has a superview with a cached or overridden one
+        let dir = match self.view.superview.borrow().upgrade() {
+            Some(SuperviewStrong::View(sv)) => HViewRef { view: (&sv).into() }.ui_direction(),
+            Some(SuperviewStrong::Window(wnd)) => HWndRef { wnd: (&wnd).into() }.ui_direction(),
+            None => UiDirection::default(),
+        };
+
+        self.view.ui_direction_cache.set(Some(dir));
+        dir
+    }
+
+    /// Update a view's direction after it was added to a new superview or
+    /// window.
+    pub(super) fn handle_ui_direction_superview_change(self) {
+        if self.view.ui_direction.get().is_some() {
+            return;
+        }
+
+        // If the direction was never resolved, neither was that of the
+        // descendants inheriting it, so there is nothing to invalidate
+        if let Some(old_dir) = self.view.ui_direction_cache.take() {
+            if self.ui_direction() != old_dir {
+                self.invalidate_ui_direction();
+            }
+        }
+    }
+
+    /// Discard the cached directions of a view and its descendants inheriting
+    /// the direction from it. This is used when a view is removed from its
+    /// superview or window.
+    pub(super) fn clear_ui_direction_cache(self) {
+        // Descendants can't have cached directions if `self` doesn't
+        if self.view.ui_direction_cache.take().is_none() {
+            return;
+        }
+
+        let layout = self.view.layout.borrow();
+        for subview in layout.subviews().iter() {
+            if subview.view.ui_direction.get().is_none() {
+                subview.as_ref().clear_ui_direction_cache();
+            }
+        }
+    }
+
+    /// Re-arrange and update a view and its descendants to reflect a change in
+    /// their effective user interface direction.
+    fn invalidate_ui_direction(self) {
+        self.set_dirty_flags_on_subtree_inheriting_direction();
+        self.set_dirty_flags_on_superviews(
+            flags![ViewDirtyFlags::{DESCENDANT_SUBVIEWS_FRAME | DESCENDANT_UPDATE_EVENT}],
+        );
+    }
+
+    fn set_dirty_flags_on_subtree_inheriting_direction(self) {
+        self.view.ui_direction_cache.set(None);
+        self.set_dirty_flags(flags![ViewDirtyFlags::{
+            SUBVIEWS_FRAME | DESCENDANT_SUBVIEWS_FRAME |
+            UPDATE_EVENT | DESCENDANT_UPDATE_EVENT
+        }]);
+
+        let layout = self.view.layout.borrow();
+        for subview in layout.subviews().iter() {
+            // Skip the subtrees with their own directions
+            if subview.view.ui_direction.get().is_none() {
+                subview
+                    .as_ref()
+                    .set_dirty_flags_on_subtree_inheriting_direction();
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! View tree introspection
+use cggeom::{prelude::*, Box2};
+use std::fmt;
+
+use super::{HView, HViewRef, HWndRef, SizeTraits, SuperviewStrong, ViewFlags};
+
+/// A snapshot of a window's view hierarchy, created by
+/// [`HWndRef::dump_view_tree`].
+///
+/// The `Display` implementation prints the hierarchy as an indented tree,
+/// which is useful for diagnosing layout problems.
+#[derive(Debug, Clone)]
+pub struct ViewTreeSnapshot {
+    /// The snapshot of the content view. `None` if the window has already been
+    /// closed.
+    pub root: Option<ViewSnapshot>,
+}
+
+/// A snapshot of a view and its descendants. See [`ViewTreeSnapshot`].
+#[derive(Debug, Clone)]
+pub struct ViewSnapshot {
+    pub view: HView,
+    /// The value set by [`HViewRef::set_debug_name`].
+    pub debug_name: Option<&'static str>,
+    /// The value returned by [`ViewListener::type_name`]. `None` if the
+    /// listener was mutably borrowed when the snapshot was taken.
+    ///
+    /// [`ViewListener::type_name`]: super::ViewListener::type_name
+    pub listener_type_name: Option<&'static str>,
+    pub flags: ViewFlags,
+    /// The internal dirty flags in a human-readable form. The format is
+    /// unspecified and subject to change.
+    pub dirty: String,
+    pub size_traits: SizeTraits,
+    pub frame: Box2<f32>,
+    pub global_frame: Box2<f32>,
+    pub global_visible_frame: Box2<f32>,
+    pub subviews: Vec<ViewSnapshot>,
+}
+
+impl HWndRef<'_> {
+    /// Take a snapshot of the window's view hierarchy.
+    ///
+    /// The frame rectangles in the snapshot might be out-dated unless this
+    /// method is called after the window is updated. See
+    /// [`HViewRef::frame`] for details.
+    pub fn dump_view_tree(self) -> ViewTreeSnapshot {
+        let root = self.wnd.content_view.borrow().clone();
+        ViewTreeSnapshot {
+            root: root.map(|view| view.as_ref().snapshot()),
+        }
+    }
+}
+
+impl HViewRef<'_> {
+    /// Get the superview of a view.
+    ///
+    /// Returns `None` if the view is a window's content view or is not
+    /// attached to any view.
+    pub fn superview(self) -> Option<HView> {
+        match self.view.superview.borrow().upgrade() {
+            Some(SuperviewStrong::View(view)) => Some(HView { view }),
+            _ => None,
+        }
+    }
+
+    /// Get the subviews of a view, i.e., the views returned by the current
+    /// layout's [`Layout::subviews`].
+    ///
+    /// It's not allowed to call this method from `Layout`'s method.
+    ///
+    /// [`Layout::subviews`]: super::Layout::subviews
+    pub fn subviews(self) -> Vec<HView> {
+        self.view.layout.borrow().subviews().to_vec()
+    }
+
+    /// Set a name to identify a view in [`ViewTreeSnapshot`].
+    ///
+    /// The default value is `None`.
+    pub fn set_debug_name(self, name: Option<&'static str>) {
+        self.view.debug_name.set(name);
+    }
+
+    /// Get the value set by [`set_debug_name`].
+    ///
+    /// [`set_debug_name`]: crate::uicore::HView::set_debug_name
+    pub fn debug_name(self) -> Option<&'static str> {
+        self.view.debug_name.get()
+    }
+
+    fn snapshot(self) -> ViewSnapshot {
+        let view = &self.view;
+        ViewSnapshot {
+            view: self.cloned(),
+            debug_name: view.debug_name.get(),
+            listener_type_name: view.listener.try_borrow().ok().map(|l| l.type_name()),
+            flags: view.flags.get(),
+            dirty: format!("{:?}", view.dirty.get()),
+            size_traits: view.size_traits.get(),
+            frame: view.frame.get(),
+            global_frame: view.global_frame.get(),
+            global_visible_frame: view.global_visible_frame.get(),
+            subviews: (view.layout.borrow().subviews().iter())
+                .map(|subview| subview.as_ref().snapshot())
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for ViewTreeSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(root) = &self.root {
+            root.fmt_indented(f, 0)
+        } else {
+            writeln!(f, "(closed)")
+        }
+    }
+}
+
+impl fmt::Display for ViewSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+impl ViewSnapshot {
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:1$}", "", depth * 2)?;
+        if let Some(name) = self.debug_name {
+            write!(f, "{:?} ", name)?;
+        }
+        writeln!(
+            f,
+            "{} [{}] flags: {:?}, dirty: {}",
+            self.listener_type_name.unwrap_or("(borrowed)"),
+            self.global_frame.display_im(),
+            self.flags,
+            self.dirty,
+        )?;
+
+        for subview in self.subviews.iter() {
+            subview.fmt_indented(f, depth + 1)?;
+        }
+
+        Ok(())
+    }
+}
@@ -115,6 +115,46 @@ impl HWndRef<'_> {
         self.wnd.focused_view.borrow().clone()
     }
 
+    /// Set the accelerator table of the window.
+    ///
+    /// The accelerator table is used to translate key events to actions, which
+    /// are dispatched through the focus chain (the focused view, its ancestors,
+    /// and finally the window's [`WndListener`]) by calling
+    /// [`ViewListener::validate_action`] and [`ViewListener::perform_action`].
+    /// Accelerator tables provided by [`WndListener::interpret_event`] take
+    /// precedence over this one.
+    ///
+    /// [`WndListener`]: super::WndListener
+    /// [`WndListener::interpret_event`]: super::WndListener::interpret_event
+    /// [`ViewListener::validate_action`]: super::ViewListener::validate_action
+    /// [`ViewListener::perform_action`]: super::ViewListener::perform_action
+    ///
+    /// # Example
+    ///
+    ///     use tcw3::{uicore::HWnd, pal};
+    ///     static ACCEL_TABLE: pal::AccelTable = pal::accel_table![
+    ///         (
+    ///             pal::actions::SELECT_ALL,
+    ///             windows("Ctrl+A"),
+    ///             gtk("Ctrl+A"),
+    ///             macos_sel("selectAll:")
+    ///         ),
+    ///     ];
+    ///     # fn test(wnd: HWnd) {
+    ///     wnd.set_accel_table(Some(&ACCEL_TABLE));
+    ///     # }
+    ///
+    pub fn set_accel_table(self, accel_table: Option<&'static pal::AccelTable>) {
+        self.wnd.accel_table.set(accel_table);
+    }
+
+    /// Get the accelerator table set by [`set_accel_table`].
+    ///
+    /// [`set_accel_table`]: HWndRef::set_accel_table
+    pub fn accel_table(self) -> Option<&'static pal::AccelTable> {
+        self.wnd.accel_table.get()
+    }
+
     pub(super) fn borrow_focused_view(&self) -> impl std::ops::Deref<Target = Option<HView>> + '_ {
         self.wnd.focused_view.borrow()
     }
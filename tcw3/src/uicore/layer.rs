@@ -1,13 +1,13 @@
 use bitflags::bitflags;
+use cggeom::{prelude::*, Box2};
 use flags_macro::flags;
-use rc_borrow::RcBorrow;
 
-use super::{HView, HViewRef, HWndRef, ViewDirtyFlags, ViewFlags};
+use super::{viewarena::ViewBorrow, HView, HViewRef, HWndRef, ViewDirtyFlags, ViewFlags};
 use crate::pal::{self, Wm};
 
 impl HViewRef<'_> {
     pub(super) fn view_with_containing_layer(self) -> Option<HView> {
-        let mut view_or_not = Some(RcBorrow::upgrade(self.view));
+        let mut view_or_not = Some(ViewBorrow::upgrade(self.view));
         while let Some(view) = view_or_not {
             if view.flags.get().contains(ViewFlags::LAYER_GROUP) {
                 return Some(HView { view });
@@ -30,6 +30,22 @@ impl HViewRef<'_> {
         }
     }
 
+    /// Schedule calls to `ViewListener::update` for the view and all of its
+    /// descendants with `UpdateReason::DPI_SCALE_CHANGE`.
+    pub(super) fn pend_update_on_dpi_scale_change(self) {
+        self.set_dpi_scale_dirty_flags();
+        self.set_dirty_flags_on_superviews(ViewDirtyFlags::DESCENDANT_UPDATE_EVENT);
+    }
+
+    fn set_dpi_scale_dirty_flags(self) {
+        self.set_dirty_flags(flags![ViewDirtyFlags::{
+            UPDATE_EVENT | DESCENDANT_UPDATE_EVENT | DPI_SCALE
+        }]);
+        for subview in self.view.layout.borrow().subviews().iter() {
+            subview.as_ref().set_dpi_scale_dirty_flags();
+        }
+    }
+
     /// Call `ViewListener::update` on sublayers as necessary.
     ///
     /// Returns `true` if `layers` has changed. The return value is used to
@@ -68,11 +84,15 @@ impl HViewRef<'_> {
                 sublayers: None,
                 layers: &mut *layers,
                 layers_updated: false,
+                damage: None,
             };
 
             if dirty.get().intersects(ViewDirtyFlags::UPDATE_EVENT) {
                 ctx.reason |= UpdateReason::PEND_UPDATE;
             }
+            if dirty.get().intersects(ViewDirtyFlags::DPI_SCALE) {
+                ctx.reason |= UpdateReason::DPI_SCALE_CHANGE;
+            }
             if dirty.get().intersects(ViewDirtyFlags::SUBLAYERS) {
                 ctx.reason |= UpdateReason::SUBLAYERS_CHANGE;
 
@@ -90,10 +110,23 @@ impl HViewRef<'_> {
                 ctx.sublayers = Some(sublayers);
             }
 
-            dirty.set(dirty.get() - update_flags);
+            dirty.set(dirty.get() - update_flags - ViewDirtyFlags::DPI_SCALE);
 
             self.view.listener.borrow().update(wm, self, &mut ctx);
 
+            // A change in the layer tree can affect any part of the window
+            let damage =
+                if ctx.layers_updated || ctx.reason.contains(UpdateReason::SUBLAYERS_CHANGE) {
+                    None
+                } else {
+                    ctx.damage
+                };
+            hwnd.add_damage(damage);
+
+            hwnd.record_debug_dirty_region(
+                damage.unwrap_or_else(|| self.view.global_visible_frame.get()),
+            );
+
             if ctx.layers_updated {
                 layers_changed = true;
             }
@@ -103,6 +136,33 @@ impl HViewRef<'_> {
     }
 }
 
+/// The region of a window affected by the layer changes made in the current
+/// frame.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum WndDamage {
+    Empty,
+    Partial(Box2<f32>),
+    Full,
+}
+
+impl Default for WndDamage {
+    fn default() -> Self {
+        Self::Empty
+    }
+}
+
+impl HWndRef<'_> {
+    /// Extend the window's damage region. `None` represents the whole window.
+    fn add_damage(self, damage: Option<Box2<f32>>) {
+        let wnd_damage = &self.wnd.damage;
+        wnd_damage.set(match (wnd_damage.get(), damage) {
+            (WndDamage::Full, _) | (_, None) => WndDamage::Full,
+            (WndDamage::Empty, Some(x)) => WndDamage::Partial(x),
+            (WndDamage::Partial(x), Some(y)) => WndDamage::Partial(x.union(&y)),
+        });
+    }
+}
+
 /// The context for [`ViewListener::update`].
 ///
 /// [`ViewListener::update`]: crate::uicore::ViewListener::update
@@ -112,6 +172,7 @@ pub struct UpdateCtx<'a> {
     layers: &'a mut Vec<pal::HLayer>,
     layers_updated: bool,
     hwnd: HWndRef<'a>,
+    damage: Option<Box2<f32>>,
 }
 
 impl<'a> UpdateCtx<'a> {
@@ -149,6 +210,21 @@ impl<'a> UpdateCtx<'a> {
     pub fn layers(&self) -> &[pal::HLayer] {
         &self.layers[..]
     }
+
+    /// Report the region (in window coordinates) affected by the layer
+    /// changes made by this call to `update`. Multiple calls are combined.
+    ///
+    /// The region must include both of the old and new extents of every
+    /// changed layer. If every view updated in a frame reports a region,
+    /// the backend may only redraw their union. Otherwise, or if the set of
+    /// layers has changed, the whole window is redrawn. Thus, a view that
+    /// modifies its layers outside `update` must not call this method.
+    pub fn set_damage(&mut self, damage: Box2<f32>) {
+        self.damage = Some(match self.damage {
+            Some(x) => x.union(&damage),
+            None => damage,
+        });
+    }
 }
 
 bitflags! {
@@ -164,5 +240,10 @@ bitflags! {
         ///
         /// This bit is valid only for layers with [`ViewFlags::LAYER_GROUP`].
         const SUBLAYERS_CHANGE = 1 << 1;
+
+        /// The DPI scale of the containing window has changed. Layer contents
+        /// depending on the DPI scale (e.g., bitmaps) should be regenerated.
+        /// Always accompanied by `PEND_UPDATE`.
+        const DPI_SCALE_CHANGE = 1 << 2;
     }
 }
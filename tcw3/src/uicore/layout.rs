@@ -1,12 +1,11 @@
 use as_any::AsAny;
 use cggeom::{box2, prelude::*, Box2};
-use cgmath::{vec2, Point2, Vector2};
+use cgmath::{vec2, EuclideanSpace, Point2, Vector2};
 use flags_macro::flags;
 use log::trace;
-use rc_borrow::RcBorrow;
-use std::{fmt, rc::Rc};
+use std::fmt;
 
-use super::{HView, HViewRef, ViewDirtyFlags, ViewFlags};
+use super::{viewarena::ViewBorrow, HView, HViewRef, UiDirection, ViewDirtyFlags, ViewFlags};
 use crate::pal::Wm;
 
 /// Represents a type defining the positioning of subviews.
@@ -181,11 +180,49 @@ impl HViewRef<'_> {
         self.view.global_visible_frame.get()
     }
 
+    /// Get the frame of a view in `other`'s local coordinate space, whose
+    /// origin is the top-left corner of `other`'s [`global_frame`].
+    ///
+    /// The views don't have to be related to each other, but they should be
+    /// in the same window for the result to be meaningful.
+    ///
+    /// [`global_frame`]: crate::uicore::HView::global_frame
+    pub fn frame_in(self, other: HViewRef<'_>) -> Box2<f32> {
+        let origin = other.global_frame().min;
+        self.global_frame().translate(vec2(-origin.x, -origin.y))
+    }
+
+    /// Convert a point in the view's local coordinate space (see
+    /// [`frame_in`]) to `other`'s local coordinate space.
+    ///
+    /// [`frame_in`]: crate::uicore::HView::frame_in
+    pub fn convert_point_to(self, other: HViewRef<'_>, p: Point2<f32>) -> Point2<f32> {
+        p + (self.global_frame().min - other.global_frame().min)
+    }
+
+    /// Convert a point in the containing window's coordinate space (e.g., a
+    /// mouse pointer location passed to [`MouseDragListener`]) to the view's
+    /// local coordinate space.
+    ///
+    /// [`MouseDragListener`]: crate::uicore::MouseDragListener
+    pub fn convert_point_from_window(self, p: Point2<f32>) -> Point2<f32> {
+        p - self.global_frame().min.to_vec()
+    }
+
+    /// Convert a point in the view's local coordinate space to the containing
+    /// window's coordinate space.
+    pub fn convert_point_to_window(self, p: Point2<f32>) -> Point2<f32> {
+        p + self.global_frame().min.to_vec()
+    }
+
     /// Update `size_traits` of a view. This implements the *up phase* of the
     /// layouting algorithm.
     ///
     /// Returns `true` if `size_traits` has changed. The return value is used to
     /// implement a recursive algorithm of `update_size_traits` itself.
+    /// A superview's `size_traits` is recalculated only if some of its
+    /// subviews' `size_traits` has actually changed, so a localized change
+    /// does not propagate further than needed.
     pub(super) fn update_size_traits(self) -> bool {
         let dirty = &self.view.dirty;
         let layout = self.view.layout.borrow();
@@ -497,12 +534,39 @@ impl<'a> LayoutCtx<'a> {
         hview.view.frame.get()
     }
 
+    /// Get the effective user interface direction of the active view.
+    ///
+    /// Layouts should mirror horizontal arrangements (e.g., the order of
+    /// items, horizontal margins, and alignment) if this is
+    /// `UiDirection::Rtl`. [`LayoutCtx::mirror_frame_if_rtl`] can be used to
+    /// do this.
+    pub fn ui_direction(&self) -> UiDirection {
+        self.active_view.ui_direction()
+    }
+
+    /// Mirror a subview frame `frame` horizontally inside the active view of
+    /// width `width` if the active view's user interface direction is
+    /// `UiDirection::Rtl`. Otherwise, return `frame` unmodified.
+    ///
+    /// This allows layouts to calculate the arrangement in the left-to-right
+    /// direction and convert it to the final one as the last step.
+    pub fn mirror_frame_if_rtl(&self, frame: Box2<f32>, width: f32) -> Box2<f32> {
+        if self.ui_direction().is_rtl() {
+            box2! {
+                min: [width - frame.max.x, frame.min.y],
+                max: [width - frame.min.x, frame.max.y],
+            }
+        } else {
+            frame
+        }
+    }
+
     /// Panic if `hview` is not a subview of the active view and
     /// debug assertions are enabled.
     fn ensure_subview(&self, hview: HViewRef<'_>) {
         debug_assert_eq!(
             *hview.view.superview.borrow(),
-            Rc::downgrade(&RcBorrow::upgrade(self.active_view.view)),
+            ViewBorrow::to_weak(self.active_view.view),
             "the view is not a subview"
         );
     }
@@ -12,8 +12,9 @@
 //!
 //! [`WeakHWnd`] and [`WeakHView`] represent [a weak reference]. They can be
 //! converted to their respective strong reference types by calling the
-//! `cloned` methods¹, which may fail and return `None` if their referents are
-//! no longer existent.
+//! `upgrade` methods, which may fail and return `None` if their referents are
+//! no longer existent. Borrowed handles can be converted to owned handles by
+//! calling the `cloned` methods¹.
 //!
 //! ¹ They are called `cloned` by analogy with `Option<&Rc<_>>::cloned`. They do
 //! not clone the underlying object, but rather clone the strong reference,
@@ -21,6 +22,26 @@
 //!
 //! [a weak reference]: https://en.wikipedia.org/wiki/Weak_reference
 //!
+//! Views are allocated from a per-thread arena instead of individually. A view
+//! handle refers to an arena slot, and a weak handle additionally records the
+//! slot's generation, which is bumped when the view is destroyed. Thus, weak
+//! handles don't keep the memory of destroyed views alive.
+//!
+//! Closures stored in long-lived places (such as event handlers, timers, and
+//! listeners of other views) should capture weak handles instead of strong
+//! ones. Otherwise, the referenced view and its entire subtree are kept alive
+//! for as long as the closure exists, causing a memory leak or even a
+//! reference cycle:
+//!
+//! ```text
+//! let view_weak = view.downgrade();
+//! let sub = button.subscribe_activated(Box::new(move |_| {
+//!     if let Some(view) = view_weak.upgrade() {
+//!         view.pend_update();
+//!     }
+//! }));
+//! ```
+//!
 //! The following diagram summarizes the possible conversions between these
 //! types:
 //!
@@ -37,6 +58,9 @@
 //!                                 v |
 //!                              WeakHView
 //!
+//! `HViewRef::downgrade` is also available as a shorthand for
+//! `view.cloned().downgrade()`.
+//!
 //! Costly operations are marked with [!].
 //! ```
 //!
@@ -59,23 +83,35 @@ use subscriber_list::{SubscriberList, UntypedSubscription};
 
 use crate::pal::{self, prelude::*, Wm};
 
+use self::viewarena::{ViewBorrow, ViewRc, ViewWeak};
+
+mod appearance;
+mod direction;
 mod images;
+mod introspect;
 mod invocation;
 mod keybd;
 mod layer;
 mod layout;
 mod mount;
 mod mouse;
+mod overlay;
+mod reentrancy;
 mod taborder;
+mod viewarena;
 mod window;
 
+pub use self::appearance::SystemAppearanceCb;
+pub use self::introspect::{ViewSnapshot, ViewTreeSnapshot};
 pub use self::layer::{UpdateCtx, UpdateReason};
 pub use self::layout::{Layout, LayoutCtx, SizeTraits};
-pub use self::mouse::{MouseDragListener, ScrollListener};
+pub use self::mouse::{GestureListener, MouseDragListener, ScrollListener};
+pub use self::overlay::DebugOverlayFlags;
 pub use self::taborder::TabOrderSibling;
 
 pub use crate::pal::{
-    actions, ActionId, ActionStatus, CursorShape, ScrollDelta, WndFlags as WndStyleFlags,
+    actions, ActionId, ActionStatus, CursorShape, ProgressState, ScrollDelta, SystemAppearance,
+    UiDirection, WndFlags as WndStyleFlags,
 };
 
 /// The maxiumum supported depth of view hierarchy.
@@ -94,12 +130,24 @@ pub trait WmExt: Sized {
     ///
     /// This is **not** an equivalent of JavaScript's `requestAnimationFrame`!
     fn invoke_on_update(self, f: impl FnOnce(Self) + 'static);
+
+    /// Register a function called when the operating system's appearance
+    /// settings (returned by `Wm::system_appearance`) have changed.
+    ///
+    /// Unlike `WndListener::system_appearance_changed` of the backend, the
+    /// function is called only once per change regardless of the number of
+    /// open windows.
+    fn subscribe_system_appearance_changed(self, cb: SystemAppearanceCb) -> Sub;
 }
 
 impl WmExt for Wm {
     fn invoke_on_update(self, f: impl FnOnce(Self) + 'static) {
         invocation::invoke_on_update(self, f);
     }
+
+    fn subscribe_system_appearance_changed(self, cb: SystemAppearanceCb) -> Sub {
+        appearance::subscribe_system_appearance_changed(self, cb)
+    }
 }
 
 /// A window handle type.
@@ -217,6 +265,14 @@ pub trait WndListener {
 
     /// Perform the specified action.
     fn perform_action(&self, _: Wm, _: HWndRef<'_>, _: ActionId) {}
+
+    /// The DPI scale of a window has changed. The new value can be retrieved
+    /// by [`HWndRef::dpi_scale`].
+    ///
+    /// By the time this method is called, `ViewListener::update` is already
+    /// scheduled to be called for all views in the window with
+    /// [`UpdateReason::DPI_SCALE_CHANGE`].
+    fn dpi_scale_changed(&self, _: Wm, _: HWndRef<'_>) {}
 }
 
 pub type InterpretEventCtx<'a> = dyn pal::iface::InterpretEventCtx<pal::AccelTable> + 'a;
@@ -265,9 +321,22 @@ struct Wnd {
     /// The content view, which can be `None` only after the window is closed.
     content_view: RefCell<Option<HView>>,
     style_attrs: RefCell<window::WndStyleAttrs>,
+    ui_direction: Cell<Option<UiDirection>>,
     updating: Cell<bool>,
+    /// The size requested by `HWnd::set_size`, which is applied by the next
+    /// update.
+    requested_size: Cell<Option<[u32; 2]>>,
+    /// The position requested by `HWnd::set_position`, which is applied by
+    /// the next update.
+    requested_position: Cell<Option<[i32; 2]>>,
+    /// The state requested by `HWnd::set_maximized`, which is applied by the
+    /// next update.
+    requested_maximized: Cell<Option<bool>>,
+    /// The current phase of the update pipeline. Used for diagnostics.
+    phase: Cell<Option<reentrancy::UpdatePhase>>,
     dpi_scale_changed_handlers: RefCell<SubscriberList<WndCb>>,
     frame_handlers: LinkedListCell<AssertUnpin<dyn FnOnce(Wm, HWndRef<'_>)>>,
+    after_update_handlers: LinkedListCell<AssertUnpin<dyn FnOnce(Wm)>>,
     focus_handlers: RefCell<SubscriberList<WndCb>>,
 
     // Mouse inputs
@@ -276,6 +345,13 @@ struct Wnd {
 
     // Keyboard inputs
     focused_view: RefCell<Option<HView>>,
+    accel_table: Cell<Option<&'static pal::AccelTable>>,
+
+    debug_overlay: RefCell<overlay::WndDebugOverlay>,
+
+    /// The damage region accumulated by `ViewListener::update` calls in the
+    /// current frame.
+    damage: Cell<layer::WndDamage>,
 }
 
 impl fmt::Debug for Wnd {
@@ -291,12 +367,24 @@ impl fmt::Debug for Wnd {
             .field("closed", &self.closed)
             .field("content_view", &self.content_view)
             .field("style_attrs", &self.style_attrs)
+            .field("ui_direction", &self.ui_direction)
             .field("updating", &self.updating)
+            .field("requested_size", &self.requested_size)
+            .field("requested_position", &self.requested_position)
+            .field("requested_maximized", &self.requested_maximized)
+            .field("phase", &self.phase)
             .field("dpi_scale_changed_handlers", &())
             .field("frame_handlers", &())
+            .field("after_update_handlers", &())
             .field("mouse_state", &self.mouse_state)
             .field("focus_handlers", &())
             .field("focused_view", &self.focused_view)
+            .field(
+                "accel_table",
+                &self.accel_table.get().map(|x| x as *const _),
+            )
+            .field("debug_overlay", &self.debug_overlay)
+            .field("damage", &self.damage)
             .finish()
     }
 }
@@ -316,13 +404,22 @@ impl Wnd {
             closed: Cell::new(false),
             content_view: RefCell::new(Some(content_view)),
             style_attrs: RefCell::new(Default::default()),
+            ui_direction: Cell::new(None),
             updating: Cell::new(false),
+            requested_size: Cell::new(None),
+            requested_position: Cell::new(None),
+            requested_maximized: Cell::new(None),
+            phase: Cell::new(None),
             dpi_scale_changed_handlers: RefCell::new(SubscriberList::new()),
             frame_handlers: LinkedListCell::new(),
+            after_update_handlers: LinkedListCell::new(),
             mouse_state: RefCell::new(mouse::WndMouseState::new()),
             cursor_shape: Cell::new(CursorShape::default()),
             focus_handlers: RefCell::new(SubscriberList::new()),
             focused_view: RefCell::new(None),
+            accel_table: Cell::new(None),
+            debug_overlay: RefCell::new(Default::default()),
+            damage: Cell::new(Default::default()),
         }
     }
 }
@@ -332,19 +429,19 @@ impl Wnd {
 /// A view handle type.
 #[derive(Clone)]
 pub struct HView {
-    view: Rc<View>,
+    view: ViewRc,
 }
 
 /// A weak view handle type.
 #[derive(Default, Debug, Clone)]
 pub struct WeakHView {
-    view: Weak<View>,
+    view: ViewWeak,
 }
 
 /// Borrowed version of [`HView`].
 #[derive(Copy, Clone)]
 pub struct HViewRef<'a> {
-    view: RcBorrow<'a, View>,
+    view: ViewBorrow<'a>,
 }
 
 impl fmt::Debug for HView {
@@ -367,7 +464,7 @@ impl fmt::Debug for HViewRef<'_> {
         )?;
 
         // Display the path
-        let mut view: Rc<View> = RcBorrow::upgrade(self.view);
+        let mut view: ViewRc = ViewBorrow::upgrade(self.view);
         loop {
             if let Some(sv) = { view }.superview.borrow().upgrade() {
                 match sv {
@@ -465,6 +562,9 @@ bitflags! {
         ///
         /// This flag cannot be added or removed once a view is created.
         const CLIP_VISIBLE_FRAME = 1 << 10;
+
+        /// The view accepts magnification and rotation gestures.
+        const ACCEPT_GESTURE = 1 << 11;
     }
 }
 
@@ -520,9 +620,16 @@ pub trait ViewListener {
     /// The system automatically flushes changes to the layers by calling
     /// [`Wm::update_wnd`] after calling this method for all
     /// pending views, so this is the optimal place to update the properties of
-    /// associated layers (if any).
+    /// associated layers (if any). All changes made during the update are
+    /// grouped by a layer transaction ([`Wm::begin_layer_txn`]).
+    ///
+    /// Backends only re-render the region covered by the layers whose
+    /// attributes have changed. Thus, if only a small part of a large view
+    /// changes frequently, consider giving that part a separate layer. The
+    /// region can be further narrowed down by [`UpdateCtx::set_damage`].
     ///
     /// [`Wm::update_wnd`]: crate::pal::iface::Wm::update_wnd
+    /// [`Wm::begin_layer_txn`]: crate::pal::iface::Wm::begin_layer_txn
     fn update(&self, _: Wm, _: HViewRef<'_>, _: &mut UpdateCtx<'_>) {}
 
     /// Called when a key is pressed.
@@ -603,6 +710,14 @@ pub trait ViewListener {
         Box::new(())
     }
 
+    /// Get event handlers for handling the magnification/rotation gesture
+    /// that started right now.
+    ///
+    /// You must set [`ViewFlags::ACCEPT_GESTURE`] for this to be called.
+    fn gesture(&self, _: Wm, _: HViewRef<'_>, _loc: Point2<f32>) -> Box<dyn GestureListener> {
+        Box::new(())
+    }
+
     /// `focus_got` is called for this view or its descendants.
     fn focus_enter(&self, _: Wm, _: HViewRef<'_>) {}
     /// `focus_lost` is called for this view or its descendants.
@@ -620,6 +735,13 @@ pub trait ViewListener {
 
     /// Perform the specified action.
     fn perform_action(&self, _: Wm, _: HViewRef<'_>, _: ActionId) {}
+
+    /// Get the name of the implementing type. This is used by
+    /// [`HWnd::dump_view_tree`] for diagnostic purposes, and there's usually
+    /// no need to override this.
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 /// A no-op implementation of `ViewListener`.
@@ -635,6 +757,12 @@ struct View {
     dirty: Cell<ViewDirtyFlags>,
     flags: Cell<ViewFlags>,
     cursor_shape: Cell<Option<CursorShape>>,
+    ui_direction: Cell<Option<UiDirection>>,
+    /// The effective direction resolved by `HViewRef::ui_direction`. `None` if
+    /// it hasn't been resolved since the last invalidation or if
+    /// `ui_direction` is set.
+    ui_direction_cache: Cell<Option<UiDirection>>,
+    debug_name: Cell<Option<&'static str>>,
 
     listener: RefCell<Box<dyn ViewListener>>,
     layout: RefCell<Box<dyn Layout>>,
@@ -665,6 +793,8 @@ impl fmt::Debug for View {
         f.debug_struct("View")
             .field("dirty", &self.dirty)
             .field("flags", &self.flags)
+            .field("ui_direction", &self.ui_direction)
+            .field("debug_name", &self.debug_name)
             .field(
                 "listener",
                 &self.listener.try_borrow().map(|x| &*x as *const _),
@@ -702,6 +832,9 @@ impl View {
             has_frame: Cell::new(false),
             layers: RefCell::new(Vec::new()),
             cursor_shape: Cell::new(None),
+            ui_direction: Cell::new(None),
+            ui_direction_cache: Cell::new(None),
+            debug_name: Cell::new(None),
             focus_link_override: RefCell::new(None),
         }
     }
@@ -709,19 +842,19 @@ impl View {
 
 #[derive(Debug, Clone, From)]
 enum Superview {
-    View(Weak<View>),
+    View(ViewWeak),
     Window(Weak<Wnd>),
 }
 
 #[derive(Debug, Clone)]
 enum SuperviewStrong {
-    View(Rc<View>),
+    View(ViewRc),
     Window(Rc<Wnd>),
 }
 
 impl Superview {
     fn empty() -> Self {
-        Superview::View(Weak::new())
+        Superview::View(ViewWeak::new())
     }
 
     fn is_empty(&self) -> bool {
@@ -738,7 +871,7 @@ impl Superview {
         }
     }
 
-    fn view(&self) -> Option<&Weak<View>> {
+    fn view(&self) -> Option<&ViewWeak> {
         match self {
             Superview::View(weak) => Some(weak),
             Superview::Window(_) => None,
@@ -753,10 +886,10 @@ impl Superview {
     }
 }
 
-impl PartialEq<Weak<View>> for Superview {
-    fn eq(&self, other: &Weak<View>) -> bool {
+impl PartialEq<ViewWeak> for Superview {
+    fn eq(&self, other: &ViewWeak) -> bool {
         match self {
-            Superview::View(weak) => Weak::ptr_eq(weak, other),
+            Superview::View(weak) => ViewWeak::ptr_eq(weak, other),
             Superview::Window(_) => false,
         }
     }
@@ -827,6 +960,14 @@ impl HWnd {
         /// See the documentation of [`HWndRef`].
         pub fn close(&self);
         pub fn pal_hwnd(&self) -> Option<pal::HWnd>;
+        pub fn size(&self) -> Option<[u32; 2]>;
+        pub fn set_size(&self, size: [u32; 2]);
+        pub fn position(&self) -> Option<[i32; 2]>;
+        pub fn set_position(&self, position: [i32; 2]);
+        pub fn is_maximized(&self) -> bool;
+        pub fn set_maximized(&self, maximized: bool);
+        pub fn render_to_bitmap(&self) -> Option<pal::Bitmap>;
+        pub fn clone_into_new_window(&self, mirror_view: impl FnOnce(HViewRef<'_>) -> HView) -> HWnd;
         pub fn dpi_scale(&self) -> f32;
         pub fn subscribe_dpi_scale_changed(&self, cb: WndCb) -> Sub;
         pub fn is_focused(&self) -> bool;
@@ -840,11 +981,29 @@ impl HWnd {
         pub fn caption(&self) -> String;
         pub fn set_style_flags(&self, flags: WndStyleFlags);
         pub fn style_flags(&self) -> WndStyleFlags;
+        pub fn set_progress(&self, progress: Option<ProgressState>);
+        pub fn progress(&self) -> Option<ProgressState>;
+        pub fn set_pointer_grab(&self, grab: bool);
+        pub fn pointer_grab(&self) -> bool;
         pub fn invoke_on_next_frame(&self, f: impl FnOnce(pal::Wm, HWndRef<'_>) + 'static);
+        pub fn invoke_after_update(&self, f: impl FnOnce(pal::Wm) + 'static);
+
+        // `direction.rs`
+        pub fn set_ui_direction(&self, dir: Option<UiDirection>);
+        pub fn ui_direction(&self) -> UiDirection;
+
+        // `introspect.rs`
+        pub fn dump_view_tree(&self) -> ViewTreeSnapshot;
+
+        // `overlay.rs`
+        pub fn set_debug_overlay(&self, flags: DebugOverlayFlags);
+        pub fn debug_overlay(&self) -> DebugOverlayFlags;
 
         // `keybd.rs`
         pub fn set_focused_view(&self, view: Option<HView>);
         pub fn focused_view(&self) -> Option<HView>;
+        pub fn set_accel_table(&self, accel_table: Option<&'static pal::AccelTable>);
+        pub fn accel_table(&self) -> Option<&'static pal::AccelTable>;
     }
 }
 
@@ -874,6 +1033,13 @@ impl HWndRef<'_> {
         self.into()
     }
 
+    /// Construct a weak handle.
+    pub fn downgrade(self) -> WeakHWnd {
+        WeakHWnd {
+            wnd: Rc::downgrade(&RcBorrow::upgrade(self.wnd)),
+        }
+    }
+
     /// Close a window.
     ///
     /// Closing a window ensures that all operating system resources associated
@@ -957,6 +1123,8 @@ impl HWndRef<'_> {
                 "the view already has a parent"
             );
 
+            *view.view.superview.borrow_mut() = Superview::Window(Rc::downgrade(&self.wnd));
+
             // Pend a call to `ViewListener::mount`
             let dirty = &view.view.dirty;
             dirty.set(dirty.get() | ViewDirtyFlags::MOUNT);
@@ -968,8 +1136,15 @@ impl HWndRef<'_> {
             dirty.set(dirty.get() | flags![window::WndDirtyFlags::{LAYER | CONTENTS}]);
         }
 
-        // Unmount the old content view
         let old_content_view = old_content_view.unwrap();
+        *old_content_view.view.superview.borrow_mut() = Superview::empty();
+        old_content_view.as_ref().clear_ui_direction_cache();
+
+        if let Some(view) = &*self.wnd.content_view.borrow() {
+            view.as_ref().handle_ui_direction_superview_change();
+        }
+
+        // Unmount the old content view
         old_content_view
             .as_ref()
             .cancel_mouse_gestures_of_subviews(&self.wnd);
@@ -981,7 +1156,7 @@ impl HWndRef<'_> {
     /// Set the window listener.
     #[momo]
     pub fn set_listener(self, listener: impl Into<Box<dyn WndListener>>) {
-        *self.wnd.listener.borrow_mut() = listener.into();
+        *self.borrow_mut_checked(&self.wnd.listener, "set_listener") = listener.into();
     }
 
     /// Set the visibility of a window.
@@ -1043,6 +1218,52 @@ impl HWndRef<'_> {
         self.wnd.style_attrs.borrow().flags
     }
 
+    /// Set the progress indicator displayed in the window's taskbar button.
+    ///
+    /// The default value is `None`. This is currently only supported on
+    /// Windows and ignored on other platforms.
+    pub fn set_progress(self, progress: Option<ProgressState>) {
+        let mut style_attrs = self.wnd.style_attrs.borrow_mut();
+        if style_attrs.progress == progress {
+            return;
+        }
+        style_attrs.progress = progress;
+        self.wnd
+            .set_dirty_flags(window::WndDirtyFlags::STYLE_PROGRESS);
+        self.pend_update();
+    }
+
+    /// Get the progress indicator of a window.
+    pub fn progress(self) -> Option<ProgressState> {
+        self.wnd.style_attrs.borrow().progress
+    }
+
+    /// Enable or disable the pointer grab mode of a window.
+    ///
+    /// While the window is focused and this mode is enabled, the mouse
+    /// pointer is confined to the window, and the active mouse drag gesture
+    /// receives [`MouseDragListener::mouse_relative_motion`] for every mouse
+    /// movement. See [`WndAttrs::pointer_grab`] for details.
+    ///
+    /// The default value is `false`.
+    ///
+    /// [`WndAttrs::pointer_grab`]: crate::pal::iface::WndAttrs::pointer_grab
+    pub fn set_pointer_grab(self, grab: bool) {
+        let mut style_attrs = self.wnd.style_attrs.borrow_mut();
+        if style_attrs.pointer_grab == grab {
+            return;
+        }
+        style_attrs.pointer_grab = grab;
+        self.wnd
+            .set_dirty_flags(window::WndDirtyFlags::STYLE_POINTER_GRAB);
+        self.pend_update();
+    }
+
+    /// Get a flag indicating whether the pointer grab mode is enabled.
+    pub fn pointer_grab(self) -> bool {
+        self.wnd.style_attrs.borrow().pointer_grab
+    }
+
     /// Enqueue a call to the specified function. The function will be called
     /// when the system is ready to accept a new displayed frame.
     ///
@@ -1050,6 +1271,20 @@ impl HWndRef<'_> {
     pub fn invoke_on_next_frame(self, f: impl FnOnce(pal::Wm, HWndRef<'_>) + 'static) {
         self.invoke_on_next_frame_inner(Node::pin(AssertUnpin::new(f)));
     }
+
+    /// Enqueue a call to the specified function. The function will be called
+    /// after the current update (i.e., the layout process and calls to
+    /// `ViewListener::update`) of the window is complete.
+    ///
+    /// If the window is not being updated, an update is scheduled, and the
+    /// function will be called after that.
+    ///
+    /// This is useful for making changes that are not allowed inside
+    /// `ViewListener`'s methods, such as [`HViewRef::set_listener`] or
+    /// [`HViewRef::set_layout`].
+    pub fn invoke_after_update(self, f: impl FnOnce(pal::Wm) + 'static) {
+        self.invoke_after_update_inner(Node::pin(AssertUnpin::new(f)));
+    }
 }
 
 impl PartialEq for HWnd {
@@ -1080,11 +1315,21 @@ impl WeakHWnd {
     }
 }
 
+/// Two weak handles are equal if they point to the same window or both of them
+/// were created by `new`.
+impl PartialEq for WeakHWnd {
+    fn eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.wnd, &other.wnd)
+    }
+}
+
+impl Eq for WeakHWnd {}
+
 impl HView {
     /// Construct a view object and return a handle to it.
     pub fn new(flags: ViewFlags) -> Self {
         let this = Self {
-            view: Rc::new(View::new(flags)),
+            view: ViewRc::new(View::new(flags)),
         };
 
         trace!("HView::new -> {:?}", this);
@@ -1095,7 +1340,7 @@ impl HView {
     /// Construct a weak handle.
     pub fn downgrade(&self) -> WeakHView {
         WeakHView {
-            view: Rc::downgrade(&self.view),
+            view: ViewRc::downgrade(&self.view),
         }
     }
 
@@ -1116,10 +1361,25 @@ impl HView {
         pub fn cursor_shape(&self) -> Option<CursorShape>;
         pub fn pend_update(&self);
 
+        // `direction.rs`
+        pub fn set_ui_direction(&self, dir: Option<UiDirection>);
+        pub fn ui_direction_override(&self) -> Option<UiDirection>;
+        pub fn ui_direction(&self) -> UiDirection;
+
         // `layout.rs`
         pub fn frame(&self) -> Box2<f32>;
         pub fn global_frame(&self) -> Box2<f32>;
         pub fn global_visible_frame(&self) -> Box2<f32>;
+        pub fn frame_in(&self, other: HViewRef<'_>) -> Box2<f32>;
+        pub fn convert_point_to(&self, other: HViewRef<'_>, p: Point2<f32>) -> Point2<f32>;
+        pub fn convert_point_from_window(&self, p: Point2<f32>) -> Point2<f32>;
+        pub fn convert_point_to_window(&self, p: Point2<f32>) -> Point2<f32>;
+
+        // `introspect.rs`
+        pub fn superview(&self) -> Option<HView>;
+        pub fn subviews(&self) -> Vec<HView>;
+        pub fn set_debug_name(&self, name: Option<&'static str>);
+        pub fn debug_name(&self) -> Option<&'static str>;
 
         // `window.rs`
         pub fn containing_wnd(&self) -> Option<HWnd>;
@@ -1142,7 +1402,7 @@ impl HView {
 impl<'a> From<HViewRef<'a>> for HView {
     fn from(x: HViewRef<'a>) -> Self {
         Self {
-            view: RcBorrow::upgrade(x.view),
+            view: ViewBorrow::upgrade(x.view),
         }
     }
 }
@@ -1150,7 +1410,7 @@ impl<'a> From<HViewRef<'a>> for HView {
 impl<'a> From<&'a HView> for HViewRef<'a> {
     fn from(x: &'a HView) -> Self {
         Self {
-            view: RcBorrow::from(&x.view),
+            view: ViewBorrow::from(&x.view),
         }
     }
 }
@@ -1161,26 +1421,38 @@ impl<'a> HViewRef<'a> {
         self.into()
     }
 
+    /// Construct a weak handle.
+    pub fn downgrade(self) -> WeakHView {
+        WeakHView {
+            view: ViewBorrow::to_weak(self.view),
+        }
+    }
+
     /// Set a new [`ViewListener`].
     ///
     /// It's not allowed to call this method from `ViewListener`'s methods or
-    /// when the `ViewListener` is currently borrowed.
+    /// when the `ViewListener` is currently borrowed. Doing so will cause
+    /// a panic.
     #[momo]
     pub fn set_listener(self, listener: impl Into<Box<dyn ViewListener>>) {
-        *self.view.listener.borrow_mut() = listener.into();
+        *self.borrow_mut_checked(&self.view.listener, "set_listener") = listener.into();
     }
 
     /// Borrow the current [`ViewListener`].
     pub fn borrow_listener(self) -> impl std::ops::Deref<Target = dyn ViewListener> + 'a {
-        owning_ref::OwningRef::new(RcBorrow::downgrade(self.view).listener.borrow()).map(|r| &**r)
+        owning_ref::OwningRef::new(ViewBorrow::downgrade(self.view).listener.borrow()).map(|r| &**r)
     }
 
     /// Take the current [`ViewListener`].
     ///
     /// It's not allowed to call this method from `ViewListener`'s methods or
-    /// when the `ViewListener` is currently borrowed
+    /// when the `ViewListener` is currently borrowed. Doing so will cause
+    /// a panic.
     pub fn take_listener(self) -> Box<dyn ViewListener> {
-        std::mem::replace(&mut *self.view.listener.borrow_mut(), Box::new(()))
+        std::mem::replace(
+            &mut *self.borrow_mut_checked(&self.view.listener, "take_listener"),
+            Box::new(()),
+        )
     }
 
     /// Set a new [`Layout`].
@@ -1190,13 +1462,18 @@ impl<'a> HViewRef<'a> {
     /// It's not allowed to call this method from `Layout`'s method. You should
     /// use [`LayoutCtx::set_layout`] instead.
     ///
+    /// These conditions are checked at runtime, and a violation will cause
+    /// a panic with a message describing the offending call.
+    ///
     /// When a focused view is removed by this method, focus lost/leave events
     /// are not raised for its ancestor views. This is a limitation in the
     /// current implementation and may be changed in the future.
     #[momo]
     pub fn set_layout(self, layout: impl Into<Box<dyn Layout>>) {
+        self.check_not_updating("set_layout");
+
         let layout = layout.into();
-        let mut cur_layout = self.view.layout.borrow_mut();
+        let mut cur_layout = self.borrow_mut_checked(&self.view.layout, "set_layout");
         let subviews_changed = !layout.has_same_subviews(&**cur_layout);
 
         let mut new_flags = ViewDirtyFlags::empty();
@@ -1207,7 +1484,7 @@ impl<'a> HViewRef<'a> {
                 let mut sup_view = hview_sub.view.superview.borrow_mut();
                 debug_assert_eq!(
                     *sup_view,
-                    Rc::downgrade(&RcBorrow::upgrade(self.view)),
+                    ViewBorrow::to_weak(self.view),
                     "existing subview's superview is invalid"
                 );
                 *sup_view = Superview::empty();
@@ -1220,7 +1497,7 @@ impl<'a> HViewRef<'a> {
                     sup_view.is_empty(),
                     "cannot add a subview already added to another view"
                 );
-                *sup_view = Rc::downgrade(&RcBorrow::upgrade(self.view)).into();
+                *sup_view = ViewBorrow::to_weak(self.view).into();
 
                 // Propagate dirty flags
                 new_flags |= hview_sub.view.dirty.get();
@@ -1254,6 +1531,19 @@ impl<'a> HViewRef<'a> {
         let old_layout = std::mem::replace(&mut *cur_layout, layout);
         drop(cur_layout);
 
+        if subviews_changed {
+            // Update the directions of the moved subviews
+            for hview_sub in old_layout.subviews().iter() {
+                if hview_sub.view.superview.borrow().is_empty() {
+                    hview_sub.as_ref().clear_ui_direction_cache();
+                }
+            }
+
+            for hview_sub in self.view.layout.borrow().subviews().iter() {
+                hview_sub.as_ref().handle_ui_direction_superview_change();
+            }
+        }
+
         if subviews_changed && self.view.dirty.get().contains(ViewDirtyFlags::MOUNTED) {
             // `MOUNTED` implies that the view is already added to some window
             let hwnd = self.containing_wnd().unwrap();
@@ -1380,6 +1670,16 @@ impl WeakHView {
     }
 }
 
+/// Two weak handles are equal if they point to the same view or both of them
+/// were created by `new`.
+impl PartialEq for WeakHView {
+    fn eq(&self, other: &Self) -> bool {
+        ViewWeak::ptr_eq(&self.view, &other.view)
+    }
+}
+
+impl Eq for WeakHView {}
+
 // =======================================================================
 //                               Dirty flags
 // =======================================================================
@@ -1448,6 +1748,10 @@ bitflags! {
         /// The view is added to a window, but `ViewListener::mount` hasn't yet
         /// been called for some of the view and its subviews.
         const MOUNT = 1 << 11;
+
+        /// The DPI scale of the containing window has changed. Always set
+        /// with `UPDATE_EVENT`. Cleared when `ViewListener::update` is called.
+        const DPI_SCALE = 1 << 12;
     }
 }
 
@@ -1548,7 +1852,7 @@ impl HViewRef<'_> {
     }
 
     fn for_each_ancestor(self, mut f: impl FnMut(HView)) {
-        let mut cur: Rc<View> = RcBorrow::upgrade(self.view);
+        let mut cur: ViewRc = ViewBorrow::upgrade(self.view);
         loop {
             let next = match &*cur.superview.borrow() {
                 Superview::View(view) => view.upgrade(),
@@ -21,7 +21,19 @@ pub trait MouseDragListener {
     fn mouse_motion(&self, _: Wm, _: HViewRef<'_>, _loc: Point2<f32>) {}
 
     /// A mouse button was pressed inside a window.
-    fn mouse_down(&self, _: Wm, _: HViewRef<'_>, _loc: Point2<f32>, _button: u8) {}
+    ///
+    /// `click_count` is the number of consecutive clicks including this one
+    /// (e.g., `2` for a double click). See
+    /// [`crate::pal::iface::MouseDragListener::mouse_down`].
+    fn mouse_down(
+        &self,
+        _: Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+        _click_count: u32,
+    ) {
+    }
 
     /// A mouse button was released inside a window.
     ///
@@ -33,6 +45,16 @@ pub trait MouseDragListener {
     /// [`WndListener::mouse_drag`]: crate::pal::iface::WndListener::mouse_drag
     fn mouse_up(&self, _: Wm, _: HViewRef<'_>, _loc: Point2<f32>, _button: u8) {}
 
+    /// The mouse was moved while the pointer grab mode is enabled (see
+    /// [`HWndRef::set_pointer_grab`]). `delta` is measured in logical pixels.
+    ///
+    /// Unlike `mouse_motion`, this method keeps being called even if the
+    /// mouse pointer has hit the edge of the window, which is useful for
+    /// implementing infinite dragging.
+    ///
+    /// [`HWndRef::set_pointer_grab`]: super::HWndRef::set_pointer_grab
+    fn mouse_relative_motion(&self, _: Wm, _: HViewRef<'_>, _delta: Vector2<f32>) {}
+
     /// A mouse drag gesture was cancelled.
     fn cancel(&self, _: Wm, _: HViewRef<'_>) {}
 }
@@ -70,10 +92,38 @@ pub trait ScrollListener {
 /// A default implementation of [`ScrollListener`].
 impl ScrollListener for () {}
 
+/// Event handlers for magnification and rotation gestures.
+///
+/// A `GestureListener` object lives until one of the following events occur:
+///
+///  - `end` is called.
+///  - `cancel` is called.
+///
+pub trait GestureListener {
+    /// The user performed a magnification gesture. `scale` is the
+    /// magnification factor relative to the previous event.
+    fn magnify(&self, _: Wm, _: HViewRef<'_>, _scale: f32) {}
+
+    /// The user performed a rotation gesture. `angle` is measured in radians
+    /// relative to the previous event. Positive values represent clockwise
+    /// rotations.
+    fn rotate(&self, _: Wm, _: HViewRef<'_>, _angle: f32) {}
+
+    /// The gesture was completed.
+    fn end(&self, _: Wm, _: HViewRef<'_>) {}
+
+    /// The gesture was cancelled.
+    fn cancel(&self, _: Wm, _: HViewRef<'_>) {}
+}
+
+/// A default implementation of [`GestureListener`].
+impl GestureListener for () {}
+
 #[derive(Debug)]
 pub(super) struct WndMouseState {
     drag_gestures: Option<Rc<DragGesture>>,
     scroll_gestures: Option<Rc<ScrollGesture>>,
+    gestures: Option<Rc<Gesture>>,
     hover_view: Option<HView>,
 }
 
@@ -82,6 +132,7 @@ impl WndMouseState {
         Self {
             drag_gestures: None,
             scroll_gestures: None,
+            gestures: None,
             hover_view: None,
         }
     }
@@ -117,6 +168,21 @@ impl fmt::Debug for ScrollGesture {
     }
 }
 
+/// Represents an active magnification/rotation gesture.
+struct Gesture {
+    view: HView,
+    listener: Box<dyn GestureListener>,
+}
+
+impl fmt::Debug for Gesture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Gesture")
+            .field("view", &self.view)
+            .field("listener", &((&*self.listener) as *const _))
+            .finish()
+    }
+}
+
 impl HWnd {
     /// The core implementation of `pal::WndListener::mouse_motion` and
     /// `pal::WndListener::mouse_leave`.
@@ -298,6 +364,16 @@ impl HWnd {
         }
     }
 
+    /// The core implementation of `pal::WndListener::mouse_relative_motion`.
+    pub(super) fn handle_mouse_relative_motion(&self, delta: Vector2<f32>) {
+        let drag = self.wnd.mouse_state.borrow().drag_gestures.clone();
+        // Make sure `mouse_state` is unborrowed before calling event handlers
+        if let Some(drag) = &drag {
+            drag.listener
+                .mouse_relative_motion(self.wnd.wm, drag.view.as_ref(), delta);
+        }
+    }
+
     /// The core implementation of `pal::WndListener::scroll_motion`.
     pub(super) fn handle_scroll_motion(&self, loc: Point2<f32>, delta: &ScrollDelta) {
         if self.wnd.mouse_state.borrow().scroll_gestures.is_some() {
@@ -391,6 +467,60 @@ impl HWnd {
             Box::new(())
         }
     }
+
+    /// The core implementation of `pal::WndListener::gesture`.
+    pub(super) fn handle_gesture(
+        &self,
+        loc: Point2<f32>,
+    ) -> Box<dyn pal::iface::GestureListener<pal::Wm>> {
+        let mut st = self.wnd.mouse_state.borrow_mut();
+
+        if st.gestures.is_some() {
+            warn!(
+                "{:?}: Rejecting the new gesture at {:?} because \
+                 there already is an active gesture",
+                self, loc
+            );
+
+            return Box::new(());
+        }
+
+        let hit_view = {
+            let content_view = self.wnd.content_view.borrow();
+            content_view.as_ref().unwrap().as_ref().hit_test(
+                loc,
+                ViewFlags::ACCEPT_GESTURE,
+                ViewFlags::DENY_MOUSE,
+            )
+        };
+
+        trace!(
+            "{:?}: Gesture at {:?} is handled by {:?}",
+            self,
+            loc,
+            hit_view
+        );
+
+        if let Some(hit_view) = hit_view {
+            let view_listener = {
+                let listener = hit_view.view.listener.borrow();
+                listener.gesture(self.wnd.wm, hit_view.as_ref(), loc)
+            };
+
+            // Remember the gesture
+            st.gestures = Some(Rc::new(Gesture {
+                view: hit_view,
+                listener: view_listener,
+            }));
+
+            // Return `dyn pal::iface::GestureListener`
+            Box::new(PalGestureListener {
+                wnd: Rc::downgrade(&self.wnd),
+            })
+        } else {
+            Box::new(())
+        }
+    }
 }
 
 impl HViewRef<'_> {
@@ -519,10 +649,10 @@ impl pal::iface::MouseDragListener<pal::Wm> for PalDragListener {
             drag.listener.mouse_motion(wm, drag.view.as_ref(), loc);
         })
     }
-    fn mouse_down(&self, wm: Wm, _: &pal::HWnd, loc: Point2<f32>, button: u8) {
+    fn mouse_down(&self, wm: Wm, _: &pal::HWnd, loc: Point2<f32>, button: u8, click_count: u32) {
         self.with_drag_gesture(|drag| {
             drag.listener
-                .mouse_down(wm, drag.view.as_ref(), loc, button);
+                .mouse_down(wm, drag.view.as_ref(), loc, button, click_count);
         })
     }
     fn mouse_up(&self, wm: Wm, _: &pal::HWnd, loc: Point2<f32>, button: u8) {
@@ -601,3 +731,64 @@ impl pal::iface::ScrollListener<pal::Wm> for PalScrollListener {
         })
     }
 }
+
+/// Implements `pal::iface::GestureListener`.
+struct PalGestureListener {
+    wnd: Weak<Wnd>,
+}
+
+impl PalGestureListener {
+    /// Get `HWnd` if the underlying object is still alive.
+    fn hwnd(&self) -> Option<HWnd> {
+        self.wnd.upgrade().map(|wnd| HWnd { wnd })
+    }
+
+    fn with_gesture(&self, cb: impl FnOnce(&Gesture)) {
+        if let Some(hwnd) = self.hwnd() {
+            let gesture = hwnd.wnd.mouse_state.borrow().gestures.clone();
+            // Make sure `mouse_state` is unborrowed before calling
+            // event handlers
+            if let Some(gesture) = &gesture {
+                cb(gesture);
+            }
+        }
+    }
+}
+
+impl Drop for PalGestureListener {
+    fn drop(&mut self) {
+        if let Some(hwnd) = self.hwnd() {
+            trace!("{:?}: Gesture ended", hwnd);
+
+            let gesture = hwnd.wnd.mouse_state.borrow_mut().gestures.take();
+            drop(gesture);
+        } else {
+            trace!("Gesture ended, but the owner is gone");
+        }
+    }
+}
+
+/// Forwards events from `pal::iface::GestureListener` to
+/// `uicore::GestureListener`.
+impl pal::iface::GestureListener<pal::Wm> for PalGestureListener {
+    fn magnify(&self, wm: Wm, _: &pal::HWnd, scale: f32) {
+        self.with_gesture(|gesture| {
+            gesture.listener.magnify(wm, gesture.view.as_ref(), scale);
+        })
+    }
+    fn rotate(&self, wm: Wm, _: &pal::HWnd, angle: f32) {
+        self.with_gesture(|gesture| {
+            gesture.listener.rotate(wm, gesture.view.as_ref(), angle);
+        })
+    }
+    fn end(&self, wm: Wm, _: &pal::HWnd) {
+        self.with_gesture(|gesture| {
+            gesture.listener.end(wm, gesture.view.as_ref());
+        })
+    }
+    fn cancel(&self, wm: Wm, _: &pal::HWnd) {
+        self.with_gesture(|gesture| {
+            gesture.listener.cancel(wm, gesture.view.as_ref());
+        })
+    }
+}
@@ -0,0 +1,401 @@
+//! Runtime layout inspector overlay
+use bitflags::bitflags;
+use cggeom::{box2, prelude::*, Box2};
+use flags_macro::flags;
+use std::{collections::VecDeque, time::Duration};
+
+use super::{window::WndDirtyFlags, HViewRef, HWnd, HWndRef};
+use crate::pal::{self, prelude::*, Wm};
+
+bitflags! {
+    /// Specifies what is drawn by a window's debug overlay. See
+    /// [`HWndRef::set_debug_overlay`].
+    pub struct DebugOverlayFlags: u8 {
+        /// Outline the frame of every view.
+        const FRAMES = 1;
+        /// Outline the bounding box of every view's subviews. The gap between
+        /// this box and the view's frame visualizes the padding applied by the
+        /// view's layout.
+        const PADDING = 1 << 1;
+        /// Briefly highlight a view's region every time its
+        /// [`ViewListener::update`] is called.
+        ///
+        /// [`ViewListener::update`]: crate::uicore::ViewListener::update
+        const DIRTY_FLASHES = 1 << 2;
+        /// Display a graph of the durations of recent window updates in the
+        /// top-left corner. Each bar represents one update, and the
+        /// horizontal line indicates 1/60 seconds.
+        ///
+        /// See [`profiling`] for a more detailed breakdown.
+        ///
+        /// [`profiling`]: crate::profiling
+        const FRAME_TIMES = 1 << 3;
+    }
+}
+
+impl Default for DebugOverlayFlags {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+const FRAME_COLOR: pal::RGBAF32 = pal::RGBAF32::new(0.2, 0.5, 1.0, 0.8);
+const PADDING_COLOR: pal::RGBAF32 = pal::RGBAF32::new(0.2, 0.8, 0.3, 0.8);
+const FLASH_COLOR: pal::RGBAF32 = pal::RGBAF32::new(1.0, 0.3, 0.2, 0.3);
+const HUD_BG_COLOR: pal::RGBAF32 = pal::RGBAF32::new(0.0, 0.0, 0.0, 0.6);
+const HUD_BAR_COLOR: pal::RGBAF32 = pal::RGBAF32::new(0.3, 0.9, 0.4, 0.9);
+const HUD_SLOW_BAR_COLOR: pal::RGBAF32 = pal::RGBAF32::new(1.0, 0.3, 0.2, 0.9);
+const HUD_LINE_COLOR: pal::RGBAF32 = pal::RGBAF32::new(1.0, 1.0, 1.0, 0.6);
+
+/// The number of frames displayed in the frame time graph.
+const HUD_NUM_BARS: usize = 60;
+const HUD_BAR_WIDTH: f32 = 2.0;
+const HUD_HEIGHT: f32 = 40.0;
+/// The frame time corresponding to `HUD_HEIGHT`.
+const HUD_MAX_FRAME_TIME: Duration = Duration::from_millis(50);
+const HUD_TARGET_FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+/// How long a dirty region stays highlighted.
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+/// The state of a window's debug overlay.
+#[derive(Debug, Default)]
+pub(super) struct WndDebugOverlay {
+    flags: DebugOverlayFlags,
+    /// The layer containing the root view's layer and the overlay layers.
+    /// `Some(_)` iff the overlay is active.
+    root_layer: Option<pal::HLayer>,
+    /// The bitmaps used for outlining frames and padding boxes, respectively.
+    outline_bmps: Option<[pal::Bitmap; 2]>,
+    outline_layers: Vec<pal::HLayer>,
+    flash_layers: Vec<pal::HLayer>,
+    /// Regions reported by `record_debug_dirty_region` that haven't been
+    /// highlighted yet.
+    pending_dirty_regions: Vec<Box2<f32>>,
+    /// The durations of recent updates reported by
+    /// `record_debug_frame_time`, oldest first.
+    frame_times: VecDeque<Duration>,
+    hud_layers: Vec<pal::HLayer>,
+}
+
+impl WndDebugOverlay {
+    /// Release all layers owned by the overlay.
+    pub(super) fn clear(&mut self, wm: Wm) {
+        if let Some(root_layer) = self.root_layer.take() {
+            // Detach the root view's layer first
+            wm.set_layer_attr(
+                &root_layer,
+                pal::LayerAttrs {
+                    sublayers: Some(Vec::new()),
+                    ..Default::default()
+                },
+            );
+            wm.remove_layer(&root_layer);
+        }
+        for layer in self.outline_layers.drain(..) {
+            wm.remove_layer(&layer);
+        }
+        for layer in self.flash_layers.drain(..) {
+            wm.remove_layer(&layer);
+        }
+        for layer in self.hud_layers.drain(..) {
+            wm.remove_layer(&layer);
+        }
+        self.pending_dirty_regions.clear();
+        self.frame_times.clear();
+        self.outline_bmps = None;
+    }
+}
+
+impl HWndRef<'_> {
+    /// Set what is drawn by the window's debug overlay.
+    ///
+    /// The debug overlay is drawn on top of the window contents using extra
+    /// layers and is meant for diagnosing layout and rendering problems. The
+    /// default value is [`DebugOverlayFlags::empty()`], which disables the
+    /// overlay.
+    pub fn set_debug_overlay(self, flags: DebugOverlayFlags) {
+        {
+            let mut overlay = self.wnd.debug_overlay.borrow_mut();
+            if overlay.flags == flags {
+                return;
+            }
+            overlay.flags = flags;
+        }
+        self.wnd.set_dirty_flags(WndDirtyFlags::DEBUG_OVERLAY);
+        self.pend_update();
+    }
+
+    /// Get the value set by [`set_debug_overlay`].
+    ///
+    /// [`set_debug_overlay`]: crate::uicore::HWnd::set_debug_overlay
+    pub fn debug_overlay(self) -> DebugOverlayFlags {
+        self.wnd.debug_overlay.borrow().flags
+    }
+
+    /// Report that a region of the window has been redrawn.
+    pub(super) fn record_debug_dirty_region(self, region: Box2<f32>) {
+        let mut overlay = self.wnd.debug_overlay.borrow_mut();
+        if overlay.flags.contains(DebugOverlayFlags::DIRTY_FLASHES) && !region.is_empty() {
+            overlay.pending_dirty_regions.push(region);
+        }
+    }
+
+    /// Report the duration of a window update.
+    ///
+    /// This doesn't pend an update by itself (that would cause an infinite
+    /// update loop), so the graph is redrawn on the next update.
+    pub(super) fn record_debug_frame_time(self, duration: Duration) {
+        let mut overlay = self.wnd.debug_overlay.borrow_mut();
+        if overlay.flags.contains(DebugOverlayFlags::FRAME_TIMES) {
+            if overlay.frame_times.len() >= HUD_NUM_BARS {
+                overlay.frame_times.pop_front();
+            }
+            overlay.frame_times.push_back(duration);
+        }
+    }
+
+    /// Update the overlay layers. Called by `update`.
+    ///
+    /// `root_layer_attr` is the `layer` attribute of the window to be applied.
+    /// This method overrides it if the window's root layer has to be replaced.
+    pub(super) fn update_debug_overlay(
+        self,
+        dirty: WndDirtyFlags,
+        contents_updated: bool,
+        root_layer_attr: &mut Option<Option<pal::HLayer>>,
+    ) {
+        let wm = self.wnd.wm;
+
+        let content_view = self.wnd.content_view.borrow().clone().unwrap();
+        let content_layer = if let Some(layer) = content_view.view.layers.borrow().first() {
+            layer.clone()
+        } else {
+            // The root view hasn't been materialized yet
+            return;
+        };
+
+        let mut overlay = self.wnd.debug_overlay.borrow_mut();
+        let overlay = &mut *overlay;
+
+        if overlay.flags.is_empty() {
+            if overlay.root_layer.is_some() {
+                // Deactivate the overlay
+                overlay.clear(wm);
+                *root_layer_attr = Some(Some(content_layer));
+            }
+            return;
+        }
+
+        let root_layer = if let Some(layer) = &overlay.root_layer {
+            let update_flags = flags![WndDirtyFlags::{LAYER | DEBUG_OVERLAY}];
+            if !contents_updated && !dirty.intersects(update_flags) {
+                return;
+            }
+            if dirty.contains(WndDirtyFlags::LAYER) {
+                *root_layer_attr = Some(Some(layer.clone()));
+            }
+            layer.clone()
+        } else {
+            // Activate the overlay
+            let layer = wm.new_layer(pal::LayerAttrs {
+                // `bounds` mustn't be empty, so...
+                bounds: Some(box2! { min: [0.0, 0.0], max: [1.0, 1.0] }),
+                ..Default::default()
+            });
+            overlay.root_layer = Some(layer.clone());
+            *root_layer_attr = Some(Some(layer.clone()));
+            layer
+        };
+
+        // Outline views
+        let mut outlines = Vec::new();
+        collect_outlines(content_view.as_ref(), overlay.flags, &mut outlines);
+
+        let outline_bmps = overlay
+            .outline_bmps
+            .get_or_insert_with(|| [new_outline_bmp(FRAME_COLOR), new_outline_bmp(PADDING_COLOR)]);
+
+        let outline_layers = &mut overlay.outline_layers;
+        while outline_layers.len() > outlines.len() {
+            wm.remove_layer(&outline_layers.pop().unwrap());
+        }
+        outline_layers.resize_with(outlines.len(), || wm.new_layer(Default::default()));
+
+        for (layer, &(bounds, kind)) in outline_layers.iter().zip(outlines.iter()) {
+            wm.set_layer_attr(
+                layer,
+                pal::LayerAttrs {
+                    contents: Some(Some(outline_bmps[kind as usize].clone().into())),
+                    bounds: Some(bounds),
+                    contents_center: Some(box2! {
+                        min: [1.0 / 3.0, 1.0 / 3.0],
+                        max: [2.0 / 3.0, 2.0 / 3.0],
+                    }),
+                    contents_scale: Some(1.0),
+                    ..Default::default()
+                },
+            );
+        }
+
+        // Highlight dirty regions. Each highlight is removed after a while.
+        for region in overlay.pending_dirty_regions.drain(..) {
+            let layer = wm.new_layer(pal::LayerAttrs {
+                bounds: Some(region),
+                bg_color: Some(FLASH_COLOR),
+                ..Default::default()
+            });
+            overlay.flash_layers.push(layer.clone());
+
+            let hwnd: HWnd = self.cloned();
+            wm.invoke_after(FLASH_DURATION..FLASH_DURATION * 2, move |_| {
+                hwnd.as_ref().remove_debug_flash(&layer);
+            });
+        }
+
+        // Draw the frame time graph
+        let hud_attrs = if overlay.flags.contains(DebugOverlayFlags::FRAME_TIMES) {
+            hud_layer_attrs(&overlay.frame_times)
+        } else {
+            overlay.frame_times.clear();
+            Vec::new()
+        };
+
+        let hud_layers = &mut overlay.hud_layers;
+        while hud_layers.len() > hud_attrs.len() {
+            wm.remove_layer(&hud_layers.pop().unwrap());
+        }
+        hud_layers.resize_with(hud_attrs.len(), || wm.new_layer(Default::default()));
+
+        for (layer, attrs) in hud_layers.iter().zip(hud_attrs) {
+            wm.set_layer_attr(layer, attrs);
+        }
+
+        let sublayers = std::iter::once(content_layer)
+            .chain(overlay.outline_layers.iter().cloned())
+            .chain(overlay.flash_layers.iter().cloned())
+            .chain(overlay.hud_layers.iter().cloned())
+            .collect();
+
+        wm.set_layer_attr(
+            &root_layer,
+            pal::LayerAttrs {
+                sublayers: Some(sublayers),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn remove_debug_flash(self, layer: &pal::HLayer) {
+        {
+            let mut overlay = self.wnd.debug_overlay.borrow_mut();
+            let flash_layers = &mut overlay.flash_layers;
+            if let Some(i) = flash_layers.iter().position(|l| l == layer) {
+                flash_layers.remove(i);
+                self.wnd.wm.remove_layer(layer);
+            } else {
+                // The overlay has been deactivated in the meantime
+                return;
+            }
+        }
+        self.wnd.set_dirty_flags(WndDirtyFlags::DEBUG_OVERLAY);
+        self.pend_update();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OutlineKind {
+    Frame = 0,
+    Padding = 1,
+}
+
+fn collect_outlines(
+    view: HViewRef<'_>,
+    flags: DebugOverlayFlags,
+    out: &mut Vec<(Box2<f32>, OutlineKind)>,
+) {
+    let frame = view.view.global_frame.get();
+    if flags.contains(DebugOverlayFlags::FRAMES) && !frame.is_empty() {
+        out.push((frame, OutlineKind::Frame));
+    }
+
+    let layout = view.view.layout.borrow();
+    let subviews = layout.subviews();
+
+    if flags.contains(DebugOverlayFlags::PADDING) {
+        let content_box = (subviews.iter())
+            .map(|subview| subview.view.global_frame.get())
+            .fold(None, |acc: Option<Box2<f32>>, frame| {
+                Some(acc.map_or(frame, |acc| acc.union(&frame)))
+            });
+
+        if let Some(content_box) = content_box.filter(|bx| !bx.is_empty()) {
+            out.push((content_box, OutlineKind::Padding));
+        }
+    }
+
+    for subview in subviews.iter() {
+        collect_outlines(subview.as_ref(), flags, out);
+    }
+}
+
+/// Construct the attributes of the layers constituting the frame time graph,
+/// from bottom to top.
+fn hud_layer_attrs(frame_times: &VecDeque<Duration>) -> Vec<pal::LayerAttrs> {
+    let y_for_duration = |d: Duration| {
+        let ratio = (d.as_secs_f32() / HUD_MAX_FRAME_TIME.as_secs_f32()).min(1.0);
+        HUD_HEIGHT * (1.0 - ratio)
+    };
+
+    let mut attrs = Vec::with_capacity(frame_times.len() + 2);
+
+    attrs.push(pal::LayerAttrs {
+        bounds: Some(box2! {
+            min: [0.0, 0.0],
+            max: [HUD_BAR_WIDTH * HUD_NUM_BARS as f32, HUD_HEIGHT],
+        }),
+        bg_color: Some(HUD_BG_COLOR),
+        ..Default::default()
+    });
+
+    for (i, &d) in frame_times.iter().enumerate() {
+        let x = HUD_BAR_WIDTH * i as f32;
+        let color = if d > HUD_TARGET_FRAME_TIME {
+            HUD_SLOW_BAR_COLOR
+        } else {
+            HUD_BAR_COLOR
+        };
+        attrs.push(pal::LayerAttrs {
+            bounds: Some(box2! {
+                // Make sure the bar is visible even if `d` is very small
+                min: [x, y_for_duration(d).min(HUD_HEIGHT - 1.0)],
+                max: [x + HUD_BAR_WIDTH, HUD_HEIGHT],
+            }),
+            bg_color: Some(color),
+            ..Default::default()
+        });
+    }
+
+    let line_y = y_for_duration(HUD_TARGET_FRAME_TIME);
+    attrs.push(pal::LayerAttrs {
+        bounds: Some(box2! {
+            min: [0.0, line_y],
+            max: [HUD_BAR_WIDTH * HUD_NUM_BARS as f32, line_y + 1.0],
+        }),
+        bg_color: Some(HUD_LINE_COLOR),
+        ..Default::default()
+    });
+
+    attrs
+}
+
+/// Create a 3×3 bitmap with a 1-pixel-wide border, intended to be used with
+/// 9-slice scaling.
+fn new_outline_bmp(color: pal::RGBAF32) -> pal::Bitmap {
+    let mut builder = pal::BitmapBuilder::new([3, 3]);
+    builder.set_stroke_rgb(color);
+    builder.set_line_width(1.0);
+    builder.rect(box2! { min: [0.5, 0.5], max: [2.5, 2.5] });
+    builder.stroke();
+    builder.into_bitmap()
+}
@@ -0,0 +1,144 @@
+//! Re-entrancy checks for the view tree.
+//!
+//! Listeners and layouts are called while parts of the view tree are
+//! borrowed. Calling a method that mutates the borrowed parts from there
+//! would result in a `BorrowMutError` that gives no clue about what went
+//! wrong. The functions in this module detect such cases and produce
+//! a diagnostic message naming the view, the offending method, and the
+//! current phase of the window update pipeline.
+use std::{
+    cell::{Cell, RefCell, RefMut},
+    fmt,
+};
+
+use super::{HViewRef, HWndRef, Wnd};
+
+/// A phase of the window update pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum UpdatePhase {
+    /// `ViewListener::mount` is being called.
+    Mount,
+    /// `Layout` methods are being called.
+    Layout,
+    /// `ViewListener::position` is being called.
+    Position,
+    /// `ViewListener::update` is being called.
+    Update,
+}
+
+impl fmt::Display for UpdatePhase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            UpdatePhase::Mount => "the mount phase (`ViewListener::mount`)",
+            UpdatePhase::Layout => "the layout phase (`Layout`)",
+            UpdatePhase::Position => "the position phase (`ViewListener::position`)",
+            UpdatePhase::Update => "the update phase (`ViewListener::update`)",
+        })
+    }
+}
+
+/// Restores the previous value of `Wnd::phase` when dropped.
+pub(super) struct PhaseGuard<'a> {
+    cell: &'a Cell<Option<UpdatePhase>>,
+    prev: Option<UpdatePhase>,
+}
+
+impl Wnd {
+    /// Mark the window as being in the specified phase until the returned
+    /// guard is dropped.
+    pub(super) fn enter_phase(&self, phase: UpdatePhase) -> PhaseGuard<'_> {
+        PhaseGuard {
+            cell: &self.phase,
+            prev: self.phase.replace(Some(phase)),
+        }
+    }
+}
+
+impl Drop for PhaseGuard<'_> {
+    fn drop(&mut self) {
+        self.cell.set(self.prev);
+    }
+}
+
+impl HViewRef<'_> {
+    /// Mutably borrow `cell`, which is a part of the view. Panics with
+    /// a diagnostic message if it's already borrowed.
+    pub(super) fn borrow_mut_checked<'a, T: ?Sized>(
+        self,
+        cell: &'a RefCell<T>,
+        method: &str,
+    ) -> RefMut<'a, T> {
+        match cell.try_borrow_mut() {
+            Ok(x) => x,
+            Err(_) => self.reentrancy_panic(method),
+        }
+    }
+
+    /// Panic with a diagnostic message if the containing window is in the
+    /// middle of calling `ViewListener::update`.
+    ///
+    /// This detects modifications of the view tree that are not caught by
+    /// `borrow_mut_checked` because they don't touch a borrowed part.
+    pub(super) fn check_not_updating(self, method: &str) {
+        let updating = self.containing_wnd().map_or(false, |hwnd| {
+            hwnd.wnd.phase.get() == Some(UpdatePhase::Update)
+        });
+        if updating {
+            self.reentrancy_panic(method);
+        }
+    }
+
+    #[cold]
+    fn reentrancy_panic(self, method: &str) -> ! {
+        let phase = self
+            .containing_wnd()
+            .and_then(|hwnd| hwnd.wnd.phase.get())
+            .map(|phase| phase.to_string())
+            .unwrap_or_else(|| "an event handler".to_owned());
+
+        let view = if let Some(name) = self.view.debug_name.get() {
+            format!("{:?} ({:?})", name, self)
+        } else {
+            format!("{:?}", self)
+        };
+
+        panic!(
+            "`HView::{}` was called on view {} during {}, but the view is in use \
+             by a listener or a layout. Defer the call by \
+             `HWnd::invoke_after_update` or use `LayoutCtx::set_layout` if you \
+             are in a layout.",
+            method, view, phase,
+        );
+    }
+}
+
+impl HWndRef<'_> {
+    /// Mutably borrow `cell`, which is a part of the window. Panics with
+    /// a diagnostic message if it's already borrowed.
+    pub(super) fn borrow_mut_checked<'a, T: ?Sized>(
+        self,
+        cell: &'a RefCell<T>,
+        method: &str,
+    ) -> RefMut<'a, T> {
+        match cell.try_borrow_mut() {
+            Ok(x) => x,
+            Err(_) => self.reentrancy_panic(method),
+        }
+    }
+
+    #[cold]
+    fn reentrancy_panic(self, method: &str) -> ! {
+        let phase = self
+            .wnd
+            .phase
+            .get()
+            .map(|phase| phase.to_string())
+            .unwrap_or_else(|| "an event handler".to_owned());
+
+        panic!(
+            "`HWnd::{}` was called on {:?} during {}, but the window is in use \
+             by its listener. Defer the call by `HWnd::invoke_after_update`.",
+            method, self, phase,
+        );
+    }
+}
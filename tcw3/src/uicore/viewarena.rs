@@ -0,0 +1,261 @@
+//! Arena-backed storage for `View`s.
+//!
+//! Views are allocated from per-thread chunks of slots, which are never freed
+//! and thus have stable addresses. A vacated slot is recycled by the next
+//! allocation. Each slot has a generation counter, which is incremented every
+//! time the slot is vacated, so that a weak handle can detect that the view
+//! it refers to is gone even if the slot has been reused.
+//!
+//! The handle types mirror `Rc`, `Weak`, and `RcBorrow`. Unlike `Weak`, a
+//! weak handle doesn't keep a slot alive.
+use std::{
+    cell::{Cell, RefCell, UnsafeCell},
+    fmt,
+    marker::PhantomData,
+    mem::MaybeUninit,
+    ops::Deref,
+    ptr,
+};
+
+use super::View;
+
+/// The number of slots in a chunk.
+const CHUNK_LEN: usize = 64;
+
+struct Slot {
+    /// Incremented every time the slot is vacated.
+    generation: Cell<u32>,
+    /// The number of `ViewRc`s. The slot is vacant if it's zero.
+    strong: Cell<usize>,
+    value: UnsafeCell<MaybeUninit<View>>,
+}
+
+impl Slot {
+    fn new() -> Self {
+        Self {
+            generation: Cell::new(0),
+            strong: Cell::new(0),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+struct Arena {
+    /// Vacant slots.
+    free: RefCell<Vec<&'static Slot>>,
+}
+
+thread_local! {
+    static ARENA: Arena = Arena { free: RefCell::new(Vec::new()) };
+}
+
+impl Arena {
+    fn alloc(&self) -> &'static Slot {
+        let mut free = self.free.borrow_mut();
+
+        if free.is_empty() {
+            // Chunks are leaked so that slots stay valid as long as any
+            // handles exist
+            let chunk: &'static [Slot] =
+                Box::leak((0..CHUNK_LEN).map(|_| Slot::new()).collect::<Box<[_]>>());
+            free.extend(chunk.iter().rev());
+        }
+
+        free.pop().unwrap()
+    }
+}
+
+/// A strong handle to a `View`, like `Rc<View>`.
+pub(super) struct ViewRc {
+    slot: &'static Slot,
+}
+
+/// A weak handle to a `View`, like `Weak<View>`.
+#[derive(Clone, Default)]
+pub(super) struct ViewWeak {
+    slot: Option<&'static Slot>,
+    generation: u32,
+}
+
+/// A borrowed `ViewRc`, like `RcBorrow<'a, View>`.
+#[derive(Clone, Copy)]
+pub(super) struct ViewBorrow<'a> {
+    slot: &'static Slot,
+    _phantom: PhantomData<&'a ViewRc>,
+}
+
+impl ViewRc {
+    pub(super) fn new(value: View) -> Self {
+        let slot = ARENA.with(Arena::alloc);
+        debug_assert_eq!(slot.strong.get(), 0);
+
+        unsafe { (*slot.value.get()).as_mut_ptr().write(value) };
+        slot.strong.set(1);
+
+        Self { slot }
+    }
+
+    pub(super) fn downgrade(this: &Self) -> ViewWeak {
+        ViewWeak {
+            slot: Some(this.slot),
+            generation: this.slot.generation.get(),
+        }
+    }
+}
+
+impl Clone for ViewRc {
+    fn clone(&self) -> Self {
+        let slot = self.slot;
+        slot.strong.set(slot.strong.get() + 1);
+        Self { slot }
+    }
+}
+
+impl Drop for ViewRc {
+    fn drop(&mut self) {
+        let slot = self.slot;
+        let strong = slot.strong.get() - 1;
+        slot.strong.set(strong);
+
+        if strong == 0 {
+            // Invalidate weak handles first so that they can't be upgraded
+            // while the view is being dropped
+            slot.generation.set(slot.generation.get().wrapping_add(1));
+
+            // Dropping a view may drop other views, so don't borrow the arena
+            // while doing this
+            unsafe { ptr::drop_in_place((*slot.value.get()).as_mut_ptr()) };
+
+            // If the thread is exiting, the slot is just leaked
+            let _ = ARENA.try_with(|arena| arena.free.borrow_mut().push(slot));
+        }
+    }
+}
+
+impl Deref for ViewRc {
+    type Target = View;
+
+    fn deref(&self) -> &Self::Target {
+        // The slot is occupied as long as `self` exists
+        unsafe { &*(*self.slot.value.get()).as_ptr() }
+    }
+}
+
+impl fmt::Debug for ViewRc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl ViewWeak {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(super) fn upgrade(&self) -> Option<ViewRc> {
+        let slot = self.slot?;
+        if slot.generation.get() != self.generation || slot.strong.get() == 0 {
+            return None;
+        }
+        slot.strong.set(slot.strong.get() + 1);
+        Some(ViewRc { slot })
+    }
+
+    pub(super) fn strong_count(&self) -> usize {
+        match self.slot {
+            Some(slot) if slot.generation.get() == self.generation => slot.strong.get(),
+            _ => 0,
+        }
+    }
+
+    /// Return `true` if both handles point to the same view or both of them
+    /// were created by `new`.
+    pub(super) fn ptr_eq(this: &Self, other: &Self) -> bool {
+        match (this.slot, other.slot) {
+            (Some(slot1), Some(slot2)) => {
+                ptr::eq(slot1, slot2) && this.generation == other.generation
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Debug for ViewWeak {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(ViewWeak)")
+    }
+}
+
+impl<'a> ViewBorrow<'a> {
+    pub(super) fn upgrade(this: Self) -> ViewRc {
+        let slot = this.slot;
+        slot.strong.set(slot.strong.get() + 1);
+        ViewRc { slot }
+    }
+
+    pub(super) fn downgrade(this: Self) -> &'a View {
+        unsafe { &*(*this.slot.value.get()).as_ptr() }
+    }
+
+    pub(super) fn to_weak(this: Self) -> ViewWeak {
+        ViewWeak {
+            slot: Some(this.slot),
+            generation: this.slot.generation.get(),
+        }
+    }
+}
+
+impl<'a> From<&'a ViewRc> for ViewBorrow<'a> {
+    fn from(x: &'a ViewRc) -> Self {
+        Self {
+            slot: x.slot,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl Deref for ViewBorrow<'_> {
+    type Target = View;
+
+    fn deref(&self) -> &Self::Target {
+        Self::downgrade(*self)
+    }
+}
+
+impl fmt::Debug for ViewBorrow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uicore::ViewFlags;
+
+    #[test]
+    fn weak_is_invalidated_by_slot_reuse() {
+        let view1 = ViewRc::new(View::new(ViewFlags::default()));
+        let weak1 = ViewRc::downgrade(&view1);
+        assert!(ptr::eq(weak1.upgrade().unwrap().slot, view1.slot));
+        assert_eq!(weak1.strong_count(), 1);
+
+        let slot1 = view1.slot as *const Slot;
+        drop(view1);
+        assert!(weak1.upgrade().is_none());
+        assert_eq!(weak1.strong_count(), 0);
+
+        // The vacated slot is reused by the next allocation
+        let view2 = ViewRc::new(View::new(ViewFlags::default()));
+        assert_eq!(view2.slot as *const Slot, slot1);
+        assert!(weak1.upgrade().is_none());
+        assert!(!ViewWeak::ptr_eq(&weak1, &ViewRc::downgrade(&view2)));
+    }
+
+    #[test]
+    fn weak_new() {
+        assert!(ViewWeak::new().upgrade().is_none());
+        assert!(ViewWeak::ptr_eq(&ViewWeak::new(), &ViewWeak::new()));
+    }
+}
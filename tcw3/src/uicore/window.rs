@@ -1,7 +1,7 @@
 use alt_fp::FloatOrd;
 use bitflags::bitflags;
 use cggeom::box2;
-use cgmath::Point2;
+use cgmath::{Point2, Vector2};
 use flags_macro::flags;
 use neo_linked_list::{linked_list::Node, AssertUnpin};
 use rc_borrow::RcBorrow;
@@ -10,14 +10,19 @@ use std::{
     cmp::{max, min},
     pin::Pin,
     rc::Weak,
+    time::Instant,
 };
 
 use super::{
-    invocation::process_pending_invocations, CursorShape, HView, HViewRef, HWnd, HWndRef,
-    Superview, SuperviewStrong, UpdateCtx, ViewDirtyFlags, ViewFlags, ViewListener, Wnd,
-    WndStyleFlags,
+    appearance::handle_system_appearance_changed, invocation::process_pending_invocations,
+    layer::WndDamage, reentrancy::UpdatePhase, CursorShape, HView, HViewRef, HWnd, HWndRef,
+    ProgressState, Superview, SuperviewStrong, UpdateCtx, ViewDirtyFlags, ViewFlags, ViewListener,
+    Wnd, WndStyleFlags,
+};
+use crate::{
+    pal::{self, prelude::*, Wm},
+    profiling::{self, SpanKind},
 };
-use crate::pal::{self, prelude::*, Wm};
 
 impl HViewRef<'_> {
     /// Get the containing window for a view.
@@ -48,6 +53,182 @@ impl HWndRef<'_> {
         self.wnd.pal_wnd.borrow().clone()
     }
 
+    /// Get the size of the window's content area.
+    ///
+    /// If [`set_size`] was called and the change hasn't been applied yet, this
+    /// method returns the requested size. Otherwise, returns `None` if the
+    /// window hasn't been materialized yet or has already been closed.
+    ///
+    /// [`set_size`]: crate::uicore::HWnd::set_size
+    pub fn size(self) -> Option<[u32; 2]> {
+        if let Some(size) = self.wnd.requested_size.get() {
+            return Some(size);
+        }
+
+        let pal_wnd = self.wnd.pal_wnd.borrow();
+        pal_wnd
+            .as_ref()
+            .map(|pal_wnd| self.wnd.wm.get_wnd_size(pal_wnd))
+    }
+
+    /// Resize the window's content area.
+    ///
+    /// The size is constrained by the `SizeTraits` of the content view. If
+    /// this method is called before the window is materialized, the given size
+    /// is used instead of the preferred size of the content view.
+    pub fn set_size(self, size: [u32; 2]) {
+        if self.wnd.closed.get() {
+            return;
+        }
+
+        self.wnd.requested_size.set(Some(size));
+
+        {
+            let view = self.wnd.content_view.borrow();
+            let view = view.as_ref().unwrap();
+            view.as_ref()
+                .set_dirty_flags(ViewDirtyFlags::SUBVIEWS_FRAME);
+        }
+
+        self.wnd.set_dirty_flags(WndDirtyFlags::CONTENTS);
+        self.pend_update();
+    }
+
+    /// Get the position of the window's top-left corner, including the window
+    /// decoration. See [`WndAttrs::position`] for the coordinate space.
+    ///
+    /// If [`set_position`] was called and the change hasn't been applied yet,
+    /// this method returns the requested position. Otherwise, returns `None`
+    /// if the window hasn't been materialized yet, has already been closed,
+    /// or the backend doesn't support this operation.
+    ///
+    /// [`WndAttrs::position`]: crate::pal::iface::WndAttrs::position
+    /// [`set_position`]: crate::uicore::HWnd::set_position
+    pub fn position(self) -> Option<[i32; 2]> {
+        if let Some(position) = self.wnd.requested_position.get() {
+            return Some(position);
+        }
+
+        let pal_wnd = self.wnd.pal_wnd.borrow();
+        pal_wnd
+            .as_ref()
+            .and_then(|pal_wnd| self.wnd.wm.get_wnd_position(pal_wnd))
+    }
+
+    /// Move the window. If this method is called before the window is
+    /// materialized, the given position is used instead of the system
+    /// default.
+    pub fn set_position(self, position: [i32; 2]) {
+        if self.wnd.closed.get() {
+            return;
+        }
+
+        self.wnd.requested_position.set(Some(position));
+        self.pend_update();
+    }
+
+    /// Get a flag indicating whether the window is maximized.
+    ///
+    /// If [`set_maximized`] was called and the change hasn't been applied yet,
+    /// this method returns the requested state.
+    ///
+    /// [`set_maximized`]: crate::uicore::HWnd::set_maximized
+    pub fn is_maximized(self) -> bool {
+        if let Some(maximized) = self.wnd.requested_maximized.get() {
+            return maximized;
+        }
+
+        let pal_wnd = self.wnd.pal_wnd.borrow();
+        pal_wnd
+            .as_ref()
+            .map(|pal_wnd| self.wnd.wm.is_wnd_maximized(pal_wnd))
+            .unwrap_or(false)
+    }
+
+    /// Maximize or restore the window.
+    pub fn set_maximized(self, maximized: bool) {
+        if self.wnd.closed.get() {
+            return;
+        }
+
+        self.wnd.requested_maximized.set(Some(maximized));
+        self.pend_update();
+    }
+
+    /// Render the current contents of the window to a bitmap without
+    /// presenting it.
+    ///
+    /// Pending updates (e.g., layout and the `update` methods of view
+    /// listeners) are processed before rendering. Thus, this method must not
+    /// be called from a view listener or a layout.
+    ///
+    /// Returns `None` if the window hasn't been materialized yet, has already
+    /// been closed, has an empty size, or if the backend does not support
+    /// this operation. See [`Wm::render_wnd_to_bitmap`] for the supported
+    /// backends.
+    ///
+    /// [`Wm::render_wnd_to_bitmap`]: crate::pal::iface::Wm::render_wnd_to_bitmap
+    pub fn render_to_bitmap(self) -> Option<pal::Bitmap> {
+        if self.wnd.closed.get() {
+            return None;
+        }
+
+        if self.wnd.dirty.get().contains(WndDirtyFlags::UPDATE) {
+            self.update();
+        }
+
+        let pal_wnd = self.wnd.pal_wnd.borrow();
+        self.wnd.wm.render_wnd_to_bitmap(pal_wnd.as_ref()?)
+    }
+
+    /// Create a new window having the same attributes as this window and a
+    /// content view mirroring this window's one.
+    ///
+    /// Views hold the states of their listeners and layouts and thus can't be
+    /// duplicated generically. Instead, `mirror_view` is called with the
+    /// current content view and should construct a new view hierarchy
+    /// displaying the same contents, typically by creating new views observing
+    /// the same model. The returned view becomes the new window's content view
+    /// and must satisfy the requirements of [`set_content_view`].
+    ///
+    /// The caption, style flags, progress indicator, size, user interface
+    /// direction, and accelerator table are copied. The minimum and maximum
+    /// sizes follow from the `SizeTraits` of the mirrored view, so they match
+    /// the original window's if the mirrored view has the same layout. The
+    /// window listener, position, maximization state, and pointer grab mode
+    /// are not copied, and the new window is initially hidden.
+    ///
+    /// The pixel data of bitmaps can be shared between windows, so a mirrored
+    /// view can reuse the `pal::Bitmap`s of the original one. Layers can't be
+    /// shared, though.
+    ///
+    /// [`set_content_view`]: crate::uicore::HWnd::set_content_view
+    pub fn clone_into_new_window(self, mirror_view: impl FnOnce(HViewRef<'_>) -> HView) -> HWnd {
+        assert!(!self.wnd.closed.get(), "the window has been already closed");
+
+        let new_wnd = HWnd::new(self.wnd.wm);
+
+        {
+            let style_attrs = self.wnd.style_attrs.borrow();
+            new_wnd.set_caption(style_attrs.caption.clone());
+            new_wnd.set_style_flags(style_attrs.flags);
+            new_wnd.set_progress(style_attrs.progress);
+        }
+        new_wnd.set_ui_direction(self.wnd.ui_direction.get());
+        new_wnd.set_accel_table(self.wnd.accel_table.get());
+
+        let content_view = self.content_view();
+        new_wnd.set_content_view(mirror_view(content_view.as_ref()));
+
+        // The new window isn't materialized yet, so this replaces the
+        // preferred size of the mirrored view
+        if let Some(size) = self.size() {
+            new_wnd.set_size(size);
+        }
+
+        new_wnd
+    }
+
     fn ensure_materialized(self) {
         assert!(!self.wnd.closed.get(), "the window has been already closed");
 
@@ -68,6 +249,8 @@ impl HWndRef<'_> {
 
         let mut attrs = Default::default();
         style_attrs.transfer_to_pal(transferred_flags, &mut attrs);
+        attrs.position = self.wnd.requested_position.take();
+        attrs.maximized = self.wnd.requested_maximized.take();
 
         let pal_wnd = self.wnd.wm.new_wnd(attrs);
         *pal_wnd_cell = Some(pal_wnd);
@@ -92,6 +275,10 @@ impl HWndRef<'_> {
         if self.wnd.wm.is_wnd_focused(pal_wnd_cell.as_ref().unwrap()) {
             self.invoke_focus_handlers();
         }
+
+        // The system appearance might have changed while there were no
+        // windows to receive notifications
+        self.wnd.wm.invoke(handle_system_appearance_changed);
     }
 
     /// Pend an update.
@@ -137,9 +324,35 @@ impl HWndRef<'_> {
         frame_handlers.push_back_node(f);
     }
 
+    #[allow(clippy::type_complexity)]
+    pub(super) fn invoke_after_update_inner(self, f: Pin<Box<Node<AssertUnpin<dyn FnOnce(Wm)>>>>) {
+        if self.wnd.closed.get() {
+            return;
+        }
+
+        self.wnd.after_update_handlers.push_back_node(f);
+
+        // If we are currently in `update`, this is no-op, and `f` will be
+        // called at the end of the current update
+        self.pend_update();
+    }
+
     /// This is basically the handler of `update_ready` event and where layers
     /// are layouted and rendered. Also, the update process clears `Wnd::dirty`.
     fn update(self) {
+        self.update_inner();
+
+        // Process `invoke_after_update`. This must happen after all borrows
+        // made by `update_inner` are released.
+        let mut after_update_handlers = self.wnd.after_update_handlers.take();
+        while let Some(cb) = after_update_handlers.pop_front_node() {
+            super::invocation::blackbox(move || {
+                (Pin::into_inner(cb).element.inner)(self.wnd.wm);
+            });
+        }
+    }
+
+    fn update_inner(self) {
         if self.wnd.closed.get() {
             return;
         }
@@ -156,6 +369,14 @@ impl HWndRef<'_> {
             return;
         };
 
+        let frame_start = Instant::now();
+        let _frame_span = profiling::begin_span(self.wnd.wm, SpanKind::Frame, self);
+
+        // Group all layer changes made in this frame (including those made by
+        // frame handlers) so that the backend presents them at once
+        self.wnd.wm.begin_layer_txn();
+        self.wnd.damage.set(WndDamage::Empty);
+
         // Process `invoke_on_next_frame`.
         {
             let mut frame_handlers = self.wnd.frame_handlers.take();
@@ -212,6 +433,10 @@ impl HWndRef<'_> {
             attrs.layer = Some(Some(layers[0].clone()));
         }
 
+        // Put the debug overlay on top of the root view's layer if enabled.
+        // This may replace the window's root layer.
+        self.update_debug_overlay(dirty, update_contents, &mut attrs.layer);
+
         if dirty.contains(WndDirtyFlags::DEFAULT_SIZE) {
             // This flag is handled by `update_views`
             debug_assert!(new_size.is_some());
@@ -220,11 +445,15 @@ impl HWndRef<'_> {
         attrs.min_size = min_size;
         attrs.max_size = max_size;
         attrs.size = new_size;
+        attrs.position = self.wnd.requested_position.take();
+        attrs.maximized = self.wnd.requested_maximized.take();
 
         // Apply changes in `WndStyleAttrs`
         let style_attrs = self.wnd.style_attrs.borrow();
         style_attrs.transfer_to_pal(dirty, &mut attrs);
 
+        let commit_span = profiling::begin_span(self.wnd.wm, SpanKind::Commit, self);
+
         // Suppress resize events (caused by `set_wnd_attr`)
         self.wnd.updating.set(true);
 
@@ -234,9 +463,26 @@ impl HWndRef<'_> {
         // Un-suppress resize events
         self.wnd.updating.set(false);
 
-        // Update layers
+        // Update layers. Limit the update to the damage region reported by
+        // views if it's certain that nothing else has changed.
+        if update_contents || dirty.contains(WndDirtyFlags::DEBUG_OVERLAY) {
+            match self.wnd.damage.get() {
+                WndDamage::Partial(damage)
+                    if !dirty.intersects(flags![WndDirtyFlags::{LAYER | DEBUG_OVERLAY}])
+                        && self.debug_overlay().is_empty() =>
+                {
+                    self.wnd.wm.update_wnd_with_damage(pal_wnd, damage);
+                }
+                _ => self.wnd.wm.update_wnd(pal_wnd),
+            }
+        }
+
+        self.wnd.wm.commit_layer_txn();
+
+        drop(commit_span);
+
         if update_contents {
-            self.wnd.wm.update_wnd(pal_wnd);
+            self.record_debug_frame_time(frame_start.elapsed());
         }
     }
 
@@ -251,6 +497,7 @@ impl HWndRef<'_> {
         let mut max_size = None;
 
         let resize_to_preferred = self.wnd.dirty.get().contains(WndDirtyFlags::DEFAULT_SIZE);
+        let requested_size = self.wnd.requested_size.take();
 
         // Repeat until the update converges...
         for _ in 0..100 {
@@ -266,14 +513,22 @@ impl HWndRef<'_> {
                 };
             }
 
-            view.as_ref().call_pending_mount_if_dirty(self.wnd.wm, self);
+            {
+                let _phase_guard = self.wnd.enter_phase(UpdatePhase::Mount);
+                view.as_ref().call_pending_mount_if_dirty(self.wnd.wm, self);
+            }
+
+            let layout_span = profiling::begin_span(self.wnd.wm, SpanKind::Layout, self);
+            let layout_phase_guard = self.wnd.enter_phase(UpdatePhase::Layout);
 
             // Layout: down phase
             view.as_ref().update_size_traits();
 
             // Constrain the window size
             let size_traits = view.view.size_traits.get();
-            let wnd_size = if resize_to_preferred {
+            let wnd_size = if let Some(size) = requested_size {
+                size
+            } else if resize_to_preferred {
                 [
                     size_traits.preferred.x as u32,
                     size_traits.preferred.y as u32,
@@ -308,7 +563,7 @@ impl HWndRef<'_> {
                 min(max(wnd_size[0], min_s[0]), max_s[0]),
                 min(max(wnd_size[1], min_s[1]), max_s[1]),
             ];
-            if new_wnd_size != wnd_size || resize_to_preferred {
+            if new_wnd_size != wnd_size || resize_to_preferred || requested_size.is_some() {
                 // Resize the window to satisfy the constraint
                 new_size = Some(new_wnd_size);
             }
@@ -342,10 +597,18 @@ impl HWndRef<'_> {
                 continue;
             }
 
+            drop(layout_phase_guard);
+            drop(layout_span);
+
             // Position views
-            view.as_ref().flush_position_event(self.wnd.wm);
+            {
+                let _phase_guard = self.wnd.enter_phase(UpdatePhase::Position);
+                view.as_ref().flush_position_event(self.wnd.wm);
+            }
 
             // Update visual
+            let _update_span = profiling::begin_span(self.wnd.wm, SpanKind::ViewUpdate, self);
+            let _phase_guard = self.wnd.enter_phase(UpdatePhase::Update);
             view.as_ref().update_layers(self.wnd.wm, self);
         }
 
@@ -390,11 +653,14 @@ impl Wnd {
             });
 
             *view.view.superview.borrow_mut() = Superview::empty();
+            view.as_ref().clear_ui_direction_cache();
 
             view.as_ref().cancel_mouse_gestures_of_subviews(self);
             view.as_ref().call_unmount(self.wm);
         }
 
+        self.debug_overlay.borrow_mut().clear(self.wm);
+
         if let Some(hwnd) = self.pal_wnd.borrow_mut().take() {
             self.wm.remove_wnd(&hwnd);
         }
@@ -502,13 +768,31 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
     fn dpi_scale_changed(&self, _: Wm, _: &pal::HWnd) {
         if let Some(hwnd) = self.hwnd() {
             let hwnd = hwnd.as_ref();
-            let handlers = hwnd.wnd.dpi_scale_changed_handlers.borrow();
-            for handler in handlers.iter() {
-                handler(hwnd.wnd.wm, hwnd);
+
+            // Layer contents (e.g., bitmaps from `tcw3::images`) must be
+            // re-rasterized at the new scale
+            {
+                let view = hwnd.wnd.content_view.borrow();
+                let view = view.as_ref().unwrap();
+                view.as_ref().pend_update_on_dpi_scale_change();
             }
+
+            {
+                let handlers = hwnd.wnd.dpi_scale_changed_handlers.borrow();
+                for handler in handlers.iter() {
+                    handler(hwnd.wnd.wm, hwnd);
+                }
+            }
+
+            let listener = hwnd.wnd.listener.borrow();
+            listener.dpi_scale_changed(hwnd.wnd.wm, hwnd);
         }
     }
 
+    fn system_appearance_changed(&self, wm: Wm, _: &pal::HWnd) {
+        handle_system_appearance_changed(wm);
+    }
+
     fn focus(&self, wm: Wm, _: &pal::HWnd) {
         // This handler can be called from `set_wnd_attrs`, which might conflict
         // with a mutable borrow for `style_attrs`
@@ -553,6 +837,10 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
             let hwnd = hwnd.as_ref();
             let listener = hwnd.wnd.listener.borrow();
             listener.interpret_event(wm, hwnd, ctx);
+
+            if let Some(accel_table) = hwnd.accel_table() {
+                ctx.use_accel(accel_table);
+            }
         }
     }
 
@@ -604,6 +892,12 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
         }
     }
 
+    fn mouse_relative_motion(&self, _: Wm, _: &pal::HWnd, delta: Vector2<f32>) {
+        if let Some(hwnd) = self.hwnd() {
+            hwnd.handle_mouse_relative_motion(delta);
+        }
+    }
+
     fn scroll_motion(&self, _: Wm, _: &pal::HWnd, loc: Point2<f32>, delta: &pal::ScrollDelta) {
         if let Some(hwnd) = self.hwnd() {
             hwnd.handle_scroll_motion(loc, delta);
@@ -622,6 +916,19 @@ impl pal::iface::WndListener<Wm> for PalWndListener {
             Box::new(())
         }
     }
+
+    fn gesture(
+        &self,
+        _: Wm,
+        _: &pal::HWnd,
+        loc: Point2<f32>,
+    ) -> Box<dyn pal::iface::GestureListener<Wm>> {
+        if let Some(hwnd) = self.hwnd() {
+            hwnd.handle_gesture(loc)
+        } else {
+            Box::new(())
+        }
+    }
 }
 
 pub(crate) fn new_root_content_view() -> HView {
@@ -682,7 +989,7 @@ bitflags! {
     /// called for the next time.
     ///
     /// Be aware that the usage is different from that of `ViewDirtyFlags`.
-    pub struct WndDirtyFlags: u8 {
+    pub struct WndDirtyFlags: u16 {
         /// The root layer should be updated.
         const LAYER = 1;
         /// The window should be resized to the default size.
@@ -691,6 +998,11 @@ bitflags! {
         const STYLE_VISIBLE = 1 << 2;
         const STYLE_FLAGS = 1 << 3;
         const STYLE_CAPTION = 1 << 4;
+        const STYLE_PROGRESS = 1 << 7;
+        const STYLE_POINTER_GRAB = 1 << 8;
+
+        /// The debug overlay should be updated.
+        const DEBUG_OVERLAY = 1 << 9;
 
         const CONTENTS = 1 << 5;
 
@@ -707,7 +1019,9 @@ impl Default for WndDirtyFlags {
 
 impl WndDirtyFlags {
     fn style() -> Self {
-        flags![WndDirtyFlags::{STYLE_VISIBLE | STYLE_FLAGS | STYLE_CAPTION}]
+        flags![WndDirtyFlags::{
+            STYLE_VISIBLE | STYLE_FLAGS | STYLE_CAPTION | STYLE_PROGRESS | STYLE_POINTER_GRAB
+        }]
     }
 }
 
@@ -716,6 +1030,8 @@ pub(super) struct WndStyleAttrs {
     pub flags: WndStyleFlags,
     pub caption: String,
     pub visible: bool,
+    pub progress: Option<ProgressState>,
+    pub pointer_grab: bool,
 }
 
 impl Default for WndStyleAttrs {
@@ -724,6 +1040,8 @@ impl Default for WndStyleAttrs {
             flags: WndStyleFlags::default(),
             caption: "TCW3 Window".to_owned(),
             visible: false,
+            progress: None,
+            pointer_grab: false,
         }
     }
 }
@@ -739,5 +1057,11 @@ impl WndStyleAttrs {
         if dirty.contains(WndDirtyFlags::STYLE_CAPTION) {
             attrs.caption = Some(self.caption[..].into());
         }
+        if dirty.contains(WndDirtyFlags::STYLE_PROGRESS) {
+            attrs.progress = Some(self.progress);
+        }
+        if dirty.contains(WndDirtyFlags::STYLE_POINTER_GRAB) {
+            attrs.pointer_grab = Some(self.pointer_grab);
+        }
     }
 }
@@ -169,3 +169,28 @@ fn actions_should_take_prescedence(twm: &dyn TestingWm) {
     assert!(events.contains(&(1, Event::Action)));
     assert!(!events.contains(&(0, Event::KeyDown)) && !events.contains(&(1, Event::KeyDown)));
 }
+
+#[use_testing_wm]
+#[test]
+fn wnd_accel_table(twm: &dyn TestingWm) {
+    static ACCEL_TABLE: pal::AccelTable = pal::accel_table![(42, windows("Ctrl+W"))];
+
+    let (wnd, pal_hwnd, events) = init_test(twm, false, vec![true]);
+
+    // `Ctrl+W` isn't translated yet
+    twm.simulate_key(&pal_hwnd, "windows", "Ctrl+W");
+    twm.step_unsend();
+    assert_eq!(
+        replace(&mut *events.borrow_mut(), Vec::new()),
+        [(1, Event::KeyDown), (1, Event::KeyUp)]
+    );
+
+    wnd.set_accel_table(Some(&ACCEL_TABLE));
+
+    // Now it's translated to the action 42
+    twm.simulate_key(&pal_hwnd, "windows", "Ctrl+W");
+    twm.step_unsend();
+    let events = replace(&mut *events.borrow_mut(), Vec::new());
+    assert!(events.contains(&(1, Event::Action)));
+    assert!(!events.contains(&(1, Event::KeyDown)));
+}
@@ -1,11 +1,16 @@
 use cggeom::box2;
+use cgmath::{Point2, Vector2};
+use std::{cell::Cell, rc::Rc};
 use try_match::try_match;
 
 use tcw3::{
     pal,
     testing::{prelude::*, use_testing_wm},
-    ui::{layouts::AbsLayout, AlignFlags},
-    uicore::{HView, HViewRef, HWnd, SizeTraits, ViewFlags, ViewListener},
+    ui::{
+        layouts::{AbsLayout, EmptyLayout},
+        AlignFlags,
+    },
+    uicore::{HView, HViewRef, HWnd, Layout, LayoutCtx, SizeTraits, ViewFlags, ViewListener},
 };
 
 struct VL;
@@ -58,3 +63,169 @@ fn position_event(twm: &dyn TestingWm) {
     twm.set_wnd_focused(&pal_hwnd, true);
     twm.step_unsend();
 }
+
+#[use_testing_wm]
+#[test]
+fn coordinate_conversion(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view1 = HView::new(ViewFlags::empty());
+    let view2 = HView::new(ViewFlags::empty());
+
+    wnd.content_view().set_layout(AbsLayout::new(
+        SizeTraits {
+            min: [100.0, 100.0].into(),
+            max: [100.0, 100.0].into(),
+            preferred: [100.0, 100.0].into(),
+        },
+        vec![
+            (
+                view1.clone(),
+                box2! { min: [10.0, 20.0], max: [40.0, 50.0] },
+                AlignFlags::JUSTIFY,
+            ),
+            (
+                view2.clone(),
+                box2! { min: [50.0, 60.0], max: [70.0, 90.0] },
+                AlignFlags::JUSTIFY,
+            ),
+        ],
+    ));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    assert_eq!(
+        view2.frame_in(view1.as_ref()),
+        box2! { min: [40.0, 40.0], max: [60.0, 70.0] }
+    );
+    assert_eq!(
+        view1.frame_in(view1.as_ref()),
+        box2! { min: [0.0, 0.0], max: [30.0, 30.0] }
+    );
+
+    assert_eq!(
+        view1.convert_point_to(view2.as_ref(), [5.0, 5.0].into()),
+        Point2::new(-35.0, -35.0)
+    );
+    assert_eq!(
+        view1.convert_point_from_window([15.0, 30.0].into()),
+        Point2::new(5.0, 10.0)
+    );
+    assert_eq!(
+        view1.convert_point_to_window([5.0, 10.0].into()),
+        Point2::new(15.0, 30.0)
+    );
+}
+
+#[use_testing_wm]
+#[test]
+fn dump_view_tree(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::empty());
+    view.set_listener(VL);
+    view.set_debug_name(Some("child"));
+
+    wnd.content_view().set_layout(AbsLayout::new(
+        SizeTraits {
+            min: [100.0, 100.0].into(),
+            max: [100.0, 100.0].into(),
+            preferred: [100.0, 100.0].into(),
+        },
+        vec![(
+            view.clone(),
+            box2! { min: [-20.0, 30.0], max: [80.0, 50.0] },
+            AlignFlags::JUSTIFY,
+        )],
+    ));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    assert_eq!(view.superview(), Some(wnd.content_view()));
+    assert_eq!(wnd.content_view().superview(), None);
+    assert_eq!(wnd.content_view().subviews(), vec![view.clone()]);
+
+    let snapshot = wnd.dump_view_tree();
+
+    let root = snapshot.root.unwrap();
+    assert_eq!(root.view, wnd.content_view());
+    assert_eq!(root.subviews.len(), 1);
+
+    let child = &root.subviews[0];
+    assert_eq!(child.view, view);
+    assert_eq!(child.debug_name, Some("child"));
+    assert!(child.listener_type_name.unwrap().ends_with("VL"));
+    assert_eq!(child.frame, box2! { min: [-20.0, 30.0], max: [80.0, 50.0] });
+    assert!(child.subviews.is_empty());
+}
+
+/// A layout that wraps a single subview and counts the calls to
+/// `Layout::size_traits`.
+struct CountingLayout {
+    subviews: [HView; 1],
+    num_calls: Rc<Cell<usize>>,
+}
+
+impl Layout for CountingLayout {
+    fn subviews(&self) -> &[HView] {
+        &self.subviews
+    }
+
+    fn size_traits(&self, ctx: &LayoutCtx<'_>) -> SizeTraits {
+        self.num_calls.set(self.num_calls.get() + 1);
+        ctx.subview_size_traits(self.subviews[0].as_ref())
+    }
+
+    fn arrange(&self, ctx: &mut LayoutCtx<'_>, size: Vector2<f32>) {
+        ctx.set_subview_frame(
+            self.subviews[0].as_ref(),
+            box2! { min: [0.0, 0.0], max: [size.x, size.y] },
+        );
+    }
+}
+
+#[use_testing_wm]
+#[test]
+fn size_traits_memoization(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let size_traits = SizeTraits {
+        min: [20.0, 20.0].into(),
+        ..Default::default()
+    };
+
+    let view = HView::new(ViewFlags::empty());
+    view.set_layout(EmptyLayout::new(size_traits));
+
+    let num_calls = Rc::new(Cell::new(0));
+    wnd.content_view().set_layout(CountingLayout {
+        subviews: [view.clone()],
+        num_calls: Rc::clone(&num_calls),
+    });
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    assert_eq!(num_calls.get(), 1);
+
+    // The subview's `SizeTraits` does not change, so the superview's
+    // `size_traits` should not be recalculated
+    view.set_layout(EmptyLayout::new(size_traits));
+    twm.step_unsend();
+
+    assert_eq!(num_calls.get(), 1);
+
+    // This time it does change
+    view.set_layout(EmptyLayout::new(SizeTraits {
+        min: [40.0, 40.0].into(),
+        ..Default::default()
+    }));
+    twm.step_unsend();
+
+    assert_eq!(num_calls.get(), 2);
+}
@@ -1,5 +1,5 @@
 use cggeom::prelude::*;
-use cgmath::Point2;
+use cgmath::{Point2, Vector2};
 use std::{cell::RefCell, mem::replace, rc::Rc};
 use try_match::try_match;
 
@@ -11,7 +11,8 @@ use tcw3::{
         AlignFlags,
     },
     uicore::{
-        HView, HViewRef, HWnd, ScrollDelta, ScrollListener, SizeTraits, ViewFlags, ViewListener,
+        GestureListener, HView, HViewRef, HWnd, MouseDragListener, ScrollDelta, ScrollListener,
+        SizeTraits, ViewFlags, ViewListener,
     },
 };
 
@@ -23,6 +24,10 @@ enum Event {
     MouseOut,
     ScrollMotion,
     ScrollGesture,
+    Gesture,
+    Magnify,
+    MouseDrag,
+    MouseRelativeMotion([i32; 2]),
 }
 
 struct RecordingViewListener(u8, Rc<RefCell<Vec<(u8, Event)>>>);
@@ -53,6 +58,39 @@ impl ViewListener for RecordingViewListener {
         self.1.borrow_mut().push((self.0, Event::ScrollGesture));
         Box::new(())
     }
+    fn mouse_drag(
+        &self,
+        _: pal::Wm,
+        _: HViewRef<'_>,
+        _loc: Point2<f32>,
+        _button: u8,
+    ) -> Box<dyn MouseDragListener> {
+        self.1.borrow_mut().push((self.0, Event::MouseDrag));
+        Box::new(RecordingDragListener(self.0, self.1.clone()))
+    }
+    fn gesture(&self, _: pal::Wm, _: HViewRef<'_>, _loc: Point2<f32>) -> Box<dyn GestureListener> {
+        self.1.borrow_mut().push((self.0, Event::Gesture));
+        Box::new(RecordingGestureListener(self.0, self.1.clone()))
+    }
+}
+
+struct RecordingDragListener(u8, Rc<RefCell<Vec<(u8, Event)>>>);
+
+impl MouseDragListener for RecordingDragListener {
+    fn mouse_relative_motion(&self, _: pal::Wm, _: HViewRef<'_>, delta: Vector2<f32>) {
+        self.1.borrow_mut().push((
+            self.0,
+            Event::MouseRelativeMotion([delta.x as i32, delta.y as i32]),
+        ));
+    }
+}
+
+struct RecordingGestureListener(u8, Rc<RefCell<Vec<(u8, Event)>>>);
+
+impl GestureListener for RecordingGestureListener {
+    fn magnify(&self, _: pal::Wm, _: HViewRef<'_>, _scale: f32) {
+        self.1.borrow_mut().push((self.0, Event::Magnify));
+    }
 }
 
 macro_rules! flush_and_assert_events {
@@ -223,3 +261,102 @@ fn scroll_evts(twm: &dyn TestingWm) {
     drop(g);
     flush_and_assert_events!(events, [(1, Event::ScrollGesture)]);
 }
+
+#[use_testing_wm]
+#[test]
+fn gesture_evts(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+
+    let view0 = HView::new(ViewFlags::default());
+    let view1 = HView::new(ViewFlags::ACCEPT_GESTURE);
+
+    view0.set_listener(RecordingViewListener(0, events.clone()));
+    view1.set_listener(RecordingViewListener(1, events.clone()));
+
+    view0.set_layout(FillLayout::new(view1.clone()).with_uniform_margin(10.0));
+
+    view1.set_layout(EmptyLayout::new(
+        SizeTraits::default().with_preferred([20.0; 2].into()),
+    ));
+
+    wnd.content_view().set_layout(FillLayout::new(view0));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    flush_and_assert_events!(events, []);
+
+    // `view0` does not have `ACCEPT_GESTURE`
+    let g = twm.raise_gesture(&pal_hwnd, [0.0; 2].into());
+    g.magnify(2.0);
+    g.end();
+    drop(g);
+    flush_and_assert_events!(events, []);
+
+    // `view1`, on the other hand
+    let g = twm.raise_gesture(&pal_hwnd, [20.0; 2].into());
+    g.magnify(2.0);
+    g.end();
+    drop(g);
+    flush_and_assert_events!(events, [(1, Event::Gesture), (1, Event::Magnify)]);
+
+    // The previous gesture is gone, so a new one can be started
+    let g = twm.raise_gesture(&pal_hwnd, [20.0; 2].into());
+    g.cancel();
+    drop(g);
+    flush_and_assert_events!(events, [(1, Event::Gesture)]);
+}
+
+#[use_testing_wm]
+#[test]
+fn mouse_relative_motion_evts(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let events = Rc::new(RefCell::new(Vec::new()));
+
+    let view0 = HView::new(ViewFlags::ACCEPT_MOUSE_DRAG);
+    view0.set_listener(RecordingViewListener(0, events.clone()));
+    view0.set_layout(EmptyLayout::new(
+        SizeTraits::default().with_preferred([20.0; 2].into()),
+    ));
+
+    wnd.content_view().set_layout(FillLayout::new(view0));
+
+    wnd.set_pointer_grab(true);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    // There's no active drag gesture to deliver the event to
+    twm.raise_mouse_relative_motion(&pal_hwnd, [3.0, 4.0].into());
+    flush_and_assert_events!(events, []);
+
+    let drag = twm.raise_mouse_drag(&pal_hwnd, [10.0; 2].into(), 0);
+    drag.mouse_down([10.0; 2].into(), 0);
+    twm.raise_mouse_relative_motion(&pal_hwnd, [3.0, 4.0].into());
+    twm.raise_mouse_relative_motion(&pal_hwnd, [-5.0, 0.0].into());
+    drag.mouse_up([10.0; 2].into(), 0);
+    drop(drag);
+
+    flush_and_assert_events!(
+        events,
+        [
+            (0, Event::MouseDrag),
+            (0, Event::MouseRelativeMotion([3, 4])),
+            (0, Event::MouseRelativeMotion([-5, 0])),
+        ]
+    );
+
+    // The drag gesture is gone
+    twm.raise_mouse_relative_motion(&pal_hwnd, [3.0, 4.0].into());
+    flush_and_assert_events!(events, []);
+}
@@ -0,0 +1,93 @@
+use tcw3::{
+    pal,
+    prelude::*,
+    testing::{prelude::*, use_testing_wm},
+    ui::layouts::{EmptyLayout, FillLayout},
+    uicore::{HView, HViewRef, HWnd, SizeTraits, UpdateCtx, ViewFlags, ViewListener},
+};
+
+struct SetListenerInUpdate;
+
+impl ViewListener for SetListenerInUpdate {
+    fn update(&self, _: pal::Wm, view: HViewRef<'_>, _: &mut UpdateCtx<'_>) {
+        view.set_listener(());
+    }
+}
+
+#[use_testing_wm]
+#[test]
+#[should_panic(expected = "`HView::set_listener` was called on view \"victim\" (HView(0x")]
+fn set_listener_in_update_names_view(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::default());
+    view.set_debug_name(Some("victim"));
+    view.set_listener(SetListenerInUpdate);
+    view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    wnd.content_view().set_layout(FillLayout::new(view));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+}
+
+#[use_testing_wm]
+#[test]
+#[should_panic(expected = "during the update phase (`ViewListener::update`)")]
+fn set_listener_in_update_names_phase(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::default());
+    view.set_listener(SetListenerInUpdate);
+    view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    wnd.content_view().set_layout(FillLayout::new(view));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+}
+
+struct SetLayoutInUpdate;
+
+impl ViewListener for SetLayoutInUpdate {
+    fn update(&self, _: pal::Wm, view: HViewRef<'_>, _: &mut UpdateCtx<'_>) {
+        // The layout isn't borrowed at this point, so this has to be detected
+        // by checking the update phase
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    }
+}
+
+#[use_testing_wm]
+#[test]
+#[should_panic(expected = "`HView::set_layout` was called on view \"victim\" (HView(0x")]
+fn set_layout_in_update(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::default());
+    view.set_debug_name(Some("victim"));
+    view.set_listener(SetLayoutInUpdate);
+    view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    wnd.content_view().set_layout(FillLayout::new(view));
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+}
+
+#[use_testing_wm]
+#[test]
+fn set_layout_outside_update(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::default());
+    wnd.content_view().set_layout(FillLayout::new(view.clone()));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    // Event handlers and `Wm::invoke` may replace layouts
+    wm.invoke(move |_| {
+        view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    });
+    twm.step_unsend();
+}
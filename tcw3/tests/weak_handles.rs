@@ -0,0 +1,34 @@
+use tcw3::{
+    testing::{prelude::*, use_testing_wm},
+    uicore::{HView, HWnd, ViewFlags, WeakHView, WeakHWnd},
+};
+
+#[test]
+fn weak_view() {
+    let view = HView::new(ViewFlags::default());
+    let weak = view.as_ref().downgrade();
+    assert_eq!(weak, view.downgrade());
+    assert_eq!(weak.upgrade(), Some(view.clone()));
+
+    drop(view);
+    assert_eq!(weak.upgrade(), None);
+
+    assert_eq!(WeakHView::new(), WeakHView::new());
+    assert_ne!(weak, WeakHView::new());
+}
+
+#[use_testing_wm]
+#[test]
+fn weak_wnd(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    let weak = wnd.as_ref().downgrade();
+    assert_eq!(weak, wnd.downgrade());
+    assert_eq!(weak.upgrade(), Some(wnd.clone()));
+
+    drop(wnd);
+    twm.step_unsend();
+    assert_eq!(weak.upgrade(), None);
+
+    assert_eq!(WeakHWnd::new(), WeakHWnd::new());
+}
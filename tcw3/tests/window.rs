@@ -1,12 +1,22 @@
+use cggeom::box2;
 use enclose::enc;
-use std::{cell::Cell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Duration,
+};
 use try_match::try_match;
 
 use tcw3::{
-    pal,
+    pal::{self, testing::wmapi::WndSnapshot},
     prelude::*,
+    profiling::{self, ProfilingListener, Span, SpanKind},
     testing::{prelude::*, use_testing_wm},
-    uicore::{HWnd, HWndRef},
+    ui::layouts::{EmptyLayout, FillLayout},
+    uicore::{
+        DebugOverlayFlags, HView, HViewRef, HWnd, HWndRef, SizeTraits, UpdateCtx, UpdateReason,
+        ViewFlags, ViewListener, WndListener,
+    },
 };
 
 #[use_testing_wm]
@@ -90,3 +100,613 @@ fn invoke_on_next_frame(twm: &dyn TestingWm) {
 
     assert_eq!(count.get(), 3);
 }
+
+#[use_testing_wm]
+#[test]
+fn system_appearance_changed(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnds = [HWnd::new(wm), HWnd::new(wm)];
+    for wnd in wnds.iter() {
+        wnd.set_visibility(true);
+    }
+    twm.step_unsend();
+
+    let count = Rc::new(Cell::new(0));
+    let sub = wm.subscribe_system_appearance_changed(Box::new(enc!((count) move |_| {
+        count.set(count.get() + 1);
+    })));
+
+    let dark = pal::SystemAppearance {
+        dark_mode: true,
+        ..Default::default()
+    };
+
+    // The handler should be called only once even though the backend sends
+    // a notification for each window
+    twm.set_system_appearance(dark);
+    assert_eq!(wm.system_appearance(), dark);
+    assert_eq!(count.get(), 1);
+
+    // No actual changes
+    twm.set_system_appearance(dark);
+    assert_eq!(count.get(), 1);
+
+    twm.set_system_appearance(Default::default());
+    assert_eq!(count.get(), 2);
+
+    sub.unsubscribe().unwrap();
+}
+
+#[use_testing_wm]
+#[test]
+fn render_to_bitmap(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    // The window isn't materialized yet
+    assert!(wnd.render_to_bitmap().is_none());
+
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+    twm.set_wnd_size(&pal_hwnd, [40, 30]);
+    twm.step_unsend();
+
+    let dpi_scale = wnd.dpi_scale();
+    let bmp = wnd
+        .render_to_bitmap()
+        .expect("the testing backend should support `render_to_bitmap`");
+    assert_eq!(
+        bmp.size(),
+        [(40.0 * dpi_scale) as u32, (30.0 * dpi_scale) as u32]
+    );
+
+    wnd.close();
+    assert!(wnd.render_to_bitmap().is_none());
+}
+
+#[use_testing_wm]
+#[test]
+fn progress(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.set_progress(Some(pal::ProgressState::Normal(0.5)));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+    assert_eq!(
+        twm.wnd_attrs(&pal_hwnd).unwrap().progress,
+        Some(pal::ProgressState::Normal(0.5))
+    );
+
+    wnd.set_progress(None);
+    assert_eq!(wnd.progress(), None);
+    twm.step_unsend();
+    assert_eq!(twm.wnd_attrs(&pal_hwnd).unwrap().progress, None);
+}
+
+#[use_testing_wm]
+#[test]
+fn pointer_grab(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.set_pointer_grab(true);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+    assert!(twm.wnd_attrs(&pal_hwnd).unwrap().pointer_grab);
+
+    wnd.set_pointer_grab(false);
+    assert!(!wnd.pointer_grab());
+    twm.step_unsend();
+    assert!(!twm.wnd_attrs(&pal_hwnd).unwrap().pointer_grab);
+}
+
+#[use_testing_wm]
+#[test]
+fn debug_overlay(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.content_view().set_layout(EmptyLayout::new(SizeTraits {
+        preferred: [100.0, 100.0].into(),
+        ..Default::default()
+    }));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    let has_opaque_pixel = || {
+        let mut ss = WndSnapshot::new();
+        twm.read_wnd_snapshot(&pal_hwnd, &mut ss);
+        (0..ss.size[1]).any(|y| {
+            let row = &ss.data[y * ss.stride..][..ss.size[0] * 4];
+            row.chunks_exact(4).any(|pixel| pixel[3] != 0)
+        })
+    };
+
+    // The root view has no contents
+    assert!(!has_opaque_pixel());
+
+    // The frame of the root view should be outlined
+    wnd.set_debug_overlay(DebugOverlayFlags::FRAMES);
+    assert_eq!(wnd.debug_overlay(), DebugOverlayFlags::FRAMES);
+    twm.step_unsend();
+    assert!(has_opaque_pixel());
+
+    wnd.set_debug_overlay(DebugOverlayFlags::empty());
+    twm.step_unsend();
+    assert!(!has_opaque_pixel());
+}
+
+#[use_testing_wm]
+#[test]
+fn clone_into_new_window(twm: &dyn TestingWm) {
+    // Build a content view with a subview constraining the window size
+    fn new_content_view() -> HView {
+        let subview = HView::new(ViewFlags::default());
+        subview.set_layout(EmptyLayout::new(SizeTraits {
+            min: [40.0, 30.0].into(),
+            max: [400.0, 300.0].into(),
+            preferred: [100.0, 60.0].into(),
+        }));
+
+        let view = HView::new(ViewFlags::LAYER_GROUP);
+        view.set_layout(FillLayout::new(subview));
+        view
+    }
+
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.set_content_view(new_content_view());
+    wnd.set_caption("hoge");
+    wnd.set_style_flags(pal::WndFlags::BORDERLESS | pal::WndFlags::RESIZABLE);
+    wnd.set_progress(Some(pal::ProgressState::Indeterminate));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+    twm.set_wnd_size(&pal_hwnd, [150, 80]);
+    twm.step_unsend();
+    assert_eq!(wnd.size(), Some([150, 80]));
+
+    let mirrored_view = Cell::new(None);
+    let new_wnd = wnd.clone_into_new_window(|view| {
+        mirrored_view.set(Some(view.cloned()));
+        new_content_view()
+    });
+
+    assert_eq!(mirrored_view.take(), Some(wnd.content_view()));
+    assert_ne!(new_wnd.content_view(), wnd.content_view());
+    assert_eq!(new_wnd.caption(), "hoge");
+    assert_eq!(
+        new_wnd.style_flags(),
+        pal::WndFlags::BORDERLESS | pal::WndFlags::RESIZABLE
+    );
+    assert_eq!(new_wnd.progress(), Some(pal::ProgressState::Indeterminate));
+    assert!(!new_wnd.visibility());
+
+    new_wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let hwnds = twm.hwnds();
+    assert_eq!(hwnds.len(), 2);
+    let new_pal_hwnd = hwnds.iter().find(|hwnd| **hwnd != pal_hwnd).unwrap();
+    let attrs = twm.wnd_attrs(new_pal_hwnd).unwrap();
+    let orig_attrs = twm.wnd_attrs(&pal_hwnd).unwrap();
+    assert_eq!(attrs.size, [150, 80]);
+    assert_eq!(attrs.min_size, orig_attrs.min_size);
+    assert_eq!(attrs.max_size, orig_attrs.max_size);
+    assert_eq!(attrs.min_size, [40, 30]);
+    assert_eq!(attrs.max_size, [400, 300]);
+    assert_eq!(attrs.progress, Some(pal::ProgressState::Indeterminate));
+
+    // The mirrored tree is laid out in the new window
+    let root = new_wnd.dump_view_tree().root.unwrap();
+    assert_eq!(root.view, new_wnd.content_view());
+    assert_eq!(
+        root.subviews[0].global_frame,
+        box2! { min: [0.0, 0.0], max: [150.0, 80.0] }
+    );
+}
+
+#[use_testing_wm]
+#[test]
+fn profiling_spans(twm: &dyn TestingWm) {
+    struct Listener(Rc<RefCell<Vec<SpanKind>>>);
+
+    impl ProfilingListener for Listener {
+        fn span_finished(&self, _: pal::Wm, span: &Span<'_>) {
+            self.0.borrow_mut().push(span.kind);
+        }
+    }
+
+    let wm = twm.wm();
+    let kinds = Rc::new(RefCell::new(Vec::new()));
+    profiling::set_listener(wm, Some(Box::new(Listener(Rc::clone(&kinds)))));
+
+    let wnd = HWnd::new(wm);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    profiling::set_listener(wm, None);
+
+    let kinds = kinds.borrow();
+    for kind in &[
+        SpanKind::Frame,
+        SpanKind::Layout,
+        SpanKind::ViewUpdate,
+        SpanKind::Commit,
+    ] {
+        assert!(kinds.contains(kind), "{:?} was not reported", kind);
+    }
+
+    // `Frame` encloses other spans, so it's reported last
+    assert_eq!(kinds.last(), Some(&SpanKind::Frame));
+}
+
+#[use_testing_wm]
+#[test]
+fn debug_overlay_frame_times(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.content_view().set_layout(EmptyLayout::new(SizeTraits {
+        preferred: [200.0, 100.0].into(),
+        ..Default::default()
+    }));
+    wnd.set_debug_overlay(DebugOverlayFlags::FRAME_TIMES);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    let mut ss = WndSnapshot::new();
+    twm.read_wnd_snapshot(&pal_hwnd, &mut ss);
+
+    // The graph's background is drawn in the top-left corner
+    let pixel = &ss.data[5 * ss.stride + 5 * 4..][..4];
+    assert_ne!(pixel[3], 0);
+}
+
+/// Create a 100×100 window containing a view inset by 20 pixels.
+fn new_inset_view_wnd(twm: &dyn TestingWm) -> (HWnd, HView, pal::HWnd) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let view = HView::new(ViewFlags::default());
+    view.set_layout(EmptyLayout::new(SizeTraits {
+        preferred: [60.0, 60.0].into(),
+        ..Default::default()
+    }));
+    wnd.content_view()
+        .set_layout(FillLayout::new(view.clone()).with_uniform_margin(20.0));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    (wnd, view, pal_hwnd)
+}
+
+/// Get the alpha value of the specified pixel of a window.
+fn pixel_alpha(twm: &dyn TestingWm, pal_hwnd: &pal::HWnd, [x, y]: [usize; 2]) -> u8 {
+    let mut ss = WndSnapshot::new();
+    twm.read_wnd_snapshot(pal_hwnd, &mut ss);
+    ss.data[y * ss.stride + x * 4 + 3]
+}
+
+#[use_testing_wm]
+#[test]
+fn debug_overlay_padding(twm: &dyn TestingWm) {
+    let (wnd, _view, pal_hwnd) = new_inset_view_wnd(twm);
+
+    // The bounding box of the root view's subviews should be outlined, but
+    // not the root view's frame
+    wnd.set_debug_overlay(DebugOverlayFlags::PADDING);
+    twm.step_unsend();
+    assert_ne!(pixel_alpha(twm, &pal_hwnd, [20, 50]), 0);
+    assert_eq!(pixel_alpha(twm, &pal_hwnd, [50, 50]), 0);
+    assert_eq!(pixel_alpha(twm, &pal_hwnd, [0, 50]), 0);
+
+    // Both kinds of outlines
+    wnd.set_debug_overlay(DebugOverlayFlags::PADDING | DebugOverlayFlags::FRAMES);
+    twm.step_unsend();
+    assert_ne!(pixel_alpha(twm, &pal_hwnd, [20, 50]), 0);
+    assert_ne!(pixel_alpha(twm, &pal_hwnd, [0, 50]), 0);
+}
+
+#[use_testing_wm]
+#[test]
+fn debug_overlay_dirty_flashes(twm: &dyn TestingWm) {
+    // Use the virtual clock to control when the highlights disappear
+    twm.advance_time(Duration::from_millis(0));
+
+    let (wnd, view, pal_hwnd) = new_inset_view_wnd(twm);
+
+    wnd.set_debug_overlay(DebugOverlayFlags::DIRTY_FLASHES);
+    twm.advance_time(Duration::from_secs(1));
+    assert_eq!(pixel_alpha(twm, &pal_hwnd, [50, 50]), 0);
+
+    // Updating the view should highlight its region
+    view.pend_update();
+    twm.step_unsend();
+    assert_ne!(pixel_alpha(twm, &pal_hwnd, [50, 50]), 0);
+    assert_eq!(pixel_alpha(twm, &pal_hwnd, [10, 10]), 0);
+
+    // ...only for a while
+    twm.advance_time(Duration::from_secs(1));
+    assert_eq!(pixel_alpha(twm, &pal_hwnd, [50, 50]), 0);
+
+    // Deactivating the overlay while a highlight is displayed should
+    // remove the highlight
+    view.pend_update();
+    twm.step_unsend();
+    assert_ne!(pixel_alpha(twm, &pal_hwnd, [50, 50]), 0);
+
+    wnd.set_debug_overlay(DebugOverlayFlags::empty());
+    twm.step_unsend();
+    assert_eq!(pixel_alpha(twm, &pal_hwnd, [50, 50]), 0);
+
+    // The scheduled removal should be a no-op
+    twm.advance_time(Duration::from_secs(1));
+}
+
+#[use_testing_wm]
+#[test]
+fn dpi_scale_changed(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    struct RecWL(Rc<Cell<u32>>);
+    impl WndListener for RecWL {
+        fn dpi_scale_changed(&self, _: pal::Wm, _: HWndRef<'_>) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    struct RecVL(Rc<RefCell<Vec<UpdateReason>>>);
+    impl ViewListener for RecVL {
+        fn update(&self, _: pal::Wm, _: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+            self.0.borrow_mut().push(ctx.reason());
+        }
+    }
+
+    let count = Rc::new(Cell::new(0));
+    let reasons = Rc::new(RefCell::new(Vec::new()));
+    wnd.set_listener(RecWL(Rc::clone(&count)));
+
+    let view = HView::new(ViewFlags::default());
+    view.set_listener(RecVL(Rc::clone(&reasons)));
+    view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    wnd.content_view().set_layout(FillLayout::new(view));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+    reasons.borrow_mut().clear();
+
+    twm.set_wnd_dpi_scale(&pal_hwnd, 2.0);
+    twm.step_unsend();
+
+    assert_eq!(count.get(), 1);
+    assert_eq!(wnd.dpi_scale(), 2.0);
+
+    let reasons = reasons.borrow();
+    assert_eq!(reasons.len(), 1);
+    assert!(reasons[0].contains(UpdateReason::DPI_SCALE_CHANGE));
+}
+
+#[use_testing_wm]
+#[test]
+fn invoke_after_update(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    struct ReplaceListenerVL(HWnd, Rc<Cell<u32>>);
+    impl ViewListener for ReplaceListenerVL {
+        fn update(&self, _: pal::Wm, view: HViewRef<'_>, _: &mut UpdateCtx<'_>) {
+            // `set_listener` is not allowed here
+            let view = view.cloned();
+            let count = Rc::clone(&self.1);
+            self.0.invoke_after_update(move |_| {
+                count.set(count.get() + 1);
+                view.set_listener(());
+            });
+        }
+    }
+
+    let count = Rc::new(Cell::new(0));
+    let view = HView::new(ViewFlags::default());
+    view.set_listener(ReplaceListenerVL(wnd.clone(), Rc::clone(&count)));
+    view.set_layout(EmptyLayout::new(SizeTraits::default()));
+    wnd.content_view().set_layout(FillLayout::new(view.clone()));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    assert_eq!(count.get(), 1);
+
+    // The listener was replaced, so this doesn't call the closure again
+    view.pend_update();
+    twm.step_unsend();
+    assert_eq!(count.get(), 1);
+
+    // The closure is called even if nothing else triggers an update
+    wnd.invoke_after_update(enc!((count) move |_| count.set(count.get() + 1)));
+    twm.step_unsend();
+    assert_eq!(count.get(), 2);
+}
+
+#[use_testing_wm]
+#[test]
+fn system_appearance_changed_reentrant(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    // The handler unsubscribes itself and subscribes another one
+    let count = Rc::new(Cell::new(0));
+    let subs = Rc::new(RefCell::new(Vec::new()));
+    let sub = wm.subscribe_system_appearance_changed(Box::new(enc!((count, subs) move |wm| {
+        count.set(count.get() + 1);
+
+        let mut subs = subs.borrow_mut();
+        for sub in subs.drain(..) {
+            sub.unsubscribe().unwrap();
+        }
+        subs.push(wm.subscribe_system_appearance_changed(Box::new(enc!((count) move |_| {
+            count.set(count.get() + 10);
+        }))));
+    })));
+
+    twm.set_system_appearance(pal::SystemAppearance {
+        dark_mode: true,
+        ..Default::default()
+    });
+    assert_eq!(count.get(), 1);
+
+    twm.set_system_appearance(Default::default());
+    assert_eq!(count.get(), 12);
+
+    sub.unsubscribe().unwrap();
+    for sub in subs.borrow_mut().drain(..) {
+        sub.unsubscribe().unwrap();
+    }
+}
+
+#[use_testing_wm]
+#[test]
+fn system_appearance_changed_without_wnds(twm: &dyn TestingWm) {
+    let wm = twm.wm();
+
+    let count = Rc::new(Cell::new(0));
+    let sub = wm.subscribe_system_appearance_changed(Box::new(enc!((count) move |_| {
+        count.set(count.get() + 1);
+    })));
+
+    // Start from a known state. (The last known value might be left over from
+    // another test.)
+    let wnd = HWnd::new(wm);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+    drop(wnd);
+    count.set(0);
+
+    // The backend can't notify anyone because there are no windows
+    twm.set_system_appearance(pal::SystemAppearance {
+        dark_mode: true,
+        ..Default::default()
+    });
+    assert_eq!(count.get(), 0);
+
+    // The change is picked up when a window is created
+    let wnd = HWnd::new(wm);
+    wnd.set_visibility(true);
+    twm.step_unsend();
+    assert_eq!(count.get(), 1);
+
+    // No actual changes
+    let wnd2 = HWnd::new(wm);
+    wnd2.set_visibility(true);
+    twm.step_unsend();
+    assert_eq!(count.get(), 1);
+
+    drop((wnd, wnd2));
+    sub.unsubscribe().unwrap();
+}
+
+#[use_testing_wm]
+#[test]
+fn update_damage(twm: &dyn TestingWm) {
+    struct DamageVL {
+        layer: RefCell<Option<pal::HLayer>>,
+        color: Rc<Cell<pal::RGBAF32>>,
+    }
+
+    impl ViewListener for DamageVL {
+        fn mount(&self, wm: pal::Wm, _: HViewRef<'_>, _: HWndRef<'_>) {
+            *self.layer.borrow_mut() = Some(wm.new_layer(pal::LayerAttrs::default()));
+        }
+
+        fn unmount(&self, wm: pal::Wm, _: HViewRef<'_>) {
+            wm.remove_layer(&self.layer.borrow_mut().take().unwrap());
+        }
+
+        fn update(&self, wm: pal::Wm, view: HViewRef<'_>, ctx: &mut UpdateCtx<'_>) {
+            let layer = self.layer.borrow();
+            let layer = layer.as_ref().unwrap();
+            let frame = view.global_frame();
+
+            wm.set_layer_attr(
+                layer,
+                pal::LayerAttrs {
+                    bounds: Some(frame),
+                    bg_color: Some(self.color.get()),
+                    ..Default::default()
+                },
+            );
+
+            if ctx.layers().is_empty() {
+                ctx.set_layers(vec![layer.clone()]);
+            }
+
+            // Deliberately report only the left half
+            ctx.set_damage(box2! {
+                min: [frame.min.x, frame.min.y],
+                max: [(frame.min.x + frame.max.x) * 0.5, frame.max.y],
+            });
+        }
+    }
+
+    let wm = twm.wm();
+    let wnd = HWnd::new(wm);
+
+    let color = Rc::new(Cell::new(pal::RGBAF32::new(1.0, 0.0, 0.0, 1.0)));
+    let view = HView::new(ViewFlags::default());
+    view.set_listener(DamageVL {
+        layer: RefCell::new(None),
+        color: Rc::clone(&color),
+    });
+    view.set_layout(EmptyLayout::new(SizeTraits {
+        preferred: [100.0, 100.0].into(),
+        ..Default::default()
+    }));
+    wnd.content_view().set_layout(FillLayout::new(view.clone()));
+    wnd.set_visibility(true);
+    twm.step_unsend();
+
+    let pal_hwnd = try_match!([x] = twm.hwnds().as_slice() => x.clone())
+        .expect("could not get a single window");
+
+    let red_at = |[x, y]: [usize; 2]| {
+        let mut ss = WndSnapshot::new();
+        twm.read_wnd_snapshot(&pal_hwnd, &mut ss);
+        ss.data[y * ss.stride + x * 4]
+    };
+    assert_eq!(red_at([25, 50]), 255);
+    assert_eq!(red_at([75, 50]), 255);
+
+    // Allocating the backing store invalidates the whole window. Flush this
+    // so that it doesn't override the damage region
+    view.pend_update();
+    twm.step_unsend();
+
+    color.set(pal::RGBAF32::new(0.0, 0.0, 1.0, 1.0));
+    view.pend_update();
+    twm.step_unsend();
+
+    // The backend redraws only the reported region
+    assert_eq!(red_at([25, 50]), 0);
+    assert_eq!(red_at([75, 50]), 255);
+}